@@ -0,0 +1,62 @@
+#[cfg(feature = "jit")]
+use gor::compile::jit::Jit;
+use gor::compile::{compiler::compile, vm::Vm};
+use gor::eval::interpreter::Interpreter;
+use gor::parser::parser::Parser;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Numeric programs whose hot code is in functions called many times, which the JIT compiles
+const WORKLOADS: [(&str, &str); 4] = [
+    (
+        "fib",
+        "package main\n\nimport \"fmt\"\n\nfunc fib(n int) int {\n\tif n < 2 {\n\t\treturn n\n\t}\n\treturn fib(n-1) + fib(n-2)\n}\n\nfunc main() {\n\tfmt.Println(fib(22))\n}\n",
+    ),
+    (
+        "integer loop",
+        "package main\n\nimport \"fmt\"\n\nfunc mix(i int) int {\n\treturn (i*i + i<<3) % 7919 ^ i>>2\n}\n\nfunc main() {\n\ttotal := 0\n\ti := 0\n\tfor i < 100000 {\n\t\ttotal = total + mix(i)\n\t\ti = i + 1\n\t}\n\tfmt.Println(total)\n}\n",
+    ),
+    (
+        "float newton",
+        "package main\n\nimport \"fmt\"\n\nfunc root(x float64) float64 {\n\tz := x / 2\n\ti := 0\n\tfor i < 30 {\n\t\tz = z - (z*z-x)/(2*z)\n\t\ti = i + 1\n\t}\n\treturn z\n}\n\nfunc main() {\n\ttotal := 0.0\n\ti := 1\n\tfor i < 5000 {\n\t\ttotal = total + root(float64(i))\n\t\ti = i + 1\n\t}\n\tfmt.Println(total)\n}\n",
+    ),
+    (
+        "collatz",
+        "package main\n\nimport \"fmt\"\n\nfunc steps(n int) int {\n\tcount := 0\n\tfor n != 1 {\n\t\tif n%2 == 0 {\n\t\t\tn = n / 2\n\t\t} else {\n\t\t\tn = 3*n + 1\n\t\t}\n\t\tcount = count + 1\n\t}\n\treturn count\n}\n\nfunc main() {\n\tlongest := 0\n\tn := 1\n\tfor n < 10000 {\n\t\tlength := steps(n)\n\t\tif length > longest {\n\t\t\tlongest = length\n\t\t}\n\t\tn = n + 1\n\t}\n\tfmt.Println(longest)\n}\n",
+    ),
+];
+
+fn bench(name: &str, iterations: u32, mut f: impl FnMut()) {
+    // Warm up before measuring
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let per_iteration: Duration = start.elapsed() / iterations;
+    println!("{:<28} {:>12.3?} / iteration", name, per_iteration);
+}
+
+fn main() {
+    for (name, source) in WORKLOADS {
+        let file = Parser::new(source).parse().unwrap();
+        bench(&format!("{} tree", name), 3, || {
+            let mut interpreter = Interpreter::new().with_output(std::io::sink());
+            interpreter.run(black_box(&file)).unwrap();
+        });
+
+        let program = compile(&file).unwrap();
+        bench(&format!("{} vm", name), 10, || {
+            let mut vm = Vm::new().with_output(std::io::sink());
+            vm.run(black_box(&program)).unwrap();
+        });
+
+        // Compiling to machine code is part of each run, as it is for `gor run`
+        #[cfg(feature = "jit")]
+        bench(&format!("{} jit", name), 10, || {
+            let jit = Jit::for_file(black_box(&file)).unwrap();
+            let mut vm = Vm::new().with_output(std::io::sink()).with_jit(jit);
+            vm.run(black_box(&program)).unwrap();
+        });
+    }
+}
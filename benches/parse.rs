@@ -0,0 +1,147 @@
+use gor::ast::{
+    arena::ArenaExpressionKind,
+    declaration::DeclarationKind,
+    expression::{Expression, ExpressionKind},
+    statement::StatementKind,
+};
+use gor::lexer::{lexer::Lexer, token::Token, token_type::TokenKind};
+use gor::parser::parser::Parser;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// A synthetic file with many functions, each with nested calls and binary expressions
+fn generate_source(functions: usize) -> String {
+    let mut source = String::from("package main\n\nimport \"fmt\"\n");
+    for i in 0..functions {
+        source.push_str(&format!("\nfunc f{}() {{\n", i));
+        for j in 0..10 {
+            source.push_str(&format!(
+                "\tfmt.Println(a{j} + b * (c - {i}) / d, g(h, \"s{j}\"), x.y.z(1, 2, 3))\n"
+            ));
+        }
+        source.push_str("}\n");
+    }
+    source
+}
+
+fn lex(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        let is_eof = token.kind == Some(TokenKind::EOF);
+        tokens.push(token);
+        if is_eof {
+            return tokens;
+        }
+    }
+}
+
+/// Count identifiers by walking the boxed tree
+fn count_identifiers(expression: &Expression) -> usize {
+    match &expression.kind {
+        ExpressionKind::Identifier(_) => 1,
+        ExpressionKind::Binary { left, right, .. } => {
+            count_identifiers(left) + count_identifiers(right)
+        }
+        ExpressionKind::FunctionCall {
+            name, arguments, ..
+        } => count_identifiers(name) + arguments.iter().map(count_identifiers).sum::<usize>(),
+        ExpressionKind::FieldAccess { object, .. } => count_identifiers(object),
+        ExpressionKind::Index { object, index } => {
+            count_identifiers(object) + count_identifiers(index)
+        }
+        ExpressionKind::Slice {
+            object,
+            low,
+            high,
+            max,
+        } => {
+            count_identifiers(object)
+                + [low, high, max]
+                    .into_iter()
+                    .flatten()
+                    .map(|bound| count_identifiers(bound))
+                    .sum::<usize>()
+        }
+        ExpressionKind::Parenthesized(inner) | ExpressionKind::Receive(inner) => {
+            count_identifiers(inner)
+        }
+        ExpressionKind::Unary { operand, .. } => count_identifiers(operand),
+        ExpressionKind::CompositeLiteral { elements, .. } => elements
+            .iter()
+            .map(|element| {
+                element.key.as_ref().map_or(0, count_identifiers)
+                    + count_identifiers(&element.value)
+            })
+            .sum(),
+        ExpressionKind::Conversion { expression, .. } => count_identifiers(expression),
+        // The generated source has no function literals, whose bodies are statements
+        ExpressionKind::FunctionLiteral { .. }
+        | ExpressionKind::Type(_)
+        | ExpressionKind::BlankIdentifier
+        | ExpressionKind::IntegerLiteral(_)
+        | ExpressionKind::FloatLiteral(_)
+        | ExpressionKind::RuneLiteral(_)
+        | ExpressionKind::StringLiteral(_) => 0,
+    }
+}
+
+fn bench(name: &str, iterations: u32, mut f: impl FnMut()) {
+    // Warm up before measuring
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let per_iteration: Duration = start.elapsed() / iterations;
+    println!("{:<28} {:>12.3?} / iteration", name, per_iteration);
+}
+
+fn main() {
+    for functions in [10, 50] {
+        let source = generate_source(functions);
+        bench(&format!("lex + parse {} functions", functions), 5, || {
+            let mut parser = Parser::new(black_box(&source));
+            black_box(parser.parse().unwrap());
+        });
+
+        // Lex up front so only the parser itself is measured
+        let tokens = lex(&source);
+        bench(&format!("parse {} functions", functions), 200, || {
+            let mut parser = Parser::from_tokens(black_box(tokens.clone()));
+            black_box(parser.parse().unwrap());
+        });
+        bench(&format!("parse arena {} functions", functions), 200, || {
+            let mut parser = Parser::from_tokens(black_box(tokens.clone()));
+            black_box(parser.parse_arena().unwrap());
+        });
+
+        let file = Parser::from_tokens(tokens.clone()).parse().unwrap();
+        bench(&format!("walk boxed {} functions", functions), 1000, || {
+            let mut identifiers = 0;
+            for declaration in &black_box(&file).decls {
+                let DeclarationKind::Function { body, .. } = &declaration.kind else {
+                    continue;
+                };
+                for statement in body {
+                    if let StatementKind::Expression(expression) = &statement.kind {
+                        identifiers += count_identifiers(expression);
+                    }
+                }
+            }
+            black_box(identifiers);
+        });
+
+        let arena_file = Parser::from_tokens(tokens).parse_arena().unwrap();
+        bench(&format!("walk arena {} functions", functions), 1000, || {
+            let identifiers = black_box(&arena_file)
+                .arena
+                .expressions()
+                .iter()
+                .filter(|expression| matches!(expression.kind, ArenaExpressionKind::Identifier(_)))
+                .count();
+            black_box(identifiers);
+        });
+    }
+}
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Run with `cargo +nightly fuzz run parse`. libfuzzer-sys aborts on any panic, so panics that
+// `parse_checked` would otherwise have turned into diagnostics are still reported as crashes.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = gor::parse_checked(input);
+    }
+});
@@ -0,0 +1,904 @@
+//! An index-based AST: every expression and statement of a file lives in one flat vector and
+//! refers to its children by id rather than through a `Box`. Walking it touches contiguous
+//! memory, and later analysis passes can key side tables on the ids.
+
+use crate::ast::{
+    ast::{File, ImportSpec},
+    declaration::{Declaration, DeclarationKind, Parameter, VariableSpec},
+    expression::{Expression, ExpressionKind, KeyedElement, SelectionKind, UnaryOperator},
+    statement::{SelectCase, Statement, StatementKind, SwitchCase},
+    type_expression::TypeExpression,
+};
+use crate::lexer::token_type::Operator;
+use crate::primitives::position::Position;
+
+/// Index of an expression in an `AstArena`
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExprId(u32);
+
+/// Index of a statement in an `AstArena`
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StmtId(u32);
+
+/// A run of expression ids stored contiguously in the arena, e.g. call arguments
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExprList {
+    start: u32,
+    len: u32,
+}
+
+impl ExprList {
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaExpression {
+    pub kind: ArenaExpressionKind,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+/// Mirrors `ExpressionKind`, with children as ids
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArenaExpressionKind {
+    Binary {
+        left: ExprId,
+        operator: Operator,
+        right: ExprId,
+    },
+    Identifier(String),
+    BlankIdentifier,
+    IntegerLiteral(String),
+    FloatLiteral(String),
+    RuneLiteral(String),
+    StringLiteral(String),
+    FunctionCall {
+        name: ExprId,
+        arguments: ExprList,
+        spread: bool,
+    },
+    FieldAccess {
+        object: ExprId,
+        field: String,
+        selection: SelectionKind,
+    },
+    Index {
+        object: ExprId,
+        index: ExprId,
+    },
+    Slice {
+        object: ExprId,
+        low: Option<ExprId>,
+        high: Option<ExprId>,
+        max: Option<ExprId>,
+    },
+    Parenthesized(ExprId),
+    Receive(ExprId),
+    Unary {
+        operator: UnaryOperator,
+        operand: ExprId,
+    },
+    CompositeLiteral {
+        type_expression: Option<TypeExpression>,
+        elements: Vec<ArenaKeyedElement>,
+    },
+    Conversion {
+        target: TypeExpression,
+        expression: ExprId,
+    },
+    FunctionLiteral {
+        parameters: Vec<Parameter>,
+        results: Vec<Parameter>,
+        body: Vec<StmtId>,
+    },
+    Type(TypeExpression),
+}
+
+/// Mirrors `KeyedElement`
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaKeyedElement {
+    pub key: Option<ExprId>,
+    pub value: ExprId,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaStatement {
+    pub kind: ArenaStatementKind,
+    pub position_start: Position,
+    pub position_end: Position,
+    pub trailing_comment: Option<String>,
+}
+
+/// Mirrors `StatementKind`, with children as ids
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArenaStatementKind {
+    Expression(ExprId),
+    Declaration(Box<ArenaDeclaration>),
+    Assignment {
+        left: ExprList,
+        right: ExprList,
+        define: bool,
+    },
+    IncDec {
+        target: ExprId,
+        increment: bool,
+    },
+    OpAssign {
+        target: ExprId,
+        operator: Operator,
+        value: ExprId,
+    },
+    For {
+        init: Option<StmtId>,
+        condition: Option<ExprId>,
+        post: Option<StmtId>,
+        body: Vec<StmtId>,
+    },
+    Range {
+        key: Option<ExprId>,
+        value: Option<ExprId>,
+        define: bool,
+        expression: ExprId,
+        body: Vec<StmtId>,
+    },
+    Block(Vec<StmtId>),
+    If {
+        init: Option<StmtId>,
+        condition: ExprId,
+        body: Vec<StmtId>,
+        else_branch: Option<StmtId>,
+    },
+    Return(ExprList),
+    Send {
+        channel: ExprId,
+        value: ExprId,
+    },
+    Defer(ExprId),
+    Go(ExprId),
+    Select(Vec<ArenaSelectCase>),
+    Switch {
+        init: Option<StmtId>,
+        tag: Option<ExprId>,
+        cases: Vec<ArenaSwitchCase>,
+    },
+    Break,
+    Continue,
+    Fallthrough,
+}
+
+/// Mirrors `SwitchCase`
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaSwitchCase {
+    pub expressions: Option<ExprList>,
+    pub body: Vec<StmtId>,
+    pub position: Position,
+}
+
+/// Mirrors `SelectCase`
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaSelectCase {
+    pub communication: Option<StmtId>,
+    pub body: Vec<StmtId>,
+    pub position: Position,
+}
+
+/// Storage for the nodes of one file
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AstArena {
+    expressions: Vec<ArenaExpression>,
+    statements: Vec<ArenaStatement>,
+    expression_lists: Vec<ExprId>,
+}
+
+impl AstArena {
+    pub fn new() -> AstArena {
+        AstArena::default()
+    }
+
+    pub fn expression(&self, id: ExprId) -> &ArenaExpression {
+        &self.expressions[id.0 as usize]
+    }
+
+    pub fn statement(&self, id: StmtId) -> &ArenaStatement {
+        &self.statements[id.0 as usize]
+    }
+
+    pub fn expression_list(&self, list: ExprList) -> &[ExprId] {
+        let start = list.start as usize;
+        &self.expression_lists[start..start + list.len as usize]
+    }
+
+    /// All expressions in allocation order; children always come before their parents
+    pub fn expressions(&self) -> &[ArenaExpression] {
+        &self.expressions
+    }
+
+    pub fn statements(&self) -> &[ArenaStatement] {
+        &self.statements
+    }
+
+    pub fn alloc_expression(&mut self, expression: ArenaExpression) -> ExprId {
+        self.expressions.push(expression);
+        ExprId(self.expressions.len() as u32 - 1)
+    }
+
+    pub fn alloc_statement(&mut self, statement: ArenaStatement) -> StmtId {
+        self.statements.push(statement);
+        StmtId(self.statements.len() as u32 - 1)
+    }
+
+    pub fn alloc_expression_list(&mut self, ids: &[ExprId]) -> ExprList {
+        let start = self.expression_lists.len() as u32;
+        self.expression_lists.extend_from_slice(ids);
+        ExprList {
+            start,
+            len: ids.len() as u32,
+        }
+    }
+
+    /// Move a boxed expression tree into the arena
+    pub fn lower_expression(&mut self, expression: Expression) -> ExprId {
+        let kind = match expression.kind {
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => ArenaExpressionKind::Binary {
+                left: self.lower_expression(*left),
+                operator,
+                right: self.lower_expression(*right),
+            },
+            ExpressionKind::Identifier(name) => ArenaExpressionKind::Identifier(name),
+            ExpressionKind::BlankIdentifier => ArenaExpressionKind::BlankIdentifier,
+            ExpressionKind::IntegerLiteral(value) => ArenaExpressionKind::IntegerLiteral(value),
+            ExpressionKind::FloatLiteral(value) => ArenaExpressionKind::FloatLiteral(value),
+            ExpressionKind::RuneLiteral(value) => ArenaExpressionKind::RuneLiteral(value),
+            ExpressionKind::StringLiteral(value) => ArenaExpressionKind::StringLiteral(value),
+            ExpressionKind::FunctionCall {
+                name,
+                arguments,
+                spread,
+            } => {
+                let name = self.lower_expression(*name);
+                let arguments: Vec<ExprId> = arguments
+                    .into_iter()
+                    .map(|argument| self.lower_expression(argument))
+                    .collect();
+                ArenaExpressionKind::FunctionCall {
+                    name,
+                    arguments: self.alloc_expression_list(&arguments),
+                    spread,
+                }
+            }
+            ExpressionKind::FieldAccess {
+                object,
+                field,
+                selection,
+            } => ArenaExpressionKind::FieldAccess {
+                object: self.lower_expression(*object),
+                field,
+                selection,
+            },
+            ExpressionKind::Index { object, index } => ArenaExpressionKind::Index {
+                object: self.lower_expression(*object),
+                index: self.lower_expression(*index),
+            },
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => ArenaExpressionKind::Slice {
+                object: self.lower_expression(*object),
+                low: low.map(|low| self.lower_expression(*low)),
+                high: high.map(|high| self.lower_expression(*high)),
+                max: max.map(|max| self.lower_expression(*max)),
+            },
+            ExpressionKind::Parenthesized(inner) => {
+                ArenaExpressionKind::Parenthesized(self.lower_expression(*inner))
+            }
+            ExpressionKind::Receive(channel) => {
+                ArenaExpressionKind::Receive(self.lower_expression(*channel))
+            }
+            ExpressionKind::Unary { operator, operand } => ArenaExpressionKind::Unary {
+                operator,
+                operand: self.lower_expression(*operand),
+            },
+            ExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            } => ArenaExpressionKind::CompositeLiteral {
+                type_expression,
+                elements: elements
+                    .into_iter()
+                    .map(|element| ArenaKeyedElement {
+                        key: element.key.map(|key| self.lower_expression(key)),
+                        value: self.lower_expression(element.value),
+                    })
+                    .collect(),
+            },
+            ExpressionKind::Conversion { target, expression } => ArenaExpressionKind::Conversion {
+                target,
+                expression: self.lower_expression(*expression),
+            },
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            } => ArenaExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body: self.lower_block(body),
+            },
+            ExpressionKind::Type(type_expression) => ArenaExpressionKind::Type(type_expression),
+        };
+        self.alloc_expression(ArenaExpression {
+            kind,
+            position_start: expression.position_start,
+            position_end: expression.position_end,
+        })
+    }
+
+    pub fn lower_statement(&mut self, statement: Statement) -> StmtId {
+        let kind = match statement.kind {
+            StatementKind::Expression(expression) => {
+                ArenaStatementKind::Expression(self.lower_expression(expression))
+            }
+            StatementKind::Declaration(declaration) => {
+                ArenaStatementKind::Declaration(Box::new(self.lower_declaration(declaration)))
+            }
+            StatementKind::Assignment {
+                left,
+                right,
+                define,
+            } => ArenaStatementKind::Assignment {
+                left: self.lower_expression_list(left),
+                right: self.lower_expression_list(right),
+                define,
+            },
+            StatementKind::IncDec { target, increment } => ArenaStatementKind::IncDec {
+                target: self.lower_expression(target),
+                increment,
+            },
+            StatementKind::OpAssign {
+                target,
+                operator,
+                value,
+            } => ArenaStatementKind::OpAssign {
+                target: self.lower_expression(target),
+                operator,
+                value: self.lower_expression(value),
+            },
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => ArenaStatementKind::For {
+                init: init.map(|init| self.lower_statement(*init)),
+                condition: condition.map(|condition| self.lower_expression(condition)),
+                post: post.map(|post| self.lower_statement(*post)),
+                body: self.lower_block(body),
+            },
+            StatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => ArenaStatementKind::Range {
+                key: key.map(|key| self.lower_expression(key)),
+                value: value.map(|value| self.lower_expression(value)),
+                define,
+                expression: self.lower_expression(expression),
+                body: self.lower_block(body),
+            },
+            StatementKind::Block(body) => ArenaStatementKind::Block(self.lower_block(body)),
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => ArenaStatementKind::If {
+                init: init.map(|init| self.lower_statement(*init)),
+                condition: self.lower_expression(condition),
+                body: self.lower_block(body),
+                else_branch: else_branch.map(|else_branch| self.lower_statement(*else_branch)),
+            },
+            StatementKind::Return(results) => {
+                ArenaStatementKind::Return(self.lower_expression_list(results))
+            }
+            StatementKind::Send { channel, value } => ArenaStatementKind::Send {
+                channel: self.lower_expression(channel),
+                value: self.lower_expression(value),
+            },
+            StatementKind::Defer(call) => ArenaStatementKind::Defer(self.lower_expression(call)),
+            StatementKind::Go(call) => ArenaStatementKind::Go(self.lower_expression(call)),
+            StatementKind::Select(cases) => ArenaStatementKind::Select(
+                cases
+                    .into_iter()
+                    .map(|case| ArenaSelectCase {
+                        communication: case
+                            .communication
+                            .map(|communication| self.lower_statement(*communication)),
+                        body: self.lower_block(case.body),
+                        position: case.position,
+                    })
+                    .collect(),
+            ),
+            StatementKind::Switch { init, tag, cases } => ArenaStatementKind::Switch {
+                init: init.map(|init| self.lower_statement(*init)),
+                tag: tag.map(|tag| self.lower_expression(tag)),
+                cases: cases
+                    .into_iter()
+                    .map(|case| ArenaSwitchCase {
+                        expressions: case
+                            .expressions
+                            .map(|expressions| self.lower_expression_list(expressions)),
+                        body: self.lower_block(case.body),
+                        position: case.position,
+                    })
+                    .collect(),
+            },
+            StatementKind::Break => ArenaStatementKind::Break,
+            StatementKind::Continue => ArenaStatementKind::Continue,
+            StatementKind::Fallthrough => ArenaStatementKind::Fallthrough,
+        };
+        self.alloc_statement(ArenaStatement {
+            kind,
+            position_start: statement.position_start,
+            position_end: statement.position_end,
+            trailing_comment: statement.trailing_comment,
+        })
+    }
+
+    pub fn lower_expression_list(&mut self, expressions: Vec<Expression>) -> ExprList {
+        let ids: Vec<ExprId> = expressions
+            .into_iter()
+            .map(|expression| self.lower_expression(expression))
+            .collect();
+        self.alloc_expression_list(&ids)
+    }
+
+    pub fn lower_declaration(&mut self, declaration: Declaration) -> ArenaDeclaration {
+        let kind = match declaration.kind {
+            DeclarationKind::Function {
+                receiver,
+                name,
+                parameters,
+                results,
+                body,
+            } => ArenaDeclarationKind::Function {
+                receiver,
+                name,
+                parameters,
+                results,
+                body: self.lower_block(body),
+            },
+            DeclarationKind::Variable { specs, grouped } => ArenaDeclarationKind::Variable {
+                specs: self.lower_specs(specs),
+                grouped,
+            },
+            DeclarationKind::Constant { specs, grouped } => ArenaDeclarationKind::Constant {
+                specs: self.lower_specs(specs),
+                grouped,
+            },
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } => ArenaDeclarationKind::Type {
+                name: self.lower_expression(name),
+                type_expression,
+            },
+        };
+        ArenaDeclaration {
+            kind,
+            doc: declaration.doc,
+            position_start: declaration.position_start,
+            position_end: declaration.position_end,
+        }
+    }
+
+    fn lower_specs(&mut self, specs: Vec<VariableSpec>) -> Vec<ArenaVariableSpec> {
+        specs
+            .into_iter()
+            .map(|spec| ArenaVariableSpec {
+                names: self.lower_expression_list(spec.names),
+                type_expression: spec.type_expression,
+                values: self.lower_expression_list(spec.values),
+                position_start: spec.position_start,
+                position_end: spec.position_end,
+            })
+            .collect()
+    }
+
+    pub fn lower_block(&mut self, statements: Vec<Statement>) -> Vec<StmtId> {
+        statements
+            .into_iter()
+            .map(|statement| self.lower_statement(statement))
+            .collect()
+    }
+
+    /// Rebuild the boxed tree for an expression, e.g. to print it
+    pub fn to_expression(&self, id: ExprId) -> Expression {
+        let expression = self.expression(id);
+        let kind = match &expression.kind {
+            ArenaExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => ExpressionKind::Binary {
+                left: Box::new(self.to_expression(*left)),
+                operator: *operator,
+                right: Box::new(self.to_expression(*right)),
+            },
+            ArenaExpressionKind::Identifier(name) => ExpressionKind::Identifier(name.clone()),
+            ArenaExpressionKind::BlankIdentifier => ExpressionKind::BlankIdentifier,
+            ArenaExpressionKind::IntegerLiteral(value) => {
+                ExpressionKind::IntegerLiteral(value.clone())
+            }
+            ArenaExpressionKind::FloatLiteral(value) => ExpressionKind::FloatLiteral(value.clone()),
+            ArenaExpressionKind::RuneLiteral(value) => ExpressionKind::RuneLiteral(value.clone()),
+            ArenaExpressionKind::StringLiteral(value) => {
+                ExpressionKind::StringLiteral(value.clone())
+            }
+            ArenaExpressionKind::FunctionCall {
+                name,
+                arguments,
+                spread,
+            } => ExpressionKind::FunctionCall {
+                name: Box::new(self.to_expression(*name)),
+                arguments: self
+                    .expression_list(*arguments)
+                    .iter()
+                    .map(|argument| self.to_expression(*argument))
+                    .collect(),
+                spread: *spread,
+            },
+            ArenaExpressionKind::FieldAccess {
+                object,
+                field,
+                selection,
+            } => ExpressionKind::FieldAccess {
+                object: Box::new(self.to_expression(*object)),
+                field: field.clone(),
+                selection: *selection,
+            },
+            ArenaExpressionKind::Index { object, index } => ExpressionKind::Index {
+                object: Box::new(self.to_expression(*object)),
+                index: Box::new(self.to_expression(*index)),
+            },
+            ArenaExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => ExpressionKind::Slice {
+                object: Box::new(self.to_expression(*object)),
+                low: low.map(|low| Box::new(self.to_expression(low))),
+                high: high.map(|high| Box::new(self.to_expression(high))),
+                max: max.map(|max| Box::new(self.to_expression(max))),
+            },
+            ArenaExpressionKind::Parenthesized(inner) => {
+                ExpressionKind::Parenthesized(Box::new(self.to_expression(*inner)))
+            }
+            ArenaExpressionKind::Receive(channel) => {
+                ExpressionKind::Receive(Box::new(self.to_expression(*channel)))
+            }
+            ArenaExpressionKind::Unary { operator, operand } => ExpressionKind::Unary {
+                operator: *operator,
+                operand: Box::new(self.to_expression(*operand)),
+            },
+            ArenaExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            } => ExpressionKind::CompositeLiteral {
+                type_expression: type_expression.clone(),
+                elements: elements
+                    .iter()
+                    .map(|element| KeyedElement {
+                        key: element.key.map(|key| self.to_expression(key)),
+                        value: self.to_expression(element.value),
+                    })
+                    .collect(),
+            },
+            ArenaExpressionKind::Conversion { target, expression } => ExpressionKind::Conversion {
+                target: target.clone(),
+                expression: Box::new(self.to_expression(*expression)),
+            },
+            ArenaExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            } => ExpressionKind::FunctionLiteral {
+                parameters: parameters.clone(),
+                results: results.clone(),
+                body: self.to_block(body),
+            },
+            ArenaExpressionKind::Type(type_expression) => {
+                ExpressionKind::Type(type_expression.clone())
+            }
+        };
+        Expression::new(kind, expression.position_start, expression.position_end)
+    }
+
+    pub fn to_statement(&self, id: StmtId) -> Statement {
+        let statement = self.statement(id);
+        let kind = match &statement.kind {
+            ArenaStatementKind::Expression(expression) => {
+                StatementKind::Expression(self.to_expression(*expression))
+            }
+            ArenaStatementKind::Declaration(declaration) => {
+                StatementKind::Declaration(self.to_declaration(declaration))
+            }
+            ArenaStatementKind::Assignment {
+                left,
+                right,
+                define,
+            } => StatementKind::Assignment {
+                left: self.to_expression_vec(*left),
+                right: self.to_expression_vec(*right),
+                define: *define,
+            },
+            ArenaStatementKind::IncDec { target, increment } => StatementKind::IncDec {
+                target: self.to_expression(*target),
+                increment: *increment,
+            },
+            ArenaStatementKind::OpAssign {
+                target,
+                operator,
+                value,
+            } => StatementKind::OpAssign {
+                target: self.to_expression(*target),
+                operator: *operator,
+                value: self.to_expression(*value),
+            },
+            ArenaStatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => StatementKind::For {
+                init: init.map(|init| Box::new(self.to_statement(init))),
+                condition: condition.map(|condition| self.to_expression(condition)),
+                post: post.map(|post| Box::new(self.to_statement(post))),
+                body: self.to_block(body),
+            },
+            ArenaStatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => StatementKind::Range {
+                key: key.map(|key| self.to_expression(key)),
+                value: value.map(|value| self.to_expression(value)),
+                define: *define,
+                expression: self.to_expression(*expression),
+                body: self.to_block(body),
+            },
+            ArenaStatementKind::Block(body) => StatementKind::Block(self.to_block(body)),
+            ArenaStatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => StatementKind::If {
+                init: init.map(|init| Box::new(self.to_statement(init))),
+                condition: self.to_expression(*condition),
+                body: self.to_block(body),
+                else_branch: else_branch
+                    .map(|else_branch| Box::new(self.to_statement(else_branch))),
+            },
+            ArenaStatementKind::Return(results) => {
+                StatementKind::Return(self.to_expression_vec(*results))
+            }
+            ArenaStatementKind::Send { channel, value } => StatementKind::Send {
+                channel: self.to_expression(*channel),
+                value: self.to_expression(*value),
+            },
+            ArenaStatementKind::Defer(call) => StatementKind::Defer(self.to_expression(*call)),
+            ArenaStatementKind::Go(call) => StatementKind::Go(self.to_expression(*call)),
+            ArenaStatementKind::Select(cases) => StatementKind::Select(
+                cases
+                    .iter()
+                    .map(|case| SelectCase {
+                        communication: case
+                            .communication
+                            .map(|communication| Box::new(self.to_statement(communication))),
+                        body: self.to_block(&case.body),
+                        position: case.position,
+                    })
+                    .collect(),
+            ),
+            ArenaStatementKind::Switch { init, tag, cases } => StatementKind::Switch {
+                init: init.map(|init| Box::new(self.to_statement(init))),
+                tag: tag.map(|tag| self.to_expression(tag)),
+                cases: cases
+                    .iter()
+                    .map(|case| SwitchCase {
+                        expressions: case
+                            .expressions
+                            .map(|expressions| self.to_expression_vec(expressions)),
+                        body: self.to_block(&case.body),
+                        position: case.position,
+                    })
+                    .collect(),
+            },
+            ArenaStatementKind::Break => StatementKind::Break,
+            ArenaStatementKind::Continue => StatementKind::Continue,
+            ArenaStatementKind::Fallthrough => StatementKind::Fallthrough,
+        };
+        let mut rebuilt = Statement::new(kind, statement.position_start, statement.position_end);
+        rebuilt.trailing_comment = statement.trailing_comment.clone();
+        rebuilt
+    }
+
+    pub fn to_block(&self, ids: &[StmtId]) -> Vec<Statement> {
+        ids.iter().map(|id| self.to_statement(*id)).collect()
+    }
+
+    pub fn to_expression_vec(&self, list: ExprList) -> Vec<Expression> {
+        self.expression_list(list)
+            .iter()
+            .map(|id| self.to_expression(*id))
+            .collect()
+    }
+
+    pub fn to_declaration(&self, declaration: &ArenaDeclaration) -> Declaration {
+        let kind = match &declaration.kind {
+            ArenaDeclarationKind::Function {
+                receiver,
+                name,
+                parameters,
+                results,
+                body,
+            } => DeclarationKind::Function {
+                receiver: receiver.clone(),
+                name: name.clone(),
+                parameters: parameters.clone(),
+                results: results.clone(),
+                body: self.to_block(body),
+            },
+            ArenaDeclarationKind::Variable { specs, grouped } => DeclarationKind::Variable {
+                specs: self.to_specs(specs),
+                grouped: *grouped,
+            },
+            ArenaDeclarationKind::Constant { specs, grouped } => DeclarationKind::Constant {
+                specs: self.to_specs(specs),
+                grouped: *grouped,
+            },
+            ArenaDeclarationKind::Type {
+                name,
+                type_expression,
+            } => DeclarationKind::Type {
+                name: self.to_expression(*name),
+                type_expression: type_expression.clone(),
+            },
+        };
+        Declaration {
+            doc: declaration.doc.clone(),
+            ..Declaration::new(kind, declaration.position_start, declaration.position_end)
+        }
+    }
+
+    fn to_specs(&self, specs: &[ArenaVariableSpec]) -> Vec<VariableSpec> {
+        specs
+            .iter()
+            .map(|spec| {
+                VariableSpec::new(
+                    self.to_expression_vec(spec.names),
+                    spec.type_expression.clone(),
+                    self.to_expression_vec(spec.values),
+                    spec.position_start,
+                    spec.position_end,
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaDeclaration {
+    pub kind: ArenaDeclarationKind,
+    pub doc: Option<String>,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+/// Mirrors `DeclarationKind`, with children as ids
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArenaDeclarationKind {
+    Function {
+        receiver: Option<Parameter>,
+        name: String,
+        parameters: Vec<Parameter>,
+        results: Vec<Parameter>,
+        body: Vec<StmtId>,
+    },
+    Variable {
+        specs: Vec<ArenaVariableSpec>,
+        grouped: bool,
+    },
+    Constant {
+        specs: Vec<ArenaVariableSpec>,
+        grouped: bool,
+    },
+    Type {
+        name: ExprId,
+        type_expression: TypeExpression,
+    },
+}
+
+/// Mirrors `VariableSpec`, with the names and values as ids
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaVariableSpec {
+    pub names: ExprList,
+    pub type_expression: Option<TypeExpression>,
+    pub values: ExprList,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+/// A `File` whose expressions and statements are stored in an `AstArena`
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaFile {
+    pub package_name: String,
+    pub imports: Vec<ImportSpec>,
+    pub decls: Vec<ArenaDeclaration>,
+    pub doc: Option<String>,
+    pub arena: AstArena,
+}
+
+impl ArenaFile {
+    /// Move a parsed file into an arena
+    pub fn from_file(file: File) -> ArenaFile {
+        let mut arena = AstArena::new();
+        let decls = file
+            .decls
+            .into_iter()
+            .map(|declaration| arena.lower_declaration(declaration))
+            .collect();
+        ArenaFile {
+            package_name: file.package_name,
+            imports: file.imports,
+            decls,
+            doc: file.doc,
+            arena,
+        }
+    }
+
+    /// Rebuild the boxed tree, e.g. to print the file
+    pub fn to_file(&self) -> File {
+        let decls = self
+            .decls
+            .iter()
+            .map(|declaration| self.arena.to_declaration(declaration))
+            .collect();
+        File {
+            doc: self.doc.clone(),
+            ..File::new(self.package_name.clone(), self.imports.clone(), decls)
+        }
+    }
+}
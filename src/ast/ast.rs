@@ -1,5 +1,155 @@
-use crate::ast::statement::Statement;
+use crate::ast::arena::ArenaExpression;
+use crate::ast::declaration::Declaration;
+use crate::ast::expression::Expression;
+use crate::ast::type_expression::TypeExpression;
+use crate::primitives::position::Position;
 
-pub struct Program {
-    pub statements: Vec<Statement>,
+/// A single Go source file (https://go.dev/ref/spec#Source_file_organization)
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct File {
+    pub package_name: String,
+    pub imports: Vec<ImportSpec>,
+    pub decls: Vec<Declaration>,
+    /// The package's documentation, from the comment right above the package clause
+    pub doc: Option<String>,
+}
+
+impl File {
+    pub fn new(package_name: String, imports: Vec<ImportSpec>, decls: Vec<Declaration>) -> File {
+        File {
+            package_name,
+            imports,
+            decls,
+            doc: None,
+        }
+    }
+
+    /// Reconstruct Go source for the whole file
+    pub fn to_source(&self) -> String {
+        self.to_string()
+    }
+
+    /// Drop every comment the tree keeps, so it prints without them
+    pub fn strip_comments(&mut self) {
+        self.doc = None;
+        for declaration in &mut self.decls {
+            declaration.strip_comments();
+        }
+    }
+}
+
+impl std::fmt::Display for File {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "package {}", self.package_name)?;
+
+        match self.imports.as_slice() {
+            [] => {}
+            [import] => write!(f, "\nimport {}\n", import)?,
+            imports => {
+                write!(f, "\nimport (\n")?;
+                for import in imports {
+                    writeln!(f, "\t{}", import)?;
+                }
+                writeln!(f, ")")?;
+            }
+        }
+
+        for declaration in &self.decls {
+            write!(f, "\n{}\n", declaration)?;
+        }
+        Ok(())
+    }
+}
+
+// import "fmt"
+// import f "fmt"
+// import . "fmt"
+// import _ "fmt"
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportSpec {
+    pub name: Option<String>,
+    pub path: String,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+impl ImportSpec {
+    pub fn new(
+        name: Option<String>,
+        path: String,
+        position_start: Position,
+        position_end: Position,
+    ) -> ImportSpec {
+        ImportSpec {
+            name,
+            path,
+            position_start,
+            position_end,
+        }
+    }
+
+    /// The path without its quotes, like `net/http`
+    pub fn unquoted_path(&self) -> &str {
+        self.path.trim_matches(|c| c == '"' || c == '`')
+    }
+
+    /// The name the import is referred to by: its alias, or by convention the last element of
+    /// its path, since the imported package's own clause isn't available
+    pub fn local_name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let path = self.unquoted_path();
+        path.rsplit('/').next().unwrap_or(path).to_string()
+    }
+}
+
+impl std::fmt::Display for ImportSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} {}", name, self.path),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// Identifies an expression or type within a file by its span. No two expressions of a file
+/// share a span, and a node keeps its id when lowered into an `AstArena`, so side tables keyed
+/// by it work for either representation.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeId {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl NodeId {
+    pub fn new(start: Position, end: Position) -> NodeId {
+        NodeId { start, end }
+    }
+
+    /// The id of an identifier, which is a single token
+    pub fn identifier(position: Position) -> NodeId {
+        NodeId::new(position, position)
+    }
+}
+
+impl From<&Expression> for NodeId {
+    fn from(expression: &Expression) -> NodeId {
+        NodeId::new(expression.position_start, expression.position_end)
+    }
+}
+
+impl From<&ArenaExpression> for NodeId {
+    fn from(expression: &ArenaExpression) -> NodeId {
+        NodeId::new(expression.position_start, expression.position_end)
+    }
+}
+
+impl From<&TypeExpression> for NodeId {
+    fn from(type_expression: &TypeExpression) -> NodeId {
+        NodeId::new(type_expression.position_start, type_expression.position_end)
+    }
 }
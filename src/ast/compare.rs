@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use crate::ast::ast::File;
+use crate::ast::declaration::DeclarationKind;
+use crate::ast::outline::{Signature, base_type_name};
+use crate::ast::type_expression::TypeExpressionKind;
+
+/// The package clause, an import or a package level declaration that differs between two
+/// versions of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// What changed on a line, like `func (c *Circle) Area() float64`, as the newer file
+    /// declares it unless it was removed
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    /// The mark a change is printed after, `+`, `-` or `~`
+    pub fn mark(&self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Changed => '~',
+        }
+    }
+}
+
+/// An import, or a function, method, type or variable a file declares, with what it's
+/// compared by
+struct Item {
+    /// What it's matched with its other version by, like `Circle.Area` for a method
+    key: String,
+    detail: String,
+    /// Its source as the pretty-printer writes it, so layout and comments don't count
+    source: String,
+}
+
+/// How `new` differs from `old` structurally: the imports and declarations it adds, those
+/// it removes and those whose source differs other than in layout or comments. A method is
+/// matched by its receiver's type and name, and a variable by its names. Those added or
+/// changed are in the order `new` declares them, followed by those removed.
+pub fn compare(old: &File, new: &File) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if old.package_name != new.package_name {
+        changes.push(Change {
+            kind: ChangeKind::Changed,
+            detail: format!("package {}", new.package_name),
+        });
+    }
+    let (old_items, new_items) = (items(old), items(new));
+    for item in &new_items {
+        let kind = match old_items.iter().find(|old| old.key == item.key) {
+            None => ChangeKind::Added,
+            Some(old) if old.source != item.source => ChangeKind::Changed,
+            Some(_) => continue,
+        };
+        changes.push(Change {
+            kind,
+            detail: item.detail.clone(),
+        });
+    }
+    for item in &old_items {
+        if !new_items.iter().any(|new| new.key == item.key) {
+            changes.push(Change {
+                kind: ChangeKind::Removed,
+                detail: item.detail.clone(),
+            });
+        }
+    }
+    changes
+}
+
+fn items(file: &File) -> Vec<Item> {
+    let mut file = file.clone();
+    file.strip_comments();
+    let mut items = Vec::new();
+    for import in &file.imports {
+        let source = format!("import {}", import);
+        items.push(Item {
+            key: source.clone(),
+            detail: source.clone(),
+            source,
+        });
+    }
+    for declaration in &file.decls {
+        match &declaration.kind {
+            DeclarationKind::Function {
+                receiver,
+                name,
+                parameters,
+                results,
+                ..
+            } => {
+                let signature = Signature(parameters, results);
+                let (key, detail) = match receiver {
+                    Some(receiver) => (
+                        format!("{}.{}", base_type_name(&receiver.type_expression), name),
+                        format!("func ({}) {}{}", receiver, name, signature),
+                    ),
+                    None => (name.clone(), format!("func {}{}", name, signature)),
+                };
+                items.push(Item {
+                    key,
+                    detail,
+                    source: declaration.to_source(),
+                });
+            }
+            DeclarationKind::Variable { specs, .. } | DeclarationKind::Constant { specs, .. } => {
+                let keyword = match declaration.kind {
+                    DeclarationKind::Constant { .. } => "const",
+                    _ => "var",
+                };
+                for spec in specs {
+                    let names: Vec<String> =
+                        spec.names.iter().map(|name| name.to_string()).collect();
+                    let detail = match &spec.type_expression {
+                        Some(type_expression) => {
+                            format!("{} {} {}", keyword, names.join(", "), type_expression)
+                        }
+                        None => format!("{} {}", keyword, names.join(", ")),
+                    };
+                    items.push(Item {
+                        key: names.join(", "),
+                        detail,
+                        source: spec.to_string(),
+                    });
+                }
+            }
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } => {
+                let written = match &type_expression.kind {
+                    TypeExpressionKind::Struct(_) => "struct".to_string(),
+                    TypeExpressionKind::Interface(_) => "interface".to_string(),
+                    _ => type_expression.to_string(),
+                };
+                items.push(Item {
+                    key: name.to_string(),
+                    detail: format!("type {} {}", name, written),
+                    source: declaration.to_source(),
+                });
+            }
+        }
+    }
+    // Names declared more than once, like init functions or `_`, are matched in order
+    let mut declared: HashMap<String, usize> = HashMap::new();
+    for item in &mut items {
+        let earlier = declared.entry(item.key.clone()).or_default();
+        if *earlier > 0 {
+            item.key = format!("{}#{}", item.key, earlier);
+        }
+        *earlier += 1;
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    fn changes(old: &str, new: &str) -> Vec<String> {
+        let old = Parser::new(old).parse().unwrap();
+        let new = Parser::new(new).parse().unwrap();
+        compare(&old, &new)
+            .iter()
+            .map(|change| format!("{} {}", change.kind.mark(), change.detail))
+            .collect()
+    }
+
+    #[test]
+    fn compare_files() {
+        let old = "package shapes
+
+import \"fmt\"
+
+var count int
+
+type Circle struct {
+	r float64
+}
+
+func (c *Circle) Area() float64 {
+	return 3 * c.r * c.r
+}
+
+func init() {}
+
+func init() {
+	fmt.Println(count)
+}
+";
+        let new = "package shapes
+
+import (
+	\"fmt\"
+	\"math\"
+)
+
+// Circle is round.
+type Circle struct{ r float64 }
+
+func (c *Circle) Area() float64 { return math.Pi * c.r * c.r }
+
+func init() {}
+
+func init() {
+	fmt.Println(\"no count\") // changed
+}
+";
+        assert_eq!(
+            changes(old, new),
+            vec![
+                "+ import \"math\"",
+                "~ func (c *Circle) Area() float64",
+                "~ func init()",
+                "- var count int",
+            ]
+        );
+        assert_eq!(changes(old, old), Vec::<String>::new());
+    }
+}
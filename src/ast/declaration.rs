@@ -0,0 +1,344 @@
+use crate::ast::expression::Expression;
+use crate::ast::statement::{Statement, write_block};
+use crate::ast::type_expression::TypeExpression;
+use crate::primitives::position::Position;
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Declaration {
+    pub kind: DeclarationKind,
+    /// Its documentation, from the comment right above it, without the comment's markers
+    pub doc: Option<String>,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeclarationKind {
+    // func main() { ... }
+    // func (p *Point) Scale(factor int) { ... }
+    Function {
+        receiver: Option<Parameter>,
+        name: String,
+        parameters: Vec<Parameter>,
+        results: Vec<Parameter>,
+        body: Vec<Statement>,
+    },
+    // var x = 1
+    // var (
+    //     y int
+    //     z = "z"
+    // )
+    Variable {
+        specs: Vec<VariableSpec>,
+        grouped: bool,
+    },
+    // const Pi = 3.14
+    // const (
+    //     A = iota
+    //     B
+    // )
+    // where a grouped spec without values repeats the type and values of the one before it
+    Constant {
+        specs: Vec<VariableSpec>,
+        grouped: bool,
+    },
+    // type Celsius float64
+    Type {
+        name: Expression,
+        type_expression: TypeExpression,
+    },
+}
+
+/// `a, b int` in a parameter or result list. Unnamed parameters, as in `func() (int, error)`,
+/// have no names.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parameter {
+    pub names: Vec<Expression>,
+    pub type_expression: TypeExpression,
+    // ...T
+    pub variadic: bool,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+/// `x, y int = 1, 2`, where either the type or the values may be left out
+/// (https://go.dev/ref/spec#Variable_declarations)
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariableSpec {
+    pub names: Vec<Expression>,
+    pub type_expression: Option<TypeExpression>,
+    pub values: Vec<Expression>,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+impl Declaration {
+    pub fn new(
+        kind: DeclarationKind,
+        position_start: Position,
+        position_end: Position,
+    ) -> Declaration {
+        Declaration {
+            kind,
+            doc: None,
+            position_start,
+            position_end,
+        }
+    }
+
+    pub fn new_function(
+        receiver: Option<Parameter>,
+        name: String,
+        parameters: Vec<Parameter>,
+        results: Vec<Parameter>,
+        body: Vec<Statement>,
+        start_pos: Position,
+        end_pos: Position,
+    ) -> Declaration {
+        Declaration::new(
+            DeclarationKind::Function {
+                receiver,
+                name,
+                parameters,
+                results,
+                body,
+            },
+            start_pos,
+            end_pos,
+        )
+    }
+}
+
+impl Declaration {
+    /// Reconstruct Go source for this declaration
+    pub fn to_source(&self) -> String {
+        self.to_string()
+    }
+
+    /// Move this declaration and everything inside it by `delta` lines
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.position_start.shift_lines(delta);
+        self.position_end.shift_lines(delta);
+        match &mut self.kind {
+            DeclarationKind::Function {
+                receiver,
+                parameters,
+                results,
+                body,
+                ..
+            } => {
+                for parameter in receiver.iter_mut().chain(parameters).chain(results) {
+                    parameter.shift_lines(delta);
+                }
+                for statement in body {
+                    statement.shift_lines(delta);
+                }
+            }
+            DeclarationKind::Variable { specs, .. } | DeclarationKind::Constant { specs, .. } => {
+                for spec in specs {
+                    spec.shift_lines(delta);
+                }
+            }
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } => {
+                name.shift_lines(delta);
+                type_expression.shift_lines(delta);
+            }
+        }
+    }
+
+    /// Drop the documentation of this declaration and the comments of the statements in it
+    pub fn strip_comments(&mut self) {
+        self.doc = None;
+        if let DeclarationKind::Function { body, .. } = &mut self.kind {
+            for statement in body {
+                statement.strip_comments();
+            }
+        }
+    }
+}
+
+impl Parameter {
+    pub fn new(
+        names: Vec<Expression>,
+        type_expression: TypeExpression,
+        variadic: bool,
+        position_start: Position,
+        position_end: Position,
+    ) -> Parameter {
+        Parameter {
+            names,
+            type_expression,
+            variadic,
+            position_start,
+            position_end,
+        }
+    }
+
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.position_start.shift_lines(delta);
+        self.position_end.shift_lines(delta);
+        for name in &mut self.names {
+            name.shift_lines(delta);
+        }
+        self.type_expression.shift_lines(delta);
+    }
+}
+
+impl VariableSpec {
+    pub fn new(
+        names: Vec<Expression>,
+        type_expression: Option<TypeExpression>,
+        values: Vec<Expression>,
+        position_start: Position,
+        position_end: Position,
+    ) -> VariableSpec {
+        VariableSpec {
+            names,
+            type_expression,
+            values,
+            position_start,
+            position_end,
+        }
+    }
+
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.position_start.shift_lines(delta);
+        self.position_end.shift_lines(delta);
+        for expression in self.names.iter_mut().chain(&mut self.values) {
+            expression.shift_lines(delta);
+        }
+        if let Some(type_expression) = &mut self.type_expression {
+            type_expression.shift_lines(delta);
+        }
+    }
+}
+
+impl std::fmt::Display for Declaration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            DeclarationKind::Function {
+                receiver,
+                name,
+                parameters,
+                results,
+                body,
+            } => {
+                write!(f, "func ")?;
+                if let Some(receiver) = receiver {
+                    write!(f, "({}) ", receiver)?;
+                }
+                write!(f, "{}", name)?;
+                write_signature(f, parameters, results)?;
+                write!(f, " ")?;
+                write_block(f, body)
+            }
+            DeclarationKind::Variable { specs, grouped }
+            | DeclarationKind::Constant { specs, grouped } => {
+                let keyword = match self.kind {
+                    DeclarationKind::Constant { .. } => "const",
+                    _ => "var",
+                };
+                if !grouped && let [spec] = specs.as_slice() {
+                    return write!(f, "{} {}", keyword, spec);
+                }
+                writeln!(f, "{} (", keyword)?;
+                for spec in specs {
+                    writeln!(f, "\t{}", spec)?;
+                }
+                write!(f, ")")
+            }
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } => write!(f, "type {} {}", name, type_expression),
+        }
+    }
+}
+
+/// `(a int) (string, error)`, the parameters and results after a function or method name
+pub fn write_signature(
+    f: &mut std::fmt::Formatter<'_>,
+    parameters: &[Parameter],
+    results: &[Parameter],
+) -> std::fmt::Result {
+    write!(f, "({})", join(parameters))?;
+    match results {
+        [] => Ok(()),
+        [result] if result.names.is_empty() => write!(f, " {}", result),
+        results => write!(f, " ({})", join(results)),
+    }
+}
+
+impl std::fmt::Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.names.is_empty() {
+            write!(f, "{} ", join(&self.names))?;
+        }
+        if self.variadic {
+            write!(f, "...")?;
+        }
+        write!(f, "{}", self.type_expression)
+    }
+}
+
+impl std::fmt::Display for VariableSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", join(&self.names))?;
+        if let Some(type_expression) = &self.type_expression {
+            write!(f, " {}", type_expression)?;
+        }
+        if !self.values.is_empty() {
+            write!(f, " = {}", join(&self.values))?;
+        }
+        Ok(())
+    }
+}
+
+/// A spec of a constant declaration, with the type and values it has once those left out are
+/// repeated from the spec before it
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ConstantSpec<'a> {
+    pub spec: &'a VariableSpec,
+    pub type_expression: Option<&'a TypeExpression>,
+    pub values: &'a [Expression],
+    /// The value of `iota` in the spec, its index in the declaration
+    pub iota: i64,
+}
+
+/// The specs of a constant declaration, where a spec without values repeats the type and values
+/// of the last one with them (https://go.dev/ref/spec#Constant_declarations)
+pub fn constant_specs(specs: &[VariableSpec]) -> Vec<ConstantSpec<'_>> {
+    let mut previous: Option<&VariableSpec> = None;
+    specs
+        .iter()
+        .enumerate()
+        .map(|(iota, spec)| {
+            if !spec.values.is_empty() {
+                previous = Some(spec);
+            }
+            let repeated = previous.unwrap_or(spec);
+            ConstantSpec {
+                spec,
+                type_expression: repeated.type_expression.as_ref(),
+                values: &repeated.values,
+                iota: iota as i64,
+            }
+        })
+        .collect()
+}
+
+/// `a, b, c`
+pub fn join<T: std::fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -0,0 +1,661 @@
+use crate::ast::ast::{File, ImportSpec};
+use crate::ast::declaration::{Declaration, DeclarationKind, Parameter, VariableSpec};
+use crate::ast::expression::{Expression, ExpressionKind, KeyedElement};
+use crate::ast::statement::{SelectCase, Statement, StatementKind, SwitchCase};
+use crate::ast::type_expression::{
+    InterfaceMethod, StructField, TypeExpression, TypeExpressionKind,
+};
+use crate::primitives::position::Position;
+
+/// How `dump` prints a tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DumpFormat {
+    // An indented outline, a node per line with its fields under it
+    Tree,
+    // A JSON object per node, with its kind, start, end and fields as keys
+    Json,
+    // An S-expression per node, its fields as keywords
+    Sexpr,
+}
+
+/// Print the tree of `file`. Each node is named for its kind, like `FunctionCall` or
+/// `IfStatement`, with the span of source it was parsed from and its fields by their names in
+/// the tree. Fields left at their defaults, like an empty list, a missing `else` or a `false`
+/// flag, are left out.
+pub fn dump(file: &File, format: DumpFormat) -> String {
+    let node = file_node(file);
+    let mut output = String::new();
+    match format {
+        DumpFormat::Tree => tree(&node, 0, &mut output),
+        DumpFormat::Json => json(&node, 0, &mut output),
+        DumpFormat::Sexpr => sexpr(&node, 0, &mut output),
+    }
+    output.push('\n');
+    output
+}
+
+/// A node of the tree as it's printed, which every kind of node in the tree is made into
+struct Node {
+    kind: &'static str,
+    span: Option<(Position, Position)>,
+    fields: Vec<(&'static str, Field)>,
+}
+
+enum Field {
+    Text(String),
+    Flag,
+    Child(Node),
+    Children(Vec<Node>),
+}
+
+impl Node {
+    fn new(kind: &'static str, start: Position, end: Position) -> Node {
+        Node {
+            kind,
+            span: Some((start, end)),
+            fields: Vec::new(),
+        }
+    }
+
+    fn text(mut self, name: &'static str, value: impl ToString) -> Node {
+        self.fields.push((name, Field::Text(value.to_string())));
+        self
+    }
+
+    fn flag(mut self, name: &'static str, value: bool) -> Node {
+        if value {
+            self.fields.push((name, Field::Flag));
+        }
+        self
+    }
+
+    fn child(mut self, name: &'static str, child: Option<Node>) -> Node {
+        if let Some(child) = child {
+            self.fields.push((name, Field::Child(child)));
+        }
+        self
+    }
+
+    fn children(mut self, name: &'static str, children: Vec<Node>) -> Node {
+        if !children.is_empty() {
+            self.fields.push((name, Field::Children(children)));
+        }
+        self
+    }
+
+    fn span(&self) -> String {
+        match self.span {
+            Some((start, end)) => format!(
+                "{}:{}-{}:{}",
+                start.line, start.column_start, end.line, end.column_end
+            ),
+            None => String::new(),
+        }
+    }
+}
+
+fn file_node(file: &File) -> Node {
+    let node = Node {
+        kind: "File",
+        span: None,
+        fields: Vec::new(),
+    }
+    .text("package_name", &file.package_name)
+    .children("imports", file.imports.iter().map(import_node).collect())
+    .children("decls", file.decls.iter().map(declaration_node).collect());
+    match &file.doc {
+        Some(doc) => node.text("doc", doc),
+        None => node,
+    }
+}
+
+fn import_node(import: &ImportSpec) -> Node {
+    let node = Node::new("ImportSpec", import.position_start, import.position_end);
+    match &import.name {
+        Some(name) => node.text("name", name),
+        None => node,
+    }
+    .text("path", &import.path)
+}
+
+fn declaration_node(declaration: &Declaration) -> Node {
+    let (start, end) = (declaration.position_start, declaration.position_end);
+    let node = match &declaration.kind {
+        DeclarationKind::Function {
+            receiver,
+            name,
+            parameters,
+            results,
+            body,
+        } => Node::new("FunctionDeclaration", start, end)
+            .child("receiver", receiver.as_ref().map(parameter_node))
+            .text("name", name)
+            .children(
+                "parameters",
+                parameters.iter().map(parameter_node).collect(),
+            )
+            .children("results", results.iter().map(parameter_node).collect())
+            .children("body", statement_nodes(body)),
+        DeclarationKind::Variable { specs, grouped } => {
+            Node::new("VariableDeclaration", start, end)
+                .children("specs", specs.iter().map(variable_spec_node).collect())
+                .flag("grouped", *grouped)
+        }
+        DeclarationKind::Constant { specs, grouped } => {
+            Node::new("ConstantDeclaration", start, end)
+                .children("specs", specs.iter().map(variable_spec_node).collect())
+                .flag("grouped", *grouped)
+        }
+        DeclarationKind::Type {
+            name,
+            type_expression,
+        } => Node::new("TypeDeclaration", start, end)
+            .child("name", Some(expression_node(name)))
+            .child("type_expression", Some(type_node(type_expression))),
+    };
+    match &declaration.doc {
+        Some(doc) => node.text("doc", doc),
+        None => node,
+    }
+}
+
+fn parameter_node(parameter: &Parameter) -> Node {
+    Node::new(
+        "Parameter",
+        parameter.position_start,
+        parameter.position_end,
+    )
+    .children("names", expression_nodes(&parameter.names))
+    .child(
+        "type_expression",
+        Some(type_node(&parameter.type_expression)),
+    )
+    .flag("variadic", parameter.variadic)
+}
+
+fn variable_spec_node(spec: &VariableSpec) -> Node {
+    Node::new("VariableSpec", spec.position_start, spec.position_end)
+        .children("names", expression_nodes(&spec.names))
+        .child(
+            "type_expression",
+            spec.type_expression.as_ref().map(type_node),
+        )
+        .children("values", expression_nodes(&spec.values))
+}
+
+fn statement_nodes(statements: &[Statement]) -> Vec<Node> {
+    statements.iter().map(statement_node).collect()
+}
+
+fn statement_node(statement: &Statement) -> Node {
+    let (start, end) = (statement.position_start, statement.position_end);
+    let node = match &statement.kind {
+        StatementKind::Expression(expression) => Node::new("ExpressionStatement", start, end)
+            .child("expression", Some(expression_node(expression))),
+        StatementKind::Declaration(declaration) => declaration_node(declaration),
+        StatementKind::Assignment {
+            left,
+            right,
+            define,
+        } => Node::new("Assignment", start, end)
+            .children("left", expression_nodes(left))
+            .children("right", expression_nodes(right))
+            .flag("define", *define),
+        StatementKind::IncDec { target, increment } => Node::new("IncDecStatement", start, end)
+            .child("target", Some(expression_node(target)))
+            .flag("increment", *increment),
+        StatementKind::OpAssign {
+            target,
+            operator,
+            value,
+        } => Node::new("OpAssignment", start, end)
+            .child("target", Some(expression_node(target)))
+            .text("operator", operator)
+            .child("value", Some(expression_node(value))),
+        StatementKind::For {
+            init,
+            condition,
+            post,
+            body,
+        } => Node::new("ForStatement", start, end)
+            .child("init", init.as_deref().map(statement_node))
+            .child("condition", condition.as_ref().map(expression_node))
+            .child("post", post.as_deref().map(statement_node))
+            .children("body", statement_nodes(body)),
+        StatementKind::Range {
+            key,
+            value,
+            define,
+            expression,
+            body,
+        } => Node::new("RangeStatement", start, end)
+            .child("key", key.as_ref().map(expression_node))
+            .child("value", value.as_ref().map(expression_node))
+            .flag("define", *define)
+            .child("expression", Some(expression_node(expression)))
+            .children("body", statement_nodes(body)),
+        StatementKind::Block(body) => {
+            Node::new("Block", start, end).children("body", statement_nodes(body))
+        }
+        StatementKind::If {
+            init,
+            condition,
+            body,
+            else_branch,
+        } => Node::new("IfStatement", start, end)
+            .child("init", init.as_deref().map(statement_node))
+            .child("condition", Some(expression_node(condition)))
+            .children("body", statement_nodes(body))
+            .child("else_branch", else_branch.as_deref().map(statement_node)),
+        StatementKind::Return(values) => {
+            Node::new("ReturnStatement", start, end).children("values", expression_nodes(values))
+        }
+        StatementKind::Send { channel, value } => Node::new("SendStatement", start, end)
+            .child("channel", Some(expression_node(channel)))
+            .child("value", Some(expression_node(value))),
+        StatementKind::Defer(call) => {
+            Node::new("DeferStatement", start, end).child("call", Some(expression_node(call)))
+        }
+        StatementKind::Go(call) => {
+            Node::new("GoStatement", start, end).child("call", Some(expression_node(call)))
+        }
+        StatementKind::Select(cases) => Node::new("SelectStatement", start, end)
+            .children("cases", cases.iter().map(select_case_node).collect()),
+        StatementKind::Switch { init, tag, cases } => Node::new("SwitchStatement", start, end)
+            .child("init", init.as_deref().map(statement_node))
+            .child("tag", tag.as_ref().map(expression_node))
+            .children("cases", cases.iter().map(switch_case_node).collect()),
+        StatementKind::Break => Node::new("BreakStatement", start, end),
+        StatementKind::Continue => Node::new("ContinueStatement", start, end),
+        StatementKind::Fallthrough => Node::new("FallthroughStatement", start, end),
+    };
+    match &statement.trailing_comment {
+        Some(comment) => node.text("trailing_comment", comment),
+        None => node,
+    }
+}
+
+fn select_case_node(case: &SelectCase) -> Node {
+    Node::new("SelectCase", case.position, case.position)
+        .child(
+            "communication",
+            case.communication.as_deref().map(statement_node),
+        )
+        .children("body", statement_nodes(&case.body))
+}
+
+fn switch_case_node(case: &SwitchCase) -> Node {
+    let node = Node::new("SwitchCase", case.position, case.position);
+    match &case.expressions {
+        Some(expressions) => node.children("expressions", expression_nodes(expressions)),
+        None => node,
+    }
+    .children("body", statement_nodes(&case.body))
+}
+
+fn expression_nodes(expressions: &[Expression]) -> Vec<Node> {
+    expressions.iter().map(expression_node).collect()
+}
+
+fn expression_node(expression: &Expression) -> Node {
+    let (start, end) = (expression.position_start, expression.position_end);
+    let boxed = |expression: &Option<Box<Expression>>| expression.as_deref().map(expression_node);
+    match &expression.kind {
+        ExpressionKind::Binary {
+            left,
+            operator,
+            right,
+        } => Node::new("Binary", start, end)
+            .child("left", Some(expression_node(left)))
+            .text("operator", operator)
+            .child("right", Some(expression_node(right))),
+        ExpressionKind::Identifier(name) => Node::new("Identifier", start, end).text("name", name),
+        ExpressionKind::BlankIdentifier => Node::new("BlankIdentifier", start, end),
+        ExpressionKind::IntegerLiteral(value) => {
+            Node::new("IntegerLiteral", start, end).text("value", value)
+        }
+        ExpressionKind::FloatLiteral(value) => {
+            Node::new("FloatLiteral", start, end).text("value", value)
+        }
+        ExpressionKind::RuneLiteral(value) => {
+            Node::new("RuneLiteral", start, end).text("value", value)
+        }
+        ExpressionKind::StringLiteral(value) => {
+            Node::new("StringLiteral", start, end).text("value", value)
+        }
+        ExpressionKind::FunctionCall {
+            name,
+            arguments,
+            spread,
+        } => Node::new("FunctionCall", start, end)
+            .child("name", Some(expression_node(name)))
+            .children("arguments", expression_nodes(arguments))
+            .flag("spread", *spread),
+        ExpressionKind::FieldAccess {
+            object,
+            field,
+            selection,
+        } => Node::new("FieldAccess", start, end)
+            .child("object", Some(expression_node(object)))
+            .text("field", field)
+            .text("selection", format!("{:?}", selection)),
+        ExpressionKind::Index { object, index } => Node::new("Index", start, end)
+            .child("object", Some(expression_node(object)))
+            .child("index", Some(expression_node(index))),
+        ExpressionKind::Slice {
+            object,
+            low,
+            high,
+            max,
+        } => Node::new("Slice", start, end)
+            .child("object", Some(expression_node(object)))
+            .child("low", boxed(low))
+            .child("high", boxed(high))
+            .child("max", boxed(max)),
+        ExpressionKind::Parenthesized(inner) => {
+            Node::new("Parenthesized", start, end).child("expression", Some(expression_node(inner)))
+        }
+        ExpressionKind::Conversion { target, expression } => Node::new("Conversion", start, end)
+            .child("target", Some(type_node(target)))
+            .child("expression", Some(expression_node(expression))),
+        ExpressionKind::Receive(channel) => {
+            Node::new("Receive", start, end).child("channel", Some(expression_node(channel)))
+        }
+        ExpressionKind::Unary { operator, operand } => Node::new("Unary", start, end)
+            .text("operator", operator)
+            .child("operand", Some(expression_node(operand))),
+        ExpressionKind::CompositeLiteral {
+            type_expression,
+            elements,
+        } => Node::new("CompositeLiteral", start, end)
+            .child("type_expression", type_expression.as_ref().map(type_node))
+            .children("elements", elements.iter().map(element_node).collect()),
+        ExpressionKind::FunctionLiteral {
+            parameters,
+            results,
+            body,
+        } => Node::new("FunctionLiteral", start, end)
+            .children(
+                "parameters",
+                parameters.iter().map(parameter_node).collect(),
+            )
+            .children("results", results.iter().map(parameter_node).collect())
+            .children("body", statement_nodes(body)),
+        ExpressionKind::Type(type_expression) => type_node(type_expression),
+    }
+}
+
+fn element_node(element: &KeyedElement) -> Node {
+    let start = element
+        .key
+        .as_ref()
+        .unwrap_or(&element.value)
+        .position_start;
+    Node::new("KeyedElement", start, element.value.position_end)
+        .child("key", element.key.as_ref().map(expression_node))
+        .child("value", Some(expression_node(&element.value)))
+}
+
+fn type_node(type_expression: &TypeExpression) -> Node {
+    let (start, end) = (type_expression.position_start, type_expression.position_end);
+    match &type_expression.kind {
+        TypeExpressionKind::Name(name) => Node::new("TypeName", start, end).text("name", name),
+        TypeExpressionKind::Qualified { package, name } => Node::new("QualifiedType", start, end)
+            .text("package", package)
+            .text("name", name),
+        TypeExpressionKind::Pointer(element) => {
+            Node::new("PointerType", start, end).child("element", Some(type_node(element)))
+        }
+        TypeExpressionKind::Slice(element) => {
+            Node::new("SliceType", start, end).child("element", Some(type_node(element)))
+        }
+        TypeExpressionKind::Array { length, element } => Node::new("ArrayType", start, end)
+            .child("length", Some(expression_node(length)))
+            .child("element", Some(type_node(element))),
+        TypeExpressionKind::Map { key, value } => Node::new("MapType", start, end)
+            .child("key", Some(type_node(key)))
+            .child("value", Some(type_node(value))),
+        TypeExpressionKind::Channel { direction, element } => Node::new("ChannelType", start, end)
+            .text("direction", format!("{:?}", direction))
+            .child("element", Some(type_node(element))),
+        TypeExpressionKind::Struct(fields) => Node::new("StructType", start, end)
+            .children("fields", fields.iter().map(field_node).collect()),
+        TypeExpressionKind::Interface(methods) => Node::new("InterfaceType", start, end)
+            .children("methods", methods.iter().map(method_node).collect()),
+        TypeExpressionKind::Function {
+            parameters,
+            results,
+        } => Node::new("FunctionType", start, end)
+            .children(
+                "parameters",
+                parameters.iter().map(parameter_node).collect(),
+            )
+            .children("results", results.iter().map(parameter_node).collect()),
+    }
+}
+
+fn field_node(field: &StructField) -> Node {
+    let node = Node::new("StructField", field.position_start, field.position_end)
+        .children("names", expression_nodes(&field.names))
+        .child("type_expression", Some(type_node(&field.type_expression)));
+    match &field.tag {
+        Some(tag) => node.text("tag", tag),
+        None => node,
+    }
+}
+
+fn method_node(method: &InterfaceMethod) -> Node {
+    Node::new(
+        "InterfaceMethod",
+        method.position_start,
+        method.position_end,
+    )
+    .text("name", &method.name)
+    .children(
+        "parameters",
+        method.parameters.iter().map(parameter_node).collect(),
+    )
+    .children(
+        "results",
+        method.results.iter().map(parameter_node).collect(),
+    )
+}
+
+/// ```text
+/// FunctionCall 3:2-3:15
+///   name:
+///     Identifier 3:2-3:8
+///       name: println
+/// ```
+fn tree(node: &Node, depth: usize, output: &mut String) {
+    output.push_str(node.kind);
+    if node.span.is_some() {
+        output.push(' ');
+        output.push_str(&node.span());
+    }
+    let indent = "  ".repeat(depth + 1);
+    for (name, field) in &node.fields {
+        output.push_str(&format!("\n{}{}:", indent, name));
+        match field {
+            Field::Text(text) => output.push_str(&format!(" {}", text)),
+            Field::Flag => output.push_str(" true"),
+            Field::Child(child) => {
+                output.push_str(&format!("\n{}  ", indent));
+                tree(child, depth + 2, output);
+            }
+            Field::Children(children) => {
+                for child in children {
+                    output.push_str(&format!("\n{}  ", indent));
+                    tree(child, depth + 2, output);
+                }
+            }
+        }
+    }
+}
+
+/// Pretty-printed with two space indents, each position an object of its line and column
+fn json(node: &Node, depth: usize, output: &mut String) {
+    let indent = "  ".repeat(depth + 1);
+    output.push_str(&format!("{{\n{}\"kind\": \"{}\"", indent, node.kind));
+    if let Some((start, end)) = node.span {
+        for (name, line, column) in [
+            ("start", start.line, start.column_start),
+            ("end", end.line, end.column_end),
+        ] {
+            output.push_str(&format!(
+                ",\n{}\"{}\": {{\"line\": {}, \"column\": {}}}",
+                indent, name, line, column
+            ));
+        }
+    }
+    for (name, field) in &node.fields {
+        output.push_str(&format!(",\n{}\"{}\": ", indent, name));
+        match field {
+            Field::Text(text) => output.push_str(&json_string(text)),
+            Field::Flag => output.push_str("true"),
+            Field::Child(child) => json(child, depth + 1, output),
+            Field::Children(children) => {
+                output.push('[');
+                for (index, child) in children.iter().enumerate() {
+                    if index > 0 {
+                        output.push(',');
+                    }
+                    output.push_str(&format!("\n{}  ", indent));
+                    json(child, depth + 2, output);
+                }
+                output.push_str(&format!("\n{}]", indent));
+            }
+        }
+    }
+    output.push_str(&format!("\n{}}}", "  ".repeat(depth)));
+}
+
+/// `text` as a JSON string, quoted and escaped
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                quoted.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Text fields and flags go on the node's line, and the nodes it holds each on one of their
+/// own, like `(Identifier 3:2-3:8 :name "println")`
+fn sexpr(node: &Node, depth: usize, output: &mut String) {
+    output.push('(');
+    output.push_str(node.kind);
+    if node.span.is_some() {
+        output.push(' ');
+        output.push_str(&node.span());
+    }
+    for (name, field) in &node.fields {
+        match field {
+            Field::Text(text) => output.push_str(&format!(" :{} {:?}", name, text)),
+            Field::Flag => output.push_str(&format!(" :{} true", name)),
+            _ => {}
+        }
+    }
+    let indent = "  ".repeat(depth + 1);
+    for (name, field) in &node.fields {
+        match field {
+            Field::Child(child) => {
+                output.push_str(&format!("\n{}:{} ", indent, name));
+                sexpr(child, depth + 1, output);
+            }
+            Field::Children(children) => {
+                output.push_str(&format!("\n{}:{} (", indent, name));
+                for (index, child) in children.iter().enumerate() {
+                    if index > 0 {
+                        output.push_str(&format!("\n{}  ", indent));
+                    }
+                    sexpr(child, depth + 2, output);
+                }
+                output.push(')');
+            }
+            _ => {}
+        }
+    }
+    output.push(')');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    const SOURCE: &str =
+        "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"hi\\n\") // greet\n}\n";
+
+    #[test]
+    fn dump_tree() {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        assert_eq!(
+            dump(&file, DumpFormat::Tree),
+            r#"File
+  package_name: main
+  imports:
+    ImportSpec 3:7-3:12
+      path: "fmt"
+  decls:
+    FunctionDeclaration 5:0-7:1
+      name: main
+      body:
+        ExpressionStatement 6:1-6:20
+          expression:
+            FunctionCall 6:1-6:20
+              name:
+                FieldAccess 6:1-6:12
+                  object:
+                    Identifier 6:1-6:4
+                      name: fmt
+                  field: Println
+                  selection: Unresolved
+              arguments:
+                StringLiteral 6:13-6:19
+                  value: "hi\n"
+          trailing_comment: // greet
+"#
+        );
+    }
+
+    #[test]
+    fn dump_json() {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        let dumped = dump(&file, DumpFormat::Json);
+        let value: serde_json::Value = serde_json::from_str(&dumped).unwrap();
+        let call = &value["decls"][0]["body"][0]["expression"];
+        assert_eq!(call["kind"], "FunctionCall");
+        assert_eq!(call["arguments"][0]["value"], "\"hi\\n\"");
+        assert_eq!(call["name"]["start"]["line"], 6);
+        assert!(value["decls"][0].get("receiver").is_none());
+    }
+
+    #[test]
+    fn dump_sexpr() {
+        let file = Parser::new("package main\n\nvar x, y = 1, 2\n")
+            .parse()
+            .unwrap();
+        assert_eq!(
+            dump(&file, DumpFormat::Sexpr),
+            r#"(File :package_name "main"
+  :decls ((VariableDeclaration 3:0-3:15
+      :specs ((VariableSpec 3:4-3:15
+          :names ((Identifier 3:4-3:5 :name "x")
+            (Identifier 3:7-3:8 :name "y"))
+          :values ((IntegerLiteral 3:11-3:12 :value "1")
+            (IntegerLiteral 3:14-3:15 :value "2")))))))
+"#
+        );
+    }
+}
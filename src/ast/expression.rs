@@ -1,7 +1,13 @@
-use crate::primitives::position::Position;
+use crate::ast::declaration::{Parameter, write_signature};
+use crate::ast::statement::{Statement, write_block};
+use crate::ast::type_expression::{ChannelDirection, TypeExpression, TypeExpressionKind};
 use crate::lexer::token_type::Operator;
+use crate::primitives::position::Position;
 
+/// `position_start` is the position of the first token of the expression and `position_end`
+/// the position of its last token, so a single-token expression has equal start and end
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Expression {
     pub kind: ExpressionKind,
     pub position_start: Position,
@@ -9,6 +15,7 @@ pub struct Expression {
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExpressionKind {
     // 10 + 20 * 30
     Binary {
@@ -18,24 +25,143 @@ pub enum ExpressionKind {
     },
     // foo
     Identifier(String),
+    // _ (https://go.dev/ref/spec#Blank_identifier)
+    BlankIdentifier,
     // 10
     IntegerLiteral(String),
+    // 1.5
+    FloatLiteral(String),
+    // 'a'
+    RuneLiteral(String),
     // "hello there"
     StringLiteral(String),
-    // foo(bar, baz)
+    // foo(bar, baz), or foo(bar, baz...) passing a slice as the variadic parameter
     FunctionCall {
         name: Box<Expression>, // Can be simple identifier or field access
         arguments: Vec<Expression>,
+        spread: bool,
     },
     // foo.bar
     FieldAccess {
         object: Box<Expression>, // fmt
         field: String,           // Println
+        selection: SelectionKind,
+    },
+    // a[i], or m[k] for a map
+    Index {
+        object: Box<Expression>,
+        index: Box<Expression>,
+    },
+    // a[low:high] or a[low:high:max], where low and high may be left out of the first form
+    // (https://go.dev/ref/spec#Slice_expressions)
+    Slice {
+        object: Box<Expression>,
+        low: Option<Box<Expression>>,
+        high: Option<Box<Expression>>,
+        max: Option<Box<Expression>>,
     },
     // (expr)
     Parenthesized(Box<Expression>), // (expr)
+    // []byte(s), a conversion to a type literal that can't be mistaken for a call. Conversions
+    // to named types like `string(b)` parse as function calls until names are resolved.
+    Conversion {
+        target: TypeExpression,
+        expression: Box<Expression>,
+    },
+    // <-ch (https://go.dev/ref/spec#Receive_operator)
+    Receive(Box<Expression>),
+    // -x, !ok, &point or *pointer (https://go.dev/ref/spec#Operators)
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<Expression>,
+    },
+    // Point{X: 1, Y: 2} or []int{1, 2}, or {1, 2} inside another literal of a type that already
+    // says what its elements are (https://go.dev/ref/spec#Composite_literals)
+    CompositeLiteral {
+        type_expression: Option<TypeExpression>,
+        elements: Vec<KeyedElement>,
+    },
+    // func(x int) int { return x * 2 }, which can refer to the variables of the function it's
+    // in (https://go.dev/ref/spec#Function_literals)
+    FunctionLiteral {
+        parameters: Vec<Parameter>,
+        results: Vec<Parameter>,
+        body: Vec<Statement>,
+    },
+    // The `[]int` in make([]int, n), a type literal passed to a builtin. Type names like the
+    // `int` in new(int) parse as identifiers.
+    Type(TypeExpression),
+}
+
+/// What a selector `x.f` refers to. The parser can't tell these apart without types, so it
+/// leaves every selector `Unresolved` for the type checker to fill in
+/// (https://go.dev/ref/spec#Selectors, https://go.dev/ref/spec#Method_values)
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelectionKind {
+    Unresolved,
+    // point.x
+    Field,
+    // instance.Method, a function with the receiver already bound
+    MethodValue,
+    // T.Method or (*T).Method, a function taking the receiver as its first argument
+    MethodExpression,
+    // fmt.Println
+    QualifiedIdentifier,
+}
+
+/// The operators of unary expressions other than receiving
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnaryOperator {
+    // +x
+    Plus,
+    // -x
+    Minus,
+    // !x
+    Not,
+    // ^x, the bitwise complement
+    Complement,
+    // &x
+    Address,
+    // *x
+    Dereference,
+}
+
+impl std::fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = match self {
+            UnaryOperator::Plus => "+",
+            UnaryOperator::Minus => "-",
+            UnaryOperator::Not => "!",
+            UnaryOperator::Complement => "^",
+            UnaryOperator::Address => "&",
+            UnaryOperator::Dereference => "*",
+        };
+        write!(f, "{}", operator)
+    }
+}
+
+/// An element of a composite literal, with its field name, index or map key if it's given one
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyedElement {
+    pub key: Option<Expression>,
+    pub value: Expression,
 }
 
+impl std::fmt::Display for KeyedElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.key {
+            Some(key) => write!(f, "{}: {}", key, self.value),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+/// Unary operators bind tighter than any binary operator
+const UNARY_PRECEDENCE: u8 = 6;
+
 impl Expression {
     pub fn new(
         kind: ExpressionKind,
@@ -49,14 +175,127 @@ impl Expression {
         }
     }
 
+    /// Reconstruct Go source for this expression
+    pub fn to_source(&self) -> String {
+        self.to_string()
+    }
+
+    /// Binding strength used to decide where parentheses are needed when printing;
+    /// anything that isn't a binary expression is an operand and binds tightest
+    fn precedence(&self) -> u8 {
+        match &self.kind {
+            ExpressionKind::Binary { operator, .. } => operator.precedence(),
+            ExpressionKind::Receive(_) | ExpressionKind::Unary { .. } => UNARY_PRECEDENCE,
+            _ => u8::MAX,
+        }
+    }
+
+    /// Move this expression and everything inside it by `delta` lines
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.position_start.shift_lines(delta);
+        self.position_end.shift_lines(delta);
+        match &mut self.kind {
+            ExpressionKind::Binary { left, right, .. } => {
+                left.shift_lines(delta);
+                right.shift_lines(delta);
+            }
+            ExpressionKind::Identifier(_)
+            | ExpressionKind::BlankIdentifier
+            | ExpressionKind::IntegerLiteral(_)
+            | ExpressionKind::FloatLiteral(_)
+            | ExpressionKind::RuneLiteral(_)
+            | ExpressionKind::StringLiteral(_) => {}
+            ExpressionKind::FunctionCall {
+                name, arguments, ..
+            } => {
+                name.shift_lines(delta);
+                for argument in arguments {
+                    argument.shift_lines(delta);
+                }
+            }
+            ExpressionKind::FieldAccess { object, .. } => object.shift_lines(delta),
+            ExpressionKind::Index { object, index } => {
+                object.shift_lines(delta);
+                index.shift_lines(delta);
+            }
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                object.shift_lines(delta);
+                for bound in [low, high, max].into_iter().flatten() {
+                    bound.shift_lines(delta);
+                }
+            }
+            ExpressionKind::Parenthesized(inner) => inner.shift_lines(delta),
+            ExpressionKind::Conversion { target, expression } => {
+                target.shift_lines(delta);
+                expression.shift_lines(delta);
+            }
+            ExpressionKind::Receive(channel) => channel.shift_lines(delta),
+            ExpressionKind::Unary { operand, .. } => operand.shift_lines(delta),
+            ExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            } => {
+                if let Some(type_expression) = type_expression {
+                    type_expression.shift_lines(delta);
+                }
+                for element in elements {
+                    if let Some(key) = &mut element.key {
+                        key.shift_lines(delta);
+                    }
+                    element.value.shift_lines(delta);
+                }
+            }
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            } => {
+                for parameter in parameters.iter_mut().chain(results) {
+                    parameter.shift_lines(delta);
+                }
+                for statement in body {
+                    statement.shift_lines(delta);
+                }
+            }
+            ExpressionKind::Type(type_expression) => type_expression.shift_lines(delta),
+        }
+    }
+
+    /// Only calls and receive operations may appear in statement context
+    /// (https://go.dev/ref/spec#Expression_statements)
+    pub fn is_valid_statement(&self) -> bool {
+        match &self.kind {
+            ExpressionKind::FunctionCall { .. } | ExpressionKind::Receive(_) => true,
+            ExpressionKind::Parenthesized(inner) => inner.is_valid_statement(),
+            _ => false,
+        }
+    }
+
     pub fn new_identifier(value: String, position: Position) -> Expression {
         Expression::new(ExpressionKind::Identifier(value), position, position)
     }
 
+    pub fn new_blank_identifier(position: Position) -> Expression {
+        Expression::new(ExpressionKind::BlankIdentifier, position, position)
+    }
+
     pub fn new_integer_literal(value: String, position: Position) -> Expression {
         Expression::new(ExpressionKind::IntegerLiteral(value), position, position)
     }
 
+    pub fn new_float_literal(value: String, position: Position) -> Expression {
+        Expression::new(ExpressionKind::FloatLiteral(value), position, position)
+    }
+
+    pub fn new_rune_literal(value: String, position: Position) -> Expression {
+        Expression::new(ExpressionKind::RuneLiteral(value), position, position)
+    }
+
     pub fn new_string_literal(value: String, position: Position) -> Expression {
         Expression::new(ExpressionKind::StringLiteral(value), position, position)
     }
@@ -64,6 +303,7 @@ impl Expression {
     pub fn new_function_call(
         name: Expression,
         arguments: Vec<Expression>,
+        spread: bool,
         start_pos: Position,
         end_pos: Position,
     ) -> Expression {
@@ -71,6 +311,7 @@ impl Expression {
             ExpressionKind::FunctionCall {
                 name: Box::new(name),
                 arguments,
+                spread,
             },
             start_pos,
             end_pos,
@@ -87,6 +328,43 @@ impl Expression {
             ExpressionKind::FieldAccess {
                 object: Box::new(object),
                 field,
+                selection: SelectionKind::Unresolved,
+            },
+            start_pos,
+            end_pos,
+        )
+    }
+
+    pub fn new_index(
+        object: Expression,
+        index: Expression,
+        start_pos: Position,
+        end_pos: Position,
+    ) -> Expression {
+        Expression::new(
+            ExpressionKind::Index {
+                object: Box::new(object),
+                index: Box::new(index),
+            },
+            start_pos,
+            end_pos,
+        )
+    }
+
+    pub fn new_slice(
+        object: Expression,
+        low: Option<Expression>,
+        high: Option<Expression>,
+        max: Option<Expression>,
+        start_pos: Position,
+        end_pos: Position,
+    ) -> Expression {
+        Expression::new(
+            ExpressionKind::Slice {
+                object: Box::new(object),
+                low: low.map(Box::new),
+                high: high.map(Box::new),
+                max: max.map(Box::new),
             },
             start_pos,
             end_pos,
@@ -111,6 +389,86 @@ impl Expression {
         )
     }
 
+    pub fn new_receive(channel: Expression, start_pos: Position) -> Expression {
+        let end_pos = channel.position_end;
+        Expression::new(
+            ExpressionKind::Receive(Box::new(channel)),
+            start_pos,
+            end_pos,
+        )
+    }
+
+    pub fn new_unary(
+        operator: UnaryOperator,
+        operand: Expression,
+        start_pos: Position,
+    ) -> Expression {
+        let end_pos = operand.position_end;
+        Expression::new(
+            ExpressionKind::Unary {
+                operator,
+                operand: Box::new(operand),
+            },
+            start_pos,
+            end_pos,
+        )
+    }
+
+    pub fn new_composite_literal(
+        type_expression: Option<TypeExpression>,
+        elements: Vec<KeyedElement>,
+        start_pos: Position,
+        end_pos: Position,
+    ) -> Expression {
+        Expression::new(
+            ExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            },
+            start_pos,
+            end_pos,
+        )
+    }
+
+    pub fn new_function_literal(
+        parameters: Vec<Parameter>,
+        results: Vec<Parameter>,
+        body: Vec<Statement>,
+        start_pos: Position,
+        end_pos: Position,
+    ) -> Expression {
+        Expression::new(
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            },
+            start_pos,
+            end_pos,
+        )
+    }
+
+    pub fn new_type(type_expression: TypeExpression) -> Expression {
+        let (start_pos, end_pos) = (type_expression.position_start, type_expression.position_end);
+        Expression::new(ExpressionKind::Type(type_expression), start_pos, end_pos)
+    }
+
+    pub fn new_conversion(
+        target: TypeExpression,
+        expression: Expression,
+        start_pos: Position,
+        end_pos: Position,
+    ) -> Expression {
+        Expression::new(
+            ExpressionKind::Conversion {
+                target,
+                expression: Box::new(expression),
+            },
+            start_pos,
+            end_pos,
+        )
+    }
+
     pub fn new_parenthesized(
         expression: Expression,
         start_pos: Position,
@@ -124,3 +482,239 @@ impl Expression {
     }
 }
 
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                // Operators are left associative, so an equal precedence on the right needs
+                // parentheses to survive a round trip
+                if left.precedence() < operator.precedence() {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+                write!(f, " {} ", operator)?;
+                if right.precedence() <= operator.precedence() {
+                    write!(f, "({})", right)
+                } else {
+                    write!(f, "{}", right)
+                }
+            }
+            ExpressionKind::Identifier(name) => write!(f, "{}", name),
+            ExpressionKind::BlankIdentifier => write!(f, "_"),
+            ExpressionKind::IntegerLiteral(value)
+            | ExpressionKind::FloatLiteral(value)
+            | ExpressionKind::RuneLiteral(value) => write!(f, "{}", value),
+            ExpressionKind::StringLiteral(value) => write!(f, "{}", value),
+            ExpressionKind::FunctionCall {
+                name,
+                arguments,
+                spread,
+            } => {
+                if name.precedence() == u8::MAX {
+                    write!(f, "{}(", name)?;
+                } else {
+                    write!(f, "({})(", name)?;
+                }
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", argument)?;
+                }
+                if *spread {
+                    write!(f, "...")?;
+                }
+                write!(f, ")")
+            }
+            ExpressionKind::FieldAccess { object, field, .. } => {
+                // `1.x` would lex as the float `1.` followed by `x`
+                let is_integer = matches!(object.kind, ExpressionKind::IntegerLiteral(_));
+                if object.precedence() == u8::MAX && !is_integer {
+                    write!(f, "{}.{}", object, field)
+                } else {
+                    write!(f, "({}).{}", object, field)
+                }
+            }
+            ExpressionKind::Index { object, index } => {
+                write_operand(f, object)?;
+                write!(f, "[{}]", index)
+            }
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                write_operand(f, object)?;
+                write!(f, "[")?;
+                if let Some(low) = low {
+                    write!(f, "{}", low)?;
+                }
+                write!(f, ":")?;
+                if let Some(high) = high {
+                    write!(f, "{}", high)?;
+                }
+                if let Some(max) = max {
+                    write!(f, ":{}", max)?;
+                }
+                write!(f, "]")
+            }
+            ExpressionKind::Parenthesized(expression) => write!(f, "({})", expression),
+            ExpressionKind::Conversion { target, expression } => match &target.kind {
+                // `*T(x)` and `<-chan T(x)` would apply the operator to a conversion
+                TypeExpressionKind::Pointer(_)
+                | TypeExpressionKind::Channel {
+                    direction: ChannelDirection::Receive,
+                    ..
+                } => write!(f, "({})({})", target, expression),
+                _ => write!(f, "{}({})", target, expression),
+            },
+            ExpressionKind::Receive(channel) if channel.precedence() < UNARY_PRECEDENCE => {
+                write!(f, "<-({})", channel)
+            }
+            ExpressionKind::Receive(channel) => write!(f, "<-{}", channel),
+            // `- -x` and `& &x` would lex as `--x` and `&&x`
+            ExpressionKind::Unary { operator, operand }
+                if operand.precedence() <= UNARY_PRECEDENCE =>
+            {
+                write!(f, "{}({})", operator, operand)
+            }
+            ExpressionKind::Unary { operator, operand } => write!(f, "{}{}", operator, operand),
+            ExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            } => {
+                if let Some(type_expression) = type_expression {
+                    write!(f, "{}", type_expression)?;
+                }
+                let elements: Vec<String> = elements.iter().map(KeyedElement::to_string).collect();
+                write!(f, "{{{}}}", elements.join(", "))
+            }
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            } => {
+                write!(f, "func")?;
+                write_signature(f, parameters, results)?;
+                write!(f, " ")?;
+                write_block(f, body)
+            }
+            ExpressionKind::Type(type_expression) => write!(f, "{}", type_expression),
+        }
+    }
+}
+
+/// The operand of an index or slice expression, parenthesized when it's an operation that
+/// would otherwise capture the brackets, as in `(<-c)[0]`
+fn write_operand(f: &mut std::fmt::Formatter<'_>, operand: &Expression) -> std::fmt::Result {
+    if operand.precedence() == u8::MAX {
+        write!(f, "{}", operand)
+    } else {
+        write!(f, "({})", operand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identifier(name: &str) -> Expression {
+        Expression::new_identifier(name.to_string(), Position::new(1, 0, 0))
+    }
+
+    fn binary(left: Expression, operator: Operator, right: Expression) -> Expression {
+        Expression::new_binary(
+            left,
+            operator,
+            right,
+            Position::new(1, 0, 0),
+            Position::new(1, 0, 0),
+        )
+    }
+
+    #[test]
+    fn print_binary_precedence() {
+        // a + b * c
+        let expression = binary(
+            identifier("a"),
+            Operator::Plus,
+            binary(identifier("b"), Operator::Star, identifier("c")),
+        );
+        assert_eq!(expression.to_source(), "a + b * c");
+
+        // (a + b) * c
+        let expression = binary(
+            binary(identifier("a"), Operator::Plus, identifier("b")),
+            Operator::Star,
+            identifier("c"),
+        );
+        assert_eq!(expression.to_source(), "(a + b) * c");
+    }
+
+    #[test]
+    fn print_binary_associativity() {
+        // a - (b - c)
+        let expression = binary(
+            identifier("a"),
+            Operator::Minus,
+            binary(identifier("b"), Operator::Minus, identifier("c")),
+        );
+        assert_eq!(expression.to_source(), "a - (b - c)");
+
+        // a - b - c
+        let expression = binary(
+            binary(identifier("a"), Operator::Minus, identifier("b")),
+            Operator::Minus,
+            identifier("c"),
+        );
+        assert_eq!(expression.to_source(), "a - b - c");
+    }
+
+    #[test]
+    fn print_function_call() {
+        let expression = Expression::new_function_call(
+            Expression::new_field_access(
+                identifier("fmt"),
+                "Println".to_string(),
+                Position::new(1, 0, 0),
+                Position::new(1, 0, 0),
+            ),
+            vec![
+                Expression::new_string_literal("\"hi\"".to_string(), Position::new(1, 0, 0)),
+                Expression::new_integer_literal("42".to_string(), Position::new(1, 0, 0)),
+            ],
+            false,
+            Position::new(1, 0, 0),
+            Position::new(1, 0, 0),
+        );
+        assert_eq!(expression.to_source(), "fmt.Println(\"hi\", 42)");
+
+        let expression = Expression::new_function_call(
+            identifier("append"),
+            vec![identifier("a"), identifier("b")],
+            true,
+            Position::new(1, 0, 0),
+            Position::new(1, 0, 0),
+        );
+        assert_eq!(expression.to_source(), "append(a, b...)");
+    }
+
+    #[test]
+    fn print_call_on_binary() {
+        // (a + b)()
+        let expression = Expression::new_function_call(
+            binary(identifier("a"), Operator::Plus, identifier("b")),
+            vec![],
+            false,
+            Position::new(1, 0, 0),
+            Position::new(1, 0, 0),
+        );
+        assert_eq!(expression.to_source(), "(a + b)()");
+    }
+}
@@ -1,3 +1,11 @@
+pub mod arena;
+#[allow(clippy::module_inception)]
 pub mod ast;
-pub mod statement;
+pub mod compare;
+pub mod declaration;
+pub mod dump;
 pub mod expression;
+pub mod outline;
+pub mod statement;
+pub mod structure;
+pub mod type_expression;
@@ -0,0 +1,388 @@
+use crate::ast::ast::File;
+use crate::ast::declaration::{DeclarationKind, Parameter, write_signature};
+use crate::ast::dump::json_string;
+use crate::ast::type_expression::{
+    InterfaceMethod, StructField, TypeExpression, TypeExpressionKind,
+};
+use crate::primitives::position::Position;
+
+/// A file's package, imports and package level declarations, as an editor's outline view
+/// lists them. A struct's fields, an interface's methods and the methods the file declares
+/// on a type are listed under the type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outline {
+    pub package: String,
+    pub imports: Vec<Entry>,
+    pub symbols: Vec<Entry>,
+}
+
+/// An import or declaration of an outline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub kind: EntryKind,
+    /// What it declares, which for an import is the name the file uses the package by
+    pub name: String,
+    /// Its declaration on a line, without a function's body or a type's fields, like
+    /// `func (p *Point) Scale(factor int)` or `type Point struct`
+    pub detail: String,
+    pub line: usize,
+    pub children: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    Import,
+    Type,
+    // A struct type's field
+    Field,
+    // A method declared on a type, or one an interface type has
+    Method,
+    Function,
+    Variable,
+    Constant,
+}
+
+impl EntryKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntryKind::Import => "import",
+            EntryKind::Type => "type",
+            EntryKind::Field => "field",
+            EntryKind::Method => "method",
+            EntryKind::Function => "function",
+            EntryKind::Variable => "variable",
+            EntryKind::Constant => "constant",
+        }
+    }
+}
+
+/// The outline of `file`, its entries in the order they're declared. A method is listed
+/// under its receiver's type if the file declares the type, and on its own otherwise.
+pub fn outline(file: &File) -> Outline {
+    let imports = file
+        .imports
+        .iter()
+        .map(|import| {
+            let detail = format!("import {}", import);
+            Entry::new(
+                EntryKind::Import,
+                import.local_name(),
+                detail,
+                import.position_start,
+            )
+        })
+        .collect();
+    let mut symbols = Vec::new();
+    let mut methods = Vec::new();
+    for declaration in &file.decls {
+        let position = declaration.position_start;
+        match &declaration.kind {
+            DeclarationKind::Function {
+                receiver: None,
+                name,
+                parameters,
+                results,
+                ..
+            } => {
+                let detail = format!("func {}{}", name, Signature(parameters, results));
+                symbols.push(Entry::new(
+                    EntryKind::Function,
+                    name.clone(),
+                    detail,
+                    position,
+                ));
+            }
+            DeclarationKind::Function {
+                receiver: Some(receiver),
+                name,
+                parameters,
+                results,
+                ..
+            } => {
+                let signature = Signature(parameters, results);
+                let detail = format!("func ({}) {}{}", receiver, name, signature);
+                let method = Entry::new(EntryKind::Method, name.clone(), detail, position);
+                methods.push((base_type_name(&receiver.type_expression), method));
+            }
+            DeclarationKind::Variable { specs, .. } | DeclarationKind::Constant { specs, .. } => {
+                let (kind, keyword) = match declaration.kind {
+                    DeclarationKind::Constant { .. } => (EntryKind::Constant, "const"),
+                    _ => (EntryKind::Variable, "var"),
+                };
+                for spec in specs {
+                    for name in &spec.names {
+                        let detail = match &spec.type_expression {
+                            Some(type_expression) => {
+                                format!("{} {} {}", keyword, name, type_expression)
+                            }
+                            None => format!("{} {}", keyword, name),
+                        };
+                        let name = name.to_string();
+                        symbols.push(Entry::new(kind, name, detail, spec.position_start));
+                    }
+                }
+            }
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } => {
+                let (written, children) = match &type_expression.kind {
+                    TypeExpressionKind::Struct(fields) => {
+                        ("struct".to_string(), field_entries(fields))
+                    }
+                    TypeExpressionKind::Interface(methods) => {
+                        ("interface".to_string(), interface_entries(methods))
+                    }
+                    _ => (type_expression.to_string(), Vec::new()),
+                };
+                let detail = format!("type {} {}", name, written);
+                let mut entry = Entry::new(EntryKind::Type, name.to_string(), detail, position);
+                entry.children = children;
+                symbols.push(entry);
+            }
+        }
+    }
+    for (receiver, method) in methods {
+        let owner = symbols
+            .iter_mut()
+            .find(|symbol| symbol.kind == EntryKind::Type && symbol.name == receiver);
+        match owner {
+            Some(owner) => owner.children.push(method),
+            None => symbols.push(method),
+        }
+    }
+    // Methods on types declared elsewhere go among the rest by where they're declared
+    symbols.sort_by_key(|symbol| symbol.line);
+    Outline {
+        package: file.package_name.clone(),
+        imports,
+        symbols,
+    }
+}
+
+/// A struct's fields, an entry per name, with an embedded field named after its type
+fn field_entries(fields: &[StructField]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    for field in fields {
+        let position = field.position_start;
+        if field.names.is_empty() {
+            let name = base_type_name(&field.type_expression);
+            let detail = field.type_expression.to_string();
+            entries.push(Entry::new(EntryKind::Field, name, detail, position));
+        }
+        for name in &field.names {
+            let detail = format!("{} {}", name, field.type_expression);
+            entries.push(Entry::new(
+                EntryKind::Field,
+                name.to_string(),
+                detail,
+                position,
+            ));
+        }
+    }
+    entries
+}
+
+fn interface_entries(methods: &[InterfaceMethod]) -> Vec<Entry> {
+    methods
+        .iter()
+        .map(|method| {
+            let signature = Signature(&method.parameters, &method.results);
+            let detail = format!("{}{}", method.name, signature);
+            Entry::new(
+                EntryKind::Method,
+                method.name.clone(),
+                detail,
+                method.position_start,
+            )
+        })
+        .collect()
+}
+
+impl Entry {
+    fn new(kind: EntryKind, name: String, detail: String, position: Position) -> Entry {
+        Entry {
+            kind,
+            name,
+            detail,
+            line: position.line,
+            children: Vec::new(),
+        }
+    }
+
+    fn json(&self, output: &mut String) {
+        output.push_str(&format!(
+            "{{\"kind\": \"{}\", \"name\": {}, \"detail\": {}, \"line\": {}",
+            self.kind.name(),
+            json_string(&self.name),
+            json_string(&self.detail),
+            self.line
+        ));
+        if !self.children.is_empty() {
+            output.push_str(", \"children\": ");
+            json_entries(&self.children, output);
+        }
+        output.push('}');
+    }
+}
+
+impl Outline {
+    /// The outline as lines of text: the package clause, then each entry's line number and
+    /// its declaration, indented under the type it's part of
+    pub fn to_text(&self) -> String {
+        let width = last_line(&self.imports)
+            .max(last_line(&self.symbols))
+            .to_string()
+            .len();
+        let mut text = format!("package {}\n", self.package);
+        text_entries(&self.imports, 0, width, &mut text);
+        text_entries(&self.symbols, 0, width, &mut text);
+        text
+    }
+
+    /// The outline as a JSON object on a line, with the package's name, and its imports and
+    /// symbols as arrays of objects of their kind, name, detail, line and any children
+    pub fn to_json(&self) -> String {
+        let mut output = format!(
+            "{{\"package\": {}, \"imports\": ",
+            json_string(&self.package)
+        );
+        json_entries(&self.imports, &mut output);
+        output.push_str(", \"symbols\": ");
+        json_entries(&self.symbols, &mut output);
+        output.push('}');
+        output
+    }
+}
+
+/// Each entry on a line after its line number, right-aligned to `width`, then its children
+/// indented under it
+fn text_entries(entries: &[Entry], depth: usize, width: usize, output: &mut String) {
+    for entry in entries {
+        output.push_str(&format!(
+            "{:>width$}  {}{}\n",
+            entry.line,
+            "  ".repeat(depth),
+            entry.detail
+        ));
+        text_entries(&entry.children, depth + 1, width, output);
+    }
+}
+
+/// The greatest line number of the entries and their children
+fn last_line(entries: &[Entry]) -> usize {
+    entries
+        .iter()
+        .map(|entry| entry.line.max(last_line(&entry.children)))
+        .max()
+        .unwrap_or(0)
+}
+
+fn json_entries(entries: &[Entry], output: &mut String) {
+    output.push('[');
+    for (index, entry) in entries.iter().enumerate() {
+        if index > 0 {
+            output.push_str(", ");
+        }
+        entry.json(output);
+    }
+    output.push(']');
+}
+
+/// The name of the type a receiver or embedded field is, without its pointer or package
+pub(crate) fn base_type_name(type_expression: &TypeExpression) -> String {
+    match &type_expression.kind {
+        TypeExpressionKind::Pointer(target) => base_type_name(target),
+        TypeExpressionKind::Qualified { name, .. } => name.clone(),
+        _ => type_expression.to_string(),
+    }
+}
+
+/// A function's parameters and results as its declaration writes them
+pub(crate) struct Signature<'a>(pub &'a [Parameter], pub &'a [Parameter]);
+
+impl std::fmt::Display for Signature<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_signature(f, self.0, self.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    const SOURCE: &str = "package shapes
+
+import (
+	\"fmt\"
+	m \"math\"
+)
+
+var (
+	count int
+	name  = \"shapes\"
+)
+
+type Shape interface {
+	Area() float64
+}
+
+type Circle struct {
+	*fmt.Stringer
+	x, y float64
+	r    float64
+}
+
+func (c *Circle) Area() float64 {
+	return m.Pi * c.r * c.r
+}
+
+func (s Square) Area() float64 {
+	return 0
+}
+
+func New(r float64) (*Circle, error) {
+	return &Circle{r: r}, nil
+}
+";
+
+    #[test]
+    fn outline_text() {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        assert_eq!(
+            outline(&file).to_text(),
+            "package shapes
+ 4  import \"fmt\"
+ 5  import m \"math\"
+ 9  var count int
+10  var name
+13  type Shape interface
+14    Area() float64
+17  type Circle struct
+18    *fmt.Stringer
+19    x float64
+19    y float64
+20    r float64
+23    func (c *Circle) Area() float64
+27  func (s Square) Area() float64
+31  func New(r float64) (*Circle, error)
+"
+        );
+    }
+
+    #[test]
+    fn outline_json() {
+        let file = Parser::new("package main\n\ntype Celsius float64\n\nfunc main() {\n}\n")
+            .parse()
+            .unwrap();
+        assert_eq!(
+            outline(&file).to_json(),
+            concat!(
+                r#"{"package": "main", "imports": [], "symbols": ["#,
+                r#"{"kind": "type", "name": "Celsius", "detail": "type Celsius float64", "line": 3}, "#,
+                r#"{"kind": "function", "name": "main", "detail": "func main()", "line": 5}]}"#
+            )
+        );
+    }
+}
@@ -1,23 +1,120 @@
+use crate::ast::declaration::{Declaration, join};
+use crate::ast::expression::{Expression, ExpressionKind};
+use crate::lexer::token_type::Operator;
 use crate::primitives::position::Position;
-use crate::ast::expression::Expression;
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Statement {
     pub kind: StatementKind,
     pub position_start: Position,
     pub position_end: Position,
+    /// A `// comment` following the statement on its last line, including the slashes
+    pub trailing_comment: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StatementKind {
     Expression(Expression),
-    PackageDeclaration(String),
-    ImportDeclaration(String),
-    FunctionDeclaration {
-        name: String,
-        parameters: Vec<String>, // TODO: There should be a Parameter type
+    // var x int, inside a function
+    Declaration(Declaration),
+    // a, b = b, a
+    // x := 1, where `define` is true
+    Assignment {
+        left: Vec<Expression>,
+        right: Vec<Expression>,
+        define: bool,
+    },
+    // x++ or x-- (https://go.dev/ref/spec#IncDec_statements)
+    IncDec {
+        target: Expression,
+        increment: bool,
+    },
+    // x += 2, where `operator` is the `+` (https://go.dev/ref/spec#Assignment_statements)
+    OpAssign {
+        target: Expression,
+        operator: Operator,
+        value: Expression,
+    },
+    // for { ... }
+    // for condition { ... }
+    // for i := 0; i < n; i++ { ... }, where any of the three may be left out
+    For {
+        init: Option<Box<Statement>>,
+        condition: Option<Expression>,
+        post: Option<Box<Statement>>,
+        body: Vec<Statement>,
+    },
+    // for key, value := range expression { ... }
+    // `define` is true for `:=` and false for `=`, and both variables are None in `for range x`.
+    // Whether a second variable is allowed depends on the type being ranged over (a channel
+    // yields one value), so that's left to the type checker.
+    Range {
+        key: Option<Expression>,
+        value: Option<Expression>,
+        define: bool,
+        expression: Expression,
+        body: Vec<Statement>,
+    },
+    // { ... }
+    Block(Vec<Statement>),
+    // if condition { ... } else { ... }
+    // if x := f(); x > 0 { ... } else if ... { ... }
+    If {
+        init: Option<Box<Statement>>,
+        condition: Expression,
         body: Vec<Statement>,
+        // Either a `Block` or another `If`
+        else_branch: Option<Box<Statement>>,
     },
+    // return a, b
+    Return(Vec<Expression>),
+    // ch <- v (https://go.dev/ref/spec#Send_statements)
+    Send {
+        channel: Expression,
+        value: Expression,
+    },
+    // defer f(x), where the expression is always a call (https://go.dev/ref/spec#Defer_statements)
+    Defer(Expression),
+    // go f(x), where the expression is always a call (https://go.dev/ref/spec#Go_statements)
+    Go(Expression),
+    // select { case v := <-ch: ... default: ... } (https://go.dev/ref/spec#Select_statements)
+    Select(Vec<SelectCase>),
+    // switch x := f(); x { case 1, 2: ... default: ... }, where a switch without a tag
+    // switches on `true` (https://go.dev/ref/spec#Expression_switches)
+    Switch {
+        init: Option<Box<Statement>>,
+        tag: Option<Expression>,
+        cases: Vec<SwitchCase>,
+    },
+    Break,
+    Continue,
+    // Only allowed as the last statement of a switch case other than the last
+    Fallthrough,
+}
+
+/// One `case` or the `default` of a switch statement
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwitchCase {
+    /// The expressions the tag is compared with, None for `default`
+    pub expressions: Option<Vec<Expression>>,
+    pub body: Vec<Statement>,
+    /// The position of `case` or `default`
+    pub position: Position,
+}
+
+/// One `case` or the `default` of a select statement
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectCase {
+    /// The send or receive the case waits on, None for `default`. A receive is an expression
+    /// statement `<-ch` or an assignment `v, ok := <-ch`.
+    pub communication: Option<Box<Statement>>,
+    pub body: Vec<Statement>,
+    /// The position of `case` or `default`
+    pub position: Position,
 }
 
 impl Statement {
@@ -26,49 +123,394 @@ impl Statement {
             kind,
             position_start,
             position_end,
+            trailing_comment: None,
         }
     }
 
-    pub fn new_package_declaration(
-        name: String,
+    pub fn new_expression_statement(
+        expression: Expression,
         start_pos: Position,
         end_pos: Position,
     ) -> Statement {
-        Statement::new(StatementKind::PackageDeclaration(name), start_pos, end_pos)
+        Statement::new(StatementKind::Expression(expression), start_pos, end_pos)
     }
 
-    pub fn new_import_declaration(
-        path: String,
-        start_pos: Position,
-        end_pos: Position,
-    ) -> Statement {
-        Statement::new(StatementKind::ImportDeclaration(path), start_pos, end_pos)
+    /// The target of `x++` or `x op= y` and the value it's given, `x + 1` or `x op y`. The
+    /// value spans the target, so it has the target's type.
+    pub fn updated_value(&self) -> Option<(&Expression, Expression)> {
+        let (target, operator, value) = match &self.kind {
+            StatementKind::IncDec { target, increment } => {
+                let operator = if *increment {
+                    Operator::Plus
+                } else {
+                    Operator::Minus
+                };
+                let one = Expression::new(
+                    ExpressionKind::IntegerLiteral("1".to_string()),
+                    target.position_start,
+                    target.position_end,
+                );
+                (target, operator, one)
+            }
+            StatementKind::OpAssign {
+                target,
+                operator,
+                value,
+            } => (target, *operator, value.clone()),
+            _ => return None,
+        };
+        let value = Expression::new_binary(
+            target.clone(),
+            operator,
+            value,
+            target.position_start,
+            target.position_end,
+        );
+        Some((target, value))
     }
 
-    pub fn new_function_declaration(
-        name: String,
-        parameters: Vec<String>,
-        body: Vec<Statement>,
-        start_pos: Position,
-        end_pos: Position,
-    ) -> Statement {
-        Statement::new(
-            StatementKind::FunctionDeclaration {
-                name,
-                parameters,
+    /// Reconstruct Go source for this statement
+    pub fn to_source(&self) -> String {
+        self.to_string()
+    }
+
+    /// Move this statement and everything inside it by `delta` lines
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.position_start.shift_lines(delta);
+        self.position_end.shift_lines(delta);
+        match &mut self.kind {
+            StatementKind::Expression(expression) => expression.shift_lines(delta),
+            StatementKind::Declaration(declaration) => declaration.shift_lines(delta),
+            StatementKind::Assignment { left, right, .. } => {
+                for expression in left.iter_mut().chain(right) {
+                    expression.shift_lines(delta);
+                }
+            }
+            StatementKind::IncDec { target, .. } => target.shift_lines(delta),
+            StatementKind::OpAssign { target, value, .. } => {
+                target.shift_lines(delta);
+                value.shift_lines(delta);
+            }
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                for statement in [init, post].into_iter().flatten() {
+                    statement.shift_lines(delta);
+                }
+                if let Some(condition) = condition {
+                    condition.shift_lines(delta);
+                }
+                for statement in body {
+                    statement.shift_lines(delta);
+                }
+            }
+            StatementKind::Range {
+                key,
+                value,
+                expression,
+                body,
+                ..
+            } => {
+                for variable in [key, value].into_iter().flatten() {
+                    variable.shift_lines(delta);
+                }
+                expression.shift_lines(delta);
+                for statement in body {
+                    statement.shift_lines(delta);
+                }
+            }
+            StatementKind::Block(body) => {
+                for statement in body {
+                    statement.shift_lines(delta);
+                }
+            }
+            StatementKind::If {
+                init,
+                condition,
                 body,
-            },
-            start_pos,
-            end_pos,
-        )
+                else_branch,
+            } => {
+                if let Some(init) = init {
+                    init.shift_lines(delta);
+                }
+                condition.shift_lines(delta);
+                for statement in body {
+                    statement.shift_lines(delta);
+                }
+                if let Some(else_branch) = else_branch {
+                    else_branch.shift_lines(delta);
+                }
+            }
+            StatementKind::Return(results) => {
+                for result in results {
+                    result.shift_lines(delta);
+                }
+            }
+            StatementKind::Send { channel, value } => {
+                channel.shift_lines(delta);
+                value.shift_lines(delta);
+            }
+            StatementKind::Defer(call) | StatementKind::Go(call) => call.shift_lines(delta),
+            StatementKind::Select(cases) => {
+                for case in cases {
+                    case.position.shift_lines(delta);
+                    if let Some(communication) = &mut case.communication {
+                        communication.shift_lines(delta);
+                    }
+                    for statement in &mut case.body {
+                        statement.shift_lines(delta);
+                    }
+                }
+            }
+            StatementKind::Switch { init, tag, cases } => {
+                if let Some(init) = init {
+                    init.shift_lines(delta);
+                }
+                if let Some(tag) = tag {
+                    tag.shift_lines(delta);
+                }
+                for case in cases {
+                    case.position.shift_lines(delta);
+                    for expression in case.expressions.iter_mut().flatten() {
+                        expression.shift_lines(delta);
+                    }
+                    for statement in &mut case.body {
+                        statement.shift_lines(delta);
+                    }
+                }
+            }
+            StatementKind::Break | StatementKind::Continue | StatementKind::Fallthrough => {}
+        }
     }
 
-    pub fn new_expression_statement(
-        expression: Expression,
-        start_pos: Position,
-        end_pos: Position,
-    ) -> Statement {
-        Statement::new(StatementKind::Expression(expression), start_pos, end_pos)
+    /// Drop the comments of this statement and the statements inside it
+    pub fn strip_comments(&mut self) {
+        self.trailing_comment = None;
+        match &mut self.kind {
+            StatementKind::Declaration(declaration) => declaration.strip_comments(),
+            StatementKind::Range { body, .. } | StatementKind::Block(body) => {
+                for statement in body {
+                    statement.strip_comments();
+                }
+            }
+            StatementKind::For {
+                init, post, body, ..
+            } => {
+                for statement in body {
+                    statement.strip_comments();
+                }
+                for statement in [init, post].into_iter().flatten() {
+                    statement.strip_comments();
+                }
+            }
+            StatementKind::If {
+                init,
+                body,
+                else_branch,
+                ..
+            } => {
+                for statement in body {
+                    statement.strip_comments();
+                }
+                for statement in [init, else_branch].into_iter().flatten() {
+                    statement.strip_comments();
+                }
+            }
+            StatementKind::Select(cases) => {
+                for case in cases {
+                    if let Some(communication) = &mut case.communication {
+                        communication.strip_comments();
+                    }
+                    for statement in &mut case.body {
+                        statement.strip_comments();
+                    }
+                }
+            }
+            StatementKind::Switch { init, cases, .. } => {
+                if let Some(init) = init {
+                    init.strip_comments();
+                }
+                for statement in cases.iter_mut().flat_map(|case| &mut case.body) {
+                    statement.strip_comments();
+                }
+            }
+            _ => {}
+        }
     }
 }
 
+impl std::fmt::Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_kind(f)?;
+        match &self.trailing_comment {
+            Some(comment) => write!(f, " {}", comment),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Statement {
+    fn fmt_kind(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            StatementKind::Expression(expression) => write!(f, "{}", expression),
+            StatementKind::Declaration(declaration) => write!(f, "{}", declaration),
+            StatementKind::Assignment {
+                left,
+                right,
+                define,
+            } => write!(
+                f,
+                "{} {} {}",
+                join(left),
+                if *define { ":=" } else { "=" },
+                join(right)
+            ),
+            StatementKind::IncDec { target, increment } => {
+                write!(f, "{}{}", target, if *increment { "++" } else { "--" })
+            }
+            StatementKind::OpAssign {
+                target,
+                operator,
+                value,
+            } => write!(f, "{} {}= {}", target, operator, value),
+            StatementKind::For {
+                init: None,
+                condition,
+                post: None,
+                body,
+            } => {
+                match condition {
+                    Some(condition) => write!(f, "for {} ", condition)?,
+                    None => write!(f, "for ")?,
+                }
+                write_block(f, body)
+            }
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                write!(f, "for ")?;
+                if let Some(init) = init {
+                    write!(f, "{}", init)?;
+                }
+                write!(f, "; ")?;
+                if let Some(condition) = condition {
+                    write!(f, "{}", condition)?;
+                }
+                write!(f, ";")?;
+                if let Some(post) = post {
+                    write!(f, " {}", post)?;
+                }
+                write!(f, " ")?;
+                write_block(f, body)
+            }
+            StatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => {
+                write!(f, "for ")?;
+                if let Some(key) = key {
+                    write!(f, "{}", key)?;
+                    if let Some(value) = value {
+                        write!(f, ", {}", value)?;
+                    }
+                    write!(f, " {} ", if *define { ":=" } else { "=" })?;
+                }
+                write!(f, "range {} ", expression)?;
+                write_block(f, body)
+            }
+            StatementKind::Block(body) => write_block(f, body),
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => {
+                write!(f, "if ")?;
+                if let Some(init) = init {
+                    write!(f, "{}; ", init)?;
+                }
+                write!(f, "{} ", condition)?;
+                write_block(f, body)?;
+                if let Some(else_branch) = else_branch {
+                    write!(f, " else {}", else_branch)?;
+                }
+                Ok(())
+            }
+            StatementKind::Return(results) if results.is_empty() => write!(f, "return"),
+            StatementKind::Return(results) => write!(f, "return {}", join(results)),
+            StatementKind::Send { channel, value } => write!(f, "{} <- {}", channel, value),
+            StatementKind::Defer(call) => write!(f, "defer {}", call),
+            StatementKind::Go(call) => write!(f, "go {}", call),
+            StatementKind::Select(cases) => {
+                writeln!(f, "select {{")?;
+                for case in cases {
+                    match &case.communication {
+                        Some(communication) => writeln!(f, "case {}:", communication)?,
+                        None => writeln!(f, "default:")?,
+                    }
+                    write_case_body(f, &case.body)?;
+                }
+                write!(f, "}}")
+            }
+            StatementKind::Switch { init, tag, cases } => {
+                write!(f, "switch ")?;
+                if let Some(init) = init {
+                    write!(f, "{}; ", init)?;
+                }
+                if let Some(tag) = tag {
+                    write!(f, "{} ", tag)?;
+                }
+                writeln!(f, "{{")?;
+                for case in cases {
+                    match &case.expressions {
+                        Some(expressions) => writeln!(f, "case {}:", join(expressions))?,
+                        None => writeln!(f, "default:")?,
+                    }
+                    write_case_body(f, &case.body)?;
+                }
+                write!(f, "}}")
+            }
+            StatementKind::Break => write!(f, "break"),
+            StatementKind::Continue => write!(f, "continue"),
+            StatementKind::Fallthrough => write!(f, "fallthrough"),
+        }
+    }
+}
+
+/// Write each statement of a select or switch case on its own tab-indented line
+fn write_case_body(f: &mut std::fmt::Formatter<'_>, statements: &[Statement]) -> std::fmt::Result {
+    for statement in statements {
+        for line in statement.to_string().lines() {
+            if line.is_empty() {
+                writeln!(f)?;
+            } else {
+                writeln!(f, "\t{}", line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write `{`, each statement on its own tab-indented line, then `}`
+pub fn write_block(f: &mut std::fmt::Formatter<'_>, statements: &[Statement]) -> std::fmt::Result {
+    writeln!(f, "{{")?;
+    for statement in statements {
+        for line in statement.to_string().lines() {
+            if line.is_empty() {
+                writeln!(f)?;
+            } else {
+                writeln!(f, "\t{}", line)?;
+            }
+        }
+    }
+    write!(f, "}}")
+}
@@ -0,0 +1,279 @@
+//! A file's structure as stretches of its source, for editors to fold away and to grow a
+//! selection through
+
+use crate::ast::ast::File;
+use crate::ast::expression::{Expression, ExpressionKind};
+use crate::ast::statement::{Statement, StatementKind};
+use crate::format::formatter::trivia;
+use crate::lexer::token_type::TokenKind;
+use crate::primitives::position::Position;
+use crate::vet::walk::{Visitor, blocks, walk_file};
+
+/// Lines of a file an editor can fold away, all but the first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fold {
+    /// The first line, from 1, which stays in view
+    pub start_line: usize,
+    pub end_line: usize,
+    pub kind: FoldKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FoldKind {
+    /// The inside of braces or parentheses, the line they close on left in view
+    Block,
+    /// A parenthesized import declaration's imports
+    Imports,
+    /// A block comment, or a run of line comments on lines of their own
+    Comment,
+}
+
+/// The folds of `file`, parsed from `source`, by the line they start on: its declarations and
+/// the blocks and composite literals in them, its import declarations and its comments that
+/// run over more than a line.
+pub fn folding_ranges(file: &File, source: &str) -> Vec<Fold> {
+    let mut folds = Folds(Vec::new());
+    for declaration in &file.decls {
+        folds.block(declaration.position_start, declaration.position_end);
+    }
+    walk_file(file, &mut folds);
+    let mut folds = folds.0;
+
+    let trivia = trivia(source);
+    for import in &trivia.imports {
+        if let Some(close) = import.close {
+            push(
+                &mut folds,
+                import.keyword.line,
+                close.line.saturating_sub(1),
+                FoldKind::Imports,
+            );
+        }
+    }
+    let lines: Vec<&str> = source.lines().collect();
+    // The run of line comments being gone through, by its first and last lines
+    let mut run: Option<(usize, usize)> = None;
+    for comment in &trivia.comments {
+        let line = comment.position.line;
+        if comment.kind == Some(TokenKind::BlockComment) {
+            let end = line + comment.value.matches('\n').count();
+            push(&mut folds, line, end, FoldKind::Comment);
+            continue;
+        }
+        let own_line = lines
+            .get(line - 1)
+            .and_then(|text| text.get(..comment.position.column_start))
+            .is_some_and(|before| before.trim().is_empty());
+        if !own_line {
+            continue;
+        }
+        run = match run {
+            Some((start, end)) if end + 1 == line => Some((start, line)),
+            Some((start, end)) => {
+                push(&mut folds, start, end, FoldKind::Comment);
+                Some((line, line))
+            }
+            None => Some((line, line)),
+        };
+    }
+    if let Some((start, end)) = run {
+        push(&mut folds, start, end, FoldKind::Comment);
+    }
+
+    folds.sort_by_key(|fold| (fold.start_line, fold.end_line));
+    folds.dedup();
+    folds
+}
+
+/// The stretches of `file` that `position` is in, innermost first, as an editor grows a
+/// selection through them: each expression, statement, import and declaration, from the
+/// position of its first token to that of its last.
+pub fn selection_ranges(file: &File, position: Position) -> Vec<(Position, Position)> {
+    let mut spans = Spans(Vec::new());
+    for import in &file.imports {
+        spans.0.push((import.position_start, import.position_end));
+    }
+    for declaration in &file.decls {
+        spans
+            .0
+            .push((declaration.position_start, declaration.position_end));
+    }
+    walk_file(file, &mut spans);
+    let at = (position.line, position.column_start);
+    let mut spans: Vec<(Position, Position)> = spans
+        .0
+        .into_iter()
+        .filter(|(start, end)| {
+            (start.line, start.column_start) <= at && at <= (end.line, end.column_end)
+        })
+        .collect();
+    // What's in a stretch starts no earlier and ends no later
+    spans.sort_by_key(|(start, end)| {
+        (
+            std::cmp::Reverse((start.line, start.column_start)),
+            (end.line, end.column_end),
+        )
+    });
+    spans.dedup();
+    spans
+}
+
+/// Add the fold of `start_line` to `end_line` if it leaves a line to fold away
+fn push(folds: &mut Vec<Fold>, start_line: usize, end_line: usize, kind: FoldKind) {
+    if end_line > start_line {
+        folds.push(Fold {
+            start_line,
+            end_line,
+            kind,
+        });
+    }
+}
+
+struct Folds(Vec<Fold>);
+
+impl Folds {
+    /// Fold what's between the first line and the last of what runs from `start` to `end`
+    fn block(&mut self, start: Position, end: Position) {
+        let last = end.line.saturating_sub(1);
+        push(&mut self.0, start.line, last, FoldKind::Block);
+    }
+
+    /// Fold a case's body, its `case` at `position` left in view
+    fn case(&mut self, position: Position, body: &[Statement]) {
+        if let Some(last) = body.last() {
+            let end = last.position_end.line;
+            push(&mut self.0, position.line, end, FoldKind::Block);
+        }
+    }
+}
+
+impl Visitor<'_> for Folds {
+    fn statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            // An `if`'s body ends where its `else` starts
+            StatementKind::If {
+                else_branch: Some(else_branch),
+                ..
+            } => self.block(statement.position_start, else_branch.position_start),
+            StatementKind::Select(cases) => {
+                self.block(statement.position_start, statement.position_end);
+                for case in cases {
+                    self.case(case.position, &case.body);
+                }
+            }
+            StatementKind::Switch { cases, .. } => {
+                self.block(statement.position_start, statement.position_end);
+                for case in cases {
+                    self.case(case.position, &case.body);
+                }
+            }
+            StatementKind::Declaration(_) => {
+                self.block(statement.position_start, statement.position_end)
+            }
+            _ if !blocks(statement).is_empty() => {
+                self.block(statement.position_start, statement.position_end)
+            }
+            _ => {}
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        if let ExpressionKind::CompositeLiteral { .. } | ExpressionKind::FunctionLiteral { .. } =
+            expression.kind
+        {
+            self.block(expression.position_start, expression.position_end);
+        }
+    }
+}
+
+struct Spans(Vec<(Position, Position)>);
+
+impl Visitor<'_> for Spans {
+    fn statement(&mut self, statement: &Statement) {
+        self.0
+            .push((statement.position_start, statement.position_end));
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        self.0
+            .push((expression.position_start, expression.position_end));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    const SOURCE: &str = "package main
+
+import (
+\t\"fmt\"
+\t\"os\"
+)
+
+// main prints
+// and exits.
+func main() {
+\tif len(os.Args) > 1 {
+\t\tfmt.Println(1)
+\t} else {
+\t\t/* nothing
+\t\t   to do */
+\t}
+\txs := []int{
+\t\t1,
+\t}
+\tfmt.Println(xs[0] + 2)
+}
+";
+
+    #[test]
+    fn fold_a_file() {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        let folds: Vec<(usize, usize, FoldKind)> = folding_ranges(&file, SOURCE)
+            .into_iter()
+            .map(|fold| (fold.start_line, fold.end_line, fold.kind))
+            .collect();
+        assert_eq!(
+            folds,
+            [
+                (3, 5, FoldKind::Imports),
+                (8, 9, FoldKind::Comment),
+                (10, 20, FoldKind::Block),
+                (11, 12, FoldKind::Block),
+                (13, 15, FoldKind::Block),
+                (14, 15, FoldKind::Comment),
+                (17, 18, FoldKind::Block)
+            ]
+        );
+    }
+
+    #[test]
+    fn select_around_a_position() {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        let spans: Vec<String> = selection_ranges(&file, Position::new_single_position(20, 16))
+            .into_iter()
+            .map(|(start, end)| {
+                format!(
+                    "{}:{}-{}:{}",
+                    start.line, start.column_start, end.line, end.column_end
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                "20:16-20:17",
+                "20:13-20:18",
+                "20:13-20:22",
+                "20:1-20:23",
+                "10:0-21:1"
+            ]
+        );
+        assert_eq!(
+            selection_ranges(&file, Position::new_single_position(4, 2)).len(),
+            1
+        );
+    }
+}
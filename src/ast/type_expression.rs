@@ -0,0 +1,296 @@
+use crate::ast::declaration::{Parameter, join, write_signature};
+use crate::ast::expression::Expression;
+use crate::primitives::position::Position;
+
+/// A type as written in source, e.g. `[]byte` or `map[string]int`
+/// (https://go.dev/ref/spec#Types)
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TypeExpression {
+    pub kind: TypeExpressionKind,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TypeExpressionKind {
+    // int
+    Name(String),
+    // time.Duration
+    Qualified {
+        package: String,
+        name: String,
+    },
+    // *T
+    Pointer(Box<TypeExpression>),
+    // []T
+    Slice(Box<TypeExpression>),
+    // [4]T
+    Array {
+        length: Box<Expression>,
+        element: Box<TypeExpression>,
+    },
+    // map[K]V
+    Map {
+        key: Box<TypeExpression>,
+        value: Box<TypeExpression>,
+    },
+    // chan T, chan<- T, <-chan T
+    Channel {
+        direction: ChannelDirection,
+        element: Box<TypeExpression>,
+    },
+    // struct { x, y int; io.Reader }
+    Struct(Vec<StructField>),
+    // interface { String() string }
+    Interface(Vec<InterfaceMethod>),
+    // func(int) (string, error) (https://go.dev/ref/spec#Function_types)
+    Function {
+        parameters: Vec<Parameter>,
+        results: Vec<Parameter>,
+    },
+}
+
+/// `x, y int` or an embedded `*T` in a struct type, with an optional tag
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StructField {
+    /// Empty for an embedded field, which is named after its type
+    pub names: Vec<Expression>,
+    pub type_expression: TypeExpression,
+    pub tag: Option<String>,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+/// `String() string` in an interface type
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterfaceMethod {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub results: Vec<Parameter>,
+    pub position_start: Position,
+    pub position_end: Position,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChannelDirection {
+    Both,
+    Send,
+    Receive,
+}
+
+impl TypeExpression {
+    pub fn new(
+        kind: TypeExpressionKind,
+        position_start: Position,
+        position_end: Position,
+    ) -> TypeExpression {
+        TypeExpression {
+            kind,
+            position_start,
+            position_end,
+        }
+    }
+
+    pub fn new_name(name: String, position: Position) -> TypeExpression {
+        TypeExpression::new(TypeExpressionKind::Name(name), position, position)
+    }
+
+    /// Reconstruct Go source for this type
+    pub fn to_source(&self) -> String {
+        self.to_string()
+    }
+
+    pub(crate) fn is_receive_channel(&self) -> bool {
+        matches!(
+            self.kind,
+            TypeExpressionKind::Channel {
+                direction: ChannelDirection::Receive,
+                ..
+            }
+        )
+    }
+
+    /// Move this type and everything inside it by `delta` lines
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.position_start.shift_lines(delta);
+        self.position_end.shift_lines(delta);
+        match &mut self.kind {
+            TypeExpressionKind::Name(_) | TypeExpressionKind::Qualified { .. } => {}
+            TypeExpressionKind::Pointer(element)
+            | TypeExpressionKind::Slice(element)
+            | TypeExpressionKind::Channel { element, .. } => element.shift_lines(delta),
+            TypeExpressionKind::Array { length, element } => {
+                length.shift_lines(delta);
+                element.shift_lines(delta);
+            }
+            TypeExpressionKind::Map { key, value } => {
+                key.shift_lines(delta);
+                value.shift_lines(delta);
+            }
+            TypeExpressionKind::Struct(fields) => {
+                for field in fields {
+                    field.shift_lines(delta);
+                }
+            }
+            TypeExpressionKind::Interface(methods) => {
+                for method in methods {
+                    method.shift_lines(delta);
+                }
+            }
+            TypeExpressionKind::Function {
+                parameters,
+                results,
+            } => {
+                for parameter in parameters.iter_mut().chain(results) {
+                    parameter.shift_lines(delta);
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for TypeExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            TypeExpressionKind::Name(name) => write!(f, "{}", name),
+            TypeExpressionKind::Qualified { package, name } => write!(f, "{}.{}", package, name),
+            TypeExpressionKind::Pointer(element) => write!(f, "*{}", element),
+            TypeExpressionKind::Slice(element) => write!(f, "[]{}", element),
+            TypeExpressionKind::Array { length, element } => write!(f, "[{}]{}", length, element),
+            TypeExpressionKind::Map { key, value } => write!(f, "map[{}]{}", key, value),
+            TypeExpressionKind::Channel { direction, element } => match direction {
+                // `chan <-chan T` would read as `chan<- (chan T)`
+                ChannelDirection::Both if element.is_receive_channel() => {
+                    write!(f, "chan ({})", element)
+                }
+                ChannelDirection::Both => write!(f, "chan {}", element),
+                ChannelDirection::Send => write!(f, "chan<- {}", element),
+                ChannelDirection::Receive => write!(f, "<-chan {}", element),
+            },
+            TypeExpressionKind::Struct(fields) if fields.is_empty() => write!(f, "struct{{}}"),
+            TypeExpressionKind::Struct(fields) => {
+                write!(f, "struct {{ ")?;
+                for (index, field) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", field)?;
+                }
+                write!(f, " }}")
+            }
+            TypeExpressionKind::Interface(methods) if methods.is_empty() => {
+                write!(f, "interface{{}}")
+            }
+            TypeExpressionKind::Interface(methods) => {
+                write!(f, "interface {{ ")?;
+                for (index, method) in methods.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", method)?;
+                }
+                write!(f, " }}")
+            }
+            TypeExpressionKind::Function {
+                parameters,
+                results,
+            } => {
+                write!(f, "func")?;
+                write_signature(f, parameters, results)
+            }
+        }
+    }
+}
+
+impl StructField {
+    pub fn new(
+        names: Vec<Expression>,
+        type_expression: TypeExpression,
+        tag: Option<String>,
+        position_start: Position,
+        position_end: Position,
+    ) -> StructField {
+        StructField {
+            names,
+            type_expression,
+            tag,
+            position_start,
+            position_end,
+        }
+    }
+
+    /// The name a field is accessed by, which for an embedded field is its type's name
+    /// without any pointer or package. That includes the package of a name the program loader
+    /// qualified, like `util.Point`.
+    pub fn embedded_name(&self) -> Option<&str> {
+        let mut type_expression = &self.type_expression;
+        if let TypeExpressionKind::Pointer(element) = &type_expression.kind {
+            type_expression = element;
+        }
+        match &type_expression.kind {
+            TypeExpressionKind::Name(name) => name.rsplit('.').next(),
+            TypeExpressionKind::Qualified { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.position_start.shift_lines(delta);
+        self.position_end.shift_lines(delta);
+        for name in &mut self.names {
+            name.shift_lines(delta);
+        }
+        self.type_expression.shift_lines(delta);
+    }
+}
+
+impl std::fmt::Display for StructField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.names.is_empty() {
+            write!(f, "{} ", join(&self.names))?;
+        }
+        write!(f, "{}", self.type_expression)?;
+        if let Some(tag) = &self.tag {
+            write!(f, " {}", tag)?;
+        }
+        Ok(())
+    }
+}
+
+impl InterfaceMethod {
+    pub fn new(
+        name: String,
+        parameters: Vec<Parameter>,
+        results: Vec<Parameter>,
+        position_start: Position,
+        position_end: Position,
+    ) -> InterfaceMethod {
+        InterfaceMethod {
+            name,
+            parameters,
+            results,
+            position_start,
+            position_end,
+        }
+    }
+
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.position_start.shift_lines(delta);
+        self.position_end.shift_lines(delta);
+        for parameter in self.parameters.iter_mut().chain(&mut self.results) {
+            parameter.shift_lines(delta);
+        }
+    }
+}
+
+impl std::fmt::Display for InterfaceMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        write_signature(f, &self.parameters, &self.results)
+    }
+}
@@ -0,0 +1,176 @@
+//! `gor bench-compile`: how long the front end takes over each file, so that it getting slower
+//! shows.
+
+use std::time::{Duration, Instant};
+
+use crate::ast::dump::json_string;
+use crate::lexer::{lexer::Lexer, token_type::TokenKind};
+use crate::parser::parser::Parser;
+use crate::semantic::checker::check;
+
+/// How long each stage of the front end took over a file, the fastest of each run
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timings {
+    pub path: String,
+    pub lines: usize,
+    pub tokens: usize,
+    pub lex: Duration,
+    /// Parsing, which lexes the file again as it goes
+    pub parse: Duration,
+    /// Checking the parsed file on its own, or None if it has syntax errors. The names it
+    /// uses from the package's other files are undefined, which it reports as it would
+    /// any other error.
+    pub check: Option<Duration>,
+}
+
+/// Lex, parse and check `source`, each `runs` times, timing each stage
+pub fn time_file(path: String, source: &str, runs: usize) -> Timings {
+    let mut timings = Timings {
+        path,
+        lines: source.lines().count(),
+        tokens: 0,
+        lex: Duration::MAX,
+        parse: Duration::MAX,
+        check: None,
+    };
+    for _ in 0..runs.max(1) {
+        let started = Instant::now();
+        let mut lexer = Lexer::new(source);
+        let mut tokens = 0;
+        while !matches!(lexer.next_token().kind, Some(TokenKind::EOF) | None) {
+            tokens += 1;
+        }
+        timings.lex = timings.lex.min(started.elapsed());
+        timings.tokens = tokens;
+
+        let started = Instant::now();
+        let mut parser = Parser::new(source);
+        let parsed = parser.parse();
+        timings.parse = timings.parse.min(started.elapsed());
+
+        if let Ok(file) = parsed
+            && parser.errors.is_empty()
+        {
+            let started = Instant::now();
+            let _ = check(&file);
+            let elapsed = started.elapsed();
+            timings.check = Some(timings.check.map_or(elapsed, |check| check.min(elapsed)));
+        }
+    }
+    timings
+}
+
+/// The timings added up over every file, named `total`. Files with syntax errors aren't
+/// counted in its check.
+pub fn total(timings: &[Timings]) -> Timings {
+    Timings {
+        path: "total".to_string(),
+        lines: timings.iter().map(|timing| timing.lines).sum(),
+        tokens: timings.iter().map(|timing| timing.tokens).sum(),
+        lex: timings.iter().map(|timing| timing.lex).sum(),
+        parse: timings.iter().map(|timing| timing.parse).sum(),
+        check: Some(timings.iter().filter_map(|timing| timing.check).sum()),
+    }
+}
+
+/// A table of each file's timings in milliseconds, then their total
+pub fn to_text(timings: &[Timings]) -> String {
+    let total = total(timings);
+    let width = timings
+        .iter()
+        .map(|timing| timing.path.len())
+        .chain(["file".len(), total.path.len()])
+        .max()
+        .unwrap_or(0);
+    let mut text = format!(
+        "{:width$}  {:>7}  {:>7}  {:>9}  {:>9}  {:>9}\n",
+        "file", "lines", "tokens", "lex", "parse", "check"
+    );
+    for timing in timings.iter().chain([&total]) {
+        text.push_str(&format!(
+            "{:width$}  {:>7}  {:>7}  {:>9}  {:>9}  {:>9}\n",
+            timing.path,
+            timing.lines,
+            timing.tokens,
+            milliseconds(timing.lex),
+            milliseconds(timing.parse),
+            timing.check.map_or("-".to_string(), milliseconds),
+        ));
+    }
+    text
+}
+
+/// The timings as a JSON object on a line, with an object per file and their total, each
+/// stage in nanoseconds and a check of null for a file with syntax errors
+pub fn to_json(timings: &[Timings]) -> String {
+    let files: Vec<String> = timings.iter().map(json).collect();
+    format!(
+        "{{\"files\": [{}], \"total\": {}}}",
+        files.join(", "),
+        json(&total(timings))
+    )
+}
+
+fn json(timing: &Timings) -> String {
+    format!(
+        "{{\"path\": {}, \"lines\": {}, \"tokens\": {}, \"lex_ns\": {}, \"parse_ns\": {}, \"check_ns\": {}}}",
+        json_string(&timing.path),
+        timing.lines,
+        timing.tokens,
+        timing.lex.as_nanos(),
+        timing.parse.as_nanos(),
+        timing
+            .check
+            .map_or("null".to_string(), |check| check.as_nanos().to_string())
+    )
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timings(path: &str, check: Option<u64>) -> Timings {
+        Timings {
+            path: path.to_string(),
+            lines: 10,
+            tokens: 40,
+            lex: Duration::from_micros(50),
+            parse: Duration::from_micros(120),
+            check: check.map(Duration::from_micros),
+        }
+    }
+
+    #[test]
+    fn time_a_file() {
+        let source = "package main\n\nfunc main() {\n\tx := 1\n\t_ = x\n}\n";
+        let timings = time_file("main.go".to_string(), source, 2);
+        assert_eq!((timings.lines, timings.tokens), (6, 20));
+        assert!(timings.check.is_some());
+        let timings = time_file("bad.go".to_string(), "package", 1);
+        assert_eq!(timings.check, None);
+    }
+
+    #[test]
+    fn print_timings() {
+        let timings = [timings("a.go", Some(300)), timings("bad.go", None)];
+        assert_eq!(
+            to_text(&timings),
+            "file      lines   tokens        lex      parse      check
+a.go         10       40    0.050ms    0.120ms    0.300ms
+bad.go       10       40    0.050ms    0.120ms          -
+total        20       80    0.100ms    0.240ms    0.300ms
+"
+        );
+        assert_eq!(
+            to_json(&timings[..1]),
+            concat!(
+                r#"{"files": [{"path": "a.go", "lines": 10, "tokens": 40, "lex_ns": 50000, "parse_ns": 120000, "check_ns": 300000}], "#,
+                r#""total": {"path": "total", "lines": 10, "tokens": 40, "lex_ns": 50000, "parse_ns": 120000, "check_ns": 300000}}"#
+            )
+        );
+    }
+}
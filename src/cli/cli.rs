@@ -1,86 +1,2246 @@
-use crate::lexer::lexer::Lexer;
+use crate::ast::{
+    compare::compare,
+    dump::{DumpFormat, dump, json_string},
+    outline::outline,
+};
+use crate::cli::bench::{self, time_file};
+use crate::cli::command::{
+    BuildOptions, CheckOptions, Command, Emit, Engine, FmtOptions, FormatMode, GraphFormat,
+    OutputFormat, RunOptions, TestOptions, VetOptions, parse_log_level, usage,
+};
+use crate::cli::completions;
+use crate::cli::doc::Package;
+use crate::cli::graph;
+use crate::cli::logger;
+use crate::cli::project::Project;
+use crate::cli::sarif::Sarif;
+#[cfg(feature = "jit")]
+use crate::compile::jit::Jit;
+use crate::compile::{
+    c,
+    compiler::compile,
+    encode::{decode, encode},
+    lower::lower,
+    opt::PassManager,
+    vm::Vm,
+    wasm,
+};
+use crate::eval::{
+    config::LoaderConfig,
+    interpreter::Interpreter,
+    loader::{
+        LoadCache, Program, go_files, load_cached, load_source, load_with_config, match_packages,
+        module_path, package_import_path,
+    },
+    observer::Tracer,
+    repl::Repl,
+    testing::{TestOutcome, TestResult},
+};
+use crate::format::{
+    config::FormatterConfig, diff::unified_diff, formatter::format_source_with_config,
+    minify::minify, selftest::selftest_directory,
+};
+use crate::lexer::{lexer::Lexer, token::Token, token_type::TokenKind};
+use crate::lsp::server::Server;
+use crate::parser::parser::Parser;
+use crate::primitives::{
+    diagnostic::{Diagnostic, Severity},
+    errors::{
+        cli::{CliError, CliErrorKind},
+        compile::CompileError,
+        config::ConfigError,
+        load::{LoadError, LoadErrorKind},
+        repl::ReplError,
+        runtime::RuntimeErrorKind,
+    },
+    explanations::{EXPLANATIONS, explain},
+    position::Position,
+};
+use crate::rewrite::rename::rename;
+use crate::semantic::{checker::check_with_config, config::CheckerConfig};
+use crate::vet::{config::VetConfig, vet::Vet};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::Instant;
 
-pub struct CLI {
-    pub args: Vec<String>,
+use log::{LevelFilter, debug};
+
+/// The status gor exits with when it did what it was asked. `gor run` exits with the
+/// program's own status instead.
+pub const EXIT_SUCCESS: i32 = 0;
+
+/// The status gor exits with when it found a problem with what it was given: diagnostics,
+/// a file it couldn't read or write, or a program that failed at run time
+pub const EXIT_FAILURE: i32 = 1;
+
+/// The status gor exits with when its command line can't be made sense of
+pub const EXIT_USAGE: i32 = 2;
+
+/// The status gor exits with when it failed itself, which is a bug in gor
+pub const EXIT_INTERNAL: i32 = 3;
+
+/// The status a program `gor run` runs exits with when it panics, as a Go program's does
+const PANIC_STATUS: i32 = 2;
+
+/// The stack `gor run` interprets on, deep enough for the evaluator's maximum call depth
+const RUN_STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// The stack each thread `gor check` and `gor vet` check packages on has, deeper than the
+/// main thread's so the checker doesn't run out of it on deeply nested code first
+const CHECK_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// The extension of the bytecode `gor build` writes, which `gor run` runs without compiling
+/// it again
+const BYTECODE_EXTENSION: &str = "gorc";
+
+/// The path that's standard input, as in `cat main.go | gor check -`
+const STDIN: &str = "-";
+
+/// What a file read from standard input is called where its errors are reported, as gofmt
+/// calls it
+const STDIN_NAME: &str = "<standard input>";
+
+/// `print!` for gor's own output, which drops what's left rather than panicking once whatever
+/// reads it has stopped reading, like `head` in `gor fmt main.go | head`
+macro_rules! out {
+    ($($argument:tt)*) => {
+        write_stdout(format_args!($($argument)*))
+    };
+}
+
+/// `println!` for gor's own output, see `out!`
+macro_rules! outln {
+    () => {
+        out!("\n")
+    };
+    ($($argument:tt)*) => {
+        out!("{}\n", format_args!($($argument)*))
+    };
+}
+
+/// Write to standard output, ignoring a closed pipe. Any other error panics, as `print!` does.
+fn write_stdout(arguments: std::fmt::Arguments) {
+    match std::io::stdout().lock().write_fmt(arguments) {
+        Err(error) if error.kind() != std::io::ErrorKind::BrokenPipe => {
+            panic!("failed printing to stdout: {}", error)
+        }
+        _ => {}
+    }
+}
+
+/// The command gor was run with, ready to execute
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cli {
+    pub command: Command,
+    /// The least severe records written to the log
+    pub log_level: LevelFilter,
+}
+
+impl Cli {
+    /// Parse the arguments gor was run with, its own name first as `std::env::args` gives it,
+    /// after the defaults of the project the current directory is in
+    pub fn parse_from(args: Vec<String>) -> Result<Cli, CliError> {
+        Cli::parse_finding_project(args, || Project::find(Path::new(".")))
+    }
+
+    /// Parse the arguments as `parse_from` does, with the project `find` finds, which is
+    /// only looked for when the command uses it
+    fn parse_finding_project(
+        args: Vec<String>,
+        find: impl FnOnce() -> Result<Option<Project>, ConfigError>,
+    ) -> Result<Cli, CliError> {
+        if let Ok(cli) = Cli::parse_with_project(args.clone(), None)
+            && !cli.command.uses_project()
+        {
+            return Ok(cli);
+        }
+        let project = find().map_err(|error| CliError::new(CliErrorKind::Config(error), None))?;
+        Cli::parse_with_project(args, project.as_ref())
+    }
+
+    /// Parse the arguments gor was run with as `parse_from` does, after the flags `project`
+    /// gives gor and the command by default, so that those given override them
+    pub fn parse_with_project(
+        args: Vec<String>,
+        project: Option<&Project>,
+    ) -> Result<Cli, CliError> {
+        let flags = |command| match project.map(|project| project.flags(command)) {
+            Some(Ok(flags)) => Ok(flags.to_vec()),
+            Some(Err(error)) => Err(CliError::new(CliErrorKind::Config(error.clone()), None)),
+            None => Ok(Vec::new()),
+        };
+        let mut global = flags(None)?;
+        global.extend(args.into_iter().skip(1));
+        let (log_level, mut args) = parse_log_level(global)?;
+        if let Some(command) = args.first() {
+            let flags = flags(Some(command))?;
+            args.splice(1..1, flags);
+        }
+        Ok(Cli {
+            command: Command::parse(args)?,
+            log_level,
+        })
+    }
+
+    /// Run the command, returning the status gor should exit with
+    pub fn execute(&self) -> i32 {
+        logger::init(self.log_level);
+        debug!("running {:?}", self.command);
+        match &self.command {
+            Command::DumpTokens { format, path } => handle_dump_tokens(*format, path),
+            Command::DumpAst { format, path } => handle_dump_ast(*format, path),
+            Command::Symbols { format, path } => handle_symbols(*format, path),
+            Command::Strip { minify, path } => handle_strip(*minify, path),
+            Command::Doc { path, symbol } => handle_doc(path, symbol.as_deref()),
+            Command::Graph {
+                format,
+                calls,
+                path,
+            } => handle_graph(*format, *calls, path),
+            Command::Run(options) => handle_run(options),
+            Command::Check(options) => handle_check(options),
+            Command::Vet(options) => handle_vet(options),
+            Command::Test(options) => handle_test(options),
+            Command::DiffAst { old, new } => handle_diff_ast(old, new),
+            Command::BenchCompile {
+                json,
+                runs,
+                exclude,
+                path,
+            } => handle_bench_compile(*json, *runs, exclude, path),
+            Command::DumpBytecode { path } => handle_dump_bytecode(path),
+            Command::DumpIr { level, path } => handle_dump_ir(*level, path),
+            Command::Build(options) => handle_build(options),
+            Command::Repl => handle_repl(),
+            Command::Lsp => handle_lsp(),
+            Command::Rename {
+                diff,
+                path,
+                line,
+                column,
+                name,
+            } => handle_rename(*diff, path, *line, *column, name),
+            Command::Fmt(options) => handle_fmt(options),
+            Command::SelftestFmt { directory } => handle_selftest_fmt(directory),
+            Command::Panic => panic!("gor internal panic"),
+            Command::Explain(code) => handle_explain(code.as_deref()),
+            Command::Completions(shell) => {
+                out!("{}", completions::script(*shell));
+                EXIT_SUCCESS
+            }
+            Command::Help(command) => {
+                out!("{}", usage(command.as_deref()).unwrap_or_default());
+                EXIT_SUCCESS
+            }
+            Command::Version => {
+                outln!("gor {}", env!("CARGO_PKG_VERSION"));
+                EXIT_SUCCESS
+            }
+        }
+    }
+}
+
+/// Print a file's tokens a line each, with its lexical errors in among them on standard
+/// error, exiting with status 1 if there are any. With `--format=json`, each token is a JSON
+/// object of its kind, value and span, and each error one of the diagnostic.
+fn handle_dump_tokens(format: OutputFormat, path: &str) -> i32 {
+    let content = match read_go_file("dump-tokens", path) {
+        Ok(content) => content,
+        Err(status) => return status,
+    };
+    let mut lexer = Lexer::new(&content);
+    if format != OutputFormat::Text {
+        loop {
+            let token = lexer.next_token();
+            if token.kind == Some(TokenKind::EOF) {
+                break;
+            }
+            outln!("{}", token_json(&token));
+        }
+        let errors = lexer.take_errors();
+        for error in &errors {
+            let located = |position| (Some(Path::new(display_name(path))), position);
+            eprintln!("{}", diagnostic_json(&Diagnostic::from(error), located));
+        }
+        return status(!errors.is_empty());
+    }
+
+    let has_errors = lexer.dump_tokens().any(|item| {
+        if item.starts_with("Error at") {
+            eprintln!("{}", item);
+            true
+        } else {
+            outln!("{}", item);
+            false
+        }
+    });
+
+    status(has_errors)
+}
+
+/// Parse a file and print its syntax tree, as an indented outline or with
+/// `--format=json` or `--format=sexpr`, as JSON or an S-expression. The tree is printed
+/// even when there are syntax errors, as far as the parser recovered, and they're written
+/// to standard error with exit status 1, as lines of JSON with `--format=json`.
+fn handle_dump_ast(format: DumpFormat, path: &str) -> i32 {
+    let content = match read_go_file("dump-ast", path) {
+        Ok(content) => content,
+        Err(status) => return status,
+    };
+    let mut parser = Parser::new(&content);
+    let (file, errors) = match parser.parse() {
+        Ok(file) => (Some(file), std::mem::take(&mut parser.errors)),
+        Err(errors) => (None, errors),
+    };
+    if let Some(file) = file {
+        out!("{}", dump(&file, format));
+    }
+    for error in &errors {
+        if format == DumpFormat::Json {
+            let located = |position| (Some(Path::new(display_name(path))), position);
+            eprintln!("{}", diagnostic_json(&Diagnostic::from(error), located));
+            continue;
+        }
+        eprintln!(
+            "{}:{}:{}: {}",
+            display_name(path),
+            error.position.line,
+            error.position.column_start,
+            error.kind
+        );
+    }
+    status(!errors.is_empty())
+}
+
+/// Print an outline of a file's imports and package level declarations, each with its line
+/// number, and a type's fields and methods under it, or with `--format=json` the outline as
+/// a JSON object. As with `gor dump-ast`, a file with syntax errors is outlined as far as the
+/// parser recovered, and the errors are written to standard error with exit status 1.
+fn handle_symbols(format: OutputFormat, path: &str) -> i32 {
+    let content = match read_go_file("symbols", path) {
+        Ok(content) => content,
+        Err(status) => return status,
+    };
+    let mut parser = Parser::new(&content);
+    let (file, errors) = match parser.parse() {
+        Ok(file) => (Some(file), std::mem::take(&mut parser.errors)),
+        Err(errors) => (None, errors),
+    };
+    if let Some(file) = file {
+        let outline = outline(&file);
+        match format {
+            OutputFormat::Text => out!("{}", outline.to_text()),
+            _ => outln!("{}", outline.to_json()),
+        }
+    }
+    for error in &errors {
+        let located = |position| (Some(Path::new(display_name(path))), position);
+        match format {
+            OutputFormat::Text => report_diagnostic(&Diagnostic::from(error), located),
+            _ => eprintln!("{}", diagnostic_json(&Diagnostic::from(error), located)),
+        }
+    }
+    status(!errors.is_empty())
 }
 
-impl CLI {
-    pub fn new(args: Vec<String>) -> CLI {
-        CLI { args }
+/// Print a file as the pretty-printer writes it, without its comments, or with `--minify`
+/// on a line with only the whitespace it needs. A file with syntax errors isn't printed,
+/// since the code the parser recovered from is missing from its tree; the errors are
+/// written to standard error with exit status 1.
+fn handle_strip(minify_source: bool, path: &str) -> i32 {
+    let content = match read_go_file("strip", path) {
+        Ok(content) => content,
+        Err(status) => return status,
+    };
+    let mut parser = Parser::new(&content);
+    let errors = match parser.parse() {
+        Ok(mut file) if parser.errors.is_empty() => {
+            file.strip_comments();
+            let printed = file.to_source();
+            match minify_source {
+                true => out!("{}", minify(&printed)),
+                false => out!("{}", printed),
+            }
+            return EXIT_SUCCESS;
+        }
+        Ok(_) => parser.errors,
+        Err(errors) => errors,
+    };
+    for error in &errors {
+        let located = |position| (Some(Path::new(display_name(path))), position);
+        report_diagnostic(&Diagnostic::from(error), located);
+    }
+    EXIT_FAILURE
+}
+
+/// Compare two files structurally and print what `new` changes: each import or package level
+/// declaration it adds, removes or changes other than in layout or comments, on a line after
+/// `+`, `-` or `~`. As with diff, the exit status is 1 if the files differ, and syntax errors
+/// are written to standard error with exit status 1 too.
+fn handle_diff_ast(old: &str, new: &str) -> i32 {
+    let mut files = Vec::new();
+    for path in [old, new] {
+        let content = match read_go_file("diff-ast", path) {
+            Ok(content) => content,
+            Err(status) => return status,
+        };
+        let mut parser = Parser::new(&content);
+        let errors = match parser.parse() {
+            Ok(file) if parser.errors.is_empty() => {
+                files.push(file);
+                continue;
+            }
+            Ok(_) => parser.errors,
+            Err(errors) => errors,
+        };
+        for error in &errors {
+            let located = |position| (Some(Path::new(display_name(path))), position);
+            report_diagnostic(&Diagnostic::from(error), located);
+        }
+    }
+    let [old_file, new_file] = files.as_slice() else {
+        return EXIT_FAILURE;
+    };
+    let changes = compare(old_file, new_file);
+    if changes.is_empty() {
+        return EXIT_SUCCESS;
     }
+    outln!("--- {}\n+++ {}", display_name(old), display_name(new));
+    for change in &changes {
+        outln!("{} {}", change.kind.mark(), change.detail);
+    }
+    EXIT_FAILURE
+}
 
-    pub fn execute(&self) {
-        self.verify_base_command();
-        let subcommand = &self.args[1];
+/// Time lexing, parsing and checking each file of the packages `pattern` names, or the file
+/// it names, and print the timings with their total as a table, or with `--json` as JSON.
+/// Each file is checked on its own, so this times the front end rather than reporting
+/// errors, and files with syntax errors are timed as far as they're parsed.
+fn handle_bench_compile(json: bool, runs: usize, exclude: &[PathBuf], pattern: &str) -> i32 {
+    let config = loader_config(false, exclude);
+    let packages = match match_packages(pattern, &config) {
+        Ok(packages) => packages,
+        Err(error) => return load_failed(&error),
+    };
+    let mut timings = Vec::new();
+    for package in packages {
+        let paths = match fs::metadata(&package) {
+            Ok(metadata) if metadata.is_dir() => match go_files(&package, &config) {
+                Ok(paths) => paths,
+                Err(error) => return load_failed(&error),
+            },
+            Ok(_) => vec![package],
+            Err(error) => {
+                let error = LoadError::new(LoadErrorKind::Io(error.to_string()), package);
+                return load_failed(&error);
+            }
+        };
+        for path in paths {
+            let source = match read_go_file("bench-compile", &path.to_string_lossy()) {
+                Ok(source) => source,
+                Err(status) => return status,
+            };
+            timings.push(time_file(path.display().to_string(), &source, runs));
+        }
+    }
+    if timings.is_empty() {
+        let error = LoadError::new(LoadErrorKind::NoGoFiles, PathBuf::from(pattern));
+        return load_failed(&error);
+    }
+    match json {
+        true => outln!("{}", bench::to_json(&timings)),
+        false => out!("{}", bench::to_text(&timings)),
+    }
+    EXIT_SUCCESS
+}
 
-        match subcommand.as_str() {
-            "dump-tokens" => self.handle_dump_tokens(),
-            _ => {
-                eprintln!("Unknown command: {}", subcommand);
+/// Print the documentation of the package in `path`, a file or a package directory, as
+/// `go doc` does, or with `symbol` that of one of its package level names or methods. The
+/// files are only parsed, not type-checked, so syntax errors are written to standard error
+/// with exit status 1, as is a symbol the package doesn't declare.
+fn handle_doc(path: &str, symbol: Option<&str>) -> i32 {
+    let directory = Path::new(path);
+    let paths = match directory.is_dir() {
+        true => match go_files(directory, &LoaderConfig::default()) {
+            Ok(paths) if paths.is_empty() => {
+                let error = LoadError::new(LoadErrorKind::NoGoFiles, directory.to_path_buf());
+                return load_failed(&error);
             }
+            Ok(paths) => paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+            Err(error) => return load_failed(&error),
+        },
+        false => vec![path.to_string()],
+    };
+    let mut files = Vec::new();
+    let mut failed = false;
+    for path in &paths {
+        let content = match read_go_file("doc", path) {
+            Ok(content) => content,
+            Err(status) => return status,
+        };
+        let mut parser = Parser::new(&content);
+        match parser.parse() {
+            Ok(file) if parser.errors.is_empty() => files.push((file, content)),
+            Ok(_) => failed = true,
+            Err(errors) => parser.errors = errors,
         }
+        for error in &parser.errors {
+            let located = |position| (Some(Path::new(display_name(path))), position);
+            report_diagnostic(&Diagnostic::from(error), located);
+            failed = true;
+        }
+    }
+    if failed {
+        return EXIT_FAILURE;
+    }
+    let package = Package {
+        import_path: match path {
+            STDIN => None,
+            path => package_import_path(Path::new(path)),
+        },
+        files,
+    };
+    let Some(symbol) = symbol else {
+        out!("{}", package.to_text());
+        return EXIT_SUCCESS;
+    };
+    match package.symbol_text(symbol) {
+        Some(text) => {
+            out!("{}", text);
+            EXIT_SUCCESS
+        }
+        None => {
+            eprintln!("Error: no symbol {} in package {}", symbol, path);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Print what a diagnostic code means, with an example of it and the example fixed, or
+/// without one, each code and the message it's reported with. A code that isn't one is
+/// written to standard error with exit status 1.
+fn handle_explain(code: Option<&str>) -> i32 {
+    let Some(code) = code else {
+        for explanation in EXPLANATIONS {
+            outln!("{}  {}", explanation.code, explanation.title);
+        }
+        return EXIT_SUCCESS;
+    };
+    match explain(code) {
+        Some(explanation) => {
+            out!("{}", explanation.to_text());
+            EXIT_SUCCESS
+        }
+        None => {
+            eprintln!("Error: no diagnostic has the code {}", code);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Run a program given as a package directory, like `./cmd/app`, or a single file, once
+/// it's type-checked as `gor check` would. The arguments after it, and after a `--` if one
+/// follows it, are the program's, its os.Args after the path it was run as. A panic exits
+/// with status 2 as Go's does, and os.Exit with the status it's given. With `--trace`, each
+/// statement is written to standard error with its line as it's run. With `--engine=vm`,
+/// the program is compiled to bytecode and run on the VM rather than interpreted, which
+/// only some programs can be so far, and without arguments, as the VM has no os.Args yet.
+/// `--engine=jit` runs it on the VM too, compiling its hot numeric functions to machine
+/// code, when gor is built with the `jit` feature. A `.gorc` file `gor build` wrote runs on
+/// the VM as it is.
+fn handle_run(options: &RunOptions) -> i32 {
+    let RunOptions {
+        trace,
+        engine,
+        path,
+        args,
+    } = options.clone();
+    if Path::new(&path)
+        .extension()
+        .is_some_and(|extension| extension == BYTECODE_EXTENSION)
+    {
+        if trace || engine == Engine::Jit || !args.is_empty() {
+            eprintln!("Error: bytecode runs on the VM, without --trace or arguments");
+            return EXIT_USAGE;
+        }
+        return run_bytecode(Path::new(&path));
+    }
+    if !args.is_empty() && engine != Engine::Tree {
+        eprintln!("Error: a program's arguments need --engine=tree, as the VM has no os.Args yet");
+        return EXIT_USAGE;
+    }
+    if trace && engine != Engine::Tree {
+        eprintln!("Error: --trace needs --engine=tree");
+        return EXIT_USAGE;
+    }
+    if engine == Engine::Jit && !cfg!(feature = "jit") {
+        eprintln!("Error: --engine=jit needs gor built with the jit feature");
+        return EXIT_USAGE;
+    }
+    let program = match load_program(&path, &LoaderConfig::default()) {
+        Ok(program) => program,
+        Err(error) => {
+            return load_failed(&error);
+        }
+    };
+    if check_program(&program) > 0 {
+        return EXIT_FAILURE;
     }
 
-    fn handle_dump_tokens(&self) {
-        let filename = &self.args[2];
-        if filename.is_empty() {
-            eprintln!("Usage: gor dump-tokens <filename>");
-            std::process::exit(1);
+    // os.Args starts with the path the program was run as
+    let args = std::iter::once(path).chain(args).collect();
+    let run = std::thread::Builder::new()
+        .stack_size(RUN_STACK_SIZE)
+        .spawn(move || {
+            let result = match engine {
+                Engine::Vm | Engine::Jit => match compile(&program.file) {
+                    Ok(compiled) => {
+                        let vm = match engine {
+                            Engine::Jit => jit_vm(&program),
+                            _ => Ok(Vm::new()),
+                        };
+                        match vm {
+                            Ok(mut vm) => vm.run(&compiled),
+                            Err(error) => {
+                                report(&program, error.position, &error.kind);
+                                return EXIT_FAILURE;
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        report(&program, error.position, &error.kind);
+                        return EXIT_FAILURE;
+                    }
+                },
+                Engine::Tree => {
+                    let mut interpreter = Interpreter::new().with_args(args);
+                    if trace {
+                        let tracer = Tracer::new(program.sources.clone(), std::io::stderr());
+                        interpreter = interpreter.with_observer(tracer);
+                    }
+                    interpreter.run(&program.file)
+                }
+            };
+            let Err(error) = result else {
+                return EXIT_SUCCESS;
+            };
+            match error.kind {
+                RuntimeErrorKind::Exit(status) => status,
+                RuntimeErrorKind::Panic { .. } => {
+                    eprintln!("{}", error.kind);
+                    PANIC_STATUS
+                }
+                RuntimeErrorKind::Internal(_) => {
+                    report(&program, error.position, &error.kind);
+                    EXIT_INTERNAL
+                }
+                _ => {
+                    report(&program, error.position, &error.kind);
+                    EXIT_FAILURE
+                }
+            }
+        });
+    match run.map(|handle| handle.join()) {
+        Ok(Ok(status)) => status,
+        _ => {
+            eprintln!("Error: the interpreter failed to run");
+            EXIT_INTERNAL
         }
-        let content = self.read_go_file(filename);
-        let mut lexer = Lexer::new(&content);
+    }
+}
+
+/// Parse and type-check programs and packages, each given as `gor run` takes it or as a
+/// pattern like `./...`, without running them. `--tests` checks each package with its
+/// `_test.go` files. Every diagnostic is printed, then how many errors and warnings there
+/// were between them if there were any, and the exit status is 1 if there was an error.
+/// With `--format=json`, each diagnostic is printed to standard output as a JSON object, and
+/// so is each package that couldn't be loaded, and the count isn't printed. `--format=sarif`
+/// prints them all as a SARIF log instead, once they've all been found. With
+/// `--max-errors`, nothing more is printed once that many errors have been.
+fn handle_check(options: &CheckOptions) -> i32 {
+    let mut reporter = Reporter::new(options.format, options.max_errors);
+    let config = loader_config(options.tests, &options.exclude);
+    let failed = report_packages(&options.paths, &config, &mut reporter, diagnose);
+    let errors = reporter.finish();
+    if reporter.diagnostics.iter().any(Diagnostic::is_internal) {
+        return EXIT_INTERNAL;
+    }
+    status(errors > 0 || failed)
+}
 
-        let has_errors = lexer.dump_tokens().any(|item| {
-            if item.starts_with("Error at") {
-                eprintln!("{}", item);
-                true
-            } else {
-                println!("{}", item);
-                false
+/// Vet programs and packages, given as `gor check` takes them, with the rules `--enable`
+/// and `--disable` leave on. A package with type errors isn't vetted; its errors are
+/// reported instead. Diagnostics are printed as `gor check` prints them, and the exit status
+/// is 1 if there were any, warnings included.
+fn handle_vet(options: &VetOptions) -> i32 {
+    let config = options
+        .enable
+        .iter()
+        .fold(VetConfig::default(), |config, rule| {
+            config.with_enabled(rule)
+        });
+    let config = options
+        .disable
+        .iter()
+        .fold(config, |config, rule| config.with_disabled(rule));
+    let vet = Vet::new(config);
+    let mut reporter = Reporter::new(options.format, None);
+    let config = loader_config(options.tests, &options.exclude);
+    let failed = report_packages(&options.paths, &config, &mut reporter, |program| {
+        vet.check(&program.file)
+    });
+    reporter.finish();
+    if reporter.diagnostics.iter().any(Diagnostic::is_internal) {
+        return EXIT_INTERNAL;
+    }
+    status(!reporter.diagnostics.is_empty() || failed)
+}
+
+/// Run the tests of packages, given as `gor check` takes them, by default the package in the
+/// current directory. Each package is loaded with its `_test.go` files and type-checked, then
+/// its test functions are run under the interpreter, those whose names contain `--run`'s
+/// text if it's given. Each test and subtest is printed with its outcome and how long it
+/// took, and with what it logged if it failed or with `-v`. A line for each package then
+/// says whether it passed, how long it took, and how many tests passed, failed and were
+/// skipped. The exit status is 1 if a test failed or a package couldn't be tested.
+fn handle_test(options: &TestOptions) -> i32 {
+    let config = loader_config(true, &options.exclude);
+    let mut cache = LoadCache::default();
+    let mut failed = false;
+    let mut internal = false;
+    for pattern in &options.paths {
+        let packages = match match_packages(pattern, &config) {
+            Ok(packages) => packages,
+            Err(error) => {
+                eprintln!("{}", error);
+                failed = true;
+                continue;
             }
+        };
+        for package in packages {
+            let status = test_package(&package, options, &mut cache);
+            failed |= status != EXIT_SUCCESS;
+            internal |= status == EXIT_INTERNAL;
+        }
+    }
+    if internal {
+        return EXIT_INTERNAL;
+    }
+    status(failed)
+}
+
+/// Run the tests of the package in `path` and print its line, as `gor test` does, returning
+/// the status to exit with for it
+fn test_package(path: &Path, options: &TestOptions, cache: &mut LoadCache) -> i32 {
+    let name = path.display().to_string();
+    let config = loader_config(true, &options.exclude);
+    let program = match load_program_cached(&name, &config, cache) {
+        Ok(program) => program,
+        Err(error) => {
+            let status = load_failed(&error);
+            outln!("FAIL\t{}\t[setup failed]", name);
+            return status;
+        }
+    };
+    if check_program(&program) > 0 {
+        outln!("FAIL\t{}\t[build failed]", name);
+        return EXIT_FAILURE;
+    }
+    let has_tests = program
+        .sources
+        .iter()
+        .any(|source| source.path.to_string_lossy().ends_with("_test.go"));
+    if !has_tests {
+        outln!("?   \t{}\t[no test files]", name);
+        return EXIT_SUCCESS;
+    }
+
+    let TestOptions { verbose, run, .. } = options.clone();
+    let started = Instant::now();
+    let tested = std::thread::Builder::new()
+        .stack_size(RUN_STACK_SIZE)
+        .spawn(move || {
+            let mut outcomes = Vec::new();
+            let filter = |test: &str| run.as_ref().is_none_or(|run| test.contains(run.as_str()));
+            let result = Interpreter::new().run_tests(&program.file, filter, |result| {
+                for (depth, result) in result.flatten() {
+                    print_test_result(&program, depth, result, verbose);
+                    outcomes.push(result.outcome);
+                }
+            });
+            let Err(error) = result else {
+                return (EXIT_SUCCESS, outcomes);
+            };
+            let status = match error.kind {
+                RuntimeErrorKind::Panic { .. } => {
+                    eprintln!("{}", error.kind);
+                    EXIT_FAILURE
+                }
+                RuntimeErrorKind::Internal(_) => {
+                    report(&program, error.position, &error.kind);
+                    EXIT_INTERNAL
+                }
+                _ => {
+                    report(&program, error.position, &error.kind);
+                    EXIT_FAILURE
+                }
+            };
+            (status, outcomes)
         });
+    let (status, outcomes) = match tested.map(|handle| handle.join()) {
+        Ok(Ok(tested)) => tested,
+        _ => {
+            eprintln!("Error: the interpreter failed to run");
+            return EXIT_INTERNAL;
+        }
+    };
+    let count = |outcome| outcomes.iter().filter(|tested| **tested == outcome).count();
+    let failed = count(TestOutcome::Failed);
+    let status = match (status, failed) {
+        (EXIT_SUCCESS, 0) => EXIT_SUCCESS,
+        (EXIT_SUCCESS, _) => EXIT_FAILURE,
+        (status, _) => status,
+    };
+    outln!(
+        "{}\t{}\t{:.3}s\t{} passed, {} failed, {} skipped",
+        if status == EXIT_SUCCESS {
+            "ok  "
+        } else {
+            "FAIL"
+        },
+        name,
+        started.elapsed().as_secs_f64(),
+        count(TestOutcome::Passed),
+        failed,
+        count(TestOutcome::Skipped)
+    );
+    status
+}
+
+/// Print a test's outcome and how long it took as `go test -v` does, indented as deep as
+/// it's nested, then what it logged if it failed or `verbose` is set
+fn print_test_result(program: &Program, depth: usize, result: &TestResult, verbose: bool) {
+    let indent = "    ".repeat(depth);
+    outln!(
+        "{}--- {}: {} ({:.2}s)",
+        indent,
+        result.outcome.label(),
+        result.name,
+        result.elapsed.as_secs_f64()
+    );
+    if !verbose && result.outcome != TestOutcome::Failed {
+        return;
+    }
+    for logged in &result.output {
+        // A message's later lines are indented under its first
+        let message = logged
+            .message
+            .replace('\n', &format!("\n{}        ", indent));
+        match program.locate(logged.position) {
+            Some((path, position)) => {
+                let file = path.file_name().unwrap_or(path.as_os_str());
+                let file = file.to_string_lossy();
+                outln!("{}    {}:{}: {}", indent, file, position.line, message);
+            }
+            None => outln!("{}    {}", indent, message),
+        }
+    }
+}
+
+/// Load each package the patterns match, with the files `config` loads, and report what
+/// `diagnose` finds in it, or its syntax errors. Packages are checked at the same time, but
+/// reported in the order they're matched. Returns whether a pattern or package couldn't be
+/// loaded for another reason.
+fn report_packages(
+    patterns: &[String],
+    config: &LoaderConfig,
+    reporter: &mut Reporter,
+    diagnose: impl Fn(&Program) -> Vec<Diagnostic> + Sync,
+) -> bool {
+    let mut packages = Vec::new();
+    for pattern in patterns {
+        match match_packages(pattern, config) {
+            Ok(matched) => packages.extend(matched.into_iter().map(Ok)),
+            Err(error) => packages.push(Err(error)),
+        }
+    }
+    let mut failed = false;
+    check_packages(&packages, config, &diagnose, |checked| match checked {
+        Ok((program, diagnostics)) => {
+            for diagnostic in diagnostics {
+                reporter.report(diagnostic, |position| locate(&program, position));
+            }
+        }
+        Err(LoadError {
+            kind: LoadErrorKind::Parse(diagnostics),
+            path,
+        }) => {
+            for diagnostic in diagnostics {
+                reporter.report(diagnostic, |position| (Some(path.as_path()), position));
+            }
+        }
+        Err(error) => {
+            reporter.load_error(&error);
+            failed = true;
+        }
+    });
+    failed
+}
+
+/// A package loaded and what was found in it, or why it couldn't be loaded
+type Checked = Result<(Program, Vec<Diagnostic>), LoadError>;
+
+/// Load each package and find what `diagnose` does in it on a thread per core, each keeping
+/// a cache of the files it's parsed. The workers send what they find to a channel, from
+/// which each package is passed to `report` in the order it's given, once it and those
+/// before it are done. A pattern that matched nothing is passed on as its error.
+fn check_packages(
+    packages: &[Result<PathBuf, LoadError>],
+    config: &LoaderConfig,
+    diagnose: &(impl Fn(&Program) -> Vec<Diagnostic> + Sync),
+    mut report: impl FnMut(Checked),
+) {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, usize::from)
+        .min(packages.len());
+    debug!(
+        "checking {} packages on {} threads",
+        packages.len(),
+        workers
+    );
+    let next = AtomicUsize::new(0);
+    let (sink, checked) = mpsc::channel::<(usize, Checked)>();
+    std::thread::scope(|scope| {
+        for worker in 0..workers {
+            let (next, sink) = (&next, sink.clone());
+            std::thread::Builder::new()
+                .name(format!("check-{}", worker))
+                .stack_size(CHECK_STACK_SIZE)
+                .spawn_scoped(scope, move || {
+                    let mut cache = LoadCache::default();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(package) = packages.get(index) else {
+                            return;
+                        };
+                        let Ok(path) = package else {
+                            continue;
+                        };
+                        let path = path.to_string_lossy();
+                        let found = load_program_cached(&path, config, &mut cache).map(|program| {
+                            let diagnostics = diagnose(&program);
+                            (program, diagnostics)
+                        });
+                        if sink.send((index, found)).is_err() {
+                            return;
+                        }
+                    }
+                })
+                .expect("a thread to check packages on");
+        }
+        drop(sink);
+        // What's been found for packages after the next to report
+        let mut found = HashMap::new();
+        for (index, package) in packages.iter().enumerate() {
+            if let Err(error) = package {
+                report(Err(error.clone()));
+                continue;
+            }
+            while !found.contains_key(&index) {
+                // A worker panicked, which the scope passes on once it's joined them
+                let Ok((checked_index, checked)) = checked.recv() else {
+                    return;
+                };
+                found.insert(checked_index, checked);
+            }
+            report(found.remove(&index).expect("it was found"));
+        }
+    });
+}
+
+/// Prints `gor check`'s and `gor vet`'s diagnostics as they're found, until `--max-errors`
+/// errors have been printed
+struct Reporter {
+    format: OutputFormat,
+    max_errors: Option<usize>,
+    /// How many of the diagnostics are errors
+    errors: usize,
+    /// Every diagnostic found, whether it was printed or not
+    diagnostics: Vec<Diagnostic>,
+    /// The diagnostics to print with `--format=sarif`, at the end
+    sarif: Sarif,
+}
+
+impl Reporter {
+    fn new(format: OutputFormat, max_errors: Option<usize>) -> Reporter {
+        Reporter {
+            format,
+            max_errors,
+            errors: 0,
+            diagnostics: Vec::new(),
+            sarif: Sarif::new(),
+        }
+    }
+
+    /// Print a diagnostic, each position given in its file by `locate`, unless the limit has
+    /// been reached. The first error past it is reported as `too many errors`, as go does.
+    fn report<'a>(
+        &mut self,
+        diagnostic: Diagnostic,
+        locate: impl Fn(Position) -> (Option<&'a Path>, Position),
+    ) {
+        let error = diagnostic.severity == Severity::Error;
+        match self.max_errors {
+            Some(max_errors) if self.errors >= max_errors => {
+                if error && self.errors == max_errors && self.format == OutputFormat::Text {
+                    eprintln!("too many errors");
+                }
+            }
+            _ => match self.format {
+                OutputFormat::Text => report_diagnostic(&diagnostic, locate),
+                OutputFormat::Json => outln!("{}", diagnostic_json(&diagnostic, locate)),
+                OutputFormat::Sarif => self.sarif.add(&diagnostic, locate),
+            },
+        }
+        self.errors += error as usize;
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Print how many errors and warnings there were, or the SARIF log, once everything's
+    /// been reported, returning how many errors there were
+    fn finish(&self) -> usize {
+        match self.format {
+            OutputFormat::Text => summarize(&self.diagnostics),
+            OutputFormat::Json => self.errors,
+            OutputFormat::Sarif => {
+                out!("{}", self.sarif.finish());
+                self.errors
+            }
+        }
+    }
+
+    /// Print why a package couldn't be loaded, when it's not for syntax errors
+    fn load_error(&mut self, error: &LoadError) {
+        match self.format {
+            OutputFormat::Text => eprintln!("{}", error),
+            OutputFormat::Json => outln!("{}", load_error_json(error)),
+            OutputFormat::Sarif => {
+                self.sarif
+                    .add_file("load", &error.kind.to_string(), &error.path)
+            }
+        }
+    }
+}
 
-        if has_errors {
-            std::process::exit(1);
+/// Print the import graph of a program, given as `gor run` takes it, in Graphviz's DOT
+/// language or as JSON: its packages, the standard library's left out, and an edge from each
+/// to each it imports. With `--calls`, print its functions and methods and an edge from each
+/// to each function it calls instead. The program is only loaded, not type-checked.
+fn handle_graph(format: GraphFormat, calls: bool, path: &str) -> i32 {
+    let program = match load_program(path, &LoaderConfig::default()) {
+        Ok(program) => program,
+        Err(error) => return load_failed(&error),
+    };
+    let graph = match calls {
+        true => graph::calls(&program),
+        false => graph::imports(&program),
+    };
+    match format {
+        GraphFormat::Dot => out!("{}", graph.to_dot()),
+        GraphFormat::Json => outln!("{}", graph.to_json()),
+    }
+    EXIT_SUCCESS
+}
+
+/// Compile a program, given as `gor run` takes it, and print its bytecode
+fn handle_dump_bytecode(path: &str) -> i32 {
+    let program = match load_program(path, &LoaderConfig::default()) {
+        Ok(program) => program,
+        Err(error) => {
+            return load_failed(&error);
+        }
+    };
+    match compile(&program.file) {
+        Ok(compiled) => {
+            out!("{}", compiled);
+            EXIT_SUCCESS
+        }
+        Err(error) => {
+            report(&program, error.position, &error.kind);
+            EXIT_FAILURE
         }
     }
+}
 
-    fn verify_base_command(&self) {
-        if self.args.len() < 2 {
-            eprintln!("Usage: gor <command>");
-            std::process::exit(1);
+/// Lower a program, given as `gor run` takes it, to the IR and print it. With
+/// `--opt-level=1`, the IR is optimized first; the default, 0, prints it as it's lowered.
+fn handle_dump_ir(level: u8, path: &str) -> i32 {
+    let program = match load_program(path, &LoaderConfig::default()) {
+        Ok(program) => program,
+        Err(error) => {
+            return load_failed(&error);
+        }
+    };
+    match lower(&program.file) {
+        Ok(mut module) => {
+            PassManager::for_level(level).run(&mut module);
+            out!("{}", module);
+            EXIT_SUCCESS
+        }
+        Err(error) => {
+            report(&program, error.position, &error.kind);
+            EXIT_FAILURE
         }
+    }
+}
 
-        if !&self.args[0].contains("gor") {
-            eprintln!("Unknown command: {}", self.args[0]);
-            std::process::exit(1);
+/// Compile a program, given as `gor run` takes it, once it's type-checked, and write
+/// what `--emit` asks for: its bytecode, which `gor run` runs on the VM, a WebAssembly
+/// module or a C program. It's written to `-o`'s path or by default, the program's name
+/// with the artifact's extension, like `main.gorc` for `main.go`. The IR the WebAssembly
+/// and C backends compile is optimized at `--opt-level=1` unless it's 0.
+fn handle_build(options: &BuildOptions) -> i32 {
+    let program = match load_program(&options.path, &LoaderConfig::default()) {
+        Ok(program) => program,
+        Err(error) => {
+            return load_failed(&error);
+        }
+    };
+    if check_program(&program) > 0 {
+        return EXIT_FAILURE;
+    }
+    let artifact = match options.emit {
+        Emit::Bytecode => compile(&program.file).and_then(|compiled| encode(&compiled)),
+        Emit::Wasm | Emit::C => lower(&program.file).and_then(|mut module| {
+            PassManager::for_level(options.level).run(&mut module);
+            match options.emit {
+                Emit::Wasm => wasm::generate(&module),
+                _ => c::generate(&module).map(String::into_bytes),
+            }
+        }),
+    };
+    let artifact = match artifact {
+        Ok(artifact) => artifact,
+        Err(error) => {
+            report(&program, error.position, &error.kind);
+            return EXIT_FAILURE;
         }
+    };
+    let output = options.output.clone().unwrap_or_else(|| {
+        let path = Path::new(&options.path);
+        let name = match path.is_dir() {
+            // A program read from standard input has no name but main
+            _ if options.path == STDIN => None,
+            true => fs::canonicalize(path).ok().and_then(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            }),
+            false => path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned()),
+        };
+        let extension = match options.emit {
+            Emit::Bytecode => BYTECODE_EXTENSION,
+            Emit::Wasm => "wasm",
+            Emit::C => "c",
+        };
+        PathBuf::from(format!(
+            "{}.{}",
+            name.unwrap_or_else(|| "main".to_string()),
+            extension
+        ))
+    });
+    if let Err(error) = fs::write(&output, artifact) {
+        eprintln!("Error writing '{}': {}", output.display(), error);
+        return EXIT_FAILURE;
     }
+    EXIT_SUCCESS
+}
 
-    fn validate_go_file(&self, path: &PathBuf) {
-        if path.extension().and_then(|ext| ext.to_str()) != Some("go") {
-            eprintln!("Error: file must have a .go extension");
-            std::process::exit(1);
+/// Read inputs from standard input and run each, printing an expression's value, until
+/// the input ends. An input whose braces, brackets or parentheses aren't closed carries
+/// on over the lines after it.
+fn handle_repl() -> i32 {
+    let repl = std::thread::Builder::new()
+        .stack_size(RUN_STACK_SIZE)
+        .spawn(|| {
+            let mut repl = Repl::new();
+            let mut input = String::new();
+            loop {
+                out!("{}", if input.is_empty() { ">>> " } else { "... " });
+                let _ = std::io::stdout().flush();
+                let mut line = String::new();
+                match std::io::stdin().lock().read_line(&mut line) {
+                    Ok(0) | Err(_) => return None,
+                    Ok(_) => input.push_str(&line),
+                }
+                if Repl::is_incomplete(&input) {
+                    continue;
+                }
+                if !input.trim().is_empty() {
+                    match repl.eval(&input) {
+                        Ok(Some(value)) => outln!("{}", value),
+                        Ok(None) => {}
+                        Err(ReplError::Runtime(error)) => {
+                            if let RuntimeErrorKind::Exit(status) = error.kind {
+                                return Some(status);
+                            }
+                            eprintln!("{}", error.kind);
+                        }
+                        Err(error) => eprintln!("{}", error),
+                    }
+                }
+                input.clear();
+            }
+        });
+    match repl.map(|handle| handle.join()) {
+        Ok(Ok(None)) => {
+            outln!();
+            EXIT_SUCCESS
+        }
+        Ok(Ok(Some(status))) => status,
+        _ => {
+            eprintln!("Error: the interpreter failed to run");
+            EXIT_INTERNAL
         }
+    }
+}
 
-        if !path.exists() {
-            eprintln!("Error: file '{:?}' does not exist", path.to_str());
-            std::process::exit(1);
+/// Serve an editor the Language Server Protocol on standard input and output until it says
+/// to exit, exiting with status 1 if it didn't say to shut down first. Its log goes to
+/// standard error, which editors keep for the server.
+/// Rename the name at `line` and `column` of the file at `path` everywhere the file's package
+/// refers to it, writing the files it changes, or with `diff`, printing a diff of the changes
+fn handle_rename(diff: bool, path: &str, line: usize, column: usize, name: &str) -> i32 {
+    let file = Path::new(path);
+    let directory = match file.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+    let config = loader_config(path.ends_with("_test.go"), &[]);
+    let program = match load_with_config(directory, &config) {
+        Ok(program) => program,
+        Err(error) => return load_failed(&error),
+    };
+    // The package's files are named by its directory, which `path` may name differently
+    let same_file = |other: &Path| match (fs::canonicalize(file), fs::canonicalize(other)) {
+        (Ok(file), Ok(other)) => file == other,
+        _ => file == other,
+    };
+    let Some(source) = program
+        .sources
+        .iter()
+        .find(|source| same_file(&source.path))
+    else {
+        eprintln!(
+            "{}: not a file of the package in {}",
+            path,
+            directory.display()
+        );
+        return EXIT_FAILURE;
+    };
+    let position = Position::new_single_position(line, column);
+    let files = match rename(&program, &source.path, position, name) {
+        Ok(files) => files,
+        Err(error) => {
+            eprintln!("{}:{}", path, error);
+            return EXIT_FAILURE;
+        }
+    };
+    let mut failed = false;
+    for edited in files {
+        let Some(source) = program
+            .sources
+            .iter()
+            .find(|source| source.path == edited.path)
+        else {
+            continue;
+        };
+        let renamed = edited.apply(&source.text);
+        let name = source.path.display().to_string();
+        match diff {
+            true => out!(
+                "{}",
+                unified_diff(&source.text, &renamed, &format!("{}.orig", name), &name)
+            ),
+            false => {
+                if let Err(error) = fs::write(&source.path, renamed) {
+                    eprintln!("Error writing '{}': {}", name, error);
+                    failed = true;
+                }
+            }
         }
     }
+    status(failed)
+}
 
-    fn read_go_file(&self, filename: &str) -> String {
-        let path = PathBuf::from(filename);
-        self.validate_go_file(&path);
+fn handle_lsp() -> i32 {
+    let stdin = std::io::stdin();
+    match Server::new().serve(stdin.lock(), std::io::stdout().lock()) {
+        Ok(shut_down) => status(!shut_down),
+        Err(error) => {
+            eprintln!("gor lsp: {}", error);
+            EXIT_FAILURE
+        }
+    }
+}
 
-        match fs::read_to_string(&path) {
+/// Format files as gofmt would, printing the result, or with `-w`, writing it back to
+/// each file that changed. `--diff` prints a unified diff of the changes instead, and
+/// `--check` does too, exiting with status 1 if any file isn't formatted, so a build can
+/// enforce formatting without rewriting files. `--group-imports` groups imports as
+/// goimports does, the packages of the file's module, or under `--local`'s prefix, last.
+/// `--remove-unused-imports` leaves out those the file doesn't use.
+fn handle_fmt(options: &FmtOptions) -> i32 {
+    let mode = options.mode;
+    let mut failed = false;
+    let mut paths = Vec::new();
+    for path in &options.paths {
+        match files_to_format(path, &loader_config(options.tests, &options.exclude)) {
+            Ok(files) => paths.extend(files),
+            Err(error) => {
+                eprintln!("{}", error);
+                failed = true;
+            }
+        }
+    }
+    for path in &paths {
+        if path == STDIN && mode == FormatMode::Write {
+            eprintln!("Error: -w can't write to standard input");
+            return EXIT_FAILURE;
+        }
+        let content = match read_go_file("fmt", path) {
             Ok(content) => content,
+            Err(status) => return status,
+        };
+        let mut config = FormatterConfig::default();
+        if options.group_imports {
+            config = config.with_grouped_imports();
+            let module = || match path == STDIN {
+                true => module_path(Path::new(".")),
+                false => module_path(Path::new(path)),
+            };
+            if let Some(prefix) = options.local_prefix.clone().or_else(module) {
+                config = config.with_local_prefix(prefix);
+            }
+        }
+        if options.remove_unused {
+            config = config.with_unused_imports_removed();
+        }
+        let formatted = match format_source_with_config(&content, &config) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                for error in errors {
+                    eprintln!(
+                        "{}:{}:{}: {}",
+                        display_name(path),
+                        error.position.line,
+                        error.position.column_start,
+                        error.kind
+                    );
+                }
+                failed = true;
+                continue;
+            }
+        };
+        match mode {
+            FormatMode::Print => out!("{}", formatted),
+            FormatMode::Write => {
+                if formatted != content
+                    && let Err(error) = fs::write(path, formatted)
+                {
+                    eprintln!("Error writing '{}': {}", path, error);
+                    failed = true;
+                }
+            }
+            FormatMode::Check | FormatMode::Diff => {
+                let name = display_name(path);
+                let diff = unified_diff(&content, &formatted, &format!("{}.orig", name), name);
+                out!("{}", diff);
+                failed |= mode == FormatMode::Check && !diff.is_empty();
+            }
+        }
+    }
+    status(failed)
+}
+
+/// The files `gor fmt` formats for `path`: the file itself, or the `.go` files of each
+/// package directory it is or matches, as `gor check` would load them
+fn files_to_format(path: &str, config: &LoaderConfig) -> Result<Vec<String>, LoadError> {
+    if !Path::new(path).is_dir() && !path.ends_with("...") {
+        return Ok(vec![path.to_string()]);
+    }
+    let mut files = Vec::new();
+    for directory in match_packages(path, config)? {
+        let go_files = go_files(&directory, config)?;
+        if go_files.is_empty() {
+            return Err(LoadError::new(LoadErrorKind::NoGoFiles, directory));
+        }
+        files.extend(go_files.iter().map(|file| file.display().to_string()));
+    }
+    Ok(files)
+}
+
+/// Format every `.go` file under a directory twice, reporting each whose formatting isn't
+/// stable or changes its syntax tree or comments, and exiting with status 1 if any does, or if
+/// none could be checked. Files gor can't parse are skipped.
+fn handle_selftest_fmt(directory: &str) -> i32 {
+    let (mut passed, mut skipped, mut failed) = (0, 0, 0);
+    for (_, result) in selftest_directory(Path::new(directory)) {
+        match result {
+            Ok(()) => passed += 1,
+            Err(error) if error.is_skipped() => {
+                eprintln!("{}", error);
+                skipped += 1;
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                failed += 1;
+            }
+        }
+    }
+    outln!("{} passed, {} failed, {} skipped", passed, failed, skipped);
+    if passed + failed == 0 {
+        eprintln!(
+            "gor internal selftest-fmt: no files in {} were checked",
+            directory
+        );
+        return EXIT_FAILURE;
+    }
+    status(failed > 0)
+}
+
+/// Read a `.go` file given to `command`, or standard input if `filename` is `-`, or print why
+/// it can't be and return the status to exit with: `EXIT_USAGE` for a path that isn't a `.go`
+/// file, or else `EXIT_FAILURE`
+fn read_go_file(command: &str, filename: &str) -> Result<String, i32> {
+    if filename == STDIN {
+        let mut content = String::new();
+        return match std::io::stdin().read_to_string(&mut content) {
+            Ok(_) => Ok(content),
             Err(e) => {
-                eprintln!("Error reading file '{}': {}", filename, e);
-                std::process::exit(1);
+                eprintln!("Error reading standard input: {}", e);
+                Err(EXIT_FAILURE)
             }
+        };
+    }
+    let path = Path::new(filename);
+    let kind = if path.extension().and_then(|ext| ext.to_str()) != Some("go") {
+        Some(CliErrorKind::NotAGoFile(path.display().to_string()))
+    } else if !path.exists() {
+        Some(CliErrorKind::NoSuchFile(path.display().to_string()))
+    } else {
+        None
+    };
+    if let Some(kind) = kind {
+        eprintln!("{}", CliError::new(kind, Some(command.to_string())));
+        return Err(EXIT_USAGE);
+    }
+    fs::read_to_string(path).map_err(|e| {
+        eprintln!("Error reading file '{}': {}", filename, e);
+        EXIT_FAILURE
+    })
+}
+
+/// Load a program as `load` does, or from standard input if `path` is `-`
+fn load_program(path: &str, config: &LoaderConfig) -> Result<Program, LoadError> {
+    if path != STDIN {
+        return load_with_config(Path::new(path), config);
+    }
+    let mut source = String::new();
+    std::io::stdin()
+        .read_to_string(&mut source)
+        .map_err(|error| {
+            LoadError::new(
+                LoadErrorKind::Io(error.to_string()),
+                PathBuf::from(STDIN_NAME),
+            )
+        })?;
+    load_source(Path::new(STDIN_NAME), source)
+}
+
+/// Load the program in `path` as `load_program` does, taking the files `cache` has parsed
+/// from it, for a command that loads many programs that may share packages
+fn load_program_cached(
+    path: &str,
+    config: &LoaderConfig,
+    cache: &mut LoadCache,
+) -> Result<Program, LoadError> {
+    match path == STDIN {
+        true => load_program(path, config),
+        false => load_cached(Path::new(path), config, cache),
+    }
+}
+
+/// Print why a program couldn't be loaded, returning the status to exit with:
+/// `EXIT_INTERNAL` if it was for a bug in gor's parser, or else `EXIT_FAILURE`
+fn load_failed(error: &LoadError) -> i32 {
+    eprintln!("{}", error);
+    match &error.kind {
+        LoadErrorKind::Parse(diagnostics) if diagnostics.iter().any(Diagnostic::is_internal) => {
+            EXIT_INTERNAL
+        }
+        _ => EXIT_FAILURE,
+    }
+}
+
+/// `EXIT_FAILURE` if a command failed, or else `EXIT_SUCCESS`
+fn status(failed: bool) -> i32 {
+    match failed {
+        true => EXIT_FAILURE,
+        false => EXIT_SUCCESS,
+    }
+}
+
+/// What `--tests` and `--exclude` ask the loader for
+fn loader_config(tests: bool, exclude: &[PathBuf]) -> LoaderConfig {
+    let config = match tests {
+        true => LoaderConfig::default().with_tests(),
+        false => LoaderConfig::default(),
+    };
+    exclude
+        .iter()
+        .fold(config, |config, path| config.with_excluded(path))
+}
+
+/// The name of the file at `path` in errors, which is standard input's own for `-`
+fn display_name(path: &str) -> &str {
+    match path == STDIN {
+        true => STDIN_NAME,
+        false => path,
+    }
+}
+
+/// A VM that compiles the program's hot functions to machine code
+#[cfg(feature = "jit")]
+fn jit_vm(program: &Program) -> Result<Vm, CompileError> {
+    Ok(Vm::new().with_jit(Jit::for_file(&program.file)?))
+}
+
+#[cfg(not(feature = "jit"))]
+fn jit_vm(_program: &Program) -> Result<Vm, CompileError> {
+    unreachable!("--engine=jit is rejected without the jit feature")
+}
+
+/// Run the bytecode `gor build` wrote on the VM, returning the exit status. There's no
+/// source to find a runtime error's file in, so it's reported with only its line and column.
+fn run_bytecode(path: &Path) -> i32 {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            eprintln!("Error reading '{}': {}", path.display(), error);
+            return EXIT_FAILURE;
+        }
+    };
+    let compiled = match decode(&bytes) {
+        Ok(compiled) => compiled,
+        Err(error) => {
+            eprintln!("{}: {}", path.display(), error);
+            return EXIT_FAILURE;
+        }
+    };
+    let Err(error) = Vm::new().run(&compiled) else {
+        return EXIT_SUCCESS;
+    };
+    match error.kind {
+        RuntimeErrorKind::Exit(status) => status,
+        RuntimeErrorKind::Panic { .. } => {
+            eprintln!("{}", error.kind);
+            PANIC_STATUS
+        }
+        _ => {
+            eprintln!(
+                "{}:{}: {}",
+                error.position.line, error.position.column_start, error.kind
+            );
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Type-check a program, printing its diagnostics in source order and how many there are,
+/// and returning how many are errors
+fn check_program(program: &Program) -> usize {
+    let diagnostics = diagnose(program);
+    for diagnostic in &diagnostics {
+        report_diagnostic(diagnostic, |position| locate(program, position));
+    }
+    summarize(&diagnostics)
+}
+
+/// Type-check a program, returning its diagnostics in source order
+fn diagnose(program: &Program) -> Vec<Diagnostic> {
+    let (_, mut diagnostics) = check_with_config(&program.file, &CheckerConfig::default());
+    diagnostics
+        .sort_by_key(|diagnostic| (diagnostic.position.line, diagnostic.position.column_start));
+    diagnostics
+}
+
+/// The file a position of the program is in, if it's known, and the position within it
+fn locate(program: &Program, position: Position) -> (Option<&Path>, Position) {
+    match program.locate(position) {
+        Some((path, position)) => (Some(path), position),
+        None => (None, position),
+    }
+}
+
+/// Print a diagnostic and its notes with the file, line and column each is at, each
+/// position given in its file by `locate`
+fn report_diagnostic<'a>(
+    diagnostic: &Diagnostic,
+    locate: impl Fn(Position) -> (Option<&'a Path>, Position),
+) {
+    let located = |position: Position| match locate(position) {
+        (Some(path), position) => format!(
+            "{}:{}:{}",
+            path.display(),
+            position.line,
+            position.column_start
+        ),
+        (None, position) => format!("{}:{}", position.line, position.column_start),
+    };
+    eprintln!(
+        "{}: {}: {}",
+        located(diagnostic.position),
+        diagnostic.severity,
+        diagnostic.message
+    );
+    for (message, position) in &diagnostic.notes {
+        eprintln!("{}: note: {}", located(*position), message);
+    }
+}
+
+/// Print how many errors and warnings there are, like `2 errors, 1 warning`, if there are
+/// any, returning how many errors
+fn summarize(diagnostics: &[Diagnostic]) -> usize {
+    let count = |severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    let plural = |count: usize, noun: &str| match count {
+        1 => format!("1 {}", noun),
+        count => format!("{} {}s", count, noun),
+    };
+    match (errors, warnings) {
+        (0, 0) => {}
+        (_, 0) => eprintln!("{}", plural(errors, "error")),
+        (0, _) => eprintln!("{}", plural(warnings, "warning")),
+        _ => eprintln!(
+            "{}, {}",
+            plural(errors, "error"),
+            plural(warnings, "warning")
+        ),
+    }
+    let mut codes: Vec<&str> = diagnostics
+        .iter()
+        .filter_map(|diagnostic| diagnostic.explanation)
+        .collect();
+    codes.sort();
+    codes.dedup();
+    match codes.as_slice() {
+        [] => {}
+        [code] => eprintln!("For more about {}, run `gor explain {}`", code, code),
+        codes => eprintln!(
+            "For more about {}, run `gor explain` with the code",
+            codes.join(", ")
+        ),
+    }
+    errors
+}
+
+/// A diagnostic as a line of JSON, like `{"kind": "error", "value": "undefined: x", "code":
+/// "E0101", "check": "names", "file": "main.go", "span": {...}, "notes": []}`, each position
+/// given in its file by `locate`. Its kind is its severity, its value its message, and its
+/// code the one `gor explain` takes, as the other formats give it, or the vet rule's name.
+fn diagnostic_json<'a>(
+    diagnostic: &Diagnostic,
+    locate: impl Fn(Position) -> (Option<&'a Path>, Position),
+) -> String {
+    let located = |position| {
+        let (path, position) = locate(position);
+        let file = path
+            .map(|path| format!("\"file\": {}, ", json_string(&path.display().to_string())))
+            .unwrap_or_default();
+        format!("{}\"span\": {}", file, span_json(position))
+    };
+    let notes: Vec<String> = diagnostic
+        .notes
+        .iter()
+        .map(|(message, position)| {
+            format!(
+                "{{\"value\": {}, {}}}",
+                json_string(message),
+                located(*position)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"kind\": \"{}\", \"value\": {}, \"code\": {}, \"check\": {}, {}, \"notes\": [{}]}}",
+        diagnostic.severity,
+        json_string(&diagnostic.message),
+        diagnostic
+            .explanation
+            .or(diagnostic.code)
+            .map(json_string)
+            .unwrap_or_else(|| "null".to_string()),
+        diagnostic
+            .code
+            .map(json_string)
+            .unwrap_or_else(|| "null".to_string()),
+        located(diagnostic.position),
+        notes.join(", ")
+    )
+}
+
+/// A package that couldn't be loaded as lines of JSON: each syntax error's diagnostic, or
+/// else the error as a diagnostic without a span, its code and check `load`
+fn load_error_json(error: &LoadError) -> String {
+    match &error.kind {
+        LoadErrorKind::Parse(diagnostics) => {
+            let located = |position| (Some(error.path.as_path()), position);
+            let lines: Vec<String> = diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic_json(diagnostic, located))
+                .collect();
+            lines.join("\n")
         }
+        kind => format!(
+            "{{\"kind\": \"error\", \"value\": {}, \"code\": \"load\", \"check\": \"load\", \"file\": {}, \"notes\": []}}",
+            json_string(&kind.to_string()),
+            json_string(&error.path.display().to_string())
+        ),
+    }
+}
+
+/// A token as a line of JSON, like `{"kind": "Identifier", "value": "main", "span": {...}}`
+fn token_json(token: &Token) -> String {
+    format!(
+        "{{\"kind\": \"{:?}\", \"value\": {}, \"span\": {}}}",
+        token.kind.unwrap_or(TokenKind::BeforeStart),
+        json_string(&token.value),
+        span_json(token.position)
+    )
+}
+
+/// Where a token or diagnostic is, as `gor dump-ast --format=json` gives a node's start and
+/// end, which are on the same line
+fn span_json(position: Position) -> String {
+    format!(
+        "{{\"start\": {{\"line\": {}, \"column\": {}}}, \"end\": {{\"line\": {}, \"column\": {}}}}}",
+        position.line, position.column_start, position.line, position.column_end
+    )
+}
+
+/// Print an error with the file, line and column it's at, like `main.go:3:4: ...`
+fn report(program: &Program, position: Position, error: &impl std::fmt::Display) {
+    match program.locate(position) {
+        Some((path, position)) => eprintln!(
+            "{}:{}:{}: {}",
+            path.display(),
+            position.line,
+            position.column_start,
+            error
+        ),
+        None => eprintln!("{}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::command::Shell;
+    use crate::primitives::errors::cli::CliErrorKind;
+
+    fn parse(args: &[&str]) -> Result<Command, CliError> {
+        let args = std::iter::once("gor").chain(args.iter().copied());
+        Cli::parse_with_project(args.map(str::to_string).collect(), None).map(|cli| cli.command)
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            parse(&["run", "--engine", "vm", "main.go", "--trace", "-"]),
+            Ok(Command::Run(RunOptions {
+                trace: false,
+                engine: Engine::Vm,
+                path: "main.go".to_string(),
+                args: vec!["--trace".to_string(), "-".to_string()],
+            }))
+        );
+        // A program run as `gor run a.go -- x --y` has os.Args[1:] of [x --y]
+        assert_eq!(
+            parse(&["run", "a.go", "--", "x", "--y"]),
+            Ok(Command::Run(RunOptions {
+                trace: false,
+                engine: Engine::Tree,
+                path: "a.go".to_string(),
+                args: vec!["x".to_string(), "--y".to_string()],
+            }))
+        );
+        assert_eq!(
+            parse(&["run", "a.go", "x", "--", "--"]).map(|command| match command {
+                Command::Run(options) => options.args,
+                _ => Vec::new(),
+            }),
+            Ok(vec!["x".to_string(), "--".to_string(), "--".to_string()])
+        );
+        assert_eq!(
+            parse(&["build", "-o", "out.c", "--emit=c", "--opt-level=0", "./app"]),
+            Ok(Command::Build(BuildOptions {
+                emit: Emit::C,
+                level: 0,
+                output: Some(PathBuf::from("out.c")),
+                path: "./app".to_string(),
+            }))
+        );
+        assert_eq!(
+            parse(&["build", "--target=wasm32", "main.go"]),
+            Ok(Command::Build(BuildOptions {
+                emit: Emit::Wasm,
+                level: 1,
+                output: None,
+                path: "main.go".to_string(),
+            }))
+        );
+        assert_eq!(
+            parse(&["fmt", "--diff", "--local=example.com", "a.go", "b.go"]),
+            Ok(Command::Fmt(FmtOptions {
+                mode: FormatMode::Diff,
+                group_imports: false,
+                local_prefix: Some("example.com".to_string()),
+                remove_unused: false,
+                tests: false,
+                exclude: Vec::new(),
+                paths: vec!["a.go".to_string(), "b.go".to_string()],
+            }))
+        );
+        assert_eq!(
+            parse(&[
+                "check",
+                "--tests",
+                "--format=json",
+                "--max-errors",
+                "3",
+                "./cmd/...",
+                "main.go"
+            ]),
+            Ok(Command::Check(CheckOptions {
+                format: OutputFormat::Json,
+                max_errors: Some(3),
+                tests: true,
+                exclude: Vec::new(),
+                paths: vec!["./cmd/...".to_string(), "main.go".to_string()],
+            }))
+        );
+        assert_eq!(
+            parse(&["dump-tokens", "--format", "json", "main.go"]),
+            Ok(Command::DumpTokens {
+                format: OutputFormat::Json,
+                path: "main.go".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&["symbols", "--format=json", "main.go"]),
+            Ok(Command::Symbols {
+                format: OutputFormat::Json,
+                path: "main.go".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&["strip", "--minify", "main.go"]),
+            Ok(Command::Strip {
+                minify: true,
+                path: "main.go".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&["diff-ast", "old.go", "new.go"]),
+            Ok(Command::DiffAst {
+                old: "old.go".to_string(),
+                new: "new.go".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&[
+                "bench-compile",
+                "--json",
+                "--runs=3",
+                "--exclude=gen,vendor",
+                "./..."
+            ]),
+            Ok(Command::BenchCompile {
+                json: true,
+                runs: 3,
+                exclude: vec![PathBuf::from("gen"), PathBuf::from("vendor")],
+                path: "./...".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&["doc", ".", "Circle.Area"]),
+            Ok(Command::Doc {
+                path: ".".to_string(),
+                symbol: Some("Circle.Area".to_string()),
+            })
+        );
+        assert_eq!(
+            parse(&["graph", "--calls", "--format", "json", "."]),
+            Ok(Command::Graph {
+                format: GraphFormat::Json,
+                calls: true,
+                path: ".".to_string(),
+            })
+        );
+        assert_eq!(
+            parse(&["dump-ast", "--format=sexpr", "main.go"]),
+            Ok(Command::DumpAst {
+                format: DumpFormat::Sexpr,
+                path: "main.go".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&["internal", "selftest-fmt", "testdata"]),
+            Ok(Command::SelftestFmt {
+                directory: "testdata".to_string()
+            })
+        );
+        assert_eq!(parse(&["internal", "panic"]), Ok(Command::Panic));
+        assert_eq!(
+            parse(&["explain", "E0101"]),
+            Ok(Command::Explain(Some("E0101".to_string())))
+        );
+        assert_eq!(
+            parse(&["completions", "zsh"]),
+            Ok(Command::Completions(Shell::Zsh))
+        );
+        assert_eq!(
+            parse(&["completions", "tcsh"]),
+            Err(CliError::new(
+                CliErrorKind::UnexpectedArgument("tcsh".to_string()),
+                Some("completions".to_string())
+            ))
+        );
+        assert_eq!(
+            parse(&["check", "-"]),
+            Ok(Command::Check(CheckOptions {
+                format: OutputFormat::Text,
+                max_errors: None,
+                tests: false,
+                exclude: Vec::new(),
+                paths: vec!["-".to_string()]
+            }))
+        );
+        assert_eq!(
+            parse(&[
+                "vet",
+                "--enable=shadow",
+                "--disable",
+                "printf,assign",
+                "./..."
+            ]),
+            Ok(Command::Vet(VetOptions {
+                format: OutputFormat::Text,
+                tests: false,
+                enable: vec!["shadow".to_string()],
+                disable: vec!["printf".to_string(), "assign".to_string()],
+                exclude: Vec::new(),
+                paths: vec!["./...".to_string()],
+            }))
+        );
+        assert_eq!(
+            parse(&["test", "-v", "--run", "Add"]),
+            Ok(Command::Test(TestOptions {
+                verbose: true,
+                run: Some("Add".to_string()),
+                exclude: Vec::new(),
+                paths: vec![".".to_string()],
+            }))
+        );
+        assert_eq!(parse(&["lsp"]), Ok(Command::Lsp));
+        assert_eq!(
+            parse(&["rename", "--diff", "C:/app/main.go:4:1", "total"]),
+            Ok(Command::Rename {
+                diff: true,
+                path: "C:/app/main.go".to_string(),
+                line: 4,
+                column: 1,
+                name: "total".to_string(),
+            })
+        );
+        assert_eq!(parse(&["--version"]), Ok(Command::Version));
+    }
+
+    #[test]
+    fn parse_log_level() {
+        let cli = |args: &[&str]| {
+            let args = std::iter::once("gor").chain(args.iter().copied());
+            Cli::parse_with_project(args.map(str::to_string).collect(), None)
+        };
+        let level = |args: &[&str]| cli(args).map(|cli| cli.log_level);
+        assert_eq!(level(&["check", "x.go"]), Ok(LevelFilter::Warn));
+        assert_eq!(level(&["-v", "test", "-v"]), Ok(LevelFilter::Debug));
+        assert_eq!(
+            level(&["--log-level", "trace", "check", "x.go"]),
+            Ok(LevelFilter::Trace)
+        );
+        assert_eq!(
+            cli(&["--verbose", "--version"]).map(|cli| cli.command),
+            Ok(Command::Version)
+        );
+        assert_eq!(
+            level(&["--log-level=loud", "check"]),
+            Err(CliError::new(
+                CliErrorKind::InvalidValue {
+                    flag: "--log-level".to_string(),
+                    value: "loud".to_string()
+                },
+                None
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_with_project() {
+        let project = Project::parse(
+            Path::new("/app"),
+            "log-level = \"info\"\nexclude = [\"vendor\"]\n\n[check]\nformat = \"json\"\ntests = true\n",
+        )
+        .unwrap();
+        let cli = |args: &[&str]| {
+            let args = std::iter::once("gor").chain(args.iter().copied());
+            Cli::parse_with_project(args.map(str::to_string).collect(), Some(&project)).unwrap()
+        };
+        // Flags given on the command line come after the project's, so override them
+        let check = cli(&["check", "--format=text", "--exclude=gen", "./..."]);
+        assert_eq!(check.log_level, LevelFilter::Info);
+        assert_eq!(
+            check.command,
+            Command::Check(CheckOptions {
+                format: OutputFormat::Text,
+                max_errors: None,
+                tests: true,
+                exclude: vec![PathBuf::from("/app/vendor"), PathBuf::from("gen")],
+                paths: vec!["./...".to_string()],
+            })
+        );
+        let run = cli(&["-v", "run", "main.go"]);
+        assert_eq!(run.log_level, LevelFilter::Debug);
+        assert!(matches!(run.command, Command::Run(_)));
+        assert_eq!(cli(&["--version"]).command, Command::Version);
+
+        // A malformed gor.toml is only an error for the commands that read it
+        let parse = |args: &[&str]| {
+            let args = std::iter::once("gor").chain(args.iter().copied());
+            Cli::parse_finding_project(args.map(str::to_string).collect(), || {
+                Project::parse(Path::new("/app"), "[nonsense]\n").map(Some)
+            })
+            .map(|cli| cli.command)
+        };
+        assert_eq!(parse(&["--version"]), Ok(Command::Version));
+        assert_eq!(
+            parse(&["help", "vet"]),
+            Ok(Command::Help(Some("vet".to_string())))
+        );
+        assert_eq!(
+            parse(&["explain", "E0101"]),
+            Ok(Command::Explain(Some("E0101".to_string())))
+        );
+        assert!(matches!(
+            parse(&["check", "main.go"]),
+            Err(CliError {
+                kind: CliErrorKind::Config(_),
+                ..
+            })
+        ));
+
+        // and a mistake in a command's table only for that command
+        let parse = |args: &[&str]| {
+            let args = std::iter::once("gor").chain(args.iter().copied());
+            Cli::parse_finding_project(args.map(str::to_string).collect(), || {
+                Project::parse(Path::new("/app"), "[vet]\ntests = \"yes\"\n").map(Some)
+            })
+            .map(|cli| cli.command)
+        };
+        assert!(matches!(parse(&["fmt", "main.go"]), Ok(Command::Fmt(_))));
+        assert_eq!(
+            parse(&["vet", "main.go"]).unwrap_err().kind.to_string(),
+            "/app/gor.toml:2: invalid value \"yes\" for tests"
+        );
+    }
+
+    #[test]
+    fn parse_help() {
+        assert_eq!(parse(&["--help"]), Ok(Command::Help(None)));
+        assert_eq!(
+            parse(&["help", "build"]),
+            Ok(Command::Help(Some("build".to_string())))
+        );
+        assert_eq!(
+            parse(&["check", "-h"]),
+            Ok(Command::Help(Some("check".to_string())))
+        );
+        assert!(usage(None).unwrap().contains("\n  dump-bytecode  "));
+        assert!(usage(Some("fmt")).unwrap().contains("\n  -w  "));
+        assert!(
+            usage(Some("build"))
+                .unwrap()
+                .contains("\n  --target=wasm32  ")
+        );
+        assert!(usage(Some("vet")).unwrap().contains("\n  unreachable  "));
+        assert_eq!(usage(Some("nope")), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |args: &[&str]| {
+            let error = parse(args).unwrap_err();
+            (error.command, error.kind)
+        };
+        let build = Some("build".to_string());
+        assert_eq!(error(&[]), (None, CliErrorKind::MissingCommand));
+        assert_eq!(
+            error(&["bulid"]),
+            (None, CliErrorKind::UnknownCommand("bulid".to_string()))
+        );
+        assert_eq!(
+            error(&["build", "--fast", "main.go"]),
+            (
+                build.clone(),
+                CliErrorKind::UnknownFlag("--fast".to_string())
+            )
+        );
+        assert_eq!(
+            error(&["build", "main.go", "-o"]),
+            (build.clone(), CliErrorKind::MissingValue("-o".to_string()))
+        );
+        assert_eq!(
+            error(&["build", "--emit=jar", "main.go"]),
+            (
+                build.clone(),
+                CliErrorKind::InvalidValue {
+                    flag: "--emit".to_string(),
+                    value: "jar".to_string()
+                }
+            )
+        );
+        assert_eq!(
+            error(&["build", "a.go", "b.go"]),
+            (build, CliErrorKind::UnexpectedArgument("b.go".to_string()))
+        );
+        assert_eq!(
+            error(&["rename", "main.go:4", "total"]).1,
+            CliErrorKind::InvalidPosition("main.go:4".to_string())
+        );
+        assert_eq!(
+            error(&["rename", "main.go:4:1"]).1,
+            CliErrorKind::MissingArgument("<file.go>:<line>:<column> <name>".to_string())
+        );
+        assert_eq!(
+            error(&["fmt", "-w", "--check", "main.go"]),
+            (
+                Some("fmt".to_string()),
+                CliErrorKind::ConflictingFlags("-w".to_string(), "--check".to_string())
+            )
+        );
+        assert_eq!(
+            error(&["dump-tokens", "--format=sarif", "main.go"]).1,
+            CliErrorKind::InvalidValue {
+                flag: "--format".to_string(),
+                value: "sarif".to_string()
+            }
+        );
+        assert_eq!(
+            error(&["vet", "--disable=printf,nope", "main.go"]).1,
+            CliErrorKind::InvalidValue {
+                flag: "--disable".to_string(),
+                value: "nope".to_string()
+            }
+        );
+        assert_eq!(
+            error(&["repl", "--trace=yes"]).1,
+            CliErrorKind::UnknownFlag("--trace=yes".to_string())
+        );
+        assert_eq!(
+            parse(&["check"]).unwrap_err().to_string(),
+            "gor check: missing <file.go | package directory | pattern>\nRun 'gor help check' for usage."
+        );
+    }
+
+    #[test]
+    fn check_packages_in_order() {
+        let module = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/testfiles/module");
+        let unmatched = LoadError::new(LoadErrorKind::NoMatchingPackages, PathBuf::from("x/..."));
+        let packages = [
+            Ok(module.join("shapes")),
+            Err(unmatched),
+            Ok(module.join("mixed")),
+            Ok(module.join("cmd/app")),
+        ];
+        let mut reported = Vec::new();
+        check_packages(&packages, &LoaderConfig::default(), &diagnose, |checked| {
+            reported.push(match checked {
+                Ok((program, diagnostics)) => {
+                    format!("{}: {}", program.file.package_name, diagnostics.len())
+                }
+                Err(error) => error.kind.to_string(),
+            })
+        });
+        assert_eq!(
+            reported,
+            [
+                "shapes: 0",
+                "matched no packages",
+                "found packages a (a.go) and b (b.go)",
+                "main: 0"
+            ]
+        );
+    }
+
+    #[test]
+    fn json_lines() {
+        let position = Position::new(6, 13, 14);
+        let diagnostic = Diagnostic::error("undefined: \"y\"".to_string(), position)
+            .with_code("names")
+            .with_explanation("E0101")
+            .with_note("declared here".to_string(), Position::new(2, 1, 2));
+        let json = diagnostic_json(&diagnostic, |position| {
+            (Some(Path::new("main.go")), position)
+        });
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"kind": "error", "value": "undefined: \"y\"", "code": "E0101", "#,
+                r#""check": "names", "file": "main.go", "#,
+                r#""span": {"start": {"line": 6, "column": 13}, "end": {"line": 6, "column": 14}}, "#,
+                r#""notes": [{"value": "declared here", "file": "main.go", "span": {"start": "#,
+                r#"{"line": 2, "column": 1}, "end": {"line": 2, "column": 2}}}]}"#
+            )
+        );
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+
+        let error = LoadError::new(LoadErrorKind::NoGoFiles, PathBuf::from("app"));
+        let value: serde_json::Value = serde_json::from_str(&load_error_json(&error)).unwrap();
+        assert_eq!(value["value"], "no Go files");
+        assert_eq!(value["code"], "load");
+
+        let mut lexer = Lexer::new("x := \"\\n\"");
+        let tokens: Vec<String> = std::iter::from_fn(|| {
+            let token = lexer.next_token();
+            (token.kind != Some(TokenKind::EOF)).then(|| token_json(&token))
+        })
+        .collect();
+        assert_eq!(
+            tokens[2],
+            r#"{"kind": "StringLiteral", "value": "\"\\n\"", "span": {"start": {"line": 1, "column": 5}, "end": {"line": 1, "column": 9}}}"#
+        );
     }
 }
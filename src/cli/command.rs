@@ -0,0 +1,1332 @@
+use std::path::PathBuf;
+
+use log::LevelFilter;
+
+use crate::ast::dump::DumpFormat;
+use crate::primitives::errors::cli::{CliError, CliErrorKind};
+use crate::vet::vet::Vet;
+
+/// What `gor run` runs a program with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    Tree,
+    Vm,
+    Jit,
+}
+
+/// What `gor build` writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Emit {
+    Bytecode,
+    Wasm,
+    C,
+}
+
+/// What `gor fmt` does with a file it's formatted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatMode {
+    Print,
+    Write,
+    Check,
+    Diff,
+}
+
+/// How `gor dump-tokens`, `gor symbols`, `gor check` and `gor vet` print what they find
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    // As the go command does, a line per token or diagnostic
+    Text,
+    // A JSON object per line, for editors and scripts
+    Json,
+    // A SARIF log, for code scanning services, which only `gor check` and `gor vet` print
+    Sarif,
+}
+
+/// How `gor graph` prints a graph
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    // In Graphviz's DOT language, for `dot -Tsvg`
+    Dot,
+    Json,
+}
+
+/// A shell `gor completions` writes a completion script for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOptions {
+    pub trace: bool,
+    pub engine: Engine,
+    pub path: String,
+    /// The arguments after the path, which are the program's own
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckOptions {
+    pub format: OutputFormat,
+    /// How many errors to print before stopping
+    pub max_errors: Option<usize>,
+    /// Check each package with its `_test.go` files
+    pub tests: bool,
+    /// Files and directories left out of those the paths name
+    pub exclude: Vec<PathBuf>,
+    /// Files, package directories and patterns like `./...`
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VetOptions {
+    pub format: OutputFormat,
+    /// Vet each package with its `_test.go` files
+    pub tests: bool,
+    /// Rules to run even if they're off by default
+    pub enable: Vec<String>,
+    /// Rules not to run
+    pub disable: Vec<String>,
+    /// Files and directories left out of those the paths name
+    pub exclude: Vec<PathBuf>,
+    /// Files, package directories and patterns like `./...`
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestOptions {
+    /// Print what every test logged, not just what failing ones did
+    pub verbose: bool,
+    /// Only run the tests whose names contain this
+    pub run: Option<String>,
+    /// Files and directories left out of those the paths name
+    pub exclude: Vec<PathBuf>,
+    /// Package directories and patterns like `./...`
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildOptions {
+    pub emit: Emit,
+    pub level: u8,
+    pub output: Option<PathBuf>,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FmtOptions {
+    pub mode: FormatMode,
+    pub group_imports: bool,
+    pub local_prefix: Option<String>,
+    pub remove_unused: bool,
+    /// Format a directory's `_test.go` files with the rest
+    pub tests: bool,
+    /// Files and directories left out of those the paths name
+    pub exclude: Vec<PathBuf>,
+    /// Files, directories and patterns like `./...`
+    pub paths: Vec<String>,
+}
+
+/// A command gor was run with, its flags and arguments parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    DumpTokens {
+        format: OutputFormat,
+        path: String,
+    },
+    DumpAst {
+        format: DumpFormat,
+        path: String,
+    },
+    Symbols {
+        format: OutputFormat,
+        path: String,
+    },
+    Strip {
+        /// Print it on a line with as little whitespace as it needs
+        minify: bool,
+        path: String,
+    },
+    Doc {
+        path: String,
+        /// A package level name or `Type.Method`, to print only its documentation
+        symbol: Option<String>,
+    },
+    Graph {
+        format: GraphFormat,
+        /// Print the functions and what they call, rather than the packages and what they
+        /// import
+        calls: bool,
+        path: String,
+    },
+    Run(RunOptions),
+    Check(CheckOptions),
+    Vet(VetOptions),
+    Test(TestOptions),
+    DiffAst {
+        old: String,
+        new: String,
+    },
+    BenchCompile {
+        json: bool,
+        /// How many times each file is timed, of which the fastest is kept
+        runs: usize,
+        /// Files and directories left out of those the path names
+        exclude: Vec<PathBuf>,
+        /// A package directory, a pattern like `./...` or a file
+        path: String,
+    },
+    DumpBytecode {
+        path: String,
+    },
+    DumpIr {
+        level: u8,
+        path: String,
+    },
+    Build(BuildOptions),
+    Repl,
+    Lsp,
+    Rename {
+        /// Print a diff of the changes rather than making them
+        diff: bool,
+        path: String,
+        line: usize,
+        column: usize,
+        name: String,
+    },
+    Fmt(FmtOptions),
+    SelftestFmt {
+        directory: String,
+    },
+    /// Fail as a bug in gor would, to check how gor reports one
+    Panic,
+    Completions(Shell),
+    /// Print what the diagnostic code means, or with None, list every code
+    Explain(Option<String>),
+    /// Print the usage of the command with the name, or with None, gor's own
+    Help(Option<String>),
+    Version,
+}
+
+/// A command as its help describes it
+pub(crate) struct Spec {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub summary: &'static str,
+    /// Each flag and what it does
+    pub flags: &'static [(&'static str, &'static str)],
+}
+
+/// gor's own flags, which go before the command's name
+pub(crate) const GLOBAL_FLAGS: &[(&str, &str)] = &[
+    ("-v, --verbose", "log what gor does, at the debug level"),
+    (
+        "--log-level=off|error|warn|info|debug|trace",
+        "log at the level and those above it, warn by default",
+    ),
+];
+
+pub(crate) const COMMANDS: &[Spec] = &[
+    Spec {
+        name: "run",
+        usage: "gor run [--trace] [--engine=tree|vm|jit] <file.go | file.gorc | package directory> [arguments...]",
+        summary: "Type-check and run a program, passing it the arguments after its path and any --",
+        flags: &[
+            (
+                "--trace",
+                "write each statement to standard error as it's run",
+            ),
+            (
+                "--engine=tree|vm|jit",
+                "interpret the program, the default, or run it on the VM, with jit compiling hot functions; only tree passes it arguments",
+            ),
+        ],
+    },
+    Spec {
+        name: "check",
+        usage: "gor check [--format=text|json|sarif] [--max-errors=<n>] [--tests] [--exclude=<path>,...] <file.go | package directory | pattern>...",
+        summary: "Parse and type-check programs and packages without running them",
+        flags: &[
+            (
+                "--format=text|json|sarif",
+                "print each diagnostic as text to standard error, the default, or as a line of JSON to standard output, or print a SARIF log of them all",
+            ),
+            (
+                "--max-errors=<n>",
+                "stop printing diagnostics after n errors, which are still counted",
+            ),
+            ("--tests", "check each package with its _test.go files"),
+            (
+                "--exclude=<path>,...",
+                "leave out the files and directories when listing a directory's files or matching a pattern",
+            ),
+        ],
+    },
+    Spec {
+        name: "vet",
+        usage: "gor vet [--format=text|json|sarif] [--enable=<rule>,...] [--disable=<rule>,...] [--tests] [--exclude=<path>,...] <file.go | package directory | pattern>...",
+        summary: "Report code that compiles but is probably wrong",
+        flags: &[
+            (
+                "--format=text|json|sarif",
+                "print each diagnostic as gor check does",
+            ),
+            ("--enable=<rule>,...", "run rules that are off by default"),
+            ("--disable=<rule>,...", "don't run the rules"),
+            ("--tests", "vet each package with its _test.go files"),
+            (
+                "--exclude=<path>,...",
+                "leave out the files and directories when listing a directory's files or matching a pattern",
+            ),
+        ],
+    },
+    Spec {
+        name: "test",
+        usage: "gor test [-v] [--run=<text>] [--exclude=<path>,...] [package directory | pattern]...",
+        summary: "Run packages' TestXxx functions, by default the current directory's",
+        flags: &[
+            (
+                "-v",
+                "print what every test logged, not just what failing ones did",
+            ),
+            (
+                "--run=<text>",
+                "only run the tests whose names contain the text",
+            ),
+            (
+                "--exclude=<path>,...",
+                "leave out the files and directories when listing a directory's files or matching a pattern",
+            ),
+        ],
+    },
+    Spec {
+        name: "build",
+        usage: "gor build [--target=wasm32] [--emit=bytecode|wasm|c] [--opt-level=0|1] [-o <output>] <file.go | package directory>",
+        summary: "Compile a program and write its bytecode, a WebAssembly module or a C program",
+        flags: &[
+            (
+                "--target=wasm32",
+                "the machine to compile for, wasm32 writing a WebAssembly module as --emit=wasm does",
+            ),
+            (
+                "--emit=bytecode|wasm|c",
+                "what to write, bytecode by default",
+            ),
+            (
+                "--opt-level=0|1",
+                "optimize the IR wasm and C are compiled from, 1 by default",
+            ),
+            (
+                "-o <output>",
+                "where to write it, by default the program's name and extension",
+            ),
+        ],
+    },
+    Spec {
+        name: "fmt",
+        usage: "gor fmt [-w | --check | --diff] [--group-imports [--local=<prefix>]] [--remove-unused-imports] [--tests] [--exclude=<path>,...] <file.go | directory | pattern>...",
+        summary: "Format files as gofmt does, printing the result",
+        flags: &[
+            ("-w", "write the result back to each file that changed"),
+            (
+                "--check",
+                "print a diff of the changes, failing if there are any",
+            ),
+            ("--diff", "print a diff of the changes"),
+            ("--group-imports", "group imports as goimports does"),
+            (
+                "--local=<prefix>",
+                "the import path prefix grouped last, by default the module's",
+            ),
+            (
+                "--remove-unused-imports",
+                "leave out the imports the file doesn't use",
+            ),
+            (
+                "--tests",
+                "format a directory's _test.go files with the rest",
+            ),
+            (
+                "--exclude=<path>,...",
+                "leave out the files and directories when listing a directory's files or matching a pattern",
+            ),
+        ],
+    },
+    Spec {
+        name: "strip",
+        usage: "gor strip [--minify] <file.go>",
+        summary: "Print a file without its comments",
+        flags: &[(
+            "--minify",
+            "print it on a line, with only the spaces and semicolons it needs",
+        )],
+    },
+    Spec {
+        name: "symbols",
+        usage: "gor symbols [--format=text|json] <file.go>",
+        summary: "Print an outline of a file's imports, types, functions and variables",
+        flags: &[(
+            "--format=text|json",
+            "print each with its line number, the default, or the outline as JSON",
+        )],
+    },
+    Spec {
+        name: "doc",
+        usage: "gor doc <file.go | package directory> [symbol]",
+        summary: "Print the documentation of a package or one of its symbols",
+        flags: &[],
+    },
+    Spec {
+        name: "graph",
+        usage: "gor graph [--format=dot|json] [--calls] <file.go | package directory>",
+        summary: "Print a program's package import graph, or its call graph, for Graphviz",
+        flags: &[
+            (
+                "--format=dot|json",
+                "print it in Graphviz's DOT language, the default, or as JSON",
+            ),
+            (
+                "--calls",
+                "print the functions and those they call rather than the packages",
+            ),
+        ],
+    },
+    Spec {
+        name: "repl",
+        usage: "gor repl",
+        summary: "Read and run statements and expressions one at a time",
+        flags: &[],
+    },
+    Spec {
+        name: "lsp",
+        usage: "gor lsp",
+        summary: "Serve editors the Language Server Protocol on standard input and output",
+        flags: &[],
+    },
+    Spec {
+        name: "rename",
+        usage: "gor rename [--diff] <file.go>:<line>:<column> <name>",
+        summary: "Rename the name at a position, as gor check reports positions, everywhere its package refers to it",
+        flags: &[(
+            "--diff",
+            "print a diff of the changes rather than making them",
+        )],
+    },
+    Spec {
+        name: "dump-tokens",
+        usage: "gor dump-tokens [--format=text|json] <file.go>",
+        summary: "Print a file's tokens",
+        flags: &[(
+            "--format=text|json",
+            "print each token and error as text, the default, or as a line of JSON",
+        )],
+    },
+    Spec {
+        name: "dump-ast",
+        usage: "gor dump-ast [--format=tree|json|sexpr] <file.go>",
+        summary: "Print a file's syntax tree",
+        flags: &[(
+            "--format=tree|json|sexpr",
+            "print it as an outline, the default, JSON, its errors as lines of JSON too, or an S-expression",
+        )],
+    },
+    Spec {
+        name: "diff-ast",
+        usage: "gor diff-ast <old.go> <new.go>",
+        summary: "Print the declarations a file adds, removes or changes from another",
+        flags: &[],
+    },
+    Spec {
+        name: "dump-bytecode",
+        usage: "gor dump-bytecode <file.go | package directory>",
+        summary: "Print a program's bytecode",
+        flags: &[],
+    },
+    Spec {
+        name: "dump-ir",
+        usage: "gor dump-ir [--opt-level=0|1] <file.go | package directory>",
+        summary: "Print a program lowered to the IR",
+        flags: &[("--opt-level=0|1", "optimize the IR first, 0 by default")],
+    },
+    Spec {
+        name: "bench-compile",
+        usage: "gor bench-compile [--json] [--runs=<n>] [--exclude=<path>,...] <directory | pattern | file.go>",
+        summary: "Time lexing, parsing and checking each file and in total",
+        flags: &[
+            ("--json", "print the timings as JSON, in nanoseconds"),
+            (
+                "--runs=<n>",
+                "time each file n times and keep the fastest, 5 by default",
+            ),
+            (
+                "--exclude=<path>,...",
+                "leave out the files and directories when listing a directory's files or matching a pattern",
+            ),
+        ],
+    },
+    Spec {
+        name: "internal",
+        usage: "gor internal selftest-fmt <directory>",
+        summary: "Commands for working on gor rather than with it",
+        flags: &[],
+    },
+    Spec {
+        name: "explain",
+        usage: "gor explain [code]",
+        summary: "Print what a diagnostic's code, like E0101, means, or list the codes",
+        flags: &[],
+    },
+    Spec {
+        name: "completions",
+        usage: "gor completions <bash|zsh|fish>",
+        summary: "Print a script that completes gor's commands and flags in the shell",
+        flags: &[],
+    },
+    Spec {
+        name: "help",
+        usage: "gor help [command]",
+        summary: "Print gor's usage, or a command's",
+        flags: &[],
+    },
+    Spec {
+        name: "version",
+        usage: "gor version",
+        summary: "Print gor's version",
+        flags: &[],
+    },
+];
+
+impl Command {
+    /// Parse the arguments gor was run with, after its own name. `--help` or `-h` anywhere a
+    /// command's flags are parsed asks for its usage.
+    pub fn parse(args: Vec<String>) -> Result<Command, CliError> {
+        let mut args = args.into_iter();
+        let Some(name) = args.next() else {
+            return Err(CliError::new(CliErrorKind::MissingCommand, None));
+        };
+        let Some(spec) = COMMANDS.iter().find(|spec| spec.name == name) else {
+            return match name.as_str() {
+                "--help" | "-h" => Ok(Command::Help(None)),
+                "--version" => Ok(Command::Version),
+                _ => Err(CliError::new(CliErrorKind::UnknownCommand(name), None)),
+            };
+        };
+        let mut arguments = Arguments { spec, args };
+        match spec.name {
+            "run" => arguments.run(),
+            "check" => arguments.check(),
+            "vet" => arguments.vet(),
+            "test" => arguments.test(),
+            "build" => arguments.build(),
+            "fmt" => arguments.fmt(),
+            "strip" => arguments.strip(),
+            "repl" => arguments.none(Command::Repl),
+            "lsp" => arguments.none(Command::Lsp),
+            "rename" => arguments.rename(),
+            "symbols" => arguments.text_or_json(|format, path| Command::Symbols { format, path }),
+            "doc" => arguments.doc(),
+            "graph" => arguments.graph(),
+            "dump-tokens" => {
+                arguments.text_or_json(|format, path| Command::DumpTokens { format, path })
+            }
+            "dump-ast" => arguments.dump_ast(),
+            "diff-ast" => arguments.diff_ast(),
+            "dump-bytecode" => arguments.single("<file.go | package directory>", |path| {
+                Command::DumpBytecode { path }
+            }),
+            "dump-ir" => arguments.dump_ir(),
+            "bench-compile" => arguments.bench_compile(),
+            "internal" => arguments.internal(),
+            "completions" => arguments.completions(),
+            "explain" => arguments.explain(),
+            "help" => arguments.help_command(),
+            _ => arguments.none(Command::Version),
+        }
+    }
+
+    /// Whether a project's `gor.toml` can change what the command does. Those that only
+    /// print about gor itself don't read it, so run even where it's malformed.
+    pub fn uses_project(&self) -> bool {
+        !matches!(
+            self,
+            Command::Help(_) | Command::Version | Command::Explain(_) | Command::Completions(_)
+        )
+    }
+}
+
+/// Parse gor's own flags, those before the command's name, returning the level to log at,
+/// warnings and errors by default, and the arguments after the flags
+pub fn parse_log_level(args: Vec<String>) -> Result<(LevelFilter, Vec<String>), CliError> {
+    let mut level = LevelFilter::Warn;
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next_if(|arg| arg.starts_with('-')) {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        match (flag.as_str(), value) {
+            ("-v" | "--verbose", None) => level = LevelFilter::Debug,
+            ("--log-level", value) => {
+                let Some(value) = value.or_else(|| args.next()) else {
+                    return Err(CliError::new(CliErrorKind::MissingValue(flag), None));
+                };
+                level = value
+                    .parse()
+                    .map_err(|_| CliError::new(CliErrorKind::InvalidValue { flag, value }, None))?;
+            }
+            // gor's own `--help` and `--version`, which Command::parse takes
+            (_, None) => {
+                let mut rest = vec![flag];
+                rest.extend(args);
+                return Ok((level, rest));
+            }
+            (_, Some(value)) => {
+                let flag = format!("{}={}", flag, value);
+                return Err(CliError::new(CliErrorKind::UnknownFlag(flag), None));
+            }
+        }
+    }
+    Ok((level, args.collect()))
+}
+
+/// The usage of the command with the name, or with None, gor's, as `--help` prints it. A
+/// name that isn't a command's gives None.
+pub fn usage(command: Option<&str>) -> Option<String> {
+    let Some(name) = command else {
+        let width = COMMANDS
+            .iter()
+            .map(|spec| spec.name.len())
+            .max()
+            .unwrap_or(0);
+        let mut usage = String::from("Usage: gor [flags] <command> [arguments]\n\nCommands:\n");
+        for spec in COMMANDS {
+            usage.push_str(&format!("  {:width$}  {}\n", spec.name, spec.summary));
+        }
+        usage.push_str("\nFlags:\n");
+        push_flags(&mut usage, GLOBAL_FLAGS);
+        usage.push_str("\nA file given as - is read from standard input. A directory then /...,\n");
+        usage.push_str("like ./..., is every package in or under it, but those in testdata.\n");
+        usage
+            .push_str("\nA gor.toml in the current directory or one above it gives defaults for\n");
+        usage.push_str("gor's flags, and in a table like [vet] a command's, which those given\n");
+        usage.push_str("override.\n");
+        usage.push_str("\nExit status is 0 on success, 1 if problems were found, 2 for a bad\n");
+        usage.push_str("command line and 3 for an internal error. gor run exits with the\n");
+        usage.push_str("program's status.\n\n");
+        usage.push_str("Run 'gor help <command>' for a command's usage.\n");
+        return Some(usage);
+    };
+    let spec = COMMANDS.iter().find(|spec| spec.name == name)?;
+    let mut usage = format!("Usage: {}\n\n{}.\n", spec.usage, spec.summary);
+    if !spec.flags.is_empty() {
+        usage.push_str("\nFlags:\n");
+        push_flags(&mut usage, spec.flags);
+    }
+    if spec.name == "vet" {
+        let vet = Vet::default();
+        let width = vet.rules().map(|rule| rule.name().len()).max().unwrap_or(0);
+        usage.push_str("\nRules:\n");
+        for rule in vet.rules() {
+            usage.push_str(&format!("  {:width$}  {}\n", rule.name(), rule.summary()));
+        }
+    }
+    Some(usage)
+}
+
+/// Each flag on a line, its description lined up after it
+fn push_flags(usage: &mut String, flags: &[(&str, &str)]) {
+    let width = flags.iter().map(|(flag, _)| flag.len()).max().unwrap_or(0);
+    for (flag, description) in flags {
+        usage.push_str(&format!("  {:width$}  {}\n", flag, description));
+    }
+}
+
+/// An argument after a command's name: a flag, split from its value at an `=`, or anything
+/// else
+enum Argument {
+    Flag(String, Option<String>),
+    Positional(String),
+}
+
+/// The arguments after a command's name, read an argument at a time
+struct Arguments {
+    spec: &'static Spec,
+    args: std::vec::IntoIter<String>,
+}
+
+impl Arguments {
+    fn next(&mut self) -> Option<Argument> {
+        let arg = self.args.next()?;
+        // A lone `-` isn't a flag, but standard input's name
+        if arg.len() < 2 || !arg.starts_with('-') {
+            return Some(Argument::Positional(arg));
+        }
+        match arg.split_once('=') {
+            Some((flag, value)) => Some(Argument::Flag(flag.to_string(), Some(value.to_string()))),
+            None => Some(Argument::Flag(arg, None)),
+        }
+    }
+
+    fn error(&self, kind: CliErrorKind) -> CliError {
+        CliError::new(kind, Some(self.spec.name.to_string()))
+    }
+
+    /// What a flag the command doesn't take means: its usage, if it's `--help`, or an error
+    fn other(&self, flag: String, value: Option<String>) -> Result<Command, CliError> {
+        match (flag.as_str(), value) {
+            ("--help" | "-h", None) => Ok(Command::Help(Some(self.spec.name.to_string()))),
+            (_, Some(value)) => {
+                Err(self.error(CliErrorKind::UnknownFlag(format!("{}={}", flag, value))))
+            }
+            (_, None) => Err(self.error(CliErrorKind::UnknownFlag(flag))),
+        }
+    }
+
+    /// A flag's value, after its `=` or as the next argument
+    fn value(&mut self, flag: &str, value: Option<String>) -> Result<String, CliError> {
+        value
+            .or_else(|| self.args.next())
+            .ok_or_else(|| self.error(CliErrorKind::MissingValue(flag.to_string())))
+    }
+
+    fn invalid(&self, flag: &str, value: String) -> CliError {
+        self.error(CliErrorKind::InvalidValue {
+            flag: flag.to_string(),
+            value,
+        })
+    }
+
+    /// An optimization level, 0 or 1
+    fn level(&mut self, flag: &str, value: Option<String>) -> Result<u8, CliError> {
+        let value = self.value(flag, value)?;
+        match value.parse::<u8>() {
+            Ok(level) if level <= 1 => Ok(level),
+            _ => Err(self.invalid(flag, value)),
+        }
+    }
+
+    /// `--format`'s value for a command that prints text or JSON
+    fn output_format(
+        &mut self,
+        flag: &str,
+        value: Option<String>,
+    ) -> Result<OutputFormat, CliError> {
+        let value = self.value(flag, value)?;
+        match value.as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "sarif" => Ok(OutputFormat::Sarif),
+            _ => Err(self.invalid(flag, value)),
+        }
+    }
+
+    /// Parse the arguments of a command that takes only a path, like `gor dump-bytecode`,
+    /// named as its usage names it, then make the command with it
+    fn single(
+        &mut self,
+        name: &str,
+        command: impl FnOnce(String) -> Command,
+    ) -> Result<Command, CliError> {
+        let mut path = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if path.is_none() => path = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        match path {
+            Some(path) => Ok(command(path)),
+            None => Err(self.error(CliErrorKind::MissingArgument(name.to_string()))),
+        }
+    }
+
+    /// Parse the arguments of a command that takes none
+    fn none(&mut self, command: Command) -> Result<Command, CliError> {
+        match self.next() {
+            None => Ok(command),
+            Some(Argument::Flag(flag, value)) => self.other(flag, value),
+            Some(Argument::Positional(arg)) => {
+                Err(self.error(CliErrorKind::UnexpectedArgument(arg)))
+            }
+        }
+    }
+
+    fn run(&mut self) -> Result<Command, CliError> {
+        let mut trace = false;
+        let mut engine = Engine::Tree;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) => match (flag.as_str(), value) {
+                    ("--trace", None) => trace = true,
+                    ("--engine", value) => {
+                        let value = self.value(&flag, value)?;
+                        engine = match value.as_str() {
+                            "tree" => Engine::Tree,
+                            "vm" => Engine::Vm,
+                            "jit" => Engine::Jit,
+                            _ => return Err(self.invalid(&flag, value)),
+                        };
+                    }
+                    (_, value) => return self.other(flag, value),
+                },
+                // Everything after the path is the program's, flags included, but for a
+                // `--` right after it, which only separates the two as it does for `go run`
+                Argument::Positional(path) => {
+                    let mut args = self.args.by_ref().peekable();
+                    args.next_if(|arg| arg == "--");
+                    return Ok(Command::Run(RunOptions {
+                        trace,
+                        engine,
+                        path,
+                        args: args.collect(),
+                    }));
+                }
+            }
+        }
+        let path = "<file.go | file.gorc | package directory>".to_string();
+        Err(self.error(CliErrorKind::MissingArgument(path)))
+    }
+
+    fn check(&mut self) -> Result<Command, CliError> {
+        let mut options = CheckOptions {
+            format: OutputFormat::Text,
+            max_errors: None,
+            tests: false,
+            exclude: Vec::new(),
+            paths: Vec::new(),
+        };
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) if flag == "--format" => {
+                    options.format = self.output_format(&flag, value)?;
+                }
+                Argument::Flag(flag, value) if flag == "--max-errors" => {
+                    let value = self.value(&flag, value)?;
+                    match value.parse::<usize>() {
+                        Ok(max_errors) if max_errors > 0 => options.max_errors = Some(max_errors),
+                        _ => return Err(self.invalid(&flag, value)),
+                    }
+                }
+                Argument::Flag(flag, None) if flag == "--tests" => options.tests = true,
+                Argument::Flag(flag, value) if flag == "--exclude" => {
+                    options.exclude.extend(self.paths(&flag, value)?);
+                }
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(path) => options.paths.push(path),
+            }
+        }
+        if options.paths.is_empty() {
+            let path = "<file.go | package directory | pattern>".to_string();
+            return Err(self.error(CliErrorKind::MissingArgument(path)));
+        }
+        Ok(Command::Check(options))
+    }
+
+    fn vet(&mut self) -> Result<Command, CliError> {
+        let mut options = VetOptions {
+            format: OutputFormat::Text,
+            tests: false,
+            enable: Vec::new(),
+            disable: Vec::new(),
+            exclude: Vec::new(),
+            paths: Vec::new(),
+        };
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) if flag == "--format" => {
+                    options.format = self.output_format(&flag, value)?;
+                }
+                Argument::Flag(flag, value) if flag == "--enable" => {
+                    let rules = self.rules(&flag, value)?;
+                    options.enable.extend(rules);
+                }
+                Argument::Flag(flag, value) if flag == "--disable" => {
+                    let rules = self.rules(&flag, value)?;
+                    options.disable.extend(rules);
+                }
+                Argument::Flag(flag, None) if flag == "--tests" => options.tests = true,
+                Argument::Flag(flag, value) if flag == "--exclude" => {
+                    options.exclude.extend(self.paths(&flag, value)?);
+                }
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(path) => options.paths.push(path),
+            }
+        }
+        if options.paths.is_empty() {
+            let path = "<file.go | package directory | pattern>".to_string();
+            return Err(self.error(CliErrorKind::MissingArgument(path)));
+        }
+        Ok(Command::Vet(options))
+    }
+
+    fn test(&mut self) -> Result<Command, CliError> {
+        let mut options = TestOptions {
+            verbose: false,
+            run: None,
+            exclude: Vec::new(),
+            paths: Vec::new(),
+        };
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, None) if flag == "-v" => options.verbose = true,
+                Argument::Flag(flag, value) if flag == "--run" => {
+                    options.run = Some(self.value(&flag, value)?);
+                }
+                Argument::Flag(flag, value) if flag == "--exclude" => {
+                    options.exclude.extend(self.paths(&flag, value)?);
+                }
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(path) => options.paths.push(path),
+            }
+        }
+        if options.paths.is_empty() {
+            options.paths.push(".".to_string());
+        }
+        Ok(Command::Test(options))
+    }
+
+    fn doc(&mut self) -> Result<Command, CliError> {
+        let mut positionals = Vec::new();
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if positionals.len() < 2 => positionals.push(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let mut positionals = positionals.into_iter();
+        let Some(path) = positionals.next() else {
+            let path = "<file.go | package directory>".to_string();
+            return Err(self.error(CliErrorKind::MissingArgument(path)));
+        };
+        Ok(Command::Doc {
+            path,
+            symbol: positionals.next(),
+        })
+    }
+
+    fn graph(&mut self) -> Result<Command, CliError> {
+        let mut format = GraphFormat::Dot;
+        let mut calls = false;
+        let mut path = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) if flag == "--format" => {
+                    let value = self.value(&flag, value)?;
+                    format = match value.as_str() {
+                        "dot" => GraphFormat::Dot,
+                        "json" => GraphFormat::Json,
+                        _ => return Err(self.invalid(&flag, value)),
+                    };
+                }
+                Argument::Flag(flag, None) if flag == "--calls" => calls = true,
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if path.is_none() => path = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let path = path.ok_or_else(|| {
+            let path = "<file.go | package directory>".to_string();
+            self.error(CliErrorKind::MissingArgument(path))
+        })?;
+        Ok(Command::Graph {
+            format,
+            calls,
+            path,
+        })
+    }
+
+    /// The comma separated names of `gor vet`'s rules, each of which must be one
+    fn rules(&mut self, flag: &str, value: Option<String>) -> Result<Vec<String>, CliError> {
+        let value = self.value(flag, value)?;
+        let vet = Vet::default();
+        let rules: Vec<String> = value.split(',').map(str::to_string).collect();
+        match rules.iter().find(|rule| vet.rule(rule).is_none()) {
+            Some(rule) => Err(self.invalid(flag, rule.clone())),
+            None => Ok(rules),
+        }
+    }
+
+    /// The comma separated paths a flag like `--exclude` names
+    fn paths(&mut self, flag: &str, value: Option<String>) -> Result<Vec<PathBuf>, CliError> {
+        let value = self.value(flag, value)?;
+        Ok(value.split(',').map(PathBuf::from).collect())
+    }
+
+    fn build(&mut self) -> Result<Command, CliError> {
+        let mut emit = Emit::Bytecode;
+        let mut level = 1;
+        let mut output = None;
+        let mut path = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) => match flag.as_str() {
+                    "-o" => output = Some(PathBuf::from(self.value(&flag, value)?)),
+                    "--emit" => {
+                        let value = self.value(&flag, value)?;
+                        emit = match value.as_str() {
+                            "bytecode" => Emit::Bytecode,
+                            "wasm" => Emit::Wasm,
+                            "c" => Emit::C,
+                            _ => return Err(self.invalid(&flag, value)),
+                        };
+                    }
+                    "--target" => match self.value(&flag, value)?.as_str() {
+                        "wasm32" => emit = Emit::Wasm,
+                        target => return Err(self.invalid(&flag, target.to_string())),
+                    },
+                    "--opt-level" => level = self.level(&flag, value)?,
+                    _ => return self.other(flag, value),
+                },
+                Argument::Positional(arg) if path.is_none() => path = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let path = path.ok_or_else(|| {
+            let path = "<file.go | package directory>".to_string();
+            self.error(CliErrorKind::MissingArgument(path))
+        })?;
+        Ok(Command::Build(BuildOptions {
+            emit,
+            level,
+            output,
+            path,
+        }))
+    }
+
+    fn fmt(&mut self) -> Result<Command, CliError> {
+        let mut options = FmtOptions {
+            mode: FormatMode::Print,
+            group_imports: false,
+            local_prefix: None,
+            remove_unused: false,
+            tests: false,
+            exclude: Vec::new(),
+            paths: Vec::new(),
+        };
+        // The flag that chose the mode, for the error if another does too
+        let mut mode_flag: Option<String> = None;
+        while let Some(argument) = self.next() {
+            let (flag, value) = match argument {
+                Argument::Positional(path) => {
+                    options.paths.push(path);
+                    continue;
+                }
+                Argument::Flag(flag, value) => (flag, value),
+            };
+            let mode = match (flag.as_str(), &value) {
+                ("-w", None) => FormatMode::Write,
+                ("--check", None) => FormatMode::Check,
+                ("--diff", None) => FormatMode::Diff,
+                ("--group-imports", None) => {
+                    options.group_imports = true;
+                    continue;
+                }
+                ("--remove-unused-imports", None) => {
+                    options.remove_unused = true;
+                    continue;
+                }
+                ("--tests", None) => {
+                    options.tests = true;
+                    continue;
+                }
+                ("--local", _) => {
+                    options.local_prefix = Some(self.value(&flag, value)?);
+                    continue;
+                }
+                ("--exclude", _) => {
+                    options.exclude.extend(self.paths(&flag, value)?);
+                    continue;
+                }
+                _ => return self.other(flag, value),
+            };
+            if let Some(previous) = &mode_flag
+                && *previous != flag
+            {
+                let kind = CliErrorKind::ConflictingFlags(previous.clone(), flag);
+                return Err(self.error(kind));
+            }
+            options.mode = mode;
+            mode_flag = Some(flag);
+        }
+        if options.paths.is_empty() {
+            let path = "<file.go | directory | pattern>".to_string();
+            return Err(self.error(CliErrorKind::MissingArgument(path)));
+        }
+        Ok(Command::Fmt(options))
+    }
+
+    /// Parse the arguments of a command that takes a file and prints it as text or JSON, like
+    /// `gor dump-tokens`, then make the command with them
+    fn text_or_json(
+        &mut self,
+        command: impl FnOnce(OutputFormat, String) -> Command,
+    ) -> Result<Command, CliError> {
+        let mut format = OutputFormat::Text;
+        let mut path = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) if flag == "--format" => {
+                    format = match self.output_format(&flag, value)? {
+                        OutputFormat::Sarif => {
+                            return Err(self.invalid(&flag, "sarif".to_string()));
+                        }
+                        format => format,
+                    };
+                }
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if path.is_none() => path = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let path =
+            path.ok_or_else(|| self.error(CliErrorKind::MissingArgument("<file.go>".to_string())))?;
+        Ok(command(format, path))
+    }
+
+    fn strip(&mut self) -> Result<Command, CliError> {
+        let mut minify = false;
+        let mut path = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, None) if flag == "--minify" => minify = true,
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if path.is_none() => path = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let path =
+            path.ok_or_else(|| self.error(CliErrorKind::MissingArgument("<file.go>".to_string())))?;
+        Ok(Command::Strip { minify, path })
+    }
+
+    fn dump_ast(&mut self) -> Result<Command, CliError> {
+        let mut format = DumpFormat::Tree;
+        let mut path = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) if flag == "--format" => {
+                    let value = self.value(&flag, value)?;
+                    format = match value.as_str() {
+                        "tree" => DumpFormat::Tree,
+                        "json" => DumpFormat::Json,
+                        "sexpr" => DumpFormat::Sexpr,
+                        _ => return Err(self.invalid(&flag, value)),
+                    };
+                }
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if path.is_none() => path = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let path =
+            path.ok_or_else(|| self.error(CliErrorKind::MissingArgument("<file.go>".to_string())))?;
+        Ok(Command::DumpAst { format, path })
+    }
+
+    fn rename(&mut self) -> Result<Command, CliError> {
+        let mut diff = false;
+        let mut positionals = Vec::new();
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, None) if flag == "--diff" => diff = true,
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if positionals.len() < 2 => positionals.push(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let mut positionals = positionals.into_iter();
+        let (Some(position), Some(name)) = (positionals.next(), positionals.next()) else {
+            let missing = "<file.go>:<line>:<column> <name>".to_string();
+            return Err(self.error(CliErrorKind::MissingArgument(missing)));
+        };
+        // The path may have colons of its own, the line and column can't
+        let mut parts = position.rsplitn(3, ':');
+        let (Some(column), Some(line), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(self.error(CliErrorKind::InvalidPosition(position)));
+        };
+        match (line.parse::<usize>(), column.parse::<usize>()) {
+            (Ok(line), Ok(column)) if line > 0 && !path.is_empty() => Ok(Command::Rename {
+                diff,
+                path: path.to_string(),
+                line,
+                column,
+                name,
+            }),
+            _ => Err(self.error(CliErrorKind::InvalidPosition(position))),
+        }
+    }
+
+    fn diff_ast(&mut self) -> Result<Command, CliError> {
+        let mut paths = Vec::new();
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if paths.len() < 2 => paths.push(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let mut paths = paths.into_iter();
+        match (paths.next(), paths.next()) {
+            (Some(old), Some(new)) => Ok(Command::DiffAst { old, new }),
+            (None, _) => Err(self.error(CliErrorKind::MissingArgument("<old.go>".to_string()))),
+            (Some(_), None) => {
+                Err(self.error(CliErrorKind::MissingArgument("<new.go>".to_string())))
+            }
+        }
+    }
+
+    fn bench_compile(&mut self) -> Result<Command, CliError> {
+        let mut json = false;
+        let mut runs = 5;
+        let mut exclude = Vec::new();
+        let mut path = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, None) if flag == "--json" => json = true,
+                Argument::Flag(flag, value) if flag == "--runs" => {
+                    let value = self.value(&flag, value)?;
+                    runs = match value.parse::<usize>() {
+                        Ok(runs) if runs > 0 => runs,
+                        _ => return Err(self.invalid(&flag, value)),
+                    };
+                }
+                Argument::Flag(flag, value) if flag == "--exclude" => {
+                    exclude.extend(self.paths(&flag, value)?);
+                }
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if path.is_none() => path = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let path = path.ok_or_else(|| {
+            let path = "<directory | pattern | file.go>".to_string();
+            self.error(CliErrorKind::MissingArgument(path))
+        })?;
+        Ok(Command::BenchCompile {
+            json,
+            runs,
+            exclude,
+            path,
+        })
+    }
+
+    fn dump_ir(&mut self) -> Result<Command, CliError> {
+        let mut level = 0;
+        let mut path = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) if flag == "--opt-level" => {
+                    level = self.level(&flag, value)?;
+                }
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if path.is_none() => path = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        let path = path.ok_or_else(|| {
+            let path = "<file.go | package directory>".to_string();
+            self.error(CliErrorKind::MissingArgument(path))
+        })?;
+        Ok(Command::DumpIr { level, path })
+    }
+
+    fn internal(&mut self) -> Result<Command, CliError> {
+        match self.next() {
+            Some(Argument::Positional(command)) if command == "selftest-fmt" => self
+                .single("<directory>", |directory| Command::SelftestFmt {
+                    directory,
+                }),
+            // Left out of the usage, being only for testing how gor reports its own failures
+            Some(Argument::Positional(command)) if command == "panic" => self.none(Command::Panic),
+            Some(Argument::Positional(command)) => {
+                Err(self.error(CliErrorKind::UnknownCommand(command)))
+            }
+            Some(Argument::Flag(flag, value)) => self.other(flag, value),
+            None => Err(self.error(CliErrorKind::MissingCommand)),
+        }
+    }
+
+    fn explain(&mut self) -> Result<Command, CliError> {
+        let mut code = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if code.is_none() => code = Some(arg),
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        Ok(Command::Explain(code))
+    }
+
+    fn completions(&mut self) -> Result<Command, CliError> {
+        let mut shell = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(arg) if shell.is_none() => {
+                    shell = Some(match arg.as_str() {
+                        "bash" => Shell::Bash,
+                        "zsh" => Shell::Zsh,
+                        "fish" => Shell::Fish,
+                        _ => return Err(self.error(CliErrorKind::UnexpectedArgument(arg))),
+                    });
+                }
+                Argument::Positional(arg) => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(arg)));
+                }
+            }
+        }
+        shell
+            .map(Command::Completions)
+            .ok_or_else(|| self.error(CliErrorKind::MissingArgument("<bash|zsh|fish>".to_string())))
+    }
+
+    /// Parse `gor help`'s arguments, the command whose usage to print, if there is one
+    fn help_command(&mut self) -> Result<Command, CliError> {
+        let mut command = None;
+        while let Some(argument) = self.next() {
+            match argument {
+                Argument::Flag(flag, value) => return self.other(flag, value),
+                Argument::Positional(name) if command.is_some() => {
+                    return Err(self.error(CliErrorKind::UnexpectedArgument(name)));
+                }
+                Argument::Positional(name) => {
+                    if !COMMANDS.iter().any(|spec| spec.name == name) {
+                        return Err(self.error(CliErrorKind::UnknownCommand(name)));
+                    }
+                    command = Some(name);
+                }
+            }
+        }
+        Ok(Command::Help(command))
+    }
+}
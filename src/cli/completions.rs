@@ -0,0 +1,492 @@
+//! `gor completions`: a script that completes gor's commands, their flags and the values
+//! those flags take in bash, zsh or fish, written from the commands' help so that it keeps
+//! up with them.
+
+use crate::cli::command::{COMMANDS, GLOBAL_FLAGS, Shell, Spec};
+use crate::primitives::explanations::EXPLANATIONS;
+
+/// A flag as a command's help writes it, like `-v, --verbose` or `--engine=tree|vm|jit`
+struct Flag {
+    names: Vec<&'static str>,
+    /// Whether its value is written after an `=`, rather than as the next argument
+    equals: bool,
+    value: Option<Value>,
+    description: &'static str,
+}
+
+struct Value {
+    /// What the value is, like `engine` or `n`
+    name: &'static str,
+    /// The values it can be, or none if it's written as `<n>` and can be anything
+    choices: Vec<&'static str>,
+}
+
+/// What an argument that isn't a flag completes to
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Files,
+    Commands,
+    Words(Vec<&'static str>),
+}
+
+/// What gor and each of its commands complete, written by their help
+struct Completion {
+    /// The command's name, or None for gor itself
+    name: Option<&'static str>,
+    flags: Vec<Flag>,
+    /// What the first argument that isn't a flag completes to
+    first: Option<Operand>,
+    /// What those after it complete to
+    rest: Option<Operand>,
+}
+
+/// The completion script for `shell`, which completes gor's commands and their flags, a
+/// flag's value from those its help lists, and paths elsewhere
+pub fn script(shell: Shell) -> String {
+    let completions = completions();
+    match shell {
+        Shell::Bash => bash(&completions),
+        Shell::Zsh => zsh(&completions),
+        Shell::Fish => fish(&completions),
+    }
+}
+
+fn completions() -> Vec<Completion> {
+    let mut global = GLOBAL_FLAGS.iter().map(flag).collect::<Vec<_>>();
+    global.extend([help_flag(), flag(&("--version", "print gor's version"))]);
+    let mut completions = vec![Completion {
+        name: None,
+        flags: global,
+        first: Some(Operand::Commands),
+        rest: Some(Operand::Commands),
+    }];
+    for spec in COMMANDS {
+        let mut flags: Vec<Flag> = spec.flags.iter().map(flag).collect();
+        flags.push(help_flag());
+        let (first, rest) = operands(spec);
+        completions.push(Completion {
+            name: Some(spec.name),
+            flags,
+            first,
+            rest,
+        });
+    }
+    completions
+}
+
+fn help_flag() -> Flag {
+    flag(&("-h, --help", "print the usage"))
+}
+
+fn flag((written, description): &(&'static str, &'static str)) -> Flag {
+    let mut names = Vec::new();
+    let mut equals = false;
+    let mut value = None;
+    for written in written.split(", ") {
+        let Some(at) = written.find(['=', ' ']) else {
+            names.push(written);
+            continue;
+        };
+        let (name, after) = (&written[..at], &written[at + 1..]);
+        names.push(name);
+        equals = written[at..].starts_with('=');
+        value = Some(match after.strip_prefix('<') {
+            Some(after) => Value {
+                name: after.split('>').next().unwrap_or(after),
+                choices: Vec::new(),
+            },
+            None => Value {
+                name: name.trim_start_matches('-'),
+                choices: after.split('|').collect(),
+            },
+        });
+    }
+    Flag {
+        names,
+        equals,
+        value,
+        description,
+    }
+}
+
+/// What a command's first argument that isn't a flag completes to and what those after it
+/// do, from its usage
+fn operands(spec: &Spec) -> (Option<Operand>, Option<Operand>) {
+    match spec.name {
+        "help" => return (Some(Operand::Commands), None),
+        "explain" => {
+            let codes = EXPLANATIONS.iter().map(|explanation| explanation.code);
+            return (Some(Operand::Words(codes.collect())), None);
+        }
+        _ => {}
+    }
+    // The usage after the command's name and any flags
+    let mut depth = 0;
+    let mut words = Vec::new();
+    for word in spec.usage.split_whitespace().skip(2) {
+        if depth == 0 && !word.starts_with("[-") {
+            words.push(word);
+            continue;
+        }
+        depth += word.matches('[').count();
+        depth -= word.matches(']').count();
+    }
+    match words.first() {
+        None => (None, None),
+        // A subcommand, like `gor internal selftest-fmt`, then its path
+        Some(word) if !word.starts_with(['<', '[']) => {
+            (Some(Operand::Words(vec![word])), Some(Operand::Files))
+        }
+        Some(word) => {
+            let inner = word.trim_start_matches(['<', '[']);
+            let inner = inner.split(['>', ']']).next().unwrap_or(inner);
+            let choices: Vec<&str> = inner.split('|').collect();
+            let is_word = |choice: &&str| choice.bytes().all(|b| b.is_ascii_lowercase());
+            match choices.len() > 1 && choices.iter().all(is_word) {
+                true => (Some(Operand::Words(choices)), None),
+                false => (Some(Operand::Files), Some(Operand::Files)),
+            }
+        }
+    }
+}
+
+fn command_names() -> Vec<&'static str> {
+    COMMANDS.iter().map(|spec| spec.name).collect()
+}
+
+fn bash(completions: &[Completion]) -> String {
+    let mut script = String::from(
+        r#"# bash completion for gor, written by `gor completions bash`
+
+_gor() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" flag="${COMP_WORDS[COMP_CWORD-1]}"
+    # bash splits --flag=value into words at the =
+    if [[ $cur == = ]]; then
+        cur=""
+    elif [[ $flag == = ]]; then
+        flag="${COMP_WORDS[COMP_CWORD-2]}"
+    fi
+
+    local i word command="" operands=0
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        word="${COMP_WORDS[i]}"
+        if [[ $word == -* || $word == = || ${COMP_WORDS[i-1]} == = ]]; then
+            continue
+        elif [[ -n $command ]]; then
+            ((operands++))
+        elif [[ ${COMP_WORDS[i-1]} != --log-level ]]; then
+            command="$word"
+        fi
+    done
+
+    case "$command" in
+"#,
+    );
+    for completion in completions {
+        let pattern = match completion.name {
+            Some(name) => name.to_string(),
+            None => "\"\"".to_string(),
+        };
+        script.push_str(&format!("        {})\n", pattern));
+        let choices: Vec<&Flag> = completion
+            .flags
+            .iter()
+            .filter(|flag| flag.value.as_ref().is_some_and(|v| !v.choices.is_empty()))
+            .collect();
+        if !choices.is_empty() {
+            script.push_str("            case \"$flag\" in\n");
+            for flag in choices {
+                let choices = &flag.value.as_ref().unwrap().choices;
+                script.push_str(&format!(
+                    "                {}) {}; return ;;\n",
+                    flag.names.join("|"),
+                    compgen(choices)
+                ));
+            }
+            script.push_str("            esac\n");
+        }
+        let flags: Vec<String> = completion
+            .flags
+            .iter()
+            .flat_map(|flag| {
+                let suffix = if flag.equals { "=" } else { "" };
+                flag.names
+                    .iter()
+                    .map(move |name| format!("{}{}", name, suffix))
+            })
+            .collect();
+        script.push_str(&format!(
+            "            if [[ $cur == -* ]]; then\n                {}\n",
+            compgen(&flags)
+        ));
+        if completion.first == completion.rest {
+            if completion.first != Some(Operand::Files) {
+                script.push_str(&format!(
+                    "            else\n                {}\n",
+                    bash_operand(&completion.first)
+                ));
+            }
+        } else {
+            script.push_str(&format!(
+                "            elif ((operands == 0)); then\n                {}\n            else\n                {}\n",
+                bash_operand(&completion.first),
+                bash_operand(&completion.rest)
+            ));
+        }
+        script.push_str("            fi\n            ;;\n");
+    }
+    script.push_str(
+        r#"    esac
+    # Leave the cursor after a flag's = for its value
+    [[ ${COMPREPLY[0]} == *= ]] && compopt -o nospace
+}
+
+complete -o default -F _gor gor
+"#,
+    );
+    script
+}
+
+fn compgen(words: &[impl AsRef<str>]) -> String {
+    let words: Vec<&str> = words.iter().map(AsRef::as_ref).collect();
+    format!(
+        "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+        words.join(" ")
+    )
+}
+
+/// A line of bash completing an operand, falling back to paths for files
+fn bash_operand(operand: &Option<Operand>) -> String {
+    match operand {
+        Some(Operand::Files) => ":".to_string(),
+        Some(Operand::Commands) => compgen(&command_names()),
+        Some(Operand::Words(words)) => compgen(words),
+        None => "compopt +o default".to_string(),
+    }
+}
+
+fn zsh(completions: &[Completion]) -> String {
+    let mut script = String::from(
+        "#compdef gor\n\n# zsh completion for gor, written by `gor completions zsh`\n\n_gor() {\n    local line state\n    _arguments -C \\\n",
+    );
+    let (global, commands) = completions.split_first().expect("gor's own completion");
+    for flag in &global.flags {
+        for spec in zsh_flag(flag) {
+            script.push_str(&format!("        {} \\\n", spec));
+        }
+    }
+    script.push_str(
+        "        '1:command:->command' \\\n        '*::argument:->argument'\n\n    case $state in\n        (command)\n            local -a commands\n            commands=(\n",
+    );
+    for spec in COMMANDS {
+        script.push_str(&format!(
+            "                {}\n",
+            zsh_quote(&format!("{}:{}", spec.name, spec.summary))
+        ));
+    }
+    script.push_str(
+        "            )\n            _describe command commands\n            ;;\n        (argument)\n            case $line[1] in\n",
+    );
+    for completion in commands {
+        script.push_str(&format!(
+            "                ({})\n                    _arguments",
+            completion.name.unwrap_or_default()
+        ));
+        let mut specs: Vec<String> = completion.flags.iter().flat_map(zsh_flag).collect();
+        match (&completion.first, &completion.rest) {
+            (first, rest) if first == rest => specs.extend(zsh_operand("*", first)),
+            (first, rest) => {
+                specs.extend(zsh_operand("1", first));
+                specs.extend(zsh_operand("*", rest));
+            }
+        }
+        for spec in specs {
+            script.push_str(&format!(" \\\n                        {}", spec));
+        }
+        script.push_str("\n                    ;;\n");
+    }
+    script.push_str("            esac\n            ;;\n    esac\n}\n\n_gor \"$@\"\n");
+    script
+}
+
+/// The `_arguments` specification of each of a flag's names
+fn zsh_flag(flag: &Flag) -> Vec<String> {
+    let description = flag
+        .description
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:");
+    flag.names
+        .iter()
+        .map(|name| {
+            let value = match &flag.value {
+                None => String::new(),
+                Some(value) if value.choices.is_empty() => format!(":{}:_files", value.name),
+                Some(value) => format!(":{}:({})", value.name, value.choices.join(" ")),
+            };
+            let equals = if flag.equals { "=" } else { "" };
+            zsh_quote(&format!("{}{}[{}]{}", name, equals, description, value))
+        })
+        .collect()
+}
+
+fn zsh_operand(position: &str, operand: &Option<Operand>) -> Option<String> {
+    let spec = match operand.as_ref()? {
+        Operand::Files => format!("{}:file:_files", position),
+        Operand::Commands => format!("{}:command:({})", position, command_names().join(" ")),
+        Operand::Words(words) => format!("{}:argument:({})", position, words.join(" ")),
+    };
+    Some(zsh_quote(&spec))
+}
+
+/// `text` in single quotes, as zsh and bash read it
+fn zsh_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+fn fish(completions: &[Completion]) -> String {
+    let mut script = String::from(
+        "# fish completion for gor, written by `gor completions fish`\n\ncomplete -c gor -f\n",
+    );
+    let names = command_names();
+    for completion in completions {
+        let condition = match completion.name {
+            Some(name) => format!("__fish_seen_subcommand_from {}", name),
+            None => "__fish_use_subcommand".to_string(),
+        };
+        script.push('\n');
+        for flag in &completion.flags {
+            let mut line = format!("complete -c gor -n {}", fish_quote(&condition));
+            for name in &flag.names {
+                match name.strip_prefix("--") {
+                    Some(long) => line.push_str(&format!(" -l {}", long)),
+                    None => line.push_str(&format!(" -s {}", name.trim_start_matches('-'))),
+                }
+            }
+            match &flag.value {
+                None => {}
+                Some(value) if value.choices.is_empty() => line.push_str(" -r -F"),
+                Some(value) => {
+                    line.push_str(&format!(" -x -a {}", fish_quote(&value.choices.join(" "))))
+                }
+            }
+            line.push_str(&format!(" -d {}\n", fish_quote(flag.description)));
+            script.push_str(&line);
+        }
+        // Until the first operand is given, then after it
+        let words = match &completion.first {
+            Some(Operand::Words(words)) => words.clone(),
+            Some(Operand::Commands) => names.clone(),
+            _ => Vec::new(),
+        };
+        // The command's own name isn't its operand, as in `gor help help`
+        let given: Vec<&str> = words
+            .iter()
+            .copied()
+            .filter(|word| Some(*word) != completion.name)
+            .collect();
+        let (first, rest) = match given.is_empty() {
+            true => (condition.clone(), condition.clone()),
+            false => (
+                format!(
+                    "{}; and not __fish_seen_subcommand_from {}",
+                    condition,
+                    given.join(" ")
+                ),
+                format!(
+                    "{}; and __fish_seen_subcommand_from {}",
+                    condition,
+                    given.join(" ")
+                ),
+            ),
+        };
+        for (condition, operand) in [(first, &completion.first), (rest, &completion.rest)] {
+            let arguments = match operand {
+                Some(Operand::Files) => "-F".to_string(),
+                Some(Operand::Words(_) | Operand::Commands) if completion.name.is_some() => {
+                    format!("-a {}", fish_quote(&words.join(" ")))
+                }
+                _ => continue,
+            };
+            script.push_str(&format!(
+                "complete -c gor -n {} {}\n",
+                fish_quote(&condition),
+                arguments
+            ));
+            if completion.first == completion.rest {
+                break;
+            }
+        }
+        if completion.name.is_none() {
+            for spec in COMMANDS {
+                script.push_str(&format!(
+                    "complete -c gor -n '__fish_use_subcommand' -a {} -d {}\n",
+                    spec.name,
+                    fish_quote(spec.summary)
+                ));
+            }
+        }
+    }
+    script
+}
+
+/// `text` in single quotes, as fish reads it
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_flags_and_operands() {
+        let parsed = flag(&("--engine=tree|vm|jit", "what to run it with"));
+        let value = parsed.value.unwrap();
+        assert_eq!((parsed.names, parsed.equals), (vec!["--engine"], true));
+        assert_eq!(
+            (value.name, value.choices),
+            ("engine", vec!["tree", "vm", "jit"])
+        );
+        let parsed = flag(&("-o <output>", "where to write it"));
+        let value = parsed.value.unwrap();
+        assert_eq!((parsed.names, parsed.equals), (vec!["-o"], false));
+        assert_eq!((value.name, value.choices.len()), ("output", 0));
+        assert_eq!(flag(&("-v, --verbose", "")).names, vec!["-v", "--verbose"]);
+
+        let operands_of = |name| operands(COMMANDS.iter().find(|spec| spec.name == name).unwrap());
+        let files = (Some(Operand::Files), Some(Operand::Files));
+        assert_eq!(operands_of("fmt"), files);
+        assert_eq!(operands_of("run"), files);
+        assert_eq!(operands_of("version"), (None, None));
+        assert_eq!(
+            operands_of("completions"),
+            (Some(Operand::Words(vec!["bash", "zsh", "fish"])), None)
+        );
+        assert_eq!(
+            operands_of("internal"),
+            (
+                Some(Operand::Words(vec!["selftest-fmt"])),
+                Some(Operand::Files)
+            )
+        );
+    }
+
+    #[test]
+    fn scripts_complete_every_command() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = script(shell);
+            for spec in COMMANDS {
+                assert!(script.contains(spec.name), "{:?} {}", shell, spec.name);
+            }
+        }
+        let bash = script(Shell::Bash);
+        assert!(bash.contains(
+            "                --engine) COMPREPLY=($(compgen -W \"tree vm jit\" -- \"$cur\")); return ;;\n"
+        ));
+        let fish = script(Shell::Fish);
+        assert!(fish.contains(
+            "complete -c gor -n '__fish_seen_subcommand_from run' -l engine -x -a 'tree vm jit' -d 'interpret the program, the default, or run it on the VM, with jit compiling hot functions; only tree passes it arguments'\n"
+        ));
+    }
+}
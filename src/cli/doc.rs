@@ -0,0 +1,377 @@
+//! `gor doc`: a package's documentation, or that of one of its symbols, printed from the
+//! comments above their declarations as `go doc` prints them.
+
+use crate::ast::ast::File;
+use crate::ast::declaration::{Declaration, DeclarationKind};
+use crate::ast::outline::{Signature, base_type_name};
+use crate::ast::type_expression::{TypeExpression, TypeExpressionKind};
+use crate::semantic::scope::is_exported;
+
+/// A package's files, each with the source it was parsed from
+#[derive(Debug)]
+pub struct Package {
+    /// Its import path, like `example.com/app/shapes`, if it's in a module
+    pub import_path: Option<String>,
+    pub files: Vec<(File, String)>,
+}
+
+impl Package {
+    /// The package clause, the package's documentation, then a line for each exported
+    /// constant, variable, function and type, with the functions that return a type under it
+    pub fn to_text(&self) -> String {
+        let mut text = self.header();
+        if let Some(doc) = self.files.iter().find_map(|(file, _)| file.doc.as_ref()) {
+            text.push_str(doc);
+            text.push_str("\n\n");
+        }
+        let mut constants = Vec::new();
+        let mut variables = Vec::new();
+        let mut functions = Vec::new();
+        let mut types = Vec::new();
+        for (declaration, _) in self.declarations() {
+            match &declaration.kind {
+                DeclarationKind::Variable { specs, .. } => {
+                    for spec in specs {
+                        if spec.names.iter().any(|name| is_exported(&name.to_string())) {
+                            variables.push(format!("var {}\n", spec));
+                        }
+                    }
+                }
+                DeclarationKind::Constant { specs, .. } => {
+                    for spec in specs {
+                        if spec.names.iter().any(|name| is_exported(&name.to_string())) {
+                            constants.push(format!("const {}\n", spec));
+                        }
+                    }
+                }
+                DeclarationKind::Function {
+                    receiver: None,
+                    name,
+                    ..
+                } if is_exported(name) && self.constructed_type(declaration).is_none() => {
+                    functions.push((name.clone(), format!("{}\n", signature(declaration))));
+                }
+                DeclarationKind::Type {
+                    name,
+                    type_expression,
+                } if is_exported(&name.to_string()) => {
+                    let name = name.to_string();
+                    let mut summary = format!("type {} {}\n", name, summary(type_expression));
+                    for constructor in self.constructors(&name) {
+                        summary.push_str(&format!("    {}\n", signature(constructor)));
+                    }
+                    types.push((name, summary));
+                }
+                _ => {}
+            }
+        }
+        functions.sort();
+        types.sort();
+        let sections = [
+            constants,
+            variables,
+            functions.into_iter().map(|(_, line)| line).collect(),
+            types.into_iter().map(|(_, summary)| summary).collect(),
+        ];
+        for section in sections.iter().filter(|section| !section.is_empty()) {
+            text.push_str(&section.concat());
+            text.push('\n');
+        }
+        text.trim_end().to_string() + "\n"
+    }
+
+    /// The declaration and documentation of `symbol`, a package level name or a method
+    /// written `Type.Method`, after the package clause, or None if the package doesn't
+    /// declare it. A type's is its whole declaration, followed by the functions that return
+    /// it and its methods.
+    pub fn symbol_text(&self, symbol: &str) -> Option<String> {
+        let mut text = self.header();
+        if let Some((type_name, method)) = symbol.split_once('.') {
+            let declaration = self
+                .methods(type_name)
+                .find(|declaration| name_of(declaration) == method)?;
+            text.push_str(&format!("{}\n", signature(declaration)));
+            text.push_str(&indented(&declaration.doc));
+            return Some(text);
+        }
+        let (declaration, source) =
+            self.declarations()
+                .find(|(declaration, _)| match &declaration.kind {
+                    DeclarationKind::Function { receiver, name, .. } => {
+                        receiver.is_none() && name == symbol
+                    }
+                    DeclarationKind::Type { name, .. } => name.to_string() == symbol,
+                    DeclarationKind::Variable { specs, .. }
+                    | DeclarationKind::Constant { specs, .. } => specs
+                        .iter()
+                        .any(|spec| spec.names.iter().any(|name| name.to_string() == symbol)),
+                })?;
+        match &declaration.kind {
+            DeclarationKind::Function { .. } => {
+                text.push_str(&format!("{}\n", signature(declaration)));
+            }
+            _ => text.push_str(&format!("{}\n", source_of(declaration, source))),
+        }
+        text.push_str(&indented(&declaration.doc));
+        if let DeclarationKind::Type { .. } = &declaration.kind {
+            let mut constructors: Vec<_> = self.constructors(symbol).collect();
+            let mut methods: Vec<_> = self.methods(symbol).collect();
+            constructors.sort_by_key(|declaration| name_of(declaration));
+            methods.sort_by_key(|declaration| name_of(declaration));
+            if !constructors.is_empty() || !methods.is_empty() {
+                text.push('\n');
+            }
+            for function in constructors.into_iter().chain(methods) {
+                text.push_str(&format!("{}\n", signature(function)));
+            }
+        }
+        Some(text)
+    }
+
+    /// `package shapes // import "example.com/app/shapes"` and a blank line
+    fn header(&self) -> String {
+        let name = self
+            .files
+            .first()
+            .map(|(file, _)| file.package_name.as_str())
+            .unwrap_or_default();
+        match &self.import_path {
+            Some(path) => format!("package {} // import \"{}\"\n\n", name, path),
+            None => format!("package {}\n\n", name),
+        }
+    }
+
+    /// Every declaration of the package's files, with the source of the file it's in
+    fn declarations(&self) -> impl Iterator<Item = (&Declaration, &str)> {
+        self.files.iter().flat_map(|(file, source)| {
+            file.decls
+                .iter()
+                .map(move |declaration| (declaration, source.as_str()))
+        })
+    }
+
+    /// The exported type a function returns first, as `T` or `*T`, which go doc lists it
+    /// under, if the package declares it
+    fn constructed_type(&self, function: &Declaration) -> Option<String> {
+        let DeclarationKind::Function {
+            receiver: None,
+            results,
+            ..
+        } = &function.kind
+        else {
+            return None;
+        };
+        let result = &results.first()?.type_expression;
+        let name = match &result.kind {
+            TypeExpressionKind::Name(name) => name,
+            TypeExpressionKind::Pointer(target) => match &target.kind {
+                TypeExpressionKind::Name(name) => name,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let declared = self.declarations().any(|(declaration, _)| {
+            matches!(&declaration.kind, DeclarationKind::Type { .. })
+                && name_of(declaration) == *name
+        });
+        (declared && is_exported(name)).then(|| name.clone())
+    }
+
+    /// The exported functions that return the type named `type_name`
+    fn constructors<'a>(&'a self, type_name: &'a str) -> impl Iterator<Item = &'a Declaration> {
+        self.declarations()
+            .map(|(declaration, _)| declaration)
+            .filter(move |declaration| {
+                is_exported(&name_of(declaration))
+                    && self.constructed_type(declaration).as_deref() == Some(type_name)
+            })
+    }
+
+    /// The methods declared on the type named `type_name`, in the order they're declared
+    fn methods<'a>(&'a self, type_name: &'a str) -> impl Iterator<Item = &'a Declaration> {
+        self.declarations()
+            .map(|(declaration, _)| declaration)
+            .filter(move |declaration| match &declaration.kind {
+                DeclarationKind::Function {
+                    receiver: Some(receiver),
+                    ..
+                } => base_type_name(&receiver.type_expression) == type_name,
+                _ => false,
+            })
+    }
+}
+
+/// A type on a line: a struct or interface with members as `struct{ ... }`, as go doc
+/// abbreviates them
+fn summary(type_expression: &TypeExpression) -> String {
+    match &type_expression.kind {
+        TypeExpressionKind::Struct(fields) if !fields.is_empty() => "struct{ ... }".to_string(),
+        TypeExpressionKind::Interface(methods) if !methods.is_empty() => {
+            "interface{ ... }".to_string()
+        }
+        _ => type_expression.to_string(),
+    }
+}
+
+/// A function's declaration without its body, like `func (c *Circle) Area() float64`
+fn signature(function: &Declaration) -> String {
+    let DeclarationKind::Function {
+        receiver,
+        name,
+        parameters,
+        results,
+        ..
+    } = &function.kind
+    else {
+        return String::new();
+    };
+    match receiver {
+        Some(receiver) => format!(
+            "func ({}) {}{}",
+            receiver,
+            name,
+            Signature(parameters, results)
+        ),
+        None => format!("func {}{}", name, Signature(parameters, results)),
+    }
+}
+
+fn name_of(declaration: &Declaration) -> String {
+    match &declaration.kind {
+        DeclarationKind::Function { name, .. } => name.clone(),
+        DeclarationKind::Type { name, .. } => name.to_string(),
+        DeclarationKind::Variable { .. } | DeclarationKind::Constant { .. } => String::new(),
+    }
+}
+
+/// The lines of `source` a declaration is written on, as it's written
+fn source_of(declaration: &Declaration, source: &str) -> String {
+    let (start, end) = (
+        declaration.position_start.line,
+        declaration.position_end.line,
+    );
+    source
+        .lines()
+        .skip(start - 1)
+        .take(end + 1 - start)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Documentation indented by four spaces, as go doc prints it under a declaration
+fn indented(doc: &Option<String>) -> String {
+    let Some(doc) = doc else {
+        return String::new();
+    };
+    doc.lines()
+        .map(|line| match line.is_empty() {
+            true => "\n".to_string(),
+            false => format!("    {}\n", line),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    const SOURCE: &str = "// Package shapes measures shapes.
+package shapes
+
+// Count is how many shapes were made.
+var Count int
+
+var made = 0
+
+// Circle is round.
+type Circle struct {
+\tR float64 // radius
+}
+
+// NewCircle makes a circle of radius r.
+func NewCircle(r float64) *Circle {
+\treturn &Circle{R: r}
+}
+
+// Area is πr².
+//
+// It's never negative.
+func (c *Circle) Area() float64 {
+\treturn 3 * c.R * c.R
+}
+
+// Draw draws nothing.
+func Draw() {}
+// Fill fills nothing.
+func Fill() {}
+
+type Celsius float64
+";
+
+    fn package() -> Package {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        Package {
+            import_path: Some("example.com/shapes".to_string()),
+            files: vec![(file, SOURCE.to_string())],
+        }
+    }
+
+    #[test]
+    fn package_text() {
+        assert_eq!(
+            package().to_text(),
+            "package shapes // import \"example.com/shapes\"
+
+Package shapes measures shapes.
+
+var Count int
+
+func Draw()
+func Fill()
+
+type Celsius float64
+type Circle struct{ ... }
+    func NewCircle(r float64) *Circle
+"
+        );
+    }
+
+    #[test]
+    fn symbol_text() {
+        let package = package();
+        assert_eq!(
+            package.symbol_text("Circle").unwrap(),
+            "package shapes // import \"example.com/shapes\"
+
+type Circle struct {
+\tR float64 // radius
+}
+    Circle is round.
+
+func NewCircle(r float64) *Circle
+func (c *Circle) Area() float64
+"
+        );
+        assert_eq!(
+            package.symbol_text("Circle.Area").unwrap(),
+            "package shapes // import \"example.com/shapes\"
+
+func (c *Circle) Area() float64
+    Area is πr².
+
+    It's never negative.
+"
+        );
+        // Documented right below the closing brace of Draw
+        assert_eq!(
+            package.symbol_text("Fill").unwrap(),
+            "package shapes // import \"example.com/shapes\"
+
+func Fill()
+    Fill fills nothing.
+"
+        );
+        assert_eq!(package.symbol_text("Square"), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! `gor graph`: a program's packages and what they import, or its functions and those they
+//! call, as a Graphviz graph or JSON, to see how it's put together.
+
+use std::collections::HashMap;
+
+use crate::ast::declaration::DeclarationKind;
+use crate::ast::{dump::json_string, outline::base_type_name};
+use crate::eval::loader::Program;
+use crate::primitives::position::Position;
+use crate::semantic::{
+    resolver::resolve,
+    scope::{ScopeKind, SymbolId, SymbolKind},
+};
+
+/// A directed graph of a program's packages or functions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Graph {
+    /// What the graph is of, `imports` or `calls`, which names it in Graphviz
+    pub name: &'static str,
+    pub nodes: Vec<String>,
+    /// Each edge, from the node to the one it imports or calls, with none twice
+    pub edges: Vec<(String, String)>,
+}
+
+/// The program's packages, by import path, each with an edge to each package of the program
+/// it imports. The standard library's packages aren't in it.
+pub fn imports(program: &Program) -> Graph {
+    let mut graph = Graph {
+        name: "imports",
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    for package in &program.packages {
+        graph.nodes.push(package.import_path.clone());
+        for import in &package.imports {
+            graph.add_edge(&package.import_path, import);
+        }
+    }
+    graph
+}
+
+/// The program's functions and methods, as the loader names them, like `shapes.New` or
+/// `Circle.Area`, each with an edge to each function it calls or refers to. Calls are
+/// found by the resolver, so those of methods, which need the types of their receivers, and
+/// of function values aren't in it.
+pub fn calls(program: &Program) -> Graph {
+    let (symbols, _) = resolve(&program.file);
+    let mut uses: Vec<(Position, SymbolId)> = symbols
+        .uses()
+        .filter(|(_, symbol)| {
+            let symbol = symbols.symbol(*symbol);
+            symbol.kind == SymbolKind::Function
+                && symbols.scope(symbol.scope).kind == ScopeKind::Package
+        })
+        .collect();
+    uses.sort_by_key(|(position, _)| key(*position));
+
+    let mut graph = Graph {
+        name: "calls",
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    // What each function's symbol is named in the graph
+    let mut named: HashMap<SymbolId, String> = HashMap::new();
+    let mut functions = Vec::new();
+    for declaration in &program.file.decls {
+        let DeclarationKind::Function { receiver, name, .. } = &declaration.kind else {
+            continue;
+        };
+        let node = match receiver {
+            Some(receiver) => format!("{}.{}", base_type_name(&receiver.type_expression), name),
+            None => name.clone(),
+        };
+        if let Some(symbol) = symbols.def_at(declaration.position_start) {
+            named.insert(symbol, node.clone());
+        }
+        graph.nodes.push(node.clone());
+        functions.push((node, declaration.position_start, declaration.position_end));
+    }
+    for (node, start, end) in functions {
+        let called = uses
+            .iter()
+            .filter(|(position, _)| key(start) <= key(*position) && key(*position) <= key(end))
+            .filter_map(|(_, symbol)| named.get(symbol));
+        for called in called {
+            graph.add_edge(&node, called);
+        }
+    }
+    graph
+}
+
+impl Graph {
+    fn add_edge(&mut self, from: &str, to: &str) {
+        let edge = (from.to_string(), to.to_string());
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+
+    /// The graph in Graphviz's DOT language, every node and then every edge
+    pub fn to_dot(&self) -> String {
+        let mut dot = format!("digraph {} {{\n", self.name);
+        for node in &self.nodes {
+            dot.push_str(&format!("\t{};\n", json_string(node)));
+        }
+        for (from, to) in &self.edges {
+            dot.push_str(&format!(
+                "\t{} -> {};\n",
+                json_string(from),
+                json_string(to)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The graph as a JSON object on a line, its nodes and its edges as objects
+    pub fn to_json(&self) -> String {
+        let nodes: Vec<String> = self.nodes.iter().map(|node| json_string(node)).collect();
+        let edges: Vec<String> = self
+            .edges
+            .iter()
+            .map(|(from, to)| {
+                format!(
+                    "{{\"from\": {}, \"to\": {}}}",
+                    json_string(from),
+                    json_string(to)
+                )
+            })
+            .collect();
+        format!(
+            "{{\"nodes\": [{}], \"edges\": [{}]}}",
+            nodes.join(", "),
+            edges.join(", ")
+        )
+    }
+}
+
+fn key(position: Position) -> (usize, usize) {
+    (position.line, position.column_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::loader::load;
+    use std::path::Path;
+
+    fn program(path: &str) -> Program {
+        let module = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/testfiles/module");
+        load(&module.join(path)).unwrap()
+    }
+
+    #[test]
+    fn import_graph() {
+        let graph = imports(&program("cmd/app"));
+        assert_eq!(
+            graph.to_dot(),
+            "digraph imports {
+\t\"example.com/app/textutil\";
+\t\"example.com/app/shapes\";
+\t\"example.com/app/cmd/app\";
+\t\"example.com/app/cmd/app\" -> \"example.com/app/textutil\";
+\t\"example.com/app/cmd/app\" -> \"example.com/app/shapes\";
+}
+"
+        );
+        assert_eq!(
+            imports(&program("textutil")).to_json(),
+            r#"{"nodes": ["example.com/app/textutil"], "edges": []}"#
+        );
+    }
+
+    #[test]
+    fn call_graph() {
+        let graph = calls(&program("cmd/app"));
+        assert_eq!(graph.name, "calls");
+        let edges: Vec<String> = graph
+            .edges
+            .iter()
+            .map(|(from, to)| format!("{} -> {}", from, to))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                "text.Title -> text.Upper",
+                "shapes.Rect.Area -> shapes.area",
+                "greeting -> text.Title",
+                "main -> shapes.New",
+                "main -> greeting",
+                "main -> text.Upper"
+            ]
+        );
+    }
+}
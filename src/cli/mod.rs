@@ -1 +1,10 @@
+pub mod bench;
+#[allow(clippy::module_inception)]
 pub mod cli;
+pub mod command;
+pub mod completions;
+pub mod doc;
+pub mod graph;
+pub mod logger;
+pub mod project;
+pub mod sarif;
@@ -0,0 +1,648 @@
+//! `gor.toml`: defaults for gor's flags and each command's, kept at a project's root so that
+//! everyone working on it runs gor the same way. Keys are flags without their dashes, those
+//! before any table gor's own and those in a table like `[vet]` the command's:
+//!
+//! ```toml
+//! log-level = "info"
+//! exclude = ["vendor", "gen/api.go"]
+//!
+//! [vet]
+//! enable = ["shadow"]
+//!
+//! [fmt]
+//! group-imports = true
+//! ```
+//!
+//! `true` gives a flag that takes no value and `false` leaves it out, a string or integer is
+//! a flag's value, and a list is the values of a flag that takes them separated by commas.
+//! The top level `exclude` is every command's that takes `--exclude`, and excluded paths are
+//! relative to the project's root. Only as much of TOML as that needs is read.
+//!
+//! A mistake in a command's table is an error only when that command runs, so that one in
+//! `[vet]` doesn't stop `gor fmt`. One anywhere else is an error for every command.
+
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::Chars;
+
+use crate::cli::command::{COMMANDS, GLOBAL_FLAGS};
+use crate::primitives::errors::config::{ConfigError, ConfigErrorKind};
+
+/// The name of the file a project's defaults are in
+pub const CONFIG_FILE: &str = "gor.toml";
+
+/// The flags that choose what a command does on a run, which a project can't give a default
+const PER_RUN: &[(&str, &str)] = &[("fmt", "-w"), ("fmt", "--check"), ("fmt", "--diff")];
+
+/// The flag `gor.toml`'s paths are given to, which are relative to the project's root
+const EXCLUDE: &str = "--exclude";
+
+/// The defaults a project's `gor.toml` gives gor's flags, as arguments put before those gor
+/// was run with so that those override them
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Project {
+    /// The directory `gor.toml` is in
+    pub root: PathBuf,
+    /// gor's own flags, from the keys before any table
+    global: Vec<String>,
+    /// Each command's flags, from its table, or what's wrong with it
+    commands: Vec<(&'static str, Result<Vec<String>, ConfigError>)>,
+}
+
+impl Project {
+    /// The project `directory` is in, from the nearest `gor.toml` at or above it, or None if
+    /// there isn't one
+    pub fn find(directory: &Path) -> Result<Option<Project>, ConfigError> {
+        let io_error = |error: std::io::Error, path: PathBuf| {
+            ConfigError::new(ConfigErrorKind::Io(error.to_string()), path, 0)
+        };
+        let absolute =
+            std::path::absolute(directory).map_err(|error| io_error(error, directory.into()))?;
+        for directory in absolute.ancestors() {
+            let path = directory.join(CONFIG_FILE);
+            if !path.is_file() {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).map_err(|error| io_error(error, path))?;
+            return Project::parse(directory, &source).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// The project whose `gor.toml`, in `root`, is `source`
+    pub fn parse(root: &Path, source: &str) -> Result<Project, ConfigError> {
+        let error = |(kind, line)| ConfigError::new(kind, root.join(CONFIG_FILE), line);
+        let mut project = Project {
+            root: root.to_path_buf(),
+            ..Project::default()
+        };
+        let mut exclude = Vec::new();
+        for table in tables(source).map_err(error)? {
+            let Some(name) = table.name else {
+                for entry in table.entries {
+                    if entry.key == "exclude" {
+                        exclude.extend(project.values(&entry).map_err(error)?);
+                        continue;
+                    }
+                    let argument = project.argument(None, GLOBAL_FLAGS, entry);
+                    project.global.extend(argument.map_err(error)?);
+                }
+                continue;
+            };
+            let Some(spec) = COMMANDS.iter().find(|spec| spec.name == name) else {
+                return Err(error((ConfigErrorKind::UnknownSection(name), table.line)));
+            };
+            let arguments = table
+                .entries
+                .into_iter()
+                .filter_map(|entry| {
+                    project
+                        .argument(Some(spec.name), spec.flags, entry)
+                        .transpose()
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(error);
+            project.commands.push((spec.name, arguments));
+        }
+        if !exclude.is_empty() {
+            let argument = format!("{}={}", EXCLUDE, exclude.join(","));
+            for spec in COMMANDS {
+                if !spec
+                    .flags
+                    .iter()
+                    .any(|(flag, _)| flag_name(flag) == EXCLUDE)
+                {
+                    continue;
+                }
+                match project
+                    .commands
+                    .iter_mut()
+                    .find(|(name, _)| *name == spec.name)
+                {
+                    Some((_, Ok(arguments))) => arguments.insert(0, argument.clone()),
+                    Some((_, Err(_))) => {}
+                    None => project
+                        .commands
+                        .push((spec.name, Ok(vec![argument.clone()]))),
+                }
+            }
+        }
+        Ok(project)
+    }
+
+    /// The flags to put before those gor was run with: gor's own for None, or else those
+    /// after the command's name, or what's wrong with the command's table
+    pub fn flags(&self, command: Option<&str>) -> Result<&[String], &ConfigError> {
+        let Some(command) = command else {
+            return Ok(&self.global);
+        };
+        match self.commands.iter().find(|(name, _)| *name == command) {
+            Some((_, Ok(arguments))) => Ok(arguments),
+            Some((_, Err(error))) => Err(error),
+            None => Ok(&[]),
+        }
+    }
+
+    /// The argument an entry of a table gives the command, or gor with None, if it's one of
+    /// its flags
+    fn argument(
+        &self,
+        command: Option<&str>,
+        flags: &[(&str, &str)],
+        entry: Entry,
+    ) -> Result<Option<String>, (ConfigErrorKind, usize)> {
+        let unknown = || ConfigErrorKind::UnknownKey {
+            section: command.map(str::to_string),
+            key: entry.key.clone(),
+        };
+        let written = flags
+            .iter()
+            .map(|(written, _)| *written)
+            .find(|written| {
+                written
+                    .split(", ")
+                    .any(|written| flag_name(written).trim_start_matches('-') == entry.key)
+            })
+            .ok_or_else(|| (unknown(), entry.line))?;
+        let name = flag_name(written);
+        if let Some(command) = command
+            && PER_RUN.contains(&(command, name))
+        {
+            return Err((ConfigErrorKind::PerRun(name.to_string()), entry.line));
+        }
+        let takes_value = written.rsplit(", ").next().is_some_and(|last| last != name);
+        let invalid = ConfigErrorKind::InvalidValue {
+            key: entry.key.clone(),
+            value: entry.value.to_string(),
+        };
+        match &entry.value {
+            Value::Boolean(true) if !takes_value => Ok(Some(name.to_string())),
+            Value::Boolean(false) if !takes_value => Ok(None),
+            Value::Array(values) if written.ends_with(",...") && values.is_empty() => Ok(None),
+            Value::String(_) | Value::Integer(_) if takes_value => {
+                Ok(Some(format!("{}={}", name, self.values(&entry)?.join(","))))
+            }
+            Value::Array(_) if written.ends_with(",...") => {
+                Ok(Some(format!("{}={}", name, self.values(&entry)?.join(","))))
+            }
+            _ => Err((invalid, entry.line)),
+        }
+    }
+
+    /// The value an entry gives its flag, or the values of a list, with paths to exclude
+    /// made absolute
+    fn values(&self, entry: &Entry) -> Result<Vec<String>, (ConfigErrorKind, usize)> {
+        let values = match &entry.value {
+            Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        let mut strings = Vec::new();
+        for value in values {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Integer(value) => value.to_string(),
+                _ => {
+                    let kind = ConfigErrorKind::InvalidValue {
+                        key: entry.key.clone(),
+                        value: entry.value.to_string(),
+                    };
+                    return Err((kind, entry.line));
+                }
+            };
+            strings.push(match entry.key == EXCLUDE.trim_start_matches('-') {
+                true => self.root.join(value).display().to_string(),
+                false => value,
+            });
+        }
+        Ok(strings)
+    }
+}
+
+/// The last of a flag's names as its help writes it, without its value, like `--verbose`
+/// for `-v, --verbose` or `--engine` for `--engine=tree|vm|jit`
+fn flag_name(written: &str) -> &str {
+    let written = written.rsplit(", ").next().unwrap_or(written);
+    written.split(['=', ' ']).next().unwrap_or(written)
+}
+
+/// A table of `gor.toml`, or the keys before any table
+struct Table {
+    name: Option<String>,
+    line: usize,
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    key: String,
+    value: Value,
+    line: usize,
+}
+
+/// The values gor reads, of those TOML has
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+/// As TOML writes it
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::String(value) => write!(f, "{:?}", value),
+            Value::Integer(value) => write!(f, "{}", value),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Array(values) => {
+                let values: Vec<String> = values.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+/// The tables of a TOML file, each key's line with it. Dotted keys, inline tables, arrays of
+/// tables, floats, dates and multi-line strings are syntax errors.
+fn tables(source: &str) -> Result<Vec<Table>, (ConfigErrorKind, usize)> {
+    let mut reader = Reader {
+        chars: source.chars().peekable(),
+        line: 1,
+    };
+    let mut tables = vec![Table {
+        name: None,
+        line: 1,
+        entries: Vec::new(),
+    }];
+    loop {
+        reader.skip_blank();
+        let line = reader.line;
+        match reader.chars.peek() {
+            None => break,
+            Some('[') => {
+                reader.next();
+                if reader.chars.peek() == Some(&'[') {
+                    return Err(reader.syntax("arrays of tables aren't supported"));
+                }
+                reader.skip_spaces();
+                let name = reader.key()?;
+                reader.skip_spaces();
+                reader.expect(']')?;
+                reader.end_of_line()?;
+                if tables
+                    .iter()
+                    .any(|table| table.name.as_ref() == Some(&name))
+                {
+                    return Err((ConfigErrorKind::Duplicate(format!("[{}]", name)), line));
+                }
+                tables.push(Table {
+                    name: Some(name),
+                    line,
+                    entries: Vec::new(),
+                });
+            }
+            Some(_) => {
+                let key = reader.key()?;
+                reader.skip_spaces();
+                reader.expect('=')?;
+                reader.skip_spaces();
+                let value = reader.value()?;
+                reader.end_of_line()?;
+                let table = tables.last_mut().expect("there's always the top level");
+                if table.entries.iter().any(|entry| entry.key == key) {
+                    return Err((ConfigErrorKind::Duplicate(key), line));
+                }
+                table.entries.push(Entry { key, value, line });
+            }
+        }
+    }
+    Ok(tables)
+}
+
+/// A TOML file, read a character at a time
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+    /// The line of the next character
+    line: usize,
+}
+
+impl Reader<'_> {
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn syntax(&self, message: &str) -> (ConfigErrorKind, usize) {
+        (ConfigErrorKind::Syntax(message.to_string()), self.line)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), (ConfigErrorKind, usize)> {
+        match self.chars.peek() {
+            Some(&c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            _ => Err(self.syntax(&format!("expected {}", expected))),
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.chars.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+    }
+
+    fn skip_comment(&mut self) {
+        if self.chars.peek() == Some(&'#') {
+            while self.chars.next_if(|&c| c != '\n').is_some() {}
+        }
+    }
+
+    /// Skip spaces, comments and newlines
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            match self.chars.peek() {
+                Some('\r' | '\n') => {
+                    self.next();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// The rest of a line after a key's value or a table's name, which can only be a comment
+    fn end_of_line(&mut self) -> Result<(), (ConfigErrorKind, usize)> {
+        self.skip_spaces();
+        self.skip_comment();
+        self.chars.next_if_eq(&'\r');
+        match self.chars.peek() {
+            None | Some('\n') => Ok(()),
+            Some(_) => Err(self.syntax("expected the end of the line")),
+        }
+    }
+
+    /// A bare or quoted key
+    fn key(&mut self) -> Result<String, (ConfigErrorKind, usize)> {
+        let key = match self.chars.peek() {
+            Some('"') => self.basic_string()?,
+            Some('\'') => self.literal_string()?,
+            _ => {
+                let mut key = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|&c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    key.push(c);
+                }
+                if key.is_empty() {
+                    return Err(self.syntax("expected a key"));
+                }
+                key
+            }
+        };
+        self.skip_spaces();
+        match self.chars.peek() {
+            Some('.') => Err(self.syntax("dotted keys aren't supported")),
+            _ => Ok(key),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, (ConfigErrorKind, usize)> {
+        match self.chars.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => Err(self.syntax("inline tables aren't supported")),
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|&c| c.is_ascii_alphanumeric() || "+-_.:".contains(c))
+                {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "" => Err(self.syntax("expected a value")),
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => match word.replace('_', "").parse() {
+                        Ok(integer) => Ok(Value::Integer(integer)),
+                        Err(_) => Err(self.syntax(&format!(
+                            "{} isn't a string, integer, boolean or list",
+                            word
+                        ))),
+                    },
+                }
+            }
+            None => Err(self.syntax("expected a value")),
+        }
+    }
+
+    /// A list of values, which may span lines, with a comma after the last if it likes
+    fn array(&mut self) -> Result<Value, (ConfigErrorKind, usize)> {
+        self.next();
+        let mut values = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.chars.next_if_eq(&']').is_some() {
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_blank();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return Err(self.syntax("expected , or ] in a list")),
+            }
+        }
+    }
+
+    /// A string in double quotes, in which `\` escapes as it does in Rust
+    fn basic_string(&mut self) -> Result<String, (ConfigErrorKind, usize)> {
+        self.next();
+        let mut string = String::new();
+        loop {
+            if matches!(self.chars.peek(), None | Some('\n')) {
+                return Err(self.syntax("unterminated string"));
+            }
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some(c @ ('u' | 'U')) => {
+                            let digits = if c == 'u' { 4 } else { 8 };
+                            let hex: String = (0..digits).filter_map(|_| self.next()).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.syntax("invalid unicode escape"))?
+                        }
+                        _ => return Err(self.syntax("invalid escape")),
+                    };
+                    string.push(escaped);
+                }
+                Some(c) => string.push(c),
+                None => unreachable!("the end was checked for"),
+            }
+        }
+    }
+
+    /// A string in single quotes, which escapes nothing
+    fn literal_string(&mut self) -> Result<String, (ConfigErrorKind, usize)> {
+        self.next();
+        let mut string = String::new();
+        loop {
+            if matches!(self.chars.peek(), None | Some('\n')) {
+                return Err(self.syntax("unterminated string"));
+            }
+            match self.next() {
+                Some('\'') => return Ok(string),
+                Some(c) => string.push(c),
+                None => unreachable!("the end was checked for"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Project, String> {
+        Project::parse(Path::new("/app"), source).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn parse_project() {
+        let project = parse(
+            "# gor's own
+log-level = 'info'
+exclude = [
+    \"vendor\", # third party
+    \"gen/api.go\",
+]
+
+[vet]
+enable = [\"shadow\"]
+disable = []
+tests = true
+
+[fmt]
+group-imports = true
+local = \"example.com/app\"
+remove-unused-imports = false
+
+[check]
+max-errors = 10
+exclude = [\"testdata\"]
+",
+        )
+        .unwrap();
+        let flags = |command| project.flags(command).unwrap();
+        let exclude = "--exclude=/app/vendor,/app/gen/api.go";
+        assert_eq!(flags(None), ["--log-level=info"]);
+        assert_eq!(flags(Some("vet")), [exclude, "--enable=shadow", "--tests"]);
+        assert_eq!(
+            flags(Some("fmt")),
+            [exclude, "--group-imports", "--local=example.com/app"]
+        );
+        assert_eq!(
+            flags(Some("check")),
+            [exclude, "--max-errors=10", "--exclude=/app/testdata"]
+        );
+        assert_eq!(flags(Some("test")), [exclude]);
+        assert!(flags(Some("run")).is_empty());
+        assert_eq!(
+            parse("[test]\nv = true\n[build]\no = \"out.wasm\"\n")
+                .unwrap()
+                .flags(Some("build"))
+                .unwrap(),
+            ["-o=out.wasm"]
+        );
+    }
+
+    #[test]
+    fn project_errors() {
+        // The first error of the file, or of any command's table
+        let error = |source| match parse(source) {
+            Ok(project) => COMMANDS
+                .iter()
+                .find_map(|spec| project.flags(Some(spec.name)).err())
+                .map(ToString::to_string),
+            Err(error) => Some(error),
+        };
+        let errors = [
+            ("[chek]\n", "/app/gor.toml:1: unknown table [chek]"),
+            (
+                "\n[check]\nformt = \"json\"\n",
+                "/app/gor.toml:3: unknown key formt in [check]",
+            ),
+            ("trace = true\n", "/app/gor.toml:1: unknown key trace"),
+            (
+                "[fmt]\nw = true\n",
+                "/app/gor.toml:2: -w is chosen on each run, not in gor.toml",
+            ),
+            (
+                "[check]\ntests = \"yes\"\n",
+                "/app/gor.toml:2: invalid value \"yes\" for tests",
+            ),
+            (
+                "[check]\nformat = [\"json\"]\n",
+                "/app/gor.toml:2: invalid value [\"json\"] for format",
+            ),
+            (
+                "[vet]\ntests = true\ntests = false\n",
+                "/app/gor.toml:3: tests is given twice",
+            ),
+            ("[vet]\n[vet]\n", "/app/gor.toml:2: [vet] is given twice"),
+            (
+                "[check.x]\n",
+                "/app/gor.toml:1: dotted keys aren't supported",
+            ),
+            (
+                "[check]\nmax-errors = 1.5\n",
+                "/app/gor.toml:2: 1.5 isn't a string, integer, boolean or list",
+            ),
+            (
+                "log-level = \"info\" x\n",
+                "/app/gor.toml:1: expected the end of the line",
+            ),
+            (
+                "exclude = [\"a\"\n\"b\"]\n",
+                "/app/gor.toml:2: expected , or ] in a list",
+            ),
+            (
+                "log-level = \"info\n",
+                "/app/gor.toml:1: unterminated string",
+            ),
+        ];
+        for (source, expected) in errors {
+            assert_eq!(error(source).as_deref(), Some(expected), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn table_errors_are_only_their_commands() {
+        let project = parse(
+            "log-level = \"info\"\nexclude = [\"vendor\"]\n\n[vet]\ntests = \"yes\"\n\n[fmt]\ngroup-imports = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            project.flags(Some("vet")).unwrap_err().to_string(),
+            "/app/gor.toml:5: invalid value \"yes\" for tests"
+        );
+        assert_eq!(project.flags(None).unwrap(), ["--log-level=info"]);
+        assert_eq!(
+            project.flags(Some("fmt")).unwrap(),
+            ["--exclude=/app/vendor", "--group-imports"]
+        );
+    }
+}
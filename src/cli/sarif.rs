@@ -0,0 +1,242 @@
+//! SARIF, the Static Analysis Results Interchange Format, which code scanning services like
+//! GitHub's take: `gor check --format=sarif` and `gor vet --format=sarif` print a single log
+//! of a single run, each diagnostic a result whose rule is its code, as `gor explain` takes it,
+//! or the vet rule's name, with the check that found it among its properties.
+
+use std::path::Path;
+
+use crate::ast::dump::json_string;
+use crate::primitives::{
+    diagnostic::{Diagnostic, Severity},
+    position::Position,
+};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const VERSION: &str = "2.1.0";
+
+/// The results of a run, added as they're found, then written out as a log
+#[derive(Debug, Default)]
+pub struct Sarif {
+    /// Each result, as JSON
+    results: Vec<String>,
+    /// The rules of the results, each once, in the order they were first seen
+    rules: Vec<&'static str>,
+}
+
+impl Sarif {
+    pub fn new() -> Sarif {
+        Sarif::default()
+    }
+
+    /// Add a diagnostic, each position given in its file by `locate`. Its notes are its
+    /// related locations.
+    pub fn add<'a>(
+        &mut self,
+        diagnostic: &Diagnostic,
+        locate: impl Fn(Position) -> (Option<&'a Path>, Position),
+    ) {
+        let related: Vec<String> = diagnostic
+            .notes
+            .iter()
+            .enumerate()
+            .map(|(index, (message, position))| {
+                let (path, position) = locate(*position);
+                format!(
+                    "{{\"id\": {}, \"message\": {{\"text\": {}}}, \"physicalLocation\": {}}}",
+                    index,
+                    json_string(message),
+                    physical_location(path, Some(position))
+                )
+            })
+            .collect();
+        let (path, position) = locate(diagnostic.position);
+        self.result(
+            diagnostic.explanation.or(diagnostic.code),
+            diagnostic.code,
+            diagnostic.severity,
+            &diagnostic.message,
+            physical_location(path, Some(position)),
+            &related,
+        );
+    }
+
+    /// Add a problem with a whole file or directory, like one that can't be read
+    pub fn add_file(&mut self, code: &'static str, message: &str, path: &Path) {
+        let location = physical_location(Some(path), None);
+        self.result(
+            Some(code),
+            Some(code),
+            Severity::Error,
+            message,
+            location,
+            &[],
+        );
+    }
+
+    fn result(
+        &mut self,
+        rule: Option<&'static str>,
+        check: Option<&'static str>,
+        severity: Severity,
+        message: &str,
+        location: String,
+        related: &[String],
+    ) {
+        let mut result = String::from("{");
+        if let Some(rule) = rule {
+            if !self.rules.contains(&rule) {
+                self.rules.push(rule);
+            }
+            result.push_str(&format!("\"ruleId\": {}, ", json_string(rule)));
+        }
+        let level = match severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        result.push_str(&format!(
+            "\"level\": \"{}\", \"message\": {{\"text\": {}}}, \"locations\": [{{\"physicalLocation\": {}}}]",
+            level,
+            json_string(message),
+            location
+        ));
+        if !related.is_empty() {
+            result.push_str(&format!(", \"relatedLocations\": [{}]", related.join(", ")));
+        }
+        if let Some(check) = check {
+            result.push_str(&format!(
+                ", \"properties\": {{\"check\": {}}}",
+                json_string(check)
+            ));
+        }
+        result.push('}');
+        self.results.push(result);
+    }
+
+    /// The log, pretty-printed with two space indents as far as each result, which is on a
+    /// line of its own
+    pub fn finish(&self) -> String {
+        let rules: Vec<String> = self
+            .rules
+            .iter()
+            .map(|rule| format!("{{\"id\": {}}}", json_string(rule)))
+            .collect();
+        let mut log = format!(
+            "{{\n  \"$schema\": \"{}\",\n  \"version\": \"{}\",\n  \"runs\": [\n    {{\n",
+            SCHEMA, VERSION
+        );
+        log.push_str(&format!(
+            "      \"tool\": {{\"driver\": {{\"name\": \"gor\", \"version\": \"{}\", \"rules\": [{}]}}}},\n",
+            env!("CARGO_PKG_VERSION"),
+            rules.join(", ")
+        ));
+        log.push_str("      \"results\": [");
+        for (index, result) in self.results.iter().enumerate() {
+            if index > 0 {
+                log.push(',');
+            }
+            log.push_str("\n        ");
+            log.push_str(result);
+        }
+        if !self.results.is_empty() {
+            log.push_str("\n      ");
+        }
+        log.push_str("]\n    }\n  ]\n}\n");
+        log
+    }
+}
+
+/// Where a result is: its file, relative to where gor was run unless it's absolute, and the
+/// span of it, if it's in one. SARIF counts columns from 1, and a region's end column is
+/// the one after it.
+fn physical_location(path: Option<&Path>, position: Option<Position>) -> String {
+    let mut location = String::new();
+    if let Some(path) = path {
+        let artifact = match path.is_absolute() {
+            true => format!("{{\"uri\": {}}}", json_string(&uri(path))),
+            false => format!(
+                "{{\"uri\": {}, \"uriBaseId\": \"%SRCROOT%\"}}",
+                json_string(&uri(path))
+            ),
+        };
+        location.push_str(&format!("\"artifactLocation\": {}", artifact));
+    }
+    if let Some(position) = position {
+        if !location.is_empty() {
+            location.push_str(", ");
+        }
+        location.push_str(&format!(
+            "\"region\": {{\"startLine\": {}, \"startColumn\": {}, \"endColumn\": {}}}",
+            position.line,
+            position.column_start + 1,
+            position.column_end.max(position.column_start) + 1
+        ));
+    }
+    format!("{{{}}}", location)
+}
+
+/// A path as a URI reference, with `/` between its components and the characters a URI
+/// can't hold percent-encoded. An absolute path is a `file` URI.
+pub(crate) fn uri(path: &Path) -> String {
+    let text = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::new();
+    for byte in text.trim_start_matches("./").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            byte => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    match path.is_absolute() {
+        true => format!("file://{}", uri),
+        false => uri,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sarif_log() {
+        let mut sarif = Sarif::new();
+        let path = Path::new("./cmd/app/main go.go");
+        let diagnostic = Diagnostic::error("undefined: x".to_string(), Position::new(4, 6, 7))
+            .with_code("names")
+            .with_explanation("E0101")
+            .with_note("declared here".to_string(), Position::new(2, 1, 2));
+        sarif.add(&diagnostic, |position| (Some(path), position));
+        sarif.add_file("load", "no Go files", Path::new("/tmp/app"));
+        let log: serde_json::Value = serde_json::from_str(&sarif.finish()).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "gor");
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            serde_json::json!([{"id": "E0101"}, {"id": "load"}])
+        );
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "E0101");
+        assert_eq!(result["properties"]["check"], "names");
+        assert_eq!(result["level"], "error");
+        assert_eq!(result["message"]["text"], "undefined: x");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "cmd/app/main%20go.go");
+        assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+        assert_eq!(
+            location["region"],
+            serde_json::json!({"startLine": 4, "startColumn": 7, "endColumn": 8})
+        );
+        assert_eq!(
+            result["relatedLocations"][0]["message"]["text"],
+            "declared here"
+        );
+        let result = &run["results"][1];
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "file:///tmp/app");
+        assert!(location.get("region").is_none());
+
+        let empty: serde_json::Value = serde_json::from_str(&Sarif::new().finish()).unwrap();
+        assert_eq!(empty["runs"][0]["results"], serde_json::json!([]));
+    }
+}
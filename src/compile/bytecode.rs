@@ -0,0 +1,235 @@
+use crate::ast::expression::Expression;
+use crate::eval::value::Value;
+use crate::primitives::position::Position;
+
+/// One step of a function's bytecode, which works on a stack of operands. Instructions that
+/// can fail or that do what an expression says refer to the expression in the program's
+/// `operations`, for the error to name it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    /// Push the program's constant at the index
+    Constant(u32),
+    Pop,
+    LoadLocal(u32),
+    StoreLocal(u32),
+    LoadGlobal(u32),
+    StoreGlobal(u32),
+    // Integer arithmetic, which wraps around as Go's does
+    AddInt,
+    SubtractInt,
+    MultiplyInt,
+    DivideInt(u32),
+    RemainderInt(u32),
+    AddFloat,
+    SubtractFloat,
+    MultiplyFloat,
+    DivideFloat,
+    Concatenate,
+    EqualInt,
+    NotEqualInt,
+    LessInt,
+    LessEqualInt,
+    GreaterInt,
+    GreaterEqualInt,
+    /// The binary operation of operation at the index, on operands of any type, for those
+    /// whose types the checker couldn't tell
+    Binary(u32),
+    NegateInt,
+    NegateFloat,
+    Not,
+    Complement,
+    IntToFloat,
+    FloatToInt,
+    Jump(u32),
+    /// Pop a bool, and jump if it's false
+    JumpIfFalse(u32),
+    /// Call the program's function at the index with the arguments on top of the stack, which
+    /// become its first locals
+    Call {
+        function: u32,
+        arguments: u32,
+    },
+    /// Call the native package function at the index of the program's `natives`
+    CallNative {
+        native: u32,
+        arguments: u32,
+    },
+    /// Return the values on top of the stack to the caller
+    Return(u32),
+    Len,
+    Cap,
+    /// Append the values on top of the stack to the slice below them
+    Append(u32),
+    /// Append the elements of the slice on top of the stack to the slice below it
+    AppendSlice,
+    /// Make a slice like the constant `slice`, which is a nil slice of its type, of the
+    /// length on top of the stack, or the length and capacity, with each element the
+    /// constant `zero`
+    MakeSlice {
+        slice: u32,
+        zero: u32,
+        arguments: u32,
+    },
+    /// Make a slice like the constant `slice` of the values on top of the stack
+    SliceLiteral {
+        slice: u32,
+        elements: u32,
+    },
+    /// Index the slice or string below the index on top of the stack
+    Index(u32),
+    /// Set the element of the slice below the index and the value on top of the stack
+    StoreIndex(u32),
+    /// Panic with the value on top of the stack, as the call to panic at the index does
+    Panic(u32),
+}
+
+/// A function compiled to bytecode. Its parameters are its first locals, and each
+/// instruction has the position of the code it was compiled from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    /// As a panic's trace names it, like `f`
+    pub name: String,
+    pub parameters: usize,
+    pub locals: usize,
+    pub code: Vec<Instruction>,
+    pub positions: Vec<Position>,
+}
+
+/// A call to a function of a native package, like `strings.Repeat`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Native {
+    pub path: String,
+    pub function: String,
+    /// The operation that's the call
+    pub call: u32,
+    /// How many results the call is used for, or None for a call whose results are dropped.
+    /// The checker doesn't know native functions' types, so the VM checks them as it calls.
+    pub results: Option<usize>,
+}
+
+/// A program compiled to bytecode, which runs `init`, initializing the package level
+/// variables, then `main`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub functions: Vec<Function>,
+    pub constants: Vec<Value>,
+    pub operations: Vec<Expression>,
+    pub natives: Vec<Native>,
+    pub globals: usize,
+    /// The package clause's name, which must be main for the program to run
+    pub package_name: String,
+    pub init: usize,
+    /// The main function, if the package declares one
+    pub main: Option<usize>,
+}
+
+/// Each function's instructions, one a line with its index and, where it starts a new line
+/// of the source, the line, like
+///
+/// ```text
+/// func double (1 parameters, 1 locals)
+///        0     9  LoadLocal 0
+///        1        Constant 0 (2)
+/// ```
+impl std::fmt::Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, function) in self.functions.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "func {} ({} parameters, {} locals)",
+                function.name, function.parameters, function.locals
+            )?;
+            let mut line = None;
+            for (offset, (instruction, position)) in
+                function.code.iter().zip(&function.positions).enumerate()
+            {
+                let shown = match line == Some(position.line) {
+                    true => String::new(),
+                    false => position.line.to_string(),
+                };
+                line = Some(position.line);
+                writeln!(
+                    f,
+                    "{:>8} {:>5}  {}",
+                    offset,
+                    shown,
+                    self.describe(instruction)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Program {
+    /// An instruction with what its operand refers to, like `Constant 0 (2)` or `Call 1 (f, 2
+    /// arguments)`
+    fn describe(&self, instruction: &Instruction) -> String {
+        let constant = |index: &u32| match &self.constants[*index as usize] {
+            Value::String(string) => format!("{:?}", string),
+            Value::Slice(slice) => format!("[]{}", slice.element),
+            value => value.to_string(),
+        };
+        let operation = |index: &u32| self.operations[*index as usize].to_string();
+        match instruction {
+            Instruction::Constant(index) => format!("Constant {} ({})", index, constant(index)),
+            Instruction::Binary(index) => format!("Binary {} ({})", index, operation(index)),
+            Instruction::Call {
+                function,
+                arguments,
+            } => format!(
+                "Call {} ({}, {} arguments)",
+                function, self.functions[*function as usize].name, arguments
+            ),
+            Instruction::CallNative { native, arguments } => {
+                let called = &self.natives[*native as usize];
+                format!(
+                    "CallNative {} ({}.{}, {} arguments)",
+                    native, called.path, called.function, arguments
+                )
+            }
+            Instruction::MakeSlice {
+                slice,
+                zero,
+                arguments,
+            } => format!(
+                "MakeSlice {} ({}, zero {}, {} arguments)",
+                slice,
+                constant(slice),
+                constant(zero),
+                arguments
+            ),
+            Instruction::SliceLiteral { slice, elements } => format!(
+                "SliceLiteral {} ({}, {} elements)",
+                slice,
+                constant(slice),
+                elements
+            ),
+            Instruction::DivideInt(index)
+            | Instruction::RemainderInt(index)
+            | Instruction::Index(index)
+            | Instruction::StoreIndex(index)
+            | Instruction::Panic(index) => {
+                let name = format!("{:?}", instruction);
+                let name = name.split('(').next().unwrap_or_default();
+                format!("{} {} ({})", name, index, operation(index))
+            }
+            Instruction::LoadLocal(index)
+            | Instruction::StoreLocal(index)
+            | Instruction::LoadGlobal(index)
+            | Instruction::StoreGlobal(index)
+            | Instruction::Jump(index)
+            | Instruction::JumpIfFalse(index)
+            | Instruction::Return(index)
+            | Instruction::Append(index) => {
+                let name = format!("{:?}", instruction);
+                let name = name.split('(').next().unwrap_or_default();
+                format!("{} {}", name, index)
+            }
+            other => format!("{:?}", other),
+        }
+    }
+}
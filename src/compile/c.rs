@@ -0,0 +1,622 @@
+use std::collections::HashSet;
+
+use crate::compile::ir::{
+    BasicBlock, BinaryOp, Block, Constant, Function, InstructionKind, Module, Target, Terminator,
+    Type, UnaryOp, Value,
+};
+use crate::primitives::{
+    errors::compile::{CompileError, CompileErrorKind},
+    position::Position,
+};
+
+/// What every program starts with: the string type and the functions the module's code calls
+/// to print, panic, and do what a single C operator doesn't the way Go does
+const RUNTIME: &str = r#"#include <inttypes.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct {
+    const char *data;
+    int64_t length;
+} gor_string;
+
+static inline void gor_print_string(FILE *file, gor_string value) {
+    fwrite(value.data, 1, (size_t)value.length, file);
+}
+
+static inline void gor_print_int(FILE *file, int64_t value) {
+    fprintf(file, "%" PRId64, value);
+}
+
+static inline void gor_print_bool(FILE *file, bool value) {
+    fputs(value ? "true" : "false", file);
+}
+
+static inline void gor_finish_panic(void) {
+    fputs("\n\ngoroutine 1 [running]:\n", stderr);
+    exit(2);
+}
+
+static inline void gor_runtime_panic(const char *message) {
+    fflush(stdout);
+    fprintf(stderr, "panic: runtime error: %s", message);
+    gor_finish_panic();
+}
+
+static inline gor_string gor_concatenate(gor_string left, gor_string right) {
+    char *data = malloc((size_t)(left.length + right.length) + 1);
+    if (data == NULL) {
+        gor_runtime_panic("out of memory");
+    }
+    memcpy(data, left.data, (size_t)left.length);
+    memcpy(data + left.length, right.data, (size_t)right.length);
+    return (gor_string){data, left.length + right.length};
+}
+
+static inline int gor_compare(gor_string left, gor_string right) {
+    int64_t shorter = left.length < right.length ? left.length : right.length;
+    int order = memcmp(left.data, right.data, (size_t)shorter);
+    if (order != 0) {
+        return order;
+    }
+    return (left.length > right.length) - (left.length < right.length);
+}
+
+static inline int64_t gor_divide(int64_t left, int64_t right) {
+    if (right == 0) {
+        gor_runtime_panic("integer divide by zero");
+    }
+    if (right == -1) {
+        return (int64_t)(0 - (uint64_t)left);
+    }
+    return left / right;
+}
+
+static inline int64_t gor_remainder(int64_t left, int64_t right) {
+    if (right == 0) {
+        gor_runtime_panic("integer divide by zero");
+    }
+    if (right == -1) {
+        return 0;
+    }
+    return left % right;
+}
+
+static inline int64_t gor_shift_left(int64_t value, int64_t count) {
+    if (count < 0) {
+        gor_runtime_panic("negative shift amount");
+    }
+    return count < 64 ? (int64_t)((uint64_t)value << count) : 0;
+}
+
+static inline int64_t gor_shift_right(int64_t value, int64_t count) {
+    if (count < 0) {
+        gor_runtime_panic("negative shift amount");
+    }
+    return value >> (count < 63 ? count : 63);
+}
+
+static inline int64_t gor_float_to_int(double value) {
+    if (isnan(value)) {
+        return 0;
+    }
+    if (value >= 9223372036854775808.0) {
+        return INT64_MAX;
+    }
+    if (value <= -9223372036854775808.0) {
+        return INT64_MIN;
+    }
+    return (int64_t)value;
+}
+"#;
+
+/// Compile a module to a C99 program, which any hosted C compiler builds, like `cc -o hello
+/// hello.c`. It prints and panics as the WebAssembly backend's modules do: what's printed is
+/// written to standard output, and a panic to standard error before exiting with status 2.
+///
+/// Each function's values are its locals and its blocks are labels, with a block's
+/// parameters assigned before the `goto` that goes to it. The strings concatenation makes are
+/// never freed. Printing floats isn't supported yet, nor are the native packages besides
+/// fmt's Print and Println.
+pub fn generate(module: &Module) -> Result<String, CompileError> {
+    let mut source = RUNTIME.to_string();
+    if !module.globals.is_empty() {
+        source.push('\n');
+    }
+    for (index, global) in module.globals.iter().enumerate() {
+        let zero = match global.value_type {
+            Type::Int => "0",
+            Type::Float => "0.0",
+            Type::Bool => "false",
+            Type::String => "{\"\", 0}",
+        };
+        source.push_str(&format!(
+            "static {} {} = {};\n",
+            c_type(global.value_type),
+            name('g', index, &global.name),
+            zero
+        ));
+    }
+    source.push('\n');
+    for (index, function) in module.functions.iter().enumerate() {
+        if function.results.len() > 1 {
+            let fields: Vec<String> = function
+                .results
+                .iter()
+                .enumerate()
+                .map(|(field, result)| format!("{} r{};", c_type(*result), field))
+                .collect();
+            source.push_str(&format!(
+                "typedef struct {{ {} }} {};\n",
+                fields.join(" "),
+                results_type(module, index)
+            ));
+        }
+    }
+    for index in 0..module.functions.len() {
+        source.push_str(&format!("{};\n", signature(module, index)));
+    }
+    for index in 0..module.functions.len() {
+        source.push('\n');
+        source.push_str(&Body::new(module, index).function()?);
+    }
+    source.push_str("\nint main(void) {\n");
+    let init = &module.functions[module.init];
+    source.push_str(&format!("    {}();\n", name('f', module.init, &init.name)));
+    if let Some(main) = module.main {
+        let function = &module.functions[main];
+        source.push_str(&format!("    {}();\n", name('f', main, &function.name)));
+    }
+    source.push_str("    return 0;\n}\n");
+    Ok(source)
+}
+
+/// Where one of the module's functions is compiled
+struct Body<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    index: usize,
+    /// The blocks a terminator goes to, which are the only ones labelled
+    targeted: HashSet<Block>,
+    code: String,
+}
+
+impl<'a> Body<'a> {
+    fn new(module: &'a Module, index: usize) -> Body<'a> {
+        let function = &module.functions[index];
+        let targeted = function
+            .blocks
+            .iter()
+            .flat_map(|block| block.terminator.targets())
+            .map(|target| target.block)
+            .collect();
+        Body {
+            module,
+            function,
+            index,
+            targeted,
+            code: String::new(),
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        self.code.push_str("    ");
+        self.code.push_str(line);
+        self.code.push('\n');
+    }
+
+    fn type_of(&self, value: Value) -> Type {
+        self.function.type_of(value)
+    }
+
+    /// The function, each value but its parameters declared at the top
+    fn function(mut self) -> Result<String, CompileError> {
+        self.code = format!("{} {{\n", signature(self.module, self.index));
+        let entry = &self.function.blocks[0];
+        let parameters: HashSet<&Value> = entry.parameters.iter().collect();
+        for block in &self.function.blocks {
+            let results = block.instructions.iter().flat_map(|i| &i.results);
+            for value in block.parameters.iter().chain(results) {
+                if !parameters.contains(value) {
+                    self.line(&format!("{} {};", c_type(self.type_of(*value)), value));
+                }
+            }
+        }
+        for (index, block) in self.function.blocks.iter().enumerate() {
+            if self.targeted.contains(&Block(index as u32)) {
+                self.code.push_str(&format!("{}:;\n", Block(index as u32)));
+            }
+            self.block(block)?;
+        }
+        self.code.push_str("}\n");
+        Ok(self.code)
+    }
+
+    fn block(&mut self, block: &BasicBlock) -> Result<(), CompileError> {
+        for instruction in &block.instructions {
+            self.instruction(
+                &instruction.kind,
+                &instruction.results,
+                instruction.position,
+            )?;
+        }
+        match &block.terminator {
+            Terminator::Jump(target) => self.jump(target),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.line(&format!("if ({}) {{", condition));
+                self.jump(then);
+                self.line("} else {");
+                self.jump(otherwise);
+                self.line("}");
+            }
+            Terminator::Return(values) => match values.as_slice() {
+                [] => self.line("return;"),
+                [value] => self.line(&format!("return {};", value)),
+                values => {
+                    let results = results_type(self.module, self.index);
+                    self.line(&format!("return ({}){{{}}};", results, join(values)));
+                }
+            },
+            Terminator::Panic { value, position } => {
+                self.line("fflush(stdout);");
+                self.line("fputs(\"panic: \", stderr);");
+                self.print("stderr", *value, *position)?;
+                self.line("gor_finish_panic();");
+            }
+        }
+        Ok(())
+    }
+
+    /// Pass the target its arguments and go to it. They're copied to temporaries first, since
+    /// a block can pass its own parameters to itself in another order.
+    fn jump(&mut self, target: &Target) {
+        let parameters = &self.function.block(target.block).parameters;
+        let mut copies = Vec::new();
+        for (index, argument) in target.arguments.iter().enumerate() {
+            copies.push(format!(
+                "{} t{} = {};",
+                c_type(self.type_of(*argument)),
+                index,
+                argument
+            ));
+        }
+        for (index, parameter) in parameters.iter().enumerate() {
+            copies.push(format!("{} = t{};", parameter, index));
+        }
+        copies.push(format!("goto {};", target.block));
+        match copies.len() {
+            1 => self.line(&copies[0]),
+            _ => self.line(&format!("{{ {} }}", copies.join(" "))),
+        }
+    }
+
+    fn instruction(
+        &mut self,
+        instruction: &InstructionKind,
+        results: &[Value],
+        position: Position,
+    ) -> Result<(), CompileError> {
+        let expression = match instruction {
+            InstructionKind::Const(constant) => constant_expression(constant),
+            InstructionKind::Copy(value) => value.to_string(),
+            InstructionKind::Binary(operation, left, right) => {
+                self.binary(*operation, *left, *right, position)?
+            }
+            InstructionKind::Unary(operation, value) => match (operation, self.type_of(*value)) {
+                (UnaryOp::Negate, Type::Int) => format!("(int64_t)(0 - (uint64_t){})", value),
+                (UnaryOp::Negate, Type::Float) => format!("-{}", value),
+                (UnaryOp::Not, Type::Bool) => format!("!{}", value),
+                (UnaryOp::Complement, Type::Int) => format!("~{}", value),
+                (operation, value_type) => {
+                    let code = format!("{} on {}", operation.name(), value_type);
+                    return Err(unsupported(&code, position));
+                }
+            },
+            InstructionKind::Convert(value) => match self.type_of(*value) {
+                Type::Int => format!("(double){}", value),
+                _ => format!("gor_float_to_int({})", value),
+            },
+            InstructionKind::Len(value) => format!("{}.length", value),
+            InstructionKind::Call {
+                function,
+                arguments,
+            } => {
+                let called = &self.module.functions[*function];
+                let call = format!(
+                    "{}({})",
+                    name('f', *function, &called.name),
+                    join(arguments)
+                );
+                if results.len() > 1 {
+                    let assigned: Vec<String> = results
+                        .iter()
+                        .enumerate()
+                        .map(|(field, result)| format!("{} = r.r{};", result, field))
+                        .collect();
+                    let results_type = results_type(self.module, *function);
+                    let line = format!(
+                        "{{ {} r = {}; {} }}",
+                        results_type,
+                        call,
+                        assigned.join(" ")
+                    );
+                    self.line(&line);
+                    return Ok(());
+                }
+                call
+            }
+            InstructionKind::CallNative {
+                path,
+                function,
+                arguments,
+            } => match (path.as_str(), function.as_str()) {
+                ("fmt", "Println" | "Print") => {
+                    let line = function == "Println";
+                    let function = self.function;
+                    let is_string = |value: &Value| function.type_of(*value) == Type::String;
+                    for (index, argument) in arguments.iter().enumerate() {
+                        // Print only spaces operands when neither is a string
+                        let spaced = match index {
+                            0 => false,
+                            _ => line || !(is_string(argument) || is_string(&arguments[index - 1])),
+                        };
+                        if spaced {
+                            self.line("putchar(' ');");
+                        }
+                        self.print("stdout", *argument, position)?;
+                    }
+                    if line {
+                        self.line("putchar('\\n');");
+                    }
+                    return Ok(());
+                }
+                _ => return Err(unsupported(&format!("{}.{}", path, function), position)),
+            },
+            InstructionKind::LoadGlobal(global) => {
+                name('g', *global, &self.module.globals[*global].name)
+            }
+            InstructionKind::StoreGlobal(global, value) => {
+                let global = name('g', *global, &self.module.globals[*global].name);
+                self.line(&format!("{} = {};", global, value));
+                return Ok(());
+            }
+        };
+        match results {
+            [result] => self.line(&format!("{} = {};", result, expression)),
+            _ => self.line(&format!("{};", expression)),
+        }
+        Ok(())
+    }
+
+    /// Print a value to the file
+    fn print(&mut self, file: &str, value: Value, position: Position) -> Result<(), CompileError> {
+        let function = match self.type_of(value) {
+            Type::Int => "gor_print_int",
+            Type::Bool => "gor_print_bool",
+            Type::String => "gor_print_string",
+            Type::Float => return Err(unsupported("printing floats", position)),
+        };
+        self.line(&format!("{}({}, {});", function, file, value));
+        Ok(())
+    }
+
+    /// The operation applied to the operands, as an expression
+    fn binary(
+        &self,
+        operation: BinaryOp,
+        left: Value,
+        right: Value,
+        position: Position,
+    ) -> Result<String, CompileError> {
+        use BinaryOp::*;
+        let operand_type = self.type_of(left);
+        let call = |function: &str| format!("{}({}, {})", function, left, right);
+        let wrapping = |operator: &str| {
+            format!(
+                "(int64_t)((uint64_t){} {} (uint64_t){})",
+                left, operator, right
+            )
+        };
+        let expression = match (operand_type, operation) {
+            (Type::Int, Add) => wrapping("+"),
+            (Type::Int, Subtract) => wrapping("-"),
+            (Type::Int, Multiply) => wrapping("*"),
+            (Type::Int, Divide) => call("gor_divide"),
+            (Type::Int, Remainder) => call("gor_remainder"),
+            (Type::Int, ShiftLeft) => call("gor_shift_left"),
+            (Type::Int, ShiftRight) => call("gor_shift_right"),
+            (Type::Int, AndNot) => format!("{} & ~{}", left, right),
+            (Type::String, Add) => call("gor_concatenate"),
+            (Type::String, comparison) if comparison.is_comparison() => {
+                format!("{} {} 0", call("gor_compare"), c_operator(comparison))
+            }
+            (Type::Int, And | Or | Xor)
+            | (Type::Int | Type::Float, Equal | NotEqual)
+            | (Type::Int | Type::Float, Less | LessEqual | Greater | GreaterEqual)
+            | (Type::Float, Add | Subtract | Multiply | Divide)
+            | (Type::Bool, Equal | NotEqual) => {
+                format!("{} {} {}", left, c_operator(operation), right)
+            }
+            _ => {
+                let code = format!("{} on {}", operation.name(), operand_type);
+                return Err(unsupported(&code, position));
+            }
+        };
+        Ok(expression)
+    }
+}
+
+/// A function's declaration, like `static int64_t f1_double(int64_t v0)`
+fn signature(module: &Module, index: usize) -> String {
+    let function = &module.functions[index];
+    let result = match function.results.as_slice() {
+        [] => "void".to_string(),
+        [result] => c_type(*result).to_string(),
+        _ => results_type(module, index),
+    };
+    let parameters: Vec<String> = match function.parameters.is_empty() {
+        true => vec!["void".to_string()],
+        false => function
+            .parameters
+            .iter()
+            .zip(&function.blocks[0].parameters)
+            .map(|(parameter_type, value)| format!("{} {}", c_type(*parameter_type), value))
+            .collect(),
+    };
+    format!(
+        "static {} {}({})",
+        result,
+        name('f', index, &function.name),
+        parameters.join(", ")
+    )
+}
+
+/// The struct a function returning more than one result returns them in
+fn results_type(module: &Module, index: usize) -> String {
+    format!(
+        "{}_results",
+        name('f', index, &module.functions[index].name)
+    )
+}
+
+/// A function's or global's name in C, prefixed with its index so it can't clash with another
+/// or C's own, like `f1_double`. Anything but an ASCII letter or digit becomes `_`.
+fn name(prefix: char, index: usize, name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    format!("{}{}_{}", prefix, index, name)
+}
+
+fn c_type(value_type: Type) -> &'static str {
+    match value_type {
+        Type::Int => "int64_t",
+        Type::Float => "double",
+        Type::Bool => "bool",
+        Type::String => "gor_string",
+    }
+}
+
+fn c_operator(operation: BinaryOp) -> &'static str {
+    use BinaryOp::*;
+    match operation {
+        Add => "+",
+        Subtract => "-",
+        Multiply => "*",
+        Divide => "/",
+        Remainder => "%",
+        And => "&",
+        Or => "|",
+        Xor => "^",
+        AndNot => "&~",
+        ShiftLeft => "<<",
+        ShiftRight => ">>",
+        Equal => "==",
+        NotEqual => "!=",
+        Less => "<",
+        LessEqual => "<=",
+        Greater => ">",
+        GreaterEqual => ">=",
+    }
+}
+
+fn constant_expression(constant: &Constant) -> String {
+    match constant {
+        // The smallest int's literal would be the negation of one too large for an int64_t
+        Constant::Int(i64::MIN) => "INT64_MIN".to_string(),
+        Constant::Int(value) => format!("INT64_C({})", value),
+        Constant::Float(value) if value.is_nan() => "NAN".to_string(),
+        Constant::Float(value) if value.is_infinite() && *value > 0.0 => "HUGE_VAL".to_string(),
+        Constant::Float(value) if value.is_infinite() => "-HUGE_VAL".to_string(),
+        Constant::Float(value) => format!("{:?}", value),
+        Constant::Bool(value) => value.to_string(),
+        Constant::String(value) => {
+            format!("(gor_string){{{}, {}}}", string_literal(value), value.len())
+        }
+    }
+}
+
+/// A string as a C literal, with every byte but printable ASCII escaped in octal, which is
+/// never more than three digits so a digit after it can't be mistaken for part of it. A `?`
+/// is escaped too, so it can't start a trigraph.
+fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in value.bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            b' '..=b'~' if byte != b'?' => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn join(values: &[Value]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn unsupported(code: &str, position: Position) -> CompileError {
+    CompileError::new(CompileErrorKind::Unsupported(code.to_string()), position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::lower::lower;
+    use crate::parser::parser::Parser;
+
+    fn generate_str(source: &str) -> Result<String, CompileError> {
+        generate(&lower(&Parser::new(source).parse().unwrap())?)
+    }
+
+    #[test]
+    fn generate_program() {
+        let source = "package main\n\nimport \"fmt\"\n\nvar name = \"gor\"\n\nfunc divide(a, b int) (int, int) {\n\treturn a / b, a % b\n}\n\nfunc main() {\n\tq, r := divide(len(name)*7, 4)\n\ti := 0\n\tfor i < q {\n\t\tfmt.Println(name+\"?\", i, r, i == 2)\n\t\ti++\n\t}\n}\n";
+        let program = generate_str(source).unwrap();
+        assert!(program.starts_with("#include <inttypes.h>\n"));
+        assert!(program.contains("static gor_string g0_name = {\"\", 0};\n"));
+        assert!(program.contains("static f1_divide_results f1_divide(int64_t v0, int64_t v1);\n"));
+        assert!(program.contains("(gor_string){\"\\077\", 1}"));
+        assert!(program.contains("gor_divide(v0, v1)"));
+        assert!(program.contains("int main(void) {\n    f0_init();\n    f2_main();\n"));
+    }
+
+    #[test]
+    fn generate_errors() {
+        let error = |source: &str| generate_str(source).unwrap_err().kind;
+        assert_eq!(
+            error("package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(1.5)\n}\n"),
+            CompileErrorKind::Unsupported("printing floats".to_string())
+        );
+        assert_eq!(
+            error("package main\n\nimport \"os\"\n\nfunc main() {\n\tos.Exit(3)\n}\n"),
+            CompileErrorKind::Unsupported("os.Exit".to_string())
+        );
+    }
+
+    #[test]
+    fn escape_strings() {
+        assert_eq!(string_literal("hi \"gor\"\n"), "\"hi \\\"gor\\\"\\012\"");
+        assert_eq!(string_literal("é1??="), "\"\\303\\2511\\077\\077=\"");
+    }
+}
@@ -0,0 +1,1285 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::{
+    ast::File,
+    declaration::{Declaration, DeclarationKind, Parameter, VariableSpec},
+    expression::{Expression, ExpressionKind, UnaryOperator},
+    statement::{Statement, StatementKind},
+    type_expression::TypeExpression,
+};
+use crate::compile::bytecode::{Function, Instruction, Native, Program};
+use crate::eval::{stdlib, value::Slice, value::Value};
+use crate::lexer::{literal::unquote_string, token_type::Operator};
+use crate::primitives::{
+    errors::compile::{CompileError, CompileErrorKind},
+    position::Position,
+};
+use crate::semantic::{
+    builtins::Builtin, checker::check, info::TypeInfo, scope::SymbolId, scope::SymbolKind,
+};
+use crate::types::{
+    constant::Constant,
+    types::{BasicKind, Type, TypeId},
+};
+
+/// The name of the function initializing the package level variables
+const INIT: &str = "init";
+
+/// Check `file` and compile it to bytecode. Only part of Go compiles so far: ints, floats,
+/// strings, bools and slices of them, functions, the if and for statements, and calls to the
+/// native packages. Anything else is an `Unsupported` error naming it.
+pub fn compile(file: &File) -> Result<Program, CompileError> {
+    let (info, errors) = check(file);
+    if let Some(error) = errors.into_iter().next() {
+        return Err(CompileError::new(
+            CompileErrorKind::Check(Box::new(error.kind)),
+            error.position,
+        ));
+    }
+    Compiler::new(file, &info).compile_file(file)
+}
+
+/// What the compiler knows of a value's type: enough to pick an instruction for it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Int,
+    Float,
+    String,
+    Bool,
+    /// A slice, or a type the compiler leaves to the VM to tell, like a native function's
+    /// result's
+    Other,
+}
+
+/// Where a variable lives
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Local(u32),
+    Global(u32),
+}
+
+/// The jumps out of a loop being compiled, to patch once their targets are known
+#[derive(Default)]
+struct Loop {
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+/// A function a call can be compiled to
+#[derive(Clone)]
+struct Callee {
+    index: u32,
+    results: usize,
+    /// Each parameter's kind, for untyped constants passed to it
+    parameters: Vec<Kind>,
+}
+
+/// A function being compiled
+#[derive(Default)]
+struct Body {
+    code: Vec<Instruction>,
+    positions: Vec<Position>,
+    locals: HashMap<SymbolId, u32>,
+    local_count: usize,
+    loops: Vec<Loop>,
+    /// The named results' locals, which a bare return returns
+    named_results: Vec<u32>,
+    /// The result types, for untyped constants returned
+    result_kinds: Vec<Kind>,
+}
+
+struct Compiler<'a> {
+    info: &'a TypeInfo,
+    /// Imported packages' paths, by the name they're referred to by
+    imports: HashMap<String, String>,
+    functions: HashMap<String, Callee>,
+    globals: HashMap<SymbolId, u32>,
+    program: Program,
+    body: Body,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(file: &File, info: &'a TypeInfo) -> Compiler<'a> {
+        Compiler {
+            info,
+            imports: file
+                .imports
+                .iter()
+                .map(|import| (import.local_name(), import.unquoted_path().to_string()))
+                .collect(),
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            program: Program {
+                functions: Vec::new(),
+                constants: Vec::new(),
+                operations: Vec::new(),
+                natives: Vec::new(),
+                globals: 0,
+                package_name: file.package_name.clone(),
+                init: 0,
+                main: None,
+            },
+            body: Body::default(),
+        }
+    }
+
+    fn compile_file(mut self, file: &File) -> Result<Program, CompileError> {
+        // Every function gets its index before any is compiled, so calls can go either way
+        let mut functions = Vec::new();
+        let mut variables = Vec::new();
+        for declaration in &file.decls {
+            match &declaration.kind {
+                DeclarationKind::Function {
+                    receiver: Some(_), ..
+                } => return Err(unsupported("methods", declaration.position_start)),
+                DeclarationKind::Function { name, .. } if name == INIT => {
+                    return Err(unsupported("init functions", declaration.position_start));
+                }
+                DeclarationKind::Function { name, results, .. } => {
+                    let callee = Callee {
+                        index: functions.len() as u32 + 1,
+                        results: count_names(results),
+                        parameters: self.parameter_kinds(declaration),
+                    };
+                    self.functions.insert(name.clone(), callee);
+                    functions.push(declaration);
+                }
+                DeclarationKind::Variable { specs, .. } => variables.extend(specs),
+                DeclarationKind::Constant { .. } => {
+                    return Err(unsupported(
+                        "constant declarations",
+                        declaration.position_start,
+                    ));
+                }
+                DeclarationKind::Type { .. } => {
+                    return Err(unsupported("type declarations", declaration.position_start));
+                }
+            }
+        }
+        for spec in &variables {
+            for name in &spec.names {
+                if let Some(symbol) = self.info.symbol_of(name) {
+                    self.globals.insert(symbol, self.program.globals as u32);
+                    self.program.globals += 1;
+                }
+            }
+        }
+
+        // Package level variables are initialized in the order they're declared, as the
+        // interpreter does
+        let end = variables
+            .last()
+            .map_or(Position::new(1, 0, 0), |spec| spec.position_start);
+        for spec in variables {
+            self.variable_spec(spec)?;
+        }
+        let init = self.finish_function(INIT, 0, end);
+        self.program.functions.push(init);
+        for function in functions {
+            let compiled = self.function(function)?;
+            self.program.functions.push(compiled);
+        }
+        self.program.main = self
+            .functions
+            .get("main")
+            .map(|callee| callee.index as usize);
+        Ok(self.program)
+    }
+
+    fn function(&mut self, function: &Declaration) -> Result<Function, CompileError> {
+        let DeclarationKind::Function {
+            name,
+            parameters,
+            results,
+            body,
+            ..
+        } = &function.kind
+        else {
+            unreachable!("only functions are compiled as functions");
+        };
+        for parameter in parameters {
+            if parameter.variadic {
+                return Err(unsupported("variadic functions", parameter.position_start));
+            }
+            for name in &parameter.names {
+                self.declare_local(name);
+            }
+        }
+        let parameter_count = self.body.local_count;
+        for result in results {
+            let kind = self.type_kind(&result.type_expression);
+            self.body
+                .result_kinds
+                .extend(std::iter::repeat_n(kind, result.names.len().max(1)));
+            for name in &result.names {
+                let slot = self.declare_local(name);
+                let zero = self.zero_value(&result.type_expression, result.position_start)?;
+                self.emit_constant(zero, result.position_start);
+                if let Some(slot) = slot {
+                    self.emit(Instruction::StoreLocal(slot), result.position_start);
+                    self.body.named_results.push(slot);
+                }
+            }
+        }
+        for statement in body {
+            self.statement(statement)?;
+        }
+        Ok(self.finish_function(name, parameter_count, function.position_end))
+    }
+
+    /// The function compiled so far, returning its named results if it runs off its end
+    fn finish_function(&mut self, name: &str, parameters: usize, end: Position) -> Function {
+        let named_results = self.body.named_results.clone();
+        for slot in &named_results {
+            self.emit(Instruction::LoadLocal(*slot), end);
+        }
+        self.emit(Instruction::Return(named_results.len() as u32), end);
+        let body = std::mem::take(&mut self.body);
+        Function {
+            name: name.to_string(),
+            parameters,
+            locals: body.local_count,
+            code: body.code,
+            positions: body.positions,
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), CompileError> {
+        let position = statement.position_start;
+        match &statement.kind {
+            StatementKind::Expression(expression) => match &expression.kind {
+                ExpressionKind::FunctionCall { .. } => self.call(expression, None),
+                _ => {
+                    self.expression(expression, Kind::Other)?;
+                    self.emit(Instruction::Pop, position);
+                    Ok(())
+                }
+            },
+            StatementKind::Declaration(declaration) => match &declaration.kind {
+                DeclarationKind::Variable { specs, .. } => {
+                    for spec in specs {
+                        self.variable_spec(spec)?;
+                    }
+                    Ok(())
+                }
+                DeclarationKind::Constant { .. } => {
+                    Err(unsupported("constant declarations", position))
+                }
+                _ => Err(unsupported("local functions and types", position)),
+            },
+            StatementKind::Assignment { left, right, .. } => self.assignment(left, right, position),
+            StatementKind::IncDec { .. } | StatementKind::OpAssign { .. } => {
+                let Some((target, value)) = statement.updated_value() else {
+                    unreachable!("inc/dec and op-assign statements update their target");
+                };
+                self.assignment(std::slice::from_ref(target), &[value], position)
+            }
+            StatementKind::Block(statements) => {
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+                Ok(())
+            }
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => {
+                if let Some(init) = init {
+                    self.statement(init)?;
+                }
+                self.expression(condition, Kind::Bool)?;
+                let to_else = self.emit(Instruction::JumpIfFalse(0), position);
+                for statement in body {
+                    self.statement(statement)?;
+                }
+                match else_branch {
+                    Some(else_branch) => {
+                        let to_end = self.emit(Instruction::Jump(0), position);
+                        self.patch(to_else);
+                        self.statement(else_branch)?;
+                        self.patch(to_end);
+                    }
+                    None => self.patch(to_else),
+                }
+                Ok(())
+            }
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                if let Some(init) = init {
+                    self.statement(init)?;
+                }
+                let start = self.body.code.len();
+                let to_end = match condition {
+                    Some(condition) => {
+                        self.expression(condition, Kind::Bool)?;
+                        Some(self.emit(Instruction::JumpIfFalse(0), position))
+                    }
+                    None => None,
+                };
+                self.loop_body(body, post.as_deref(), start, position)?;
+                if let Some(to_end) = to_end {
+                    self.patch(to_end);
+                }
+                self.finish_loop();
+                Ok(())
+            }
+            StatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => self.range(
+                key.as_ref(),
+                value.as_ref(),
+                *define,
+                expression,
+                body,
+                position,
+            ),
+            StatementKind::Return(values) => {
+                let count = match values.as_slice() {
+                    [] => {
+                        let named_results = self.body.named_results.clone();
+                        for slot in &named_results {
+                            self.emit(Instruction::LoadLocal(*slot), position);
+                        }
+                        named_results.len()
+                    }
+                    [call] if self.result_count(call).is_some_and(|count| count != 1) => {
+                        let count = self.result_count(call).unwrap_or_default();
+                        self.call(call, Some(count))?;
+                        count
+                    }
+                    values => {
+                        let kinds = self.body.result_kinds.clone();
+                        for (value, kind) in values.iter().zip(kinds) {
+                            self.expression(value, kind)?;
+                        }
+                        values.len()
+                    }
+                };
+                self.emit(Instruction::Return(count as u32), position);
+                Ok(())
+            }
+            StatementKind::Break | StatementKind::Continue => {
+                let jump = self.emit(Instruction::Jump(0), position);
+                let Some(innermost) = self.body.loops.last_mut() else {
+                    return Err(unsupported(&statement.to_string(), position));
+                };
+                match statement.kind {
+                    StatementKind::Break => innermost.breaks.push(jump),
+                    _ => innermost.continues.push(jump),
+                }
+                Ok(())
+            }
+            StatementKind::Defer(_) => Err(unsupported("defer", position)),
+            StatementKind::Go(_) => Err(unsupported("goroutines", position)),
+            StatementKind::Send { .. } | StatementKind::Select(_) => {
+                Err(unsupported("channels", position))
+            }
+            StatementKind::Switch { .. } | StatementKind::Fallthrough => {
+                Err(unsupported("switch statements", position))
+            }
+        }
+    }
+
+    /// A loop's body and its post statement, then a jump back to `start`. A continue goes to
+    /// the post statement.
+    fn loop_body(
+        &mut self,
+        body: &[Statement],
+        post: Option<&Statement>,
+        start: usize,
+        position: Position,
+    ) -> Result<(), CompileError> {
+        self.body.loops.push(Loop::default());
+        for statement in body {
+            self.statement(statement)?;
+        }
+        let continues = self
+            .body
+            .loops
+            .last_mut()
+            .map(|innermost| std::mem::take(&mut innermost.continues))
+            .unwrap_or_default();
+        let post_start = self.body.code.len();
+        for jump in continues {
+            self.patch_to(jump, post_start);
+        }
+        if let Some(post) = post {
+            self.statement(post)?;
+        }
+        self.emit(Instruction::Jump(start as u32), position);
+        Ok(())
+    }
+
+    /// Point the innermost loop's breaks past its end
+    fn finish_loop(&mut self) {
+        if let Some(innermost) = self.body.loops.pop() {
+            for jump in innermost.breaks {
+                self.patch(jump);
+            }
+        }
+    }
+
+    /// A range loop over an int or a slice, counting the index in a local of its own. Each
+    /// iteration stores the index and element in the loop's variables, and continuing counts
+    /// on to the next.
+    fn range(
+        &mut self,
+        key: Option<&Expression>,
+        value: Option<&Expression>,
+        define: bool,
+        expression: &Expression,
+        body: &[Statement],
+        position: Position,
+    ) -> Result<(), CompileError> {
+        let is_slice = self.slice_element(expression).is_some();
+        if !is_slice && self.kind(expression) != Kind::Int {
+            return Err(unsupported(
+                "range over anything but ints and slices",
+                position,
+            ));
+        }
+        let (index, limit, ranged) = (self.temporary(), self.temporary(), self.temporary());
+        self.expression(expression, Kind::Int)?;
+        self.emit(Instruction::StoreLocal(ranged), position);
+        self.emit(Instruction::LoadLocal(ranged), position);
+        if is_slice {
+            self.emit(Instruction::Len, position);
+        }
+        self.emit(Instruction::StoreLocal(limit), position);
+        self.emit_constant(Value::Int(0), position);
+        self.emit(Instruction::StoreLocal(index), position);
+
+        let start = self.body.code.len();
+        self.emit(Instruction::LoadLocal(index), position);
+        self.emit(Instruction::LoadLocal(limit), position);
+        self.emit(Instruction::LessInt, position);
+        let to_end = self.emit(Instruction::JumpIfFalse(0), position);
+        if let Some(key) = key {
+            self.emit(Instruction::LoadLocal(index), position);
+            self.store(key, define, position)?;
+        }
+        if let Some(value) = value {
+            let operation = self.operation(expression);
+            self.emit(Instruction::LoadLocal(ranged), position);
+            self.emit(Instruction::LoadLocal(index), position);
+            self.emit(Instruction::Index(operation), position);
+            self.store(value, define, position)?;
+        }
+
+        // Continuing jumps to the increment rather than back to the condition
+        self.body.loops.push(Loop::default());
+        for statement in body {
+            self.statement(statement)?;
+        }
+        let increment = self.body.code.len();
+        let continues = self
+            .body
+            .loops
+            .last_mut()
+            .map(|innermost| std::mem::take(&mut innermost.continues))
+            .unwrap_or_default();
+        for jump in continues {
+            self.patch_to(jump, increment);
+        }
+        self.emit(Instruction::LoadLocal(index), position);
+        self.emit_constant(Value::Int(1), position);
+        self.emit(Instruction::AddInt, position);
+        self.emit(Instruction::StoreLocal(index), position);
+        self.emit(Instruction::Jump(start as u32), position);
+        self.patch(to_end);
+        self.finish_loop();
+        Ok(())
+    }
+
+    fn variable_spec(&mut self, spec: &VariableSpec) -> Result<(), CompileError> {
+        let position = spec.position_start;
+        if spec.values.is_empty() {
+            let Some(type_expression) = &spec.type_expression else {
+                return Err(unsupported(&spec.to_string(), position));
+            };
+            for name in &spec.names {
+                let zero = self.zero_value(type_expression, position)?;
+                self.emit_constant(zero, position);
+                self.store(name, true, position)?;
+            }
+            return Ok(());
+        }
+        self.assignment(&spec.names, &spec.values, position)
+    }
+
+    /// Assign `right`'s values to `left`, every value evaluated before any is assigned. A
+    /// name a `:=` or `var` declares gets a local of its own.
+    fn assignment(
+        &mut self,
+        left: &[Expression],
+        right: &[Expression],
+        position: Position,
+    ) -> Result<(), CompileError> {
+        match (left, right) {
+            ([target], [value]) => {
+                if let ExpressionKind::Index { object, index } = &target.kind {
+                    let operation = self.operation(target);
+                    self.expression(object, Kind::Other)?;
+                    self.expression(index, Kind::Int)?;
+                    self.expression(value, self.kind(target))?;
+                    self.emit(Instruction::StoreIndex(operation), position);
+                    return Ok(());
+                }
+                self.expression(value, self.kind(target))?;
+                self.store(target, true, position)
+            }
+            (targets, [call]) if targets.len() > 1 => {
+                self.call(call, Some(targets.len()))?;
+                self.store_all(targets, position)
+            }
+            (targets, values) => {
+                for (target, value) in targets.iter().zip(values) {
+                    self.expression(value, self.kind(target))?;
+                }
+                self.store_all(targets, position)
+            }
+        }
+    }
+
+    /// Store the values on top of the stack in `targets`, the last value in the last target
+    fn store_all(
+        &mut self,
+        targets: &[Expression],
+        position: Position,
+    ) -> Result<(), CompileError> {
+        let temporaries: Vec<u32> = targets.iter().map(|_| self.temporary()).collect();
+        for temporary in temporaries.iter().rev() {
+            self.emit(Instruction::StoreLocal(*temporary), position);
+        }
+        for (target, temporary) in targets.iter().zip(temporaries) {
+            match &target.kind {
+                ExpressionKind::Index { object, index } => {
+                    let operation = self.operation(target);
+                    self.expression(object, Kind::Other)?;
+                    self.expression(index, Kind::Int)?;
+                    self.emit(Instruction::LoadLocal(temporary), position);
+                    self.emit(Instruction::StoreIndex(operation), position);
+                }
+                _ => {
+                    self.emit(Instruction::LoadLocal(temporary), position);
+                    self.store(target, true, position)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Store the value on top of the stack in the variable `target` names, declaring it if
+    /// `target` declares it and `define` allows
+    fn store(
+        &mut self,
+        target: &Expression,
+        define: bool,
+        position: Position,
+    ) -> Result<(), CompileError> {
+        let slot = match &target.kind {
+            ExpressionKind::BlankIdentifier => None,
+            ExpressionKind::Identifier(name) if name == "_" => None,
+            ExpressionKind::Identifier(_) => match self.slot(target) {
+                Some(slot) => Some(slot),
+                None if define => self.declare_local(target).map(Slot::Local),
+                None => return Err(unsupported(&target.to_string(), position)),
+            },
+            _ => return Err(unsupported(&format!("assigning to {}", target), position)),
+        };
+        let instruction = match slot {
+            None => Instruction::Pop,
+            Some(Slot::Local(slot)) => Instruction::StoreLocal(slot),
+            Some(Slot::Global(slot)) => Instruction::StoreGlobal(slot),
+        };
+        self.emit(instruction, position);
+        Ok(())
+    }
+
+    /// Push `expression`'s value. An untyped constant is made a value of the kind `hint`
+    /// when that's a numeric kind, as it would be converted to its context's type.
+    fn expression(&mut self, expression: &Expression, hint: Kind) -> Result<(), CompileError> {
+        let position = expression.position_start;
+        match &expression.kind {
+            ExpressionKind::IntegerLiteral(_)
+            | ExpressionKind::FloatLiteral(_)
+            | ExpressionKind::RuneLiteral(_) => {
+                let value = self.constant(expression, hint)?;
+                self.emit_constant(value, position);
+            }
+            ExpressionKind::StringLiteral(literal) => {
+                let Some(value) = unquote_string(literal) else {
+                    return Err(unsupported(literal, position));
+                };
+                self.emit_constant(Value::String(value), position);
+            }
+            ExpressionKind::Identifier(name) => match self.slot(expression) {
+                Some(Slot::Local(slot)) => {
+                    self.emit(Instruction::LoadLocal(slot), position);
+                }
+                Some(Slot::Global(slot)) => {
+                    self.emit(Instruction::LoadGlobal(slot), position);
+                }
+                None => {
+                    let value = match name.as_str() {
+                        "true" => Value::Bool(true),
+                        "false" => Value::Bool(false),
+                        "nil" => Value::Nil,
+                        _ => {
+                            return Err(unsupported(
+                                &format!("using {} as a value", name),
+                                position,
+                            ));
+                        }
+                    };
+                    self.emit_constant(value, position);
+                }
+            },
+            ExpressionKind::Parenthesized(inner) => self.expression(inner, hint)?,
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => self.binary(expression, left, *operator, right, hint)?,
+            ExpressionKind::Unary { operator, operand } => {
+                let kind = match self.kind(expression) {
+                    Kind::Other => hint,
+                    kind => kind,
+                };
+                self.expression(operand, kind)?;
+                let instruction = match (operator, kind) {
+                    (UnaryOperator::Plus, Kind::Int | Kind::Float) => return Ok(()),
+                    (UnaryOperator::Minus, Kind::Int) => Instruction::NegateInt,
+                    (UnaryOperator::Minus, Kind::Float) => Instruction::NegateFloat,
+                    (UnaryOperator::Not, _) => Instruction::Not,
+                    (UnaryOperator::Complement, Kind::Int) => Instruction::Complement,
+                    _ => return Err(unsupported(&expression.to_string(), position)),
+                };
+                self.emit(instruction, position);
+            }
+            ExpressionKind::FunctionCall { .. } => self.call(expression, Some(1))?,
+            ExpressionKind::Index { object, index } => {
+                let operation = self.operation(expression);
+                self.expression(object, Kind::Other)?;
+                self.expression(index, Kind::Int)?;
+                self.emit(Instruction::Index(operation), position);
+            }
+            ExpressionKind::CompositeLiteral {
+                type_expression: Some(_),
+                elements,
+            } if elements.iter().all(|element| element.key.is_none()) => {
+                let (Some(slice), Some(element)) =
+                    (self.nil_slice(expression), self.slice_element(expression))
+                else {
+                    return Err(unsupported(&expression.to_string(), position));
+                };
+                for element_value in elements {
+                    self.expression(&element_value.value, element)?;
+                }
+                let slice = self.add_constant(slice);
+                self.emit(
+                    Instruction::SliceLiteral {
+                        slice,
+                        elements: elements.len() as u32,
+                    },
+                    position,
+                );
+            }
+            ExpressionKind::FunctionLiteral { .. } => {
+                return Err(unsupported("function literals", position));
+            }
+            _ => return Err(unsupported(&expression.to_string(), position)),
+        }
+        Ok(())
+    }
+
+    fn binary(
+        &mut self,
+        expression: &Expression,
+        left: &Expression,
+        operator: Operator,
+        right: &Expression,
+        hint: Kind,
+    ) -> Result<(), CompileError> {
+        let position = expression.position_start;
+        // && and || only evaluate their right operand if they need to
+        if matches!(operator, Operator::AndAnd | Operator::PipePipe) {
+            self.expression(left, Kind::Bool)?;
+            let to_short_circuit = match operator {
+                Operator::AndAnd => self.emit(Instruction::JumpIfFalse(0), position),
+                _ => {
+                    self.emit(Instruction::Not, position);
+                    self.emit(Instruction::JumpIfFalse(0), position)
+                }
+            };
+            self.expression(right, Kind::Bool)?;
+            let to_end = self.emit(Instruction::Jump(0), position);
+            self.patch(to_short_circuit);
+            self.emit_constant(Value::Bool(operator == Operator::PipePipe), position);
+            self.patch(to_end);
+            return Ok(());
+        }
+
+        let is_comparison = matches!(
+            operator,
+            Operator::EqualEqual
+                | Operator::BangEqual
+                | Operator::Less
+                | Operator::LessEqual
+                | Operator::Greater
+                | Operator::GreaterEqual
+        );
+        // A comparison's operands are of the typed operand's kind, and an operation's of its
+        // own, unless it's an untyped constant
+        let kind = match is_comparison {
+            true => self.operand_kind(left, right),
+            false if self.is_untyped(expression) => match (self.kind(expression), hint) {
+                (Kind::Int, Kind::Float) => Kind::Float,
+                (kind, _) => kind,
+            },
+            false => self.kind(expression),
+        };
+        let is_shift = matches!(operator, Operator::LessLess | Operator::GreaterGreater);
+        self.expression(left, kind)?;
+        self.expression(right, if is_shift { Kind::Int } else { kind })?;
+        let instruction = match (kind, operator) {
+            (Kind::Int, Operator::Plus) => Instruction::AddInt,
+            (Kind::Int, Operator::Minus) => Instruction::SubtractInt,
+            (Kind::Int, Operator::Star) => Instruction::MultiplyInt,
+            (Kind::Int, Operator::Slash) => Instruction::DivideInt(self.operation(expression)),
+            (Kind::Int, Operator::Percent) => Instruction::RemainderInt(self.operation(expression)),
+            (Kind::Int, Operator::EqualEqual) => Instruction::EqualInt,
+            (Kind::Int, Operator::BangEqual) => Instruction::NotEqualInt,
+            (Kind::Int, Operator::Less) => Instruction::LessInt,
+            (Kind::Int, Operator::LessEqual) => Instruction::LessEqualInt,
+            (Kind::Int, Operator::Greater) => Instruction::GreaterInt,
+            (Kind::Int, Operator::GreaterEqual) => Instruction::GreaterEqualInt,
+            (Kind::Float, Operator::Plus) => Instruction::AddFloat,
+            (Kind::Float, Operator::Minus) => Instruction::SubtractFloat,
+            (Kind::Float, Operator::Star) => Instruction::MultiplyFloat,
+            (Kind::Float, Operator::Slash) => Instruction::DivideFloat,
+            (Kind::String, Operator::Plus) => Instruction::Concatenate,
+            _ => Instruction::Binary(self.operation(expression)),
+        };
+        self.emit(instruction, position);
+        Ok(())
+    }
+
+    /// Compile a call, leaving `results` values on the stack, or none when it's None
+    fn call(&mut self, call: &Expression, results: Option<usize>) -> Result<(), CompileError> {
+        let ExpressionKind::FunctionCall {
+            name: callee,
+            arguments,
+            spread,
+        } = &call.kind
+        else {
+            return Err(unsupported(&call.to_string(), call.position_start));
+        };
+        let position = call.position_start;
+        match &callee.kind {
+            ExpressionKind::Identifier(name) if self.slot(callee).is_none() => {
+                if let Some(callee) = self.functions.get(name).cloned() {
+                    if *spread {
+                        return Err(unsupported("calls passing a slice", position));
+                    }
+                    let argument_count = self.arguments(arguments, &callee.parameters)?;
+                    self.emit(
+                        Instruction::Call {
+                            function: callee.index,
+                            arguments: argument_count,
+                        },
+                        position,
+                    );
+                    if results.is_none() {
+                        for _ in 0..callee.results {
+                            self.emit(Instruction::Pop, position);
+                        }
+                    }
+                    return Ok(());
+                }
+                if let Some(builtin) = Builtin::from_name(name) {
+                    return self.builtin(builtin, call, arguments, *spread, results);
+                }
+                if let [argument] = arguments.as_slice() {
+                    return self.conversion(call, argument, results);
+                }
+                Err(unsupported(&call.to_string(), position))
+            }
+            ExpressionKind::FieldAccess { object, field, .. } => {
+                let path = match &object.kind {
+                    ExpressionKind::Identifier(package) if self.is_package(object) => {
+                        self.imports.get(package).cloned()
+                    }
+                    _ => None,
+                };
+                let Some(path) =
+                    path.filter(|path| path == "fmt" || stdlib::package(path).is_some())
+                else {
+                    return Err(unsupported(&call.to_string(), position));
+                };
+                if *spread {
+                    return Err(unsupported("calls passing a slice", position));
+                }
+                let argument_count = self.arguments(arguments, &[])?;
+                let native = Native {
+                    path,
+                    function: field.clone(),
+                    call: self.operation(call),
+                    results,
+                };
+                self.program.natives.push(native);
+                self.emit(
+                    Instruction::CallNative {
+                        native: self.program.natives.len() as u32 - 1,
+                        arguments: argument_count,
+                    },
+                    position,
+                );
+                Ok(())
+            }
+            _ => Err(unsupported(&call.to_string(), position)),
+        }
+    }
+
+    /// Push a call's arguments, an untyped constant as the kind of the parameter it's passed
+    /// to, returning how many values they are. A single call returning several values passes
+    /// each of them.
+    fn arguments(
+        &mut self,
+        arguments: &[Expression],
+        parameters: &[Kind],
+    ) -> Result<u32, CompileError> {
+        if let [argument] = arguments
+            && let Some(count) = self.result_count(argument)
+            && count != 1
+        {
+            self.call(argument, Some(count))?;
+            return Ok(count as u32);
+        }
+        for (index, argument) in arguments.iter().enumerate() {
+            let kind = parameters.get(index).copied().unwrap_or(Kind::Other);
+            self.expression(argument, kind)?;
+        }
+        Ok(arguments.len() as u32)
+    }
+
+    fn builtin(
+        &mut self,
+        builtin: Builtin,
+        call: &Expression,
+        arguments: &[Expression],
+        spread: bool,
+        results: Option<usize>,
+    ) -> Result<(), CompileError> {
+        let position = call.position_start;
+        match (builtin, arguments) {
+            (Builtin::Len | Builtin::Cap, [argument]) => {
+                self.expression(argument, Kind::Other)?;
+                let instruction = match builtin {
+                    Builtin::Len => Instruction::Len,
+                    _ => Instruction::Cap,
+                };
+                self.emit(instruction, position);
+            }
+            (Builtin::Append, [slice, elements @ ..]) => {
+                let element = self.slice_element(call).unwrap_or(Kind::Other);
+                self.expression(slice, Kind::Other)?;
+                for value in elements {
+                    self.expression(value, element)?;
+                }
+                let instruction = match spread {
+                    true => Instruction::AppendSlice,
+                    false => Instruction::Append(elements.len() as u32),
+                };
+                self.emit(instruction, position);
+            }
+            (Builtin::Make, [_, sizes @ ..]) if (1..=2).contains(&sizes.len()) => {
+                let (Some(slice), Some(element_type)) =
+                    (self.nil_slice(call), self.element_type(call))
+                else {
+                    return Err(unsupported(&call.to_string(), position));
+                };
+                let zero = self.zero_of(element_type, position)?;
+                for size in sizes {
+                    self.expression(size, Kind::Int)?;
+                }
+                let (slice, zero) = (self.add_constant(slice), self.add_constant(zero));
+                self.emit(
+                    Instruction::MakeSlice {
+                        slice,
+                        zero,
+                        arguments: sizes.len() as u32,
+                    },
+                    position,
+                );
+            }
+            (Builtin::Panic, [value]) => {
+                self.expression(value, Kind::Other)?;
+                let operation = self.operation(call);
+                self.emit(Instruction::Panic(operation), position);
+                return Ok(());
+            }
+            _ => return Err(unsupported(&call.to_string(), position)),
+        }
+        if results.is_none() {
+            self.emit(Instruction::Pop, position);
+        }
+        Ok(())
+    }
+
+    /// A conversion to int or float64, or a basic type of the same kind
+    fn conversion(
+        &mut self,
+        call: &Expression,
+        argument: &Expression,
+        results: Option<usize>,
+    ) -> Result<(), CompileError> {
+        let position = call.position_start;
+        let target = self.kind(call);
+        let source = match self.kind(argument) {
+            // A constant converted to a float, like float64(1), is made a float to begin with
+            Kind::Int if self.is_untyped(argument) => target,
+            kind => kind,
+        };
+        self.expression(argument, source)?;
+        match (source, target) {
+            (Kind::Int, Kind::Float) => {
+                self.emit(Instruction::IntToFloat, position);
+            }
+            (Kind::Float, Kind::Int) => {
+                self.emit(Instruction::FloatToInt, position);
+            }
+            (source, target) if source == target && source != Kind::Other => {}
+            _ => return Err(unsupported(&call.to_string(), position)),
+        }
+        if results.is_none() {
+            self.emit(Instruction::Pop, position);
+        }
+        Ok(())
+    }
+
+    /// An integer, float or rune literal's value, as the kind `hint` if it's numeric
+    fn constant(&self, literal: &Expression, hint: Kind) -> Result<Value, CompileError> {
+        let constant = match &literal.kind {
+            ExpressionKind::IntegerLiteral(text) => Constant::from_integer_literal(text),
+            ExpressionKind::FloatLiteral(text) => Constant::from_float_literal(text),
+            ExpressionKind::RuneLiteral(text) => Constant::from_rune_literal(text),
+            _ => None,
+        };
+        let kind = match hint {
+            Kind::Int | Kind::Float => hint,
+            _ => self.kind(literal),
+        };
+        let value = match (constant, kind) {
+            (Some(Constant::Int(value)), Kind::Float) => Some(Value::Float(value as f64)),
+            (Some(Constant::Int(value)), _) => i64::try_from(value).ok().map(Value::Int),
+            (Some(Constant::Float(value)), Kind::Int) if value.fract() == 0.0 => {
+                Some(Value::Int(value as i64))
+            }
+            (Some(Constant::Float(value)), _) => Some(Value::Float(value)),
+            _ => None,
+        };
+        value.ok_or_else(|| unsupported(&literal.to_string(), literal.position_start))
+    }
+
+    /// How many results a call to a Go function returns, or None if `expression` isn't one
+    fn result_count(&self, expression: &Expression) -> Option<usize> {
+        let ExpressionKind::FunctionCall { name, .. } = &expression.kind else {
+            return None;
+        };
+        let ExpressionKind::Identifier(name) = &name.kind else {
+            return None;
+        };
+        self.functions.get(name).map(|callee| callee.results)
+    }
+
+    /// The kinds of a function's parameters, whose symbol is declared by its `func` keyword
+    fn parameter_kinds(&self, function: &Declaration) -> Vec<Kind> {
+        match self
+            .info
+            .type_at(function.position_start)
+            .map(|type_id| self.info.types.get(type_id))
+        {
+            Some(Type::Function(signature)) => signature
+                .parameters
+                .iter()
+                .map(|parameter| self.kind_of(*parameter))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn kind(&self, expression: &Expression) -> Kind {
+        self.info
+            .type_of(expression)
+            .map_or(Kind::Other, |type_id| self.kind_of(type_id))
+    }
+
+    fn type_kind(&self, type_expression: &TypeExpression) -> Kind {
+        self.info
+            .type_of(type_expression)
+            .map_or(Kind::Other, |type_id| self.kind_of(type_id))
+    }
+
+    fn kind_of(&self, type_id: TypeId) -> Kind {
+        match self.info.types.basic_kind(type_id) {
+            Some(kind) if kind.is_integer() || kind == BasicKind::UntypedRune => Kind::Int,
+            Some(kind) if kind.is_float() => Kind::Float,
+            Some(kind) if kind.is_string() => Kind::String,
+            Some(kind) if kind.is_boolean() => Kind::Bool,
+            _ => Kind::Other,
+        }
+    }
+
+    fn is_untyped(&self, expression: &Expression) -> bool {
+        self.info
+            .type_of(expression)
+            .and_then(|type_id| self.info.types.basic_kind(type_id))
+            .is_some_and(|kind| kind.is_untyped())
+    }
+
+    /// The kind a comparison compares: the typed operand's, or with two untyped constants, a
+    /// float if either is
+    fn operand_kind(&self, left: &Expression, right: &Expression) -> Kind {
+        match (self.is_untyped(left), self.is_untyped(right)) {
+            (false, _) => self.kind(left),
+            (true, false) => self.kind(right),
+            (true, true) => match (self.kind(left), self.kind(right)) {
+                (Kind::Float, _) | (_, Kind::Float) => Kind::Float,
+                (kind, _) => kind,
+            },
+        }
+    }
+
+    /// The element type of the slice `expression` is, if it's a slice
+    fn element_type(&self, expression: &Expression) -> Option<TypeId> {
+        let type_id = self.info.type_of(expression)?;
+        match self.info.types.get(self.info.types.underlying(type_id)) {
+            Type::Slice(element) => Some(*element),
+            _ => None,
+        }
+    }
+
+    fn slice_element(&self, expression: &Expression) -> Option<Kind> {
+        self.element_type(expression)
+            .map(|element| self.kind_of(element))
+    }
+
+    /// A nil slice of the type of the slice `expression` is
+    fn nil_slice(&self, expression: &Expression) -> Option<Value> {
+        let element = self.element_type(expression)?;
+        let element = TypeExpression::new_name(
+            self.info.types.type_string(element),
+            expression.position_start,
+        );
+        Some(Value::Slice(Slice::nil(Rc::new(element))))
+    }
+
+    fn zero_value(
+        &self,
+        type_expression: &TypeExpression,
+        position: Position,
+    ) -> Result<Value, CompileError> {
+        match self.info.type_of(type_expression) {
+            Some(type_id) => self.zero_of(type_id, position),
+            None => Err(unsupported(&type_expression.to_string(), position)),
+        }
+    }
+
+    /// The zero value of a basic type or a slice
+    fn zero_of(&self, type_id: TypeId, position: Position) -> Result<Value, CompileError> {
+        let types = &self.info.types;
+        match types.get(types.underlying(type_id)) {
+            Type::Slice(element) => {
+                let element = TypeExpression::new_name(types.type_string(*element), position);
+                Ok(Value::Slice(Slice::nil(Rc::new(element))))
+            }
+            _ => match self.kind_of(type_id) {
+                Kind::Int => Ok(Value::Int(0)),
+                Kind::Float => Ok(Value::Float(0.0)),
+                Kind::String => Ok(Value::String(String::new())),
+                Kind::Bool => Ok(Value::Bool(false)),
+                Kind::Other => Err(unsupported(
+                    &format!("values of type {}", types.type_string(type_id)),
+                    position,
+                )),
+            },
+        }
+    }
+
+    fn is_package(&self, identifier: &Expression) -> bool {
+        self.info
+            .symbol_of(identifier)
+            .is_some_and(|symbol| self.info.symbols.symbol(symbol).kind == SymbolKind::Package)
+    }
+
+    /// Where the variable an identifier refers to lives, if it's one the compiler declared
+    fn slot(&self, identifier: &Expression) -> Option<Slot> {
+        let symbol = self.info.symbol_of(identifier)?;
+        if let Some(slot) = self.body.locals.get(&symbol) {
+            return Some(Slot::Local(*slot));
+        }
+        self.globals.get(&symbol).map(|slot| Slot::Global(*slot))
+    }
+
+    /// Give the variable `name` declares a local of its own, or the global it's given
+    fn declare_local(&mut self, name: &Expression) -> Option<u32> {
+        let symbol = self.info.symbol_of(name)?;
+        if self.globals.contains_key(&symbol) {
+            return None;
+        }
+        let slot = self.temporary();
+        self.body.locals.insert(symbol, slot);
+        Some(slot)
+    }
+
+    /// A local of the compiler's own, like a range loop's index
+    fn temporary(&mut self) -> u32 {
+        self.body.local_count += 1;
+        self.body.local_count as u32 - 1
+    }
+
+    /// Record `expression` for an instruction to refer to
+    fn operation(&mut self, expression: &Expression) -> u32 {
+        self.program.operations.push(expression.clone());
+        self.program.operations.len() as u32 - 1
+    }
+
+    fn add_constant(&mut self, value: Value) -> u32 {
+        let index =
+            match self.program.constants.iter().position(|constant| {
+                constant == &value && constant.type_name() == value.type_name()
+            }) {
+                Some(index) => index,
+                None => {
+                    self.program.constants.push(value);
+                    self.program.constants.len() - 1
+                }
+            };
+        index as u32
+    }
+
+    fn emit_constant(&mut self, value: Value, position: Position) {
+        let index = self.add_constant(value);
+        self.emit(Instruction::Constant(index), position);
+    }
+
+    /// Add an instruction, returning its offset
+    fn emit(&mut self, instruction: Instruction, position: Position) -> usize {
+        self.body.code.push(instruction);
+        self.body.positions.push(position);
+        self.body.code.len() - 1
+    }
+
+    /// Point the jump at `offset` at the next instruction
+    fn patch(&mut self, offset: usize) {
+        let target = self.body.code.len();
+        self.patch_to(offset, target);
+    }
+
+    fn patch_to(&mut self, offset: usize, target: usize) {
+        match &mut self.body.code[offset] {
+            Instruction::Jump(to) | Instruction::JumpIfFalse(to) => *to = target as u32,
+            _ => unreachable!("only jumps are patched"),
+        }
+    }
+}
+
+fn unsupported(code: &str, position: Position) -> CompileError {
+    CompileError::new(CompileErrorKind::Unsupported(code.to_string()), position)
+}
+
+/// How many values a parameter or result list declares, where an unnamed one is one
+fn count_names(parameters: &[Parameter]) -> usize {
+    parameters
+        .iter()
+        .map(|parameter| parameter.names.len().max(1))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::primitives::errors::semantic::SemanticErrorKind;
+
+    fn compile_str(source: &str) -> Result<Program, CompileError> {
+        compile(&Parser::new(source).parse().unwrap())
+    }
+
+    #[test]
+    fn disassemble() {
+        let source = "package main\n\nimport \"fmt\"\n\nvar scale = 2.0\n\nfunc half(n int) float64 {\n\treturn float64(n) / scale\n}\n\nfunc main() {\n\tfor i := range 2 {\n\t\tfmt.Println(half(i) + 1)\n\t}\n}\n";
+        let program = compile_str(source).unwrap();
+        assert_eq!(
+            program.to_string(),
+            "\
+func init (0 parameters, 0 locals)
+       0     5  Constant 0 (2)
+       1        StoreGlobal 0
+       2        Return 0
+
+func half (1 parameters, 1 locals)
+       0     8  LoadLocal 0
+       1        IntToFloat
+       2        LoadGlobal 0
+       3        DivideFloat
+       4        Return 1
+       5     9  Return 0
+
+func main (0 parameters, 4 locals)
+       0    12  Constant 1 (2)
+       1        StoreLocal 2
+       2        LoadLocal 2
+       3        StoreLocal 1
+       4        Constant 2 (0)
+       5        StoreLocal 0
+       6        LoadLocal 0
+       7        LoadLocal 1
+       8        LessInt
+       9        JumpIfFalse 22
+      10        LoadLocal 0
+      11        StoreLocal 3
+      12    13  LoadLocal 3
+      13        Call 1 (half, 1 arguments)
+      14        Constant 3 (1)
+      15        AddFloat
+      16        CallNative 0 (fmt.Println, 1 arguments)
+      17    12  LoadLocal 0
+      18        Constant 4 (1)
+      19        AddInt
+      20        StoreLocal 0
+      21        Jump 6
+      22    15  Return 0
+"
+        );
+    }
+
+    #[test]
+    fn compile_errors() {
+        let error = |source: &str| compile_str(source).unwrap_err().kind;
+        assert_eq!(
+            error("package main\n\nfunc main() {\n\tx := y\n\t_ = x\n}\n"),
+            CompileErrorKind::Check(Box::new(SemanticErrorKind::UndefinedName("y".to_string())))
+        );
+        assert_eq!(
+            error("package main\n\nfunc main() {\n\tm := map[string]int{}\n\t_ = m\n}\n"),
+            CompileErrorKind::Unsupported("map[string]int{}".to_string())
+        );
+        assert_eq!(
+            error("package main\n\nfunc main() {\n\tdefer println()\n}\n"),
+            CompileErrorKind::Unsupported("defer".to_string())
+        );
+    }
+}
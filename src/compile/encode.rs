@@ -0,0 +1,524 @@
+//! The file `gor build --emit=bytecode` writes: a compiled program, which `gor run` runs on
+//! the VM without parsing or checking it again. It starts with `GORC` and the format's
+//! version, then the program's parts in the order `Program` declares them. Numbers are
+//! little-endian, and strings and lists are their length as a u32 then their contents.
+//!
+//! An operation is kept as its source and position, and parsed again as it's decoded, so
+//! the expressions inside it are at the positions of its first line.
+
+use std::rc::Rc;
+
+use crate::ast::{expression::Expression, type_expression::TypeExpression};
+use crate::compile::bytecode::{Function, Instruction, Native, Program};
+use crate::eval::value::{Slice, Value};
+use crate::parser::parser::Parser;
+use crate::primitives::{
+    errors::compile::{CompileError, CompileErrorKind, DecodeError, DecodeErrorKind},
+    position::Position,
+};
+
+const MAGIC: &[u8; 4] = b"GORC";
+/// Changed whenever the format is, so an old build is rejected rather than misread
+const VERSION: u32 = 1;
+
+/// Encode a program to bytes `decode` reads back. Only the constants the compiler makes can
+/// be encoded: basic values, nil and nil slices.
+pub fn encode(program: &Program) -> Result<Vec<u8>, CompileError> {
+    let mut encoder = Encoder { bytes: Vec::new() };
+    encoder.bytes.extend(MAGIC);
+    encoder.u32(VERSION);
+    encoder.u32(program.functions.len() as u32);
+    for function in &program.functions {
+        encoder.function(function);
+    }
+    encoder.u32(program.constants.len() as u32);
+    for constant in &program.constants {
+        encoder.constant(constant)?;
+    }
+    encoder.u32(program.operations.len() as u32);
+    for operation in &program.operations {
+        encoder.position(operation.position_start);
+        encoder.position(operation.position_end);
+        encoder.string(&operation.to_string());
+    }
+    encoder.u32(program.natives.len() as u32);
+    for native in &program.natives {
+        encoder.string(&native.path);
+        encoder.string(&native.function);
+        encoder.u32(native.call);
+        encoder.optional(native.results.map(|results| results as u32));
+    }
+    encoder.u32(program.globals as u32);
+    encoder.string(&program.package_name);
+    encoder.u32(program.init as u32);
+    encoder.optional(program.main.map(|main| main as u32));
+    Ok(encoder.bytes)
+}
+
+/// Decode a program `encode` encoded
+pub fn decode(bytes: &[u8]) -> Result<Program, DecodeError> {
+    let mut decoder = Decoder { bytes, offset: 0 };
+    if decoder.take(MAGIC.len())? != MAGIC {
+        return Err(DecodeError::new(DecodeErrorKind::NotBytecode, 0));
+    }
+    let version = decoder.u32()?;
+    if version != VERSION {
+        return Err(DecodeError::new(
+            DecodeErrorKind::Version(version),
+            MAGIC.len(),
+        ));
+    }
+    let functions = decoder.list(Decoder::function)?;
+    let constants = decoder.list(Decoder::constant)?;
+    let operations = decoder.list(Decoder::operation)?;
+    let natives = decoder.list(|decoder| {
+        Ok(Native {
+            path: decoder.string()?,
+            function: decoder.string()?,
+            call: decoder.u32()?,
+            results: decoder.optional()?.map(|results| results as usize),
+        })
+    })?;
+    let program = Program {
+        functions,
+        constants,
+        operations,
+        natives,
+        globals: decoder.u32()? as usize,
+        package_name: decoder.string()?,
+        init: decoder.u32()? as usize,
+        main: decoder.optional()?.map(|main| main as usize),
+    };
+    if decoder.offset != bytes.len() {
+        return Err(DecodeError::new(
+            DecodeErrorKind::TrailingBytes,
+            decoder.offset,
+        ));
+    }
+    decoder.validate(&program)?;
+    Ok(program)
+}
+
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn optional(&mut self, value: Option<u32>) {
+        match value {
+            Some(value) => {
+                self.bytes.push(1);
+                self.u32(value);
+            }
+            None => self.bytes.push(0),
+        }
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend(value.as_bytes());
+    }
+
+    fn position(&mut self, position: Position) {
+        self.u32(position.line as u32);
+        self.u32(position.column_start as u32);
+        self.u32(position.column_end as u32);
+    }
+
+    fn function(&mut self, function: &Function) {
+        self.string(&function.name);
+        self.u32(function.parameters as u32);
+        self.u32(function.locals as u32);
+        self.u32(function.code.len() as u32);
+        for (instruction, position) in function.code.iter().zip(&function.positions) {
+            self.instruction(instruction);
+            self.position(*position);
+        }
+    }
+
+    /// An instruction's opcode, its index in `Instruction`, then its operands
+    fn instruction(&mut self, instruction: &Instruction) {
+        use Instruction::*;
+        let (opcode, operands): (u8, &[u32]) = match instruction {
+            Constant(index) => (0, &[*index]),
+            Pop => (1, &[]),
+            LoadLocal(index) => (2, &[*index]),
+            StoreLocal(index) => (3, &[*index]),
+            LoadGlobal(index) => (4, &[*index]),
+            StoreGlobal(index) => (5, &[*index]),
+            AddInt => (6, &[]),
+            SubtractInt => (7, &[]),
+            MultiplyInt => (8, &[]),
+            DivideInt(operation) => (9, &[*operation]),
+            RemainderInt(operation) => (10, &[*operation]),
+            AddFloat => (11, &[]),
+            SubtractFloat => (12, &[]),
+            MultiplyFloat => (13, &[]),
+            DivideFloat => (14, &[]),
+            Concatenate => (15, &[]),
+            EqualInt => (16, &[]),
+            NotEqualInt => (17, &[]),
+            LessInt => (18, &[]),
+            LessEqualInt => (19, &[]),
+            GreaterInt => (20, &[]),
+            GreaterEqualInt => (21, &[]),
+            Binary(operation) => (22, &[*operation]),
+            NegateInt => (23, &[]),
+            NegateFloat => (24, &[]),
+            Not => (25, &[]),
+            Complement => (26, &[]),
+            IntToFloat => (27, &[]),
+            FloatToInt => (28, &[]),
+            Jump(target) => (29, &[*target]),
+            JumpIfFalse(target) => (30, &[*target]),
+            Call {
+                function,
+                arguments,
+            } => (31, &[*function, *arguments]),
+            CallNative { native, arguments } => (32, &[*native, *arguments]),
+            Return(count) => (33, &[*count]),
+            Len => (34, &[]),
+            Cap => (35, &[]),
+            Append(count) => (36, &[*count]),
+            AppendSlice => (37, &[]),
+            MakeSlice {
+                slice,
+                zero,
+                arguments,
+            } => (38, &[*slice, *zero, *arguments]),
+            SliceLiteral { slice, elements } => (39, &[*slice, *elements]),
+            Index(operation) => (40, &[*operation]),
+            StoreIndex(operation) => (41, &[*operation]),
+            Panic(operation) => (42, &[*operation]),
+        };
+        self.bytes.push(opcode);
+        for operand in operands {
+            self.u32(*operand);
+        }
+    }
+
+    fn constant(&mut self, constant: &Value) -> Result<(), CompileError> {
+        match constant {
+            Value::Int(value) => {
+                self.bytes.push(0);
+                self.bytes.extend(value.to_le_bytes());
+            }
+            Value::Float(value) => {
+                self.bytes.push(1);
+                self.bytes.extend(value.to_le_bytes());
+            }
+            Value::String(value) => {
+                self.bytes.push(2);
+                self.string(value);
+            }
+            Value::Bool(value) => self.bytes.extend([3, *value as u8]),
+            Value::Nil => self.bytes.push(4),
+            Value::Slice(slice) if slice.backing.is_none() => {
+                self.bytes.push(5);
+                self.string(&slice.element.to_string());
+            }
+            value => {
+                let code = format!("encoding constants of type {}", value.type_name());
+                return Err(CompileError::new(
+                    CompileErrorKind::Unsupported(code),
+                    Position::new(1, 0, 0),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Decoder<'_> {
+    fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+        DecodeError::new(kind, self.offset)
+    }
+
+    fn take(&mut self, count: usize) -> Result<&[u8], DecodeError> {
+        let end = self
+            .offset
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error(DecodeErrorKind::Truncated))?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn optional(&mut self) -> Result<Option<u32>, DecodeError> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.u32()?)),
+            tag => Err(self.error(DecodeErrorKind::InvalidTag(tag))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, DecodeError> {
+        let length = self.u32()? as usize;
+        let start = self.offset;
+        let bytes = self.take(length)?.to_vec();
+        String::from_utf8(bytes)
+            .map_err(|_| DecodeError::new(DecodeErrorKind::InvalidString, start))
+    }
+
+    fn position(&mut self) -> Result<Position, DecodeError> {
+        let line = self.u32()? as usize;
+        let column_start = self.u32()? as usize;
+        let column_end = self.u32()? as usize;
+        Ok(Position::new(line, column_start, column_end))
+    }
+
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Vec<T>, DecodeError> {
+        let count = self.u32()?;
+        // Each item takes at least a byte, which a corrupt count can't be allowed to outgrow
+        if count as usize > self.bytes.len() - self.offset {
+            return Err(self.error(DecodeErrorKind::Truncated));
+        }
+        (0..count).map(|_| item(self)).collect()
+    }
+
+    fn function(&mut self) -> Result<Function, DecodeError> {
+        let name = self.string()?;
+        let parameters = self.u32()? as usize;
+        let locals = self.u32()? as usize;
+        let (code, positions) = self
+            .list(|decoder| Ok((decoder.instruction()?, decoder.position()?)))?
+            .into_iter()
+            .unzip();
+        Ok(Function {
+            name,
+            parameters,
+            locals,
+            code,
+            positions,
+        })
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, DecodeError> {
+        use Instruction::*;
+        let opcode = self.byte()?;
+        let instruction = match opcode {
+            0 => Constant(self.u32()?),
+            1 => Pop,
+            2 => LoadLocal(self.u32()?),
+            3 => StoreLocal(self.u32()?),
+            4 => LoadGlobal(self.u32()?),
+            5 => StoreGlobal(self.u32()?),
+            6 => AddInt,
+            7 => SubtractInt,
+            8 => MultiplyInt,
+            9 => DivideInt(self.u32()?),
+            10 => RemainderInt(self.u32()?),
+            11 => AddFloat,
+            12 => SubtractFloat,
+            13 => MultiplyFloat,
+            14 => DivideFloat,
+            15 => Concatenate,
+            16 => EqualInt,
+            17 => NotEqualInt,
+            18 => LessInt,
+            19 => LessEqualInt,
+            20 => GreaterInt,
+            21 => GreaterEqualInt,
+            22 => Binary(self.u32()?),
+            23 => NegateInt,
+            24 => NegateFloat,
+            25 => Not,
+            26 => Complement,
+            27 => IntToFloat,
+            28 => FloatToInt,
+            29 => Jump(self.u32()?),
+            30 => JumpIfFalse(self.u32()?),
+            31 => Call {
+                function: self.u32()?,
+                arguments: self.u32()?,
+            },
+            32 => CallNative {
+                native: self.u32()?,
+                arguments: self.u32()?,
+            },
+            33 => Return(self.u32()?),
+            34 => Len,
+            35 => Cap,
+            36 => Append(self.u32()?),
+            37 => AppendSlice,
+            38 => MakeSlice {
+                slice: self.u32()?,
+                zero: self.u32()?,
+                arguments: self.u32()?,
+            },
+            39 => SliceLiteral {
+                slice: self.u32()?,
+                elements: self.u32()?,
+            },
+            40 => Index(self.u32()?),
+            41 => StoreIndex(self.u32()?),
+            42 => Panic(self.u32()?),
+            opcode => {
+                return Err(DecodeError::new(
+                    DecodeErrorKind::InvalidTag(opcode),
+                    self.offset - 1,
+                ));
+            }
+        };
+        Ok(instruction)
+    }
+
+    fn constant(&mut self) -> Result<Value, DecodeError> {
+        let value = match self.byte()? {
+            0 => Value::Int(self.u64()? as i64),
+            1 => Value::Float(f64::from_bits(self.u64()?)),
+            2 => Value::String(self.string()?),
+            3 => Value::Bool(self.byte()? != 0),
+            4 => Value::Nil,
+            5 => {
+                let element = TypeExpression::new_name(self.string()?, Position::new(1, 0, 0));
+                Value::Slice(Slice::nil(Rc::new(element)))
+            }
+            tag => {
+                return Err(DecodeError::new(
+                    DecodeErrorKind::InvalidTag(tag),
+                    self.offset - 1,
+                ));
+            }
+        };
+        Ok(value)
+    }
+
+    fn operation(&mut self) -> Result<Expression, DecodeError> {
+        let start = self.position()?;
+        let end = self.position()?;
+        let offset = self.offset;
+        let source = self.string()?;
+        let mut operation = Parser::parse_expression_str(&source)
+            .map_err(|_| DecodeError::new(DecodeErrorKind::InvalidOperation(source), offset))?;
+        operation.shift_lines(start.line as isize - 1);
+        operation.position_start = start;
+        operation.position_end = end;
+        Ok(operation)
+    }
+
+    /// Check every index an instruction has is in range, so the VM can't be made to panic
+    fn validate(&self, program: &Program) -> Result<(), DecodeError> {
+        let invalid = |what: &str| {
+            Err(DecodeError::new(
+                DecodeErrorKind::InvalidIndex(what.to_string()),
+                self.offset,
+            ))
+        };
+        let in_range = |index: u32, length: usize| (index as usize) < length;
+        if !in_range(program.init as u32, program.functions.len())
+            || program
+                .main
+                .is_some_and(|main| !in_range(main as u32, program.functions.len()))
+        {
+            return invalid("function");
+        }
+        if program
+            .natives
+            .iter()
+            .any(|native| !in_range(native.call, program.operations.len()))
+        {
+            return invalid("operation");
+        }
+        for function in &program.functions {
+            for instruction in &function.code {
+                use Instruction::*;
+                let valid = match *instruction {
+                    Constant(index) => in_range(index, program.constants.len()),
+                    LoadLocal(index) | StoreLocal(index) => in_range(index, function.locals),
+                    LoadGlobal(index) | StoreGlobal(index) => in_range(index, program.globals),
+                    DivideInt(index) | RemainderInt(index) | Binary(index) | Index(index)
+                    | StoreIndex(index) | Panic(index) => in_range(index, program.operations.len()),
+                    Jump(target) | JumpIfFalse(target) => in_range(target, function.code.len()),
+                    Call { function, .. } => in_range(function, program.functions.len()),
+                    CallNative { native, .. } => in_range(native, program.natives.len()),
+                    MakeSlice { slice, zero, .. } => {
+                        in_range(slice, program.constants.len())
+                            && in_range(zero, program.constants.len())
+                    }
+                    SliceLiteral { slice, .. } => in_range(slice, program.constants.len()),
+                    _ => true,
+                };
+                if !valid {
+                    return invalid(&format!("{:?}", instruction));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::{compiler::compile, vm::Vm};
+    use crate::test_support::Captured;
+
+    #[test]
+    fn encode_round_trip() {
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\t\"strings\"\n)\n\nvar names []string\n\nfunc shout(name string) string {\n\treturn strings.ToUpper(name) + \"!\"\n}\n\nfunc main() {\n\tnames = append(names, \"gor\", \"go\")\n\tfor _, name := range names {\n\t\tfmt.Println(shout(name), 1.5*2, len(name)%2 == 0)\n\t}\n\tpanic(\"done\")\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let program = compile(&file).unwrap();
+        let decoded = decode(&encode(&program).unwrap()).unwrap();
+        assert_eq!(decoded.functions, program.functions);
+        assert_eq!(decoded.constants, program.constants);
+        assert_eq!(decoded.natives, program.natives);
+        assert_eq!(decoded.to_string(), program.to_string());
+
+        let output = Captured::default();
+        let error = Vm::new()
+            .with_output(output.clone())
+            .run(&decoded)
+            .unwrap_err();
+        assert_eq!(output.take(), "GOR! 3 false\nGO! 3 true\n");
+        assert_eq!(error.position, Position::new(19, 1, 6));
+    }
+
+    #[test]
+    fn decode_errors() {
+        let file = Parser::new("package main\n\nvar x = 1\n\nfunc main() {\n\tx = x + 1\n}\n")
+            .parse()
+            .unwrap();
+        let bytes = encode(&compile(&file).unwrap()).unwrap();
+        let kind = |bytes: &[u8]| decode(bytes).unwrap_err().kind;
+        assert_eq!(kind(b"\0asm\x01\0\0\0"), DecodeErrorKind::NotBytecode);
+        assert_eq!(kind(b"GORC\x09\0\0\0"), DecodeErrorKind::Version(9));
+        assert_eq!(kind(&bytes[..bytes.len() - 1]), DecodeErrorKind::Truncated);
+        assert_eq!(
+            kind(&[&bytes[..], b"\0"].concat()),
+            DecodeErrorKind::TrailingBytes
+        );
+        let mut corrupt = bytes.clone();
+        // The first function's first instruction's opcode, after its name `init`
+        corrupt[8 + 4 + 4 + 4 + 4 + 4 + 4] = 99;
+        assert_eq!(kind(&corrupt), DecodeErrorKind::InvalidTag(99));
+    }
+}
@@ -0,0 +1,497 @@
+use crate::primitives::position::Position;
+
+/// A value an instruction or block parameter defines, once, like `v3`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Value(pub u32);
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+/// A basic block of a function, like `b2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Block(pub u32);
+
+impl std::fmt::Display for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "b{}", self.0)
+    }
+}
+
+/// The type of a value. Only Go's basic types are lowered to the IR so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    String,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Type::Int => "int",
+            Type::Float => "float64",
+            Type::Bool => "bool",
+            Type::String => "string",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+}
+
+impl Constant {
+    pub fn type_of(&self) -> Type {
+        match self {
+            Constant::Int(_) => Type::Int,
+            Constant::Float(_) => Type::Float,
+            Constant::Bool(_) => Type::Bool,
+            Constant::String(_) => Type::String,
+        }
+    }
+}
+
+/// A float always with a decimal point or exponent, like `2.0`, so it can't be mistaken for
+/// an int, and a string quoted
+impl std::fmt::Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constant::Int(value) => write!(f, "{}", value),
+            Constant::Float(value) => write!(f, "{:?}", value),
+            Constant::Bool(value) => write!(f, "{}", value),
+            Constant::String(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// A binary operation, whose operands are of the same type except a shift's count, which is
+/// an int. Integer arithmetic wraps around as Go's does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    And,
+    Or,
+    Xor,
+    AndNot,
+    ShiftLeft,
+    ShiftRight,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+impl BinaryOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "add",
+            BinaryOp::Subtract => "sub",
+            BinaryOp::Multiply => "mul",
+            BinaryOp::Divide => "div",
+            BinaryOp::Remainder => "rem",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+            BinaryOp::Xor => "xor",
+            BinaryOp::AndNot => "andnot",
+            BinaryOp::ShiftLeft => "shl",
+            BinaryOp::ShiftRight => "shr",
+            BinaryOp::Equal => "eq",
+            BinaryOp::NotEqual => "ne",
+            BinaryOp::Less => "lt",
+            BinaryOp::LessEqual => "le",
+            BinaryOp::Greater => "gt",
+            BinaryOp::GreaterEqual => "ge",
+        }
+    }
+
+    /// Whether the operation compares its operands, giving a bool
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Equal
+                | BinaryOp::NotEqual
+                | BinaryOp::Less
+                | BinaryOp::LessEqual
+                | BinaryOp::Greater
+                | BinaryOp::GreaterEqual
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+    Complement,
+}
+
+impl UnaryOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnaryOp::Negate => "neg",
+            UnaryOp::Not => "not",
+            UnaryOp::Complement => "compl",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstructionKind {
+    Const(Constant),
+    /// The same value under another name, which copy propagation removes
+    Copy(Value),
+    Binary(BinaryOp, Value, Value),
+    Unary(UnaryOp, Value),
+    /// An int converted to a float or a float to an int, as the result's type says
+    Convert(Value),
+    /// A string's length in bytes
+    Len(Value),
+    /// Call the module's function at the index
+    Call {
+        function: usize,
+        arguments: Vec<Value>,
+    },
+    /// Call a native package's function, like `fmt.Println`, whose results aren't used
+    CallNative {
+        path: String,
+        function: String,
+        arguments: Vec<Value>,
+    },
+    LoadGlobal(usize),
+    StoreGlobal(usize, Value),
+}
+
+impl InstructionKind {
+    /// The values the instruction uses
+    pub fn operands(&self) -> Vec<Value> {
+        match self {
+            InstructionKind::Const(_) | InstructionKind::LoadGlobal(_) => Vec::new(),
+            InstructionKind::Copy(value)
+            | InstructionKind::Unary(_, value)
+            | InstructionKind::Convert(value)
+            | InstructionKind::Len(value)
+            | InstructionKind::StoreGlobal(_, value) => vec![*value],
+            InstructionKind::Binary(_, left, right) => vec![*left, *right],
+            InstructionKind::Call { arguments, .. }
+            | InstructionKind::CallNative { arguments, .. } => arguments.clone(),
+        }
+    }
+
+    /// Replace each value the instruction uses with what `replace` gives for it
+    pub fn map_operands(&mut self, mut replace: impl FnMut(Value) -> Value) {
+        match self {
+            InstructionKind::Const(_) | InstructionKind::LoadGlobal(_) => {}
+            InstructionKind::Copy(value)
+            | InstructionKind::Unary(_, value)
+            | InstructionKind::Convert(value)
+            | InstructionKind::Len(value)
+            | InstructionKind::StoreGlobal(_, value) => *value = replace(*value),
+            InstructionKind::Binary(_, left, right) => {
+                *left = replace(*left);
+                *right = replace(*right);
+            }
+            InstructionKind::Call { arguments, .. }
+            | InstructionKind::CallNative { arguments, .. } => {
+                for argument in arguments {
+                    *argument = replace(*argument);
+                }
+            }
+        }
+    }
+
+    /// Whether the instruction does anything but define its results, so it has to stay even
+    /// when they aren't used. A division may panic, which counts.
+    pub fn has_side_effects(&self) -> bool {
+        matches!(
+            self,
+            InstructionKind::Call { .. }
+                | InstructionKind::CallNative { .. }
+                | InstructionKind::StoreGlobal(..)
+                | InstructionKind::Binary(BinaryOp::Divide | BinaryOp::Remainder, ..)
+        )
+    }
+}
+
+/// An instruction, defining `results`, with the position of the code it was lowered from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub results: Vec<Value>,
+    pub kind: InstructionKind,
+    pub position: Position,
+}
+
+/// A block a terminator goes to, with the values its parameters take
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub block: Block,
+    pub arguments: Vec<Value>,
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.block)?;
+        if !self.arguments.is_empty() {
+            write!(f, "({})", join(&self.arguments))?;
+        }
+        Ok(())
+    }
+}
+
+/// How a block ends
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(Target),
+    /// Go to `then` if the bool `condition` is true, and `otherwise` if it's false
+    Branch {
+        condition: Value,
+        then: Target,
+        otherwise: Target,
+    },
+    Return(Vec<Value>),
+    Panic {
+        value: Value,
+        position: Position,
+    },
+}
+
+impl Terminator {
+    pub fn targets(&self) -> Vec<&Target> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Terminator::Return(_) | Terminator::Panic { .. } => Vec::new(),
+        }
+    }
+
+    pub fn targets_mut(&mut self) -> Vec<&mut Target> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Terminator::Return(_) | Terminator::Panic { .. } => Vec::new(),
+        }
+    }
+
+    /// Replace each value the terminator uses, its targets' arguments included, with what
+    /// `replace` gives for it
+    pub fn map_operands(&mut self, mut replace: impl FnMut(Value) -> Value) {
+        match self {
+            Terminator::Branch { condition, .. } => *condition = replace(*condition),
+            Terminator::Return(values) => {
+                for value in values {
+                    *value = replace(*value);
+                }
+            }
+            Terminator::Panic { value, .. } => *value = replace(*value),
+            Terminator::Jump(_) => {}
+        }
+        for target in self.targets_mut() {
+            for argument in &mut target.arguments {
+                *argument = replace(*argument);
+            }
+        }
+    }
+
+    /// The values the terminator uses, its targets' arguments included
+    pub fn operands(&self) -> Vec<Value> {
+        let mut operands = Vec::new();
+        let mut terminator = self.clone();
+        terminator.map_operands(|value| {
+            operands.push(value);
+            value
+        });
+        operands
+    }
+}
+
+impl std::fmt::Display for Terminator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Terminator::Jump(target) => write!(f, "jump {}", target),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => write!(f, "branch {}, {}, {}", condition, then, otherwise),
+            Terminator::Return(values) if values.is_empty() => write!(f, "return"),
+            Terminator::Return(values) => write!(f, "return {}", join(values)),
+            Terminator::Panic { value, .. } => write!(f, "panic {}", value),
+        }
+    }
+}
+
+/// Instructions that run in order, then a terminator. Its parameters take the values the
+/// terminators that go to it pass, as phi nodes would.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub parameters: Vec<Value>,
+    pub instructions: Vec<Instruction>,
+    pub terminator: Terminator,
+}
+
+/// A function in SSA form: every value is defined once, by an instruction or a block
+/// parameter, before it's used. The entry block is the first, and its parameters are the
+/// function's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub parameters: Vec<Type>,
+    pub results: Vec<Type>,
+    pub blocks: Vec<BasicBlock>,
+    /// Each value's type, by its number
+    pub types: Vec<Type>,
+}
+
+impl Function {
+    pub fn block(&self, block: Block) -> &BasicBlock {
+        &self.blocks[block.0 as usize]
+    }
+
+    pub fn type_of(&self, value: Value) -> Type {
+        self.types[value.0 as usize]
+    }
+}
+
+/// A package level variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub value_type: Type,
+}
+
+/// A program lowered to the IR, which runs `init`, initializing the globals, then `main`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module {
+    pub package_name: String,
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
+    pub init: usize,
+    pub main: Option<usize>,
+}
+
+/// The module as text, its globals then each function's blocks, like
+///
+/// ```text
+/// func double(int) -> int {
+/// b0(v0: int):
+///     v1: int = const 2
+///     v2: int = mul v0, v1
+///     return v2
+/// }
+/// ```
+impl std::fmt::Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for global in &self.globals {
+            writeln!(f, "global {}: {}", global.name, global.value_type)?;
+        }
+        for (index, function) in self.functions.iter().enumerate() {
+            if index > 0 || !self.globals.is_empty() {
+                writeln!(f)?;
+            }
+            self.write_function(f, function)?;
+        }
+        Ok(())
+    }
+}
+
+impl Module {
+    fn write_function(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        function: &Function,
+    ) -> std::fmt::Result {
+        write!(f, "func {}({})", function.name, join(&function.parameters))?;
+        match function.results.as_slice() {
+            [] => {}
+            [result] => write!(f, " -> {}", result)?,
+            results => write!(f, " -> ({})", join(results))?,
+        }
+        writeln!(f, " {{")?;
+        for (index, block) in function.blocks.iter().enumerate() {
+            write!(f, "{}", Block(index as u32))?;
+            if !block.parameters.is_empty() {
+                let parameters: Vec<String> = block
+                    .parameters
+                    .iter()
+                    .map(|parameter| format!("{}: {}", parameter, function.type_of(*parameter)))
+                    .collect();
+                write!(f, "({})", parameters.join(", "))?;
+            }
+            writeln!(f, ":")?;
+            for instruction in &block.instructions {
+                write!(f, "    ")?;
+                if !instruction.results.is_empty() {
+                    let results: Vec<String> = instruction
+                        .results
+                        .iter()
+                        .map(|result| format!("{}: {}", result, function.type_of(*result)))
+                        .collect();
+                    write!(f, "{} = ", results.join(", "))?;
+                }
+                writeln!(f, "{}", self.describe(&instruction.kind))?;
+            }
+            writeln!(f, "    {}", block.terminator)?;
+        }
+        writeln!(f, "}}")
+    }
+
+    fn describe(&self, instruction: &InstructionKind) -> String {
+        match instruction {
+            InstructionKind::Const(constant) => format!("const {}", constant),
+            InstructionKind::Copy(value) => format!("copy {}", value),
+            InstructionKind::Binary(operation, left, right) => {
+                format!("{} {}, {}", operation.name(), left, right)
+            }
+            InstructionKind::Unary(operation, operand) => {
+                format!("{} {}", operation.name(), operand)
+            }
+            InstructionKind::Convert(value) => format!("convert {}", value),
+            InstructionKind::Len(value) => format!("len {}", value),
+            InstructionKind::Call {
+                function,
+                arguments,
+            } => format!(
+                "call {}({})",
+                self.functions[*function].name,
+                join(arguments)
+            ),
+            InstructionKind::CallNative {
+                path,
+                function,
+                arguments,
+            } => format!("call {}.{}({})", path, function, join(arguments)),
+            InstructionKind::LoadGlobal(global) => {
+                format!("load_global {}", self.globals[*global].name)
+            }
+            InstructionKind::StoreGlobal(global, value) => {
+                format!("store_global {}, {}", self.globals[*global].name, value)
+            }
+        }
+    }
+}
+
+fn join(items: &[impl std::fmt::Display]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
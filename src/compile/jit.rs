@@ -0,0 +1,817 @@
+use std::collections::HashMap;
+
+use cranelift_codegen::ir::{
+    AbiParam, Block as ClifBlock, FuncRef, InstBuilder, MemFlags, Signature, UserFuncName,
+    Value as ClifValue,
+    condcodes::{FloatCC, IntCC},
+    types,
+};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{FuncId, Linkage, Module, default_libcall_names};
+
+use crate::ast::ast::File;
+use crate::compile::ir::{
+    BinaryOp, Block, Constant, Function, InstructionKind, Target, Terminator, Type, UnaryOp, Value,
+};
+use crate::compile::{lower::lower_each, opt::PassManager};
+use crate::eval::value::Value as RuntimeValue;
+use crate::primitives::{
+    errors::compile::{CompileError, CompileErrorKind},
+    position::Position,
+};
+
+/// How many times a function is called before it's compiled
+pub const HOT_CALLS: u32 = 50;
+
+/// Where each field of `State` is, for the loads and stores compiled code does
+const DEPTH: i32 = 0;
+const MAX_DEPTH: i32 = 8;
+const FAILED: i32 = 16;
+
+/// What compiled code shares with the functions it calls and the VM calling it: how deep the
+/// calls are, and whether one failed, dividing by zero, shifting by a negative count or
+/// nesting too deep. A function that fails returns at once, and its callers return when they
+/// see it has.
+#[repr(C)]
+struct State {
+    depth: i64,
+    max_depth: i64,
+    failed: i64,
+}
+
+/// The entry the VM calls a compiled function through, which reads its arguments from one
+/// array and writes its results to another, each as eight bytes
+type Trampoline = unsafe extern "C" fn(*mut State, *const i64, *mut i64);
+
+/// Compiles the functions that are called often to machine code with Cranelift, as `gor run
+/// --engine=jit` does. Only functions using nothing but ints, floats and bools, and calling
+/// nothing but each other, can be compiled, so the VM runs the rest. Those have no side
+/// effects, so when one fails the VM runs the call again itself, failing with the trace it
+/// keeps.
+pub struct Jit {
+    module: JITModule,
+    functions: Vec<Option<Function>>,
+    /// Whether each function can be compiled
+    compilable: Vec<bool>,
+    calls: Vec<u32>,
+    threshold: u32,
+    declared: Vec<Option<FuncId>>,
+    defined: Vec<bool>,
+    trampolines: Vec<Option<Trampoline>>,
+}
+
+impl Jit {
+    /// A JIT for the functions `lower_each` lowered, by the index the bytecode compiler gave
+    /// them. It fails if Cranelift can't generate code for this machine.
+    pub fn new(functions: Vec<Option<Function>>) -> Result<Jit, CompileError> {
+        let unsupported = |reason: String| {
+            CompileError::new(
+                CompileErrorKind::Unsupported(format!("JIT compilation on this machine: {reason}")),
+                Position::new(1, 0, 0),
+            )
+        };
+        let mut flags = settings::builder();
+        for (name, value) in [
+            ("opt_level", "speed"),
+            ("use_colocated_libcalls", "false"),
+            ("is_pic", "false"),
+            ("enable_multi_ret_implicit_sret", "true"),
+        ] {
+            flags
+                .set(name, value)
+                .map_err(|error| unsupported(error.to_string()))?;
+        }
+        let isa = cranelift_native::builder()
+            .map_err(|error| unsupported(error.to_string()))?
+            .finish(settings::Flags::new(flags))
+            .map_err(|error| unsupported(error.to_string()))?;
+        let count = functions.len();
+        Ok(Jit {
+            module: JITModule::new(JITBuilder::with_isa(isa, default_libcall_names())),
+            compilable: compilable(&functions),
+            functions,
+            calls: vec![0; count],
+            threshold: HOT_CALLS,
+            declared: vec![None; count],
+            defined: vec![false; count],
+            trampolines: vec![None; count],
+        })
+    }
+
+    /// A JIT for the program compiled from `file`, which lowers each of its functions that
+    /// can be and optimizes them. A file that can't be lowered at all, like one with methods,
+    /// runs on the VM alone.
+    pub fn for_file(file: &File) -> Result<Jit, CompileError> {
+        let mut functions = lower_each(file).unwrap_or_default();
+        let passes = PassManager::for_level(1);
+        for function in functions.iter_mut().flatten() {
+            passes.run_function(function);
+        }
+        Jit::new(functions)
+    }
+
+    /// Compile a function once it's been called `threshold` times, rather than `HOT_CALLS`
+    pub fn with_threshold(mut self, threshold: u32) -> Jit {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Whether the function at `function` can be compiled
+    pub fn is_compilable(&self, function: usize) -> bool {
+        self.compilable.get(function).copied().unwrap_or(false)
+    }
+
+    /// Run the function at `function` as machine code, if it's hot, compiling it first if it
+    /// hasn't been. The calls the VM is running, `depth`, count towards `max_depth`. None
+    /// means the VM should run it, as it isn't hot or it failed.
+    pub fn call(
+        &mut self,
+        function: usize,
+        arguments: &[RuntimeValue],
+        depth: usize,
+        max_depth: usize,
+    ) -> Option<Vec<RuntimeValue>> {
+        if !self.is_compilable(function) || depth >= max_depth {
+            return None;
+        }
+        if self.trampolines[function].is_none() {
+            self.calls[function] += 1;
+            if self.calls[function] < self.threshold {
+                return None;
+            }
+            if self.compile(function).is_err() {
+                // The module may be left half defined, so nothing more is compiled
+                self.compilable.fill(false);
+                return None;
+            }
+        }
+        let trampoline = self.trampolines[function]?;
+        let arguments = arguments
+            .iter()
+            .map(|argument| match argument {
+                RuntimeValue::Int(value) => Some(*value),
+                RuntimeValue::Float(value) => Some(value.to_bits() as i64),
+                RuntimeValue::Bool(value) => Some(*value as i64),
+                _ => None,
+            })
+            .collect::<Option<Vec<i64>>>()?;
+        let result_types = &self.functions[function].as_ref()?.results;
+        let mut results = vec![0; result_types.len()];
+        let mut state = State {
+            depth: depth as i64,
+            max_depth: max_depth as i64,
+            failed: 0,
+        };
+        // Safety: the trampoline was compiled for this function, which takes as many
+        // arguments and returns as many results as these arrays hold
+        unsafe { trampoline(&mut state, arguments.as_ptr(), results.as_mut_ptr()) };
+        if state.failed != 0 {
+            return None;
+        }
+        Some(
+            result_types
+                .iter()
+                .zip(results)
+                .map(|(result_type, result)| match result_type {
+                    Type::Float => RuntimeValue::Float(f64::from_bits(result as u64)),
+                    Type::Bool => RuntimeValue::Bool(result & 0xFF != 0),
+                    _ => RuntimeValue::Int(result),
+                })
+                .collect(),
+        )
+    }
+
+    /// Compile `function`, with every function it calls, and its trampoline
+    fn compile(&mut self, function: usize) -> Result<(), String> {
+        let mut pending = vec![function];
+        while let Some(index) = pending.pop() {
+            if self.defined[index] {
+                continue;
+            }
+            self.define(index)?;
+            pending.extend(callees(self.function(index)));
+        }
+        let trampoline = self.define_trampoline(function)?;
+        self.module
+            .finalize_definitions()
+            .map_err(|error| error.to_string())?;
+        let code = self.module.get_finalized_function(trampoline);
+        // Safety: the trampoline was defined with the signature `Trampoline` has
+        self.trampolines[function] =
+            Some(unsafe { std::mem::transmute::<*const u8, Trampoline>(code) });
+        Ok(())
+    }
+
+    fn function(&self, index: usize) -> &Function {
+        self.functions[index]
+            .as_ref()
+            .expect("only lowered functions are compiled")
+    }
+
+    /// The function's signature: the state, then its parameters
+    fn signature(&self, index: usize) -> Signature {
+        let function = self.function(index);
+        let mut signature = self.module.make_signature();
+        signature.params.push(AbiParam::new(types::I64));
+        for parameter in &function.parameters {
+            signature.params.push(AbiParam::new(clif_type(*parameter)));
+        }
+        for result in &function.results {
+            signature.returns.push(AbiParam::new(clif_type(*result)));
+        }
+        signature
+    }
+
+    fn declare(&mut self, index: usize) -> Result<FuncId, String> {
+        if let Some(id) = self.declared[index] {
+            return Ok(id);
+        }
+        let signature = self.signature(index);
+        let id = self
+            .module
+            .declare_function(&format!("f{index}"), Linkage::Local, &signature)
+            .map_err(|error| error.to_string())?;
+        self.declared[index] = Some(id);
+        Ok(id)
+    }
+
+    fn define(&mut self, index: usize) -> Result<(), String> {
+        let id = self.declare(index)?;
+        let mut context = self.module.make_context();
+        context.func.signature = self.signature(index);
+        context.func.name = UserFuncName::user(0, index as u32);
+        let mut references = HashMap::new();
+        for callee in callees(self.function(index)) {
+            let callee_id = self.declare(callee)?;
+            let reference = self
+                .module
+                .declare_func_in_func(callee_id, &mut context.func);
+            references.insert(callee, reference);
+        }
+        let mut builder_context = FunctionBuilderContext::new();
+        let function = self.functions[index]
+            .as_ref()
+            .expect("only lowered functions are compiled");
+        let builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+        Translator {
+            builder,
+            function,
+            blocks: Vec::new(),
+            values: HashMap::new(),
+            state: None,
+            callees: references,
+        }
+        .translate();
+        self.module
+            .define_function(id, &mut context)
+            .map_err(|error| format!("{error:?}"))?;
+        self.module.clear_context(&mut context);
+        self.defined[index] = true;
+        Ok(())
+    }
+
+    /// Define the entry the VM calls `index` through
+    fn define_trampoline(&mut self, index: usize) -> Result<FuncId, String> {
+        let callee = self.declare(index)?;
+        let mut signature = self.module.make_signature();
+        signature.params.extend([AbiParam::new(types::I64); 3]);
+        let id = self
+            .module
+            .declare_function(&format!("enter{index}"), Linkage::Local, &signature)
+            .map_err(|error| error.to_string())?;
+        let mut context = self.module.make_context();
+        context.func.signature = signature;
+        let reference = self.module.declare_func_in_func(callee, &mut context.func);
+        let function = self.function(index);
+        let mut builder_context = FunctionBuilderContext::new();
+        let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        let &[state, arguments, results] = builder.block_params(entry) else {
+            unreachable!("the trampoline takes three parameters");
+        };
+        let mut call_arguments = vec![state];
+        for (slot, parameter) in function.parameters.iter().enumerate() {
+            call_arguments.push(builder.ins().load(
+                clif_type(*parameter),
+                MemFlags::trusted(),
+                arguments,
+                slot as i32 * 8,
+            ));
+        }
+        let call = builder.ins().call(reference, &call_arguments);
+        let values = builder.inst_results(call).to_vec();
+        for (slot, value) in values.into_iter().enumerate() {
+            builder
+                .ins()
+                .store(MemFlags::trusted(), value, results, slot as i32 * 8);
+        }
+        builder.ins().return_(&[]);
+        builder.seal_all_blocks();
+        builder.finalize();
+        self.module
+            .define_function(id, &mut context)
+            .map_err(|error| format!("{error:?}"))?;
+        self.module.clear_context(&mut context);
+        Ok(id)
+    }
+}
+
+impl std::fmt::Debug for Jit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jit")
+            .field("compilable", &self.compilable)
+            .field("calls", &self.calls)
+            .field("threshold", &self.threshold)
+            .finish()
+    }
+}
+
+/// Builds one function's Cranelift IR from the IR
+struct Translator<'a> {
+    builder: FunctionBuilder<'a>,
+    function: &'a Function,
+    /// The Cranelift block of each block. The first block's is the function's entry, after
+    /// which the depth is checked.
+    blocks: Vec<ClifBlock>,
+    values: HashMap<Value, ClifValue>,
+    state: Option<ClifValue>,
+    callees: HashMap<usize, FuncRef>,
+}
+
+impl Translator<'_> {
+    fn translate(mut self) {
+        self.blocks = self
+            .function
+            .blocks
+            .iter()
+            .map(|_| self.builder.create_block())
+            .collect();
+        let entry = self.blocks[0];
+        self.builder.append_block_params_for_function_params(entry);
+        let parameters = self.builder.block_params(entry).to_vec();
+        self.state = Some(parameters[0]);
+        for (block, basic_block) in self.function.blocks.iter().enumerate() {
+            if block == 0 {
+                self.values.extend(
+                    basic_block
+                        .parameters
+                        .iter()
+                        .copied()
+                        .zip(parameters[1..].iter().copied()),
+                );
+                continue;
+            }
+            for parameter in &basic_block.parameters {
+                let value_type = clif_type(self.function.type_of(*parameter));
+                let value = self
+                    .builder
+                    .append_block_param(self.blocks[block], value_type);
+                self.values.insert(*parameter, value);
+            }
+        }
+
+        for block in reverse_postorder(self.function) {
+            self.builder.switch_to_block(self.blocks[block.0 as usize]);
+            if block == Block(0) {
+                let depth = self.load(DEPTH);
+                let depth = self.builder.ins().iadd_imm(depth, 1);
+                self.store(depth, DEPTH);
+            }
+            self.translate_block(block);
+        }
+        self.builder.seal_all_blocks();
+        self.builder.finalize();
+    }
+
+    fn translate_block(&mut self, block: Block) {
+        let function = self.function;
+        let basic_block = function.block(block);
+        for instruction in &basic_block.instructions {
+            let results = match &instruction.kind {
+                InstructionKind::Const(constant) => vec![self.constant(constant)],
+                InstructionKind::Copy(value) => vec![self.value(*value)],
+                InstructionKind::Binary(op, left, right) => {
+                    vec![self.binary(*op, *left, *right)]
+                }
+                InstructionKind::Unary(op, value) => vec![self.unary(*op, *value)],
+                InstructionKind::Convert(value) => {
+                    let converted = self.value(*value);
+                    let ins = self.builder.ins();
+                    vec![match function.type_of(instruction.results[0]) {
+                        Type::Float => ins.fcvt_from_sint(types::F64, converted),
+                        _ => ins.fcvt_to_sint_sat(types::I64, converted),
+                    }]
+                }
+                InstructionKind::Call {
+                    function: callee,
+                    arguments,
+                } => {
+                    // Calls nested too deep fail where the call is, as in the VM
+                    let depth = self.load(DEPTH);
+                    let max_depth = self.load(MAX_DEPTH);
+                    let overflows =
+                        self.builder
+                            .ins()
+                            .icmp(IntCC::SignedGreaterThanOrEqual, depth, max_depth);
+                    let call_block = self.builder.create_block();
+                    self.fail_if(overflows, call_block);
+                    let mut values = vec![self.state()];
+                    values.extend(arguments.iter().map(|argument| self.value(*argument)));
+                    let call = self.builder.ins().call(self.callees[callee], &values);
+                    let results = self.builder.inst_results(call).to_vec();
+                    // A call that failed has already said so, so this returns too
+                    let failed = self.load(FAILED);
+                    let next = self.builder.create_block();
+                    let failed_block = self.builder.create_block();
+                    self.builder
+                        .ins()
+                        .brif(failed, failed_block, &[], next, &[]);
+                    self.builder.switch_to_block(failed_block);
+                    self.return_zeros();
+                    self.builder.switch_to_block(next);
+                    results
+                }
+                InstructionKind::Len(_)
+                | InstructionKind::CallNative { .. }
+                | InstructionKind::LoadGlobal(_)
+                | InstructionKind::StoreGlobal(..) => {
+                    unreachable!("only functions that can be compiled are")
+                }
+            };
+            for (result, value) in instruction.results.iter().zip(results) {
+                self.values.insert(*result, value);
+            }
+        }
+        match &basic_block.terminator {
+            Terminator::Jump(target) => {
+                let (block, arguments) = self.target(target);
+                self.builder.ins().jump(block, &arguments);
+            }
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.value(*condition);
+                let (then, then_arguments) = self.target(then);
+                let (otherwise, otherwise_arguments) = self.target(otherwise);
+                self.builder.ins().brif(
+                    condition,
+                    then,
+                    &then_arguments,
+                    otherwise,
+                    &otherwise_arguments,
+                );
+            }
+            Terminator::Return(values) => {
+                let depth = self.load(DEPTH);
+                let depth = self.builder.ins().iadd_imm(depth, -1);
+                self.store(depth, DEPTH);
+                let values: Vec<ClifValue> =
+                    values.iter().map(|value| self.value(*value)).collect();
+                self.builder.ins().return_(&values);
+            }
+            Terminator::Panic { .. } => unreachable!("only functions that can be compiled are"),
+        }
+    }
+
+    fn constant(&mut self, constant: &Constant) -> ClifValue {
+        let ins = self.builder.ins();
+        match constant {
+            Constant::Int(value) => ins.iconst(types::I64, *value),
+            Constant::Float(value) => ins.f64const(*value),
+            Constant::Bool(value) => ins.iconst(types::I8, *value as i64),
+            Constant::String(_) => unreachable!("only functions that can be compiled are"),
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, left: Value, right: Value) -> ClifValue {
+        let operand_type = self.function.type_of(left);
+        let (left, right) = (self.value(left), self.value(right));
+        if operand_type == Type::Float {
+            let ins = self.builder.ins();
+            return match op {
+                BinaryOp::Add => ins.fadd(left, right),
+                BinaryOp::Subtract => ins.fsub(left, right),
+                BinaryOp::Multiply => ins.fmul(left, right),
+                BinaryOp::Divide => ins.fdiv(left, right),
+                BinaryOp::Equal => ins.fcmp(FloatCC::Equal, left, right),
+                BinaryOp::NotEqual => ins.fcmp(FloatCC::NotEqual, left, right),
+                BinaryOp::Less => ins.fcmp(FloatCC::LessThan, left, right),
+                BinaryOp::LessEqual => ins.fcmp(FloatCC::LessThanOrEqual, left, right),
+                BinaryOp::Greater => ins.fcmp(FloatCC::GreaterThan, left, right),
+                BinaryOp::GreaterEqual => ins.fcmp(FloatCC::GreaterThanOrEqual, left, right),
+                _ => unreachable!("the checker only allows {} on ints", op.name()),
+            };
+        }
+        match op {
+            BinaryOp::Divide | BinaryOp::Remainder => {
+                let zero = self.builder.ins().icmp_imm(IntCC::Equal, right, 0);
+                let next = self.builder.create_block();
+                self.fail_if(zero, next);
+                // Dividing the smallest int by -1 overflows, which traps, so -1 is
+                // handled apart: the quotient wraps to the smallest int and the remainder is 0
+                let builder = &mut self.builder;
+                let minus_one = builder.ins().icmp_imm(IntCC::Equal, right, -1);
+                let one = builder.ins().iconst(types::I64, 1);
+                let divisor = builder.ins().select(minus_one, one, right);
+                if op == BinaryOp::Divide {
+                    let quotient = builder.ins().sdiv(left, divisor);
+                    let negated = builder.ins().ineg(left);
+                    builder.ins().select(minus_one, negated, quotient)
+                } else {
+                    let remainder = builder.ins().srem(left, divisor);
+                    let zero = builder.ins().iconst(types::I64, 0);
+                    builder.ins().select(minus_one, zero, remainder)
+                }
+            }
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                let negative = self.builder.ins().icmp_imm(IntCC::SignedLessThan, right, 0);
+                let next = self.builder.create_block();
+                self.fail_if(negative, next);
+                // Cranelift only uses the count's low bits, where Go shifts every bit out
+                let builder = &mut self.builder;
+                let all_out = builder.ins().icmp_imm(IntCC::SignedGreaterThan, right, 63);
+                if op == BinaryOp::ShiftLeft {
+                    let shifted = builder.ins().ishl(left, right);
+                    let zero = builder.ins().iconst(types::I64, 0);
+                    builder.ins().select(all_out, zero, shifted)
+                } else {
+                    let last = builder.ins().iconst(types::I64, 63);
+                    let count = builder.ins().select(all_out, last, right);
+                    builder.ins().sshr(left, count)
+                }
+            }
+            _ => {
+                let ins = self.builder.ins();
+                match op {
+                    BinaryOp::Add => ins.iadd(left, right),
+                    BinaryOp::Subtract => ins.isub(left, right),
+                    BinaryOp::Multiply => ins.imul(left, right),
+                    BinaryOp::And => ins.band(left, right),
+                    BinaryOp::Or => ins.bor(left, right),
+                    BinaryOp::Xor => ins.bxor(left, right),
+                    BinaryOp::AndNot => ins.band_not(left, right),
+                    BinaryOp::Equal => ins.icmp(IntCC::Equal, left, right),
+                    BinaryOp::NotEqual => ins.icmp(IntCC::NotEqual, left, right),
+                    BinaryOp::Less => ins.icmp(IntCC::SignedLessThan, left, right),
+                    BinaryOp::LessEqual => ins.icmp(IntCC::SignedLessThanOrEqual, left, right),
+                    BinaryOp::Greater => ins.icmp(IntCC::SignedGreaterThan, left, right),
+                    BinaryOp::GreaterEqual => {
+                        ins.icmp(IntCC::SignedGreaterThanOrEqual, left, right)
+                    }
+                    _ => unreachable!("division and shifts are handled above"),
+                }
+            }
+        }
+    }
+
+    fn unary(&mut self, op: UnaryOp, value: Value) -> ClifValue {
+        let is_float = self.function.type_of(value) == Type::Float;
+        let value = self.value(value);
+        let ins = self.builder.ins();
+        match op {
+            UnaryOp::Negate if is_float => ins.fneg(value),
+            UnaryOp::Negate => ins.ineg(value),
+            UnaryOp::Not => ins.bxor_imm(value, 1),
+            UnaryOp::Complement => ins.bnot(value),
+        }
+    }
+
+    /// Go to `next` unless `condition` is true, when the function fails
+    fn fail_if(&mut self, condition: ClifValue, next: ClifBlock) {
+        let failed = self.builder.create_block();
+        self.builder.set_cold_block(failed);
+        self.builder.ins().brif(condition, failed, &[], next, &[]);
+        self.builder.switch_to_block(failed);
+        let one = self.builder.ins().iconst(types::I64, 1);
+        self.store(one, FAILED);
+        self.return_zeros();
+        self.builder.switch_to_block(next);
+    }
+
+    /// Return from a function that failed, with results that won't be read
+    fn return_zeros(&mut self) {
+        let results: Vec<ClifValue> = self
+            .function
+            .results
+            .iter()
+            .map(|result| match result {
+                Type::Float => self.builder.ins().f64const(0.0),
+                result => self.builder.ins().iconst(clif_type(*result), 0),
+            })
+            .collect();
+        self.builder.ins().return_(&results);
+    }
+
+    fn target(&self, target: &Target) -> (ClifBlock, Vec<ClifValue>) {
+        (
+            self.blocks[target.block.0 as usize],
+            target
+                .arguments
+                .iter()
+                .map(|argument| self.value(*argument))
+                .collect(),
+        )
+    }
+
+    fn value(&self, value: Value) -> ClifValue {
+        self.values[&value]
+    }
+
+    fn state(&self) -> ClifValue {
+        self.state
+            .expect("the state is the entry's first parameter")
+    }
+
+    fn load(&mut self, offset: i32) -> ClifValue {
+        let state = self.state();
+        self.builder
+            .ins()
+            .load(types::I64, MemFlags::trusted(), state, offset)
+    }
+
+    fn store(&mut self, value: ClifValue, offset: i32) {
+        let state = self.state();
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), value, state, offset);
+    }
+}
+
+fn clif_type(value_type: Type) -> types::Type {
+    match value_type {
+        Type::Float => types::F64,
+        Type::Bool => types::I8,
+        _ => types::I64,
+    }
+}
+
+/// The functions `function` calls
+fn callees(function: &Function) -> Vec<usize> {
+    let mut callees = Vec::new();
+    for block in &function.blocks {
+        for instruction in &block.instructions {
+            if let InstructionKind::Call { function, .. } = instruction.kind
+                && !callees.contains(&function)
+            {
+                callees.push(function);
+            }
+        }
+    }
+    callees
+}
+
+/// Which functions can be compiled: those without strings, globals, native calls or panics,
+/// which call only functions that can be
+fn compilable(functions: &[Option<Function>]) -> Vec<bool> {
+    let mut compilable: Vec<bool> = functions
+        .iter()
+        .map(|function| function.as_ref().is_some_and(is_numeric))
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (index, function) in functions.iter().enumerate() {
+            if compilable[index]
+                && function.as_ref().is_some_and(|function| {
+                    callees(function).iter().any(|callee| !compilable[*callee])
+                })
+            {
+                compilable[index] = false;
+                changed = true;
+            }
+        }
+    }
+    compilable
+}
+
+fn is_numeric(function: &Function) -> bool {
+    function
+        .types
+        .iter()
+        .all(|value_type| *value_type != Type::String)
+        && function.blocks.iter().all(|block| {
+            // The first block is the entry the depth is counted in, so nothing goes to it
+            !matches!(block.terminator, Terminator::Panic { .. })
+                && block
+                    .terminator
+                    .targets()
+                    .iter()
+                    .all(|target| target.block != Block(0))
+                && block.instructions.iter().all(|instruction| {
+                    matches!(
+                        instruction.kind,
+                        InstructionKind::Const(_)
+                            | InstructionKind::Copy(_)
+                            | InstructionKind::Binary(..)
+                            | InstructionKind::Unary(..)
+                            | InstructionKind::Convert(_)
+                            | InstructionKind::Call { .. }
+                    )
+                })
+        })
+}
+
+/// The function's blocks reachable from its first, each before the blocks it goes to, but
+/// for loops, so each value is translated before it's used
+fn reverse_postorder(function: &Function) -> Vec<Block> {
+    let mut visited = vec![false; function.blocks.len()];
+    let mut order = Vec::new();
+    let mut stack = vec![(Block(0), 0)];
+    visited[0] = true;
+    while let Some((block, next)) = stack.pop() {
+        let targets = function.block(block).terminator.targets();
+        match targets.get(next) {
+            Some(target) => {
+                stack.push((block, next + 1));
+                let successor = target.block;
+                if !visited[successor.0 as usize] {
+                    visited[successor.0 as usize] = true;
+                    stack.push((successor, 0));
+                }
+            }
+            None => order.push(block),
+        }
+    }
+    order.reverse();
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::{compiler::compile, vm::Vm};
+    use crate::parser::parser::Parser;
+    use crate::test_support::Captured;
+
+    const SOURCE: &str = "package main\n\nimport \"fmt\"\n\nfunc fib(n int) int {\n\tif n < 2 {\n\t\treturn n\n\t}\n\treturn fib(n-1) + fib(n-2)\n}\n\nfunc divide(a, b int) (int, int) {\n\treturn a / b, a % b\n}\n\nfunc shift(a, n int) (int, int) {\n\treturn a << n, a >> n\n}\n\nfunc root(x float64) float64 {\n\tz := 1.0\n\ti := 0\n\tfor i < 20 {\n\t\tz = z - (z*z-x)/(2*z)\n\t\ti = i + 1\n\t}\n\treturn z\n}\n\nfunc even(n int) bool {\n\treturn n%2 == 0 && !(n < 0)\n}\n\nfunc show(n int) {\n\tfmt.Println(n)\n}\n\nfunc twice(n int) int {\n\tshow(n)\n\treturn n * 2\n}\n\nfunc main() {\n\tmin := -9223372036854775807 - 1\n\tfmt.Println(fib(20), root(2), even(4), even(-4), int(root(10)))\n\tfmt.Println(divide(min, -1))\n\tfmt.Println(divide(-7, 2))\n\tfmt.Println(shift(-8, 70))\n\tfmt.Println(twice(3))\n\tfmt.Println(divide(1, 0))\n}\n";
+
+    fn jit(source: &str) -> Jit {
+        Jit::for_file(&Parser::new(source).parse().unwrap())
+            .unwrap()
+            .with_threshold(1)
+    }
+
+    #[test]
+    fn compilable_functions() {
+        let jit = jit(SOURCE);
+        // init, which has no variables to initialize, and every function but `show`, which
+        // prints, and `twice` and `main`, which call it
+        let compilable: Vec<usize> = (0..9).filter(|index| jit.is_compilable(*index)).collect();
+        assert_eq!(compilable, [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn call_compiled() {
+        let mut jit = jit(SOURCE);
+        let int = RuntimeValue::Int;
+        assert_eq!(jit.call(1, &[int(20)], 0, 100), Some(vec![int(6765)]));
+        assert_eq!(
+            jit.call(2, &[int(i64::MIN), int(-1)], 0, 100),
+            Some(vec![int(i64::MIN), int(0)])
+        );
+        assert_eq!(
+            jit.call(3, &[int(3), int(2)], 0, 100),
+            Some(vec![int(12), int(0)])
+        );
+        assert_eq!(
+            jit.call(4, &[RuntimeValue::Float(4.0)], 0, 100),
+            Some(vec![RuntimeValue::Float(2.0)])
+        );
+        assert_eq!(
+            jit.call(5, &[int(-4)], 0, 100),
+            Some(vec![RuntimeValue::Bool(false)])
+        );
+        // Failing calls are left to the VM
+        assert_eq!(jit.call(2, &[int(1), int(0)], 0, 100), None);
+        assert_eq!(jit.call(3, &[int(1), int(-1)], 0, 100), None);
+        assert_eq!(jit.call(1, &[int(20)], 90, 100), None);
+        assert_eq!(jit.call(7, &[int(1)], 0, 100), None);
+    }
+
+    #[test]
+    fn run_with_jit() {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        let program = compile(&file).unwrap();
+        let run = |vm: Vm| {
+            let output = Captured::default();
+            let result = vm.with_output(output.clone()).run(&program);
+            (
+                output.take(),
+                result.err().map(|error| error.kind.to_string()),
+            )
+        };
+        let compiled = run(Vm::new().with_jit(jit(SOURCE)));
+        assert_eq!(compiled, run(Vm::new()));
+        assert_eq!(
+            compiled.0,
+            "6765 1.414213562373095 true false 3\n-9223372036854775808 0\n-3 -1\n0 -1\n3\n6\n"
+        );
+        assert!(compiled.1.unwrap().contains("integer divide by zero"));
+    }
+}
@@ -0,0 +1,1515 @@
+use std::collections::HashMap;
+
+use crate::ast::{
+    ast::File,
+    declaration::{Declaration, DeclarationKind, VariableSpec},
+    expression::{Expression, ExpressionKind, UnaryOperator},
+    statement::{Statement, StatementKind},
+};
+use crate::compile::ir::{
+    BasicBlock, BinaryOp, Block, Constant, Function, Global, Instruction, InstructionKind, Module,
+    Target, Terminator, Type, UnaryOp, Value,
+};
+use crate::eval::stdlib;
+use crate::lexer::{literal::unquote_string, token_type::Operator};
+use crate::primitives::{
+    errors::compile::{CompileError, CompileErrorKind},
+    position::Position,
+};
+use crate::semantic::{
+    builtins::Builtin, checker::check, info::TypeInfo, scope::SymbolId, scope::SymbolKind,
+};
+use crate::types::{
+    constant::Constant as ConstantValue,
+    types::{BasicKind, Type as CheckedType, TypeId},
+};
+
+/// The name of the function initializing the package level variables
+const INIT: &str = "init";
+
+/// Check `file` and lower it to the IR. The same part of Go lowers as the bytecode compiler
+/// compiles, without slices: ints, floats, strings and bools, functions, the if and for
+/// statements, and calls to the native packages whose results go unused.
+pub fn lower(file: &File) -> Result<Module, CompileError> {
+    let info = checked(file)?;
+    let (mut module, functions) = Lowerer::new(file, &info).lower_file(file)?;
+    module.functions = functions.into_iter().collect::<Result<_, _>>()?;
+    Ok(module)
+}
+
+/// Check `file` and lower each function that can be to the IR, by the index the bytecode
+/// compiler gives it, with None for the others, like a function using slices or calling one
+/// that can't be lowered
+pub fn lower_each(file: &File) -> Result<Vec<Option<Function>>, CompileError> {
+    let info = checked(file)?;
+    let (_, functions) = Lowerer::new(file, &info).lower_file(file)?;
+    Ok(functions.into_iter().map(Result::ok).collect())
+}
+
+fn checked(file: &File) -> Result<TypeInfo, CompileError> {
+    let (info, errors) = check(file);
+    match errors.into_iter().next() {
+        Some(error) => Err(CompileError::new(
+            CompileErrorKind::Check(Box::new(error.kind)),
+            error.position,
+        )),
+        None => Ok(info),
+    }
+}
+
+/// What a value is kept in while a function is built: a Go variable, or one of the
+/// lowering's own, like a range loop's counter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Variable {
+    Symbol(SymbolId),
+    Temporary(u32),
+}
+
+#[derive(Default)]
+struct BlockState {
+    parameters: Vec<Value>,
+    instructions: Vec<Instruction>,
+    terminator: Option<Terminator>,
+    predecessors: Vec<Block>,
+    /// Whether every block that goes to this one is known, so a variable read in it can be
+    /// looked up in them
+    sealed: bool,
+    /// Parameters added for variables read before the block was sealed, which get their
+    /// arguments once it is
+    incomplete: Vec<(Variable, Value)>,
+    /// Whether nothing goes to the block, so where it goes to doesn't count as a way there
+    unreachable: bool,
+}
+
+/// Builds a function in SSA form straight from its statements, as in Braun et al.'s "Simple
+/// and Efficient Construction of Static Single Assignment Form". Each variable's value is
+/// tracked per block, and reading one in a block that doesn't assign it looks it up in the
+/// blocks before, adding a block parameter where they disagree.
+struct Builder {
+    blocks: Vec<BlockState>,
+    types: Vec<Type>,
+    current: Block,
+    definitions: HashMap<(Variable, Block), Value>,
+    variable_types: HashMap<Variable, Type>,
+    /// Parameters found to always take the same value, which is used instead
+    aliases: HashMap<Value, Value>,
+    temporaries: u32,
+    /// Where zero values made up for variables read in unreachable code are said to be
+    position: Position,
+}
+
+impl Builder {
+    fn new(position: Position) -> Builder {
+        let mut builder = Builder {
+            blocks: Vec::new(),
+            types: Vec::new(),
+            current: Block(0),
+            definitions: HashMap::new(),
+            variable_types: HashMap::new(),
+            aliases: HashMap::new(),
+            temporaries: 0,
+            position,
+        };
+        let entry = builder.new_block();
+        builder.seal(entry);
+        builder
+    }
+
+    fn new_block(&mut self) -> Block {
+        self.blocks.push(BlockState::default());
+        Block(self.blocks.len() as u32 - 1)
+    }
+
+    fn new_value(&mut self, value_type: Type) -> Value {
+        self.types.push(value_type);
+        Value(self.types.len() as u32 - 1)
+    }
+
+    fn new_temporary(&mut self, value_type: Type) -> Variable {
+        self.temporaries += 1;
+        let variable = Variable::Temporary(self.temporaries);
+        self.variable_types.insert(variable, value_type);
+        variable
+    }
+
+    fn state(&mut self, block: Block) -> &mut BlockState {
+        &mut self.blocks[block.0 as usize]
+    }
+
+    fn emit(&mut self, kind: InstructionKind, types: &[Type], position: Position) -> Vec<Value> {
+        let results: Vec<Value> = types
+            .iter()
+            .map(|value_type| self.new_value(*value_type))
+            .collect();
+        let instruction = Instruction {
+            results: results.clone(),
+            kind,
+            position,
+        };
+        let current = self.current;
+        self.state(current).instructions.push(instruction);
+        results
+    }
+
+    fn emit_value(&mut self, kind: InstructionKind, value_type: Type, position: Position) -> Value {
+        self.emit(kind, &[value_type], position)[0]
+    }
+
+    /// End the current block. Whatever's lowered after it, like code after a return, goes in
+    /// a block nothing goes to, which is dropped when the function is finished.
+    fn terminate(&mut self, terminator: Terminator) {
+        let current = self.current;
+        let targets: Vec<Block> = match self.state(current).unreachable {
+            true => Vec::new(),
+            false => terminator
+                .targets()
+                .iter()
+                .map(|target| target.block)
+                .collect(),
+        };
+        for target in targets {
+            let predecessors = &mut self.state(target).predecessors;
+            if !predecessors.contains(&current) {
+                predecessors.push(current);
+            }
+        }
+        self.state(current).terminator = Some(terminator);
+        let unreachable = self.new_block();
+        self.seal(unreachable);
+        self.current = unreachable;
+    }
+
+    fn jump(&mut self, block: Block) {
+        self.terminate(Terminator::Jump(Target {
+            block,
+            arguments: Vec::new(),
+        }));
+    }
+
+    fn branch(&mut self, condition: Value, then: Block, otherwise: Block) {
+        self.terminate(Terminator::Branch {
+            condition,
+            then: Target {
+                block: then,
+                arguments: Vec::new(),
+            },
+            otherwise: Target {
+                block: otherwise,
+                arguments: Vec::new(),
+            },
+        });
+    }
+
+    /// Say every block that goes to `block` is known
+    fn seal(&mut self, block: Block) {
+        let incomplete = std::mem::take(&mut self.state(block).incomplete);
+        for (variable, parameter) in incomplete {
+            self.add_arguments(variable, block, parameter);
+        }
+        let state = self.state(block);
+        state.sealed = true;
+        state.unreachable = block != Block(0) && state.predecessors.is_empty();
+    }
+
+    fn write(&mut self, variable: Variable, value: Value) {
+        self.definitions.insert((variable, self.current), value);
+    }
+
+    fn read(&mut self, variable: Variable) -> Value {
+        self.read_in(variable, self.current)
+    }
+
+    fn read_in(&mut self, variable: Variable, block: Block) -> Value {
+        match self.definitions.get(&(variable, block)) {
+            Some(value) => self.resolve(*value),
+            None => self.read_before(variable, block),
+        }
+    }
+
+    /// A variable's value in `block` before the block assigns it, from the blocks that go to
+    /// it
+    fn read_before(&mut self, variable: Variable, block: Block) -> Value {
+        let value_type = self.variable_types[&variable];
+        let state = self.state(block);
+        let value = if !state.sealed {
+            let parameter = self.add_parameter(block, value_type);
+            self.state(block).incomplete.push((variable, parameter));
+            parameter
+        } else if let [predecessor] = state.predecessors[..] {
+            self.read_in(variable, predecessor)
+        } else if state.predecessors.is_empty() {
+            // Only unreachable code reads a variable nothing before it assigned
+            self.zero_value(block, value_type)
+        } else {
+            // The parameter is the variable's value while its arguments are looked up, which
+            // ends the search when it loops back here
+            let parameter = self.add_parameter(block, value_type);
+            self.definitions.insert((variable, block), parameter);
+            self.add_arguments(variable, block, parameter)
+        };
+        self.definitions.insert((variable, block), value);
+        value
+    }
+
+    fn add_parameter(&mut self, block: Block, value_type: Type) -> Value {
+        let parameter = self.new_value(value_type);
+        self.state(block).parameters.push(parameter);
+        parameter
+    }
+
+    /// Pass the variable's value in each block that goes to `block` as the argument for
+    /// `parameter`, returning the parameter, or the value it always takes
+    fn add_arguments(&mut self, variable: Variable, block: Block, parameter: Value) -> Value {
+        let predecessors = self.state(block).predecessors.clone();
+        let mut arguments = Vec::new();
+        for predecessor in predecessors {
+            let argument = self.read_in(variable, predecessor);
+            arguments.push(argument);
+            let terminator = self.state(predecessor).terminator.as_mut();
+            for target in terminator.into_iter().flat_map(Terminator::targets_mut) {
+                if target.block == block {
+                    target.arguments.push(argument);
+                }
+            }
+        }
+        self.remove_trivial(parameter, &arguments)
+    }
+
+    /// A parameter that only ever takes one value besides itself isn't needed
+    fn remove_trivial(&mut self, parameter: Value, arguments: &[Value]) -> Value {
+        let mut same = None;
+        for argument in arguments {
+            let argument = self.resolve(*argument);
+            if Some(argument) == same || argument == parameter {
+                continue;
+            }
+            if same.is_some() {
+                return parameter;
+            }
+            same = Some(argument);
+        }
+        match same {
+            Some(value) => {
+                self.aliases.insert(parameter, value);
+                value
+            }
+            None => parameter,
+        }
+    }
+
+    fn resolve(&self, mut value: Value) -> Value {
+        while let Some(alias) = self.aliases.get(&value) {
+            value = *alias;
+        }
+        value
+    }
+
+    /// A zero value defined at the start of `block`
+    fn zero_value(&mut self, block: Block, value_type: Type) -> Value {
+        let constant = match value_type {
+            Type::Int => Constant::Int(0),
+            Type::Float => Constant::Float(0.0),
+            Type::Bool => Constant::Bool(false),
+            Type::String => Constant::String(String::new()),
+        };
+        let value = self.new_value(value_type);
+        let instruction = Instruction {
+            results: vec![value],
+            kind: InstructionKind::Const(constant),
+            position: self.position,
+        };
+        self.state(block).instructions.insert(0, instruction);
+        value
+    }
+
+    /// The function built, without the blocks it can never reach or the parameters found to
+    /// be unneeded, and with its blocks and values numbered in order
+    fn finish(mut self, name: &str, parameters: Vec<Type>, results: Vec<Type>) -> Function {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut pending = vec![0];
+        while let Some(index) = pending.pop() {
+            if std::mem::replace(&mut reachable[index], true) {
+                continue;
+            }
+            let terminator = self.blocks[index].terminator.as_ref();
+            for target in terminator.into_iter().flat_map(Terminator::targets) {
+                pending.push(target.block.0 as usize);
+            }
+        }
+        let mut renumbered = HashMap::new();
+        for index in (0..self.blocks.len()).filter(|index| reachable[*index]) {
+            renumbered.insert(Block(index as u32), Block(renumbered.len() as u32));
+        }
+        // Which of each block's parameters are still needed
+        let kept: Vec<Vec<bool>> = self
+            .blocks
+            .iter()
+            .map(|block| {
+                block
+                    .parameters
+                    .iter()
+                    .map(|parameter| !self.aliases.contains_key(parameter))
+                    .collect()
+            })
+            .collect();
+
+        let states = std::mem::take(&mut self.blocks);
+        let mut blocks = Vec::new();
+        for (index, state) in states.into_iter().enumerate() {
+            if !reachable[index] {
+                continue;
+            }
+            let mut instructions = state.instructions;
+            for instruction in &mut instructions {
+                instruction.kind.map_operands(|value| self.resolve(value));
+            }
+            let mut terminator = state.terminator.expect("every block is terminated");
+            terminator.map_operands(|value| self.resolve(value));
+            for target in terminator.targets_mut() {
+                let kept = &kept[target.block.0 as usize];
+                target.arguments = std::mem::take(&mut target.arguments)
+                    .into_iter()
+                    .zip(kept)
+                    .filter(|(_, kept)| **kept)
+                    .map(|(argument, _)| argument)
+                    .collect();
+                target.block = renumbered[&target.block];
+            }
+            blocks.push(BasicBlock {
+                parameters: state
+                    .parameters
+                    .into_iter()
+                    .zip(&kept[index])
+                    .filter(|(_, kept)| **kept)
+                    .map(|(parameter, _)| parameter)
+                    .collect(),
+                instructions,
+                terminator,
+            });
+        }
+
+        // Values are numbered in the order they're defined
+        let mut values = HashMap::new();
+        let mut types = Vec::new();
+        for block in &blocks {
+            let defined = block.instructions.iter().flat_map(|i| &i.results);
+            for value in block.parameters.iter().chain(defined) {
+                values.insert(*value, Value(types.len() as u32));
+                types.push(self.types[value.0 as usize]);
+            }
+        }
+        let number = |value: Value| values[&value];
+        for block in &mut blocks {
+            for parameter in &mut block.parameters {
+                *parameter = number(*parameter);
+            }
+            for instruction in &mut block.instructions {
+                for result in &mut instruction.results {
+                    *result = number(*result);
+                }
+                instruction.kind.map_operands(number);
+            }
+            block.terminator.map_operands(number);
+        }
+        Function {
+            name: name.to_string(),
+            parameters,
+            results,
+            blocks,
+            types,
+        }
+    }
+}
+
+/// A function a call can be lowered to
+#[derive(Clone)]
+struct Callee {
+    index: usize,
+    parameters: Vec<Type>,
+    results: Vec<Type>,
+}
+
+/// The blocks a loop's continue and break statements go to
+struct Loop {
+    continue_block: Block,
+    break_block: Block,
+}
+
+struct Lowerer<'a> {
+    info: &'a TypeInfo,
+    /// Imported packages' paths, by the name they're referred to by
+    imports: HashMap<String, String>,
+    functions: HashMap<String, Callee>,
+    globals: HashMap<SymbolId, usize>,
+    /// The functions and package level variables whose types can't be lowered, with what
+    /// can't be
+    unsupported_functions: HashMap<String, CompileError>,
+    unsupported_globals: HashMap<SymbolId, String>,
+    module: Module,
+    builder: Builder,
+    loops: Vec<Loop>,
+    named_results: Vec<Variable>,
+    result_types: Vec<Type>,
+}
+
+impl<'a> Lowerer<'a> {
+    fn new(file: &File, info: &'a TypeInfo) -> Lowerer<'a> {
+        Lowerer {
+            info,
+            imports: file
+                .imports
+                .iter()
+                .map(|import| (import.local_name(), import.unquoted_path().to_string()))
+                .collect(),
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            unsupported_functions: HashMap::new(),
+            unsupported_globals: HashMap::new(),
+            module: Module {
+                package_name: file.package_name.clone(),
+                globals: Vec::new(),
+                functions: Vec::new(),
+                init: 0,
+                main: None,
+            },
+            builder: Builder::new(Position::new(1, 0, 0)),
+            loops: Vec::new(),
+            named_results: Vec::new(),
+            result_types: Vec::new(),
+        }
+    }
+
+    /// Lower the file to a module without functions, and each function, or why it can't be
+    fn lower_file(
+        mut self,
+        file: &File,
+    ) -> Result<(Module, Vec<Result<Function, CompileError>>), CompileError> {
+        // Every function gets its index before any is lowered, so calls can go either way
+        let mut functions = Vec::new();
+        let mut variables = Vec::new();
+        for declaration in &file.decls {
+            let position = declaration.position_start;
+            match &declaration.kind {
+                DeclarationKind::Function {
+                    receiver: Some(_), ..
+                } => return Err(unsupported("methods", position)),
+                DeclarationKind::Function { name, .. } if name == INIT => {
+                    return Err(unsupported("init functions", position));
+                }
+                DeclarationKind::Function { name, .. } => {
+                    match self.callee(declaration, functions.len() + 1) {
+                        Ok(callee) => {
+                            self.functions.insert(name.clone(), callee);
+                        }
+                        Err(error) => {
+                            self.unsupported_functions.insert(name.clone(), error);
+                        }
+                    }
+                    functions.push(declaration);
+                }
+                DeclarationKind::Variable { specs, .. } => variables.extend(specs),
+                DeclarationKind::Constant { .. } => {
+                    return Err(unsupported("constant declarations", position));
+                }
+                DeclarationKind::Type { .. } => {
+                    return Err(unsupported("type declarations", position));
+                }
+            }
+        }
+        for spec in &variables {
+            for name in &spec.names {
+                let (Some(symbol), ExpressionKind::Identifier(identifier)) =
+                    (self.info.symbol_of(name), &name.kind)
+                else {
+                    continue;
+                };
+                match self.symbol_type(symbol, name.position_start) {
+                    Ok(value_type) => {
+                        self.globals.insert(symbol, self.module.globals.len());
+                        self.module.globals.push(Global {
+                            name: identifier.clone(),
+                            value_type,
+                        });
+                    }
+                    Err(error) => {
+                        let CompileErrorKind::Unsupported(code) = error.kind else {
+                            return Err(error);
+                        };
+                        self.unsupported_globals.insert(symbol, code);
+                    }
+                }
+            }
+        }
+
+        // Package level variables are initialized in the order they're declared, as the
+        // interpreter does
+        let init = variables
+            .into_iter()
+            .try_for_each(|spec| self.variable_spec(spec))
+            .map(|()| {
+                self.builder.terminate(Terminator::Return(Vec::new()));
+                let builder =
+                    std::mem::replace(&mut self.builder, Builder::new(Position::new(1, 0, 0)));
+                builder.finish(INIT, Vec::new(), Vec::new())
+            });
+        let mut lowered = vec![init];
+        for function in functions {
+            lowered.push(self.function(function));
+        }
+        self.module.main = file
+            .decls
+            .iter()
+            .filter(|declaration| matches!(declaration.kind, DeclarationKind::Function { .. }))
+            .position(|declaration| {
+                matches!(&declaration.kind, DeclarationKind::Function { name, .. } if name == "main")
+            })
+            .map(|index| index + 1);
+        Ok((self.module, lowered))
+    }
+
+    /// How a function is called, with the index it's given, if its types can be lowered
+    fn callee(&self, function: &Declaration, index: usize) -> Result<Callee, CompileError> {
+        let position = function.position_start;
+        let Some(CheckedType::Function(signature)) = self
+            .info
+            .type_at(position)
+            .map(|type_id| self.info.types.get(type_id))
+        else {
+            return Err(unsupported("functions of this type", position));
+        };
+        let types = |types: &[TypeId]| {
+            types
+                .iter()
+                .map(|type_id| self.type_of_id(*type_id, position))
+                .collect::<Result<Vec<Type>, CompileError>>()
+        };
+        Ok(Callee {
+            index,
+            parameters: types(&signature.parameters)?,
+            results: types(&signature.results)?,
+        })
+    }
+
+    fn function(&mut self, function: &Declaration) -> Result<Function, CompileError> {
+        let DeclarationKind::Function {
+            name,
+            parameters,
+            results,
+            body,
+            ..
+        } = &function.kind
+        else {
+            unreachable!("only functions are lowered as functions");
+        };
+        let Some(callee) = self.functions.get(name).cloned() else {
+            return Err(self.unsupported_functions[name].clone());
+        };
+        self.builder = Builder::new(function.position_start);
+        self.named_results.clear();
+        self.result_types = callee.results.clone();
+
+        // The entry block's parameters are the function's
+        let mut types = callee.parameters.iter();
+        for parameter in parameters {
+            if parameter.variadic {
+                return Err(unsupported("variadic functions", parameter.position_start));
+            }
+            let names: Vec<Option<&Expression>> = match parameter.names.is_empty() {
+                true => vec![None],
+                false => parameter.names.iter().map(Some).collect(),
+            };
+            for name in names {
+                let value_type = *types.next().expect("the checker typed every parameter");
+                let value = self.builder.add_parameter(Block(0), value_type);
+                if let Some(name) = name {
+                    self.define(name, value)?;
+                }
+            }
+        }
+        for result in results {
+            for name in &result.names {
+                let symbol = self.info.symbol_of(name);
+                let Some(symbol) = symbol else { continue };
+                let value_type = self.symbol_type(symbol, name.position_start)?;
+                let zero = self.builder.zero_value(Block(0), value_type);
+                self.define(name, zero)?;
+                self.named_results.push(Variable::Symbol(symbol));
+            }
+        }
+        for statement in body {
+            self.statement(statement)?;
+        }
+        let values = self.returned_values();
+        self.builder.terminate(Terminator::Return(values));
+        let builder = std::mem::replace(&mut self.builder, Builder::new(Position::new(1, 0, 0)));
+        Ok(builder.finish(name, callee.parameters, callee.results))
+    }
+
+    /// What a bare return returns: the named results, or zero values for unnamed ones, which
+    /// only a function running off its end without returning gets to
+    fn returned_values(&mut self) -> Vec<Value> {
+        if !self.named_results.is_empty() {
+            let named_results = self.named_results.clone();
+            return named_results
+                .into_iter()
+                .map(|variable| self.builder.read(variable))
+                .collect();
+        }
+        let result_types = self.result_types.clone();
+        result_types
+            .into_iter()
+            .map(|value_type| {
+                let current = self.builder.current;
+                self.builder.zero_value(current, value_type)
+            })
+            .collect()
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), CompileError> {
+        let position = statement.position_start;
+        match &statement.kind {
+            StatementKind::Expression(expression) => match &expression.kind {
+                ExpressionKind::FunctionCall { .. } => self.call(expression, false).map(|_| ()),
+                _ => self.expression(expression, None).map(|_| ()),
+            },
+            StatementKind::Declaration(declaration) => match &declaration.kind {
+                DeclarationKind::Variable { specs, .. } => {
+                    for spec in specs {
+                        self.variable_spec(spec)?;
+                    }
+                    Ok(())
+                }
+                DeclarationKind::Constant { .. } => {
+                    Err(unsupported("constant declarations", position))
+                }
+                _ => Err(unsupported("local functions and types", position)),
+            },
+            StatementKind::Assignment { left, right, .. } => self.assignment(left, right, position),
+            StatementKind::IncDec { .. } | StatementKind::OpAssign { .. } => {
+                let Some((target, value)) = statement.updated_value() else {
+                    unreachable!("inc/dec and op-assign statements update their target");
+                };
+                self.assignment(std::slice::from_ref(target), &[value], position)
+            }
+            StatementKind::Block(statements) => {
+                for statement in statements {
+                    self.statement(statement)?;
+                }
+                Ok(())
+            }
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => {
+                if let Some(init) = init {
+                    self.statement(init)?;
+                }
+                let condition = self.expression(condition, Some(Type::Bool))?;
+                let then = self.builder.new_block();
+                let end = self.builder.new_block();
+                let otherwise = match else_branch {
+                    Some(_) => self.builder.new_block(),
+                    None => end,
+                };
+                self.builder.branch(condition, then, otherwise);
+                self.builder.seal(then);
+                self.builder.current = then;
+                for statement in body {
+                    self.statement(statement)?;
+                }
+                self.builder.jump(end);
+                if let Some(else_branch) = else_branch {
+                    self.builder.seal(otherwise);
+                    self.builder.current = otherwise;
+                    self.statement(else_branch)?;
+                    self.builder.jump(end);
+                }
+                self.builder.seal(end);
+                self.builder.current = end;
+                Ok(())
+            }
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                if let Some(init) = init {
+                    self.statement(init)?;
+                }
+                let header = self.builder.new_block();
+                let body_block = self.builder.new_block();
+                // A continue goes to the post statement, if there is one
+                let post_block = match post {
+                    Some(_) => self.builder.new_block(),
+                    None => header,
+                };
+                let end = self.builder.new_block();
+                self.builder.jump(header);
+                self.builder.current = header;
+                match condition {
+                    Some(condition) => {
+                        let condition = self.expression(condition, Some(Type::Bool))?;
+                        self.builder.branch(condition, body_block, end);
+                    }
+                    None => self.builder.jump(body_block),
+                }
+                self.builder.seal(body_block);
+                self.builder.current = body_block;
+                self.loop_body(body, post_block, end)?;
+                self.builder.jump(post_block);
+                if let Some(post) = post {
+                    self.builder.seal(post_block);
+                    self.builder.current = post_block;
+                    self.statement(post)?;
+                    self.builder.jump(header);
+                }
+                self.builder.seal(header);
+                self.builder.seal(end);
+                self.builder.current = end;
+                Ok(())
+            }
+            StatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => self.range(key.as_ref(), value.as_ref(), *define, expression, body),
+            StatementKind::Return(values) => {
+                let values = match values.as_slice() {
+                    [] => self.returned_values(),
+                    [call] if self.result_types.len() > 1 => self.call(call, true)?,
+                    values => {
+                        let mut lowered = Vec::new();
+                        let result_types = self.result_types.clone();
+                        for (value, value_type) in values.iter().zip(result_types) {
+                            lowered.push(self.expression(value, Some(value_type))?);
+                        }
+                        lowered
+                    }
+                };
+                self.builder.terminate(Terminator::Return(values));
+                Ok(())
+            }
+            StatementKind::Break | StatementKind::Continue => {
+                let Some(innermost) = self.loops.last() else {
+                    return Err(unsupported(&statement.to_string(), position));
+                };
+                let target = match statement.kind {
+                    StatementKind::Break => innermost.break_block,
+                    _ => innermost.continue_block,
+                };
+                self.builder.jump(target);
+                Ok(())
+            }
+            StatementKind::Defer(_) => Err(unsupported("defer", position)),
+            StatementKind::Go(_) => Err(unsupported("goroutines", position)),
+            StatementKind::Send { .. } | StatementKind::Select(_) => {
+                Err(unsupported("channels", position))
+            }
+            StatementKind::Switch { .. } | StatementKind::Fallthrough => {
+                Err(unsupported("switch statements", position))
+            }
+        }
+    }
+
+    fn loop_body(
+        &mut self,
+        body: &[Statement],
+        continue_block: Block,
+        break_block: Block,
+    ) -> Result<(), CompileError> {
+        self.loops.push(Loop {
+            continue_block,
+            break_block,
+        });
+        let result = body
+            .iter()
+            .try_for_each(|statement| self.statement(statement));
+        self.loops.pop();
+        result
+    }
+
+    /// A range loop over an int, counting in a variable of its own, which a continue goes
+    /// on to count up
+    fn range(
+        &mut self,
+        key: Option<&Expression>,
+        value: Option<&Expression>,
+        define: bool,
+        expression: &Expression,
+        body: &[Statement],
+    ) -> Result<(), CompileError> {
+        let position = expression.position_start;
+        if value.is_some() || self.value_type(expression) != Some(Type::Int) {
+            return Err(unsupported("range over anything but ints", position));
+        }
+        let limit = self.expression(expression, Some(Type::Int))?;
+        let counter = self.builder.new_temporary(Type::Int);
+        let zero = self.constant_value(Constant::Int(0), position);
+        self.builder.write(counter, zero);
+
+        let header = self.builder.new_block();
+        let body_block = self.builder.new_block();
+        let increment = self.builder.new_block();
+        let end = self.builder.new_block();
+        self.builder.jump(header);
+        self.builder.current = header;
+        let index = self.builder.read(counter);
+        let condition = self.builder.emit_value(
+            InstructionKind::Binary(BinaryOp::Less, index, limit),
+            Type::Bool,
+            position,
+        );
+        self.builder.branch(condition, body_block, end);
+        self.builder.seal(body_block);
+        self.builder.current = body_block;
+        if let Some(key) = key {
+            match define {
+                true => self.define(key, index)?,
+                false => self.store(key, index, position)?,
+            }
+        }
+        self.loop_body(body, increment, end)?;
+        self.builder.jump(increment);
+        self.builder.seal(increment);
+        self.builder.current = increment;
+        let index = self.builder.read(counter);
+        let one = self.constant_value(Constant::Int(1), position);
+        let next = self.builder.emit_value(
+            InstructionKind::Binary(BinaryOp::Add, index, one),
+            Type::Int,
+            position,
+        );
+        self.builder.write(counter, next);
+        self.builder.jump(header);
+        self.builder.seal(header);
+        self.builder.seal(end);
+        self.builder.current = end;
+        Ok(())
+    }
+
+    fn variable_spec(&mut self, spec: &VariableSpec) -> Result<(), CompileError> {
+        let position = spec.position_start;
+        if !spec.values.is_empty() {
+            return self.assignment(&spec.names, &spec.values, position);
+        }
+        for name in &spec.names {
+            let Some(symbol) = self.info.symbol_of(name) else {
+                continue;
+            };
+            let value_type = self.symbol_type(symbol, position)?;
+            let zero = self.constant_value(zero(value_type), position);
+            self.store(name, zero, position)?;
+        }
+        Ok(())
+    }
+
+    /// Assign `right`'s values to `left`, every value evaluated before any is assigned.
+    /// Assigning one variable's value to another copies it, so each variable has a value of
+    /// its own until copies are propagated.
+    fn assignment(
+        &mut self,
+        left: &[Expression],
+        right: &[Expression],
+        position: Position,
+    ) -> Result<(), CompileError> {
+        let values = match (left, right) {
+            (targets, [call]) if targets.len() > 1 => self.call(call, true)?,
+            (targets, values) => {
+                let mut lowered = Vec::new();
+                for (target, value) in targets.iter().zip(values) {
+                    let hint = self.value_type(target);
+                    let mut lowered_value = self.expression(value, hint)?;
+                    if let ExpressionKind::Identifier(_) = value.kind
+                        && self.info.symbol_of(value).is_some()
+                    {
+                        let value_type = self.builder.types[lowered_value.0 as usize];
+                        lowered_value = self.builder.emit_value(
+                            InstructionKind::Copy(lowered_value),
+                            value_type,
+                            value.position_start,
+                        );
+                    }
+                    lowered.push(lowered_value);
+                }
+                lowered
+            }
+        };
+        for (target, value) in left.iter().zip(values) {
+            self.store(target, value, position)?;
+        }
+        Ok(())
+    }
+
+    /// Give the variable `name` declares its first value
+    fn define(&mut self, name: &Expression, value: Value) -> Result<(), CompileError> {
+        self.store(name, value, name.position_start)
+    }
+
+    /// Assign `value` to the variable `target` names
+    fn store(
+        &mut self,
+        target: &Expression,
+        value: Value,
+        position: Position,
+    ) -> Result<(), CompileError> {
+        match &target.kind {
+            ExpressionKind::BlankIdentifier => Ok(()),
+            ExpressionKind::Identifier(name) if name == "_" => Ok(()),
+            ExpressionKind::Identifier(_) => {
+                let Some(symbol) = self.info.symbol_of(target) else {
+                    return Err(unsupported(&target.to_string(), position));
+                };
+                if let Some(code) = self.unsupported_globals.get(&symbol) {
+                    return Err(unsupported(code, position));
+                }
+                if let Some(global) = self.globals.get(&symbol) {
+                    self.builder
+                        .emit(InstructionKind::StoreGlobal(*global, value), &[], position);
+                    return Ok(());
+                }
+                let variable = Variable::Symbol(symbol);
+                let value_type = self.builder.types[value.0 as usize];
+                self.builder.variable_types.insert(variable, value_type);
+                self.builder.write(variable, value);
+                Ok(())
+            }
+            _ => Err(unsupported(&format!("assigning to {}", target), position)),
+        }
+    }
+
+    /// Lower `expression` to its value. An untyped constant is made a value of the type
+    /// `hint` when that's numeric, as it would be converted to its context's type.
+    fn expression(
+        &mut self,
+        expression: &Expression,
+        hint: Option<Type>,
+    ) -> Result<Value, CompileError> {
+        let position = expression.position_start;
+        match &expression.kind {
+            ExpressionKind::IntegerLiteral(_)
+            | ExpressionKind::FloatLiteral(_)
+            | ExpressionKind::RuneLiteral(_) => {
+                let constant = self.constant(expression, hint)?;
+                Ok(self.constant_value(constant, position))
+            }
+            ExpressionKind::StringLiteral(literal) => {
+                let Some(value) = unquote_string(literal) else {
+                    return Err(unsupported(literal, position));
+                };
+                Ok(self.constant_value(Constant::String(value), position))
+            }
+            ExpressionKind::Identifier(name) => {
+                let symbol = self.info.symbol_of(expression);
+                let kind = symbol.map(|symbol| self.info.symbols.symbol(symbol).kind);
+                match (symbol, kind) {
+                    (Some(symbol), Some(SymbolKind::Variable)) => {
+                        if let Some(code) = self.unsupported_globals.get(&symbol) {
+                            return Err(unsupported(code, position));
+                        }
+                        if let Some(global) = self.globals.get(&symbol) {
+                            let value_type = self.symbol_type(symbol, position)?;
+                            return Ok(self.builder.emit_value(
+                                InstructionKind::LoadGlobal(*global),
+                                value_type,
+                                position,
+                            ));
+                        }
+                        Ok(self.builder.read(Variable::Symbol(symbol)))
+                    }
+                    (_, Some(SymbolKind::Constant)) if name == "true" || name == "false" => {
+                        let constant = Constant::Bool(name == "true");
+                        Ok(self.constant_value(constant, position))
+                    }
+                    _ => Err(unsupported(&format!("using {} as a value", name), position)),
+                }
+            }
+            ExpressionKind::Parenthesized(inner) => self.expression(inner, hint),
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => self.binary(expression, left, *operator, right, hint),
+            ExpressionKind::Unary { operator, operand } => {
+                let value_type = match self.is_untyped(expression) {
+                    true => hint.or(self.value_type(expression)),
+                    false => self.value_type(expression),
+                };
+                let operand = self.expression(operand, value_type)?;
+                let operation = match operator {
+                    UnaryOperator::Plus => return Ok(operand),
+                    UnaryOperator::Minus => UnaryOp::Negate,
+                    UnaryOperator::Not => UnaryOp::Not,
+                    UnaryOperator::Complement => UnaryOp::Complement,
+                    _ => return Err(unsupported(&expression.to_string(), position)),
+                };
+                let value_type = self.builder.types[operand.0 as usize];
+                Ok(self.builder.emit_value(
+                    InstructionKind::Unary(operation, operand),
+                    value_type,
+                    position,
+                ))
+            }
+            ExpressionKind::FunctionCall { .. } => {
+                let values = self.call(expression, true)?;
+                match values.as_slice() {
+                    [value] => Ok(*value),
+                    _ => Err(unsupported(&expression.to_string(), position)),
+                }
+            }
+            ExpressionKind::FunctionLiteral { .. } => {
+                Err(unsupported("function literals", position))
+            }
+            _ => Err(unsupported(&expression.to_string(), position)),
+        }
+    }
+
+    fn binary(
+        &mut self,
+        expression: &Expression,
+        left: &Expression,
+        operator: Operator,
+        right: &Expression,
+        hint: Option<Type>,
+    ) -> Result<Value, CompileError> {
+        let position = expression.position_start;
+        // && and || only evaluate their right operand if they need to, merging the two ways
+        // through in a block parameter
+        if matches!(operator, Operator::AndAnd | Operator::PipePipe) {
+            let left = self.expression(left, Some(Type::Bool))?;
+            let result = self.builder.new_temporary(Type::Bool);
+            self.builder.write(result, left);
+            let right_block = self.builder.new_block();
+            let end = self.builder.new_block();
+            match operator {
+                Operator::AndAnd => self.builder.branch(left, right_block, end),
+                _ => self.builder.branch(left, end, right_block),
+            }
+            self.builder.seal(right_block);
+            self.builder.current = right_block;
+            let right = self.expression(right, Some(Type::Bool))?;
+            self.builder.write(result, right);
+            self.builder.jump(end);
+            self.builder.seal(end);
+            self.builder.current = end;
+            return Ok(self.builder.read(result));
+        }
+
+        let operation = match operator {
+            Operator::Plus => BinaryOp::Add,
+            Operator::Minus => BinaryOp::Subtract,
+            Operator::Star => BinaryOp::Multiply,
+            Operator::Slash => BinaryOp::Divide,
+            Operator::Percent => BinaryOp::Remainder,
+            Operator::Ampersand => BinaryOp::And,
+            Operator::Pipe => BinaryOp::Or,
+            Operator::Caret => BinaryOp::Xor,
+            Operator::AmpersandCaret => BinaryOp::AndNot,
+            Operator::LessLess => BinaryOp::ShiftLeft,
+            Operator::GreaterGreater => BinaryOp::ShiftRight,
+            Operator::EqualEqual => BinaryOp::Equal,
+            Operator::BangEqual => BinaryOp::NotEqual,
+            Operator::Less => BinaryOp::Less,
+            Operator::LessEqual => BinaryOp::LessEqual,
+            Operator::Greater => BinaryOp::Greater,
+            Operator::GreaterEqual => BinaryOp::GreaterEqual,
+            Operator::AndAnd | Operator::PipePipe => unreachable!("lowered above"),
+        };
+        // A comparison's operands are of the typed operand's type, and an operation's of its
+        // own, unless it's an untyped constant
+        let operand_type = match operation.is_comparison() {
+            true => self.operand_type(left, right),
+            false if self.is_untyped(expression) => match (self.value_type(expression), hint) {
+                (Some(Type::Int), Some(Type::Float)) => Some(Type::Float),
+                (value_type, _) => value_type,
+            },
+            false => self.value_type(expression),
+        };
+        let Some(operand_type) = operand_type else {
+            return Err(unsupported(&expression.to_string(), position));
+        };
+        let is_shift = matches!(operation, BinaryOp::ShiftLeft | BinaryOp::ShiftRight);
+        let left = self.expression(left, Some(operand_type))?;
+        let right_type = if is_shift { Type::Int } else { operand_type };
+        let right = self.expression(right, Some(right_type))?;
+        let result_type = match operation.is_comparison() {
+            true => Type::Bool,
+            false => operand_type,
+        };
+        Ok(self.builder.emit_value(
+            InstructionKind::Binary(operation, left, right),
+            result_type,
+            position,
+        ))
+    }
+
+    /// Lower a call, returning its results, which must be used when `used` is true
+    fn call(&mut self, call: &Expression, used: bool) -> Result<Vec<Value>, CompileError> {
+        let position = call.position_start;
+        let ExpressionKind::FunctionCall {
+            name: callee,
+            arguments,
+            spread,
+        } = &call.kind
+        else {
+            return Err(unsupported(&call.to_string(), position));
+        };
+        if *spread {
+            return Err(unsupported("calls passing a slice", position));
+        }
+        match &callee.kind {
+            ExpressionKind::Identifier(name) if !self.is_variable(callee) => {
+                if self.unsupported_functions.contains_key(name) {
+                    return Err(unsupported(&call.to_string(), position));
+                }
+                if let Some(function) = self.functions.get(name).cloned() {
+                    let arguments = self.arguments(arguments, &function.parameters)?;
+                    let kind = InstructionKind::Call {
+                        function: function.index,
+                        arguments,
+                    };
+                    return Ok(self.builder.emit(kind, &function.results, position));
+                }
+                match (Builtin::from_name(name), arguments.as_slice()) {
+                    (Some(Builtin::Len), [argument])
+                        if self.value_type(argument) == Some(Type::String) =>
+                    {
+                        let string = self.expression(argument, None)?;
+                        let length = self.builder.emit_value(
+                            InstructionKind::Len(string),
+                            Type::Int,
+                            position,
+                        );
+                        Ok(vec![length])
+                    }
+                    (Some(Builtin::Panic), [argument]) if !used => {
+                        let value = self.expression(argument, None)?;
+                        self.builder
+                            .terminate(Terminator::Panic { value, position });
+                        Ok(Vec::new())
+                    }
+                    (None, [argument]) => self.conversion(call, argument).map(|value| vec![value]),
+                    _ => Err(unsupported(&call.to_string(), position)),
+                }
+            }
+            ExpressionKind::FieldAccess { object, field, .. } if !used => {
+                let path = match &object.kind {
+                    ExpressionKind::Identifier(package) if self.is_package(object) => {
+                        self.imports.get(package).cloned()
+                    }
+                    _ => None,
+                };
+                let Some(path) =
+                    path.filter(|path| path == "fmt" || stdlib::package(path).is_some())
+                else {
+                    return Err(unsupported(&call.to_string(), position));
+                };
+                let arguments = self.arguments(arguments, &[])?;
+                let kind = InstructionKind::CallNative {
+                    path,
+                    function: field.clone(),
+                    arguments,
+                };
+                self.builder.emit(kind, &[], position);
+                Ok(Vec::new())
+            }
+            _ => Err(unsupported(&call.to_string(), position)),
+        }
+    }
+
+    /// Lower a call's arguments, an untyped constant as the type of the parameter it's passed
+    /// to. A single call returning several values passes each of them.
+    fn arguments(
+        &mut self,
+        arguments: &[Expression],
+        parameters: &[Type],
+    ) -> Result<Vec<Value>, CompileError> {
+        if let [argument] = arguments
+            && self.result_count(argument).is_some_and(|count| count != 1)
+        {
+            return self.call(argument, true);
+        }
+        let mut lowered = Vec::new();
+        for (index, argument) in arguments.iter().enumerate() {
+            let hint = parameters.get(index).copied();
+            lowered.push(self.expression(argument, hint)?);
+        }
+        Ok(lowered)
+    }
+
+    /// A conversion between ints and floats, or to the type a value already has
+    fn conversion(
+        &mut self,
+        call: &Expression,
+        argument: &Expression,
+    ) -> Result<Value, CompileError> {
+        let position = call.position_start;
+        let Some(target) = self.value_type(call) else {
+            return Err(unsupported(&call.to_string(), position));
+        };
+        // A constant converted to a float, like float64(1), is made a float to begin with
+        let source = match self.is_untyped(argument) {
+            true => Some(target),
+            false => self.value_type(argument),
+        };
+        let value = self.expression(argument, source)?;
+        match (source, target) {
+            (Some(source), target) if source == target => Ok(value),
+            (Some(Type::Int), Type::Float) | (Some(Type::Float), Type::Int) => Ok(self
+                .builder
+                .emit_value(InstructionKind::Convert(value), target, position)),
+            _ => Err(unsupported(&call.to_string(), position)),
+        }
+    }
+
+    fn constant_value(&mut self, constant: Constant, position: Position) -> Value {
+        let value_type = constant.type_of();
+        self.builder
+            .emit_value(InstructionKind::Const(constant), value_type, position)
+    }
+
+    /// An integer, float or rune literal's value, as the type `hint` if it's numeric
+    fn constant(&self, literal: &Expression, hint: Option<Type>) -> Result<Constant, CompileError> {
+        let value = match &literal.kind {
+            ExpressionKind::IntegerLiteral(text) => ConstantValue::from_integer_literal(text),
+            ExpressionKind::FloatLiteral(text) => ConstantValue::from_float_literal(text),
+            ExpressionKind::RuneLiteral(text) => ConstantValue::from_rune_literal(text),
+            _ => None,
+        };
+        let value_type = match hint {
+            Some(Type::Int | Type::Float) => hint,
+            _ => self.value_type(literal),
+        };
+        let constant = match (value, value_type) {
+            (Some(ConstantValue::Int(value)), Some(Type::Float)) => {
+                Some(Constant::Float(value as f64))
+            }
+            (Some(ConstantValue::Int(value)), _) => i64::try_from(value).ok().map(Constant::Int),
+            (Some(ConstantValue::Float(value)), Some(Type::Int)) if value.fract() == 0.0 => {
+                Some(Constant::Int(value as i64))
+            }
+            (Some(ConstantValue::Float(value)), _) => Some(Constant::Float(value)),
+            _ => None,
+        };
+        constant.ok_or_else(|| unsupported(&literal.to_string(), literal.position_start))
+    }
+
+    fn value_type(&self, expression: &Expression) -> Option<Type> {
+        let type_id = self.info.type_of(expression)?;
+        basic_type(self.info.types.basic_kind(type_id)?)
+    }
+
+    /// The IR type of a checked type, which must be a basic one
+    fn type_of_id(&self, type_id: TypeId, position: Position) -> Result<Type, CompileError> {
+        self.info
+            .types
+            .basic_kind(type_id)
+            .and_then(basic_type)
+            .ok_or_else(|| {
+                let type_string = self.info.types.type_string(type_id);
+                unsupported(&format!("values of type {}", type_string), position)
+            })
+    }
+
+    fn symbol_type(&self, symbol: SymbolId, position: Position) -> Result<Type, CompileError> {
+        match self.info.symbol_type(symbol) {
+            Some(type_id) => self.type_of_id(type_id, position),
+            None => Err(unsupported(
+                &self.info.symbols.symbol(symbol).name,
+                position,
+            )),
+        }
+    }
+
+    fn is_untyped(&self, expression: &Expression) -> bool {
+        self.info
+            .type_of(expression)
+            .and_then(|type_id| self.info.types.basic_kind(type_id))
+            .is_some_and(|kind| kind.is_untyped())
+    }
+
+    /// The type a comparison compares: the typed operand's, or with two untyped constants, a
+    /// float if either is
+    fn operand_type(&self, left: &Expression, right: &Expression) -> Option<Type> {
+        match (self.is_untyped(left), self.is_untyped(right)) {
+            (false, _) => self.value_type(left),
+            (true, false) => self.value_type(right),
+            (true, true) => match (self.value_type(left), self.value_type(right)) {
+                (Some(Type::Float), _) | (_, Some(Type::Float)) => Some(Type::Float),
+                (value_type, _) => value_type,
+            },
+        }
+    }
+
+    /// How many results a call to a Go function returns, or None if `expression` isn't one
+    fn result_count(&self, expression: &Expression) -> Option<usize> {
+        let ExpressionKind::FunctionCall { name, .. } = &expression.kind else {
+            return None;
+        };
+        let ExpressionKind::Identifier(name) = &name.kind else {
+            return None;
+        };
+        self.functions.get(name).map(|callee| callee.results.len())
+    }
+
+    fn is_variable(&self, identifier: &Expression) -> bool {
+        self.info
+            .symbol_of(identifier)
+            .is_some_and(|symbol| self.info.symbols.symbol(symbol).kind == SymbolKind::Variable)
+    }
+
+    fn is_package(&self, identifier: &Expression) -> bool {
+        self.info
+            .symbol_of(identifier)
+            .is_some_and(|symbol| self.info.symbols.symbol(symbol).kind == SymbolKind::Package)
+    }
+}
+
+/// The IR type of a basic kind, an untyped constant's being its default type's
+fn basic_type(kind: BasicKind) -> Option<Type> {
+    match kind {
+        kind if kind.is_integer() || kind == BasicKind::UntypedRune => Some(Type::Int),
+        kind if kind.is_float() => Some(Type::Float),
+        kind if kind.is_string() => Some(Type::String),
+        kind if kind.is_boolean() => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+fn zero(value_type: Type) -> Constant {
+    match value_type {
+        Type::Int => Constant::Int(0),
+        Type::Float => Constant::Float(0.0),
+        Type::Bool => Constant::Bool(false),
+        Type::String => Constant::String(String::new()),
+    }
+}
+
+fn unsupported(code: &str, position: Position) -> CompileError {
+    CompileError::new(CompileErrorKind::Unsupported(code.to_string()), position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    fn lower_str(source: &str) -> Result<Module, CompileError> {
+        lower(&Parser::new(source).parse().unwrap())
+    }
+
+    #[test]
+    fn lower_to_ssa() {
+        let source = "package main\n\nimport \"fmt\"\n\nvar limit = 3\n\nfunc sum(n int) (total int) {\n\tfor i := range n {\n\t\tif i == 1 || i > limit {\n\t\t\tcontinue\n\t\t}\n\t\ttotal = total + i\n\t}\n\treturn\n}\n\nfunc main() {\n\tx := sum(5)\n\ty := x\n\tfmt.Println(float64(y) / 2)\n}\n";
+        let module = lower_str(source).unwrap();
+        assert_eq!(
+            module.to_string(),
+            "\
+global limit: int
+
+func init() {
+b0:
+    v0: int = const 3
+    store_global limit, v0
+    return
+}
+
+func sum(int) -> int {
+b0(v0: int):
+    v1: int = const 0
+    v2: int = const 0
+    jump b1(v2, v1)
+b1(v3: int, v4: int):
+    v5: bool = lt v3, v0
+    branch v5, b2, b4
+b2:
+    v6: int = const 1
+    v7: bool = eq v3, v6
+    branch v7, b6(v7), b5
+b3(v8: int):
+    v9: int = const 1
+    v10: int = add v3, v9
+    jump b1(v10, v8)
+b4:
+    return v4
+b5:
+    v11: int = load_global limit
+    v12: bool = gt v3, v11
+    jump b6(v12)
+b6(v13: bool):
+    branch v13, b7, b8
+b7:
+    jump b3(v4)
+b8:
+    v14: int = add v4, v3
+    jump b3(v14)
+}
+
+func main() {
+b0:
+    v0: int = const 5
+    v1: int = call sum(v0)
+    v2: int = copy v1
+    v3: float64 = convert v2
+    v4: float64 = const 2.0
+    v5: float64 = div v3, v4
+    call fmt.Println(v5)
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn unreachable_code_is_dropped() {
+        let source = "package main\n\nfunc sign(n int) int {\n\tif n < 0 {\n\t\treturn -1\n\t} else {\n\t\treturn 1\n\t}\n\tpanic(\"unreachable\")\n}\n\nfunc main() {\n\t_ = sign(2)\n}\n";
+        let module = lower_str(source).unwrap();
+        assert_eq!(
+            module.to_string(),
+            "\
+func init() {
+b0:
+    return
+}
+
+func sign(int) -> int {
+b0(v0: int):
+    v1: int = const 0
+    v2: bool = lt v0, v1
+    branch v2, b1, b2
+b1:
+    v3: int = const 1
+    v4: int = neg v3
+    return v4
+b2:
+    v5: int = const 1
+    return v5
+}
+
+func main() {
+b0:
+    v0: int = const 2
+    v1: int = call sign(v0)
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn lower_errors() {
+        let error = |source: &str| lower_str(source).unwrap_err().kind;
+        assert_eq!(
+            error("package main\n\nfunc main() {\n\tvar s []int\n\t_ = s\n}\n"),
+            CompileErrorKind::Unsupported("values of type []int".to_string())
+        );
+        assert_eq!(
+            error("package main\n\nfunc main() {\n\tgo main()\n}\n"),
+            CompileErrorKind::Unsupported("goroutines".to_string())
+        );
+    }
+}
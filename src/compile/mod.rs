@@ -0,0 +1,11 @@
+pub mod bytecode;
+pub mod c;
+pub mod compiler;
+pub mod encode;
+pub mod ir;
+#[cfg(feature = "jit")]
+pub mod jit;
+pub mod lower;
+pub mod opt;
+pub mod vm;
+pub mod wasm;
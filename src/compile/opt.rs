@@ -0,0 +1,683 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::compile::ir::{
+    BasicBlock, BinaryOp, Block, Constant, Function, InstructionKind, Module, Terminator, UnaryOp,
+    Value,
+};
+
+/// The most times the passes are run over a function, should they keep changing it
+const MAX_ROUNDS: usize = 32;
+
+/// A transformation of a function in the IR, which keeps it in SSA form
+pub trait Pass {
+    /// The pass's name, like `dce`
+    fn name(&self) -> &'static str;
+
+    /// Run the pass over `function`, returning whether it changed anything
+    fn run(&self, function: &mut Function) -> bool;
+}
+
+/// Runs passes over each of a module's functions, in the order they were added, and again
+/// until none of them changes anything, since each can leave work for the others
+pub struct PassManager {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PassManager {
+    /// A pass manager without any passes
+    pub fn new() -> PassManager {
+        PassManager { passes: Vec::new() }
+    }
+
+    /// The passes for an optimization level: none at 0, and from 1 up, constant folding,
+    /// copy propagation, dead code elimination and merging blocks
+    pub fn for_level(level: u8) -> PassManager {
+        match level {
+            0 => PassManager::new(),
+            _ => PassManager::new()
+                .with_pass(ConstantFolding)
+                .with_pass(CopyPropagation)
+                .with_pass(DeadCodeElimination)
+                .with_pass(MergeBlocks),
+        }
+    }
+
+    pub fn with_pass(mut self, pass: impl Pass + 'static) -> PassManager {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// The names of the passes, in the order they're run
+    pub fn passes(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+
+    pub fn run(&self, module: &mut Module) {
+        for function in &mut module.functions {
+            self.run_function(function);
+        }
+    }
+
+    pub fn run_function(&self, function: &mut Function) {
+        for _ in 0..MAX_ROUNDS {
+            let mut changed = false;
+            for pass in &self.passes {
+                changed |= pass.run(function);
+            }
+            if !changed {
+                return;
+            }
+        }
+    }
+}
+
+/// Computes operations whose operands are constants, replacing them with their results, and
+/// makes a branch on a constant a jump. An operation that would panic, like a division by
+/// zero, is left to panic when it's run.
+pub struct ConstantFolding;
+
+impl Pass for ConstantFolding {
+    fn name(&self) -> &'static str {
+        "constant-folding"
+    }
+
+    fn run(&self, function: &mut Function) -> bool {
+        let mut constants = HashMap::new();
+        for block in &function.blocks {
+            for instruction in &block.instructions {
+                if let InstructionKind::Const(constant) = &instruction.kind {
+                    constants.insert(instruction.results[0], constant.clone());
+                }
+            }
+        }
+        let mut changed = false;
+        for block in &mut function.blocks {
+            for instruction in &mut block.instructions {
+                let operand = |value: &Value| constants.get(value);
+                let folded = match &instruction.kind {
+                    InstructionKind::Binary(operation, left, right) => {
+                        match (operand(left), operand(right)) {
+                            (Some(left), Some(right)) => fold_binary(*operation, left, right),
+                            _ => None,
+                        }
+                    }
+                    InstructionKind::Unary(operation, value) => {
+                        operand(value).and_then(|value| fold_unary(*operation, value))
+                    }
+                    InstructionKind::Convert(value) => operand(value).and_then(fold_conversion),
+                    InstructionKind::Len(value) => match operand(value) {
+                        Some(Constant::String(string)) => Some(Constant::Int(string.len() as i64)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some(constant) = folded {
+                    constants.insert(instruction.results[0], constant.clone());
+                    instruction.kind = InstructionKind::Const(constant);
+                    changed = true;
+                }
+            }
+            if let Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } = &block.terminator
+                && let Some(Constant::Bool(condition)) = constants.get(condition)
+            {
+                let target = if *condition { then } else { otherwise };
+                block.terminator = Terminator::Jump(target.clone());
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn fold_binary(operation: BinaryOp, left: &Constant, right: &Constant) -> Option<Constant> {
+    use BinaryOp::*;
+    let constant = match (left, right) {
+        (Constant::Int(left), Constant::Int(right)) => {
+            let (left, right) = (*left, *right);
+            match operation {
+                Add => Constant::Int(left.wrapping_add(right)),
+                Subtract => Constant::Int(left.wrapping_sub(right)),
+                Multiply => Constant::Int(left.wrapping_mul(right)),
+                Divide if right != 0 => Constant::Int(left.wrapping_div(right)),
+                Remainder if right != 0 => Constant::Int(left.wrapping_rem(right)),
+                And => Constant::Int(left & right),
+                Or => Constant::Int(left | right),
+                Xor => Constant::Int(left ^ right),
+                AndNot => Constant::Int(left & !right),
+                // A negative count panics, and a count past the width shifts every bit out
+                ShiftLeft if right >= 64 => Constant::Int(0),
+                ShiftLeft if right >= 0 => Constant::Int(left << right),
+                ShiftRight if right >= 64 => Constant::Int(if left < 0 { -1 } else { 0 }),
+                ShiftRight if right >= 0 => Constant::Int(left >> right),
+                Divide | Remainder | ShiftLeft | ShiftRight => return None,
+                comparison => Constant::Bool(compare(comparison, left.cmp(&right))?),
+            }
+        }
+        (Constant::Float(left), Constant::Float(right)) => {
+            let (left, right) = (*left, *right);
+            match operation {
+                Add => Constant::Float(left + right),
+                Subtract => Constant::Float(left - right),
+                Multiply => Constant::Float(left * right),
+                Divide => Constant::Float(left / right),
+                Equal => Constant::Bool(left == right),
+                NotEqual => Constant::Bool(left != right),
+                Less => Constant::Bool(left < right),
+                LessEqual => Constant::Bool(left <= right),
+                Greater => Constant::Bool(left > right),
+                GreaterEqual => Constant::Bool(left >= right),
+                _ => return None,
+            }
+        }
+        (Constant::String(left), Constant::String(right)) => match operation {
+            Add => Constant::String(format!("{}{}", left, right)),
+            comparison => Constant::Bool(compare(comparison, left.cmp(right))?),
+        },
+        (Constant::Bool(left), Constant::Bool(right)) => match operation {
+            Equal => Constant::Bool(left == right),
+            NotEqual => Constant::Bool(left != right),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(constant)
+}
+
+/// Whether two values ordered as `ordering` satisfy the comparison, or None if `operation`
+/// isn't one
+fn compare(operation: BinaryOp, ordering: std::cmp::Ordering) -> Option<bool> {
+    let satisfied = match operation {
+        BinaryOp::Equal => ordering.is_eq(),
+        BinaryOp::NotEqual => ordering.is_ne(),
+        BinaryOp::Less => ordering.is_lt(),
+        BinaryOp::LessEqual => ordering.is_le(),
+        BinaryOp::Greater => ordering.is_gt(),
+        BinaryOp::GreaterEqual => ordering.is_ge(),
+        _ => return None,
+    };
+    Some(satisfied)
+}
+
+fn fold_unary(operation: UnaryOp, value: &Constant) -> Option<Constant> {
+    let constant = match (operation, value) {
+        (UnaryOp::Negate, Constant::Int(value)) => Constant::Int(value.wrapping_neg()),
+        (UnaryOp::Negate, Constant::Float(value)) => Constant::Float(-value),
+        (UnaryOp::Not, Constant::Bool(value)) => Constant::Bool(!value),
+        (UnaryOp::Complement, Constant::Int(value)) => Constant::Int(!value),
+        _ => return None,
+    };
+    Some(constant)
+}
+
+/// An int converted to a float, or a float to an int, truncated, when it's in range
+fn fold_conversion(value: &Constant) -> Option<Constant> {
+    match value {
+        Constant::Int(value) => Some(Constant::Float(*value as f64)),
+        Constant::Float(value) if value.is_finite() && value.abs() < i64::MAX as f64 => {
+            Some(Constant::Int(*value as i64))
+        }
+        _ => None,
+    }
+}
+
+/// Replaces the results of copies with the values they copy, and a block parameter passed
+/// the same value along every way into the block with that value
+pub struct CopyPropagation;
+
+impl Pass for CopyPropagation {
+    fn name(&self) -> &'static str {
+        "copy-propagation"
+    }
+
+    fn run(&self, function: &mut Function) -> bool {
+        let mut copies = HashMap::new();
+        for block in &function.blocks {
+            for instruction in &block.instructions {
+                if let InstructionKind::Copy(value) = instruction.kind {
+                    copies.insert(instruction.results[0], value);
+                }
+            }
+        }
+        // The entry block's parameters are the function's, which no block passes
+        let arguments = incoming_arguments(function);
+        for (index, block) in function.blocks.iter().enumerate().skip(1) {
+            for (position, parameter) in block.parameters.iter().enumerate() {
+                let Some(arguments) = arguments.get(&(Block(index as u32), position)) else {
+                    continue;
+                };
+                let mut distinct = arguments.iter().filter(|argument| *argument != parameter);
+                if let Some(first) = distinct.next()
+                    && distinct.all(|argument| argument == first)
+                {
+                    copies.insert(*parameter, *first);
+                }
+            }
+        }
+        if copies.is_empty() {
+            return false;
+        }
+
+        // Parameters passed each other only ever are in code nothing reaches, and are left
+        // for dead code elimination to remove
+        let resolve = |mut value: Value| {
+            for _ in 0..copies.len() {
+                match copies.get(&value) {
+                    Some(copied) => value = *copied,
+                    None => return value,
+                }
+            }
+            value
+        };
+        let replaced: HashSet<Value> = copies
+            .keys()
+            .filter(|value| resolve(**value) != **value)
+            .copied()
+            .collect();
+        if replaced.is_empty() {
+            return false;
+        }
+        for block in &mut function.blocks {
+            block.instructions.retain(|instruction| {
+                !matches!(instruction.kind, InstructionKind::Copy(_))
+                    || !replaced.contains(&instruction.results[0])
+            });
+            for instruction in &mut block.instructions {
+                instruction.kind.map_operands(resolve);
+            }
+            block.terminator.map_operands(resolve);
+        }
+        let kept: Vec<Vec<bool>> = function
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| {
+                block
+                    .parameters
+                    .iter()
+                    .map(|parameter| index == 0 || !replaced.contains(parameter))
+                    .collect()
+            })
+            .collect();
+        remove_parameters(function, &kept);
+        true
+    }
+}
+
+/// Removes the blocks nothing goes to, the instructions whose results aren't used and that
+/// don't do anything else, and the block parameters that aren't used
+pub struct DeadCodeElimination;
+
+impl Pass for DeadCodeElimination {
+    fn name(&self) -> &'static str {
+        "dce"
+    }
+
+    fn run(&self, function: &mut Function) -> bool {
+        let mut changed = remove_unreachable_blocks(function);
+
+        // A value is used if something that has to stay uses it, or a used value is computed
+        // from it, or it's passed to a used block parameter
+        let mut used: HashSet<Value> = HashSet::new();
+        for block in &function.blocks {
+            for instruction in &block.instructions {
+                if instruction.kind.has_side_effects() {
+                    used.extend(instruction.kind.operands());
+                }
+            }
+            match &block.terminator {
+                Terminator::Branch { condition, .. } => {
+                    used.insert(*condition);
+                }
+                Terminator::Return(values) => used.extend(values),
+                Terminator::Panic { value, .. } => {
+                    used.insert(*value);
+                }
+                Terminator::Jump(_) => {}
+            }
+        }
+        let arguments = incoming_arguments(function);
+        loop {
+            let before = used.len();
+            for (index, block) in function.blocks.iter().enumerate() {
+                for instruction in &block.instructions {
+                    if instruction
+                        .results
+                        .iter()
+                        .any(|result| used.contains(result))
+                    {
+                        used.extend(instruction.kind.operands());
+                    }
+                }
+                for (position, parameter) in block.parameters.iter().enumerate() {
+                    if used.contains(parameter)
+                        && let Some(arguments) = arguments.get(&(Block(index as u32), position))
+                    {
+                        used.extend(arguments);
+                    }
+                }
+            }
+            if used.len() == before {
+                break;
+            }
+        }
+
+        for block in &mut function.blocks {
+            let count = block.instructions.len();
+            block.instructions.retain(|instruction| {
+                instruction.kind.has_side_effects()
+                    || instruction
+                        .results
+                        .iter()
+                        .any(|result| used.contains(result))
+            });
+            changed |= block.instructions.len() != count;
+        }
+        let kept: Vec<Vec<bool>> = function
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| {
+                block
+                    .parameters
+                    .iter()
+                    .map(|parameter| index == 0 || used.contains(parameter))
+                    .collect()
+            })
+            .collect();
+        changed |= remove_parameters(function, &kept);
+        changed
+    }
+}
+
+/// Merges a block into the block before it when that's the only way into it, and it jumps
+/// there, as folding a branch leaves it to
+pub struct MergeBlocks;
+
+impl Pass for MergeBlocks {
+    fn name(&self) -> &'static str {
+        "merge-blocks"
+    }
+
+    fn run(&self, function: &mut Function) -> bool {
+        let mut changed = false;
+        while let Some((from, to)) = mergeable(function) {
+            let Terminator::Jump(target) = &function.blocks[from].terminator else {
+                unreachable!("only a block ending in a jump is merged into");
+            };
+            // The block's parameters become the values passed to them
+            let arguments: HashMap<Value, Value> = function.blocks[to]
+                .parameters
+                .iter()
+                .copied()
+                .zip(target.arguments.iter().copied())
+                .collect();
+            let merged = std::mem::replace(
+                &mut function.blocks[to],
+                BasicBlock {
+                    parameters: Vec::new(),
+                    instructions: Vec::new(),
+                    terminator: Terminator::Return(Vec::new()),
+                },
+            );
+            let block = &mut function.blocks[from];
+            block.instructions.extend(merged.instructions);
+            block.terminator = merged.terminator;
+            let replace = |value: Value| arguments.get(&value).copied().unwrap_or(value);
+            for block in &mut function.blocks {
+                for instruction in &mut block.instructions {
+                    instruction.kind.map_operands(replace);
+                }
+                block.terminator.map_operands(replace);
+            }
+            changed = true;
+        }
+        if changed {
+            remove_unreachable_blocks(function);
+        }
+        changed
+    }
+}
+
+/// A block that jumps to another, other than the entry block, that nothing else goes to, and
+/// the block it jumps to
+fn mergeable(function: &Function) -> Option<(usize, usize)> {
+    let mut incoming = vec![0; function.blocks.len()];
+    for block in &function.blocks {
+        for target in block.terminator.targets() {
+            incoming[target.block.0 as usize] += 1;
+        }
+    }
+    function
+        .blocks
+        .iter()
+        .enumerate()
+        .find_map(|(index, block)| match &block.terminator {
+            Terminator::Jump(target)
+                if target.block.0 != 0
+                    && target.block.0 as usize != index
+                    && incoming[target.block.0 as usize] == 1 =>
+            {
+                Some((index, target.block.0 as usize))
+            }
+            _ => None,
+        })
+}
+
+/// The values passed to each block parameter, by its block and position, along every way
+/// into the block
+fn incoming_arguments(function: &Function) -> HashMap<(Block, usize), Vec<Value>> {
+    let mut arguments: HashMap<(Block, usize), Vec<Value>> = HashMap::new();
+    for block in &function.blocks {
+        for target in block.terminator.targets() {
+            for (position, argument) in target.arguments.iter().enumerate() {
+                arguments
+                    .entry((target.block, position))
+                    .or_default()
+                    .push(*argument);
+            }
+        }
+    }
+    arguments
+}
+
+/// Remove the block parameters `kept` says aren't kept, by block and position, and the
+/// arguments passed to them, returning whether there were any
+fn remove_parameters(function: &mut Function, kept: &[Vec<bool>]) -> bool {
+    if kept.iter().flatten().all(|kept| *kept) {
+        return false;
+    }
+    let keep = |values: Vec<Value>, kept: &[bool]| -> Vec<Value> {
+        values
+            .into_iter()
+            .zip(kept)
+            .filter(|(_, kept)| **kept)
+            .map(|(value, _)| value)
+            .collect()
+    };
+    for (index, block) in function.blocks.iter_mut().enumerate() {
+        block.parameters = keep(std::mem::take(&mut block.parameters), &kept[index]);
+        for target in block.terminator.targets_mut() {
+            let arguments = std::mem::take(&mut target.arguments);
+            target.arguments = keep(arguments, &kept[target.block.0 as usize]);
+        }
+    }
+    true
+}
+
+/// Remove the blocks the entry block can't reach, numbering the rest in order, returning
+/// whether there were any
+fn remove_unreachable_blocks(function: &mut Function) -> bool {
+    let mut reachable = vec![false; function.blocks.len()];
+    let mut pending = vec![0];
+    while let Some(index) = pending.pop() {
+        if std::mem::replace(&mut reachable[index], true) {
+            continue;
+        }
+        for target in function.blocks[index].terminator.targets() {
+            pending.push(target.block.0 as usize);
+        }
+    }
+    if reachable.iter().all(|reachable| *reachable) {
+        return false;
+    }
+    let mut renumbered = HashMap::new();
+    for index in (0..function.blocks.len()).filter(|index| reachable[*index]) {
+        renumbered.insert(Block(index as u32), Block(renumbered.len() as u32));
+    }
+    let blocks = std::mem::take(&mut function.blocks);
+    for (index, mut block) in blocks.into_iter().enumerate() {
+        if !reachable[index] {
+            continue;
+        }
+        for target in block.terminator.targets_mut() {
+            target.block = renumbered[&target.block];
+        }
+        function.blocks.push(block);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::lower::lower;
+    use crate::parser::parser::Parser;
+
+    /// The text of the function `main` lowered from `body`, after `passes`
+    fn optimize(body: &str, passes: PassManager) -> String {
+        let source = format!(
+            "package main\n\nimport \"fmt\"\n\nfunc main() {{\n{}}}\n",
+            body
+        );
+        let mut module = lower(&Parser::new(&source).parse().unwrap()).unwrap();
+        passes.run(&mut module);
+        let text = module.to_string();
+        text[text.find("func main").unwrap()..].to_string()
+    }
+
+    #[test]
+    fn fold_constants() {
+        let body = "\tx := 6 * 7\n\tfmt.Println(x+1, x/0, -x, float64(x)/4, len(\"gor\") == 3)\n";
+        assert_eq!(
+            optimize(body, PassManager::new().with_pass(ConstantFolding)),
+            "\
+func main() {
+b0:
+    v0: int = const 6
+    v1: int = const 7
+    v2: int = const 42
+    v3: int = const 1
+    v4: int = const 43
+    v5: int = const 0
+    v6: int = div v2, v5
+    v7: int = const -42
+    v8: float64 = const 42.0
+    v9: float64 = const 4.0
+    v10: float64 = const 10.5
+    v11: string = const \"gor\"
+    v12: int = const 3
+    v13: int = const 3
+    v14: bool = const true
+    call fmt.Println(v4, v6, v7, v10, v14)
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn propagate_copies() {
+        let body = "\tx := 1\n\ty := x\n\tz := y\n\tfmt.Println(z)\n";
+        assert_eq!(
+            optimize(body, PassManager::new().with_pass(CopyPropagation)),
+            "\
+func main() {
+b0:
+    v0: int = const 1
+    call fmt.Println(v0)
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn eliminate_dead_code() {
+        let body = "\tx := 1\n\ty := x + 2\n\t_ = y\n\ti := 0\n\tfor i < 3 {\n\t\ti = i + 1\n\t}\n\tfmt.Println(x)\n";
+        assert_eq!(
+            optimize(body, PassManager::new().with_pass(DeadCodeElimination)),
+            "\
+func main() {
+b0:
+    v0: int = const 1
+    v4: int = const 0
+    jump b1(v4)
+b1(v5: int):
+    v6: int = const 3
+    v7: bool = lt v5, v6
+    branch v7, b2, b3
+b2:
+    v8: int = const 1
+    v9: int = add v5, v8
+    jump b1(v9)
+b3:
+    call fmt.Println(v0)
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn optimize_at_level_one() {
+        let body = "\tdebug := 2 > 3\n\tcount := 0\n\tif debug {\n\t\tcount = count + 1\n\t} else {\n\t\tcount = count + 2\n\t}\n\tn := count\n\tfmt.Println(n * 10)\n";
+        assert_eq!(
+            optimize(body, PassManager::for_level(0)),
+            "\
+func main() {
+b0:
+    v0: int = const 2
+    v1: int = const 3
+    v2: bool = gt v0, v1
+    v3: int = const 0
+    branch v2, b1, b3
+b1:
+    v4: int = const 1
+    v5: int = add v3, v4
+    jump b2(v5)
+b2(v6: int):
+    v7: int = copy v6
+    v8: int = const 10
+    v9: int = mul v7, v8
+    call fmt.Println(v9)
+    return
+b3:
+    v10: int = const 2
+    v11: int = add v3, v10
+    jump b2(v11)
+}
+"
+        );
+        assert_eq!(
+            optimize(body, PassManager::for_level(1)),
+            "\
+func main() {
+b0:
+    v9: int = const 20
+    call fmt.Println(v9)
+    return
+}
+"
+        );
+    }
+}
@@ -0,0 +1,728 @@
+use std::io::Write;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::ast::expression::{Expression, ExpressionKind};
+use crate::compile::bytecode::{Instruction, Native, Program};
+#[cfg(feature = "jit")]
+use crate::compile::jit::Jit;
+use crate::eval::{
+    builtins,
+    evaluator::{MAX_CALL_DEPTH, binary, format_arguments, panic_message},
+    fmt,
+    scheduler::MAIN_GOROUTINE,
+    stdlib::{self, Arguments},
+    value::{Error, Slice, Value},
+};
+use crate::primitives::{
+    errors::runtime::{RuntimeError, RuntimeErrorKind, StackFrame},
+    position::Position,
+};
+
+/// A call being run: which function, the next instruction, and where its locals start on the
+/// stack
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    function: usize,
+    ip: usize,
+    base: usize,
+}
+
+/// Runs programs compiled to bytecode, as `gor run --engine=vm` does. Calls are frames of the
+/// VM's own rather than the host's, so deep recursion doesn't need a large stack.
+pub struct Vm {
+    output: Box<dyn Write>,
+    max_depth: usize,
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    globals: Vec<Value>,
+    #[cfg(feature = "jit")]
+    jit: Option<Jit>,
+}
+
+impl Vm {
+    pub fn new() -> Vm {
+        Vm {
+            output: Box::new(std::io::stdout()),
+            max_depth: MAX_CALL_DEPTH,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: Vec::new(),
+            #[cfg(feature = "jit")]
+            jit: None,
+        }
+    }
+
+    /// Write the program's output to `output` rather than the process's standard output
+    pub fn with_output(mut self, output: impl Write + 'static) -> Vm {
+        self.output = Box::new(output);
+        self
+    }
+
+    /// Report calls nested deeper than `max_depth` as a stack overflow
+    pub fn with_max_depth(mut self, max_depth: usize) -> Vm {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Run the functions `jit` can compile as machine code once they're hot, as `gor run
+    /// --engine=jit` does. Its functions are the program's, lowered by `lower_each`.
+    #[cfg(feature = "jit")]
+    pub fn with_jit(mut self, jit: Jit) -> Vm {
+        self.jit = Some(jit);
+        self
+    }
+
+    /// Run the program's `init`, then its `main`. As with the interpreter, a call to os.Exit
+    /// ends it, failing with any status but 0, and a panic fails with its trace.
+    pub fn run(&mut self, program: &Program) -> Result<(), RuntimeError> {
+        match self.run_main(program) {
+            Err(error) if error.kind == RuntimeErrorKind::Exit(0) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn run_main(&mut self, program: &Program) -> Result<(), RuntimeError> {
+        // The package clause is the file's first line
+        let package_position = Position::new(1, 0, 7);
+        if program.package_name != "main" {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::NotMainPackage(program.package_name.clone()),
+                package_position,
+            ));
+        }
+        let Some(main) = program.main else {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::MissingMain,
+                package_position,
+            ));
+        };
+        self.globals = vec![Value::Nil; program.globals];
+        self.call(program, program.init)?;
+        self.call(program, main)?;
+        Ok(())
+    }
+
+    /// Run the function at `function`, which takes no arguments, to its end
+    fn call(&mut self, program: &Program, function: usize) -> Result<Vec<Value>, RuntimeError> {
+        self.stack.clear();
+        self.frames.clear();
+        let result = self
+            .enter(program, function, 0)
+            .and_then(|()| self.execute(program));
+        result.map_err(|error| self.unwind(program, error))
+    }
+
+    /// Start a call to `function`, whose arguments are the top `arguments` values
+    fn enter(
+        &mut self,
+        program: &Program,
+        function: usize,
+        arguments: usize,
+    ) -> Result<(), RuntimeError> {
+        if self.frames.len() == self.max_depth {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::StackOverflow,
+                self.position(program),
+            ));
+        }
+        let base = self.stack.len() - arguments;
+        self.stack
+            .resize(base + program.functions[function].locals, Value::Nil);
+        self.frames.push(Frame {
+            function,
+            ip: 0,
+            base,
+        });
+        Ok(())
+    }
+
+    fn execute(&mut self, program: &Program) -> Result<Vec<Value>, RuntimeError> {
+        loop {
+            let frame = self.frames.last_mut().expect("a call is running");
+            let instruction = program.functions[frame.function].code[frame.ip];
+            frame.ip += 1;
+            let base = frame.base;
+            match instruction {
+                Instruction::Constant(index) => {
+                    self.stack.push(program.constants[index as usize].clone())
+                }
+                Instruction::Pop => {
+                    self.stack.pop();
+                }
+                Instruction::LoadLocal(slot) => {
+                    let value = self.stack[base + slot as usize].clone();
+                    self.stack.push(value);
+                }
+                Instruction::StoreLocal(slot) => {
+                    let value = self.pop();
+                    self.stack[base + slot as usize] = value;
+                }
+                Instruction::LoadGlobal(slot) => {
+                    self.stack.push(self.globals[slot as usize].clone());
+                }
+                Instruction::StoreGlobal(slot) => {
+                    self.globals[slot as usize] = self.pop();
+                }
+                Instruction::AddInt => {
+                    self.int_operation(|left, right| Value::Int(left.wrapping_add(right)))
+                }
+                Instruction::SubtractInt => {
+                    self.int_operation(|left, right| Value::Int(left.wrapping_sub(right)))
+                }
+                Instruction::MultiplyInt => {
+                    self.int_operation(|left, right| Value::Int(left.wrapping_mul(right)))
+                }
+                Instruction::DivideInt(operation) | Instruction::RemainderInt(operation) => {
+                    let (Value::Int(left), Value::Int(right)) = self.pop_pair() else {
+                        unreachable!("the compiler only divides ints with DivideInt");
+                    };
+                    if right == 0 {
+                        return Err(RuntimeError::new(
+                            RuntimeErrorKind::DivisionByZero,
+                            program.operations[operation as usize].position_start,
+                        ));
+                    }
+                    let value = match instruction {
+                        Instruction::DivideInt(_) => left.wrapping_div(right),
+                        _ => left.wrapping_rem(right),
+                    };
+                    self.stack.push(Value::Int(value));
+                }
+                Instruction::AddFloat => {
+                    self.float_operation(|left, right| Value::Float(left + right))
+                }
+                Instruction::SubtractFloat => {
+                    self.float_operation(|left, right| Value::Float(left - right))
+                }
+                Instruction::MultiplyFloat => {
+                    self.float_operation(|left, right| Value::Float(left * right))
+                }
+                Instruction::DivideFloat => {
+                    self.float_operation(|left, right| Value::Float(left / right))
+                }
+                Instruction::Concatenate => {
+                    let (Value::String(mut left), Value::String(right)) = self.pop_pair() else {
+                        unreachable!("the compiler only concatenates strings");
+                    };
+                    left.push_str(&right);
+                    self.stack.push(Value::String(left));
+                }
+                Instruction::EqualInt => {
+                    self.int_operation(|left, right| Value::Bool(left == right))
+                }
+                Instruction::NotEqualInt => {
+                    self.int_operation(|left, right| Value::Bool(left != right))
+                }
+                Instruction::LessInt => self.int_operation(|left, right| Value::Bool(left < right)),
+                Instruction::LessEqualInt => {
+                    self.int_operation(|left, right| Value::Bool(left <= right))
+                }
+                Instruction::GreaterInt => {
+                    self.int_operation(|left, right| Value::Bool(left > right))
+                }
+                Instruction::GreaterEqualInt => {
+                    self.int_operation(|left, right| Value::Bool(left >= right))
+                }
+                Instruction::Binary(operation) => {
+                    let operation = &program.operations[operation as usize];
+                    let ExpressionKind::Binary { operator, .. } = &operation.kind else {
+                        unreachable!("Binary's operation is a binary expression");
+                    };
+                    let (left, right) = self.pop_pair();
+                    let value = binary(operation, *operator, left, right)?;
+                    self.stack.push(value);
+                }
+                Instruction::NegateInt => {
+                    let value = match self.pop() {
+                        Value::Int(value) => Value::Int(value.wrapping_neg()),
+                        value => value,
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::NegateFloat => {
+                    let value = match self.pop() {
+                        Value::Float(value) => Value::Float(-value),
+                        value => value,
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::Not => {
+                    let value = match self.pop() {
+                        Value::Bool(value) => Value::Bool(!value),
+                        value => value,
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::Complement => {
+                    let value = match self.pop() {
+                        Value::Int(value) => Value::Int(!value),
+                        value => value,
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::IntToFloat => {
+                    let value = match self.pop() {
+                        Value::Int(value) => Value::Float(value as f64),
+                        value => value,
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::FloatToInt => {
+                    let value = match self.pop() {
+                        Value::Float(value) => Value::Int(value as i64),
+                        value => value,
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::Jump(target) => self.jump(target),
+                Instruction::JumpIfFalse(target) => {
+                    if self.pop() == Value::Bool(false) {
+                        self.jump(target);
+                    }
+                }
+                Instruction::Call {
+                    function,
+                    arguments,
+                } => {
+                    #[cfg(feature = "jit")]
+                    if let Some(jit) = &mut self.jit {
+                        let start = self.stack.len() - arguments as usize;
+                        let depth = self.frames.len();
+                        let arguments = &self.stack[start..];
+                        if let Some(results) =
+                            jit.call(function as usize, arguments, depth, self.max_depth)
+                        {
+                            self.stack.truncate(start);
+                            self.stack.extend(results);
+                            continue;
+                        }
+                    }
+                    self.enter(program, function as usize, arguments as usize)?
+                }
+                Instruction::CallNative { native, arguments } => {
+                    let arguments = self.stack.split_off(self.stack.len() - arguments as usize);
+                    let native = &program.natives[native as usize];
+                    let results = self.call_native(program, native, arguments)?;
+                    if native.results.is_some() {
+                        self.stack.extend(results);
+                    }
+                }
+                Instruction::Return(count) => {
+                    let results = self.stack.split_off(self.stack.len() - count as usize);
+                    self.stack.truncate(base);
+                    self.frames.pop();
+                    if self.frames.is_empty() {
+                        return Ok(results);
+                    }
+                    self.stack.extend(results);
+                }
+                Instruction::Len | Instruction::Cap => {
+                    let value = self.pop();
+                    let size = match instruction {
+                        Instruction::Len => builtins::length(&value),
+                        _ => builtins::capacity(&value),
+                    };
+                    self.stack.push(Value::Int(size.unwrap_or_default() as i64));
+                }
+                Instruction::Append(count) => {
+                    let elements = self.stack.split_off(self.stack.len() - count as usize);
+                    let Value::Slice(slice) = self.pop() else {
+                        unreachable!("the compiler only appends to slices");
+                    };
+                    let appended = builtins::append(&slice, elements);
+                    self.stack.push(Value::Slice(appended));
+                }
+                Instruction::AppendSlice => {
+                    let (Value::Slice(slice), Value::Slice(elements)) = self.pop_pair() else {
+                        unreachable!("the compiler only appends slices to slices");
+                    };
+                    let appended = builtins::append(&slice, elements.elements());
+                    self.stack.push(Value::Slice(appended));
+                }
+                Instruction::MakeSlice {
+                    slice,
+                    zero,
+                    arguments,
+                } => {
+                    let sizes = self.stack.split_off(self.stack.len() - arguments as usize);
+                    let value = self.make_slice(program, slice, zero, &sizes)?;
+                    self.stack.push(value);
+                }
+                Instruction::SliceLiteral { slice, elements } => {
+                    let elements = self.stack.split_off(self.stack.len() - elements as usize);
+                    let Value::Slice(slice) = &program.constants[slice as usize] else {
+                        unreachable!("a slice literal's constant is a slice");
+                    };
+                    let cap = elements.len();
+                    let value = Slice::new(slice.element.clone(), elements, cap);
+                    self.stack.push(Value::Slice(value));
+                }
+                Instruction::Index(operation) => {
+                    let (object, index) = self.pop_pair();
+                    let operation = &program.operations[operation as usize];
+                    let value = match object {
+                        Value::String(string) => {
+                            let i = element_index(operation, &index, string.len())?;
+                            Value::Int(i64::from(string.as_bytes()[i]))
+                        }
+                        Value::Slice(slice) => {
+                            slice.get(element_index(operation, &index, slice.len)?)
+                        }
+                        _ => unreachable!("the compiler only indexes slices and strings"),
+                    };
+                    self.stack.push(value);
+                }
+                Instruction::StoreIndex(operation) => {
+                    let value = self.pop();
+                    let (object, index) = self.pop_pair();
+                    let Value::Slice(slice) = object else {
+                        unreachable!("the compiler only stores in slices");
+                    };
+                    let operation = &program.operations[operation as usize];
+                    slice.set(element_index(operation, &index, slice.len)?, value);
+                }
+                Instruction::Panic(operation) => {
+                    let call = &program.operations[operation as usize];
+                    return Err(panic(self.pop(), call));
+                }
+            }
+        }
+    }
+
+    /// Call a native package's function, checking it returns as many results as the call
+    /// needs, as the checker would have had it known the function's type
+    fn call_native(
+        &mut self,
+        program: &Program,
+        native: &Native,
+        arguments: Vec<Value>,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let call = &program.operations[native.call as usize];
+        let qualified = format!("{}.{}", native.path, native.function);
+        let results = match (native.path.as_str(), native.function.as_str()) {
+            ("fmt", function) => self.call_fmt(function, &qualified, &arguments, call)?,
+            ("time", "Sleep") => {
+                let arguments = Arguments::new(qualified, &arguments, call);
+                arguments.expect(1)?;
+                let duration = arguments.duration(0)?;
+                if duration > 0 {
+                    std::thread::sleep(Duration::from_nanos(duration as u64));
+                }
+                Vec::new()
+            }
+            (path, function) => match stdlib::package(path) {
+                Some(package) => package(function, &Arguments::new(qualified, &arguments, call))?,
+                None => {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::Unsupported(call.to_string()),
+                        call.position_start,
+                    ));
+                }
+            },
+        };
+        let kind = match (native.results, results.len()) {
+            (None, _) => return Ok(results),
+            (Some(want), have) if want == have => return Ok(results),
+            (Some(1), 0) => RuntimeErrorKind::NoValue(call.to_string()),
+            (Some(1), _) => RuntimeErrorKind::MultipleValues(call.to_string()),
+            (Some(variables), values) => RuntimeErrorKind::AssignmentMismatch { variables, values },
+        };
+        Err(RuntimeError::new(kind, call.position_start))
+    }
+
+    /// fmt's print functions, which print values as they are, since a program the compiler
+    /// compiles has no methods to print them with
+    fn call_fmt(
+        &mut self,
+        function: &str,
+        qualified: &str,
+        arguments: &[Value],
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let printed = match function {
+            "Print" => fmt::sprint(arguments),
+            "Println" => fmt::sprintln(arguments),
+            "Printf" => {
+                let (format, operands) = format_arguments(qualified, arguments, call)?;
+                fmt::sprintf(format, operands)
+            }
+            "Sprint" => return Ok(vec![Value::String(fmt::sprint(arguments))]),
+            "Sprintln" => return Ok(vec![Value::String(fmt::sprintln(arguments))]),
+            "Sprintf" => {
+                let (format, operands) = format_arguments(qualified, arguments, call)?;
+                return Ok(vec![Value::String(fmt::sprintf(format, operands))]);
+            }
+            "Errorf" => {
+                let (format, operands) = format_arguments(qualified, arguments, call)?;
+                return Ok(vec![fmt::errorf(format, operands, operands)]);
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedName(qualified.to_string()),
+                    call.position_start,
+                ));
+            }
+        };
+        // The print functions return the number of bytes written and any write error
+        Ok(match self.output.write_all(printed.as_bytes()) {
+            Ok(()) => vec![Value::Int(printed.len() as i64), Value::Nil],
+            Err(error) => vec![
+                Value::Int(0),
+                Value::Error(Rc::new(Error::new("*fs.PathError", error.to_string()))),
+            ],
+        })
+    }
+
+    /// `make([]T, len)` or `make([]T, len, cap)`, like the constant `slice` with each
+    /// element the constant `zero`
+    fn make_slice(
+        &self,
+        program: &Program,
+        slice: u32,
+        zero: u32,
+        sizes: &[Value],
+    ) -> Result<Value, RuntimeError> {
+        let Value::Slice(slice) = &program.constants[slice as usize] else {
+            unreachable!("make's constant is a slice");
+        };
+        let size = |index: usize| match sizes.get(index) {
+            Some(Value::Int(size)) => *size,
+            _ => 0,
+        };
+        let (len, cap) = match sizes.len() {
+            1 => (size(0), size(0)),
+            _ => (size(0), size(1)),
+        };
+        let out_of_range = |size: &str| {
+            Err(RuntimeError::new(
+                RuntimeErrorKind::SizeOutOfRange {
+                    function: "makeslice".to_string(),
+                    size: size.to_string(),
+                },
+                self.position(program),
+            ))
+        };
+        if len < 0 {
+            return out_of_range("len");
+        }
+        if cap < len {
+            return out_of_range("cap");
+        }
+        let elements = vec![program.constants[zero as usize].clone(); len as usize];
+        Ok(Value::Slice(Slice::new(
+            slice.element.clone(),
+            elements,
+            cap as usize,
+        )))
+    }
+
+    /// `error` with the trace of the calls it unwound, innermost first, as a panic, when it's
+    /// a panic or a run-time error Go panics with
+    fn unwind(&mut self, program: &Program, mut error: RuntimeError) -> RuntimeError {
+        if error.kind.is_run_time_panic() {
+            error.kind = RuntimeErrorKind::Panic {
+                messages: vec![error.kind.to_string()],
+                stack: Vec::new(),
+                goroutine: MAIN_GOROUTINE,
+            };
+        }
+        if let RuntimeErrorKind::Panic { stack, .. } = &mut error.kind {
+            stack.extend(self.frames.iter().rev().map(|frame| {
+                let function = &program.functions[frame.function];
+                StackFrame {
+                    function: function.name.clone(),
+                    position: function.positions[frame.ip.saturating_sub(1)],
+                }
+            }));
+        }
+        self.frames.clear();
+        self.stack.clear();
+        error
+    }
+
+    /// The position of the instruction being run
+    fn position(&self, program: &Program) -> Position {
+        self.frames.last().map_or(Position::new(1, 0, 0), |frame| {
+            program.functions[frame.function].positions[frame.ip.saturating_sub(1)]
+        })
+    }
+
+    fn jump(&mut self, target: u32) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.ip = target as usize;
+        }
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("the compiler balances the stack")
+    }
+
+    /// The top two values, the one below the top first
+    fn pop_pair(&mut self) -> (Value, Value) {
+        let right = self.pop();
+        (self.pop(), right)
+    }
+
+    fn int_operation(&mut self, operation: impl Fn(i64, i64) -> Value) {
+        let (Value::Int(left), Value::Int(right)) = self.pop_pair() else {
+            unreachable!("the compiler only uses int instructions on ints");
+        };
+        self.stack.push(operation(left, right));
+    }
+
+    fn float_operation(&mut self, operation: impl Fn(f64, f64) -> Value) {
+        let (Value::Float(left), Value::Float(right)) = self.pop_pair() else {
+            unreachable!("the compiler only uses float instructions on floats");
+        };
+        self.stack.push(operation(left, right));
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Vm {
+        Vm::new()
+    }
+}
+
+impl std::fmt::Debug for Vm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vm")
+            .field("max_depth", &self.max_depth)
+            .field("stack", &self.stack)
+            .field("frames", &self.frames)
+            .finish()
+    }
+}
+
+/// The panic `panic(value)` starts, whose trace is added as it unwinds
+fn panic(value: Value, call: &Expression) -> RuntimeError {
+    // Since Go 1.21, panic(nil) panics with a *runtime.PanicNilError instead
+    let value = match value {
+        Value::Nil => Value::Error(Rc::new(Error::new(
+            "*runtime.PanicNilError",
+            "panic called with nil argument".to_string(),
+        ))),
+        value => value,
+    };
+    RuntimeError::new(
+        RuntimeErrorKind::Panic {
+            messages: vec![panic_message(&value)],
+            stack: Vec::new(),
+            goroutine: MAIN_GOROUTINE,
+        },
+        call.position_start,
+    )
+}
+
+/// The element `value` indexes of one of `length`, where `operation` is the index expression,
+/// or a range loop's, which never indexes out of range
+fn element_index(
+    operation: &Expression,
+    value: &Value,
+    length: usize,
+) -> Result<usize, RuntimeError> {
+    let (index, position) = match &operation.kind {
+        ExpressionKind::Index { index, .. } => (index.to_string(), index.position_start),
+        _ => (operation.to_string(), operation.position_start),
+    };
+    let i = match value {
+        Value::Int(i) => *i,
+        value => {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::NonIntegerIndex {
+                    index,
+                    index_type: value.type_name(),
+                },
+                position,
+            ));
+        }
+    };
+    usize::try_from(i)
+        .ok()
+        .filter(|&i| i < length)
+        .ok_or_else(|| {
+            RuntimeError::new(
+                RuntimeErrorKind::IndexOutOfRange { index: i, length },
+                position,
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::compiler::compile;
+    use crate::eval::interpreter::Interpreter;
+    use crate::parser::parser::Parser;
+    use crate::test_support::Captured;
+
+    /// Run `source` on the VM and in the interpreter, returning what each printed and how it
+    /// failed
+    fn run_both(source: &str) -> [(String, Option<String>); 2] {
+        let file = Parser::new(source).parse().unwrap();
+        let program = compile(&file).unwrap();
+        let output = Captured::default();
+        let vm = Vm::new().with_output(output.clone()).run(&program);
+        let interpreted = Captured::default();
+        let interpreter = Interpreter::new()
+            .with_output(interpreted.clone())
+            .run(&file);
+        [(output, vm), (interpreted, interpreter)].map(|(output, result)| {
+            (
+                output.take(),
+                result.err().map(|error| error.kind.to_string()),
+            )
+        })
+    }
+
+    #[test]
+    fn run_like_the_interpreter() {
+        let sources = [
+            "package main\n\nimport \"fmt\"\n\nvar total = 10\n\nfunc fib(n int) int {\n\tif n < 2 {\n\t\treturn n\n\t}\n\treturn fib(n-1) + fib(n-2)\n}\n\nfunc divmod(a, b int) (q, r int) {\n\tq = a / b\n\tr = a % b\n\treturn\n}\n\nfunc main() {\n\tq, r := divmod(17, 5)\n\tfmt.Println(fib(15), total, q, r)\n\ttotal = total - q\n\tfmt.Println(total, -total, ^total, total<<2, total&3 == 1 || total > 100)\n}\n",
+            "package main\n\nimport (\n\t\"fmt\"\n\t\"strings\"\n)\n\nfunc main() {\n\ts := []int{1, 2, 3}\n\ts = append(s, 4, 5)\n\ts = append(s, s...)\n\tsum := 0\n\tfor i, v := range s {\n\t\tif i == 1 {\n\t\t\tcontinue\n\t\t}\n\t\tif v == 5 {\n\t\t\tbreak\n\t\t}\n\t\tsum = sum + v\n\t}\n\tfmt.Println(sum, len(s), cap(s), s)\n\tm := make([]string, 2, 4)\n\tm[1] = strings.Repeat(\"ab\", 3)\n\tfmt.Printf(\"%q %d %T\\n\", m, len(m), m)\n\tx := 1.5\n\tfmt.Println(x*2, float64(len(s))/4, int(x*3), 7/2, 7.0/2)\n\tfor i := range 3 {\n\t\tfmt.Print(i, \" \")\n\t}\n\tword := \"hey\"\n\tfmt.Println(word[1], word+\"!\", word < \"hi\", fmt.Sprint(x, true))\n}\n",
+            "package main\n\nimport \"fmt\"\n\nfunc at(s []int, i int) int {\n\treturn s[i]\n}\n\nfunc main() {\n\tfmt.Println(at([]int{1}, 0))\n\tfmt.Println(at([]int{1}, 3))\n}\n",
+            "package main\n\nfunc inner(n int) int {\n\tif n == 0 {\n\t\tpanic(\"boom\")\n\t}\n\treturn inner(n - 1)\n}\n\nfunc main() {\n\tinner(2)\n}\n",
+            "package main\n\nimport \"os\"\n\nfunc main() {\n\tos.Exit(3)\n}\n",
+            "package main\n\nfunc main() {\n\tzero := 0\n\t_ = 1 / zero\n}\n",
+            "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tsum := 0\n\tfor i := 0; i < 10; i++ {\n\t\tif i%3 == 0 {\n\t\t\tcontinue\n\t\t}\n\t\tsum += i\n\t}\n\tx := 1.5\n\tx *= 2\n\tx--\n\ts := []int{1, 2}\n\ts[1] <<= 3\n\tfmt.Println(sum, x, s)\n}\n",
+        ];
+        for source in sources {
+            let [vm, interpreter] = run_both(source);
+            assert_eq!(vm, interpreter, "{}", source);
+        }
+    }
+
+    #[test]
+    fn run_errors() {
+        let run = |source: &str| {
+            let file = Parser::new(source).parse().unwrap();
+            let program = compile(&file).unwrap();
+            Vm::new()
+                .with_max_depth(100)
+                .run(&program)
+                .unwrap_err()
+                .kind
+        };
+        assert_eq!(
+            run(
+                "package main\n\nfunc f(n int) int {\n\treturn f(n + 1)\n}\n\nfunc main() {\n\tf(0)\n}\n"
+            ),
+            RuntimeErrorKind::StackOverflow
+        );
+        assert_eq!(
+            run("package main\n\nfunc helper() {}\n"),
+            RuntimeErrorKind::MissingMain
+        );
+        assert_eq!(
+            run("package lib\n\nfunc main() {}\n"),
+            RuntimeErrorKind::NotMainPackage("lib".to_string())
+        );
+        assert_eq!(
+            run(
+                "package main\n\nimport \"strconv\"\n\nfunc main() {\n\tx := strconv.Atoi(\"1\")\n\t_ = x\n}\n"
+            ),
+            RuntimeErrorKind::MultipleValues("strconv.Atoi(\"1\")".to_string())
+        );
+    }
+}
@@ -0,0 +1,1163 @@
+use std::collections::HashMap;
+
+use crate::compile::ir::{
+    BasicBlock, BinaryOp, Constant, Function, InstructionKind, Module, Target, Terminator, Type,
+    UnaryOp, Value,
+};
+use crate::primitives::{
+    errors::compile::{CompileError, CompileErrorKind},
+    position::Position,
+};
+
+/// Where the iovec `fd_write` is passed is kept, and where it writes how much it wrote
+const IOVEC: i32 = 0;
+const WRITTEN: i32 = 8;
+/// An int is formatted backwards from the end of this buffer, long enough for any int's
+/// digits and sign
+const DIGITS_END: i32 = 48;
+/// Where string constants start, each its length as four bytes then its bytes. The heap
+/// strings are concatenated into starts after them.
+const DATA_START: u32 = 64;
+const PAGE_SIZE: u32 = 65536;
+
+const I32: u8 = 0x7F;
+const I64: u8 = 0x7E;
+const F64: u8 = 0x7C;
+const EMPTY_BLOCK: u8 = 0x40;
+
+/// The functions every module has, by index: WASI's imports, then the runtime's own
+mod index {
+    pub const FD_WRITE: u32 = 0;
+    pub const PROC_EXIT: u32 = 1;
+    pub const WRITE: u32 = 2;
+    pub const PRINT_STRING: u32 = 3;
+    pub const PRINT_INT: u32 = 4;
+    pub const PRINT_BOOL: u32 = 5;
+    pub const ALLOCATE: u32 = 6;
+    pub const CONCATENATE: u32 = 7;
+    pub const COMPARE: u32 = 8;
+    pub const FINISH_PANIC: u32 = 9;
+    pub const RUNTIME_PANIC: u32 = 10;
+    pub const DIVIDE: u32 = 11;
+    pub const REMAINDER: u32 = 12;
+    pub const SHIFT_LEFT: u32 = 13;
+    pub const SHIFT_RIGHT: u32 = 14;
+    /// The module's functions follow, then `_start`
+    pub const FIRST_FUNCTION: u32 = 15;
+    pub const IMPORTS: u32 = 2;
+}
+
+/// The instructions the backend emits
+mod op {
+    pub const UNREACHABLE: u8 = 0x00;
+    pub const BLOCK: u8 = 0x02;
+    pub const LOOP: u8 = 0x03;
+    pub const IF: u8 = 0x04;
+    pub const ELSE: u8 = 0x05;
+    pub const END: u8 = 0x0B;
+    pub const BR: u8 = 0x0C;
+    pub const BR_IF: u8 = 0x0D;
+    pub const BR_TABLE: u8 = 0x0E;
+    pub const RETURN: u8 = 0x0F;
+    pub const CALL: u8 = 0x10;
+    pub const DROP: u8 = 0x1A;
+    pub const SELECT: u8 = 0x1B;
+    pub const LOCAL_GET: u8 = 0x20;
+    pub const LOCAL_SET: u8 = 0x21;
+    pub const LOCAL_TEE: u8 = 0x22;
+    pub const GLOBAL_GET: u8 = 0x23;
+    pub const GLOBAL_SET: u8 = 0x24;
+    pub const I32_LOAD: u8 = 0x28;
+    pub const I32_LOAD8_U: u8 = 0x2D;
+    pub const I32_STORE: u8 = 0x36;
+    pub const I32_STORE8: u8 = 0x3A;
+    pub const MEMORY_SIZE: u8 = 0x3F;
+    pub const MEMORY_GROW: u8 = 0x40;
+    pub const I32_CONST: u8 = 0x41;
+    pub const I64_CONST: u8 = 0x42;
+    pub const F64_CONST: u8 = 0x44;
+    pub const I32_EQZ: u8 = 0x45;
+    pub const I32_EQ: u8 = 0x46;
+    pub const I32_NE: u8 = 0x47;
+    pub const I32_LT_S: u8 = 0x48;
+    pub const I32_LT_U: u8 = 0x49;
+    pub const I32_GT_S: u8 = 0x4A;
+    pub const I32_GT_U: u8 = 0x4B;
+    pub const I32_LE_S: u8 = 0x4C;
+    pub const I32_GE_S: u8 = 0x4E;
+    pub const I64_EQZ: u8 = 0x50;
+    pub const I64_EQ: u8 = 0x51;
+    pub const I64_NE: u8 = 0x52;
+    pub const I64_LT_S: u8 = 0x53;
+    pub const I64_GT_S: u8 = 0x55;
+    pub const I64_LE_S: u8 = 0x57;
+    pub const I64_GE_S: u8 = 0x59;
+    pub const F64_EQ: u8 = 0x61;
+    pub const F64_NE: u8 = 0x62;
+    pub const F64_LT: u8 = 0x63;
+    pub const F64_GT: u8 = 0x64;
+    pub const F64_LE: u8 = 0x65;
+    pub const F64_GE: u8 = 0x66;
+    pub const I32_ADD: u8 = 0x6A;
+    pub const I32_SUB: u8 = 0x6B;
+    pub const I32_AND: u8 = 0x71;
+    pub const I32_SHL: u8 = 0x74;
+    pub const I32_SHR_U: u8 = 0x76;
+    pub const I64_ADD: u8 = 0x7C;
+    pub const I64_SUB: u8 = 0x7D;
+    pub const I64_MUL: u8 = 0x7E;
+    pub const I64_DIV_S: u8 = 0x7F;
+    pub const I64_DIV_U: u8 = 0x80;
+    pub const I64_REM_S: u8 = 0x81;
+    pub const I64_REM_U: u8 = 0x82;
+    pub const I64_AND: u8 = 0x83;
+    pub const I64_OR: u8 = 0x84;
+    pub const I64_XOR: u8 = 0x85;
+    pub const I64_SHL: u8 = 0x86;
+    pub const I64_SHR_S: u8 = 0x87;
+    pub const F64_NEG: u8 = 0x9A;
+    pub const F64_ADD: u8 = 0xA0;
+    pub const F64_SUB: u8 = 0xA1;
+    pub const F64_MUL: u8 = 0xA2;
+    pub const F64_DIV: u8 = 0xA3;
+    pub const I32_WRAP_I64: u8 = 0xA7;
+    pub const I64_EXTEND_I32_U: u8 = 0xAD;
+    pub const F64_CONVERT_I64_S: u8 = 0xB9;
+    /// The prefix of the saturating truncations and bulk memory instructions
+    pub const PREFIX: u8 = 0xFC;
+    pub const I64_TRUNC_SAT_F64_S: u8 = 0x06;
+    pub const MEMORY_COPY: u8 = 0x0A;
+}
+
+/// Compile a module to a WebAssembly binary. It's a WASI command, so wasmtime runs it as it
+/// is, and a browser with a WASI shim providing `fd_write` and `proc_exit`. What's printed is
+/// written to standard output as it's printed, and a panic is written to standard error
+/// before exiting with status 2, as Go's is.
+///
+/// Strings are pointers into linear memory, to their length then their bytes, and the
+/// strings concatenation makes are allocated after the constants and never freed. Printing
+/// floats isn't supported yet, nor are the native packages besides fmt's Print and Println.
+pub fn generate(module: &Module) -> Result<Vec<u8>, CompileError> {
+    Generator::new(module).generate()
+}
+
+/// The binary being built: its function types, code and strings
+struct Generator<'a> {
+    module: &'a Module,
+    types: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Each function's type and body, the runtime's then the module's
+    functions: Vec<(u32, Vec<u8>)>,
+    data: Vec<u8>,
+    strings: HashMap<String, i32>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(module: &'a Module) -> Generator<'a> {
+        Generator {
+            module,
+            types: Vec::new(),
+            functions: Vec::new(),
+            data: Vec::new(),
+            strings: HashMap::new(),
+        }
+    }
+
+    fn generate(mut self) -> Result<Vec<u8>, CompileError> {
+        self.runtime();
+        for function in &self.module.functions {
+            self.function(function)?;
+        }
+        let mut start = Code::new();
+        start.call(index::FIRST_FUNCTION + self.module.init as u32);
+        if let Some(main) = self.module.main {
+            start.call(index::FIRST_FUNCTION + main as u32);
+        }
+        let start_type = self.function_type(&[], &[]);
+        self.functions.push((start_type, start.finish(&[])));
+
+        let fd_write = self.function_type(&[I32, I32, I32, I32], &[I32]);
+        let proc_exit = self.function_type(&[I32], &[]);
+        let heap_start = (DATA_START + self.data.len() as u32).next_multiple_of(8);
+        let mut binary = b"\0asm".to_vec();
+        binary.extend(1u32.to_le_bytes());
+
+        let mut types = Vec::new();
+        unsigned(&mut types, self.types.len() as u64);
+        for (parameters, results) in &self.types {
+            types.push(0x60);
+            bytes(&mut types, parameters);
+            bytes(&mut types, results);
+        }
+        section(&mut binary, 1, types);
+
+        let mut imports = Vec::new();
+        unsigned(&mut imports, index::IMPORTS as u64);
+        for (name, type_index) in [("fd_write", fd_write), ("proc_exit", proc_exit)] {
+            string(&mut imports, "wasi_snapshot_preview1");
+            string(&mut imports, name);
+            imports.push(0x00);
+            unsigned(&mut imports, type_index as u64);
+        }
+        section(&mut binary, 2, imports);
+
+        let mut functions = Vec::new();
+        unsigned(&mut functions, self.functions.len() as u64);
+        for (type_index, _) in &self.functions {
+            unsigned(&mut functions, *type_index as u64);
+        }
+        section(&mut binary, 3, functions);
+
+        let mut memory = vec![1, 0x00];
+        unsigned(&mut memory, (heap_start / PAGE_SIZE + 1) as u64);
+        section(&mut binary, 5, memory);
+
+        // The heap's next free address, then the module's globals, each its zero value
+        let mut globals = Vec::new();
+        unsigned(&mut globals, self.module.globals.len() as u64 + 1);
+        let empty = self.string("");
+        let mut initializers = vec![(I32, Code::new())];
+        initializers[0].1.i32_const(heap_start as i32);
+        for global in &self.module.globals {
+            let mut initializer = Code::new();
+            match global.value_type {
+                Type::Int => initializer.i64_const(0),
+                Type::Float => initializer.f64_const(0.0),
+                Type::Bool => initializer.i32_const(0),
+                Type::String => initializer.i32_const(empty),
+            }
+            initializers.push((value_type(global.value_type), initializer));
+        }
+        for (global_type, mut initializer) in initializers {
+            globals.extend([global_type, 0x01]);
+            initializer.op(op::END);
+            globals.extend(initializer.bytes);
+        }
+        section(&mut binary, 6, globals);
+
+        let mut exports = Vec::new();
+        unsigned(&mut exports, 2);
+        string(&mut exports, "memory");
+        exports.extend([0x02, 0x00]);
+        string(&mut exports, "_start");
+        exports.push(0x00);
+        let start_index = index::IMPORTS + self.functions.len() as u32 - 1;
+        unsigned(&mut exports, start_index as u64);
+        section(&mut binary, 7, exports);
+
+        let mut code = Vec::new();
+        unsigned(&mut code, self.functions.len() as u64);
+        for (_, body) in &self.functions {
+            bytes(&mut code, body);
+        }
+        section(&mut binary, 10, code);
+
+        let mut data = vec![1, 0x00];
+        let mut offset = Code::new();
+        offset.i32_const(DATA_START as i32);
+        offset.op(op::END);
+        data.extend(offset.bytes);
+        bytes(&mut data, &self.data);
+        section(&mut binary, 11, data);
+        Ok(binary)
+    }
+
+    /// The index of the function type taking `parameters` and returning `results`
+    fn function_type(&mut self, parameters: &[u8], results: &[u8]) -> u32 {
+        let signature = (parameters.to_vec(), results.to_vec());
+        match self.types.iter().position(|known| *known == signature) {
+            Some(position) => position as u32,
+            None => {
+                self.types.push(signature);
+                self.types.len() as u32 - 1
+            }
+        }
+    }
+
+    /// The address of a string constant, its length then its bytes
+    fn string(&mut self, value: &str) -> i32 {
+        if let Some(address) = self.strings.get(value) {
+            return *address;
+        }
+        let address = DATA_START as i32 + self.data.len() as i32;
+        self.data.extend((value.len() as u32).to_le_bytes());
+        self.data.extend(value.as_bytes());
+        while !self.data.len().is_multiple_of(4) {
+            self.data.push(0);
+        }
+        self.strings.insert(value.to_string(), address);
+        address
+    }
+
+    fn add_function(&mut self, parameters: &[u8], results: &[u8], locals: &[u8], code: Code) {
+        let type_index = self.function_type(parameters, results);
+        self.functions.push((type_index, code.finish(locals)));
+    }
+
+    /// The functions the module's code calls to print, panic, and do what a single
+    /// WebAssembly instruction doesn't
+    fn runtime(&mut self) {
+        // The zero value of a string global, interned before the heap's start is worked out
+        self.string("");
+        let true_string = self.string("true");
+        let false_string = self.string("false");
+        let runtime_error = self.string("panic: runtime error: ");
+        let goroutine = self.string("\n\ngoroutine 1 [running]:\n");
+        let divide_by_zero = self.string("integer divide by zero");
+        let negative_shift = self.string("negative shift amount");
+
+        // write(fd, address, length)
+        let mut code = Code::new();
+        code.i32_const(IOVEC);
+        code.local_get(1);
+        code.memory(op::I32_STORE, 2, 0);
+        code.i32_const(IOVEC);
+        code.local_get(2);
+        code.memory(op::I32_STORE, 2, 4);
+        code.local_get(0);
+        code.i32_const(IOVEC);
+        code.i32_const(1);
+        code.i32_const(WRITTEN);
+        code.call(index::FD_WRITE);
+        code.op(op::DROP);
+        self.add_function(&[I32, I32, I32], &[], &[], code);
+
+        // print_string(fd, string)
+        let mut code = Code::new();
+        code.local_get(0);
+        code.local_get(1);
+        code.i32_const(4);
+        code.op(op::I32_ADD);
+        code.local_get(1);
+        code.memory(op::I32_LOAD, 2, 0);
+        code.call(index::WRITE);
+        self.add_function(&[I32, I32], &[], &[], code);
+
+        // print_int(fd, int), writing its digits from the last, then its sign
+        let (position, magnitude) = (2, 3);
+        let mut code = Code::new();
+        code.i32_const(DIGITS_END);
+        code.local_set(position);
+        code.i64_const(0);
+        code.local_get(1);
+        code.op(op::I64_SUB);
+        code.local_get(1);
+        code.local_get(1);
+        code.i64_const(0);
+        code.op(op::I64_LT_S);
+        code.op(op::SELECT);
+        code.local_set(magnitude);
+        code.block(op::LOOP, EMPTY_BLOCK);
+        code.local_get(position);
+        code.i32_const(1);
+        code.op(op::I32_SUB);
+        code.local_tee(position);
+        code.local_get(magnitude);
+        code.i64_const(10);
+        code.op(op::I64_REM_U);
+        code.op(op::I32_WRAP_I64);
+        code.i32_const(b'0' as i32);
+        code.op(op::I32_ADD);
+        code.memory(op::I32_STORE8, 0, 0);
+        code.local_get(magnitude);
+        code.i64_const(10);
+        code.op(op::I64_DIV_U);
+        code.local_tee(magnitude);
+        code.op(op::I64_EQZ);
+        code.op(op::I32_EQZ);
+        code.branch(op::BR_IF, 0);
+        code.op(op::END);
+        code.local_get(1);
+        code.i64_const(0);
+        code.op(op::I64_LT_S);
+        code.block(op::IF, EMPTY_BLOCK);
+        code.local_get(position);
+        code.i32_const(1);
+        code.op(op::I32_SUB);
+        code.local_tee(position);
+        code.i32_const(b'-' as i32);
+        code.memory(op::I32_STORE8, 0, 0);
+        code.op(op::END);
+        code.local_get(0);
+        code.local_get(position);
+        code.i32_const(DIGITS_END);
+        code.local_get(position);
+        code.op(op::I32_SUB);
+        code.call(index::WRITE);
+        self.add_function(&[I32, I64], &[], &[I32, I64], code);
+
+        // print_bool(fd, bool)
+        let mut code = Code::new();
+        code.local_get(0);
+        code.i32_const(true_string);
+        code.i32_const(false_string);
+        code.local_get(1);
+        code.op(op::SELECT);
+        code.call(index::PRINT_STRING);
+        self.add_function(&[I32, I32], &[], &[], code);
+
+        // allocate(size) -> address, growing the memory when the heap outgrows it
+        let address = 1;
+        let mut code = Code::new();
+        code.global_get(0);
+        code.local_set(address);
+        code.global_get(0);
+        code.local_get(0);
+        code.op(op::I32_ADD);
+        code.i32_const(7);
+        code.op(op::I32_ADD);
+        code.i32_const(-8);
+        code.op(op::I32_AND);
+        code.global_set(0);
+        code.global_get(0);
+        code.memory_size();
+        code.op(op::I32_GT_U);
+        code.block(op::IF, EMPTY_BLOCK);
+        code.global_get(0);
+        code.memory_size();
+        code.op(op::I32_SUB);
+        code.i32_const(PAGE_SIZE as i32 - 1);
+        code.op(op::I32_ADD);
+        code.i32_const(16);
+        code.op(op::I32_SHR_U);
+        code.op(op::MEMORY_GROW);
+        code.bytes.push(0x00);
+        code.op(op::DROP);
+        code.op(op::END);
+        code.local_get(address);
+        self.add_function(&[I32], &[I32], &[I32], code);
+
+        // concatenate(left, right) -> string
+        let (left_length, right_length, result) = (2, 3, 4);
+        let mut code = Code::new();
+        code.local_get(0);
+        code.memory(op::I32_LOAD, 2, 0);
+        code.local_set(left_length);
+        code.local_get(1);
+        code.memory(op::I32_LOAD, 2, 0);
+        code.local_set(right_length);
+        code.local_get(left_length);
+        code.local_get(right_length);
+        code.op(op::I32_ADD);
+        code.i32_const(4);
+        code.op(op::I32_ADD);
+        code.call(index::ALLOCATE);
+        code.local_tee(result);
+        code.local_get(left_length);
+        code.local_get(right_length);
+        code.op(op::I32_ADD);
+        code.memory(op::I32_STORE, 2, 0);
+        for (source, length, skip) in [(0, left_length, None), (1, right_length, Some(left_length))]
+        {
+            code.local_get(result);
+            code.i32_const(4);
+            code.op(op::I32_ADD);
+            if let Some(skip) = skip {
+                code.local_get(skip);
+                code.op(op::I32_ADD);
+            }
+            code.local_get(source);
+            code.i32_const(4);
+            code.op(op::I32_ADD);
+            code.local_get(length);
+            code.memory_copy();
+        }
+        code.local_get(result);
+        self.add_function(&[I32, I32], &[I32], &[I32, I32, I32], code);
+
+        // compare(left, right) -> -1, 0 or 1, comparing their bytes in order
+        let (left_length, right_length, shorter, at, left_byte, right_byte) = (2, 3, 4, 5, 6, 7);
+        let mut code = Code::new();
+        code.local_get(0);
+        code.memory(op::I32_LOAD, 2, 0);
+        code.local_set(left_length);
+        code.local_get(1);
+        code.memory(op::I32_LOAD, 2, 0);
+        code.local_set(right_length);
+        code.local_get(left_length);
+        code.local_get(right_length);
+        code.local_get(left_length);
+        code.local_get(right_length);
+        code.op(op::I32_LT_U);
+        code.op(op::SELECT);
+        code.local_set(shorter);
+        code.block(op::LOOP, EMPTY_BLOCK);
+        code.local_get(at);
+        code.local_get(shorter);
+        code.op(op::I32_EQ);
+        code.block(op::IF, EMPTY_BLOCK);
+        code.sign(left_length, right_length);
+        code.op(op::RETURN);
+        code.op(op::END);
+        for (string, byte) in [(0, left_byte), (1, right_byte)] {
+            code.local_get(string);
+            code.local_get(at);
+            code.op(op::I32_ADD);
+            code.memory(op::I32_LOAD8_U, 0, 4);
+            code.local_set(byte);
+        }
+        code.local_get(left_byte);
+        code.local_get(right_byte);
+        code.op(op::I32_NE);
+        code.block(op::IF, EMPTY_BLOCK);
+        code.sign(left_byte, right_byte);
+        code.op(op::RETURN);
+        code.op(op::END);
+        code.local_get(at);
+        code.i32_const(1);
+        code.op(op::I32_ADD);
+        code.local_set(at);
+        code.branch(op::BR, 0);
+        code.op(op::END);
+        code.op(op::UNREACHABLE);
+        self.add_function(&[I32, I32], &[I32], &[I32; 6], code);
+
+        // finish_panic(), after its message is written
+        let mut code = Code::new();
+        code.i32_const(2);
+        code.i32_const(goroutine);
+        code.call(index::PRINT_STRING);
+        code.i32_const(2);
+        code.call(index::PROC_EXIT);
+        code.op(op::UNREACHABLE);
+        self.add_function(&[], &[], &[], code);
+
+        // runtime_panic(message)
+        let mut code = Code::new();
+        code.i32_const(2);
+        code.i32_const(runtime_error);
+        code.call(index::PRINT_STRING);
+        code.i32_const(2);
+        code.local_get(0);
+        code.call(index::PRINT_STRING);
+        code.call(index::FINISH_PANIC);
+        self.add_function(&[I32], &[], &[], code);
+
+        // divide(left, right) and remainder(left, right), where dividing the smallest int by
+        // -1 wraps around rather than trapping
+        for operation in [op::I64_DIV_S, op::I64_REM_S] {
+            let mut code = Code::new();
+            code.local_get(1);
+            code.op(op::I64_EQZ);
+            code.block(op::IF, EMPTY_BLOCK);
+            code.i32_const(divide_by_zero);
+            code.call(index::RUNTIME_PANIC);
+            code.op(op::END);
+            code.local_get(1);
+            code.i64_const(-1);
+            code.op(op::I64_EQ);
+            code.block(op::IF, EMPTY_BLOCK);
+            match operation {
+                op::I64_DIV_S => {
+                    code.i64_const(0);
+                    code.local_get(0);
+                    code.op(op::I64_SUB);
+                }
+                _ => code.i64_const(0),
+            }
+            code.op(op::RETURN);
+            code.op(op::END);
+            code.local_get(0);
+            code.local_get(1);
+            code.op(operation);
+            self.add_function(&[I64, I64], &[I64], &[], code);
+        }
+
+        // shift_left(value, count) and shift_right(value, count), where a count past the
+        // width shifts every bit out rather than wrapping around
+        for operation in [op::I64_SHL, op::I64_SHR_S] {
+            let mut code = Code::new();
+            code.local_get(1);
+            code.i64_const(0);
+            code.op(op::I64_LT_S);
+            code.block(op::IF, EMPTY_BLOCK);
+            code.i32_const(negative_shift);
+            code.call(index::RUNTIME_PANIC);
+            code.op(op::END);
+            code.local_get(0);
+            code.local_get(1);
+            code.i64_const(63);
+            code.local_get(1);
+            code.i64_const(63);
+            code.op(op::I64_LT_S);
+            code.op(op::SELECT);
+            code.op(operation);
+            if operation == op::I64_SHL {
+                // Shifting by 63 only leaves the lowest bit, so a count of 64 or more zeroes it
+                code.i64_const(0);
+                code.local_get(1);
+                code.i64_const(64);
+                code.op(op::I64_LT_S);
+                code.op(op::SELECT);
+            }
+            self.add_function(&[I64, I64], &[I64], &[], code);
+        }
+    }
+
+    /// Compile a function. Its blocks run in a loop around a `br_table` on the block to run
+    /// next, since WebAssembly's control flow is structured, and each block's parameters and
+    /// every value are locals, the function's parameters first.
+    fn function(&mut self, function: &Function) -> Result<(), CompileError> {
+        let mut locals = HashMap::new();
+        let mut local_types = Vec::new();
+        let entry = &function.blocks[0];
+        let defined = function.blocks.iter().flat_map(|block| {
+            let results = block.instructions.iter().flat_map(|i| &i.results);
+            block.parameters.iter().chain(results)
+        });
+        for value in entry.parameters.iter().chain(defined) {
+            if !locals.contains_key(value) {
+                locals.insert(*value, locals.len() as u32);
+                local_types.push(value_type(function.type_of(*value)));
+            }
+        }
+        let label = locals.len() as u32;
+        local_types.push(I32);
+        let parameters: Vec<u8> = function.parameters.iter().map(|t| value_type(*t)).collect();
+        let results: Vec<u8> = function.results.iter().map(|t| value_type(*t)).collect();
+
+        let mut code = Code::new();
+        let count = function.blocks.len() as u32;
+        code.block(op::LOOP, EMPTY_BLOCK);
+        for _ in 0..count {
+            code.block(op::BLOCK, EMPTY_BLOCK);
+        }
+        code.local_get(label);
+        code.op(op::BR_TABLE);
+        unsigned(&mut code.bytes, count as u64 - 1);
+        for depth in 0..count {
+            unsigned(&mut code.bytes, depth as u64);
+        }
+        for (index, block) in function.blocks.iter().enumerate() {
+            code.op(op::END);
+            let mut body = Body {
+                generator: self,
+                function,
+                locals: &locals,
+                label,
+                code: &mut code,
+                // How many blocks enclose this one's code inside the loop
+                depth: count - 1 - index as u32,
+            };
+            body.block(block)?;
+        }
+        code.op(op::END);
+        code.op(op::UNREACHABLE);
+        let type_index = self.function_type(&parameters, &results);
+        let locals = &local_types[parameters.len()..];
+        self.functions.push((type_index, code.finish(locals)));
+        Ok(())
+    }
+}
+
+/// Where one of a function's blocks is compiled
+struct Body<'g, 'a> {
+    generator: &'g mut Generator<'a>,
+    function: &'g Function,
+    locals: &'g HashMap<Value, u32>,
+    label: u32,
+    code: &'g mut Code,
+    depth: u32,
+}
+
+impl Body<'_, '_> {
+    fn get(&mut self, value: Value) {
+        self.code.local_get(self.locals[&value]);
+    }
+
+    fn set(&mut self, value: Value) {
+        self.code.local_set(self.locals[&value]);
+    }
+
+    fn block(&mut self, block: &BasicBlock) -> Result<(), CompileError> {
+        for instruction in &block.instructions {
+            self.instruction(&instruction.kind, instruction.position)?;
+            for result in instruction.results.iter().rev() {
+                self.set(*result);
+            }
+        }
+        match &block.terminator {
+            Terminator::Jump(target) => self.jump(target, 0),
+            Terminator::Branch {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.get(*condition);
+                self.code.block(op::IF, EMPTY_BLOCK);
+                self.jump(then, 1);
+                self.code.op(op::ELSE);
+                self.jump(otherwise, 1);
+                self.code.op(op::END);
+            }
+            Terminator::Return(values) => {
+                for value in values {
+                    self.get(*value);
+                }
+                self.code.op(op::RETURN);
+            }
+            Terminator::Panic { value, position } => {
+                let prefix = self.generator.string("panic: ");
+                self.code.i32_const(2);
+                self.code.i32_const(prefix);
+                self.code.call(index::PRINT_STRING);
+                self.code.i32_const(2);
+                self.print(*value, *position)?;
+                self.code.call(index::FINISH_PANIC);
+                self.code.op(op::UNREACHABLE);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pass the target its arguments and branch back to the loop to run it, from `nested`
+    /// blocks further in
+    fn jump(&mut self, target: &Target, nested: u32) {
+        for argument in &target.arguments {
+            self.get(*argument);
+        }
+        let parameters = &self.function.block(target.block).parameters;
+        for parameter in parameters.iter().rev() {
+            self.set(*parameter);
+        }
+        self.code.i32_const(target.block.0 as i32);
+        self.code.local_set(self.label);
+        self.code.branch(op::BR, self.depth + nested);
+    }
+
+    /// Leave the instruction's results on the stack
+    fn instruction(
+        &mut self,
+        instruction: &InstructionKind,
+        position: Position,
+    ) -> Result<(), CompileError> {
+        match instruction {
+            InstructionKind::Const(constant) => match constant {
+                Constant::Int(value) => self.code.i64_const(*value),
+                Constant::Float(value) => self.code.f64_const(*value),
+                Constant::Bool(value) => self.code.i32_const(*value as i32),
+                Constant::String(value) => {
+                    let address = self.generator.string(value);
+                    self.code.i32_const(address);
+                }
+            },
+            InstructionKind::Copy(value) => self.get(*value),
+            InstructionKind::Binary(operation, left, right) => {
+                self.get(*left);
+                self.get(*right);
+                self.binary(*operation, self.function.type_of(*left), position)?;
+            }
+            InstructionKind::Unary(operation, value) => {
+                match (operation, self.function.type_of(*value)) {
+                    (UnaryOp::Negate, Type::Int) => {
+                        self.code.i64_const(0);
+                        self.get(*value);
+                        self.code.op(op::I64_SUB);
+                    }
+                    (UnaryOp::Negate, Type::Float) => {
+                        self.get(*value);
+                        self.code.op(op::F64_NEG);
+                    }
+                    (UnaryOp::Not, Type::Bool) => {
+                        self.get(*value);
+                        self.code.op(op::I32_EQZ);
+                    }
+                    (UnaryOp::Complement, Type::Int) => {
+                        self.get(*value);
+                        self.code.i64_const(-1);
+                        self.code.op(op::I64_XOR);
+                    }
+                    (operation, value_type) => {
+                        let code = format!("{} on {}", operation.name(), value_type);
+                        return Err(unsupported(&code, position));
+                    }
+                }
+            }
+            InstructionKind::Convert(value) => {
+                self.get(*value);
+                match self.function.type_of(*value) {
+                    Type::Int => self.code.op(op::F64_CONVERT_I64_S),
+                    _ => {
+                        self.code.op(op::PREFIX);
+                        self.code.op(op::I64_TRUNC_SAT_F64_S);
+                    }
+                }
+            }
+            InstructionKind::Len(value) => {
+                self.get(*value);
+                self.code.memory(op::I32_LOAD, 2, 0);
+                self.code.op(op::I64_EXTEND_I32_U);
+            }
+            InstructionKind::Call {
+                function,
+                arguments,
+            } => {
+                for argument in arguments {
+                    self.get(*argument);
+                }
+                self.code.call(index::FIRST_FUNCTION + *function as u32);
+            }
+            InstructionKind::CallNative {
+                path,
+                function,
+                arguments,
+            } => match (path.as_str(), function.as_str()) {
+                ("fmt", "Println" | "Print") => {
+                    let line = function == "Println";
+                    let space = self.generator.string(" ");
+                    let function = self.function;
+                    let is_string = |value: &Value| function.type_of(*value) == Type::String;
+                    for (index, argument) in arguments.iter().enumerate() {
+                        // Print only spaces operands when neither is a string
+                        let spaced = match index {
+                            0 => false,
+                            _ => line || !(is_string(argument) || is_string(&arguments[index - 1])),
+                        };
+                        if spaced {
+                            self.code.i32_const(1);
+                            self.code.i32_const(space);
+                            self.code.call(index::PRINT_STRING);
+                        }
+                        self.code.i32_const(1);
+                        self.print(*argument, position)?;
+                    }
+                    if line {
+                        let newline = self.generator.string("\n");
+                        self.code.i32_const(1);
+                        self.code.i32_const(newline);
+                        self.code.call(index::PRINT_STRING);
+                    }
+                }
+                _ => return Err(unsupported(&format!("{}.{}", path, function), position)),
+            },
+            InstructionKind::LoadGlobal(global) => self.code.global_get(*global as u32 + 1),
+            InstructionKind::StoreGlobal(global, value) => {
+                self.get(*value);
+                self.code.global_set(*global as u32 + 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Print a value to the file descriptor on the stack
+    fn print(&mut self, value: Value, position: Position) -> Result<(), CompileError> {
+        self.get(value);
+        let function = match self.function.type_of(value) {
+            Type::Int => index::PRINT_INT,
+            Type::Bool => index::PRINT_BOOL,
+            Type::String => index::PRINT_STRING,
+            Type::Float => return Err(unsupported("printing floats", position)),
+        };
+        self.code.call(function);
+        Ok(())
+    }
+
+    /// Apply the operation to the two operands of `operand_type` on the stack
+    fn binary(
+        &mut self,
+        operation: BinaryOp,
+        operand_type: Type,
+        position: Position,
+    ) -> Result<(), CompileError> {
+        use BinaryOp::*;
+        match (operand_type, operation) {
+            (Type::Int, Divide) => self.code.call(index::DIVIDE),
+            (Type::Int, Remainder) => self.code.call(index::REMAINDER),
+            (Type::Int, ShiftLeft) => self.code.call(index::SHIFT_LEFT),
+            (Type::Int, ShiftRight) => self.code.call(index::SHIFT_RIGHT),
+            (Type::Int, AndNot) => {
+                self.code.i64_const(-1);
+                self.code.op(op::I64_XOR);
+                self.code.op(op::I64_AND);
+            }
+            (Type::String, Add) => self.code.call(index::CONCATENATE),
+            (Type::String, comparison) if comparison.is_comparison() => {
+                self.code.call(index::COMPARE);
+                self.code.i32_const(0);
+                self.code.op(match comparison {
+                    Equal => op::I32_EQ,
+                    NotEqual => op::I32_NE,
+                    Less => op::I32_LT_S,
+                    LessEqual => op::I32_LE_S,
+                    Greater => op::I32_GT_S,
+                    _ => op::I32_GE_S,
+                });
+            }
+            (operand_type, operation) => {
+                let instruction = match (operand_type, operation) {
+                    (Type::Int, Add) => op::I64_ADD,
+                    (Type::Int, Subtract) => op::I64_SUB,
+                    (Type::Int, Multiply) => op::I64_MUL,
+                    (Type::Int, And) => op::I64_AND,
+                    (Type::Int, Or) => op::I64_OR,
+                    (Type::Int, Xor) => op::I64_XOR,
+                    (Type::Int, Equal) => op::I64_EQ,
+                    (Type::Int, NotEqual) => op::I64_NE,
+                    (Type::Int, Less) => op::I64_LT_S,
+                    (Type::Int, LessEqual) => op::I64_LE_S,
+                    (Type::Int, Greater) => op::I64_GT_S,
+                    (Type::Int, GreaterEqual) => op::I64_GE_S,
+                    (Type::Float, Add) => op::F64_ADD,
+                    (Type::Float, Subtract) => op::F64_SUB,
+                    (Type::Float, Multiply) => op::F64_MUL,
+                    (Type::Float, Divide) => op::F64_DIV,
+                    (Type::Float, Equal) => op::F64_EQ,
+                    (Type::Float, NotEqual) => op::F64_NE,
+                    (Type::Float, Less) => op::F64_LT,
+                    (Type::Float, LessEqual) => op::F64_LE,
+                    (Type::Float, Greater) => op::F64_GT,
+                    (Type::Float, GreaterEqual) => op::F64_GE,
+                    (Type::Bool, Equal) => op::I32_EQ,
+                    (Type::Bool, NotEqual) => op::I32_NE,
+                    _ => {
+                        let code = format!("{} on {}", operation.name(), operand_type);
+                        return Err(unsupported(&code, position));
+                    }
+                };
+                self.code.op(instruction);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A function body's instructions as they're encoded
+struct Code {
+    bytes: Vec<u8>,
+}
+
+impl Code {
+    fn new() -> Code {
+        Code { bytes: Vec::new() }
+    }
+
+    fn op(&mut self, opcode: u8) {
+        self.bytes.push(opcode);
+    }
+
+    fn index(&mut self, opcode: u8, index: u32) {
+        self.bytes.push(opcode);
+        unsigned(&mut self.bytes, index as u64);
+    }
+
+    fn local_get(&mut self, local: u32) {
+        self.index(op::LOCAL_GET, local);
+    }
+
+    fn local_set(&mut self, local: u32) {
+        self.index(op::LOCAL_SET, local);
+    }
+
+    fn local_tee(&mut self, local: u32) {
+        self.index(op::LOCAL_TEE, local);
+    }
+
+    fn global_get(&mut self, global: u32) {
+        self.index(op::GLOBAL_GET, global);
+    }
+
+    fn global_set(&mut self, global: u32) {
+        self.index(op::GLOBAL_SET, global);
+    }
+
+    fn call(&mut self, function: u32) {
+        self.index(op::CALL, function);
+    }
+
+    fn branch(&mut self, opcode: u8, depth: u32) {
+        self.index(opcode, depth);
+    }
+
+    /// Start a block, loop or if, with its block type
+    fn block(&mut self, opcode: u8, block_type: u8) {
+        self.bytes.extend([opcode, block_type]);
+    }
+
+    fn i32_const(&mut self, value: i32) {
+        self.bytes.push(op::I32_CONST);
+        signed(&mut self.bytes, value as i64);
+    }
+
+    fn i64_const(&mut self, value: i64) {
+        self.bytes.push(op::I64_CONST);
+        signed(&mut self.bytes, value);
+    }
+
+    fn f64_const(&mut self, value: f64) {
+        self.bytes.push(op::F64_CONST);
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    /// A load or store, with the log2 of its alignment and its offset
+    fn memory(&mut self, opcode: u8, align: u32, offset: u32) {
+        self.bytes.push(opcode);
+        unsigned(&mut self.bytes, align as u64);
+        unsigned(&mut self.bytes, offset as u64);
+    }
+
+    /// The memory's size in bytes
+    fn memory_size(&mut self) {
+        self.bytes.extend([op::MEMORY_SIZE, 0x00]);
+        self.i32_const(16);
+        self.op(op::I32_SHL);
+    }
+
+    fn memory_copy(&mut self) {
+        self.bytes.extend([op::PREFIX, op::MEMORY_COPY, 0x00, 0x00]);
+    }
+
+    /// 1, 0 or -1 as the i32 local `left` is greater, equal or less than `right`, unsigned
+    fn sign(&mut self, left: u32, right: u32) {
+        self.local_get(left);
+        self.local_get(right);
+        self.op(op::I32_GT_U);
+        self.local_get(left);
+        self.local_get(right);
+        self.op(op::I32_LT_U);
+        self.op(op::I32_SUB);
+    }
+
+    /// The encoded body, declaring the locals besides the parameters, of the types given
+    fn finish(mut self, locals: &[u8]) -> Vec<u8> {
+        let mut runs: Vec<(u32, u8)> = Vec::new();
+        for local in locals {
+            match runs.last_mut() {
+                Some((count, last)) if last == local => *count += 1,
+                _ => runs.push((1, *local)),
+            }
+        }
+        let mut body = Vec::new();
+        unsigned(&mut body, runs.len() as u64);
+        for (count, local) in runs {
+            unsigned(&mut body, count as u64);
+            body.push(local);
+        }
+        body.append(&mut self.bytes);
+        body.push(op::END);
+        body
+    }
+}
+
+fn value_type(value_type: Type) -> u8 {
+    match value_type {
+        Type::Int => I64,
+        Type::Float => F64,
+        Type::Bool | Type::String => I32,
+    }
+}
+
+/// Append an unsigned LEB128 number
+fn unsigned(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Append a signed LEB128 number
+fn signed(bytes: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Append a vector of bytes, its length first
+fn bytes(out: &mut Vec<u8>, contents: &[u8]) {
+    unsigned(out, contents.len() as u64);
+    out.extend(contents);
+}
+
+fn string(out: &mut Vec<u8>, value: &str) {
+    bytes(out, value.as_bytes());
+}
+
+fn section(out: &mut Vec<u8>, id: u8, contents: Vec<u8>) {
+    out.push(id);
+    bytes(out, &contents);
+}
+
+fn unsupported(code: &str, position: Position) -> CompileError {
+    CompileError::new(CompileErrorKind::Unsupported(code.to_string()), position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile::lower::lower;
+    use crate::parser::parser::Parser;
+
+    fn generate_str(source: &str) -> Result<Vec<u8>, CompileError> {
+        generate(&lower(&Parser::new(source).parse().unwrap())?)
+    }
+
+    #[test]
+    fn leb128() {
+        let encode = |encoder: fn(&mut Vec<u8>, i64), value: i64| {
+            let mut bytes = Vec::new();
+            encoder(&mut bytes, value);
+            bytes
+        };
+        let unsigned = |bytes: &mut Vec<u8>, value: i64| unsigned(bytes, value as u64);
+        assert_eq!(encode(unsigned, 0), [0x00]);
+        assert_eq!(encode(unsigned, 127), [0x7F]);
+        assert_eq!(encode(unsigned, 624485), [0xE5, 0x8E, 0x26]);
+        assert_eq!(encode(signed, -1), [0x7F]);
+        assert_eq!(encode(signed, 63), [0x3F]);
+        assert_eq!(encode(signed, 64), [0xC0, 0x00]);
+        assert_eq!(encode(signed, -123456), [0xC0, 0xBB, 0x78]);
+        assert_eq!(
+            encode(signed, i64::MIN),
+            [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7F]
+        );
+    }
+
+    #[test]
+    fn generate_module() {
+        let source = "package main\n\nimport \"fmt\"\n\nvar name = \"gor\"\n\nfunc greet(greeting string) string {\n\treturn greeting + \", \" + name\n}\n\nfunc main() {\n\tfmt.Println(greet(\"hello\"), len(name)*2)\n}\n";
+        let binary = generate_str(source).unwrap();
+        assert_eq!(binary[..8], *b"\0asm\x01\0\0\0");
+        // Each section once, in the order the format requires
+        let mut sections = Vec::new();
+        let mut rest = &binary[8..];
+        while let [id, contents @ ..] = rest {
+            let (mut length, mut read) = (0, 0);
+            while contents[read] & 0x80 != 0 {
+                length |= ((contents[read] & 0x7F) as usize) << (7 * read);
+                read += 1;
+            }
+            length |= (contents[read] as usize) << (7 * read);
+            sections.push(*id);
+            rest = &contents[read + 1 + length..];
+        }
+        assert_eq!(sections, [1, 2, 3, 5, 6, 7, 10, 11]);
+        let contains = |text: &[u8]| binary.windows(text.len()).any(|window| window == text);
+        assert!(contains(b"\x03\0\0\0gor"));
+        assert!(contains(b"\x05\0\0\0hello"));
+        assert!(contains(b"\x16wasi_snapshot_preview1\x08fd_write"));
+        assert!(contains(b"\x06_start"));
+    }
+
+    #[test]
+    fn generate_errors() {
+        let error = |source: &str| generate_str(source).unwrap_err().kind;
+        assert_eq!(
+            error("package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(1.5)\n}\n"),
+            CompileErrorKind::Unsupported("printing floats".to_string())
+        );
+        assert_eq!(
+            error("package main\n\nimport \"os\"\n\nfunc main() {\n\tos.Exit(3)\n}\n"),
+            CompileErrorKind::Unsupported("os.Exit".to_string())
+        );
+    }
+}
@@ -0,0 +1,465 @@
+use std::rc::Rc;
+
+use crate::ast::{
+    expression::{Expression, ExpressionKind},
+    type_expression::{TypeExpression, TypeExpressionKind},
+};
+use crate::eval::{
+    config::ELEMENT_SIZE,
+    evaluator::Evaluator,
+    value::{Channel, Map, Named, Pointer, Slice, Value},
+};
+use crate::primitives::errors::runtime::{RuntimeError, RuntimeErrorKind};
+
+/// The builtin functions the evaluator implements (https://go.dev/ref/spec#Built-in_functions)
+pub const BUILTINS: &[&str] = &[
+    "append", "cap", "close", "copy", "delete", "len", "make", "new", "panic", "recover",
+];
+
+impl Evaluator {
+    /// Call the builtin `name`. Unlike a function's, the first argument of make and new is a
+    /// type.
+    pub(crate) fn call_builtin(
+        &mut self,
+        name: &str,
+        arguments: &[Expression],
+        spread: bool,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let argument_count = |want: usize| {
+            RuntimeError::new(
+                RuntimeErrorKind::ArgumentCount {
+                    function: name.to_string(),
+                    have: arguments.len(),
+                    want,
+                },
+                call.position_start,
+            )
+        };
+        match (name, arguments) {
+            ("make", [type_argument, sizes @ ..]) => {
+                let type_expression = self.type_argument(type_argument)?;
+                let sizes = sizes
+                    .iter()
+                    .map(|size| self.eval_size(size))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let made = self.make(&type_expression, &sizes, call)?;
+                return Ok(vec![self.assigned_value(made, &type_expression)]);
+            }
+            ("new", [type_argument]) => {
+                let type_expression = self.type_argument(type_argument)?;
+                let zero = self.zero_value(&type_expression);
+                return Ok(vec![Value::Pointer(Pointer::new(
+                    Rc::new(type_expression),
+                    zero,
+                ))]);
+            }
+            ("make" | "new", _) => return Err(argument_count(1)),
+            _ => {}
+        }
+
+        let values: Vec<Value> = arguments
+            .iter()
+            .map(|argument| self.eval_expression(argument))
+            .collect::<Result<_, _>>()?;
+        self.apply_builtin(name, arguments, values, spread, call)
+    }
+
+    /// Call the builtin `name` with its arguments' `values`, like a deferred call does once
+    /// they've been evaluated. make and new, which take a type, can't be called this way.
+    pub(crate) fn apply_builtin(
+        &mut self,
+        name: &str,
+        arguments: &[Expression],
+        values: Vec<Value>,
+        spread: bool,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let argument_count = |want: usize| {
+            RuntimeError::new(
+                RuntimeErrorKind::ArgumentCount {
+                    function: name.to_string(),
+                    have: arguments.len(),
+                    want,
+                },
+                call.position_start,
+            )
+        };
+        // The builtins act on a value of a named type as its underlying type's, and append
+        // gives a slice of the named type back
+        let named = match (name, values.first()) {
+            ("append", Some(Value::Named(named))) => Some(named.name.clone()),
+            _ => None,
+        };
+        let values: Vec<Value> = match name {
+            "panic" => values,
+            _ => values.into_iter().map(Value::into_underlying).collect(),
+        };
+        let invalid = |index: usize| invalid_argument(&arguments[index], &values[index], name);
+        let result = match (name, values.as_slice()) {
+            ("len", [value]) => Value::Int(length(value).ok_or_else(|| invalid(0))? as i64),
+            ("len", _) => return Err(argument_count(1)),
+            ("cap", [value]) => Value::Int(capacity(value).ok_or_else(|| invalid(0))? as i64),
+            ("cap", _) => return Err(argument_count(1)),
+            ("append", [Value::Slice(slice), elements @ ..]) => {
+                let elements = match (spread, elements) {
+                    (false, elements) => elements.to_vec(),
+                    (true, [Value::Slice(other)]) => other.elements(),
+                    // append(bytes, s...) appends the string's bytes
+                    (true, [Value::String(string)]) => string
+                        .bytes()
+                        .map(|byte| Value::Int(i64::from(byte)))
+                        .collect(),
+                    (true, [_]) => return Err(invalid(1)),
+                    (true, _) => return Err(argument_count(2)),
+                };
+                let elements = elements
+                    .into_iter()
+                    .map(|element| self.assigned_value(element, &slice.element))
+                    .collect();
+                let appended = append(slice, elements);
+                if appended.cap != slice.cap {
+                    self.allocate(appended.cap * ELEMENT_SIZE, call.position_start)?;
+                }
+                Value::Slice(appended)
+            }
+            ("append", [_, ..]) => return Err(invalid(0)),
+            ("append", []) => return Err(argument_count(1)),
+            ("copy", [Value::Slice(destination), source]) => {
+                let source = match source {
+                    Value::Slice(source) => source.elements(),
+                    // copy(bytes, s) copies the string's bytes
+                    Value::String(string) => string
+                        .bytes()
+                        .map(|byte| Value::Int(i64::from(byte)))
+                        .collect(),
+                    _ => return Err(invalid(1)),
+                };
+                Value::Int(copy(destination, source) as i64)
+            }
+            ("copy", [_, _]) => return Err(invalid(0)),
+            ("copy", _) => return Err(argument_count(2)),
+            ("delete", [Value::Map(map), key]) => {
+                let Some(key) = key.key() else {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::UnhashableKey(key.type_name()),
+                        arguments[1].position_start,
+                    ));
+                };
+                // Deleting from a nil map does nothing
+                if let Some(entries) = &map.entries {
+                    entries.borrow_mut().remove(&key);
+                }
+                return Ok(Vec::new());
+            }
+            ("close", [Value::Channel(channel)]) => {
+                self.close(channel, call.position_start)?;
+                return Ok(Vec::new());
+            }
+            ("close", [other]) => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::NonChannel {
+                        operation: "close".to_string(),
+                        value: arguments[0].to_string(),
+                        value_type: other.type_name(),
+                    },
+                    arguments[0].position_start,
+                ));
+            }
+            ("close", _) => return Err(argument_count(1)),
+            ("delete", [_, _]) => return Err(invalid(0)),
+            ("delete", _) => return Err(argument_count(2)),
+            ("panic", [value]) => return Err(self.panic(value.clone(), call)),
+            ("panic", _) => return Err(argument_count(1)),
+            ("recover", []) => self.recover(),
+            ("recover", _) => return Err(argument_count(0)),
+            _ => unreachable!("{} isn't a builtin", name),
+        };
+        Ok(vec![match named {
+            Some(name) => Value::Named(Named::new(name, result)),
+            None => result,
+        }])
+    }
+
+    /// The type a builtin's type argument names, like the `[]int` of `make([]int, 3)`
+    fn type_argument(&self, argument: &Expression) -> Result<TypeExpression, RuntimeError> {
+        match &argument.kind {
+            ExpressionKind::Type(type_expression) => Ok(type_expression.clone()),
+            ExpressionKind::Identifier(name)
+                if self.lookup(name).is_none() && self.is_type_name(name) =>
+            {
+                Ok(TypeExpression::new_name(
+                    name.clone(),
+                    argument.position_start,
+                ))
+            }
+            ExpressionKind::Parenthesized(inner) => self.type_argument(inner),
+            // An imported package's type, like sync.WaitGroup
+            ExpressionKind::FieldAccess { object, field, .. } => match &object.kind {
+                ExpressionKind::Identifier(package)
+                    if self.lookup(package).is_none() && self.is_package(package) =>
+                {
+                    Ok(TypeExpression {
+                        kind: TypeExpressionKind::Qualified {
+                            package: package.clone(),
+                            name: field.clone(),
+                        },
+                        position_start: argument.position_start,
+                        position_end: argument.position_end,
+                    })
+                }
+                _ => Err(RuntimeError::new(
+                    RuntimeErrorKind::NotAType(argument.to_string()),
+                    argument.position_start,
+                )),
+            },
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::NotAType(argument.to_string()),
+                argument.position_start,
+            )),
+        }
+    }
+
+    /// A length or capacity passed to make, which may be negative until it's checked
+    fn eval_size(&mut self, size: &Expression) -> Result<i64, RuntimeError> {
+        match self.eval_expression(size)?.into_underlying() {
+            Value::Int(size) => Ok(size),
+            // A constant like 2.0 is an integer
+            Value::Float(float) if float.fract() == 0.0 => Ok(float as i64),
+            value => Err(invalid_argument(size, &value, "make")),
+        }
+    }
+
+    /// A new slice, map or channel (https://go.dev/ref/spec#Making_slices_maps_and_channels)
+    fn make(
+        &self,
+        type_expression: &TypeExpression,
+        sizes: &[i64],
+        call: &Expression,
+    ) -> Result<Value, RuntimeError> {
+        let out_of_range = |function: &str, size: &str| {
+            Err(RuntimeError::new(
+                RuntimeErrorKind::SizeOutOfRange {
+                    function: function.to_string(),
+                    size: size.to_string(),
+                },
+                call.position_start,
+            ))
+        };
+        let argument_count = |want: usize| {
+            Err(RuntimeError::new(
+                RuntimeErrorKind::ArgumentCount {
+                    function: "make".to_string(),
+                    have: sizes.len() + 1,
+                    want,
+                },
+                call.position_start,
+            ))
+        };
+        match &self.underlying(type_expression).kind {
+            TypeExpressionKind::Slice(element) => {
+                let (len, cap) = match *sizes {
+                    [len] => (len, len),
+                    [len, cap] => (len, cap),
+                    [] => return argument_count(2),
+                    _ => return argument_count(3),
+                };
+                if len < 0 {
+                    return out_of_range("makeslice", "len");
+                }
+                if cap < len {
+                    return out_of_range("makeslice", "cap");
+                }
+                self.allocate(
+                    (cap as usize).saturating_mul(ELEMENT_SIZE),
+                    call.position_start,
+                )?;
+                // Each element is a zero value of its own, since a sync value's copies share it
+                let elements = std::iter::repeat_with(|| self.zero_value(element))
+                    .take(len as usize)
+                    .collect();
+                Ok(Value::Slice(Slice::new(
+                    Rc::new((**element).clone()),
+                    elements,
+                    cap as usize,
+                )))
+            }
+            TypeExpressionKind::Map { key, value } => {
+                let hint = match *sizes {
+                    [] => 0,
+                    [hint] => hint,
+                    _ => return argument_count(2),
+                };
+                if hint < 0 {
+                    return out_of_range("makemap", "size");
+                }
+                self.allocate(
+                    (hint as usize).saturating_mul(2 * ELEMENT_SIZE),
+                    call.position_start,
+                )?;
+                Ok(Value::Map(Map::new(
+                    Rc::new((**key).clone()),
+                    Rc::new((**value).clone()),
+                    hint as usize,
+                )))
+            }
+            TypeExpressionKind::Channel { element, .. } => {
+                let size = match *sizes {
+                    [] => 0,
+                    [size] => size,
+                    _ => return argument_count(2),
+                };
+                if size < 0 {
+                    return out_of_range("makechan", "size");
+                }
+                self.allocate(
+                    (size as usize).saturating_mul(ELEMENT_SIZE),
+                    call.position_start,
+                )?;
+                Ok(Value::Channel(Channel::new(
+                    Rc::new((**element).clone()),
+                    size as usize,
+                )))
+            }
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::CannotMake(type_expression.to_string()),
+                call.position_start,
+            )),
+        }
+    }
+}
+
+/// The length of a string in bytes, or of a slice, map or channel's buffer
+pub fn length(value: &Value) -> Option<usize> {
+    Some(match value {
+        Value::String(string) => string.len(),
+        Value::Slice(slice) => slice.len,
+        Value::Map(map) => map.len(),
+        // Past the capacity are values waiting for a receiver, which aren't buffered yet
+        Value::Channel(channel) => channel.state.as_ref().map_or(0, |state| {
+            let state = state.borrow();
+            state.buffer.len().min(state.capacity)
+        }),
+        _ => return None,
+    })
+}
+
+/// The capacity of a slice or a channel's buffer
+pub fn capacity(value: &Value) -> Option<usize> {
+    Some(match value {
+        Value::Slice(slice) => slice.cap,
+        Value::Channel(channel) => channel
+            .state
+            .as_ref()
+            .map_or(0, |state| state.borrow().capacity),
+        _ => return None,
+    })
+}
+
+/// `slice` with `elements` after its own. They're stored in its backing array when it has
+/// room, where other slices of the array can see them, and otherwise in a new array.
+pub fn append(slice: &Slice, elements: Vec<Value>) -> Slice {
+    let len = slice.len + elements.len();
+    if let Some(backing) = &slice.backing
+        && len <= slice.cap
+    {
+        let mut array = backing.borrow_mut();
+        let start = slice.offset + slice.len;
+        for (index, element) in (start..).zip(elements) {
+            match array.get_mut(index) {
+                Some(stored) => *stored = element,
+                None => array.push(element),
+            }
+        }
+        return Slice {
+            len,
+            ..slice.clone()
+        };
+    }
+    let mut array = slice.elements();
+    array.extend(elements);
+    Slice::new(slice.element.clone(), array, grow(slice.cap, len))
+}
+
+/// The capacity of the array `append` moves a slice's elements to, as Go's runtime grows it:
+/// doubling while small, then by a quarter plus 192 elements. Go then rounds the array up to
+/// its allocator's size classes, which depend on the element size; that isn't modelled, so
+/// capacities can come out a little smaller than Go's.
+fn grow(cap: usize, needed: usize) -> usize {
+    const THRESHOLD: usize = 256;
+    if needed > cap * 2 {
+        return needed;
+    }
+    if cap < THRESHOLD {
+        return cap * 2;
+    }
+    let mut grown = cap;
+    while grown < needed {
+        grown += (grown + 3 * THRESHOLD) / 4;
+    }
+    grown
+}
+
+/// Copy as many of `source` into `destination` as both have, returning how many
+pub fn copy(destination: &Slice, source: Vec<Value>) -> usize {
+    let count = destination.len.min(source.len());
+    if let Some(backing) = &destination.backing {
+        let mut array = backing.borrow_mut();
+        let start = destination.offset;
+        array[start..start + count].clone_from_slice(&source[..count]);
+    }
+    count
+}
+
+fn invalid_argument(argument: &Expression, value: &Value, builtin: &str) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::InvalidArgument {
+            argument: argument.to_string(),
+            argument_type: value.type_name(),
+            builtin: builtin.to_string(),
+        },
+        argument.position_start,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::position::Position;
+
+    fn int_slice(elements: &[i64], cap: usize) -> Slice {
+        let element = Rc::new(TypeExpression::new_name(
+            "int".to_string(),
+            Position::new(1, 0, 0),
+        ));
+        let elements = elements
+            .iter()
+            .map(|element| Value::Int(*element))
+            .collect();
+        Slice::new(element, elements, cap)
+    }
+
+    #[test]
+    fn append_shares_or_reallocates() {
+        // With room to spare, both appends write the same element of the shared array
+        let base = int_slice(&[1], 4);
+        let first = append(&base, vec![Value::Int(2)]);
+        let second = append(&base, vec![Value::Int(3)]);
+        assert_eq!(Value::Slice(first.clone()).to_string(), "[1 3]");
+        assert_eq!(Value::Slice(second).to_string(), "[1 3]");
+        assert_eq!((first.len, first.cap), (2, 4));
+
+        // Without room, the appended slice gets an array of its own
+        let full = int_slice(&[1, 2], 2);
+        let grown = append(&full, vec![Value::Int(3)]);
+        assert_eq!((grown.len, grown.cap), (3, 4));
+        copy(&grown, vec![Value::Int(9)]);
+        assert_eq!(Value::Slice(full).to_string(), "[1 2]");
+        assert_eq!(Value::Slice(grown).to_string(), "[9 2 3]");
+
+        assert_eq!(grow(0, 1), 1);
+        assert_eq!(grow(4, 5), 8);
+        assert_eq!(grow(4, 9), 9);
+        assert_eq!(grow(512, 513), 512 + (512 + 768) / 4);
+    }
+}
@@ -0,0 +1,278 @@
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::primitives::{
+    errors::runtime::{RuntimeError, RuntimeErrorKind},
+    position::Position,
+};
+
+/// How many bytes each element of a slice, map or channel buffer is counted as taking, which
+/// is what a value takes in the evaluator rather than in Go
+pub const ELEMENT_SIZE: usize = std::mem::size_of::<crate::eval::value::Value>();
+
+/// Limits on how much a program may do, and the seed its random choices are made from, for
+/// embedding the interpreter to run programs that can't be trusted, like a playground's. The
+/// default has no limits but `MAX_CALL_DEPTH` and a random seed; `sandbox` suits a
+/// playground.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterpreterConfig {
+    max_steps: Option<u64>,
+    max_allocation: Option<usize>,
+    time_limit: Option<Duration>,
+    max_call_depth: Option<usize>,
+    max_stack: Option<usize>,
+    seed: Option<u64>,
+}
+
+impl InterpreterConfig {
+    /// A hundred million statements, 256 MiB, ten seconds, and calls a thousand deep taking
+    /// up to 512 KiB of stack, which a thread of the default size has room for, with map
+    /// iteration and select choices made the same way every run
+    pub fn sandbox() -> InterpreterConfig {
+        InterpreterConfig::default()
+            .with_max_steps(100_000_000)
+            .with_max_allocation(256 << 20)
+            .with_time_limit(Duration::from_secs(10))
+            .with_max_call_depth(1_000)
+            .with_max_stack(512 << 10)
+            .with_seed(0)
+    }
+
+    /// End the program once it has run `steps` statements and loop iterations, counted
+    /// across all of its goroutines
+    pub fn with_max_steps(mut self, steps: u64) -> InterpreterConfig {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    /// End the program once it has allocated `bytes`. Slices, maps, channels and strings are
+    /// counted as they're made or grown, each element as `ELEMENT_SIZE` bytes and a string
+    /// as its length, and nothing is counted as freed.
+    pub fn with_max_allocation(mut self, bytes: usize) -> InterpreterConfig {
+        self.max_allocation = Some(bytes);
+        self
+    }
+
+    /// End the program once it has run for `limit`, sleeping included
+    pub fn with_time_limit(mut self, limit: Duration) -> InterpreterConfig {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Report calls nested deeper than `depth` as a stack overflow, rather than
+    /// `MAX_CALL_DEPTH`
+    pub fn with_max_call_depth(mut self, depth: usize) -> InterpreterConfig {
+        self.max_call_depth = Some(depth);
+        self
+    }
+
+    /// Report a stack overflow once the program's calls take more than `bytes` of the stack
+    /// of the thread running them, beyond what was in use when they started. How many calls
+    /// fit depends on the build, so this keeps a program from overflowing the thread's stack
+    /// where a call depth can't.
+    pub fn with_max_stack(mut self, bytes: usize) -> InterpreterConfig {
+        self.max_stack = Some(bytes);
+        self
+    }
+
+    /// Seed the generator that shuffles map iteration order and picks between ready select
+    /// cases, so a program whose goroutines don't race on the clock prints the same every run
+    pub fn with_seed(mut self, seed: u64) -> InterpreterConfig {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn max_steps(&self) -> Option<u64> {
+        self.max_steps
+    }
+
+    pub fn max_allocation(&self) -> Option<usize> {
+        self.max_allocation
+    }
+
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    pub fn max_call_depth(&self) -> Option<usize> {
+        self.max_call_depth
+    }
+
+    pub fn max_stack(&self) -> Option<usize> {
+        self.max_stack
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+/// Which of a package directory's files are loaded. The default loads what `go build` would:
+/// every `.go` file but tests, and those the go command always ignores.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoaderConfig {
+    tests: bool,
+    /// Absolute paths of the files and directories left out
+    excluded: Vec<PathBuf>,
+}
+
+impl LoaderConfig {
+    /// Load a package's `_test.go` files with it, as `go vet` does. Tests of an external
+    /// `_test` package aren't part of the package, so they're still left out.
+    pub fn with_tests(mut self) -> LoaderConfig {
+        self.tests = true;
+        self
+    }
+
+    /// Leave out the file, or the directory and everything under it, when a directory's
+    /// files are listed or a pattern like `./...` is matched. A package named by its own
+    /// path, or imported, is still loaded.
+    pub fn with_excluded(mut self, path: &Path) -> LoaderConfig {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        self.excluded.push(path);
+        self
+    }
+
+    pub fn tests(&self) -> bool {
+        self.tests
+    }
+
+    /// Whether `path` is, or is under, a path left out
+    pub fn excluded(&self, path: &Path) -> bool {
+        let Ok(path) = std::path::absolute(path) else {
+            return false;
+        };
+        self.excluded
+            .iter()
+            .any(|excluded| path.starts_with(excluded))
+    }
+}
+
+/// What a program has used of its config's limits, shared by its goroutines, which only
+/// touch it while they hold the turn
+#[derive(Debug, Default)]
+pub(crate) struct Budget {
+    max_steps: Option<u64>,
+    max_allocation: Option<usize>,
+    time_limit: Option<Duration>,
+    steps: Cell<u64>,
+    allocated: Cell<usize>,
+    /// When the time limit runs out, from when the program started
+    deadline: Cell<Option<Instant>>,
+}
+
+impl Budget {
+    pub(crate) fn new(config: &InterpreterConfig) -> Budget {
+        Budget {
+            max_steps: config.max_steps,
+            max_allocation: config.max_allocation,
+            time_limit: config.time_limit,
+            ..Budget::default()
+        }
+    }
+
+    /// Start the clock on the time limit
+    pub(crate) fn start(&self) {
+        let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        self.deadline.set(deadline);
+    }
+
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.deadline.get()
+    }
+
+    /// Count a statement or loop iteration
+    pub(crate) fn step(&self, position: Position) -> Result<(), RuntimeError> {
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+        match self.max_steps {
+            Some(max_steps) if steps > max_steps => Err(RuntimeError::new(
+                RuntimeErrorKind::StepLimitExceeded(max_steps),
+                position,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if the time limit has run out
+    pub(crate) fn check_time(&self, position: Position) -> Result<(), RuntimeError> {
+        match (self.time_limit, self.deadline.get()) {
+            (Some(limit), Some(deadline)) if Instant::now() >= deadline => Err(RuntimeError::new(
+                RuntimeErrorKind::TimeLimitExceeded(limit),
+                position,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count `bytes` as allocated, failing rather than counting them if that would take the
+    /// program past its limit
+    pub(crate) fn allocate(&self, bytes: usize, position: Position) -> Result<(), RuntimeError> {
+        let allocated = self.allocated.get().saturating_add(bytes);
+        match self.max_allocation {
+            Some(max_allocation) if allocated > max_allocation => Err(RuntimeError::new(
+                RuntimeErrorKind::AllocationLimitExceeded(max_allocation),
+                position,
+            )),
+            _ => {
+                self.allocated.set(allocated);
+                Ok(())
+            }
+        }
+    }
+
+    /// Fail if allocating `bytes` would take the program past its limit, without counting
+    /// them, before making something too large to make at all
+    pub(crate) fn can_allocate(
+        &self,
+        bytes: usize,
+        position: Position,
+    ) -> Result<(), RuntimeError> {
+        match self.max_allocation {
+            Some(max_allocation) if self.allocated.get().saturating_add(bytes) > max_allocation => {
+                Err(RuntimeError::new(
+                    RuntimeErrorKind::AllocationLimitExceeded(max_allocation),
+                    position,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_limits() {
+        let position = Position::new(1, 0, 0);
+        let budget = Budget::new(
+            &InterpreterConfig::default()
+                .with_max_steps(2)
+                .with_max_allocation(10),
+        );
+        assert_eq!(budget.step(position), Ok(()));
+        assert_eq!(budget.step(position), Ok(()));
+        assert_eq!(
+            budget.step(position).unwrap_err().kind,
+            RuntimeErrorKind::StepLimitExceeded(2)
+        );
+        assert_eq!(budget.allocate(6, position), Ok(()));
+        assert!(budget.can_allocate(5, position).is_err());
+        // Failing to allocate doesn't count
+        assert!(budget.allocate(5, position).is_err());
+        assert_eq!(budget.allocate(4, position), Ok(()));
+        assert_eq!(budget.check_time(position), Ok(()));
+
+        let budget = Budget::new(&InterpreterConfig::default().with_time_limit(Duration::ZERO));
+        budget.start();
+        assert_eq!(
+            budget.check_time(position).unwrap_err().kind,
+            RuntimeErrorKind::TimeLimitExceeded(Duration::ZERO)
+        );
+    }
+}
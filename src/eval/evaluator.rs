@@ -0,0 +1,3845 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::io::{BufRead, Write};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::ast::{
+    ast::ImportSpec,
+    declaration::{Declaration, DeclarationKind, Parameter, VariableSpec, constant_specs},
+    expression::{Expression, ExpressionKind, KeyedElement, UnaryOperator},
+    statement::{SelectCase, Statement, StatementKind, SwitchCase},
+    type_expression::{TypeExpression, TypeExpressionKind},
+};
+use crate::eval::{
+    builtins,
+    config::{Budget, ELEMENT_SIZE, InterpreterConfig},
+    fmt,
+    observer::Observer,
+    scheduler::{Interruption, MAIN_GOROUTINE, Scheduler},
+    stdlib::{self, Arguments, HostFunction, bufio, time},
+    value::{
+        Channel, Closure, Error, Field, File, Input, Key, Map, Named, Native, Output, Pointer,
+        Slice, Struct, StructType, Target, Value,
+    },
+};
+use crate::lexer::{
+    literal::{unquote_rune, unquote_string},
+    token_type::Operator,
+};
+use crate::primitives::{
+    errors::runtime::{RuntimeError, RuntimeErrorKind, StackFrame},
+    position::Position,
+};
+use crate::types::constant::Constant;
+
+/// Calls deeper than this are reported as a stack overflow. Each call takes a few kilobytes
+/// of the host's own stack, so deep recursion needs a thread with a large one.
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+/// The stack a goroutine's thread reserves for each call it may make. It's only address space
+/// until the calls are made.
+const GOROUTINE_STACK_PER_CALL: usize = 32 << 10;
+
+/// How many statements a goroutine runs before letting the others ready to run have the turn,
+/// as Go preempts a goroutine that runs for too long
+const STATEMENTS_PER_TURN: usize = 1_000;
+
+/// Evaluates expressions and executes statements by walking the AST. Nothing is type
+/// checked first, so an ill-typed operation like `1 + "a"` is only an error once it's
+/// evaluated.
+#[derive(Debug)]
+pub struct Evaluator {
+    /// Package level variables, shared with every goroutine
+    globals: Rc<RefCell<Scope>>,
+    /// Variables of the function being run, innermost scope last
+    scopes: Vec<Scope>,
+    /// Package level functions, by name
+    functions: HashMap<String, Rc<Declaration>>,
+    /// Methods by name, by the name of their receiver's base type, like `Point` for both
+    /// `func (p Point)` and `func (p *Point)`
+    methods: HashMap<String, HashMap<String, Rc<Declaration>>>,
+    /// Declared types, by name, for zero values and conversions
+    types: HashMap<String, TypeExpression>,
+    /// Imported packages' paths, by the name they're referred to by
+    packages: HashMap<String, String>,
+    /// Functions the embedding application provides, by name, by their package's path
+    host_functions: HashMap<String, HashMap<String, HostFunction>>,
+    /// Where fmt.Println and friends write, shared with every goroutine
+    output: Rc<RefCell<Output>>,
+    /// What os.Stderr writes to
+    error_output: Rc<RefCell<Output>>,
+    /// What os.Stdin reads from
+    input: Rc<RefCell<Input>>,
+    /// os.Args: the program's name, then its arguments
+    args: Vec<String>,
+    /// Takes turns running the program's goroutines
+    scheduler: Arc<Scheduler>,
+    /// The id of the goroutine the evaluator runs
+    goroutine: usize,
+    /// Statements run since the goroutine was last given the turn
+    steps: usize,
+    /// Calls in progress
+    depth: usize,
+    /// The call the evaluator is running
+    frame: Frame,
+    max_depth: usize,
+    /// How many bytes of the host's stack the calls in progress may take
+    max_stack: Option<usize>,
+    /// Where the host's stack was when the outermost call in progress started
+    stack_base: usize,
+    /// The calls each function in progress has deferred, innermost function last
+    defers: Vec<Vec<PendingCall>>,
+    /// The value of the panic unwinding the stack, until a deferred call recovers it
+    panicking: Option<Value>,
+    /// The depth of the deferred call that can recover the panic, which is one the panicking
+    /// function deferred
+    recoverer: Option<usize>,
+    /// Whether range loops visit a map's keys in order rather than a random one
+    deterministic: bool,
+    /// State of the generator shuffling map iteration order
+    random: u64,
+    /// What the program has used of its limits, shared with every goroutine
+    budget: Rc<Budget>,
+    /// What's watching the program run, shared with every goroutine
+    observer: Option<Rc<RefCell<dyn Observer + Send>>>,
+}
+
+/// A scope's variables by name. Each is a cell of its own, which pointers to it share.
+type Scope = HashMap<String, Rc<RefCell<Value>>>;
+
+/// The function a call runs, for naming the closures it makes as Go does: `main.func1` for
+/// main's first function literal, and `main.func1.1` for the first one inside that
+#[derive(Debug, Default)]
+struct Frame {
+    /// The call's name in a panic's trace
+    name: String,
+    /// Whether the call is a closure's
+    closure: bool,
+    /// Where the function literals it has made closures of start, numbered in the order they
+    /// were first made
+    literals: Vec<Position>,
+}
+
+/// What a call calls
+#[derive(Debug)]
+enum Callee {
+    /// A package level function
+    Function(String),
+    /// A builtin, which takes its arguments unevaluated since make and new take types
+    Builtin(String),
+    /// A conversion to a named type
+    Conversion(TypeExpression),
+    /// A function of an imported package, by the package's path
+    Package {
+        path: String,
+        function: String,
+    },
+    Method {
+        receiver: Value,
+        method: String,
+    },
+    Closure(Rc<Closure>),
+}
+
+/// What an operand denotes: a place when it's addressable
+/// (https://go.dev/ref/spec#Address_operators), which a pointer to it shares, or else only a
+/// value
+#[derive(Debug)]
+enum Operand {
+    Place(Pointer),
+    Value(Value),
+}
+
+/// A call made after the statement naming it, by a defer statement when the function returns
+/// or a go statement in a new goroutine, with its callee and arguments evaluated when the
+/// statement ran
+#[derive(Debug)]
+struct PendingCall {
+    callee: Callee,
+    arguments: Vec<Value>,
+    call: Expression,
+}
+
+/// A new goroutine's evaluator and the call it makes, on their way to its thread
+struct Goroutine {
+    evaluator: Evaluator,
+    call: PendingCall,
+}
+
+// SAFETY: Everything an embedder hands the evaluator (output, input, observer and native
+// functions) is `Send`, so what isn't is the evaluator's own `Rc`s and `RefCell`s, which a
+// goroutine's values share with every other goroutine's. The scheduler runs one goroutine at a
+// time: the thread a goroutine is sent to only touches its values while holding the turn,
+// having waited for it, and drops them all before passing the turn on. The scheduler's mutex
+// orders those turns, so no two threads touch a shared count or cell at once, as with a
+// `Mutex` of a `Send` value.
+unsafe impl Send for Goroutine {}
+
+impl Goroutine {
+    /// Make the goroutine's call once it's given the turn as goroutine `id`, then hand the turn
+    /// on, reporting an error that ends the program
+    fn run(self, id: usize) {
+        let scheduler = self.evaluator.scheduler.clone();
+        let position = self.call.call.position_start;
+        let failure = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let Goroutine {
+                mut evaluator,
+                call,
+            } = self;
+            evaluator.goroutine = id;
+            match scheduler.wait_turn(id) {
+                // A goroutine that calls Goexit just ends
+                Ok(()) => evaluator
+                    .call_callee(call.callee, call.arguments, &call.call)
+                    .err()
+                    .filter(|error| error.kind != RuntimeErrorKind::Goexit),
+                // The program ended before the goroutine started
+                Err(_) => None,
+            }
+        }));
+        // A bug in the interpreter itself ends the program as a failure would, so main doesn't
+        // wait for the turn forever
+        let failure = failure.unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            Some(RuntimeError::new(
+                RuntimeErrorKind::Internal(message),
+                position,
+            ))
+        });
+        scheduler.finish(failure);
+    }
+}
+
+/// A select case's communication, with its channel and any value to send evaluated
+enum Communication {
+    Send { channel: Channel, value: Value },
+    Receive(Channel),
+}
+
+impl Communication {
+    /// Whether the communication can go ahead without waiting. Sending on a closed channel
+    /// can, to fail.
+    fn is_ready(&self) -> bool {
+        match self {
+            Communication::Send { channel, .. } => channel.state.as_ref().is_some_and(|state| {
+                let state = state.borrow();
+                state.closed || state.buffer.len() < state.capacity + state.receivers
+            }),
+            Communication::Receive(channel) => channel.state.as_ref().is_some_and(|state| {
+                let state = state.borrow();
+                state.closed || !state.buffer.is_empty()
+            }),
+        }
+    }
+}
+
+/// How a statement finished, for the loop or call around it to act on
+#[derive(Debug, PartialEq, Clone)]
+pub enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Vec<Value>),
+}
+
+impl Evaluator {
+    pub fn new() -> Evaluator {
+        Evaluator {
+            globals: Rc::new(RefCell::new(HashMap::new())),
+            scopes: Vec::new(),
+            functions: HashMap::new(),
+            methods: HashMap::new(),
+            types: HashMap::new(),
+            packages: HashMap::new(),
+            host_functions: HashMap::new(),
+            output: Rc::new(RefCell::new(Output(Box::new(std::io::stdout())))),
+            error_output: Rc::new(RefCell::new(Output(Box::new(std::io::stderr())))),
+            input: Rc::new(RefCell::new(Input(Box::new(std::io::BufReader::new(
+                std::io::stdin(),
+            ))))),
+            args: Vec::new(),
+            scheduler: Arc::new(Scheduler::new()),
+            goroutine: MAIN_GOROUTINE,
+            steps: 0,
+            depth: 0,
+            frame: Frame::default(),
+            max_depth: MAX_CALL_DEPTH,
+            max_stack: None,
+            stack_base: 0,
+            defers: Vec::new(),
+            panicking: None,
+            recoverer: None,
+            deterministic: false,
+            // Xorshift needs a nonzero seed
+            random: RandomState::new().hash_one(0u64) | 1,
+            budget: Rc::new(Budget::default()),
+            observer: None,
+        }
+    }
+
+    /// Limit the program as `config` says, and make its random choices from its seed
+    pub fn with_config(mut self, config: &InterpreterConfig) -> Evaluator {
+        self.budget = Rc::new(Budget::new(config));
+        if let Some(max_depth) = config.max_call_depth() {
+            self.max_depth = max_depth;
+        }
+        self.max_stack = config.max_stack();
+        if let Some(seed) = config.seed() {
+            self.random = scramble(seed) | 1;
+        }
+        self
+    }
+
+    /// Start the clock on the program's time limit, as it starts running
+    pub fn start(&self) {
+        self.budget.start();
+    }
+
+    /// Report calls nested deeper than `max_depth` as a stack overflow
+    pub fn with_max_depth(mut self, max_depth: usize) -> Evaluator {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Write the program's output to `output` rather than the process's standard output
+    pub fn with_output(mut self, output: impl Write + Send + 'static) -> Evaluator {
+        self.output = Rc::new(RefCell::new(Output(Box::new(output))));
+        self
+    }
+
+    /// Read the program's standard input from `input` rather than the process's
+    pub fn with_input(mut self, input: impl BufRead + Send + 'static) -> Evaluator {
+        self.input = Rc::new(RefCell::new(Input(Box::new(input))));
+        self
+    }
+
+    /// Give the program `args` as os.Args, which start with the program's name
+    pub fn with_args(mut self, args: Vec<String>) -> Evaluator {
+        self.args = args;
+        self
+    }
+
+    /// Range over maps in key order rather than a random one, so a program's output is
+    /// reproducible
+    pub fn deterministic(mut self) -> Evaluator {
+        self.deterministic = true;
+        self
+    }
+
+    /// Tell `observer` about each statement, call and value as the program runs
+    pub fn with_observer(mut self, observer: impl Observer + Send + 'static) -> Evaluator {
+        self.observer = Some(Rc::new(RefCell::new(observer)));
+        self
+    }
+
+    /// Make an imported package's functions, variables and types usable. Only fmt's,
+    /// errors', strings', strconv's, os's, bufio's and time's functions, os's standard streams
+    /// and arguments, time's units, sync's and time's types, and the testing.T tests are
+    /// called with exist so far.
+    pub fn import(&mut self, import: &ImportSpec) {
+        self.packages
+            .insert(import.local_name(), import.unquoted_path().to_string());
+    }
+
+    /// Provide `function` to programs as `name` of the package at `path`. It's called before
+    /// any native function of the same name, so it can add to a standard library package.
+    pub fn register_native(&mut self, path: &str, name: &str, function: HostFunction) {
+        self.host_functions
+            .entry(path.to_string())
+            .or_default()
+            .insert(name.to_string(), function);
+    }
+
+    /// Declare `name` in the innermost scope, which is the package's outside of a function
+    pub fn define(&mut self, name: &str, value: Value) {
+        if name == "_" {
+            return;
+        }
+        let variable = Rc::new(RefCell::new(value));
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.to_string(), variable),
+            None => self.globals.borrow_mut().insert(name.to_string(), variable),
+        };
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        self.variable(name)
+            .map(|variable| variable.borrow().clone())
+    }
+
+    /// The variable `name`, in the innermost scope declaring it
+    fn variable(&self, name: &str) -> Option<Rc<RefCell<Value>>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+            .or_else(|| self.globals.borrow().get(name).cloned())
+    }
+
+    /// Give the variable `name` a new value
+    fn assign(&mut self, name: &str, value: Value, position: Position) -> Result<(), RuntimeError> {
+        match self.variable(name) {
+            Some(variable) => {
+                let value = stored(&variable.borrow(), value);
+                *variable.borrow_mut() = value;
+                Ok(())
+            }
+            None => Err(RuntimeError::new(
+                RuntimeErrorKind::UndefinedName(name.to_string()),
+                position,
+            )),
+        }
+    }
+
+    /// Run a package level declaration: functions and types are recorded for later, and
+    /// variables and constants are initialized
+    pub fn declare(&mut self, declaration: &Declaration) -> Result<(), RuntimeError> {
+        match &declaration.kind {
+            DeclarationKind::Function {
+                receiver: Some(receiver),
+                name,
+                ..
+            } => {
+                let base_type = match &receiver.type_expression.kind {
+                    TypeExpressionKind::Pointer(base_type) => base_type,
+                    _ => &receiver.type_expression,
+                };
+                if let TypeExpressionKind::Name(base_type) = &base_type.kind {
+                    self.methods
+                        .entry(base_type.clone())
+                        .or_default()
+                        .insert(name.clone(), Rc::new(declaration.clone()));
+                }
+                Ok(())
+            }
+            DeclarationKind::Function { name, .. } => {
+                self.functions
+                    .insert(name.clone(), Rc::new(declaration.clone()));
+                Ok(())
+            }
+            DeclarationKind::Variable { specs, .. } => {
+                for spec in specs {
+                    self.exec_variable_spec(spec)?;
+                }
+                Ok(())
+            }
+            DeclarationKind::Constant { specs, .. } => self.exec_constant_specs(specs),
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } => {
+                self.types.insert(name.to_string(), type_expression.clone());
+                Ok(())
+            }
+        }
+    }
+
+    /// Call the package level function `name`, returning its results
+    pub fn call_function(
+        &mut self,
+        name: &str,
+        arguments: Vec<Value>,
+        position: Position,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let Some(function) = self.functions.get(name).cloned() else {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::UndefinedName(name.to_string()),
+                position,
+            ));
+        };
+        self.call_declaration(&function, name, None, None, arguments, position)
+    }
+
+    /// Call a function or method, with `receiver` bound to its receiver's name, or a closure
+    /// seeing the variables it `captured`. A panic's trace names the call `name`, like `main.f`
+    /// or `main.(*Point).Scale` would be.
+    fn call_declaration(
+        &mut self,
+        function: &Declaration,
+        name: &str,
+        receiver: Option<Value>,
+        captured: Option<Vec<Scope>>,
+        arguments: Vec<Value>,
+        position: Position,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let DeclarationKind::Function {
+            receiver: receiver_parameter,
+            parameters,
+            results,
+            body,
+            ..
+        } = &function.kind
+        else {
+            unreachable!("only functions are recorded as functions");
+        };
+        let want = count_names(parameters);
+        if arguments.len() != want {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::ArgumentCount {
+                    function: name.to_string(),
+                    have: arguments.len(),
+                    want,
+                },
+                position,
+            ));
+        }
+        if self.depth == self.max_depth || self.stack_exhausted() {
+            return Err(RuntimeError::new(RuntimeErrorKind::StackOverflow, position));
+        }
+        if let Some(observer) = &self.observer {
+            observer.borrow_mut().on_call(name, &arguments, position);
+        }
+
+        // The body sees the package's variables and its own, and a closure's those of the
+        // function it was made in, but not its caller's
+        let mut scope = HashMap::new();
+        if let (Some(parameter), Some(receiver)) = (receiver_parameter, receiver)
+            && let Some(ExpressionKind::Identifier(name)) =
+                parameter.names.first().map(|name| &name.kind)
+        {
+            scope.insert(name.clone(), Rc::new(RefCell::new(receiver)));
+        }
+        let mut arguments = arguments.into_iter();
+        for parameter in parameters {
+            for name in &parameter.names {
+                let argument = arguments.next().unwrap_or(Value::Nil);
+                if let ExpressionKind::Identifier(name) = &name.kind {
+                    let argument = self.assigned_value(argument, &parameter.type_expression);
+                    scope.insert(name.clone(), Rc::new(RefCell::new(argument)));
+                }
+            }
+        }
+        for result in results {
+            let zero = self.zero_value(&result.type_expression);
+            for name in &result.names {
+                if let ExpressionKind::Identifier(name) = &name.kind {
+                    scope.insert(name.clone(), Rc::new(RefCell::new(zero.clone())));
+                }
+            }
+        }
+        let frame = Frame {
+            name: name.to_string(),
+            closure: captured.is_some(),
+            literals: Vec::new(),
+        };
+        let mut scopes = captured.unwrap_or_default();
+        scopes.push(scope);
+        let caller_scopes = std::mem::replace(&mut self.scopes, scopes);
+        let caller_frame = std::mem::replace(&mut self.frame, frame);
+        self.depth += 1;
+        self.defers.push(Vec::new());
+        let flow = self
+            .exec_statements(body)
+            .map_err(|error| self.run_time_panic(error));
+        let flow = self.run_deferred(flow);
+        self.depth -= 1;
+        self.frame = caller_frame;
+        let scope = std::mem::replace(&mut self.scopes, caller_scopes)
+            .pop()
+            .unwrap_or_default();
+        let flow = match flow {
+            Ok(flow) => flow,
+            // A panic's trace gains this call, and carries on from where it was made
+            Err(mut error) => {
+                if self.is_panic(&error)
+                    && let RuntimeErrorKind::Panic { stack, .. } = &mut error.kind
+                {
+                    stack.push(StackFrame {
+                        function: name.to_string(),
+                        position: error.position,
+                    });
+                    error.position = position;
+                }
+                return Err(error);
+            }
+        };
+        match flow {
+            // A bare return returns the named results as they are, as does a call that
+            // recovered from a panic, whose unnamed results are zero
+            Flow::Return(values) if values.is_empty() => Ok(results
+                .iter()
+                .flat_map(|result| {
+                    let zero = self.zero_value(&result.type_expression);
+                    if result.names.is_empty() {
+                        return vec![zero];
+                    }
+                    result
+                        .names
+                        .iter()
+                        .map(|name| match &name.kind {
+                            ExpressionKind::Identifier(name) => scope
+                                .get(name)
+                                .map_or(zero.clone(), |variable| variable.borrow().clone()),
+                            _ => zero.clone(),
+                        })
+                        .collect()
+                })
+                .collect()),
+            Flow::Return(values) => {
+                let result_types = results.iter().flat_map(|result| {
+                    std::iter::repeat_n(&result.type_expression, result.names.len().max(1))
+                });
+                Ok(values
+                    .into_iter()
+                    .zip(result_types)
+                    .map(|(value, type_expression)| self.assigned_value(value, type_expression))
+                    .collect())
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Run the calls the returning function deferred, last deferred first. While it's
+    /// panicking, each may recover the panic, after which the function returns normally, and
+    /// one panicking itself takes over the unwinding.
+    fn run_deferred(
+        &mut self,
+        mut outcome: Result<Flow, RuntimeError>,
+    ) -> Result<Flow, RuntimeError> {
+        let deferred = self.defers.pop().unwrap_or_default();
+        for deferred in deferred.into_iter().rev() {
+            let panicking = matches!(&outcome, Err(error) if self.is_panic(error));
+            let exiting = matches!(&outcome, Err(error) if error.kind == RuntimeErrorKind::Goexit);
+            // Errors that aren't panics or a Goexit, like a stack overflow, are fatal
+            if outcome.is_err() && !panicking && !exiting {
+                break;
+            }
+            let recoverer =
+                std::mem::replace(&mut self.recoverer, panicking.then_some(self.depth + 1));
+            let result = self
+                .call_callee(deferred.callee, deferred.arguments, &deferred.call)
+                .map_err(|error| self.run_time_panic(error));
+            self.recoverer = recoverer;
+            outcome = match (result, outcome) {
+                // Go reports a panic during a panic after the one it interrupted
+                (Err(mut error), Err(earlier)) if self.is_panic(&error) => {
+                    if let (
+                        RuntimeErrorKind::Panic { messages, .. },
+                        RuntimeErrorKind::Panic {
+                            messages: earlier, ..
+                        },
+                    ) = (&mut error.kind, earlier.kind)
+                    {
+                        messages.splice(0..0, earlier);
+                    }
+                    Err(error)
+                }
+                (Err(error), _) => Err(error),
+                // Nothing recovers a Goexit
+                (Ok(_), Err(error))
+                    if self.panicking.is_none() && error.kind != RuntimeErrorKind::Goexit =>
+                {
+                    Ok(Flow::Return(Vec::new()))
+                }
+                (Ok(_), outcome) => outcome,
+            };
+        }
+        outcome
+    }
+
+    /// Start panicking with `value`, returning the error that unwinds the stack
+    pub(crate) fn panic(&mut self, value: Value, call: &Expression) -> RuntimeError {
+        // Since Go 1.21, panic(nil) panics with a *runtime.PanicNilError instead
+        let value = match value {
+            Value::Nil => Value::Error(Rc::new(Error::new(
+                "*runtime.PanicNilError",
+                "panic called with nil argument".to_string(),
+            ))),
+            value => value,
+        };
+        let message = panic_message(&value);
+        self.panicking = Some(value);
+        RuntimeError::new(
+            RuntimeErrorKind::Panic {
+                messages: vec![message],
+                stack: Vec::new(),
+                goroutine: self.goroutine,
+            },
+            call.position_start,
+        )
+    }
+
+    /// `error` as the panic Go makes of a run-time error like an index out of range, whose
+    /// value is an error with the same message (https://go.dev/ref/spec#Run_time_panics).
+    /// Other errors are returned as they are.
+    fn run_time_panic(&mut self, error: RuntimeError) -> RuntimeError {
+        if !error.kind.is_run_time_panic() {
+            return error;
+        }
+        let message = error.kind.to_string();
+        // A package panics with a string, and the runtime with an error
+        self.panicking = Some(match error.kind {
+            RuntimeErrorKind::PackagePanic(_) => Value::String(message.clone()),
+            // Each kind of run-time error has its own unexported type, which all implement
+            // runtime.Error
+            _ => Value::Error(Rc::new(Error::new("runtime.Error", message.clone()))),
+        });
+        RuntimeError::new(
+            RuntimeErrorKind::Panic {
+                messages: vec![message],
+                stack: Vec::new(),
+                goroutine: self.goroutine,
+            },
+            error.position,
+        )
+    }
+
+    /// Whether `error` is a panic of this goroutine's, which deferred calls run during and can
+    /// recover. Another goroutine's panic ends the program.
+    fn is_panic(&self, error: &RuntimeError) -> bool {
+        matches!(error.kind, RuntimeErrorKind::Panic { goroutine, .. } if goroutine == self.goroutine)
+    }
+
+    /// The panic's value, which stops the panic, when called by a call the panicking function
+    /// deferred; otherwise nil (https://go.dev/ref/spec#Handling_panics)
+    pub(crate) fn recover(&mut self) -> Value {
+        if self.recoverer != Some(self.depth) {
+            return Value::Nil;
+        }
+        self.panicking.take().unwrap_or(Value::Nil)
+    }
+
+    /// Forget the panic a statement run outside any function ended with, which no deferred
+    /// call was there to recover, so the next statement starts afresh
+    pub(crate) fn clear_panic(&mut self) {
+        self.panicking = None;
+        self.recoverer = None;
+    }
+
+    pub fn exec_statements(&mut self, statements: &[Statement]) -> Result<Flow, RuntimeError> {
+        for statement in statements {
+            let flow = self.exec_statement(statement)?;
+            if flow != Flow::Normal {
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Execute `statements` in a scope of their own
+    fn exec_block(&mut self, statements: &[Statement]) -> Result<Flow, RuntimeError> {
+        self.scopes.push(HashMap::new());
+        let flow = self.exec_statements(statements);
+        self.scopes.pop();
+        flow
+    }
+
+    pub fn exec_statement(&mut self, statement: &Statement) -> Result<Flow, RuntimeError> {
+        self.preempt(statement.position_start)?;
+        if let Some(observer) = &self.observer {
+            observer
+                .borrow_mut()
+                .on_statement_enter(statement, self.goroutine);
+        }
+        match &statement.kind {
+            StatementKind::Expression(expression) => {
+                match &expression.kind {
+                    // A call's results can be dropped, including none or several of them
+                    ExpressionKind::FunctionCall { .. } => {
+                        self.eval_call(expression)?;
+                    }
+                    _ => {
+                        self.eval_expression(expression)?;
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+            StatementKind::Declaration(declaration) => match &declaration.kind {
+                DeclarationKind::Variable { specs, .. } => {
+                    for spec in specs {
+                        self.exec_variable_spec(spec)?;
+                    }
+                    Ok(Flow::Normal)
+                }
+                DeclarationKind::Constant { specs, .. } => {
+                    self.exec_constant_specs(specs).map(|()| Flow::Normal)
+                }
+                _ => {
+                    self.declare(declaration)?;
+                    Ok(Flow::Normal)
+                }
+            },
+            StatementKind::Assignment {
+                left,
+                right,
+                define,
+            } => {
+                let values = self.eval_values(right, left.len(), statement.position_start)?;
+                for (target, value) in left.iter().zip(values) {
+                    self.exec_assignment(target, value, *define)?;
+                }
+                Ok(Flow::Normal)
+            }
+            StatementKind::Block(statements) => self.exec_block(statements),
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => {
+                // Variables the init statement declares are scoped to the whole if
+                self.scopes.push(HashMap::new());
+                let flow = self.exec_if(init.as_deref(), condition, body, else_branch.as_deref());
+                self.scopes.pop();
+                flow
+            }
+            // Each statement kind with much to do does it in a function of its own, keeping
+            // this one's frame small for deep recursion
+            StatementKind::IncDec { .. } | StatementKind::OpAssign { .. } => {
+                self.exec_update(statement)
+            }
+            StatementKind::For { .. } => self.exec_for(statement),
+            StatementKind::Switch { init, tag, cases } => {
+                self.exec_switch(init.as_deref(), tag.as_ref(), cases)
+            }
+            // A case's body ends before its fallthrough, which exec_switch follows
+            StatementKind::Fallthrough => Ok(Flow::Normal),
+            StatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => self.exec_range(key.as_ref(), value.as_ref(), *define, expression, body),
+            StatementKind::Return(values) => {
+                let values = match values.as_slice() {
+                    [value] if matches!(value.kind, ExpressionKind::FunctionCall { .. }) => {
+                        self.eval_call(value)?
+                    }
+                    _ => values
+                        .iter()
+                        .map(|value| self.eval_expression(value))
+                        .collect::<Result<_, _>>()?,
+                };
+                Ok(Flow::Return(values))
+            }
+            StatementKind::Break => Ok(Flow::Break),
+            StatementKind::Continue => Ok(Flow::Continue),
+            StatementKind::Defer(call) => {
+                let deferred = self.pending_call(call, "defer")?;
+                if let Some(defers) = self.defers.last_mut() {
+                    defers.push(deferred);
+                }
+                Ok(Flow::Normal)
+            }
+            StatementKind::Go(call) => {
+                let call = self.pending_call(call, "go")?;
+                self.go(call)?;
+                Ok(Flow::Normal)
+            }
+            StatementKind::Send { channel, value } => {
+                self.exec_send(channel, value, statement.position_start)?;
+                Ok(Flow::Normal)
+            }
+            StatementKind::Select(cases) => self.exec_select(cases, statement.position_start),
+        }
+    }
+
+    /// Count a step towards the goroutine's turn, and once it's taken enough, let the others
+    /// ready to run have theirs. Loops count each iteration, so even an empty one is preempted.
+    fn preempt(&mut self, position: Position) -> Result<(), RuntimeError> {
+        self.budget.step(position)?;
+        self.steps += 1;
+        if self.steps < STATEMENTS_PER_TURN {
+            return Ok(());
+        }
+        self.steps = 0;
+        self.budget.check_time(position)?;
+        self.scheduler
+            .yield_now(self.goroutine)
+            .map_err(|interruption| interrupted(interruption, position))
+    }
+
+    /// Run `x++` or `x op= y`
+    fn exec_update(&mut self, statement: &Statement) -> Result<Flow, RuntimeError> {
+        let (target, operator, value) = match &statement.kind {
+            StatementKind::IncDec { target, increment } => {
+                let operator = match increment {
+                    true => Operator::Plus,
+                    false => Operator::Minus,
+                };
+                (target, operator, Value::Int(1))
+            }
+            StatementKind::OpAssign {
+                target,
+                operator,
+                value,
+            } => (target, *operator, self.eval_expression(value)?),
+            _ => unreachable!("only inc/dec and op-assign statements update"),
+        };
+        let current = self.eval_expression(target)?;
+        let value = binary(target, operator, current, value)?;
+        self.exec_assignment(target, value, false)?;
+        Ok(Flow::Normal)
+    }
+
+    /// Run a for loop, in a scope of its own for the variables its init statement declares
+    fn exec_for(&mut self, statement: &Statement) -> Result<Flow, RuntimeError> {
+        self.scopes.push(HashMap::new());
+        let flow = self.exec_for_clauses(statement);
+        self.scopes.pop();
+        flow
+    }
+
+    fn exec_for_clauses(&mut self, statement: &Statement) -> Result<Flow, RuntimeError> {
+        let StatementKind::For {
+            init,
+            condition,
+            post,
+            body,
+        } = &statement.kind
+        else {
+            unreachable!("only for statements loop");
+        };
+        let position = statement.position_start;
+        if let Some(init) = init {
+            self.exec_statement(init)?;
+        }
+        loop {
+            self.preempt(position)?;
+            if let Some(condition) = condition
+                && !self.eval_condition(condition, "for")?
+            {
+                break;
+            }
+            match self.exec_block(body)? {
+                Flow::Break => break,
+                Flow::Return(values) => return Ok(Flow::Return(values)),
+                Flow::Normal | Flow::Continue => {}
+            }
+            // Each iteration has variables of its own, starting with the values the last
+            // one's ended with (https://go.dev/ref/spec#For_clause)
+            if let Some(scope) = self.scopes.last_mut() {
+                for variable in scope.values_mut() {
+                    let value = variable.borrow().clone();
+                    *variable = Rc::new(RefCell::new(value));
+                }
+            }
+            if let Some(post) = post {
+                self.exec_statement(post)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Run a switch, in a scope of its own for the variables its init statement declares
+    fn exec_switch(
+        &mut self,
+        init: Option<&Statement>,
+        tag: Option<&Expression>,
+        cases: &[SwitchCase],
+    ) -> Result<Flow, RuntimeError> {
+        self.scopes.push(HashMap::new());
+        let flow = self.exec_switch_cases(init, tag, cases);
+        self.scopes.pop();
+        flow
+    }
+
+    /// Run the first case of a switch whose value equals the tag, or true without one, and
+    /// otherwise its default, following each fallthrough to the next case's body
+    /// (https://go.dev/ref/spec#Expression_switches)
+    fn exec_switch_cases(
+        &mut self,
+        init: Option<&Statement>,
+        tag: Option<&Expression>,
+        cases: &[SwitchCase],
+    ) -> Result<Flow, RuntimeError> {
+        if let Some(init) = init {
+            self.exec_statement(init)?;
+        }
+        let tag_value = match tag {
+            Some(tag) => self.eval_expression(tag)?,
+            None => Value::Bool(true),
+        };
+        let mut chosen = None;
+        'cases: for (index, case) in cases.iter().enumerate() {
+            for expression in case.expressions.iter().flatten() {
+                let value = self.eval_expression(expression)?;
+                let operation = tag.unwrap_or(expression);
+                if binary(operation, Operator::EqualEqual, tag_value.clone(), value)?
+                    == Value::Bool(true)
+                {
+                    chosen = Some(index);
+                    break 'cases;
+                }
+            }
+        }
+        let default = cases.iter().position(|case| case.expressions.is_none());
+        let Some(mut index) = chosen.or(default) else {
+            return Ok(Flow::Normal);
+        };
+        loop {
+            let body = cases[index].body.as_slice();
+            let (body, falls_through) = match body.split_last() {
+                Some((last, rest)) if last.kind == StatementKind::Fallthrough => (rest, true),
+                _ => (body, false),
+            };
+            match self.exec_block(body)? {
+                // A break in a switch ends the switch, not the loop around it
+                Flow::Break => return Ok(Flow::Normal),
+                Flow::Normal if falls_through => index += 1,
+                flow => return Ok(flow),
+            }
+        }
+    }
+
+    fn exec_if(
+        &mut self,
+        init: Option<&Statement>,
+        condition: &Expression,
+        body: &[Statement],
+        else_branch: Option<&Statement>,
+    ) -> Result<Flow, RuntimeError> {
+        if let Some(init) = init {
+            self.exec_statement(init)?;
+        }
+        if self.eval_condition(condition, "if")? {
+            self.exec_block(body)
+        } else if let Some(else_branch) = else_branch {
+            self.exec_statement(else_branch)
+        } else {
+            Ok(Flow::Normal)
+        }
+    }
+
+    /// Run a range loop over an integer, string, slice, map or channel. A string is ranged
+    /// over by rune, with the byte index each starts at as its key, and a channel until it's
+    /// closed.
+    fn exec_range(
+        &mut self,
+        key: Option<&Expression>,
+        value: Option<&Expression>,
+        define: bool,
+        expression: &Expression,
+        body: &[Statement],
+    ) -> Result<Flow, RuntimeError> {
+        let mut iterations = match self.eval_expression(expression)?.into_underlying() {
+            Value::Int(count) => Iterations::Fixed(
+                (0..count)
+                    .map(|i| (Value::Int(i), Value::Nil))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            Value::String(string) => Iterations::Fixed(
+                string
+                    .char_indices()
+                    .map(|(index, character)| {
+                        (
+                            Value::Int(index as i64),
+                            Value::Int(i64::from(u32::from(character))),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            Value::Slice(slice) => Iterations::Slice { slice, next: 0 },
+            Value::Map(map) => {
+                let keys = self.range_keys(&map).into_iter();
+                Iterations::Map { map, keys }
+            }
+            Value::Channel(channel) => Iterations::Channel(channel),
+            other => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::CannotRange {
+                        value: expression.to_string(),
+                        value_type: other.type_name().to_string(),
+                    },
+                    expression.position_start,
+                ));
+            }
+        };
+        loop {
+            self.preempt(expression.position_start)?;
+            // Ranging over a channel receives until it's closed, which the iterator can't do
+            let next = match &mut iterations {
+                Iterations::Channel(channel) => {
+                    let (value, received) = self.receive(channel, expression.position_start)?;
+                    received.then_some((value, Value::Nil))
+                }
+                iterations => iterations.next(),
+            };
+            let Some((key_value, value_value)) = next else {
+                break;
+            };
+            // Each iteration has variables of its own
+            self.scopes.push(HashMap::new());
+            let mut assigned = Ok(());
+            if let Some(key) = key {
+                assigned = self.exec_assignment(key, key_value, define);
+            }
+            if let Some(value) = value
+                && assigned.is_ok()
+            {
+                assigned = self.exec_assignment(value, value_value, define);
+            }
+            let flow = assigned.and_then(|_| self.exec_statements(body));
+            self.scopes.pop();
+            match flow? {
+                Flow::Break => break,
+                Flow::Return(values) => return Ok(Flow::Return(values)),
+                Flow::Normal | Flow::Continue => {}
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// A map's keys in the order a range loop visits them, which is random as in Go unless
+    /// the evaluator is deterministic
+    fn range_keys(&mut self, map: &Map) -> Vec<Key> {
+        let mut keys: Vec<Key> = map
+            .sorted_entries()
+            .iter()
+            .filter_map(|(key, _)| key.key())
+            .collect();
+        if !self.deterministic {
+            for i in (1..keys.len()).rev() {
+                let j = (self.next_random() % (i as u64 + 1)) as usize;
+                keys.swap(i, j);
+            }
+        }
+        keys
+    }
+
+    /// The next number from a xorshift generator, which is plenty to shuffle with
+    fn next_random(&mut self) -> u64 {
+        let mut x = self.random;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.random = x;
+        x
+    }
+
+    fn exec_variable_spec(&mut self, spec: &VariableSpec) -> Result<(), RuntimeError> {
+        let values = if spec.values.is_empty() {
+            let zero = match &spec.type_expression {
+                Some(type_expression) => self.zero_value(type_expression),
+                None => Value::Nil,
+            };
+            vec![zero; spec.names.len()]
+        } else {
+            self.eval_values(&spec.values, spec.names.len(), spec.position_start)?
+        };
+        for (name, value) in spec.names.iter().zip(values) {
+            let value = match &spec.type_expression {
+                Some(type_expression) => self.assigned_value(value, type_expression),
+                None => value,
+            };
+            if let ExpressionKind::Identifier(name) = &name.kind {
+                self.define(name, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Declare the constants of a const declaration, each spec's values evaluated with `iota`
+    /// as the spec's index
+    fn exec_constant_specs(&mut self, specs: &[VariableSpec]) -> Result<(), RuntimeError> {
+        for constant in constant_specs(specs) {
+            let iota = Rc::new(RefCell::new(Value::Int(constant.iota)));
+            self.scopes
+                .push(HashMap::from([("iota".to_string(), iota)]));
+            let values = self.eval_values(
+                constant.values,
+                constant.spec.names.len(),
+                constant.spec.position_start,
+            );
+            self.scopes.pop();
+            for (name, value) in constant.spec.names.iter().zip(values?) {
+                let value = match constant.type_expression {
+                    Some(type_expression) => self.assigned_value(value, type_expression),
+                    None => value,
+                };
+                if let ExpressionKind::Identifier(name) = &name.kind {
+                    self.define(name, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Assign `value` to `target`, or declare it when `define` is set and the innermost scope
+    /// doesn't already declare it (https://go.dev/ref/spec#Short_variable_declarations)
+    fn exec_assignment(
+        &mut self,
+        target: &Expression,
+        value: Value,
+        define: bool,
+    ) -> Result<(), RuntimeError> {
+        match &target.kind {
+            ExpressionKind::BlankIdentifier => Ok(()),
+            ExpressionKind::Identifier(name) => {
+                let declared_here = match self.scopes.last() {
+                    Some(scope) => scope.contains_key(name),
+                    None => self.globals.borrow().contains_key(name),
+                };
+                if define && !declared_here {
+                    self.define(name, value);
+                    Ok(())
+                } else {
+                    self.assign(name, value, target.position_start)
+                }
+            }
+            ExpressionKind::Index { object, index } => {
+                let object_value = self.eval_expression(object)?;
+                let index_value = self.eval_expression(index)?;
+                match object_value.into_underlying() {
+                    Value::Slice(slice) => {
+                        let i = element_index(index, &index_value, slice.len)?;
+                        let value = self.assigned_value(value, &slice.element);
+                        slice.set(i, value);
+                        Ok(())
+                    }
+                    Value::Map(map) => {
+                        let key = hash_key(index, &index_value)?;
+                        let Some(entries) = &map.entries else {
+                            return Err(RuntimeError::new(
+                                RuntimeErrorKind::NilMapAssignment,
+                                target.position_start,
+                            ));
+                        };
+                        if !entries.borrow().contains_key(&key) {
+                            self.allocate(2 * ELEMENT_SIZE, target.position_start)?;
+                        }
+                        let key_value = self.assigned_value(index_value, &map.key);
+                        let value = self.assigned_value(value, &map.value);
+                        entries.borrow_mut().insert(key, (key_value, value));
+                        Ok(())
+                    }
+                    Value::String(_) => Err(RuntimeError::new(
+                        RuntimeErrorKind::CannotAssign(target.to_string()),
+                        target.position_start,
+                    )),
+                    other => Err(cannot_index(object, &other)),
+                }
+            }
+            ExpressionKind::FieldAccess { .. } | ExpressionKind::Unary { .. } => {
+                match self.operand(target)? {
+                    Operand::Place(pointer) => {
+                        let value = pointer.inspect(|current| stored(current, value));
+                        pointer.store(value.unwrap_or(Value::Nil));
+                        Ok(())
+                    }
+                    Operand::Value(_) => Err(RuntimeError::new(
+                        RuntimeErrorKind::CannotAssign(target.to_string()),
+                        target.position_start,
+                    )),
+                }
+            }
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::Unsupported(target.to_string()),
+                target.position_start,
+            )),
+        }
+    }
+
+    /// The values assigned to `count` variables, where a single call may return them all
+    fn eval_values(
+        &mut self,
+        values: &[Expression],
+        count: usize,
+        position: Position,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        // v, ok := m[k], where ok is whether the key is present
+        if let [value] = values
+            && count == 2
+            && let ExpressionKind::Index { object, index } = &value.kind
+        {
+            let object_value = self.eval_expression(object)?;
+            if !matches!(object_value, Value::Map(_)) {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::AssignmentMismatch {
+                        variables: count,
+                        values: 1,
+                    },
+                    position,
+                ));
+            }
+            let index_value = self.eval_expression(index)?;
+            let (value, present) = self.index(object, object_value, index, index_value)?;
+            return Ok(vec![value, Value::Bool(present)]);
+        }
+        // v, ok := <-ch, where ok is whether a value was sent rather than the channel closed
+        if let [value] = values
+            && count == 2
+            && let Some(channel) = received_channel(value)
+        {
+            let (value, received) = self.eval_receive(channel, value.position_start)?;
+            return Ok(vec![value, Value::Bool(received)]);
+        }
+        let values = match values {
+            [value] if count > 1 && matches!(value.kind, ExpressionKind::FunctionCall { .. }) => {
+                self.eval_call(value)?
+            }
+            _ => values
+                .iter()
+                .map(|value| self.eval_expression(value))
+                .collect::<Result<_, _>>()?,
+        };
+        if values.len() != count {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::AssignmentMismatch {
+                    variables: count,
+                    values: values.len(),
+                },
+                position,
+            ));
+        }
+        Ok(values)
+    }
+
+    fn eval_condition(
+        &mut self,
+        condition: &Expression,
+        statement: &str,
+    ) -> Result<bool, RuntimeError> {
+        match self.eval_expression(condition)?.into_underlying() {
+            Value::Bool(value) => Ok(value),
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::NonBooleanCondition {
+                    condition: condition.to_string(),
+                    statement: statement.to_string(),
+                },
+                condition.position_start,
+            )),
+        }
+    }
+
+    pub fn eval_expression(&mut self, expression: &Expression) -> Result<Value, RuntimeError> {
+        let value = self.eval_expression_kind(expression)?;
+        if let Some(observer) = &self.observer {
+            observer.borrow_mut().on_value(expression, &value);
+        }
+        Ok(value)
+    }
+
+    fn eval_expression_kind(&mut self, expression: &Expression) -> Result<Value, RuntimeError> {
+        match &expression.kind {
+            ExpressionKind::IntegerLiteral(literal) => {
+                match Constant::from_integer_literal(literal) {
+                    Some(Constant::Int(value)) => i64::try_from(value)
+                        .map(Value::Int)
+                        .map_err(|_| invalid_literal(expression)),
+                    _ => Err(invalid_literal(expression)),
+                }
+            }
+            ExpressionKind::FloatLiteral(literal) => match Constant::from_float_literal(literal) {
+                Some(Constant::Float(value)) => Ok(Value::Float(value)),
+                _ => Err(invalid_literal(expression)),
+            },
+            // A rune is an int32, which is an int until sized integers are
+            ExpressionKind::RuneLiteral(literal) => unquote_rune(literal)
+                .map(|code_point| Value::Int(i64::from(code_point)))
+                .ok_or_else(|| invalid_literal(expression)),
+            ExpressionKind::StringLiteral(literal) => unquote_string(literal)
+                .map(Value::String)
+                .ok_or_else(|| invalid_literal(expression)),
+            ExpressionKind::Identifier(name) => {
+                if let Some(value) = self.lookup(name) {
+                    return Ok(value);
+                }
+                if let Some(function) = self.functions.get(name) {
+                    return Ok(Value::Function(function.clone()));
+                }
+                match name.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "nil" => Ok(Value::Nil),
+                    _ => Err(RuntimeError::new(
+                        RuntimeErrorKind::UndefinedName(name.clone()),
+                        expression.position_start,
+                    )),
+                }
+            }
+            ExpressionKind::Parenthesized(inner) => self.eval_expression(inner),
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left_value = self.eval_expression(left)?;
+                // && and || only evaluate their right operand when it decides the result
+                match (operator, &left_value) {
+                    (Operator::AndAnd, Value::Bool(false)) => return Ok(Value::Bool(false)),
+                    (Operator::PipePipe, Value::Bool(true)) => return Ok(Value::Bool(true)),
+                    _ => {}
+                }
+                let right_value = self.eval_expression(right)?;
+                if let (Operator::Plus, Value::String(left), Value::String(right)) =
+                    (operator, &left_value, &right_value)
+                {
+                    self.allocate(left.len() + right.len(), expression.position_start)?;
+                }
+                binary(expression, *operator, left_value, right_value)
+            }
+            ExpressionKind::Conversion {
+                target,
+                expression: operand,
+            } => {
+                let value = self.eval_expression(operand)?;
+                self.convert_to_type(value, target, operand)
+            }
+            ExpressionKind::Index { object, index } => {
+                let object_value = self.eval_expression(object)?;
+                let index_value = self.eval_expression(index)?;
+                Ok(self.index(object, object_value, index, index_value)?.0)
+            }
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                let value = self.eval_expression(object)?;
+                let bounds = [
+                    self.eval_bound(low.as_deref())?,
+                    self.eval_bound(high.as_deref())?,
+                    self.eval_bound(max.as_deref())?,
+                ];
+                self.slice(object, value, bounds, expression)
+            }
+            ExpressionKind::Receive(channel) => {
+                Ok(self.eval_receive(channel, expression.position_start)?.0)
+            }
+            ExpressionKind::FieldAccess { object, field, .. } => {
+                self.eval_field_access(expression, object, field)
+            }
+            ExpressionKind::Unary { operator, operand } => {
+                self.eval_unary(expression, *operator, operand)
+            }
+            ExpressionKind::CompositeLiteral {
+                type_expression: Some(literal_type),
+                elements,
+            } => self.eval_composite_literal(expression, literal_type, elements),
+            ExpressionKind::FunctionLiteral { .. } => Ok(self.closure(expression)),
+            ExpressionKind::FunctionCall { .. } => {
+                let mut values = self.eval_call(expression)?;
+                match values.len() {
+                    1 => Ok(values.remove(0)),
+                    0 => Err(RuntimeError::new(
+                        RuntimeErrorKind::NoValue(expression.to_string()),
+                        expression.position_start,
+                    )),
+                    _ => Err(RuntimeError::new(
+                        RuntimeErrorKind::MultipleValues(expression.to_string()),
+                        expression.position_start,
+                    )),
+                }
+            }
+            _ => Err(RuntimeError::new(
+                RuntimeErrorKind::Unsupported(expression.to_string()),
+                expression.position_start,
+            )),
+        }
+    }
+
+    /// The closure a function literal makes, sharing the variables in scope
+    fn closure(&mut self, literal: &Expression) -> Value {
+        let ExpressionKind::FunctionLiteral {
+            parameters,
+            results,
+            body,
+        } = &literal.kind
+        else {
+            unreachable!("only function literals make closures");
+        };
+        let literals = &mut self.frame.literals;
+        let number = match literals
+            .iter()
+            .position(|start| *start == literal.position_start)
+        {
+            Some(index) => index + 1,
+            None => {
+                literals.push(literal.position_start);
+                literals.len()
+            }
+        };
+        let name = match (self.frame.name.as_str(), self.frame.closure) {
+            (outer, true) => format!("{}.{}", outer, number),
+            // Package level variables are initialized by the package's init
+            ("", false) => format!("init.func{}", number),
+            (outer, false) => format!("{}.func{}", outer, number),
+        };
+        let function = Declaration::new_function(
+            None,
+            name,
+            parameters.clone(),
+            results.clone(),
+            body.clone(),
+            literal.position_start,
+            literal.position_end,
+        );
+        Value::Closure(Rc::new(Closure {
+            function,
+            captured: self.scopes.clone(),
+        }))
+    }
+
+    /// `object.field`: a struct's field, or a package's variable like `os.Args`
+    fn eval_field_access(
+        &mut self,
+        expression: &Expression,
+        object: &Expression,
+        field: &str,
+    ) -> Result<Value, RuntimeError> {
+        let Some(path) = self.package_path(object) else {
+            return match self.operand(expression)? {
+                Operand::Place(pointer) => Ok(pointer.load().unwrap_or(Value::Nil)),
+                Operand::Value(value) => Ok(value),
+            };
+        };
+        let value = match (path.as_str(), field) {
+            ("os", "Args") => Some(stdlib::string_slice(
+                self.args.clone(),
+                expression.position_start,
+            )),
+            ("os", "Stdin") => Some(Value::Native(Native::File(File::Stdin(self.input.clone())))),
+            ("os", "Stdout") => Some(Value::Native(Native::File(File::Stdout(
+                self.output.clone(),
+            )))),
+            ("os", "Stderr") => Some(Value::Native(Native::File(File::Stderr(
+                self.error_output.clone(),
+            )))),
+            ("time", unit) => time::unit(unit),
+            _ => None,
+        };
+        value.ok_or_else(|| {
+            RuntimeError::new(
+                RuntimeErrorKind::UndefinedName(format!("{}.{}", path, field)),
+                expression.position_start,
+            )
+        })
+    }
+
+    /// The path of the package `object` names, unless it isn't a package's name or a variable
+    /// shadows it
+    fn package_path(&self, object: &Expression) -> Option<String> {
+        match &object.kind {
+            ExpressionKind::Identifier(package) if self.variable(package).is_none() => {
+                self.packages.get(package).cloned()
+            }
+            _ => None,
+        }
+    }
+
+    /// What `expression` denotes: the variable, pointer indirection, field or slice element
+    /// it's a place for, or else its value. A field is selected through any number of pointers
+    /// and embedded fields, as `p.X` is `(*p).X` for a pointer p.
+    fn operand(&mut self, expression: &Expression) -> Result<Operand, RuntimeError> {
+        match &expression.kind {
+            ExpressionKind::Identifier(name) => match self.variable(name) {
+                Some(variable) => {
+                    let target_type = pointer_type(&variable.borrow(), expression.position_start);
+                    Ok(Operand::Place(Pointer::to(
+                        target_type,
+                        Target::Variable(variable),
+                    )))
+                }
+                None => Ok(Operand::Value(self.eval_expression(expression)?)),
+            },
+            ExpressionKind::Parenthesized(inner) => self.operand(inner),
+            ExpressionKind::Unary {
+                operator: UnaryOperator::Dereference,
+                operand,
+            } => {
+                let value = self.eval_expression(operand)?;
+                Ok(Operand::Place(dereference(value, expression)?))
+            }
+            ExpressionKind::FieldAccess { object, field, .. }
+                if self.package_path(object).is_none() =>
+            {
+                let object = match self.operand(object)? {
+                    Operand::Place(place) => match place.inspect(|value| match value {
+                        Value::Pointer(pointer) => Some(pointer.clone()),
+                        _ => None,
+                    }) {
+                        Some(Some(pointer)) => dereference(Value::Pointer(pointer), expression)?,
+                        _ => place,
+                    },
+                    Operand::Value(value @ (Value::Pointer(_) | Value::Nil)) => {
+                        dereference(value, expression)?
+                    }
+                    // Like a call's result, which is a struct but not a place
+                    Operand::Value(value) => {
+                        let target_type = pointer_type(&value, expression.position_start);
+                        Pointer::new(target_type, value)
+                    }
+                };
+                if let Some(field) = select_field(&object, field) {
+                    return Ok(Operand::Place(field));
+                }
+                match self.method_value(object.clone(), field) {
+                    Some(method) => Ok(Operand::Value(method)),
+                    None => Err(RuntimeError::new(
+                        RuntimeErrorKind::UndefinedMethod {
+                            expression: expression.to_string(),
+                            value_type: object.load().unwrap_or(Value::Nil).type_name(),
+                            method: field.clone(),
+                        },
+                        expression.position_start,
+                    )),
+                }
+            }
+            ExpressionKind::Index { object, index } => {
+                let object_value = self.eval_expression(object)?;
+                let index_value = self.eval_expression(index)?;
+                match object_value.into_underlying() {
+                    Value::Slice(slice) => {
+                        let i = element_index(index, &index_value, slice.len)?;
+                        let backing = slice.backing.clone().expect("a nil slice has no elements");
+                        Ok(Operand::Place(Pointer::to(
+                            slice.element.clone(),
+                            Target::Element(backing, slice.offset + i),
+                        )))
+                    }
+                    object_value => Ok(Operand::Value(
+                        self.index(object, object_value, index, index_value)?.0,
+                    )),
+                }
+            }
+            _ => Ok(Operand::Value(self.eval_expression(expression)?)),
+        }
+    }
+
+    /// A unary operation, like `-x` or `&point` (https://go.dev/ref/spec#Unary_operators)
+    fn eval_unary(
+        &mut self,
+        expression: &Expression,
+        operator: UnaryOperator,
+        operand: &Expression,
+    ) -> Result<Value, RuntimeError> {
+        match operator {
+            // Taking the address of a composite literal makes a new variable for it
+            UnaryOperator::Address => match &operand.kind {
+                ExpressionKind::CompositeLiteral { .. } => {
+                    let value = self.eval_expression(operand)?;
+                    let target_type = pointer_type(&value, operand.position_start);
+                    Ok(Value::Pointer(Pointer::new(target_type, value)))
+                }
+                _ => match self.operand(operand)? {
+                    Operand::Place(pointer) => Ok(Value::Pointer(pointer)),
+                    Operand::Value(_) => Err(RuntimeError::new(
+                        RuntimeErrorKind::CannotTakeAddress(operand.to_string()),
+                        expression.position_start,
+                    )),
+                },
+            },
+            UnaryOperator::Dereference => {
+                let value = self.eval_expression(operand)?;
+                let pointer = dereference(value, expression)?;
+                Ok(pointer.load().unwrap_or(Value::Nil))
+            }
+            // -9223372036854775808 is an int, though its operand alone isn't
+            UnaryOperator::Minus if let ExpressionKind::IntegerLiteral(literal) = &operand.kind => {
+                match Constant::from_integer_literal(literal).and_then(|c| c.unary(operator)) {
+                    Some(Constant::Int(value)) => i64::try_from(value)
+                        .map(Value::Int)
+                        .map_err(|_| invalid_literal(expression)),
+                    _ => Err(invalid_literal(expression)),
+                }
+            }
+            // The operation on a value of a named type is its underlying type's, giving a value
+            // of the named type
+            _ => match self.eval_expression(operand)? {
+                Value::Named(named) => {
+                    let value = unary(expression, operator, *named.value)?;
+                    Ok(Value::Named(Named::new(named.name, value)))
+                }
+                value => unary(expression, operator, value),
+            },
+        }
+    }
+
+    /// A composite literal of `literal_type` (https://go.dev/ref/spec#Composite_literals).
+    /// Fields without an element are zero, as are slice elements skipped by an index key.
+    fn eval_composite_literal(
+        &mut self,
+        expression: &Expression,
+        literal_type: &TypeExpression,
+        elements: &[KeyedElement],
+    ) -> Result<Value, RuntimeError> {
+        if let Some((name, declared)) = self.named_type(literal_type) {
+            let declared = declared.clone();
+            let value = self.eval_composite_literal(expression, &declared, elements)?;
+            return Ok(Value::Named(Named::new(name, value)));
+        }
+        match &self.underlying(literal_type).kind {
+            TypeExpressionKind::Struct(_) => {
+                let Value::Struct(mut value) = self.zero_value(literal_type) else {
+                    return Err(unsupported(expression));
+                };
+                let struct_type = value.struct_type.clone();
+                for (position, element) in elements.iter().enumerate() {
+                    let index = match &element.key {
+                        Some(Expression {
+                            kind: ExpressionKind::Identifier(name),
+                            ..
+                        }) => struct_type.field(name),
+                        Some(_) => None,
+                        None => (position < struct_type.fields.len()).then_some(position),
+                    };
+                    let Some(index) = index else {
+                        return Err(unknown_field(&struct_type, element, expression));
+                    };
+                    let field_type = struct_type.fields[index].type_expression.clone();
+                    value.fields[index] = self.eval_element(&element.value, &field_type)?;
+                }
+                Ok(Value::Struct(value))
+            }
+            TypeExpressionKind::Slice(element_type) => {
+                let element_type = Rc::new((**element_type).clone());
+                let mut values = Vec::with_capacity(elements.len());
+                let mut next = 0;
+                for element in elements {
+                    if let Some(key) = &element.key {
+                        let index = self.eval_expression(key)?;
+                        next = usize::try_from(integer_index(key, &index)?).map_err(|_| {
+                            RuntimeError::new(
+                                RuntimeErrorKind::IndexOutOfRange {
+                                    index: as_int(&index),
+                                    length: values.len(),
+                                },
+                                key.position_start,
+                            )
+                        })?;
+                    }
+                    let value = self.eval_element(&element.value, &element_type)?;
+                    if next >= values.len() {
+                        let added = next + 1 - values.len();
+                        self.allocate(
+                            added.saturating_mul(ELEMENT_SIZE),
+                            element.value.position_start,
+                        )?;
+                        values.resize(next + 1, self.zero_value(&element_type));
+                    }
+                    values[next] = value;
+                    next += 1;
+                }
+                let len = values.len();
+                Ok(Value::Slice(Slice::new(element_type, values, len)))
+            }
+            TypeExpressionKind::Map { key, value } => {
+                self.allocate(elements.len() * 2 * ELEMENT_SIZE, expression.position_start)?;
+                let map = Map::new(
+                    Rc::new((**key).clone()),
+                    Rc::new((**value).clone()),
+                    elements.len(),
+                );
+                let entries = map.entries.clone().expect("a new map isn't nil");
+                for element in elements {
+                    let Some(key) = &element.key else {
+                        return Err(unsupported(expression));
+                    };
+                    let key_value = self.eval_element(key, &map.key)?;
+                    let hashed = hash_key(key, &key_value)?;
+                    let value = self.eval_element(&element.value, &map.value)?;
+                    entries.borrow_mut().insert(hashed, (key_value, value));
+                }
+                Ok(Value::Map(map))
+            }
+            _ => Err(unsupported(expression)),
+        }
+    }
+
+    /// An element of a composite literal whose elements are of `element_type`. An element
+    /// that's itself a literal may leave out its type, and its `&` when it's a pointer.
+    fn eval_element(
+        &mut self,
+        element: &Expression,
+        element_type: &TypeExpression,
+    ) -> Result<Value, RuntimeError> {
+        let ExpressionKind::CompositeLiteral {
+            type_expression: None,
+            elements,
+        } = &element.kind
+        else {
+            let value = self.eval_expression(element)?;
+            return Ok(self.assigned_value(value, element_type));
+        };
+        match &element_type.kind {
+            TypeExpressionKind::Pointer(target_type) => {
+                let value = self.eval_composite_literal(element, target_type, elements)?;
+                Ok(Value::Pointer(Pointer::new(
+                    Rc::new((**target_type).clone()),
+                    value,
+                )))
+            }
+            _ => self.eval_composite_literal(element, element_type, elements),
+        }
+    }
+
+    /// The struct type `type_expression` is or is defined as, with its fields in order, or
+    /// None if it isn't a struct type. Values of a declared type are named after it.
+    fn struct_type(&self, type_expression: &TypeExpression) -> Option<StructType> {
+        let TypeExpressionKind::Struct(declared) = &self.underlying(type_expression).kind else {
+            return None;
+        };
+        let name = match &type_expression.kind {
+            TypeExpressionKind::Name(name) => Some(name.clone()),
+            _ => None,
+        };
+        let mut fields = Vec::new();
+        for field in declared {
+            let field_type = Rc::new(field.type_expression.clone());
+            if field.names.is_empty() {
+                fields.push(Field {
+                    name: field.embedded_name().unwrap_or_default().to_string(),
+                    type_expression: field_type.clone(),
+                    embedded: true,
+                });
+            }
+            // A blank field still takes its place among the others
+            for name in &field.names {
+                let name = match &name.kind {
+                    ExpressionKind::Identifier(name) => name.clone(),
+                    _ => "_".to_string(),
+                };
+                fields.push(Field {
+                    name,
+                    type_expression: field_type.clone(),
+                    embedded: false,
+                });
+            }
+        }
+        Some(StructType { name, fields })
+    }
+
+    /// `object[index]`, and whether it's present: always for a slice or string, and for a map
+    /// whether the key is. A missing key gives the map's zero value, even from a nil map.
+    fn index(
+        &self,
+        object: &Expression,
+        object_value: Value,
+        index: &Expression,
+        index_value: Value,
+    ) -> Result<(Value, bool), RuntimeError> {
+        match object_value.into_underlying() {
+            Value::Map(map) => {
+                let key = hash_key(index, &index_value)?;
+                let entry = map
+                    .entries
+                    .as_ref()
+                    .and_then(|entries| entries.borrow().get(&key).cloned());
+                Ok(match entry {
+                    Some((_, value)) => (value, true),
+                    None => (self.zero_value(&map.value), false),
+                })
+            }
+            Value::String(string) => {
+                let i = element_index(index, &index_value, string.len())?;
+                Ok((Value::Int(i64::from(string.as_bytes()[i])), true))
+            }
+            Value::Slice(slice) => {
+                let i = element_index(index, &index_value, slice.len)?;
+                Ok((slice.get(i), true))
+            }
+            other => Err(cannot_index(object, &other)),
+        }
+    }
+
+    /// A slice expression's bound, if it has one
+    fn eval_bound(&mut self, bound: Option<&Expression>) -> Result<Option<i64>, RuntimeError> {
+        bound
+            .map(|bound| {
+                let value = self.eval_expression(bound)?;
+                integer_index(bound, &value)
+            })
+            .transpose()
+    }
+
+    /// `object[low:high:max]` (https://go.dev/ref/spec#Slice_expressions). A slice of a slice
+    /// views the same backing array, so each sees the other's stores, and it may extend past
+    /// the original's length up to its capacity.
+    fn slice(
+        &self,
+        object: &Expression,
+        value: Value,
+        [low, high, max]: [Option<i64>; 3],
+        expression: &Expression,
+    ) -> Result<Value, RuntimeError> {
+        let out_of_range = |bounds: String| {
+            RuntimeError::new(
+                RuntimeErrorKind::SliceBoundsOutOfRange(bounds),
+                expression.position_start,
+            )
+        };
+        match value {
+            // Slicing a value of a named type gives a value of the named type
+            Value::Named(named) => {
+                let value = self.slice(object, *named.value, [low, high, max], expression)?;
+                Ok(Value::Named(Named::new(named.name, value)))
+            }
+            Value::String(_) if max.is_some() => Err(RuntimeError::new(
+                RuntimeErrorKind::ThreeIndexString,
+                expression.position_start,
+            )),
+            Value::String(string) => {
+                let (low, high, _) =
+                    slice_bounds([low, high, max], string.len(), string.len(), "length")
+                        .map_err(out_of_range)?;
+                let bytes = &string.as_bytes()[low..high];
+                Ok(Value::String(String::from_utf8_lossy(bytes).into_owned()))
+            }
+            Value::Slice(slice) => {
+                let (low, high, max) =
+                    slice_bounds([low, high, max], slice.len, slice.cap, "capacity")
+                        .map_err(out_of_range)?;
+                // Slicing a nil slice gives a nil slice
+                let Some(backing) = &slice.backing else {
+                    return Ok(Value::Slice(slice));
+                };
+                // Elements past the original's length may not have been stored yet
+                let end = slice.offset + high;
+                if backing.borrow().len() < end {
+                    let zero = self.zero_value(&slice.element);
+                    backing.borrow_mut().resize(end, zero);
+                }
+                Ok(Value::Slice(Slice {
+                    offset: slice.offset + low,
+                    len: high - low,
+                    cap: max - low,
+                    ..slice
+                }))
+            }
+            other => Err(RuntimeError::new(
+                RuntimeErrorKind::CannotSlice {
+                    value: object.to_string(),
+                    value_type: other.type_name(),
+                },
+                object.position_start,
+            )),
+        }
+    }
+
+    /// The results of a call, which is a conversion when the callee names a type
+    pub(crate) fn eval_call(&mut self, call: &Expression) -> Result<Vec<Value>, RuntimeError> {
+        let ExpressionKind::FunctionCall {
+            arguments, spread, ..
+        } = &call.kind
+        else {
+            return Err(unsupported(call));
+        };
+        match self.callee(call)? {
+            Callee::Builtin(name) => self.call_builtin(&name, arguments, *spread, call),
+            _ if *spread => Err(unsupported(call)),
+            callee => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.eval_expression(argument))
+                    .collect::<Result<_, _>>()?;
+                self.call_callee(callee, arguments, call)
+            }
+        }
+    }
+
+    /// What `call` calls. A package's name is shadowed by a variable of the same name, and
+    /// builtins and types like any other predeclared name.
+    fn callee(&mut self, call: &Expression) -> Result<Callee, RuntimeError> {
+        let ExpressionKind::FunctionCall {
+            name: callee,
+            arguments,
+            ..
+        } = &call.kind
+        else {
+            return Err(unsupported(call));
+        };
+        let name = match &callee.kind {
+            ExpressionKind::Identifier(name) => name,
+            ExpressionKind::FieldAccess { object, field, .. } => {
+                if let Some(path) = self.package_path(object) {
+                    return Ok(Callee::Package {
+                        path,
+                        function: field.clone(),
+                    });
+                }
+                // A variable's method is called on its address, so one with a pointer receiver
+                // can change it
+                let receiver = match self.operand(object)? {
+                    Operand::Place(pointer) => {
+                        match pointer
+                            .inspect(|value| matches!(value, Value::Struct(_) | Value::Named(_)))
+                        {
+                            Some(true) => Value::Pointer(pointer),
+                            _ => pointer.load().unwrap_or(Value::Nil),
+                        }
+                    }
+                    Operand::Value(value) => value,
+                };
+                return Ok(Callee::Method {
+                    receiver,
+                    method: field.clone(),
+                });
+            }
+            // Any other callee, like `func() { ... }` or `handlers[i]`, is called with the
+            // function it evaluates to
+            _ => return function_callee(callee, self.eval_expression(callee)?),
+        };
+        if let Some(value) = self.lookup(name) {
+            return function_callee(callee, value);
+        }
+        let predeclared = !self.functions.contains_key(name);
+        if predeclared && builtins::BUILTINS.contains(&name.as_str()) {
+            return Ok(Callee::Builtin(name.clone()));
+        }
+        if predeclared && arguments.len() == 1 && self.is_type_name(name) {
+            let target = TypeExpression::new_name(name.clone(), callee.position_start);
+            return Ok(Callee::Conversion(target));
+        }
+        Ok(Callee::Function(name.clone()))
+    }
+
+    /// Call `callee` with its arguments' values
+    fn call_callee(
+        &mut self,
+        callee: Callee,
+        arguments: Vec<Value>,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let expressions = match &call.kind {
+            ExpressionKind::FunctionCall { arguments, .. } => arguments.as_slice(),
+            _ => &[],
+        };
+        match (callee, arguments.as_slice()) {
+            (Callee::Function(name), _) => {
+                self.call_function(&name, arguments, call.position_start)
+            }
+            (Callee::Builtin(name), _) => {
+                self.apply_builtin(&name, expressions, arguments, false, call)
+            }
+            (Callee::Conversion(target), [value]) => {
+                let value = value.clone();
+                Ok(vec![self.convert_to_type(
+                    value,
+                    &target,
+                    &expressions[0],
+                )?])
+            }
+            (Callee::Package { path, function }, _) => {
+                self.call_package_function(&path, &function, arguments, call)
+            }
+            (Callee::Method { receiver, method }, _) => {
+                self.call_method(receiver, &method, arguments, call)
+            }
+            (Callee::Closure(closure), _) => {
+                self.call_function_value(&Value::Closure(closure), arguments, call.position_start)
+            }
+            _ => Err(unsupported(call)),
+        }
+    }
+
+    /// Call `method` of `receiver`, or of the value it points to. A struct's field holding a
+    /// function is called like a method.
+    fn call_method(
+        &mut self,
+        receiver: Value,
+        method: &str,
+        arguments: Vec<Value>,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        if let Some((receiver, declaration)) = self.method(&receiver, method) {
+            return self.call_declared_method(&declaration, receiver, arguments, call);
+        }
+        let receiver = match receiver {
+            Value::Pointer(pointer) => dereference(Value::Pointer(pointer), call)?
+                .load()
+                .unwrap_or(Value::Nil),
+            receiver => receiver,
+        };
+        if let Value::Struct(_) = receiver {
+            let pointer = Pointer::new(pointer_type(&receiver, call.position_start), receiver);
+            return match select_field(&pointer, method).and_then(|field| field.load()) {
+                Some(function @ (Value::Function(_) | Value::Closure(_))) => {
+                    self.call_function_value(&function, arguments, call.position_start)
+                }
+                _ => Err(unsupported(call)),
+            };
+        }
+        match (receiver, method, arguments.as_slice()) {
+            (Value::Error(error), "Error", []) => Ok(vec![Value::String(error.message.clone())]),
+            (Value::Native(Native::Scanner(scanner)), _, _) => {
+                let arguments = Arguments::new(callee_name(call), &arguments, call);
+                bufio::call_method(&scanner, method, &arguments)
+            }
+            (Value::Native(Native::Time(receiver)), _, _) => {
+                let arguments = Arguments::new(callee_name(call), &arguments, call);
+                time::call_time_method(&receiver, method, &arguments)
+            }
+            (Value::Duration(duration), _, _) => {
+                let arguments = Arguments::new(callee_name(call), &arguments, call);
+                time::call_duration_method(duration, method, &arguments)
+            }
+            (Value::Native(Native::Test(test)), _, _) => {
+                self.call_test_method(&test, method, arguments, call)
+            }
+            (Value::Native(native), _, _) => {
+                self.call_sync_method(&native, method, arguments, call)
+            }
+            _ => Err(unsupported(call)),
+        }
+    }
+
+    /// The method `name` of `receiver`'s type, if it declares one, and the receiver to call it
+    /// with. A struct's methods include those promoted from its embedded fields, which are
+    /// called with the embedded field as the receiver.
+    fn method(&self, receiver: &Value, name: &str) -> Option<(Value, Rc<Declaration>)> {
+        let type_name = match receiver {
+            Value::Struct(value) => value.struct_type.name.as_deref(),
+            Value::Named(named) => Some(&*named.name),
+            Value::Pointer(pointer) => match &pointer.target_type.kind {
+                TypeExpressionKind::Name(type_name) => Some(type_name.as_str()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let method = type_name
+            .and_then(|type_name| self.methods.get(type_name))
+            .and_then(|methods| methods.get(name));
+        if let Some(method) = method {
+            return Some((receiver.clone(), method.clone()));
+        }
+        let value = match receiver {
+            Value::Struct(value) => value.clone(),
+            Value::Pointer(pointer) => match pointer.load()? {
+                Value::Struct(value) => value,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        value
+            .struct_type
+            .fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| field.embedded)
+            .find_map(|(index, field)| {
+                let embedded = match (receiver, &value.fields[index]) {
+                    (Value::Pointer(pointer), Value::Struct(_)) => {
+                        Value::Pointer(pointer.field(field.type_expression.clone(), index))
+                    }
+                    (_, embedded) => embedded.clone(),
+                };
+                self.method(&embedded, name)
+            })
+    }
+
+    /// The method `name` of what `object` points to bound to its receiver, as `f := c.F` makes.
+    /// A value receiver is copied now, so changes to what it was copied from aren't seen.
+    fn method_value(&self, object: Pointer, name: &str) -> Option<Value> {
+        let (receiver, method) = self.method(&Value::Pointer(object), name)?;
+        let pointer_receiver = matches!(&method.kind, DeclarationKind::Function {
+            receiver: Some(receiver), ..
+        } if matches!(receiver.type_expression.kind, TypeExpressionKind::Pointer(_)));
+        let receiver = match receiver {
+            Value::Pointer(pointer) if !pointer_receiver => pointer.load()?,
+            receiver => receiver,
+        };
+        Some(Value::Method(method, Box::new(receiver)))
+    }
+
+    /// Call a method declared in the program. One with a value receiver gets a copy of what a
+    /// pointer receiver points to, and one with a pointer receiver a pointer to a copy of a
+    /// value that isn't a place.
+    fn call_declared_method(
+        &mut self,
+        method: &Declaration,
+        receiver: Value,
+        arguments: Vec<Value>,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let DeclarationKind::Function {
+            receiver: Some(parameter),
+            name,
+            ..
+        } = &method.kind
+        else {
+            return Err(unsupported(call));
+        };
+        let (receiver, frame) = match (&parameter.type_expression.kind, receiver) {
+            (TypeExpressionKind::Pointer(base_type), Value::Pointer(pointer)) => (
+                Value::Pointer(pointer),
+                format!("(*{}).{}", base_type, name),
+            ),
+            (TypeExpressionKind::Pointer(base_type), receiver) => {
+                let target_type = pointer_type(&receiver, call.position_start);
+                let pointer = Pointer::new(target_type, receiver);
+                (
+                    Value::Pointer(pointer),
+                    format!("(*{}).{}", base_type, name),
+                )
+            }
+            (_, Value::Pointer(pointer)) => {
+                let receiver = dereference(Value::Pointer(pointer), call)?;
+                let receiver = receiver.load().unwrap_or(Value::Nil);
+                (receiver, format!("{}.{}", parameter.type_expression, name))
+            }
+            (_, receiver) => (receiver, format!("{}.{}", parameter.type_expression, name)),
+        };
+        self.call_declaration(
+            method,
+            &frame,
+            Some(receiver),
+            None,
+            arguments,
+            call.position_start,
+        )
+    }
+
+    /// Call the function a function value holds: a package level function or a closure
+    pub(crate) fn call_function_value(
+        &mut self,
+        function: &Value,
+        arguments: Vec<Value>,
+        position: Position,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        match function {
+            Value::Function(function) => {
+                self.call_function(function_name(function), arguments, position)
+            }
+            Value::Closure(closure) => self.call_declaration(
+                &closure.function,
+                closure.name(),
+                None,
+                Some(closure.captured.clone()),
+                arguments,
+                position,
+            ),
+            value => Err(RuntimeError::new(
+                RuntimeErrorKind::NonFunction {
+                    value: value.to_string(),
+                    value_type: value.type_name(),
+                },
+                position,
+            )),
+        }
+    }
+
+    /// `call` ready to be deferred or run in a new goroutine by `statement`, with its callee and
+    /// arguments evaluated now (https://go.dev/ref/spec#Defer_statements)
+    fn pending_call(
+        &mut self,
+        call: &Expression,
+        statement: &str,
+    ) -> Result<PendingCall, RuntimeError> {
+        let ExpressionKind::FunctionCall {
+            arguments, spread, ..
+        } = &call.kind
+        else {
+            return Err(unsupported(call));
+        };
+        let callee = self.callee(call)?;
+        // Only builtins without results can be deferred, and conversions can't
+        let discarded = match &callee {
+            Callee::Builtin(name) => !matches!(
+                name.as_str(),
+                "close" | "copy" | "delete" | "panic" | "recover"
+            ),
+            Callee::Conversion(_) => true,
+            _ => false,
+        };
+        if discarded {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::DiscardedResult {
+                    statement: statement.to_string(),
+                    call: call.to_string(),
+                },
+                call.position_start,
+            ));
+        }
+        if *spread {
+            return Err(unsupported(call));
+        }
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.eval_expression(argument))
+            .collect::<Result<_, _>>()?;
+        Ok(PendingCall {
+            callee,
+            arguments,
+            call: call.clone(),
+        })
+    }
+
+    /// Whether the calls in progress have taken more of the host's stack than they may, which
+    /// is measured from where it was when the outermost one started
+    fn stack_exhausted(&mut self) -> bool {
+        let Some(max_stack) = self.max_stack else {
+            return false;
+        };
+        let marker = 0u8;
+        let here = std::hint::black_box(&marker) as *const u8 as usize;
+        if self.depth == 0 {
+            self.stack_base = here;
+        }
+        self.stack_base.abs_diff(here) > max_stack
+    }
+
+    /// Start a goroutine making `call` (https://go.dev/ref/spec#Go_statements)
+    fn go(&mut self, call: PendingCall) -> Result<(), RuntimeError> {
+        let position = call.call.position_start;
+        let goroutine = Goroutine {
+            evaluator: self.goroutine_evaluator(),
+            call,
+        };
+        let mut stack_size = self
+            .max_depth
+            .saturating_add(1)
+            .saturating_mul(GOROUTINE_STACK_PER_CALL);
+        // Room for the stack the calls may take, and for what runs between the checks on it
+        if let Some(max_stack) = self.max_stack {
+            stack_size = stack_size.min(max_stack.saturating_mul(2));
+        }
+        self.scheduler
+            .spawn(stack_size, move |id| goroutine.run(id))
+            .map_err(|error| {
+                RuntimeError::new(
+                    RuntimeErrorKind::CannotStartGoroutine(error.to_string()),
+                    position,
+                )
+            })
+    }
+
+    /// An evaluator for a new goroutine, sharing this one's package level variables, standard
+    /// streams and scheduler
+    fn goroutine_evaluator(&mut self) -> Evaluator {
+        Evaluator {
+            globals: self.globals.clone(),
+            scopes: Vec::new(),
+            functions: self.functions.clone(),
+            methods: self.methods.clone(),
+            types: self.types.clone(),
+            packages: self.packages.clone(),
+            host_functions: self.host_functions.clone(),
+            output: self.output.clone(),
+            error_output: self.error_output.clone(),
+            input: self.input.clone(),
+            args: self.args.clone(),
+            scheduler: self.scheduler.clone(),
+            // Given its own once the goroutine starts
+            goroutine: MAIN_GOROUTINE,
+            steps: 0,
+            depth: 0,
+            frame: Frame::default(),
+            max_depth: self.max_depth,
+            max_stack: self.max_stack,
+            stack_base: 0,
+            defers: Vec::new(),
+            panicking: None,
+            recoverer: None,
+            deterministic: self.deterministic,
+            random: self.next_random() | 1,
+            budget: self.budget.clone(),
+            observer: self.observer.clone(),
+        }
+    }
+
+    /// Count `bytes` towards the program's allocation limit
+    pub(crate) fn allocate(&self, bytes: usize, position: Position) -> Result<(), RuntimeError> {
+        self.budget.allocate(bytes, position)
+    }
+
+    /// End the program once main has returned, unwinding any goroutines still running
+    pub fn exit(&self) {
+        self.scheduler.exit();
+    }
+
+    /// Wait for another goroutine to change something this one is waiting for
+    pub(crate) fn block(&self, position: Position) -> Result<(), RuntimeError> {
+        self.scheduler
+            .block(self.goroutine)
+            .map_err(|interruption| interrupted(interruption, position))
+    }
+
+    /// time.Sleep: let the other goroutines run for at least the duration
+    fn sleep(&self, arguments: &Arguments) -> Result<Vec<Value>, RuntimeError> {
+        arguments.expect(1)?;
+        let duration = arguments.duration(0)?;
+        if duration > 0 {
+            let mut until = Instant::now() + Duration::from_nanos(duration as u64);
+            // Waking at the time limit to end the program, rather than sleeping past it
+            if let Some(deadline) = self.budget.deadline() {
+                until = until.min(deadline);
+            }
+            self.scheduler
+                .sleep(self.goroutine, until)
+                .map_err(|interruption| interrupted(interruption, arguments.position()))?;
+            self.budget.check_time(arguments.position())?;
+        }
+        Ok(Vec::new())
+    }
+
+    /// Wake the goroutines waiting for something to change, after a change one of them may be
+    /// waiting for
+    pub(crate) fn wake_blocked(&self) {
+        self.scheduler.wake_blocked();
+    }
+
+    /// `channel <- value`
+    fn exec_send(
+        &mut self,
+        channel: &Expression,
+        value: &Expression,
+        position: Position,
+    ) -> Result<(), RuntimeError> {
+        let channel_value = self.eval_expression(channel)?;
+        let channel_value = as_channel(channel, channel_value, "send to")?;
+        let value = self.eval_expression(value)?;
+        self.send(&channel_value, value, position)
+    }
+
+    /// `<-channel`, and whether a value was sent rather than the channel closed
+    fn eval_receive(
+        &mut self,
+        channel: &Expression,
+        position: Position,
+    ) -> Result<(Value, bool), RuntimeError> {
+        let channel_value = self.eval_expression(channel)?;
+        let channel_value = as_channel(channel, channel_value, "receive from")?;
+        self.receive(&channel_value, position)
+    }
+
+    /// Send `value` on `channel`, waiting until a receiver takes it or, for a buffered
+    /// channel, there's room in the buffer (https://go.dev/ref/spec#Send_statements)
+    fn send(
+        &mut self,
+        channel: &Channel,
+        value: Value,
+        position: Position,
+    ) -> Result<(), RuntimeError> {
+        let send_on_closed = || RuntimeError::new(RuntimeErrorKind::SendOnClosedChannel, position);
+        // Sending on a nil channel waits forever
+        let Some(state) = &channel.state else {
+            loop {
+                self.block(position)?;
+            }
+        };
+        let value = self.assigned_value(value, &channel.element);
+        // Values past the buffer's capacity belong to senders waiting for a receiver
+        let sequence = {
+            let mut state = state.borrow_mut();
+            if state.closed {
+                return Err(send_on_closed());
+            }
+            state.buffer.push_back(value);
+            state.sent += 1;
+            state.sent
+        };
+        self.scheduler.wake_blocked();
+        loop {
+            {
+                let state = state.borrow();
+                if sequence <= state.received + state.capacity {
+                    return Ok(());
+                }
+                // Closing the channel dropped the value
+                if state.closed {
+                    return Err(send_on_closed());
+                }
+            }
+            self.block(position)?;
+        }
+    }
+
+    /// Receive from `channel`, waiting until a value is sent or it's closed, and whether one
+    /// was: a closed channel gives what's left in its buffer, then zero values
+    /// (https://go.dev/ref/spec#Receive_operator)
+    fn receive(
+        &mut self,
+        channel: &Channel,
+        position: Position,
+    ) -> Result<(Value, bool), RuntimeError> {
+        if let Some(received) = self.poll_receive(channel) {
+            return Ok(received);
+        }
+        self.count_receiver(channel, true);
+        let received = loop {
+            if let Some(received) = self.poll_receive(channel) {
+                break Ok(received);
+            }
+            if let Err(error) = self.block(position) {
+                break Err(error);
+            }
+        };
+        self.count_receiver(channel, false);
+        received
+    }
+
+    /// Receive from `channel` if that needn't wait
+    fn poll_receive(&self, channel: &Channel) -> Option<(Value, bool)> {
+        let mut state = channel.state.as_ref()?.borrow_mut();
+        let Some(value) = state.buffer.pop_front() else {
+            return state
+                .closed
+                .then(|| (self.zero_value(&channel.element), false));
+        };
+        state.received += 1;
+        // The sender may be waiting for the value to be taken
+        self.scheduler.wake_blocked();
+        Some((value, true))
+    }
+
+    /// Start or stop counting a receiver waiting on `channel`, which lets a select send to it
+    /// when it's unbuffered
+    fn count_receiver(&self, channel: &Channel, waiting: bool) {
+        let Some(state) = &channel.state else {
+            return;
+        };
+        let mut state = state.borrow_mut();
+        if waiting {
+            state.receivers += 1;
+            self.scheduler.wake_blocked();
+        } else {
+            state.receivers -= 1;
+        }
+    }
+
+    /// Close `channel`. Receivers get what's left in its buffer and then zero values, and
+    /// senders still waiting for a receiver fail (https://go.dev/ref/spec#Close).
+    pub(crate) fn close(&self, channel: &Channel, position: Position) -> Result<(), RuntimeError> {
+        let Some(state) = &channel.state else {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::CloseOfNilChannel,
+                position,
+            ));
+        };
+        let mut state = state.borrow_mut();
+        if state.closed {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::CloseOfClosedChannel,
+                position,
+            ));
+        }
+        state.closed = true;
+        let capacity = state.capacity;
+        state.buffer.truncate(capacity);
+        self.scheduler.wake_blocked();
+        Ok(())
+    }
+
+    /// Run the clause of a select whose communication can go ahead, picked at random when
+    /// several can, or else the default. Without one it waits until a communication can.
+    /// (https://go.dev/ref/spec#Select_statements)
+    fn exec_select(
+        &mut self,
+        cases: &[SelectCase],
+        position: Position,
+    ) -> Result<Flow, RuntimeError> {
+        // Channels and values to send are evaluated once, in source order
+        let mut communications = Vec::with_capacity(cases.len());
+        for case in cases {
+            communications.push(match &case.communication {
+                Some(communication) => Some(self.communication(communication)?),
+                None => None,
+            });
+        }
+        let default = cases.iter().position(|case| case.communication.is_none());
+        let mut waiting = false;
+        let chosen = loop {
+            let ready: Vec<usize> = communications
+                .iter()
+                .enumerate()
+                .filter(|(_, communication)| {
+                    communication
+                        .as_ref()
+                        .is_some_and(|communication| communication.is_ready())
+                })
+                .map(|(index, _)| index)
+                .collect();
+            if !ready.is_empty() {
+                let pick = if self.deterministic {
+                    0
+                } else {
+                    (self.next_random() % ready.len() as u64) as usize
+                };
+                break Ok(ready[pick]);
+            }
+            if let Some(default) = default {
+                break Ok(default);
+            }
+            if !waiting {
+                waiting = true;
+                self.count_select_receivers(&communications, true);
+            }
+            if let Err(error) = self.block(position) {
+                break Err(error);
+            }
+        };
+        if waiting {
+            self.count_select_receivers(&communications, false);
+        }
+        let chosen = chosen?;
+        let case = &cases[chosen];
+
+        // The clause is a block of its own, where `v, ok := <-ch` declares its variables
+        self.scopes.push(HashMap::new());
+        let flow = self.exec_select_case(case, communications.swap_remove(chosen));
+        self.scopes.pop();
+        match flow? {
+            // A break in a select leaves the select
+            Flow::Break => Ok(Flow::Normal),
+            flow => Ok(flow),
+        }
+    }
+
+    /// Start or stop counting a select waiting on each channel it receives from as a receiver
+    fn count_select_receivers(&self, communications: &[Option<Communication>], waiting: bool) {
+        for communication in communications.iter().flatten() {
+            if let Communication::Receive(channel) = communication {
+                self.count_receiver(channel, waiting);
+            }
+        }
+    }
+
+    /// Carry out the chosen clause's communication, assigning what it received, then run its
+    /// statements
+    fn exec_select_case(
+        &mut self,
+        case: &SelectCase,
+        communication: Option<Communication>,
+    ) -> Result<Flow, RuntimeError> {
+        match (communication, case.communication.as_deref()) {
+            (Some(Communication::Send { channel, value }), Some(statement)) => {
+                self.send(&channel, value, statement.position_start)?;
+            }
+            (Some(Communication::Receive(channel)), Some(statement)) => {
+                let (value, received) = self.receive(&channel, statement.position_start)?;
+                if let StatementKind::Assignment { left, define, .. } = &statement.kind {
+                    let values = [value, Value::Bool(received)];
+                    for (target, value) in left.iter().zip(values) {
+                        self.exec_assignment(target, value, *define)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.exec_statements(&case.body)
+    }
+
+    /// A select case's send or receive, with its operands evaluated
+    fn communication(&mut self, statement: &Statement) -> Result<Communication, RuntimeError> {
+        let receive = match &statement.kind {
+            StatementKind::Send { channel, value } => {
+                let channel_value = self.eval_expression(channel)?;
+                let channel_value = as_channel(channel, channel_value, "send to")?;
+                let value = self.eval_expression(value)?;
+                return Ok(Communication::Send {
+                    channel: channel_value,
+                    value,
+                });
+            }
+            StatementKind::Expression(expression) => Some(expression),
+            StatementKind::Assignment { right, .. } => right.first(),
+            _ => None,
+        };
+        let Some(channel) = receive.and_then(received_channel) else {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::Unsupported(statement.to_string()),
+                statement.position_start,
+            ));
+        };
+        let channel_value = self.eval_expression(channel)?;
+        Ok(Communication::Receive(as_channel(
+            channel,
+            channel_value,
+            "receive from",
+        )?))
+    }
+
+    /// The value converted to `target` (https://go.dev/ref/spec#Conversions)
+    fn convert_to_type(
+        &self,
+        value: Value,
+        target: &TypeExpression,
+        operand: &Expression,
+    ) -> Result<Value, RuntimeError> {
+        if let Some((name, declared)) = self.named_type(target) {
+            let converted = self.convert_to_type(value, declared, operand)?;
+            return Ok(Value::Named(Named::new(name, converted)));
+        }
+        let value = value.into_underlying();
+        if let TypeExpressionKind::Name(name) = &target.kind
+            && let Some(basic) = self.basic_type_name(name)
+        {
+            return convert(value, basic, operand, name);
+        }
+        let converted = match (&self.underlying(target).kind, &value) {
+            // []byte("hi") is the string's bytes and []rune("hi") its code points
+            (TypeExpressionKind::Slice(element), Value::String(string)) => {
+                let elements: Vec<Value> = match &element.kind {
+                    TypeExpressionKind::Name(name) => match self.basic_type_name(name) {
+                        Some("byte" | "uint8") => string
+                            .bytes()
+                            .map(|byte| Value::Int(i64::from(byte)))
+                            .collect(),
+                        Some("rune" | "int32") => string
+                            .chars()
+                            .map(|character| Value::Int(i64::from(u32::from(character))))
+                            .collect(),
+                        _ => return Err(cannot_convert(&value, operand, target)),
+                    },
+                    _ => return Err(cannot_convert(&value, operand, target)),
+                };
+                let len = elements.len();
+                self.allocate(len * ELEMENT_SIZE, operand.position_start)?;
+                Value::Slice(Slice::new(Rc::new((**element).clone()), elements, len))
+            }
+            (TypeExpressionKind::Slice(element), Value::Slice(slice)) => Value::Slice(Slice {
+                element: Rc::new((**element).clone()),
+                ..slice.clone()
+            }),
+            (_, Value::Nil) => self.zero_value(target),
+            // Between struct types with the same fields
+            (TypeExpressionKind::Struct(_), Value::Struct(from)) => {
+                match self.struct_type(target) {
+                    Some(struct_type) if struct_type.fields.len() == from.fields.len() => {
+                        Value::Struct(Struct {
+                            struct_type: Rc::new(struct_type),
+                            fields: from.fields.clone(),
+                        })
+                    }
+                    _ => return Err(cannot_convert(&value, operand, target)),
+                }
+            }
+            (TypeExpressionKind::Map { .. }, Value::Map(_))
+            | (TypeExpressionKind::Channel { .. }, Value::Channel(_))
+            | (TypeExpressionKind::Pointer(_), Value::Pointer(_)) => value,
+            (TypeExpressionKind::Name(name), Value::Error(_)) if name == "error" => value,
+            _ => return Err(cannot_convert(&value, operand, target)),
+        };
+        Ok(converted)
+    }
+
+    /// Call a function of the imported package at `path`, which is native rather than Go,
+    /// counting the strings and slices it makes towards the allocation limit
+    fn call_package_function(
+        &mut self,
+        path: &str,
+        function: &str,
+        arguments: Vec<Value>,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        if let Some(observer) = &self.observer {
+            let name = format!("{}.{}", path, function);
+            observer
+                .borrow_mut()
+                .on_call(&name, &arguments, call.position_start);
+        }
+        let results = self.call_native_function(path, function, arguments, call)?;
+        let allocated = results.iter().map(allocated_size).sum();
+        self.allocate(allocated, call.position_start)?;
+        Ok(results)
+    }
+
+    fn call_native_function(
+        &mut self,
+        path: &str,
+        function: &str,
+        arguments: Vec<Value>,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let qualified = format!("{}.{}", path, function);
+        if let Some(host_function) = self
+            .host_functions
+            .get(path)
+            .and_then(|functions| functions.get(function))
+        {
+            let arguments = Arguments::new(qualified, &arguments, call).with_budget(&self.budget);
+            return host_function.call(&arguments);
+        }
+        if (path, function) == ("time", "Sleep") {
+            return self.sleep(&Arguments::new(qualified, &arguments, call));
+        }
+        if let Some(package) = stdlib::package(path) {
+            let arguments = Arguments::new(qualified, &arguments, call).with_budget(&self.budget);
+            return package(function, &arguments);
+        }
+        // The print functions write to standard output, and the Fprint ones to their first
+        // argument
+        let (writer, operands) = match (path, function) {
+            ("fmt", "Fprint" | "Fprintln" | "Fprintf") => {
+                let (writer, operands) = writer_argument(&qualified, &arguments, call)?;
+                (Some(writer), operands)
+            }
+            _ => (None, arguments.as_slice()),
+        };
+        // How the last of the arguments, which are the operands, print
+        let as_printed = self.as_printed(&arguments, call)?;
+        let as_printed = |operands: &[Value]| &as_printed[arguments.len() - operands.len()..];
+        let printed = match (path, function) {
+            ("fmt", "Print" | "Fprint") => fmt::sprint(as_printed(operands)),
+            ("fmt", "Println" | "Fprintln") => fmt::sprintln(as_printed(operands)),
+            ("fmt", "Printf" | "Fprintf") => {
+                let (format, operands) = format_arguments(&qualified, operands, call)?;
+                fmt::sprintf_printed(format, operands, as_printed(operands))
+            }
+            ("fmt", "Sprint") => {
+                return Ok(vec![Value::String(fmt::sprint(as_printed(&arguments)))]);
+            }
+            ("fmt", "Sprintln") => {
+                return Ok(vec![Value::String(fmt::sprintln(as_printed(&arguments)))]);
+            }
+            ("fmt", "Sprintf") => {
+                let (format, operands) = format_arguments(&qualified, &arguments, call)?;
+                let printed = fmt::sprintf_printed(format, operands, as_printed(operands));
+                return Ok(vec![Value::String(printed)]);
+            }
+            ("fmt", "Errorf") => {
+                let (format, operands) = format_arguments(&qualified, &arguments, call)?;
+                return Ok(vec![fmt::errorf(format, operands, as_printed(operands))]);
+            }
+            ("fmt", _) => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedName(qualified),
+                    call.position_start,
+                ));
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::Unsupported(call.to_string()),
+                    call.position_start,
+                ));
+            }
+        };
+        let output = match writer {
+            None => self.output.clone(),
+            Some(File::Stdout(output) | File::Stderr(output)) => output.clone(),
+            Some(File::Stdin(_)) => {
+                let error = "write /dev/stdin: bad file descriptor".to_string();
+                return Ok(vec![Value::Int(0), path_error(error)]);
+            }
+        };
+        // The print functions return the number of bytes written and any write error
+        let written = output.borrow_mut().0.write_all(printed.as_bytes());
+        Ok(match written {
+            Ok(()) => vec![Value::Int(printed.len() as i64), Value::Nil],
+            Err(error) => vec![Value::Int(0), path_error(error.to_string())],
+        })
+    }
+
+    /// Each of a print function's arguments as fmt prints it, which for a value whose type
+    /// declares an Error or String method is what the method returns, Error first as in Go
+    pub(crate) fn as_printed(
+        &mut self,
+        arguments: &[Value],
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let mut printed = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            let method = ["Error", "String"]
+                .iter()
+                .find_map(|name| self.method(argument, name))
+                .filter(|(_, method)| {
+                    matches!(&method.kind, DeclarationKind::Function { parameters, .. }
+                        if parameters.is_empty())
+                });
+            let Some((receiver, method)) = method.filter(|_| !argument.is_nil()) else {
+                printed.push(argument.clone());
+                continue;
+            };
+            let mut results = self.call_declared_method(&method, receiver, Vec::new(), call)?;
+            printed.push(match results.as_slice() {
+                [Value::String(_)] => results.remove(0),
+                _ => argument.clone(),
+            });
+        }
+        Ok(printed)
+    }
+
+    /// A value as a variable of the type holds it, like the untyped constant 1 as a float64, or
+    /// nil as a nil slice
+    pub(crate) fn assigned_value(&self, value: Value, type_expression: &TypeExpression) -> Value {
+        if value == Value::Nil {
+            return self.zero_value(type_expression);
+        }
+        if let Some((name, declared)) = self.named_type(type_expression) {
+            return match value {
+                Value::Named(named) if named.name == name => Value::Named(named),
+                value => Value::Named(Named::new(name, self.assigned_value(value, declared))),
+            };
+        }
+        if let TypeExpressionKind::Qualified { package, name } = &type_expression.kind
+            && let Value::Int(value) = value
+            && name == "Duration"
+            && self
+                .packages
+                .get(package)
+                .is_some_and(|path| path == "time")
+        {
+            return Value::Duration(value);
+        }
+        let TypeExpressionKind::Name(name) = &type_expression.kind else {
+            return value;
+        };
+        match (self.basic_type_name(name), value) {
+            (Some("float32" | "float64"), Value::Int(value)) => Value::Float(value as f64),
+            (_, value) => value,
+        }
+    }
+
+    /// The value a variable of the type has before anything is assigned to it
+    /// (https://go.dev/ref/spec#The_zero_value)
+    pub(crate) fn zero_value(&self, type_expression: &TypeExpression) -> Value {
+        if let Some((name, declared)) = self.named_type(type_expression) {
+            return Value::Named(Named::new(name, self.zero_value(declared)));
+        }
+        if let TypeExpressionKind::Name(name) = &type_expression.kind
+            && let Some(basic) = self.basic_type_name(name)
+        {
+            return match basic {
+                "string" => Value::String(String::new()),
+                "bool" => Value::Bool(false),
+                "float32" | "float64" => Value::Float(0.0),
+                _ => Value::Int(0),
+            };
+        }
+        match &self.underlying(type_expression).kind {
+            TypeExpressionKind::Slice(element) => {
+                Value::Slice(Slice::nil(Rc::new((**element).clone())))
+            }
+            TypeExpressionKind::Map { key, value } => Value::Map(Map::nil(
+                Rc::new((**key).clone()),
+                Rc::new((**value).clone()),
+            )),
+            TypeExpressionKind::Channel { element, .. } => {
+                Value::Channel(Channel::nil(Rc::new((**element).clone())))
+            }
+            TypeExpressionKind::Pointer(target) => {
+                Value::Pointer(Pointer::nil(Rc::new((**target).clone())))
+            }
+            TypeExpressionKind::Struct(_) => match self.struct_type(type_expression) {
+                Some(struct_type) => Value::Struct(Struct {
+                    fields: struct_type
+                        .fields
+                        .iter()
+                        .map(|field| self.zero_value(&field.type_expression))
+                        .collect(),
+                    struct_type: Rc::new(struct_type),
+                }),
+                None => Value::Nil,
+            },
+            TypeExpressionKind::Qualified { package, name } => self
+                .packages
+                .get(package)
+                .and_then(|path| package_zero_value(path, name))
+                .unwrap_or(Value::Nil),
+            _ => Value::Nil,
+        }
+    }
+
+    /// The type literal or predeclared type a declared type is defined as, like `[]int` for
+    /// `type Ints []int`
+    pub(crate) fn underlying<'a>(
+        &'a self,
+        type_expression: &'a TypeExpression,
+    ) -> &'a TypeExpression {
+        let mut underlying = type_expression;
+        // Bounded in case of a cycle like `type A B; type B A`, which the checker rejects
+        for _ in 0..=self.types.len() {
+            match &underlying.kind {
+                TypeExpressionKind::Name(name) if self.types.contains_key(name) => {
+                    underlying = &self.types[name];
+                }
+                _ => break,
+            }
+        }
+        underlying
+    }
+
+    /// The name of the declared type `type_expression` is, and what it's declared as, if its
+    /// values aren't structs or interfaces, like `Celsius` of `type Celsius float64`, whose
+    /// values keep the name for their methods
+    fn named_type<'a>(
+        &'a self,
+        type_expression: &TypeExpression,
+    ) -> Option<(Rc<str>, &'a TypeExpression)> {
+        let TypeExpressionKind::Name(name) = &type_expression.kind else {
+            return None;
+        };
+        let declared = self.types.get(name)?;
+        match &self.underlying(declared).kind {
+            TypeExpressionKind::Struct(_) | TypeExpressionKind::Interface(_) => None,
+            _ => Some((Rc::from(name.as_str()), declared)),
+        }
+    }
+
+    /// Whether `name` refers to an imported package
+    pub(crate) fn is_package(&self, name: &str) -> bool {
+        self.packages.contains_key(name)
+    }
+
+    /// Whether `name` is a predeclared or declared type
+    pub(crate) fn is_type_name(&self, name: &str) -> bool {
+        self.types.contains_key(name) || BASIC_TYPES.contains(&name) || name == "error"
+    }
+
+    /// The predeclared type `name` is, or is defined as, like `float64` for
+    /// `type Celsius float64`
+    fn basic_type_name(&self, name: &str) -> Option<&'static str> {
+        if let Some(type_expression) = self.types.get(name) {
+            return match &type_expression.kind {
+                TypeExpressionKind::Name(underlying) if underlying != name => {
+                    self.basic_type_name(underlying)
+                }
+                _ => None,
+            };
+        }
+        BASIC_TYPES.iter().find(|basic| **basic == name).copied()
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Evaluator {
+        Evaluator::new()
+    }
+}
+
+/// The type of a pointer to `value`, which is named after the value's type
+fn pointer_type(value: &Value, position: Position) -> Rc<TypeExpression> {
+    let name = match value {
+        Value::Struct(value) => match &value.struct_type.name {
+            Some(name) => name.clone(),
+            None => value.struct_type.type_name(),
+        },
+        Value::Named(named) => named.name.to_string(),
+        value => value.type_name(),
+    };
+    Rc::new(TypeExpression::new_name(name, position))
+}
+
+/// The pointer `value` is, to indirect through, which mustn't be nil
+fn dereference(value: Value, expression: &Expression) -> Result<Pointer, RuntimeError> {
+    match value {
+        Value::Pointer(pointer) if pointer.target.is_some() => Ok(pointer),
+        Value::Pointer(_) | Value::Nil => Err(RuntimeError::new(
+            RuntimeErrorKind::NilDereference,
+            expression.position_start,
+        )),
+        value => Err(RuntimeError::new(
+            RuntimeErrorKind::UndefinedOperator {
+                operation: expression.to_string(),
+                operator: "*".to_string(),
+                operand_type: value.type_name(),
+            },
+            expression.position_start,
+        )),
+    }
+}
+
+/// A pointer to the field `name` of the struct `object` points to, which may be promoted from
+/// an embedded field, or from what an embedded pointer points to
+fn select_field(object: &Pointer, name: &str) -> Option<Pointer> {
+    let struct_type = object
+        .inspect(|value| match value {
+            Value::Struct(value) => Some(value.struct_type.clone()),
+            _ => None,
+        })
+        .flatten()?;
+    if let Some(index) = struct_type.field(name) {
+        let field_type = struct_type.fields[index].type_expression.clone();
+        return Some(object.field(field_type, index));
+    }
+    struct_type
+        .fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| field.embedded)
+        .find_map(|(index, field)| {
+            let embedded = object.field(field.type_expression.clone(), index);
+            match embedded.load() {
+                Some(Value::Pointer(pointer)) => select_field(&pointer, name),
+                _ => select_field(&embedded, name),
+            }
+        })
+}
+
+/// The error for a struct literal's element that isn't one of its fields
+fn unknown_field(
+    struct_type: &StructType,
+    element: &KeyedElement,
+    literal: &Expression,
+) -> RuntimeError {
+    let kind = match &element.key {
+        Some(key) => RuntimeErrorKind::UnknownField {
+            field: key.to_string(),
+            type_name: struct_type.type_name(),
+        },
+        None => RuntimeErrorKind::TooManyValues(struct_type.type_name()),
+    };
+    RuntimeError::new(kind, literal.position_start)
+}
+
+/// The predeclared types values exist for. Complex numbers don't yet.
+pub(crate) const BASIC_TYPES: &[&str] = &[
+    "bool", "string", "int", "int8", "int16", "int32", "int64", "uint", "uint8", "uint16",
+    "uint32", "uint64", "uintptr", "byte", "rune", "float32", "float64",
+];
+
+/// `value` as it's stored over `current`: an untyped constant stored to a float is a float,
+/// and nil stored to a slice is a nil slice
+fn stored(current: &Value, value: Value) -> Value {
+    match (current, value) {
+        // An untyped constant stored to a value of a named type is of the type too
+        (Value::Named(current), value) if !matches!(value, Value::Named(_)) => {
+            match stored(&current.value, value) {
+                Value::Nil => Value::Nil,
+                value => Value::Named(Named::new(current.name.clone(), value)),
+            }
+        }
+        (Value::Float(_), Value::Int(value)) => Value::Float(value as f64),
+        (Value::Duration(_), Value::Int(value)) => Value::Duration(value),
+        (Value::Slice(slice), Value::Nil) => Value::Slice(Slice::nil(slice.element.clone())),
+        (Value::Map(map), Value::Nil) => Value::Map(Map::nil(map.key.clone(), map.value.clone())),
+        (Value::Channel(channel), Value::Nil) => {
+            Value::Channel(Channel::nil(channel.element.clone()))
+        }
+        (Value::Pointer(pointer), Value::Nil) => {
+            Value::Pointer(Pointer::nil(pointer.target_type.clone()))
+        }
+        (_, value) => value,
+    }
+}
+
+/// The value converted to the predeclared type `target` (https://go.dev/ref/spec#Conversions)
+fn convert(
+    value: Value,
+    target: &str,
+    operand: &Expression,
+    target_name: &str,
+) -> Result<Value, RuntimeError> {
+    // A time.Duration converts as the int64 it's defined as
+    let converted = match (target, &value) {
+        ("float32", Value::Int(value) | Value::Duration(value)) => {
+            Value::Float(*value as f32 as f64)
+        }
+        ("float32", Value::Float(value)) => Value::Float(*value as f32 as f64),
+        ("float64", Value::Int(value) | Value::Duration(value)) => Value::Float(*value as f64),
+        ("float64", Value::Float(_)) => value,
+        // string(65) is "A", and a value that isn't a code point is "�"
+        ("string", Value::Int(value)) => Value::String(rune(*value).to_string()),
+        // string(b) for a []byte is its bytes as UTF-8, and for a []rune its code points
+        ("string", Value::Slice(slice)) if slice.is_bytes() => Value::String(
+            String::from_utf8_lossy(
+                &slice
+                    .elements()
+                    .iter()
+                    .map(|element| as_int(element) as u8)
+                    .collect::<Vec<u8>>(),
+            )
+            .into_owned(),
+        ),
+        ("string", Value::Slice(slice)) => Value::String(
+            slice
+                .elements()
+                .iter()
+                .map(|element| rune(as_int(element)))
+                .collect(),
+        ),
+        ("string", Value::String(_)) | ("bool", Value::Bool(_)) => value,
+        // A float converted to an integer is truncated towards zero
+        (_, Value::Float(value)) if target != "string" && target != "bool" => {
+            Value::Int(wrap_integer(*value as i64, target))
+        }
+        (_, Value::Int(value) | Value::Duration(value))
+            if target != "string" && target != "bool" =>
+        {
+            Value::Int(wrap_integer(*value, target))
+        }
+        _ => {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::CannotConvert {
+                    value: operand.to_string(),
+                    value_type: value.type_name().to_string(),
+                    target_type: target_name.to_string(),
+                },
+                operand.position_start,
+            ));
+        }
+    };
+    Ok(converted)
+}
+
+/// The character an integer is the code point of, or "�" when it isn't one
+fn rune(value: i64) -> char {
+    u32::try_from(value)
+        .ok()
+        .and_then(char::from_u32)
+        .unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// An integer as the sized integer type `target` holds it, wrapping around like Go's
+/// conversions do
+fn wrap_integer(value: i64, target: &str) -> i64 {
+    match target {
+        "int8" => value as i8 as i64,
+        "int16" => value as i16 as i64,
+        "int32" | "rune" => value as i32 as i64,
+        "uint8" | "byte" => value as u8 as i64,
+        "uint16" => value as u16 as i64,
+        "uint32" => value as u32 as i64,
+        _ => value,
+    }
+}
+
+/// The format string a Printf-like function's first argument must be, and the operands after it
+pub(crate) fn format_arguments<'a>(
+    function: &str,
+    arguments: &'a [Value],
+    call: &Expression,
+) -> Result<(&'a str, &'a [Value]), RuntimeError> {
+    match arguments {
+        [Value::String(format), operands @ ..] => Ok((format, operands)),
+        [] => Err(RuntimeError::new(
+            RuntimeErrorKind::ArgumentCount {
+                function: function.to_string(),
+                have: 0,
+                want: 1,
+            },
+            call.position_start,
+        )),
+        [format, ..] => Err(RuntimeError::new(
+            RuntimeErrorKind::CannotUseArgument {
+                value_type: format.type_name().to_string(),
+                parameter_type: "string".to_string(),
+                function: function.to_string(),
+            },
+            call.position_start,
+        )),
+    }
+}
+
+/// The zero value of the type `name` the native package at `path` declares
+fn package_zero_value(path: &str, name: &str) -> Option<Value> {
+    match (path, name) {
+        ("sync", _) => Native::zero(name).map(Value::Native),
+        ("time", "Duration") => Some(Value::Duration(0)),
+        ("time", "Time") => Some(Value::Native(Native::Time(time::Time::zero()))),
+        _ => None,
+    }
+}
+
+/// The error of a failed operation on a file, like Go's `*fs.PathError`
+fn path_error(message: String) -> Value {
+    Value::Error(Rc::new(Error::new("*fs.PathError", message)))
+}
+
+/// The file an Fprint-like function's first argument must be, and the operands after it. Only
+/// the standard streams are writers so far.
+fn writer_argument<'a>(
+    function: &str,
+    arguments: &'a [Value],
+    call: &Expression,
+) -> Result<(&'a File, &'a [Value]), RuntimeError> {
+    match arguments {
+        [Value::Native(Native::File(file)), operands @ ..] => Ok((file, operands)),
+        [] => Err(RuntimeError::new(
+            RuntimeErrorKind::ArgumentCount {
+                function: function.to_string(),
+                have: 0,
+                want: 1,
+            },
+            call.position_start,
+        )),
+        [writer, ..] => Err(RuntimeError::new(
+            RuntimeErrorKind::CannotUseArgument {
+                value_type: writer.type_name(),
+                parameter_type: "io.Writer".to_string(),
+                function: function.to_string(),
+            },
+            call.position_start,
+        )),
+    }
+}
+
+/// What a range loop iterates over, producing each iteration's key and value as the loop
+/// reaches it
+enum Iterations {
+    /// An integer's or a string's, which are known up front
+    Fixed(std::vec::IntoIter<(Value, Value)>),
+    /// A slice's elements are read as they're reached, so the loop sees the body's stores to
+    /// later ones. Its length is fixed when the loop starts.
+    Slice { slice: Slice, next: usize },
+    /// A map's keys are fixed when the loop starts, but one deleted before it's reached is
+    /// skipped
+    Map {
+        map: Map,
+        keys: std::vec::IntoIter<Key>,
+    },
+    /// A channel's values are received by the loop itself, since that may wait on another
+    /// goroutine
+    Channel(Channel),
+}
+
+impl Iterator for Iterations {
+    type Item = (Value, Value);
+
+    fn next(&mut self) -> Option<(Value, Value)> {
+        match self {
+            Iterations::Fixed(iterations) => iterations.next(),
+            Iterations::Slice { slice, next } => {
+                let index = *next;
+                if index >= slice.len {
+                    return None;
+                }
+                *next += 1;
+                Some((Value::Int(index as i64), slice.get(index)))
+            }
+            Iterations::Map { map, keys } => {
+                let entries = map.entries.as_ref()?.borrow();
+                keys.find_map(|key| entries.get(&key).cloned())
+            }
+            Iterations::Channel(_) => None,
+        }
+    }
+}
+
+/// Go's checks of a slice expression's bounds against the length and capacity of what's
+/// sliced, in its order, with the bounds it reports when one fails. `limit` names what the
+/// capacity is: a string's is its length.
+fn slice_bounds(
+    [low, high, max]: [Option<i64>; 3],
+    len: usize,
+    cap: usize,
+    limit: &str,
+) -> Result<(usize, usize, usize), String> {
+    let (len, cap) = (len as i64, cap as i64);
+    let low = low.unwrap_or(0);
+    let high_or_len = high.unwrap_or(len);
+    if let Some(max) = max {
+        if !(0..=cap).contains(&max) {
+            return Err(format!("[::{}] with {} {}", max, limit, cap));
+        }
+        if !(0..=max).contains(&high_or_len) {
+            return Err(format!("[:{}:{}]", high_or_len, max));
+        }
+        if !(0..=high_or_len).contains(&low) {
+            return Err(format!("[{}:{}:]", low, high_or_len));
+        }
+        return Ok((low as usize, high_or_len as usize, max as usize));
+    }
+    if !(0..=cap).contains(&high_or_len) {
+        return Err(format!("[:{}] with {} {}", high_or_len, limit, cap));
+    }
+    if !(0..=high_or_len).contains(&low) {
+        return Err(format!("[{}:{}]", low, high_or_len));
+    }
+    Ok((low as usize, high_or_len as usize, cap as usize))
+}
+
+/// An index, which must be an integer, though a constant like 2.0 is one
+fn integer_index(index: &Expression, value: &Value) -> Result<i64, RuntimeError> {
+    match value.underlying() {
+        Value::Int(value) => Ok(*value),
+        Value::Float(value) if value.fract() == 0.0 => Ok(*value as i64),
+        _ => Err(RuntimeError::new(
+            RuntimeErrorKind::NonIntegerIndex {
+                index: index.to_string(),
+                index_type: value.type_name(),
+            },
+            index.position_start,
+        )),
+    }
+}
+
+/// An index of one of `length` elements or bytes
+fn element_index(index: &Expression, value: &Value, length: usize) -> Result<usize, RuntimeError> {
+    let i = integer_index(index, value)?;
+    usize::try_from(i)
+        .ok()
+        .filter(|&i| i < length)
+        .ok_or_else(|| {
+            RuntimeError::new(
+                RuntimeErrorKind::IndexOutOfRange { index: i, length },
+                index.position_start,
+            )
+        })
+}
+
+/// The key a map stores `value` under
+fn hash_key(index: &Expression, value: &Value) -> Result<Key, RuntimeError> {
+    value.key().ok_or_else(|| {
+        RuntimeError::new(
+            RuntimeErrorKind::UnhashableKey(value.type_name()),
+            index.position_start,
+        )
+    })
+}
+
+fn cannot_index(object: &Expression, value: &Value) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::CannotIndex {
+            value: object.to_string(),
+            value_type: value.type_name(),
+        },
+        object.position_start,
+    )
+}
+
+/// The channel `value` is, for `operation` like "send to" to act on
+fn as_channel(
+    expression: &Expression,
+    value: Value,
+    operation: &str,
+) -> Result<Channel, RuntimeError> {
+    match value {
+        Value::Channel(channel) => Ok(channel),
+        other => Err(RuntimeError::new(
+            RuntimeErrorKind::NonChannel {
+                operation: operation.to_string(),
+                value: expression.to_string(),
+                value_type: other.type_name(),
+            },
+            expression.position_start,
+        )),
+    }
+}
+
+/// The channel operand of `expression` when it's a receive like `<-ch`
+fn received_channel(expression: &Expression) -> Option<&Expression> {
+    match &expression.kind {
+        ExpressionKind::Receive(channel) => Some(channel),
+        ExpressionKind::Parenthesized(inner) => received_channel(inner),
+        _ => None,
+    }
+}
+
+/// The error a goroutine stops with when it can't carry on from waiting at `position`
+/// The name of a package level function
+/// What calling `value`, which `callee` evaluated to, calls. A variable holding a method value
+/// calls the method on the receiver it was bound to.
+fn function_callee(callee: &Expression, value: Value) -> Result<Callee, RuntimeError> {
+    match value.into_underlying() {
+        Value::Function(function) => Ok(Callee::Function(function_name(&function).to_string())),
+        Value::Method(method, receiver) => Ok(Callee::Method {
+            receiver: *receiver,
+            method: function_name(&method).to_string(),
+        }),
+        Value::Closure(closure) => Ok(Callee::Closure(closure)),
+        value => Err(RuntimeError::new(
+            RuntimeErrorKind::NonFunction {
+                value: callee.to_string(),
+                value_type: value.type_name(),
+            },
+            callee.position_start,
+        )),
+    }
+}
+
+fn function_name(function: &Declaration) -> &str {
+    match &function.kind {
+        DeclarationKind::Function { name, .. } => name,
+        _ => "",
+    }
+}
+
+/// The function or method a call calls, as written, like `wg.Add`
+pub(crate) fn callee_name(call: &Expression) -> String {
+    match &call.kind {
+        ExpressionKind::FunctionCall { name, .. } => name.to_string(),
+        _ => call.to_string(),
+    }
+}
+
+fn interrupted(interruption: Interruption, position: Position) -> RuntimeError {
+    match interruption {
+        Interruption::Deadlock => RuntimeError::new(RuntimeErrorKind::Deadlock, position),
+        Interruption::Failed(error) => error,
+        Interruption::Exiting => RuntimeError::new(RuntimeErrorKind::ProgramExited, position),
+    }
+}
+
+/// How an unrecovered panic prints its value: an error by its message, and anything else as
+/// the print builtin would, except that Go prints a composite's address rather than its
+/// elements
+pub(crate) fn panic_message(value: &Value) -> String {
+    match value {
+        Value::String(message) => message.clone(),
+        Value::Error(error) => error.message.clone(),
+        Value::Float(value) => print_float(*value),
+        Value::Int(_) | Value::Bool(_) => value.to_string(),
+        value => format!("({}) {}", value.type_name(), value),
+    }
+}
+
+/// A float as the print builtin writes it, like `+1.500000e+000`
+fn print_float(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "+Inf" } else { "-Inf" }.to_string();
+    }
+    let formatted = format!("{:.6e}", value);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let sign = if value.is_sign_negative() { "" } else { "+" };
+    let exponent_sign = if exponent < 0 { '-' } else { '+' };
+    format!(
+        "{}{}e{}{:03}",
+        sign,
+        mantissa,
+        exponent_sign,
+        exponent.abs()
+    )
+}
+
+fn unsupported(expression: &Expression) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::Unsupported(expression.to_string()),
+        expression.position_start,
+    )
+}
+
+/// The number of values a parameter list takes
+fn count_names(parameters: &[Parameter]) -> usize {
+    parameters
+        .iter()
+        .map(|parameter| parameter.names.len().max(1))
+        .sum()
+}
+
+/// What making `value` counts towards the allocation limit, for a native function's result
+fn allocated_size(value: &Value) -> usize {
+    match value {
+        Value::String(string) => string.len(),
+        Value::Slice(slice) => slice.cap * ELEMENT_SIZE,
+        _ => 0,
+    }
+}
+
+/// Mix a seed's bits, so that nearby seeds start the generator far apart (SplitMix64's
+/// finalizer)
+fn scramble(seed: u64) -> u64 {
+    let mut x = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// https://go.dev/ref/spec#Arithmetic_operators, https://go.dev/ref/spec#Comparison_operators
+pub(crate) fn binary(
+    operation: &Expression,
+    operator: Operator,
+    left: Value,
+    right: Value,
+) -> Result<Value, RuntimeError> {
+    let error = |kind| Err(RuntimeError::new(kind, operation.position_start));
+    let operand_type = left.type_name();
+    let value = match (&left, &right) {
+        (Value::Named(_), _) | (_, Value::Named(_)) => {
+            return named_binary(operation, operator, left, right);
+        }
+        (Value::Int(left), Value::Int(right)) => {
+            let (left, right) = (*left, *right);
+            if let Some(comparison) = compare(operator, left.partial_cmp(&right)) {
+                return Ok(Value::Bool(comparison));
+            }
+            // Integer arithmetic wraps around, as in Go
+            Value::Int(match operator {
+                Operator::Plus => left.wrapping_add(right),
+                Operator::Minus => left.wrapping_sub(right),
+                Operator::Star => left.wrapping_mul(right),
+                Operator::Slash | Operator::Percent if right == 0 => {
+                    return error(RuntimeErrorKind::DivisionByZero);
+                }
+                Operator::Slash => left.wrapping_div(right),
+                Operator::Percent => left.wrapping_rem(right),
+                Operator::Ampersand => left & right,
+                Operator::Pipe => left | right,
+                Operator::Caret => left ^ right,
+                Operator::AmpersandCaret => left & !right,
+                Operator::LessLess | Operator::GreaterGreater if right < 0 => {
+                    return error(RuntimeErrorKind::NegativeShift);
+                }
+                // Shifting every bit out leaves 0, or -1 for a negative value shifted right
+                Operator::LessLess if right >= 64 => 0,
+                Operator::LessLess => left << right,
+                Operator::GreaterGreater => left >> right.min(63),
+                _ => return undefined_operator(operation, operator, &operand_type),
+            })
+        }
+        // An untyped constant mixed with a Duration is a Duration, as with 2 * time.Second
+        (Value::Duration(_), Value::Duration(_) | Value::Int(_) | Value::Float(_))
+        | (Value::Int(_) | Value::Float(_), Value::Duration(_)) => {
+            return duration_binary(operation, operator, left, right);
+        }
+        // An untyped int constant mixed with a float is a float
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            let (left, right) = (as_float(&left), as_float(&right));
+            if let Some(comparison) = compare(operator, left.partial_cmp(&right)) {
+                return Ok(Value::Bool(comparison));
+            }
+            Value::Float(match operator {
+                Operator::Plus => left + right,
+                Operator::Minus => left - right,
+                Operator::Star => left * right,
+                // Float division by zero is infinite rather than a panic
+                Operator::Slash => left / right,
+                _ => return undefined_operator(operation, operator, &operand_type),
+            })
+        }
+        (Value::String(left), Value::String(right)) => {
+            if let Some(comparison) = compare(operator, left.partial_cmp(right)) {
+                return Ok(Value::Bool(comparison));
+            }
+            match operator {
+                Operator::Plus => Value::String(format!("{}{}", left, right)),
+                _ => return undefined_operator(operation, operator, &operand_type),
+            }
+        }
+        (Value::Bool(left), Value::Bool(right)) => Value::Bool(match operator {
+            Operator::AndAnd => *left && *right,
+            Operator::PipePipe => *left || *right,
+            Operator::EqualEqual => left == right,
+            Operator::BangEqual => left != right,
+            _ => return undefined_operator(operation, operator, &operand_type),
+        }),
+        // Only nil and nil slices, maps, channels and pointers are equal to nil, as with
+        // `err != nil`
+        (Value::Nil, _) | (_, Value::Nil) => Value::Bool(match operator {
+            Operator::EqualEqual => left.is_nil() && right.is_nil(),
+            Operator::BangEqual => !(left.is_nil() && right.is_nil()),
+            _ => return undefined_operator(operation, operator, &operand_type),
+        }),
+        // Slices and maps can only be compared to nil
+        (Value::Slice(_), Value::Slice(_)) | (Value::Map(_), Value::Map(_)) => {
+            return undefined_operator(operation, operator, &operand_type);
+        }
+        // An error compared to a value of a type declaring an Error method, which are
+        // different dynamic types of the error interface, is never equal to it
+        (Value::Channel(_), Value::Channel(_))
+        | (Value::Pointer(_), Value::Pointer(_))
+        | (Value::Struct(_), Value::Struct(_))
+        | (Value::Error(_), Value::Error(_) | Value::Pointer(_) | Value::Struct(_))
+        | (Value::Pointer(_) | Value::Struct(_), Value::Error(_)) => Value::Bool(match operator {
+            Operator::EqualEqual => left == right,
+            Operator::BangEqual => left != right,
+            _ => return undefined_operator(operation, operator, &operand_type),
+        }),
+        _ => {
+            return error(RuntimeErrorKind::MismatchedTypes {
+                operation: operation.to_string(),
+                left_type: left.type_name().to_string(),
+                right_type: right.type_name().to_string(),
+            });
+        }
+    };
+    Ok(value)
+}
+
+/// An operation on Durations, which is the operation on the int64s they're defined as. A
+/// float constant, like the 1.5 of `1.5 * time.Second`, makes a whole number of nanoseconds.
+fn duration_binary(
+    operation: &Expression,
+    operator: Operator,
+    left: Value,
+    right: Value,
+) -> Result<Value, RuntimeError> {
+    let untyped = |value: Value| match value {
+        Value::Duration(duration) => Value::Int(duration),
+        value => value,
+    };
+    Ok(
+        match binary(operation, operator, untyped(left), untyped(right))? {
+            Value::Int(duration) => Value::Duration(duration),
+            Value::Float(duration) => Value::Duration(duration as i64),
+            // A comparison
+            value => value,
+        },
+    )
+}
+
+/// An operation on values of a named type, which is the operation on their underlying type's,
+/// as with Durations. Its result is of the named type, but for a comparison's, and a shift's of
+/// an untyped constant. Values of different types in interfaces are never equal.
+fn named_binary(
+    operation: &Expression,
+    operator: Operator,
+    left: Value,
+    right: Value,
+) -> Result<Value, RuntimeError> {
+    let different_types = match (&left, &right) {
+        (Value::Named(left), Value::Named(right)) => left.name != right.name,
+        (Value::Named(_), other) | (other, Value::Named(_)) => {
+            matches!(
+                other,
+                Value::Error(_) | Value::Pointer(_) | Value::Struct(_)
+            )
+        }
+        _ => false,
+    };
+    match operator {
+        Operator::EqualEqual if different_types => return Ok(Value::Bool(false)),
+        Operator::BangEqual if different_types => return Ok(Value::Bool(true)),
+        _ => {}
+    }
+    let name = match (&left, &right, operator) {
+        (Value::Named(named), _, _) => named.name.clone(),
+        (_, _, Operator::LessLess | Operator::GreaterGreater) => {
+            return binary(operation, operator, left, right.into_underlying());
+        }
+        (_, Value::Named(named), _) => named.name.clone(),
+        _ => return binary(operation, operator, left, right),
+    };
+    let value = binary(
+        operation,
+        operator,
+        left.into_underlying(),
+        right.into_underlying(),
+    )?;
+    // A comparison's result is an untyped bool
+    Ok(match compare(operator, None) {
+        Some(_) => value,
+        None => Value::Named(Named::new(name, value)),
+    })
+}
+
+/// The result of a comparison operator, or None for other operators
+fn compare(operator: Operator, ordering: Option<std::cmp::Ordering>) -> Option<bool> {
+    use std::cmp::Ordering;
+    Some(match operator {
+        Operator::EqualEqual => ordering == Some(Ordering::Equal),
+        Operator::BangEqual => ordering != Some(Ordering::Equal),
+        Operator::Less => ordering == Some(Ordering::Less),
+        Operator::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Operator::Greater => ordering == Some(Ordering::Greater),
+        Operator::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        _ => return None,
+    })
+}
+
+/// A unary operation on a value that isn't of a named type
+fn unary(
+    expression: &Expression,
+    operator: UnaryOperator,
+    value: Value,
+) -> Result<Value, RuntimeError> {
+    match (operator, value) {
+        (UnaryOperator::Plus, value @ (Value::Int(_) | Value::Float(_))) => Ok(value),
+        (UnaryOperator::Plus, value @ Value::Duration(_)) => Ok(value),
+        (UnaryOperator::Minus, Value::Int(value)) => Ok(Value::Int(value.wrapping_neg())),
+        (UnaryOperator::Minus, Value::Float(value)) => Ok(Value::Float(-value)),
+        (UnaryOperator::Minus, Value::Duration(value)) => Ok(Value::Duration(value.wrapping_neg())),
+        (UnaryOperator::Not, Value::Bool(value)) => Ok(Value::Bool(!value)),
+        (UnaryOperator::Complement, Value::Int(value)) => Ok(Value::Int(!value)),
+        (_, value) => Err(RuntimeError::new(
+            RuntimeErrorKind::UndefinedOperator {
+                operation: expression.to_string(),
+                operator: operator.to_string(),
+                operand_type: value.type_name(),
+            },
+            expression.position_start,
+        )),
+    }
+}
+
+fn as_int(value: &Value) -> i64 {
+    match value {
+        Value::Int(value) => *value,
+        _ => 0,
+    }
+}
+
+fn as_float(value: &Value) -> f64 {
+    match value {
+        Value::Int(value) => *value as f64,
+        Value::Float(value) => *value,
+        _ => f64::NAN,
+    }
+}
+
+fn undefined_operator(
+    operation: &Expression,
+    operator: Operator,
+    operand_type: &str,
+) -> Result<Value, RuntimeError> {
+    Err(RuntimeError::new(
+        RuntimeErrorKind::UndefinedOperator {
+            operation: operation.to_string(),
+            operator: operator.to_string(),
+            operand_type: operand_type.to_string(),
+        },
+        operation.position_start,
+    ))
+}
+
+fn cannot_convert(value: &Value, operand: &Expression, target: &TypeExpression) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::CannotConvert {
+            value: operand.to_string(),
+            value_type: value.type_name(),
+            target_type: target.to_string(),
+        },
+        operand.position_start,
+    )
+}
+
+fn invalid_literal(literal: &Expression) -> RuntimeError {
+    RuntimeError::new(
+        RuntimeErrorKind::InvalidLiteral(literal.to_string()),
+        literal.position_start,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    fn eval(input: &str) -> Result<Value, String> {
+        let expression = Parser::parse_expression_str(input).unwrap();
+        Evaluator::new()
+            .eval_expression(&expression)
+            .map_err(|error| error.kind.to_string())
+    }
+
+    #[test]
+    fn evaluate_arithmetic() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(Value::Int(7)));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(Value::Int(9)));
+        assert_eq!(eval("7 / 2"), Ok(Value::Int(3)));
+        assert_eq!(eval("7 % 3"), Ok(Value::Int(1)));
+        assert_eq!(eval("7 / 2.0"), Ok(Value::Float(3.5)));
+        assert_eq!(eval("1 << 62 + 1 << 62"), Ok(Value::Int(i64::MIN)));
+        assert_eq!(eval("255 &^ 15 | 1"), Ok(Value::Int(241)));
+        assert_eq!(eval("'a' + 1"), Ok(Value::Int(98)));
+        assert_eq!(
+            eval("1 / 0"),
+            Err("runtime error: integer divide by zero".to_string())
+        );
+    }
+
+    #[test]
+    fn evaluate_strings_and_comparisons() {
+        assert_eq!(
+            eval("\"hello, \" + \"world\\n\""),
+            Ok(Value::String("hello, world\n".to_string()))
+        );
+        assert_eq!(eval("\"a\" < \"b\" && 2 >= 1.5"), Ok(Value::Bool(true)));
+        assert_eq!(eval("false && undefined"), Ok(Value::Bool(false)));
+        assert_eq!(eval("nil == nil"), Ok(Value::Bool(true)));
+        assert_eq!(
+            eval("1 + \"a\""),
+            Err("invalid operation: 1 + \"a\" (mismatched types int and string)".to_string())
+        );
+        assert_eq!(
+            eval("\"a\" - \"b\""),
+            Err("invalid operation: \"a\" - \"b\" (operator - not defined on string)".to_string())
+        );
+        assert_eq!(eval("x + 1"), Err("undefined: x".to_string()));
+    }
+
+    #[test]
+    fn evaluate_variables() {
+        let expression = Parser::parse_expression_str("x * 2").unwrap();
+        let mut evaluator = Evaluator::new();
+        evaluator.define("x", Value::Int(21));
+        assert_eq!(evaluator.eval_expression(&expression), Ok(Value::Int(42)));
+    }
+}
@@ -0,0 +1,460 @@
+use std::rc::Rc;
+
+use crate::eval::{
+    stdlib::strconv::format_float,
+    value::{Error, Value},
+};
+
+/// The operands formatted with `%v`, with spaces between operands when neither side is a
+/// string, like `fmt.Sprint`
+pub fn sprint(arguments: &[Value]) -> String {
+    let mut output = String::new();
+    for (i, argument) in arguments.iter().enumerate() {
+        if i > 0
+            && !matches!(argument.underlying(), Value::String(_))
+            && !matches!(arguments[i - 1].underlying(), Value::String(_))
+        {
+            output.push(' ');
+        }
+        output.push_str(&argument.to_string());
+    }
+    output
+}
+
+/// The operands formatted with `%v`, separated by spaces and ending in a newline, like
+/// `fmt.Sprintln`
+pub fn sprintln(arguments: &[Value]) -> String {
+    let operands: Vec<String> = arguments
+        .iter()
+        .map(|argument| argument.to_string())
+        .collect();
+    format!("{}\n", operands.join(" "))
+}
+
+/// `format` with its verbs replaced by the arguments, like `fmt.Sprintf`
+/// (https://pkg.go.dev/fmt#hdr-Printing). Mistakes are written into the output as Go does,
+/// e.g. `%!d(string=a)` for a string printed with `%d`.
+pub fn sprintf(format: &str, arguments: &[Value]) -> String {
+    format_operands(format, arguments, arguments, false).0
+}
+
+/// `sprintf` of arguments some of whose types have an Error or String method, which `printed`
+/// has each of them as instead: what the method returns. Verbs that print a value as a string
+/// print that, and the others the value itself, as `%T` does.
+pub fn sprintf_printed(format: &str, arguments: &[Value], printed: &[Value]) -> String {
+    format_operands(format, arguments, printed, false).0
+}
+
+/// The error `fmt.Errorf` makes, wrapping each error operand of a `%w` verb, where `printed`
+/// is as `sprintf_printed` takes it
+pub fn errorf(format: &str, arguments: &[Value], printed: &[Value]) -> Value {
+    let (message, wrapped) = format_operands(format, arguments, printed, true);
+    let type_name = match wrapped.len() {
+        0 => "*errors.errorString",
+        1 => "*fmt.wrapError",
+        _ => "*fmt.wrapErrors",
+    };
+    Value::Error(Rc::new(Error {
+        type_name,
+        message,
+        wrapped,
+    }))
+}
+
+/// `format` with its verbs replaced by the arguments, and the errors formatted with `%w`,
+/// which only fmt.Errorf, `wrapping`, accepts. An error of a type declared in the program is
+/// wrapped too, which its printed message tells apart from a value that isn't an error.
+fn format_operands(
+    format: &str,
+    arguments: &[Value],
+    printed: &[Value],
+    wrapping: bool,
+) -> (String, Vec<Value>) {
+    let mut output = String::new();
+    let mut wrapped = Vec::new();
+    let mut arguments = arguments.iter().zip(printed);
+    let mut chars = format.chars().peekable();
+    while let Some(character) = chars.next() {
+        if character != '%' {
+            output.push(character);
+            continue;
+        }
+        let mut spec = Spec::default();
+        while let Some(flag) = chars.next_if(|c| matches!(c, '-' | '+' | '#' | '0' | ' ')) {
+            match flag {
+                '-' => spec.minus = true,
+                '+' => spec.plus = true,
+                '#' => spec.sharp = true,
+                '0' => spec.zero = true,
+                _ => spec.space = true,
+            }
+        }
+        spec.width = number(&mut chars);
+        if chars.next_if_eq(&'.').is_some() {
+            spec.precision = Some(number(&mut chars).unwrap_or(0));
+        }
+        let Some(verb) = chars.next() else {
+            output.push_str("%!(NOVERB)");
+            break;
+        };
+        if verb == '%' {
+            output.push('%');
+            continue;
+        }
+        match arguments.next() {
+            Some((argument, printed)) if wrapping && verb == 'w' && is_error(argument, printed) => {
+                wrapped.push(argument.clone());
+                output.push_str(&format_verb('v', &spec, printed));
+            }
+            Some((_, printed)) if matches!(verb, 'v' | 's' | 'q' | 'x' | 'X') => {
+                output.push_str(&format_verb(verb, &spec, printed))
+            }
+            Some((argument, _)) => output.push_str(&format_verb(verb, &spec, argument)),
+            None => output.push_str(&format!("%!{}(MISSING)", verb)),
+        }
+    }
+    let extra: Vec<String> = arguments
+        .map(|(argument, printed)| format!("{}={}", argument.type_name(), printed))
+        .collect();
+    if !extra.is_empty() {
+        output.push_str(&format!("%!(EXTRA {})", extra.join(", ")));
+    }
+    (output, wrapped)
+}
+
+/// Whether an operand is an error: one errors.New or fmt.Errorf made, or a value of a type
+/// declared in the program, printed as what its Error method returned
+fn is_error(argument: &Value, printed: &Value) -> bool {
+    match argument {
+        Value::Error(_) => true,
+        Value::Struct(_) | Value::Pointer(_) | Value::Named(_) => {
+            matches!(printed, Value::String(_))
+        }
+        _ => false,
+    }
+}
+
+/// The flags, width and precision between a `%` and its verb
+#[derive(Debug, Default)]
+struct Spec {
+    minus: bool,
+    plus: bool,
+    sharp: bool,
+    zero: bool,
+    space: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+fn number(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<usize> {
+    let mut value = None;
+    while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+        value = Some(value.unwrap_or(0) * 10 + digit.to_digit(10).unwrap() as usize);
+    }
+    value
+}
+
+fn format_verb(verb: char, spec: &Spec, argument: &Value) -> String {
+    // A value of a named type prints as its underlying type's, as it does when its type has no
+    // String method
+    if let Value::Named(named) = argument
+        && verb != 'T'
+    {
+        return format_verb(verb, spec, &named.value);
+    }
+    let formatted = match (verb, argument) {
+        // A []byte prints like a string with %s, %q and %x
+        ('s' | 'q' | 'x' | 'X', Value::Slice(slice)) if slice.is_bytes() => {
+            let bytes: Vec<u8> = slice
+                .elements()
+                .iter()
+                .map(|element| match element {
+                    Value::Int(byte) => *byte as u8,
+                    _ => 0,
+                })
+                .collect();
+            let string = String::from_utf8_lossy(&bytes).into_owned();
+            return format_verb(verb, spec, &Value::String(string));
+        }
+        // Other verbs apply to each element of a slice, or each key and value of a map
+        ('T', _) => argument.type_name(),
+        // A Duration is an int64 with a String method, which %v and %s print it with
+        ('v' | 's', Value::Duration(_)) => argument.to_string(),
+        (_, Value::Duration(duration)) => {
+            return format_verb(verb, spec, &Value::Int(*duration));
+        }
+        (_, Value::Slice(slice)) => {
+            let elements: Vec<String> = slice
+                .elements()
+                .iter()
+                .map(|element| format_verb(verb, spec, element))
+                .collect();
+            return format!("[{}]", elements.join(" "));
+        }
+        (_, Value::Map(map)) => {
+            let entries: Vec<String> = map
+                .sorted_entries()
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "{}:{}",
+                        format_verb(verb, spec, key),
+                        format_verb(verb, spec, value)
+                    )
+                })
+                .collect();
+            return format!("map[{}]", entries.join(" "));
+        }
+        // %+v prints a struct's field names too
+        ('v', Value::Struct(value)) if spec.plus => {
+            let fields: Vec<String> = value
+                .struct_type
+                .fields
+                .iter()
+                .zip(&value.fields)
+                .map(|(field, value)| format!("{}:{}", field.name, format_verb('v', spec, value)))
+                .collect();
+            return format!("{{{}}}", fields.join(" "));
+        }
+        ('v', Value::Pointer(pointer)) if spec.plus => match pointer.load() {
+            Some(value @ Value::Struct(_)) => {
+                return format!("&{}", format_verb('v', spec, &value));
+            }
+            _ => argument.to_string(),
+        },
+        ('v', Value::String(value)) if spec.sharp => quote(value),
+        ('v', Value::Float(value)) if spec.precision.is_some() => {
+            format!("{:.*}", spec.precision.unwrap(), value)
+        }
+        ('v', Value::Int(value)) | ('d', Value::Int(value)) => signed(*value, spec),
+        ('v', _) => argument.to_string(),
+        ('s', Value::String(value)) => truncate(value, spec.precision),
+        ('s', Value::Error(error)) => truncate(&error.message, spec.precision),
+        ('q', Value::String(value)) => quote(value),
+        ('q', Value::Int(value)) => quote_rune(*value),
+        ('c', Value::Int(value)) => rune(*value).to_string(),
+        ('t', Value::Bool(value)) => value.to_string(),
+        ('x', Value::Int(value)) => hex(*value, spec),
+        ('X', Value::Int(value)) => hex(*value, spec).to_uppercase(),
+        ('x', Value::String(value)) => value.bytes().map(|b| format!("{:02x}", b)).collect(),
+        ('X', Value::String(value)) => value.bytes().map(|b| format!("{:02X}", b)).collect(),
+        ('b', Value::Int(value)) => format!("{:b}", value),
+        ('o', Value::Int(value)) => format!("{:o}", value),
+        ('f' | 'F', Value::Float(value)) => float(*value, spec.precision.unwrap_or(6), spec),
+        ('e', Value::Float(value)) => exponent(*value, spec.precision.unwrap_or(6)),
+        ('g' | 'G', Value::Float(value)) => {
+            let precision = spec.precision.map_or(-1, |precision| precision as i64);
+            format_float(*value, verb, precision, 64)
+        }
+        _ => {
+            return format!("%!{}({}={})", verb, argument.type_name(), argument);
+        }
+    };
+    pad(
+        formatted,
+        spec,
+        matches!(argument, Value::Int(_) | Value::Float(_)),
+    )
+}
+
+/// Pad to the spec's width, with zeros after any sign for a number with the `0` flag
+fn pad(formatted: String, spec: &Spec, numeric: bool) -> String {
+    let Some(width) = spec.width else {
+        return formatted;
+    };
+    let length = formatted.chars().count();
+    if length >= width {
+        return formatted;
+    }
+    let padding = width - length;
+    if spec.minus {
+        format!("{}{}", formatted, " ".repeat(padding))
+    } else if spec.zero && numeric {
+        let (sign, digits) = match formatted.chars().next() {
+            Some(sign @ ('-' | '+' | ' ')) => (sign.to_string(), &formatted[1..]),
+            _ => (String::new(), formatted.as_str()),
+        };
+        format!("{}{}{}", sign, "0".repeat(padding), digits)
+    } else {
+        format!("{}{}", " ".repeat(padding), formatted)
+    }
+}
+
+fn signed(value: i64, spec: &Spec) -> String {
+    match value >= 0 {
+        true if spec.plus => format!("+{}", value),
+        true if spec.space => format!(" {}", value),
+        _ => value.to_string(),
+    }
+}
+
+fn float(value: f64, precision: usize, spec: &Spec) -> String {
+    let formatted = format!("{:.*}", precision, value);
+    match value >= 0.0 {
+        true if spec.plus => format!("+{}", formatted),
+        true if spec.space => format!(" {}", formatted),
+        _ => formatted,
+    }
+}
+
+/// `%e`, with an exponent of at least two digits like Go's `1.500000e+00`
+fn exponent(value: f64, precision: usize) -> String {
+    let formatted = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+fn hex(value: i64, spec: &Spec) -> String {
+    let digits = format!("{:x}", value.unsigned_abs());
+    let prefix = if spec.sharp { "0x" } else { "" };
+    let sign = if value < 0 { "-" } else { "" };
+    format!("{}{}{}", sign, prefix, digits)
+}
+
+fn truncate(value: &str, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => value.chars().take(precision).collect(),
+        None => value.to_string(),
+    }
+}
+
+fn rune(value: i64) -> char {
+    u32::try_from(value)
+        .ok()
+        .and_then(char::from_u32)
+        .unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// A double-quoted Go string literal for `value`, like `strconv.Quote`
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for character in value.chars() {
+        escape(character, '"', &mut quoted);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A single-quoted Go rune literal for `value`, like `strconv.QuoteRune`
+fn quote_rune(value: i64) -> String {
+    let mut quoted = String::from('\'');
+    escape(rune(value), '\'', &mut quoted);
+    quoted.push('\'');
+    quoted
+}
+
+fn escape(character: char, quote: char, output: &mut String) {
+    match character {
+        '\u{07}' => output.push_str("\\a"),
+        '\u{08}' => output.push_str("\\b"),
+        '\u{0c}' => output.push_str("\\f"),
+        '\n' => output.push_str("\\n"),
+        '\r' => output.push_str("\\r"),
+        '\t' => output.push_str("\\t"),
+        '\u{0b}' => output.push_str("\\v"),
+        '\\' => output.push_str("\\\\"),
+        character if character == quote => {
+            output.push('\\');
+            output.push(quote);
+        }
+        character if (character as u32) < 0x20 || character == '\u{7f}' => {
+            output.push_str(&format!("\\x{:02x}", character as u32))
+        }
+        character => output.push(character),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_verbs() {
+        let arguments = [
+            Value::Int(42),
+            Value::String("go".to_string()),
+            Value::Float(2.5),
+            Value::Bool(true),
+        ];
+        assert_eq!(
+            sprintf(
+                "%d %s %v %v|%T %T %T %T",
+                &[&arguments[..], &arguments[..]].concat()
+            ),
+            "42 go 2.5 true|int string float64 bool"
+        );
+        assert_eq!(
+            sprintf(
+                "%q %q %5d|%-4s|%05d|%.2f|%x|%c|%%",
+                &[
+                    Value::String("a\"b\n".to_string()),
+                    Value::Int(97),
+                    Value::Int(7),
+                    Value::String("ab".to_string()),
+                    Value::Int(-42),
+                    Value::Float(1.23456),
+                    Value::Int(255),
+                    Value::Int(0x4e16),
+                ]
+            ),
+            "\"a\\\"b\\n\" 'a'     7|ab  |-0042|1.23|ff|世|%"
+        );
+        assert_eq!(
+            sprintf(
+                "%g %.3g %v",
+                &[Value::Float(1e6), Value::Float(1.23456), Value::Float(1e6)]
+            ),
+            "1e+06 1.23 1e+06"
+        );
+        assert_eq!(
+            sprintf("%d %d", &[Value::String("a".to_string())]),
+            "%!d(string=a) %!d(MISSING)"
+        );
+        assert_eq!(
+            sprintf("%v", &[Value::Int(1), Value::String("x".to_string())]),
+            "1%!(EXTRA string=x)"
+        );
+    }
+
+    #[test]
+    fn print_operands() {
+        let arguments = [
+            Value::Int(1),
+            Value::Int(2),
+            Value::String("a".to_string()),
+            Value::Int(3),
+            Value::Nil,
+        ];
+        assert_eq!(sprint(&arguments), "1 2a3 <nil>");
+        assert_eq!(sprintln(&arguments), "1 2 a 3 <nil>\n");
+    }
+
+    #[test]
+    fn format_composites() {
+        use crate::ast::type_expression::TypeExpression;
+        use crate::eval::value::Slice;
+        use crate::primitives::position::Position;
+        use std::rc::Rc;
+
+        let byte = Rc::new(TypeExpression::new_name(
+            "byte".to_string(),
+            Position::new(1, 0, 0),
+        ));
+        let bytes = Value::Slice(Slice::new(byte, vec![Value::Int(104), Value::Int(105)], 2));
+        assert_eq!(
+            sprintf(
+                "%v %s %q %x %T",
+                &[
+                    bytes.clone(),
+                    bytes.clone(),
+                    bytes.clone(),
+                    bytes.clone(),
+                    bytes
+                ]
+            ),
+            "[104 105] hi \"hi\" 6869 []byte"
+        );
+    }
+}
@@ -0,0 +1,1010 @@
+use std::rc::Rc;
+
+use crate::ast::{
+    ast::File,
+    declaration::{Declaration, DeclarationKind, Parameter},
+    type_expression::TypeExpressionKind,
+};
+use crate::eval::{
+    config::InterpreterConfig,
+    evaluator::Evaluator,
+    observer::Observer,
+    stdlib::{Arguments, HostFunction},
+    testing::{TestResult, is_test_name},
+    value::Value,
+};
+use crate::primitives::{
+    errors::runtime::{RuntimeError, RuntimeErrorKind},
+    position::Position,
+};
+
+/// Runs programs: a `package main` file's package level variables are initialized, then its
+/// `func main` is called. Go calls are run on the host's stack, so a program recursing
+/// thousands of calls deep needs a thread with a large stack, or a lower `with_max_depth`.
+#[derive(Debug, Default)]
+pub struct Interpreter {
+    evaluator: Evaluator,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            evaluator: Evaluator::new(),
+        }
+    }
+
+    pub fn with_max_depth(self, max_depth: usize) -> Interpreter {
+        Interpreter {
+            evaluator: self.evaluator.with_max_depth(max_depth),
+        }
+    }
+
+    /// Write the program's output to `output` rather than the process's standard output
+    pub fn with_output(self, output: impl std::io::Write + Send + 'static) -> Interpreter {
+        Interpreter {
+            evaluator: self.evaluator.with_output(output),
+        }
+    }
+
+    /// Read the program's standard input from `input` rather than the process's
+    pub fn with_input(self, input: impl std::io::BufRead + Send + 'static) -> Interpreter {
+        Interpreter {
+            evaluator: self.evaluator.with_input(input),
+        }
+    }
+
+    /// Give the program `args` as os.Args, which start with the program's name
+    pub fn with_args(self, args: Vec<String>) -> Interpreter {
+        Interpreter {
+            evaluator: self.evaluator.with_args(args),
+        }
+    }
+
+    /// Range over maps in key order rather than a random one, so a program's output is
+    /// reproducible
+    pub fn deterministic(self) -> Interpreter {
+        Interpreter {
+            evaluator: self.evaluator.deterministic(),
+        }
+    }
+
+    /// Limit the program's steps, allocation and running time, and seed its random choices,
+    /// as `config` says
+    pub fn with_config(self, config: &InterpreterConfig) -> Interpreter {
+        Interpreter {
+            evaluator: self.evaluator.with_config(config),
+        }
+    }
+
+    /// Tell `observer` about each statement the program runs, each call it makes and each
+    /// value it evaluates, as it goes
+    pub fn with_observer(self, observer: impl Observer + Send + 'static) -> Interpreter {
+        Interpreter {
+            evaluator: self.evaluator.with_observer(observer),
+        }
+    }
+
+    /// Provide `function` to programs as `function_name` of the package `path`, so a program
+    /// that imports the package can call it like any other, as `mypkg.DoThing(x)`. Its
+    /// arguments are taken with `Arguments`' accessors, which fail as Go's type checker would
+    /// for an argument of the wrong type, and `Arguments::panic` panics in the program.
+    pub fn register_native(
+        &mut self,
+        path: &str,
+        function_name: &str,
+        function: impl Fn(&Arguments) -> Result<Vec<Value>, RuntimeError> + Send + 'static,
+    ) {
+        self.evaluator
+            .register_native(path, function_name, HostFunction::new(function));
+    }
+
+    /// Run the program, which ends when main returns, even with other goroutines running. A
+    /// call to os.Exit ends it too, failing with any status but 0.
+    pub fn run(&mut self, file: &File) -> Result<(), RuntimeError> {
+        self.evaluator.start();
+        let result = match self.run_main(file) {
+            Err(error) if error.kind == RuntimeErrorKind::Exit(0) => Ok(()),
+            result => result,
+        };
+        self.evaluator.exit();
+        result
+    }
+
+    fn run_main(&mut self, file: &File) -> Result<(), RuntimeError> {
+        // The package clause is the file's first line
+        let package_position = Position::new(1, 0, 7);
+        if file.package_name != "main" {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::NotMainPackage(file.package_name.clone()),
+                package_position,
+            ));
+        }
+
+        self.initialize(file)?;
+
+        let main = file.decls.iter().find(|declaration| {
+            matches!(
+                &declaration.kind,
+                DeclarationKind::Function { receiver: None, name, .. } if name == "main"
+            )
+        });
+        let Some(main) = main else {
+            return Err(RuntimeError::new(
+                RuntimeErrorKind::MissingMain,
+                package_position,
+            ));
+        };
+        self.evaluator
+            .call_function("main", Vec::new(), main.position_start)?;
+        Ok(())
+    }
+
+    /// Run the package's tests: its package level variables are initialized, then each test
+    /// function whose name `filter` accepts is called with a new testing.T, in the order
+    /// they're declared. A test function is a `func TestXxx(t *testing.T)` of the package
+    /// itself rather than of one it imports. `report` is given each test's result as it
+    /// finishes. A panic or another error ends the run, as a panicking test ends `go test`'s,
+    /// once the test it happened in is reported as failed.
+    pub fn run_tests(
+        &mut self,
+        file: &File,
+        filter: impl Fn(&str) -> bool,
+        mut report: impl FnMut(&TestResult),
+    ) -> Result<(), RuntimeError> {
+        self.evaluator.start();
+        let result = self.initialize(file).and_then(|()| {
+            for (name, function) in test_functions(file) {
+                if !filter(name) {
+                    continue;
+                }
+                let position = function.position_start;
+                let function = Value::Function(Rc::new(function.clone()));
+                let (result, error) =
+                    self.evaluator
+                        .run_test(name.to_string(), &function, position);
+                report(&result);
+                if let Some(error) = error {
+                    return Err(error);
+                }
+            }
+            Ok(())
+        });
+        self.evaluator.exit();
+        result
+    }
+
+    /// Import the file's packages and declare its package level names. Functions, types and
+    /// constants are declared before any variable is initialized, so initializers can call
+    /// functions declared after them. Variables are initialized in the order they're declared, rather
+    /// than the order they depend on each other.
+    fn initialize(&mut self, file: &File) -> Result<(), RuntimeError> {
+        for import in &file.imports {
+            self.evaluator.import(import);
+        }
+        let (variables, others): (Vec<_>, Vec<_>) = file
+            .decls
+            .iter()
+            .partition(|declaration| matches!(declaration.kind, DeclarationKind::Variable { .. }));
+        for declaration in others.into_iter().chain(variables) {
+            self.evaluator.declare(declaration)?;
+        }
+        Ok(())
+    }
+
+    /// The evaluator the program ran in, with its package level variables as the program
+    /// left them
+    pub fn evaluator(&self) -> &Evaluator {
+        &self.evaluator
+    }
+}
+
+/// The file's test functions and their names. Those of the packages it imports were renamed
+/// `pkg.TestXxx` when they were loaded, so they aren't among them.
+fn test_functions(file: &File) -> Vec<(&str, &Declaration)> {
+    file.decls
+        .iter()
+        .filter_map(|declaration| match &declaration.kind {
+            DeclarationKind::Function {
+                receiver: None,
+                name,
+                parameters,
+                results,
+                ..
+            } if is_test_name(name) && results.is_empty() && takes_testing_t(parameters) => {
+                Some((name.as_str(), declaration))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether a function's only parameter is a `*testing.T`, which is taken to be any pointer to
+/// a package's `T`, whatever the package is imported as
+fn takes_testing_t(parameters: &[Parameter]) -> bool {
+    let [parameter] = parameters else {
+        return false;
+    };
+    let TypeExpressionKind::Pointer(target) = &parameter.type_expression.kind else {
+        return false;
+    };
+    parameter.names.len() <= 1
+        && matches!(&target.kind, TypeExpressionKind::Qualified { name, .. } if name == "T")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{
+        expression::{Expression, ExpressionKind},
+        statement::Statement,
+    };
+    use crate::eval::{loader::Source, observer::Tracer, testing::TestOutcome, value::Value};
+    use crate::parser::parser::Parser;
+    use crate::test_support::Captured;
+    use std::sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use std::time::Duration;
+
+    fn run(source: &str) -> Result<Interpreter, String> {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        // Shallow enough for a test thread's stack
+        let mut interpreter = Interpreter::new().with_max_depth(100);
+        interpreter
+            .run(&file)
+            .map_err(|error| error.kind.to_string())?;
+        Ok(interpreter)
+    }
+
+    fn output(source: &str) -> String {
+        let (output, error) = output_and_error(source);
+        assert_eq!(error, None);
+        output
+    }
+
+    /// What the program printed before it finished or failed, and how it failed
+    fn output_and_error(source: &str) -> (String, Option<String>) {
+        let file = Parser::new(source).parse().unwrap();
+        let captured = Captured::default();
+        let mut interpreter = Interpreter::new()
+            .with_max_depth(100)
+            .with_output(captured.clone())
+            .deterministic();
+        let error = interpreter.run(&file).err();
+        let output = captured.take();
+        (output, error.map(|error| error.kind.to_string()))
+    }
+
+    /// How a panic in main, at `line`, is reported
+    fn main_panic(message: &str, line: usize) -> Option<String> {
+        Some(format!(
+            "panic: {}\n\ngoroutine 1 [running]:\nmain.main(...)\n\tline {}",
+            message, line
+        ))
+    }
+
+    fn result(source: &str) -> Value {
+        let interpreter = run(source).unwrap();
+        interpreter.evaluator().lookup("result").unwrap()
+    }
+
+    #[test]
+    fn run_functions() {
+        let source = "package main\n\nvar result = 0\n\nfunc fib(n int) int {\n\tif n < 2 {\n\t\treturn n\n\t}\n\treturn fib(n-1) + fib(n-2)\n}\n\nfunc main() {\n\tresult = fib(20)\n}\n";
+        assert_eq!(result(source), Value::Int(6765));
+
+        let source = "package main\n\nvar result string\n\nfunc divide(a, b int) (quotient, remainder int) {\n\tquotient = a / b\n\tremainder = a % b\n\treturn\n}\n\nfunc swap(a, b string) (string, string) {\n\treturn b, a\n}\n\nfunc main() {\n\tq, r := divide(17, 5)\n\tx, y := swap(\"a\", \"b\")\n\tif q == 3 && r == 2 {\n\t\tresult = x + y\n\t}\n}\n";
+        assert_eq!(result(source), Value::String("ba".to_string()));
+
+        let source = "package main\n\nvar result = half(3)\n\nfunc half(x float64) float64 {\n\treturn x / 2\n}\n\nfunc main() {\n}\n";
+        assert_eq!(result(source), Value::Float(1.5));
+    }
+
+    #[test]
+    fn run_loops_and_scopes() {
+        let source = "package main\n\nvar result int\n\nfunc main() {\n\ti := 0\n\tfor {\n\t\ti = i + 1\n\t\tif i%2 == 0 {\n\t\t\tcontinue\n\t\t} else if i > 9 {\n\t\t\tbreak\n\t\t}\n\t\tresult = result + i\n\t}\n\tx := 100\n\tif x := 1; x > 0 {\n\t\tresult = result + x\n\t}\n\tresult = result + x\n}\n";
+        assert_eq!(result(source), Value::Int(1 + 3 + 5 + 7 + 9 + 1 + 100));
+
+        let source = "package main\n\nvar result int\n\nfunc main() {\n\tfor i := range 5 {\n\t\tresult = result + i\n\t}\n\tfor _, r := range \"ab\" {\n\t\tresult = result + int(r)\n\t}\n\tn := 300\n\tvar b byte = byte(n)\n\tresult = result + int(b)\n}\n";
+        assert_eq!(result(source), Value::Int(10 + 97 + 98 + 44));
+
+        // Three-clause loops, whose continue runs the post statement, and updates in place. Each
+        // iteration has an i of its own.
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tsum := 0\n\tvar ps []*int\n\tfor i := 0; i < 10; i++ {\n\t\tps = append(ps, &i)\n\t\tif i%3 == 0 {\n\t\t\tcontinue\n\t\t}\n\t\tsum += i\n\t}\n\tn := 10\n\tfor ; n > 0; n -= 3 {\n\t}\n\tx := 1.5\n\tx *= 2\n\tx--\n\ts := \"go\"\n\ts += \"r\"\n\tb := 1\n\tb <<= 4\n\tb |= 1\n\tfmt.Println(sum, n, x, s, b, *ps[0], *ps[9])\n}\n";
+        assert_eq!(output(source), "27 -2 2 gor 17 0 9\n");
+    }
+
+    #[test]
+    fn run_switch() {
+        let source = "package main\n\nimport \"fmt\"\n\nfunc sign(n int) string {\n\tswitch {\n\tcase n < 0:\n\t\treturn \"negative\"\n\tcase n == 0:\n\t\treturn \"zero\"\n\t}\n\treturn \"positive\"\n}\n\nfunc main() {\n\tfor i := 0; i < 5; i++ {\n\t\tswitch x := i * 2; x {\n\t\tcase 0:\n\t\t\tfmt.Println(\"zero\")\n\t\tdefault:\n\t\t\tfmt.Println(\"other\", x)\n\t\t\tif x == 6 {\n\t\t\t\tbreak\n\t\t\t}\n\t\t\tfmt.Println(\"not six\")\n\t\tcase 2, 4:\n\t\t\tfmt.Println(\"two or four\")\n\t\t\tfallthrough\n\t\tcase 100:\n\t\t\tfmt.Println(\"fell\")\n\t\t}\n\t}\n\tfmt.Println(sign(-1), sign(0), sign(5))\n}\n";
+        assert_eq!(
+            output(source),
+            "zero\ntwo or four\nfell\ntwo or four\nfell\nother 6\nother 8\nnot six\nnegative zero positive\n"
+        );
+    }
+
+    #[test]
+    fn run_constants() {
+        let source = "package main\n\nimport \"fmt\"\n\ntype Weekday int\n\nconst (\n\tSunday Weekday = iota\n\tMonday\n\tTuesday\n)\n\nconst (\n\t_  = iota\n\tKB = 1 << (10 * iota)\n\tMB\n)\n\nconst greeting = \"hi\"\n\nfunc main() {\n\tconst half = 2.5\n\tfmt.Println(Sunday, Monday, Tuesday, KB, MB, greeting, half*2)\n}\n";
+        assert_eq!(output(source), "0 1 2 1024 1048576 hi 5\n");
+    }
+
+    #[test]
+    fn run_fmt() {
+        assert_eq!(
+            output(include_str!("../../tests/testfiles/simple.go")),
+            "Hello, World!\n"
+        );
+
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tname := \"gor\"\n\tn, err := fmt.Printf(\"%s has %d %T\\n\", name, 3, 1.5)\n\tfmt.Println(n, err == nil, fmt.Sprintf(\"%q\", name))\n\terr = fmt.Errorf(\"bad %v: %w\", 1, fmt.Errorf(\"inner\"))\n\tfmt.Println(err, err.Error() == \"bad 1: inner\")\n\tfmt.Printf(\"%v %T\\n\", err, err)\n}\n";
+        assert_eq!(
+            output(source),
+            "gor has 3 float64\n18 true \"gor\"\nbad 1: inner true\nbad 1: inner *fmt.wrapError\n"
+        );
+
+        // A local variable shadows the package
+        assert_eq!(
+            run("package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt := 1\n\tfmt.Println(fmt)\n}\n").err(),
+            Some("cannot evaluate fmt.Println(fmt) yet".to_string())
+        );
+        assert_eq!(
+            run("package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Printf(1)\n}\n").err(),
+            Some("cannot use int value as string value in argument to fmt.Printf".to_string())
+        );
+    }
+
+    #[test]
+    fn run_builtins() {
+        let source = "package main\n\nimport \"fmt\"\n\ntype Ints []int\n\nfunc main() {\n\tvar s []int\n\tfmt.Println(s == nil, len(s), cap(s))\n\tfor i := range 5 {\n\t\ts = append(s, i)\n\t\tfmt.Print(cap(s), \" \")\n\t}\n\tfmt.Println(s)\n\ta := make([]int, 1, 3)\n\tb := append(a, 1)\n\tc := append(a, 2)\n\tfmt.Println(b, c)\n\tbs := []byte(\"hey\")\n\tn := copy(bs, \"go\")\n\tfmt.Printf(\"%d %s %v\\n\", n, bs, string(bs))\n\tbs = append(bs, \"!!\"...)\n\tfmt.Println(string(bs), len(bs), cap(bs))\n\tm := make(map[string]int)\n\tdelete(m, \"x\")\n\tfmt.Println(len(m), m)\n\tp := new(int)\n\tfmt.Println(p != nil, len(make(Ints, 2)))\n\tch := make(chan string, 3)\n\tfmt.Printf(\"%T %d %d\\n\", ch, len(ch), cap(ch))\n}\n";
+        assert_eq!(
+            output(source),
+            "true 0 0\n1 2 4 4 8 [0 1 2 3 4]\n[0 2] [0 2]\n2 goy goy\ngoy!! 5 6\n0 map[]\ntrue 2\nchan string 0 3\n"
+        );
+
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\tn := 0 - 1\n\t_ = make([]int, n)\n}\n").err(),
+            main_panic("runtime error: makeslice: len out of range", 5)
+        );
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\t_ = len(1)\n}\n").err(),
+            Some("invalid argument: 1 (type int) for built-in len".to_string())
+        );
+    }
+
+    #[test]
+    fn run_slices_and_maps() {
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\ts := make([]int, 3, 5)\n\tt := s[1:4]\n\tt[0] = 7\n\tt[2] = 9\n\tfmt.Println(s, t, len(t), cap(t), s[:5])\n\tu := s[1:2:3]\n\tu = append(u, 8)\n\tu = append(u, 6)\n\tu[0] = 1\n\tfmt.Println(s, u, cap(u))\n\tword := \"hello\"\n\tfmt.Println(word[1:3], word[:2], word[3:], word[1])\n\tfor i, v := range s {\n\t\tif i == 0 {\n\t\t\ts[2] = 5\n\t\t}\n\t\tfmt.Print(v, \" \")\n\t}\n\tfmt.Println()\n}\n";
+        assert_eq!(
+            output(source),
+            "[0 7 0] [7 0 9] 3 4 [0 7 0 9 0]\n[0 7 8] [1 8 6] 4\nel he lo 101\n0 7 5 \n"
+        );
+
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tm := make(map[string]int)\n\tm[\"b\"] = 2\n\tm[\"a\"] = 1\n\tm[\"c\"] = 3\n\tv, ok := m[\"a\"]\n\tw, found := m[\"z\"]\n\tfmt.Println(v, ok, w, found, len(m))\n\tfor k, v := range m {\n\t\tdelete(m, \"c\")\n\t\tfmt.Print(k, v, \" \")\n\t}\n\tfmt.Println()\n\tvar nilMap map[string]bool\n\tfmt.Println(nilMap[\"x\"], len(nilMap))\n}\n";
+        assert_eq!(output(source), "1 true 0 false 3\na1 b2 \nfalse 0\n");
+
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\tvar m map[string]int\n\tm[\"a\"] = 1\n}\n").err(),
+            main_panic("assignment to entry in nil map", 5)
+        );
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\ts := make([]int, 3)\n\ti := 5\n\t_ = s[i]\n}\n")
+                .err(),
+            main_panic("runtime error: index out of range [5] with length 3", 6)
+        );
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\ts := make([]int, 2, 3)\n\t_ = s[:4]\n}\n").err(),
+            main_panic(
+                "runtime error: slice bounds out of range [:4] with capacity 3",
+                5
+            )
+        );
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\ts := \"go\"\n\ti := 2\n\t_ = s[i:1]\n}\n").err(),
+            main_panic("runtime error: slice bounds out of range [2:1]", 6)
+        );
+    }
+
+    #[test]
+    fn map_iteration_order_is_random() {
+        // With 8 keys, 20 loops in the same order would be a 1 in 40320^19 coincidence
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tm := make(map[int]bool)\n\tfor i := range 8 {\n\t\tm[i] = true\n\t}\n\tfor range 20 {\n\t\tfor k := range m {\n\t\t\tfmt.Print(k)\n\t\t}\n\t\tfmt.Println()\n\t}\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let captured = Captured::default();
+        let mut interpreter = Interpreter::new().with_output(captured.clone());
+        interpreter.run(&file).unwrap();
+        let output = captured.take();
+        let orders: std::collections::HashSet<&str> = output.lines().collect();
+        assert!(orders.len() > 1, "{}", output);
+    }
+
+    #[test]
+    fn run_defer_panic_and_recover() {
+        // Deferred calls run last first, with the arguments they had when deferred
+        let source = "package main\n\nimport \"fmt\"\n\nfunc show(label string, n int) {\n\tfmt.Println(label, n)\n}\n\nfunc main() {\n\tfor i := range 3 {\n\t\tdefer show(\"deferred\", i)\n\t}\n\tx := 1\n\tdefer show(\"x was\", x)\n\tx = 2\n\tfmt.Println(\"body\", x)\n}\n";
+        assert_eq!(
+            output(source),
+            "body 2\nx was 1\ndeferred 2\ndeferred 1\ndeferred 0\n"
+        );
+
+        // A recovering function returns its named results as they were
+        let source = "package main\n\nimport \"fmt\"\n\nfunc handle() {\n\tif r := recover(); r != nil {\n\t\tfmt.Println(\"recovered:\", r)\n\t}\n}\n\nfunc risky(n int) (result int) {\n\tdefer handle()\n\tresult = n\n\tif n > 1 {\n\t\tpanic(fmt.Sprintf(\"too big: %d\", n))\n\t}\n\treturn n * 10\n}\n\nfunc half(n int) int {\n\tdefer handle()\n\tpanic(fmt.Errorf(\"odd: %d\", n))\n}\n\nfunc main() {\n\tfmt.Println(risky(1))\n\tfmt.Println(risky(2))\n\tfmt.Println(half(3))\n\tfmt.Println(recover())\n}\n";
+        assert_eq!(
+            output(source),
+            "10\nrecovered: too big: 2\n2\nrecovered: odd: 3\n0\n<nil>\n"
+        );
+
+        // Unrecovered, the deferred calls still run before the program dies
+        let source = "package main\n\nimport \"fmt\"\n\nfunc cleanup() {\n\tfmt.Println(\"cleaning up\")\n}\n\nfunc fail(n int) {\n\tdefer cleanup()\n\tpanic(n)\n}\n\nfunc main() {\n\tfail(1)\n}\n";
+        assert_eq!(
+            output_and_error(source),
+            (
+                "cleaning up\n".to_string(),
+                Some(
+                    "panic: 1\n\ngoroutine 1 [running]:\nmain.fail(...)\n\tline 11\nmain.main(...)\n\tline 15"
+                        .to_string()
+                )
+            )
+        );
+
+        // recover only stops a panic when a deferred call calls it directly, and a panic in
+        // a deferred call is reported after the one it interrupted
+        let source = "package main\n\nimport \"fmt\"\n\nfunc helper() {\n\tfmt.Println(\"helper recovered\", recover())\n}\n\nfunc deferred() {\n\thelper()\n}\n\nfunc second() {\n\tpanic(1.5)\n}\n\nfunc main() {\n\tdefer deferred()\n\tdefer second()\n\tpanic(\"first\")\n}\n";
+        assert_eq!(
+            output_and_error(source),
+            (
+                "helper recovered <nil>\n".to_string(),
+                Some(
+                    "panic: first\n\tpanic: +1.500000e+000\n\ngoroutine 1 [running]:\nmain.second(...)\n\tline 14\nmain.main(...)\n\tline 19"
+                        .to_string()
+                )
+            )
+        );
+
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\tdefer len(\"a\")\n}\n").err(),
+            Some("defer discards result of len(\"a\")".to_string())
+        );
+    }
+
+    #[test]
+    fn run_function_literals() {
+        // A function literal shares the variables it refers to, each loop iteration's own
+        let source = "package main\n\nimport \"fmt\"\n\nfunc counter() func() int {\n\tn := 0\n\treturn func() int {\n\t\tn++\n\t\treturn n\n\t}\n}\n\nfunc main() {\n\tnext := counter()\n\tnext()\n\tfmt.Println(next(), next())\n\tvar squares []func() int\n\tfor i := 0; i < 3; i++ {\n\t\tsquares = append(squares, func() int { return i * i })\n\t}\n\tfor _, square := range squares {\n\t\tfmt.Print(square(), \" \")\n\t}\n\ttotal := 0\n\tadd := func(x int) { total += x }\n\tadd(2)\n\tadd(3)\n\tfmt.Println(total, func(a, b int) int { return a * b }(6, 7))\n\tdone := make(chan string)\n\tgo func(name string) {\n\t\tdone <- \"hello \" + name\n\t}(\"gopher\")\n\tfmt.Println(<-done)\n\tvar none func()\n\tfmt.Printf(\"%T %v\\n\", add, none == nil)\n}\n";
+        assert_eq!(
+            output(source),
+            "2 3\n0 1 4 5 42\nhello gopher\nfunc(int) true\n"
+        );
+
+        // A deferred function literal can recover and set its function's named results
+        let source = "package main\n\nimport \"fmt\"\n\nfunc divide(a, b int) (result int, err error) {\n\tdefer func() {\n\t\tif r := recover(); r != nil {\n\t\t\terr = fmt.Errorf(\"recovered: %v\", r)\n\t\t}\n\t}()\n\treturn a / b, nil\n}\n\nfunc main() {\n\tq, err := divide(6, 3)\n\tfmt.Println(q, err)\n\tq, err = divide(1, 0)\n\tfmt.Println(q, err)\n\tdefer func() { fmt.Println(\"main recovered:\", recover()) }()\n\tfunc() {\n\t\tdefer fmt.Println(\"inner deferred\")\n\t\tpanic(\"boom\")\n\t}()\n}\n";
+        assert_eq!(
+            output(source),
+            "2 <nil>\n0 recovered: runtime error: integer divide by zero\ninner deferred\nmain recovered: boom\n"
+        );
+
+        // Function literals are named after the function they're in, as Go names them
+        let source = "package main\n\nfunc main() {\n\tfunc() {\n\t\tfunc() {\n\t\t\tpanic(\"deep\")\n\t\t}()\n\t}()\n}\n";
+        assert_eq!(
+            output_and_error(source),
+            (
+                String::new(),
+                Some(
+                    "panic: deep\n\ngoroutine 1 [running]:\nmain.main.func1.1(...)\n\tline 6\nmain.main.func1(...)\n\tline 5\nmain.main(...)\n\tline 4"
+                        .to_string()
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn run_goroutines_and_channels() {
+        // A closed channel ends a range over it, then gives zero values
+        let source = "package main\n\nimport \"fmt\"\n\nfunc produce(ch chan int, n int) {\n\tfor i := range n {\n\t\tch <- i\n\t}\n\tclose(ch)\n}\n\nfunc main() {\n\tch := make(chan int)\n\tgo produce(ch, 3)\n\tfor v := range ch {\n\t\tfmt.Println(v)\n\t}\n\tv, ok := <-ch\n\tfmt.Println(v, ok)\n\tbuffered := make(chan string, 2)\n\tbuffered <- \"a\"\n\tbuffered <- \"b\"\n\tfmt.Println(len(buffered), cap(buffered))\n\tclose(buffered)\n\tfmt.Println(<-buffered, <-buffered, <-buffered == \"\")\n}\n";
+        assert_eq!(output(source), "0\n1\n2\n0 false\n2 2\na b true\n");
+
+        // A goroutine that never blocks still gives the others a turn
+        let source = "package main\n\nimport \"fmt\"\n\nvar done bool\n\nfunc finish() {\n\tdone = true\n}\n\nfunc main() {\n\tgo finish()\n\tfor done == false {\n\t}\n\tfmt.Println(\"done\")\n}\n";
+        assert_eq!(output(source), "done\n");
+
+        // The program ends when main returns, whatever other goroutines are doing
+        let source = "package main\n\nimport \"fmt\"\n\nfunc forever(ch chan int) {\n\tch <- 1\n\tfmt.Println(\"unreachable\")\n}\n\nfunc main() {\n\tgo forever(make(chan int))\n\tfmt.Println(\"main returned\")\n}\n";
+        assert_eq!(output(source), "main returned\n");
+
+        // An unrecovered panic in any goroutine ends the program without running main's
+        // deferred calls
+        let source = "package main\n\nimport \"fmt\"\n\nfunc cleanup() {\n\tfmt.Println(\"cleaning up\")\n}\n\nfunc boom() {\n\tpanic(\"boom\")\n}\n\nfunc main() {\n\tdefer cleanup()\n\tgo boom()\n\t<-make(chan bool)\n}\n";
+        assert_eq!(
+            output_and_error(source),
+            (
+                String::new(),
+                Some(
+                    "panic: boom\n\ngoroutine 2 [running]:\nmain.boom(...)\n\tline 10".to_string()
+                )
+            )
+        );
+
+        let deadlock = Some("fatal error: all goroutines are asleep - deadlock!".to_string());
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\tch := make(chan int)\n\tch <- 1\n}\n").err(),
+            deadlock
+        );
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\tselect {}\n}\n").err(),
+            deadlock
+        );
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\tch := make(chan int, 1)\n\tclose(ch)\n\tch <- 1\n}\n").err(),
+            main_panic("send on closed channel", 6)
+        );
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\tch := make(chan int)\n\tclose(ch)\n\tclose(ch)\n}\n").err(),
+            main_panic("close of closed channel", 6)
+        );
+        // Each goroutine's thread has room for as many calls as the interpreter allows
+        assert_eq!(
+            run("package main\n\nfunc f(n int) int {\n\treturn f(n + 1)\n}\n\nfunc main() {\n\tgo f(0)\n\t<-make(chan int)\n}\n").err(),
+            Some("fatal error: stack overflow".to_string())
+        );
+    }
+
+    #[test]
+    fn run_select() {
+        let source = "package main\n\nimport \"fmt\"\n\nfunc send(ch chan string, s string) {\n\tch <- s\n}\n\nfunc relay(in chan int) {\n\tfmt.Println(\"relayed\", <-in)\n}\n\nfunc main() {\n\tch := make(chan int, 1)\n\tfor range 2 {\n\t\tselect {\n\t\tcase v, ok := <-ch:\n\t\t\tfmt.Println(\"received\", v, ok)\n\t\tdefault:\n\t\t\tfmt.Println(\"nothing ready\")\n\t\t\tch <- 1\n\t\t}\n\t}\n\ta := make(chan string)\n\tb := make(chan string)\n\tgo send(a, \"a\")\n\tgo send(b, \"b\")\n\tfor range 2 {\n\t\tselect {\n\t\tcase s := <-a:\n\t\t\tfmt.Println(s)\n\t\tcase s := <-b:\n\t\t\tfmt.Println(s)\n\t\t}\n\t}\n\tin := make(chan int)\n\tgo relay(in)\n\tselect {\n\tcase in <- 7:\n\t\tfmt.Println(\"sent\")\n\t}\n\tfor {\n\t\tselect {\n\t\tcase <-ch:\n\t\tdefault:\n\t\t\tbreak\n\t\t}\n\t\tfmt.Println(\"left the select\")\n\t\tbreak\n\t}\n}\n";
+        assert_eq!(
+            output(source),
+            "nothing ready\nreceived 1 true\na\nb\nrelayed 7\nsent\nleft the select\n"
+        );
+
+        // Several ready cases are picked between at random
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\ta := make(chan int, 1)\n\tb := make(chan int, 1)\n\tfor range 40 {\n\t\ta <- 1\n\t\tb <- 2\n\t\tfor range 2 {\n\t\t\tselect {\n\t\t\tcase v := <-a:\n\t\t\t\tfmt.Print(v)\n\t\t\tcase v := <-b:\n\t\t\t\tfmt.Print(v)\n\t\t\t}\n\t\t}\n\t}\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let captured = Captured::default();
+        let mut interpreter = Interpreter::new().with_output(captured.clone());
+        interpreter.run(&file).unwrap();
+        let output = captured.take();
+        assert!(output.contains("12") && output.contains("21"), "{}", output);
+    }
+
+    #[test]
+    fn run_sync() {
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\t\"sync\"\n)\n\nvar mu sync.Mutex\nvar once sync.Once\nvar total int\n\nfunc setup() {\n\tfmt.Println(\"setup\")\n}\n\nfunc worker(id int, wg *sync.WaitGroup) {\n\tdefer wg.Done()\n\tonce.Do(setup)\n\tfor range 3 {\n\t\tmu.Lock()\n\t\ttotal = total + id\n\t\tmu.Unlock()\n\t}\n}\n\nfunc waiter(ready chan bool, wg *sync.WaitGroup) {\n\t<-ready\n\tmu.Lock()\n\tfmt.Println(\"acquired\")\n\tmu.Unlock()\n\twg.Done()\n}\n\nfunc main() {\n\twg := new(sync.WaitGroup)\n\tfor i := range 5 {\n\t\twg.Add(1)\n\t\tgo worker(i, wg)\n\t}\n\twg.Wait()\n\tonce.Do(setup)\n\tfmt.Println(total)\n\tready := make(chan bool)\n\tmu.Lock()\n\twg.Add(1)\n\tgo waiter(ready, wg)\n\tready <- true\n\tfmt.Println(\"releasing\", mu.TryLock())\n\tmu.Unlock()\n\twg.Wait()\n}\n";
+        assert_eq!(output(source), "setup\n30\nreleasing false\nacquired\n");
+
+        let (_, error) = output_and_error(
+            "package main\n\nimport \"sync\"\n\nfunc main() {\n\tvar wg sync.WaitGroup\n\twg.Done()\n}\n",
+        );
+        assert!(
+            error.is_some_and(|error| error.starts_with("panic: sync: negative WaitGroup counter")),
+        );
+        assert_eq!(
+            run("package main\n\nimport \"sync\"\n\nfunc main() {\n\tvar mu sync.Mutex\n\tmu.Unlock()\n}\n").err(),
+            Some("fatal error: sync: unlock of unlocked mutex".to_string())
+        );
+        assert_eq!(
+            run("package main\n\nimport \"sync\"\n\nfunc main() {\n\tvar wg sync.WaitGroup\n\twg.Add(1)\n\twg.Wait()\n}\n").err(),
+            Some("fatal error: all goroutines are asleep - deadlock!".to_string())
+        );
+    }
+
+    #[test]
+    fn run_strings_and_strconv() {
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\t\"strconv\"\n\t\"strings\"\n)\n\nfunc main() {\n\twords := strings.Split(\"go,rust,zig\", \",\")\n\tfmt.Println(len(words), strings.ToUpper(strings.Join(words, \" \")))\n\tn, err := strconv.Atoi(\"42\")\n\tfmt.Println(n+1, err)\n\t_, err = strconv.Atoi(\"4x\")\n\tfmt.Println(err)\n\tf, _ := strconv.ParseFloat(\"2.5\", 64)\n\tfmt.Println(strconv.FormatFloat(f*2, 'f', 2, 64), strconv.Itoa(7)+\"!\")\n}\n";
+        assert_eq!(
+            output(source),
+            "3 GO RUST ZIG\n43 <nil>\nstrconv.Atoi: parsing \"4x\": invalid syntax\n5.00 7!\n"
+        );
+    }
+
+    #[test]
+    fn run_os_and_bufio() {
+        let source = "package main\n\nimport (\n\t\"bufio\"\n\t\"fmt\"\n\t\"os\"\n\t\"strings\"\n)\n\nfunc main() {\n\tdefer fmt.Println(\"never printed\")\n\tfmt.Println(len(os.Args), os.Args[1:])\n\tscanner := bufio.NewScanner(os.Stdin)\n\ttotal := 0\n\tfor scanner.Scan() {\n\t\ttotal = total + len(strings.Fields(scanner.Text()))\n\t\tfmt.Fprintf(os.Stdout, \"%q\\n\", scanner.Text())\n\t}\n\tfmt.Println(total, scanner.Err(), scanner.Text() == \"\")\n\tdata, err := os.ReadFile(\"missing.txt\")\n\tfmt.Println(len(data), data == nil, err)\n\t_, err = fmt.Fprintln(os.Stdin, \"hi\")\n\tfmt.Println(err)\n\tos.Exit(3)\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let captured = Captured::default();
+        let mut interpreter = Interpreter::new()
+            .with_output(captured.clone())
+            .with_input("a b\r\nc\nd e f".as_bytes())
+            .with_args(vec!["main".to_string(), "x".to_string(), "y".to_string()]);
+        let error = interpreter.run(&file).err();
+        assert_eq!(
+            captured.take(),
+            "3 [x y]\n\"a b\"\n\"c\"\n\"d e f\"\n6 <nil> true\n0 true open missing.txt: no such file or directory\nwrite /dev/stdin: bad file descriptor\n"
+        );
+        assert_eq!(
+            error.map(|error| error.kind.to_string()),
+            Some("exit status 3".to_string())
+        );
+
+        // Exiting with status 0 succeeds
+        assert!(run("package main\n\nimport \"os\"\n\nfunc main() {\n\tos.Exit(0)\n\tpanic(\"unreachable\")\n}\n").is_ok());
+    }
+
+    #[test]
+    fn run_errors() {
+        let source = "package main\n\nimport (\n\t\"errors\"\n\t\"fmt\"\n\t\"strconv\"\n)\n\nvar ErrNotFound = errors.New(\"not found\")\n\nfunc find(key string) error {\n\tif key == \"\" {\n\t\treturn ErrNotFound\n\t}\n\treturn nil\n}\n\nfunc lookup(key string) error {\n\tif err := find(key); err != nil {\n\t\treturn fmt.Errorf(\"lookup %q: %w\", key, err)\n\t}\n\treturn nil\n}\n\nfunc main() {\n\terr := lookup(\"\")\n\tfmt.Println(err)\n\tfmt.Println(errors.Is(err, ErrNotFound), err == ErrNotFound, errors.Unwrap(err) == ErrNotFound)\n\tfmt.Println(errors.Is(err, errors.New(\"not found\")), lookup(\"a\") == nil, errors.Is(nil, nil))\n\t_, parseErr := strconv.Atoi(\"x\")\n\tjoined := errors.Join(err, nil, parseErr)\n\tfmt.Println(joined)\n\tfmt.Println(errors.Is(joined, ErrNotFound), errors.Unwrap(joined) == nil)\n\tfmt.Printf(\"%T %T %T\\n\", err, joined, parseErr)\n\ttarget := new(error)\n\tfmt.Println(errors.As(joined, target), errors.As(nil, target))\n}\n";
+        assert_eq!(
+            output(source),
+            "lookup \"\": not found\ntrue false true\nfalse true true\nlookup \"\": not found\nstrconv.Atoi: parsing \"x\": invalid syntax\ntrue true\n*fmt.wrapError *errors.joinError *strconv.NumError\ntrue false\n"
+        );
+    }
+
+    #[test]
+    fn run_time() {
+        // Sleeping goroutines wake in the order their sleeps end
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\t\"time\"\n)\n\nfunc worker(id int, delay time.Duration, done chan int) {\n\ttime.Sleep(delay)\n\tdone <- id\n}\n\nfunc main() {\n\tstart := time.Now()\n\tdone := make(chan int)\n\tgo worker(1, 30*time.Millisecond, done)\n\tgo worker(2, 10*time.Millisecond, done)\n\tgo worker(3, 20*time.Millisecond, done)\n\tfmt.Println(<-done, <-done, <-done)\n\telapsed := time.Since(start)\n\tfmt.Println(elapsed >= 30*time.Millisecond, elapsed < time.Second)\n\tvar timeout time.Duration = 90\n\ttimeout = timeout * time.Second\n\tfmt.Println(timeout, timeout.Minutes(), 1.5*time.Second, time.Duration(1500)*time.Microsecond)\n\tfmt.Printf(\"%v %d %T %.1f\\n\", 2*time.Hour+time.Minute, time.Millisecond, timeout, time.Minute.Seconds())\n\tvar zero time.Time\n\tfmt.Println(zero, zero.IsZero(), start.Before(time.Now()), int64(time.Microsecond))\n}\n";
+        assert_eq!(
+            output(source),
+            "2 3 1\ntrue true\n1m30s 1.5 1.5s 1.5ms\n2h1m0s 1000000 time.Duration 60.0\n0001-01-01 00:00:00 +0000 UTC true true 1000\n"
+        );
+    }
+
+    #[test]
+    fn run_structs_and_methods() {
+        // Methods with pointer receivers change what they're called on, through an embedded
+        // pointer too, and interfaces call the dynamic type's methods
+        let source = "package main\n\nimport (\n\t\"errors\"\n\t\"fmt\"\n)\n\ntype Point struct {\n\tX, Y int\n}\n\nfunc (p Point) Add(q Point) Point {\n\treturn Point{p.X + q.X, p.Y + q.Y}\n}\n\nfunc (p *Point) Scale(factor int) {\n\tp.X = p.X * factor\n\tp.Y = p.Y * factor\n}\n\ntype Named struct {\n\t*Point\n\tName string\n}\n\ntype Shape interface {\n\tArea() float64\n}\n\ntype Rect struct{ W, H float64 }\n\ntype Square struct{ Side float64 }\n\nfunc (r Rect) Area() float64 { return r.W * r.H }\n\nfunc (s *Square) Area() float64 { return s.Side * s.Side }\n\nfunc (s *Square) String() string { return fmt.Sprint(\"square \", s.Side) }\n\ntype NotFound struct{ Key string }\n\nfunc (e *NotFound) Error() string { return e.Key + \" not found\" }\n\nfunc find(key string) error {\n\treturn fmt.Errorf(\"find: %w\", &NotFound{key})\n}\n\nfunc main() {\n\tp := Point{1, 2}\n\tp.Scale(3)\n\tq := &Point{Y: 1}\n\tq.Scale(2)\n\tfmt.Println(p, *q, p.Add(*q), q.Add(p), q)\n\tn := Named{&p, \"p\"}\n\tn.Scale(2)\n\tn.X = n.X + 1\n\tfmt.Println(p, n.Y, n.Name)\n\tfmt.Printf(\"%+v %T %T %v\\n\", Rect{1, 2}, q, n, &Square{2})\n\tshapes := []Shape{Rect{2, 3}, &Square{2}}\n\ttotal := 0.0\n\tfor _, s := range shapes {\n\t\ttotal = total + s.Area()\n\t}\n\tfmt.Println(total)\n\tc := p\n\tc.Y = -1\n\tr := &c.X\n\t*r = -*r\n\tfmt.Println(p, c, p == c, -p.X, ^0, !(p == c))\n\tpoints := []Point{{1, 2}, {3, 4}}\n\tpoints[1].Scale(10)\n\tfirst := &points[0]\n\tfirst.X = 7\n\tfmt.Println(points, len(points))\n\tm := map[string]*Point{\"a\": {1, 1}}\n\tm[\"a\"].Scale(4)\n\tfmt.Println(m[\"a\"].X, *m[\"a\"])\n\terr := find(\"k\")\n\tvar notFound *NotFound\n\tfmt.Println(err, errors.As(err, &notFound), notFound.Key)\n\tvar nilPoint *Point\n\tfmt.Println(nilPoint.X)\n}\n";
+        assert_eq!(
+            output_and_error(source),
+            (
+                "{3 6} {0 2} {3 8} {3 8} &{0 2}\n{7 12} 12 p\n{W:1 H:2} *main.Point main.Named square 2\n10\n{7 12} {-7 -1} false -7 -1 true\n[{7 2} {30 40}] 2\n4 {4 4}\nfind: k not found true k\n".to_string(),
+                main_panic("runtime error: invalid memory address or nil pointer dereference", 82)
+            )
+        );
+    }
+
+    #[test]
+    fn run_methods_on_named_types() {
+        // Values of a declared type that isn't a struct keep it, for its methods and %T, and a
+        // method value is bound to a copy of a value receiver, or a pointer to a variable
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\t\"strings\"\n)\n\ntype Weekday int\n\nconst (\n\tSunday Weekday = iota\n\tMonday\n\tTuesday\n)\n\nfunc (d Weekday) String() string {\n\tif d == Sunday {\n\t\treturn \"Sun\"\n\t}\n\treturn fmt.Sprintf(\"Day(%d)\", int(d))\n}\n\nfunc (d *Weekday) Next() { *d = (*d + 1) % 7 }\n\ntype Celsius float64\n\nfunc (c Celsius) Fahrenheit() float64 { return float64(c)*9/5 + 32 }\n\ntype Stack []int\n\nfunc (s *Stack) Push(x int) { *s = append(*s, x) }\n\nfunc (s Stack) Len() int { return len(s) }\n\ntype Set map[string]bool\n\nfunc (s Set) Add(x string) { s[x] = true }\n\ntype Name string\n\nfunc (n Name) Upper() Name { return Name(strings.ToUpper(string(n))) }\n\ntype Failure string\n\nfunc (f Failure) Error() string { return \"failed: \" + string(f) }\n\ntype Point struct{ X, Y int }\n\nfunc (p Point) Sum() int { return p.X + p.Y }\n\nfunc (p *Point) Move() { p.X++ }\n\nfunc check(fail bool) error {\n\tif fail {\n\t\treturn Failure(\"disk\")\n\t}\n\treturn nil\n}\n\nfunc main() {\n\tc := Celsius(100)\n\tfmt.Println(c.Fahrenheit(), Celsius(-40).Fahrenheit())\n\td := Sunday\n\td.Next()\n\tfmt.Println(d, Sunday, d == Monday, d+1)\n\tnext := d.Next\n\tnext()\n\tfmt.Printf(\"%v %d %T %T\\n\", d, d, d, c)\n\tvar s Stack\n\ts.Push(1)\n\ts.Push(2)\n\tfmt.Println(s.Len(), s[1], s, s[1:].Len())\n\tset := Set{}\n\tadd := set.Add\n\tadd(\"a\")\n\tfmt.Println(set, Name(\"go\").Upper()+\"!\")\n\tfmt.Println(check(true), check(false) == nil)\n\tp := Point{1, 2}\n\tsum, move := p.Sum, p.Move\n\tmove()\n\tfmt.Println(sum(), p.Sum())\n}\n";
+        assert_eq!(
+            output(source),
+            "212 -40\nDay(1) Sun true Day(2)\nDay(2) 2 main.Weekday main.Celsius\n2 2 [1 2] 1\nmap[a:true] GO!\nfailed: disk true\n3 4\n"
+        );
+    }
+
+    #[test]
+    fn run_packages() {
+        let directory = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/testfiles/module/cmd/app"
+        );
+        let program = crate::eval::loader::load(std::path::Path::new(directory)).unwrap();
+        let captured = Captured::default();
+        let mut interpreter = Interpreter::new()
+            .with_max_depth(100)
+            .with_output(captured.clone());
+        interpreter.run(&program.file).unwrap();
+        assert_eq!(
+            captured.take(),
+            "6 1 Hello, gor\n4 DONE\nshapes.Rect {2 3}\n2\n"
+        );
+    }
+
+    /// What the program printed under `config`, and how it failed
+    fn configured(source: &str, config: &InterpreterConfig) -> (String, Option<String>) {
+        let file = Parser::new(source).parse().unwrap();
+        let captured = Captured::default();
+        let mut interpreter = Interpreter::new()
+            .with_max_depth(100)
+            .with_output(captured.clone())
+            .with_config(config);
+        let error = interpreter.run(&file).err();
+        let output = captured.take();
+        (output, error.map(|error| error.kind.to_string()))
+    }
+
+    #[test]
+    fn run_with_limits() {
+        let steps = InterpreterConfig::default().with_max_steps(1_000);
+        // Recovering doesn't get around a limit, and goroutines share it
+        let source = "package main\n\nimport \"fmt\"\n\nfunc spin() {\n\tdefer recover()\n\tfor {\n\t}\n}\n\nfunc main() {\n\tfmt.Println(\"start\")\n\tgo spin()\n\t<-make(chan int)\n}\n";
+        assert_eq!(
+            configured(source, &steps),
+            (
+                "start\n".to_string(),
+                Some("fatal error: step limit of 1000 exceeded".to_string())
+            )
+        );
+
+        let allocation = InterpreterConfig::default().with_max_allocation(1 << 20);
+        let limited = Some("fatal error: allocation limit of 1048576 bytes exceeded".to_string());
+        let sources = [
+            "package main\n\nfunc main() {\n\ts := []int{}\n\tfor {\n\t\ts = append(s, 1)\n\t}\n}\n",
+            "package main\n\nfunc main() {\n\t_ = make([]int, 1<<40)\n}\n",
+            "package main\n\nimport \"strings\"\n\nfunc main() {\n\t_ = strings.Repeat(\"ab\", 1<<40)\n}\n",
+            "package main\n\nfunc main() {\n\ts := \"ab\"\n\tfor {\n\t\ts = s + s\n\t}\n}\n",
+            "package main\n\nfunc main() {\n\tm := map[int]bool{}\n\tfor i := range 1 << 20 {\n\t\tm[i] = true\n\t}\n}\n",
+        ];
+        for source in sources {
+            assert_eq!(
+                configured(source, &allocation),
+                (String::new(), limited.clone())
+            );
+        }
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\ts := make([]int, 0, 100)\n\tfmt.Println(len(append(s, 1)))\n}\n";
+        assert_eq!(configured(source, &allocation), ("1\n".to_string(), None));
+
+        let time = InterpreterConfig::default().with_time_limit(Duration::from_millis(50));
+        let limited = Some("fatal error: time limit of 50ms exceeded".to_string());
+        let spin = "package main\n\nfunc main() {\n\tfor {\n\t}\n}\n";
+        assert_eq!(configured(spin, &time), (String::new(), limited.clone()));
+        let start = std::time::Instant::now();
+        let sleep =
+            "package main\n\nimport \"time\"\n\nfunc main() {\n\ttime.Sleep(time.Hour)\n}\n";
+        assert_eq!(configured(sleep, &time), (String::new(), limited));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn sandbox_limits_the_stack() {
+        // Recursion ends in a stack overflow, not an abort, on a thread of the default size,
+        // in main and in a goroutine alike
+        let sources = [
+            "package main\n\nfunc down(n int) int {\n\treturn down(n+1) + 1\n}\n\nfunc main() {\n\tdown(0)\n}\n",
+            "package main\n\nfunc down(n int) int {\n\treturn down(n+1) + 1\n}\n\nfunc main() {\n\tgo down(0)\n\t<-make(chan int)\n}\n",
+        ];
+        for source in sources {
+            let error = std::thread::spawn(move || {
+                let file = Parser::new(source).parse().unwrap();
+                let mut interpreter = Interpreter::new()
+                    .with_output(std::io::sink())
+                    .with_config(&InterpreterConfig::sandbox());
+                interpreter.run(&file).unwrap_err().kind
+            })
+            .join()
+            .unwrap();
+            assert_eq!(error, RuntimeErrorKind::StackOverflow);
+        }
+        let depth = InterpreterConfig::default().with_max_call_depth(3);
+        let source =
+            "package main\n\nfunc down(n int) {\n\tdown(n + 1)\n}\n\nfunc main() {\n\tdown(0)\n}\n";
+        assert_eq!(
+            configured(source, &depth).1,
+            Some("fatal error: stack overflow".to_string())
+        );
+    }
+
+    #[test]
+    fn seeded_runs_are_reproducible() {
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tm := map[int]int{}\n\tfor i := range 20 {\n\t\tm[i] = i\n\t}\n\tfor k := range m {\n\t\tfmt.Print(k, \" \")\n\t}\n\ta, b := make(chan int, 1), make(chan int, 1)\n\tfor range 20 {\n\t\ta <- 1\n\t\tb <- 2\n\t\tselect {\n\t\tcase v := <-a:\n\t\t\tfmt.Print(v)\n\t\t\t<-b\n\t\tcase v := <-b:\n\t\t\tfmt.Print(v)\n\t\t\t<-a\n\t\t}\n\t}\n}\n";
+        let config = InterpreterConfig::sandbox().with_seed(7);
+        let (first, error) = configured(source, &config);
+        assert_eq!(error, None);
+        assert_eq!(configured(source, &config).0, first);
+        let (other, _) = configured(source, &config.with_seed(8));
+        assert_ne!(other, first);
+    }
+
+    #[test]
+    fn run_host_functions() {
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\t\"host\"\n\t\"strings\"\n)\n\nfunc handle() {\n\tfmt.Println(\"recovered:\", recover())\n}\n\nfunc main() {\n\tdefer handle()\n\tfmt.Println(host.Add(1, 2), strings.Shout(\"hi\"))\n\tname, err := host.Lookup(\"a\")\n\tfmt.Println(name, err)\n\t_, err = host.Lookup(\"b\")\n\tfmt.Println(err)\n\thost.Count()\n\thost.Count()\n\thost.Fail()\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let captured = Captured::default();
+        let mut interpreter = Interpreter::new().with_output(captured.clone());
+        interpreter.register_native("host", "Add", |arguments| {
+            arguments.expect(2)?;
+            Ok(vec![Value::Int(arguments.int(0)? + arguments.int(1)?)])
+        });
+        interpreter.register_native("host", "Lookup", |arguments| {
+            arguments.expect(1)?;
+            Ok(match arguments.string(0)? {
+                "a" => vec![Value::String("alpha".to_string()), Value::Nil],
+                key => vec![
+                    Value::String(String::new()),
+                    Value::error(format!("host: {} not found", key)),
+                ],
+            })
+        });
+        // Host functions can keep state of their own, and add to standard library packages
+        let count = Arc::new(AtomicUsize::new(0));
+        let counted = count.clone();
+        interpreter.register_native("host", "Count", move |arguments| {
+            arguments.expect(0)?;
+            counted.fetch_add(1, Ordering::Relaxed);
+            Ok(Vec::new())
+        });
+        interpreter.register_native("strings", "Shout", |arguments| {
+            arguments.expect(1)?;
+            Ok(vec![Value::String(arguments.string(0)?.to_uppercase())])
+        });
+        interpreter.register_native("host", "Fail", |arguments| {
+            Err(arguments.panic("host: failed"))
+        });
+        interpreter.run(&file).unwrap();
+        assert_eq!(
+            captured.take(),
+            "3 HI\nalpha <nil>\nhost: b not found\nrecovered: host: failed\n"
+        );
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+
+        let source = "package main\n\nimport \"host\"\n\nfunc main() {\n\thost.Add(\"1\", 2)\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.register_native("host", "Add", |arguments| {
+            Ok(vec![Value::Int(arguments.int(0)? + arguments.int(1)?)])
+        });
+        assert_eq!(
+            interpreter.run(&file).unwrap_err().kind.to_string(),
+            "cannot use string value as int value in argument to host.Add"
+        );
+    }
+
+    /// Records what it's told about a program as it runs
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Observer for Recorder {
+        fn on_statement_enter(&mut self, statement: &Statement, goroutine: usize) {
+            let line = statement.to_string();
+            let line = line.lines().next().unwrap_or_default();
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", goroutine, line));
+        }
+
+        fn on_call(&mut self, function: &str, arguments: &[Value], position: Position) {
+            let arguments: Vec<String> = arguments.iter().map(Value::to_string).collect();
+            self.0.lock().unwrap().push(format!(
+                "call {}({}) line {}",
+                function,
+                arguments.join(", "),
+                position.line
+            ));
+        }
+
+        fn on_value(&mut self, expression: &Expression, value: &Value) {
+            if matches!(expression.kind, ExpressionKind::FunctionCall { .. }) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} = {}", expression, value));
+            }
+        }
+    }
+
+    #[test]
+    fn run_with_observer() {
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\t\"strings\"\n)\n\nfunc double(n int) int {\n\treturn n * 2\n}\n\nfunc main() {\n\tx := double(2)\n\tfmt.Println(strings.Repeat(\"a\", x))\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let recorder = Recorder::default();
+        let mut interpreter = Interpreter::new()
+            .with_output(Captured::default())
+            .with_observer(recorder.clone());
+        interpreter.run(&file).unwrap();
+        assert_eq!(
+            std::mem::take(&mut *recorder.0.lock().unwrap()),
+            [
+                "call main() line 12",
+                "1 x := double(2)",
+                "call double(2) line 13",
+                "1 return n * 2",
+                "double(2) = 4",
+                "1 fmt.Println(strings.Repeat(\"a\", x))",
+                "call strings.Repeat(a, 4) line 14",
+                "strings.Repeat(\"a\", x) = aaaa",
+                "call fmt.Println(aaaa) line 14",
+            ]
+        );
+
+        // The tracer writes each statement's line as the file has it, marking other
+        // goroutines'
+        let source = "package main\n\nfunc send(ch chan int) {\n\tch <- 1 // to main\n}\n\nfunc main() {\n\tch := make(chan int)\n\tgo send(ch)\n\t<-ch\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let sources = vec![Source {
+            path: "main.go".into(),
+            first_line: 1,
+            text: source.to_string(),
+        }];
+        let traced = Captured::default();
+        let mut interpreter =
+            Interpreter::new().with_observer(Tracer::new(sources, traced.clone()));
+        interpreter.run(&file).unwrap();
+        assert_eq!(
+            traced.take(),
+            "main.go:8: ch := make(chan int)\nmain.go:9: go send(ch)\nmain.go:10: <-ch\n[goroutine 2] main.go:4: ch <- 1 // to main\n"
+        );
+    }
+
+    #[test]
+    fn runtime_errors() {
+        assert_eq!(
+            run("package util\n\nfunc main() {\n}\n").err(),
+            Some("package util is not a main package".to_string())
+        );
+        assert_eq!(
+            run("package main\n\nfunc helper() {\n}\n").err(),
+            Some("function main is undeclared in the main package".to_string())
+        );
+        assert_eq!(
+            run("package main\n\nfunc f(n int) int {\n\treturn f(n + 1)\n}\n\nfunc main() {\n\tf(0)\n}\n").err(),
+            Some("fatal error: stack overflow".to_string())
+        );
+        assert_eq!(
+            run("package main\n\nfunc main() {\n\tx := 0\n\t_ = 10 / x\n}\n").err(),
+            main_panic("runtime error: integer divide by zero", 5)
+        );
+    }
+
+    #[test]
+    fn run_time_panics() {
+        // Run-time errors panic, so deferred calls can recover them
+        let source = "package main\n\nimport \"fmt\"\n\nfunc rescue() {\n\tfmt.Println(\"recovered:\", recover())\n}\n\nfunc divide(a int, b int) int {\n\tdefer rescue()\n\treturn a / b\n}\n\nfunc at(s []int, i int) int {\n\treturn s[i]\n}\n\nfunc main() {\n\tfmt.Println(divide(7, 0))\n\ts := make([]int, 3)\n\tfmt.Println(at(s, 5))\n}\n";
+        assert_eq!(
+            output_and_error(source),
+            (
+                "recovered: runtime error: integer divide by zero\n0\n".to_string(),
+                Some(
+                    "panic: runtime error: index out of range [5] with length 3\n\ngoroutine 1 [running]:\nmain.at(...)\n\tline 15\nmain.main(...)\n\tline 21"
+                        .to_string()
+                )
+            )
+        );
+    }
+
+    /// A test's name, outcome and logged messages
+    type Tested = (String, TestOutcome, Vec<String>);
+
+    /// Each test and subtest the package's tests ran, and the error that ended the run if one
+    /// did
+    fn test_results(source: &str, filter: impl Fn(&str) -> bool) -> (Vec<Tested>, Option<String>) {
+        let file = Parser::new(source).parse().unwrap();
+        let mut results = Vec::new();
+        let error = Interpreter::new()
+            .with_max_depth(100)
+            .run_tests(&file, filter, |result| {
+                for (_, result) in result.flatten() {
+                    let output = result.output.iter().map(|logged| logged.message.clone());
+                    results.push((result.name.clone(), result.outcome, output.collect()));
+                }
+            })
+            .err();
+        (results, error.map(|error| error.kind.to_string()))
+    }
+
+    #[test]
+    fn run_tests() {
+        let source = "package add\n\nimport \"testing\"\n\nvar deferred = 0\n\nfunc Add(a, b int) int {\n\treturn a + b\n}\n\nfunc TestAdd(t *testing.T) {\n\tif Add(1, 2) != 4 {\n\t\tt.Errorf(\"Add(1, 2) = %d, want %d\", Add(1, 2), 4)\n\t}\n\tt.Run(\"negative numbers\", testNegative)\n\tt.Log(\"done\", t.Failed())\n}\n\nfunc testNegative(t *testing.T) {\n\tdefer count()\n\tt.Fatal(t.Name())\n\tt.Error(\"not reached\")\n}\n\nfunc count() {\n\tdeferred = deferred + 1\n}\n\nfunc TestSkip(t *testing.T) {\n\tt.Skipf(\"not on %s\", \"gor\")\n\tt.Fail()\n}\n\nfunc Testify(t *testing.T) {\n\tt.Fail()\n}\n\nfunc TestPass(t *testing.T) {\n\tif deferred != 1 {\n\t\tt.Errorf(\"deferred %d times\", deferred)\n\t}\n}\n\nfunc TestPanic(t *testing.T) {\n\ts := []int{}\n\t_ = s[1]\n}\n\nfunc TestAfterPanic(t *testing.T) {\n}\n";
+        let (results, error) = test_results(source, |_| true);
+        let strings = |strings: &[&str]| strings.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            results,
+            vec![
+                (
+                    "TestAdd".to_string(),
+                    TestOutcome::Failed,
+                    strings(&["Add(1, 2) = 3, want 4", "done true"])
+                ),
+                (
+                    "TestAdd/negative_numbers".to_string(),
+                    TestOutcome::Failed,
+                    strings(&["TestAdd/negative_numbers"])
+                ),
+                (
+                    "TestSkip".to_string(),
+                    TestOutcome::Skipped,
+                    strings(&["not on gor"])
+                ),
+                ("TestPass".to_string(), TestOutcome::Passed, Vec::new()),
+                ("TestPanic".to_string(), TestOutcome::Failed, Vec::new()),
+            ]
+        );
+        assert!(error.is_some_and(|error| {
+            error.starts_with("panic: runtime error: index out of range [1] with length 0")
+        }));
+
+        let (results, error) = test_results(source, |name| name == "TestSkip");
+        assert_eq!(
+            results,
+            vec![(
+                "TestSkip".to_string(),
+                TestOutcome::Skipped,
+                strings(&["not on gor"])
+            )]
+        );
+        assert_eq!(error, None);
+    }
+}
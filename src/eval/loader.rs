@@ -0,0 +1,1140 @@
+//! Loading a program from its package directory: every file of the package is parsed, the
+//! packages of the module it imports are loaded the same way, and the whole program is merged
+//! into one `File` the interpreter runs. The interpreter has a single package namespace, so an
+//! imported package's package level names are renamed to `util.Name`, which no identifier can
+//! collide with, and its importers' selectors `util.Name` are rewritten to match. Each
+//! package is merged after the packages it imports, so it's checked after them too, and an
+//! import cycle is an error.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use log::debug;
+
+use crate::ast::{
+    ast::{File, ImportSpec},
+    declaration::{Declaration, DeclarationKind, Parameter},
+    expression::{Expression, ExpressionKind},
+    statement::{Statement, StatementKind},
+    type_expression::{TypeExpression, TypeExpressionKind},
+};
+use crate::eval::config::LoaderConfig;
+use crate::parser::parser::parse_checked;
+use crate::primitives::{
+    diagnostic::Severity,
+    errors::load::{LoadError, LoadErrorKind},
+    position::Position,
+};
+use crate::semantic::{
+    resolver::resolve,
+    scope::{ScopeKind, SymbolKind},
+};
+
+/// A loaded program, ready to run
+#[derive(Debug, Clone)]
+pub struct Program {
+    /// Every package's declarations, the packages a package imports before it, and the
+    /// standard library packages any of them import
+    pub file: File,
+    /// The files the program was loaded from, in the order their lines were numbered
+    pub sources: Vec<Source>,
+    /// The program's import graph: each of its packages after those it imports, so the main
+    /// package is last
+    pub packages: Vec<Package>,
+}
+
+/// A package of a loaded program, with the packages of its module it imports
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    /// Like `example.com/app/shapes`, or the package's directory outside of a module
+    pub import_path: String,
+    pub name: String,
+    pub directory: PathBuf,
+    /// The import paths of the program's packages this one imports, in the order it first
+    /// imports them
+    pub imports: Vec<String>,
+}
+
+/// The files parsed by the loads it's passed to, so that loading many programs that share
+/// packages, as `gor check ./...` does, parses each of their files once
+#[derive(Debug, Default)]
+pub struct LoadCache {
+    /// Each file's source and syntax tree, its lines numbered from 1, by its absolute path
+    files: HashMap<PathBuf, (String, File)>,
+}
+
+impl LoadCache {
+    /// How many files have been parsed
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Take `source` as the text of the file at `path`, as an editor has it open with edits
+    /// it hasn't saved, rather than reading the file
+    pub fn insert(&mut self, path: &Path, source: String) -> Result<(), LoadError> {
+        let key = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let file = parse_file(path, &source)?;
+        self.files.insert(key, (source, file));
+        Ok(())
+    }
+}
+
+/// A file of a loaded program. Each file's lines are numbered on from the previous file's, so
+/// no two nodes of the program share a position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Source {
+    pub path: PathBuf,
+    /// The program's line number for the file's first line
+    pub first_line: usize,
+    pub text: String,
+}
+
+impl Source {
+    /// The file of `sources` a position of their program is in, and the position within it
+    pub fn locate(sources: &[Source], position: Position) -> Option<(&Source, Position)> {
+        let source = sources
+            .iter()
+            .rev()
+            .find(|source| source.first_line <= position.line)?;
+        let mut located = position;
+        located.shift_lines(1 - source.first_line as isize);
+        Some((source, located))
+    }
+
+    /// The file's line `line`, counting from 1, without its line ending
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.text.lines().nth(line.checked_sub(1)?)
+    }
+}
+
+impl Program {
+    /// The file a position of the program is in, and the position within that file
+    pub fn locate(&self, position: Position) -> Option<(&Path, Position)> {
+        Source::locate(&self.sources, position)
+            .map(|(source, position)| (source.path.as_path(), position))
+    }
+}
+
+/// Load the program in `path`, a package directory or a single `.go` file. Imports of packages
+/// in the module, found by the `go.mod` in `path` or a directory above it, are loaded from
+/// the module's directories. Any other import is left to the standard library.
+pub fn load(path: &Path) -> Result<Program, LoadError> {
+    load_with_config(path, &LoaderConfig::default())
+}
+
+/// Load the program in `path` as `load` does, with the package directory's files `config`
+/// chooses. The packages it imports are loaded without their tests either way.
+pub fn load_with_config(path: &Path, config: &LoaderConfig) -> Result<Program, LoadError> {
+    load_cached(path, config, &mut LoadCache::default())
+}
+
+/// Load the program in `path` as `load_with_config` does, taking the files `cache` has
+/// parsed from it rather than parsing them again, and adding those it parses
+pub fn load_cached(
+    path: &Path,
+    config: &LoaderConfig,
+    cache: &mut LoadCache,
+) -> Result<Program, LoadError> {
+    let mut loader = Loader::new(find_module(path)?);
+    loader.cache = std::mem::take(cache);
+    let files = match path.is_dir() {
+        true => go_files(path, config),
+        false => Ok(vec![path.to_path_buf()]),
+    };
+    let import_path = loader.import_path(path);
+    let loaded = files.and_then(|files| loader.load_package(path, &files, import_path));
+    *cache = std::mem::take(&mut loader.cache);
+    Ok(loader.finish(loaded?))
+}
+
+/// Load the program that's the single file `source`, like one read from standard input, named
+/// `path` where its errors are reported. It's taken to be in the current directory, so
+/// imports of the module that directory is in are loaded as `load` loads them.
+pub fn load_source(path: &Path, source: String) -> Result<Program, LoadError> {
+    let directory = Path::new(".");
+    let mut loader = Loader::new(find_module(directory)?);
+    let import_path = loader.import_path(directory);
+    let file = loader.parse_source(path, source)?;
+    let package_name = loader.add_package(directory, vec![(path, file)], import_path)?;
+    Ok(loader.finish(package_name))
+}
+
+/// The package directories `pattern` names, like the go command's: `dir/...` is `dir` and
+/// every directory under it with a `.go` file to load, leaving out `testdata` directories,
+/// those whose names start with `.` or `_`, other modules and those `config` excludes.
+/// Anything else is a package directory or file itself.
+pub fn match_packages(pattern: &str, config: &LoaderConfig) -> Result<Vec<PathBuf>, LoadError> {
+    let root = match pattern {
+        "..." => ".",
+        _ => match pattern.strip_suffix("/...") {
+            Some("") => "/",
+            Some(root) => root,
+            None => return Ok(vec![PathBuf::from(pattern)]),
+        },
+    };
+    let mut directories = Vec::new();
+    collect_packages(Path::new(root), config, &mut directories)?;
+    if directories.is_empty() {
+        return Err(LoadError::new(
+            LoadErrorKind::NoMatchingPackages,
+            PathBuf::from(pattern),
+        ));
+    }
+    directories.sort();
+    Ok(directories)
+}
+
+/// Add `directory` to `directories` if it's a package, then the packages under it
+fn collect_packages(
+    directory: &Path,
+    config: &LoaderConfig,
+    directories: &mut Vec<PathBuf>,
+) -> Result<(), LoadError> {
+    let io_error = |error: std::io::Error| {
+        LoadError::new(
+            LoadErrorKind::Io(error.to_string()),
+            directory.to_path_buf(),
+        )
+    };
+    if !go_files(directory, config)?.is_empty() {
+        directories.push(directory.to_path_buf());
+    }
+    for entry in std::fs::read_dir(directory).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        let name = file_name(&path);
+        if path.is_dir()
+            && !config.excluded(&path)
+            && !name.starts_with(['.', '_'])
+            && name != "testdata"
+            && !path.join("go.mod").is_file()
+        {
+            collect_packages(&path, config, directories)?;
+        }
+    }
+    Ok(())
+}
+
+/// The path of the module `path` is in, like `example.com/app`, from the `go.mod` in its
+/// directory or one above it
+pub fn module_path(path: &Path) -> Option<String> {
+    find_module(path).ok().flatten().map(|module| module.path)
+}
+
+/// The import path of the package in `path`, like `example.com/app/geometry`, if it's in a
+/// module
+pub fn package_import_path(path: &Path) -> Option<String> {
+    find_module(path).ok().flatten()?.import_path(path)
+}
+
+/// A module's path, like `example.com/app`, and the directory its `go.mod` is in
+#[derive(Debug)]
+struct Module {
+    path: String,
+    root: PathBuf,
+}
+
+struct Loader {
+    module: Option<Module>,
+    sources: Vec<Source>,
+    /// How many lines the files loaded so far have between them
+    lines: usize,
+    /// The standard library packages imported so far
+    imports: Vec<ImportSpec>,
+    /// The declarations of every package loaded so far, in the order they were loaded
+    decls: Vec<Declaration>,
+    /// The name of each package loaded so far, by import path
+    loaded: HashMap<String, String>,
+    /// The import path of each imported package loaded so far, by name
+    names: HashMap<String, String>,
+    /// The import paths of the packages being loaded, each importing the next
+    loading: Vec<String>,
+    /// The packages loaded so far, each after those it imports
+    packages: Vec<Package>,
+    cache: LoadCache,
+}
+
+impl Loader {
+    fn new(module: Option<Module>) -> Loader {
+        Loader {
+            module,
+            sources: Vec::new(),
+            lines: 0,
+            imports: Vec::new(),
+            decls: Vec::new(),
+            loaded: HashMap::new(),
+            names: HashMap::new(),
+            loading: Vec::new(),
+            packages: Vec::new(),
+            cache: LoadCache::default(),
+        }
+    }
+
+    /// The program of every package loaded, the main package `package_name`
+    fn finish(self, package_name: String) -> Program {
+        Program {
+            file: File::new(package_name, self.imports, self.decls),
+            sources: self.sources,
+            packages: self.packages,
+        }
+    }
+
+    /// Load the package made of `files`, after the packages they import, returning its name
+    fn load_package(
+        &mut self,
+        directory: &Path,
+        files: &[PathBuf],
+        import_path: String,
+    ) -> Result<String, LoadError> {
+        debug!(
+            "loading package {} from {}",
+            import_path,
+            directory.display()
+        );
+        let mut parsed = Vec::new();
+        for path in files {
+            parsed.push((path.as_path(), self.parse(path)?));
+        }
+        self.add_package(directory, parsed, import_path)
+    }
+
+    /// Add the package made of the `parsed` files, after the packages they import, returning
+    /// its name. The package's declarations are renamed unless it's the program's main
+    /// package.
+    fn add_package(
+        &mut self,
+        directory: &Path,
+        parsed: Vec<(&Path, File)>,
+        import_path: String,
+    ) -> Result<String, LoadError> {
+        let Some((first_path, first)) = parsed.first() else {
+            return Err(LoadError::new(
+                LoadErrorKind::NoGoFiles,
+                directory.to_path_buf(),
+            ));
+        };
+        let name = first.package_name.clone();
+        if let Some((path, file)) = parsed.iter().find(|(_, file)| file.package_name != name) {
+            return Err(LoadError::new(
+                LoadErrorKind::MultiplePackages {
+                    first: format!("{} ({})", name, file_name(first_path)),
+                    second: format!("{} ({})", file.package_name, file_name(path)),
+                },
+                directory.to_path_buf(),
+            ));
+        }
+
+        self.loading.push(import_path.clone());
+        // The name each of the package's imports of the module's packages is referred to by,
+        // and the name that package is renamed with
+        let mut qualifiers = HashMap::new();
+        let mut imports = Vec::new();
+        let mut local_imports = Vec::new();
+        let mut decls = Vec::new();
+        for (_, file) in parsed {
+            for mut import in file.imports {
+                if let Some(imported) = self.local_directory(import.unquoted_path()) {
+                    let package = self.load_import(import.unquoted_path(), &imported, directory)?;
+                    if !local_imports
+                        .iter()
+                        .any(|path| path == import.unquoted_path())
+                    {
+                        local_imports.push(import.unquoted_path().to_string());
+                    }
+                    // Unaliased, the package is referred to by its own name, which needn't be
+                    // its directory's
+                    let local_name = import.name.clone().unwrap_or_else(|| package.clone());
+                    import.name = Some(local_name.clone());
+                    qualifiers.insert(local_name, package);
+                } else if !self.imports.iter().any(|imported| {
+                    imported.local_name() == import.local_name()
+                        && imported.unquoted_path() == import.unquoted_path()
+                }) {
+                    self.imports.push(import.clone());
+                }
+                imports.push(import);
+            }
+            decls.extend(file.decls);
+        }
+        self.loading.pop();
+
+        let mut package = File::new(name.clone(), imports, decls);
+        let prefix = (!self.loading.is_empty()).then(|| name.clone());
+        rename(&mut package, prefix, &qualifiers);
+        self.decls.extend(package.decls);
+        self.packages.push(Package {
+            import_path: import_path.clone(),
+            name: name.clone(),
+            directory: directory.to_path_buf(),
+            imports: local_imports,
+        });
+        self.loaded.insert(import_path, name.clone());
+        Ok(name)
+    }
+
+    /// Load the package `import_path` of the module, from `directory`, unless it already has
+    /// been. `importer` is the directory of the package importing it.
+    fn load_import(
+        &mut self,
+        import_path: &str,
+        directory: &Path,
+        importer: &Path,
+    ) -> Result<String, LoadError> {
+        if let Some(name) = self.loaded.get(import_path) {
+            return Ok(name.clone());
+        }
+        if let Some(start) = self.loading.iter().position(|path| path == import_path) {
+            let mut cycle = self.loading[start..].to_vec();
+            cycle.push(import_path.to_string());
+            return Err(LoadError::new(
+                LoadErrorKind::ImportCycle(cycle),
+                importer.to_path_buf(),
+            ));
+        }
+        let files = go_files(directory, &LoaderConfig::default())?;
+        let name = self.load_package(directory, &files, import_path.to_string())?;
+        if let Some(other) = self.names.insert(name.clone(), import_path.to_string()) {
+            return Err(LoadError::new(
+                LoadErrorKind::DuplicatePackageName {
+                    name,
+                    first: other,
+                    second: import_path.to_string(),
+                },
+                directory.to_path_buf(),
+            ));
+        }
+        Ok(name)
+    }
+
+    /// Parse the file at `path`, or take it from the cache if it's been parsed, numbering its
+    /// lines on from the files parsed before it
+    fn parse(&mut self, path: &Path) -> Result<File, LoadError> {
+        let key = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some((source, file)) = self.cache.files.get(&key) {
+            debug!("reusing {}", path.display());
+            let (source, file) = (source.clone(), file.clone());
+            return Ok(self.add_source(path, source, file));
+        }
+        debug!("parsing {}", path.display());
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| LoadError::new(LoadErrorKind::Io(error.to_string()), path.into()))?;
+        let file = parse_file(path, &source)?;
+        self.cache.files.insert(key, (source.clone(), file.clone()));
+        Ok(self.add_source(path, source, file))
+    }
+
+    /// Parse `source`, the file at `path`, as `parse` does
+    fn parse_source(&mut self, path: &Path, source: String) -> Result<File, LoadError> {
+        let file = parse_file(path, &source)?;
+        Ok(self.add_source(path, source, file))
+    }
+
+    /// Add the file at `path` to the program's sources, numbering the lines of its syntax
+    /// tree on from the files added before it
+    fn add_source(&mut self, path: &Path, source: String, mut file: File) -> File {
+        let delta = self.lines as isize;
+        for import in &mut file.imports {
+            import.position_start.shift_lines(delta);
+            import.position_end.shift_lines(delta);
+        }
+        for declaration in &mut file.decls {
+            declaration.shift_lines(delta);
+        }
+        let lines = source.lines().count().max(1);
+        self.sources.push(Source {
+            path: path.to_path_buf(),
+            first_line: self.lines + 1,
+            text: source,
+        });
+        self.lines += lines;
+        file
+    }
+
+    /// The directory of the module's package `import_path`, or None if it's not the module's
+    fn local_directory(&self, import_path: &str) -> Option<PathBuf> {
+        let module = self.module.as_ref()?;
+        if import_path == module.path {
+            return Some(module.root.clone());
+        }
+        let relative = import_path.strip_prefix(&module.path)?.strip_prefix('/')?;
+        Some(module.root.join(relative))
+    }
+
+    /// The import path of the package in `path`, or the path itself outside of a module
+    fn import_path(&self, path: &Path) -> String {
+        self.module
+            .as_ref()
+            .and_then(|module| module.import_path(path))
+            .unwrap_or_else(|| package_directory(path).display().to_string())
+    }
+}
+
+impl Module {
+    /// The import path of the module's package in `path`, a directory or a file in one, or
+    /// None if it isn't the module's
+    fn import_path(&self, path: &Path) -> Option<String> {
+        let directory = std::path::absolute(package_directory(path)).ok()?;
+        let relative = directory.strip_prefix(&self.root).ok()?;
+        Some(match relative.as_os_str().is_empty() {
+            true => self.path.clone(),
+            false => format!("{}/{}", self.path, relative.to_string_lossy()),
+        })
+    }
+}
+
+/// The syntax tree of `source`, the file at `path`, or its syntax errors
+fn parse_file(path: &Path, source: &str) -> Result<File, LoadError> {
+    let (file, diagnostics) = parse_checked(source);
+    match file {
+        Some(file)
+            if diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.severity != Severity::Error) =>
+        {
+            Ok(file)
+        }
+        _ => Err(LoadError::new(
+            LoadErrorKind::Parse(diagnostics),
+            path.to_path_buf(),
+        )),
+    }
+}
+
+/// The directory of the package in `path`, which is `path` or the file's directory
+fn package_directory(path: &Path) -> &Path {
+    match path.is_dir() {
+        true => path,
+        false => path.parent().unwrap_or(path),
+    }
+}
+
+/// The module `path` is in, from the nearest `go.mod` at or above it
+fn find_module(path: &Path) -> Result<Option<Module>, LoadError> {
+    let io_error = |error: std::io::Error| {
+        LoadError::new(LoadErrorKind::Io(error.to_string()), path.to_path_buf())
+    };
+    let absolute = std::path::absolute(path).map_err(io_error)?;
+    for directory in absolute.ancestors() {
+        let go_mod = directory.join("go.mod");
+        if !go_mod.is_file() {
+            continue;
+        }
+        let contents = std::fs::read_to_string(&go_mod).map_err(io_error)?;
+        let module_path = contents.lines().find_map(|line| {
+            let module_path = line.trim().strip_prefix("module")?;
+            Some(module_path.trim().trim_matches('"').to_string())
+        });
+        return Ok(module_path.map(|path| Module {
+            path,
+            root: directory.to_path_buf(),
+        }));
+    }
+    Ok(None)
+}
+
+/// The `.go` files in `directory` that `config` loads, in name order as the go command takes
+/// them. Like the go command, this leaves out files whose names start with `.` or `_` and
+/// those constrained by `//go:build ignore`, though it doesn't evaluate other constraints.
+/// Files `config` excludes are left out too.
+pub fn go_files(directory: &Path, config: &LoaderConfig) -> Result<Vec<PathBuf>, LoadError> {
+    let io_error = |error: std::io::Error| {
+        LoadError::new(
+            LoadErrorKind::Io(error.to_string()),
+            directory.to_path_buf(),
+        )
+    };
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(directory).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        let name = file_name(&path);
+        let test = name.ends_with("_test.go");
+        if !path.is_file()
+            || !name.ends_with(".go")
+            || name.starts_with(['.', '_'])
+            || (test && !config.tests())
+            || config.excluded(&path)
+        {
+            continue;
+        }
+        let source = std::fs::read_to_string(&path)
+            .map_err(|error| LoadError::new(LoadErrorKind::Io(error.to_string()), path.clone()))?;
+        let header = Header::read(&source);
+        // An external test package's files are another package's, importing this one
+        let external = test && header.package.is_some_and(|name| name.ends_with("_test"));
+        if !header.ignored && !external {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// What the comments before a file's package clause, and the clause, say about loading it
+#[derive(Debug, Default, PartialEq)]
+struct Header<'a> {
+    /// Whether it's constrained by `//go:build ignore`
+    ignored: bool,
+    package: Option<&'a str>,
+}
+
+impl<'a> Header<'a> {
+    /// Read the header of `source`, stopping at the first line that isn't blank or a line
+    /// comment. A file that starts some other way is left for the parser to report on.
+    fn read(source: &'a str) -> Header<'a> {
+        let mut header = Header::default();
+        for line in source.lines().map(str::trim) {
+            if let Some(constraint) = line.strip_prefix("//go:build ") {
+                header.ignored |= constraint.trim() == "ignore";
+            } else if let Some(clause) = line.strip_prefix("package ") {
+                header.package = clause.split("//").next().map(str::trim);
+                break;
+            } else if !line.is_empty() && !line.starts_with("//") {
+                break;
+            }
+        }
+        header
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Rename the package level names of `package`, all of its files merged, to `prefix.Name`
+/// if there's a prefix, and rewrite each `qualifier.Name` naming another package's member to
+/// the name that member was renamed to. Which identifiers refer to what comes from the
+/// resolver, so a local variable shadowing a package level name or an import is left alone.
+fn rename(package: &mut File, prefix: Option<String>, qualifiers: &HashMap<String, String>) {
+    let (table, _) = resolve(package);
+    let mut renamer = Renamer {
+        prefix,
+        renamed: HashSet::new(),
+        qualifiers: HashMap::new(),
+    };
+    for (position, symbol) in table.defs().chain(table.uses()) {
+        let symbol = table.symbol(symbol);
+        if table.scope(symbol.scope).kind == ScopeKind::Package {
+            renamer.renamed.insert(position);
+        } else if symbol.kind == SymbolKind::Package
+            && let Some(package) = qualifiers.get(&symbol.name)
+        {
+            renamer.qualifiers.insert(position, package.clone());
+        }
+    }
+    if renamer.prefix.is_some() || !renamer.qualifiers.is_empty() {
+        for declaration in &mut package.decls {
+            renamer.declaration(declaration);
+        }
+    }
+}
+
+struct Renamer {
+    /// The package's name, unless it's the main package, whose names are left as they are
+    prefix: Option<String>,
+    /// The positions of identifiers declaring or using package level names
+    renamed: HashSet<Position>,
+    /// The package each import of the module's packages refers to, by the positions of its
+    /// uses
+    qualifiers: HashMap<Position, String>,
+}
+
+impl Renamer {
+    fn name(&self, name: &mut String, position: Position) {
+        if let Some(prefix) = &self.prefix
+            && self.renamed.contains(&position)
+        {
+            *name = format!("{}.{}", prefix, name);
+        }
+    }
+
+    fn declaration(&self, declaration: &mut Declaration) {
+        match &mut declaration.kind {
+            DeclarationKind::Function {
+                receiver,
+                name,
+                parameters,
+                results,
+                body,
+            } => {
+                // A function's symbol is declared at `func`. Methods aren't renamed, as
+                // they're found through their receiver's type.
+                self.name(name, declaration.position_start);
+                for parameter in receiver.iter_mut().chain(parameters).chain(results) {
+                    self.parameter(parameter);
+                }
+                self.statements(body);
+            }
+            DeclarationKind::Variable { specs, .. } | DeclarationKind::Constant { specs, .. } => {
+                for spec in specs {
+                    for expression in spec.names.iter_mut().chain(&mut spec.values) {
+                        self.expression(expression);
+                    }
+                    if let Some(type_expression) = &mut spec.type_expression {
+                        self.type_expression(type_expression);
+                    }
+                }
+            }
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } => {
+                self.expression(name);
+                self.type_expression(type_expression);
+            }
+        }
+    }
+
+    fn parameter(&self, parameter: &mut Parameter) {
+        self.type_expression(&mut parameter.type_expression);
+    }
+
+    fn statements(&self, statements: &mut [Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&self, statement: &mut Statement) {
+        match &mut statement.kind {
+            StatementKind::Expression(expression)
+            | StatementKind::Defer(expression)
+            | StatementKind::Go(expression) => self.expression(expression),
+            StatementKind::Declaration(declaration) => self.declaration(declaration),
+            StatementKind::Assignment { left, right, .. } => {
+                for expression in left.iter_mut().chain(right) {
+                    self.expression(expression);
+                }
+            }
+            StatementKind::IncDec { target, .. } => self.expression(target),
+            StatementKind::OpAssign { target, value, .. } => {
+                self.expression(target);
+                self.expression(value);
+            }
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                for statement in [init, post].into_iter().flatten() {
+                    self.statement(statement);
+                }
+                if let Some(condition) = condition {
+                    self.expression(condition);
+                }
+                self.statements(body);
+            }
+            StatementKind::Switch { init, tag, cases } => {
+                if let Some(init) = init {
+                    self.statement(init);
+                }
+                if let Some(tag) = tag {
+                    self.expression(tag);
+                }
+                for case in cases {
+                    for expression in case.expressions.iter_mut().flatten() {
+                        self.expression(expression);
+                    }
+                    self.statements(&mut case.body);
+                }
+            }
+            StatementKind::Range {
+                key,
+                value,
+                expression,
+                body,
+                ..
+            } => {
+                for variable in [key, value].into_iter().flatten() {
+                    self.expression(variable);
+                }
+                self.expression(expression);
+                self.statements(body);
+            }
+            StatementKind::Block(body) => self.statements(body),
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => {
+                if let Some(init) = init {
+                    self.statement(init);
+                }
+                self.expression(condition);
+                self.statements(body);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            StatementKind::Return(results) => {
+                for result in results {
+                    self.expression(result);
+                }
+            }
+            StatementKind::Send { channel, value } => {
+                self.expression(channel);
+                self.expression(value);
+            }
+            StatementKind::Select(cases) => {
+                for case in cases {
+                    if let Some(communication) = &mut case.communication {
+                        self.statement(communication);
+                    }
+                    self.statements(&mut case.body);
+                }
+            }
+            StatementKind::Break | StatementKind::Continue | StatementKind::Fallthrough => {}
+        }
+    }
+
+    fn expression(&self, expression: &mut Expression) {
+        match &mut expression.kind {
+            ExpressionKind::Identifier(name) => self.name(name, expression.position_start),
+            ExpressionKind::FieldAccess { object, field, .. } => {
+                let qualifier = match &object.kind {
+                    ExpressionKind::Identifier(_) => self.qualifiers.get(&object.position_start),
+                    _ => None,
+                };
+                match qualifier {
+                    Some(package) => {
+                        expression.kind =
+                            ExpressionKind::Identifier(format!("{}.{}", package, field));
+                    }
+                    None => self.expression(object),
+                }
+            }
+            ExpressionKind::Binary { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            ExpressionKind::FunctionCall {
+                name, arguments, ..
+            } => {
+                self.expression(name);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            }
+            ExpressionKind::Index { object, index } => {
+                self.expression(object);
+                self.expression(index);
+            }
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                self.expression(object);
+                for bound in [low, high, max].into_iter().flatten() {
+                    self.expression(bound);
+                }
+            }
+            ExpressionKind::Parenthesized(inner)
+            | ExpressionKind::Receive(inner)
+            | ExpressionKind::Unary { operand: inner, .. } => self.expression(inner),
+            ExpressionKind::Conversion { target, expression } => {
+                self.type_expression(target);
+                self.expression(expression);
+            }
+            // Field names as keys were never resolved, so aren't renamed
+            ExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            } => {
+                if let Some(type_expression) = type_expression {
+                    self.type_expression(type_expression);
+                }
+                for element in elements {
+                    if let Some(key) = &mut element.key {
+                        self.expression(key);
+                    }
+                    self.expression(&mut element.value);
+                }
+            }
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            } => {
+                for parameter in parameters.iter_mut().chain(results) {
+                    self.parameter(parameter);
+                }
+                self.statements(body);
+            }
+            ExpressionKind::Type(type_expression) => self.type_expression(type_expression),
+            ExpressionKind::BlankIdentifier
+            | ExpressionKind::IntegerLiteral(_)
+            | ExpressionKind::FloatLiteral(_)
+            | ExpressionKind::RuneLiteral(_)
+            | ExpressionKind::StringLiteral(_) => {}
+        }
+    }
+
+    fn type_expression(&self, type_expression: &mut TypeExpression) {
+        match &mut type_expression.kind {
+            TypeExpressionKind::Name(name) => self.name(name, type_expression.position_start),
+            TypeExpressionKind::Qualified { name, .. } => {
+                if let Some(package) = self.qualifiers.get(&type_expression.position_start) {
+                    type_expression.kind =
+                        TypeExpressionKind::Name(format!("{}.{}", package, name));
+                }
+            }
+            TypeExpressionKind::Pointer(element)
+            | TypeExpressionKind::Slice(element)
+            | TypeExpressionKind::Channel { element, .. } => self.type_expression(element),
+            TypeExpressionKind::Array { length, element } => {
+                self.expression(length);
+                self.type_expression(element);
+            }
+            TypeExpressionKind::Map { key, value } => {
+                self.type_expression(key);
+                self.type_expression(value);
+            }
+            TypeExpressionKind::Struct(fields) => {
+                for field in fields {
+                    self.type_expression(&mut field.type_expression);
+                }
+            }
+            TypeExpressionKind::Interface(methods) => {
+                for method in methods {
+                    for parameter in method.parameters.iter_mut().chain(&mut method.results) {
+                        self.parameter(parameter);
+                    }
+                }
+            }
+            TypeExpressionKind::Function {
+                parameters,
+                results,
+            } => {
+                for parameter in parameters.iter_mut().chain(results) {
+                    self.parameter(parameter);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/testfiles/module")
+            .join(path)
+    }
+
+    fn function_names(program: &Program) -> Vec<&str> {
+        let mut names: Vec<&str> = program
+            .file
+            .decls
+            .iter()
+            .filter_map(|declaration| match &declaration.kind {
+                DeclarationKind::Function {
+                    receiver: None,
+                    name,
+                    ..
+                } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn load_packages_of_the_module() {
+        let program = load(&module("cmd/app")).unwrap();
+        assert_eq!(program.file.package_name, "main");
+        // Only the standard library's imports are left
+        let imports: Vec<&str> = program
+            .file
+            .imports
+            .iter()
+            .map(ImportSpec::unquoted_path)
+            .collect();
+        assert_eq!(imports, ["strings", "fmt"]);
+        assert_eq!(
+            function_names(&program),
+            [
+                "greeting",
+                "main",
+                "shapes.New",
+                "shapes.area",
+                "text.Title",
+                "text.Upper"
+            ]
+        );
+        let source = program.file.to_source();
+        // A local variable shadowing a package level name or an import keeps its name
+        assert!(source.contains("area := s.Side * s.Side\n\treturn area\n"));
+        assert!(source.contains("shapes := []int{1, 2}\n\tfmt.Println(len(shapes))"));
+        assert!(source.contains("var s shapes.Shape = &shapes.Square{Side: 2}"));
+        assert!(source.contains("return text.Upper(s[:1]) + s[1:]"));
+
+        // The main package's files come first, then each package after the ones it imports
+        let files: Vec<String> = program
+            .sources
+            .iter()
+            .map(|source| file_name(&source.path))
+            .collect();
+        assert_eq!(
+            files,
+            ["greet.go", "main.go", "text.go", "shapes.go", "square.go"]
+        );
+        let (path, position) = program
+            .locate(Position::new(program.sources[1].first_line + 10, 1, 2))
+            .unwrap();
+        assert_eq!(file_name(path), "main.go");
+        assert_eq!(position, Position::new(11, 1, 2));
+
+        let graph: Vec<(&str, &str, Vec<&str>)> = program
+            .packages
+            .iter()
+            .map(|package| {
+                let imports = package.imports.iter().map(String::as_str).collect();
+                (package.import_path.as_str(), package.name.as_str(), imports)
+            })
+            .collect();
+        assert_eq!(
+            graph,
+            [
+                ("example.com/app/textutil", "text", vec![]),
+                ("example.com/app/shapes", "shapes", vec![]),
+                (
+                    "example.com/app/cmd/app",
+                    "main",
+                    vec!["example.com/app/textutil", "example.com/app/shapes"]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn load_with_a_cache() {
+        let config = LoaderConfig::default();
+        let mut cache = LoadCache::default();
+        load_cached(&module("cmd/app"), &config, &mut cache).unwrap();
+        assert_eq!(cache.len(), 5);
+        // The package's files are taken from the cache, their lines numbered for the program
+        let shapes = load_cached(&module("shapes"), &config, &mut cache).unwrap();
+        assert_eq!(cache.len(), 5);
+        let parsed = load(&module("shapes")).unwrap();
+        assert_eq!(shapes.sources, parsed.sources);
+        assert_eq!(shapes.file.to_source(), parsed.file.to_source());
+        // What a load that fails parses is kept
+        assert!(load_cached(&module("cycle/a"), &config, &mut cache).is_err());
+        assert_eq!(cache.len(), 7);
+        // A file's text can be given rather than read
+        let edited = "package shapes\n\nvar Edited = 1\n";
+        cache
+            .insert(&module("shapes/square.go"), edited.to_string())
+            .unwrap();
+        let shapes = load_cached(&module("shapes"), &config, &mut cache).unwrap();
+        assert!(shapes.sources.iter().any(|source| source.text == edited));
+        assert!(
+            cache
+                .insert(&module("shapes/square.go"), "package".to_string())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn load_source_as_a_file() {
+        let path = Path::new("<standard input>");
+        let source = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(1)\n}\n";
+        let program = load_source(path, source.to_string()).unwrap();
+        assert_eq!(function_names(&program), ["main"]);
+        assert_eq!(
+            program.locate(Position::new(6, 1, 4)),
+            Some((path, Position::new(6, 1, 4)))
+        );
+        let error = load_source(path, "package main\n\nfunc {".to_string()).unwrap_err();
+        assert!(matches!(error.kind, LoadErrorKind::Parse(_)));
+        assert_eq!(error.path, path);
+    }
+
+    #[test]
+    fn load_tests_and_patterns() {
+        let tests = LoaderConfig::default().with_tests();
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files.iter().map(|path| file_name(path)).collect()
+        };
+        // Neither the ignored generator nor the external test package is the package's
+        assert_eq!(
+            names(go_files(&module("textutil"), &LoaderConfig::default()).unwrap()),
+            ["text.go"]
+        );
+        assert_eq!(
+            names(go_files(&module("textutil"), &tests).unwrap()),
+            ["text.go", "text_test.go"]
+        );
+        let program = load_with_config(&module("textutil"), &tests).unwrap();
+        assert_eq!(function_names(&program), ["TestTitle", "Title", "Upper"]);
+
+        let root = module("").display().to_string();
+        assert_eq!(
+            match_packages(&format!("{}/...", root), &tests).unwrap(),
+            [
+                "cmd/app", "cycle/a", "cycle/b", "mixed", "shapes", "textutil"
+            ]
+            .map(module)
+        );
+        assert_eq!(
+            match_packages("main.go", &tests).unwrap(),
+            [PathBuf::from("main.go")]
+        );
+        // A testdata directory is only matched when the pattern is in it
+        assert_eq!(
+            match_packages(&format!("{}/textutil/testdata/...", root), &tests).unwrap(),
+            [module("textutil/testdata")]
+        );
+        let error = match_packages(&format!("{}/docs/...", root), &tests).unwrap_err();
+        assert_eq!(error.kind, LoadErrorKind::NoMatchingPackages);
+
+        // An excluded directory is left out with everything under it, and a file on its own
+        let excluded = tests
+            .with_excluded(&module("cycle"))
+            .with_excluded(&module("textutil/text_test.go"));
+        assert_eq!(
+            match_packages(&format!("{}/...", root), &excluded).unwrap(),
+            ["cmd/app", "mixed", "shapes", "textutil"].map(module)
+        );
+        assert_eq!(
+            names(go_files(&module("textutil"), &excluded).unwrap()),
+            ["text.go"]
+        );
+    }
+
+    #[test]
+    fn load_errors() {
+        let error = load(&module("cycle/a")).unwrap_err();
+        assert_eq!(
+            error.kind,
+            LoadErrorKind::ImportCycle(vec![
+                "example.com/app/cycle/a".to_string(),
+                "example.com/app/cycle/b".to_string(),
+                "example.com/app/cycle/a".to_string(),
+            ])
+        );
+        let error = load(&module("mixed")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{}: found packages a (a.go) and b (b.go)",
+                module("mixed").display()
+            )
+        );
+        assert_eq!(
+            load(&module("")).unwrap_err().kind,
+            LoadErrorKind::NoGoFiles
+        );
+    }
+}
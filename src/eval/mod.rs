@@ -0,0 +1,13 @@
+pub mod builtins;
+pub mod config;
+pub mod evaluator;
+pub mod fmt;
+pub mod interpreter;
+pub mod loader;
+pub mod observer;
+pub mod repl;
+pub mod scheduler;
+pub mod stdlib;
+pub mod sync;
+pub mod testing;
+pub mod value;
@@ -0,0 +1,68 @@
+use std::io::Write;
+
+use crate::ast::{expression::Expression, statement::Statement};
+use crate::eval::{loader::Source, scheduler::MAIN_GOROUTINE, value::Value};
+use crate::primitives::position::Position;
+
+/// Watches a program as it runs, for tracing it or stepping through it. The interpreter calls
+/// it before it goes on, so one that waits, say for a key press, pauses the program. Every
+/// method does nothing unless it's implemented.
+pub trait Observer {
+    /// `statement` is about to run on goroutine `goroutine`, which is 1 for main's
+    fn on_statement_enter(&mut self, _statement: &Statement, _goroutine: usize) {}
+
+    /// A Go function or method, or a package's function, is about to be called at `position`.
+    /// It's named like `f`, `(*Point).Scale` or `strings.Repeat`.
+    fn on_call(&mut self, _function: &str, _arguments: &[Value], _position: Position) {}
+
+    /// `expression` was evaluated to `value`
+    fn on_value(&mut self, _expression: &Expression, _value: &Value) {}
+}
+
+impl std::fmt::Debug for dyn Observer + Send {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Observer")
+    }
+}
+
+/// Writes each statement as it's run, with its file, line and source, like
+/// `main.go:5: x := f(2)`. Statements other goroutines run are marked with theirs, like
+/// `[goroutine 2] main.go:9: ch <- x`.
+pub struct Tracer {
+    sources: Vec<Source>,
+    output: Box<dyn Write + Send>,
+}
+
+impl Tracer {
+    /// Trace a program loaded from `sources`, writing to `output`
+    pub fn new(sources: Vec<Source>, output: impl Write + Send + 'static) -> Tracer {
+        Tracer {
+            sources,
+            output: Box::new(output),
+        }
+    }
+}
+
+impl Observer for Tracer {
+    fn on_statement_enter(&mut self, statement: &Statement, goroutine: usize) {
+        let position = statement.position_start;
+        let mut line = match Source::locate(&self.sources, position) {
+            Some((source, position)) => format!(
+                "{}:{}: {}",
+                source.path.display(),
+                position.line,
+                source.line(position.line).unwrap_or_default().trim()
+            ),
+            None => format!(
+                "{}: {}",
+                position.line,
+                statement.to_string().lines().next().unwrap_or_default()
+            ),
+        };
+        if goroutine != MAIN_GOROUTINE {
+            line = format!("[goroutine {}] {}", goroutine, line);
+        }
+        // A trace that can't be written isn't worth ending the program over
+        let _ = writeln!(self.output, "{}", line);
+    }
+}
@@ -0,0 +1,215 @@
+use std::io::Write;
+
+use crate::ast::expression::{Expression, ExpressionKind};
+use crate::eval::{
+    evaluator::{Evaluator, callee_name},
+    fmt,
+};
+use crate::lexer::{lexer::Lexer, token_type::TokenKind};
+use crate::parser::parser::Parser;
+use crate::primitives::errors::{lexer::LexerErrorKind, repl::ReplError};
+
+/// The print functions, whose results a REPL doesn't print, since they're there to print
+const PRINT_FUNCTIONS: &[&str] = &[
+    "fmt.Print",
+    "fmt.Println",
+    "fmt.Printf",
+    "fmt.Fprint",
+    "fmt.Fprintln",
+    "fmt.Fprintf",
+];
+
+/// Runs Go an input at a time, as `gor repl` does. An input is an import, a function or type
+/// declaration, a statement, or an expression whose value is printed, and whatever one
+/// declares is there for the inputs after it, as though they were all in one function.
+#[derive(Debug)]
+pub struct Repl {
+    evaluator: Evaluator,
+}
+
+impl Repl {
+    pub fn new() -> Repl {
+        let evaluator = Evaluator::new();
+        evaluator.start();
+        Repl { evaluator }
+    }
+
+    /// Write what the inputs print to `output` rather than the process's standard output
+    pub fn with_output(mut self, output: impl Write + Send + 'static) -> Repl {
+        self.evaluator = std::mem::take(&mut self.evaluator).with_output(output);
+        self
+    }
+
+    /// Whether `input` needs more lines before it can be run, because it has braces,
+    /// brackets or parentheses left open, or a comment that isn't closed
+    pub fn is_incomplete(input: &str) -> bool {
+        let mut lexer = Lexer::new(input);
+        let mut depth = 0isize;
+        loop {
+            match lexer.next_token().kind {
+                Some(TokenKind::LeftBrace | TokenKind::LeftBracket | TokenKind::LeftParen) => {
+                    depth += 1
+                }
+                Some(TokenKind::RightBrace | TokenKind::RightBracket | TokenKind::RightParen) => {
+                    depth -= 1
+                }
+                Some(TokenKind::EOF) => break,
+                _ => {}
+            }
+        }
+        depth > 0
+            || lexer
+                .errors()
+                .iter()
+                .any(|error| matches!(error.kind, LexerErrorKind::UnterminatedComment(_)))
+    }
+
+    /// Run `input`, returning an expression's value as fmt.Println would print it, without
+    /// the line ending, or the results of a call other than a print function's separated by
+    /// spaces. A panic ends only the input it's in.
+    pub fn eval(&mut self, input: &str) -> Result<Option<String>, ReplError> {
+        let first_word = input
+            .trim_start()
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        let result = match first_word {
+            "import" | "func" | "type" => self.declare(input).map(|()| None),
+            _ => match Parser::parse_expression_str(input) {
+                Ok(expression) => self.print(&expression),
+                Err(_) => {
+                    let statement = Parser::parse_statement_str(input).map_err(ReplError::Parse)?;
+                    self.evaluator
+                        .exec_statement(&statement)
+                        .map(|_| None)
+                        .map_err(ReplError::Runtime)
+                }
+            },
+        };
+        if result.is_err() {
+            self.evaluator.clear_panic();
+        }
+        result
+    }
+
+    /// Run imports and package level declarations, which may replace earlier ones
+    fn declare(&mut self, input: &str) -> Result<(), ReplError> {
+        // The package clause takes a line of its own before the input's first
+        let mut parser = Parser::new_at_line(&format!("package main\n{}", input), 0);
+        let file = parser.parse().map_err(ReplError::Parse)?;
+        if !parser.errors.is_empty() {
+            return Err(ReplError::Parse(parser.errors));
+        }
+        for import in &file.imports {
+            self.evaluator.import(import);
+        }
+        for declaration in &file.decls {
+            self.evaluator
+                .declare(declaration)
+                .map_err(ReplError::Runtime)?;
+        }
+        Ok(())
+    }
+
+    /// Evaluate `expression`, printed unless it's a call to a print function or has no
+    /// results
+    fn print(&mut self, expression: &Expression) -> Result<Option<String>, ReplError> {
+        let values = match &expression.kind {
+            ExpressionKind::FunctionCall { .. } => self.evaluator.eval_call(expression),
+            _ => self
+                .evaluator
+                .eval_expression(expression)
+                .map(|value| vec![value]),
+        }
+        .map_err(ReplError::Runtime)?;
+        let is_print = PRINT_FUNCTIONS.contains(&callee_name(expression).as_str());
+        if values.is_empty() || is_print {
+            return Ok(None);
+        }
+        let values = self
+            .evaluator
+            .as_printed(&values, expression)
+            .map_err(ReplError::Runtime)?;
+        let printed = fmt::sprintln(&values);
+        Ok(Some(printed.trim_end_matches('\n').to_string()))
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Repl {
+        Repl::new()
+    }
+}
+
+/// Ends the goroutines the inputs started
+impl Drop for Repl {
+    fn drop(&mut self) {
+        self.evaluator.exit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Captured;
+
+    #[test]
+    fn eval_inputs() {
+        let captured = Captured::default();
+        let mut repl = Repl::new().with_output(captured.clone());
+        let inputs = [
+            ("import \"fmt\"", Ok(None)),
+            ("x := 2", Ok(None)),
+            ("func double(n int) int {\n\treturn n * 2\n}", Ok(None)),
+            ("double(x) + 1", Ok(Some("5"))),
+            ("fmt.Println(\"hi\", x)", Ok(None)),
+            (
+                "func pair() (int, string) {\n\treturn x, \"two\"\n}",
+                Ok(None),
+            ),
+            ("pair()", Ok(Some("2 two"))),
+            ("type Point struct {\n\tX, Y int\n}", Ok(None)),
+            ("p := Point{X: 1}", Ok(None)),
+            ("p.Y = x", Ok(None)),
+            ("p", Ok(Some("{1 2}"))),
+            ("s := []int{1, 2}", Ok(None)),
+            (
+                "s[5]",
+                Err("runtime error: index out of range [5] with length 2"),
+            ),
+            (
+                "panic(\"boom\")",
+                Err("panic: boom\n\ngoroutine 1 [running]:"),
+            ),
+            // Neither the panic nor the failed input undid what came before
+            ("len(s) + x", Ok(Some("4"))),
+            ("func send(ch chan int) {\n\tch <- x\n}", Ok(None)),
+            ("ch := make(chan int)", Ok(None)),
+            ("go send(ch)", Ok(None)),
+            ("<-ch", Ok(Some("2"))),
+            ("x :=", Err("1:4: Not a primary expression: ")),
+        ];
+        for (input, want) in inputs {
+            let result = repl.eval(input).map_err(|error| error.to_string());
+            assert_eq!(
+                result,
+                want.map(|value| value.map(str::to_string))
+                    .map_err(str::to_string),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(captured.take(), "hi 2\n");
+    }
+
+    #[test]
+    fn incomplete_inputs() {
+        assert!(Repl::is_incomplete("func f() {"));
+        assert!(Repl::is_incomplete("x := []int{1,\n2,"));
+        assert!(Repl::is_incomplete("/* a"));
+        assert!(!Repl::is_incomplete("func f() {\n}"));
+        assert!(!Repl::is_incomplete("s := \"{\" // ("));
+        // Too many closing braces is an error to report, not a reason to wait
+        assert!(!Repl::is_incomplete("}"));
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+use crate::primitives::errors::runtime::RuntimeError;
+
+/// The id of the goroutine running `func main`, which Go numbers 1 too
+pub const MAIN_GOROUTINE: usize = 1;
+
+/// Runs goroutines one at a time. Each has a thread of its own, but only the goroutine
+/// holding the turn runs, so values are shared between them without locks and a program
+/// runs the same way every time. A goroutine gives up the turn when it blocks, sleeps, yields
+/// or returns, and it passes to the goroutine that's been ready the longest. Goroutines whose
+/// sleep has ended are ready, and when only sleeping ones are left the scheduler waits for the
+/// first to wake.
+#[derive(Debug)]
+pub struct Scheduler {
+    state: Mutex<State>,
+    /// Signalled whenever the turn passes
+    turn: Condvar,
+}
+
+#[derive(Debug)]
+struct State {
+    /// The goroutine holding the turn
+    running: usize,
+    /// Goroutines waiting for the turn, in the order they'll get it
+    ready: VecDeque<usize>,
+    /// Goroutines waiting for a channel to change, all made ready again when any does
+    blocked: Vec<usize>,
+    /// Goroutines sleeping, with when they wake
+    sleeping: Vec<(Instant, usize)>,
+    next_id: usize,
+    /// Set once main returns, after which each goroutine left unwinds when given the turn
+    exiting: bool,
+    /// What ended the program, for main to report once it's given the turn
+    failure: Option<Interruption>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+/// Why a goroutine given the turn can't carry on from where it waited
+#[derive(Debug)]
+pub enum Interruption {
+    /// Every goroutine is blocked, so none will ever be woken. Main reports it.
+    Deadlock,
+    /// Another goroutine failed, which ends the whole program. Main reports it.
+    Failed(RuntimeError),
+    /// Main returned, and the goroutine should unwind without running anything more
+    Exiting,
+}
+
+impl State {
+    fn new() -> State {
+        State {
+            running: MAIN_GOROUTINE,
+            ready: VecDeque::new(),
+            blocked: Vec::new(),
+            sleeping: Vec::new(),
+            next_id: MAIN_GOROUTINE + 1,
+            exiting: false,
+            failure: None,
+            threads: Vec::new(),
+        }
+    }
+
+    /// Wake main to report why the program ended, wherever it's waiting
+    fn fail(&mut self, failure: Interruption) {
+        self.ready.retain(|id| *id != MAIN_GOROUTINE);
+        self.blocked.retain(|id| *id != MAIN_GOROUTINE);
+        self.sleeping.retain(|(_, id)| *id != MAIN_GOROUTINE);
+        self.failure.get_or_insert(failure);
+        self.running = MAIN_GOROUTINE;
+    }
+
+    /// Make the goroutines whose sleep has ended ready, those that woke first first
+    fn wake_sleepers(&mut self) {
+        let now = Instant::now();
+        self.sleeping.sort();
+        let woken = self.sleeping.partition_point(|(until, _)| *until <= now);
+        let woken: Vec<usize> = self.sleeping.drain(..woken).map(|(_, id)| id).collect();
+        self.ready.extend(woken);
+    }
+
+    /// The goroutine to give the turn to next: the one that's been ready the longest, or when
+    /// none is, the sleeping one that wakes first, once it has. None when every goroutine is
+    /// blocked.
+    fn next(&mut self) -> Option<usize> {
+        self.wake_sleepers();
+        if let Some(next) = self.ready.pop_front() {
+            return Some(next);
+        }
+        self.sleeping.sort();
+        if self.sleeping.is_empty() {
+            return None;
+        }
+        let (until, next) = self.sleeping.remove(0);
+        // Nothing else can run in the meantime
+        std::thread::sleep(until.saturating_duration_since(Instant::now()));
+        Some(next)
+    }
+
+    /// Any goroutine left, ready, blocked or sleeping, to unwind once main has returned
+    fn next_to_unwind(&mut self) -> Option<usize> {
+        self.ready
+            .pop_front()
+            .or_else(|| self.blocked.pop())
+            .or_else(|| self.sleeping.pop().map(|(_, id)| id))
+    }
+
+    /// Whether goroutine `id`, just given the turn, carries on
+    fn resume(&mut self, id: usize) -> Result<(), Interruption> {
+        if id == MAIN_GOROUTINE
+            && let Some(failure) = self.failure.take()
+        {
+            return Err(failure);
+        }
+        if self.exiting && id != MAIN_GOROUTINE {
+            return Err(Interruption::Exiting);
+        }
+        Ok(())
+    }
+}
+
+impl Scheduler {
+    /// A scheduler whose only goroutine is main, holding the turn
+    pub fn new() -> Scheduler {
+        Scheduler {
+            state: Mutex::new(State::new()),
+            turn: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Start a goroutine that calls `run` with its id on a thread of its own, once it's given
+    /// the turn. It's ready straight away, behind those already waiting.
+    pub fn spawn(
+        &self,
+        stack_size: usize,
+        run: impl FnOnce(usize) + Send + 'static,
+    ) -> std::io::Result<()> {
+        let mut state = self.lock();
+        let id = state.next_id;
+        let thread = std::thread::Builder::new()
+            .name(format!("goroutine {}", id))
+            .stack_size(stack_size)
+            .spawn(move || run(id))?;
+        state.next_id += 1;
+        state.ready.push_back(id);
+        state.threads.push(thread);
+        Ok(())
+    }
+
+    /// Wait until goroutine `id` is given the turn for the first time
+    pub fn wait_turn(&self, id: usize) -> Result<(), Interruption> {
+        let mut state = self.lock();
+        while state.running != id {
+            state = self
+                .turn
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        state.resume(id)
+    }
+
+    /// Let the goroutines waiting for the turn run before goroutine `id` carries on
+    pub fn yield_now(&self, id: usize) -> Result<(), Interruption> {
+        let mut state = self.lock();
+        state.wake_sleepers();
+        if state.ready.is_empty() {
+            return Ok(());
+        }
+        state.ready.push_back(id);
+        self.pass_turn(state, id)
+    }
+
+    /// Wait until something changes that goroutine `id` is waiting for, after which it checks
+    /// again. When every goroutine is waiting, nothing ever will change, which is a deadlock.
+    pub fn block(&self, id: usize) -> Result<(), Interruption> {
+        let mut state = self.lock();
+        state.blocked.push(id);
+        self.pass_turn(state, id)
+    }
+
+    /// Let the other goroutines run until `until`, when goroutine `id` carries on
+    pub fn sleep(&self, id: usize, until: Instant) -> Result<(), Interruption> {
+        let mut state = self.lock();
+        state.sleeping.push((until, id));
+        self.pass_turn(state, id)
+    }
+
+    /// Make every blocked goroutine ready, after a change one of them may be waiting for
+    pub fn wake_blocked(&self) {
+        let mut state = self.lock();
+        let blocked = std::mem::take(&mut state.blocked);
+        state.ready.extend(blocked);
+    }
+
+    /// Hand the turn to the goroutine that's been ready the longest, then wait for it to come
+    /// back to goroutine `id`
+    fn pass_turn(&self, mut state: MutexGuard<'_, State>, id: usize) -> Result<(), Interruption> {
+        match state.next() {
+            Some(next) => state.running = next,
+            None => state.fail(Interruption::Deadlock),
+        }
+        self.turn.notify_all();
+        while state.running != id {
+            state = self
+                .turn
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        state.resume(id)
+    }
+
+    /// Goroutine `id` has returned, or failed with `failure`, which ends the program. Either
+    /// way its values must all be dropped by now, since the next goroutine runs straight away.
+    pub fn finish(&self, failure: Option<RuntimeError>) {
+        let mut state = self.lock();
+        if state.exiting {
+            // The goroutines left unwind one after another, then main finishes exiting
+            state.running = state.next_to_unwind().unwrap_or(MAIN_GOROUTINE);
+        } else if let Some(failure) = failure {
+            state.fail(Interruption::Failed(failure));
+        } else if let Some(next) = state.next() {
+            state.running = next;
+        } else {
+            // Main hasn't returned, so it's blocked along with everything else
+            state.fail(Interruption::Deadlock);
+        }
+        self.turn.notify_all();
+    }
+
+    /// End the program, from main once it returns: every other goroutine unwinds in turn,
+    /// then their threads are joined
+    pub fn exit(&self) {
+        let mut state = self.lock();
+        state.exiting = true;
+        if let Some(next) = state.next_to_unwind() {
+            state.running = next;
+            self.turn.notify_all();
+            while state.running != MAIN_GOROUTINE {
+                state = self
+                    .turn
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+        let threads = std::mem::take(&mut state.threads);
+        *state = State::new();
+        drop(state);
+        for thread in threads {
+            // Goroutines report failures through the scheduler, so a thread can only panic
+            // on a bug in the interpreter itself, which has already been printed
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Scheduler {
+        Scheduler::new()
+    }
+}
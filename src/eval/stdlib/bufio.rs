@@ -0,0 +1,79 @@
+use std::{cell::RefCell, io::BufRead, rc::Rc};
+
+use crate::eval::{
+    stdlib::Arguments,
+    value::{File, Native, Scanner, Value},
+};
+use crate::primitives::errors::runtime::RuntimeError;
+
+/// Call `function` of the bufio package (https://pkg.go.dev/bufio). Only a Scanner of lines
+/// exists so far.
+pub fn call(function: &str, arguments: &Arguments) -> Result<Vec<Value>, RuntimeError> {
+    match function {
+        "NewScanner" => {
+            arguments.expect(1)?;
+            let scanner = Scanner {
+                file: arguments.file(0, "io.Reader")?.clone(),
+                token: String::new(),
+                error: None,
+            };
+            let scanner = Native::Scanner(Rc::new(RefCell::new(scanner)));
+            Ok(vec![Value::Native(scanner)])
+        }
+        _ => Err(arguments.undefined()),
+    }
+}
+
+/// Call `method` of a Scanner. Scan waits for a whole line, holding up every other goroutine
+/// while it does.
+pub fn call_method(
+    scanner: &RefCell<Scanner>,
+    method: &str,
+    arguments: &Arguments,
+) -> Result<Vec<Value>, RuntimeError> {
+    let mut scanner = scanner.borrow_mut();
+    let result = match method {
+        "Scan" => {
+            arguments.expect(0)?;
+            Value::Bool(scan(&mut scanner))
+        }
+        "Text" => {
+            arguments.expect(0)?;
+            Value::String(scanner.token.clone())
+        }
+        "Err" => {
+            arguments.expect(0)?;
+            scanner.error.clone().map_or(Value::Nil, Value::error)
+        }
+        _ => return Err(arguments.undefined_method("*bufio.Scanner", method)),
+    };
+    Ok(vec![result])
+}
+
+/// Read the next line into the scanner's token, dropping its `\n` or `\r\n`. False once the
+/// input ends or fails to be read, which leaves the token empty.
+fn scan(scanner: &mut Scanner) -> bool {
+    scanner.token.clear();
+    if scanner.error.is_some() {
+        return false;
+    }
+    let input = match &scanner.file {
+        File::Stdin(input) => input.clone(),
+        File::Stdout(_) => return fail(scanner, "read /dev/stdout: bad file descriptor"),
+        File::Stderr(_) => return fail(scanner, "read /dev/stderr: bad file descriptor"),
+    };
+    let mut line = String::new();
+    match input.borrow_mut().0.read_line(&mut line) {
+        Ok(0) => return false,
+        Ok(_) => {}
+        Err(error) => return fail(scanner, &format!("read /dev/stdin: {}", error)),
+    }
+    let line = line.strip_suffix('\n').unwrap_or(&line);
+    scanner.token = line.strip_suffix('\r').unwrap_or(line).to_string();
+    true
+}
+
+fn fail(scanner: &mut Scanner, error: &str) -> bool {
+    scanner.error = Some(error.to_string());
+    false
+}
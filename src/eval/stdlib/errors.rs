@@ -0,0 +1,89 @@
+use std::rc::Rc;
+
+use crate::eval::{
+    stdlib::Arguments,
+    value::{Error, Value},
+};
+use crate::primitives::errors::runtime::RuntimeError;
+
+/// Call `function` of the errors package (https://pkg.go.dev/errors). Is and As look through
+/// the errors an error wraps depth first, as Go's do.
+pub fn call(function: &str, arguments: &Arguments) -> Result<Vec<Value>, RuntimeError> {
+    let result = match function {
+        "New" => {
+            arguments.expect(1)?;
+            Value::error(arguments.string(0)?)
+        }
+        "Is" => {
+            arguments.expect(2)?;
+            let (error, target) = (arguments.error(0)?, arguments.error(1)?);
+            // Nil is only nil
+            if error.is_nil() || target.is_nil() {
+                Value::Bool(error.is_nil() && target.is_nil())
+            } else {
+                Value::Bool(chain(error).any(|error| error == target))
+            }
+        }
+        "As" => {
+            arguments.expect(2)?;
+            let error = arguments.error(0)?;
+            let Value::Pointer(target) = arguments.value(1) else {
+                return Err(arguments.panic("errors: target must be a non-nil pointer"));
+            };
+            if target.target.is_none() {
+                return Err(arguments.panic("errors: target must be a non-nil pointer"));
+            }
+            // An error variable holds any error, and another type's only errors of that type
+            let target_type = arguments.value(1).type_name();
+            let target_type = target_type.strip_prefix('*').unwrap_or_default();
+            let any = target_type == "error" || target.load() == Some(Value::Nil);
+            let found = chain(error).find(|error| any || error.type_name() == target_type);
+            if let Some(found) = &found {
+                target.store((*found).clone());
+            }
+            Value::Bool(found.is_some())
+        }
+        "Unwrap" => {
+            arguments.expect(1)?;
+            match arguments.error(0)? {
+                Value::Error(error) => error.unwrap().cloned().unwrap_or(Value::Nil),
+                _ => Value::Nil,
+            }
+        }
+        "Join" => {
+            let errors = (0..arguments.values().len())
+                .map(|index| arguments.error(index))
+                .collect::<Result<Vec<_>, _>>()?;
+            let wrapped: Vec<Value> = errors
+                .into_iter()
+                .filter(|error| !error.is_nil())
+                .cloned()
+                .collect();
+            if wrapped.is_empty() {
+                Value::Nil
+            } else {
+                let messages: Vec<String> = wrapped.iter().map(Value::to_string).collect();
+                Value::Error(Rc::new(Error {
+                    type_name: "*errors.joinError",
+                    message: messages.join("\n"),
+                    wrapped,
+                }))
+            }
+        }
+        _ => return Err(arguments.undefined()),
+    };
+    Ok(vec![result])
+}
+
+/// `error` followed by every error it wraps, depth first, or nothing for nil
+fn chain(error: &Value) -> impl Iterator<Item = &Value> {
+    let mut pending = vec![error];
+    std::iter::from_fn(move || {
+        let error = pending.pop()?;
+        if let Value::Error(wrapping) = error {
+            pending.extend(wrapping.wrapped.iter().rev());
+        }
+        Some(error)
+    })
+    .filter(|error| !error.is_nil())
+}
@@ -0,0 +1,281 @@
+use std::rc::Rc;
+
+use crate::ast::{
+    expression::{Expression, ExpressionKind},
+    type_expression::TypeExpression,
+};
+use crate::eval::{
+    config::Budget,
+    stdlib::time::Time,
+    value::{File, Native, Slice, Value},
+};
+use crate::primitives::{
+    errors::runtime::{RuntimeError, RuntimeErrorKind},
+    position::Position,
+};
+
+pub mod bufio;
+pub mod errors;
+pub mod os;
+pub mod strconv;
+pub mod strings;
+pub mod time;
+
+/// Calls a native package's function by name
+pub type Package = fn(&str, &Arguments) -> Result<Vec<Value>, RuntimeError>;
+
+/// A function the application embedding the interpreter provides to programs, which call it
+/// like any package's function, as `mypkg.DoThing(x)` once they've imported "mypkg"
+#[derive(Clone)]
+pub struct HostFunction(Rc<HostFn>);
+
+type HostFn = dyn Fn(&Arguments) -> Result<Vec<Value>, RuntimeError> + Send;
+
+impl HostFunction {
+    pub fn new(
+        function: impl Fn(&Arguments) -> Result<Vec<Value>, RuntimeError> + Send + 'static,
+    ) -> HostFunction {
+        HostFunction(Rc::new(function))
+    }
+
+    pub fn call(&self, arguments: &Arguments) -> Result<Vec<Value>, RuntimeError> {
+        (self.0)(arguments)
+    }
+}
+
+impl std::fmt::Debug for HostFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HostFunction")
+    }
+}
+
+/// The packages implemented natively, by path. fmt writes to the program's output, os's
+/// variables are the program's streams and arguments, and sync's methods and time.Sleep hand
+/// the turn to other goroutines, so the evaluator implements those itself.
+const PACKAGES: &[(&str, Package)] = &[
+    ("bufio", bufio::call),
+    ("errors", errors::call),
+    ("os", os::call),
+    ("strconv", strconv::call),
+    ("strings", strings::call),
+    ("time", time::call),
+];
+
+/// The native package imported as `path`, if it's implemented
+pub fn package(path: &str) -> Option<Package> {
+    PACKAGES
+        .iter()
+        .find(|(name, _)| *name == path)
+        .map(|(_, package)| *package)
+}
+
+/// A `[]string` of `strings`, made by the expression at `position`
+pub fn string_slice(strings: Vec<String>, position: Position) -> Value {
+    let element = TypeExpression::new_name("string".to_string(), position);
+    let elements: Vec<Value> = strings.into_iter().map(Value::String).collect();
+    let len = elements.len();
+    Value::Slice(Slice::new(Rc::new(element), elements, len))
+}
+
+/// The arguments of a call to a native package function, like `strings.Split`, checked
+/// against the types of the function's parameters as they're taken
+pub struct Arguments<'a> {
+    /// The function as it's called, like `strings.Split`, or the method, like `scanner.Scan`
+    function: String,
+    values: &'a [Value],
+    call: &'a Expression,
+    /// The program's allocation limit, for a function that would make something too large
+    /// to make at all
+    budget: Option<&'a Budget>,
+}
+
+impl<'a> Arguments<'a> {
+    pub fn new(function: String, values: &'a [Value], call: &'a Expression) -> Arguments<'a> {
+        Arguments {
+            function,
+            values,
+            call,
+            budget: None,
+        }
+    }
+
+    pub(crate) fn with_budget(self, budget: &'a Budget) -> Arguments<'a> {
+        Arguments {
+            budget: Some(budget),
+            ..self
+        }
+    }
+
+    /// Fail if making something of `bytes` would take the program past its allocation limit.
+    /// The evaluator counts what the function returns once it has.
+    pub fn can_allocate(&self, bytes: usize) -> Result<(), RuntimeError> {
+        match self.budget {
+            Some(budget) => budget.can_allocate(bytes, self.position()),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail unless there are `want` arguments
+    pub fn expect(&self, want: usize) -> Result<(), RuntimeError> {
+        if self.values.len() == want {
+            return Ok(());
+        }
+        Err(RuntimeError::new(
+            RuntimeErrorKind::ArgumentCount {
+                function: self.function.clone(),
+                have: self.values.len(),
+                want,
+            },
+            self.call.position_start,
+        ))
+    }
+
+    /// The argument as it is, for a parameter of any type
+    pub fn value(&self, index: usize) -> &'a Value {
+        &self.values[index]
+    }
+
+    /// Every argument, for a variadic parameter
+    pub fn values(&self) -> &'a [Value] {
+        self.values
+    }
+
+    pub fn string(&self, index: usize) -> Result<&'a str, RuntimeError> {
+        match self.values[index].underlying() {
+            Value::String(string) => Ok(string),
+            value => Err(self.cannot_use(index, value, "string")),
+        }
+    }
+
+    pub fn int(&self, index: usize) -> Result<i64, RuntimeError> {
+        match self.values[index].underlying() {
+            Value::Int(int) => Ok(*int),
+            value => Err(self.cannot_use(index, value, "int")),
+        }
+    }
+
+    pub fn float(&self, index: usize) -> Result<f64, RuntimeError> {
+        match self.values[index].underlying() {
+            Value::Float(float) => Ok(*float),
+            // An untyped constant like 2 is a float64 here
+            Value::Int(int) => Ok(*int as f64),
+            value => Err(self.cannot_use(index, value, "float64")),
+        }
+    }
+
+    pub fn bool(&self, index: usize) -> Result<bool, RuntimeError> {
+        match self.values[index].underlying() {
+            Value::Bool(bool) => Ok(*bool),
+            value => Err(self.cannot_use(index, value, "bool")),
+        }
+    }
+
+    /// A `time.Duration`, which may be an untyped constant like 5
+    pub fn duration(&self, index: usize) -> Result<i64, RuntimeError> {
+        match self.values[index].underlying() {
+            Value::Duration(duration) | Value::Int(duration) => Ok(*duration),
+            value => Err(self.cannot_use(index, value, "time.Duration")),
+        }
+    }
+
+    pub fn time(&self, index: usize) -> Result<Time, RuntimeError> {
+        match self.values[index].underlying() {
+            Value::Native(Native::Time(time)) => Ok(*time),
+            value => Err(self.cannot_use(index, value, "time.Time")),
+        }
+    }
+
+    /// One of the standard streams, passed as an `io.Reader` or `io.Writer`
+    pub fn file(&self, index: usize, parameter_type: &str) -> Result<&'a File, RuntimeError> {
+        match self.values[index].underlying() {
+            Value::Native(Native::File(file)) => Ok(file),
+            value => Err(self.cannot_use(index, value, parameter_type)),
+        }
+    }
+
+    /// An `error` argument, which may be nil. A struct, pointer or value of another named type
+    /// is taken to be of a type declaring an Error method, as the checker makes sure it is.
+    pub fn error(&self, index: usize) -> Result<&'a Value, RuntimeError> {
+        match &self.values[index] {
+            value @ (Value::Error(_)
+            | Value::Struct(_)
+            | Value::Pointer(_)
+            | Value::Named(_)
+            | Value::Nil) => Ok(value),
+            value => Err(self.cannot_use(index, value, "error")),
+        }
+    }
+
+    /// A `[]string` argument's elements
+    pub fn strings(&self, index: usize) -> Result<Vec<String>, RuntimeError> {
+        let value = &self.values[index];
+        let Value::Slice(slice) = value.underlying() else {
+            return Err(self.cannot_use(index, value, "[]string"));
+        };
+        slice
+            .elements()
+            .into_iter()
+            .map(|element| match element.into_underlying() {
+                Value::String(string) => Ok(string),
+                _ => Err(self.cannot_use(index, value, "[]string")),
+            })
+            .collect()
+    }
+
+    /// A `[]string` result
+    pub fn string_slice(&self, strings: Vec<String>) -> Value {
+        string_slice(strings, self.call.position_start)
+    }
+
+    /// The error for a function the package doesn't have, or doesn't have yet
+    pub fn undefined(&self) -> RuntimeError {
+        RuntimeError::new(
+            RuntimeErrorKind::UndefinedName(self.function.clone()),
+            self.call.position_start,
+        )
+    }
+
+    /// The error for a method the receiver's type doesn't have, or doesn't have yet
+    pub fn undefined_method(&self, value_type: &str, method: &str) -> RuntimeError {
+        self.fail(RuntimeErrorKind::UndefinedMethod {
+            expression: self.function.clone(),
+            value_type: value_type.to_string(),
+            method: method.to_string(),
+        })
+    }
+
+    /// An error of the call, like one ending the program
+    pub fn fail(&self, kind: RuntimeErrorKind) -> RuntimeError {
+        RuntimeError::new(kind, self.position())
+    }
+
+    /// Where the call starts
+    pub fn position(&self) -> Position {
+        self.call.position_start
+    }
+
+    /// The panic of a function called with arguments it can't handle, like a negative count
+    pub fn panic(&self, message: &str) -> RuntimeError {
+        RuntimeError::new(
+            RuntimeErrorKind::PackagePanic(message.to_string()),
+            self.call.position_start,
+        )
+    }
+
+    fn cannot_use(&self, index: usize, value: &Value, parameter_type: &str) -> RuntimeError {
+        let position = match &self.call.kind {
+            ExpressionKind::FunctionCall { arguments, .. } => arguments
+                .get(index)
+                .map_or(self.call.position_start, |argument| argument.position_start),
+            _ => self.call.position_start,
+        };
+        RuntimeError::new(
+            RuntimeErrorKind::CannotUseArgument {
+                value_type: value.type_name(),
+                parameter_type: parameter_type.to_string(),
+                function: self.function.clone(),
+            },
+            position,
+        )
+    }
+}
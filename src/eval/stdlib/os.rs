@@ -0,0 +1,70 @@
+use std::rc::Rc;
+
+use crate::ast::type_expression::TypeExpression;
+use crate::eval::{
+    stdlib::Arguments,
+    value::{Error, Slice, Value},
+};
+use crate::primitives::errors::runtime::{RuntimeError, RuntimeErrorKind};
+
+/// Call `function` of the os package (https://pkg.go.dev/os). Its variables, like os.Args and
+/// os.Stdout, are the evaluator's.
+pub fn call(function: &str, arguments: &Arguments) -> Result<Vec<Value>, RuntimeError> {
+    match function {
+        "Exit" => {
+            arguments.expect(1)?;
+            // The status is truncated as the host's exit status would be
+            let code = arguments.int(0)? as i32;
+            Err(arguments.fail(RuntimeErrorKind::Exit(code)))
+        }
+        "ReadFile" => {
+            arguments.expect(1)?;
+            let name = arguments.string(0)?;
+            Ok(match std::fs::read(name) {
+                Ok(bytes) => vec![byte_slice(bytes, arguments), Value::Nil],
+                Err(error) => {
+                    let message = format!("open {}: {}", name, describe(&error));
+                    let error = Error::new("*fs.PathError", message);
+                    vec![nil_byte_slice(arguments), Value::Error(Rc::new(error))]
+                }
+            })
+        }
+        _ => Err(arguments.undefined()),
+    }
+}
+
+/// A `[]byte` of `bytes`
+fn byte_slice(bytes: Vec<u8>, arguments: &Arguments) -> Value {
+    let elements: Vec<Value> = bytes
+        .into_iter()
+        .map(|byte| Value::Int(i64::from(byte)))
+        .collect();
+    let len = elements.len();
+    Value::Slice(Slice::new(byte_type(arguments), elements, len))
+}
+
+fn nil_byte_slice(arguments: &Arguments) -> Value {
+    Value::Slice(Slice::nil(byte_type(arguments)))
+}
+
+fn byte_type(arguments: &Arguments) -> Rc<TypeExpression> {
+    Rc::new(TypeExpression::new_name(
+        "byte".to_string(),
+        arguments.position(),
+    ))
+}
+
+/// An I/O error as Go describes it, like `no such file or directory`, without the host's
+/// error number
+fn describe(error: &std::io::Error) -> String {
+    let message = error.to_string();
+    let message = match message.find(" (os error") {
+        Some(end) => &message[..end],
+        None => &message,
+    };
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
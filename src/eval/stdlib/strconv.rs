@@ -0,0 +1,353 @@
+use std::rc::Rc;
+
+use crate::eval::{
+    fmt::quote,
+    stdlib::Arguments,
+    value::{Error, Value},
+};
+use crate::primitives::errors::runtime::RuntimeError;
+
+/// Call `function` of the strconv package (https://pkg.go.dev/strconv). A parse that fails
+/// returns an error like Go's `strconv.Atoi: parsing "x": invalid syntax`, along with zero or,
+/// when the value is out of range, the nearest value that's in range.
+pub fn call(function: &str, arguments: &Arguments) -> Result<Vec<Value>, RuntimeError> {
+    let with_error = |value: Value, failure: Option<&str>, input: &str| {
+        let error = match failure {
+            Some(reason) => {
+                let message = format!("strconv.{}: parsing {}: {}", function, quote(input), reason);
+                Value::Error(Rc::new(Error::new("*strconv.NumError", message)))
+            }
+            None => Value::Nil,
+        };
+        Ok(vec![value, error])
+    };
+    let result = match function {
+        "Itoa" => {
+            arguments.expect(1)?;
+            Value::String(arguments.int(0)?.to_string())
+        }
+        "Atoi" => {
+            arguments.expect(1)?;
+            let input = arguments.string(0)?;
+            let (value, failure) = split(parse_int(input, 10, 64));
+            return with_error(Value::Int(value), failure.as_deref(), input);
+        }
+        "ParseInt" => {
+            arguments.expect(3)?;
+            let input = arguments.string(0)?;
+            let (base, bit_size) = (arguments.int(1)?, arguments.int(2)?);
+            let (value, failure) = match (base, bit_size) {
+                (2..=36 | 0, 0..=64) => split(parse_int(input, base as u32, bit_size as u32)),
+                (_, 0..=64) => (0, Some(format!("invalid base {}", base))),
+                _ => (0, Some(format!("invalid bit size {}", bit_size))),
+            };
+            return with_error(Value::Int(value), failure.as_deref(), input);
+        }
+        "FormatInt" => {
+            arguments.expect(2)?;
+            let (value, base) = (arguments.int(0)?, arguments.int(1)?);
+            if !(2..=36).contains(&base) {
+                return Err(arguments.panic("strconv: illegal AppendInt/FormatInt base"));
+            }
+            Value::String(format_int(value, base as u32))
+        }
+        "ParseFloat" => {
+            arguments.expect(2)?;
+            let input = arguments.string(0)?;
+            let bit_size = arguments.int(1)?;
+            let (value, failure) = parse_float(input, bit_size);
+            return with_error(Value::Float(value), failure, input);
+        }
+        "FormatFloat" => {
+            arguments.expect(4)?;
+            let value = arguments.float(0)?;
+            // The format is a byte, like 'f'
+            let format = u32::try_from(arguments.int(1)?)
+                .ok()
+                .and_then(char::from_u32)
+                .unwrap_or(char::REPLACEMENT_CHARACTER);
+            let (precision, bit_size) = (arguments.int(2)?, arguments.int(3)?);
+            if bit_size != 32 && bit_size != 64 {
+                return Err(arguments.panic("strconv: illegal AppendFloat/FormatFloat bitSize"));
+            }
+            Value::String(format_float(value, format, precision, bit_size))
+        }
+        "ParseBool" => {
+            arguments.expect(1)?;
+            let input = arguments.string(0)?;
+            let (value, failure) = match input {
+                "1" | "t" | "T" | "true" | "TRUE" | "True" => (true, None),
+                "0" | "f" | "F" | "false" | "FALSE" | "False" => (false, None),
+                _ => (false, Some("invalid syntax")),
+            };
+            return with_error(Value::Bool(value), failure, input);
+        }
+        "FormatBool" => {
+            arguments.expect(1)?;
+            Value::String(arguments.bool(0)?.to_string())
+        }
+        "Quote" => {
+            arguments.expect(1)?;
+            Value::String(quote(arguments.string(0)?))
+        }
+        _ => return Err(arguments.undefined()),
+    };
+    Ok(vec![result])
+}
+
+/// Why a parse failed, and the value it gives anyway
+type Parsed<T> = Result<T, (T, &'static str)>;
+
+fn split<T>(parsed: Parsed<T>) -> (T, Option<String>) {
+    match parsed {
+        Ok(value) => (value, None),
+        Err((value, reason)) => (value, Some(reason.to_string())),
+    }
+}
+
+/// `input` as a signed integer of `bit_size` bits (64 for 0) in `base`. Base 0 takes the base
+/// from a prefix like `0x`, and then allows underscores between digits.
+fn parse_int(input: &str, base: u32, bit_size: u32) -> Parsed<i64> {
+    const SYNTAX: &str = "invalid syntax";
+    let (negative, unsigned) = match input.as_bytes().first() {
+        Some(b'-') => (true, &input[1..]),
+        Some(b'+') => (false, &input[1..]),
+        _ => (false, input),
+    };
+    let (base, digits) = match base {
+        0 => {
+            let lower = unsigned.to_ascii_lowercase();
+            let (base, prefix) = if lower.starts_with("0x") {
+                (16, 2)
+            } else if lower.starts_with("0o") {
+                (8, 2)
+            } else if lower.starts_with("0b") {
+                (2, 2)
+            } else if unsigned.len() > 1 && unsigned.starts_with('0') {
+                (8, 1)
+            } else {
+                (10, 0)
+            };
+            let digits = &unsigned[prefix..];
+            // Underscores may only separate digits, or follow the prefix
+            let separated = format!("{}{}", if prefix > 0 { "0" } else { "" }, digits);
+            if separated.starts_with('_') || separated.ends_with('_') || separated.contains("__") {
+                return Err((0, SYNTAX));
+            }
+            (base, digits.replace('_', ""))
+        }
+        base => (base, unsigned.to_string()),
+    };
+    if digits.is_empty() || !digits.chars().all(|digit| digit.is_digit(base)) {
+        return Err((0, SYNTAX));
+    }
+    let bit_size = if bit_size == 0 { 64 } else { bit_size };
+    let max = (1i128 << (bit_size - 1)) - 1;
+    let min = -(1i128 << (bit_size - 1));
+    let magnitude = u128::from_str_radix(&digits, base).unwrap_or(u128::MAX);
+    let value = match i128::try_from(magnitude) {
+        Ok(magnitude) if negative => -magnitude,
+        Ok(magnitude) => magnitude,
+        Err(_) if negative => i128::MIN,
+        Err(_) => i128::MAX,
+    };
+    match value {
+        value if value > max => Err((max as i64, "value out of range")),
+        value if value < min => Err((min as i64, "value out of range")),
+        value => Ok(value as i64),
+    }
+}
+
+fn format_int(value: i64, base: u32) -> String {
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        let digit = (magnitude % u64::from(base)) as u32;
+        digits.push(char::from_digit(digit, base).unwrap());
+        magnitude /= u64::from(base);
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        digits.push('-');
+    }
+    digits.iter().rev().collect()
+}
+
+/// `input` as a float of `bit_size` bits, which Go's syntax and Rust's agree on, except that
+/// hexadecimal floats and underscores aren't accepted. Out of range is ±Inf.
+fn parse_float(input: &str, bit_size: i64) -> (f64, Option<&'static str>) {
+    let parsed = match bit_size {
+        32 => input.parse::<f32>().map(f64::from),
+        _ => input.parse::<f64>(),
+    };
+    let Ok(value) = parsed else {
+        return (0.0, Some("invalid syntax"));
+    };
+    let infinity = input
+        .trim_start_matches(['+', '-'])
+        .to_ascii_lowercase()
+        .starts_with("inf");
+    if value.is_infinite() && !infinity {
+        return (value, Some("value out of range"));
+    }
+    (value, None)
+}
+
+/// `value` as `strconv.FormatFloat` formats it: `f` without an exponent, `e` or `E` with one,
+/// and `g` or `G` with one only for large or small exponents. A negative precision is the
+/// fewest digits that parse back to the same float of `bit_size` bits.
+pub fn format_float(value: f64, format: char, precision: i64, bit_size: i64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "+Inf" } else { "-Inf" }.to_string();
+    }
+    let shortest = precision < 0;
+    let precision = precision.max(0) as usize;
+    // Rust's formatting is shortest or correctly rounded, like Go's, for either size
+    let single = value as f32;
+    let fixed = |precision: usize| match (shortest, bit_size) {
+        (true, 32) => format!("{}", single),
+        (true, _) => format!("{}", value),
+        (false, _) => format!("{:.*}", precision, value),
+    };
+    let scientific = |precision: usize| match (shortest, bit_size) {
+        (true, 32) => format!("{:e}", single),
+        (true, _) => format!("{:e}", value),
+        (false, _) => format!("{:.*e}", precision, value),
+    };
+    match format {
+        'f' => fixed(precision),
+        'e' | 'E' => {
+            let formatted = with_exponent(&scientific(precision));
+            match format {
+                'E' => formatted.to_uppercase(),
+                _ => formatted,
+            }
+        }
+        'g' | 'G' => {
+            let significant = if shortest { 0 } else { precision.max(1) };
+            let formatted = general(&scientific(significant.saturating_sub(1)), significant);
+            match format {
+                'G' => formatted.to_uppercase(),
+                _ => formatted,
+            }
+        }
+        // Go's format for an unknown verb, which 'b' and 'x' are here too
+        format => format!("%{}", format),
+    }
+}
+
+/// Rust's `1.5e2` as Go writes it, `1.5e+02`, with a sign and at least two exponent digits
+fn with_exponent(scientific: &str) -> String {
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+/// The `g` format of a float written in Rust's scientific notation, rounded to `significant`
+/// digits or, for 0, as few as identify it. The exponent's written when it's below -4 or at
+/// least the number of digits, or 6 for the fewest, so `1e+06` is written with one.
+fn general(scientific: &str, significant: usize) -> String {
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i64 = exponent.parse().unwrap();
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits = mantissa.replace('.', "");
+    let digits = match digits.trim_end_matches('0') {
+        "" => "0",
+        digits => digits,
+    };
+    let count = digits.len() as i64;
+    let point = exponent + 1;
+    let threshold = match significant {
+        0 => 6,
+        significant if significant as i64 > count && count >= point => count,
+        significant => significant as i64,
+    };
+    if exponent < -4 || exponent >= threshold {
+        let fraction = match &digits[1..] {
+            "" => String::new(),
+            fraction => format!(".{}", fraction),
+        };
+        return with_exponent(&format!(
+            "{}{}{}e{}",
+            sign,
+            &digits[..1],
+            fraction,
+            exponent
+        ));
+    }
+    let fixed = if point <= 0 {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    } else if point >= count {
+        format!("{}{}", digits, "0".repeat((point - count) as usize))
+    } else {
+        let (whole, fraction) = digits.split_at(point as usize);
+        format!("{}.{}", whole, fraction)
+    };
+    format!("{}{}", sign, fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format_numbers() {
+        assert_eq!(parse_int("-42", 10, 64), Ok(-42));
+        assert_eq!(parse_int("0x_1F", 0, 64), Ok(31));
+        assert_eq!(parse_int("017", 0, 64), Ok(15));
+        assert_eq!(parse_int("1_000", 10, 64), Err((0, "invalid syntax")));
+        assert_eq!(parse_int("", 10, 64), Err((0, "invalid syntax")));
+        assert_eq!(parse_int("300", 10, 8), Err((127, "value out of range")));
+        assert_eq!(
+            parse_int("-99999999999999999999", 10, 64),
+            Err((i64::MIN, "value out of range"))
+        );
+        assert_eq!(format_int(-255, 16), "-ff");
+
+        assert_eq!(parse_float("1.5e3", 64), (1500.0, None));
+        assert_eq!(parse_float("-Inf", 64), (f64::NEG_INFINITY, None));
+        assert_eq!(
+            parse_float("1e400", 64),
+            (f64::INFINITY, Some("value out of range"))
+        );
+        assert_eq!(parse_float("1.5x", 64), (0.0, Some("invalid syntax")));
+
+        let cases = [
+            (3.0, 'f', -1, "3"),
+            (1.23456, 'f', 2, "1.23"),
+            (1234.5678, 'e', 3, "1.235e+03"),
+            (0.000015, 'e', -1, "1.5e-05"),
+            (1234.5678, 'E', -1, "1.2345678E+03"),
+            (100000.0, 'g', -1, "100000"),
+            (1234567.0, 'g', -1, "1.234567e+06"),
+            (1e21, 'g', -1, "1e+21"),
+            (0.0001, 'g', -1, "0.0001"),
+            (0.00001, 'g', -1, "1e-05"),
+            (1234.5678, 'g', 3, "1.23e+03"),
+            (1.5, 'g', 5, "1.5"),
+            (0.0, 'g', 3, "0"),
+            (-2.5, 'G', -1, "-2.5"),
+            (f64::NAN, 'g', -1, "NaN"),
+        ];
+        for (value, format, precision, formatted) in cases {
+            assert_eq!(
+                format_float(value, format, precision, 64),
+                formatted,
+                "{} {} {}",
+                value,
+                format,
+                precision
+            );
+        }
+        assert_eq!(format_float(0.1f32.into(), 'g', -1, 32), "0.1");
+    }
+}
@@ -0,0 +1,223 @@
+use crate::eval::{stdlib::Arguments, value::Value};
+use crate::primitives::errors::runtime::RuntimeError;
+
+/// Call `function` of the strings package (https://pkg.go.dev/strings). Indexes are byte
+/// offsets, as Go's are.
+pub fn call(function: &str, arguments: &Arguments) -> Result<Vec<Value>, RuntimeError> {
+    let result = match function {
+        "Contains" => {
+            arguments.expect(2)?;
+            Value::Bool(arguments.string(0)?.contains(arguments.string(1)?))
+        }
+        "ContainsRune" => {
+            arguments.expect(2)?;
+            let s = arguments.string(0)?;
+            let rune = u32::try_from(arguments.int(1)?)
+                .ok()
+                .and_then(char::from_u32);
+            Value::Bool(rune.is_some_and(|rune| s.contains(rune)))
+        }
+        "Count" => {
+            arguments.expect(2)?;
+            // Go counts the empty string once per rune, plus once at the end, as Rust does
+            let count = arguments.string(0)?.matches(arguments.string(1)?).count();
+            Value::Int(count as i64)
+        }
+        "Fields" => {
+            arguments.expect(1)?;
+            let fields = arguments.string(0)?.split_whitespace().map(String::from);
+            arguments.string_slice(fields.collect())
+        }
+        "HasPrefix" => {
+            arguments.expect(2)?;
+            Value::Bool(arguments.string(0)?.starts_with(arguments.string(1)?))
+        }
+        "HasSuffix" => {
+            arguments.expect(2)?;
+            Value::Bool(arguments.string(0)?.ends_with(arguments.string(1)?))
+        }
+        "Index" => {
+            arguments.expect(2)?;
+            let index = arguments.string(0)?.find(arguments.string(1)?);
+            Value::Int(index.map_or(-1, |index| index as i64))
+        }
+        "LastIndex" => {
+            arguments.expect(2)?;
+            let index = arguments.string(0)?.rfind(arguments.string(1)?);
+            Value::Int(index.map_or(-1, |index| index as i64))
+        }
+        "Join" => {
+            arguments.expect(2)?;
+            let elements = arguments.strings(0)?;
+            Value::String(elements.join(arguments.string(1)?))
+        }
+        "Repeat" => {
+            arguments.expect(2)?;
+            let count = arguments.int(1)?;
+            if count < 0 {
+                return Err(arguments.panic("strings: negative Repeat count"));
+            }
+            let s = arguments.string(0)?;
+            let Some(length) = s.len().checked_mul(count as usize) else {
+                return Err(arguments.panic("strings: Repeat output length overflow"));
+            };
+            arguments.can_allocate(length)?;
+            Value::String(s.repeat(count as usize))
+        }
+        "Replace" => {
+            arguments.expect(4)?;
+            let (s, old, new) = (
+                arguments.string(0)?,
+                arguments.string(1)?,
+                arguments.string(2)?,
+            );
+            // A negative count replaces every instance
+            let replaced = match usize::try_from(arguments.int(3)?) {
+                Ok(count) => s.replacen(old, new, count),
+                Err(_) => s.replace(old, new),
+            };
+            Value::String(replaced)
+        }
+        "ReplaceAll" => {
+            arguments.expect(3)?;
+            let s = arguments.string(0)?;
+            Value::String(s.replace(arguments.string(1)?, arguments.string(2)?))
+        }
+        "Split" => {
+            arguments.expect(2)?;
+            let (s, separator) = (arguments.string(0)?, arguments.string(1)?);
+            // An empty separator splits after each rune
+            let parts: Vec<String> = match separator {
+                "" => s.chars().map(String::from).collect(),
+                separator => s.split(separator).map(String::from).collect(),
+            };
+            arguments.string_slice(parts)
+        }
+        "ToLower" => {
+            arguments.expect(1)?;
+            Value::String(map_case(arguments.string(0)?, char::to_lowercase))
+        }
+        "ToUpper" => {
+            arguments.expect(1)?;
+            Value::String(map_case(arguments.string(0)?, char::to_uppercase))
+        }
+        "Trim" => {
+            arguments.expect(2)?;
+            let cutset = arguments.string(1)?;
+            let trimmed = arguments.string(0)?.trim_matches(|c| cutset.contains(c));
+            Value::String(trimmed.to_string())
+        }
+        "TrimPrefix" => {
+            arguments.expect(2)?;
+            let s = arguments.string(0)?;
+            Value::String(
+                s.strip_prefix(arguments.string(1)?)
+                    .unwrap_or(s)
+                    .to_string(),
+            )
+        }
+        "TrimSuffix" => {
+            arguments.expect(2)?;
+            let s = arguments.string(0)?;
+            Value::String(
+                s.strip_suffix(arguments.string(1)?)
+                    .unwrap_or(s)
+                    .to_string(),
+            )
+        }
+        "TrimSpace" => {
+            arguments.expect(1)?;
+            Value::String(arguments.string(0)?.trim().to_string())
+        }
+        _ => return Err(arguments.undefined()),
+    };
+    Ok(vec![result])
+}
+
+/// Map each rune of `s` to its other case the way Go does, one rune to one rune, so a rune
+/// like `ß` whose uppercase is two runes stays as it is
+fn map_case<I: ExactSizeIterator<Item = char>>(s: &str, mapping: impl Fn(char) -> I) -> String {
+    s.chars()
+        .map(|c| {
+            let mut mapped = mapping(c);
+            match (mapped.len(), mapped.next()) {
+                (1, Some(mapped)) => mapped,
+                _ => c,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::expression::{Expression, ExpressionKind};
+    use crate::primitives::position::Position;
+
+    fn strings(function: &str, values: &[Value]) -> Result<Value, String> {
+        let expression = Expression::new(
+            ExpressionKind::Identifier(function.to_string()),
+            Position::new(1, 0, 0),
+            Position::new(1, 0, 0),
+        );
+        let arguments = Arguments::new(format!("strings.{}", function), values, &expression);
+        call(function, &arguments)
+            .map(|mut results| results.remove(0))
+            .map_err(|error| error.kind.to_string())
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn call_strings_functions() {
+        assert_eq!(
+            strings("Split", &[string("a,b,,c"), string(",")]).map(|v| v.to_string()),
+            Ok("[a b  c]".to_string())
+        );
+        assert_eq!(
+            strings("Split", &[string("héllo"), string("")]).map(|v| v.to_string()),
+            Ok("[h é l l o]".to_string())
+        );
+        assert_eq!(
+            strings("Fields", &[string("  a \t b\n")]).map(|v| v.to_string()),
+            Ok("[a b]".to_string())
+        );
+        assert_eq!(
+            strings("ToUpper", &[string("straße")]),
+            Ok(string("STRAßE"))
+        );
+        assert_eq!(
+            strings(
+                "Replace",
+                &[string("aaaa"), string("a"), string("b"), Value::Int(2)]
+            ),
+            Ok(string("bbaa"))
+        );
+        assert_eq!(
+            strings("Index", &[string("héllo"), string("l")]),
+            Ok(Value::Int(3))
+        );
+        assert_eq!(
+            strings("Count", &[string("five"), string("")]),
+            Ok(Value::Int(5))
+        );
+        assert_eq!(
+            strings("Trim", &[string("xxhixyx"), string("xy")]),
+            Ok(string("hi"))
+        );
+        assert_eq!(
+            strings("Repeat", &[string("a"), Value::Int(-1)]),
+            Err("strings: negative Repeat count".to_string())
+        );
+        assert_eq!(
+            strings("Contains", &[string("a"), Value::Int(1)]),
+            Err("cannot use int value as string value in argument to strings.Contains".to_string())
+        );
+        assert_eq!(
+            strings("Cut", &[]),
+            Err("undefined: strings.Cut".to_string())
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::eval::{
+    stdlib::Arguments,
+    value::{Native, Value},
+};
+use crate::primitives::errors::runtime::RuntimeError;
+
+/// The Duration units the package declares, in nanoseconds
+const UNITS: &[(&str, i64)] = &[
+    ("Nanosecond", 1),
+    ("Microsecond", 1_000),
+    ("Millisecond", 1_000_000),
+    ("Second", 1_000_000_000),
+    ("Minute", 60_000_000_000),
+    ("Hour", 3_600_000_000_000),
+];
+
+/// Seconds from January 1st of the year 1, the zero Time, to the Unix epoch
+const ZERO_TO_UNIX: i64 = 62_135_596_800;
+
+/// An instant as a time.Time holds it (https://pkg.go.dev/time#Time): a wall clock reading,
+/// and for one time.Now made, a monotonic clock reading that's used to measure how long has
+/// passed since, as Go's is. Times are always in UTC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Time {
+    /// Seconds since the Unix epoch, which are negative before it
+    pub seconds: i64,
+    pub nanoseconds: u32,
+    pub monotonic: Option<Instant>,
+}
+
+impl Time {
+    /// The zero Time, January 1st of the year 1
+    pub fn zero() -> Time {
+        Time {
+            seconds: -ZERO_TO_UNIX,
+            nanoseconds: 0,
+            monotonic: None,
+        }
+    }
+
+    pub fn now() -> Time {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Time {
+            seconds: since_epoch.as_secs() as i64,
+            nanoseconds: since_epoch.subsec_nanos(),
+            monotonic: Some(Instant::now()),
+        }
+    }
+
+    fn unix_nanoseconds(&self) -> i128 {
+        i128::from(self.seconds) * 1_000_000_000 + i128::from(self.nanoseconds)
+    }
+
+    /// The Duration from `earlier` to this time, saturating as Go's Sub does
+    fn sub(&self, earlier: &Time) -> i64 {
+        let nanoseconds = match (self.monotonic, earlier.monotonic) {
+            (Some(later), Some(earlier)) if later >= earlier => {
+                later.duration_since(earlier).as_nanos() as i128
+            }
+            (Some(later), Some(earlier)) => -(earlier.duration_since(later).as_nanos() as i128),
+            _ => self.unix_nanoseconds() - earlier.unix_nanoseconds(),
+        };
+        nanoseconds.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64
+    }
+}
+
+/// As Go prints a Time, like `2009-11-10 23:00:00.5 +0000 UTC`, without the monotonic
+/// reading
+impl std::fmt::Display for Time {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let days = self.seconds.div_euclid(86_400);
+        let seconds = self.seconds.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )?;
+        if self.nanoseconds > 0 {
+            let fraction = format!("{:09}", self.nanoseconds);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        write!(f, " +0000 UTC")
+    }
+}
+
+/// The year, month and day of the day `days` after the Unix epoch, in the proleptic Gregorian
+/// calendar (https://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// A Duration as Go prints it, like `1h2m0.5s`, `1.5ms` or `0s`
+pub fn format_duration(duration: i64) -> String {
+    let sign = if duration < 0 { "-" } else { "" };
+    let nanoseconds = duration.unsigned_abs();
+    if nanoseconds == 0 {
+        return "0s".to_string();
+    }
+    // Under a second, the largest unit that's at most the duration
+    if nanoseconds < 1_000_000_000 {
+        let (unit, precision) = match nanoseconds {
+            0..1_000 => ("ns", 0),
+            1_000..1_000_000 => ("µs", 3),
+            _ => ("ms", 6),
+        };
+        let (whole, fraction) = split_fraction(nanoseconds, precision);
+        return format!("{}{}{}{}", sign, whole, fraction, unit);
+    }
+    let (seconds, fraction) = split_fraction(nanoseconds, 9);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}{}{}s", sign, seconds, fraction),
+        (0, _) => format!("{}{}m{}{}s", sign, minutes, seconds, fraction),
+        _ => format!("{}{}h{}m{}{}s", sign, hours, minutes, seconds, fraction),
+    }
+}
+
+/// `value` divided by 10 to the `precision`, and its remainder as a decimal fraction like
+/// `.25`, which is empty when it's zero
+fn split_fraction(value: u64, precision: u32) -> (u64, String) {
+    let scale = 10u64.pow(precision);
+    let remainder = value % scale;
+    if remainder == 0 {
+        return (value / scale, String::new());
+    }
+    let digits = format!("{:0width$}", remainder, width = precision as usize);
+    (value / scale, format!(".{}", digits.trim_end_matches('0')))
+}
+
+/// The Duration unit `name`, like time.Second, or None if the package doesn't declare it
+pub fn unit(name: &str) -> Option<Value> {
+    UNITS
+        .iter()
+        .find(|(unit, _)| *unit == name)
+        .map(|(_, nanoseconds)| Value::Duration(*nanoseconds))
+}
+
+/// Call `function` of the time package (https://pkg.go.dev/time). Sleep hands the turn to
+/// other goroutines, so the evaluator implements it.
+pub fn call(function: &str, arguments: &Arguments) -> Result<Vec<Value>, RuntimeError> {
+    let result = match function {
+        "Now" => {
+            arguments.expect(0)?;
+            Value::Native(Native::Time(Time::now()))
+        }
+        "Since" => {
+            arguments.expect(1)?;
+            Value::Duration(Time::now().sub(&arguments.time(0)?))
+        }
+        "Until" => {
+            arguments.expect(1)?;
+            Value::Duration(arguments.time(0)?.sub(&Time::now()))
+        }
+        // A conversion, like time.Duration(n)
+        "Duration" => {
+            arguments.expect(1)?;
+            Value::Duration(arguments.duration(0)?)
+        }
+        _ => return Err(arguments.undefined()),
+    };
+    Ok(vec![result])
+}
+
+/// Call `method` of a Duration
+pub fn call_duration_method(
+    duration: i64,
+    method: &str,
+    arguments: &Arguments,
+) -> Result<Vec<Value>, RuntimeError> {
+    let in_units = |unit: i64| Value::Float(duration as f64 / unit as f64);
+    let result = match method {
+        "Nanoseconds" => Value::Int(duration),
+        "Microseconds" => Value::Int(duration / 1_000),
+        "Milliseconds" => Value::Int(duration / 1_000_000),
+        "Seconds" => in_units(1_000_000_000),
+        "Minutes" => in_units(60_000_000_000),
+        "Hours" => in_units(3_600_000_000_000),
+        "String" => Value::String(format_duration(duration)),
+        _ => return Err(arguments.undefined_method("time.Duration", method)),
+    };
+    arguments.expect(0)?;
+    Ok(vec![result])
+}
+
+/// Call `method` of a Time
+pub fn call_time_method(
+    time: &Time,
+    method: &str,
+    arguments: &Arguments,
+) -> Result<Vec<Value>, RuntimeError> {
+    let result = match method {
+        "Sub" => {
+            arguments.expect(1)?;
+            Value::Duration(time.sub(&arguments.time(0)?))
+        }
+        "Add" => {
+            arguments.expect(1)?;
+            let duration = arguments.duration(0)?;
+            let nanoseconds = time.unix_nanoseconds() + i128::from(duration);
+            let offset = std::time::Duration::from_nanos(duration.unsigned_abs());
+            let monotonic = time.monotonic.and_then(|monotonic| match duration < 0 {
+                true => monotonic.checked_sub(offset),
+                false => monotonic.checked_add(offset),
+            });
+            Value::Native(Native::Time(Time {
+                seconds: nanoseconds.div_euclid(1_000_000_000) as i64,
+                nanoseconds: nanoseconds.rem_euclid(1_000_000_000) as u32,
+                monotonic,
+            }))
+        }
+        "Before" | "After" | "Equal" => {
+            arguments.expect(1)?;
+            let ordering = time.sub(&arguments.time(0)?).cmp(&0);
+            Value::Bool(match method {
+                "Before" => ordering.is_lt(),
+                "After" => ordering.is_gt(),
+                _ => ordering.is_eq(),
+            })
+        }
+        "IsZero" => {
+            arguments.expect(0)?;
+            Value::Bool(time.seconds == -ZERO_TO_UNIX && time.nanoseconds == 0)
+        }
+        "Unix" => {
+            arguments.expect(0)?;
+            Value::Int(time.seconds)
+        }
+        "UnixMilli" => {
+            arguments.expect(0)?;
+            Value::Int((time.unix_nanoseconds() / 1_000_000) as i64)
+        }
+        "UnixNano" => {
+            arguments.expect(0)?;
+            Value::Int(time.unix_nanoseconds() as i64)
+        }
+        "String" => {
+            arguments.expect(0)?;
+            Value::String(time.to_string())
+        }
+        _ => return Err(arguments.undefined_method("time.Time", method)),
+    };
+    Ok(vec![result])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_durations_and_times() {
+        let cases = [
+            (0, "0s"),
+            (1, "1ns"),
+            (1_500, "1.5µs"),
+            (2_250_000, "2.25ms"),
+            (-1_000_000_000, "-1s"),
+            (90_000_000_000, "1m30s"),
+            (3_600_000_000_000, "1h0m0s"),
+            (3_723_500_000_000, "1h2m3.5s"),
+        ];
+        for (duration, formatted) in cases {
+            assert_eq!(format_duration(duration), formatted);
+        }
+        assert_eq!(Time::zero().to_string(), "0001-01-01 00:00:00 +0000 UTC");
+        let time = Time {
+            seconds: 1_257_894_000,
+            nanoseconds: 500_000_000,
+            monotonic: None,
+        };
+        assert_eq!(time.to_string(), "2009-11-10 23:00:00.5 +0000 UTC");
+    }
+}
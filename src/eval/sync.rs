@@ -0,0 +1,132 @@
+use crate::ast::expression::Expression;
+use crate::eval::{
+    evaluator::{Evaluator, callee_name},
+    value::{Native, OnceState, Value},
+};
+use crate::primitives::errors::runtime::{RuntimeError, RuntimeErrorKind};
+
+impl Evaluator {
+    /// Call `method` of a sync value (https://pkg.go.dev/sync). Waiting, like a WaitGroup's
+    /// Wait or a locked Mutex's Lock, blocks the goroutine until another changes the value.
+    /// Other native values, like an os.File, have none of these methods.
+    pub(crate) fn call_sync_method(
+        &mut self,
+        native: &Native,
+        method: &str,
+        arguments: Vec<Value>,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let position = call.position_start;
+        let function = callee_name(call);
+        let argument_count = |want: usize| {
+            RuntimeError::new(
+                RuntimeErrorKind::ArgumentCount {
+                    function: function.clone(),
+                    have: arguments.len(),
+                    want,
+                },
+                position,
+            )
+        };
+        let cannot_use = |value: &Value, parameter_type: &str| {
+            RuntimeError::new(
+                RuntimeErrorKind::CannotUseArgument {
+                    value_type: value.type_name(),
+                    parameter_type: parameter_type.to_string(),
+                    function: function.clone(),
+                },
+                position,
+            )
+        };
+        match (native, method, arguments.as_slice()) {
+            (Native::WaitGroup(counter), "Add", [Value::Int(delta)]) => {
+                self.add_to_wait_group(counter, *delta, call)?;
+            }
+            (Native::WaitGroup(_), "Add", [delta]) => return Err(cannot_use(delta, "int")),
+            (Native::WaitGroup(_), "Add", _) => return Err(argument_count(1)),
+            (Native::WaitGroup(counter), "Done", []) => {
+                self.add_to_wait_group(counter, -1, call)?;
+            }
+            (Native::WaitGroup(counter), "Wait", []) => {
+                while *counter.borrow() > 0 {
+                    self.block(position)?;
+                }
+            }
+            (Native::Mutex(locked), "Lock", []) => {
+                while *locked.borrow() {
+                    self.block(position)?;
+                }
+                *locked.borrow_mut() = true;
+            }
+            (Native::Mutex(locked), "TryLock", []) => {
+                let acquired = !locked.replace(true);
+                return Ok(vec![Value::Bool(acquired)]);
+            }
+            (Native::Mutex(locked), "Unlock", []) => {
+                if !locked.replace(false) {
+                    return Err(RuntimeError::new(
+                        RuntimeErrorKind::UnlockOfUnlockedMutex,
+                        position,
+                    ));
+                }
+                self.wake_blocked();
+            }
+            (Native::Once(state), "Do", [function @ (Value::Function(_) | Value::Closure(_))]) => {
+                // Callers while the first is running wait for it to return, so a function
+                // calling Do on its own Once waits forever
+                while *state.borrow() == OnceState::Running {
+                    self.block(position)?;
+                }
+                if *state.borrow() == OnceState::Done {
+                    return Ok(Vec::new());
+                }
+                *state.borrow_mut() = OnceState::Running;
+                let result = self.call_function_value(function, Vec::new(), position);
+                // Done even if the function panicked
+                *state.borrow_mut() = OnceState::Done;
+                self.wake_blocked();
+                result?;
+            }
+            (Native::Once(_), "Do", [f]) => return Err(cannot_use(f, "func()")),
+            (Native::Once(_), "Do", _) => return Err(argument_count(1)),
+            (Native::WaitGroup(_), "Done" | "Wait", _)
+            | (Native::Mutex(_), "Lock" | "TryLock" | "Unlock", _) => {
+                return Err(argument_count(0));
+            }
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedMethod {
+                        expression: function,
+                        value_type: native.type_name().to_string(),
+                        method: method.to_string(),
+                    },
+                    position,
+                ));
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Add `delta` to a WaitGroup's counter, waking its waiters once it reaches zero. A
+    /// negative counter panics, as Go's does.
+    fn add_to_wait_group(
+        &mut self,
+        counter: &std::cell::RefCell<i64>,
+        delta: i64,
+        call: &Expression,
+    ) -> Result<(), RuntimeError> {
+        let count = {
+            let mut counter = counter.borrow_mut();
+            *counter += delta;
+            *counter
+        };
+        if count < 0 {
+            let message = Value::String("sync: negative WaitGroup counter".to_string());
+            return Err(self.panic(message, call));
+        }
+        if count == 0 {
+            self.wake_blocked();
+        }
+        Ok(())
+    }
+}
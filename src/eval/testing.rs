@@ -0,0 +1,265 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::ast::expression::Expression;
+use crate::eval::{
+    evaluator::{Evaluator, callee_name, format_arguments},
+    fmt,
+    value::{Native, Value},
+};
+use crate::primitives::{
+    errors::runtime::{RuntimeError, RuntimeErrorKind},
+    position::Position,
+};
+
+/// A testing.T: how the test it was made for has gone so far (https://pkg.go.dev/testing#T)
+#[derive(Debug)]
+pub struct Test {
+    /// Its name, which for a subtest is its parent's, a slash and the name it was run with
+    pub name: String,
+    pub failed: bool,
+    pub skipped: bool,
+    pub output: Vec<Logged>,
+    /// The subtests it ran with Run, in the order they finished
+    pub subtests: Vec<TestResult>,
+}
+
+/// A message a test logged, with the call that logged it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Logged {
+    pub position: Position,
+    pub message: String,
+}
+
+/// How a test function went once it returned
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: TestOutcome,
+    pub elapsed: Duration,
+    /// What it logged with Log, Error, Skip and the like, in order
+    pub output: Vec<Logged>,
+    pub subtests: Vec<TestResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestOutcome {
+    Passed,
+    // A failed test, even one that was skipped after failing
+    Failed,
+    Skipped,
+}
+
+impl TestOutcome {
+    /// The outcome as `go test` reports it, like `--- FAIL: TestAdd`
+    pub fn label(&self) -> &'static str {
+        match self {
+            TestOutcome::Passed => "PASS",
+            TestOutcome::Failed => "FAIL",
+            TestOutcome::Skipped => "SKIP",
+        }
+    }
+}
+
+impl TestResult {
+    /// The result and those of its subtests, depth first, each with how deep it's nested
+    pub fn flatten(&self) -> Vec<(usize, &TestResult)> {
+        let mut flattened = vec![(0, self)];
+        for subtest in &self.subtests {
+            flattened.extend(
+                subtest
+                    .flatten()
+                    .into_iter()
+                    .map(|(depth, result)| (depth + 1, result)),
+            );
+        }
+        flattened
+    }
+}
+
+/// Whether a function's name makes it a test, as `go test` finds them: `Test` then nothing or
+/// anything but a lowercase letter, like `TestAdd` or `Test_add` but not `Testify`
+pub fn is_test_name(name: &str) -> bool {
+    name.strip_prefix("Test")
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_lowercase()))
+}
+
+impl Evaluator {
+    /// Call the test function `function` with a new testing.T named `name`, returning how the
+    /// test went. A test that ends with FailNow or SkipNow, or one of the methods that call
+    /// them, returns as if it had finished. A panic or another error fails the test, and is
+    /// returned with the result, to end the run as a panicking test ends `go test`'s.
+    pub(crate) fn run_test(
+        &mut self,
+        name: String,
+        function: &Value,
+        position: Position,
+    ) -> (TestResult, Option<RuntimeError>) {
+        let test = Rc::new(RefCell::new(Test {
+            name,
+            failed: false,
+            skipped: false,
+            output: Vec::new(),
+            subtests: Vec::new(),
+        }));
+        let started = Instant::now();
+        let t = Value::Native(Native::Test(test.clone()));
+        let error = match self.call_function_value(function, vec![t], position) {
+            Err(error) if error.kind != RuntimeErrorKind::Goexit => Some(error),
+            _ => None,
+        };
+        let elapsed = started.elapsed();
+        let mut test = test.borrow_mut();
+        test.failed |= error.is_some();
+        let outcome = match (test.failed, test.skipped) {
+            (true, _) => TestOutcome::Failed,
+            (false, true) => TestOutcome::Skipped,
+            (false, false) => TestOutcome::Passed,
+        };
+        let result = TestResult {
+            name: test.name.clone(),
+            outcome,
+            elapsed,
+            output: std::mem::take(&mut test.output),
+            subtests: std::mem::take(&mut test.subtests),
+        };
+        (result, error)
+    }
+
+    /// Call `method` of a testing.T. Messages are formatted as fmt.Sprintln and fmt.Sprintf
+    /// format them, and logged with the position of the call. Helper is accepted, but
+    /// messages are still logged where they're called from.
+    pub(crate) fn call_test_method(
+        &mut self,
+        test: &Rc<RefCell<Test>>,
+        method: &str,
+        arguments: Vec<Value>,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let position = call.position_start;
+        let function = callee_name(call);
+        let argument_count = |want: usize| {
+            RuntimeError::new(
+                RuntimeErrorKind::ArgumentCount {
+                    function: function.clone(),
+                    have: arguments.len(),
+                    want,
+                },
+                position,
+            )
+        };
+        // FailNow and SkipNow end the test's goroutine, running its deferred calls
+        let goexit = RuntimeError::new(RuntimeErrorKind::Goexit, position);
+        match method {
+            "Log" | "Error" | "Fatal" | "Skip" => {
+                let printed = self.as_printed(&arguments, call)?;
+                let message = fmt::sprintln(&printed);
+                log(test, position, message.trim_end_matches('\n'));
+            }
+            "Logf" | "Errorf" | "Fatalf" | "Skipf" => {
+                let (format, operands) = format_arguments(&function, &arguments, call)?;
+                let printed = self.as_printed(operands, call)?;
+                let message = fmt::sprintf_printed(format, operands, &printed);
+                log(
+                    test,
+                    position,
+                    message.strip_suffix('\n').unwrap_or(&message),
+                );
+            }
+            "Fail" | "FailNow" | "SkipNow" | "Failed" | "Skipped" | "Name" | "Helper" => {
+                if !arguments.is_empty() {
+                    return Err(argument_count(0));
+                }
+            }
+            "Run" => return self.run_subtest(test, arguments, call),
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::UndefinedMethod {
+                        expression: function,
+                        value_type: Native::Test(test.clone()).type_name().to_string(),
+                        method: method.to_string(),
+                    },
+                    position,
+                ));
+            }
+        }
+        let mut state = test.borrow_mut();
+        match method {
+            "Error" | "Errorf" | "Fail" => state.failed = true,
+            "Fatal" | "Fatalf" | "FailNow" => {
+                state.failed = true;
+                return Err(goexit);
+            }
+            "Skip" | "Skipf" | "SkipNow" => {
+                state.skipped = true;
+                return Err(goexit);
+            }
+            "Failed" => return Ok(vec![Value::Bool(state.failed)]),
+            "Skipped" => return Ok(vec![Value::Bool(state.skipped)]),
+            "Name" => return Ok(vec![Value::String(state.name.clone())]),
+            _ => {}
+        }
+        Ok(Vec::new())
+    }
+
+    /// t.Run(name, f): run `f` as a subtest of `test`, returning whether it didn't fail. Its
+    /// name is the parent's, a slash and `name` with its spaces made underscores, and its
+    /// failing fails the parent.
+    fn run_subtest(
+        &mut self,
+        test: &Rc<RefCell<Test>>,
+        arguments: Vec<Value>,
+        call: &Expression,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let position = call.position_start;
+        let cannot_use = |value: &Value, parameter_type: &str| {
+            RuntimeError::new(
+                RuntimeErrorKind::CannotUseArgument {
+                    value_type: value.type_name(),
+                    parameter_type: parameter_type.to_string(),
+                    function: callee_name(call),
+                },
+                position,
+            )
+        };
+        let (name, function) = match arguments.as_slice() {
+            [
+                Value::String(name),
+                function @ (Value::Function(_) | Value::Closure(_)),
+            ] => (name, function.clone()),
+            [Value::String(_), f] => return Err(cannot_use(f, "func(*testing.T)")),
+            [name, _] => return Err(cannot_use(name, "string")),
+            _ => {
+                return Err(RuntimeError::new(
+                    RuntimeErrorKind::ArgumentCount {
+                        function: callee_name(call),
+                        have: arguments.len(),
+                        want: 2,
+                    },
+                    position,
+                ));
+            }
+        };
+        let name = format!("{}/{}", test.borrow().name, name.replace(' ', "_"));
+        let (result, error) = self.run_test(name, &function, position);
+        let passed = result.outcome != TestOutcome::Failed;
+        let mut parent = test.borrow_mut();
+        parent.failed |= !passed;
+        parent.subtests.push(result);
+        drop(parent);
+        match error {
+            Some(error) => Err(error),
+            None => Ok(vec![Value::Bool(passed)]),
+        }
+    }
+}
+
+fn log(test: &RefCell<Test>, position: Position, message: &str) {
+    test.borrow_mut().output.push(Logged {
+        position,
+        message: message.to_string(),
+    });
+}
@@ -0,0 +1,878 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    collections::VecDeque,
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+use crate::ast::{
+    declaration::{Declaration, DeclarationKind, Parameter},
+    type_expression::{TypeExpression, TypeExpressionKind},
+};
+use crate::eval::{
+    evaluator::BASIC_TYPES,
+    stdlib::{
+        strconv::format_float,
+        time::{Time, format_duration},
+    },
+    testing::Test,
+};
+
+/// A value of a running program. Every integer is an int64 and every float a float64 for now.
+/// Slices, maps, channels and pointers refer to storage shared by their copies, and each
+/// remembers its type so a nil one still prints and converts like Go's. A struct's fields are
+/// its own, so its copies don't share them.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    // A time.Duration, in nanoseconds
+    Duration(i64),
+    // A value of the error interface, like one errors.New or fmt.Errorf makes
+    Error(Rc<Error>),
+    Slice(Slice),
+    Map(Map),
+    Channel(Channel),
+    Pointer(Pointer),
+    Struct(Struct),
+    // A package level function, named without calling it, like the f of once.Do(f)
+    Function(Rc<Declaration>),
+    // A method bound to its receiver, like the c.F of f := c.F
+    Method(Rc<Declaration>, Box<Value>),
+    // A function literal's value, like the f of f := func() { n++ }
+    Closure(Rc<Closure>),
+    // A value of a declared type that isn't a struct type, like `type Celsius float64`
+    Named(Named),
+    // A value of a type a native package declares, like sync.WaitGroup
+    Native(Native),
+    Nil,
+}
+
+impl Value {
+    /// The Go type of the value, as `%T` prints it
+    pub fn type_name(&self) -> String {
+        match self {
+            Value::Int(_) => "int".to_string(),
+            Value::Float(_) => "float64".to_string(),
+            Value::String(_) => "string".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::Duration(_) => "time.Duration".to_string(),
+            Value::Error(error) => error.type_name.to_string(),
+            Value::Slice(slice) => format!("[]{}", slice.element),
+            Value::Map(map) => format!("map[{}]{}", map.key, map.value),
+            Value::Channel(channel) => format!("chan {}", channel.element),
+            Value::Pointer(pointer) => format!("*{}", qualified(&pointer.target_type)),
+            Value::Struct(value) => value.struct_type.type_name(),
+            Value::Function(function) | Value::Method(function, _) => function_type_name(function),
+            Value::Closure(closure) => function_type_name(&closure.function),
+            Value::Named(named) => named.type_name(),
+            Value::Native(native) => native.type_name().to_string(),
+            Value::Nil => "nil".to_string(),
+        }
+    }
+
+    /// The value of a named type as a value of its underlying type, like the float64 a
+    /// Celsius is, which the operations on it act on
+    pub fn underlying(&self) -> &Value {
+        match self {
+            Value::Named(named) => &named.value,
+            value => value,
+        }
+    }
+
+    /// `underlying`, taking the value
+    pub fn into_underlying(self) -> Value {
+        match self {
+            Value::Named(named) => *named.value,
+            value => value,
+        }
+    }
+
+    /// A new error with `message`, like errors.New makes
+    pub fn error(message: impl Into<String>) -> Value {
+        Value::Error(Rc::new(Error::new("*errors.errorString", message.into())))
+    }
+
+    /// Whether the value is nil, including a nil slice, map, channel or pointer
+    pub fn is_nil(&self) -> bool {
+        match self {
+            Value::Slice(slice) => slice.backing.is_none(),
+            Value::Map(map) => map.entries.is_none(),
+            Value::Channel(channel) => channel.state.is_none(),
+            Value::Pointer(pointer) => pointer.target.is_none(),
+            Value::Named(named) => named.value.is_nil(),
+            Value::Nil => true,
+            _ => false,
+        }
+    }
+
+    /// The value as a map key, or None for a slice, map or function, which can't be one
+    pub fn key(&self) -> Option<Key> {
+        Some(match self {
+            Value::Int(value) => Key::Int(*value),
+            // 0.0 and -0.0 are the same key
+            Value::Float(value) if *value == 0.0 => Key::Float(0.0f64.to_bits()),
+            Value::Float(value) => Key::Float(value.to_bits()),
+            Value::String(value) => Key::String(value.clone()),
+            Value::Bool(value) => Key::Bool(*value),
+            Value::Duration(value) => Key::Int(*value),
+            Value::Error(error) => Key::Address(Rc::as_ptr(error) as usize),
+            Value::Channel(channel) => Key::Address(channel.address()),
+            Value::Pointer(pointer) => Key::Field(pointer.address(), pointer.fields.clone()),
+            // A struct is a key when each of its fields is
+            Value::Struct(value) => Key::Struct(
+                value
+                    .fields
+                    .iter()
+                    .map(Value::key)
+                    .collect::<Option<Vec<Key>>>()?,
+            ),
+            Value::Native(native) => native.key(),
+            Value::Named(named) => return named.value.key(),
+            Value::Nil => Key::Nil,
+            Value::Slice(_)
+            | Value::Map(_)
+            | Value::Function(_)
+            | Value::Method(..)
+            | Value::Closure(_) => return None,
+        })
+    }
+}
+
+/// A value of a declared type whose underlying type isn't a struct, which keeps the type's name
+/// for its methods and `%T`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Named {
+    /// The type's name, like `Celsius`, or `shapes.Unit` for one of an imported package
+    pub name: Rc<str>,
+    pub value: Box<Value>,
+}
+
+impl Named {
+    /// `value` as a value of the type `name`, whatever named type it had before
+    pub fn new(name: Rc<str>, value: Value) -> Named {
+        Named {
+            name,
+            value: Box::new(value.into_underlying()),
+        }
+    }
+
+    /// The type as `%T` prints it, like `main.Celsius`
+    pub fn type_name(&self) -> String {
+        match self.name.contains('.') {
+            true => self.name.to_string(),
+            false => format!("main.{}", self.name),
+        }
+    }
+}
+
+/// A function literal made into a function (https://go.dev/ref/spec#Function_literals). It
+/// shares the variables it refers to with the function it was made in, so changes either
+/// makes are seen by the other.
+pub struct Closure {
+    /// The literal as a function declaration, named as a panic's trace names its calls, like
+    /// `main.func1` for the first literal in main
+    pub function: Declaration,
+    /// The scopes of the function it was made in, innermost last, as they were when it was
+    /// made
+    pub captured: Vec<HashMap<String, Rc<RefCell<Value>>>>,
+}
+
+impl Closure {
+    pub fn name(&self) -> &str {
+        match &self.function.kind {
+            DeclarationKind::Function { name, .. } => name,
+            _ => "",
+        }
+    }
+}
+
+// A closure can capture the variable holding it, so it's neither printed nor compared through
+// what it captured
+impl std::fmt::Debug for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Closure")
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Closure) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// An error (https://go.dev/ref/spec#Errors). Go's errors are pointers, so two errors are
+/// equal only when they're the same error, even if their messages are the same.
+#[derive(Debug)]
+pub struct Error {
+    /// The error's dynamic type, like `*errors.errorString`
+    pub type_name: &'static str,
+    pub message: String,
+    /// The errors it wraps, which errors.Is and errors.As look through
+    pub wrapped: Vec<Value>,
+}
+
+impl Error {
+    pub fn new(type_name: &'static str, message: String) -> Error {
+        Error {
+            type_name,
+            message,
+            wrapped: Vec::new(),
+        }
+    }
+
+    /// What the error's `Unwrap() error` method returns. An error wrapping several, like one
+    /// errors.Join makes, has `Unwrap() []error` instead.
+    pub fn unwrap(&self) -> Option<&Value> {
+        match (self.type_name, self.wrapped.as_slice()) {
+            ("*fmt.wrapError", [wrapped]) => Some(wrapped),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// A map key: a value that can be hashed and compared for equality
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum Key {
+    Int(i64),
+    // The float's bits
+    Float(u64),
+    String(String),
+    Bool(bool),
+    // A pointer, channel, error or native value, which are equal when they refer to the same
+    // storage
+    Address(usize),
+    // A pointer: where its target is, and the fields leading from there to what it points to
+    Field(usize, Vec<usize>),
+    Struct(Vec<Key>),
+    // A time.Time's seconds and nanoseconds since the Unix epoch
+    Time(i64, u32),
+    Nil,
+}
+
+/// A view of `len` elements of a backing array, starting `offset` elements in, which `append`
+/// can grow into until `cap` (https://go.dev/blog/slices-intro). Elements past `len` that
+/// nothing has stored to aren't in the backing vector yet.
+#[derive(Debug, Clone)]
+pub struct Slice {
+    pub element: Rc<TypeExpression>,
+    /// None for a nil slice
+    pub backing: Option<Rc<RefCell<Vec<Value>>>>,
+    pub offset: usize,
+    pub len: usize,
+    pub cap: usize,
+}
+
+impl Slice {
+    pub fn nil(element: Rc<TypeExpression>) -> Slice {
+        Slice {
+            element,
+            backing: None,
+            offset: 0,
+            len: 0,
+            cap: 0,
+        }
+    }
+
+    /// A slice of `elements` in a new backing array with room for `cap` of them
+    pub fn new(element: Rc<TypeExpression>, elements: Vec<Value>, cap: usize) -> Slice {
+        Slice {
+            element,
+            len: elements.len(),
+            cap: cap.max(elements.len()),
+            backing: Some(Rc::new(RefCell::new(elements))),
+            offset: 0,
+        }
+    }
+
+    /// The elements the slice views, copied out of its backing array
+    pub fn elements(&self) -> Vec<Value> {
+        match &self.backing {
+            Some(backing) => backing.borrow()[self.offset..self.offset + self.len].to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The element at `index`, which must be less than `len`
+    pub fn get(&self, index: usize) -> Value {
+        let backing = self.backing.as_ref().expect("a nil slice has no elements");
+        backing.borrow()[self.offset + index].clone()
+    }
+
+    /// Store `value` at `index`, which must be less than `len`, where every slice sharing
+    /// the backing array sees it
+    pub fn set(&self, index: usize, value: Value) {
+        let backing = self.backing.as_ref().expect("a nil slice has no elements");
+        backing.borrow_mut()[self.offset + index] = value;
+    }
+
+    /// Whether the slice's elements are bytes, which `%s` and conversions treat as a string
+    pub fn is_bytes(&self) -> bool {
+        matches!(self.element.to_string().as_str(), "byte" | "uint8")
+    }
+}
+
+/// Slices can only be compared to nil, so two slices are equal only when they're the same view
+impl PartialEq for Slice {
+    fn eq(&self, other: &Slice) -> bool {
+        let same_backing = match (&self.backing, &other.backing) {
+            (Some(left), Some(right)) => Rc::ptr_eq(left, right),
+            (None, None) => true,
+            _ => false,
+        };
+        same_backing && self.offset == other.offset && self.len == other.len
+    }
+}
+
+/// A map's entries by key, keeping each key's value to range over and print
+pub type Entries = HashMap<Key, (Value, Value)>;
+
+#[derive(Debug, Clone)]
+pub struct Map {
+    pub key: Rc<TypeExpression>,
+    pub value: Rc<TypeExpression>,
+    /// None for a nil map
+    pub entries: Option<Rc<RefCell<Entries>>>,
+}
+
+impl Map {
+    pub fn nil(key: Rc<TypeExpression>, value: Rc<TypeExpression>) -> Map {
+        Map {
+            key,
+            value,
+            entries: None,
+        }
+    }
+
+    pub fn new(key: Rc<TypeExpression>, value: Rc<TypeExpression>, capacity: usize) -> Map {
+        Map {
+            key,
+            value,
+            entries: Some(Rc::new(RefCell::new(HashMap::with_capacity(capacity)))),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .as_ref()
+            .map_or(0, |entries| entries.borrow().len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The entries ordered by key, as fmt prints them
+    pub fn sorted_entries(&self) -> Vec<(Value, Value)> {
+        let mut entries: Vec<(Value, Value)> = match &self.entries {
+            Some(entries) => entries.borrow().values().cloned().collect(),
+            None => Vec::new(),
+        };
+        entries.sort_by(|(left, _), (right, _)| compare_keys(left, right));
+        entries
+    }
+}
+
+/// Maps are equal only to themselves
+impl PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        match (&self.entries, &other.entries) {
+            (Some(left), Some(right)) => Rc::ptr_eq(left, right),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub element: Rc<TypeExpression>,
+    /// None for a nil channel
+    pub state: Option<Rc<RefCell<ChannelState>>>,
+}
+
+#[derive(Debug)]
+pub struct ChannelState {
+    /// Buffered values, then those of senders waiting for a receiver to take them
+    pub buffer: VecDeque<Value>,
+    pub capacity: usize,
+    pub closed: bool,
+    /// How many values have been sent and received, which tells a sender when its value has
+    /// fit in the buffer or been taken
+    pub sent: usize,
+    pub received: usize,
+    /// Receivers waiting for a value
+    pub receivers: usize,
+}
+
+impl Channel {
+    pub fn nil(element: Rc<TypeExpression>) -> Channel {
+        Channel {
+            element,
+            state: None,
+        }
+    }
+
+    pub fn new(element: Rc<TypeExpression>, capacity: usize) -> Channel {
+        Channel {
+            element,
+            state: Some(Rc::new(RefCell::new(ChannelState {
+                buffer: VecDeque::with_capacity(capacity),
+                capacity,
+                closed: false,
+                sent: 0,
+                received: 0,
+                receivers: 0,
+            }))),
+        }
+    }
+
+    fn address(&self) -> usize {
+        self.state
+            .as_ref()
+            .map_or(0, |state| Rc::as_ptr(state) as usize)
+    }
+}
+
+/// Channels are equal when they're the same channel
+impl PartialEq for Channel {
+    fn eq(&self, other: &Channel) -> bool {
+        self.address() == other.address()
+    }
+}
+
+/// A value of a struct type (https://go.dev/ref/spec#Struct_types), with a value for each of
+/// its type's fields
+#[derive(Debug, Clone)]
+pub struct Struct {
+    pub struct_type: Rc<StructType>,
+    pub fields: Vec<Value>,
+}
+
+/// Structs are equal when their types and fields are
+impl PartialEq for Struct {
+    fn eq(&self, other: &Struct) -> bool {
+        self.struct_type.type_name() == other.struct_type.type_name() && self.fields == other.fields
+    }
+}
+
+/// A struct type's fields in order, and its name if it's a declared type like
+/// `type Point struct { X, Y int }`
+#[derive(Debug)]
+pub struct StructType {
+    pub name: Option<String>,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Debug)]
+pub struct Field {
+    /// An embedded field's name is its type's, like `Animal` of `*Animal`
+    pub name: String,
+    pub type_expression: Rc<TypeExpression>,
+    pub embedded: bool,
+}
+
+impl StructType {
+    /// The type as `%T` prints it, like `main.Point` or `struct { X int; Y int }`. A type of
+    /// an imported package was named `shapes.Rect` as the program was loaded.
+    pub fn type_name(&self) -> String {
+        match &self.name {
+            Some(name) if name.contains('.') => return name.clone(),
+            Some(name) => return format!("main.{}", name),
+            None => {}
+        }
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field| match field.embedded {
+                true => field.type_expression.to_string(),
+                false => format!("{} {}", field.name, field.type_expression),
+            })
+            .collect();
+        match fields.is_empty() {
+            true => "struct {}".to_string(),
+            false => format!("struct {{ {} }}", fields.join("; ")),
+        }
+    }
+
+    /// The index of the field `name`, not counting those promoted from embedded fields
+    pub fn field(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+}
+
+/// Where a pointer points: a variable, or an element of a slice's backing array
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// A variable, or what new or `&T{}` allocated
+    Variable(Rc<RefCell<Value>>),
+    Element(Rc<RefCell<Vec<Value>>>, usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Pointer {
+    pub target_type: Rc<TypeExpression>,
+    /// None for a nil pointer
+    pub target: Option<Target>,
+    /// The indexes of the fields leading from the target to what's pointed to, like X's of
+    /// `&point.X`
+    pub fields: Vec<usize>,
+}
+
+impl Pointer {
+    pub fn nil(target_type: Rc<TypeExpression>) -> Pointer {
+        Pointer {
+            target_type,
+            target: None,
+            fields: Vec::new(),
+        }
+    }
+
+    /// A pointer to a new variable holding `value`
+    pub fn new(target_type: Rc<TypeExpression>, value: Value) -> Pointer {
+        Pointer::to(target_type, Target::Variable(Rc::new(RefCell::new(value))))
+    }
+
+    pub fn to(target_type: Rc<TypeExpression>, target: Target) -> Pointer {
+        Pointer {
+            target_type,
+            target: Some(target),
+            fields: Vec::new(),
+        }
+    }
+
+    /// A pointer to the field at `index` of the struct this one points to
+    pub fn field(&self, field_type: Rc<TypeExpression>, index: usize) -> Pointer {
+        let mut fields = self.fields.clone();
+        fields.push(index);
+        Pointer {
+            target_type: field_type,
+            target: self.target.clone(),
+            fields,
+        }
+    }
+
+    /// A copy of what the pointer points to, or None if it's nil
+    pub fn load(&self) -> Option<Value> {
+        self.inspect(Value::clone)
+    }
+
+    /// Call `inspect` with what the pointer points to, without copying it, or return None if
+    /// it's nil
+    pub fn inspect<T>(&self, inspect: impl FnOnce(&Value) -> T) -> Option<T> {
+        let follow = |value: &Value| {
+            self.fields
+                .iter()
+                .try_fold(value, |value, index| match value {
+                    Value::Struct(value) => value.fields.get(*index),
+                    _ => None,
+                })
+                .map(inspect)
+        };
+        match self.target.as_ref()? {
+            Target::Variable(variable) => follow(&variable.borrow()),
+            Target::Element(backing, index) => follow(backing.borrow().get(*index)?),
+        }
+    }
+
+    /// Store `value` where the pointer points, which does nothing if it's nil
+    pub fn store(&self, value: Value) {
+        let follow = |target: &mut Value| {
+            let target = self
+                .fields
+                .iter()
+                .try_fold(target, |target, index| match target {
+                    Value::Struct(target) => target.fields.get_mut(*index),
+                    _ => None,
+                });
+            if let Some(target) = target {
+                *target = value;
+            }
+        };
+        match &self.target {
+            Some(Target::Variable(variable)) => follow(&mut variable.borrow_mut()),
+            Some(Target::Element(backing, index)) => {
+                if let Some(element) = backing.borrow_mut().get_mut(*index) {
+                    follow(element);
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Where the target is, which is 0 for nil
+    fn address(&self) -> usize {
+        match &self.target {
+            Some(Target::Variable(variable)) => Rc::as_ptr(variable) as usize,
+            Some(Target::Element(backing, index)) => {
+                Rc::as_ptr(backing) as usize + index * std::mem::size_of::<Value>()
+            }
+            None => 0,
+        }
+    }
+}
+
+/// Pointers are equal when they point to the same variable, element or field
+impl PartialEq for Pointer {
+    fn eq(&self, other: &Pointer) -> bool {
+        self.address() == other.address() && self.fields == other.fields
+    }
+}
+
+/// A value of a type a native package declares. Its state is shared by its copies, which Go
+/// programs don't make of the sync types anyway, since `go vet` reports copying one.
+#[derive(Debug, Clone)]
+pub enum Native {
+    /// How many goroutines are still to call Done
+    WaitGroup(Rc<RefCell<i64>>),
+    /// Whether the mutex is locked
+    Mutex(Rc<RefCell<bool>>),
+    Once(Rc<RefCell<OnceState>>),
+    /// One of os.Stdin, os.Stdout and os.Stderr
+    File(File),
+    /// A bufio.Scanner reading lines
+    Scanner(Rc<RefCell<Scanner>>),
+    /// A time.Time, which unlike the others is a value rather than shared state
+    Time(Time),
+    /// The *testing.T a test function is called with
+    Test(Rc<RefCell<Test>>),
+}
+
+/// The program's standard streams, shared with every goroutine
+#[derive(Debug, Clone)]
+pub enum File {
+    Stdin(Rc<RefCell<Input>>),
+    Stdout(Rc<RefCell<Output>>),
+    Stderr(Rc<RefCell<Output>>),
+}
+
+/// The program's standard input
+pub struct Input(pub Box<dyn BufRead + Send>);
+
+impl std::fmt::Debug for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Input")
+    }
+}
+
+/// Where the program writes, like its standard output
+pub struct Output(pub Box<dyn Write + Send>);
+
+impl std::fmt::Debug for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Output")
+    }
+}
+
+/// A bufio.Scanner splitting what it reads into lines (https://pkg.go.dev/bufio#Scanner)
+#[derive(Debug)]
+pub struct Scanner {
+    pub file: File,
+    /// The line the last call to Scan read, without its line ending
+    pub token: String,
+    /// The error that stopped the scanner reading, other than the end of its input
+    pub error: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OnceState {
+    NotStarted,
+    /// The function is being called, and other callers of Do wait for it to return
+    Running,
+    Done,
+}
+
+impl Native {
+    /// The zero value of the sync type `name`, or None if the package doesn't declare it
+    pub fn zero(name: &str) -> Option<Native> {
+        Some(match name {
+            "WaitGroup" => Native::WaitGroup(Rc::new(RefCell::new(0))),
+            "Mutex" => Native::Mutex(Rc::new(RefCell::new(false))),
+            "Once" => Native::Once(Rc::new(RefCell::new(OnceState::NotStarted))),
+            _ => return None,
+        })
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Native::WaitGroup(_) => "sync.WaitGroup",
+            Native::Mutex(_) => "sync.Mutex",
+            Native::Once(_) => "sync.Once",
+            Native::File(_) => "*os.File",
+            Native::Scanner(_) => "*bufio.Scanner",
+            Native::Time(_) => "time.Time",
+            Native::Test(_) => "*testing.T",
+        }
+    }
+
+    /// The value as a map key: where its state is, or a Time's instant
+    fn key(&self) -> Key {
+        let address = match self {
+            Native::WaitGroup(counter) => Rc::as_ptr(counter) as usize,
+            Native::Mutex(locked) => Rc::as_ptr(locked) as usize,
+            Native::Once(state) => Rc::as_ptr(state) as usize,
+            Native::File(File::Stdin(input)) => Rc::as_ptr(input) as usize,
+            Native::File(File::Stdout(output) | File::Stderr(output)) => {
+                Rc::as_ptr(output) as usize
+            }
+            Native::Scanner(scanner) => Rc::as_ptr(scanner) as usize,
+            Native::Time(time) => return Key::Time(time.seconds, time.nanoseconds),
+            Native::Test(test) => Rc::as_ptr(test) as usize,
+        };
+        Key::Address(address)
+    }
+}
+
+/// Native values are equal when they share their state, and Times when they're the same
+/// instant
+impl PartialEq for Native {
+    fn eq(&self, other: &Native) -> bool {
+        self.key() == other.key()
+    }
+}
+
+/// A type as `%T` prints it, where a type the program declares is qualified by its package,
+/// like `main.Point`
+fn qualified(type_expression: &TypeExpression) -> String {
+    match &type_expression.kind {
+        TypeExpressionKind::Name(name)
+            if !BASIC_TYPES.contains(&name.as_str())
+                && name != "error"
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+        {
+            format!("main.{}", name)
+        }
+        _ => type_expression.to_string(),
+    }
+}
+
+/// The type of a function, like `func(int, ...string) error`
+fn function_type_name(function: &Declaration) -> String {
+    let DeclarationKind::Function {
+        parameters,
+        results,
+        ..
+    } = &function.kind
+    else {
+        return "func()".to_string();
+    };
+    // Each name of `a, b int` is a parameter of its own
+    let types = |parameters: &[Parameter]| -> Vec<String> {
+        parameters
+            .iter()
+            .flat_map(|parameter| {
+                let variadic = if parameter.variadic { "..." } else { "" };
+                let name = format!("{}{}", variadic, parameter.type_expression);
+                vec![name; parameter.names.len().max(1)]
+            })
+            .collect()
+    };
+    let results = types(results);
+    let results = match results.as_slice() {
+        [] => String::new(),
+        [result] => format!(" {}", result),
+        results => format!(" ({})", results.join(", ")),
+    };
+    format!("func({}){}", types(parameters).join(", "), results)
+}
+
+/// The order fmt prints map keys in: numbers by value, strings lexically, false before true
+fn compare_keys(left: &Value, right: &Value) -> std::cmp::Ordering {
+    match (left.underlying(), right.underlying()) {
+        (Value::Int(left), Value::Int(right)) => left.cmp(right),
+        (Value::Float(left), Value::Float(right)) => left.total_cmp(right),
+        (Value::String(left), Value::String(right)) => left.cmp(right),
+        (Value::Bool(left), Value::Bool(right)) => left.cmp(right),
+        _ => left.to_string().cmp(&right.to_string()),
+    }
+}
+
+/// The value as `%v` prints it, e.g. `fmt.Println(1e21)` prints `1e+21`
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", format_float(*value, 'g', -1, 64)),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Duration(duration) => write!(f, "{}", format_duration(*duration)),
+            Value::Error(error) => write!(f, "{}", error.message),
+            Value::Slice(slice) => {
+                let elements: Vec<String> = slice.elements().iter().map(nested).collect();
+                write!(f, "[{}]", elements.join(" "))
+            }
+            Value::Map(map) => {
+                let entries: Vec<String> = map
+                    .sorted_entries()
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", nested(key), nested(value)))
+                    .collect();
+                write!(f, "map[{}]", entries.join(" "))
+            }
+            // Pointers and channels print as their address
+            Value::Channel(channel) if channel.state.is_some() => {
+                write!(f, "{:#x}", channel.address())
+            }
+            Value::Struct(value) => {
+                let fields: Vec<String> = value.fields.iter().map(nested).collect();
+                write!(f, "{{{}}}", fields.join(" "))
+            }
+            // Except a pointer to a struct, which prints as `&` and the struct
+            Value::Pointer(pointer) => match pointer.load() {
+                Some(target @ Value::Struct(_)) => write!(f, "&{}", target),
+                Some(_) => write!(f, "{:#x}", pointer.address()),
+                None => write!(f, "<nil>"),
+            },
+            Value::Function(function) | Value::Method(function, _) => {
+                write!(f, "{:p}", Rc::as_ptr(function))
+            }
+            Value::Closure(closure) => write!(f, "{:p}", Rc::as_ptr(closure)),
+            Value::Named(named) => write!(f, "{}", named.value),
+            // A Time has a String method, which fmt prints it with
+            Value::Native(Native::Time(time)) => write!(f, "{}", time),
+            // Their fields are unexported, which isn't modelled
+            Value::Native(_) => write!(f, "{{}}"),
+            Value::Channel(_) | Value::Nil => write!(f, "<nil>"),
+        }
+    }
+}
+
+/// A value inside a slice, map or struct, where fmt prints a pointer to a struct as its address
+/// rather than what it points to
+fn nested(value: &Value) -> String {
+    match value {
+        Value::Pointer(pointer) if pointer.target.is_some() => {
+            format!("{:#x}", pointer.address())
+        }
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_values() {
+        assert_eq!(Value::Float(3.5).to_string(), "3.5");
+        assert_eq!(Value::Float(100000.0).to_string(), "100000");
+        assert_eq!(Value::Float(1e21).to_string(), "1e+21");
+        assert_eq!(Value::Float(1.5e-5).to_string(), "1.5e-05");
+        assert_eq!(Value::Float(0.0001).to_string(), "0.0001");
+        assert_eq!(Value::Float(-0.0).to_string(), "-0");
+        assert_eq!(Value::Float(f64::INFINITY).to_string(), "+Inf");
+        assert_eq!(Value::Int(-7).to_string(), "-7");
+        assert_eq!(Value::Nil.to_string(), "<nil>");
+    }
+}
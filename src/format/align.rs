@@ -0,0 +1,88 @@
+/// Ends a cell the formatter wants aligned with the cells above and below it, as a tab does
+/// for gofmt's tabwriter. It can't appear in Go source outside a literal.
+pub const CELL: char = '\x0b';
+
+/// Replace each `CELL` with the spaces that line its cell up with the same cell of the lines
+/// around it: one more than the widest of them. A run of lines is aligned while each has the
+/// cell and the same indentation, and a column of only empty cells takes no space. The text
+/// after a line's last `CELL`, like a trailing comment, isn't a cell and isn't padded.
+pub fn align(text: &str) -> String {
+    let lines: Vec<(&str, Vec<&str>)> = text
+        .split('\n')
+        .map(|line| {
+            let indent = line.len() - line.trim_start_matches('\t').len();
+            let (indent, rest) = line.split_at(indent);
+            (indent, rest.split(CELL).collect())
+        })
+        .collect();
+    let mut widths: Vec<Vec<usize>> = lines
+        .iter()
+        .map(|(_, cells)| vec![0; cells.len() - 1])
+        .collect();
+    align_column(&lines, &mut widths, 0, 0..lines.len());
+
+    let mut aligned = String::with_capacity(text.len());
+    for (index, ((indent, cells), widths)) in lines.iter().zip(&widths).enumerate() {
+        if index > 0 {
+            aligned.push('\n');
+        }
+        aligned.push_str(indent);
+        for (cell, width) in cells.iter().zip(widths) {
+            aligned.push_str(cell);
+            if *width > 0 {
+                let padding = width - cell.chars().count() + 1;
+                aligned.extend(std::iter::repeat_n(' ', padding));
+            }
+        }
+        aligned.push_str(cells.last().expect("a line has text after its cells"));
+    }
+    aligned
+}
+
+/// Find the widths of `column` for each run of the lines in `range` that have it, then of the
+/// columns after it within each run
+fn align_column(
+    lines: &[(&str, Vec<&str>)],
+    widths: &mut [Vec<usize>],
+    column: usize,
+    range: std::ops::Range<usize>,
+) {
+    let has_column = |line: usize| lines[line].1.len() - 1 > column;
+    let mut line = range.start;
+    while line < range.end {
+        if !has_column(line) {
+            line += 1;
+            continue;
+        }
+        let start = line;
+        let indent = lines[start].0;
+        while line < range.end && has_column(line) && lines[line].0 == indent {
+            line += 1;
+        }
+        let width = (start..line)
+            .map(|line| lines[line].1[column].chars().count())
+            .max()
+            .unwrap_or(0);
+        for widths in &mut widths[start..line] {
+            widths[column] = width;
+        }
+        align_column(lines, widths, column + 1, start..line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_cells() {
+        // `Longer` has no comment, so the comments above and below it aren't aligned
+        let text = "type T struct {\n\tA\x0bint\x0b// a\n\tLonger\x0bstring\n\tB\x0b[]byte\x0b// b\n}\n\nx := 1\x0b// one\nyy := 2\x0b// two\n\tz\x0b// three";
+        assert_eq!(
+            align(text),
+            "type T struct {\n\tA      int // a\n\tLonger string\n\tB      []byte // b\n}\n\nx := 1  // one\nyy := 2 // two\n\tz // three"
+        );
+        // A column that's empty on every line takes no space
+        assert_eq!(align("a\x0b\x0b= 1\nbc\x0b\x0b= 2"), "a  = 1\nbc = 2");
+    }
+}
@@ -0,0 +1,63 @@
+/// How `format_source_with_config` treats a file's imports beyond gofmt's sorting, and what
+/// it indents with. The default formats as gofmt does; `goimports` also regroups imports as
+/// goimports lays them out.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormatterConfig {
+    group_imports: bool,
+    local_prefix: Option<String>,
+    remove_unused_imports: bool,
+    indent_spaces: Option<usize>,
+}
+
+impl FormatterConfig {
+    /// Imports grouped into standard library, other and local packages
+    pub fn goimports() -> FormatterConfig {
+        FormatterConfig::default().with_grouped_imports()
+    }
+
+    /// Merge a file's imports into one declaration, sorted into a group of standard library
+    /// packages, then one of other packages, then one of the packages under the local prefix,
+    /// with a blank line between the groups
+    pub fn with_grouped_imports(mut self) -> FormatterConfig {
+        self.group_imports = true;
+        self
+    }
+
+    /// Group the imports of paths starting with `prefix`, like a module's path, as local. A
+    /// path is under the prefix if it is the prefix or continues it with a `/`.
+    pub fn with_local_prefix(mut self, prefix: String) -> FormatterConfig {
+        self.local_prefix = Some(prefix);
+        self
+    }
+
+    /// Leave out the imports the file doesn't use, and their comments. Imports named `_` or
+    /// `.` are always kept, since their uses can't be seen.
+    pub fn with_unused_imports_removed(mut self) -> FormatterConfig {
+        self.remove_unused_imports = true;
+        self
+    }
+
+    /// Indent with `width` spaces for each tab gofmt would indent with, for editors set to
+    /// insert spaces. Alignment is with spaces either way.
+    pub fn with_indent_spaces(mut self, width: usize) -> FormatterConfig {
+        self.indent_spaces = Some(width);
+        self
+    }
+
+    pub fn group_imports(&self) -> bool {
+        self.group_imports
+    }
+
+    pub fn local_prefix(&self) -> Option<&str> {
+        self.local_prefix.as_deref()
+    }
+
+    pub fn remove_unused_imports(&self) -> bool {
+        self.remove_unused_imports
+    }
+
+    pub fn indent_spaces(&self) -> Option<usize> {
+        self.indent_spaces
+    }
+}
@@ -0,0 +1,259 @@
+/// The unchanged lines a hunk shows before and after each change, as `diff -u` does
+const CONTEXT: usize = 3;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Edit {
+    Keep,
+    Delete,
+    Insert,
+}
+
+/// A unified diff from `old` to `new`, with `old_name` and `new_name` in its header, or an
+/// empty string if they're the same
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = shortest_edit(&old_lines, &new_lines);
+    if edits.iter().all(|edit| *edit == Edit::Keep) {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", old_name, new_name);
+    // The index into `edits`, `old_lines` and `new_lines` each edit starts at
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_index, mut new_index) = (0, 0);
+    for edit in &edits {
+        positions.push((old_index, new_index));
+        match edit {
+            Edit::Keep => {
+                old_index += 1;
+                new_index += 1;
+            }
+            Edit::Delete => old_index += 1,
+            Edit::Insert => new_index += 1,
+        }
+    }
+    positions.push((old_index, new_index));
+
+    let mut start = 0;
+    while let Some(change) = edits[start..].iter().position(|edit| *edit != Edit::Keep) {
+        // A hunk runs from the context before a change to the context after the last change
+        // that's too close to the one before it to get a hunk of its own
+        let first_change = start + change;
+        let hunk_start = first_change.saturating_sub(CONTEXT).max(start);
+        let mut hunk_end = first_change;
+        let mut index = first_change;
+        while index < edits.len() {
+            if edits[index] != Edit::Keep {
+                hunk_end = index + 1;
+            } else if index - hunk_end >= 2 * CONTEXT {
+                break;
+            }
+            index += 1;
+        }
+        let hunk_end = (hunk_end + CONTEXT).min(edits.len());
+
+        let (old_start, new_start) = positions[hunk_start];
+        let (old_end, new_end) = positions[hunk_end];
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_end - old_start),
+            range(new_start, new_end - new_start)
+        ));
+        for (edit, (old_index, new_index)) in edits[hunk_start..hunk_end]
+            .iter()
+            .zip(&positions[hunk_start..hunk_end])
+        {
+            let (prefix, line) = match edit {
+                Edit::Keep => (' ', old_lines[*old_index]),
+                Edit::Delete => ('-', old_lines[*old_index]),
+                Edit::Insert => ('+', new_lines[*new_index]),
+            };
+            diff.push(prefix);
+            diff.push_str(line);
+            if !line.ends_with('\n') {
+                diff.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        start = hunk_end;
+    }
+    diff
+}
+
+/// A run of `old`'s lines a diff replaces, as `line_changes` finds them
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineChange {
+    /// The replaced lines, by index from 0, empty where lines are only inserted
+    pub lines: std::ops::Range<usize>,
+    /// The lines that replace them, each with its line break
+    pub text: String,
+}
+
+/// The fewest runs of lines to replace that turn `old` into `new`, in order
+pub fn line_changes(old: &str, new: &str) -> Vec<LineChange> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let mut changes: Vec<LineChange> = Vec::new();
+    let (mut old_index, mut new_index) = (0, 0);
+    let mut changing = false;
+    for edit in shortest_edit(&old_lines, &new_lines) {
+        if edit != Edit::Keep && !changing {
+            changes.push(LineChange {
+                lines: old_index..old_index,
+                text: String::new(),
+            });
+        }
+        changing = edit != Edit::Keep;
+        match edit {
+            Edit::Keep => {
+                old_index += 1;
+                new_index += 1;
+            }
+            Edit::Delete => {
+                old_index += 1;
+                changes.last_mut().expect("a change was started").lines.end = old_index;
+            }
+            Edit::Insert => {
+                let change = changes.last_mut().expect("a change was started");
+                change.text.push_str(new_lines[new_index]);
+                new_index += 1;
+            }
+        }
+    }
+    changes
+}
+
+/// A hunk's range of lines, `start,count` counting from 1, or the line before an empty one
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// The fewest deletions and insertions that turn `old` into `new`, with the lines kept
+/// between them, by Myers' algorithm
+fn shortest_edit(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    // The furthest `x` reached on each diagonal `k = x - y`, for each number of edits
+    let mut furthest = vec![0isize; 2 * max + 3];
+    let mut trace = Vec::new();
+    'search: for edits in 0..=max as isize {
+        trace.push(furthest.clone());
+        for k in (-edits..=edits).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -edits || (k != edits && furthest[index - 1] < furthest[index + 1])
+            {
+                furthest[index + 1]
+            } else {
+                furthest[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[index] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk back from the end through the diagonals each number of edits reached
+    let mut script = Vec::with_capacity(max);
+    let (mut x, mut y) = (n, m);
+    for (edits, furthest) in trace.iter().enumerate().rev() {
+        let edits = edits as isize;
+        let k = x - y;
+        let previous_k = if k == -edits
+            || (k != edits
+                && furthest[(k - 1 + offset) as usize] < furthest[(k + 1 + offset) as usize])
+        {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = furthest[(previous_k + offset) as usize];
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            script.push(Edit::Keep);
+            x -= 1;
+            y -= 1;
+        }
+        if edits > 0 {
+            script.push(if x == previous_x {
+                Edit::Insert
+            } else {
+                Edit::Delete
+            });
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    script.reverse();
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_hunks() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n";
+        let new = "1\ntwo\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n15\nsixteen\n";
+        assert_eq!(
+            unified_diff(old, new, "main.go.orig", "main.go"),
+            "--- main.go.orig\n+++ main.go\n@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n@@ -11,5 +11,5 @@\n 11\n 12\n 13\n-14\n 15\n+sixteen\n"
+        );
+
+        // Changes closer than twice the context share a hunk
+        assert_eq!(
+            unified_diff("a\nb\nc\nd\n", "a\nB\nc\nD\n", "old", "new"),
+            "--- old\n+++ new\n@@ -1,4 +1,4 @@\n a\n-b\n+B\n c\n-d\n+D\n"
+        );
+    }
+
+    #[test]
+    fn diff_line_changes() {
+        assert_eq!(line_changes("a\nb\n", "a\nb\n"), []);
+        assert_eq!(
+            line_changes("a\nb\nc\nd\n", "a\nB\nC\nd\ne\n"),
+            [
+                LineChange {
+                    lines: 1..3,
+                    text: "B\nC\n".to_string()
+                },
+                LineChange {
+                    lines: 4..4,
+                    text: "e\n".to_string()
+                }
+            ]
+        );
+        assert_eq!(
+            line_changes("a\nb", "b\n"),
+            [LineChange {
+                lines: 0..2,
+                text: "b\n".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn diff_edges() {
+        assert_eq!(
+            unified_diff("", "a\n", "old", "new"),
+            "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n"
+        );
+        assert_eq!(
+            unified_diff("a", "a\n", "old", "new"),
+            "--- old\n+++ new\n@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+a\n"
+        );
+    }
+}
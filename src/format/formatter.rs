@@ -0,0 +1,1817 @@
+use crate::ast::ast::{File, ImportSpec};
+use crate::ast::declaration::{Declaration, DeclarationKind, Parameter, VariableSpec};
+use crate::ast::expression::{Expression, ExpressionKind, KeyedElement, UnaryOperator};
+use crate::ast::statement::{SelectCase, Statement, StatementKind, SwitchCase};
+use crate::ast::type_expression::{
+    ChannelDirection, InterfaceMethod, StructField, TypeExpression, TypeExpressionKind,
+};
+use crate::format::{
+    align::{CELL, align},
+    config::FormatterConfig,
+    imports::{ImportLine, group, sort_runs, unused_imports},
+};
+use crate::lexer::{
+    lexer::Lexer,
+    token::Token,
+    token_type::{Keyword, Operator, TokenKind},
+};
+use crate::parser::parser::Parser;
+use crate::primitives::{errors::parser::ParserError, position::Position};
+
+/// The most line breaks kept between two lines, so runs of blank lines become one
+const MAX_NEWLINES: usize = 2;
+/// Unary operators bind tighter than any binary operator, and the operands of selectors,
+/// calls and index expressions tighter still
+const UNARY_PRECEDENCE: u8 = 6;
+const HIGHEST_PRECEDENCE: u8 = 7;
+/// The longest a function written on one line can be and stay on one line, as with gofmt
+const MAX_ONE_LINE_FUNCTION: usize = 100;
+const MAX_ONE_LINE_STATEMENTS: usize = 5;
+
+/// Parse `source` and format it as gofmt would: tabs for indentation, spaces around binary
+/// operators but those binding tighter than their neighbours, a blank line at most between
+/// lines, and struct fields, grouped variables and trailing comments aligned. Its comments
+/// are kept where they were relative to the code.
+pub fn format_source(source: &str) -> Result<String, Vec<ParserError>> {
+    format_source_with_config(source, &FormatterConfig::default())
+}
+
+/// Format `source` like `format_source`, organizing its imports as `config` asks
+pub fn format_source_with_config(
+    source: &str,
+    config: &FormatterConfig,
+) -> Result<String, Vec<ParserError>> {
+    let mut parser = Parser::new(source);
+    let file = parser.parse()?;
+    // Code the parser recovered from errors in is left out of the tree, so it can't be printed
+    if !parser.errors.is_empty() {
+        return Err(parser.errors);
+    }
+    Ok(format_file_with_config(&file, source, config))
+}
+
+/// Format `file`, which was parsed from `source`. The comments are taken from `source`, and
+/// the positions of `file`'s nodes decide where they go and which blank lines are kept.
+pub fn format_file(file: &File, source: &str) -> String {
+    format_file_with_config(file, source, &FormatterConfig::default())
+}
+
+/// Format `file` like `format_file`, organizing its imports as `config` asks
+pub fn format_file_with_config(file: &File, source: &str, config: &FormatterConfig) -> String {
+    let trivia = trivia(source);
+    let mut formatter = Formatter::new(&trivia.comments, &trivia.code_before);
+    formatter.file(file, &trivia, config);
+    let formatted = align(&formatter.output);
+    match config.indent_spaces() {
+        Some(width) => indent_with_spaces(&formatted, width),
+        None => formatted,
+    }
+}
+
+/// `text`, formatted, with the tabs each line is indented with made `width` spaces each.
+/// The lines inside a block comment are left as they're written.
+fn indent_with_spaces(text: &str, width: usize) -> String {
+    let mut lexer = Lexer::new(text);
+    let mut verbatim = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        match token.kind {
+            Some(TokenKind::BlockComment) => {
+                let line = token.position.line;
+                verbatim.extend(line + 1..=line + token.value.matches('\n').count());
+            }
+            Some(TokenKind::EOF) | None => break,
+            _ => {}
+        }
+    }
+    let mut indented = String::with_capacity(text.len());
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let tabs = line.len() - line.trim_start_matches('\t').len();
+        match verbatim.contains(&(index + 1)) {
+            true => indented.push_str(line),
+            false => {
+                indented.extend(std::iter::repeat_n(' ', tabs * width));
+                indented.push_str(&line[tabs..]);
+            }
+        }
+    }
+    indented
+}
+
+/// `expression` formatted on its own, for code made rather than parsed
+pub(crate) fn format_expression(expression: &Expression) -> String {
+    let mut formatter = Formatter::new(&[], &[]);
+    formatter.expression(expression);
+    align(&formatter.output)
+}
+
+/// `statement` formatted on its own, its lines after the first indented by `indent` tabs
+pub(crate) fn format_statement(statement: &Statement, indent: usize) -> String {
+    let mut formatter = Formatter::new(&[], &[]);
+    formatter.indent = indent;
+    formatter.statement(statement);
+    align(&formatter.output)
+        .trim_start_matches('\t')
+        .to_string()
+}
+
+/// What the tree doesn't keep of a file's source
+pub(crate) struct Trivia {
+    pub comments: Vec<Token>,
+    /// The position of the code token before each of `comments`, the line's last token for a
+    /// comment that trails code
+    pub code_before: Vec<Position>,
+    /// The line the package clause is on
+    pub package_line: usize,
+    pub imports: Vec<ImportDeclaration>,
+}
+
+/// An import declaration, which the tree only keeps the specs of
+pub(crate) struct ImportDeclaration {
+    /// The position of `import`
+    pub keyword: Position,
+    /// The position of the `)` of a parenthesized declaration
+    pub close: Option<Position>,
+    pub parenthesized: bool,
+}
+
+pub(crate) fn trivia(source: &str) -> Trivia {
+    let mut lexer = Lexer::new(source);
+    let mut comments = Vec::new();
+    let mut code_before = Vec::new();
+    let mut last_code = Position::new(0, 0, 0);
+    let mut package_line = None;
+    let mut imports: Vec<ImportDeclaration> = Vec::new();
+    let mut after_import = false;
+    let mut in_import = false;
+    loop {
+        let token = lexer.next_token();
+        match token.kind {
+            Some(TokenKind::SingleLineComment | TokenKind::BlockComment) => {
+                comments.push(token);
+                code_before.push(last_code);
+            }
+            Some(TokenKind::Newline) => {}
+            Some(TokenKind::EOF) | None => {
+                return Trivia {
+                    comments,
+                    code_before,
+                    package_line: package_line.unwrap_or(token.position.line),
+                    imports,
+                };
+            }
+            kind => {
+                last_code = token.position;
+                package_line.get_or_insert(token.position.line);
+                match kind {
+                    Some(TokenKind::Keyword(Keyword::Import)) => imports.push(ImportDeclaration {
+                        keyword: token.position,
+                        close: None,
+                        parenthesized: false,
+                    }),
+                    Some(TokenKind::LeftParen) if after_import => {
+                        in_import = true;
+                        if let Some(import) = imports.last_mut() {
+                            import.parenthesized = true;
+                        }
+                    }
+                    Some(TokenKind::RightParen) if in_import => {
+                        in_import = false;
+                        if let Some(import) = imports.last_mut() {
+                            import.close = Some(token.position);
+                        }
+                    }
+                    _ => {}
+                }
+                after_import = kind == Some(TokenKind::Keyword(Keyword::Import));
+            }
+        }
+    }
+}
+
+/// Where a comment ends, since a block comment can span lines
+fn end_line(comment: &Token) -> usize {
+    comment.position.line + comment.value.matches('\n').count()
+}
+
+fn is_before(comment: &Token, position: Position) -> bool {
+    precedes(comment.position, position)
+}
+
+fn precedes(a: Position, b: Position) -> bool {
+    (a.line, a.column_start) < (b.line, b.column_start)
+}
+
+/// Whether a token at `a` ends where a node ending at `b` does
+fn same_token(a: Position, b: Position) -> bool {
+    (a.line, a.column_end) == (b.line, b.column_end)
+}
+
+/// An import declaration's specs and the comments inside it
+struct ImportBlock<'a> {
+    /// The index of the first comment before the declaration
+    first_comment: usize,
+    /// The comments before `import`
+    doc: Vec<&'a Token>,
+    /// The comments after `import (` on its line
+    opening: Vec<&'a Token>,
+    lines: Vec<ImportLine<'a>>,
+    /// The comments after the last spec, before `)`
+    closing: Vec<&'a Token>,
+}
+
+/// Writes a file's tree back out as Go source, with `CELL`s where `align` lines text up
+struct Formatter<'a> {
+    output: String,
+    indent: usize,
+    /// Whether the line being written has no text yet, so it needs its indentation
+    line_start: bool,
+    /// The source line of the last code or comment written, from which the blank lines
+    /// between it and the next are kept
+    line: usize,
+    comments: &'a [Token],
+    /// The position of the code before each comment, which a comment trailing it stays after
+    code_before: &'a [Position],
+    next_comment: usize,
+    /// The `}` of the innermost block being written, before which the comments trailing the
+    /// code in it are, those after it trailing the block
+    block_close: Option<Position>,
+    /// Whether the last thing written was a `// comment`, which nothing can follow on its line
+    after_line_comment: bool,
+    /// Whether the last thing written was a `/* comment */`, which code can follow on its line
+    after_block_comment: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(comments: &'a [Token], code_before: &'a [Position]) -> Formatter<'a> {
+        Formatter {
+            output: String::new(),
+            indent: 0,
+            line_start: true,
+            line: 0,
+            comments,
+            code_before,
+            next_comment: 0,
+            block_close: None,
+            after_line_comment: false,
+            after_block_comment: false,
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.after_line_comment {
+            self.newline();
+        }
+        if self.line_start && !text.is_empty() {
+            self.output.extend(std::iter::repeat_n('\t', self.indent));
+            self.line_start = false;
+        }
+        // Code after a `/* comment */` is kept apart from it, but punctuation isn't
+        if self.after_block_comment && !text.starts_with([')', ']', '}', ',', ':', ';', ' ']) {
+            self.output.push(' ');
+        }
+        self.output.push_str(text);
+        if !text.is_empty() {
+            self.after_block_comment = false;
+        }
+    }
+
+    fn newline(&mut self) {
+        self.output.push('\n');
+        self.line_start = true;
+        self.after_line_comment = false;
+        self.after_block_comment = false;
+    }
+
+    /// Start a new line for what's on `line` of the source, keeping a blank line before it
+    /// if the source has one since the last line written, or if `min` asks for it
+    fn linebreak(&mut self, line: usize, min: usize) {
+        let count = line.saturating_sub(self.line).clamp(min, MAX_NEWLINES);
+        for _ in 0..count {
+            self.newline();
+        }
+    }
+
+    /// The next comment not yet written, if it's before `position`
+    fn comment_before(&self, position: Position) -> Option<&'a Token> {
+        self.comments
+            .get(self.next_comment)
+            .filter(|comment| is_before(comment, position))
+    }
+
+    fn comment(&mut self, comment: &Token) {
+        self.next_comment += 1;
+        if self.line_start {
+            // A line was started for it, or the file starts with it
+        } else if comment.position.line > self.line {
+            self.linebreak(comment.position.line, 1);
+        } else if !self.output.ends_with([' ', '(', '[', '{']) {
+            self.write(" ");
+        }
+        self.write(&comment.value);
+        self.line = end_line(comment);
+        if comment.kind == Some(TokenKind::SingleLineComment) {
+            self.after_line_comment = true;
+        } else {
+            self.after_block_comment = true;
+        }
+    }
+
+    /// Write the comments before `position`, each on its own line unless it shares one with
+    /// the code written last
+    fn comments_before(&mut self, position: Position) {
+        while let Some(comment) = self.comment_before(position) {
+            self.comment(comment);
+        }
+    }
+
+    /// Write the comments after the code on `line`, the first in a cell so a column of them
+    /// lines up
+    fn trailing_comments(&mut self, line: usize) {
+        let mut first = true;
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            let after_block = self
+                .block_close
+                .is_some_and(|close| !is_before(comment, close));
+            if comment.position.line != line || self.line_start || after_block {
+                break;
+            }
+            self.next_comment += 1;
+            if first {
+                self.write(&CELL.to_string());
+                first = false;
+            } else {
+                self.write(" ");
+            }
+            self.write(&comment.value);
+            self.line = end_line(comment);
+            self.after_line_comment = comment.kind == Some(TokenKind::SingleLineComment);
+            self.after_block_comment = !self.after_line_comment;
+        }
+    }
+
+    /// Start the line of an item at `position`, like a statement, after the comments before
+    /// it. The first line break keeps at least `min` of them.
+    fn start_line(&mut self, position: Position, min: usize) {
+        let mut min = min;
+        while let Some(comment) = self.comment_before(position) {
+            if !self.output.is_empty() {
+                self.linebreak(comment.position.line, min);
+            }
+            min = 1;
+            self.comment(comment);
+        }
+        // Code can follow a `/* comment */` on its line
+        let follows_comment = self.after_block_comment && self.line == position.line;
+        if !follows_comment && !self.output.is_empty() {
+            self.linebreak(position.line, min);
+        }
+        self.line = position.line;
+    }
+
+    fn file(&mut self, file: &File, trivia: &Trivia, config: &FormatterConfig) {
+        let package = Position::new(trivia.package_line, 0, 0);
+        self.start_line(package, 1);
+        self.write(&format!("package {}", file.package_name));
+        self.line = trivia.package_line;
+        self.trailing_comments(self.line);
+
+        self.imports(file, trivia, config);
+
+        let mut previous: Option<&Declaration> = None;
+        for declaration in &file.decls {
+            // Declarations of a kind stay together, unless the next has a comment before it
+            let same_kind = previous.is_some_and(|previous| {
+                std::mem::discriminant(&previous.kind) == std::mem::discriminant(&declaration.kind)
+            });
+            let documented = self.comment_before(declaration.position_start).is_some();
+            let min = if same_kind && !documented { 1 } else { 2 };
+            self.start_line(declaration.position_start, min);
+            self.declaration(declaration);
+            self.line = declaration.position_end.line;
+            self.trailing_comments(self.line);
+            previous = Some(declaration);
+        }
+
+        // The comments after everything else
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            self.comment(comment);
+        }
+        self.newline();
+    }
+
+    /// Write the import declarations with their specs sorted as gofmt sorts them, or merged
+    /// and grouped if `config` asks, leaving out the unused ones if it asks for that
+    fn imports(&mut self, file: &File, trivia: &Trivia, config: &FormatterConfig) {
+        let unused = match config.remove_unused_imports() {
+            true => unused_imports(file),
+            false => Vec::new(),
+        };
+        let mut blocks = Vec::new();
+        for (index, declaration) in trivia.imports.iter().enumerate() {
+            let next = trivia.imports.get(index + 1).map(|next| next.keyword);
+            let specs: Vec<&ImportSpec> = file
+                .imports
+                .iter()
+                .filter(|spec| {
+                    precedes(declaration.keyword, spec.position_start)
+                        && next.is_none_or(|next| precedes(spec.position_start, next))
+                })
+                .collect();
+            let mut block = self.import_block(declaration, &specs);
+            block
+                .lines
+                .retain(|line| !unused.iter().any(|spec| std::ptr::eq(*spec, line.spec)));
+            blocks.push((declaration, block));
+        }
+        let end = self.next_comment;
+
+        if config.group_imports() && !blocks.is_empty() {
+            // Everything moves into the first declaration, the comments before the others
+            // moving with their first spec
+            let mut merged: Option<(&ImportDeclaration, ImportBlock)> = None;
+            for (declaration, mut block) in blocks {
+                match &mut merged {
+                    None => merged = Some((declaration, block)),
+                    Some((_, first)) => {
+                        let mut comments = block.doc;
+                        comments.append(&mut block.opening);
+                        match block.lines.first_mut() {
+                            Some(line) => {
+                                comments.append(&mut line.leading);
+                                line.leading = comments;
+                            }
+                            None => first.closing.append(&mut comments),
+                        }
+                        first.lines.append(&mut block.lines);
+                        first.closing.append(&mut block.closing);
+                    }
+                }
+            }
+            if let Some((declaration, mut block)) = merged {
+                let lines = std::mem::take(&mut block.lines);
+                let parenthesized = declaration.parenthesized || lines.len() > 1;
+                self.import_declaration(declaration, block, group(lines, config), parenthesized, 2);
+            }
+        } else {
+            for (index, (declaration, mut block)) in blocks.into_iter().enumerate() {
+                let lines = std::mem::take(&mut block.lines);
+                let min = if index == 0 { 2 } else { 1 };
+                let parenthesized = declaration.parenthesized;
+                self.import_declaration(declaration, block, sort_runs(lines), parenthesized, min);
+            }
+        }
+        self.next_comment = end;
+    }
+
+    /// Take the comments of an import declaration with `specs` from those not yet written
+    fn import_block<'f>(
+        &mut self,
+        declaration: &ImportDeclaration,
+        specs: &[&'f ImportSpec],
+    ) -> ImportBlock<'f>
+    where
+        'a: 'f,
+    {
+        let first_comment = self.next_comment;
+        let doc = self.take_comments(|comment| is_before(comment, declaration.keyword));
+        let opening = match (declaration.parenthesized, specs.first()) {
+            (true, first) => self.take_comments(|comment| {
+                comment.position.line == declaration.keyword.line
+                    && first.is_none_or(|first| is_before(comment, first.position_start))
+            }),
+            (false, _) => Vec::new(),
+        };
+        let mut lines = Vec::new();
+        for spec in specs {
+            let leading = self.take_comments(|comment| is_before(comment, spec.position_start));
+            let line = spec.position_end.line;
+            let trailing = self.take_comments(|comment| comment.position.line == line);
+            lines.push(ImportLine {
+                spec,
+                leading,
+                trailing,
+            });
+        }
+        let closing = match declaration.close {
+            Some(close) => self.take_comments(|comment| is_before(comment, close)),
+            None => Vec::new(),
+        };
+        ImportBlock {
+            first_comment,
+            doc,
+            opening,
+            lines,
+            closing,
+        }
+    }
+
+    /// Skip the comments not yet written while `condition` holds, returning them
+    fn take_comments(&mut self, condition: impl Fn(&Token) -> bool) -> Vec<&'a Token> {
+        let mut taken = Vec::new();
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if !condition(comment) {
+                break;
+            }
+            taken.push(comment);
+            self.next_comment += 1;
+        }
+        taken
+    }
+
+    /// Write an import declaration with its specs in `groups`, a blank line between each
+    fn import_declaration(
+        &mut self,
+        declaration: &ImportDeclaration,
+        block: ImportBlock,
+        groups: Vec<Vec<ImportLine>>,
+        parenthesized: bool,
+        min: usize,
+    ) {
+        self.next_comment = block.first_comment;
+        let empty = groups.is_empty() && block.opening.is_empty() && block.closing.is_empty();
+        if empty {
+            // With its specs left out, only the comments before it are kept
+            self.comments_before(declaration.keyword);
+            return;
+        }
+        self.start_line(declaration.keyword, min);
+        self.write("import");
+        if !parenthesized {
+            self.write(" ");
+            for line in groups.iter().flatten() {
+                self.import_line(line);
+            }
+            return;
+        }
+        self.write(" (");
+        for comment in &block.opening {
+            self.write(" ");
+            self.write(&comment.value);
+        }
+        self.after_line_comment = false;
+        self.indent += 1;
+        for (index, group) in groups.iter().enumerate() {
+            if index > 0 {
+                self.newline();
+            }
+            for line in group {
+                self.newline();
+                self.import_line(line);
+            }
+        }
+        for comment in &block.closing {
+            self.newline();
+            self.write(&comment.value);
+        }
+        self.indent -= 1;
+        self.newline();
+        self.write(")");
+        self.line = declaration.close.map_or(self.line, |close| close.line);
+    }
+
+    /// A spec with its comments, which start on the line being written
+    fn import_line(&mut self, line: &ImportLine) {
+        for comment in &line.leading {
+            self.write(&comment.value);
+            self.newline();
+        }
+        if let Some(name) = &line.spec.name {
+            self.write(&format!("{} ", name));
+        }
+        self.write(&line.spec.path);
+        for (index, comment) in line.trailing.iter().enumerate() {
+            self.write(&if index == 0 { CELL } else { ' ' }.to_string());
+            self.write(&comment.value);
+        }
+        self.after_line_comment = false;
+        self.line = line.spec.position_end.line;
+    }
+
+    fn declaration(&mut self, declaration: &Declaration) {
+        match &declaration.kind {
+            DeclarationKind::Function {
+                receiver,
+                name,
+                parameters,
+                results,
+                body,
+            } => {
+                self.write("func ");
+                if let Some(receiver) = receiver {
+                    self.write("(");
+                    self.parameter(receiver);
+                    self.write(") ");
+                }
+                self.write(name);
+                let (start, end) = (declaration.position_start, declaration.position_end);
+                self.function(start, end, parameters, results, body);
+            }
+            DeclarationKind::Variable { specs, grouped }
+            | DeclarationKind::Constant { specs, grouped } => {
+                self.write(match declaration.kind {
+                    DeclarationKind::Constant { .. } => "const ",
+                    _ => "var ",
+                });
+                match specs.as_slice() {
+                    [spec] if !grouped => self.variable_spec(spec, false, 0),
+                    specs => self.variable_group(specs, declaration.position_end),
+                }
+            }
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } => {
+                self.write("type ");
+                self.expression(name);
+                self.write(" ");
+                self.type_expression(type_expression);
+            }
+        }
+    }
+
+    /// A function's signature and body, for a declaration or literal from `start` to `end`
+    fn function(
+        &mut self,
+        start: Position,
+        end: Position,
+        parameters: &[Parameter],
+        results: &[Parameter],
+        body: &[Statement],
+    ) {
+        self.signature(start.line, parameters, results);
+        self.write(" ");
+        if !self.one_line_body(start, end, body) {
+            // The body opens on the line the signature ends on
+            let open_line = parameters
+                .iter()
+                .chain(results)
+                .map(|parameter| parameter.position_end.line)
+                .fold(start.line, usize::max);
+            self.block(open_line, body, end);
+        }
+    }
+
+    /// Write a function's body on the line of its signature, as `{ return x }`, if it's
+    /// written on one line, short and without comments
+    fn one_line_body(&mut self, start: Position, end: Position, body: &[Statement]) -> bool {
+        if start.line != end.line
+            || body.len() > MAX_ONE_LINE_STATEMENTS
+            || self.comment_before(end).is_some()
+        {
+            return false;
+        }
+        if body.is_empty() {
+            self.write("{}");
+            return true;
+        }
+        let statements: Vec<String> = body
+            .iter()
+            .map(|statement| {
+                let mut formatter = Formatter::new(&[], &[]);
+                formatter.statement(statement);
+                formatter.output
+            })
+            .collect();
+        let line = self.output.rsplit('\n').next().unwrap_or_default();
+        let length = line.chars().count() + statements.iter().map(String::len).sum::<usize>();
+        if length > MAX_ONE_LINE_FUNCTION
+            || statements
+                .iter()
+                .any(|statement| statement.contains(['\n', CELL]))
+        {
+            return false;
+        }
+        self.write(&format!("{{ {} }}", statements.join("; ")));
+        true
+    }
+
+    /// A group of variable specs, with their names, types and values in columns. As gofmt
+    /// does, a run of specs with values keeps a column for types if one of them has a type,
+    /// and otherwise puts its values where the types would be.
+    fn variable_group(&mut self, specs: &[VariableSpec], close: Position) {
+        let keep_types = keep_type_column(specs);
+        self.write("(");
+        self.line = specs.first().map_or(close.line, |spec| {
+            spec.position_start.line.saturating_sub(1)
+        });
+        self.indent += 1;
+        for (spec, keep_type) in specs.iter().zip(keep_types) {
+            self.start_line(spec.position_start, 1);
+            self.variable_spec(spec, keep_type, 1);
+            self.line = spec.position_end.line;
+            self.trailing_comments(self.line);
+        }
+        self.comments_before(close);
+        self.indent -= 1;
+        self.newline();
+        self.write(")");
+    }
+
+    /// `names type = values`, with `CELL`s between the columns of a group when `columns` is
+    /// one, so a comment after any of them lines up with the others'
+    fn variable_spec(&mut self, spec: &VariableSpec, keep_type: bool, columns: usize) {
+        let separator = if columns > 0 {
+            CELL.to_string()
+        } else {
+            " ".to_string()
+        };
+        self.expression_list(&spec.names, 1);
+        let mut cells = 0;
+        if spec.type_expression.is_some() || keep_type {
+            self.write(&separator);
+            cells += 1;
+        }
+        if let Some(type_expression) = &spec.type_expression {
+            self.type_expression(type_expression);
+        }
+        if !spec.values.is_empty() {
+            self.write(&separator);
+            self.write("= ");
+            self.expression_list(&spec.values, 1);
+            cells += 1;
+        }
+        let has_comment = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.position.line == spec.position_end.line);
+        if columns > 0 && has_comment {
+            for _ in cells..2 {
+                self.write(&CELL.to_string());
+            }
+        }
+    }
+
+    /// Parameters and results, the parameters' `(` being on the source's `open_line`
+    fn signature(&mut self, open_line: usize, parameters: &[Parameter], results: &[Parameter]) {
+        self.parameters("(", open_line, parameters);
+        let open_line = parameters
+            .last()
+            .map_or(open_line, |parameter| parameter.position_end.line);
+        match results {
+            [] => {}
+            [result] if result.names.is_empty() => {
+                self.write(" ");
+                self.parameter(result);
+            }
+            results => self.parameters(" (", open_line, results),
+        }
+    }
+
+    fn parameters(&mut self, open: &str, open_line: usize, parameters: &[Parameter]) {
+        // Without the position of `)`, the list is taken to close after its last parameter
+        let close = parameters
+            .last()
+            .map_or(Position::new(open_line, 0, 0), |parameter| {
+                parameter.position_end
+            });
+        self.list(
+            open,
+            open_line,
+            parameters,
+            close,
+            ")",
+            |formatter, parameter| formatter.parameter(parameter),
+            |parameter| (parameter.position_start, parameter.position_end),
+            false,
+        );
+    }
+
+    fn parameter(&mut self, parameter: &Parameter) {
+        if !parameter.names.is_empty() {
+            self.expression_list(&parameter.names, 1);
+            self.write(" ");
+        }
+        if parameter.variadic {
+            self.write("...");
+        }
+        self.type_expression(&parameter.type_expression);
+    }
+
+    /// `{`, the statements, then `}` at `close`. The block starts on the source's `open_line`.
+    fn block(&mut self, open_line: usize, statements: &[Statement], close: Position) {
+        let outer_close = self.block_close.replace(close);
+        self.write("{");
+        self.line = open_line;
+        self.trailing_comments(open_line);
+        self.indent += 1;
+        let length = self.output.len();
+        self.statements(statements);
+        self.comments_before(close);
+        self.indent -= 1;
+        self.block_close = outer_close;
+        if self.output.len() > length || close.line > open_line || self.after_line_comment {
+            self.newline();
+        }
+        self.write("}");
+        self.line = close.line;
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.start_line(statement.position_start, 1);
+            self.statement(statement);
+            self.line = statement.position_end.line;
+            self.trailing_comments(self.line);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let line = statement.position_start.line;
+        match &statement.kind {
+            StatementKind::Expression(expression) => self.expression_at(expression, 1),
+            StatementKind::Declaration(declaration) => self.declaration(declaration),
+            StatementKind::Assignment {
+                left,
+                right,
+                define,
+            } => {
+                let depth = if left.len() > 1 && right.len() > 1 {
+                    2
+                } else {
+                    1
+                };
+                self.expression_list(left, depth);
+                self.write(if *define { " := " } else { " = " });
+                self.expression_list(right, depth);
+            }
+            StatementKind::IncDec { target, increment } => {
+                self.expression_at(target, 1);
+                self.write(if *increment { "++" } else { "--" });
+            }
+            StatementKind::OpAssign {
+                target,
+                operator,
+                value,
+            } => {
+                self.expression_at(target, 1);
+                self.write(&format!(" {}= ", operator));
+                self.expression_at(value, 1);
+            }
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                self.write("for ");
+                // Without an init or post statement, a loop's semicolons are dropped
+                if init.is_some() || post.is_some() {
+                    if let Some(init) = init {
+                        self.statement(init);
+                    }
+                    self.write("; ");
+                    if let Some(condition) = condition {
+                        self.expression(strip_parentheses(condition));
+                    }
+                    self.write(";");
+                    if let Some(post) = post {
+                        self.write(" ");
+                        self.statement(post);
+                    }
+                    self.write(" ");
+                } else if let Some(condition) = condition {
+                    self.expression(strip_parentheses(condition));
+                    self.write(" ");
+                }
+                self.block(line, body, statement.position_end);
+            }
+            StatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => {
+                self.write("for ");
+                if let Some(key) = key {
+                    self.expression(key);
+                    if let Some(value) = value {
+                        self.write(", ");
+                        self.expression(value);
+                    }
+                    self.write(if *define { " := " } else { " = " });
+                }
+                self.write("range ");
+                self.expression(strip_parentheses(expression));
+                self.write(" ");
+                self.block(line, body, statement.position_end);
+            }
+            StatementKind::Block(body) => self.block(line, body, statement.position_end),
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => {
+                self.write("if ");
+                if let Some(init) = init {
+                    self.statement(init);
+                    self.write("; ");
+                }
+                self.expression(strip_parentheses(condition));
+                self.write(" ");
+                let close = else_branch
+                    .as_ref()
+                    .map_or(statement.position_end, |branch| branch.position_start);
+                self.block(line, body, close);
+                if let Some(else_branch) = else_branch {
+                    self.write(" else ");
+                    self.statement(else_branch);
+                }
+            }
+            StatementKind::Return(results) => {
+                self.write("return");
+                if !results.is_empty() {
+                    self.write(" ");
+                    self.expression_list(results, 1);
+                }
+            }
+            StatementKind::Send { channel, value } => {
+                self.expression_at(channel, 1);
+                self.write(" <- ");
+                self.expression_at(value, 1);
+            }
+            StatementKind::Defer(call) => {
+                self.write("defer ");
+                self.expression(call);
+            }
+            StatementKind::Go(call) => {
+                self.write("go ");
+                self.expression(call);
+            }
+            StatementKind::Select(cases) => {
+                self.write("select {");
+                let outer_close = self.block_close.replace(statement.position_end);
+                self.line = line;
+                self.trailing_comments(line);
+                for (index, case) in cases.iter().enumerate() {
+                    let end = cases
+                        .get(index + 1)
+                        .map_or(statement.position_end, |next| next.position);
+                    self.select_case(case, end);
+                }
+                self.block_close = outer_close;
+                self.newline();
+                self.write("}");
+            }
+            StatementKind::Switch { init, tag, cases } => {
+                self.write("switch ");
+                if let Some(init) = init {
+                    self.statement(init);
+                    self.write("; ");
+                }
+                if let Some(tag) = tag {
+                    self.expression(strip_parentheses(tag));
+                    self.write(" ");
+                }
+                self.write("{");
+                let outer_close = self.block_close.replace(statement.position_end);
+                self.line = line;
+                self.trailing_comments(line);
+                for (index, case) in cases.iter().enumerate() {
+                    let end = cases
+                        .get(index + 1)
+                        .map_or(statement.position_end, |next| next.position);
+                    self.switch_case(case, end);
+                }
+                self.block_close = outer_close;
+                self.newline();
+                self.write("}");
+            }
+            StatementKind::Fallthrough => self.write("fallthrough"),
+            StatementKind::Break => self.write("break"),
+            StatementKind::Continue => self.write("continue"),
+        }
+    }
+
+    /// A case of a select, with the comments before `end`, where the next case or the select's
+    /// `}` is, in its body
+    fn select_case(&mut self, case: &SelectCase, end: Position) {
+        self.start_line(case.position, 1);
+        match &case.communication {
+            Some(communication) => {
+                self.write("case ");
+                self.statement(communication);
+                self.write(":");
+            }
+            None => self.write("default:"),
+        }
+        self.case_body(case.position, &case.body, end);
+    }
+
+    /// A case of a switch, likewise
+    fn switch_case(&mut self, case: &SwitchCase, end: Position) {
+        self.start_line(case.position, 1);
+        match &case.expressions {
+            Some(expressions) => {
+                self.write("case ");
+                self.expression_list(expressions, 1);
+                self.write(":");
+            }
+            None => self.write("default:"),
+        }
+        self.case_body(case.position, &case.body, end);
+    }
+
+    /// The body of a case whose `case` or `default` is at `position`, up to `end`
+    fn case_body(&mut self, position: Position, body: &[Statement], end: Position) {
+        self.line = position.line;
+        self.trailing_comments(self.line);
+        self.indent += 1;
+        self.statements(body);
+        self.comments_before(end);
+        self.indent -= 1;
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        self.expression_at(expression, 1);
+    }
+
+    /// Write `expression` at `depth`, which grows inside lists of arguments and indexes,
+    /// where gofmt drops the spaces around more operators
+    fn expression_at(&mut self, expression: &Expression, depth: usize) {
+        self.operand(expression, 0, depth);
+    }
+
+    /// Write `expression` where it needs parentheses if it binds less tightly than
+    /// `precedence`
+    fn operand(&mut self, expression: &Expression, precedence: u8, depth: usize) {
+        self.comments_before(expression.position_start);
+        match &expression.kind {
+            ExpressionKind::Binary { .. } => {
+                let cutoff = cutoff(expression, depth);
+                self.binary(expression, precedence, cutoff, depth.max(1));
+            }
+            ExpressionKind::Identifier(name) => self.write(name),
+            ExpressionKind::BlankIdentifier => self.write("_"),
+            ExpressionKind::IntegerLiteral(value)
+            | ExpressionKind::FloatLiteral(value)
+            | ExpressionKind::RuneLiteral(value)
+            | ExpressionKind::StringLiteral(value) => self.write(value),
+            ExpressionKind::FunctionCall {
+                name,
+                arguments,
+                spread,
+            } => {
+                let depth = if arguments.len() > 1 {
+                    depth + 1
+                } else {
+                    depth
+                };
+                self.operand(name, HIGHEST_PRECEDENCE, depth);
+                self.list(
+                    "(",
+                    name.position_end.line,
+                    arguments,
+                    expression.position_end,
+                    ")",
+                    |formatter, argument| formatter.expression_at(argument, depth),
+                    |argument| (argument.position_start, argument.position_end),
+                    *spread,
+                );
+            }
+            ExpressionKind::FieldAccess { object, field, .. } => {
+                // `1.x` would lex as the float `1.` followed by `x`
+                if matches!(object.kind, ExpressionKind::IntegerLiteral(_)) {
+                    self.write("(");
+                    self.expression(object);
+                    self.write(")");
+                } else {
+                    self.operand(object, HIGHEST_PRECEDENCE, depth);
+                }
+                self.write(".");
+                self.write(field);
+            }
+            ExpressionKind::Index { object, index } => {
+                self.operand(object, HIGHEST_PRECEDENCE, 1);
+                self.write("[");
+                self.expression_at(index, depth + 1);
+                self.write("]");
+            }
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                self.operand(object, HIGHEST_PRECEDENCE, 1);
+                self.write("[");
+                let indexes = [low, high, max];
+                let given = indexes.iter().filter(|index| index.is_some()).count();
+                let has_binary = indexes
+                    .iter()
+                    .flat_map(|index| index.as_deref())
+                    .any(|index| matches!(index.kind, ExpressionKind::Binary { .. }));
+                // `s[a+1 : b]` has spaces around its colons when an index is binary
+                let spaced = depth <= 1 && given > 1 && has_binary;
+                let count = if max.is_some() { 3 } else { 2 };
+                for (position, index) in indexes.iter().take(count).enumerate() {
+                    if position > 0 {
+                        if spaced && indexes[position - 1].is_some() {
+                            self.write(" ");
+                        }
+                        self.write(":");
+                        if spaced && index.is_some() {
+                            self.write(" ");
+                        }
+                    }
+                    if let Some(index) = index {
+                        self.expression_at(index, depth + 1);
+                    }
+                }
+                self.write("]");
+            }
+            ExpressionKind::Parenthesized(inner) => {
+                self.write("(");
+                // Parentheses undo one level of depth
+                self.expression_at(inner, depth.saturating_sub(1).max(1));
+                self.write(")");
+            }
+            ExpressionKind::Conversion {
+                target,
+                expression: converted,
+            } => {
+                // `*T(x)` and `<-chan T(x)` would apply the operator to a conversion
+                let parenthesized = matches!(
+                    target.kind,
+                    TypeExpressionKind::Pointer(_)
+                        | TypeExpressionKind::Channel {
+                            direction: ChannelDirection::Receive,
+                            ..
+                        }
+                );
+                if parenthesized {
+                    self.write("(");
+                }
+                self.type_expression(target);
+                if parenthesized {
+                    self.write(")");
+                }
+                self.write("(");
+                self.expression_at(converted, depth);
+                self.write(")");
+            }
+            ExpressionKind::Receive(channel) => {
+                self.unary("<-", channel, precedence, depth);
+            }
+            ExpressionKind::Unary { operator, operand } => {
+                self.unary(&operator.to_string(), operand, precedence, depth);
+            }
+            ExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            } => {
+                let open_line = match type_expression {
+                    Some(type_expression) => {
+                        self.type_expression(type_expression);
+                        type_expression.position_end.line
+                    }
+                    None => expression.position_start.line,
+                };
+                let multiline = elements.first().is_some_and(|element| {
+                    element_span(element).0.line > open_line
+                        || expression.position_end.line > element_span(element).1.line
+                });
+                self.list(
+                    "{",
+                    open_line,
+                    elements,
+                    expression.position_end,
+                    "}",
+                    |formatter, element| formatter.element(element, multiline),
+                    element_span,
+                    false,
+                );
+            }
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            } => {
+                self.write("func");
+                let (start, end) = (expression.position_start, expression.position_end);
+                self.function(start, end, parameters, results, body);
+            }
+            ExpressionKind::Type(type_expression) => self.type_expression(type_expression),
+        }
+    }
+
+    fn unary(&mut self, operator: &str, operand: &Expression, precedence: u8, depth: usize) {
+        if UNARY_PRECEDENCE < precedence {
+            self.write("(");
+            self.unary(operator, operand, 0, depth);
+            self.write(")");
+            return;
+        }
+        self.write(operator);
+        // `- -x` and `& &x` would lex as `--x` and `&&x`
+        if let ExpressionKind::Unary {
+            operator: inner, ..
+        } = &operand.kind
+            && matches!(
+                (operator, inner),
+                ("-", UnaryOperator::Minus)
+                    | ("+", UnaryOperator::Plus)
+                    | ("&", UnaryOperator::Address)
+                    | ("&", UnaryOperator::Complement)
+            )
+        {
+            self.write(" ");
+        }
+        self.operand(operand, UNARY_PRECEDENCE, depth);
+    }
+
+    /// Write a binary expression as gofmt does, with spaces around its operator only if it
+    /// binds less tightly than `cutoff`: `a + b*c`
+    fn binary(&mut self, expression: &Expression, precedence: u8, cutoff: u8, depth: usize) {
+        let ExpressionKind::Binary {
+            left,
+            operator,
+            right,
+        } = &expression.kind
+        else {
+            return self.operand(expression, precedence, depth);
+        };
+        let operator_precedence = operator.precedence();
+        if operator_precedence < precedence {
+            // Only a tree made rather than parsed can need parentheses the source didn't have
+            self.write("(");
+            self.expression_at(expression, depth.saturating_sub(1).max(1));
+            self.write(")");
+            return;
+        }
+        let spaced = operator_precedence < cutoff;
+        let left_depth = match left.kind {
+            ExpressionKind::Binary { operator, .. }
+                if operator.precedence() == operator_precedence =>
+            {
+                depth
+            }
+            _ => depth + 1,
+        };
+        self.binary_operand(left, operator_precedence, cutoff, left_depth);
+        // A comment right after the left operand stays before the operator: `a /* b */ + c`
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if !same_token(self.code_before[self.next_comment], left.position_end) {
+                break;
+            }
+            self.comment(comment);
+        }
+        if spaced {
+            self.write(" ");
+        }
+        self.write(operator.as_str());
+        // A line break after the operator is kept, indenting what follows
+        let broken =
+            right.position_start.line > left.position_end.line && left.position_end.line > 0;
+        if broken {
+            self.indent += 1;
+            self.line = left.position_end.line;
+            self.linebreak(right.position_start.line, 1);
+        } else if spaced {
+            self.write(" ");
+        }
+        self.binary_operand(right, operator_precedence + 1, cutoff, depth + 1);
+        if broken {
+            self.indent -= 1;
+        }
+    }
+
+    /// An operand of a binary expression, which shares its cutoff if it's binary too
+    fn binary_operand(&mut self, operand: &Expression, precedence: u8, cutoff: u8, depth: usize) {
+        if let ExpressionKind::Binary { .. } = operand.kind {
+            self.comments_before(operand.position_start);
+            self.binary(operand, precedence, cutoff, depth);
+        } else {
+            self.operand(operand, precedence, depth);
+        }
+    }
+
+    fn element(&mut self, element: &KeyedElement, aligned: bool) {
+        if let Some(key) = &element.key {
+            self.expression(key);
+            // The values of a literal written a line per element line up
+            self.write(":");
+            self.write(&if aligned { CELL } else { ' ' }.to_string());
+        }
+        self.expression(&element.value);
+    }
+
+    /// Write `items` between `open` and `close`, separated by commas. If the source has line
+    /// breaks in the list, each item starts a line if it did in the source, indented, and the
+    /// last is followed by a comma if `close` is on a line of its own.
+    #[allow(clippy::too_many_arguments)]
+    fn list<T>(
+        &mut self,
+        open: &str,
+        open_line: usize,
+        items: &[T],
+        close_position: Position,
+        close: &str,
+        mut item: impl FnMut(&mut Self, &T),
+        span: impl Fn(&T) -> (Position, Position),
+        spread: bool,
+    ) {
+        self.write(open);
+        let (Some(first), Some(last)) = (items.first(), items.last()) else {
+            self.comments_before(close_position);
+            self.write(close);
+            return;
+        };
+        let breaks_after_open = span(first).0.line > open_line;
+        let breaks_before_close = close_position.line > span(last).1.line;
+        let breaks_between = items
+            .windows(2)
+            .any(|pair| span(&pair[1]).0.line > span(&pair[0]).1.line);
+        if !breaks_after_open && !breaks_before_close && !breaks_between {
+            for (index, element) in items.iter().enumerate() {
+                if index > 0 {
+                    self.write(", ");
+                }
+                item(self, element);
+            }
+            if spread {
+                self.write("...");
+            }
+            self.comments_before(close_position);
+            self.write(close);
+            return;
+        }
+
+        self.indent += 1;
+        self.line = open_line;
+        if breaks_after_open {
+            self.trailing_comments(open_line);
+        }
+        let mut previous_end: Option<Position> = None;
+        for (index, element) in items.iter().enumerate() {
+            let (start, end) = span(element);
+            match previous_end {
+                Some(previous_end) if start.line == previous_end.line => {
+                    self.write(" ");
+                    self.comments_before(start);
+                }
+                _ if index == 0 && !breaks_after_open => self.comments_before(start),
+                _ => self.start_line(start, 1),
+            }
+            item(self, element);
+            if index + 1 < items.len() || breaks_before_close {
+                if index + 1 == items.len() && spread {
+                    self.write("...");
+                }
+                self.write(",");
+            }
+            previous_end = Some(end);
+            let next_on_line = items
+                .get(index + 1)
+                .is_some_and(|next| span(next).0.line == end.line);
+            if !next_on_line {
+                self.line = end.line;
+                self.trailing_comments(end.line);
+            }
+        }
+        if spread && !breaks_before_close {
+            self.write("...");
+        }
+        self.comments_before(close_position);
+        self.indent -= 1;
+        if breaks_before_close {
+            self.newline();
+        }
+        self.write(close);
+        self.line = close_position.line;
+    }
+
+    fn expression_list(&mut self, expressions: &[Expression], depth: usize) {
+        for (index, expression) in expressions.iter().enumerate() {
+            if index > 0 {
+                self.write(", ");
+            }
+            self.expression_at(expression, depth);
+        }
+    }
+
+    fn type_expression(&mut self, type_expression: &TypeExpression) {
+        match &type_expression.kind {
+            TypeExpressionKind::Name(name) => self.write(name),
+            TypeExpressionKind::Qualified { package, name } => {
+                self.write(&format!("{}.{}", package, name));
+            }
+            TypeExpressionKind::Pointer(element) => {
+                self.write("*");
+                self.type_expression(element);
+            }
+            TypeExpressionKind::Slice(element) => {
+                self.write("[]");
+                self.type_expression(element);
+            }
+            TypeExpressionKind::Array { length, element } => {
+                self.write("[");
+                self.expression(length);
+                self.write("]");
+                self.type_expression(element);
+            }
+            TypeExpressionKind::Map { key, value } => {
+                self.write("map[");
+                self.type_expression(key);
+                self.write("]");
+                self.type_expression(value);
+            }
+            TypeExpressionKind::Channel { direction, element } => {
+                match direction {
+                    ChannelDirection::Both => self.write("chan "),
+                    ChannelDirection::Send => self.write("chan<- "),
+                    ChannelDirection::Receive => self.write("<-chan "),
+                }
+                // `chan <-chan T` would read as `chan<- (chan T)`
+                let parenthesized =
+                    *direction == ChannelDirection::Both && element.is_receive_channel();
+                if parenthesized {
+                    self.write("(");
+                }
+                self.type_expression(element);
+                if parenthesized {
+                    self.write(")");
+                }
+            }
+            TypeExpressionKind::Struct(fields) => self.members(
+                "struct",
+                type_expression,
+                fields,
+                |formatter, field, cells| formatter.field(field, cells),
+            ),
+            TypeExpressionKind::Interface(methods) => self.members(
+                "interface",
+                type_expression,
+                methods,
+                |formatter, method, _| {
+                    formatter.write(&method.name);
+                    let line = method.position_start.line;
+                    formatter.signature(line, &method.parameters, &method.results);
+                },
+            ),
+            TypeExpressionKind::Function {
+                parameters,
+                results,
+            } => {
+                self.write("func");
+                self.signature(type_expression.position_start.line, parameters, results);
+            }
+        }
+    }
+
+    /// A struct's fields or an interface's methods, on one line as `struct{ a int }` if they
+    /// were in the source, and otherwise a line each
+    fn members<T: Member>(
+        &mut self,
+        keyword: &str,
+        type_expression: &TypeExpression,
+        members: &[T],
+        mut member: impl FnMut(&mut Self, &T, bool),
+    ) {
+        let open_line = type_expression.position_start.line;
+        let close = type_expression.position_end;
+        let has_comments = self.comment_before(close).is_some();
+        if members.is_empty() && !has_comments {
+            return self.write(&format!("{}{{}}", keyword));
+        }
+        if close.line == open_line && !has_comments {
+            self.write(&format!("{}{{ ", keyword));
+            for (index, item) in members.iter().enumerate() {
+                if index > 0 {
+                    self.write("; ");
+                }
+                member(self, item, false);
+            }
+            return self.write(" }");
+        }
+        self.write(&format!("{} {{", keyword));
+        self.line = open_line;
+        self.trailing_comments(open_line);
+        self.indent += 1;
+        for item in members {
+            let (start, end) = item.span();
+            self.start_line(start, 1);
+            member(self, item, members.len() > 1);
+            self.line = end.line;
+            self.trailing_comments(end.line);
+        }
+        self.comments_before(close);
+        self.indent -= 1;
+        self.newline();
+        self.write("}");
+        self.line = close.line;
+    }
+
+    /// `names type tag`, with the columns in cells if `cells`
+    fn field(&mut self, field: &StructField, cells: bool) {
+        let separator = if cells {
+            CELL.to_string()
+        } else {
+            " ".to_string()
+        };
+        if !field.names.is_empty() {
+            self.expression_list(&field.names, 1);
+            self.write(&separator);
+        }
+        self.type_expression(&field.type_expression);
+        if let Some(tag) = &field.tag {
+            self.write(&separator);
+            self.write(tag);
+        }
+    }
+}
+
+/// A struct field or interface method, which are written a line each
+trait Member {
+    fn span(&self) -> (Position, Position);
+}
+
+impl Member for StructField {
+    fn span(&self) -> (Position, Position) {
+        (self.position_start, self.position_end)
+    }
+}
+
+impl Member for InterfaceMethod {
+    fn span(&self) -> (Position, Position) {
+        (self.position_start, self.position_end)
+    }
+}
+
+fn element_span(element: &KeyedElement) -> (Position, Position) {
+    let start = element
+        .key
+        .as_ref()
+        .unwrap_or(&element.value)
+        .position_start;
+    (start, element.value.position_end)
+}
+
+/// The gofmt rule for whether a run of specs keeps its type column, for each spec. A run of
+/// specs with values keeps it if any of them has a type.
+fn keep_type_column(specs: &[VariableSpec]) -> Vec<bool> {
+    let mut keep = vec![false; specs.len()];
+    let mut run_start = None;
+    let mut has_type = false;
+    for (index, spec) in specs.iter().enumerate() {
+        if !spec.values.is_empty() {
+            if run_start.is_none() {
+                run_start = Some(index);
+                has_type = false;
+            }
+        } else if let Some(start) = run_start.take() {
+            keep[start..index].fill(has_type);
+        }
+        if spec.type_expression.is_some() {
+            has_type = true;
+        }
+    }
+    if let Some(start) = run_start {
+        keep[start..].fill(has_type);
+    }
+    keep
+}
+
+/// The precedence below which the operators of a binary expression at `depth` get spaces,
+/// as gofmt decides it: `x = a + b*c` but `f(a+b, c)`
+fn cutoff(expression: &Expression, depth: usize) -> u8 {
+    let (has4, has5, max_problem) = walk_binary(expression);
+    if max_problem > 0 {
+        return max_problem + 1;
+    }
+    match (has4 && has5, depth == 1) {
+        (true, true) => 5,
+        (true, false) => 4,
+        (false, true) => 6,
+        (false, false) => 4,
+    }
+}
+
+/// Whether a chain of binary operators has additive and multiplicative ones, and the highest
+/// precedence whose operators need spaces to stay apart from what follows, like `a / *p`
+fn walk_binary(expression: &Expression) -> (bool, bool, u8) {
+    let ExpressionKind::Binary {
+        left,
+        operator,
+        right,
+    } = &expression.kind
+    else {
+        return (false, false, 0);
+    };
+    let precedence = operator.precedence();
+    let mut has4 = precedence == 4;
+    let mut has5 = precedence == 5;
+    let mut max_problem = 0;
+    if let ExpressionKind::Binary {
+        operator: left_operator,
+        ..
+    } = &left.kind
+        && left_operator.precedence() >= precedence
+    {
+        let (left4, left5, problem) = walk_binary(left);
+        has4 |= left4;
+        has5 |= left5;
+        max_problem = max_problem.max(problem);
+    }
+    match &right.kind {
+        ExpressionKind::Binary {
+            operator: right_operator,
+            ..
+        } if right_operator.precedence() > precedence => {
+            let (right4, right5, problem) = walk_binary(right);
+            has4 |= right4;
+            has5 |= right5;
+            max_problem = max_problem.max(problem);
+        }
+        ExpressionKind::Unary {
+            operator: unary, ..
+        } => match (operator, unary) {
+            (Operator::Slash, UnaryOperator::Dereference)
+            | (Operator::Ampersand, UnaryOperator::Address)
+            | (Operator::Ampersand, UnaryOperator::Complement) => max_problem = 5,
+            (Operator::Plus, UnaryOperator::Plus) | (Operator::Minus, UnaryOperator::Minus) => {
+                max_problem = max_problem.max(4)
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+    (has4, has5, max_problem)
+}
+
+/// The expression inside any parentheses around it, which conditions and range expressions
+/// don't need, unless it has a composite literal that'd be read as the statement's block
+pub(crate) fn strip_parentheses(expression: &Expression) -> &Expression {
+    let mut stripped = expression;
+    while let ExpressionKind::Parenthesized(inner) = &stripped.kind {
+        if has_composite_literal(inner) {
+            break;
+        }
+        stripped = inner;
+    }
+    stripped
+}
+
+fn has_composite_literal(expression: &Expression) -> bool {
+    match &expression.kind {
+        ExpressionKind::CompositeLiteral {
+            type_expression, ..
+        } => type_expression.is_some(),
+        ExpressionKind::Binary { left, right, .. } => {
+            has_composite_literal(left) || has_composite_literal(right)
+        }
+        ExpressionKind::FunctionCall {
+            name, arguments, ..
+        } => has_composite_literal(name) || arguments.iter().any(has_composite_literal),
+        ExpressionKind::FieldAccess { object, .. } => has_composite_literal(object),
+        ExpressionKind::Index { object, index } => {
+            has_composite_literal(object) || has_composite_literal(index)
+        }
+        ExpressionKind::Slice { object, .. } => has_composite_literal(object),
+        ExpressionKind::Conversion { expression, .. }
+        | ExpressionKind::Receive(expression)
+        | ExpressionKind::Unary {
+            operand: expression,
+            ..
+        } => has_composite_literal(expression),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            format_source(&formatted).unwrap(),
+            formatted,
+            "not idempotent"
+        );
+        formatted
+    }
+
+    #[test]
+    fn format_layout() {
+        let source = "package main\nimport (\n\"fmt\"\nstr \"strings\"\n)\n\n\n\nfunc add(a,b int) int {\n    return a+b\n}\nfunc main() {\nx:=add(1,2)\n\n\n  if (x>2) {\n  fmt.Println(str.ToUpper(\"big\"))\n  } else {\n  fmt.Println(x)\n}\nfor i := range 3 {\n}\n}\n";
+        assert_eq!(
+            format(source),
+            "package main\n\nimport (\n\t\"fmt\"\n\tstr \"strings\"\n)\n\nfunc add(a, b int) int {\n\treturn a + b\n}\nfunc main() {\n\tx := add(1, 2)\n\n\tif x > 2 {\n\t\tfmt.Println(str.ToUpper(\"big\"))\n\t} else {\n\t\tfmt.Println(x)\n\t}\n\tfor i := range 3 {\n\t}\n}\n"
+        );
+    }
+
+    #[test]
+    fn format_switches_and_loops() {
+        let source = "package main\n\nconst (\nA = iota\nB\n)\n\nfunc main() {\nfor i:=0;i<3;i++ {\nx+=i\n}\nfor ;(x<10); {\nx++\n}\nswitch y:=x;(y) {\ncase 1,2: // small\nf()\nfallthrough\ndefault:\ng()\n}\n}\n";
+        assert_eq!(
+            format(source),
+            "package main\n\nconst (\n\tA = iota\n\tB\n)\n\nfunc main() {\n\tfor i := 0; i < 3; i++ {\n\t\tx += i\n\t}\n\tfor x < 10 {\n\t\tx++\n\t}\n\tswitch y := x; y {\n\tcase 1, 2: // small\n\t\tf()\n\t\tfallthrough\n\tdefault:\n\t\tg()\n\t}\n}\n"
+        );
+    }
+
+    #[test]
+    fn format_operators() {
+        let source = "package main\n\nfunc main() {\n\tx := a+b*c\n\ty := a * b\n\tf(a + b, c*d + e)\n\tg(a + b)\n\tz := (a+b)*c\n\tw := a/ *p\n\ts := xs[i+1 : j]\n\tok := a<b && c==d\n\tn := - -x\n}\n";
+        assert_eq!(
+            format(source),
+            "package main\n\nfunc main() {\n\tx := a + b*c\n\ty := a * b\n\tf(a+b, c*d+e)\n\tg(a + b)\n\tz := (a + b) * c\n\tw := a / *p\n\ts := xs[i+1 : j]\n\tok := a < b && c == d\n\tn := - -x\n}\n"
+        );
+    }
+
+    #[test]
+    fn format_comments() {
+        let source = "// Package main is an example.\npackage main // the package\n\n// add adds.\nfunc add(a, b int) int {\n\treturn a + b // the sum\n}\n\nfunc main() { // start\n\t// own line\n\tx := 1 // one\n\tlonger := 2 // two\n\n\tf(x, /* inline */ longer)\n\t/* block */ g()\n\t// before the end\n}\n\n// the end\n";
+        assert_eq!(
+            format(source),
+            "// Package main is an example.\npackage main // the package\n\n// add adds.\nfunc add(a, b int) int {\n\treturn a + b // the sum\n}\n\nfunc main() { // start\n\t// own line\n\tx := 1      // one\n\tlonger := 2 // two\n\n\tf(x, /* inline */ longer)\n\t/* block */ g()\n\t// before the end\n}\n\n// the end\n"
+        );
+    }
+
+    #[test]
+    fn format_comments_after_their_line_last_token() {
+        let source = "package main\n\nfunc scale(p Point, f int) int {\n\tif p.X>0 { return p.X*f }  // trailing\n\n\tfor i := 0; i < 3; i++ { } // loop\n\n\tswitch f { case 1: g() } // switch\n\treturn p.X + p.Y /* mid */ + 1\n}\n";
+        assert_eq!(
+            format(source),
+            "package main\n\nfunc scale(p Point, f int) int {\n\tif p.X > 0 {\n\t\treturn p.X * f\n\t} // trailing\n\n\tfor i := 0; i < 3; i++ {} // loop\n\n\tswitch f {\n\tcase 1:\n\t\tg()\n\t} // switch\n\treturn p.X + p.Y /* mid */ + 1\n}\n"
+        );
+    }
+
+    #[test]
+    fn format_alignment() {
+        let source = "package main\n\ntype Point struct {\nX int \"x\" // across\nLongName string\n}\n\ntype Small struct{ a, b int }\n\nvar (\na = 1\nlonger int = 2 // two\nc string\n)\n\nvar m = map[string]int{\n\"a\": 1,\n\"bbb\": 2,\n}\n\nfunc main() {\n\tf(1,\n\t\t2)\n\tg(\n\t\t1, 2,\n\t\t3,\n\t)\n}\n";
+        assert_eq!(
+            format(source),
+            "package main\n\ntype Point struct {\n\tX        int \"x\" // across\n\tLongName string\n}\n\ntype Small struct{ a, b int }\n\nvar (\n\ta          = 1\n\tlonger int = 2 // two\n\tc      string\n)\n\nvar m = map[string]int{\n\t\"a\":   1,\n\t\"bbb\": 2,\n}\n\nfunc main() {\n\tf(1,\n\t\t2)\n\tg(\n\t\t1, 2,\n\t\t3,\n\t)\n}\n"
+        );
+    }
+
+    #[test]
+    fn format_one_line_functions() {
+        let source =
+            "package main\n\nfunc zero() int { return 0 }\n\nfunc empty() {}\n\nfunc main() {\n}\n";
+        assert_eq!(format(source), source);
+    }
+
+    #[test]
+    fn format_function_literals() {
+        let source = "package main\n\nfunc main() {\n\tdefer func() { recover() }()\n\tvar f func(int) (string, error)\n\tgo func(n int) {\n\t\tf(n)\n\t}(1)\n}\n";
+        assert_eq!(format(source), source);
+    }
+
+    #[test]
+    fn format_sorts_imports() {
+        let source = "package main\n\nimport (\n\t\"strings\" // s\n\t// fmt's doc\n\t\"fmt\"\n\n\t\"os\"\n\t\"bufio\"\n\t\"os\"\n)\nimport \"errors\"\n";
+        assert_eq!(
+            format(source),
+            "package main\n\nimport (\n\t// fmt's doc\n\t\"fmt\"\n\t\"strings\" // s\n\n\t\"bufio\"\n\t\"os\"\n)\nimport \"errors\"\n"
+        );
+    }
+
+    #[test]
+    fn format_groups_imports() {
+        let source = "package main\n\nimport (\n\t\"example.com/app/util\"\n\t\"fmt\"\n\t\"github.com/pkg/errors\" // wrapped\n\t\"os\"\n)\n\nimport \"strings\"\n\nfunc main() { fmt.Println(util.X, errors.New(strings.ToUpper(\"x\"))) }\n";
+        let config = FormatterConfig::goimports()
+            .with_local_prefix("example.com/app".to_string())
+            .with_unused_imports_removed();
+        let formatted = format_source_with_config(source, &config).unwrap();
+        assert_eq!(
+            formatted,
+            "package main\n\nimport (\n\t\"fmt\"\n\t\"strings\"\n\n\t\"github.com/pkg/errors\" // wrapped\n\n\t\"example.com/app/util\"\n)\n\nfunc main() { fmt.Println(util.X, errors.New(strings.ToUpper(\"x\"))) }\n"
+        );
+        assert_eq!(
+            format_source_with_config(&formatted, &config).unwrap(),
+            formatted
+        );
+
+        // Removing every import removes the declaration
+        let source = "package main\n\nimport \"os\"\n\nfunc main() {}\n";
+        let config = FormatterConfig::default().with_unused_imports_removed();
+        assert_eq!(
+            format_source_with_config(source, &config).unwrap(),
+            "package main\n\nfunc main() {}\n"
+        );
+    }
+
+    #[test]
+    fn format_with_spaces() {
+        let source = "package main\n\ntype T struct {\nA int // a\nLonger string\n}\n\nfunc main() {\n/* kept\n\tas written */\nif true {\nf()\n}\n}\n";
+        let config = FormatterConfig::default().with_indent_spaces(4);
+        let formatted = format_source_with_config(source, &config).unwrap();
+        assert_eq!(
+            formatted,
+            "package main\n\ntype T struct {\n    A      int // a\n    Longer string\n}\n\nfunc main() {\n    /* kept\n\tas written */\n    if true {\n        f()\n    }\n}\n"
+        );
+        assert_eq!(
+            format_source_with_config(&formatted, &config).unwrap(),
+            formatted
+        );
+    }
+
+    #[test]
+    fn format_errors() {
+        assert!(format_source("package main\n\nfunc main() {\n\tx :=\n}\n").is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::ast::ast::{File, ImportSpec};
+use crate::format::config::FormatterConfig;
+use crate::lexer::token::Token;
+use crate::semantic::{resolver::resolve, scope::SymbolKind};
+
+/// An import spec with the comments on the lines before it and after it on its line, which
+/// move with it when the imports are sorted
+#[derive(Debug)]
+pub(crate) struct ImportLine<'a> {
+    pub spec: &'a ImportSpec,
+    pub leading: Vec<&'a Token>,
+    pub trailing: Vec<&'a Token>,
+}
+
+impl ImportLine<'_> {
+    fn first_line(&self) -> usize {
+        self.leading
+            .first()
+            .map_or(self.spec.position_start.line, |comment| {
+                comment.position.line
+            })
+    }
+
+    fn last_line(&self) -> usize {
+        self.trailing
+            .last()
+            .map_or(self.spec.position_end.line, |comment| comment.position.line)
+    }
+}
+
+/// Sort the imports of a declaration as gofmt does: each run of lines without a blank line
+/// between them is sorted by path then name on its own, and the runs keep their order. A spec
+/// repeated in a run is kept once.
+pub(crate) fn sort_runs(lines: Vec<ImportLine<'_>>) -> Vec<Vec<ImportLine<'_>>> {
+    let mut runs: Vec<Vec<ImportLine>> = Vec::new();
+    let mut previous_line = None;
+    for line in lines {
+        let starts_run = previous_line.is_none_or(|previous| line.first_line() > previous + 1);
+        previous_line = Some(line.last_line());
+        match runs.last_mut() {
+            Some(run) if !starts_run => run.push(line),
+            _ => runs.push(vec![line]),
+        }
+    }
+    for run in &mut runs {
+        sort(run);
+    }
+    runs
+}
+
+/// Sort imports as goimports groups them: standard library packages first, then other
+/// packages, then those under `config`'s local prefix, each group sorted
+pub(crate) fn group<'a>(
+    lines: Vec<ImportLine<'a>>,
+    config: &FormatterConfig,
+) -> Vec<Vec<ImportLine<'a>>> {
+    let mut groups: [Vec<ImportLine>; 3] = Default::default();
+    for line in lines {
+        let path = line.spec.unquoted_path();
+        let group = if config
+            .local_prefix()
+            .is_some_and(|prefix| is_under(path, prefix))
+        {
+            2
+        } else if is_standard(path) {
+            0
+        } else {
+            1
+        };
+        groups[group].push(line);
+    }
+    groups
+        .into_iter()
+        .filter(|group| !group.is_empty())
+        .map(|mut group| {
+            sort(&mut group);
+            group
+        })
+        .collect()
+}
+
+fn sort(lines: &mut Vec<ImportLine<'_>>) {
+    lines.sort_by(|a, b| {
+        let key = |line: &ImportLine| {
+            (
+                line.spec.unquoted_path().to_string(),
+                line.spec.name.clone(),
+            )
+        };
+        key(a).cmp(&key(b))
+    });
+    lines.dedup_by(|line, previous| {
+        line.spec.name == previous.spec.name
+            && line.spec.path == previous.spec.path
+            && line.leading.is_empty()
+            && line.trailing.is_empty()
+    });
+}
+
+/// Whether `path` is a standard library package's, which as the go command decides, is when
+/// its first element has no dot, unlike a domain like `github.com`
+fn is_standard(path: &str) -> bool {
+    let first = path.split('/').next().unwrap_or(path);
+    !first.contains('.')
+}
+
+fn is_under(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The imports of `file` that none of its names refer to. Imports named `_` or `.` are never
+/// unused, since what they're used for can't be seen.
+pub fn unused_imports(file: &File) -> Vec<&ImportSpec> {
+    let (symbols, _) = resolve(file);
+    file.imports
+        .iter()
+        .filter(|import| !matches!(import.name.as_deref(), Some("_" | ".")))
+        .filter(|import| {
+            symbols.def_at(import.position_start).is_some_and(|symbol| {
+                symbols.symbol(symbol).kind == SymbolKind::Package
+                    && symbols.uses_of(symbol).next().is_none()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    #[test]
+    fn find_unused_imports() {
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\t\"os\"\n\t_ \"embed\"\n\tstr \"strings\"\n)\n\nfunc main() {\n\tfmt.Println(str.ToUpper(\"x\"))\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let unused: Vec<&str> = unused_imports(&file)
+            .iter()
+            .map(|import| import.path.as_str())
+            .collect();
+        assert_eq!(unused, ["\"os\""]);
+    }
+
+    #[test]
+    fn standard_and_local_paths() {
+        assert!(is_standard("fmt"));
+        assert!(is_standard("encoding/json"));
+        assert!(!is_standard("github.com/user/repo"));
+        assert!(is_under("example.com/app/util", "example.com/app"));
+        assert!(is_under("example.com/app", "example.com/app/"));
+        assert!(!is_under("example.com/application", "example.com/app"));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::lexer::{lexer::Lexer, token::Token, token_type::TokenKind};
+
+/// `source` on one line with as little whitespace as still lexes to the same tokens: a
+/// newline becomes the `;` Go inserts for it (https://go.dev/ref/spec#Semicolons), or goes
+/// if there's none, and tokens are only kept apart by a space where they'd otherwise run
+/// together, like `func main` or `a - -b`. Comments are dropped.
+pub fn minify(source: &str) -> String {
+    let mut lexer = Lexer::new(source);
+    let mut output = String::new();
+    let mut previous: Option<Token> = None;
+    loop {
+        let token = lexer.next_token();
+        match token.kind {
+            Some(TokenKind::EOF) | None => break,
+            Some(TokenKind::SingleLineComment) => continue,
+            Some(TokenKind::BlockComment) if !token.value.contains('\n') => continue,
+            // A comment spanning lines counts as a newline
+            Some(TokenKind::Newline | TokenKind::BlockComment) => {
+                if previous
+                    .as_ref()
+                    .is_some_and(Token::should_insert_semicolon)
+                {
+                    output.push(';');
+                    previous = Some(Token::new_with_kind(
+                        TokenKind::Semicolon,
+                        ";",
+                        token.position,
+                    ));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        if previous
+            .as_ref()
+            .is_some_and(|previous| runs_together(previous, &token))
+        {
+            output.push(' ');
+        }
+        output.push_str(&token.value);
+        previous = Some(token);
+    }
+    // Go inserts the last semicolon before the end of the file too
+    if previous.is_some_and(|previous| previous.should_insert_semicolon()) {
+        output.push(';');
+    }
+    output.push('\n');
+    output
+}
+
+/// Whether `next` written right after `previous` would lex as something else, like `x` and
+/// `y` as `xy` or `<` and `-` as `<-`
+fn runs_together(previous: &Token, next: &Token) -> bool {
+    let joined = format!("{}{}", previous.value, next.value);
+    Lexer::new(&joined).next_token().value != previous.value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minify_source() {
+        let source = "package main
+
+import (
+\t\"fmt\"
+)
+
+// main prints.
+func main() {
+\tx := 1 // one
+\tif x < -x {
+\t\tfmt.Println(x - -1)
+\t} else {
+\t\treturn
+\t}
+\tys := []int{
+\t\t1,
+\t\t2,
+\t}
+\t_ = ys
+}
+";
+        assert_eq!(
+            minify(source),
+            "package main;import(\"fmt\";);func main(){x:=1;if x< -x{fmt.Println(x- -1);}else{return;};ys:=[]int{1,2,};_=ys;};\n"
+        );
+    }
+}
@@ -0,0 +1,7 @@
+pub mod align;
+pub mod config;
+pub mod diff;
+pub mod formatter;
+pub mod imports;
+pub mod minify;
+pub mod selftest;
@@ -0,0 +1,199 @@
+//! A check that the formatter is safe to run over a corpus of Go files: each is formatted
+//! twice, and the second formatting must change nothing, the formatted source must parse to
+//! the tree the original did, and every comment must survive.
+
+use std::path::{Path, PathBuf};
+
+use crate::ast::{
+    ast::File,
+    declaration::DeclarationKind,
+    statement::{Statement, StatementKind},
+};
+use crate::format::{
+    diff::unified_diff,
+    formatter::{format_file, strip_parentheses},
+};
+use crate::lexer::{lexer::Lexer, token_type::TokenKind};
+use crate::parser::parser::Parser;
+use crate::primitives::{
+    diagnostic::Diagnostic,
+    errors::format::{SelftestError, SelftestErrorKind},
+};
+
+/// Format `source` twice, checking the formatting is stable and keeps its tree and comments
+pub fn selftest_source(source: &str) -> Result<(), SelftestErrorKind> {
+    let original = parse(source).map_err(SelftestErrorKind::Unparsable)?;
+    let formatted = format_file(&original, source);
+    let reparsed = parse(&formatted).map_err(SelftestErrorKind::Reparse)?;
+    let again = format_file(&reparsed, &formatted);
+    if again != formatted {
+        let diff = unified_diff(&formatted, &again, "once", "twice");
+        return Err(SelftestErrorKind::Unstable(diff));
+    }
+
+    let (before, after) = (normalized(&original), normalized(&reparsed));
+    if before != after {
+        let diff = unified_diff(&before, &after, "original", "formatted");
+        return Err(SelftestErrorKind::TreeChanged(diff));
+    }
+
+    let mut dropped = comments(source);
+    let mut added = Vec::new();
+    for comment in comments(&formatted) {
+        match dropped.iter().position(|original| *original == comment) {
+            Some(index) => {
+                dropped.remove(index);
+            }
+            None => added.push(comment),
+        }
+    }
+    if !dropped.is_empty() || !added.is_empty() {
+        return Err(SelftestErrorKind::CommentsChanged { dropped, added });
+    }
+    Ok(())
+}
+
+/// Run `selftest_source` on every `.go` file under `directory`, in path order
+pub fn selftest_directory(directory: &Path) -> Vec<(PathBuf, Result<(), SelftestError>)> {
+    let mut paths = Vec::new();
+    let mut errors = Vec::new();
+    collect_go_files(directory, &mut paths, &mut errors);
+    paths.sort();
+    let mut results: Vec<(PathBuf, Result<(), SelftestError>)> = errors
+        .into_iter()
+        .map(|error| (error.path.clone(), Err(error)))
+        .collect();
+    for path in paths {
+        let result = match std::fs::read_to_string(&path) {
+            Ok(source) => selftest_source(&source),
+            Err(error) => Err(SelftestErrorKind::Io(error.to_string())),
+        };
+        let result = result.map_err(|kind| SelftestError::new(kind, path.clone()));
+        results.push((path, result));
+    }
+    results
+}
+
+fn collect_go_files(directory: &Path, paths: &mut Vec<PathBuf>, errors: &mut Vec<SelftestError>) {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            let kind = SelftestErrorKind::Io(error.to_string());
+            errors.push(SelftestError::new(kind, directory.to_path_buf()));
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_go_files(&path, paths, errors);
+        } else if path.extension().is_some_and(|extension| extension == "go") {
+            paths.push(path);
+        }
+    }
+}
+
+fn parse(source: &str) -> Result<File, Vec<Diagnostic>> {
+    let mut parser = Parser::new(source);
+    let result = parser.parse();
+    match result {
+        Ok(file) if parser.errors.is_empty() => Ok(file),
+        Ok(_) => Err(parser.errors.iter().map(Diagnostic::from).collect()),
+        Err(errors) => Err(errors.iter().map(Diagnostic::from).collect()),
+    }
+}
+
+/// The text of each comment in `source`, without trailing spaces, which the formatter drops
+fn comments(source: &str) -> Vec<String> {
+    let mut lexer = Lexer::new(source);
+    let mut comments = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        match token.kind {
+            Some(TokenKind::SingleLineComment | TokenKind::BlockComment) => {
+                comments.push(token.value.trim_end().to_string());
+            }
+            Some(TokenKind::EOF) | None => return comments,
+            _ => {}
+        }
+    }
+}
+
+/// `file` printed without what formatting is allowed to change: the order of its imports and
+/// the parentheses around conditions
+fn normalized(file: &File) -> String {
+    let mut file = file.clone();
+    file.imports
+        .sort_by(|a, b| (&a.path, &a.name).cmp(&(&b.path, &b.name)));
+    file.imports
+        .dedup_by(|a, b| a.path == b.path && a.name == b.name);
+    for declaration in &mut file.decls {
+        if let DeclarationKind::Function { body, .. } = &mut declaration.kind {
+            body.iter_mut().for_each(strip_conditions);
+        }
+    }
+    file.to_source()
+}
+
+fn strip_conditions(statement: &mut Statement) {
+    match &mut statement.kind {
+        StatementKind::If {
+            condition,
+            body,
+            else_branch,
+            ..
+        } => {
+            *condition = strip_parentheses(condition).clone();
+            body.iter_mut().for_each(strip_conditions);
+            if let Some(else_branch) = else_branch {
+                strip_conditions(else_branch);
+            }
+        }
+        StatementKind::For {
+            condition, body, ..
+        } => {
+            if let Some(condition) = condition {
+                *condition = strip_parentheses(condition).clone();
+            }
+            body.iter_mut().for_each(strip_conditions);
+        }
+        StatementKind::Range {
+            expression, body, ..
+        } => {
+            *expression = strip_parentheses(expression).clone();
+            body.iter_mut().for_each(strip_conditions);
+        }
+        StatementKind::Block(body) => body.iter_mut().for_each(strip_conditions),
+        StatementKind::Select(cases) => {
+            for case in cases {
+                case.body.iter_mut().for_each(strip_conditions);
+            }
+        }
+        StatementKind::Switch { tag, cases, .. } => {
+            if let Some(tag) = tag {
+                *tag = strip_parentheses(tag).clone();
+            }
+            for case in cases {
+                case.body.iter_mut().for_each(strip_conditions);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selftest_passes() {
+        let source = "package main\n\nimport (\n\"os\"\n\"fmt\" // print\n)\n\nfunc main() {\nif (len(os.Args) > 1) { fmt.Println(((1))) } /* done */\n}\n";
+        assert_eq!(selftest_source(source), Ok(()));
+    }
+
+    #[test]
+    fn selftest_skips_unparsable() {
+        let result = selftest_source("package main\n\nfunc main() {\n\tx :=\n}\n");
+        assert!(matches!(result, Err(SelftestErrorKind::Unparsable(_))));
+    }
+}
@@ -0,0 +1,451 @@
+//! Classifying a file's tokens for highlighting, by what they are to the lexer and, for
+//! names, what they refer to once resolved, for editors and rendered source alike
+
+use std::collections::HashSet;
+
+use crate::ast::{
+    ast::File,
+    declaration::{Declaration, DeclarationKind},
+    expression::{Expression, ExpressionKind},
+    statement::{Statement, StatementKind},
+    type_expression::{TypeExpression, TypeExpressionKind},
+};
+use crate::lexer::{lexer::Lexer, token::Token, token_type::TokenKind};
+use crate::parser::parser::parse_checked;
+use crate::primitives::position::Position;
+use crate::semantic::{
+    resolver::resolve,
+    scope::{SymbolKind, SymbolTable},
+};
+use crate::vet::walk::{Visitor, walk_statement};
+
+/// What a highlighted stretch of source is
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum Class {
+    Keyword,
+    Comment,
+    String,
+    Number,
+    Operator,
+    // The name of an imported package
+    Namespace,
+    Type,
+    // A function or method, where it's declared or called
+    Function,
+    Parameter,
+    Variable,
+    // `true`, `false`, `iota` and `nil`
+    Constant,
+    // A struct's field, or a name selected from a value whose type isn't known here
+    Property,
+}
+
+impl Class {
+    pub const ALL: &[Class] = &[
+        Class::Keyword,
+        Class::Comment,
+        Class::String,
+        Class::Number,
+        Class::Operator,
+        Class::Namespace,
+        Class::Type,
+        Class::Function,
+        Class::Parameter,
+        Class::Variable,
+        Class::Constant,
+        Class::Property,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Class::Keyword => "keyword",
+            Class::Comment => "comment",
+            Class::String => "string",
+            Class::Number => "number",
+            Class::Operator => "operator",
+            Class::Namespace => "namespace",
+            Class::Type => "type",
+            Class::Function => "function",
+            Class::Parameter => "parameter",
+            Class::Variable => "variable",
+            Class::Constant => "constant",
+            Class::Property => "property",
+        }
+    }
+}
+
+/// A stretch of a line of source, from `column_start` to `column_end` in bytes, and how it's
+/// highlighted
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Highlight {
+    pub position: Position,
+    pub class: Class,
+}
+
+/// The highlights of `source`, in order. Each is on one line, so a comment over many lines
+/// has one for each of them.
+pub fn highlight(source: &str) -> Vec<Highlight> {
+    let (file, _) = parse_checked(source);
+    highlight_file(source, file.as_ref())
+}
+
+/// The highlights of `source` as `highlight` finds them, taking its names' meanings from
+/// `file`, what it parsed to, if it parsed far enough. Without a file, names are only
+/// highlighted where the tokens around them make plain what they are.
+pub fn highlight_file(source: &str, file: Option<&File>) -> Vec<Highlight> {
+    let tokens = tokens(source);
+    let names = file.map(Names::new).unwrap_or_default();
+    let mut highlights = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let Some(kind) = token.kind else {
+            continue;
+        };
+        let class = match kind {
+            TokenKind::SingleLineComment | TokenKind::BlockComment => {
+                highlights.extend(lines(token, Class::Comment));
+                continue;
+            }
+            TokenKind::StringLiteral => {
+                highlights.extend(lines(token, Class::String));
+                continue;
+            }
+            TokenKind::Keyword(_) => Class::Keyword,
+            TokenKind::RuneLiteral => Class::String,
+            TokenKind::IntegerLiteral | TokenKind::FloatLiteral => Class::Number,
+            TokenKind::Identifier => {
+                let before = index.checked_sub(1).map(|before| &tokens[before]);
+                match names.class(token, before, tokens.get(index + 1)) {
+                    Some(class) => class,
+                    None => continue,
+                }
+            }
+            kind if is_operator(kind) => Class::Operator,
+            _ => continue,
+        };
+        highlights.push(Highlight {
+            position: token.position,
+            class,
+        });
+    }
+    highlights
+}
+
+/// The file's tokens, other than newlines, up to the end
+fn tokens(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer::new(source);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        match token.kind {
+            Some(TokenKind::EOF) => return tokens,
+            Some(TokenKind::Newline) => {}
+            _ => tokens.push(token),
+        }
+    }
+}
+
+/// A highlight for each line of a token, which only a comment or string may have many of
+fn lines(token: &Token, class: Class) -> impl Iterator<Item = Highlight> + '_ {
+    token
+        .value
+        .split('\n')
+        .enumerate()
+        .map(move |(index, line)| {
+            let column_start = match index {
+                0 => token.position.column_start,
+                _ => 0,
+            };
+            Highlight {
+                position: Position::new(
+                    token.position.line + index,
+                    column_start,
+                    column_start + line.len(),
+                ),
+                class,
+            }
+        })
+        .filter(|highlight| highlight.position.column_end > highlight.position.column_start)
+}
+
+fn is_operator(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Operator(_)
+            | TokenKind::LessMinus
+            | TokenKind::MinusMinus
+            | TokenKind::PlusPlus
+            | TokenKind::Equal
+            | TokenKind::ColonEqual
+            | TokenKind::PlusEqual
+            | TokenKind::MinusEqual
+            | TokenKind::StarEqual
+            | TokenKind::SlashEqual
+            | TokenKind::PercentEqual
+            | TokenKind::AmpersandEqual
+            | TokenKind::PipeEqual
+            | TokenKind::CaretEqual
+            | TokenKind::LessLessEqual
+            | TokenKind::GreaterGreaterEqual
+            | TokenKind::AmpersandCaretEqual
+            | TokenKind::Bang
+            | TokenKind::DotDotDot
+    )
+}
+
+/// What the names of a file refer to: the symbols the resolver finds, and the names it
+/// doesn't resolve that the tree shows the meaning of
+#[derive(Default)]
+struct Names {
+    symbols: SymbolTable,
+    /// Where each parameter, result and receiver is named
+    parameters: HashSet<Position>,
+    /// Where each struct field is named
+    fields: HashSet<Position>,
+    /// Where the name of each `pkg.T` type is
+    qualified_types: HashSet<Position>,
+}
+
+impl Names {
+    fn new(file: &File) -> Names {
+        let (symbols, _) = resolve(file);
+        let mut names = Names {
+            symbols,
+            ..Names::default()
+        };
+        for declaration in &file.decls {
+            names.declaration(declaration);
+        }
+        names
+    }
+
+    /// How the identifier `token` is highlighted, given the tokens either side of it, or
+    /// None if what it names isn't known
+    fn class(&self, token: &Token, before: Option<&Token>, after: Option<&Token>) -> Option<Class> {
+        let position = token.position;
+        let symbol = self
+            .symbols
+            .def_at(position)
+            .or_else(|| self.symbols.use_at(position));
+        if let Some(symbol) = symbol {
+            let symbol = self.symbols.symbol(symbol);
+            return Some(match symbol.kind {
+                SymbolKind::Type => Class::Type,
+                SymbolKind::Function => Class::Function,
+                SymbolKind::Package => Class::Namespace,
+                SymbolKind::Constant | SymbolKind::Nil => Class::Constant,
+                SymbolKind::Variable
+                    if symbol
+                        .position
+                        .is_some_and(|declared| self.parameters.contains(&declared)) =>
+                {
+                    Class::Parameter
+                }
+                SymbolKind::Variable => Class::Variable,
+            });
+        }
+        let kind = |token: Option<&Token>| token.and_then(|token| token.kind);
+        if self.qualified_types.contains(&position) {
+            Some(Class::Type)
+        } else if kind(after) == Some(TokenKind::LeftParen) {
+            // A function's or method's name where it's declared, or a method or a function
+            // of another package where it's called
+            Some(Class::Function)
+        } else if self.fields.contains(&position) || kind(before) == Some(TokenKind::Dot) {
+            Some(Class::Property)
+        } else {
+            None
+        }
+    }
+
+    fn declaration(&mut self, declaration: &Declaration) {
+        match &declaration.kind {
+            DeclarationKind::Function {
+                receiver,
+                parameters,
+                results,
+                body,
+                ..
+            } => {
+                for parameter in receiver.iter().chain(parameters).chain(results) {
+                    self.parameters
+                        .extend(parameter.names.iter().map(|name| name.position_start));
+                    self.type_expression(&parameter.type_expression);
+                }
+                for statement in body {
+                    walk_statement(statement, self);
+                }
+            }
+            DeclarationKind::Variable { specs, .. } | DeclarationKind::Constant { specs, .. } => {
+                for type_expression in specs
+                    .iter()
+                    .filter_map(|spec| spec.type_expression.as_ref())
+                {
+                    self.type_expression(type_expression);
+                }
+            }
+            DeclarationKind::Type {
+                type_expression, ..
+            } => self.type_expression(type_expression),
+        }
+    }
+
+    fn type_expression(&mut self, type_expression: &TypeExpression) {
+        match &type_expression.kind {
+            TypeExpressionKind::Name(_) => {}
+            TypeExpressionKind::Qualified { .. } => {
+                self.qualified_types.insert(type_expression.position_end);
+            }
+            TypeExpressionKind::Pointer(element)
+            | TypeExpressionKind::Slice(element)
+            | TypeExpressionKind::Array { element, .. }
+            | TypeExpressionKind::Channel { element, .. } => self.type_expression(element),
+            TypeExpressionKind::Map { key, value } => {
+                self.type_expression(key);
+                self.type_expression(value);
+            }
+            TypeExpressionKind::Struct(fields) => {
+                for field in fields {
+                    self.fields
+                        .extend(field.names.iter().map(|name| name.position_start));
+                    self.type_expression(&field.type_expression);
+                }
+            }
+            TypeExpressionKind::Interface(methods) => {
+                for parameter in methods
+                    .iter()
+                    .flat_map(|method| method.parameters.iter().chain(&method.results))
+                {
+                    self.type_expression(&parameter.type_expression);
+                }
+            }
+            TypeExpressionKind::Function {
+                parameters,
+                results,
+            } => {
+                for parameter in parameters.iter().chain(results) {
+                    self.type_expression(&parameter.type_expression);
+                }
+            }
+        }
+    }
+}
+
+/// The walk finds the types written in function bodies
+impl<'a> Visitor<'a> for Names {
+    fn statement(&mut self, statement: &'a Statement) {
+        if let StatementKind::Declaration(declaration) = &statement.kind {
+            self.declaration(declaration);
+        }
+    }
+
+    fn expression(&mut self, expression: &'a Expression) {
+        match &expression.kind {
+            ExpressionKind::CompositeLiteral {
+                type_expression: Some(type_expression),
+                ..
+            }
+            | ExpressionKind::Conversion {
+                target: type_expression,
+                ..
+            }
+            | ExpressionKind::Type(type_expression) => self.type_expression(type_expression),
+            // The walk goes on into the body
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                ..
+            } => {
+                for parameter in parameters.iter().chain(results) {
+                    self.parameters
+                        .extend(parameter.names.iter().map(|name| name.position_start));
+                    self.type_expression(&parameter.type_expression);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each highlight's text and class, like `main:function`
+    fn classes(source: &str) -> Vec<String> {
+        let lines: Vec<&str> = source.split('\n').collect();
+        highlight(source)
+            .iter()
+            .map(|highlight| {
+                let position = highlight.position;
+                let line = lines[position.line - 1];
+                format!(
+                    "{}:{}",
+                    &line[position.column_start..position.column_end],
+                    highlight.class.name()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn highlight_tokens_and_names() {
+        let source = "package main\n\nimport \"fmt\"\n\n/* A point,\n   on a plane */\ntype Point struct {\n\tX int\n}\n\nfunc (p Point) Scale(by int) Point {\n\tq := Point{X: p.X * by} // scaled\n\tfmt.Println(q, true, 'x', 1.5)\n\treturn q\n}\n";
+        assert_eq!(
+            classes(source),
+            [
+                "package:keyword",
+                "import:keyword",
+                "\"fmt\":string",
+                "/* A point,:comment",
+                "   on a plane */:comment",
+                "type:keyword",
+                "Point:type",
+                "struct:keyword",
+                "X:property",
+                "int:type",
+                "func:keyword",
+                "p:parameter",
+                "Point:type",
+                "Scale:function",
+                "by:parameter",
+                "int:type",
+                "Point:type",
+                "q:variable",
+                ":=:operator",
+                "Point:type",
+                "p:parameter",
+                "X:property",
+                "*:operator",
+                "by:parameter",
+                "// scaled:comment",
+                "fmt:namespace",
+                "Println:function",
+                "q:variable",
+                "true:constant",
+                "'x':string",
+                "1.5:number",
+                "return:keyword",
+                "q:variable",
+            ]
+        );
+    }
+
+    #[test]
+    fn highlight_without_a_tree() {
+        // The names' meanings are unknown without a tree, but the tokens are highlighted
+        let source = "package main\n\nfunc main() {\n\tx := f(\n";
+        assert_eq!(
+            highlight_file(source, None)
+                .iter()
+                .map(|highlight| highlight.class)
+                .collect::<Vec<Class>>(),
+            [
+                Class::Keyword,
+                Class::Keyword,
+                Class::Function,
+                Class::Operator,
+                Class::Function
+            ]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! Source as highlighted HTML, for documentation and playgrounds to show
+
+use crate::highlight::highlight::highlight;
+
+/// `source` as an HTML `pre` element, each highlighted stretch of it in a `span` named after
+/// its class, like `<span class="keyword">func</span>`, for a stylesheet to color
+pub fn to_html(source: &str) -> String {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(offset, _)| offset + 1))
+        .collect();
+    let mut html = String::from("<pre class=\"gor\"><code>");
+    let mut written = 0;
+    for highlight in highlight(source) {
+        let position = highlight.position;
+        let Some(line_start) = line_starts.get(position.line - 1) else {
+            continue;
+        };
+        let (start, end) = (
+            line_start + position.column_start,
+            line_start + position.column_end,
+        );
+        let Some(text) = source.get(start..end).filter(|_| start >= written) else {
+            continue;
+        };
+        html.push_str(&escape(&source[written..start]));
+        html.push_str(&format!(
+            "<span class=\"{}\">{}</span>",
+            highlight.class.name(),
+            escape(text)
+        ));
+        written = end;
+    }
+    html.push_str(&escape(&source[written..]));
+    html.push_str("</code></pre>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_html() {
+        assert_eq!(
+            to_html("package main\n\n// a < b\nvar s = \"&\"\n"),
+            "<pre class=\"gor\"><code><span class=\"keyword\">package</span> main\n\n<span class=\"comment\">// a &lt; b</span>\n<span class=\"keyword\">var</span> <span class=\"variable\">s</span> <span class=\"operator\">=</span> <span class=\"string\">&quot;&amp;&quot;</span>\n</code></pre>\n"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use log::trace;
+
 use crate::lexer::token::Token;
 use crate::lexer::token_type::TokenKind;
 use crate::primitives::{
@@ -10,15 +12,22 @@ pub struct Lexer {
     input: String, // TODO: this should be a stream or a &str but i cbf to deal with lifetimes
     current_position: usize,
     anchor: usize,
-    line_start: usize, // Character position of the start of the current line
+    line_start: usize, // Byte offset of the start of the current line
     errors: Vec<LexerError>,
     is_parsing_string: bool,
     is_parsing_rune: bool,
     newline_before_current_token: bool,
+    first_line: usize, // Line number reported for the first line of input
 }
 
 impl Lexer {
     pub fn new(input: &str) -> Lexer {
+        Lexer::new_at_line(input, 1)
+    }
+
+    /// Lex `input` as though it starts on line `first_line` of a larger file, e.g. when only
+    /// part of a file is being re-lexed
+    pub fn new_at_line(input: &str, first_line: usize) -> Lexer {
         Lexer {
             input: input.to_string(),
             current_position: 0,
@@ -28,58 +37,64 @@ impl Lexer {
             is_parsing_string: false,
             is_parsing_rune: false,
             newline_before_current_token: false,
+            first_line,
         }
     }
 
     pub fn next_token(&mut self) -> Token {
+        let token = self.lex_token();
+        trace!(
+            "{}:{}: {:?} {:?}",
+            token.position.line, token.position.column_start, token.kind, token.value
+        );
+        token
+    }
+
+    fn lex_token(&mut self) -> Token {
         loop {
             match self.next() {
                 Some(ch) => match ch {
-                    ch if ch == '\n' => {
+                    '\n' => {
                         if self.is_parsing_string {
                             self.errors.push(LexerError::new(
                                 LexerErrorKind::UnterminatedString(
-                                    self.proposed_token(false).to_string(),
+                                    self.proposed_token(true).to_string(),
                                 ),
                                 self.current_token_position(),
                             ));
                             self.is_parsing_string = false;
                             self.anchor = self.current_position;
+                            self.line_start = self.current_position;
                             return Token::new("", self.current_token_position());
                         }
                         if self.is_parsing_rune {
                             self.errors.push(LexerError::new(
                                 LexerErrorKind::UnterminatedRune(
-                                    self.proposed_token(false).to_string(),
+                                    self.proposed_token(true).to_string(),
                                 ),
                                 self.current_token_position(),
                             ));
                             self.is_parsing_rune = false;
                             self.anchor = self.current_position;
+                            self.line_start = self.current_position;
                             return Token::new("", self.current_token_position());
                         }
                         if self.peek().is_some() {
                             self.line_start = self.current_position;
                         }
                         self.anchor = self.current_position;
-                        return Token::new_with_kind(TokenKind::Newline, "\n", self.current_token_position());
+                        return Token::new_with_kind(
+                            TokenKind::Newline,
+                            "\n",
+                            self.current_token_position(),
+                        );
                     }
                     ch if is_whitespace(ch) && !self.is_parsing_string && !self.is_parsing_rune => {
                         self.handle_whitespace();
                         continue;
                     }
-                    ch if is_whitespace(ch) && self.is_parsing_rune => {
-                        self.errors.push(LexerError::new(
-                            LexerErrorKind::UnterminatedRune(
-                                self.proposed_token(false).to_string(),
-                            ),
-                            self.current_token_position(),
-                        ));
-                        self.is_parsing_rune = false;
-                        self.anchor = self.current_position;
-                        return Token::new("", self.current_token_position());
-                    }
-                    '"' => {
+                    // A quote of the other kind is part of the literal, as in "it's" or '"'
+                    '"' if !self.is_parsing_rune => {
                         if self.is_parsing_string {
                             // End of string - include the closing quote
                             return self.finalize_string();
@@ -87,13 +102,13 @@ impl Lexer {
                             // Start of string
                             self.is_parsing_string = true;
                             self.anchor = self.current_position - 1; // Include the opening quote -
-                                                                     // we've already called
-                                                                     // next(), so we need to go
-                                                                     // back a char
+                            // we've already called
+                            // next(), so we need to go
+                            // back a char
                             continue;
                         }
                     }
-                    '\'' => {
+                    '\'' if !self.is_parsing_string => {
                         if self.is_parsing_rune {
                             // End of rune - include the closing quote
                             return self.finalize_rune();
@@ -101,9 +116,9 @@ impl Lexer {
                             // Start of rune
                             self.is_parsing_rune = true;
                             self.anchor = self.current_position - 1; // Include the opening quote -
-                                                                     // we've already called
-                                                                     // next(), so we need to go
-                                                                     // back a char
+                            // we've already called
+                            // next(), so we need to go
+                            // back a char
                             continue;
                         }
                     }
@@ -120,6 +135,18 @@ impl Lexer {
                         }
                         continue;
                     }
+                    '/' if self.anchor == self.current_position - 1
+                        && matches!(self.peek(), Some('/') | Some('*')) =>
+                    {
+                        return self.lex_comment();
+                    }
+                    // The decimal point of a float literal like `1.5` isn't a selector, but the
+                    // `...` in `f(1...)` isn't a decimal point
+                    '.' if self.is_lexing_integer()
+                        && !self.input[self.current_position..].starts_with('.') =>
+                    {
+                        continue;
+                    }
                     ch if is_symbol(ch) => {
                         if let Some(token) = self.handle_symbol_char() {
                             return token;
@@ -197,20 +224,14 @@ impl Lexer {
                 self.current_position = symbol_pos;
 
                 // Create word token
-                let word_token = match TokenKind::from_str(pending_value) {
-                    Some(_) => Token::new(
-                        pending_value,
-                        Position::new(self.current_line(), self.anchor, symbol_pos),
-                    ),
+                let word_token = match TokenKind::from_text(pending_value) {
+                    Some(_) => Token::new(pending_value, self.word_position(symbol_pos)),
                     None => {
                         self.errors.push(LexerError::new(
                             LexerErrorKind::UnexpectedToken(pending_value.to_string()),
-                            Position::new(self.current_line(), self.anchor, symbol_pos),
+                            self.word_position(symbol_pos),
                         ));
-                        Token::new(
-                            "",
-                            Position::new(self.current_line(), self.anchor, symbol_pos),
-                        )
+                        Token::new("", self.word_position(symbol_pos))
                     }
                 };
 
@@ -226,23 +247,37 @@ impl Lexer {
         None
     }
 
+    /// Whether the characters since the anchor, not counting the one just consumed, are the
+    /// digits of an integer literal
+    fn is_lexing_integer(&self) -> bool {
+        let pending = &self.input[self.anchor..self.current_position - 1];
+        !pending.is_empty() && pending.bytes().all(|byte| byte.is_ascii_digit())
+    }
+
+    /// Position of a word running from the anchor up to the byte offset `end`
+    fn word_position(&self, end: usize) -> Position {
+        Position::new(
+            self.current_line(),
+            self.anchor - self.line_start,
+            end - self.line_start,
+        )
+    }
+
     fn handle_word(&mut self) -> Option<Token> {
         let value = self.proposed_token(false);
         match self.tokenize(value) {
             Ok(Some(token)) => match token.kind {
-                None => return None,
+                None => None,
                 _ => {
                     self.anchor = self.current_position;
-                    return Some(token);
+                    Some(token)
                 }
             },
-            Ok(None) => {
-                return None;
-            }
+            Ok(None) => None,
             Err(error) => {
                 self.errors.push(error);
                 self.anchor = self.current_position;
-                return Some(Token::new("", self.current_token_position()));
+                Some(Token::new("", self.current_token_position()))
             }
         }
     }
@@ -267,17 +302,17 @@ impl Lexer {
         if self.current_position >= self.input.len() {
             return None;
         }
-        let c = self.input.chars().nth(self.current_position);
-        self.current_position += 1;
-        return c;
+        // Positions are byte offsets, so slices of `input` always land on char boundaries
+        let c = self.input[self.current_position..].chars().next();
+        self.current_position += c.map_or(1, char::len_utf8);
+        c
     }
 
     fn peek(&self) -> Option<char> {
         if self.current_position >= self.input.len() {
             return None;
         }
-        let c = self.input.chars().nth(self.current_position);
-        return c;
+        self.input[self.current_position..].chars().next()
     }
 
     fn peek_is_whitespace(&self) -> bool {
@@ -290,17 +325,17 @@ impl Lexer {
     /// returns Some(token) if the token is tokenized, None if it is incomplete, err if there is an
     /// incomplete or unexpected full token
     fn tokenize(&self, value: &str) -> Result<Option<Token>, LexerError> {
-        match TokenKind::from_str(value) {
+        match TokenKind::from_text(value) {
             Some(_) => {
-                if let Some(next_c) = self.peek() {
-                    if !self.peek_is_whitespace() {
-                        let longer = value.to_string() + &next_c.to_string();
-                        if TokenKind::could_match(&longer) {
-                            return Ok(None); // Continue accumulating
-                        }
+                if let Some(next_c) = self.peek()
+                    && !self.peek_is_whitespace()
+                {
+                    let longer = value.to_string() + &next_c.to_string();
+                    if TokenKind::could_match(&longer) {
+                        return Ok(None); // Continue accumulating
                     }
                 }
-                return Ok(Some(Token::new(value, self.current_token_position())));
+                Ok(Some(Token::new(value, self.current_token_position())))
             }
             None => {
                 if !self.peek_is_whitespace() && TokenKind::could_match(value) {
@@ -308,12 +343,68 @@ impl Lexer {
                 }
 
                 // At a boundary but no valid token - this is an error
-                return Err(LexerError::new(
+                Err(LexerError::new(
                     LexerErrorKind::UnexpectedToken(value.to_string()),
                     self.current_token_position(),
-                ));
+                ))
+            }
+        }
+    }
+
+    /// Lex a `// line` or `/* block */` comment, having just consumed its first '/'. A block
+    /// comment spanning lines is positioned on its first line.
+    fn lex_comment(&mut self) -> Token {
+        let line = self.current_line();
+        let line_start = self.line_start;
+        if self.next() == Some('/') {
+            while !matches!(self.peek(), Some('\n') | None) {
+                self.next();
             }
+            let token = Token::new_with_kind(
+                TokenKind::SingleLineComment,
+                self.proposed_token(false),
+                self.current_token_position(),
+            );
+            self.anchor = self.current_position;
+            return token;
+        }
+
+        let mut first_line_end = None;
+        let mut terminated = false;
+        while let Some(ch) = self.next() {
+            match ch {
+                '*' if self.peek() == Some('/') => {
+                    self.next();
+                    terminated = true;
+                    break;
+                }
+                '\n' => {
+                    first_line_end.get_or_insert(self.current_position - 1);
+                    self.line_start = self.current_position;
+                }
+                _ => {}
+            }
+        }
+        let position = Position::new(
+            line,
+            self.anchor - line_start,
+            first_line_end.unwrap_or(self.current_position) - line_start,
+        );
+        let token = if terminated {
+            Token::new_with_kind(
+                TokenKind::BlockComment,
+                self.proposed_token(false),
+                position,
+            )
+        } else {
+            self.errors.push(LexerError::new(
+                LexerErrorKind::UnterminatedComment(self.proposed_token(false).to_string()),
+                position,
+            ));
+            Token::new("", position)
         };
+        self.anchor = self.current_position;
+        token
     }
 
     fn finalize_string(&mut self) -> Token {
@@ -345,15 +436,17 @@ impl Lexer {
     }
 
     fn current_token_position(&self) -> Position {
-        Position::new(self.current_line(), self.anchor - self.line_start, self.current_position - self.line_start)
+        Position::new(
+            self.current_line(),
+            self.anchor - self.line_start,
+            self.current_position - self.line_start,
+        )
     }
 
     fn handle_whitespace(&mut self) {
         // Check if the current character is a newline
-        if let Some(ch) = self.input.chars().nth(self.current_position - 1) {
-            if ch == '\n' {
-                self.newline_before_current_token = true;
-            }
+        if self.input.as_bytes()[self.current_position - 1] == b'\n' {
+            self.newline_before_current_token = true;
         }
         self.anchor = self.current_position;
     }
@@ -362,9 +455,11 @@ impl Lexer {
         &self.input[self.anchor..self.current_position - if already_iterated { 1 } else { 0 }]
     }
 
+    /// The line the token being built starts on, so tokens that end by consuming a newline
+    /// (e.g. an unterminated string) still report the line they began on
     fn current_line(&self) -> usize {
-        let end = self.current_position.min(self.input.len());
-        self.input[0..end].split('\n').count()
+        let end = self.anchor.min(self.input.len());
+        self.input[0..end].split('\n').count() + self.first_line - 1
     }
 
     /// Check if a newline was encountered before the current token and reset the flag
@@ -378,6 +473,11 @@ impl Lexer {
         &self.errors
     }
 
+    /// Move the collected errors out, leaving none behind
+    pub fn take_errors(&mut self) -> Vec<LexerError> {
+        std::mem::take(&mut self.errors)
+    }
+
     pub fn dump_tokens(&mut self) -> impl Iterator<Item = String> + '_ {
         let mut tokens: Vec<(Position, String)> = Vec::new();
         let mut errors: Vec<(Position, String)> = Vec::new();
@@ -498,17 +598,10 @@ mod tests {
         let mut lexer = Lexer::new(input);
 
         let token1 = lexer.next_token();
-        println!(
-            "Token 1: {:?}, value: '{}', errors: {}",
-            token1.kind,
-            token1.value,
-            lexer.errors.len()
-        );
         assert_eq!(token1.kind, Some(TokenKind::Identifier));
         assert_eq!(lexer.errors.len(), 0);
 
         let token2 = lexer.next_token();
-        println!("Token 2: {:?}, value: '{}'", token2.kind, token2.value);
         assert_eq!(token2.kind, Some(TokenKind::LeftParen));
 
         let token3 = lexer.next_token();
@@ -691,7 +784,7 @@ f"#;
         assert_eq!(token1.kind, None);
 
         assert_eq!(lexer.errors.len(), 1);
-        // The error should be for an unterminated rune (invalid due to whitespace)
+        // The error should be for an unterminated rune (EOF reached)
     }
 
     #[test]
@@ -779,6 +872,20 @@ f"#;
         // The error should be for an unterminated string
     }
 
+    #[test]
+    fn unterminated_string_ends_at_newline() {
+        let mut lexer = Lexer::new("\"abc\n  x");
+        assert_eq!(lexer.next_token().kind, None);
+        assert_eq!(
+            lexer.errors[0].kind,
+            LexerErrorKind::UnterminatedString("\"abc".to_string())
+        );
+
+        let token = lexer.next_token();
+        assert_eq!(token.kind, Some(TokenKind::Identifier));
+        assert_eq!(token.position, Position::new(2, 2, 3));
+    }
+
     #[test]
     fn string_mixed_with_other_tokens() {
         let input = r#"func main() { fmt.Println("Hello, World!") }"#;
@@ -868,6 +975,26 @@ f"#;
         assert_eq!(lexer.errors.len(), 0);
     }
 
+    #[test]
+    fn quotes_and_spaces_inside_literals() {
+        let input = r#""it's" '"' ' ' '\'' "\"'""#;
+        let mut lexer = Lexer::new(input);
+
+        let expected = [
+            (TokenKind::StringLiteral, r#""it's""#),
+            (TokenKind::RuneLiteral, "'\"'"),
+            (TokenKind::RuneLiteral, "' '"),
+            (TokenKind::RuneLiteral, r"'\''"),
+            (TokenKind::StringLiteral, r#""\"'""#),
+        ];
+        for (kind, value) in expected {
+            let token = lexer.next_token();
+            assert_eq!((token.kind, token.value.as_str()), (Some(kind), value));
+        }
+        assert_eq!(lexer.next_token().kind, Some(TokenKind::EOF));
+        assert_eq!(lexer.errors.len(), 0);
+    }
+
     #[test]
     fn mixed_strings_and_runes() {
         let input = r#"'a' + "hello" + 'b'"#;
@@ -1238,6 +1365,18 @@ f"#;
         assert_eq!(token.kind, Some(TokenKind::DotDotDot));
     }
 
+    #[test]
+    fn integer_before_dot_dot_dot() {
+        let input = "1...";
+        let mut lexer = Lexer::new(input);
+        let token = lexer.next_token();
+        assert_eq!(token.kind, Some(TokenKind::IntegerLiteral));
+        assert_eq!(token.value, "1");
+        let token = lexer.next_token();
+        assert_eq!(token.kind, Some(TokenKind::DotDotDot));
+        assert_eq!(token.position, Position::new(1, 1, 4));
+    }
+
     #[test]
     fn dot_start() {
         let input = ".";
@@ -1309,4 +1448,47 @@ f"#;
         let token = lexer.next_token();
         assert_eq!(token.kind, Some(TokenKind::EOF));
     }
+
+    #[test]
+    fn comments() {
+        let input = "a / b // line comment\n/* block\ncomment */ c/*x*/d /* open";
+        let mut lexer = Lexer::new(input);
+        let mut tokens: Vec<Token> = Vec::new();
+        loop {
+            let token = lexer.next_token();
+            if token.kind == Some(TokenKind::EOF) {
+                break;
+            }
+            tokens.push(token);
+        }
+
+        let kinds: Vec<Option<TokenKind>> = tokens.iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(TokenKind::Identifier),
+                Some(TokenKind::Operator(Operator::Slash)),
+                Some(TokenKind::Identifier),
+                Some(TokenKind::SingleLineComment),
+                Some(TokenKind::Newline),
+                Some(TokenKind::BlockComment),
+                Some(TokenKind::Identifier),
+                Some(TokenKind::BlockComment),
+                Some(TokenKind::Identifier),
+                None,
+            ]
+        );
+        assert_eq!(tokens[3].value, "// line comment");
+        assert_eq!(tokens[3].position, Position::new(1, 6, 21));
+        assert_eq!(tokens[5].value, "/* block\ncomment */");
+        assert_eq!(tokens[5].position, Position::new(2, 0, 8));
+        // Columns after a multi-line comment are relative to its last line
+        assert_eq!(tokens[6].position, Position::new(3, 11, 12));
+        assert_eq!(tokens[7].value, "/*x*/");
+        assert_eq!(lexer.errors.len(), 1);
+        assert_eq!(
+            lexer.errors[0].kind,
+            LexerErrorKind::UnterminatedComment("/* open".to_string())
+        );
+    }
 }
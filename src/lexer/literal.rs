@@ -0,0 +1,111 @@
+/// The text of an interpreted string literal like `"a\tb"`, with its escapes decoded
+/// (https://go.dev/ref/spec#String_literals). Byte escapes that aren't valid UTF-8, like
+/// `"\xff"`, can't be held in a `String`, so give None.
+pub fn unquote_string(literal: &str) -> Option<String> {
+    let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(character) = chars.next() {
+        match character {
+            '\\' => match escape(&mut chars, '"')? {
+                Escaped::Byte(byte) => bytes.push(byte),
+                Escaped::Char(character) => push_char(&mut bytes, character),
+            },
+            character => push_char(&mut bytes, character),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// The code point of a rune literal like `'a'`, `'\n'` or `'\u00e9'`
+/// (https://go.dev/ref/spec#Rune_literals)
+pub fn unquote_rune(literal: &str) -> Option<u32> {
+    let inner = literal.strip_prefix('\'')?.strip_suffix('\'')?;
+    let mut chars = inner.chars();
+    let value = match chars.next()? {
+        '\\' => match escape(&mut chars, '\'')? {
+            Escaped::Byte(byte) => u32::from(byte),
+            Escaped::Char(character) => u32::from(character),
+        },
+        character => u32::from(character),
+    };
+    chars.next().is_none().then_some(value)
+}
+
+enum Escaped {
+    // \xff and \377, a single byte even when it isn't valid UTF-8 alone
+    Byte(u8),
+    Char(char),
+}
+
+/// Decode the escape after a backslash. `quote` is the literal's own quote, the only one
+/// that can be escaped in it.
+fn escape(chars: &mut std::str::Chars, quote: char) -> Option<Escaped> {
+    let character = match chars.next()? {
+        'a' => '\u{07}',
+        'b' => '\u{08}',
+        'f' => '\u{0c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'v' => '\u{0b}',
+        '\\' => '\\',
+        character if character == quote => quote,
+        'x' => {
+            return digits(chars, 2, 16)
+                .and_then(|byte| u8::try_from(byte).ok().map(Escaped::Byte));
+        }
+        'u' => {
+            return digits(chars, 4, 16)
+                .and_then(char::from_u32)
+                .map(Escaped::Char);
+        }
+        'U' => {
+            return digits(chars, 8, 16)
+                .and_then(char::from_u32)
+                .map(Escaped::Char);
+        }
+        first @ '0'..='7' => {
+            let rest = digits(chars, 2, 8)?;
+            let value = first.to_digit(8)? * 64 + rest;
+            return u8::try_from(value).ok().map(Escaped::Byte);
+        }
+        _ => return None,
+    };
+    Some(Escaped::Char(character))
+}
+
+fn digits(chars: &mut std::str::Chars, count: usize, radix: u32) -> Option<u32> {
+    (0..count).try_fold(0, |value, _| {
+        Some(value * radix + chars.next()?.to_digit(radix)?)
+    })
+}
+
+fn push_char(bytes: &mut Vec<u8>, character: char) {
+    bytes.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unquote_literals() {
+        assert_eq!(unquote_string("\"hello\""), Some("hello".to_string()));
+        assert_eq!(
+            unquote_string("\"a\\tb\\n\\\"c\\\"\""),
+            Some("a\tb\n\"c\"".to_string())
+        );
+        assert_eq!(
+            unquote_string("\"\\u00e9\\xc3\\xa9\""),
+            Some("éé".to_string())
+        );
+        assert_eq!(unquote_string("\"\\xff\""), None);
+        assert_eq!(unquote_string("\"\\'\""), None);
+        assert_eq!(unquote_rune("'a'"), Some(97));
+        assert_eq!(unquote_rune("'\\''"), Some(39));
+        assert_eq!(unquote_rune("'\\101'"), Some(65));
+        assert_eq!(unquote_rune("'\\U0001F600'"), Some(0x1F600));
+        assert_eq!(unquote_rune("'ab'"), None);
+    }
+}
@@ -1,3 +1,5 @@
+#[allow(clippy::module_inception)]
 pub mod lexer;
+pub mod literal;
 pub mod token;
 pub mod token_type;
@@ -1,4 +1,4 @@
-use crate::lexer::token_type::{TokenKind, Keyword};
+use crate::lexer::token_type::{Keyword, TokenKind};
 use crate::primitives::position::Position;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -11,7 +11,7 @@ pub struct Token {
 impl Token {
     pub fn new(value: &str, position: Position) -> Token {
         Token {
-            kind: TokenKind::from_str(value),
+            kind: TokenKind::from_text(value),
             value: value.to_string(),
             position,
         }
@@ -33,6 +33,18 @@ impl Token {
         }
     }
 
+    /// Human readable description for diagnostics, e.g. `'{'`, `newline` or `EOF`
+    pub fn describe(&self) -> String {
+        match self.kind {
+            // Semicolons inserted for a newline carry "\n" as their value
+            Some(TokenKind::Semicolon) if self.value == "\n" => "newline".to_string(),
+            Some(TokenKind::EOF) => "EOF".to_string(),
+            _ if !self.value.is_empty() => format!("'{}'", self.value),
+            Some(kind) => kind.to_string(),
+            None => "invalid token".to_string(),
+        }
+    }
+
     /// if this token precedes a newline outside a string, should the parser insert a semicolon?
     /// (according to the formal syntax -> https://go.dev/ref/spec#Semicolons )
     //
@@ -42,23 +54,23 @@ impl Token {
     // one of the keywords break, continue, fallthrough, or return
     // one of the operators and punctuation ++, --, ), ], or }
     pub fn should_insert_semicolon(&self) -> bool {
-        match self.kind {
+        matches!(
+            self.kind,
             Some(TokenKind::Identifier)
-            | Some(TokenKind::IntegerLiteral)
-            | Some(TokenKind::FloatLiteral)
-            | Some(TokenKind::RuneLiteral)
-            | Some(TokenKind::StringLiteral)
-            | Some(TokenKind::Keyword(Keyword::Break))
-            | Some(TokenKind::Keyword(Keyword::Continue))
-            | Some(TokenKind::Keyword(Keyword::Fallthrough))
-            | Some(TokenKind::Keyword(Keyword::Return))
-            | Some(TokenKind::PlusPlus)
-            | Some(TokenKind::MinusMinus)
-            | Some(TokenKind::RightParen)
-            | Some(TokenKind::RightBracket)
-            | Some(TokenKind::RightBrace) => true,
-            _ => false,
-        }
+                | Some(TokenKind::IntegerLiteral)
+                | Some(TokenKind::FloatLiteral)
+                | Some(TokenKind::RuneLiteral)
+                | Some(TokenKind::StringLiteral)
+                | Some(TokenKind::Keyword(Keyword::Break))
+                | Some(TokenKind::Keyword(Keyword::Continue))
+                | Some(TokenKind::Keyword(Keyword::Fallthrough))
+                | Some(TokenKind::Keyword(Keyword::Return))
+                | Some(TokenKind::PlusPlus)
+                | Some(TokenKind::MinusMinus)
+                | Some(TokenKind::RightParen)
+                | Some(TokenKind::RightBracket)
+                | Some(TokenKind::RightBrace)
+        )
     }
 }
 
@@ -1,4 +1,5 @@
 #[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operator {
     Plus,
     Minus,
@@ -43,6 +44,36 @@ impl Operator {
             | Operator::AmpersandCaret => 5,
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Star => "*",
+            Operator::Slash => "/",
+            Operator::Percent => "%",
+            Operator::Ampersand => "&",
+            Operator::Pipe => "|",
+            Operator::Caret => "^",
+            Operator::LessLess => "<<",
+            Operator::GreaterGreater => ">>",
+            Operator::AmpersandCaret => "&^",
+            Operator::AndAnd => "&&",
+            Operator::PipePipe => "||",
+            Operator::EqualEqual => "==",
+            Operator::BangEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+        }
+    }
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -74,11 +105,71 @@ pub enum Keyword {
     Var,
 }
 
+impl Keyword {
+    pub const ALL: &[Keyword] = &[
+        Keyword::Break,
+        Keyword::Case,
+        Keyword::Chan,
+        Keyword::Const,
+        Keyword::Continue,
+        Keyword::Default,
+        Keyword::Defer,
+        Keyword::Else,
+        Keyword::Fallthrough,
+        Keyword::For,
+        Keyword::Func,
+        Keyword::Go,
+        Keyword::Goto,
+        Keyword::If,
+        Keyword::Import,
+        Keyword::Interface,
+        Keyword::Map,
+        Keyword::Package,
+        Keyword::Range,
+        Keyword::Return,
+        Keyword::Select,
+        Keyword::Struct,
+        Keyword::Switch,
+        Keyword::Type,
+        Keyword::Var,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::Break => "break",
+            Keyword::Case => "case",
+            Keyword::Chan => "chan",
+            Keyword::Const => "const",
+            Keyword::Continue => "continue",
+            Keyword::Default => "default",
+            Keyword::Defer => "defer",
+            Keyword::Else => "else",
+            Keyword::Fallthrough => "fallthrough",
+            Keyword::For => "for",
+            Keyword::Func => "func",
+            Keyword::Go => "go",
+            Keyword::Goto => "goto",
+            Keyword::If => "if",
+            Keyword::Import => "import",
+            Keyword::Interface => "interface",
+            Keyword::Map => "map",
+            Keyword::Package => "package",
+            Keyword::Range => "range",
+            Keyword::Return => "return",
+            Keyword::Select => "select",
+            Keyword::Struct => "struct",
+            Keyword::Switch => "switch",
+            Keyword::Type => "type",
+            Keyword::Var => "var",
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum TokenKind {
     // Utilities
     SingleLineComment,
+    BlockComment,
     StartBlockComment,
     EndBlockComment,
     EOF,
@@ -135,6 +226,58 @@ pub enum TokenKind {
     Newline,
 }
 
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let punctuation = match self {
+            TokenKind::SingleLineComment
+            | TokenKind::BlockComment
+            | TokenKind::StartBlockComment
+            | TokenKind::EndBlockComment => return write!(f, "comment"),
+            TokenKind::EOF => return write!(f, "EOF"),
+            TokenKind::BeforeStart => return write!(f, "start of input"),
+            TokenKind::Identifier => return write!(f, "identifier"),
+            TokenKind::IntegerLiteral => return write!(f, "integer literal"),
+            TokenKind::FloatLiteral => return write!(f, "float literal"),
+            TokenKind::RuneLiteral => return write!(f, "rune literal"),
+            TokenKind::StringLiteral => return write!(f, "string literal"),
+            TokenKind::Newline => return write!(f, "newline"),
+            TokenKind::Keyword(keyword) => keyword.as_str(),
+            TokenKind::Operator(operator) => operator.as_str(),
+            TokenKind::LessMinus => "<-",
+            TokenKind::MinusMinus => "--",
+            TokenKind::PlusPlus => "++",
+            TokenKind::Equal => "=",
+            TokenKind::ColonEqual => ":=",
+            TokenKind::PlusEqual => "+=",
+            TokenKind::MinusEqual => "-=",
+            TokenKind::StarEqual => "*=",
+            TokenKind::SlashEqual => "/=",
+            TokenKind::PercentEqual => "%=",
+            TokenKind::AmpersandEqual => "&=",
+            TokenKind::PipeEqual => "|=",
+            TokenKind::CaretEqual => "^=",
+            TokenKind::LessLessEqual => "<<=",
+            TokenKind::GreaterGreaterEqual => ">>=",
+            TokenKind::AmpersandCaretEqual => "&^=",
+            TokenKind::Bang => "!",
+            TokenKind::DotDotDot => "...",
+            TokenKind::Dot => ".",
+            TokenKind::Colon => ":",
+            TokenKind::Comma => ",",
+            TokenKind::Semicolon => ";",
+            TokenKind::LeftParen => "(",
+            TokenKind::RightParen => ")",
+            TokenKind::LeftBracket => "[",
+            TokenKind::RightBracket => "]",
+            TokenKind::LeftBrace => "{",
+            TokenKind::RightBrace => "}",
+            TokenKind::Backtick => "`",
+            TokenKind::DollarSign => "$",
+        };
+        write!(f, "'{}'", punctuation)
+    }
+}
+
 fn is_valid_string_content(content: &str) -> bool {
     let mut chars = content.chars();
     while let Some(c) = chars.next() {
@@ -170,7 +313,27 @@ fn is_valid_rune_content(value: &str) -> bool {
 }
 
 impl TokenKind {
-    pub fn from_str(value: &str) -> Option<TokenKind> {
+    /// The operator of an assignment operation like `+=`
+    /// (https://go.dev/ref/spec#Assignment_statements)
+    pub fn assign_operator(&self) -> Option<Operator> {
+        Some(match self {
+            TokenKind::PlusEqual => Operator::Plus,
+            TokenKind::MinusEqual => Operator::Minus,
+            TokenKind::StarEqual => Operator::Star,
+            TokenKind::SlashEqual => Operator::Slash,
+            TokenKind::PercentEqual => Operator::Percent,
+            TokenKind::AmpersandEqual => Operator::Ampersand,
+            TokenKind::PipeEqual => Operator::Pipe,
+            TokenKind::CaretEqual => Operator::Caret,
+            TokenKind::LessLessEqual => Operator::LessLess,
+            TokenKind::GreaterGreaterEqual => Operator::GreaterGreater,
+            TokenKind::AmpersandCaretEqual => Operator::AmpersandCaret,
+            _ => return None,
+        })
+    }
+
+    /// The kind of token `value` is all of, or None if it isn't a single token
+    pub fn from_text(value: &str) -> Option<TokenKind> {
         if value.is_empty() || value.chars().all(|c| c.is_whitespace()) {
             return None;
         }
@@ -300,7 +463,7 @@ impl TokenKind {
     }
 
     pub fn is_tokenizeable(value: &str) -> bool {
-        TokenKind::from_str(value).is_some()
+        TokenKind::from_text(value).is_some()
     }
 
     pub fn could_match(input: &str) -> bool {
@@ -387,12 +550,11 @@ impl TokenKind {
         }
 
         // Check if input could be a partial identifier (letters, digits, underscore, but must start with letter or underscore)
-        if let Some(first_char) = input.chars().next() {
-            if (first_char.is_ascii_alphabetic() || first_char == '_')
-                && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
-            {
-                return true;
-            }
+        if let Some(first_char) = input.chars().next()
+            && (first_char.is_ascii_alphabetic() || first_char == '_')
+            && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return true;
         }
 
         // Check if input could be a partial integer literal (only digits)
@@ -418,66 +580,66 @@ mod tests {
 
     #[test]
     fn func_tokenizes() {
-        let token = TokenKind::from_str("func");
+        let token = TokenKind::from_text("func");
         assert_eq!(token, Some(TokenKind::Keyword(Keyword::Func)));
     }
 
     #[test]
     fn identifier_tokenizes() {
-        assert_eq!(TokenKind::from_str("main"), Some(TokenKind::Identifier));
+        assert_eq!(TokenKind::from_text("main"), Some(TokenKind::Identifier));
         assert_eq!(
-            TokenKind::from_str("variable123"),
+            TokenKind::from_text("variable123"),
             Some(TokenKind::Identifier)
         );
         assert_eq!(
-            TokenKind::from_str("_underscore"),
+            TokenKind::from_text("_underscore"),
             Some(TokenKind::Identifier)
         );
     }
 
     #[test]
     fn integer_literal_tokenizes() {
-        assert_eq!(TokenKind::from_str("123"), Some(TokenKind::IntegerLiteral));
-        assert_eq!(TokenKind::from_str("0"), Some(TokenKind::IntegerLiteral));
+        assert_eq!(TokenKind::from_text("123"), Some(TokenKind::IntegerLiteral));
+        assert_eq!(TokenKind::from_text("0"), Some(TokenKind::IntegerLiteral));
     }
 
     #[test]
     fn float_literal_tokenizes() {
         assert_eq!(
-            TokenKind::from_str("123.456"),
+            TokenKind::from_text("123.456"),
             Some(TokenKind::FloatLiteral)
         );
-        assert_eq!(TokenKind::from_str("0.5"), Some(TokenKind::FloatLiteral));
+        assert_eq!(TokenKind::from_text("0.5"), Some(TokenKind::FloatLiteral));
     }
 
     #[test]
     fn strings_tokenize() {
         assert_eq!(
-            TokenKind::from_str("\"hello\""),
+            TokenKind::from_text("\"hello\""),
             Some(TokenKind::StringLiteral)
         );
         assert_eq!(
-            TokenKind::from_str("\"hello world\""),
+            TokenKind::from_text("\"hello world\""),
             Some(TokenKind::StringLiteral)
         );
         assert_eq!(
-            TokenKind::from_str("\"hello \\\"quoted\\\" text\""),
+            TokenKind::from_text("\"hello \\\"quoted\\\" text\""),
             Some(TokenKind::StringLiteral)
         );
     }
 
     #[test]
     fn rune_literal_tokenizes() {
-        assert_eq!(TokenKind::from_str("'a'"), Some(TokenKind::RuneLiteral));
-        assert_eq!(TokenKind::from_str("'\\n'"), Some(TokenKind::RuneLiteral));
+        assert_eq!(TokenKind::from_text("'a'"), Some(TokenKind::RuneLiteral));
+        assert_eq!(TokenKind::from_text("'\\n'"), Some(TokenKind::RuneLiteral));
     }
 
     #[test]
     fn invalid_tokens_return_none() {
-        assert_eq!(TokenKind::from_str("123."), None); // ends with dot
-        assert_eq!(TokenKind::from_str(".123"), None); // starts with dot
-        assert_eq!(TokenKind::from_str("12.3.4"), None); // multiple dots
-        assert_eq!(TokenKind::from_str("main()"), None); // contains symbol
-        assert_eq!(TokenKind::from_str("123abc"), None); // mixed number and letter
+        assert_eq!(TokenKind::from_text("123."), None); // ends with dot
+        assert_eq!(TokenKind::from_text(".123"), None); // starts with dot
+        assert_eq!(TokenKind::from_text("12.3.4"), None); // multiple dots
+        assert_eq!(TokenKind::from_text("main()"), None); // contains symbol
+        assert_eq!(TokenKind::from_text("123abc"), None); // mixed number and letter
     }
 }
@@ -1,5 +1,18 @@
 pub mod ast;
+pub mod compile;
+pub mod eval;
+pub mod format;
+pub mod highlight;
 pub mod lexer;
+pub mod lsp;
 pub mod parser;
 pub mod primitives;
+pub mod rewrite;
+pub mod semantic;
+pub mod types;
+pub mod vet;
 pub mod cli; // TODO: may not need this in the lib
+#[cfg(test)]
+mod test_support;
+
+pub use parser::parser::parse_checked;
@@ -0,0 +1,154 @@
+//! Completing the name being typed from those in scope where it's typed
+
+use crate::lexer::token_type::Keyword;
+use crate::lsp::document::Document;
+use crate::semantic::{
+    resolver::resolve,
+    scope::{Symbol, SymbolKind},
+};
+
+/// A name that could be typed, and what it names
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CompletionKind {
+    Keyword,
+    Function,
+    Variable,
+    Constant,
+    Type,
+    Package,
+}
+
+impl CompletionKind {
+    /// The protocol's number for the kind of completion item
+    /// (https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionItemKind)
+    pub fn code(self) -> usize {
+        match self {
+            CompletionKind::Function => 3,
+            CompletionKind::Variable => 6,
+            CompletionKind::Type => 7,
+            CompletionKind::Package => 9,
+            CompletionKind::Keyword => 14,
+            CompletionKind::Constant => 21,
+        }
+    }
+}
+
+impl From<&Symbol> for CompletionKind {
+    fn from(symbol: &Symbol) -> CompletionKind {
+        match symbol.kind {
+            SymbolKind::Type => CompletionKind::Type,
+            SymbolKind::Constant | SymbolKind::Nil => CompletionKind::Constant,
+            SymbolKind::Variable => CompletionKind::Variable,
+            SymbolKind::Function => CompletionKind::Function,
+            SymbolKind::Package => CompletionKind::Package,
+        }
+    }
+}
+
+/// What the name that ends at `offset` in the document could be completed to: the names in
+/// scope there that start with what's been typed of it, innermost first, then the keywords
+/// that do. After a `.`, where a package's or a value's members go, there are none, as those
+/// depend on types.
+pub fn completions(document: &Document, offset: usize) -> Vec<Completion> {
+    let typed = document.text[..offset]
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |before| {
+            before
+                + document.text[before..]
+                    .chars()
+                    .next()
+                    .map_or(0, char::len_utf8)
+        });
+    let prefix = &document.text[typed..offset];
+    if document.text[..typed].ends_with('.') {
+        return Vec::new();
+    }
+
+    let mut completions = Vec::new();
+    if let Some(tree) = document.tree() {
+        let (symbols, _) = resolve(tree);
+        completions.extend(
+            symbols
+                .visible_at(document.position(typed))
+                .into_iter()
+                .map(|symbol| symbols.symbol(symbol))
+                .filter(|symbol| symbol.name.starts_with(prefix))
+                .map(|symbol| Completion {
+                    label: symbol.name.clone(),
+                    kind: symbol.into(),
+                }),
+        );
+    }
+    completions.extend(
+        Keyword::ALL
+            .iter()
+            .map(Keyword::as_str)
+            .filter(|keyword| keyword.starts_with(prefix))
+            .map(|keyword| Completion {
+                label: keyword.to_string(),
+                kind: CompletionKind::Keyword,
+            }),
+    );
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(text: &str, at: &str) -> Vec<String> {
+        let document = Document::new(text.to_string(), 1);
+        let offset = text.find(at).unwrap() + at.len();
+        completions(&document, offset)
+            .into_iter()
+            .map(|completion| completion.label)
+            .collect()
+    }
+
+    #[test]
+    fn complete_names_in_scope() {
+        let text = "package main\n\nimport \"fmt\"\n\nvar count int\n\nfunc main() {\n\tcolumns := 1\n\tfor c := range columns {\n\t\tfmt.Println(c)\n\t}\n\ttotal := co\n}\n";
+        assert_eq!(
+            labels(text, "fmt.Println(c")[..3],
+            ["c", "columns", "count"]
+        );
+        // The loop's variable is out of scope after the loop
+        assert_eq!(
+            labels(text, "total := co"),
+            [
+                "columns",
+                "count",
+                "comparable",
+                "complex",
+                "complex128",
+                "complex64",
+                "copy",
+                "const",
+                "continue"
+            ]
+        );
+        assert_eq!(labels(text, "fmt.Pr"), Vec::<String>::new());
+
+        let document = Document::new(text.to_string(), 1);
+        let kinds: Vec<CompletionKind> = completions(&document, text.find("\tfmt").unwrap() + 1)
+            .into_iter()
+            .filter(|completion| ["fmt", "main", "int", "for"].contains(&completion.label.as_str()))
+            .map(|completion| completion.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                CompletionKind::Package,
+                CompletionKind::Function,
+                CompletionKind::Type,
+                CompletionKind::Keyword
+            ]
+        );
+    }
+}
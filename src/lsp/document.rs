@@ -0,0 +1,206 @@
+//! The files a language server's client has open, kept as the client edits them
+
+use std::ops::Range;
+
+use crate::ast::ast::File;
+use crate::parser::{
+    incremental::TextEdit,
+    parser::{Parser, parse_checked},
+};
+use crate::primitives::{diagnostic::Diagnostic, position::Position};
+use crate::semantic::{checker::check_with_config, config::CheckerConfig};
+
+/// A place in a document as the protocol gives it: a line, from 0, and a character on it,
+/// counted in UTF-16 code units
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct LspPosition {
+    pub line: usize,
+    pub character: usize,
+}
+
+impl LspPosition {
+    pub fn new(line: usize, character: usize) -> LspPosition {
+        LspPosition { line, character }
+    }
+}
+
+/// An open file's text and what it parses to, which is updated as the text is edited
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub text: String,
+    /// The version the client gave the text, which goes up with each edit
+    pub version: i64,
+    /// The byte offset each line starts at
+    line_starts: Vec<usize>,
+    /// The text's tree, if it parsed far enough to have one
+    tree: Option<File>,
+    /// The lexer's and parser's diagnostics for the text
+    syntax: Vec<Diagnostic>,
+}
+
+impl Document {
+    pub fn new(text: String, version: i64) -> Document {
+        let (tree, syntax) = parse_checked(&text);
+        Document {
+            line_starts: line_starts(&text),
+            text,
+            version,
+            tree,
+            syntax,
+        }
+    }
+
+    /// Replace the bytes in `range` of the text with `text`, or the whole text if there's no
+    /// range. As long as the text parses without errors, only the declarations an edit
+    /// touches are parsed again.
+    pub fn edit(&mut self, range: Option<Range<usize>>, text: &str) {
+        let edit = TextEdit::new(range.unwrap_or(0..self.text.len()), text);
+        let (tree, syntax) = match self.tree.take() {
+            Some(tree) if self.syntax.is_empty() => {
+                Parser::reparse_checked(tree, &self.text, &edit)
+            }
+            _ => parse_checked(&edit.apply(&self.text)),
+        };
+        self.text = edit.apply(&self.text);
+        self.line_starts = line_starts(&self.text);
+        self.tree = tree;
+        self.syntax = syntax;
+    }
+
+    /// The text's tree, as far as it parses
+    pub fn tree(&self) -> Option<&File> {
+        self.tree.as_ref()
+    }
+
+    /// The text's lexer and parser errors if it has any, or else what checking it finds, in
+    /// source order. The file is checked on its own, as `gor check` checks a single file.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        if !self.syntax.is_empty() {
+            return self.syntax.clone();
+        }
+        let Some(tree) = &self.tree else {
+            return Vec::new();
+        };
+        let (_, mut diagnostics) = check_with_config(tree, &CheckerConfig::default());
+        diagnostics
+            .sort_by_key(|diagnostic| (diagnostic.position.line, diagnostic.position.column_start));
+        diagnostics
+    }
+
+    /// The byte offset of `position`, or of the end of its line if it's past the end, or of
+    /// the text if the line is
+    pub fn offset(&self, position: LspPosition) -> usize {
+        let Some(&start) = self.line_starts.get(position.line) else {
+            return self.text.len();
+        };
+        let end = self
+            .line_starts
+            .get(position.line + 1)
+            .map_or(self.text.len(), |next| next - 1);
+        let mut units = 0;
+        for (offset, c) in self.text[start..end].char_indices() {
+            if units >= position.character {
+                return start + offset;
+            }
+            units += c.len_utf16();
+        }
+        end
+    }
+
+    /// The position of the byte at `offset`
+    pub fn lsp_position(&self, offset: usize) -> LspPosition {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].encode_utf16().count();
+        LspPosition::new(line, character)
+    }
+
+    /// The byte offset of the column `column` of the 1-based line `line`, as gor's positions
+    /// give them
+    pub fn source_offset(&self, line: usize, column: usize) -> usize {
+        self.line_starts
+            .get(line.saturating_sub(1))
+            .map_or(self.text.len(), |start| {
+                (start + column).min(self.text.len())
+            })
+    }
+
+    /// The start and end of `position`, gor's, as the protocol gives them
+    pub fn range(&self, position: Position) -> (LspPosition, LspPosition) {
+        let start = self.source_offset(position.line, position.column_start);
+        let end = self.source_offset(position.line, position.column_end);
+        (self.lsp_position(start), self.lsp_position(end.max(start)))
+    }
+
+    /// The start of `start` to the end of `end`, gor's positions of the first and last
+    /// tokens of a stretch of the text, as the protocol gives them
+    pub fn span(&self, start: Position, end: Position) -> (LspPosition, LspPosition) {
+        let start = self.source_offset(start.line, start.column_start);
+        let end = self.source_offset(end.line, end.column_end);
+        (self.lsp_position(start), self.lsp_position(end.max(start)))
+    }
+
+    /// gor's position of the byte at `offset`
+    pub fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        Position::new_single_position(line, offset - self.line_starts[line - 1])
+    }
+}
+
+fn line_starts(text: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(offset, _)| offset + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_positions() {
+        let document = Document::new("package main\n\nvar s = \"é😀x\"\n".to_string(), 1);
+        let x = document.text.find('x').unwrap();
+        assert_eq!(document.lsp_position(x), LspPosition::new(2, 12));
+        assert_eq!(document.offset(LspPosition::new(2, 12)), x);
+        assert_eq!(
+            document.position(x),
+            Position::new_single_position(3, x - 14)
+        );
+        assert_eq!(document.source_offset(3, x - 14), x);
+        // Past the end of a line, or of the text
+        assert_eq!(document.offset(LspPosition::new(0, 40)), 12);
+        assert_eq!(document.offset(LspPosition::new(9, 0)), document.text.len());
+        assert_eq!(
+            document.range(Position::new(3, 4, 5)),
+            (LspPosition::new(2, 4), LspPosition::new(2, 5))
+        );
+    }
+
+    #[test]
+    fn edit_and_diagnose() {
+        let text = "package main\n\nfunc a() {\n\tprintln(1)\n}\n\nfunc b() {\n\tprintln(2)\n}\n";
+        let mut document = Document::new(text.to_string(), 1);
+        assert_eq!(document.diagnostics(), Vec::new());
+
+        let start = document.text.find("println(2)").unwrap();
+        document.edit(Some(start..start + 7), "missing");
+        assert_eq!(document.text, text.replace("println(2)", "missing(2)"));
+        let messages: Vec<String> = document
+            .diagnostics()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(messages, ["8:1: error: undefined: missing"]);
+
+        document.edit(Some(start..start + 7), "println(");
+        assert_eq!(document.diagnostics()[0].code, Some("syntax"));
+        // The tree is still there to ask about, as far as it parsed
+        assert!(document.tree().is_some());
+
+        document.edit(None, text);
+        assert_eq!(document.text, text);
+        assert_eq!(document.diagnostics(), Vec::new());
+        assert_eq!(document.tree(), Parser::new(text).parse().ok().as_ref());
+    }
+}
@@ -0,0 +1,97 @@
+//! Formatting a document as `gor fmt` does, as the edits that make it so
+
+use std::ops::RangeInclusive;
+
+use crate::format::{
+    config::FormatterConfig,
+    diff::{LineChange, line_changes},
+    formatter::format_source_with_config,
+};
+use crate::lsp::document::{Document, LspPosition};
+
+/// The edits that format `document` as `config` asks, each the range of whole lines it
+/// replaces and the text that replaces them, or None if the document doesn't parse. Only
+/// the edits touching `lines`, if it's given, are made, so lines aligned with ones outside
+/// them may be left unaligned.
+pub fn formatting(
+    document: &Document,
+    config: &FormatterConfig,
+    lines: Option<RangeInclusive<usize>>,
+) -> Option<Vec<((LspPosition, LspPosition), String)>> {
+    let formatted = format_source_with_config(&document.text, config).ok()?;
+    let line_start = |line: usize| document.lsp_position(document.source_offset(line + 1, 0));
+    let edits = line_changes(&document.text, &formatted)
+        .into_iter()
+        .flat_map(split)
+        .filter(|change| {
+            // An insertion touches the line it's inserted before
+            let touched = change.lines.start..change.lines.end.max(change.lines.start + 1);
+            lines
+                .as_ref()
+                .is_none_or(|lines| touched.start <= *lines.end() && touched.end > *lines.start())
+        })
+        .map(|change| {
+            let range = (line_start(change.lines.start), line_start(change.lines.end));
+            (range, change.text)
+        })
+        .collect();
+    Some(edits)
+}
+
+/// `change` as a change of each of its lines to the line replacing it, the last with the rest
+/// of both, so formatting a line changes it alone
+fn split(change: LineChange) -> Vec<LineChange> {
+    let mut lines = change.text.split_inclusive('\n');
+    let mut changes = Vec::new();
+    let mut line = change.lines.start;
+    while line + 1 < change.lines.end {
+        let Some(text) = lines.next() else {
+            break;
+        };
+        changes.push(LineChange {
+            lines: line..line + 1,
+            text: text.to_string(),
+        });
+        line += 1;
+    }
+    changes.push(LineChange {
+        lines: line..change.lines.end,
+        text: lines.collect(),
+    });
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(text: &str, edits: &[((LspPosition, LspPosition), String)]) -> String {
+        let mut document = Document::new(text.to_string(), 1);
+        for ((start, end), text) in edits.iter().rev() {
+            let range = document.offset(*start)..document.offset(*end);
+            document.edit(Some(range), text);
+        }
+        document.text
+    }
+
+    #[test]
+    fn format_documents() {
+        let text = "package main\n\nfunc main() {\nx := 1\n\tif x > 0 {\n  x = 2\n\t}\n}";
+        let document = Document::new(text.to_string(), 1);
+        let config = FormatterConfig::default();
+        let edits = formatting(&document, &config, None).unwrap();
+        assert_eq!(
+            apply(text, &edits),
+            "package main\n\nfunc main() {\n\tx := 1\n\tif x > 0 {\n\t\tx = 2\n\t}\n}\n"
+        );
+        // Only the lines asked for, with spaces
+        let config = FormatterConfig::default().with_indent_spaces(2);
+        let edits = formatting(&document, &config, Some(3..=3)).unwrap();
+        assert_eq!(
+            apply(text, &edits),
+            "package main\n\nfunc main() {\n  x := 1\n\tif x > 0 {\n  x = 2\n\t}\n}"
+        );
+        let document = Document::new("package main\n\nfunc main() {\n".to_string(), 1);
+        assert_eq!(formatting(&document, &config, None), None);
+    }
+}
@@ -0,0 +1,421 @@
+//! The JSON the Language Server Protocol's messages are written in. Only what a message
+//! needs is read and written: numbers are read as floats, and an object's members are kept
+//! in the order they're written in.
+
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use crate::ast::dump::json_string;
+use crate::primitives::errors::json::JsonError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// The single value `text` is, with nothing but whitespace around it
+    pub fn parse(text: &str) -> Result<Json, JsonError> {
+        let mut reader = Reader {
+            chars: text.char_indices().peekable(),
+            length: text.len(),
+        };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        match reader.chars.peek() {
+            Some(_) => Err(reader.error("expected the end of the text")),
+            None => Ok(value),
+        }
+    }
+
+    /// An object of `members`, in order
+    pub fn object<'a>(members: impl IntoIterator<Item = (&'a str, Json)>) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// The member `key` of an object, or None if it has no such member or isn't an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(member, _)| member == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The number, if it's a whole one that fits in an `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(number) if number.fract() == 0.0 && number.abs() < i64::MAX as f64 => {
+                Some(*number as i64)
+            }
+            _ => None,
+        }
+    }
+
+    /// The number, if it's a whole one that isn't negative
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_i64()
+            .and_then(|number| usize::try_from(number).ok())
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+}
+
+/// Compact, with no whitespace between tokens
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) if !number.is_finite() => write!(f, "null"),
+            Json::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                write!(f, "{}", *number as i64)
+            }
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(text) => write!(f, "{}", json_string(text)),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", json_string(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(number: usize) -> Json {
+        Json::Number(number as f64)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(number: i64) -> Json {
+        Json::Number(number as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(text: &str) -> Json {
+        Json::String(text.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(text: String) -> Json {
+        Json::String(text)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(elements: Vec<Json>) -> Json {
+        Json::Array(elements)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+struct Reader<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    /// The length of the text, the offset its end is reported at
+    length: usize,
+}
+
+impl Reader<'_> {
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map_or(self.length, |(offset, _)| *offset)
+    }
+
+    fn error(&mut self, message: &str) -> JsonError {
+        JsonError::new(message, self.offset())
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, c)| c)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected '{}'", expected))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('-' | '0'..='9') => self.number(),
+            Some('a'..='z') => self.literal(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("expected a value, not the end of the text")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.string()?;
+            self.expect(':')?;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some('}') => return Ok(Json::Object(members)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(Json::Array(elements));
+        }
+        loop {
+            elements.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(Json::Array(elements)),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.next();
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => text.push(self.escape()?),
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string"));
+                }
+                Some(c) => text.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, JsonError> {
+        match self.next() {
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('/') => Ok('/'),
+            Some('b') => Ok('\u{8}'),
+            Some('f') => Ok('\u{c}'),
+            Some('n') => Ok('\n'),
+            Some('r') => Ok('\r'),
+            Some('t') => Ok('\t'),
+            Some('u') => {
+                let unit = self.hex()?;
+                // A character outside the basic plane is escaped as a surrogate pair
+                let code = match unit {
+                    0xd800..=0xdbff => {
+                        for expected in ['\\', 'u'] {
+                            if self.peek() != Some(expected) {
+                                return Err(self.error("unpaired surrogate"));
+                            }
+                            self.next();
+                        }
+                        let low = self.hex()?;
+                        if !(0xdc00..=0xdfff).contains(&low) {
+                            return Err(self.error("unpaired surrogate"));
+                        }
+                        0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00)
+                    }
+                    _ => unit,
+                };
+                char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))
+            }
+            _ => Err(self.error("invalid escape")),
+        }
+    }
+
+    /// The four hex digits of a `\u` escape
+    fn hex(&mut self) -> Result<u32, JsonError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("expected four hex digits"))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.offset();
+        let mut text = String::new();
+        while let Some(c @ ('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) = self.peek() {
+            text.push(c);
+            self.next();
+        }
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| JsonError::new("invalid number", start))
+    }
+
+    /// `true`, `false` or `null`
+    fn literal(&mut self) -> Result<Json, JsonError> {
+        let start = self.offset();
+        let mut word = String::new();
+        while let Some(c @ 'a'..='z') = self.peek() {
+            word.push(c);
+            self.next();
+        }
+        match word.as_str() {
+            "true" => Ok(Json::Bool(true)),
+            "false" => Ok(Json::Bool(false)),
+            "null" => Ok(Json::Null),
+            _ => Err(JsonError::new("expected a value", start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_write() {
+        let text = r#" {"id": 1, "params": {"text": "a\"\\\n\u00e9\ud83d\ude00", "ok": [true, false, null]}, "x": -2.5e1} "#;
+        let json = Json::parse(text).unwrap();
+        assert_eq!(json.get("id").and_then(Json::as_i64), Some(1));
+        let params = json.get("params").unwrap();
+        assert_eq!(
+            params.get("text").and_then(Json::as_str),
+            Some("a\"\\\né😀")
+        );
+        assert_eq!(json.get("x"), Some(&Json::Number(-25.0)));
+        assert_eq!(json.get("missing"), None);
+        assert_eq!(
+            json.to_string(),
+            r#"{"id":1,"params":{"text":"a\"\\\né😀","ok":[true,false,null]},"x":-25}"#
+        );
+        assert_eq!(Json::parse(&json.to_string()), Ok(json));
+        assert_eq!(Json::Number(0.5).to_string(), "0.5");
+        assert_eq!(
+            Json::object([
+                ("a", Json::from(Some("b"))),
+                ("c", Json::from(None::<usize>))
+            ])
+            .to_string(),
+            r#"{"a":"b","c":null}"#
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |text: &str| Json::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error(""),
+            "invalid JSON at byte 0: expected a value, not the end of the text"
+        );
+        assert_eq!(error("{\"a\" 1}"), "invalid JSON at byte 5: expected ':'");
+        assert_eq!(
+            error("[1, 2"),
+            "invalid JSON at byte 5: expected ',' or ']'"
+        );
+        assert_eq!(
+            error("\"abc"),
+            "invalid JSON at byte 4: unterminated string"
+        );
+        assert_eq!(error("nul"), "invalid JSON at byte 0: expected a value");
+        assert_eq!(
+            error("1 2"),
+            "invalid JSON at byte 2: expected the end of the text"
+        );
+        assert_eq!(
+            error("\"\\ud800\""),
+            "invalid JSON at byte 7: unpaired surrogate"
+        );
+    }
+}
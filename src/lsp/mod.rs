@@ -0,0 +1,8 @@
+pub mod completion;
+pub mod document;
+pub mod formatting;
+pub mod json;
+pub mod references;
+pub mod rename;
+pub mod server;
+pub mod transport;
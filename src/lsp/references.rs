@@ -0,0 +1,263 @@
+//! Finding every reference to a name, across the package of the document it's in
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::cli::sarif::uri as path_uri;
+use crate::eval::{
+    config::LoaderConfig,
+    loader::{LoadCache, Program, Source, load_cached},
+};
+use crate::lsp::document::{Document, LspPosition};
+use crate::primitives::position::Position;
+use crate::semantic::{
+    navigation::{name_span, references as symbol_references, symbol_named_at},
+    resolver::resolve,
+};
+
+/// A reference to a name, in the document at `uri`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub uri: String,
+    pub range: (LspPosition, LspPosition),
+}
+
+/// Every reference to the name at `offset` in `documents[uri]`, its declaration first if
+/// `declaration` is set. The document's package is loaded with the open documents in place
+/// of their files, so references in its other files are found too. If it can't be, as when
+/// the document isn't a file or a file of the package doesn't parse, only the document's
+/// own references are.
+pub fn references(
+    documents: &HashMap<String, Document>,
+    uri: &str,
+    offset: usize,
+    declaration: bool,
+) -> Vec<Reference> {
+    let Some(document) = documents.get(uri) else {
+        return Vec::new();
+    };
+    let position = document.position(offset);
+    if let Some(references) = package_references(documents, uri, position, declaration) {
+        return references;
+    }
+    let Some(tree) = document.tree() else {
+        return Vec::new();
+    };
+    let (symbols, _) = resolve(tree);
+    let line = document.text.lines().nth(position.line - 1).unwrap_or("");
+    let Some(symbol) = symbol_named_at(&symbols, line, position) else {
+        return Vec::new();
+    };
+    let declared = symbols.symbol(symbol);
+    symbol_references(&symbols, symbol)
+        .into_iter()
+        .filter(|reference| declaration || Some(*reference) != declared.position)
+        .map(|reference| {
+            let line = document.text.lines().nth(reference.line - 1).unwrap_or("");
+            Reference {
+                uri: uri.to_string(),
+                range: line_range(line, name_span(line, reference, &declared.name)),
+            }
+        })
+        .collect()
+}
+
+/// The references as `references` finds them across the document's package, or None if the
+/// package can't be loaded
+fn package_references(
+    documents: &HashMap<String, Document>,
+    uri: &str,
+    position: Position,
+    declaration: bool,
+) -> Option<Vec<Reference>> {
+    let (program, path) = load_package(documents, uri)?;
+    let source = program.sources.iter().find(|source| source.path == path)?;
+    let line = source.line(position.line).unwrap_or("");
+    let mut position = position;
+    position.shift_lines(source.first_line as isize - 1);
+
+    let (symbols, _) = resolve(&program.file);
+    let symbol = symbol_named_at(&symbols, line, position)?;
+    let declared = symbols.symbol(symbol).position;
+    let name = &symbols.symbol(symbol).name;
+    let references = symbol_references(&symbols, symbol)
+        .into_iter()
+        .filter(|reference| declaration || Some(*reference) != declared)
+        .filter_map(|reference| {
+            let (source, reference) = Source::locate(&program.sources, reference)?;
+            let line = source.line(reference.line).unwrap_or("");
+            Some(Reference {
+                uri: match source.path == path {
+                    true => uri.to_string(),
+                    false => path_uri(&source.path),
+                },
+                range: line_range(line, name_span(line, reference, name)),
+            })
+        })
+        .collect();
+    Some(references)
+}
+
+/// The program of the package of the document at `uri`, loaded with the open documents in
+/// place of their files, and the document's path. None if the document isn't a file of a
+/// package that loads.
+pub(crate) fn load_package(
+    documents: &HashMap<String, Document>,
+    uri: &str,
+) -> Option<(Program, PathBuf)> {
+    let path = uri_path(uri)?;
+    let mut cache = LoadCache::default();
+    for (open, document) in documents {
+        if let Some(open) = uri_path(open) {
+            cache.insert(&open, document.text.clone()).ok()?;
+        }
+    }
+    let program = load_cached(path.parent()?, &LoaderConfig::default(), &mut cache).ok()?;
+    program
+        .sources
+        .iter()
+        .any(|source| source.path == path)
+        .then_some((program, path))
+}
+
+/// The start and end of `position` on `line`, the line it's on, as the protocol gives them
+fn line_range(line: &str, position: Position) -> (LspPosition, LspPosition) {
+    let character = |column: usize| {
+        let column = column.min(line.len());
+        line.get(..column)
+            .map_or(column, |before| before.encode_utf16().count())
+    };
+    (
+        LspPosition::new(position.line - 1, character(position.column_start)),
+        LspPosition::new(position.line - 1, character(position.column_end)),
+    )
+}
+
+/// The path of a `file` URI, with its percent-encoded bytes decoded
+pub(crate) fn uri_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::new();
+    let mut index = 0;
+    while index < encoded.len() {
+        let byte = match encoded[index] {
+            b'%' => {
+                let hex = std::str::from_utf8(encoded.get(index + 1..index + 3)?).ok()?;
+                index += 2;
+                u8::from_str_radix(hex, 16).ok()?
+            }
+            byte => byte,
+        };
+        bytes.push(byte);
+        index += 1;
+    }
+    let path = PathBuf::from(String::from_utf8(bytes).ok()?);
+    path.is_absolute().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn module(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/testfiles/module")
+            .join(path)
+    }
+
+    /// The references to the name at `line` and `character` of the open file `path` of the
+    /// test module, as the file names and ranges they're at
+    fn references_in(
+        documents: &HashMap<String, Document>,
+        path: &str,
+        line: usize,
+        character: usize,
+        declaration: bool,
+    ) -> Vec<(String, usize, usize, usize)> {
+        let uri = path_uri(&module(path));
+        let document = &documents[&uri];
+        let offset = document.offset(LspPosition::new(line, character));
+        references(documents, &uri, offset, declaration)
+            .into_iter()
+            .map(|reference| {
+                let file = uri_path(&reference.uri).unwrap();
+                let file = file.file_name().unwrap().to_string_lossy().to_string();
+                let (start, end) = reference.range;
+                (file, start.line, start.character, end.character)
+            })
+            .collect()
+    }
+
+    fn open(paths: &[&str]) -> HashMap<String, Document> {
+        paths
+            .iter()
+            .map(|path| {
+                let text = std::fs::read_to_string(module(path)).unwrap();
+                (path_uri(&module(path)), Document::new(text, 1))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn find_references_across_a_package() {
+        let documents = open(&["shapes/shapes.go", "cmd/app/main.go"]);
+        // `area` is declared in one of the package's files and used in another
+        assert_eq!(
+            references_in(&documents, "shapes/shapes.go", 19, 9, true),
+            [
+                ("square.go".to_string(), 11, 5, 9),
+                ("shapes.go".to_string(), 19, 8, 12)
+            ]
+        );
+        assert_eq!(
+            references_in(&documents, "shapes/shapes.go", 19, 9, false),
+            [("shapes.go".to_string(), 19, 8, 12)]
+        );
+        // And another package's members where the package imports them
+        let new = [
+            ("shapes.go".to_string(), 13, 5, 8),
+            ("main.go".to_string(), 10, 13, 16),
+        ];
+        assert_eq!(
+            references_in(&documents, "cmd/app/main.go", 10, 13, true),
+            new
+        );
+        assert_eq!(
+            references_in(&documents, "shapes/shapes.go", 13, 6, true),
+            new[..1]
+        );
+    }
+
+    #[test]
+    fn find_references_in_a_document() {
+        let text = "package main\n\nfunc main() {\n\tn := 1\n\tprintln(n, n)\n}\n";
+        let documents = HashMap::from([(
+            "untitled:main.go".to_string(),
+            Document::new(text.to_string(), 1),
+        )]);
+        let offset = text.find("n :=").unwrap();
+        let ranges: Vec<(LspPosition, LspPosition)> =
+            references(&documents, "untitled:main.go", offset, true)
+                .into_iter()
+                .map(|reference| reference.range)
+                .collect();
+        assert_eq!(
+            ranges,
+            [
+                (LspPosition::new(3, 1), LspPosition::new(3, 2)),
+                (LspPosition::new(4, 9), LspPosition::new(4, 10)),
+                (LspPosition::new(4, 12), LspPosition::new(4, 13))
+            ]
+        );
+    }
+
+    #[test]
+    fn convert_uris() {
+        assert_eq!(
+            uri_path("file:///app/main%20go.go"),
+            Some(PathBuf::from("/app/main go.go"))
+        );
+        assert_eq!(uri_path("untitled:main.go"), None);
+        assert_eq!(uri_path("file://app%2"), None);
+    }
+}
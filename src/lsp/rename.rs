@@ -0,0 +1,143 @@
+//! Renaming a name across the package of the document it's in, as edits for the client to
+//! make
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::cli::sarif::uri as path_uri;
+use crate::eval::loader::{Program, Source};
+use crate::lsp::{
+    document::{Document, LspPosition},
+    references::load_package,
+};
+use crate::primitives::errors::rewrite::{RewriteError, RewriteErrorKind};
+use crate::rewrite::rename::rename as rename_program;
+
+/// The edits a rename makes to the document at `uri`, each a range and the text that
+/// replaces it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentEdits {
+    pub uri: String,
+    pub edits: Vec<((LspPosition, LspPosition), String)>,
+}
+
+/// Rename the name at `offset` in `documents[uri]` to `name` everywhere the document's
+/// package refers to it, as `gor rename` does, with the open documents in place of their
+/// files. If the package can't be loaded, it's renamed in the document alone.
+pub fn rename(
+    documents: &HashMap<String, Document>,
+    uri: &str,
+    offset: usize,
+    name: &str,
+) -> Result<Vec<DocumentEdits>, RewriteError> {
+    let Some(document) = documents.get(uri) else {
+        return Ok(Vec::new());
+    };
+    let position = document.position(offset);
+    let (program, path) = match load_package(documents, uri) {
+        Some(loaded) => loaded,
+        None => {
+            let tree = document
+                .tree()
+                .ok_or_else(|| RewriteError::new(RewriteErrorKind::NotAName, position))?;
+            let path = PathBuf::from(uri);
+            let source = Source {
+                path: path.clone(),
+                first_line: 1,
+                text: document.text.clone(),
+            };
+            let program = Program {
+                file: tree.clone(),
+                sources: vec![source],
+                packages: Vec::new(),
+            };
+            (program, path)
+        }
+    };
+    let files = rename_program(&program, &path, position, name)?;
+    Ok(files
+        .into_iter()
+        .filter_map(|file| {
+            let source = program
+                .sources
+                .iter()
+                .find(|source| source.path == file.path)?;
+            let edits = file
+                .edits
+                .iter()
+                .map(|edit| {
+                    let start = lsp_position(&source.text, edit.start);
+                    let end = lsp_position(&source.text, edit.end);
+                    ((start, end), edit.text.clone())
+                })
+                .collect();
+            let uri = match file.path == path {
+                true => uri.to_string(),
+                false => path_uri(&file.path),
+            };
+            Some(DocumentEdits { uri, edits })
+        })
+        .collect())
+}
+
+/// The position of the byte at `offset` of `text`
+fn lsp_position(text: &str, offset: usize) -> LspPosition {
+    let before = &text[..offset];
+    let start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    LspPosition::new(
+        before.matches('\n').count(),
+        before[start..].encode_utf16().count(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn rename_across_a_package() {
+        let module = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/testfiles/module");
+        let main = module.join("cmd/app/main.go");
+        // The open document's edits, not the file's, are renamed in
+        let text = std::fs::read_to_string(&main)
+            .unwrap()
+            .replace("greeting(\"gor\")", "greeting(\"é\") + greeting(\"\")");
+        let uri = path_uri(&main);
+        let documents = HashMap::from([(uri.clone(), Document::new(text.clone(), 2))]);
+        let offset = text.rfind("greeting").unwrap();
+        let renamed = rename(&documents, &uri, offset, "welcome").unwrap();
+        let mut edits = Vec::new();
+        for document in &renamed {
+            let file = document.uri.rsplit('/').next().unwrap();
+            for ((start, end), text) in &document.edits {
+                assert_eq!(text, "welcome");
+                edits.push((file, start.line, start.character, end.character));
+            }
+        }
+        assert_eq!(
+            edits,
+            [
+                ("greet.go", 4, 5, 13),
+                ("main.go", 11, 37, 45),
+                ("main.go", 11, 53, 61)
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_in_a_document() {
+        let text = "package main\n\nfunc main() {\n\tn := 1\n\tprintln(n)\n}\n";
+        let uri = "untitled:main.go";
+        let documents = HashMap::from([(uri.to_string(), Document::new(text.to_string(), 1))]);
+        let offset = text.find("n :=").unwrap();
+        let renamed = rename(&documents, uri, offset, "count").unwrap();
+        assert_eq!(renamed.len(), 1);
+        assert_eq!(renamed[0].uri, uri);
+        assert_eq!(renamed[0].edits.len(), 2);
+        assert_eq!(
+            rename(&documents, uri, offset, "println").unwrap_err().kind,
+            RewriteErrorKind::Hides("println".to_string())
+        );
+    }
+}
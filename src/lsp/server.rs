@@ -0,0 +1,1021 @@
+//! `gor lsp`: a language server, which keeps the files an editor has open parsed as they're
+//! edited, publishes what's wrong with them and completes names in them
+//! (https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/)
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::ops::{Range, RangeInclusive};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use log::{debug, warn};
+
+use crate::ast::structure::{FoldKind, folding_ranges, selection_ranges};
+use crate::format::config::FormatterConfig;
+use crate::highlight::highlight::{Class, highlight_file};
+use crate::lsp::{
+    completion::completions,
+    document::{Document, LspPosition},
+    formatting::formatting,
+    json::Json,
+    references::references,
+    rename::rename,
+    transport::{read_message, write_message},
+};
+use crate::primitives::{
+    diagnostic::{Diagnostic, Severity},
+    position::Position,
+};
+use crate::semantic::navigation::{definition_at, hover_at};
+
+// The codes of the errors a request can be answered with
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+const REQUEST_FAILED: i64 = -32803;
+
+/// The protocol's `TextDocumentSyncKind.Incremental`: the client sends only the part of a
+/// document each edit changes
+const INCREMENTAL_SYNC: usize = 2;
+
+/// The semantic token types the server's tokens are of, which they refer to by index
+const TOKEN_TYPES: &[&str] = &[
+    "keyword",
+    "comment",
+    "string",
+    "number",
+    "operator",
+    "namespace",
+    "type",
+    "function",
+    "parameter",
+    "variable",
+    "property",
+];
+
+/// The semantic token modifiers, which a token has a bit of the set of for each: constants
+/// are read-only variables
+const TOKEN_MODIFIERS: &[&str] = &["readonly"];
+
+/// Why a request couldn't be answered
+#[derive(Debug, Clone, PartialEq)]
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+impl ResponseError {
+    fn new(code: i64, message: String) -> ResponseError {
+        ResponseError { code, message }
+    }
+
+    fn invalid_params(message: String) -> ResponseError {
+        ResponseError::new(INVALID_PARAMS, message)
+    }
+}
+
+/// The files the client has open, and where it is in the protocol's lifecycle
+#[derive(Debug, Default)]
+pub struct Server {
+    /// Each open document, by its URI
+    documents: HashMap<String, Document>,
+    /// Whether the client has sent `initialize`, before which it may send nothing else
+    initialized: bool,
+    /// Whether the client has sent `shutdown`, after which it may only send `exit`
+    shut_down: bool,
+    /// Whether the client has sent `exit`
+    exited: bool,
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server::default()
+    }
+
+    /// Answer the messages the client sends on `input` on `output`, until it says to exit or
+    /// `input` ends. Returns whether the client asked the server to shut down first, as it
+    /// should.
+    pub fn serve(&mut self, mut input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+        while !self.exited {
+            let Some(content) = read_message(&mut input)? else {
+                break;
+            };
+            for message in self.handle_text(&content) {
+                write_message(&mut output, &message.to_string())?;
+            }
+        }
+        Ok(self.shut_down)
+    }
+
+    /// Handle the content of a message as `handle` does, answering it with an error if it
+    /// isn't JSON
+    pub fn handle_text(&mut self, content: &str) -> Vec<Json> {
+        match Json::parse(content) {
+            Ok(message) => self.handle(&message),
+            Err(error) => vec![response(
+                Json::Null,
+                Err(ResponseError::new(PARSE_ERROR, error.to_string())),
+            )],
+        }
+    }
+
+    /// Handle a request or a notification, returning the messages to send the client: the
+    /// response to a request, and notifications like the diagnostics of a document that's
+    /// changed. A notification that can't be handled is only logged, as there's nothing to
+    /// answer it with.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let Some(method) = message.get("method").and_then(Json::as_str) else {
+            // Responses to requests the server doesn't make are ignored
+            return match message.get("id") {
+                Some(id) if message.get("result").is_none() && message.get("error").is_none() => {
+                    vec![response(
+                        id.clone(),
+                        Err(ResponseError::new(
+                            INVALID_REQUEST,
+                            "a request has no method".to_string(),
+                        )),
+                    )]
+                }
+                _ => Vec::new(),
+            };
+        };
+        let params = message.get("params").unwrap_or(&Json::Null);
+        // A bug in handling one message shouldn't take the editor's server down with it
+        let panicked = || format!("gor panicked handling {}", method);
+        match message.get("id") {
+            Some(id) => {
+                let result = catch_unwind(AssertUnwindSafe(|| self.request(method, params)))
+                    .unwrap_or_else(|_| Err(ResponseError::new(INTERNAL_ERROR, panicked())));
+                vec![response(id.clone(), result)]
+            }
+            None => match catch_unwind(AssertUnwindSafe(|| self.notification(method, params))) {
+                Ok(Ok(messages)) => messages,
+                Ok(Err(error)) => {
+                    warn!("{}: {}", method, error.message);
+                    Vec::new()
+                }
+                Err(_) => {
+                    warn!("{}", panicked());
+                    Vec::new()
+                }
+            },
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, ResponseError> {
+        debug!("request {}", method);
+        if method == "initialize" {
+            self.initialized = true;
+            return Ok(initialize_result());
+        }
+        if !self.initialized {
+            return Err(ResponseError::new(
+                SERVER_NOT_INITIALIZED,
+                "the server hasn't been initialized".to_string(),
+            ));
+        }
+        if self.shut_down {
+            return Err(ResponseError::new(
+                INVALID_REQUEST,
+                "the server has shut down".to_string(),
+            ));
+        }
+        match method {
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Json::Null)
+            }
+            "textDocument/completion" => self.completion(params),
+            "textDocument/semanticTokens/full" => self.semantic_tokens(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/references" => self.references(params),
+            "textDocument/rename" => self.rename(params),
+            "textDocument/formatting" => self.format(params, None),
+            "textDocument/rangeFormatting" => self.range_formatting(params),
+            "textDocument/foldingRange" => self.folding_ranges(params),
+            "textDocument/selectionRange" => self.selection_ranges(params),
+            _ => Err(ResponseError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method {}", method),
+            )),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Result<Vec<Json>, ResponseError> {
+        debug!("notification {}", method);
+        match method {
+            "exit" => {
+                self.exited = true;
+                Ok(Vec::new())
+            }
+            _ if !self.initialized || self.shut_down => Ok(Vec::new()),
+            "textDocument/didOpen" => self.did_open(params),
+            "textDocument/didChange" => self.did_change(params),
+            "textDocument/didClose" => self.did_close(params),
+            // Like `initialized` and `$/cancelRequest`, which there's nothing to do for
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn did_open(&mut self, params: &Json) -> Result<Vec<Json>, ResponseError> {
+        let item = member(params, "textDocument")?;
+        let uri = string(item, "uri")?;
+        let version = integer(item, "version")?;
+        let text = string(item, "text")?;
+        self.documents
+            .insert(uri.to_string(), Document::new(text.to_string(), version));
+        Ok(vec![self.publish_diagnostics(uri)])
+    }
+
+    /// Apply each change, in order, to the document, then publish its diagnostics
+    fn did_change(&mut self, params: &Json) -> Result<Vec<Json>, ResponseError> {
+        let identifier = member(params, "textDocument")?;
+        let uri = string(identifier, "uri")?;
+        let document = self
+            .documents
+            .get_mut(uri)
+            .ok_or_else(|| ResponseError::invalid_params(format!("{} isn't open", uri)))?;
+        let changes = member(params, "contentChanges")?
+            .as_array()
+            .ok_or_else(|| ResponseError::invalid_params("contentChanges isn't a list".into()))?;
+        for change in changes {
+            let range = match change.get("range") {
+                Some(range) => Some(offset_range(document, range)?),
+                None => None,
+            };
+            document.edit(range, string(change, "text")?);
+        }
+        document.version = integer(identifier, "version")?;
+        Ok(vec![self.publish_diagnostics(uri)])
+    }
+
+    /// Forget the document, clearing its diagnostics, which are only for open documents
+    fn did_close(&mut self, params: &Json) -> Result<Vec<Json>, ResponseError> {
+        let uri = string(member(params, "textDocument")?, "uri")?;
+        self.documents.remove(uri);
+        Ok(vec![notification(
+            "textDocument/publishDiagnostics",
+            Json::object([
+                ("uri", uri.into()),
+                ("diagnostics", Json::Array(Vec::new())),
+            ]),
+        )])
+    }
+
+    fn completion(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (document, offset) = self.document_offset(params)?;
+        let items = completions(document, offset)
+            .into_iter()
+            .map(|completion| {
+                Json::object([
+                    ("label", completion.label.into()),
+                    ("kind", completion.kind.code().into()),
+                ])
+            })
+            .collect::<Vec<Json>>();
+        Ok(Json::object([
+            ("isIncomplete", false.into()),
+            ("items", items.into()),
+        ]))
+    }
+
+    /// Where the name at the position is declared, if it's in the document
+    fn definition(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (document, offset) = self.document_offset(params)?;
+        let uri = string(member(params, "textDocument")?, "uri")?;
+        let definition = document
+            .tree()
+            .and_then(|tree| definition_at(tree, document.position(offset)));
+        Ok(definition.map_or(Json::Null, |position| {
+            location_json(uri, document, position)
+        }))
+    }
+
+    /// What the name at the position is, as a Go snippet followed by its documentation
+    fn hover(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (document, offset) = self.document_offset(params)?;
+        let hover = document
+            .tree()
+            .and_then(|tree| hover_at(tree, document.position(offset)));
+        Ok(hover.map_or(Json::Null, |hover| {
+            let mut value = format!("```go\n{}\n```", hover.signature);
+            if let Some(doc) = hover.doc {
+                value.push_str("\n\n");
+                value.push_str(&doc);
+            }
+            Json::object([
+                (
+                    "contents",
+                    Json::object([("kind", "markdown".into()), ("value", value.into())]),
+                ),
+                ("range", range_json(document.range(hover.position))),
+            ])
+        }))
+    }
+
+    /// Every reference to the name at the position, in the document's package
+    fn references(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (_, offset) = self.document_offset(params)?;
+        let uri = string(member(params, "textDocument")?, "uri")?;
+        let declaration = params
+            .get("context")
+            .and_then(|context| context.get("includeDeclaration"))
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        let locations = references(&self.documents, uri, offset, declaration)
+            .into_iter()
+            .map(|reference| {
+                Json::object([
+                    ("uri", reference.uri.into()),
+                    ("range", range_json(reference.range)),
+                ])
+            })
+            .collect::<Vec<Json>>();
+        Ok(locations.into())
+    }
+
+    /// The edits renaming the name at the position to `newName` makes, across the
+    /// document's package, or why it can't be renamed
+    fn rename(&self, params: &Json) -> Result<Json, ResponseError> {
+        let (_, offset) = self.document_offset(params)?;
+        let uri = string(member(params, "textDocument")?, "uri")?;
+        let name = string(params, "newName")?;
+        let documents = rename(&self.documents, uri, offset, name)
+            .map_err(|error| ResponseError::new(REQUEST_FAILED, error.kind.to_string()))?;
+        let changes = documents
+            .into_iter()
+            .map(|document| {
+                let edits = document
+                    .edits
+                    .into_iter()
+                    .map(|(range, text)| {
+                        Json::object([("range", range_json(range)), ("newText", text.into())])
+                    })
+                    .collect::<Vec<Json>>();
+                (document.uri, edits.into())
+            })
+            .collect();
+        Ok(Json::object([("changes", Json::Object(changes))]))
+    }
+
+    /// The edits that format the lines of the range, as `gor fmt` does
+    fn range_formatting(&self, params: &Json) -> Result<Json, ResponseError> {
+        let range = member(params, "range")?;
+        let start = lsp_position(member(range, "start")?)?;
+        let end = lsp_position(member(range, "end")?)?;
+        // A range ending at the start of a line doesn't take it in
+        let last = match end.character {
+            0 if end.line > start.line => end.line - 1,
+            _ => end.line.max(start.line),
+        };
+        self.format(params, Some(start.line..=last))
+    }
+
+    /// The edits that format the document, or those of its `lines`, as `gor fmt` does, or
+    /// null if it doesn't parse
+    fn format(
+        &self,
+        params: &Json,
+        lines: Option<RangeInclusive<usize>>,
+    ) -> Result<Json, ResponseError> {
+        let document = self.document(params)?;
+        let config = formatter_config(member(params, "options")?);
+        let Some(edits) = formatting(document, &config, lines) else {
+            return Ok(Json::Null);
+        };
+        let edits = edits
+            .into_iter()
+            .map(|(range, text)| {
+                Json::object([("range", range_json(range)), ("newText", text.into())])
+            })
+            .collect::<Vec<Json>>();
+        Ok(edits.into())
+    }
+
+    /// The lines of the document that can be folded away, or null if it doesn't parse
+    fn folding_ranges(&self, params: &Json) -> Result<Json, ResponseError> {
+        let document = self.document(params)?;
+        let Some(tree) = document.tree() else {
+            return Ok(Json::Null);
+        };
+        let folds = folding_ranges(tree, &document.text)
+            .into_iter()
+            .map(|fold| {
+                let mut members = vec![
+                    ("startLine", (fold.start_line - 1).into()),
+                    ("endLine", (fold.end_line - 1).into()),
+                ];
+                match fold.kind {
+                    FoldKind::Block => {}
+                    FoldKind::Imports => members.push(("kind", "imports".into())),
+                    FoldKind::Comment => members.push(("kind", "comment".into())),
+                }
+                Json::object(members)
+            })
+            .collect::<Vec<Json>>();
+        Ok(folds.into())
+    }
+
+    /// For each position, the stretches of the document around it, innermost first, each
+    /// the parent of the one before, and the whole document last
+    fn selection_ranges(&self, params: &Json) -> Result<Json, ResponseError> {
+        let document = self.document(params)?;
+        let positions = member(params, "positions")?
+            .as_array()
+            .ok_or_else(|| ResponseError::invalid_params("positions isn't an array".to_string()))?;
+        let whole = (
+            LspPosition::new(0, 0),
+            document.lsp_position(document.text.len()),
+        );
+        let mut ranges = Vec::new();
+        for position in positions {
+            let position = document.position(document.offset(lsp_position(position)?));
+            let spans = document
+                .tree()
+                .map_or(Vec::new(), |tree| selection_ranges(tree, position));
+            let mut range = Json::object([("range", range_json(whole))]);
+            for (start, end) in spans.into_iter().rev() {
+                let span = document.span(start, end);
+                if span != whole {
+                    range = Json::object([("range", range_json(span)), ("parent", range)]);
+                }
+            }
+            ranges.push(range);
+        }
+        Ok(ranges.into())
+    }
+
+    /// The highlights of the whole document, each as five numbers: how many lines it's
+    /// after the last, how many characters it's after the last's start if it's on the same
+    /// line or else after the line's start, how long it is, its type, and its modifiers
+    fn semantic_tokens(&self, params: &Json) -> Result<Json, ResponseError> {
+        let document = self.document(params)?;
+        let mut data = Vec::new();
+        let mut last = LspPosition::new(0, 0);
+        for highlight in highlight_file(&document.text, document.tree()) {
+            let (start, end) = document.range(highlight.position);
+            let (token_type, modifiers) = match highlight.class {
+                Class::Constant => ("variable", 1),
+                class => (class.name(), 0),
+            };
+            let Some(token_type) = TOKEN_TYPES.iter().position(|name| *name == token_type) else {
+                continue;
+            };
+            let delta_character = match start.line == last.line {
+                true => start.character - last.character,
+                false => start.character,
+            };
+            data.extend([
+                start.line - last.line,
+                delta_character,
+                end.character - start.character,
+                token_type,
+                modifiers,
+            ]);
+            last = start;
+        }
+        Ok(Json::object([(
+            "data",
+            data.into_iter()
+                .map(Json::from)
+                .collect::<Vec<Json>>()
+                .into(),
+        )]))
+    }
+
+    /// The open document a request is about
+    fn document(&self, params: &Json) -> Result<&Document, ResponseError> {
+        let uri = string(member(params, "textDocument")?, "uri")?;
+        self.documents
+            .get(uri)
+            .ok_or_else(|| ResponseError::invalid_params(format!("{} isn't open", uri)))
+    }
+
+    /// The open document and the byte offset in it of the position a request is about
+    fn document_offset(&self, params: &Json) -> Result<(&Document, usize), ResponseError> {
+        let document = self.document(params)?;
+        let position = lsp_position(member(params, "position")?)?;
+        Ok((document, document.offset(position)))
+    }
+
+    /// The notification of the diagnostics of the open document `uri`
+    fn publish_diagnostics(&self, uri: &str) -> Json {
+        let document = &self.documents[uri];
+        let diagnostics = document
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic_json(uri, document, diagnostic))
+            .collect::<Vec<Json>>();
+        notification(
+            "textDocument/publishDiagnostics",
+            Json::object([
+                ("uri", uri.into()),
+                ("version", document.version.into()),
+                ("diagnostics", diagnostics.into()),
+            ]),
+        )
+    }
+}
+
+/// What the server can do, in answer to `initialize`
+fn initialize_result() -> Json {
+    let capabilities = Json::object([
+        ("positionEncoding", "utf-16".into()),
+        (
+            "textDocumentSync",
+            Json::object([
+                ("openClose", true.into()),
+                ("change", INCREMENTAL_SYNC.into()),
+            ]),
+        ),
+        ("completionProvider", Json::object([])),
+        ("definitionProvider", true.into()),
+        ("hoverProvider", true.into()),
+        ("referencesProvider", true.into()),
+        ("renameProvider", true.into()),
+        ("documentFormattingProvider", true.into()),
+        ("documentRangeFormattingProvider", true.into()),
+        ("foldingRangeProvider", true.into()),
+        ("selectionRangeProvider", true.into()),
+        (
+            "semanticTokensProvider",
+            Json::object([
+                (
+                    "legend",
+                    Json::object([
+                        ("tokenTypes", names(TOKEN_TYPES)),
+                        ("tokenModifiers", names(TOKEN_MODIFIERS)),
+                    ]),
+                ),
+                ("full", true.into()),
+            ]),
+        ),
+    ]);
+    Json::object([
+        ("capabilities", capabilities),
+        (
+            "serverInfo",
+            Json::object([
+                ("name", "gor".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+fn names(names: &[&str]) -> Json {
+    Json::Array(names.iter().map(|name| Json::from(*name)).collect())
+}
+
+fn response(id: Json, result: Result<Json, ResponseError>) -> Json {
+    let (key, value) = match result {
+        Ok(result) => ("result", result),
+        Err(error) => (
+            "error",
+            Json::object([
+                ("code", error.code.into()),
+                ("message", error.message.into()),
+            ]),
+        ),
+    };
+    Json::object([("jsonrpc", "2.0".into()), ("id", id), (key, value)])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn diagnostic_json(uri: &str, document: &Document, diagnostic: &Diagnostic) -> Json {
+    let severity: usize = match diagnostic.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+    };
+    let mut members = vec![
+        ("range", range_json(document.range(diagnostic.position))),
+        ("severity", severity.into()),
+    ];
+    if let Some(code) = diagnostic.explanation.or(diagnostic.code) {
+        members.push(("code", code.into()));
+    }
+    members.push(("source", "gor".into()));
+    members.push(("message", diagnostic.message.as_str().into()));
+    if !diagnostic.notes.is_empty() {
+        let related = diagnostic
+            .notes
+            .iter()
+            .map(|(message, position)| {
+                Json::object([
+                    ("location", location_json(uri, document, *position)),
+                    ("message", message.as_str().into()),
+                ])
+            })
+            .collect::<Vec<Json>>();
+        members.push(("relatedInformation", related.into()));
+    }
+    Json::object(members)
+}
+
+fn location_json(uri: &str, document: &Document, position: Position) -> Json {
+    Json::object([
+        ("uri", uri.into()),
+        ("range", range_json(document.range(position))),
+    ])
+}
+
+fn position_json(position: LspPosition) -> Json {
+    Json::object([
+        ("line", position.line.into()),
+        ("character", position.character.into()),
+    ])
+}
+
+fn range_json((start, end): (LspPosition, LspPosition)) -> Json {
+    Json::object([("start", position_json(start)), ("end", position_json(end))])
+}
+
+fn lsp_position(json: &Json) -> Result<LspPosition, ResponseError> {
+    let number = |key| {
+        member(json, key)?
+            .as_usize()
+            .ok_or_else(|| ResponseError::invalid_params(format!("{} isn't a position", key)))
+    };
+    Ok(LspPosition::new(number("line")?, number("character")?))
+}
+
+/// The bytes of the document a range covers
+fn offset_range(document: &Document, range: &Json) -> Result<Range<usize>, ResponseError> {
+    let start = document.offset(lsp_position(member(range, "start")?)?);
+    let end = document.offset(lsp_position(member(range, "end")?)?);
+    Ok(start..end.max(start))
+}
+
+/// The formatting the client's options ask for. Only indenting with spaces changes what gofmt
+/// does, which never leaves trailing whitespace and ends the file with one line break.
+fn formatter_config(options: &Json) -> FormatterConfig {
+    let spaces = options.get("insertSpaces").and_then(Json::as_bool);
+    let width = options.get("tabSize").and_then(Json::as_usize);
+    match (spaces, width) {
+        (Some(true), Some(width)) => FormatterConfig::default().with_indent_spaces(width),
+        _ => FormatterConfig::default(),
+    }
+}
+
+fn member<'a>(json: &'a Json, key: &str) -> Result<&'a Json, ResponseError> {
+    json.get(key)
+        .ok_or_else(|| ResponseError::invalid_params(format!("missing {}", key)))
+}
+
+fn string<'a>(json: &'a Json, key: &str) -> Result<&'a str, ResponseError> {
+    member(json, key)?
+        .as_str()
+        .ok_or_else(|| ResponseError::invalid_params(format!("{} isn't a string", key)))
+}
+
+fn integer(json: &Json, key: &str) -> Result<i64, ResponseError> {
+    member(json, key)?
+        .as_i64()
+        .ok_or_else(|| ResponseError::invalid_params(format!("{} isn't an integer", key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///app/main.go";
+
+    /// Serve the messages, returning what was sent back and whether the client shut the
+    /// server down first
+    fn serve(messages: &[&str]) -> (Vec<String>, bool) {
+        let mut input = Vec::new();
+        for message in messages {
+            write_message(&mut input, message).unwrap();
+        }
+        let mut output = Vec::new();
+        let shut_down = Server::new().serve(input.as_slice(), &mut output).unwrap();
+        let mut output = output.as_slice();
+        let mut sent = Vec::new();
+        while let Some(message) = read_message(&mut output).unwrap() {
+            sent.push(message);
+        }
+        (sent, shut_down)
+    }
+
+    fn initialize(id: usize) -> String {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"initialize","params":{{}}}}"#,
+            id
+        )
+    }
+
+    #[test]
+    fn serve_lifecycle() {
+        let (sent, shut_down) = serve(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#,
+            &initialize(2),
+            r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":"three","method":"textDocument/unknown","params":{}}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","id":5,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","method":"exit"}"#,
+            r#"{"jsonrpc":"2.0","id":6,"method":"shutdown"}"#,
+        ]);
+        assert!(shut_down);
+        let initialized = Json::parse(&sent[1]).unwrap();
+        let result = initialized.get("result").unwrap();
+        assert_eq!(
+            result
+                .get("capabilities")
+                .and_then(|capabilities| capabilities.get("textDocumentSync"))
+                .map(ToString::to_string)
+                .as_deref(),
+            Some(r#"{"openClose":true,"change":2}"#)
+        );
+        assert_eq!(
+            result.get("serverInfo").map(ToString::to_string),
+            Some(format!(
+                r#"{{"name":"gor","version":"{}"}}"#,
+                env!("CARGO_PKG_VERSION")
+            ))
+        );
+        assert_eq!(
+            [&sent[..1], &sent[2..]].concat(),
+            [
+                r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32002,"message":"the server hasn't been initialized"}}"#,
+                r#"{"jsonrpc":"2.0","id":"three","error":{"code":-32601,"message":"unknown method textDocument/unknown"}}"#,
+                r#"{"jsonrpc":"2.0","id":4,"result":null}"#,
+                r#"{"jsonrpc":"2.0","id":5,"error":{"code":-32600,"message":"the server has shut down"}}"#,
+            ]
+        );
+
+        let (sent, shut_down) = serve(&[&initialize(1), r#"{"method":"exit"}"#]);
+        assert_eq!(sent.len(), 1);
+        assert!(!shut_down);
+    }
+
+    #[test]
+    fn serve_invalid_messages() {
+        let (sent, _) = serve(&[
+            "{\"id\":",
+            &initialize(1),
+            r#"{"jsonrpc":"2.0","id":2}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/completion","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{}}"#,
+        ]);
+        assert_eq!(
+            sent[0],
+            r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"invalid JSON at byte 6: expected a value, not the end of the text"}}"#
+        );
+        assert_eq!(
+            sent[2..],
+            [
+                r#"{"jsonrpc":"2.0","id":2,"error":{"code":-32600,"message":"a request has no method"}}"#,
+                r#"{"jsonrpc":"2.0","id":3,"error":{"code":-32602,"message":"missing textDocument"}}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_semantic_tokens() {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"go","version":1,"text":"package main\n\nvar s = \"é\" // nil\nvar t = nil\n"}}}}}}"#,
+            URI
+        );
+        let tokens = format!(
+            r#"{{"jsonrpc":"2.0","id":2,"method":"textDocument/semanticTokens/full","params":{{"textDocument":{{"uri":"{}"}}}}}}"#,
+            URI
+        );
+        let (sent, _) = serve(&[&initialize(1), &open, &tokens]);
+        // Columns and lengths are counted in UTF-16, so the comment starts a character
+        // closer to the string than it does a byte
+        assert_eq!(
+            sent[2],
+            r#"{"jsonrpc":"2.0","id":2,"result":{"data":[0,0,7,0,0,2,0,3,0,0,0,4,1,9,0,0,2,1,4,0,0,2,3,2,0,0,4,6,1,0,1,0,3,0,0,0,4,1,9,0,0,2,1,4,0,0,2,3,9,1]}}"#
+        );
+    }
+
+    #[test]
+    fn serve_definitions() {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"go","version":1,"text":"package main\n\nfunc main() {{\n\tcount := 1\n\tprintln(count, len(\"\"))\n}}\n"}}}}}}"#,
+            URI
+        );
+        let definition = |id: usize, character: usize| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/definition","params":{{"textDocument":{{"uri":"{}"}},"position":{{"line":4,"character":{}}}}}}}"#,
+                id, URI, character
+            )
+        };
+        let (sent, _) = serve(&[
+            &initialize(1),
+            &open,
+            &definition(2, 11),
+            &definition(3, 17),
+        ]);
+        assert_eq!(
+            sent[2..],
+            [
+                format!(
+                    r#"{{"jsonrpc":"2.0","id":2,"result":{{"uri":"{}","range":{{"start":{{"line":3,"character":1}},"end":{{"line":3,"character":6}}}}}}}}"#,
+                    URI
+                ),
+                r#"{"jsonrpc":"2.0","id":3,"result":null}"#.to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_hovers() {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"go","version":1,"text":"package main\n\n// double gives twice n.\nfunc double(n int) int {{\n\treturn n * 2\n}}\n"}}}}}}"#,
+            URI
+        );
+        let hover = |id: usize, line: usize, character: usize| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/hover","params":{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}}}}}}"#,
+                id, URI, line, character
+            )
+        };
+        let (sent, _) = serve(&[
+            &initialize(1),
+            &open,
+            &hover(2, 4, 8),
+            &hover(3, 3, 0),
+            &hover(4, 4, 12),
+        ]);
+        assert_eq!(
+            sent[2..],
+            [
+                r#"{"jsonrpc":"2.0","id":2,"result":{"contents":{"kind":"markdown","value":"```go\nvar n int\n```"},"range":{"start":{"line":4,"character":8},"end":{"line":4,"character":9}}}}"#,
+                r#"{"jsonrpc":"2.0","id":3,"result":{"contents":{"kind":"markdown","value":"```go\nfunc double(int) int\n```\n\ndouble gives twice n."},"range":{"start":{"line":3,"character":0},"end":{"line":3,"character":4}}}}"#,
+                r#"{"jsonrpc":"2.0","id":4,"result":null}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_references() {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"go","version":1,"text":"package main\n\nfunc main() {{\n\tcount := 1\n\tprintln(count)\n}}\n"}}}}}}"#,
+            URI
+        );
+        let references = |id: usize, declaration: bool| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/references","params":{{"textDocument":{{"uri":"{}"}},"position":{{"line":4,"character":9}},"context":{{"includeDeclaration":{}}}}}}}"#,
+                id, URI, declaration
+            )
+        };
+        let (sent, _) = serve(&[
+            &initialize(1),
+            &open,
+            &references(2, true),
+            &references(3, false),
+        ]);
+        assert_eq!(
+            sent[2..],
+            [
+                format!(
+                    r#"{{"jsonrpc":"2.0","id":2,"result":[{{"uri":"{0}","range":{{"start":{{"line":3,"character":1}},"end":{{"line":3,"character":6}}}}}},{{"uri":"{0}","range":{{"start":{{"line":4,"character":9}},"end":{{"line":4,"character":14}}}}}}]}}"#,
+                    URI
+                ),
+                format!(
+                    r#"{{"jsonrpc":"2.0","id":3,"result":[{{"uri":"{}","range":{{"start":{{"line":4,"character":9}},"end":{{"line":4,"character":14}}}}}}]}}"#,
+                    URI
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_renames() {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"go","version":1,"text":"package main\n\nfunc main() {{\n\tcount := 1\n\tprintln(count)\n}}\n"}}}}}}"#,
+            URI
+        );
+        let rename = |id: usize, name: &str| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/rename","params":{{"textDocument":{{"uri":"{}"}},"position":{{"line":4,"character":9}},"newName":"{}"}}}}"#,
+                id, URI, name
+            )
+        };
+        let (sent, _) = serve(&[
+            &initialize(1),
+            &open,
+            &rename(2, "total"),
+            &rename(3, "func"),
+        ]);
+        assert_eq!(
+            sent[2..],
+            [
+                format!(
+                    r#"{{"jsonrpc":"2.0","id":2,"result":{{"changes":{{"{}":[{{"range":{{"start":{{"line":3,"character":1}},"end":{{"line":3,"character":6}}}},"newText":"total"}},{{"range":{{"start":{{"line":4,"character":9}},"end":{{"line":4,"character":14}}}},"newText":"total"}}]}}}}}}"#,
+                    URI
+                ),
+                r#"{"jsonrpc":"2.0","id":3,"error":{"code":-32803,"message":"func is not a valid identifier"}}"#.to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_formatting() {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"go","version":1,"text":"package main\n\nfunc main() {{\nx := 1\n}}\n"}}}}}}"#,
+            URI
+        );
+        let format = |id: usize, spaces: bool| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/formatting","params":{{"textDocument":{{"uri":"{}"}},"options":{{"tabSize":2,"insertSpaces":{}}}}}}}"#,
+                id, URI, spaces
+            )
+        };
+        let format_range = format!(
+            r#"{{"jsonrpc":"2.0","id":4,"method":"textDocument/rangeFormatting","params":{{"textDocument":{{"uri":"{}"}},"range":{{"start":{{"line":0,"character":0}},"end":{{"line":3,"character":0}}}},"options":{{"tabSize":4,"insertSpaces":false}}}}}}"#,
+            URI
+        );
+        let (sent, _) = serve(&[
+            &initialize(1),
+            &open,
+            &format(2, false),
+            &format(3, true),
+            &format_range,
+        ]);
+        assert_eq!(
+            sent[2..],
+            [
+                r#"{"jsonrpc":"2.0","id":2,"result":[{"range":{"start":{"line":3,"character":0},"end":{"line":4,"character":0}},"newText":"\tx := 1\n"}]}"#,
+                r#"{"jsonrpc":"2.0","id":3,"result":[{"range":{"start":{"line":3,"character":0},"end":{"line":4,"character":0}},"newText":"  x := 1\n"}]}"#,
+                r#"{"jsonrpc":"2.0","id":4,"result":[]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_folding_and_selection_ranges() {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"go","version":1,"text":"package main\n\n// main\n// runs\nfunc main() {{\n\tprintln(1 + 2)\n}}\n"}}}}}}"#,
+            URI
+        );
+        let fold = format!(
+            r#"{{"jsonrpc":"2.0","id":2,"method":"textDocument/foldingRange","params":{{"textDocument":{{"uri":"{}"}}}}}}"#,
+            URI
+        );
+        let select = format!(
+            r#"{{"jsonrpc":"2.0","id":3,"method":"textDocument/selectionRange","params":{{"textDocument":{{"uri":"{}"}},"positions":[{{"line":5,"character":13}},{{"line":0,"character":0}}]}}}}"#,
+            URI
+        );
+        let (sent, _) = serve(&[&initialize(1), &open, &fold, &select]);
+        assert_eq!(
+            sent[2..],
+            [
+                r#"{"jsonrpc":"2.0","id":2,"result":[{"startLine":2,"endLine":3,"kind":"comment"},{"startLine":4,"endLine":5}]}"#,
+                r#"{"jsonrpc":"2.0","id":3,"result":[{"range":{"start":{"line":5,"character":13},"end":{"line":5,"character":14}},"parent":{"range":{"start":{"line":5,"character":9},"end":{"line":5,"character":14}},"parent":{"range":{"start":{"line":5,"character":1},"end":{"line":5,"character":15}},"parent":{"range":{"start":{"line":4,"character":0},"end":{"line":6,"character":1}},"parent":{"range":{"start":{"line":0,"character":0},"end":{"line":7,"character":0}}}}}}},{"range":{"start":{"line":0,"character":0},"end":{"line":7,"character":0}}}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_document_changes() {
+        let open = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"{}","languageId":"go","version":1,"text":"package main\n\nfunc main() {{\n\tcount := 1\n\tprintln(cout)\n}}\n"}}}}}}"#,
+            URI
+        );
+        // Fix the typo, then start typing a name
+        let change = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didChange","params":{{"textDocument":{{"uri":"{}","version":2}},"contentChanges":[{{"range":{{"start":{{"line":4,"character":12}},"end":{{"line":4,"character":12}}}},"text":"n"}},{{"range":{{"start":{{"line":4,"character":15}},"end":{{"line":4,"character":15}}}},"text":"\n\tprintln(co"}}]}}}}"#,
+            URI
+        );
+        let complete = format!(
+            r#"{{"jsonrpc":"2.0","id":2,"method":"textDocument/completion","params":{{"textDocument":{{"uri":"{}"}},"position":{{"line":5,"character":11}}}}}}"#,
+            URI
+        );
+        let close = format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/didClose","params":{{"textDocument":{{"uri":"{}"}}}}}}"#,
+            URI
+        );
+        let (sent, _) = serve(&[&initialize(1), &open, &change, &complete, &close]);
+        assert_eq!(
+            sent[1..],
+            [
+                format!(
+                    r#"{{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{{"uri":"{}","version":1,"diagnostics":[{{"range":{{"start":{{"line":4,"character":9}},"end":{{"line":4,"character":13}}}},"severity":1,"code":"E0101","source":"gor","message":"undefined: cout"}}]}}}}"#,
+                    URI
+                ),
+                format!(
+                    r#"{{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{{"uri":"{}","version":2,"diagnostics":[{{"range":{{"start":{{"line":6,"character":0}},"end":{{"line":6,"character":0}}}},"severity":1,"code":"E0002","source":"gor","message":"expected ')' after arguments, found newline"}}]}}}}"#,
+                    URI
+                ),
+                r#"{"jsonrpc":"2.0","id":2,"result":{"isIncomplete":false,"items":[{"label":"count","kind":6},{"label":"comparable","kind":7},{"label":"complex","kind":3},{"label":"complex128","kind":7},{"label":"complex64","kind":7},{"label":"copy","kind":3},{"label":"const","kind":14},{"label":"continue","kind":14}]}}"#.to_string(),
+                format!(
+                    r#"{{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{{"uri":"{}","diagnostics":[]}}}}"#,
+                    URI
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! The Language Server Protocol's base protocol: each message is a header, of which only
+//! `Content-Length` matters, then a blank line and the message's JSON, that many bytes of it
+//! (https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#baseProtocol)
+
+use std::io::{self, BufRead, Write};
+
+/// The content of the next message, or None if the input ends before another starts
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    let mut started = false;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return match started {
+                true => Err(invalid("the input ended in a message's header")),
+                false => Ok(None),
+            };
+        }
+        started = true;
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(invalid(&format!("invalid header line: {}", line)));
+        };
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            let value = value.trim();
+            length = Some(
+                value
+                    .parse::<usize>()
+                    .map_err(|_| invalid(&format!("invalid Content-Length: {}", value)))?,
+            );
+        }
+    }
+    let Some(length) = length else {
+        return Err(invalid("a message's header has no Content-Length"));
+    };
+    let mut content = vec![0; length];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|_| invalid("a message isn't UTF-8"))
+}
+
+/// Write `content` as a message, with its header
+pub fn write_message(output: &mut impl Write, content: &str) -> io::Result<()> {
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_and_write_messages() {
+        let mut output = Vec::new();
+        write_message(&mut output, "{\"a\":\"é\"}").unwrap();
+        write_message(&mut output, "[]").unwrap();
+        assert_eq!(
+            String::from_utf8(output.clone()).unwrap(),
+            "Content-Length: 10\r\n\r\n{\"a\":\"é\"}Content-Length: 2\r\n\r\n[]"
+        );
+
+        let mut input = output.as_slice();
+        assert_eq!(
+            read_message(&mut input).unwrap().as_deref(),
+            Some("{\"a\":\"é\"}")
+        );
+        assert_eq!(read_message(&mut input).unwrap().as_deref(), Some("[]"));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+
+        let mut input = "content-length: 2\r\nContent-Type: x\r\n\r\n{}".as_bytes();
+        assert_eq!(read_message(&mut input).unwrap().as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn read_invalid_messages() {
+        let error = |input: &str| read_message(&mut input.as_bytes()).unwrap_err().to_string();
+        assert_eq!(
+            error("Content-Type: x\r\n\r\n{}"),
+            "a message's header has no Content-Length"
+        );
+        assert_eq!(
+            error("Content-Length: two\r\n\r\n"),
+            "invalid Content-Length: two"
+        );
+        assert_eq!(
+            error("Content-Length: 2\r\n"),
+            "the input ended in a message's header"
+        );
+        assert_eq!(
+            error("Content-Length: 4\r\n\r\n{}"),
+            "failed to fill whole buffer"
+        );
+    }
+}
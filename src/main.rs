@@ -1,7 +1,18 @@
-use gor::cli::cli::CLI;
+use gor::cli::cli::{Cli, EXIT_INTERNAL, EXIT_USAGE};
 use std::env;
+use std::panic::{self, AssertUnwindSafe};
 
 fn main() {
-    let cli = CLI::new(env::args().collect());
-    cli.execute();
+    let status = match Cli::parse_from(env::args().collect()) {
+        // A panic that gets this far is a bug in gor, its message already printed by the hook
+        Ok(cli) => panic::catch_unwind(AssertUnwindSafe(|| cli.execute())).unwrap_or_else(|_| {
+            eprintln!("gor: internal error");
+            EXIT_INTERNAL
+        }),
+        Err(error) => {
+            eprintln!("{}", error);
+            EXIT_USAGE
+        }
+    };
+    std::process::exit(status);
 }
@@ -0,0 +1,318 @@
+use std::ops::Range;
+
+use crate::ast::ast::File;
+use crate::lexer::{lexer::Lexer, token_type::TokenKind};
+use crate::parser::parser::{Parser, parse_checked};
+use crate::primitives::{diagnostic::Diagnostic, errors::parser::ParserError};
+
+/// Replace the bytes in `range` of a source file with `text`
+#[derive(Debug, PartialEq, Clone)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, text: &str) -> TextEdit {
+        TextEdit {
+            range,
+            text: text.to_string(),
+        }
+    }
+
+    /// The source after the edit. Panics if the range isn't on char boundaries of `source`.
+    pub fn apply(&self, source: &str) -> String {
+        let mut edited = source.to_string();
+        edited.replace_range(self.range.clone(), &self.text);
+        edited
+    }
+
+    /// How many lines the edit adds, or removes if negative
+    fn line_delta(&self, source: &str) -> isize {
+        let removed = source[self.range.clone()].matches('\n').count();
+        let added = self.text.matches('\n').count();
+        added as isize - removed as isize
+    }
+}
+
+/// 1-based line containing the byte at `offset`
+fn line_at(source: &str, offset: usize) -> usize {
+    source[..offset].matches('\n').count() + 1
+}
+
+/// Byte offset where 1-based `line` begins, or the end of `source` if it has fewer lines
+fn line_start_offset(source: &str, line: usize) -> usize {
+    if line <= 1 {
+        return 0;
+    }
+    source
+        .match_indices('\n')
+        .nth(line - 2)
+        .map_or(source.len(), |(offset, _)| offset + 1)
+}
+
+/// The line after the last line of `prefix` with code on it, where the comments that may
+/// document what follows `prefix` begin
+fn line_after_code(prefix: &str) -> usize {
+    let mut lexer = Lexer::new(prefix);
+    let mut code_end_line = 0;
+    loop {
+        let token = lexer.next_token();
+        match token.kind {
+            Some(TokenKind::EOF) => return code_end_line + 1,
+            Some(TokenKind::Newline | TokenKind::SingleLineComment | TokenKind::BlockComment) => {}
+            _ => code_end_line = token.position.line,
+        }
+    }
+}
+
+impl Parser {
+    /// Parse `old_source` with `edit` applied, reusing the declarations of `old_tree` (the
+    /// result of parsing `old_source` without errors) that lie entirely on lines the edit
+    /// doesn't touch. Only the lines between the reused declarations are reparsed.
+    ///
+    /// Falls back to parsing the whole file when the edit reaches the package clause or imports,
+    /// or when the reparsed region has errors, so the result and any errors always match a
+    /// full parse of the edited source.
+    pub fn reparse(
+        old_tree: File,
+        old_source: &str,
+        edit: &TextEdit,
+    ) -> Result<File, Vec<ParserError>> {
+        let new_source = edit.apply(old_source);
+        match Parser::reparse_region(old_tree, old_source, &new_source, edit) {
+            Some(file) => Ok(file),
+            None => Parser::new(&new_source).parse(),
+        }
+    }
+
+    /// Reparse as `reparse` does, converting every error into a diagnostic as `parse_checked`
+    /// does, for an editor that keeps a file's tree up to date as it's typed in. The tree is
+    /// returned whenever parsing got far enough to produce one, even with errors.
+    pub fn reparse_checked(
+        old_tree: File,
+        old_source: &str,
+        edit: &TextEdit,
+    ) -> (Option<File>, Vec<Diagnostic>) {
+        let new_source = edit.apply(old_source);
+        match Parser::reparse_region(old_tree, old_source, &new_source, edit) {
+            Some(file) => (Some(file), Vec::new()),
+            None => parse_checked(&new_source),
+        }
+    }
+
+    /// The edited tree with only the lines between the declarations the edit leaves alone
+    /// reparsed, or None if the whole file needs parsing again
+    fn reparse_region(
+        old_tree: File,
+        old_source: &str,
+        new_source: &str,
+        edit: &TextEdit,
+    ) -> Option<File> {
+        let edit_start_line = line_at(old_source, edit.range.start);
+        let edit_end_line = line_at(old_source, edit.range.end);
+        let line_delta = edit.line_delta(old_source);
+
+        let File {
+            package_name,
+            imports,
+            mut decls,
+            doc,
+        } = old_tree;
+        let first_declaration_line = match decls.first() {
+            Some(first) if first.position_start.line <= edit_start_line => {
+                first.position_start.line
+            }
+            _ => return None,
+        };
+
+        let reused_before = decls
+            .iter()
+            .take_while(|declaration| declaration.position_end.line < edit_start_line)
+            .count();
+        let reused_after_start = decls
+            .iter()
+            .position(|declaration| declaration.position_start.line > edit_end_line)
+            .unwrap_or(decls.len());
+        let mut reused_after = decls.split_off(reused_after_start);
+        decls.truncate(reused_before);
+
+        // The region runs from the line after the last declaration before the edit, or after the
+        // imports, up to the line before the first declaration after it, in the edited source's
+        // line numbers. It takes in the comments above the first declaration it reparses, which
+        // are its doc comment.
+        let region_start_line = match decls.last() {
+            Some(declaration) => declaration.position_end.line + 1,
+            None => line_after_code(
+                &new_source[..line_start_offset(new_source, first_declaration_line)],
+            ),
+        };
+        let region_start = line_start_offset(new_source, region_start_line);
+        let region_end = match reused_after.first() {
+            Some(declaration) => line_start_offset(
+                new_source,
+                declaration
+                    .position_start
+                    .line
+                    .saturating_add_signed(line_delta),
+            ),
+            None => new_source.len(),
+        };
+
+        let mut parser =
+            Parser::new_at_line(&new_source[region_start..region_end], region_start_line);
+        let reparsed = parser.parse_top_level_declarations();
+        parser.collect_lexer_errors();
+        if !parser.errors.is_empty() {
+            return None;
+        }
+
+        for declaration in &mut reused_after {
+            declaration.shift_lines(line_delta);
+        }
+        // The comments above the first reused declaration are in the region, and may have been
+        // edited, so its doc comment is taken again from what the region's parser lexed
+        if let Some(first) = reused_after.first_mut() {
+            first.doc = parser.doc_comment(first.position_start.line);
+        }
+        decls.extend(reparsed);
+        decls.extend(reused_after);
+        Some(File {
+            doc,
+            ..File::new(package_name, imports, decls)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "package main\n\nimport \"fmt\"\n\n// a prints 1.\nfunc a() {\n\tfmt.Println(1)\n}\n\nfunc b() {\n\tfmt.Println(2)\n}\n\n// c prints 3.\nfunc c() {\n\tfmt.Println(3)\n}\n";
+
+    /// Reparsing must give exactly what parsing the edited source from scratch gives
+    fn assert_reparse_matches_full_parse(replace: &str, text: &str) {
+        let start = SOURCE.find(replace).unwrap();
+        let edit = TextEdit::new(start..start + replace.len(), text);
+        let old_tree = Parser::new(SOURCE).parse().unwrap();
+        let expected = Parser::new(&edit.apply(SOURCE)).parse();
+        assert_eq!(Parser::reparse(old_tree, SOURCE, &edit), expected);
+    }
+
+    #[test]
+    fn reparse_edit_inside_declaration() {
+        assert_reparse_matches_full_parse("Println(2)", "Printf(x + 2)");
+    }
+
+    #[test]
+    fn reparse_shifts_later_declarations() {
+        assert_reparse_matches_full_parse("fmt.Println(2)", "fmt.Println(2)\n\tfmt.Println(22)");
+        assert_reparse_matches_full_parse("\tfmt.Println(2)\n", "");
+    }
+
+    #[test]
+    fn reparse_edit_between_declarations() {
+        assert_reparse_matches_full_parse("}\n\nfunc b", "}\n\nfunc inserted() {\n}\n\nfunc b");
+    }
+
+    #[test]
+    fn reparse_edit_in_doc_comment() {
+        assert_reparse_matches_full_parse("c prints 3.", "c prints three.");
+        assert_reparse_matches_full_parse("// c prints 3.\n", "");
+        assert_reparse_matches_full_parse("}\n\n// c", "}\n// c");
+        assert_reparse_matches_full_parse("func b", "// b prints 2.\nfunc b");
+    }
+
+    #[test]
+    fn reparse_edit_in_imports() {
+        assert_reparse_matches_full_parse("\"fmt\"", "(\n\t\"fmt\"\n\t\"os\"\n)");
+    }
+
+    #[test]
+    fn reparse_with_errors() {
+        assert_reparse_matches_full_parse("Println(2)", "Println(2");
+    }
+
+    #[test]
+    fn reparse_checked_matches_parse_checked() {
+        for (replace, text) in [
+            ("Println(2)", "Printf(x + 2)"),
+            ("Println(2)", "Println(2"),
+            ("func c", "fun c"),
+        ] {
+            let start = SOURCE.find(replace).unwrap();
+            let edit = TextEdit::new(start..start + replace.len(), text);
+            let old_tree = Parser::new(SOURCE).parse().unwrap();
+            assert_eq!(
+                Parser::reparse_checked(old_tree, SOURCE, &edit),
+                parse_checked(&edit.apply(SOURCE))
+            );
+        }
+    }
+
+    /// Pieces of Go that edits splice in, chosen to cut across tokens, comments and
+    /// declarations
+    const FRAGMENTS: &[&str] = &[
+        "",
+        "\n",
+        "\n\n",
+        "}",
+        "{",
+        "(",
+        ")",
+        "// note\n",
+        "/* block */",
+        "/*\n*/",
+        "\"",
+        "'",
+        "`",
+        "x",
+        "func d() {\n}\n",
+        "var v = 1\n",
+        "type T int\n",
+        "import \"os\"\n",
+        "package",
+        "é",
+        "\t",
+        ";",
+    ];
+
+    proptest::proptest! {
+        #[test]
+        fn reparse_matches_full_parse_after_random_edits(
+            start in 0..=SOURCE.len(),
+            length in 0..40usize,
+            pieces in proptest::collection::vec(proptest::sample::select(FRAGMENTS), 0..4),
+        ) {
+            let end = (start + length).min(SOURCE.len());
+            let edit = TextEdit::new(start..end, &pieces.concat());
+            let old_tree = Parser::new(SOURCE).parse().unwrap();
+            let new_source = edit.apply(SOURCE);
+            proptest::prop_assert_eq!(
+                Parser::reparse(old_tree.clone(), SOURCE, &edit),
+                Parser::new(&new_source).parse()
+            );
+            proptest::prop_assert_eq!(
+                Parser::reparse_checked(old_tree, SOURCE, &edit),
+                parse_checked(&new_source)
+            );
+        }
+    }
+
+    #[test]
+    fn reparse_reuses_unaffected_declarations() {
+        let edit = TextEdit::new(0..0, "");
+        let start = SOURCE.find("Println(2)").unwrap();
+        let old_tree = Parser::new(SOURCE).parse().unwrap();
+        let new_tree = Parser::reparse(old_tree.clone(), SOURCE, &edit).unwrap();
+        assert_eq!(new_tree, old_tree);
+
+        let edit = TextEdit::new(start..start, "\n");
+        let new_tree = Parser::reparse(old_tree.clone(), SOURCE, &edit).unwrap();
+        assert_eq!(new_tree.decls[0], old_tree.decls[0]);
+        assert_eq!(
+            new_tree.decls[2].position_start.line,
+            old_tree.decls[2].position_start.line + 1
+        );
+    }
+}
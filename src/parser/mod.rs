@@ -1 +1,3 @@
+pub mod incremental;
+#[allow(clippy::module_inception)]
 pub mod parser;
@@ -1,32 +1,221 @@
-use crate::ast::{ast::Program, expression::Expression, statement::Statement};
+use std::collections::VecDeque;
+
+use log::debug;
+
+use crate::ast::{
+    arena::ArenaFile,
+    ast::{File, ImportSpec},
+    declaration::{Declaration, DeclarationKind, Parameter, VariableSpec},
+    expression::{Expression, ExpressionKind, KeyedElement, UnaryOperator},
+    statement::{SelectCase, Statement, StatementKind, SwitchCase},
+    type_expression::{
+        ChannelDirection, InterfaceMethod, StructField, TypeExpression, TypeExpressionKind,
+    },
+};
 use crate::lexer::{
     lexer::Lexer,
     token::Token,
-    token_type::{Keyword, TokenKind},
+    token_type::{Keyword, Operator, TokenKind},
 };
 use crate::primitives::{
-    errors::parser::{ParserError, ParserErrorKind},
+    diagnostic::Diagnostic,
+    errors::{
+        lexer::LexerError,
+        parser::{ParserError, ParserErrorKind},
+    },
     position::Position,
 };
 
+/// Where the parser pulls its tokens from
+enum TokenSource {
+    Lexer(Lexer),
+    Tokens {
+        tokens: std::vec::IntoIter<Token>,
+        last_position: Position,
+    },
+}
+
+impl TokenSource {
+    /// Errors from lexing, taken so they're only reported once
+    fn take_errors(&mut self) -> Vec<LexerError> {
+        match self {
+            TokenSource::Lexer(lexer) => lexer.take_errors(),
+            TokenSource::Tokens { .. } => Vec::new(),
+        }
+    }
+
+    /// A token vector that runs out without an EOF token ends with a synthesized one
+    fn next_raw_token(&mut self) -> Token {
+        match self {
+            TokenSource::Lexer(lexer) => loop {
+                let token = lexer.next_token();
+                // The lexer has already recorded an error for anything it couldn't classify
+                if token.kind.is_some() {
+                    return token;
+                }
+            },
+            TokenSource::Tokens {
+                tokens,
+                last_position,
+            } => match tokens.next() {
+                Some(token) => {
+                    *last_position = token.position;
+                    token
+                }
+                None => Token::new_with_kind(TokenKind::EOF, "", *last_position),
+            },
+        }
+    }
+
+    /// Pull the next token, applying Go's automatic semicolon insertion: a newline becomes a
+    /// ';' when the preceding token allows it, and is skipped otherwise. Comments are moved
+    /// into `comments`; one spanning lines counts as a newline.
+    fn next_token(&mut self, insert_semicolon: bool, comments: &mut Vec<Token>) -> Token {
+        loop {
+            let token = self.next_raw_token();
+            let is_newline = match token.kind {
+                Some(TokenKind::Newline) => true,
+                Some(TokenKind::SingleLineComment) => false,
+                Some(TokenKind::BlockComment) => token.value.contains('\n'),
+                _ => return token,
+            };
+            let position = token.position;
+            if token.kind != Some(TokenKind::Newline) {
+                comments.push(token);
+            }
+            if is_newline && insert_semicolon {
+                return Token::new_with_kind(TokenKind::Semicolon, "\n", position);
+            }
+        }
+    }
+
+    /// Pull the token that follows `previous` in the stream, staying at EOF once it's reached
+    fn token_after(&mut self, previous: &Token, comments: &mut Vec<Token>) -> Token {
+        if previous.kind == Some(TokenKind::EOF) {
+            return Token::new_with_kind(TokenKind::EOF, "", previous.position);
+        }
+        self.next_token(previous.should_insert_semicolon(), comments)
+    }
+}
+
 pub struct Parser {
-    lexer: Lexer,
+    source: TokenSource,
     pub current_token: Token,
     pub peek_token: Token,
+    /// Tokens after `peek_token` that were pulled early by `peek_nth`
+    lookahead: VecDeque<Token>,
     pub errors: Vec<ParserError>,
+    /// Every comment seen so far, in source order
+    pub comments: Vec<Token>,
+    /// Comments before this index can no longer trail a statement
+    attached_comments: usize,
+    /// The line the last token of code consumed is on, e.g. the previous declaration's closing
+    /// brace. Semicolons inserted at a newline sit on the line after the code they end, so
+    /// they don't count.
+    code_end_line: usize,
+    /// How many recursive productions are currently being parsed, see `nested`
+    depth: usize,
+    tokens_consumed: usize,
+    /// Whether an if or for statement's header is being parsed, where `T{` starts the
+    /// statement's block rather than a composite literal, see `with_control_clause`
+    control_clause: bool,
+}
+
+/// Deep enough for any real program while keeping the recursive descent well within the stack
+const MAX_NESTING_DEPTH: usize = 100;
+
+/// The header of a for statement, before the body
+enum ForClause {
+    // The init statement, condition and post statement, any of which may be missing
+    Clauses(
+        Option<Box<Statement>>,
+        Option<Expression>,
+        Option<Box<Statement>>,
+    ),
+    Range {
+        variables: Vec<Expression>,
+        define: bool,
+    },
+}
+
+/// The production being parsed when an error occurred, which decides where parsing can resume
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum RecoveryContext {
+    TopLevel,
+    Block,
+}
+
+impl RecoveryContext {
+    fn is_sync_keyword(&self, keyword: Keyword) -> bool {
+        match self {
+            RecoveryContext::TopLevel => matches!(
+                keyword,
+                Keyword::Package
+                    | Keyword::Import
+                    | Keyword::Func
+                    | Keyword::Type
+                    | Keyword::Var
+                    | Keyword::Const
+            ),
+            // Keywords that start a statement, plus switch/select clauses
+            RecoveryContext::Block => matches!(
+                keyword,
+                Keyword::Break
+                    | Keyword::Case
+                    | Keyword::Const
+                    | Keyword::Continue
+                    | Keyword::Default
+                    | Keyword::Defer
+                    | Keyword::Fallthrough
+                    | Keyword::For
+                    | Keyword::Go
+                    | Keyword::Goto
+                    | Keyword::If
+                    | Keyword::Return
+                    | Keyword::Select
+                    | Keyword::Switch
+                    | Keyword::Type
+                    | Keyword::Var
+            ),
+        }
+    }
 }
 
 impl Parser {
     pub fn new(input: &str) -> Parser {
-        let mut lexer = Lexer::new(input);
-        let current_token = Token::new_before_start();
-        let peek_token = lexer.next_token();
-        Parser {
-            lexer,
-            current_token,
-            peek_token,
+        Parser::from_source(TokenSource::Lexer(Lexer::new(input)))
+    }
+
+    /// Parse `input` as though it starts on line `first_line` of a larger file
+    pub(crate) fn new_at_line(input: &str, first_line: usize) -> Parser {
+        Parser::from_source(TokenSource::Lexer(Lexer::new_at_line(input, first_line)))
+    }
+
+    /// Parse tokens that were already produced, e.g. by an earlier lexer run or synthesized in
+    /// a test. Newline tokens are handled the same way as when lexing from source.
+    pub fn from_tokens(tokens: Vec<Token>) -> Parser {
+        Parser::from_source(TokenSource::Tokens {
+            tokens: tokens.into_iter(),
+            last_position: Position::new(1, 0, 0),
+        })
+    }
+
+    fn from_source(source: TokenSource) -> Parser {
+        let mut parser = Parser {
+            source,
+            current_token: Token::new_before_start(),
+            peek_token: Token::new_before_start(),
+            lookahead: VecDeque::new(),
             errors: Vec::new(),
-        }
+            comments: Vec::new(),
+            attached_comments: 0,
+            code_end_line: 0,
+            depth: 0,
+            tokens_consumed: 0,
+            control_clause: false,
+        };
+        parser.peek_token = parser.source.next_token(false, &mut parser.comments);
+        parser
     }
 
     pub fn dump_errors(&self) -> String {
@@ -39,320 +228,2312 @@ impl Parser {
     }
 
     fn advance(&mut self) -> &Token {
-        self.current_token = self.peek_token.clone();
-
-        if self.current_token.kind != Some(TokenKind::EOF) {
-            self.peek_token = self.lexer.next_token();
-        } else {
-            self.peek_token = Token::new_with_kind(TokenKind::EOF, "", self.current_token.position);
+        let next = match self.lookahead.pop_front() {
+            Some(token) => token,
+            None => self
+                .source
+                .token_after(&self.peek_token, &mut self.comments),
+        };
+        self.current_token = std::mem::replace(&mut self.peek_token, next);
+        self.tokens_consumed += 1;
+        if !matches!(
+            (self.current_token.kind, self.current_token.value.as_str()),
+            (Some(TokenKind::Semicolon), "\n") | (Some(TokenKind::EOF), _)
+        ) {
+            self.code_end_line = self.current_token.position.line;
         }
 
-        return &self.current_token;
+        &self.current_token
     }
 
     fn peek(&self) -> &Token {
         &self.peek_token
     }
 
+    /// Look `n` tokens past the current one without consuming anything; `peek_nth(0)` is the
+    /// same token as `peek()`. Needed where one token of lookahead can't tell productions
+    /// apart, e.g. `x := 1` vs `x + 1`, or `label:` vs an expression.
+    pub fn peek_nth(&mut self, n: usize) -> &Token {
+        if n == 0 {
+            return &self.peek_token;
+        }
+        while self.lookahead.len() < n {
+            let previous = self.lookahead.back().unwrap_or(&self.peek_token);
+            let token = self.source.token_after(previous, &mut self.comments);
+            self.lookahead.push_back(token);
+        }
+        &self.lookahead[n - 1]
+    }
+
     fn expect_token(&mut self, kind: TokenKind) -> Result<&Token, ParserError> {
         if self.peek().kind == Some(kind) {
             return Ok(self.advance());
         }
-        let error = ParserError::new(
-            ParserErrorKind::UnexpectedToken(self.peek().value.clone()),
-            self.peek().position,
-        );
-        self.errors.push(error.clone());
-        self.synchronize();
-        return Err(error);
+        Err(self.expected(vec![kind], None))
     }
 
-    pub fn parse(&mut self) -> Result<Program, Vec<ParserError>> {
-        let mut statements = Vec::new();
-        if !self.errors.is_empty() {
-            return Err(self.errors.clone());
-        } else {
-            while !matches!(self.peek().kind, Some(TokenKind::EOF)) {
-                match self.parse_statement() {
-                    Ok(statement) => {
-                        statements.push(statement);
-                    }
-                    Err(error) => {
-                        self.errors.push(error);
-                        // Try to recover by synchronizing to next statement boundary
-                        self.synchronize();
-                        // Skip the current problematic token to avoid infinite loop
-                        if !matches!(self.peek().kind, Some(TokenKind::EOF)) {
-                            self.advance();
-                        }
-                    }
-                }
-            }
-            Ok(Program { statements })
+    /// Like `expect_token`, with `context` describing where the token was expected for the
+    /// error message, e.g. "after arguments"
+    fn expect_token_with_context(
+        &mut self,
+        kind: TokenKind,
+        context: &'static str,
+    ) -> Result<&Token, ParserError> {
+        if self.peek().kind == Some(kind) {
+            return Ok(self.advance());
         }
+        Err(self.expected(vec![kind], Some(context)))
     }
 
-    fn parse_statement(&mut self) -> Result<Statement, ParserError> {
-        match self.peek().kind {
-            Some(TokenKind::Keyword(Keyword::Package)) => self.parse_package_declaration(),
-            Some(TokenKind::Keyword(Keyword::Import)) => self.parse_import_declaration(),
-            Some(TokenKind::Keyword(Keyword::Func)) => self.parse_function_declaration(),
-            _ => {
-                // Default to expression statement
-                self.parse_expression_statement()
-            }
-        }
+    /// Build an error reporting that one of `expected` should have come next instead of the
+    /// peeked token
+    fn expected(&self, expected: Vec<TokenKind>, context: Option<&'static str>) -> ParserError {
+        ParserError::new(
+            ParserErrorKind::Expected {
+                expected,
+                found: self.peek().clone(),
+                context,
+            },
+            self.peek().position,
+        )
     }
 
-    fn parse_expression_statement(&mut self) -> Result<Statement, ParserError> {
-        let expression = self.parse_expression()?;
-        let start_position = expression.position_start;
-        let end_position = self.handle_semicolon_insertion()?;
-        Ok(Statement::new_expression_statement(
-            expression,
-            start_position,
-            end_position,
-        ))
+    /// Parse a standalone expression such as `a + b * c`, without a surrounding file
+    pub fn parse_expression_str(input: &str) -> Result<Expression, Vec<ParserError>> {
+        let mut parser = Parser::new(input);
+        let result = parser.parse_expression();
+        if let Ok(expression) = &result {
+            parser.check_blank_identifiers(expression);
+        }
+        parser.finish_fragment(result)
     }
 
-    fn parse_package_declaration(&mut self) -> Result<Statement, ParserError> {
-        let package_token = self.expect_token(TokenKind::Keyword(Keyword::Package))?;
-        let package_pos = package_token.position;
-        let name_token = self.expect_token(TokenKind::Identifier)?;
-        let name_value = name_token.value.clone();
-        let end_position = self.handle_semicolon_insertion()?;
-
-        Ok(Statement::new_package_declaration(
-            name_value,
-            package_pos,
-            end_position,
-        ))
+    /// Parse a single statement such as `fmt.Println(x)`, without a surrounding function
+    pub fn parse_statement_str(input: &str) -> Result<Statement, Vec<ParserError>> {
+        let mut parser = Parser::new(input);
+        let result = parser.parse_statement();
+        parser.finish_fragment(result)
     }
 
-    fn parse_import_declaration(&mut self) -> Result<Statement, ParserError> {
-        let import_token = self.expect_token(TokenKind::Keyword(Keyword::Import))?;
-        let import_pos = import_token.position;
-        let path_token = self.expect_token(TokenKind::StringLiteral)?;
-        let path_value = path_token.value.clone();
-        let end_position = self.handle_semicolon_insertion()?;
-
-        Ok(Statement::new_import_declaration(
-            path_value,
-            import_pos,
-            end_position,
-        ))
+    /// Check a fragment parse consumed all of the input (bar a trailing semicolon) and fold
+    /// the result together with any errors recorded along the way
+    fn finish_fragment<T>(
+        &mut self,
+        result: Result<T, ParserError>,
+    ) -> Result<T, Vec<ParserError>> {
+        let result = result.and_then(|value| {
+            if self.peek().kind == Some(TokenKind::Semicolon) {
+                self.advance();
+            }
+            self.expect_token(TokenKind::EOF)?;
+            Ok(value)
+        });
+        if let Err(error) = result {
+            self.errors.push(error);
+            self.collect_lexer_errors();
+            return Err(self.errors.clone());
+        }
+        self.collect_lexer_errors();
+        match result {
+            Ok(value) if self.errors.is_empty() => Ok(value),
+            _ => Err(self.errors.clone()),
+        }
     }
 
-    fn parse_function_declaration(&mut self) -> Result<Statement, ParserError> {
-        let func_token = self.expect_token(TokenKind::Keyword(Keyword::Func))?;
-        let func_pos = func_token.position;
-        let name_token = self.expect_token(TokenKind::Identifier)?;
-        let func_name = name_token.value.clone();
+    /// Merge errors from lexing into the parser's errors, keeping them in source order and,
+    /// as go does, only the first syntax error on each line, since the rest usually follow
+    /// from it. A lexing error comes first on its line, as the likeliest cause of the rest.
+    /// Returns whether there were any.
+    pub(crate) fn collect_lexer_errors(&mut self) -> bool {
+        let lexer_errors = self.source.take_errors();
+        let had_errors = !lexer_errors.is_empty();
+        self.errors.extend(lexer_errors.into_iter().map(|error| {
+            let position = error.position;
+            ParserError::new(ParserErrorKind::LexerError(error), position)
+        }));
+        self.errors.sort_by_key(|error| {
+            let lexer_error = matches!(error.kind, ParserErrorKind::LexerError(_));
+            (
+                error.position.line,
+                !lexer_error,
+                error.position.column_start,
+            )
+        });
+        let mut line = None;
+        self.errors.retain(|error| {
+            // Not a syntax error to go, which reports every one
+            if error.kind == ParserErrorKind::BlankIdentifierAsValue {
+                return true;
+            }
+            let first = line != Some(error.position.line);
+            line = Some(error.position.line);
+            first
+        });
+        had_errors
+    }
 
-        self.expect_token(TokenKind::LeftParen)?;
-        // TODO: Implement parameter parsing
-        self.expect_token(TokenKind::RightParen)?;
+    pub fn parse(&mut self) -> Result<File, Vec<ParserError>> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.clone());
+        }
 
-        self.expect_token(TokenKind::LeftBrace)?;
-        let mut body_statements = Vec::new();
+        let doc = self.doc_comment(self.peek().position.line);
+        let started_at = self.tokens_consumed;
+        let package_name = match self.parse_package_clause() {
+            Ok(name) => name,
+            Err(error) => {
+                self.recover(error, RecoveryContext::TopLevel, started_at);
+                String::new()
+            }
+        };
 
-        while !matches!(self.peek().kind, Some(TokenKind::RightBrace)) {
-            if matches!(self.peek().kind, Some(TokenKind::EOF)) {
-                return Err(ParserError::new(
-                    ParserErrorKind::UnexpectedToken(
-                        "Expected '}' to close function body".to_string(),
-                    ),
-                    self.peek().position,
-                ));
+        let mut imports = Vec::new();
+        while matches!(self.peek().kind, Some(TokenKind::Keyword(Keyword::Import))) {
+            let started_at = self.tokens_consumed;
+            match self.parse_import_declaration() {
+                Ok(specs) => imports.extend(specs),
+                Err(error) => self.recover(error, RecoveryContext::TopLevel, started_at),
             }
-            body_statements.push(self.parse_statement()?);
         }
 
-        let right_brace = self.expect_token(TokenKind::RightBrace)?;
-        let end_pos = right_brace.position;
+        let decls = self.parse_top_level_declarations();
 
-        Ok(Statement::new_function_declaration(
-            func_name,
-            Vec::new(), // No parameters for now
-            body_statements,
-            func_pos,
-            end_pos,
-        ))
+        let failed = self.collect_lexer_errors();
+        debug!(
+            "parsed package {}: {} imports, {} declarations, {} errors",
+            package_name,
+            imports.len(),
+            decls.len(),
+            self.errors.len()
+        );
+        if failed {
+            return Err(self.errors.clone());
+        }
+        Ok(File {
+            doc,
+            ..File::new(package_name, imports, decls)
+        })
     }
 
-    fn parse_expression(&mut self) -> Result<Expression, ParserError> {
-        let left = self.parse_binary_expression(0);
-        left
+    /// Parse declarations until EOF, recording errors and recovering as it goes
+    pub(crate) fn parse_top_level_declarations(&mut self) -> Vec<Declaration> {
+        let mut decls = Vec::new();
+        while !matches!(self.peek().kind, Some(TokenKind::EOF)) {
+            let started_at = self.tokens_consumed;
+            let doc = self.doc_comment(self.peek().position.line);
+            match self.parse_top_level_declaration() {
+                Ok(Some(declaration)) => decls.push(Declaration { doc, ..declaration }),
+                Ok(None) => {}
+                Err(error) => self.recover(error, RecoveryContext::TopLevel, started_at),
+            }
+        }
+        decls
     }
 
-    fn parse_binary_expression(&mut self, min_precedence: u8) -> Result<Expression, ParserError> {
-        let mut left = self.parse_primary_expression()?;
-        loop {
-            let next = self.peek();
-            match next.kind {
-                Some(TokenKind::Operator(op)) => {
-                    if op.precedence() < min_precedence {
-                        return Ok(left);
-                    }
+    /// Like `parse`, with the file's expressions and statements stored in an `AstArena`
+    pub fn parse_arena(&mut self) -> Result<ArenaFile, Vec<ParserError>> {
+        self.parse().map(ArenaFile::from_file)
+    }
+
+    /// Record the error and skip to a point where the enclosing production can resume.
+    /// `started_at` is the token count when the failed production began, used to guarantee
+    /// progress when the error occurred on a token that is itself a sync point.
+    fn recover(&mut self, error: ParserError, context: RecoveryContext, started_at: usize) {
+        self.errors.push(error);
+        self.synchronize(context);
+        match self.peek().kind {
+            Some(TokenKind::EOF) => {}
+            Some(TokenKind::Semicolon) => {
+                self.advance();
+            }
+            _ => {
+                // Skip the current problematic token to avoid infinite loop
+                if self.tokens_consumed == started_at {
                     self.advance();
-                    let right = self.parse_binary_expression(op.precedence() + 1)?;
-                    left = Expression::new_binary(
-                        left.clone(),
-                        op,
-                        right.clone(),
-                        left.position_start,
-                        right.position_end,
-                    );
-                }
-                _ => {
-                    return Ok(left);
                 }
             }
         }
     }
 
-    fn parse_primary_expression(&mut self) -> Result<Expression, ParserError> {
-        let next = self.peek();
-        match next.kind {
-            Some(TokenKind::Identifier) => self.parse_identifier_expression(),
-            Some(TokenKind::Keyword(Keyword::Func)) => self.parse_identifier_expression(),
-            Some(TokenKind::IntegerLiteral) => {
-                let integer = self.expect_token(TokenKind::IntegerLiteral)?;
-                Ok(Expression::new_integer_literal(
-                    integer.value.clone(),
-                    integer.position,
-                ))
+    /// Returns Ok(None) when the construct was parsed but doesn't belong at the top level,
+    /// in which case the error has already been recorded
+    fn parse_top_level_declaration(&mut self) -> Result<Option<Declaration>, ParserError> {
+        match self.peek().kind {
+            Some(TokenKind::Keyword(Keyword::Func)) => Ok(Some(self.parse_function_declaration()?)),
+            Some(TokenKind::Keyword(Keyword::Var | Keyword::Const)) => {
+                let declaration = self.parse_variable_declaration()?;
+                self.handle_semicolon_insertion()?;
+                Ok(Some(declaration))
             }
-            Some(TokenKind::StringLiteral) => {
-                let string = self.expect_token(TokenKind::StringLiteral)?;
-                Ok(Expression::new_string_literal(
-                    string.value.clone(),
-                    string.position,
-                ))
+            Some(TokenKind::Keyword(Keyword::Type)) => {
+                let declaration = self.parse_type_declaration()?;
+                self.handle_semicolon_insertion()?;
+                Ok(Some(declaration))
             }
-            Some(TokenKind::LeftParen) => {
-                self.advance(); // consume '('
-                let expression = self.parse_expression()?;
-                self.expect_token(TokenKind::RightParen)?;
-                Ok(expression)
+            Some(TokenKind::Keyword(Keyword::Import)) => {
+                let import_pos = self.peek().position;
+                self.parse_import_declaration()?;
+                self.errors.push(ParserError::new(
+                    ParserErrorKind::ImportAfterDeclaration,
+                    import_pos,
+                ));
+                Ok(None)
+            }
+            Some(TokenKind::Keyword(Keyword::Package)) => {
+                let error = self.expected(
+                    vec![
+                        TokenKind::Keyword(Keyword::Func),
+                        TokenKind::Keyword(Keyword::Type),
+                        TokenKind::Keyword(Keyword::Var),
+                        TokenKind::Keyword(Keyword::Const),
+                    ],
+                    None,
+                );
+                self.parse_package_clause()?;
+                self.errors.push(error);
+                Ok(None)
+            }
+            _ => {
+                let statement = self.parse_statement()?;
+                self.errors.push(ParserError::new(
+                    ParserErrorKind::NonDeclarationStatementOutsideFunction,
+                    statement.position_start,
+                ));
+                Ok(None)
             }
-            _ => Err(ParserError::new(
-                ParserErrorKind::NotAPrimaryExpression(next.value.to_string()),
-                next.position,
-            )),
         }
     }
 
-    /// Parse identifier-based expressions (identifier, field access, function calls, etc)
-    fn parse_identifier_expression(&mut self) -> Result<Expression, ParserError> {
-        let identifier_token = self.expect_token(TokenKind::Identifier)?;
-        let mut expression =
-            Expression::new_identifier(identifier_token.value.clone(), identifier_token.position);
-
-        loop {
-            match self.peek().kind {
-                Some(TokenKind::Dot) => {
-                    // Field access: obj.field
-                    self.advance(); // consume the dot
-                    let field_token = self.expect_token(TokenKind::Identifier)?;
-                    let start_pos = expression.position_start;
-                    let end_pos = field_token.position;
-
-                    expression = Expression::new_field_access(
-                        expression,
-                        field_token.value.clone(),
-                        start_pos,
-                        end_pos,
-                    );
-                }
-                Some(TokenKind::LeftParen) => {
-                    // Function call: expr(args)
-                    let start_pos = expression.position_start;
-                    self.advance(); // consume the '('
+    fn parse_statement(&mut self) -> Result<Statement, ParserError> {
+        let mut statement = self.nested(|parser| match parser.peek().kind {
+            Some(TokenKind::Keyword(Keyword::For)) => parser.parse_for_statement(),
+            Some(TokenKind::Keyword(Keyword::If)) => parser.parse_if_statement(),
+            Some(TokenKind::Keyword(Keyword::Return)) => parser.parse_return_statement(),
+            Some(TokenKind::Keyword(Keyword::Defer | Keyword::Go)) => parser.parse_call_statement(),
+            Some(TokenKind::Keyword(Keyword::Select)) => parser.parse_select_statement(),
+            Some(TokenKind::Keyword(Keyword::Switch)) => parser.parse_switch_statement(),
+            Some(TokenKind::Keyword(Keyword::Break | Keyword::Continue | Keyword::Fallthrough)) => {
+                parser.parse_branch_statement()
+            }
+            Some(TokenKind::LeftBrace) => parser.parse_block_statement(),
+            Some(TokenKind::Keyword(Keyword::Var | Keyword::Const | Keyword::Type)) => {
+                parser.parse_declaration_statement()
+            }
+            _ => parser.parse_simple_statement(),
+        })?;
+        statement.trailing_comment = self.take_trailing_comment(statement.position_end);
+        Ok(statement)
+    }
 
-                    let mut arguments = Vec::new();
+    /// The first unattached `// comment` starting on the same line after `end`, if any. By the
+    /// time a statement is parsed its terminating newline has been read, so any such comment
+    /// has already been collected.
+    fn take_trailing_comment(&mut self, end: Position) -> Option<String> {
+        let offset = self.comments[self.attached_comments..]
+            .iter()
+            .position(|comment| {
+                comment.kind == Some(TokenKind::SingleLineComment)
+                    && comment.position.line == end.line
+                    && comment.position.column_start >= end.column_end
+            })?;
+        self.attached_comments += offset + 1;
+        Some(self.comments[self.attached_comments - 1].value.clone())
+    }
 
-                    if !matches!(self.peek().kind, Some(TokenKind::RightParen)) {
-                        loop {
-                            arguments.push(self.parse_expression()?);
+    /// The text of the comments right above `line`, which go/doc takes as the documentation
+    /// of what starts on it: those on the lines just before it, after the code before it
+    pub(crate) fn doc_comment(&self, line: usize) -> Option<String> {
+        let mut next_line = line;
+        let mut group = Vec::new();
+        for comment in self.comments.iter().rev() {
+            if comment.position.line >= line {
+                continue;
+            }
+            let last_line = comment.position.line + comment.value.matches('\n').count();
+            if last_line + 1 != next_line || comment.position.line <= self.code_end_line {
+                break;
+            }
+            group.push(comment);
+            next_line = comment.position.line;
+        }
+        group.reverse();
+        comment_text(&group)
+    }
 
-                            if matches!(self.peek().kind, Some(TokenKind::Comma)) {
-                                self.advance(); // consume comma
-                            } else {
-                                break;
-                            }
-                        }
-                    }
+    /// https://go.dev/ref/spec#For_statements
+    fn parse_for_statement(&mut self) -> Result<Statement, ParserError> {
+        let start_position = self.advance().position; // consume 'for'
 
-                    let right_paren = self.expect_token(TokenKind::RightParen)?;
-                    let end_pos = right_paren.position;
+        let clause = match self.peek().kind {
+            Some(TokenKind::LeftBrace) => ForClause::Clauses(None, None, None),
+            Some(TokenKind::Keyword(Keyword::Range)) => ForClause::Range {
+                variables: Vec::new(),
+                define: false,
+            },
+            _ => self.with_control_clause(true, Parser::parse_for_clause)?,
+        };
 
-                    expression =
-                        Expression::new_function_call(expression, arguments, start_pos, end_pos);
+        let kind = match clause {
+            ForClause::Clauses(init, condition, post) => {
+                let (body, _) = self.parse_block()?;
+                StatementKind::For {
+                    init,
+                    condition,
+                    post,
+                    body,
                 }
-                _ => {
-                    // No more chaining, return the expression
-                    break;
+            }
+            ForClause::Range { variables, define } => {
+                self.advance(); // consume 'range'
+                let expression = self.with_control_clause(true, Parser::parse_expression)?;
+                self.check_blank_identifiers(&expression);
+                let (key, value) = self.range_variables(variables, define);
+                let (body, _) = self.parse_block()?;
+                StatementKind::Range {
+                    key,
+                    value,
+                    define,
+                    expression,
+                    body,
                 }
             }
-        }
-
-        Ok(expression)
+        };
+        let end_position = self.current_token.position; // the closing '}'
+        self.handle_semicolon_insertion()?;
+        Ok(Statement::new(kind, start_position, end_position))
     }
 
-    fn handle_semicolon_insertion(&mut self) -> Result<Position, ParserError> {
+    /// The condition or three clauses of a for statement, or the variables of a range clause
+    /// up to `range`
+    fn parse_for_clause(&mut self) -> Result<ForClause, ParserError> {
         if self.peek().kind == Some(TokenKind::Semicolon) {
-            let semicolon = self.advance();
-            Ok(semicolon.position)
-        } else if self.is_end_of_line() {
-            Ok(self.current_token.position)
-        } else {
-            Err(ParserError::new(
-                ParserErrorKind::UnexpectedToken(format!(
-                    "Expected ';' to separate statements on same line, got '{}'",
-                    self.peek().value
-                )),
-                self.peek().position,
-            ))
+            return self.parse_for_clauses(None);
+        }
+        let mut expressions = self.parse_expression_list()?;
+        let is_range = matches!(
+            self.peek_nth(1).kind,
+            Some(TokenKind::Keyword(Keyword::Range))
+        );
+        match self.peek().kind {
+            Some(TokenKind::ColonEqual) | Some(TokenKind::Equal) if is_range => {
+                let define = self.advance().kind == Some(TokenKind::ColonEqual);
+                Ok(ForClause::Range {
+                    variables: expressions,
+                    define,
+                })
+            }
+            Some(TokenKind::LeftBrace) if expressions.len() == 1 => {
+                let condition = expressions.remove(0);
+                self.check_blank_identifiers(&condition);
+                Ok(ForClause::Clauses(None, Some(condition), None))
+            }
+            _ if self.continues_simple_statement() => {
+                let init = self.finish_simple_statement(expressions)?;
+                self.parse_for_clauses(Some(Box::new(init)))
+            }
+            _ => Err(self.expected(vec![TokenKind::LeftBrace], Some("after for clause"))),
         }
     }
 
-    fn is_end_of_line(&mut self) -> bool {
-        if matches!(self.peek().kind, Some(TokenKind::EOF)) {
-            return true;
+    /// The condition and post statement of a three-clause for statement, after its init
+    /// statement
+    fn parse_for_clauses(
+        &mut self,
+        init: Option<Box<Statement>>,
+    ) -> Result<ForClause, ParserError> {
+        self.expect_token_with_context(TokenKind::Semicolon, "after for loop init")?;
+        let condition = match self.peek().kind {
+            Some(TokenKind::Semicolon) => None,
+            _ => {
+                let condition = self.parse_expression()?;
+                self.check_blank_identifiers(&condition);
+                Some(condition)
+            }
+        };
+        self.expect_token_with_context(TokenKind::Semicolon, "after for loop condition")?;
+        if self.peek().kind == Some(TokenKind::LeftBrace) {
+            return Ok(ForClause::Clauses(init, condition, None));
+        }
+        let left = self.parse_expression_list()?;
+        let post = self.finish_simple_statement(left)?;
+        if let StatementKind::Assignment { define: true, .. } = post.kind {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::DefineInPostStatement,
+                post.position_start,
+            ));
         }
+        Ok(ForClause::Clauses(init, condition, Some(Box::new(post))))
+    }
 
-        self.lexer.had_newline_before_current_token()
+    // var x int, const x = 1 or type T int, inside a function
+    fn parse_declaration_statement(&mut self) -> Result<Statement, ParserError> {
+        let declaration = match self.peek().kind {
+            Some(TokenKind::Keyword(Keyword::Type)) => self.parse_type_declaration()?,
+            _ => self.parse_variable_declaration()?,
+        };
+        let (start_position, end_position) = (declaration.position_start, declaration.position_end);
+        self.handle_semicolon_insertion()?;
+        Ok(Statement::new(
+            StatementKind::Declaration(declaration),
+            start_position,
+            end_position,
+        ))
     }
 
-    fn synchronize(&mut self) {
-        while !matches!(
-            self.peek().kind,
-            Some(TokenKind::EOF) | Some(TokenKind::Semicolon)
-        ) {
-            self.advance();
-        }
+    /// https://go.dev/ref/spec#If_statements
+    fn parse_if_statement(&mut self) -> Result<Statement, ParserError> {
+        let statement = self.parse_if()?;
+        self.handle_semicolon_insertion()?;
+        Ok(statement)
     }
-}
 
-mod tests {
-    #[cfg(test)]
-    mod tests {
-        use crate::{
-            ast::{expression::Expression, statement::StatementKind},
-            lexer::token_type::{Keyword, TokenKind},
-            parser::parser::Parser,
-            primitives::position::Position,
+    /// An `if` statement without its terminating semicolon, so an `else if` can be parsed the
+    /// same way
+    fn parse_if(&mut self) -> Result<Statement, ParserError> {
+        let start_position = self.advance().position; // consume 'if'
+
+        let (init, condition) = self.with_control_clause(true, Parser::parse_if_header)?;
+        self.check_blank_identifiers(&condition);
+        let (body, mut end_position) = self.parse_block()?;
+
+        let else_branch = if self.peek().kind == Some(TokenKind::Keyword(Keyword::Else)) {
+            self.advance(); // consume 'else'
+            let branch = match self.peek().kind {
+                Some(TokenKind::Keyword(Keyword::If)) => self.parse_if()?,
+                Some(TokenKind::LeftBrace) => {
+                    let block_start = self.peek().position;
+                    let (body, block_end) = self.parse_block()?;
+                    Statement::new(StatementKind::Block(body), block_start, block_end)
+                }
+                _ => {
+                    return Err(self.expected(
+                        vec![TokenKind::Keyword(Keyword::If), TokenKind::LeftBrace],
+                        Some("after else"),
+                    ));
+                }
+            };
+            end_position = branch.position_end;
+            Some(Box::new(branch))
+        } else {
+            None
         };
 
-        #[test]
-        fn parser_parse_program() {
-            let input = "identifier;";
-            let mut parser = Parser::new(input);
-            let program = parser.parse().unwrap();
-            assert_eq!(program.statements.len(), 1);
-            assert_eq!(
-                program.statements[0].kind,
+        Ok(Statement::new(
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            },
+            start_position,
+            end_position,
+        ))
+    }
+
+    /// The optional simple statement and the condition of an if statement
+    fn parse_if_header(&mut self) -> Result<(Option<Box<Statement>>, Expression), ParserError> {
+        let mut left = self.parse_expression_list()?;
+        match self.peek().kind {
+            _ if self.continues_simple_statement() => {
+                let init = self.finish_simple_statement(left)?;
+                self.expect_token_with_context(TokenKind::Semicolon, "after if statement")?;
+                Ok((Some(Box::new(init)), self.parse_expression()?))
+            }
+            _ if left.len() > 1 => {
+                Err(self.expected(vec![TokenKind::LeftBrace], Some("after if condition")))
+            }
+            _ => Ok((None, left.remove(0))),
+        }
+    }
+
+    // break
+    // continue
+    // fallthrough
+    fn parse_branch_statement(&mut self) -> Result<Statement, ParserError> {
+        let token = self.advance();
+        let position = token.position;
+        let kind = match token.kind {
+            Some(TokenKind::Keyword(Keyword::Break)) => StatementKind::Break,
+            Some(TokenKind::Keyword(Keyword::Fallthrough)) => StatementKind::Fallthrough,
+            _ => StatementKind::Continue,
+        };
+        self.handle_semicolon_insertion()?;
+        Ok(Statement::new(kind, position, position))
+    }
+
+    // { ... }
+    fn parse_block_statement(&mut self) -> Result<Statement, ParserError> {
+        let start_position = self.peek().position;
+        let (body, end_position) = self.parse_block()?;
+        self.handle_semicolon_insertion()?;
+        Ok(Statement::new(
+            StatementKind::Block(body),
+            start_position,
+            end_position,
+        ))
+    }
+
+    // return
+    // return a, b
+    fn parse_return_statement(&mut self) -> Result<Statement, ParserError> {
+        let start_position = self.advance().position; // consume 'return'
+        let results = if self.peek().kind == Some(TokenKind::Semicolon) || self.can_omit_semicolon()
+        {
+            Vec::new()
+        } else {
+            self.parse_expression_list()?
+        };
+        for result in &results {
+            self.check_blank_identifiers(result);
+        }
+        let end_position = results
+            .last()
+            .map_or(start_position, |last| last.position_end);
+        self.handle_semicolon_insertion()?;
+        Ok(Statement::new(
+            StatementKind::Return(results),
+            start_position,
+            end_position,
+        ))
+    }
+
+    // defer f(x)
+    // go f(x)
+    fn parse_call_statement(&mut self) -> Result<Statement, ParserError> {
+        let keyword = self.advance().clone(); // consume 'defer' or 'go'
+        let go = keyword.kind == Some(TokenKind::Keyword(Keyword::Go));
+        let call = self.parse_expression()?;
+        if !matches!(call.kind, ExpressionKind::FunctionCall { .. }) {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::NotACall(if go { "go" } else { "defer" }),
+                call.position_start,
+            ));
+        }
+        self.check_blank_identifiers(&call);
+        let end_position = call.position_end;
+        self.handle_semicolon_insertion()?;
+        Ok(Statement::new(
+            if go {
+                StatementKind::Go(call)
+            } else {
+                StatementKind::Defer(call)
+            },
+            keyword.position,
+            end_position,
+        ))
+    }
+
+    // select {
+    // case v, ok := <-ch:
+    //     ...
+    // case out <- v:
+    //     ...
+    // default:
+    //     ...
+    // }
+    fn parse_select_statement(&mut self) -> Result<Statement, ParserError> {
+        let start_position = self.advance().position; // consume 'select'
+        self.expect_token(TokenKind::LeftBrace)?;
+        let mut cases: Vec<SelectCase> = Vec::new();
+        while self.peek().kind != Some(TokenKind::RightBrace) {
+            let position = self.peek().position;
+            let communication = match self.peek().kind {
+                Some(TokenKind::Keyword(Keyword::Case)) => {
+                    self.advance();
+                    let left = self.parse_expression_list()?;
+                    let communication = self.finish_simple_statement(left)?;
+                    if !is_communication(&communication) {
+                        self.errors.push(ParserError::new(
+                            ParserErrorKind::NotACommunication,
+                            communication.position_start,
+                        ));
+                    }
+                    Some(Box::new(communication))
+                }
+                Some(TokenKind::Keyword(Keyword::Default)) => {
+                    self.advance();
+                    if cases.iter().any(|case| case.communication.is_none()) {
+                        self.errors.push(ParserError::new(
+                            ParserErrorKind::MultipleDefaults("select"),
+                            position,
+                        ));
+                    }
+                    None
+                }
+                _ => {
+                    return Err(self.expected(
+                        vec![
+                            TokenKind::Keyword(Keyword::Case),
+                            TokenKind::Keyword(Keyword::Default),
+                            TokenKind::RightBrace,
+                        ],
+                        Some("in select"),
+                    ));
+                }
+            };
+            self.expect_token(TokenKind::Colon)?;
+            let body = self.parse_case_body()?;
+            self.check_fallthroughs(&body, false);
+            cases.push(SelectCase {
+                communication,
+                body,
+                position,
+            });
+        }
+        let end_position = self.advance().position; // consume '}'
+        self.handle_semicolon_insertion()?;
+        Ok(Statement::new(
+            StatementKind::Select(cases),
+            start_position,
+            end_position,
+        ))
+    }
+
+    // switch x := f(); x {
+    // case 1, 2:
+    //     ...
+    //     fallthrough
+    // default:
+    //     ...
+    // }
+    fn parse_switch_statement(&mut self) -> Result<Statement, ParserError> {
+        let start_position = self.advance().position; // consume 'switch'
+        let (init, tag) = match self.peek().kind {
+            Some(TokenKind::LeftBrace) => (None, None),
+            _ => self.with_control_clause(true, Parser::parse_switch_header)?,
+        };
+        self.expect_token(TokenKind::LeftBrace)?;
+        let mut cases: Vec<SwitchCase> = Vec::new();
+        while self.peek().kind != Some(TokenKind::RightBrace) {
+            let position = self.peek().position;
+            let expressions = match self.peek().kind {
+                Some(TokenKind::Keyword(Keyword::Case)) => {
+                    self.advance();
+                    let expressions = self.parse_expression_list()?;
+                    for expression in &expressions {
+                        self.check_blank_identifiers(expression);
+                    }
+                    Some(expressions)
+                }
+                Some(TokenKind::Keyword(Keyword::Default)) => {
+                    self.advance();
+                    if cases.iter().any(|case| case.expressions.is_none()) {
+                        self.errors.push(ParserError::new(
+                            ParserErrorKind::MultipleDefaults("switch"),
+                            position,
+                        ));
+                    }
+                    None
+                }
+                _ => {
+                    return Err(self.expected(
+                        vec![
+                            TokenKind::Keyword(Keyword::Case),
+                            TokenKind::Keyword(Keyword::Default),
+                            TokenKind::RightBrace,
+                        ],
+                        Some("in switch"),
+                    ));
+                }
+            };
+            self.expect_token(TokenKind::Colon)?;
+            let body = self.parse_case_body()?;
+            cases.push(SwitchCase {
+                expressions,
+                body,
+                position,
+            });
+        }
+        // Only a case other than the last may fall through, and only as its last statement
+        for (index, case) in cases.iter().enumerate() {
+            self.check_fallthroughs(&case.body, true);
+            if index == cases.len() - 1
+                && let Some(last) = case.body.last()
+                && last.kind == StatementKind::Fallthrough
+            {
+                self.errors.push(ParserError::new(
+                    ParserErrorKind::FallthroughInFinalCase,
+                    last.position_start,
+                ));
+            }
+        }
+        let end_position = self.advance().position; // consume '}'
+        self.handle_semicolon_insertion()?;
+        Ok(Statement::new(
+            StatementKind::Switch { init, tag, cases },
+            start_position,
+            end_position,
+        ))
+    }
+
+    /// The optional simple statement and the optional tag of a switch statement
+    fn parse_switch_header(
+        &mut self,
+    ) -> Result<(Option<Box<Statement>>, Option<Expression>), ParserError> {
+        let init = match self.peek().kind {
+            Some(TokenKind::Semicolon) => None,
+            _ => {
+                let mut left = self.parse_expression_list()?;
+                if !self.continues_simple_statement() {
+                    if left.len() > 1 {
+                        return Err(
+                            self.expected(vec![TokenKind::LeftBrace], Some("after switch tag"))
+                        );
+                    }
+                    let tag = left.remove(0);
+                    self.check_blank_identifiers(&tag);
+                    return Ok((None, Some(tag)));
+                }
+                Some(Box::new(self.finish_simple_statement(left)?))
+            }
+        };
+        self.expect_token_with_context(TokenKind::Semicolon, "after switch statement")?;
+        let tag = match self.peek().kind {
+            Some(TokenKind::LeftBrace) => None,
+            _ => {
+                let tag = self.parse_expression()?;
+                self.check_blank_identifiers(&tag);
+                Some(tag)
+            }
+        };
+        Ok((init, tag))
+    }
+
+    /// Report each `fallthrough` in `statements`, except at their end when `allowed` there
+    fn check_fallthroughs(&mut self, statements: &[Statement], allowed: bool) {
+        for (index, statement) in statements.iter().enumerate() {
+            if statement.kind == StatementKind::Fallthrough
+                && !(allowed && index == statements.len() - 1)
+            {
+                self.errors.push(ParserError::new(
+                    ParserErrorKind::MisplacedFallthrough,
+                    statement.position_start,
+                ));
+            }
+        }
+    }
+
+    /// The statements of a select or switch clause, up to the next `case`, `default` or
+    /// closing '}'
+    fn parse_case_body(&mut self) -> Result<Vec<Statement>, ParserError> {
+        self.nested(|parser| {
+            let mut statements = Vec::new();
+            loop {
+                match parser.peek().kind {
+                    Some(
+                        TokenKind::RightBrace
+                        | TokenKind::Keyword(Keyword::Case | Keyword::Default),
+                    ) => return Ok(statements),
+                    Some(TokenKind::EOF) => {
+                        return Err(
+                            parser.expected(vec![TokenKind::RightBrace], Some("to close block"))
+                        );
+                    }
+                    Some(TokenKind::Semicolon) => {
+                        parser.advance();
+                    }
+                    _ => {
+                        let started_at = parser.tokens_consumed;
+                        match parser.parse_statement() {
+                            Ok(statement) => statements.push(statement),
+                            Err(error) => parser.recover(error, RecoveryContext::Block, started_at),
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Split the variables of a range clause into key and value, reporting anything past the
+    /// second and, for `:=`, anything that isn't a name
+    fn range_variables(
+        &mut self,
+        variables: Vec<Expression>,
+        define: bool,
+    ) -> (Option<Expression>, Option<Expression>) {
+        if define {
+            for variable in &variables {
+                if !matches!(
+                    variable.kind,
+                    ExpressionKind::Identifier(_) | ExpressionKind::BlankIdentifier
+                ) {
+                    self.errors.push(ParserError::new(
+                        ParserErrorKind::NonNameOnLeftOfDefine,
+                        variable.position_start,
+                    ));
+                }
+            }
+        }
+        if let (Some(extra), Some(last)) = (variables.get(2), variables.last()) {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::TooManyRangeVariables,
+                Position::new(
+                    extra.position_start.line,
+                    extra.position_start.column_start,
+                    last.position_end.column_end,
+                ),
+            ));
+        }
+        let mut variables = variables.into_iter();
+        (variables.next(), variables.next())
+    }
+
+    /// Comma separated expressions, e.g. the left hand side of an assignment
+    fn parse_expression_list(&mut self) -> Result<Vec<Expression>, ParserError> {
+        let mut expressions = vec![self.parse_expression()?];
+        while self.peek().kind == Some(TokenKind::Comma) {
+            self.advance();
+            expressions.push(self.parse_expression()?);
+        }
+        Ok(expressions)
+    }
+
+    /// Whether the next token carries on a simple statement whose expressions have been
+    /// parsed, rather than them being a condition or tag on their own
+    fn continues_simple_statement(&self) -> bool {
+        match &self.peek().kind {
+            Some(kind) => {
+                matches!(
+                    kind,
+                    TokenKind::Equal
+                        | TokenKind::ColonEqual
+                        | TokenKind::Semicolon
+                        | TokenKind::PlusPlus
+                        | TokenKind::MinusMinus
+                        | TokenKind::LessMinus
+                ) || kind.assign_operator().is_some()
+            }
+            None => false,
+        }
+    }
+
+    /// An expression statement or an assignment (https://go.dev/ref/spec#SimpleStmt)
+    fn parse_simple_statement(&mut self) -> Result<Statement, ParserError> {
+        let left = self.parse_expression_list()?;
+        let statement = self.finish_simple_statement(left)?;
+        self.handle_semicolon_insertion()?;
+        Ok(statement)
+    }
+
+    /// The assignment or expression statement starting with the already parsed `left`, without
+    /// its terminating semicolon
+    fn finish_simple_statement(
+        &mut self,
+        mut left: Vec<Expression>,
+    ) -> Result<Statement, ParserError> {
+        let start_position = left[0].position_start;
+        if let Some(TokenKind::Equal | TokenKind::ColonEqual) = self.peek().kind {
+            let define = self.advance().kind == Some(TokenKind::ColonEqual);
+            if define {
+                for variable in &left {
+                    if !matches!(
+                        variable.kind,
+                        ExpressionKind::Identifier(_) | ExpressionKind::BlankIdentifier
+                    ) {
+                        self.errors.push(ParserError::new(
+                            ParserErrorKind::NonNameOnLeftOfDefine,
+                            variable.position_start,
+                        ));
+                    }
+                }
+            }
+            let right = self.parse_expression_list()?;
+            for expression in &right {
+                self.check_blank_identifiers(expression);
+            }
+            let end_position = right
+                .last()
+                .map_or(start_position, |last| last.position_end);
+            return Ok(Statement::new(
+                StatementKind::Assignment {
+                    left,
+                    right,
+                    define,
+                },
+                start_position,
+                end_position,
+            ));
+        }
+        if left.len() > 1 {
+            return Err(self.expected(
+                vec![TokenKind::Equal, TokenKind::ColonEqual],
+                Some("after expression list"),
+            ));
+        }
+        if let Some(TokenKind::PlusPlus | TokenKind::MinusMinus) = self.peek().kind {
+            let token = self.advance();
+            let increment = token.kind == Some(TokenKind::PlusPlus);
+            let end_position = token.position;
+            let target = left.remove(0);
+            self.check_blank_identifiers(&target);
+            return Ok(Statement::new(
+                StatementKind::IncDec { target, increment },
+                start_position,
+                end_position,
+            ));
+        }
+        if let Some(operator) = self.peek().kind.and_then(|kind| kind.assign_operator()) {
+            self.advance(); // consume the operator
+            let target = left.remove(0);
+            let value = self.parse_expression()?;
+            self.check_blank_identifiers(&target);
+            self.check_blank_identifiers(&value);
+            let end_position = value.position_end;
+            return Ok(Statement::new(
+                StatementKind::OpAssign {
+                    target,
+                    operator,
+                    value,
+                },
+                start_position,
+                end_position,
+            ));
+        }
+        if self.peek().kind == Some(TokenKind::LessMinus) {
+            self.advance(); // consume '<-'
+            let channel = left.remove(0);
+            let value = self.parse_expression()?;
+            self.check_blank_identifiers(&channel);
+            self.check_blank_identifiers(&value);
+            let end_position = value.position_end;
+            return Ok(Statement::new(
+                StatementKind::Send { channel, value },
+                start_position,
+                end_position,
+            ));
+        }
+        self.finish_expression_statement(left.remove(0))
+    }
+
+    fn finish_expression_statement(
+        &mut self,
+        expression: Expression,
+    ) -> Result<Statement, ParserError> {
+        self.check_blank_identifiers(&expression);
+        let start_position = expression.position_start;
+        if !expression.is_valid_statement() {
+            // Record the error but keep the statement, the surrounding syntax is still sound
+            self.errors.push(ParserError::new(
+                ParserErrorKind::ExpressionNotUsedAsStatement,
+                Position::new(
+                    start_position.line,
+                    start_position.column_start,
+                    expression.position_end.column_end,
+                ),
+            ));
+        }
+        let end_position = expression.position_end;
+        Ok(Statement::new_expression_statement(
+            expression,
+            start_position,
+            end_position,
+        ))
+    }
+
+    // var x = 1
+    // var (
+    //     y int
+    //     z = "z"
+    // )
+    // or the same with `const`, whose specs need values unless they're later ones in a group
+    fn parse_variable_declaration(&mut self) -> Result<Declaration, ParserError> {
+        let keyword = self.advance(); // consume 'var' or 'const'
+        let start_position = keyword.position;
+        let constant = keyword.kind == Some(TokenKind::Keyword(Keyword::Const));
+        let declaration = |specs, grouped| match constant {
+            true => DeclarationKind::Constant { specs, grouped },
+            false => DeclarationKind::Variable { specs, grouped },
+        };
+        if self.peek().kind != Some(TokenKind::LeftParen) {
+            let spec = self.parse_variable_spec(constant)?;
+            if constant {
+                self.check_constant_spec(&spec, true);
+            }
+            let end_position = spec.position_end;
+            return Ok(Declaration::new(
+                declaration(vec![spec], false),
+                start_position,
+                end_position,
+            ));
+        }
+        self.advance(); // consume '('
+        let mut specs = Vec::new();
+        while !matches!(
+            self.peek().kind,
+            Some(TokenKind::RightParen) | Some(TokenKind::EOF)
+        ) {
+            let spec = self.parse_variable_spec(constant)?;
+            if constant {
+                self.check_constant_spec(&spec, specs.is_empty());
+            }
+            specs.push(spec);
+            self.handle_semicolon_insertion()?;
+        }
+        let end_position = self
+            .expect_token_with_context(TokenKind::RightParen, "after variable specs")?
+            .position;
+        Ok(Declaration::new(
+            declaration(specs, true),
+            start_position,
+            end_position,
+        ))
+    }
+
+    /// Report a constant spec without values, unless it's a group's later spec repeating the
+    /// ones before it, which it can't with a type of its own
+    fn check_constant_spec(&mut self, spec: &VariableSpec, first: bool) {
+        if spec.values.is_empty() && (first || spec.type_expression.is_some()) {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::MissingConstantValue,
+                spec.position_start,
+            ));
+        }
+    }
+
+    // type Celsius float64
+    fn parse_type_declaration(&mut self) -> Result<Declaration, ParserError> {
+        let start_position = self.advance().position; // consume 'type'
+        let name = self.parse_name()?;
+        let type_expression = self.parse_type()?;
+        let end_position = type_expression.position_end;
+        Ok(Declaration::new(
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            },
+            start_position,
+            end_position,
+        ))
+    }
+
+    /// `x, y int = 1, 2`, where either the type or the values may be left out, or both in a
+    /// `constant` spec
+    fn parse_variable_spec(&mut self, constant: bool) -> Result<VariableSpec, ParserError> {
+        let mut names = vec![self.parse_name()?];
+        while self.peek().kind == Some(TokenKind::Comma) {
+            self.advance();
+            names.push(self.parse_name()?);
+        }
+        let type_expression = match self.peek().kind {
+            Some(TokenKind::Equal) => None,
+            Some(TokenKind::Semicolon | TokenKind::RightParen) if constant => None,
+            _ => Some(self.parse_type()?),
+        };
+        let mut values = Vec::new();
+        if self.peek().kind == Some(TokenKind::Equal) {
+            self.advance();
+            values = self.parse_expression_list()?;
+            for value in &values {
+                self.check_blank_identifiers(value);
+            }
+        }
+        let position_start = names[0].position_start;
+        let position_end = match (values.last(), &type_expression) {
+            (Some(value), _) => value.position_end,
+            (None, Some(type_expression)) => type_expression.position_end,
+            (None, None) => names[names.len() - 1].position_end,
+        };
+        Ok(VariableSpec::new(
+            names,
+            type_expression,
+            values,
+            position_start,
+            position_end,
+        ))
+    }
+
+    /// An identifier being declared, which may be `_`
+    fn parse_name(&mut self) -> Result<Expression, ParserError> {
+        let token = self.expect_token(TokenKind::Identifier)?;
+        if token.value == "_" {
+            return Ok(Expression::new_blank_identifier(token.position));
+        }
+        Ok(Expression::new_identifier(
+            token.value.clone(),
+            token.position,
+        ))
+    }
+
+    fn parse_package_clause(&mut self) -> Result<String, ParserError> {
+        if !matches!(self.peek().kind, Some(TokenKind::Keyword(Keyword::Package))) {
+            return Err(ParserError::new(
+                ParserErrorKind::MissingPackageClause,
+                self.peek().position,
+            ));
+        }
+        self.advance();
+        let name_token = self.expect_token(TokenKind::Identifier)?;
+        let name_value = name_token.value.clone();
+        self.handle_semicolon_insertion()?;
+
+        Ok(name_value)
+    }
+
+    // import "fmt"
+    // import (
+    //     "fmt"
+    //     str "strings"
+    // )
+    fn parse_import_declaration(&mut self) -> Result<Vec<ImportSpec>, ParserError> {
+        self.expect_token(TokenKind::Keyword(Keyword::Import))?;
+        let mut specs = Vec::new();
+
+        if matches!(self.peek().kind, Some(TokenKind::LeftParen)) {
+            self.advance(); // consume '('
+            while !matches!(
+                self.peek().kind,
+                Some(TokenKind::RightParen) | Some(TokenKind::EOF)
+            ) {
+                specs.push(self.parse_import_spec()?);
+                self.handle_semicolon_insertion()?;
+            }
+            self.expect_token_with_context(TokenKind::RightParen, "after import specs")?;
+        } else {
+            specs.push(self.parse_import_spec()?);
+        }
+        self.handle_semicolon_insertion()?;
+
+        Ok(specs)
+    }
+
+    fn parse_import_spec(&mut self) -> Result<ImportSpec, ParserError> {
+        let start_pos = self.peek().position;
+        let name = match self.peek().kind {
+            Some(TokenKind::Identifier) | Some(TokenKind::Dot) => {
+                Some(self.advance().value.clone())
+            }
+            _ => None,
+        };
+        let path_token = self.expect_token(TokenKind::StringLiteral)?;
+
+        Ok(ImportSpec::new(
+            name,
+            path_token.value.clone(),
+            start_pos,
+            path_token.position,
+        ))
+    }
+
+    fn parse_function_declaration(&mut self) -> Result<Declaration, ParserError> {
+        let func_token = self.expect_token(TokenKind::Keyword(Keyword::Func))?;
+        let func_pos = func_token.position;
+        let receiver = match self.peek().kind {
+            Some(TokenKind::LeftParen) => Some(self.parse_receiver()?),
+            _ => None,
+        };
+        let name_token = self.expect_token(TokenKind::Identifier)?;
+        let func_name = name_token.value.clone();
+
+        let parameters = self.parse_parameters()?;
+        let results = self.parse_results()?;
+
+        let (body_statements, end_pos) = self.parse_block()?;
+        self.handle_semicolon_insertion()?;
+
+        Ok(Declaration::new_function(
+            receiver,
+            func_name,
+            parameters,
+            results,
+            body_statements,
+            func_pos,
+            end_pos,
+        ))
+    }
+
+    /// `(p *Point)` before a method's name, which is a parameter list of exactly one parameter
+    /// (https://go.dev/ref/spec#Method_declarations)
+    fn parse_receiver(&mut self) -> Result<Parameter, ParserError> {
+        let position = self.peek().position;
+        let mut parameters = self.parse_parameters()?;
+        let count: usize = parameters
+            .iter()
+            .map(|parameter| parameter.names.len().max(1))
+            .sum();
+        if count != 1 {
+            self.errors.push(ParserError::new(
+                ParserErrorKind::ReceiverCount(count),
+                position,
+            ));
+        }
+        if parameters.is_empty() {
+            return Err(self.expected(vec![TokenKind::Identifier], Some("for receiver")));
+        }
+        Ok(parameters.swap_remove(0))
+    }
+
+    /// `(a, b int, rest ...string)` or `(int, error)` (https://go.dev/ref/spec#Function_types).
+    /// Whether a lone identifier is a name or a type depends on the rest of the list: names
+    /// are only used if some entry has both a name and a type.
+    fn parse_parameters(&mut self) -> Result<Vec<Parameter>, ParserError> {
+        self.expect_token_with_context(TokenKind::LeftParen, "for parameters")?;
+        // Each entry is an optional name and a type, where an identifier on its own is kept
+        // as a name until the form of the list is known
+        let mut entries: Vec<(Option<Expression>, Option<TypeExpression>, bool)> = Vec::new();
+        while self.peek().kind != Some(TokenKind::RightParen) {
+            let next_kind = self.peek_nth(1).kind;
+            let entry = match self.peek().kind {
+                Some(TokenKind::Identifier)
+                    if matches!(
+                        next_kind,
+                        Some(TokenKind::Comma) | Some(TokenKind::RightParen)
+                    ) =>
+                {
+                    (Some(self.parse_name()?), None, false)
+                }
+                Some(TokenKind::Identifier) if next_kind != Some(TokenKind::Dot) => {
+                    let name = self.parse_name()?;
+                    let variadic = self.parse_variadic();
+                    (Some(name), Some(self.parse_type()?), variadic)
+                }
+                _ => {
+                    let variadic = self.parse_variadic();
+                    (None, Some(self.parse_type()?), variadic)
+                }
+            };
+            entries.push(entry);
+            if self.peek().kind != Some(TokenKind::Comma) {
+                break;
+            }
+            self.advance();
+        }
+        let right_paren = self
+            .expect_token_with_context(TokenKind::RightParen, "after parameters")?
+            .position;
+
+        let named = entries
+            .iter()
+            .any(|(name, type_expression, _)| name.is_some() && type_expression.is_some());
+        if !named {
+            return Ok(entries
+                .into_iter()
+                .map(|(name, type_expression, variadic)| {
+                    let type_expression = type_expression.unwrap_or_else(|| {
+                        let name = name.expect("entries without a type have a name");
+                        let type_name = match name.kind {
+                            ExpressionKind::Identifier(type_name) => type_name,
+                            _ => "_".to_string(),
+                        };
+                        TypeExpression::new_name(type_name, name.position_start)
+                    });
+                    let (start, end) =
+                        (type_expression.position_start, type_expression.position_end);
+                    Parameter::new(Vec::new(), type_expression, variadic, start, end)
+                })
+                .collect());
+        }
+
+        let mut parameters = Vec::new();
+        let mut names = Vec::new();
+        for (name, type_expression, variadic) in entries {
+            match (name, type_expression) {
+                (Some(name), None) => names.push(name),
+                (Some(name), Some(type_expression)) => {
+                    names.push(name);
+                    let start = names[0].position_start;
+                    let end = type_expression.position_end;
+                    parameters.push(Parameter::new(
+                        std::mem::take(&mut names),
+                        type_expression,
+                        variadic,
+                        start,
+                        end,
+                    ));
+                }
+                (None, Some(type_expression)) => {
+                    return Err(ParserError::new(
+                        ParserErrorKind::MixedNamedAndUnnamedParameters,
+                        type_expression.position_start,
+                    ));
+                }
+                (None, None) => unreachable!("every entry has a name or a type"),
+            }
+        }
+        if !names.is_empty() {
+            return Err(ParserError::new(
+                ParserErrorKind::MixedNamedAndUnnamedParameters,
+                right_paren,
+            ));
+        }
+        Ok(parameters)
+    }
+
+    /// Consume a `...` before a final parameter's type, returning whether there was one
+    fn parse_variadic(&mut self) -> bool {
+        if self.peek().kind == Some(TokenKind::DotDotDot) {
+            self.advance();
+            return true;
+        }
+        false
+    }
+
+    /// The results after a function's parameters: nothing, a single type, or a parenthesized
+    /// list like the parameters
+    fn parse_results(&mut self) -> Result<Vec<Parameter>, ParserError> {
+        match self.peek().kind {
+            Some(TokenKind::LeftParen) => self.parse_parameters(),
+            // A function type's signature may also end before whatever follows the type
+            Some(TokenKind::LeftBrace)
+            | Some(TokenKind::RightBrace)
+            | Some(TokenKind::Semicolon)
+            | Some(TokenKind::EOF)
+            | Some(TokenKind::Comma)
+            | Some(TokenKind::RightParen)
+            | Some(TokenKind::RightBracket)
+            | Some(TokenKind::Equal)
+            | Some(TokenKind::StringLiteral) => Ok(Vec::new()),
+            _ => {
+                let type_expression = self.parse_type()?;
+                let (start, end) = (type_expression.position_start, type_expression.position_end);
+                Ok(vec![Parameter::new(
+                    Vec::new(),
+                    type_expression,
+                    false,
+                    start,
+                    end,
+                )])
+            }
+        }
+    }
+
+    /// Parse `{ statements }`, recovering from errors in individual statements so one bad
+    /// statement doesn't discard the rest of the block. Returns the position of the `}`.
+    fn parse_block(&mut self) -> Result<(Vec<Statement>, Position), ParserError> {
+        // The statements around a block take far more stack than an expression, so each block
+        // counts against the nesting limit on top of the statement containing it
+        self.nested(|parser| parser.parse_braced_statements())
+    }
+
+    fn parse_braced_statements(&mut self) -> Result<(Vec<Statement>, Position), ParserError> {
+        self.expect_token(TokenKind::LeftBrace)?;
+        let mut statements = Vec::new();
+
+        while !matches!(self.peek().kind, Some(TokenKind::RightBrace)) {
+            if matches!(self.peek().kind, Some(TokenKind::EOF)) {
+                return Err(self.expected(vec![TokenKind::RightBrace], Some("to close block")));
+            }
+            // Empty statements, e.g. `;;` or a lone `;` on a line, leave nothing in the AST
+            if matches!(self.peek().kind, Some(TokenKind::Semicolon)) {
+                self.advance();
+                continue;
+            }
+            let started_at = self.tokens_consumed;
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => self.recover(error, RecoveryContext::Block, started_at),
+            }
+        }
+        self.check_fallthroughs(&statements, false);
+
+        let right_brace = self.expect_token(TokenKind::RightBrace)?;
+        Ok((statements, right_brace.position))
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, ParserError> {
+        self.nested(|parser| parser.parse_binary_expression(0))
+    }
+
+    /// An expression inside parentheses, brackets or braces, where `T{` can only start a
+    /// composite literal
+    fn parse_enclosed_expression(&mut self) -> Result<Expression, ParserError> {
+        self.with_control_clause(false, Parser::parse_expression)
+    }
+
+    /// Run `parse` inside or outside of an if or for statement's header. There a composite
+    /// literal of a type name like `T{}` has to be enclosed, since the `{` would otherwise
+    /// start the block (https://go.dev/ref/spec#Composite_literals).
+    fn with_control_clause<T>(
+        &mut self,
+        control_clause: bool,
+        parse: impl FnOnce(&mut Parser) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        let outer = std::mem::replace(&mut self.control_clause, control_clause);
+        let result = parse(self);
+        self.control_clause = outer;
+        result
+    }
+
+    /// Run a production that may recurse into itself, failing instead of overflowing the stack
+    /// on absurdly deep input like ten thousand nested parentheses
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(ParserError::new(
+                ParserErrorKind::NestingTooDeep,
+                self.peek().position,
+            ));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Record an error for each `_` in an expression whose value is used. The blank identifier
+    /// can only be assigned to or declared, never read.
+    fn check_blank_identifiers(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::BlankIdentifier => self.errors.push(ParserError::new(
+                ParserErrorKind::BlankIdentifierAsValue,
+                expression.position_start,
+            )),
+            ExpressionKind::Binary { left, right, .. } => {
+                self.check_blank_identifiers(left);
+                self.check_blank_identifiers(right);
+            }
+            ExpressionKind::FunctionCall {
+                name, arguments, ..
+            } => {
+                self.check_blank_identifiers(name);
+                for argument in arguments {
+                    self.check_blank_identifiers(argument);
+                }
+            }
+            ExpressionKind::FieldAccess { object, .. } => self.check_blank_identifiers(object),
+            ExpressionKind::Index { object, index } => {
+                self.check_blank_identifiers(object);
+                self.check_blank_identifiers(index);
+            }
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                self.check_blank_identifiers(object);
+                for bound in [low, high, max].into_iter().flatten() {
+                    self.check_blank_identifiers(bound);
+                }
+            }
+            ExpressionKind::Parenthesized(inner) | ExpressionKind::Receive(inner) => {
+                self.check_blank_identifiers(inner)
+            }
+            ExpressionKind::Conversion { expression, .. }
+            | ExpressionKind::Unary {
+                operand: expression,
+                ..
+            } => self.check_blank_identifiers(expression),
+            // A field name key isn't a value, but `_` can't name a field either
+            ExpressionKind::CompositeLiteral { elements, .. } => {
+                for element in elements {
+                    if let Some(key) = &element.key {
+                        self.check_blank_identifiers(key);
+                    }
+                    self.check_blank_identifiers(&element.value);
+                }
+            }
+            // The body's statements were checked as they were parsed
+            ExpressionKind::FunctionLiteral { .. }
+            | ExpressionKind::Type(_)
+            | ExpressionKind::Identifier(_)
+            | ExpressionKind::IntegerLiteral(_)
+            | ExpressionKind::FloatLiteral(_)
+            | ExpressionKind::RuneLiteral(_)
+            | ExpressionKind::StringLiteral(_) => {}
+        }
+    }
+
+    fn parse_binary_expression(&mut self, min_precedence: u8) -> Result<Expression, ParserError> {
+        let mut left = self.parse_primary_expression()?;
+        loop {
+            let next = self.peek();
+            match next.kind {
+                Some(TokenKind::Operator(op)) => {
+                    if op.precedence() < min_precedence {
+                        return Ok(left);
+                    }
+                    self.advance();
+                    let right = self.parse_binary_expression(op.precedence() + 1)?;
+                    let (position_start, position_end) = (left.position_start, right.position_end);
+                    left = Expression::new_binary(left, op, right, position_start, position_end);
+                }
+                _ => {
+                    return Ok(left);
+                }
+            }
+        }
+    }
+
+    fn parse_primary_expression(&mut self) -> Result<Expression, ParserError> {
+        if self.peek().kind == Some(TokenKind::LessMinus) {
+            let start_pos = self.advance().position; // consume '<-'
+            let operand = self.nested(Parser::parse_primary_expression)?;
+            // `<-chan T` is a receive-only channel type rather than a receive from `chan T`,
+            // while `<-chan T(c)` receives from the conversion `chan T(c)`
+            // (https://go.dev/ref/spec#Conversions)
+            return Ok(match operand.kind {
+                ExpressionKind::Type(TypeExpression {
+                    kind:
+                        TypeExpressionKind::Channel {
+                            direction: ChannelDirection::Both,
+                            element,
+                        },
+                    position_end,
+                    ..
+                }) => Expression::new_type(TypeExpression::new(
+                    TypeExpressionKind::Channel {
+                        direction: ChannelDirection::Receive,
+                        element,
+                    },
+                    start_pos,
+                    position_end,
+                )),
+                kind => Expression::new_receive(
+                    Expression::new(kind, operand.position_start, operand.position_end),
+                    start_pos,
+                ),
+            });
+        }
+        if let Some(operator) = self.unary_operator() {
+            let start_pos = self.advance().position; // consume the operator
+            let operand = self.nested(Parser::parse_primary_expression)?;
+            return Ok(Expression::new_unary(operator, operand, start_pos));
+        }
+        let operand = self.parse_operand()?;
+        self.parse_postfix_expression(operand)
+    }
+
+    /// The unary operator the next token is, other than `<-`
+    fn unary_operator(&self) -> Option<UnaryOperator> {
+        Some(match self.peek().kind? {
+            TokenKind::Operator(Operator::Plus) => UnaryOperator::Plus,
+            TokenKind::Operator(Operator::Minus) => UnaryOperator::Minus,
+            TokenKind::Bang => UnaryOperator::Not,
+            TokenKind::Operator(Operator::Caret) => UnaryOperator::Complement,
+            TokenKind::Operator(Operator::Ampersand) => UnaryOperator::Address,
+            TokenKind::Operator(Operator::Star) => UnaryOperator::Dereference,
+            _ => return None,
+        })
+    }
+
+    fn parse_operand(&mut self) -> Result<Expression, ParserError> {
+        let next = self.peek();
+        match next.kind {
+            Some(TokenKind::Identifier) => {
+                let identifier = self.expect_token(TokenKind::Identifier)?;
+                if identifier.value == "_" {
+                    return Ok(Expression::new_blank_identifier(identifier.position));
+                }
+                Ok(Expression::new_identifier(
+                    identifier.value.clone(),
+                    identifier.position,
+                ))
+            }
+            Some(TokenKind::IntegerLiteral) => {
+                let integer = self.expect_token(TokenKind::IntegerLiteral)?;
+                Ok(Expression::new_integer_literal(
+                    integer.value.clone(),
+                    integer.position,
+                ))
+            }
+            Some(TokenKind::FloatLiteral) => {
+                let float = self.advance();
+                Ok(Expression::new_float_literal(
+                    float.value.clone(),
+                    float.position,
+                ))
+            }
+            Some(TokenKind::RuneLiteral) => {
+                let rune = self.advance();
+                Ok(Expression::new_rune_literal(
+                    rune.value.clone(),
+                    rune.position,
+                ))
+            }
+            Some(TokenKind::StringLiteral) => {
+                let string = self.expect_token(TokenKind::StringLiteral)?;
+                Ok(Expression::new_string_literal(
+                    string.value.clone(),
+                    string.position,
+                ))
+            }
+            Some(TokenKind::LeftParen) => {
+                let start_pos = self.advance().position; // consume '('
+                let expression = self.parse_enclosed_expression()?;
+                let end_pos = self
+                    .expect_token_with_context(TokenKind::RightParen, "after expression")?
+                    .position;
+                // `(<-chan int)(c)` converts to a type that needs parentheses
+                if let ExpressionKind::Type(target) = expression.kind {
+                    if self.peek().kind == Some(TokenKind::LeftParen) {
+                        return self.finish_conversion(target, start_pos);
+                    }
+                    let expression = Expression::new_type(target);
+                    return Ok(Expression::new_parenthesized(
+                        expression, start_pos, end_pos,
+                    ));
+                }
+                Ok(Expression::new_parenthesized(
+                    expression, start_pos, end_pos,
+                ))
+            }
+            Some(TokenKind::LeftBracket)
+            | Some(TokenKind::Keyword(Keyword::Func))
+            | Some(TokenKind::Keyword(Keyword::Map))
+            | Some(TokenKind::Keyword(Keyword::Chan))
+            | Some(TokenKind::Keyword(Keyword::Struct))
+            | Some(TokenKind::Keyword(Keyword::Interface)) => self.parse_conversion(),
+            _ => Err(ParserError::new(
+                ParserErrorKind::NotAPrimaryExpression(next.value.to_string()),
+                next.position,
+            )),
+        }
+    }
+
+    /// A conversion to a type literal, e.g. `[]byte(s)` (https://go.dev/ref/spec#Conversions),
+    /// a composite literal of one, e.g. `[]int{1, 2}`, or the type literal alone when it's an
+    /// argument, as in `make([]int, n)`. A function type followed by a block is a function
+    /// literal.
+    fn parse_conversion(&mut self) -> Result<Expression, ParserError> {
+        let target = self.parse_type()?;
+        let start_pos = target.position_start;
+        match self.peek().kind {
+            Some(TokenKind::LeftBrace)
+                if matches!(target.kind, TypeExpressionKind::Function { .. }) =>
+            {
+                self.parse_function_literal(target)
+            }
+            Some(TokenKind::LeftParen) => self.finish_conversion(target, start_pos),
+            Some(TokenKind::LeftBrace) => self.parse_composite_literal(Some(target), start_pos),
+            _ => Ok(Expression::new_type(target)),
+        }
+    }
+
+    /// `func(x int) int { return x * 2 }`, the body following its function type `signature`
+    /// (https://go.dev/ref/spec#Function_literals)
+    fn parse_function_literal(
+        &mut self,
+        signature: TypeExpression,
+    ) -> Result<Expression, ParserError> {
+        let TypeExpressionKind::Function {
+            parameters,
+            results,
+        } = signature.kind
+        else {
+            unreachable!("only a function type starts a function literal");
+        };
+        // The body may hold composite literals like any other, even in an if statement's header
+        let (body, end_pos) = self.with_control_clause(false, Parser::parse_block)?;
+        Ok(Expression::new_function_literal(
+            parameters,
+            results,
+            body,
+            signature.position_start,
+            end_pos,
+        ))
+    }
+
+    // Point{X: 1, Y: 2}, []Point{{1, 2}, {3, 4}}
+    fn parse_composite_literal(
+        &mut self,
+        type_expression: Option<TypeExpression>,
+        start_pos: Position,
+    ) -> Result<Expression, ParserError> {
+        self.expect_token_with_context(TokenKind::LeftBrace, "in composite literal")?;
+        let mut elements = Vec::new();
+        while !matches!(
+            self.peek().kind,
+            Some(TokenKind::RightBrace) | Some(TokenKind::EOF)
+        ) {
+            let value = self.parse_element()?;
+            let element = if self.peek().kind == Some(TokenKind::Colon) {
+                self.advance(); // consume ':'
+                KeyedElement {
+                    key: Some(value),
+                    value: self.parse_element()?,
+                }
+            } else {
+                KeyedElement { key: None, value }
+            };
+            elements.push(element);
+            if self.peek().kind != Some(TokenKind::Comma) {
+                break;
+            }
+            self.advance(); // consume ','
+        }
+        let end_pos = self
+            .expect_token_with_context(TokenKind::RightBrace, "after composite literal elements")?
+            .position;
+        Ok(Expression::new_composite_literal(
+            type_expression,
+            elements,
+            start_pos,
+            end_pos,
+        ))
+    }
+
+    /// An element or key of a composite literal, which may be a literal with its type elided
+    fn parse_element(&mut self) -> Result<Expression, ParserError> {
+        if self.peek().kind == Some(TokenKind::LeftBrace) {
+            let start_pos = self.peek().position;
+            return self.nested(|parser| parser.parse_composite_literal(None, start_pos));
+        }
+        self.parse_enclosed_expression()
+    }
+
+    /// The parenthesized operand of a conversion to `target`
+    fn finish_conversion(
+        &mut self,
+        target: TypeExpression,
+        start_pos: Position,
+    ) -> Result<Expression, ParserError> {
+        self.expect_token_with_context(TokenKind::LeftParen, "for conversion")?;
+        let expression = self.parse_enclosed_expression()?;
+        // A single trailing comma is allowed, as in a call
+        if self.peek().kind == Some(TokenKind::Comma) {
+            self.advance();
+        }
+        let end_pos = self
+            .expect_token_with_context(TokenKind::RightParen, "after conversion")?
+            .position;
+        Ok(Expression::new_conversion(
+            target, expression, start_pos, end_pos,
+        ))
+    }
+
+    fn parse_type(&mut self) -> Result<TypeExpression, ParserError> {
+        self.nested(Parser::parse_type_expression)
+    }
+
+    /// https://go.dev/ref/spec#Types
+    fn parse_type_expression(&mut self) -> Result<TypeExpression, ParserError> {
+        let next = self.peek();
+        match next.kind {
+            Some(TokenKind::Identifier) => {
+                let name_token = self.advance();
+                let (name, start_pos) = (name_token.value.clone(), name_token.position);
+                if self.peek().kind != Some(TokenKind::Dot) {
+                    return Ok(TypeExpression::new_name(name, start_pos));
+                }
+                self.advance(); // consume the dot
+                let qualified = self.expect_token(TokenKind::Identifier)?;
+                Ok(TypeExpression::new(
+                    TypeExpressionKind::Qualified {
+                        package: name,
+                        name: qualified.value.clone(),
+                    },
+                    start_pos,
+                    qualified.position,
+                ))
+            }
+            Some(TokenKind::Operator(Operator::Star)) => {
+                let start_pos = self.advance().position;
+                let element = self.parse_type()?;
+                let end_pos = element.position_end;
+                Ok(TypeExpression::new(
+                    TypeExpressionKind::Pointer(Box::new(element)),
+                    start_pos,
+                    end_pos,
+                ))
+            }
+            Some(TokenKind::LeftBracket) => {
+                let start_pos = self.advance().position;
+                let length = match self.peek().kind {
+                    Some(TokenKind::RightBracket) => None,
+                    _ => Some(self.parse_expression()?),
+                };
+                self.expect_token_with_context(TokenKind::RightBracket, "in type")?;
+                let element = Box::new(self.parse_type()?);
+                let end_pos = element.position_end;
+                let kind = match length {
+                    Some(length) => TypeExpressionKind::Array {
+                        length: Box::new(length),
+                        element,
+                    },
+                    None => TypeExpressionKind::Slice(element),
+                };
+                Ok(TypeExpression::new(kind, start_pos, end_pos))
+            }
+            Some(TokenKind::Keyword(Keyword::Map)) => {
+                let start_pos = self.advance().position;
+                self.expect_token_with_context(TokenKind::LeftBracket, "after map")?;
+                let key = self.parse_type()?;
+                self.expect_token_with_context(TokenKind::RightBracket, "after map key type")?;
+                let value = self.parse_type()?;
+                let end_pos = value.position_end;
+                Ok(TypeExpression::new(
+                    TypeExpressionKind::Map {
+                        key: Box::new(key),
+                        value: Box::new(value),
+                    },
+                    start_pos,
+                    end_pos,
+                ))
+            }
+            Some(TokenKind::Keyword(Keyword::Func)) => {
+                let start_pos = self.advance().position;
+                let parameters = self.parse_parameters()?;
+                let results = self.parse_results()?;
+                Ok(TypeExpression::new(
+                    TypeExpressionKind::Function {
+                        parameters,
+                        results,
+                    },
+                    start_pos,
+                    self.current_token.position,
+                ))
+            }
+            Some(TokenKind::Keyword(Keyword::Chan)) => {
+                let start_pos = self.advance().position;
+                let direction = match self.peek().kind {
+                    Some(TokenKind::LessMinus) => {
+                        self.advance();
+                        ChannelDirection::Send
+                    }
+                    _ => ChannelDirection::Both,
+                };
+                self.parse_channel_type(direction, start_pos)
+            }
+            Some(TokenKind::LessMinus) => {
+                let start_pos = self.advance().position;
+                self.expect_token_with_context(TokenKind::Keyword(Keyword::Chan), "after '<-'")?;
+                self.parse_channel_type(ChannelDirection::Receive, start_pos)
+            }
+            Some(TokenKind::Keyword(Keyword::Struct)) => self.parse_struct_type(),
+            Some(TokenKind::Keyword(Keyword::Interface)) => self.parse_interface_type(),
+            Some(TokenKind::LeftParen) => {
+                self.advance();
+                let inner = self.parse_type()?;
+                self.expect_token_with_context(TokenKind::RightParen, "after type")?;
+                Ok(inner)
+            }
+            _ => Err(ParserError::new(
+                ParserErrorKind::NotAType(next.value.to_string()),
+                next.position,
+            )),
+        }
+    }
+
+    // struct { x, y int; io.Reader; *Node `json:"node"` }
+    fn parse_struct_type(&mut self) -> Result<TypeExpression, ParserError> {
+        let start_pos = self.advance().position; // consume 'struct'
+        self.expect_token_with_context(TokenKind::LeftBrace, "after struct")?;
+        let mut fields = Vec::new();
+        while !matches!(
+            self.peek().kind,
+            Some(TokenKind::RightBrace) | Some(TokenKind::EOF)
+        ) {
+            fields.push(self.parse_struct_field()?);
+            self.handle_semicolon_insertion()?;
+        }
+        let end_pos = self
+            .expect_token_with_context(TokenKind::RightBrace, "after struct fields")?
+            .position;
+        Ok(TypeExpression::new(
+            TypeExpressionKind::Struct(fields),
+            start_pos,
+            end_pos,
+        ))
+    }
+
+    /// `x, y int`, or an embedded `T`, `*T` or `pkg.T`, followed by an optional tag
+    fn parse_struct_field(&mut self) -> Result<StructField, ParserError> {
+        let position_start = self.peek().position;
+        // An identifier followed by the end of the field, a tag or a `.` is an embedded type
+        let embedded = match self.peek().kind {
+            Some(TokenKind::Identifier) => matches!(
+                self.peek_nth(1).kind,
+                Some(TokenKind::Semicolon)
+                    | Some(TokenKind::RightBrace)
+                    | Some(TokenKind::StringLiteral)
+                    | Some(TokenKind::Dot)
+            ),
+            _ => true,
+        };
+        let mut names = Vec::new();
+        if !embedded {
+            names.push(self.parse_name()?);
+            while self.peek().kind == Some(TokenKind::Comma) {
+                self.advance();
+                names.push(self.parse_name()?);
+            }
+        }
+        let type_expression = self.parse_type()?;
+        let mut position_end = type_expression.position_end;
+        let tag = match self.peek().kind {
+            Some(TokenKind::StringLiteral) => {
+                let tag = self.advance();
+                position_end = tag.position;
+                Some(tag.value.clone())
+            }
+            _ => None,
+        };
+        Ok(StructField::new(
+            names,
+            type_expression,
+            tag,
+            position_start,
+            position_end,
+        ))
+    }
+
+    // interface { String() string }
+    fn parse_interface_type(&mut self) -> Result<TypeExpression, ParserError> {
+        let start_pos = self.advance().position; // consume 'interface'
+        self.expect_token_with_context(TokenKind::LeftBrace, "after interface")?;
+        let mut methods = Vec::new();
+        while !matches!(
+            self.peek().kind,
+            Some(TokenKind::RightBrace) | Some(TokenKind::EOF)
+        ) {
+            let name_token = self.expect_token(TokenKind::Identifier)?;
+            let (name, method_start) = (name_token.value.clone(), name_token.position);
+            let parameters = self.parse_parameters()?;
+            let results = self.parse_results()?;
+            let method_end = match results.last() {
+                Some(result) => result.position_end,
+                None => self.current_token.position,
+            };
+            methods.push(InterfaceMethod::new(
+                name,
+                parameters,
+                results,
+                method_start,
+                method_end,
+            ));
+            self.handle_semicolon_insertion()?;
+        }
+        let end_pos = self
+            .expect_token_with_context(TokenKind::RightBrace, "after interface methods")?
+            .position;
+        Ok(TypeExpression::new(
+            TypeExpressionKind::Interface(methods),
+            start_pos,
+            end_pos,
+        ))
+    }
+
+    fn parse_channel_type(
+        &mut self,
+        direction: ChannelDirection,
+        start_pos: Position,
+    ) -> Result<TypeExpression, ParserError> {
+        let element = self.parse_type()?;
+        let end_pos = element.position_end;
+        Ok(TypeExpression::new(
+            TypeExpressionKind::Channel {
+                direction,
+                element: Box::new(element),
+            },
+            start_pos,
+            end_pos,
+        ))
+    }
+
+    /// Parse the selectors and calls chained onto an operand (field access, function calls, etc)
+    fn parse_postfix_expression(
+        &mut self,
+        mut expression: Expression,
+    ) -> Result<Expression, ParserError> {
+        loop {
+            match self.peek().kind {
+                Some(TokenKind::Dot) => {
+                    // Field access: obj.field
+                    self.advance(); // consume the dot
+                    let field_token = self.expect_token(TokenKind::Identifier)?;
+                    let start_pos = expression.position_start;
+                    let end_pos = field_token.position;
+
+                    expression = Expression::new_field_access(
+                        expression,
+                        field_token.value.clone(),
+                        start_pos,
+                        end_pos,
+                    );
+                }
+                Some(TokenKind::LeftParen) => {
+                    // Function call: expr(args)
+                    let start_pos = expression.position_start;
+                    self.advance(); // consume the '('
+
+                    let mut arguments = Vec::new();
+                    let mut spread = false;
+
+                    if !matches!(self.peek().kind, Some(TokenKind::RightParen)) {
+                        loop {
+                            arguments.push(self.parse_enclosed_expression()?);
+                            // `...` may only follow the final argument
+                            spread = self.parse_variadic();
+
+                            if matches!(self.peek().kind, Some(TokenKind::Comma)) {
+                                self.advance(); // consume comma
+                            } else {
+                                break;
+                            }
+                            // A trailing comma, as in a call split over several lines
+                            if spread || matches!(self.peek().kind, Some(TokenKind::RightParen)) {
+                                break;
+                            }
+                        }
+                    }
+
+                    let right_paren =
+                        self.expect_token_with_context(TokenKind::RightParen, "after arguments")?;
+                    let end_pos = right_paren.position;
+
+                    expression = Expression::new_function_call(
+                        expression, arguments, spread, start_pos, end_pos,
+                    );
+                }
+                Some(TokenKind::LeftBracket) => {
+                    // Index or slice: expr[i], expr[low:high] or expr[low:high:max]
+                    let start_pos = expression.position_start;
+                    self.advance(); // consume the '['
+                    let low = if self.peek().kind == Some(TokenKind::Colon) {
+                        None
+                    } else {
+                        let index = self.parse_enclosed_expression()?;
+                        if self.peek().kind != Some(TokenKind::Colon) {
+                            let end_pos = self
+                                .expect_token_with_context(TokenKind::RightBracket, "after index")?
+                                .position;
+                            expression =
+                                Expression::new_index(expression, index, start_pos, end_pos);
+                            continue;
+                        }
+                        Some(index)
+                    };
+                    self.advance(); // consume the ':'
+                    let high = match self.peek().kind {
+                        Some(TokenKind::Colon) | Some(TokenKind::RightBracket) => None,
+                        _ => Some(self.parse_enclosed_expression()?),
+                    };
+                    let mut max = None;
+                    if self.peek().kind == Some(TokenKind::Colon) {
+                        let colon = self.advance().position;
+                        if high.is_none() {
+                            return Err(ParserError::new(
+                                ParserErrorKind::MissingSliceIndex("Middle"),
+                                colon,
+                            ));
+                        }
+                        if self.peek().kind == Some(TokenKind::RightBracket) {
+                            return Err(ParserError::new(
+                                ParserErrorKind::MissingSliceIndex("Final"),
+                                self.peek().position,
+                            ));
+                        }
+                        max = Some(self.parse_enclosed_expression()?);
+                    }
+                    let end_pos = self
+                        .expect_token_with_context(TokenKind::RightBracket, "after slice indices")?
+                        .position;
+                    expression =
+                        Expression::new_slice(expression, low, high, max, start_pos, end_pos);
+                }
+                // A composite literal of a named type: T{...} or pkg.T{...}
+                Some(TokenKind::LeftBrace) if !self.control_clause => {
+                    let Some(type_expression) = literal_type(&expression) else {
+                        break;
+                    };
+                    let start_pos = expression.position_start;
+                    expression = self.parse_composite_literal(Some(type_expression), start_pos)?;
+                }
+                _ => {
+                    // No more chaining, return the expression
+                    break;
+                }
+            }
+        }
+
+        Ok(expression)
+    }
+
+    fn handle_semicolon_insertion(&mut self) -> Result<Position, ParserError> {
+        if self.peek().kind == Some(TokenKind::Semicolon) {
+            let semicolon = self.advance();
+            Ok(semicolon.position)
+        } else if self.can_omit_semicolon() {
+            Ok(self.current_token.position)
+        } else {
+            Err(self.expected(
+                vec![TokenKind::Semicolon],
+                Some("to separate statements on the same line"),
+            ))
+        }
+    }
+
+    /// Newlines are already turned into semicolons by `next_token`, so the only places a
+    /// semicolon may be left out are at EOF and before a closing ')' or '}'.
+    fn can_omit_semicolon(&self) -> bool {
+        matches!(
+            self.peek().kind,
+            Some(TokenKind::EOF) | Some(TokenKind::RightParen) | Some(TokenKind::RightBrace)
+        )
+    }
+
+    /// Skip tokens until a statement boundary: a ';', the '}' closing the current block, or a
+    /// keyword that starts a new construct in this context. Nested braces are skipped whole.
+    fn synchronize(&mut self, context: RecoveryContext) {
+        let mut depth = 0;
+        loop {
+            match self.peek().kind {
+                Some(TokenKind::EOF) => return,
+                Some(TokenKind::Semicolon) if depth == 0 => return,
+                Some(TokenKind::RightBrace) if depth == 0 && context == RecoveryContext::Block => {
+                    return;
+                }
+                // A stray '}' at the top level closes nothing, skip over it
+                Some(TokenKind::RightBrace) if depth == 0 => {}
+                Some(TokenKind::RightBrace) => depth -= 1,
+                Some(TokenKind::LeftBrace) => depth += 1,
+                Some(TokenKind::Keyword(keyword))
+                    if depth == 0 && context.is_sync_keyword(keyword) =>
+                {
+                    return;
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+}
+
+/// The text of a comment group as go/doc gives it: without its markers, the space after a
+/// `//` or directives like `//go:generate`, and without blank lines at either end. None if
+/// that leaves nothing.
+fn comment_text(comments: &[&Token]) -> Option<String> {
+    let mut lines = Vec::new();
+    for comment in comments {
+        match comment.value.strip_prefix("//") {
+            Some(text) if text.starts_with("go:") || text.starts_with("line ") => {}
+            Some(text) => lines.push(text.strip_prefix(' ').unwrap_or(text).trim_end()),
+            None => {
+                let text = comment.value.trim_start_matches("/*");
+                let text = text.strip_suffix("*/").unwrap_or(text);
+                lines.extend(text.lines().map(str::trim_end));
+            }
+        }
+    }
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    let start = lines.iter().position(|line| !line.is_empty())?;
+    Some(lines[start..].join("\n"))
+}
+
+/// Whether `statement` may be a select case: a send, a receive, or a receive assigned to at
+/// most two variables (https://go.dev/ref/spec#Select_statements)
+/// The type a composite literal's `T` or `pkg.T` names, or None if the expression before the
+/// `{` can't be a type name
+fn literal_type(expression: &Expression) -> Option<TypeExpression> {
+    match &expression.kind {
+        ExpressionKind::Identifier(name) => Some(TypeExpression::new_name(
+            name.clone(),
+            expression.position_start,
+        )),
+        ExpressionKind::FieldAccess { object, field, .. } => match &object.kind {
+            ExpressionKind::Identifier(package) => Some(TypeExpression::new(
+                TypeExpressionKind::Qualified {
+                    package: package.clone(),
+                    name: field.clone(),
+                },
+                expression.position_start,
+                expression.position_end,
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_communication(statement: &Statement) -> bool {
+    fn is_receive(expression: &Expression) -> bool {
+        match &expression.kind {
+            ExpressionKind::Receive(_) => true,
+            ExpressionKind::Parenthesized(inner) => is_receive(inner),
+            _ => false,
+        }
+    }
+    match &statement.kind {
+        StatementKind::Send { .. } => true,
+        StatementKind::Expression(expression) => is_receive(expression),
+        StatementKind::Assignment { left, right, .. } => {
+            left.len() <= 2 && right.len() == 1 && is_receive(&right[0])
+        }
+        _ => false,
+    }
+}
+
+/// Parse a whole file, converting every error into a diagnostic. Never panics, whatever the
+/// input: a panic inside the lexer or parser is caught and reported as a diagnostic instead,
+/// which makes this the entry point for fuzzing. The file is returned whenever parsing got far
+/// enough to produce one, even if errors were recovered from along the way.
+pub fn parse_checked(input: &str) -> (Option<File>, Vec<Diagnostic>) {
+    let result = std::panic::catch_unwind(|| {
+        let mut parser = Parser::new(input);
+        let result = parser.parse();
+        (result, parser.errors)
+    });
+    match result {
+        Ok((Ok(file), errors)) => (Some(file), errors.iter().map(Diagnostic::from).collect()),
+        Ok((Err(errors), _)) => (None, errors.iter().map(Diagnostic::from).collect()),
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            let diagnostic = Diagnostic::error(
+                format!("internal parser error: {}", message),
+                Position::new(1, 0, 0),
+            )
+            .with_code("internal");
+            (None, vec![diagnostic])
+        }
+    }
+}
+
+mod tests {
+    #[cfg(test)]
+    mod tests {
+        use crate::{
+            ast::{
+                arena::{ArenaDeclarationKind, ArenaExpressionKind, ArenaStatementKind},
+                ast::ImportSpec,
+                declaration::DeclarationKind,
+                expression::{Expression, ExpressionKind, SelectionKind, UnaryOperator},
+                statement::{Statement, StatementKind},
+                type_expression::{TypeExpression, TypeExpressionKind},
+            },
+            lexer::{
+                lexer::Lexer,
+                token::Token,
+                token_type::{Keyword, TokenKind},
+            },
+            parser::parser::{Parser, RecoveryContext},
+            primitives::{
+                errors::{
+                    lexer::{LexerError, LexerErrorKind},
+                    parser::ParserErrorKind,
+                },
+                position::Position,
+            },
+        };
+
+        /// Parse a sequence of statements without the surrounding file structure
+        fn parse_statements(parser: &mut Parser) -> Vec<Statement> {
+            let mut statements = Vec::new();
+            while !matches!(parser.peek().kind, Some(TokenKind::EOF)) {
+                let started_at = parser.tokens_consumed;
+                match parser.parse_statement() {
+                    Ok(statement) => statements.push(statement),
+                    Err(error) => parser.recover(error, RecoveryContext::Block, started_at),
+                }
+            }
+            statements
+        }
+
+        #[test]
+        fn parser_parse_program() {
+            let input = "identifier;";
+            let mut parser = Parser::new(input);
+            let statements = parse_statements(&mut parser);
+            assert_eq!(statements.len(), 1);
+            assert_eq!(
+                statements[0].kind,
                 StatementKind::Expression(Expression::new_identifier(
                     "identifier".to_string(),
                     Position::new(1, 0, 10)
@@ -361,356 +2542,1666 @@ mod tests {
         }
 
         #[test]
-        fn parser_initialization() {
-            let input = "func main";
-            let parser = Parser::new(input);
+        fn parser_initialization() {
+            let input = "func main";
+            let parser = Parser::new(input);
+
+            // Parser should start "before" the first token
+            assert_eq!(parser.current_token.kind, Some(TokenKind::BeforeStart));
+            assert_eq!(
+                parser.peek_token.kind,
+                Some(TokenKind::Keyword(Keyword::Func))
+            );
+            assert_eq!(parser.errors.len(), 0);
+        }
+
+        #[test]
+        fn advance_simple() {
+            let input = "func main";
+            let mut parser = Parser::new(input);
+
+            // First advance should get "func"
+            let token = parser.advance();
+            assert_eq!(token.kind, Some(TokenKind::Keyword(Keyword::Func)));
+            assert_eq!(token.value, "func");
+
+            // Peek should now be "main"
+            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
+            assert_eq!(parser.peek().value, "main");
+        }
+
+        #[test]
+        fn advance_to_eof() {
+            let input = "func";
+            let mut parser = Parser::new(input);
+
+            // First advance gets "func"
+            parser.advance();
+            assert_eq!(
+                parser.current_token.kind,
+                Some(TokenKind::Keyword(Keyword::Func))
+            );
+
+            // Second advance gets EOF
+            parser.advance();
+            assert_eq!(parser.current_token.kind, Some(TokenKind::EOF));
+
+            // Peek should also be EOF
+            assert_eq!(parser.peek().kind, Some(TokenKind::EOF));
+        }
+
+        #[test]
+        fn expect_token_success() {
+            let input = "func main";
+            let mut parser = Parser::new(input);
+
+            // Expect "func" - should succeed
+            let token = parser
+                .expect_token(TokenKind::Keyword(Keyword::Func))
+                .unwrap();
+            assert_eq!(token.kind, Some(TokenKind::Keyword(Keyword::Func)));
+            assert_eq!(token.value, "func");
+            assert_eq!(parser.errors.len(), 0);
+
+            // Current token should now be "func", peek should be "main"
+            assert_eq!(
+                parser.current_token.kind,
+                Some(TokenKind::Keyword(Keyword::Func))
+            );
+            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
+        }
+
+        #[test]
+        fn expect_token_failure() {
+            let input = "func main";
+            let mut parser = Parser::new(input);
+
+            // Expect "var" but get "func" - should fail without consuming anything
+            let result = parser.expect_token(TokenKind::Keyword(Keyword::Var));
+            assert!(result.is_err());
+
+            // Recording and recovering is left to the enclosing production
+            assert_eq!(parser.errors.len(), 0);
+            assert_eq!(parser.current_token.kind, Some(TokenKind::BeforeStart));
+            assert_eq!(parser.peek().kind, Some(TokenKind::Keyword(Keyword::Func)));
+        }
+
+        #[test]
+        fn synchronize_to_semicolon() {
+            let input = "func main ( ) ;";
+            let mut parser = Parser::new(input);
+
+            // Expect something wrong to trigger synchronization
+            let error = parser
+                .expect_token(TokenKind::Keyword(Keyword::Var))
+                .unwrap_err(); // Wrong token
+            parser.errors.push(error);
+            parser.synchronize(RecoveryContext::Block);
+
+            // Should have synchronized to before the semicolon
+            assert_eq!(parser.errors.len(), 1);
+            assert_eq!(parser.peek().kind, Some(TokenKind::Semicolon));
+        }
+
+        #[test]
+        fn synchronize_to_eof() {
+            let input = "func main";
+            let mut parser = Parser::new(input);
+
+            // Expect wrong token to trigger synchronization
+            let error = parser
+                .expect_token(TokenKind::Keyword(Keyword::Var))
+                .unwrap_err(); // Wrong token
+            parser.errors.push(error);
+            parser.synchronize(RecoveryContext::Block);
+
+            // Should synchronize to EOF since there's no semicolon
+            assert_eq!(parser.errors.len(), 1);
+            assert_eq!(parser.peek().kind, Some(TokenKind::EOF));
+        }
+
+        #[test]
+        fn peek_consistency() {
+            let input = "func main ( )";
+            let mut parser = Parser::new(input);
+
+            // Peek should be consistent
+            assert_eq!(parser.peek().kind, Some(TokenKind::Keyword(Keyword::Func)));
+            assert_eq!(parser.peek().kind, Some(TokenKind::Keyword(Keyword::Func))); // Multiple peeks
+
+            // Advance and check again
+            parser.advance();
+            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
+            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier)); // Multiple peeks
+        }
+
+        #[test]
+        fn empty_input() {
+            let input = "";
+            let parser = Parser::new(input);
+
+            // Should handle empty input gracefully
+            assert_eq!(parser.peek().kind, Some(TokenKind::EOF));
+            assert_eq!(parser.errors.len(), 0);
+        }
+
+        #[test]
+        fn whitespace_handling() {
+            let input = "  func   main  ";
+            let mut parser = Parser::new(input);
+
+            // Whitespace should be ignored
+            assert_eq!(parser.peek().kind, Some(TokenKind::Keyword(Keyword::Func)));
+
+            parser.advance();
+            assert_eq!(
+                parser.current_token.kind,
+                Some(TokenKind::Keyword(Keyword::Func))
+            );
+            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
+        }
+
+        #[test]
+        fn multiple_advances() {
+            let input = "func main ( ) { }";
+            let mut parser = Parser::new(input);
+
+            // Test sequence of advances
+            parser.advance(); // func
+            assert_eq!(
+                parser.current_token.kind,
+                Some(TokenKind::Keyword(Keyword::Func))
+            );
+
+            parser.advance(); // main
+            assert_eq!(parser.current_token.kind, Some(TokenKind::Identifier));
+
+            parser.advance(); // (
+            assert_eq!(parser.current_token.kind, Some(TokenKind::LeftParen));
+
+            parser.advance(); // )
+            assert_eq!(parser.current_token.kind, Some(TokenKind::RightParen));
+
+            parser.advance(); // {
+            assert_eq!(parser.current_token.kind, Some(TokenKind::LeftBrace));
+
+            parser.advance(); // }
+            assert_eq!(parser.current_token.kind, Some(TokenKind::RightBrace));
+
+            parser.advance(); // EOF
+            assert_eq!(parser.current_token.kind, Some(TokenKind::EOF));
+        }
+
+        #[test]
+        fn semicolon_insertion_single_statement_no_semicolon() {
+            let input = "println()";
+            let mut parser = Parser::new(input);
+            let statements = parse_statements(&mut parser);
+            assert!(
+                parser.errors.is_empty(),
+                "Should parse single statement without semicolon"
+            );
+            assert_eq!(statements.len(), 1);
+        }
+
+        #[test]
+        fn semicolon_insertion_single_statement_with_semicolon() {
+            let input = "println();";
+            let mut parser = Parser::new(input);
+            let statements = parse_statements(&mut parser);
+            assert!(
+                parser.errors.is_empty(),
+                "Should parse single statement with semicolon"
+            );
+            assert_eq!(statements.len(), 1);
+        }
+
+        #[test]
+        fn semicolon_insertion_multiple_statements_no_semicolon_error() {
+            let input = "println() println()";
+            let mut parser = Parser::new(input);
+            let _ = parse_statements(&mut parser);
+            assert!(
+                !parser.errors.is_empty(),
+                "Should have errors for multiple statements without semicolon"
+            );
+        }
+
+        #[test]
+        fn semicolon_insertion_multiline_with_newlines() {
+            let input = "println()\nprintln()\nprintln()";
+            let mut parser = Parser::new(input);
+            let statements = parse_statements(&mut parser);
+            assert!(
+                parser.errors.is_empty(),
+                "Should parse multiple lines without semicolons"
+            );
+            assert_eq!(statements.len(), 3);
+        }
+
+        #[test]
+        fn parse_string_literal() {
+            let input = r#""Hello, World!""#;
+            let mut parser = Parser::new(input);
+            let result = parser.parse_expression();
+            assert!(result.is_ok(), "Should parse string literal");
+            assert_eq!(
+                result.unwrap(),
+                Expression::new_string_literal(
+                    r#""Hello, World!""#.to_string(),
+                    Position::new(1, 0, 15)
+                )
+            );
+        }
+
+        #[test]
+        fn parse_simple_function_call() {
+            let input = r#"println("hello")"#;
+            let mut parser = Parser::new(input);
+            let statements = parse_statements(&mut parser);
+            assert!(
+                parser.errors.is_empty(),
+                "Should parse simple function call"
+            );
+            assert_eq!(statements.len(), 1);
+        }
+
+        #[test]
+        fn parse_method_call() {
+            let input = r#"fmt.Println("hello")"#;
+            let mut parser = Parser::new(input);
+            let statements = parse_statements(&mut parser);
+            assert!(parser.errors.is_empty(), "Should parse method call");
+            assert_eq!(statements.len(), 1);
+        }
 
-            // Parser should start "before" the first token
-            assert_eq!(parser.current_token.kind, Some(TokenKind::BeforeStart));
+        #[test]
+        fn parse_package_declaration() {
+            let input = "package main";
+            let mut parser = Parser::new(input);
+            let result = parser.parse();
+            assert!(result.is_ok(), "Should parse package declaration");
+            assert!(parser.errors.is_empty());
+            let file = result.unwrap();
+            assert_eq!(file.package_name, "main");
+            assert!(file.imports.is_empty());
+            assert!(file.decls.is_empty());
+        }
+
+        #[test]
+        fn parse_import_declaration() {
+            let input = "package main\nimport \"fmt\"";
+            let mut parser = Parser::new(input);
+            let result = parser.parse();
+            assert!(result.is_ok(), "Should parse import declaration");
+            assert!(parser.errors.is_empty());
+            let file = result.unwrap();
             assert_eq!(
-                parser.peek_token.kind,
-                Some(TokenKind::Keyword(Keyword::Func))
+                file.imports,
+                vec![ImportSpec::new(
+                    None,
+                    "\"fmt\"".to_string(),
+                    Position::new(2, 7, 12),
+                    Position::new(2, 7, 12)
+                )]
+            );
+        }
+
+        #[test]
+        fn parse_grouped_import_declaration() {
+            let input = "package main\n\nimport (\n\t\"fmt\"\n\tstr \"strings\"\n\t_ \"os\"\n)\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let imports: Vec<(Option<&str>, &str)> = file
+                .imports
+                .iter()
+                .map(|spec| (spec.name.as_deref(), spec.path.as_str()))
+                .collect();
+            assert_eq!(
+                imports,
+                vec![
+                    (None, "\"fmt\""),
+                    (Some("str"), "\"strings\""),
+                    (Some("_"), "\"os\"")
+                ]
+            );
+        }
+
+        #[test]
+        fn missing_package_clause() {
+            let input = "func main() {}";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(parser.errors[0].kind, ParserErrorKind::MissingPackageClause);
+            assert_eq!(file.package_name, "");
+        }
+
+        #[test]
+        fn import_after_declaration() {
+            let input = "package main\nfunc main() {}\nimport \"fmt\"";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(parser.errors.len(), 1);
+            assert_eq!(
+                parser.errors[0].kind,
+                ParserErrorKind::ImportAfterDeclaration
+            );
+            assert_eq!(parser.errors[0].position.line, 3);
+            assert_eq!(file.decls.len(), 1);
+        }
+
+        #[test]
+        fn statement_outside_function() {
+            let input = "package main\nprintln()";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(parser.errors.len(), 1);
+            assert_eq!(
+                parser.errors[0].kind,
+                ParserErrorKind::NonDeclarationStatementOutsideFunction
+            );
+            assert!(file.decls.is_empty());
+        }
+
+        #[test]
+        fn parse_simple_function_declaration() {
+            let input = "package main\nfunc main() {\n    println(\"hello\")\n}";
+            let mut parser = Parser::new(input);
+            let result = parser.parse();
+            assert!(result.is_ok(), "Should parse function declaration");
+            assert!(parser.errors.is_empty());
+            let file = result.unwrap();
+            assert_eq!(file.decls.len(), 1);
+            match &file.decls[0].kind {
+                DeclarationKind::Function { name, body, .. } => {
+                    assert_eq!(name, "main");
+                    assert_eq!(body.len(), 1);
+                }
+                kind => panic!("expected a function, got {:?}", kind),
+            }
+        }
+
+        #[test]
+        fn parse_complete_hello_world() {
+            let input = r#"package main
+
+import "fmt"
+
+func main() {
+    fmt.Println("Hello, World!")
+}
+"#;
+            let mut parser = Parser::new(input);
+            let result = parser.parse();
+            assert!(result.is_ok(), "Should parse complete Hello World program");
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let file = result.unwrap();
+            assert_eq!(file.package_name, "main");
+            assert_eq!(file.imports.len(), 1);
+            assert_eq!(file.decls.len(), 1);
+        }
+
+        // Binary Expression Tests
+        #[test]
+        fn parse_binary_expressions() {
+            let expressions = vec![
+                "a + b",          // Addition
+                "x * y",          // Multiplication
+                "p - q",          // Subtraction
+                "m / n",          // Division
+                "a % b",          // Modulo
+                "x == y",         // Equality
+                "a != b",         // Inequality
+                "p < q",          // Less than
+                "m <= n",         // Less than or equal
+                "a > b",          // Greater than
+                "x >= y",         // Greater than or equal
+                "a + b * c",      // Precedence test
+                "(a + b) * c",    // Parentheses
+                "obj.field + 42", // Mixed field access and literals
+            ];
+
+            for expr in expressions {
+                let mut parser = Parser::new(expr);
+                let result = parser.parse_expression();
+                assert!(
+                    parser.errors.is_empty(),
+                    "Should parse binary expression: {}\n{}",
+                    expr,
+                    parser.dump_errors()
+                );
+                assert!(result.is_ok(), "Should parse binary expression: {}", expr);
+                assert_eq!(
+                    parser.peek().kind,
+                    Some(TokenKind::EOF),
+                    "Should consume the whole expression: {}",
+                    expr
+                );
+            }
+        }
+
+        #[test]
+        fn expression_statement_must_be_call() {
+            let statements = vec!["a + b", "42", r#""hello""#, "obj.field", "(a + b) * c"];
+
+            for statement in statements {
+                let mut parser = Parser::new(statement);
+                let statements = parse_statements(&mut parser);
+                assert_eq!(statements.len(), 1);
+                assert_eq!(
+                    parser.errors.len(),
+                    1,
+                    "Should reject expression statement: {}",
+                    statement
+                );
+                assert_eq!(
+                    parser.errors[0].kind,
+                    ParserErrorKind::ExpressionNotUsedAsStatement
+                );
+            }
+        }
+
+        #[test]
+        fn expression_statement_error_spans_expression() {
+            let input = "foo()\n1 + 2";
+            let mut parser = Parser::new(input);
+            let _ = parse_statements(&mut parser);
+            assert_eq!(parser.errors.len(), 1);
+            assert_eq!(parser.errors[0].position, Position::new(2, 0, 5));
+        }
+
+        #[test]
+        fn expression_statement_calls_are_valid() {
+            let statements = vec!["foo()", "fmt.Println(a + b)", "(foo())"];
+
+            for statement in statements {
+                let mut parser = Parser::new(statement);
+                let _ = parse_statements(&mut parser);
+                assert!(
+                    parser.errors.is_empty(),
+                    "Should accept expression statement: {}\n{}",
+                    statement,
+                    parser.dump_errors()
+                );
+            }
+        }
+        #[test]
+        fn spans_cover_expressions() {
+            let input = "package main\n\nfunc main() {\n\tfmt.Println((a + 1) * b, \"s\")\n}\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            assert_eq!(file.decls[0].position_start, Position::new(3, 0, 4));
+            assert_eq!(file.decls[0].position_end, Position::new(5, 0, 1));
+
+            let statement = &body[0];
+            assert_eq!(statement.position_start, Position::new(4, 1, 4));
+            assert_eq!(statement.position_end, Position::new(4, 29, 30));
+
+            let StatementKind::Expression(call) = &statement.kind else {
+                panic!("Expected an expression statement, got {:?}", statement.kind);
+            };
+            assert_eq!(call.position_start, Position::new(4, 1, 4));
+            assert_eq!(call.position_end, Position::new(4, 29, 30));
+
+            let ExpressionKind::FunctionCall {
+                name, arguments, ..
+            } = &call.kind
+            else {
+                panic!("Expected a function call, got {:?}", call.kind);
+            };
+            // fmt.Println
+            assert_eq!(name.position_start, Position::new(4, 1, 4));
+            assert_eq!(name.position_end, Position::new(4, 5, 12));
+
+            // (a + 1) * b
+            let product = &arguments[0];
+            assert_eq!(product.position_start, Position::new(4, 13, 14));
+            assert_eq!(product.position_end, Position::new(4, 23, 24));
+            let ExpressionKind::Binary { left, .. } = &product.kind else {
+                panic!("Expected a binary expression, got {:?}", product.kind);
+            };
+            // (a + 1)
+            assert_eq!(left.position_start, Position::new(4, 13, 14));
+            assert_eq!(left.position_end, Position::new(4, 19, 20));
+            let ExpressionKind::Parenthesized(sum) = &left.kind else {
+                panic!("Expected a parenthesized expression, got {:?}", left.kind);
+            };
+            // a + 1
+            assert_eq!(sum.position_start, Position::new(4, 14, 15));
+            assert_eq!(sum.position_end, Position::new(4, 18, 19));
+
+            // "s"
+            assert_eq!(arguments[1].position_start, Position::new(4, 26, 29));
+            assert_eq!(arguments[1].position_end, Position::new(4, 26, 29));
+        }
+
+        #[test]
+        fn spans_cover_calls_on_parenthesized_operands() {
+            let input = "(f)(x)";
+            let mut parser = Parser::new(input);
+            let statements = parse_statements(&mut parser);
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            assert_eq!(statements[0].position_start, Position::new(1, 0, 1));
+            assert_eq!(statements[0].position_end, Position::new(1, 5, 6));
+        }
+
+        #[test]
+        fn spans_cover_import_specs() {
+            let input = "package main\nimport (\n\tstr \"strings\"\n)";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(file.imports[0].position_start, Position::new(3, 1, 4));
+            assert_eq!(file.imports[0].position_end, Position::new(3, 5, 14));
+        }
+        #[test]
+        fn recover_within_function_body() {
+            let input = "package main\n\nfunc main() {\n\tfoo(\n\tbar()\n\tbaz()\n}\n\nfunc other() {\n\tqux()\n}\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(parser.errors.len(), 1, "{}", parser.dump_errors());
+            assert_eq!(file.decls.len(), 2);
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            // `foo(` swallows `bar()` as its argument before failing, `baz()` survives
+            assert_eq!(body.len(), 1);
+            assert_eq!(body[0].to_source(), "baz()");
+        }
+
+        #[test]
+        fn report_one_error_per_line() {
+            let input = "package main\n\nfunc main() {\n\tfor i := 0; i < 10; j := i {\n\t}\n\tf(_, _)\n\ts := 1 + \"a\n}\n";
+            let mut parser = Parser::new(input);
+            let _ = parser.parse();
+            let errors: Vec<String> = parser
+                .errors
+                .iter()
+                .map(|error| format!("{}:{}", error.position.line, error.position.column_start))
+                .collect();
+            // Each blank identifier is an error of its own, and the unterminated string is
+            // reported rather than what the parser made of it
+            assert_eq!(
+                errors[..4],
+                ["4:21", "6:3", "6:6", "7:10"],
+                "{}",
+                parser.dump_errors()
+            );
+            assert!(matches!(
+                parser.errors[3].kind,
+                ParserErrorKind::LexerError(_)
+            ));
+        }
+
+        #[test]
+        fn recover_to_closing_brace() {
+            let input = "package main\nfunc main() { foo(; bar() }\nfunc other() {}";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(parser.errors.len(), 1, "{}", parser.dump_errors());
+            assert_eq!(file.decls.len(), 2);
+        }
+
+        #[test]
+        fn recover_to_top_level_keyword() {
+            let input = "package main\nfunc broken( {\n\tfoo()\n}\nfunc main() {\n\tbar()\n}";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(parser.errors.len(), 1, "{}", parser.dump_errors());
+            assert_eq!(file.decls.len(), 1);
+            let DeclarationKind::Function { name, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            assert_eq!(name, "main");
+        }
+
+        #[test]
+        fn synchronize_skips_nested_braces() {
+            let input = "x { a; { b } c } ; d";
+            let mut parser = Parser::new(input);
+            parser.synchronize(RecoveryContext::Block);
+            assert_eq!(parser.peek().kind, Some(TokenKind::Semicolon));
+            assert_eq!(parser.peek().position.column_start, 17);
+        }
+
+        #[test]
+        fn synchronize_stops_at_case_and_default() {
+            for (input, keyword) in [
+                ("x y case", Keyword::Case),
+                ("x y default", Keyword::Default),
+            ] {
+                let mut parser = Parser::new(input);
+                parser.synchronize(RecoveryContext::Block);
+                assert_eq!(parser.peek().kind, Some(TokenKind::Keyword(keyword)));
+            }
+        }
+
+        #[test]
+        fn recover_from_error_on_sync_keyword() {
+            let input = "package main\npackage other\nfunc main() {}";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(parser.errors.len(), 1, "{}", parser.dump_errors());
+            assert_eq!(file.decls.len(), 1);
+        }
+        #[test]
+        fn expected_error_reports_expected_and_found() {
+            // `a {` would start a composite literal
+            let input = "foo(a ]";
+            let mut parser = Parser::new(input);
+            let _ = parse_statements(&mut parser);
+            let error = &parser.errors[0];
+            let ParserErrorKind::Expected {
+                expected, found, ..
+            } = &error.kind
+            else {
+                panic!("Expected an Expected error, got {:?}", error.kind);
+            };
+            assert_eq!(expected, &vec![TokenKind::RightParen]);
+            assert_eq!(found.kind, Some(TokenKind::RightBracket));
+            assert_eq!(error.position, Position::new(1, 6, 7));
+            assert_eq!(
+                error.kind.to_string(),
+                "expected ')' after arguments, found ']'"
+            );
+        }
+
+        #[test]
+        fn expected_error_describes_newlines_and_eof() {
+            let mut parser = Parser::new("package main\nfunc main() {\n\tfoo(a\n}");
+            let _ = parser.parse();
+            assert_eq!(
+                parser.errors[0].kind.to_string(),
+                "expected ')' after arguments, found newline"
+            );
+
+            let mut parser = Parser::new("package main\nfunc main() {\n\tfoo()");
+            let _ = parser.parse();
+            assert_eq!(
+                parser.errors[0].kind.to_string(),
+                "expected '}' to close block, found EOF"
+            );
+        }
+
+        #[test]
+        fn expected_error_lists_alternatives() {
+            let mut parser = Parser::new("package main\npackage other");
+            let _ = parser.parse();
+            assert_eq!(
+                parser.errors[0].kind.to_string(),
+                "expected 'func', 'type', 'var' or 'const', found 'package'"
+            );
+        }
+        #[test]
+        fn parse_expression_fragment() {
+            let expression = Parser::parse_expression_str("a + b * c").unwrap();
+            assert_eq!(expression.to_source(), "a + b * c");
+
+            let expression = Parser::parse_expression_str("fmt.Sprint(x)\n").unwrap();
+            assert_eq!(expression.to_source(), "fmt.Sprint(x)");
+        }
+
+        #[test]
+        fn parse_expression_fragment_rejects_trailing_input() {
+            let errors = Parser::parse_expression_str("a + b c").unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].kind.to_string(), "expected EOF, found 'c'");
+        }
+
+        #[test]
+        fn parse_statement_fragment() {
+            let statement = Parser::parse_statement_str("fmt.Println(x);").unwrap();
+            assert_eq!(statement.to_source(), "fmt.Println(x)");
+
+            let errors = Parser::parse_statement_str("1 + 2").unwrap_err();
+            assert_eq!(
+                errors[0].kind,
+                ParserErrorKind::ExpressionNotUsedAsStatement
+            );
+
+            let errors = Parser::parse_statement_str("foo(); bar()").unwrap_err();
+            assert_eq!(errors[0].kind.to_string(), "expected EOF, found 'bar'");
+        }
+        #[test]
+        fn parse_from_lexed_tokens() {
+            let input = "package main\n\nfunc main() {\n\tfoo()\n}\n";
+            let mut lexer = Lexer::new(input);
+            let mut tokens = Vec::new();
+            loop {
+                let token = lexer.next_token();
+                let is_eof = token.kind == Some(TokenKind::EOF);
+                tokens.push(token);
+                if is_eof {
+                    break;
+                }
+            }
+
+            let from_tokens = Parser::from_tokens(tokens).parse().unwrap();
+            let from_source = Parser::new(input).parse().unwrap();
+            assert_eq!(from_tokens, from_source);
+        }
+
+        #[test]
+        fn parse_from_synthetic_tokens() {
+            // foo(1) without a trailing EOF token
+            let tokens = vec![
+                Token::new("foo", Position::new(1, 0, 3)),
+                Token::new("(", Position::new(1, 3, 4)),
+                Token::new("1", Position::new(1, 4, 5)),
+                Token::new(")", Position::new(1, 5, 6)),
+            ];
+            let mut parser = Parser::from_tokens(tokens);
+            let statements = parse_statements(&mut parser);
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            assert_eq!(statements.len(), 1);
+            assert_eq!(statements[0].to_source(), "foo(1)");
+            assert_eq!(parser.peek().position, Position::new(1, 5, 6));
+        }
+        #[test]
+        fn peek_nth_looks_past_peek() {
+            let input = "x := 1";
+            let mut parser = Parser::new(input);
+            assert_eq!(parser.peek_nth(0).kind, Some(TokenKind::Identifier));
+            assert_eq!(parser.peek_nth(1).kind, Some(TokenKind::ColonEqual));
+            assert_eq!(parser.peek_nth(2).kind, Some(TokenKind::IntegerLiteral));
+            assert_eq!(parser.peek_nth(3).kind, Some(TokenKind::EOF));
+            assert_eq!(parser.peek_nth(5).kind, Some(TokenKind::EOF));
+
+            // Looking ahead doesn't consume anything
+            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
+            parser.advance();
+            assert_eq!(parser.current_token.kind, Some(TokenKind::Identifier));
+            assert_eq!(parser.peek().kind, Some(TokenKind::ColonEqual));
+            parser.advance();
+            parser.advance();
+            assert_eq!(parser.current_token.kind, Some(TokenKind::IntegerLiteral));
+            assert_eq!(parser.peek().kind, Some(TokenKind::EOF));
+        }
+
+        #[test]
+        fn peek_nth_applies_semicolon_insertion() {
+            let input = "foo\n(\nbar";
+            let mut parser = Parser::new(input);
+            assert_eq!(parser.peek_nth(1).kind, Some(TokenKind::Semicolon));
+            // No semicolon is inserted after '('
+            assert_eq!(parser.peek_nth(2).kind, Some(TokenKind::LeftParen));
+            assert_eq!(parser.peek_nth(3).kind, Some(TokenKind::Identifier));
+
+            let statements_input = "foo()\nbar()";
+            let mut parser = Parser::new(statements_input);
+            assert_eq!(parser.peek_nth(4).kind, Some(TokenKind::Identifier));
+            let statements = parse_statements(&mut parser);
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            assert_eq!(statements.len(), 2);
+        }
+        #[test]
+        fn lexer_errors_fail_the_parse() {
+            let input = "package main\n\nfunc main() {\n\tfoo(\"unterminated)\n\tbar()\n}\n";
+            let mut parser = Parser::new(input);
+            let errors = parser.parse().unwrap_err();
+            assert!(
+                matches!(
+                    &errors[0].kind,
+                    ParserErrorKind::LexerError(LexerError {
+                        kind: LexerErrorKind::UnterminatedString(_),
+                        ..
+                    })
+                ),
+                "{}",
+                parser.dump_errors()
+            );
+            assert_eq!(errors[0].position.line, 4);
+            // The error's position is given once, by the error rather than its message
+            assert_eq!(
+                errors[0].kind.to_string(),
+                "Unterminated string: \"unterminated)"
+            );
+        }
+
+        #[test]
+        fn lexer_errors_are_reported_once_in_source_order() {
+            let input = "package main\nfunc main() {\n\tfoo(1)\n\tx := 'ab\n}\n@";
+            let mut parser = Parser::new(input);
+            let errors = parser.parse().unwrap_err();
+            let lines: Vec<usize> = errors.iter().map(|error| error.position.line).collect();
+            let mut sorted = lines.clone();
+            sorted.sort();
+            assert_eq!(lines, sorted);
+            let lexer_errors = errors
+                .iter()
+                .filter(|error| matches!(error.kind, ParserErrorKind::LexerError(_)))
+                .count();
+            assert_eq!(lexer_errors, 2, "{}", parser.dump_errors());
+        }
+
+        #[test]
+        fn lexer_errors_fail_fragments() {
+            let errors = Parser::parse_expression_str("foo(\"bar)").unwrap_err();
+            assert!(
+                errors
+                    .iter()
+                    .any(|error| matches!(error.kind, ParserErrorKind::LexerError(_)))
             );
-            assert_eq!(parser.errors.len(), 0);
         }
 
         #[test]
-        fn advance_simple() {
-            let input = "func main";
-            let mut parser = Parser::new(input);
-
-            // First advance should get "func"
-            let token = parser.advance();
-            assert_eq!(token.kind, Some(TokenKind::Keyword(Keyword::Func)));
-            assert_eq!(token.value, "func");
+        fn parse_arena_round_trips() {
+            let input =
+                "package main\n\nfunc main() {\n\tfmt.Println(a + b * (c - 1), f(x.y))\n\tg()\n}\n";
+            let file = Parser::new(input).parse().unwrap();
+            let arena_file = Parser::new(input).parse_arena().unwrap();
+            assert_eq!(arena_file.to_file(), file);
 
-            // Peek should now be "main"
-            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
-            assert_eq!(parser.peek().value, "main");
+            // Children are allocated before their parents, so the outermost call comes last
+            let expressions = arena_file.arena.expressions();
+            assert_eq!(expressions.len(), 17);
+            let ArenaDeclarationKind::Function { body, .. } = &arena_file.decls[0].kind else {
+                unreachable!()
+            };
+            let ArenaStatementKind::Expression(id) = arena_file.arena.statement(body[0]).kind
+            else {
+                panic!("expected an expression statement");
+            };
+            match &arena_file.arena.expression(id).kind {
+                ArenaExpressionKind::FunctionCall { arguments, .. } => {
+                    assert_eq!(arguments.len(), 2);
+                    let first = arena_file.arena.expression_list(*arguments)[0];
+                    assert_eq!(
+                        arena_file.arena.to_expression(first).to_source(),
+                        "a + b * (c - 1)"
+                    );
+                }
+                kind => panic!("expected a function call, got {:?}", kind),
+            }
         }
 
         #[test]
-        fn advance_to_eof() {
-            let input = "func";
-            let mut parser = Parser::new(input);
+        fn parse_arena_round_trips_every_statement() {
+            let input = "package main
 
-            // First advance gets "func"
-            parser.advance();
-            assert_eq!(
-                parser.current_token.kind,
-                Some(TokenKind::Keyword(Keyword::Func))
-            );
+const (
+\ta = iota
+\tb
+)
 
-            // Second advance gets EOF
-            parser.advance();
-            assert_eq!(parser.current_token.kind, Some(TokenKind::EOF));
+var c, d = 1, 2
 
-            // Peek should also be EOF
-            assert_eq!(parser.peek().kind, Some(TokenKind::EOF));
+func main() {
+\tfor i := 0; i < 3; i++ {
+\t\tc += i
+\t}
+\tswitch x := c; x {
+\tcase 1, 2:
+\t\tfallthrough
+\tdefault:
+\t\tf := func(n int) int { return n * 2 }
+\t\td = f(x)
+\t}
+}
+";
+            let file = Parser::new(input).parse().unwrap();
+            let arena_file = Parser::new(input).parse_arena().unwrap();
+            assert_eq!(arena_file.to_file(), file);
         }
 
         #[test]
-        fn expect_token_success() {
-            let input = "func main";
+        fn parse_empty_statements() {
+            let input =
+                "package main\n\nfunc main() {\n\t;\n\tf();;\n\t;;g()\n}\n\nfunc empty() { ; }\n";
             let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            assert_eq!(body.len(), 2);
+            assert_eq!(body[0].to_source(), "f()");
+            assert_eq!(body[1].to_source(), "g()");
+            let DeclarationKind::Function { body, .. } = &file.decls[1].kind else {
+                unreachable!()
+            };
+            assert!(body.is_empty());
+        }
 
-            // Expect "func" - should succeed
-            let token = parser
-                .expect_token(TokenKind::Keyword(Keyword::Func))
-                .unwrap();
-            assert_eq!(token.kind, Some(TokenKind::Keyword(Keyword::Func)));
-            assert_eq!(token.value, "func");
-            assert_eq!(parser.errors.len(), 0);
+        #[test]
+        fn parse_blank_identifier() {
+            let errors = Parser::parse_expression_str("f(_, a + _)").unwrap_err();
+            assert_eq!(errors.len(), 2);
+            assert!(
+                errors
+                    .iter()
+                    .all(|error| error.kind == ParserErrorKind::BlankIdentifierAsValue)
+            );
+            assert_eq!(errors[0].position, Position::new(1, 2, 3));
+            assert_eq!(errors[1].position, Position::new(1, 9, 10));
 
-            // Current token should now be "func", peek should be "main"
+            let mut parser = Parser::new("_");
+            let expression = parser.parse_expression().unwrap();
+            assert_eq!(expression.kind, ExpressionKind::BlankIdentifier);
+            assert_eq!(expression.to_source(), "_");
+
+            // Blank import names aren't values
+            let input = "package main\n\nimport _ \"fmt\"\n\nfunc main() {\n\t_.x()\n}\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert_eq!(file.imports[0].name.as_deref(), Some("_"));
+            assert_eq!(parser.errors.len(), 1);
             assert_eq!(
-                parser.current_token.kind,
-                Some(TokenKind::Keyword(Keyword::Func))
+                parser.errors[0].kind,
+                ParserErrorKind::BlankIdentifierAsValue
             );
-            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
+            assert_eq!(parser.errors[0].position.line, 6);
         }
 
         #[test]
-        fn expect_token_failure() {
-            let input = "func main";
-            let mut parser = Parser::new(input);
+        fn parse_method_values_as_selectors() {
+            // Without types, `T.Method`, `v.Method` and `v.field` all look alike, so they're
+            // left for the type checker to resolve
+            let expression = Parser::parse_expression_str("g(T.Method, v.Method)").unwrap();
+            let ExpressionKind::FunctionCall { arguments, .. } = &expression.kind else {
+                panic!("expected a function call, got {:?}", expression.kind);
+            };
+            for (argument, (receiver, method)) in
+                arguments.iter().zip([("T", "Method"), ("v", "Method")])
+            {
+                match &argument.kind {
+                    ExpressionKind::FieldAccess {
+                        object,
+                        field,
+                        selection,
+                    } => {
+                        assert_eq!(object.to_source(), receiver);
+                        assert_eq!(field, method);
+                        assert_eq!(*selection, SelectionKind::Unresolved);
+                    }
+                    kind => panic!("expected a selector, got {:?}", kind),
+                }
+            }
+            assert_eq!(expression.to_source(), "g(T.Method, v.Method)");
+        }
 
-            // Expect "var" but get "func" - should fail and synchronize
-            let _token = parser.expect_token(TokenKind::Keyword(Keyword::Var));
-            assert_eq!(parser.errors.len(), 1);
+        #[test]
+        fn parse_conversions() {
+            for input in [
+                "[]byte(s)",
+                "[4]int(x)",
+                "map[string][]time.Duration(m)",
+                "chan int(c)",
+                "chan<- *T(c)",
+                "[][]byte(f(x)).y",
+            ] {
+                let expression = Parser::parse_expression_str(input).unwrap();
+                assert_eq!(expression.to_source(), input);
+            }
+
+            let expression = Parser::parse_expression_str("[]byte(s)").unwrap();
+            match &expression.kind {
+                ExpressionKind::Conversion { target, expression } => {
+                    assert_eq!(
+                        target.kind,
+                        TypeExpressionKind::Slice(Box::new(TypeExpression::new_name(
+                            "byte".to_string(),
+                            Position::new(1, 2, 6)
+                        )))
+                    );
+                    assert_eq!(expression.to_source(), "s");
+                }
+                kind => panic!("expected a conversion, got {:?}", kind),
+            }
+            assert_eq!(expression.position_start, Position::new(1, 0, 1));
+            assert_eq!(expression.position_end, Position::new(1, 8, 9));
 
-            // Should have synchronized and advanced past the error
-            assert!(parser.current_token.kind.is_some());
+            // Conversions to named types can't be told apart from calls yet
+            let expression = Parser::parse_expression_str("string(b)").unwrap();
+            assert!(matches!(
+                expression.kind,
+                ExpressionKind::FunctionCall { .. }
+            ));
+
+            let errors = Parser::parse_expression_str("[]1(x)").unwrap_err();
+            assert_eq!(errors[0].kind, ParserErrorKind::NotAType("1".to_string()));
         }
 
         #[test]
-        fn synchronize_to_semicolon() {
-            let input = "func main ( ) ;";
-            let mut parser = Parser::new(input);
-
-            // Expect something wrong to trigger synchronization
-            let _ = parser.expect_token(TokenKind::Keyword(Keyword::Var)); // Wrong token
+        fn parse_for_statements() {
+            for input in [
+                "for {\n\tf()\n}",
+                "for ok {\n}",
+                "for range ch {\n}",
+                "for v := range ch {\n\tg(v)\n}",
+                "for i, v := range xs {\n\tfor {\n\t\th(i, v)\n\t}\n}",
+                "for _, s.field = range xs {\n}",
+            ] {
+                let statement = Parser::parse_statement_str(input).unwrap();
+                assert_eq!(statement.to_source(), input);
+            }
 
-            // Should have synchronized to before the semicolon
-            assert_eq!(parser.errors.len(), 1);
+            let statement = Parser::parse_statement_str("for i, v := range xs {\n}").unwrap();
+            assert_eq!(statement.position_start, Position::new(1, 0, 3));
+            assert_eq!(statement.position_end, Position::new(2, 0, 1));
+            match statement.kind {
+                StatementKind::Range {
+                    key, value, define, ..
+                } => {
+                    assert_eq!(key.unwrap().to_source(), "i");
+                    assert_eq!(value.unwrap().to_source(), "v");
+                    assert!(define);
+                }
+                kind => panic!("expected a range statement, got {:?}", kind),
+            }
         }
 
         #[test]
-        fn synchronize_to_eof() {
-            let input = "func main";
-            let mut parser = Parser::new(input);
+        fn parse_range_variable_errors() {
+            let errors = Parser::parse_statement_str("for i, v, x, y := range m {}").unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].kind, ParserErrorKind::TooManyRangeVariables);
+            // Spans the variables past the second
+            assert_eq!(errors[0].position, Position::new(1, 10, 14));
 
-            // Expect wrong token to trigger synchronization
-            let _ = parser.expect_token(TokenKind::Keyword(Keyword::Var)); // Wrong token
+            let errors = Parser::parse_statement_str("for a.b := range m {}").unwrap_err();
+            assert_eq!(errors[0].kind, ParserErrorKind::NonNameOnLeftOfDefine);
+            assert_eq!(errors[0].position, Position::new(1, 4, 5));
 
-            // Should synchronize to EOF since there's no semicolon
-            assert_eq!(parser.errors.len(), 1);
+            let errors = Parser::parse_statement_str("for i := 0; i < 10; j := i {}").unwrap_err();
+            assert_eq!(errors[0].kind, ParserErrorKind::DefineInPostStatement);
+            assert_eq!(errors[0].position, Position::new(1, 20, 21));
         }
 
         #[test]
-        fn peek_consistency() {
-            let input = "func main ( )";
+        fn parse_trailing_comments() {
+            let input = "package main\n\n// Leading comment\nfunc main() {\n\tf() // call f\n\t// own line\n\tg(1, /* inline */ 2)\n\tfor range xs { // loop\n\t\th() /* block */\n\t}\n}\n";
             let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            assert_eq!(parser.comments.len(), 6);
 
-            // Peek should be consistent
-            assert_eq!(parser.peek().kind, Some(TokenKind::Keyword(Keyword::Func)));
-            assert_eq!(parser.peek().kind, Some(TokenKind::Keyword(Keyword::Func))); // Multiple peeks
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            let comments: Vec<Option<&str>> = body
+                .iter()
+                .map(|statement| statement.trailing_comment.as_deref())
+                .collect();
+            assert_eq!(comments, vec![Some("// call f"), None, None]);
+            let StatementKind::Range { body, .. } = &body[2].kind else {
+                panic!("expected a range statement, got {:?}", body[2].kind);
+            };
+            assert_eq!(body[0].trailing_comment, None);
 
-            // Advance and check again
-            parser.advance();
-            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
-            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier)); // Multiple peeks
+            assert_eq!(
+                file.to_source(),
+                "package main\n\nfunc main() {\n\tf() // call f\n\tg(1, 2)\n\tfor range xs {\n\t\th()\n\t}\n}\n"
+            );
         }
 
         #[test]
-        fn empty_input() {
-            let input = "";
-            let parser = Parser::new(input);
-
-            // Should handle empty input gracefully
-            assert_eq!(parser.peek().kind, Some(TokenKind::EOF));
-            assert_eq!(parser.errors.len(), 0);
+        fn parse_doc_comments() {
+            let input = "// Package shapes draws shapes.\n//\n// It has two.\npackage shapes\n\nimport \"fmt\"\n\n// Circle is round.\n//go:generate stringer\ntype Circle struct{}\n\n// Not attached to anything\n\nfunc Draw() {} // draws\nvar count int\n\n/*\n   Square has\n   four sides.\n*/\ntype Square struct{}\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            assert_eq!(
+                file.doc.as_deref(),
+                Some("Package shapes draws shapes.\n\nIt has two.")
+            );
+            let docs: Vec<Option<&str>> = file
+                .decls
+                .iter()
+                .map(|declaration| declaration.doc.as_deref())
+                .collect();
+            assert_eq!(
+                docs,
+                vec![
+                    Some("Circle is round."),
+                    None,
+                    None,
+                    Some("   Square has\n   four sides.")
+                ]
+            );
         }
 
         #[test]
-        fn whitespace_handling() {
-            let input = "  func   main  ";
+        fn parse_doc_comments_of_adjacent_declarations() {
+            let input = "package main\n\n// A does a.\nfunc A() {\n}\n// B does b.\nfunc B() {\n}\n// C is a count.\nvar C int\n// D is a count too.\nvar D int\n";
             let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let docs: Vec<Option<&str>> = file
+                .decls
+                .iter()
+                .map(|declaration| declaration.doc.as_deref())
+                .collect();
+            assert_eq!(
+                docs,
+                vec![
+                    Some("A does a."),
+                    Some("B does b."),
+                    Some("C is a count."),
+                    Some("D is a count too.")
+                ]
+            );
+        }
 
-            // Whitespace should be ignored
-            assert_eq!(parser.peek().kind, Some(TokenKind::Keyword(Keyword::Func)));
+        #[test]
+        fn parse_function_signatures() {
+            let input = "package main\n\nfunc f(a, b int, rest ...string) (n int, err error) {\n}\n\nfunc g(int, []byte) bool {\n}\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Function {
+                parameters,
+                results,
+                ..
+            } = &file.decls[0].kind
+            else {
+                unreachable!()
+            };
+            assert_eq!(parameters.len(), 2);
+            assert_eq!(parameters[0].names.len(), 2);
+            assert!(parameters[1].variadic);
+            assert_eq!(results.len(), 2);
+            let DeclarationKind::Function { parameters, .. } = &file.decls[1].kind else {
+                unreachable!()
+            };
+            assert!(
+                parameters
+                    .iter()
+                    .all(|parameter| parameter.names.is_empty())
+            );
+            assert_eq!(file.to_source(), input);
 
-            parser.advance();
+            let mut parser = Parser::new("package main\nfunc f(a int, string) {\n}\n");
+            let _ = parser.parse();
             assert_eq!(
-                parser.current_token.kind,
-                Some(TokenKind::Keyword(Keyword::Func))
+                parser.errors[0].kind,
+                ParserErrorKind::MixedNamedAndUnnamedParameters
             );
-            assert_eq!(parser.peek().kind, Some(TokenKind::Identifier));
         }
 
         #[test]
-        fn multiple_advances() {
-            let input = "func main ( ) { }";
+        fn parse_variable_declarations() {
+            let input = "package main\n\nvar x = 1\n\nvar (\n\ta, b int\n\tc = 1.5\n)\n\nfunc main() {\n\tvar y []string\n}\n";
             let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Variable { specs, grouped } = &file.decls[1].kind else {
+                unreachable!()
+            };
+            assert!(grouped);
+            assert_eq!(specs[0].names.len(), 2);
+            assert!(specs[0].values.is_empty());
+            assert!(specs[1].type_expression.is_none());
+            assert_eq!(file.to_source(), input);
+        }
 
-            // Test sequence of advances
-            parser.advance(); // func
+        #[test]
+        fn parse_assignments() {
+            let statement = Parser::parse_statement_str("a, _ := f(), 'x'").unwrap();
+            let StatementKind::Assignment {
+                left,
+                right,
+                define,
+            } = &statement.kind
+            else {
+                unreachable!()
+            };
+            assert!(define);
+            assert_eq!(left[1].kind, ExpressionKind::BlankIdentifier);
             assert_eq!(
-                parser.current_token.kind,
-                Some(TokenKind::Keyword(Keyword::Func))
+                right[1].kind,
+                ExpressionKind::RuneLiteral("'x'".to_string())
             );
+            assert_eq!(statement.to_string(), "a, _ := f(), 'x'");
 
-            parser.advance(); // main
-            assert_eq!(parser.current_token.kind, Some(TokenKind::Identifier));
-
-            parser.advance(); // (
-            assert_eq!(parser.current_token.kind, Some(TokenKind::LeftParen));
+            let statement = Parser::parse_statement_str("x = 2.5").unwrap();
+            assert!(matches!(
+                statement.kind,
+                StatementKind::Assignment { define: false, .. }
+            ));
 
-            parser.advance(); // )
-            assert_eq!(parser.current_token.kind, Some(TokenKind::RightParen));
+            let errors = Parser::parse_statement_str("a.b := 1").unwrap_err();
+            assert_eq!(errors[0].kind, ParserErrorKind::NonNameOnLeftOfDefine);
+        }
 
-            parser.advance(); // {
-            assert_eq!(parser.current_token.kind, Some(TokenKind::LeftBrace));
+        #[test]
+        fn parse_spread_calls() {
+            let expression = Parser::parse_expression_str("append(xs, ys...)").unwrap();
+            let ExpressionKind::FunctionCall {
+                arguments, spread, ..
+            } = &expression.kind
+            else {
+                unreachable!()
+            };
+            assert!(spread);
+            assert_eq!(arguments.len(), 2);
+            assert_eq!(expression.to_string(), "append(xs, ys...)");
 
-            parser.advance(); // }
-            assert_eq!(parser.current_token.kind, Some(TokenKind::RightBrace));
+            // A trailing comma is allowed after the final argument
+            let expression = Parser::parse_expression_str("f(a, b...,)").unwrap();
+            assert_eq!(expression.to_string(), "f(a, b...)");
 
-            parser.advance(); // EOF
-            assert_eq!(parser.current_token.kind, Some(TokenKind::EOF));
+            // Only the final argument can be spread
+            assert!(Parser::parse_expression_str("f(a..., b)").is_err());
         }
 
         #[test]
-        fn semicolon_insertion_single_statement_no_semicolon() {
-            let input = "println";
-            let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(
-                result.is_ok(),
-                "Should parse single statement without semicolon"
+        fn parse_if_statements() {
+            let statement = Parser::parse_statement_str(
+                "if err := f(); err != nil {\n\treturn err\n} else if x {\n\tbreak\n} else {\n\tcontinue\n}",
+            )
+            .unwrap();
+            let StatementKind::If {
+                init,
+                condition,
+                else_branch,
+                ..
+            } = &statement.kind
+            else {
+                unreachable!()
+            };
+            assert!(matches!(
+                init.as_deref().map(|init| &init.kind),
+                Some(StatementKind::Assignment { define: true, .. })
+            ));
+            assert_eq!(condition.to_string(), "err != nil");
+            let else_if = else_branch.as_deref().unwrap();
+            let StatementKind::If { else_branch, .. } = &else_if.kind else {
+                unreachable!()
+            };
+            assert!(matches!(
+                else_branch.as_deref().map(|branch| &branch.kind),
+                Some(StatementKind::Block(_))
+            ));
+            assert_eq!(statement.position_end, Position::new(7, 0, 1));
+            assert_eq!(
+                statement.to_string(),
+                "if err := f(); err != nil {\n\treturn err\n} else if x {\n\tbreak\n} else {\n\tcontinue\n}"
             );
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 1);
+
+            let errors = Parser::parse_statement_str("if x {} else y()").unwrap_err();
+            assert!(matches!(errors[0].kind, ParserErrorKind::Expected { .. }));
         }
 
         #[test]
-        fn semicolon_insertion_single_statement_with_semicolon() {
-            let input = "println;";
+        fn parse_return_statements() {
+            let input = "package main\n\nfunc f() (int, error) {\n\t{\n\t\treturn\n\t}\n\treturn 1, nil\n}\n";
             let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(
-                result.is_ok(),
-                "Should parse single statement with semicolon"
-            );
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 1);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            let StatementKind::Block(block) = &body[0].kind else {
+                unreachable!()
+            };
+            assert_eq!(block[0].kind, StatementKind::Return(Vec::new()));
+            let StatementKind::Return(results) = &body[1].kind else {
+                unreachable!()
+            };
+            assert_eq!(results.len(), 2);
+            assert_eq!(file.to_source(), input);
         }
 
         #[test]
-        fn semicolon_insertion_multiple_statements_no_semicolon_error() {
-            let input = "println println";
+        fn parse_types_and_methods() {
+            let input = "package main\n\ntype Celsius float64\n\ntype Stringer interface { String() string; Set(v string, force bool) }\n\nfunc (c *Celsius) Set(v string, force bool) {\n\ttype local interface{}\n}\n\nfunc (Celsius) String() string {\n\treturn \"\"\n}\n";
             let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Type {
+                type_expression, ..
+            } = &file.decls[1].kind
+            else {
+                unreachable!()
+            };
+            let TypeExpressionKind::Interface(methods) = &type_expression.kind else {
+                unreachable!()
+            };
+            assert_eq!(methods.len(), 2);
+            assert_eq!(methods[1].parameters.len(), 2);
+            let DeclarationKind::Function { receiver, body, .. } = &file.decls[2].kind else {
+                unreachable!()
+            };
+            let receiver = receiver.as_ref().unwrap();
+            assert_eq!(receiver.names.len(), 1);
+            assert!(matches!(
+                receiver.type_expression.kind,
+                TypeExpressionKind::Pointer(_)
+            ));
+            assert!(matches!(body[0].kind, StatementKind::Declaration(_)));
+            assert_eq!(file.to_source(), input);
+
+            let input = "package main\n\ntype Shape interface {\n\tArea() float64\n\tPerimeter() float64\n}\n";
+            let mut parser = Parser::new(input);
+            parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+
+            let mut parser = Parser::new("package main\nfunc (a, b T) f() {\n}\n");
             let _ = parser.parse();
-            assert!(
-                !parser.errors.is_empty(),
-                "Should have errors for multiple statements without semicolon"
-            );
+            assert_eq!(parser.errors[0].kind, ParserErrorKind::ReceiverCount(2));
         }
 
         #[test]
-        fn semicolon_insertion_multiline_with_newlines() {
-            let input = "println\nprintln\nprintln";
+        fn parse_struct_types() {
+            let input = "package main\n\ntype Point struct { x, y int; *Node; io.Reader; name string \"json\" }\n\ntype Empty struct{}\n";
             let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(
-                result.is_ok(),
-                "Should parse multiple lines without semicolons"
-            );
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 3);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Type {
+                type_expression, ..
+            } = &file.decls[0].kind
+            else {
+                unreachable!()
+            };
+            let TypeExpressionKind::Struct(fields) = &type_expression.kind else {
+                unreachable!()
+            };
+            assert_eq!(fields.len(), 4);
+            assert_eq!(fields[0].names.len(), 2);
+            assert_eq!(fields[1].embedded_name(), Some("Node"));
+            assert_eq!(fields[2].embedded_name(), Some("Reader"));
+            assert_eq!(fields[3].tag.as_deref(), Some("\"json\""));
+            assert_eq!(file.to_source(), input);
+
+            let input = "package main\n\ntype Node struct {\n\tvalue int\n\tnext  *Node\n}\n";
+            let mut parser = Parser::new(input);
+            parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
         }
 
         #[test]
-        fn parse_string_literal() {
-            let input = r#""Hello, World!""#;
+        fn parse_type_operands() {
+            let input = "package main\n\nfunc main() {\n\tm := make(map[string][]int, 10)\n\tc := make(chan int)\n\tb := []byte(\"hi\")\n}\n";
             let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(result.is_ok(), "Should parse string literal");
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 1);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            let StatementKind::Assignment { right, .. } = &body[0].kind else {
+                unreachable!()
+            };
+            let ExpressionKind::FunctionCall { arguments, .. } = &right[0].kind else {
+                unreachable!()
+            };
+            assert!(matches!(arguments[0].kind, ExpressionKind::Type(_)));
+            assert_eq!(file.to_source(), input);
         }
 
         #[test]
-        fn parse_simple_function_call() {
-            let input = r#"println("hello")"#;
+        fn parse_channel_operations() {
+            let input = "package main\n\nfunc main() {\n\tc <- <-d + 1\n\tv, ok := <-c\n\t<-done\n\tr := (<-chan int)(c)\n\ts := <-chan int(c)\n\tx := <-(<-c)\n}\n";
             let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(result.is_ok(), "Should parse simple function call");
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 1);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            let StatementKind::Send { channel, value } = &body[0].kind else {
+                unreachable!()
+            };
+            assert_eq!(channel.to_string(), "c");
+            let ExpressionKind::Binary { left, .. } = &value.kind else {
+                unreachable!()
+            };
+            assert!(matches!(left.kind, ExpressionKind::Receive(_)));
+            assert!(matches!(
+                body[2].kind,
+                StatementKind::Expression(Expression {
+                    kind: ExpressionKind::Receive(_),
+                    ..
+                })
+            ));
+            let StatementKind::Assignment { right, .. } = &body[3].kind else {
+                unreachable!()
+            };
+            assert!(matches!(right[0].kind, ExpressionKind::Conversion { .. }));
+            let StatementKind::Assignment { right, .. } = &body[4].kind else {
+                unreachable!()
+            };
+            assert!(matches!(right[0].kind, ExpressionKind::Receive(_)));
+            assert_eq!(file.to_source(), input);
         }
 
         #[test]
-        fn parse_method_call() {
-            let input = r#"fmt.Println("hello")"#;
-            let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(result.is_ok(), "Should parse method call");
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 1);
+        fn parse_index_and_slice_expressions() {
+            let expression = Parser::parse_expression_str("m[k][0] + s[1:][:n:cap(s)]").unwrap();
+            assert_eq!(expression.to_source(), "m[k][0] + s[1:][:n:cap(s)]");
+            let ExpressionKind::Binary { left, right, .. } = &expression.kind else {
+                unreachable!()
+            };
+            let ExpressionKind::Index { object, .. } = &left.kind else {
+                unreachable!()
+            };
+            assert!(matches!(object.kind, ExpressionKind::Index { .. }));
+            let ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } = &right.kind
+            else {
+                unreachable!()
+            };
+            assert!(matches!(object.kind, ExpressionKind::Slice { .. }));
+            assert!(low.is_none());
+            assert_eq!(high.as_ref().unwrap().to_source(), "n");
+            assert_eq!(max.as_ref().unwrap().to_source(), "cap(s)");
+
+            let errors = Parser::parse_expression_str("s[:2:]").unwrap_err();
+            assert_eq!(errors[0].kind, ParserErrorKind::MissingSliceIndex("Final"));
+            let errors = Parser::parse_expression_str("s[1::3]").unwrap_err();
+            assert_eq!(
+                errors[0].kind.to_string(),
+                "Middle index required in 3-index slice"
+            );
         }
 
         #[test]
-        fn parse_package_declaration() {
-            let input = "package main";
-            let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(result.is_ok(), "Should parse package declaration");
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 1);
+        fn parse_unary_expressions() {
+            let expression = Parser::parse_expression_str("-x * -(-y) + *p - ^0").unwrap();
+            assert_eq!(expression.to_source(), "-x * -(-y) + *p - ^0");
+            let ExpressionKind::Binary { left, .. } = &expression.kind else {
+                unreachable!()
+            };
+            let ExpressionKind::Binary { left, .. } = &left.kind else {
+                unreachable!()
+            };
+            let ExpressionKind::Binary { left, .. } = &left.kind else {
+                unreachable!()
+            };
+            assert!(matches!(
+                left.kind,
+                ExpressionKind::Unary {
+                    operator: UnaryOperator::Minus,
+                    ..
+                }
+            ));
+            let expression = Parser::parse_expression_str("!ok && &p.X != nil").unwrap();
+            assert_eq!(expression.to_source(), "!ok && &p.X != nil");
         }
 
         #[test]
-        fn parse_import_declaration() {
-            let input = r#"import "fmt""#;
+        fn parse_composite_literals() {
+            let input = "package main\n\nfunc main() {\n\tp := Point{X: 1, Y: 2}\n\tq := &geo.Point{1, 2}\n\tps := []*Point{{1, 2}, {3, 4}}\n\tm := map[string]Point{\"a\": {}}\n\tif p == (Point{}) {\n\t}\n\tfor _, v := range []int{1, 2} {\n\t}\n}\n";
             let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(result.is_ok(), "Should parse import declaration");
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 1);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            assert_eq!(file.to_source(), input);
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            let StatementKind::Assignment { right, .. } = &body[0].kind else {
+                unreachable!()
+            };
+            let ExpressionKind::CompositeLiteral {
+                type_expression: Some(type_expression),
+                elements,
+            } = &right[0].kind
+            else {
+                unreachable!()
+            };
+            assert_eq!(type_expression.to_string(), "Point");
+            assert_eq!(elements[1].key.as_ref().unwrap().to_source(), "Y");
+            let StatementKind::Assignment { right, .. } = &body[2].kind else {
+                unreachable!()
+            };
+            let ExpressionKind::CompositeLiteral { elements, .. } = &right[0].kind else {
+                unreachable!()
+            };
+            assert!(matches!(
+                elements[0].value.kind,
+                ExpressionKind::CompositeLiteral {
+                    type_expression: None,
+                    ..
+                }
+            ));
+
+            // In an if header a brace after a name starts the block, not a literal
+            let input = "package main\n\nfunc main() {\n\tif p == origin {\n\t}\n}\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            assert_eq!(file.to_source(), input);
         }
 
         #[test]
-        fn parse_simple_function_declaration() {
-            let input = "func main() {\n    println(\"hello\")\n}";
+        fn parse_function_literals() {
+            let input = "package main\n\nfunc main() {\n\tdefer func() {\n\t\trecover()\n\t}()\n\tgo func(n int) {\n\t}(1)\n\tvar f func(int) (string, error)\n\tif g := func() bool {\n\t\treturn true\n\t}; g() {\n\t}\n}\n";
             let mut parser = Parser::new(input);
-            let result = parser.parse();
-            assert!(result.is_ok(), "Should parse function declaration");
-            let program = result.unwrap();
-            assert_eq!(program.statements.len(), 1);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            assert_eq!(file.to_source(), input);
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            let StatementKind::Defer(call) = &body[0].kind else {
+                unreachable!()
+            };
+            let ExpressionKind::FunctionCall { name, .. } = &call.kind else {
+                unreachable!()
+            };
+            let ExpressionKind::FunctionLiteral { body, .. } = &name.kind else {
+                unreachable!()
+            };
+            assert_eq!(body[0].to_source(), "recover()");
         }
 
         #[test]
-        fn parse_complete_hello_world() {
-            let input = r#"package main
+        fn parse_defer_statements() {
+            let input = "package main\n\nfunc main() {\n\tdefer fmt.Println(\"done\")\n\tdefer close(c)\n}\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            let StatementKind::Defer(call) = &body[0].kind else {
+                unreachable!()
+            };
+            assert!(matches!(call.kind, ExpressionKind::FunctionCall { .. }));
+            assert_eq!(file.to_source(), input);
 
-import "fmt"
+            let errors = Parser::parse_statement_str("defer x").unwrap_err();
+            assert_eq!(
+                errors[0].kind.to_string(),
+                "Expression in defer must be function call"
+            );
+        }
 
-func main() {
-    fmt.Println("Hello, World!")
-}
-"#;
+        #[test]
+        fn parse_go_and_select_statements() {
+            let input = "package main\n\nfunc main() {\n\tgo worker(c)\n\tselect {\n\tcase v, ok := <-c:\n\t\tuse(v, ok)\n\tcase out <- 1:\n\tcase <-done:\n\t\treturn\n\tdefault:\n\t}\n}\n";
             let mut parser = Parser::new(input);
-            let result = parser.parse();
-            if let Err(ref errors) = result {
-                println!("Parse errors: {:?}", errors);
-            }
-            if !parser.errors.is_empty() {
-                println!("Parser errors: {:?}", parser.errors);
-            }
-            assert!(result.is_ok(), "Should parse complete Hello World program");
-            let program = result.unwrap();
-            println!("Parsed {} statements", program.statements.len());
-            assert_eq!(program.statements.len(), 3); // package, import, func
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            assert!(matches!(body[0].kind, StatementKind::Go(_)));
+            let StatementKind::Select(cases) = &body[1].kind else {
+                unreachable!()
+            };
+            assert_eq!(cases.len(), 4);
+            assert_eq!(cases[0].position, Position::new(6, 1, 5));
+            assert_eq!(cases[0].body.len(), 1);
+            assert!(matches!(
+                cases[1].communication.as_deref().map(|send| &send.kind),
+                Some(StatementKind::Send { .. })
+            ));
+            assert!(cases[3].communication.is_none());
+            assert_eq!(file.to_source(), input);
+
+            let errors = Parser::parse_statement_str("go x").unwrap_err();
+            assert_eq!(
+                errors[0].kind.to_string(),
+                "Expression in go must be function call"
+            );
+            let errors = Parser::parse_statement_str("select {\ncase f():\ndefault:\ndefault:\n}")
+                .unwrap_err();
+            let messages: Vec<String> = errors.iter().map(|error| error.kind.to_string()).collect();
+            assert_eq!(
+                messages,
+                vec![
+                    "Select case must be receive, send or assign recv",
+                    "Multiple defaults in select"
+                ]
+            );
         }
 
-        // Binary Expression Tests
         #[test]
-        fn parse_binary_expressions() {
-            let expressions = vec![
-                "a + b",          // Addition
-                "x * y",          // Multiplication
-                "p - q",          // Subtraction
-                "m / n",          // Division
-                "a % b",          // Modulo
-                "x == y",         // Equality
-                "a != b",         // Inequality
-                "p < q",          // Less than
-                "m <= n",         // Less than or equal
-                "a > b",          // Greater than
-                "x >= y",         // Greater than or equal
-                "a + b * c",      // Precedence test
-                "(a + b) * c",    // Parentheses
-                "obj.field + 42", // Mixed field access and literals
-            ];
+        fn parse_switch_statements_and_constants() {
+            let input = "package main\n\nconst (\n\tA int = iota\n\tB\n)\n\nfunc main() {\n\tswitch x := f(); x {\n\tcase 1, 2:\n\t\tg()\n\t\tfallthrough\n\tdefault:\n\t}\n\tfor i := 0; i < 3; i++ {\n\t\tn += i\n\t}\n}\n";
+            let mut parser = Parser::new(input);
+            let file = parser.parse().unwrap();
+            assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+            let DeclarationKind::Constant { specs, grouped } = &file.decls[0].kind else {
+                unreachable!()
+            };
+            assert!(grouped);
+            assert!(specs[1].values.is_empty());
+            let DeclarationKind::Function { body, .. } = &file.decls[1].kind else {
+                unreachable!()
+            };
+            let StatementKind::Switch { init, tag, cases } = &body[0].kind else {
+                unreachable!()
+            };
+            assert!(init.is_some() && tag.is_some());
+            assert_eq!(cases[0].expressions.as_ref().map(Vec::len), Some(2));
+            assert_eq!(cases[0].body[1].kind, StatementKind::Fallthrough);
+            assert!(cases[1].expressions.is_none());
+            assert!(matches!(
+                &body[1].kind,
+                StatementKind::For {
+                    init: Some(_),
+                    condition: Some(_),
+                    post: Some(_),
+                    ..
+                }
+            ));
+            assert_eq!(file.to_source(), input);
 
-            for expr in expressions {
-                let mut parser = Parser::new(expr);
-                let result = parser.parse();
-                assert!(
-                    parser.errors.is_empty(),
-                    "Should parse binary expression: {}\n{}",
-                    expr,
-                    parser.dump_errors()
-                );
-                assert!(result.is_ok(), "Should parse binary expression: {}", expr);
-                let program = result.unwrap();
-                assert_eq!(
-                    program.statements.len(),
-                    1,
-                    "Should have one statement for: {}",
-                    expr
-                );
-            }
+            let errors = Parser::parse_statement_str(
+                "switch {\ncase a:\n\tfallthrough\n\tf()\ndefault:\ndefault:\n\tfallthrough\n}",
+            )
+            .unwrap_err();
+            let messages: Vec<String> = errors.iter().map(|error| error.kind.to_string()).collect();
+            assert_eq!(
+                messages,
+                vec![
+                    "Fallthrough statement out of place",
+                    "Multiple defaults in switch",
+                    "Cannot fallthrough final case in switch"
+                ]
+            );
+            let errors = Parser::parse_statement_str("const x int").unwrap_err();
+            assert_eq!(errors[0].kind, ParserErrorKind::MissingConstantValue);
         }
     }
 }
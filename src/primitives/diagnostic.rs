@@ -0,0 +1,150 @@
+use crate::primitives::{
+    errors::{
+        lexer::LexerError,
+        parser::ParserError,
+        runtime::RuntimeError,
+        semantic::{SemanticError, SemanticErrorKind, SemanticWarning, SemanticWarningKind},
+    },
+    position::Position,
+};
+
+/// A problem found in the source, in a form any tool can report without knowing which stage
+/// produced it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub position: Position,
+    /// What kind of problem it is, for tools to tell problems apart by: `syntax` for a
+    /// syntax error, or the name of the semantic check that found it, like `names`
+    pub code: Option<&'static str>,
+    /// The code `gor explain` describes the problem by, like `E0101`, if it has one
+    pub explanation: Option<&'static str>,
+    /// Other places the problem involves, e.g. the declaration a reference can't reach, each
+    /// with a message of its own
+    pub notes: Vec<(String, Position)>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: String, position: Position) -> Diagnostic {
+        Diagnostic {
+            severity,
+            message,
+            position,
+            code: None,
+            explanation: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Diagnostic {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_explanation(mut self, code: &'static str) -> Diagnostic {
+        self.explanation = Some(code);
+        self
+    }
+
+    /// Whether the diagnostic is for a bug in gor rather than in the source, like a panic in
+    /// the parser
+    pub fn is_internal(&self) -> bool {
+        self.code == Some("internal")
+    }
+
+    pub fn with_note(mut self, message: String, position: Position) -> Diagnostic {
+        self.notes.push((message, position));
+        self
+    }
+
+    pub fn error(message: String, position: Position) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message, position)
+    }
+
+    pub fn warning(message: String, position: Position) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message, position)
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.position.line, self.position.column_start, self.severity, self.message
+        )?;
+        for (message, position) in &self.notes {
+            write!(
+                f,
+                "\n{}:{}: note: {}",
+                position.line, position.column_start, message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(error: &ParserError) -> Diagnostic {
+        Diagnostic::error(error.kind.to_string(), error.position)
+            .with_code("syntax")
+            .with_explanation(error.kind.code())
+    }
+}
+
+impl From<&LexerError> for Diagnostic {
+    fn from(error: &LexerError) -> Diagnostic {
+        Diagnostic::error(error.kind.to_string(), error.position)
+            .with_code("syntax")
+            .with_explanation(error.kind.code())
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(error: &RuntimeError) -> Diagnostic {
+        Diagnostic::error(error.kind.to_string(), error.position)
+    }
+}
+
+impl From<&SemanticError> for Diagnostic {
+    fn from(error: &SemanticError) -> Diagnostic {
+        let diagnostic = Diagnostic::error(error.kind.to_string(), error.position)
+            .with_explanation(error.kind.code());
+        match &error.kind {
+            SemanticErrorKind::UnexportedName {
+                name,
+                declaration: Some(declaration),
+                ..
+            } => diagnostic.with_note(format!("{} declared here", name), *declaration),
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<&SemanticWarning> for Diagnostic {
+    fn from(warning: &SemanticWarning) -> Diagnostic {
+        let diagnostic = Diagnostic::warning(warning.kind.to_string(), warning.position)
+            .with_explanation(warning.kind.code());
+        match &warning.kind {
+            SemanticWarningKind::Shadowed { name, declaration } => {
+                diagnostic.with_note(format!("shadowed {} declared here", name), *declaration)
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::primitives::errors::config::ConfigError;
+
+/// A command line gor can't make sense of, and the command it was for, if it got that far
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliError {
+    pub kind: CliErrorKind,
+    pub command: Option<String>,
+}
+
+impl CliError {
+    pub fn new(kind: CliErrorKind, command: Option<String>) -> CliError {
+        CliError { kind, command }
+    }
+}
+
+/// As the go command reports it, like `gor build: unknown flag --x` then where to find the
+/// command's usage
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.command {
+            Some(command) => write!(
+                f,
+                "gor {}: {}\nRun 'gor help {}' for usage.",
+                command, self.kind, command
+            ),
+            None => write!(f, "gor: {}\nRun 'gor help' for usage.", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliErrorKind {
+    // gor without anything after it
+    MissingCommand,
+    // gor bulid
+    UnknownCommand(String),
+    UnknownFlag(String),
+    // A flag that takes a value without one, like a trailing -o
+    MissingValue(String),
+    // A value a flag doesn't take, like --opt-level=3, or a value for one that takes none
+    InvalidValue { flag: String, value: String },
+    // A file or other argument the command needs, as its usage names it, like <file.go>
+    MissingArgument(String),
+    // An argument after all those the command takes
+    UnexpectedArgument(String),
+    // A path given for a <file.go> that doesn't end in .go
+    NotAGoFile(String),
+    // A path given for a file that doesn't exist
+    NoSuchFile(String),
+    // A position that isn't <file.go>:<line>:<column>
+    InvalidPosition(String),
+    // Two flags that can't be used together, like gor fmt's -w and --check
+    ConflictingFlags(String, String),
+    // A gor.toml whose defaults for the flags can't be read
+    Config(ConfigError),
+}
+
+impl std::fmt::Display for CliErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliErrorKind::MissingCommand => write!(f, "no command given"),
+            CliErrorKind::UnknownCommand(command) => write!(f, "unknown command {}", command),
+            CliErrorKind::UnknownFlag(flag) => write!(f, "unknown flag {}", flag),
+            CliErrorKind::MissingValue(flag) => write!(f, "flag {} needs a value", flag),
+            CliErrorKind::InvalidValue { flag, value } => {
+                write!(f, "invalid value {:?} for flag {}", value, flag)
+            }
+            CliErrorKind::MissingArgument(argument) => write!(f, "missing {}", argument),
+            CliErrorKind::UnexpectedArgument(argument) => {
+                write!(f, "unexpected argument {}", argument)
+            }
+            CliErrorKind::NotAGoFile(path) => write!(f, "{} is not a .go file", path),
+            CliErrorKind::NoSuchFile(path) => write!(f, "{}: no such file", path),
+            CliErrorKind::InvalidPosition(position) => {
+                write!(
+                    f,
+                    "invalid position {}, want <file.go>:<line>:<column>",
+                    position
+                )
+            }
+            CliErrorKind::ConflictingFlags(first, second) => {
+                write!(f, "{} and {} can't be combined", first, second)
+            }
+            CliErrorKind::Config(error) => write!(f, "{}", error),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::primitives::{errors::semantic::SemanticErrorKind, position::Position};
+
+/// A program that can't be compiled, to bytecode or the IR, and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileError {
+    pub kind: CompileErrorKind,
+    pub position: Position,
+}
+
+impl CompileError {
+    pub fn new(kind: CompileErrorKind, position: Position) -> CompileError {
+        CompileError { kind, position }
+    }
+}
+
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Compile error at {:?}: {}", self.position, self.kind)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompileErrorKind {
+    // An error the type checker found, since only checked programs are compiled. It's boxed,
+    // being much larger than the others.
+    Check(Box<SemanticErrorKind>),
+    // A struct, map, goroutine or other part of Go the compiler can't compile yet, as written
+    Unsupported(String),
+}
+
+impl std::fmt::Display for CompileErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompileErrorKind::Check(kind) => write!(f, "{}", kind),
+            CompileErrorKind::Unsupported(code) => {
+                write!(f, "the compiler doesn't support {} yet", code)
+            }
+        }
+    }
+}
+
+/// Bytes that aren't a program `gor build` wrote, and the offset they stop making sense at
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    pub offset: usize,
+}
+
+impl DecodeError {
+    pub fn new(kind: DecodeErrorKind, offset: usize) -> DecodeError {
+        DecodeError { kind, offset }
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Decode error at byte {}: {}", self.offset, self.kind)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    // Bytes that don't start with the magic number, like a wasm module's
+    NotBytecode,
+    // A program an older or newer gor built, by its format's version
+    Version(u32),
+    // Bytes that end partway through the program
+    Truncated,
+    // Bytes left after the program
+    TrailingBytes,
+    // An opcode, constant's tag or flag that isn't one
+    InvalidTag(u8),
+    // A name or string constant that isn't UTF-8
+    InvalidString,
+    // An operation whose source doesn't parse
+    InvalidOperation(String),
+    // An instruction's operand, or the program's init or main, past the end of what it indexes
+    InvalidIndex(String),
+}
+
+impl std::fmt::Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeErrorKind::NotBytecode => write!(f, "not a gor bytecode file"),
+            DecodeErrorKind::Version(version) => {
+                write!(f, "bytecode version {} is not supported", version)
+            }
+            DecodeErrorKind::Truncated => write!(f, "unexpected end of bytecode"),
+            DecodeErrorKind::TrailingBytes => write!(f, "unexpected bytes after the program"),
+            DecodeErrorKind::InvalidTag(tag) => write!(f, "invalid tag {}", tag),
+            DecodeErrorKind::InvalidString => write!(f, "string is not valid UTF-8"),
+            DecodeErrorKind::InvalidOperation(source) => {
+                write!(f, "operation {:?} does not parse", source)
+            }
+            DecodeErrorKind::InvalidIndex(what) => write!(f, "{} is out of range", what),
+        }
+    }
+}
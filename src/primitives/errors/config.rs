@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+/// A `gor.toml` that can't be read or doesn't make sense, and the line of it that's the
+/// problem, or 0 for the whole file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub kind: ConfigErrorKind,
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+impl ConfigError {
+    pub fn new(kind: ConfigErrorKind, path: PathBuf, line: usize) -> ConfigError {
+        ConfigError { kind, path, line }
+    }
+}
+
+/// Like `gor.toml:3: unknown key formt in [check]`
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            0 => write!(f, "{}: {}", self.path.display(), self.kind),
+            line => write!(f, "{}:{}: {}", self.path.display(), line, self.kind),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigErrorKind {
+    // A file that can't be read
+    Io(String),
+    // Something that isn't TOML, or is TOML gor doesn't read, like a float or a table array
+    Syntax(String),
+    // A table that isn't a command's, like [chek]
+    UnknownSection(String),
+    // A key that isn't a flag of the command, or of gor's, that its table is for
+    UnknownKey {
+        section: Option<String>,
+        key: String,
+    },
+    // A flag that's chosen on each run rather than by default, like gor fmt's -w
+    PerRun(String),
+    // A value of the wrong type for its flag, like a list for one that takes a value
+    InvalidValue {
+        key: String,
+        value: String,
+    },
+    // A key or table given twice
+    Duplicate(String),
+}
+
+impl std::fmt::Display for ConfigErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigErrorKind::Io(message) => write!(f, "{}", message),
+            ConfigErrorKind::Syntax(message) => write!(f, "{}", message),
+            ConfigErrorKind::UnknownSection(section) => write!(f, "unknown table [{}]", section),
+            ConfigErrorKind::UnknownKey {
+                section: Some(section),
+                key,
+            } => write!(f, "unknown key {} in [{}]", key, section),
+            ConfigErrorKind::UnknownKey { section: None, key } => write!(f, "unknown key {}", key),
+            ConfigErrorKind::PerRun(flag) => {
+                write!(f, "{} is chosen on each run, not in gor.toml", flag)
+            }
+            ConfigErrorKind::InvalidValue { key, value } => {
+                write!(f, "invalid value {} for {}", value, key)
+            }
+            ConfigErrorKind::Duplicate(name) => write!(f, "{} is given twice", name),
+        }
+    }
+}
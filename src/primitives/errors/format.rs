@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+
+use crate::primitives::diagnostic::Diagnostic;
+
+/// A file the formatter can't be shown to format safely, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelftestError {
+    pub kind: SelftestErrorKind,
+    pub path: PathBuf,
+}
+
+impl SelftestError {
+    pub fn new(kind: SelftestErrorKind, path: PathBuf) -> SelftestError {
+        SelftestError { kind, path }
+    }
+
+    /// Whether the file couldn't be checked at all, rather than the formatter failing it
+    pub fn is_skipped(&self) -> bool {
+        matches!(
+            self.kind,
+            SelftestErrorKind::Io(_) | SelftestErrorKind::Unparsable(_)
+        )
+    }
+}
+
+impl std::fmt::Display for SelftestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.kind)
+    }
+}
+
+impl std::error::Error for SelftestError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelftestErrorKind {
+    // A file that can't be read
+    Io(String),
+    // A file gor can't parse, which isn't the formatter's fault
+    Unparsable(Vec<Diagnostic>),
+    // Formatted source that doesn't parse
+    Reparse(Vec<Diagnostic>),
+    // Formatting the formatted source changes it, shown as a diff from the first formatting
+    Unstable(String),
+    // The formatted source parses to a different tree, shown as a diff of the trees printed
+    TreeChanged(String),
+    // Comments that are in the source and not in the formatted source, or the other way round
+    CommentsChanged {
+        dropped: Vec<String>,
+        added: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for SelftestErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelftestErrorKind::Io(message) => write!(f, "{}", message),
+            SelftestErrorKind::Unparsable(diagnostics) => {
+                write!(f, "skipped, {} syntax errors", diagnostics.len())
+            }
+            SelftestErrorKind::Reparse(diagnostics) => {
+                write!(f, "the formatted source doesn't parse")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n{}", diagnostic)?;
+                }
+                Ok(())
+            }
+            SelftestErrorKind::Unstable(diff) => {
+                write!(f, "formatting twice changes the source\n{}", diff)
+            }
+            SelftestErrorKind::TreeChanged(diff) => {
+                write!(f, "formatting changes the syntax tree\n{}", diff)
+            }
+            SelftestErrorKind::CommentsChanged { dropped, added } => {
+                write!(f, "formatting changes the comments")?;
+                for comment in dropped {
+                    write!(f, "\ndropped {}", comment)?;
+                }
+                for comment in added {
+                    write!(f, "\nadded {}", comment)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
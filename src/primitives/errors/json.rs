@@ -0,0 +1,24 @@
+/// Text that isn't JSON, and the byte offset into it where that became clear
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl JsonError {
+    pub fn new(message: &str, offset: usize) -> JsonError {
+        JsonError {
+            message: message.to_string(),
+            offset,
+        }
+    }
+}
+
+/// Like `invalid JSON at byte 12: expected ':'`
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid JSON at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for JsonError {}
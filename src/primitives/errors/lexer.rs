@@ -26,6 +26,14 @@ pub enum LexerErrorKind {
     UnexpectedToken(String),
     UnterminatedString(String),
     UnterminatedRune(String),
+    UnterminatedComment(String),
+}
+
+impl LexerErrorKind {
+    /// The code `gor explain` describes the error by, which every lexical error shares
+    pub fn code(&self) -> &'static str {
+        "E0001"
+    }
 }
 
 impl std::fmt::Display for LexerErrorKind {
@@ -37,6 +45,9 @@ impl std::fmt::Display for LexerErrorKind {
                 write!(f, "Unterminated string: {}", token)
             }
             LexerErrorKind::UnterminatedRune(token) => write!(f, "Unterminated rune: {}", token),
+            LexerErrorKind::UnterminatedComment(token) => {
+                write!(f, "Unterminated comment: {}", token)
+            }
         }
     }
 }
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use crate::primitives::diagnostic::Diagnostic;
+
+/// A program that couldn't be loaded, and the file or directory that's the problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    pub kind: LoadErrorKind,
+    pub path: PathBuf,
+}
+
+impl LoadError {
+    pub fn new(kind: LoadErrorKind, path: PathBuf) -> LoadError {
+        LoadError { kind, path }
+    }
+}
+
+/// As the go command reports it, like `app/main.go:3:1: error: ...` for a file that doesn't
+/// parse, or `app: no Go files in app`
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            LoadErrorKind::Parse(diagnostics) => {
+                for (index, diagnostic) in diagnostics.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}:{}", self.path.display(), diagnostic)?;
+                }
+                Ok(())
+            }
+            kind => write!(f, "{}: {}", self.path.display(), kind),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadErrorKind {
+    // A file or directory that can't be read
+    Io(String),
+    // A directory without a `.go` file, other than tests
+    NoGoFiles,
+    // A pattern like ./... without a package under it
+    NoMatchingPackages,
+    // A file with syntax errors
+    Parse(Vec<Diagnostic>),
+    // package util in one file and package main in another
+    MultiplePackages {
+        first: String,
+        second: String,
+    },
+    // a imports b, which imports a
+    ImportCycle(Vec<String>),
+    // Two local packages with the same name, like a/util and b/util, which the interpreter
+    // can't tell apart
+    DuplicatePackageName {
+        name: String,
+        first: String,
+        second: String,
+    },
+}
+
+impl std::fmt::Display for LoadErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadErrorKind::Io(message) => write!(f, "{}", message),
+            LoadErrorKind::NoGoFiles => write!(f, "no Go files"),
+            LoadErrorKind::NoMatchingPackages => write!(f, "matched no packages"),
+            LoadErrorKind::Parse(diagnostics) => write!(f, "{} syntax errors", diagnostics.len()),
+            LoadErrorKind::MultiplePackages { first, second } => {
+                write!(f, "found packages {} and {}", first, second)
+            }
+            LoadErrorKind::ImportCycle(cycle) => {
+                write!(f, "import cycle not allowed: {}", cycle.join(" -> "))
+            }
+            LoadErrorKind::DuplicatePackageName {
+                name,
+                first,
+                second,
+            } => write!(
+                f,
+                "packages {} and {} are both named {}, which isn't supported",
+                first, second, name
+            ),
+        }
+    }
+}
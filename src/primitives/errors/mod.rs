@@ -1,3 +1,13 @@
+pub mod cli;
+pub mod compile;
+pub mod config;
+pub mod format;
+pub mod json;
 pub mod lexer;
+pub mod load;
 pub mod parser;
 pub mod programming;
+pub mod repl;
+pub mod rewrite;
+pub mod runtime;
+pub mod semantic;
@@ -1,3 +1,4 @@
+use crate::lexer::{token::Token, token_type::TokenKind};
 use crate::primitives::{errors::lexer::LexerError, position::Position};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -23,18 +24,153 @@ impl std::error::Error for ParserError {}
 #[derive(Debug, Clone, PartialEq)]
 pub enum ParserErrorKind {
     LexerError(LexerError),
-    UnexpectedToken(String),
+    /// `context` completes the sentence "expected X ...", e.g. "after arguments"
+    Expected {
+        expected: Vec<TokenKind>,
+        found: Token,
+        context: Option<&'static str>,
+    },
     NotAPrimaryExpression(String),
-    NotImplemented,
+    NotAType(String),
+    ExpressionNotUsedAsStatement,
+    BlankIdentifierAsValue,
+    NonNameOnLeftOfDefine,
+    TooManyRangeVariables,
+    MixedNamedAndUnnamedParameters,
+    // A method's receiver list with no parameters, or more than one
+    ReceiverCount(usize),
+    // a[1::3] or a[1:2:], missing the "Middle" or "Final" index
+    MissingSliceIndex(&'static str),
+    // defer x, where the statement is "defer"
+    NotACall(&'static str),
+    // case f(): in a select, which must send or receive
+    NotACommunication,
+    // a second default: in a select or switch, which is the statement
+    MultipleDefaults(&'static str),
+    // const x, or const x int, outside a group's later specs
+    MissingConstantValue,
+    // for i := 0; i < n; j := i {}
+    DefineInPostStatement,
+    // fallthrough anywhere but at the end of a switch case
+    MisplacedFallthrough,
+    // fallthrough at the end of a switch's last case
+    FallthroughInFinalCase,
+    MissingPackageClause,
+    ImportAfterDeclaration,
+    NonDeclarationStatementOutsideFunction,
+    NestingTooDeep,
+}
+
+impl ParserErrorKind {
+    /// The code `gor explain` describes the error by, like `E0002`, or that of a lexical error
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserErrorKind::LexerError(error) => error.kind.code(),
+            ParserErrorKind::Expected { .. } => "E0002",
+            ParserErrorKind::NotAPrimaryExpression(_) => "E0003",
+            ParserErrorKind::NotAType(_) => "E0004",
+            ParserErrorKind::ExpressionNotUsedAsStatement => "E0005",
+            ParserErrorKind::BlankIdentifierAsValue => "E0006",
+            ParserErrorKind::NonNameOnLeftOfDefine => "E0007",
+            ParserErrorKind::TooManyRangeVariables => "E0008",
+            ParserErrorKind::MixedNamedAndUnnamedParameters => "E0009",
+            ParserErrorKind::ReceiverCount(_) => "E0010",
+            ParserErrorKind::MissingSliceIndex(_) => "E0011",
+            ParserErrorKind::NotACall(_) => "E0012",
+            ParserErrorKind::NotACommunication => "E0013",
+            ParserErrorKind::MultipleDefaults(_) => "E0014",
+            ParserErrorKind::MissingConstantValue => "E0015",
+            ParserErrorKind::DefineInPostStatement => "E0016",
+            ParserErrorKind::MisplacedFallthrough => "E0017",
+            ParserErrorKind::FallthroughInFinalCase => "E0018",
+            ParserErrorKind::MissingPackageClause => "E0019",
+            ParserErrorKind::ImportAfterDeclaration => "E0020",
+            ParserErrorKind::NonDeclarationStatementOutsideFunction => "E0021",
+            ParserErrorKind::NestingTooDeep => "E0022",
+        }
+    }
 }
 
 impl std::fmt::Display for ParserErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParserErrorKind::LexerError(error) => write!(f, "{}", error),
-            ParserErrorKind::UnexpectedToken(token) => write!(f, "Unexpected token: {}", token),
-            ParserErrorKind::NotAPrimaryExpression(token) => write!(f, "Not a primary expression: {}", token),
-            ParserErrorKind::NotImplemented => write!(f, "Not implemented"),
+            ParserErrorKind::LexerError(error) => write!(f, "{}", error.kind),
+            ParserErrorKind::Expected {
+                expected,
+                found,
+                context,
+            } => {
+                write!(f, "expected ")?;
+                for (i, kind) in expected.iter().enumerate() {
+                    if i > 0 {
+                        write!(
+                            f,
+                            "{}",
+                            if i == expected.len() - 1 {
+                                " or "
+                            } else {
+                                ", "
+                            }
+                        )?;
+                    }
+                    write!(f, "{}", kind)?;
+                }
+                if let Some(context) = context {
+                    write!(f, " {}", context)?;
+                }
+                write!(f, ", found {}", found.describe())
+            }
+            ParserErrorKind::NotAPrimaryExpression(token) => {
+                write!(f, "Not a primary expression: {}", token)
+            }
+            ParserErrorKind::NotAType(token) => write!(f, "Not a type: {}", token),
+            ParserErrorKind::ExpressionNotUsedAsStatement => {
+                write!(f, "Expression is not used as a statement")
+            }
+            ParserErrorKind::BlankIdentifierAsValue => write!(f, "Cannot use _ as value"),
+            ParserErrorKind::NonNameOnLeftOfDefine => write!(f, "Non-name on left side of :="),
+            ParserErrorKind::TooManyRangeVariables => {
+                write!(f, "Range clause permits at most two iteration variables")
+            }
+            ParserErrorKind::MixedNamedAndUnnamedParameters => {
+                write!(f, "Mixed named and unnamed parameters")
+            }
+            ParserErrorKind::ReceiverCount(0) => write!(f, "Method has no receiver"),
+            ParserErrorKind::ReceiverCount(_) => write!(f, "Method has multiple receivers"),
+            ParserErrorKind::MissingSliceIndex(which) => {
+                write!(f, "{} index required in 3-index slice", which)
+            }
+            ParserErrorKind::NotACall(statement) => {
+                write!(f, "Expression in {} must be function call", statement)
+            }
+            ParserErrorKind::NotACommunication => {
+                write!(f, "Select case must be receive, send or assign recv")
+            }
+            ParserErrorKind::MultipleDefaults(statement) => {
+                write!(f, "Multiple defaults in {}", statement)
+            }
+            ParserErrorKind::MissingConstantValue => {
+                write!(f, "Missing init expr for const declaration")
+            }
+            ParserErrorKind::DefineInPostStatement => {
+                write!(f, "Cannot declare in post statement of for loop")
+            }
+            ParserErrorKind::MisplacedFallthrough => {
+                write!(f, "Fallthrough statement out of place")
+            }
+            ParserErrorKind::FallthroughInFinalCase => {
+                write!(f, "Cannot fallthrough final case in switch")
+            }
+            ParserErrorKind::MissingPackageClause => {
+                write!(f, "Expected 'package' clause at the start of the file")
+            }
+            ParserErrorKind::ImportAfterDeclaration => {
+                write!(f, "Imports must appear before other declarations")
+            }
+            ParserErrorKind::NonDeclarationStatementOutsideFunction => {
+                write!(f, "Non-declaration statement outside function body")
+            }
+            ParserErrorKind::NestingTooDeep => write!(f, "Nesting is too deep"),
         }
     }
 }
@@ -0,0 +1,33 @@
+use crate::primitives::errors::{parser::ParserError, runtime::RuntimeError};
+
+/// Why a REPL input failed: it didn't parse, or it failed as it ran
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplError {
+    Parse(Vec<ParserError>),
+    Runtime(RuntimeError),
+}
+
+/// Each syntax error on a line of its own with where it is in the input, like
+/// `1:4: ...`, or what went wrong at run time as `gor run` reports it
+impl std::fmt::Display for ReplError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplError::Parse(errors) => {
+                for (index, error) in errors.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(
+                        f,
+                        "{}:{}: {}",
+                        error.position.line, error.position.column_start, error.kind
+                    )?;
+                }
+                Ok(())
+            }
+            ReplError::Runtime(error) => write!(f, "{}", error.kind),
+        }
+    }
+}
+
+impl std::error::Error for ReplError {}
@@ -0,0 +1,74 @@
+use crate::primitives::position::Position;
+
+/// A rewrite that can't be made, and where in the source it was asked for
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewriteError {
+    pub kind: RewriteErrorKind,
+    pub position: Position,
+}
+
+impl RewriteError {
+    pub fn new(kind: RewriteErrorKind, position: Position) -> RewriteError {
+        RewriteError { kind, position }
+    }
+}
+
+impl std::fmt::Display for RewriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.position.line, self.position.column_start, self.kind
+        )
+    }
+}
+
+impl std::error::Error for RewriteError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RewriteErrorKind {
+    // A position outside the source, like one of a node that was made rather than parsed
+    OutOfRange,
+    // Two edits of the same text
+    OverlappingEdits,
+    // A rename of something other than a name the resolver knows
+    NotAName,
+    // A rename to something that isn't an identifier, like `1x`
+    InvalidName(String),
+    // A rename to a name already declared where the renamed one is
+    NameTaken(String),
+    // A rename to a name the renamed one would hide where it's used, like `len`
+    Hides(String),
+    // A rename of a name gor declares, like `len`
+    Predeclared(String),
+    // A rename to an unexported name of a package's member another package uses
+    Unexported(String),
+    // An import removed that the file doesn't have
+    NoSuchImport(String),
+}
+
+impl std::fmt::Display for RewriteErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RewriteErrorKind::OutOfRange => write!(f, "position is outside the source"),
+            RewriteErrorKind::OverlappingEdits => write!(f, "edits overlap"),
+            RewriteErrorKind::NotAName => write!(f, "no name to rename here"),
+            RewriteErrorKind::InvalidName(name) => write!(f, "{} is not a valid identifier", name),
+            RewriteErrorKind::NameTaken(name) => write!(f, "{} is already declared", name),
+            RewriteErrorKind::Hides(name) => {
+                write!(f, "renaming to {} would hide the {} used here", name, name)
+            }
+            RewriteErrorKind::Predeclared(name) => {
+                write!(f, "{} is predeclared and can't be renamed", name)
+            }
+            RewriteErrorKind::Unexported(name) => {
+                write!(
+                    f,
+                    "{} isn't exported, but another package uses the name",
+                    name
+                )
+            }
+            RewriteErrorKind::NoSuchImport(path) => write!(f, "{} is not imported", path),
+        }
+    }
+}
@@ -0,0 +1,479 @@
+use crate::primitives::position::Position;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub position: Position,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, position: Position) -> RuntimeError {
+        RuntimeError { kind, position }
+    }
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Runtime error at {:?}: {}", self.position, self.kind)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// A call a panic unwound, and where in the function it was: the panic itself for the
+/// innermost, and the call it was making for the rest
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    pub function: String,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    // 1 / 0
+    DivisionByZero,
+    // 1 << n, where n is negative
+    NegativeShift,
+    UndefinedName(String),
+    // 1 + "a", which the checker rejects but can still be evaluated unchecked
+    MismatchedTypes {
+        operation: String,
+        left_type: String,
+        right_type: String,
+    },
+    // "a" - "b"
+    UndefinedOperator {
+        operation: String,
+        operator: String,
+        operand_type: String,
+    },
+    // 100000000000000000000, too large for an int64
+    InvalidLiteral(String),
+    // f() (no value) used as value
+    NoValue(String),
+    // multiple-value f() in single-value context
+    MultipleValues(String),
+    // a, b := 1
+    AssignmentMismatch {
+        variables: usize,
+        values: usize,
+    },
+    // f(1, 2) for a function of one parameter
+    ArgumentCount {
+        function: String,
+        have: usize,
+        want: usize,
+    },
+    // fmt.Printf(1), where the argument's value is only known at run time
+    CannotUseArgument {
+        value_type: String,
+        parameter_type: String,
+        function: String,
+    },
+    // len(1)
+    InvalidArgument {
+        argument: String,
+        argument_type: String,
+        builtin: String,
+    },
+    // make(x), where x isn't a type
+    NotAType(String),
+    // make(int)
+    CannotMake(String),
+    // make([]int, n), where n is negative, with the function "makeslice" and size "len"
+    SizeOutOfRange {
+        function: String,
+        size: String,
+    },
+    // delete(m, k), where k is a slice stored in an interface
+    UnhashableKey(String),
+    // x[0], where x is an int
+    CannotIndex {
+        value: String,
+        value_type: String,
+    },
+    // x[1:], where x is a map
+    CannotSlice {
+        value: String,
+        value_type: String,
+    },
+    // s[1:2:3], where s is a string
+    ThreeIndexString,
+    // s["a"], where s is a slice
+    NonIntegerIndex {
+        index: String,
+        index_type: String,
+    },
+    // s[0] = 'a', where s is a string
+    CannotAssign(String),
+    // s[5], where s has 3 elements
+    IndexOutOfRange {
+        index: i64,
+        length: usize,
+    },
+    // s[2:1], with the bounds as Go prints them, like "[2:1]" or "[:5] with capacity 3"
+    SliceBoundsOutOfRange(String),
+    // m[k] = v, where m is a nil map
+    NilMapAssignment,
+    // defer len(s), where the statement is "defer"
+    DiscardedResult {
+        statement: String,
+        call: String,
+    },
+    // panic("boom") or a run-time error like s[5], unrecovered. A deferred call panicking
+    // while an earlier panic unwinds adds to its messages. The stack is innermost first, and the goroutine is the panicking
+    // one's id, where main's is 1.
+    Panic {
+        messages: Vec<String>,
+        stack: Vec<StackFrame>,
+        goroutine: usize,
+    },
+    // <-x, where x is an int, with the operation "receive from", "send to" or "close"
+    NonChannel {
+        operation: String,
+        value: String,
+        value_type: String,
+    },
+    // ch <- v, where ch is closed
+    SendOnClosedChannel,
+    // close(ch), where ch is already closed
+    CloseOfClosedChannel,
+    // close(ch), where ch is nil
+    CloseOfNilChannel,
+    // Every goroutine blocked on a channel, so none can ever be woken
+    Deadlock,
+    // A goroutine's thread couldn't be started, with why
+    CannotStartGoroutine(String),
+    // Unwinds a goroutine still running when main returns, and is never reported
+    ProgramExited,
+    // mu.Unlock(), where mu isn't locked
+    UnlockOfUnlockedMutex,
+    // p.Lock(), where p is a nil *sync.Mutex
+    NilDereference,
+    // f(), where f is an int
+    NonFunction {
+        value: String,
+        value_type: String,
+    },
+    // strings.Repeat("a", n), where n is negative, with the message the package panics with
+    PackagePanic(String),
+    // wg.Stop(), where wg is a sync.WaitGroup
+    UndefinedMethod {
+        expression: String,
+        value_type: String,
+        method: String,
+    },
+    // Point{Z: 1}, where Point has no field Z
+    UnknownField {
+        field: String,
+        type_name: String,
+    },
+    // Point{1, 2, 3}, where Point has two fields
+    TooManyValues(String),
+    // &f(), which isn't a variable
+    CannotTakeAddress(String),
+    // os.Exit(3), which ends the program at once, without running deferred calls
+    Exit(i32),
+    // t.FailNow(), which like runtime.Goexit ends the goroutine after running its deferred
+    // calls
+    Goexit,
+    // if 1 { ... }, where the statement is "if" or "for"
+    NonBooleanCondition {
+        condition: String,
+        statement: String,
+    },
+    // int("a")
+    CannotConvert {
+        value: String,
+        value_type: String,
+        target_type: String,
+    },
+    // for x := range 1.5 { ... }
+    CannotRange {
+        value: String,
+        value_type: String,
+    },
+    // Calls nested deeper than the interpreter allows, usually unbounded recursion
+    StackOverflow,
+    // A program running more statements than its InterpreterConfig allows
+    StepLimitExceeded(u64),
+    // A program allocating more bytes than its InterpreterConfig allows
+    AllocationLimitExceeded(usize),
+    // A program running for longer than its InterpreterConfig allows
+    TimeLimitExceeded(std::time::Duration),
+    // gor run on a file that isn't `package main`
+    NotMainPackage(String),
+    MissingMain,
+    // An expression the evaluator can't evaluate yet
+    Unsupported(String),
+    // A panic in the interpreter itself, a bug in gor rather than the program
+    Internal(String),
+}
+
+impl RuntimeErrorKind {
+    /// Whether Go reports the error by panicking, so deferred calls run and can recover it,
+    /// rather than rejecting the program or failing fatally
+    pub fn is_run_time_panic(&self) -> bool {
+        matches!(
+            self,
+            RuntimeErrorKind::DivisionByZero
+                | RuntimeErrorKind::NegativeShift
+                | RuntimeErrorKind::SizeOutOfRange { .. }
+                | RuntimeErrorKind::UnhashableKey(_)
+                | RuntimeErrorKind::IndexOutOfRange { .. }
+                | RuntimeErrorKind::SliceBoundsOutOfRange(_)
+                | RuntimeErrorKind::NilMapAssignment
+                | RuntimeErrorKind::SendOnClosedChannel
+                | RuntimeErrorKind::CloseOfClosedChannel
+                | RuntimeErrorKind::CloseOfNilChannel
+                | RuntimeErrorKind::NilDereference
+                | RuntimeErrorKind::PackagePanic(_)
+        )
+    }
+}
+
+impl std::fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeErrorKind::DivisionByZero => write!(f, "runtime error: integer divide by zero"),
+            RuntimeErrorKind::NegativeShift => write!(f, "runtime error: negative shift amount"),
+            RuntimeErrorKind::UndefinedName(name) => write!(f, "undefined: {}", name),
+            RuntimeErrorKind::MismatchedTypes {
+                operation,
+                left_type,
+                right_type,
+            } => write!(
+                f,
+                "invalid operation: {} (mismatched types {} and {})",
+                operation, left_type, right_type
+            ),
+            RuntimeErrorKind::UndefinedOperator {
+                operation,
+                operator,
+                operand_type,
+            } => write!(
+                f,
+                "invalid operation: {} (operator {} not defined on {})",
+                operation, operator, operand_type
+            ),
+            RuntimeErrorKind::InvalidLiteral(literal) => {
+                write!(f, "cannot represent {} as a value", literal)
+            }
+            RuntimeErrorKind::NoValue(expression) => {
+                write!(f, "{} (no value) used as value", expression)
+            }
+            RuntimeErrorKind::MultipleValues(expression) => {
+                write!(f, "multiple-value {} in single-value context", expression)
+            }
+            RuntimeErrorKind::AssignmentMismatch { variables, values } => write!(
+                f,
+                "assignment mismatch: {} variable{} but {} value{}",
+                variables,
+                plural(*variables),
+                values,
+                plural(*values)
+            ),
+            RuntimeErrorKind::ArgumentCount {
+                function,
+                have,
+                want,
+            } => write!(
+                f,
+                "wrong number of arguments in call to {} (expected {}, found {})",
+                function, want, have
+            ),
+            RuntimeErrorKind::CannotUseArgument {
+                value_type,
+                parameter_type,
+                function,
+            } => write!(
+                f,
+                "cannot use {} value as {} value in argument to {}",
+                value_type, parameter_type, function
+            ),
+            RuntimeErrorKind::InvalidArgument {
+                argument,
+                argument_type,
+                builtin,
+            } => write!(
+                f,
+                "invalid argument: {} (type {}) for built-in {}",
+                argument, argument_type, builtin
+            ),
+            RuntimeErrorKind::NotAType(expression) => write!(f, "{} is not a type", expression),
+            RuntimeErrorKind::CannotMake(type_name) => write!(
+                f,
+                "invalid argument: cannot make {}; type must be slice, map, or channel",
+                type_name
+            ),
+            RuntimeErrorKind::SizeOutOfRange { function, size } => {
+                write!(f, "runtime error: {}: {} out of range", function, size)
+            }
+            RuntimeErrorKind::UnhashableKey(type_name) => {
+                write!(f, "runtime error: hash of unhashable type {}", type_name)
+            }
+            RuntimeErrorKind::CannotIndex { value, value_type } => write!(
+                f,
+                "invalid operation: cannot index {} (type {})",
+                value, value_type
+            ),
+            RuntimeErrorKind::CannotSlice { value, value_type } => {
+                write!(f, "cannot slice {} (type {})", value, value_type)
+            }
+            RuntimeErrorKind::ThreeIndexString => {
+                write!(f, "invalid operation: 3-index slice of string")
+            }
+            RuntimeErrorKind::NonIntegerIndex { index, index_type } => write!(
+                f,
+                "invalid argument: index {} (type {}) must be integer",
+                index, index_type
+            ),
+            RuntimeErrorKind::CannotAssign(target) => write!(
+                f,
+                "cannot assign to {} (neither addressable nor a map index expression)",
+                target
+            ),
+            RuntimeErrorKind::IndexOutOfRange { index, length } => write!(
+                f,
+                "runtime error: index out of range [{}] with length {}",
+                index, length
+            ),
+            RuntimeErrorKind::SliceBoundsOutOfRange(bounds) => {
+                write!(f, "runtime error: slice bounds out of range {}", bounds)
+            }
+            RuntimeErrorKind::NilMapAssignment => write!(f, "assignment to entry in nil map"),
+            RuntimeErrorKind::DiscardedResult { statement, call } => {
+                write!(f, "{} discards result of {}", statement, call)
+            }
+            // As Go prints it, though without arguments or file names
+            RuntimeErrorKind::Panic {
+                messages,
+                stack,
+                goroutine,
+            } => {
+                for (i, message) in messages.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "\n\t")?;
+                    }
+                    write!(f, "panic: {}", message)?;
+                }
+                write!(f, "\n\ngoroutine {} [running]:", goroutine)?;
+                for frame in stack {
+                    write!(
+                        f,
+                        "\nmain.{}(...)\n\tline {}",
+                        frame.function, frame.position.line
+                    )?;
+                }
+                Ok(())
+            }
+            RuntimeErrorKind::NonChannel {
+                operation,
+                value,
+                value_type,
+            } => write!(
+                f,
+                "invalid operation: cannot {} non-channel {} (type {})",
+                operation, value, value_type
+            ),
+            RuntimeErrorKind::SendOnClosedChannel => write!(f, "send on closed channel"),
+            RuntimeErrorKind::CloseOfClosedChannel => write!(f, "close of closed channel"),
+            RuntimeErrorKind::CloseOfNilChannel => write!(f, "close of nil channel"),
+            RuntimeErrorKind::Deadlock => {
+                write!(f, "fatal error: all goroutines are asleep - deadlock!")
+            }
+            RuntimeErrorKind::CannotStartGoroutine(reason) => {
+                write!(f, "cannot start goroutine: {}", reason)
+            }
+            RuntimeErrorKind::ProgramExited => write!(f, "program exited"),
+            RuntimeErrorKind::UnlockOfUnlockedMutex => {
+                write!(f, "fatal error: sync: unlock of unlocked mutex")
+            }
+            RuntimeErrorKind::NilDereference => write!(
+                f,
+                "runtime error: invalid memory address or nil pointer dereference"
+            ),
+            RuntimeErrorKind::NonFunction { value, value_type } => write!(
+                f,
+                "invalid operation: cannot call non-function {} (type {})",
+                value, value_type
+            ),
+            RuntimeErrorKind::PackagePanic(message) => write!(f, "{}", message),
+            RuntimeErrorKind::UndefinedMethod {
+                expression,
+                value_type,
+                method,
+            } => write!(
+                f,
+                "{} undefined (type {} has no field or method {})",
+                expression, value_type, method
+            ),
+            // As go run reports a program's exit
+            RuntimeErrorKind::Exit(code) => write!(f, "exit status {}", code),
+            RuntimeErrorKind::Goexit => write!(f, "runtime.Goexit called outside a test"),
+            RuntimeErrorKind::NonBooleanCondition {
+                condition,
+                statement,
+            } => write!(
+                f,
+                "non-boolean condition {} in {} statement",
+                condition, statement
+            ),
+            RuntimeErrorKind::CannotConvert {
+                value,
+                value_type,
+                target_type,
+            } => write!(
+                f,
+                "cannot convert {} (type {}) to type {}",
+                value, value_type, target_type
+            ),
+            RuntimeErrorKind::CannotRange { value, value_type } => {
+                write!(f, "cannot range over {} (type {})", value, value_type)
+            }
+            RuntimeErrorKind::StackOverflow => write!(f, "fatal error: stack overflow"),
+            RuntimeErrorKind::StepLimitExceeded(steps) => {
+                write!(f, "fatal error: step limit of {} exceeded", steps)
+            }
+            RuntimeErrorKind::AllocationLimitExceeded(bytes) => {
+                write!(
+                    f,
+                    "fatal error: allocation limit of {} bytes exceeded",
+                    bytes
+                )
+            }
+            RuntimeErrorKind::TimeLimitExceeded(limit) => {
+                write!(f, "fatal error: time limit of {:?} exceeded", limit)
+            }
+            RuntimeErrorKind::NotMainPackage(package) => {
+                write!(f, "package {} is not a main package", package)
+            }
+            RuntimeErrorKind::MissingMain => {
+                write!(f, "function main is undeclared in the main package")
+            }
+            RuntimeErrorKind::UnknownField { field, type_name } => write!(
+                f,
+                "unknown field {} in struct literal of type {}",
+                field, type_name
+            ),
+            RuntimeErrorKind::TooManyValues(type_name) => {
+                write!(f, "too many values in struct literal of type {}", type_name)
+            }
+            RuntimeErrorKind::CannotTakeAddress(expression) => {
+                write!(
+                    f,
+                    "invalid operation: cannot take address of {}",
+                    expression
+                )
+            }
+            RuntimeErrorKind::Unsupported(expression) => {
+                write!(f, "cannot evaluate {} yet", expression)
+            }
+            RuntimeErrorKind::Internal(message) => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
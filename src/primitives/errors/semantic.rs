@@ -0,0 +1,521 @@
+use crate::primitives::position::Position;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticError {
+    pub kind: SemanticErrorKind,
+    pub position: Position,
+}
+
+impl SemanticError {
+    pub fn new(kind: SemanticErrorKind, position: Position) -> SemanticError {
+        SemanticError { kind, position }
+    }
+}
+
+impl std::fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Semantic error at {:?}: {}", self.position, self.kind)
+    }
+}
+
+impl std::error::Error for SemanticError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticErrorKind {
+    UndefinedName(String),
+    Redeclared(String),
+    NotAType(String),
+    NoNewVariables,
+    AssignmentMismatch {
+        variables: usize,
+        values: usize,
+    },
+    // f() (no value) used as value
+    NoValue(String),
+    // multiple-value f() in single-value context
+    MultipleValues(String),
+    UntypedNil,
+    NotEnoughArguments {
+        function: String,
+        have: String,
+        want: String,
+    },
+    TooManyArguments {
+        function: String,
+        have: String,
+        want: String,
+    },
+    // cannot use x (type int) as type string in argument to f, where the context is
+    // "argument to f", "return statement", "assignment" or "variable declaration"
+    CannotUse {
+        value: String,
+        value_type: String,
+        target_type: String,
+        context: String,
+    },
+    // cannot use p (type Point) as type Stringer in assignment:
+    //     Point does not implement Stringer (missing method String)
+    DoesNotImplement {
+        value: String,
+        value_type: String,
+        target_type: String,
+        context: String,
+        reason: String,
+    },
+    // cannot convert x (type string) to type int
+    CannotConvert {
+        value: String,
+        value_type: String,
+        target_type: String,
+    },
+    // f(xs...) where f isn't variadic
+    NonVariadicSpread(String),
+    NotEnoughReturnValues {
+        have: String,
+        want: String,
+    },
+    TooManyReturnValues {
+        have: String,
+        want: String,
+    },
+    MissingReturn,
+    // func (p *T) M() where T is a pointer or interface type, or not declared in the file
+    InvalidReceiver(String),
+    MethodRedeclared {
+        type_name: String,
+        method: String,
+    },
+    // fmt.println, with where `println` is declared once other packages are loaded
+    UnexportedName {
+        package: String,
+        name: String,
+        declaration: Option<Position>,
+    },
+    // type T struct { t T }, with the types of the cycle in order, starting with the one
+    // reported
+    InvalidRecursiveType(Vec<String>),
+    // var a = b; var b = a, with the variables and functions of the cycle in order
+    InitializationCycle(Vec<String>),
+    // len(a, b), where `want` is the fewest or most arguments the builtin takes
+    BuiltinArgumentCount {
+        call: String,
+        have: usize,
+        want: usize,
+    },
+    // len(1)
+    InvalidBuiltinArgument {
+        builtin: String,
+        value: String,
+        value_type: String,
+    },
+    // f := len
+    BuiltinNotCalled(String),
+    // x := []int
+    NotAnExpression(String),
+    // x <- 1, where x isn't a channel
+    NotAChannel {
+        operation: ChannelOperation,
+        value: String,
+        value_type: String,
+    },
+    // ch <- 1, where ch is a `<-chan int`
+    WrongChannelDirection {
+        operation: ChannelOperation,
+        channel: String,
+        channel_type: String,
+    },
+    // p.nme, where `suggestion` is a close name like `field name`
+    MissingFieldOrMethod {
+        selector: String,
+        type_name: String,
+        name: String,
+        suggestion: Option<String>,
+    },
+    // Point{Z: 1}, where Point has no field Z
+    UnknownField {
+        field: String,
+        type_name: String,
+    },
+    // x[0], where x is an int
+    CannotIndex {
+        value: String,
+        value_type: String,
+    },
+    // x[1:], where x is a map
+    CannotSlice {
+        value: String,
+        value_type: String,
+    },
+    // s[1:2:3], where s is a string
+    ThreeIndexString,
+    // s["a"], where s is a slice
+    NonIntegerIndex {
+        index: String,
+        index_type: String,
+    },
+    // a[5], where a is a [4]int
+    IndexOutOfBounds {
+        index: String,
+        length: u64,
+    },
+    // var b byte = 300
+    ConstantOverflows {
+        constant: String,
+        target_type: String,
+    },
+    // var i int = 1.5
+    ConstantTruncated(String),
+    // var x = iota
+    IotaOutsideConstant,
+}
+
+impl SemanticErrorKind {
+    /// The code `gor explain` describes the error by, like `E0101`. Its first two digits are
+    /// the check that finds it, in the order they're listed.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SemanticErrorKind::UndefinedName(_) => "E0101",
+            SemanticErrorKind::Redeclared(_) => "E0102",
+            SemanticErrorKind::NotAType(_) => "E0103",
+            SemanticErrorKind::UnexportedName { .. } => "E0104",
+            SemanticErrorKind::NoNewVariables => "E0201",
+            SemanticErrorKind::AssignmentMismatch { .. } => "E0202",
+            SemanticErrorKind::UntypedNil => "E0203",
+            SemanticErrorKind::CannotUse { .. } => "E0204",
+            SemanticErrorKind::DoesNotImplement { .. } => "E0205",
+            SemanticErrorKind::NoValue(_) => "E0301",
+            SemanticErrorKind::MultipleValues(_) => "E0302",
+            SemanticErrorKind::NotEnoughArguments { .. } => "E0303",
+            SemanticErrorKind::TooManyArguments { .. } => "E0304",
+            SemanticErrorKind::NonVariadicSpread(_) => "E0305",
+            SemanticErrorKind::BuiltinArgumentCount { .. } => "E0306",
+            SemanticErrorKind::InvalidBuiltinArgument { .. } => "E0307",
+            SemanticErrorKind::BuiltinNotCalled(_) => "E0308",
+            SemanticErrorKind::CannotConvert { .. } => "E0401",
+            SemanticErrorKind::NotAnExpression(_) => "E0402",
+            SemanticErrorKind::NotEnoughReturnValues { .. } => "E0501",
+            SemanticErrorKind::TooManyReturnValues { .. } => "E0502",
+            SemanticErrorKind::MissingReturn => "E0503",
+            SemanticErrorKind::InvalidReceiver(_) => "E0601",
+            SemanticErrorKind::MethodRedeclared { .. } => "E0602",
+            SemanticErrorKind::MissingFieldOrMethod { .. } => "E0701",
+            SemanticErrorKind::UnknownField { .. } => "E0702",
+            SemanticErrorKind::CannotIndex { .. } => "E0801",
+            SemanticErrorKind::CannotSlice { .. } => "E0802",
+            SemanticErrorKind::ThreeIndexString => "E0803",
+            SemanticErrorKind::NonIntegerIndex { .. } => "E0804",
+            SemanticErrorKind::IndexOutOfBounds { .. } => "E0805",
+            SemanticErrorKind::NotAChannel { .. } => "E0901",
+            SemanticErrorKind::WrongChannelDirection { .. } => "E0902",
+            SemanticErrorKind::InvalidRecursiveType(_) => "E1001",
+            SemanticErrorKind::InitializationCycle(_) => "E1002",
+            SemanticErrorKind::ConstantOverflows { .. } => "E1101",
+            SemanticErrorKind::ConstantTruncated(_) => "E1102",
+            SemanticErrorKind::IotaOutsideConstant => "E1103",
+        }
+    }
+}
+
+impl std::fmt::Display for SemanticErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SemanticErrorKind::UndefinedName(name) => write!(f, "undefined: {}", name),
+            SemanticErrorKind::Redeclared(name) => write!(f, "{} redeclared in this block", name),
+            SemanticErrorKind::NotAType(name) => write!(f, "{} is not a type", name),
+            SemanticErrorKind::NoNewVariables => write!(f, "no new variables on left side of :="),
+            SemanticErrorKind::AssignmentMismatch { variables, values } => write!(
+                f,
+                "assignment mismatch: {} variable{} but {} value{}",
+                variables,
+                plural(*variables),
+                values,
+                plural(*values)
+            ),
+            SemanticErrorKind::NoValue(expression) => {
+                write!(f, "{} (no value) used as value", expression)
+            }
+            SemanticErrorKind::MultipleValues(expression) => {
+                write!(f, "multiple-value {} in single-value context", expression)
+            }
+            SemanticErrorKind::UntypedNil => write!(f, "use of untyped nil in assignment"),
+            SemanticErrorKind::NotEnoughArguments {
+                function,
+                have,
+                want,
+            } => write!(
+                f,
+                "not enough arguments in call to {}\n\thave {}\n\twant {}",
+                function, have, want
+            ),
+            SemanticErrorKind::TooManyArguments {
+                function,
+                have,
+                want,
+            } => write!(
+                f,
+                "too many arguments in call to {}\n\thave {}\n\twant {}",
+                function, have, want
+            ),
+            SemanticErrorKind::CannotUse {
+                value,
+                value_type,
+                target_type,
+                context,
+            } => write!(
+                f,
+                "cannot use {} (type {}) as type {} in {}",
+                value, value_type, target_type, context
+            ),
+            SemanticErrorKind::DoesNotImplement {
+                value,
+                value_type,
+                target_type,
+                context,
+                reason,
+            } => write!(
+                f,
+                "cannot use {} (type {}) as type {} in {}:\n\t{}",
+                value, value_type, target_type, context, reason
+            ),
+            SemanticErrorKind::CannotConvert {
+                value,
+                value_type,
+                target_type,
+            } => write!(
+                f,
+                "cannot convert {} (type {}) to type {}",
+                value, value_type, target_type
+            ),
+            SemanticErrorKind::NonVariadicSpread(function) => {
+                write!(f, "cannot use ... in call to non-variadic {}", function)
+            }
+            SemanticErrorKind::NotEnoughReturnValues { have, want } => write!(
+                f,
+                "not enough return values\n\thave {}\n\twant {}",
+                have, want
+            ),
+            SemanticErrorKind::TooManyReturnValues { have, want } => write!(
+                f,
+                "too many return values\n\thave {}\n\twant {}",
+                have, want
+            ),
+            SemanticErrorKind::MissingReturn => write!(f, "missing return"),
+            SemanticErrorKind::InvalidReceiver(receiver) => {
+                write!(f, "invalid receiver type {}", receiver)
+            }
+            SemanticErrorKind::MethodRedeclared { type_name, method } => {
+                write!(f, "method {}.{} already declared", type_name, method)
+            }
+            SemanticErrorKind::UnexportedName { package, name, .. } => {
+                write!(f, "name {} not exported by package {}", name, package)
+            }
+            SemanticErrorKind::InvalidRecursiveType(path) if path.len() == 1 => {
+                write!(f, "invalid recursive type: {} refers to itself", path[0])
+            }
+            SemanticErrorKind::InvalidRecursiveType(path) => {
+                write!(f, "invalid recursive type {}", path[0])?;
+                write_cycle(f, path)
+            }
+            SemanticErrorKind::InitializationCycle(path) if path.len() == 1 => {
+                write!(f, "initialization cycle: {} refers to itself", path[0])
+            }
+            SemanticErrorKind::InitializationCycle(path) => {
+                write!(f, "initialization cycle:")?;
+                write_cycle(f, path)
+            }
+            SemanticErrorKind::BuiltinArgumentCount { call, have, want } => write!(
+                f,
+                "{} arguments for {} (expected {}, found {})",
+                if have < want {
+                    "not enough"
+                } else {
+                    "too many"
+                },
+                call,
+                want,
+                have
+            ),
+            SemanticErrorKind::InvalidBuiltinArgument {
+                builtin,
+                value,
+                value_type,
+            } => write!(
+                f,
+                "invalid argument: {} (type {}) for built-in {}",
+                value, value_type, builtin
+            ),
+            SemanticErrorKind::BuiltinNotCalled(builtin) => {
+                write!(
+                    f,
+                    "{} (built-in function {}) must be called",
+                    builtin, builtin
+                )
+            }
+            SemanticErrorKind::NotAnExpression(type_expression) => {
+                write!(f, "{} (type) is not an expression", type_expression)
+            }
+            SemanticErrorKind::NotAChannel {
+                operation,
+                value,
+                value_type,
+            } => write!(
+                f,
+                "invalid operation: cannot {} non-channel {} (type {})",
+                operation, value, value_type
+            ),
+            SemanticErrorKind::WrongChannelDirection {
+                operation,
+                channel,
+                channel_type,
+            } => write!(
+                f,
+                "invalid operation: cannot {} {} channel {} (type {})",
+                operation,
+                operation.forbidden_direction(),
+                channel,
+                channel_type
+            ),
+            SemanticErrorKind::MissingFieldOrMethod {
+                selector,
+                type_name,
+                name,
+                suggestion,
+            } => {
+                write!(
+                    f,
+                    "{} undefined (type {} has no field or method {}",
+                    selector, type_name, name
+                )?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, ", but does have {}", suggestion)?;
+                }
+                write!(f, ")")
+            }
+            SemanticErrorKind::UnknownField { field, type_name } => write!(
+                f,
+                "unknown field {} in struct literal of type {}",
+                field, type_name
+            ),
+            SemanticErrorKind::CannotIndex { value, value_type } => write!(
+                f,
+                "invalid operation: cannot index {} (type {})",
+                value, value_type
+            ),
+            SemanticErrorKind::CannotSlice { value, value_type } => {
+                write!(f, "cannot slice {} (type {})", value, value_type)
+            }
+            SemanticErrorKind::ThreeIndexString => {
+                write!(f, "invalid operation: 3-index slice of string")
+            }
+            SemanticErrorKind::NonIntegerIndex { index, index_type } => write!(
+                f,
+                "invalid argument: index {} (type {}) must be integer",
+                index, index_type
+            ),
+            SemanticErrorKind::IndexOutOfBounds { index, length } => write!(
+                f,
+                "invalid argument: index {} out of bounds [0:{}]",
+                index, length
+            ),
+            SemanticErrorKind::ConstantOverflows {
+                constant,
+                target_type,
+            } => write!(f, "constant {} overflows {}", constant, target_type),
+            SemanticErrorKind::ConstantTruncated(constant) => {
+                write!(f, "constant {} truncated to integer", constant)
+            }
+            SemanticErrorKind::IotaOutsideConstant => {
+                write!(f, "cannot use iota outside constant declaration")
+            }
+        }
+    }
+}
+
+/// What's done with a channel (https://go.dev/ref/spec#Channel_types)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelOperation {
+    Send,
+    Receive,
+    Close,
+    Range,
+}
+
+impl ChannelOperation {
+    /// The kind of channel the operation isn't allowed on
+    pub fn forbidden_direction(&self) -> &'static str {
+        match self {
+            ChannelOperation::Send | ChannelOperation::Close => "receive-only",
+            ChannelOperation::Receive | ChannelOperation::Range => "send-only",
+        }
+    }
+}
+
+impl std::fmt::Display for ChannelOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelOperation::Send => write!(f, "send to"),
+            ChannelOperation::Receive => write!(f, "receive from"),
+            ChannelOperation::Close => write!(f, "close"),
+            ChannelOperation::Range => write!(f, "range over"),
+        }
+    }
+}
+
+/// One `a refers to b` line per step of a cycle, ending back at its start
+fn write_cycle(f: &mut std::fmt::Formatter<'_>, path: &[String]) -> std::fmt::Result {
+    for (i, name) in path.iter().enumerate() {
+        write!(f, "\n\t{} refers to {}", name, path[(i + 1) % path.len()])?;
+    }
+    Ok(())
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
+
+/// Something legal but likely a mistake, reported by the opt-in analyses rather than the
+/// checker
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticWarning {
+    pub kind: SemanticWarningKind,
+    pub position: Position,
+}
+
+impl SemanticWarning {
+    pub fn new(kind: SemanticWarningKind, position: Position) -> SemanticWarning {
+        SemanticWarning { kind, position }
+    }
+}
+
+impl std::fmt::Display for SemanticWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Semantic warning at {:?}: {}", self.position, self.kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticWarningKind {
+    // err := g() inside a block, when an outer err is used after the block
+    Shadowed { name: String, declaration: Position },
+}
+
+impl SemanticWarningKind {
+    /// The code `gor explain` describes the warning by
+    pub fn code(&self) -> &'static str {
+        match self {
+            SemanticWarningKind::Shadowed { .. } => "E1201",
+        }
+    }
+}
+
+impl std::fmt::Display for SemanticWarningKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SemanticWarningKind::Shadowed { name, declaration } => write!(
+                f,
+                "declaration of {} shadows declaration at line {}",
+                name, declaration.line
+            ),
+        }
+    }
+}
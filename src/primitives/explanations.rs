@@ -0,0 +1,1600 @@
+/// What a diagnostic's code means, as `gor explain` prints it: why it's reported, with an
+/// example that's reported and the same example fixed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Explanation {
+    /// Like `E0101`, of which the first two digits are the check that reports it, or 00 for
+    /// a syntax error
+    pub code: &'static str,
+    /// The message the diagnostic is reported with, like `undefined: x`
+    pub title: &'static str,
+    /// The semantic check that reports it, like `names`, or None for a syntax error
+    pub check: Option<&'static str>,
+    pub description: &'static str,
+    pub example: &'static str,
+    pub fixed: &'static str,
+}
+
+impl Explanation {
+    /// The explanation as `gor explain` prints it, each example indented by four spaces
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{}: {}\n\n{}",
+            self.code,
+            self.title,
+            wrapped(self.description)
+        );
+        if let Some(check) = self.check {
+            text.push_str(&format!("\nIt's reported by the {} check.\n", check));
+        }
+        text.push_str(&format!(
+            "\nFor example:\n\n{}\nFixed:\n\n{}",
+            indented(self.example),
+            indented(self.fixed)
+        ));
+        text
+    }
+}
+
+/// `text` on lines of up to 80 characters, broken between words
+fn wrapped(text: &str) -> String {
+    let mut output = String::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > 80 {
+            output.push_str(&line);
+            output.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    output.push_str(&line);
+    output.push('\n');
+    output
+}
+
+fn indented(source: &str) -> String {
+    source
+        .lines()
+        .map(|line| match line.is_empty() {
+            true => "\n".to_string(),
+            false => format!("    {}\n", line),
+        })
+        .collect()
+}
+
+/// The explanation of `code`, like `E0101`, whatever its case
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS
+        .iter()
+        .find(|explanation| explanation.code.eq_ignore_ascii_case(code))
+}
+
+/// Every code a diagnostic can have, in order
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0001",
+        title: "Unterminated string: \"hello",
+        check: None,
+        description: "The source has something that isn't a Go token, like a string, rune or \
+            block comment that isn't closed before the end of its line or file, or a character \
+            that isn't part of any token.",
+        example: "package main
+
+var greeting = \"hello
+",
+        fixed: "package main
+
+var greeting = \"hello\"
+",
+    },
+    Explanation {
+        code: "E0002",
+        title: "expected ..., found ...",
+        check: None,
+        description: "The tokens aren't in an order Go's grammar allows, like a parenthesis \
+            that isn't closed. The parser recovers at the next statement or declaration, so one \
+            mistake can be reported more than once.",
+        example: "package main
+
+func main() {
+\tx := (1 + 2
+\t_ = x
+}
+",
+        fixed: "package main
+
+func main() {
+\tx := (1 + 2)
+\t_ = x
+}
+",
+    },
+    Explanation {
+        code: "E0003",
+        title: "Not a primary expression: )",
+        check: None,
+        description: "An operand is missing where an expression needs one, like after a binary \
+            operator or before a closing parenthesis, so the token there can't start an \
+            expression.",
+        example: "package main
+
+func main() {
+\tx := 1 + )
+\t_ = x
+}
+",
+        fixed: "package main
+
+func main() {
+\tx := 1 + 2
+\t_ = x
+}
+",
+    },
+    Explanation {
+        code: "E0004",
+        title: "Not a type: 1",
+        check: None,
+        description: "Something other than a type is written where Go expects one, like an array's \
+            element type or a parameter's type.",
+        example: "package main
+
+var x [3]1
+",
+        fixed: "package main
+
+var x [3]int
+",
+    },
+    Explanation {
+        code: "E0005",
+        title: "Expression is not used as a statement",
+        check: None,
+        description: "An expression whose value is thrown away stands as a statement. Only calls \
+            and receives can stand alone; anything else has to be assigned or used.",
+        example: "package main
+
+func main() {
+\tx := 1
+\tx + 1
+}
+",
+        fixed: "package main
+
+func main() {
+\tx := 1
+\tx = x + 1
+}
+",
+    },
+    Explanation {
+        code: "E0006",
+        title: "Cannot use _ as value",
+        check: None,
+        description: "The blank identifier is read. It can only be assigned to or declared, \
+            discarding the value, so it never holds one.",
+        example: "package main
+
+func main() {
+\tx := _
+}
+",
+        fixed: "package main
+
+func main() {
+\t_ = 1
+}
+",
+    },
+    Explanation {
+        code: "E0007",
+        title: "Non-name on left side of :=",
+        check: None,
+        description: "A short variable declaration declares the names on its left, so something \
+            that isn't a name, like a field or an index, can only be assigned with =.",
+        example: "package main
+
+type Point struct{ X int }
+
+func main() {
+\tvar p Point
+\tp.X := 1
+}
+",
+        fixed: "package main
+
+type Point struct{ X int }
+
+func main() {
+\tvar p Point
+\tp.X = 1
+}
+",
+    },
+    Explanation {
+        code: "E0008",
+        title: "Range clause permits at most two iteration variables",
+        check: None,
+        description: "A range clause gives an index or key and a value each iteration, so it can't \
+            have more than two variables.",
+        example: "package main
+
+func main() {
+\tfor i, v, w := range []int{1} {
+\t}
+}
+",
+        fixed: "package main
+
+func main() {
+\tfor i, v := range []int{1} {
+\t\t_, _ = i, v
+\t}
+}
+",
+    },
+    Explanation {
+        code: "E0009",
+        title: "Mixed named and unnamed parameters",
+        check: None,
+        description: "A function's parameters, or its results, either all have names or none do. A \
+            lone type among named parameters is read as a name missing its type.",
+        example: "package main
+
+func add(a int, int) int {
+\treturn a
+}
+",
+        fixed: "package main
+
+func add(a int, b int) int {
+\treturn a + b
+}
+",
+    },
+    Explanation {
+        code: "E0010",
+        title: "Method has multiple receivers",
+        check: None,
+        description: "A method is declared with no receiver, or more than one, in the parentheses \
+            before its name. A method has exactly one receiver; other values it needs are \
+            parameters.",
+        example: "package main
+
+type Point struct{ X int }
+
+func (p Point, q Point) Sum() int {
+\treturn p.X + q.X
+}
+",
+        fixed: "package main
+
+type Point struct{ X int }
+
+func (p Point) Sum(q Point) int {
+\treturn p.X + q.X
+}
+",
+    },
+    Explanation {
+        code: "E0011",
+        title: "Final index required in 3-index slice",
+        check: None,
+        description: "A slice expression with two colons sets the result's capacity, so it needs \
+            both its middle and final indices.",
+        example: "package main
+
+func main() {
+\ts := []int{1, 2, 3}
+\t_ = s[1:2:]
+}
+",
+        fixed: "package main
+
+func main() {
+\ts := []int{1, 2, 3}
+\t_ = s[1:2:3]
+}
+",
+    },
+    Explanation {
+        code: "E0012",
+        title: "Expression in defer must be function call",
+        check: None,
+        description: "A defer or go statement runs a call, so what follows the keyword has to be \
+            one, with its parentheses, rather than the function itself.",
+        example: "package main
+
+func main() {
+\tdefer recover
+}
+",
+        fixed: "package main
+
+func main() {
+\tdefer recover()
+}
+",
+    },
+    Explanation {
+        code: "E0013",
+        title: "Select case must be receive, send or assign recv",
+        check: None,
+        description: "Each case of a select statement waits on a channel, so it has to send to \
+            one, receive from one, or assign what's received.",
+        example: "package main
+
+func ready() bool {
+\treturn true
+}
+
+func main() {
+\tselect {
+\tcase ready():
+\t}
+}
+",
+        fixed: "package main
+
+func ready(c chan bool) {
+\tc <- true
+}
+
+func main() {
+\tc := make(chan bool)
+\tgo ready(c)
+\tselect {
+\tcase <-c:
+\t}
+}
+",
+    },
+    Explanation {
+        code: "E0014",
+        title: "Multiple defaults in switch",
+        check: None,
+        description: "A switch or select statement has more than one default case, so it's \
+            ambiguous which runs when no other case does.",
+        example: "package main
+
+func main() {
+\tswitch {
+\tdefault:
+\tdefault:
+\t}
+}
+",
+        fixed: "package main
+
+func main() {
+\tswitch {
+\tdefault:
+\t}
+}
+",
+    },
+    Explanation {
+        code: "E0015",
+        title: "Missing init expr for const declaration",
+        check: None,
+        description: "A constant is declared without a value. Only a later spec in a parenthesized \
+            group can leave its value out, repeating the one before it.",
+        example: "package main
+
+const limit int
+",
+        fixed: "package main
+
+const limit int = 10
+",
+    },
+    Explanation {
+        code: "E0016",
+        title: "Cannot declare in post statement of for loop",
+        check: None,
+        description: "A for loop's post statement runs after each iteration, so a variable it \
+            declared would go out of scope straight away. Declare it in the init statement.",
+        example: "package main
+
+func main() {
+\tfor i := 0; i < 3; j := i {
+\t}
+}
+",
+        fixed: "package main
+
+func main() {
+\tfor i := 0; i < 3; i++ {
+\t}
+}
+",
+    },
+    Explanation {
+        code: "E0017",
+        title: "Fallthrough statement out of place",
+        check: None,
+        description: "A fallthrough statement can only be the last statement of a switch case, \
+            where it carries on into the next case, and not inside a block or loop within it.",
+        example: "package main
+
+func main() {
+\tswitch {
+\tcase true:
+\t\tif true {
+\t\t\tfallthrough
+\t\t}
+\tdefault:
+\t}
+}
+",
+        fixed: "package main
+
+func main() {
+\tswitch {
+\tcase true:
+\t\tfallthrough
+\tdefault:
+\t}
+}
+",
+    },
+    Explanation {
+        code: "E0018",
+        title: "Cannot fallthrough final case in switch",
+        check: None,
+        description: "The last case of a switch ends in a fallthrough statement, but there's no \
+            case after it to carry on into.",
+        example: "package main
+
+func main() {
+\tswitch {
+\tcase true:
+\t\tfallthrough
+\t}
+}
+",
+        fixed: "package main
+
+func main() {
+\tswitch {
+\tcase true:
+\t}
+}
+",
+    },
+    Explanation {
+        code: "E0019",
+        title: "Expected 'package' clause at the start of the file",
+        check: None,
+        description: "Every Go file starts with a package clause naming the package it belongs to, \
+            before its imports and declarations.",
+        example: "import \"fmt\"
+
+func main() {
+\tfmt.Println()
+}
+",
+        fixed: "package main
+
+import \"fmt\"
+
+func main() {
+\tfmt.Println()
+}
+",
+    },
+    Explanation {
+        code: "E0020",
+        title: "Imports must appear before other declarations",
+        check: None,
+        description: "An import declaration comes after a constant, variable, type or function \
+            declaration. A file's imports all come first, after its package clause.",
+        example: "package main
+
+var x = 1
+
+import \"fmt\"
+
+func main() {
+\tfmt.Println(x)
+}
+",
+        fixed: "package main
+
+import \"fmt\"
+
+var x = 1
+
+func main() {
+\tfmt.Println(x)
+}
+",
+    },
+    Explanation {
+        code: "E0021",
+        title: "Non-declaration statement outside function body",
+        check: None,
+        description: "A statement other than a declaration is at the top level of a file. Outside \
+            functions there can only be declarations, so a variable there is declared with var \
+            rather than :=.",
+        example: "package main
+
+x := 1
+
+func main() {
+}
+",
+        fixed: "package main
+
+var x = 1
+
+func main() {
+}
+",
+    },
+    Explanation {
+        code: "E0022",
+        title: "Nesting is too deep",
+        check: None,
+        description: "An expression, type or block is nested a hundred levels deep. The parser \
+            stops there rather than overflow its stack, so split the nesting up with variables \
+            or functions.",
+        example: "package main
+
+var x = !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!\
+            !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!true
+",
+        fixed: "package main
+
+var x = true
+",
+    },
+    Explanation {
+        code: "E0101",
+        title: "undefined: x",
+        check: Some("names"),
+        description: "A name is used that isn't declared in the scope it's used in, often \
+            because it's misspelt, or declared in a block that ended before it's used.",
+        example: "package main
+
+func main() {
+\ttotal := 1
+\t_ = totl + 1
+}
+",
+        fixed: "package main
+
+func main() {
+\ttotal := 1
+\t_ = total + 1
+}
+",
+    },
+    Explanation {
+        code: "E0102",
+        title: "x redeclared in this block",
+        check: Some("names"),
+        description: "A name is declared twice in the same block. A block can only declare \
+            each name once, though a block inside it can declare the name again.",
+        example: "package main
+
+func main() {
+\tx := 1
+\tvar x = 2
+\t_ = x
+}
+",
+        fixed: "package main
+
+func main() {
+\tx := 1
+\tx = 2
+\t_ = x
+}
+",
+    },
+    Explanation {
+        code: "E0103",
+        title: "x is not a type",
+        check: Some("names"),
+        description: "A name is used where a type is expected, but it names a variable, \
+            constant or function rather than a type.",
+        example: "package main
+
+var count = 3
+
+var n count
+",
+        fixed: "package main
+
+var count = 3
+
+var n int
+",
+    },
+    Explanation {
+        code: "E0104",
+        title: "name x not exported by package p",
+        check: Some("names"),
+        description: "A name is used from another package that the package doesn't export. \
+            Only names starting with an upper case letter can be used outside the package \
+            declaring them.",
+        example: "package main
+
+import \"fmt\"
+
+func main() {
+\tfmt.println(\"hello\")
+}
+",
+        fixed: "package main
+
+import \"fmt\"
+
+func main() {
+\tfmt.Println(\"hello\")
+}
+",
+    },
+    Explanation {
+        code: "E0201",
+        title: "no new variables on left side of :=",
+        check: Some("assignments"),
+        description: "A short variable declaration declares no variables, because every name \
+            on its left is already declared in the block. Assign to them with = instead.",
+        example: "package main
+
+func main() {
+\tx := 1
+\tx := 2
+\t_ = x
+}
+",
+        fixed: "package main
+
+func main() {
+\tx := 1
+\tx = 2
+\t_ = x
+}
+",
+    },
+    Explanation {
+        code: "E0202",
+        title: "assignment mismatch: 2 variables but 1 value",
+        check: Some("assignments"),
+        description: "An assignment or declaration has a different number of variables than \
+            values. A call returning several values counts as that many.",
+        example: "package main
+
+func main() {
+\ta, b := 1
+\t_, _ = a, b
+}
+",
+        fixed: "package main
+
+func main() {
+\ta, b := 1, 2
+\t_, _ = a, b
+}
+",
+    },
+    Explanation {
+        code: "E0203",
+        title: "use of untyped nil in assignment",
+        check: Some("assignments"),
+        description: "A variable is declared with the value nil and no type. nil has no type \
+            of its own, so the variable's type must be written.",
+        example: "package main
+
+func main() {
+\tx := nil
+\t_ = x
+}
+",
+        fixed: "package main
+
+func main() {
+\tvar x *int = nil
+\t_ = x
+}
+",
+    },
+    Explanation {
+        code: "E0204",
+        title: "cannot use x (type T) as type U in assignment",
+        check: Some("assignments"),
+        description: "A value is assigned, passed or returned where a value of another type \
+            is expected. Go doesn't convert between types implicitly, even between numeric ones.",
+        example: "package main
+
+func main() {
+\tvar s string = 1
+\t_ = s
+}
+",
+        fixed: "package main
+
+func main() {
+\tvar s string = \"1\"
+\t_ = s
+}
+",
+    },
+    Explanation {
+        code: "E0205",
+        title: "cannot use x (type T) as type I in assignment: T does not implement I",
+        check: Some("assignments"),
+        description: "A value is used as an interface its type doesn't implement, because \
+            the type is missing one of the interface's methods. A method declared on a pointer \
+            receiver belongs to the pointer type, not to the type it points to.",
+        example: "package main
+
+type Shape interface {
+\tArea() float64
+}
+
+type Square struct {
+\tside float64
+}
+
+func (s *Square) Area() float64 {
+\treturn s.side * s.side
+}
+
+func main() {
+\tvar shape Shape = Square{side: 2}
+\t_ = shape
+}
+",
+        fixed: "package main
+
+type Shape interface {
+\tArea() float64
+}
+
+type Square struct {
+\tside float64
+}
+
+func (s *Square) Area() float64 {
+\treturn s.side * s.side
+}
+
+func main() {
+\tvar shape Shape = &Square{side: 2}
+\t_ = shape
+}
+",
+    },
+    Explanation {
+        code: "E0301",
+        title: "f() (no value) used as value",
+        check: Some("calls"),
+        description: "A call to a function with no results is used as a value.",
+        example: "package main
+
+func greet() {}
+
+func main() {
+\tx := greet()
+\t_ = x
+}
+",
+        fixed: "package main
+
+func greet() {}
+
+func main() {
+\tgreet()
+}
+",
+    },
+    Explanation {
+        code: "E0302",
+        title: "multiple-value f() in single-value context",
+        check: Some("calls"),
+        description: "A call to a function with several results is used where only one value \
+            can be, like an operand. Assign its results to variables first.",
+        example: "package main
+
+func divide(a, b int) (int, int) {
+\treturn a / b, a % b
+}
+
+func main() {
+\tq := divide(7, 2) + 1
+\t_ = q
+}
+",
+        fixed: "package main
+
+func divide(a, b int) (int, int) {
+\treturn a / b, a % b
+}
+
+func main() {
+\tq, _ := divide(7, 2)
+\t_ = q + 1
+}
+",
+    },
+    Explanation {
+        code: "E0303",
+        title: "not enough arguments in call to f",
+        check: Some("calls"),
+        description: "A function is called with fewer arguments than it has parameters. The \
+            diagnostic lists the types of the arguments it has and of the parameters it wants.",
+        example: "package main
+
+func add(a, b int) int {
+\treturn a + b
+}
+
+func main() {
+\t_ = add(1)
+}
+",
+        fixed: "package main
+
+func add(a, b int) int {
+\treturn a + b
+}
+
+func main() {
+\t_ = add(1, 2)
+}
+",
+    },
+    Explanation {
+        code: "E0304",
+        title: "too many arguments in call to f",
+        check: Some("calls"),
+        description: "A function is called with more arguments than it has parameters. The \
+            diagnostic lists the types of the arguments it has and of the parameters it wants.",
+        example: "package main
+
+func add(a, b int) int {
+\treturn a + b
+}
+
+func main() {
+\t_ = add(1, 2, 3)
+}
+",
+        fixed: "package main
+
+func add(a, b int) int {
+\treturn a + b
+}
+
+func main() {
+\t_ = add(add(1, 2), 3)
+}
+",
+    },
+    Explanation {
+        code: "E0305",
+        title: "cannot use ... in call to non-variadic f",
+        check: Some("calls"),
+        description: "A slice is passed with ... to a function whose last parameter isn't \
+            variadic. Only a parameter declared as ...T takes a slice spread this way.",
+        example: "package main
+
+func sum(xs []int) int {
+\treturn len(xs)
+}
+
+func main() {
+\txs := []int{1, 2}
+\t_ = sum(xs...)
+}
+",
+        fixed: "package main
+
+func sum(xs []int) int {
+\treturn len(xs)
+}
+
+func main() {
+\txs := []int{1, 2}
+\t_ = sum(xs)
+}
+",
+    },
+    Explanation {
+        code: "E0306",
+        title: "too many arguments for len(a, b) (expected 1, found 2)",
+        check: Some("calls"),
+        description: "A built-in function is called with more or fewer arguments than it \
+            takes.",
+        example: "package main
+
+func main() {
+\ta, b := \"ab\", \"cd\"
+\t_ = len(a, b)
+}
+",
+        fixed: "package main
+
+func main() {
+\ta, b := \"ab\", \"cd\"
+\t_ = len(a) + len(b)
+}
+",
+    },
+    Explanation {
+        code: "E0307",
+        title: "invalid argument: x (type T) for built-in f",
+        check: Some("calls"),
+        description: "A built-in function is passed a value of a type it doesn't take, like \
+            len of a number. len takes a string, array, slice, map or channel.",
+        example: "package main
+
+func main() {
+\tn := 12345
+\t_ = len(n)
+}
+",
+        fixed: "package main
+
+func main() {
+\tn := \"12345\"
+\t_ = len(n)
+}
+",
+    },
+    Explanation {
+        code: "E0308",
+        title: "f (built-in function f) must be called",
+        check: Some("calls"),
+        description: "A built-in function is used as a value. Built-in functions have no \
+            type of their own, so they can only be called.",
+        example: "package main
+
+func main() {
+\tsize := len
+\t_ = size
+}
+",
+        fixed: "package main
+
+func main() {
+\tsize := len(\"abc\")
+\t_ = size
+}
+",
+    },
+    Explanation {
+        code: "E0401",
+        title: "cannot convert x (type T) to type U",
+        check: Some("conversions"),
+        description: "A value is converted to a type it can't be converted to. Go converts \
+            between numeric types, and between strings and byte or rune slices, but parsing a \
+            number from a string takes a function like strconv.Atoi.",
+        example: "package main
+
+func main() {
+\ts := \"42\"
+\t_ = int(s)
+}
+",
+        fixed: "package main
+
+func main() {
+\ts := \"42\"
+\t_ = []byte(s)
+}
+",
+    },
+    Explanation {
+        code: "E0402",
+        title: "T (type) is not an expression",
+        check: Some("conversions"),
+        description: "A type is used where a value is expected. A composite literal of the \
+            type needs braces, even when it's empty.",
+        example: "package main
+
+func main() {
+\txs := []int
+\t_ = xs
+}
+",
+        fixed: "package main
+
+func main() {
+\txs := []int{}
+\t_ = xs
+}
+",
+    },
+    Explanation {
+        code: "E0501",
+        title: "not enough return values",
+        check: Some("returns"),
+        description: "A return statement has fewer values than the function has results. \
+            Only a function with named results can return without values.",
+        example: "package main
+
+func lookup() (int, bool) {
+\treturn 1
+}
+",
+        fixed: "package main
+
+func lookup() (int, bool) {
+\treturn 1, true
+}
+",
+    },
+    Explanation {
+        code: "E0502",
+        title: "too many return values",
+        check: Some("returns"),
+        description: "A return statement has more values than the function has results.",
+        example: "package main
+
+func count() int {
+\treturn 1, 2
+}
+",
+        fixed: "package main
+
+func count() (int, int) {
+\treturn 1, 2
+}
+",
+    },
+    Explanation {
+        code: "E0503",
+        title: "missing return",
+        check: Some("returns"),
+        description: "A function with results can reach the end of its body without \
+            returning. Every path through it must end in a return statement, a panic or an \
+            infinite loop, and an if only counts when it has an else.",
+        example: "package main
+
+func sign(x int) int {
+\tif x < 0 {
+\t\treturn -1
+\t} else if x > 0 {
+\t\treturn 1
+\t}
+}
+",
+        fixed: "package main
+
+func sign(x int) int {
+\tif x < 0 {
+\t\treturn -1
+\t} else if x > 0 {
+\t\treturn 1
+\t}
+\treturn 0
+}
+",
+    },
+    Explanation {
+        code: "E0601",
+        title: "invalid receiver type T",
+        check: Some("methods"),
+        description: "A method is declared on a type it can't be, like an interface or \
+            pointer type, or a type the package doesn't declare.",
+        example: "package main
+
+type Shape interface {
+\tArea() float64
+}
+
+func (s Shape) Double() float64 {
+\treturn 2 * s.Area()
+}
+",
+        fixed: "package main
+
+type Shape interface {
+\tArea() float64
+}
+
+func double(s Shape) float64 {
+\treturn 2 * s.Area()
+}
+",
+    },
+    Explanation {
+        code: "E0602",
+        title: "method T.M already declared",
+        check: Some("methods"),
+        description: "A type has two methods of the same name, whether their receivers are \
+            the type or a pointer to it.",
+        example: "package main
+
+type Counter struct {
+\tn int
+}
+
+func (c Counter) Value() int {
+\treturn c.n
+}
+
+func (c *Counter) Value() int {
+\treturn c.n
+}
+",
+        fixed: "package main
+
+type Counter struct {
+\tn int
+}
+
+func (c Counter) Value() int {
+\treturn c.n
+}
+",
+    },
+    Explanation {
+        code: "E0701",
+        title: "x.f undefined (type T has no field or method f)",
+        check: Some("selectors"),
+        description: "A field or method is selected that the value's type doesn't have. When \
+            the type has one with a similar name, the diagnostic suggests it.",
+        example: "package main
+
+type Person struct {
+\tname string
+}
+
+func main() {
+\tp := Person{name: \"Ada\"}
+\t_ = p.nme
+}
+",
+        fixed: "package main
+
+type Person struct {
+\tname string
+}
+
+func main() {
+\tp := Person{name: \"Ada\"}
+\t_ = p.name
+}
+",
+    },
+    Explanation {
+        code: "E0702",
+        title: "unknown field f in struct literal of type T",
+        check: Some("selectors"),
+        description: "A struct literal sets a field its type doesn't have.",
+        example: "package main
+
+type Point struct {
+\tX, Y int
+}
+
+func main() {
+\t_ = Point{X: 1, Z: 2}
+}
+",
+        fixed: "package main
+
+type Point struct {
+\tX, Y int
+}
+
+func main() {
+\t_ = Point{X: 1, Y: 2}
+}
+",
+    },
+    Explanation {
+        code: "E0801",
+        title: "invalid operation: cannot index x (type T)",
+        check: Some("indexes"),
+        description: "A value is indexed whose type can't be, like a number. Strings, \
+            arrays, slices, maps and pointers to arrays can be indexed.",
+        example: "package main
+
+func main() {
+\tn := 1234
+\t_ = n[0]
+}
+",
+        fixed: "package main
+
+func main() {
+\tn := \"1234\"
+\t_ = n[0]
+}
+",
+    },
+    Explanation {
+        code: "E0802",
+        title: "cannot slice x (type T)",
+        check: Some("indexes"),
+        description: "A value is sliced whose type can't be, like a map. Strings, arrays, \
+            slices and pointers to arrays can be sliced.",
+        example: "package main
+
+func main() {
+\tages := map[string]int{\"Ada\": 36}
+\t_ = ages[1:]
+}
+",
+        fixed: "package main
+
+func main() {
+\tages := []int{36}
+\t_ = ages[1:]
+}
+",
+    },
+    Explanation {
+        code: "E0803",
+        title: "invalid operation: 3-index slice of string",
+        check: Some("indexes"),
+        description: "A string is sliced with a capacity. Strings have no capacity, so they \
+            can only be sliced with a low and high index.",
+        example: "package main
+
+func main() {
+\ts := \"hello\"
+\t_ = s[1:2:3]
+}
+",
+        fixed: "package main
+
+func main() {
+\ts := \"hello\"
+\t_ = s[1:2]
+}
+",
+    },
+    Explanation {
+        code: "E0804",
+        title: "invalid argument: index x (type T) must be integer",
+        check: Some("indexes"),
+        description: "A string, array or slice is indexed with a value that isn't an \
+            integer. Only a map is indexed by keys of another type.",
+        example: "package main
+
+func main() {
+\txs := []int{1, 2}
+\t_ = xs[\"0\"]
+}
+",
+        fixed: "package main
+
+func main() {
+\txs := []int{1, 2}
+\t_ = xs[0]
+}
+",
+    },
+    Explanation {
+        code: "E0805",
+        title: "invalid argument: index n out of bounds [0:length]",
+        check: Some("indexes"),
+        description: "An array is indexed with a constant outside it. Indexes start at 0, so \
+            the last element of an array of length n is at n-1.",
+        example: "package main
+
+func main() {
+\tvar a [4]int
+\ta[4] = 1
+}
+",
+        fixed: "package main
+
+func main() {
+\tvar a [4]int
+\ta[3] = 1
+}
+",
+    },
+    Explanation {
+        code: "E0901",
+        title: "invalid operation: cannot send to non-channel x (type T)",
+        check: Some("channels"),
+        description: "A value that isn't a channel is sent to, received from, closed or \
+            ranged over as a channel.",
+        example: "package main
+
+func main() {
+\tn := 0
+\tn <- 1
+}
+",
+        fixed: "package main
+
+func main() {
+\tn := make(chan int, 1)
+\tn <- 1
+}
+",
+    },
+    Explanation {
+        code: "E0902",
+        title: "invalid operation: cannot send to receive-only channel ch (type <-chan T)",
+        check: Some("channels"),
+        description: "A channel is used in a direction its type doesn't allow, like sending \
+            to a receive-only channel or receiving from a send-only one.",
+        example: "package main
+
+func produce(ch <-chan int) {
+\tch <- 1
+}
+",
+        fixed: "package main
+
+func produce(ch chan<- int) {
+\tch <- 1
+}
+",
+    },
+    Explanation {
+        code: "E1001",
+        title: "invalid recursive type T",
+        check: Some("cycles"),
+        description: "A type contains itself, directly or through other types, so it would \
+            take infinite space. Refer to it through a pointer, slice or map instead, which \
+            don't.",
+        example: "package main
+
+type Node struct {
+\tvalue int
+\tnext  Node
+}
+",
+        fixed: "package main
+
+type Node struct {
+\tvalue int
+\tnext  *Node
+}
+",
+    },
+    Explanation {
+        code: "E1002",
+        title: "initialization cycle",
+        check: Some("cycles"),
+        description: "Package level variables are initialized from each other in a cycle, \
+            directly or through the functions they call, so none of them can be initialized \
+            first.",
+        example: "package main
+
+var a = b + 1
+
+var b = a + 1
+",
+        fixed: "package main
+
+var a = 1
+
+var b = a + 1
+",
+    },
+    Explanation {
+        code: "E1101",
+        title: "constant x overflows T",
+        check: Some("constants"),
+        description: "A constant is used as a type too small to hold it, like 300 as a byte, \
+            which holds 0 to 255.",
+        example: "package main
+
+var b byte = 300
+",
+        fixed: "package main
+
+var b uint16 = 300
+",
+    },
+    Explanation {
+        code: "E1102",
+        title: "constant x truncated to integer",
+        check: Some("constants"),
+        description: "A constant with a fractional part is used as an integer type. Go \
+            doesn't round constants, so write a whole number or use a floating point type.",
+        example: "package main
+
+var i int = 1.5
+",
+        fixed: "package main
+
+var f float64 = 1.5
+",
+    },
+    Explanation {
+        code: "E1103",
+        title: "cannot use iota outside constant declaration",
+        check: Some("constants"),
+        description: "iota is the index of a spec in a constant declaration, counting from 0, \
+            so it has no value anywhere else.",
+        example: "package main
+
+var first = iota
+",
+        fixed: "package main
+
+const first = iota
+",
+    },
+    Explanation {
+        code: "E1201",
+        title: "declaration of x shadows declaration at line n",
+        check: Some("shadowing"),
+        description: "A variable is declared in a block with the name of one outside it, \
+            which is used after the block, so what's assigned inside it is lost. It's legal Go, \
+            so the check is off unless it's turned on.",
+        example: "package main
+
+func parse(s string) (int, bool) {
+\treturn len(s), true
+}
+
+func main() {
+\tn, ok := parse(\"42\")
+\tif n > 0 {
+\t\tn, ok := parse(\"7\")
+\t\t_, _ = n, ok
+\t}
+\t_ = ok
+}
+",
+        fixed: "package main
+
+func parse(s string) (int, bool) {
+\treturn len(s), true
+}
+
+func main() {
+\tn, ok := parse(\"42\")
+\tif n > 0 {
+\t\tn, ok = parse(\"7\")
+\t\t_, _ = n, ok
+\t}
+\t_ = ok
+}
+",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::token::Token;
+    use crate::parser::parser::Parser;
+    use crate::primitives::diagnostic::Diagnostic;
+    use crate::primitives::errors::{
+        lexer::{LexerError, LexerErrorKind},
+        parser::ParserErrorKind,
+    };
+    use crate::primitives::position::Position;
+    use crate::semantic::checker::check_with_config;
+    use crate::semantic::config::{Check, CheckerConfig, Level};
+
+    /// The codes of what's reported in `source`, with every check on
+    fn codes(source: &str) -> Vec<&'static str> {
+        let mut parser = Parser::new(source);
+        let diagnostics: Vec<Diagnostic> = match parser.parse() {
+            Ok(file) if parser.errors.is_empty() => {
+                let config = CheckerConfig::default().with(Check::Shadowing, Level::Warning);
+                check_with_config(&file, &config).1
+            }
+            Ok(_) => parser.errors.iter().map(Diagnostic::from).collect(),
+            Err(errors) => errors.iter().map(Diagnostic::from).collect(),
+        };
+        diagnostics
+            .iter()
+            .filter_map(|diagnostic| diagnostic.explanation)
+            .collect()
+    }
+
+    #[test]
+    fn examples_are_reported() {
+        for explanation in EXPLANATIONS {
+            let code = explanation.code;
+            assert!(codes(explanation.example).contains(&code), "{}", code);
+            assert_eq!(codes(explanation.fixed), Vec::<&str>::new(), "{}", code);
+        }
+    }
+
+    /// One of each kind of syntax error, which the match keeps complete
+    fn parser_error_kinds() -> Vec<ParserErrorKind> {
+        let position = Position::new(1, 0, 1);
+        let kinds = vec![
+            ParserErrorKind::LexerError(LexerError::new(
+                LexerErrorKind::UnterminatedString("\"a".to_string()),
+                position,
+            )),
+            ParserErrorKind::Expected {
+                expected: Vec::new(),
+                found: Token::new_before_start(),
+                context: None,
+            },
+            ParserErrorKind::NotAPrimaryExpression(")".to_string()),
+            ParserErrorKind::NotAType("1".to_string()),
+            ParserErrorKind::ExpressionNotUsedAsStatement,
+            ParserErrorKind::BlankIdentifierAsValue,
+            ParserErrorKind::NonNameOnLeftOfDefine,
+            ParserErrorKind::TooManyRangeVariables,
+            ParserErrorKind::MixedNamedAndUnnamedParameters,
+            ParserErrorKind::ReceiverCount(2),
+            ParserErrorKind::MissingSliceIndex("Final"),
+            ParserErrorKind::NotACall("defer"),
+            ParserErrorKind::NotACommunication,
+            ParserErrorKind::MultipleDefaults("switch"),
+            ParserErrorKind::MissingConstantValue,
+            ParserErrorKind::DefineInPostStatement,
+            ParserErrorKind::MisplacedFallthrough,
+            ParserErrorKind::FallthroughInFinalCase,
+            ParserErrorKind::MissingPackageClause,
+            ParserErrorKind::ImportAfterDeclaration,
+            ParserErrorKind::NonDeclarationStatementOutsideFunction,
+            ParserErrorKind::NestingTooDeep,
+        ];
+        for kind in &kinds {
+            match kind {
+                ParserErrorKind::LexerError(_)
+                | ParserErrorKind::Expected { .. }
+                | ParserErrorKind::NotAPrimaryExpression(_)
+                | ParserErrorKind::NotAType(_)
+                | ParserErrorKind::ExpressionNotUsedAsStatement
+                | ParserErrorKind::BlankIdentifierAsValue
+                | ParserErrorKind::NonNameOnLeftOfDefine
+                | ParserErrorKind::TooManyRangeVariables
+                | ParserErrorKind::MixedNamedAndUnnamedParameters
+                | ParserErrorKind::ReceiverCount(_)
+                | ParserErrorKind::MissingSliceIndex(_)
+                | ParserErrorKind::NotACall(_)
+                | ParserErrorKind::NotACommunication
+                | ParserErrorKind::MultipleDefaults(_)
+                | ParserErrorKind::MissingConstantValue
+                | ParserErrorKind::DefineInPostStatement
+                | ParserErrorKind::MisplacedFallthrough
+                | ParserErrorKind::FallthroughInFinalCase
+                | ParserErrorKind::MissingPackageClause
+                | ParserErrorKind::ImportAfterDeclaration
+                | ParserErrorKind::NonDeclarationStatementOutsideFunction
+                | ParserErrorKind::NestingTooDeep => {}
+            }
+        }
+        kinds
+    }
+
+    #[test]
+    fn syntax_errors_are_explained() {
+        let codes: Vec<&str> = parser_error_kinds()
+            .iter()
+            .map(|kind| kind.code())
+            .collect();
+        for code in &codes {
+            assert!(
+                explain(code).is_some_and(|explanation| explanation.check.is_none()),
+                "{}",
+                code
+            );
+        }
+        let distinct: std::collections::HashSet<&&str> = codes.iter().collect();
+        assert_eq!(distinct.len(), codes.len(), "{:?}", codes);
+    }
+
+    #[test]
+    fn explain_a_code() {
+        assert_eq!(
+            explain("e0503").map(|explanation| explanation.code),
+            Some("E0503")
+        );
+        assert_eq!(explain("E9999"), None);
+        assert!(
+            explain("E0101")
+                .unwrap()
+                .to_text()
+                .starts_with("E0101: undefined: x\n\nA name is used")
+        );
+    }
+}
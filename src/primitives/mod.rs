@@ -1,2 +1,5 @@
-pub mod position;
+pub mod diagnostic;
 pub mod errors;
+pub mod explanations;
+pub mod position;
+pub mod suggestion;
@@ -1,4 +1,5 @@
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub line: usize,
     pub column_start: usize,
@@ -21,4 +22,9 @@ impl Position {
             column_end: column,
         }
     }
+
+    /// Move the position down (or up, for a negative `delta`) by `delta` lines
+    pub fn shift_lines(&mut self, delta: isize) {
+        self.line = self.line.saturating_add_signed(delta);
+    }
 }
@@ -0,0 +1,69 @@
+/// The candidate `name` was most likely meant to be, for a "did you mean" style hint. A
+/// candidate differing only in case is preferred, then the closest one within a third of the
+/// name's length in edits, so short names only match by case.
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let candidates: Vec<&str> = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .collect();
+    if let Some(candidate) = candidates
+        .iter()
+        .find(|candidate| candidate.eq_ignore_ascii_case(name))
+    {
+        return Some(candidate);
+    }
+    let most = name.chars().count() / 3;
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= most)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of characters inserted, deleted, substituted or swapped with their neighbour
+/// to turn `a` into `b` (the optimal string alignment distance)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i characters of a and j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closest_matches() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("Nmae", "Name"), 1);
+        assert_eq!(closest_match("name", ["Name", "nome"]), Some("Name"));
+        assert_eq!(closest_match("lenght", ["width", "length"]), Some("length"));
+        assert_eq!(closest_match("x", ["y", "X"]), Some("X"));
+        assert_eq!(closest_match("x", ["y", "count"]), None);
+        assert_eq!(closest_match("count", ["total", "size"]), None);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod rename;
+pub mod rewriter;
@@ -0,0 +1,299 @@
+//! Renaming a name everywhere a program refers to it, across its files
+
+use std::path::{Path, PathBuf};
+
+use crate::eval::loader::{Program, Source};
+use crate::parser::parser::Parser;
+use crate::primitives::{
+    errors::rewrite::{RewriteError, RewriteErrorKind},
+    position::Position,
+};
+use crate::rewrite::rewriter::{Edit, Rewriter, is_identifier};
+use crate::semantic::{
+    navigation::{name_span, references, symbol_named_at},
+    resolver::resolve,
+    scope::{ScopeId, SymbolKind, SymbolTable, is_exported},
+};
+
+/// The edits a rename makes to one of the program's files
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEdits {
+    pub path: PathBuf,
+    /// Each in the order it's in the file, none overlapping another
+    pub edits: Vec<Edit>,
+}
+
+impl FileEdits {
+    /// `source`, the file's text, with the edits made
+    pub fn apply(&self, source: &str) -> String {
+        let mut result = String::with_capacity(source.len());
+        let mut copied = 0;
+        for edit in &self.edits {
+            result.push_str(&source[copied..edit.start]);
+            result.push_str(&edit.text);
+            copied = edit.end;
+        }
+        result.push_str(&source[copied..]);
+        result
+    }
+}
+
+/// Rename the variable, constant, type, function or import whose name `position` of the file
+/// at `path` is in to `name`, in each of `program`'s files that refers to it, in the order
+/// they were loaded. Only the program's files are renamed in, so a package's exported name
+/// isn't renamed in the packages that import it unless they're the program's. An import
+/// without a name is given one, in its file alone. Fields and methods, which are reached
+/// through a value, aren't renamed.
+///
+/// The rename fails if `name` isn't an identifier, if it's declared where the renamed name
+/// is, or if the renamed name would refer to another where it's used, or hide another
+/// where that one's used.
+pub fn rename(
+    program: &Program,
+    path: &Path,
+    position: Position,
+    name: &str,
+) -> Result<Vec<FileEdits>, RewriteError> {
+    let error = |kind| RewriteError::new(kind, position);
+    if !is_identifier(name) {
+        return Err(error(RewriteErrorKind::InvalidName(name.to_string())));
+    }
+    let source = program
+        .sources
+        .iter()
+        .find(|source| source.path == path)
+        .ok_or_else(|| error(RewriteErrorKind::OutOfRange))?;
+    let line = source
+        .line(position.line)
+        .ok_or_else(|| error(RewriteErrorKind::OutOfRange))?;
+    // The loader names the members of the module's packages for them, so their imports are
+    // left unused in the program, and an import is renamed in its file on its own
+    if let Some(renamed) = rename_import(source, line, position, name)? {
+        return Ok(vec![renamed]);
+    }
+    let mut at = position;
+    at.shift_lines(source.first_line as isize - 1);
+
+    let (symbols, _) = resolve(&program.file);
+    let symbol =
+        symbol_named_at(&symbols, line, at).ok_or_else(|| error(RewriteErrorKind::NotAName))?;
+    let declared = symbols.symbol(symbol);
+    let Some(declaration) = declared.position else {
+        return Err(error(RewriteErrorKind::Predeclared(declared.name.clone())));
+    };
+    // A package's members are named for it, like `shapes.New`, unless it's the main package
+    let qualified = match declared.name.rsplit_once('.') {
+        Some((package, _)) => format!("{}.{}", package, name),
+        None => name.to_string(),
+    };
+    let package = |reference: Position| {
+        Source::locate(&program.sources, reference).and_then(|(source, _)| source.path.parent())
+    };
+    let references = references(&symbols, symbol);
+    for reference in &references {
+        let scope = symbols
+            .scope_at(*reference)
+            .ok_or_else(|| error(RewriteErrorKind::NotAName))?;
+        // Another package refers to it with its package's name before it, which it can't
+        // once it's unexported
+        let mut names = vec![qualified.as_str()];
+        match package(*reference) == package(declaration) {
+            true => names.push(name),
+            false if !is_exported(name) => {
+                return Err(error(RewriteErrorKind::Unexported(name.to_string())));
+            }
+            false => {}
+        }
+        for candidate in names {
+            // Only a name declared around the renamed one can be hidden by it
+            if let Some(other) = symbols.lookup(scope, candidate)
+                && other != symbol
+                && !outside(&symbols, symbols.symbol(other).scope, declared.scope)
+            {
+                return Err(error(RewriteErrorKind::NameTaken(name.to_string())));
+            }
+        }
+    }
+    // Names declared around the renamed one, which it would hide where they're used
+    for (used, other) in symbols.uses() {
+        let hidden = symbols.symbol(other);
+        if other != symbol
+            && (hidden.name == name || hidden.name == qualified)
+            && outside(&symbols, hidden.scope, declared.scope)
+            && symbols
+                .scope_at(used)
+                .is_some_and(|scope| encloses(&symbols, declared.scope, scope))
+        {
+            return Err(error(RewriteErrorKind::Hides(name.to_string())));
+        }
+    }
+
+    let mut files: Vec<FileEdits> = Vec::new();
+    for reference in references {
+        let Some((source, reference)) = Source::locate(&program.sources, reference) else {
+            continue;
+        };
+        let line = source.line(reference.line).unwrap_or("");
+        let span = name_span(line, reference, &declared.name);
+        let start = line_offset(&source.text, span.line) + span.column_start;
+        let edit = Edit {
+            start,
+            end: start + (span.column_end - span.column_start),
+            text: name.to_string(),
+        };
+        match files.iter_mut().find(|file| file.path == source.path) {
+            Some(file) => file.edits.push(edit),
+            None => files.push(FileEdits {
+                path: source.path.clone(),
+                edits: vec![edit],
+            }),
+        }
+    }
+    for file in &mut files {
+        file.edits.sort_by_key(|edit| edit.start);
+    }
+    Ok(files)
+}
+
+/// Rename the import whose name `position` of `source`, on `line`, is in, as the rewriter
+/// renames it, or None if it's not in an import's name
+fn rename_import(
+    source: &Source,
+    line: &str,
+    position: Position,
+    name: &str,
+) -> Result<Option<FileEdits>, RewriteError> {
+    let Ok(file) = Parser::new(&source.text).parse() else {
+        return Ok(None);
+    };
+    let (symbols, _) = resolve(&file);
+    let declared = symbol_named_at(&symbols, line, position)
+        .map(|symbol| symbols.symbol(symbol))
+        .filter(|symbol| symbol.kind == SymbolKind::Package)
+        .and_then(|symbol| symbol.position);
+    let Some(declared) = declared else {
+        return Ok(None);
+    };
+    let mut rewriter = Rewriter::new(&source.text, &file);
+    rewriter.rename(declared, name)?;
+    let mut edits = rewriter.edits().to_vec();
+    edits.sort_by_key(|edit| edit.start);
+    Ok(Some(FileEdits {
+        path: source.path.clone(),
+        edits,
+    }))
+}
+
+/// Whether `outer` is a scope around `inner`, and not `inner` itself
+fn outside(symbols: &SymbolTable, outer: ScopeId, inner: ScopeId) -> bool {
+    outer != inner && encloses(symbols, outer, inner)
+}
+
+/// Whether `inner` is `outer` or a scope in it
+fn encloses(symbols: &SymbolTable, outer: ScopeId, inner: ScopeId) -> bool {
+    let mut scope = Some(inner);
+    while let Some(id) = scope {
+        if id == outer {
+            return true;
+        }
+        scope = symbols.scope(id).parent;
+    }
+    false
+}
+
+/// The offset of the start of the 1-based line `line` of `text`
+fn line_offset(text: &str, line: usize) -> usize {
+    match line.checked_sub(2) {
+        Some(newline) => text
+            .match_indices('\n')
+            .nth(newline)
+            .map_or(text.len(), |(offset, _)| offset + 1),
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::loader::load;
+
+    fn module(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/testfiles/module")
+            .join(path)
+    }
+
+    /// Rename the name at `line` and `column` of the test module's file `path`, in the program
+    /// of its package, returning each file's name and what it's renamed to
+    fn renamed(
+        path: &str,
+        line: usize,
+        column: usize,
+        name: &str,
+    ) -> Result<Vec<(String, String)>, RewriteErrorKind> {
+        let path = module(path);
+        let program = load(path.parent().unwrap()).unwrap();
+        let position = Position::new_single_position(line, column);
+        let files = rename(&program, &path, position, name).map_err(|error| error.kind)?;
+        Ok(files
+            .iter()
+            .map(|file| {
+                let source = std::fs::read_to_string(&file.path).unwrap();
+                let name = file.path.file_name().unwrap().to_string_lossy();
+                (name.to_string(), file.apply(&source))
+            })
+            .collect())
+    }
+
+    #[test]
+    fn rename_across_files() {
+        // A function declared in one file of the package and called in another
+        let files = renamed("cmd/app/main.go", 12, 41, "welcome").unwrap();
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["greet.go", "main.go"]);
+        assert!(files[0].1.contains("func welcome(name string) string {"));
+        assert!(files[1].1.contains("shapes.Count, welcome(\"gor\"))"));
+        // A member of another package, from where it's used and where it's declared
+        let files = renamed("cmd/app/main.go", 11, 14, "Make").unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0].1.contains("func Make(w, h int) Rect {"));
+        assert!(files[1].1.contains("r := shapes.Make(2, 3)"));
+        let files = renamed("shapes/shapes.go", 15, 1, "Total").unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].1.contains("var Total = 0"));
+        assert!(files[0].1.contains("Total = Total + 1"));
+        // An import, in its file alone
+        let files = renamed("cmd/app/main.go", 14, 23, "tu").unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].1.contains("\ttu \"example.com/app/textutil\""));
+        assert!(files[0].1.contains("tu.Upper(\"done\")"));
+    }
+
+    #[test]
+    fn rename_errors() {
+        let error =
+            |line, column, name| renamed("cmd/app/main.go", line, column, name).unwrap_err();
+        assert_eq!(
+            error(11, 1, "s"),
+            RewriteErrorKind::NameTaken("s".to_string())
+        );
+        assert_eq!(
+            error(11, 1, "fmt"),
+            RewriteErrorKind::Hides("fmt".to_string())
+        );
+        assert_eq!(
+            error(11, 1, "range"),
+            RewriteErrorKind::InvalidName("range".to_string())
+        );
+        assert_eq!(
+            error(17, 14, "size"),
+            RewriteErrorKind::Predeclared("len".to_string())
+        );
+        assert_eq!(
+            error(11, 14, "make"),
+            RewriteErrorKind::Unexported("make".to_string())
+        );
+        // Methods and fields aren't renamed
+        assert_eq!(error(12, 15, "Size"), RewriteErrorKind::NotAName);
+    }
+}
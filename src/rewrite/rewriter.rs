@@ -0,0 +1,596 @@
+use crate::ast::{
+    ast::File, declaration::DeclarationKind, expression::Expression, statement::Statement,
+};
+use crate::format::formatter::{format_expression, format_statement, trivia};
+use crate::lexer::{lexer::Lexer, token_type::TokenKind};
+use crate::primitives::{
+    errors::rewrite::{RewriteError, RewriteErrorKind},
+    position::Position,
+};
+use crate::semantic::{resolver::resolve, scope::SymbolKind};
+
+/// A replacement of the source's bytes from `start` up to `end` with `text`. An insertion has
+/// `start` equal to `end`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Changes to a parsed file made as edits of its source, so everything a change doesn't touch,
+/// comments and formatting included, is kept as it was. The nodes edited are those of the
+/// tree `file` parsed from `source`; what replaces them is printed as gofmt would print it.
+pub struct Rewriter<'a> {
+    source: &'a str,
+    file: &'a File,
+    /// The offset each line starts at
+    line_starts: Vec<usize>,
+    edits: Vec<Edit>,
+}
+
+impl<'a> Rewriter<'a> {
+    pub fn new(source: &'a str, file: &'a File) -> Rewriter<'a> {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Rewriter {
+            source,
+            file,
+            line_starts,
+            edits: Vec::new(),
+        }
+    }
+
+    /// The edits made so far, in the order they were made
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Replace the source from the start of `start` to the end of `end`, as a node's positions
+    /// span it, with `text`
+    pub fn replace(
+        &mut self,
+        start: Position,
+        end: Position,
+        text: &str,
+    ) -> Result<(), RewriteError> {
+        let start_offset = self.offset(start.line, start.column_start, start)?;
+        let end_offset = self.offset(end.line, end.column_end, end)?;
+        self.edit(start_offset, end_offset, text);
+        Ok(())
+    }
+
+    /// Insert `text` before `position`
+    pub fn insert(&mut self, position: Position, text: &str) -> Result<(), RewriteError> {
+        let offset = self.offset(position.line, position.column_start, position)?;
+        self.edit(offset, offset, text);
+        Ok(())
+    }
+
+    pub fn replace_expression(
+        &mut self,
+        target: &Expression,
+        replacement: &Expression,
+    ) -> Result<(), RewriteError> {
+        let text = format_expression(replacement);
+        self.replace(target.position_start, target.position_end, &text)
+    }
+
+    pub fn replace_statement(
+        &mut self,
+        target: &Statement,
+        replacement: &Statement,
+    ) -> Result<(), RewriteError> {
+        let indent = self.indentation(target.position_start)?;
+        let text = format_statement(replacement, indent.len());
+        self.replace(target.position_start, target.position_end, &text)
+    }
+
+    /// Insert `statement` on a line of its own before `target`, indented as `target` is
+    pub fn insert_statement_before(
+        &mut self,
+        target: &Statement,
+        statement: &Statement,
+    ) -> Result<(), RewriteError> {
+        let indent = self.indentation(target.position_start)?;
+        let text = format!("{}\n{}", format_statement(statement, indent.len()), indent);
+        self.insert(target.position_start, &text)
+    }
+
+    /// Insert `statement` on a line of its own after `target`, and any comment after it
+    pub fn insert_statement_after(
+        &mut self,
+        target: &Statement,
+        statement: &Statement,
+    ) -> Result<(), RewriteError> {
+        let indent = self.indentation(target.position_start)?;
+        let end = self.line_end(target.position_end.line, target.position_end)?;
+        let text = format!("\n{}{}", indent, format_statement(statement, indent.len()));
+        self.edit(end, end, &text);
+        Ok(())
+    }
+
+    /// Remove `target`, with the lines it's on if nothing else but a comment is on them
+    pub fn remove_statement(&mut self, target: &Statement) -> Result<(), RewriteError> {
+        let (start, end) = (target.position_start, target.position_end);
+        let start_offset = self.offset(start.line, start.column_start, start)?;
+        let end_offset = self.offset(end.line, end.column_end, end)?;
+        let line_start = self.offset(start.line, 0, start)?;
+        let line_end = self.line_end(end.line, end)?;
+        let before = &self.source[line_start..start_offset];
+        let after = self.source[end_offset..line_end].trim_start();
+        let alone = before.trim().is_empty() && (after.is_empty() || after.starts_with("//"));
+        match alone {
+            true => self.edit(line_start, (line_end + 1).min(self.source.len()), ""),
+            false => self.edit(start_offset, end_offset, ""),
+        }
+        Ok(())
+    }
+
+    /// Rename the variable, constant, type, function or import declared or used at `position`
+    /// to `name`, everywhere the resolver finds it's referred to. An import without a name is
+    /// given one. Fields and methods, which are reached through a value, aren't renamed.
+    pub fn rename(&mut self, position: Position, name: &str) -> Result<(), RewriteError> {
+        if !is_identifier(name) {
+            return Err(RewriteError::new(
+                RewriteErrorKind::InvalidName(name.to_string()),
+                position,
+            ));
+        }
+        let (symbols, _) = resolve(self.file);
+        let symbol = symbols
+            .def_at(position)
+            .or_else(|| symbols.use_at(position))
+            .or_else(|| symbols.def_at(self.function_at(position)?))
+            .ok_or_else(|| RewriteError::new(RewriteErrorKind::NotAName, position))?;
+        let declared = symbols.symbol(symbol);
+        if symbols.lookup_local(declared.scope, name).is_some() {
+            return Err(RewriteError::new(
+                RewriteErrorKind::NameTaken(name.to_string()),
+                position,
+            ));
+        }
+
+        for use_position in symbols.uses_of(symbol).collect::<Vec<_>>() {
+            self.replace(use_position, use_position, name)?;
+        }
+        let Some(definition) = declared.position else {
+            return Ok(());
+        };
+        match declared.kind {
+            SymbolKind::Function => {
+                let name_position = self.file.decls.iter().find_map(|declaration| {
+                    let found = declaration.position_start == definition;
+                    found.then(|| self.function_name(declaration.position_start))?
+                });
+                if let Some(name_position) = name_position {
+                    self.replace(name_position, name_position, name)?;
+                }
+            }
+            SymbolKind::Package => {
+                let import = self
+                    .file
+                    .imports
+                    .iter()
+                    .find(|import| import.position_start == definition);
+                match import {
+                    Some(import) if import.name.is_some() => {
+                        self.replace(definition, definition, name)?;
+                    }
+                    Some(import) => self.insert(import.position_start, &format!("{} ", name))?,
+                    None => {}
+                }
+            }
+            _ => self.replace(definition, definition, name)?,
+        }
+        Ok(())
+    }
+
+    /// Import the package at `path`, like `encoding/json`, as `name` if it's given one. It's
+    /// added to the first parenthesized import declaration in sorted order, or an import on
+    /// its own is made one, or a declaration is added after the package clause. Nothing
+    /// changes if the file already imports it so.
+    pub fn add_import(&mut self, path: &str, name: Option<&str>) -> Result<(), RewriteError> {
+        let imported = self
+            .file
+            .imports
+            .iter()
+            .any(|import| import.unquoted_path() == path && import.name.as_deref() == name);
+        if imported {
+            return Ok(());
+        }
+        let spec = match name {
+            Some(name) => format!("{} \"{}\"", name, path),
+            None => format!("\"{}\"", path),
+        };
+        let trivia = trivia(self.source);
+        let parenthesized = trivia
+            .imports
+            .iter()
+            .find(|declaration| declaration.parenthesized);
+
+        if let Some(declaration) = parenthesized {
+            let close = declaration.close.unwrap_or(declaration.keyword);
+            let next = self.file.imports.iter().find(|import| {
+                precedes(declaration.keyword, import.position_start)
+                    && precedes(import.position_start, close)
+                    && import.unquoted_path() > path
+            });
+            return match next {
+                Some(import) => {
+                    let indent = self.indentation(import.position_start)?;
+                    let line_start =
+                        self.offset(import.position_start.line, 0, import.position_start)?;
+                    self.edit(line_start, line_start, &format!("{}{}\n", indent, spec));
+                    Ok(())
+                }
+                None => {
+                    let line_start = self.offset(close.line, 0, close)?;
+                    self.edit(line_start, line_start, &format!("\t{}\n", spec));
+                    Ok(())
+                }
+            };
+        }
+
+        if let Some(declaration) = trivia.imports.last()
+            && let Some(import) = self
+                .file
+                .imports
+                .iter()
+                .rfind(|import| precedes(declaration.keyword, import.position_start))
+        {
+            let mut specs = [import.to_string(), spec];
+            specs.sort_by_key(|spec| spec.rsplit(' ').next().unwrap_or_default().to_string());
+            let text = format!("import (\n\t{}\n\t{}\n)", specs[0], specs[1]);
+            return self.replace(declaration.keyword, import.position_end, &text);
+        }
+
+        let package = Position::new(trivia.package_line, 0, 0);
+        let end = self.line_end(trivia.package_line, package)?;
+        self.edit(end, end, &format!("\n\nimport {}", spec));
+        Ok(())
+    }
+
+    /// Remove the import of the package at `path`, with its declaration if it's the only one
+    /// in it
+    pub fn remove_import(&mut self, path: &str) -> Result<(), RewriteError> {
+        let import = self
+            .file
+            .imports
+            .iter()
+            .find(|import| import.unquoted_path() == path)
+            .ok_or_else(|| {
+                RewriteError::new(
+                    RewriteErrorKind::NoSuchImport(path.to_string()),
+                    Position::new(1, 0, 0),
+                )
+            })?;
+        let trivia = trivia(self.source);
+        let declaration = trivia
+            .imports
+            .iter()
+            .rfind(|declaration| precedes(declaration.keyword, import.position_start));
+        let alone = declaration.is_some_and(|declaration| {
+            let close = declaration.close;
+            self.file
+                .imports
+                .iter()
+                .filter(|other| {
+                    precedes(declaration.keyword, other.position_start)
+                        && close.is_none_or(|close| precedes(other.position_start, close))
+                        && (close.is_some()
+                            || other.position_start.line == declaration.keyword.line)
+                })
+                .count()
+                == 1
+        });
+        let (first, last) = match declaration {
+            Some(declaration) if alone => (
+                declaration.keyword,
+                declaration.close.unwrap_or(import.position_end),
+            ),
+            _ => (import.position_start, import.position_end),
+        };
+        let start = self.offset(first.line, 0, first)?;
+        let mut end = (self.line_end(last.line, last)? + 1).min(self.source.len());
+        // A declaration between blank lines takes one of them with it
+        let blank = |line: usize| {
+            self.line_starts.get(line).is_some_and(|start| {
+                let end = self.line_end(line + 1, last).unwrap_or(*start);
+                self.source[*start..end].trim().is_empty()
+            })
+        };
+        if alone && first.line >= 2 && blank(first.line - 2) && blank(last.line) {
+            end = self.line_end(last.line + 1, last)? + 1;
+        }
+        self.edit(start, end.min(self.source.len()), "");
+        Ok(())
+    }
+
+    /// The source with the edits made, or an error if two of them change the same text.
+    /// Insertions at one place are made in the order they were asked for.
+    pub fn finish(self) -> Result<String, RewriteError> {
+        let mut edits = self.edits.clone();
+        edits.sort_by_key(|edit| (edit.start, edit.end));
+        let mut result = String::with_capacity(self.source.len());
+        let mut copied = 0;
+        for edit in &edits {
+            if edit.start < copied {
+                return Err(RewriteError::new(
+                    RewriteErrorKind::OverlappingEdits,
+                    self.position(edit.start),
+                ));
+            }
+            result.push_str(&self.source[copied..edit.start]);
+            result.push_str(&edit.text);
+            copied = edit.end;
+        }
+        result.push_str(&self.source[copied..]);
+        Ok(result)
+    }
+
+    fn edit(&mut self, start: usize, end: usize, text: &str) {
+        self.edits.push(Edit {
+            start,
+            end,
+            text: text.to_string(),
+        });
+    }
+
+    /// The offset of `column` on `line`, or an error at `position` if it's not in the source
+    fn offset(
+        &self,
+        line: usize,
+        column: usize,
+        position: Position,
+    ) -> Result<usize, RewriteError> {
+        let out_of_range = || RewriteError::new(RewriteErrorKind::OutOfRange, position);
+        let start = *line
+            .checked_sub(1)
+            .and_then(|index| self.line_starts.get(index))
+            .ok_or_else(out_of_range)?;
+        let offset = start + column;
+        match offset <= self.line_end(line, position)? {
+            true => Ok(offset),
+            false => Err(out_of_range()),
+        }
+    }
+
+    /// The offset of the end of `line`, before its line break
+    fn line_end(&self, line: usize, position: Position) -> Result<usize, RewriteError> {
+        let next = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |start| start - 1);
+        match line >= 1 && line <= self.line_starts.len() {
+            true => Ok(next),
+            false => Err(RewriteError::new(RewriteErrorKind::OutOfRange, position)),
+        }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let column = offset - self.line_starts[line - 1];
+        Position::new(line, column, column)
+    }
+
+    /// The tabs and spaces `position`'s line starts with
+    fn indentation(&self, position: Position) -> Result<&'a str, RewriteError> {
+        let start = self.offset(position.line, 0, position)?;
+        let end = self.line_end(position.line, position)?;
+        let line = &self.source[start..end];
+        Ok(&line[..line.len() - line.trim_start().len()])
+    }
+
+    /// The position of the declaration of the function named at `position`, which is where
+    /// the resolver has it declared
+    fn function_at(&self, position: Position) -> Option<Position> {
+        self.file.decls.iter().find_map(|declaration| {
+            let DeclarationKind::Function { receiver: None, .. } = declaration.kind else {
+                return None;
+            };
+            let name = self.function_name(declaration.position_start)?;
+            (name == position).then_some(declaration.position_start)
+        })
+    }
+
+    /// The position of the name of the function declared at `declaration`, the identifier
+    /// after `func`
+    fn function_name(&self, declaration: Position) -> Option<Position> {
+        let start = self
+            .offset(declaration.line, declaration.column_start, declaration)
+            .ok()?;
+        let mut lexer = Lexer::new(&self.source[start..]);
+        loop {
+            let token = lexer.next_token();
+            match token.kind {
+                Some(TokenKind::Identifier) => {
+                    let mut position = token.position;
+                    position.line += declaration.line - 1;
+                    if token.position.line == 1 {
+                        position.column_start += declaration.column_start;
+                        position.column_end += declaration.column_start;
+                    }
+                    return Some(position);
+                }
+                Some(TokenKind::EOF) | None | Some(TokenKind::LeftParen) => return None,
+                _ => {}
+            }
+        }
+    }
+}
+
+fn precedes(a: Position, b: Position) -> bool {
+    (a.line, a.column_start) < (b.line, b.column_start)
+}
+
+/// Whether `name` lexes as a single identifier, which keywords don't
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut lexer = Lexer::new(name);
+    let token = lexer.next_token();
+    token.kind == Some(TokenKind::Identifier)
+        && token.value == name
+        && lexer.next_token().kind == Some(TokenKind::EOF)
+}
+
+impl std::fmt::Display for Edit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}: {:?}", self.start, self.end, self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{expression::ExpressionKind, statement::StatementKind};
+    use crate::parser::parser::Parser;
+
+    fn rewrite(source: &str, change: impl FnOnce(&mut Rewriter, &File)) -> String {
+        let file = Parser::new(source).parse().unwrap();
+        let mut rewriter = Rewriter::new(source, &file);
+        change(&mut rewriter, &file);
+        rewriter.finish().unwrap()
+    }
+
+    fn body(file: &File, index: usize) -> &Vec<Statement> {
+        match &file.decls[index].kind {
+            DeclarationKind::Function { body, .. } => body,
+            _ => panic!("not a function"),
+        }
+    }
+
+    #[test]
+    fn rename_keeps_comments() {
+        let source = "package main\n\nimport \"fmt\"\n\n// add sums\nfunc add(a int, b int) int {\n\treturn a + b /* sum */\n}\n\nfunc main() {\n\tx := add(1, 2) // three\n\tfmt.Println(x)\n}\n";
+        let renamed = rewrite(source, |rewriter, _| {
+            rewriter.rename(Position::new(6, 9, 10), "left").unwrap();
+            rewriter.rename(Position::new(6, 5, 8), "sum").unwrap();
+            rewriter.rename(Position::new(12, 1, 4), "format").unwrap();
+        });
+        assert_eq!(
+            renamed,
+            "package main\n\nimport format \"fmt\"\n\n// add sums\nfunc sum(left int, b int) int {\n\treturn left + b /* sum */\n}\n\nfunc main() {\n\tx := sum(1, 2) // three\n\tformat.Println(x)\n}\n"
+        );
+    }
+
+    #[test]
+    fn rename_errors() {
+        let source = "package main\n\nfunc main() {\n\tx := 1\n\ty := 2\n\tprintln(x, y)\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let mut rewriter = Rewriter::new(source, &file);
+        let x = Position::new(4, 1, 2);
+        let kind = |result: Result<(), RewriteError>| result.unwrap_err().kind;
+        assert_eq!(
+            kind(rewriter.rename(x, "y")),
+            RewriteErrorKind::NameTaken("y".to_string())
+        );
+        assert_eq!(
+            kind(rewriter.rename(x, "for")),
+            RewriteErrorKind::InvalidName("for".to_string())
+        );
+        assert_eq!(
+            kind(rewriter.rename(Position::new(4, 3, 5), "z")),
+            RewriteErrorKind::NotAName
+        );
+        assert!(rewriter.edits().is_empty());
+    }
+
+    #[test]
+    fn add_imports() {
+        let grouped = "package main\n\nimport (\n\t\"fmt\" // print\n\t\"strings\"\n)\n";
+        assert_eq!(
+            rewrite(grouped, |rewriter, _| {
+                rewriter.add_import("os", None).unwrap();
+                rewriter.add_import("unicode", Some("u")).unwrap();
+                rewriter.add_import("fmt", None).unwrap();
+            }),
+            "package main\n\nimport (\n\t\"fmt\" // print\n\t\"os\"\n\t\"strings\"\n\tu \"unicode\"\n)\n"
+        );
+        assert_eq!(
+            rewrite("package main\n\nimport \"os\"\n", |rewriter, _| {
+                rewriter.add_import("fmt", None).unwrap();
+            }),
+            "package main\n\nimport (\n\t\"fmt\"\n\t\"os\"\n)\n"
+        );
+        assert_eq!(
+            rewrite(
+                "// Package main runs\npackage main\n\nfunc main() {}\n",
+                |rewriter, _| {
+                    rewriter.add_import("fmt", None).unwrap();
+                }
+            ),
+            "// Package main runs\npackage main\n\nimport \"fmt\"\n\nfunc main() {}\n"
+        );
+    }
+
+    #[test]
+    fn remove_imports() {
+        let source = "package main\n\nimport \"os\"\n\nimport (\n\t\"fmt\"\n\t\"strings\" // upper\n)\n\nfunc main() {}\n";
+        assert_eq!(
+            rewrite(source, |rewriter, _| {
+                rewriter.remove_import("os").unwrap();
+                rewriter.remove_import("strings").unwrap();
+            }),
+            "package main\n\nimport (\n\t\"fmt\"\n)\n\nfunc main() {}\n"
+        );
+        let file = Parser::new(source).parse().unwrap();
+        let error = Rewriter::new(source, &file)
+            .remove_import("io")
+            .unwrap_err();
+        assert_eq!(error.kind, RewriteErrorKind::NoSuchImport("io".to_string()));
+    }
+
+    #[test]
+    fn edit_statements() {
+        let source =
+            "package main\n\nfunc main() {\n\tx := 1 + 2 // three\n\tprintln(x)\n\tprintln(0)\n}\n";
+        let rewritten = rewrite(source, |rewriter, file| {
+            let statements = body(file, 0);
+            let other =
+                Parser::new("package main\n\nfunc f() {\n\tif x > 2 {\n\t\tx = 2\n\t}\n}\n")
+                    .parse()
+                    .unwrap();
+            let clamp = &body(&other, 0)[0];
+            rewriter
+                .insert_statement_after(&statements[0], clamp)
+                .unwrap();
+            rewriter.remove_statement(&statements[2]).unwrap();
+        });
+        assert_eq!(
+            rewritten,
+            "package main\n\nfunc main() {\n\tx := 1 + 2 // three\n\tif x > 2 {\n\t\tx = 2\n\t}\n\tprintln(x)\n}\n"
+        );
+    }
+
+    #[test]
+    fn edit_expressions() {
+        let source = "package main\n\nfunc main() {\n\tprintln(1+2, 3) // sum\n}\n";
+        let file = Parser::new(source).parse().unwrap();
+        let mut rewriter = Rewriter::new(source, &file);
+        let StatementKind::Expression(call) = &body(&file, 0)[0].kind else {
+            panic!("not an expression statement");
+        };
+        let ExpressionKind::FunctionCall { arguments, .. } = &call.kind else {
+            panic!("not a call");
+        };
+        rewriter
+            .replace_expression(&arguments[0], &arguments[1])
+            .unwrap();
+        rewriter
+            .replace_expression(&arguments[1], &arguments[0])
+            .unwrap();
+        assert_eq!(
+            rewriter.finish().unwrap(),
+            "package main\n\nfunc main() {\n\tprintln(3, 1 + 2) // sum\n}\n"
+        );
+
+        let mut rewriter = Rewriter::new(source, &file);
+        rewriter.replace_expression(call, &arguments[1]).unwrap();
+        rewriter
+            .replace_expression(&arguments[0], &arguments[1])
+            .unwrap();
+        let error = rewriter.finish().unwrap_err();
+        assert_eq!(error.kind, RewriteErrorKind::OverlappingEdits);
+    }
+}
@@ -0,0 +1,105 @@
+/// The predeclared functions (https://go.dev/ref/spec#Built-in_functions). Most of them take
+/// any of several types, or a type as an argument, so their calls are checked one by one
+/// rather than against a signature.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Builtin {
+    Append,
+    Cap,
+    Clear,
+    Close,
+    Complex,
+    Copy,
+    Delete,
+    Imag,
+    Len,
+    Make,
+    Max,
+    Min,
+    New,
+    Panic,
+    Print,
+    Println,
+    Real,
+    Recover,
+}
+
+impl Builtin {
+    pub const ALL: &[Builtin] = &[
+        Builtin::Append,
+        Builtin::Cap,
+        Builtin::Clear,
+        Builtin::Close,
+        Builtin::Complex,
+        Builtin::Copy,
+        Builtin::Delete,
+        Builtin::Imag,
+        Builtin::Len,
+        Builtin::Make,
+        Builtin::Max,
+        Builtin::Min,
+        Builtin::New,
+        Builtin::Panic,
+        Builtin::Print,
+        Builtin::Println,
+        Builtin::Real,
+        Builtin::Recover,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Builtin> {
+        Builtin::ALL
+            .iter()
+            .copied()
+            .find(|builtin| builtin.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Append => "append",
+            Builtin::Cap => "cap",
+            Builtin::Clear => "clear",
+            Builtin::Close => "close",
+            Builtin::Complex => "complex",
+            Builtin::Copy => "copy",
+            Builtin::Delete => "delete",
+            Builtin::Imag => "imag",
+            Builtin::Len => "len",
+            Builtin::Make => "make",
+            Builtin::Max => "max",
+            Builtin::Min => "min",
+            Builtin::New => "new",
+            Builtin::Panic => "panic",
+            Builtin::Print => "print",
+            Builtin::Println => "println",
+            Builtin::Real => "real",
+            Builtin::Recover => "recover",
+        }
+    }
+
+    /// The fewest and most arguments a call takes, with None for no limit
+    pub fn arity(self) -> (usize, Option<usize>) {
+        match self {
+            // append(s), with nothing to append, is allowed
+            Builtin::Append => (1, None),
+            Builtin::Cap
+            | Builtin::Clear
+            | Builtin::Close
+            | Builtin::Imag
+            | Builtin::Len
+            | Builtin::New
+            | Builtin::Panic
+            | Builtin::Real => (1, Some(1)),
+            Builtin::Complex | Builtin::Copy | Builtin::Delete => (2, Some(2)),
+            // make([]T, length, capacity)
+            Builtin::Make => (1, Some(3)),
+            Builtin::Max | Builtin::Min => (1, None),
+            Builtin::Print | Builtin::Println => (0, None),
+            Builtin::Recover => (0, Some(0)),
+        }
+    }
+}
+
+impl std::fmt::Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
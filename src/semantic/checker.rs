@@ -0,0 +1,2983 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+use log::{debug, trace};
+
+use crate::ast::{
+    ast::{File, NodeId},
+    declaration::{
+        ConstantSpec, Declaration, DeclarationKind, Parameter, VariableSpec, constant_specs,
+    },
+    expression::{Expression, ExpressionKind, KeyedElement, UnaryOperator},
+    statement::{Statement, StatementKind},
+    type_expression::{ChannelDirection, TypeExpression, TypeExpressionKind},
+};
+use crate::lexer::token_type::Operator;
+use crate::primitives::{
+    diagnostic::Diagnostic,
+    errors::semantic::{ChannelOperation, SemanticError, SemanticErrorKind},
+    position::Position,
+    suggestion::closest_match,
+};
+use crate::semantic::cycles::find_cycles;
+use crate::semantic::{
+    builtins::Builtin,
+    config::{Check, CheckerConfig},
+    info::TypeInfo,
+    resolver::resolve,
+    scope::{SymbolId, SymbolKind, SymbolTable},
+    shadow::find_shadowing,
+};
+use crate::types::constant::{Constant, Unrepresentable};
+use crate::types::lookup::Selection;
+use crate::types::types::{
+    BasicKind, DeclaredMethod, Field, Method, Signature, Type, TypeId, TypeTable,
+};
+
+/// Resolve names in `file` and infer the type of every expression and variable in it. The
+/// errors of both passes are returned, in the order they were found.
+pub fn check(file: &File) -> (TypeInfo, Vec<SemanticError>) {
+    run(file, true)
+}
+
+/// Check `file` like `check`, running only the checks `config` enables and reporting what
+/// they find at the severity it gives them. Errors come first, in the order they were found,
+/// then the warnings of the opt-in analyses.
+pub fn check_with_config(file: &File, config: &CheckerConfig) -> (TypeInfo, Vec<Diagnostic>) {
+    let (info, errors) = run(file, config.is_enabled(Check::Cycles));
+    let mut diagnostics: Vec<Diagnostic> = errors
+        .iter()
+        .filter_map(|error| config.error_diagnostic(error))
+        .collect();
+    if config.is_enabled(Check::Shadowing) {
+        diagnostics.extend(
+            find_shadowing(&info.symbols)
+                .iter()
+                .filter_map(|warning| config.warning_diagnostic(warning)),
+        );
+    }
+    (info, diagnostics)
+}
+
+fn run(file: &File, cycles: bool) -> (TypeInfo, Vec<SemanticError>) {
+    let started = Instant::now();
+    let (symbols, errors) = resolve(file);
+    debug!(
+        "resolved package {} in {:?}: {} errors",
+        file.package_name,
+        started.elapsed(),
+        errors.len()
+    );
+    let started = Instant::now();
+    let mut checker = Checker::new(symbols, errors);
+    checker.check_file(file);
+    if cycles {
+        checker.errors.extend(find_cycles(file, &checker.symbols));
+    }
+    debug!(
+        "checked package {} in {:?}: {} errors in all",
+        file.package_name,
+        started.elapsed(),
+        checker.errors.len()
+    );
+    let info = TypeInfo {
+        symbols: checker.symbols,
+        types: checker.types,
+        nodes: checker.nodes,
+        symbol_types: checker.symbol_types,
+    };
+    (info, checker.errors)
+}
+
+struct Checker<'a> {
+    symbols: SymbolTable,
+    types: TypeTable,
+    nodes: HashMap<NodeId, TypeId>,
+    symbol_types: HashMap<SymbolId, TypeId>,
+    errors: Vec<SemanticError>,
+    /// Types declared in the file, by the symbol of their name
+    named_types: HashMap<SymbolId, TypeId>,
+    /// Package level variables may be used before they're declared, so their types are
+    /// inferred the first time they're needed
+    package_variables: HashMap<SymbolId, &'a VariableSpec>,
+    /// Package level constants, likewise
+    package_constants: HashMap<SymbolId, ConstantSpec<'a>>,
+    /// The values of the constants declared in the file
+    constant_values: HashMap<SymbolId, Constant>,
+    /// The value of `iota` in the constant spec being checked, if any
+    iota: Option<i64>,
+    /// Specs already checked or being checked, so a package level spec used before its
+    /// declaration isn't checked twice, and `var a = b; var b = a` doesn't recurse forever
+    checked_specs: HashSet<(Position, Position)>,
+    /// Result types of the function being checked, for its return statements
+    results: Vec<TypeId>,
+    /// Whether those results are named, allowing a bare `return`
+    named_results: bool,
+    any: TypeId,
+    error: TypeId,
+}
+
+impl<'a> Checker<'a> {
+    fn new(symbols: SymbolTable, errors: Vec<SemanticError>) -> Checker<'a> {
+        let mut types = TypeTable::new();
+        let any = types.interface(Vec::new());
+        let string = types.basic(BasicKind::String);
+        let error_method = Method {
+            name: "Error".to_string(),
+            signature: Signature {
+                parameters: Vec::new(),
+                results: vec![string],
+                variadic: false,
+            },
+        };
+        let error_interface = types.interface(vec![error_method]);
+        let error = types.new_named("error");
+        types.set_underlying(error, error_interface);
+        Checker {
+            symbols,
+            types,
+            nodes: HashMap::new(),
+            symbol_types: HashMap::new(),
+            errors,
+            named_types: HashMap::new(),
+            package_variables: HashMap::new(),
+            package_constants: HashMap::new(),
+            constant_values: HashMap::new(),
+            iota: None,
+            checked_specs: HashSet::new(),
+            results: Vec::new(),
+            named_results: false,
+            any,
+            error,
+        }
+    }
+
+    fn check_file(&mut self, file: &'a File) {
+        // Types come first, since every other declaration's type may refer to them
+        let mut definitions = Vec::new();
+        for declaration in &file.decls {
+            if let DeclarationKind::Type {
+                name,
+                type_expression,
+            } = &declaration.kind
+                && let Some(named) = self.declare_named_type(name)
+            {
+                definitions.push((named, type_expression));
+            }
+        }
+        self.define_named_types(definitions);
+
+        // Every package level name gets its type before any body is checked
+        for declaration in &file.decls {
+            match &declaration.kind {
+                DeclarationKind::Function {
+                    receiver,
+                    parameters,
+                    results,
+                    ..
+                } => {
+                    let signature = self.signature(parameters, results);
+                    if let Some(receiver) = receiver {
+                        self.declare_method(declaration, receiver, signature);
+                        continue;
+                    }
+                    let function = self.types.function(signature);
+                    if let Some(symbol) = self.symbols.def_at(declaration.position_start) {
+                        self.symbol_types.insert(symbol, function);
+                    }
+                }
+                DeclarationKind::Variable { specs, .. } => {
+                    for spec in specs {
+                        for name in &spec.names {
+                            if let Some(symbol) = self.symbols.def_at(name.position_start) {
+                                self.package_variables.insert(symbol, spec);
+                            }
+                        }
+                    }
+                }
+                DeclarationKind::Constant { specs, .. } => {
+                    for constant in constant_specs(specs) {
+                        for name in &constant.spec.names {
+                            if let Some(symbol) = self.symbols.def_at(name.position_start) {
+                                self.package_constants.insert(symbol, constant);
+                            }
+                        }
+                    }
+                }
+                DeclarationKind::Type { .. } => {}
+            }
+        }
+
+        for declaration in &file.decls {
+            let errors = self.errors.len();
+            self.check_declaration(declaration);
+            trace!(
+                "checked the declaration at line {}: {} errors",
+                declaration.position_start.line,
+                self.errors.len() - errors
+            );
+        }
+    }
+
+    /// Create the named type declared by `name`, to be defined once every type name is known
+    fn declare_named_type(&mut self, name: &Expression) -> Option<TypeId> {
+        let ExpressionKind::Identifier(identifier) = &name.kind else {
+            return None;
+        };
+        let symbol = self.symbols.def_at(name.position_start)?;
+        let named = self.types.new_named(identifier);
+        self.named_types.insert(symbol, named);
+        Some(named)
+    }
+
+    /// Set the underlying type of each named type. `type A B` needs `B` defined first, so
+    /// definitions are retried until no more can be made; any left over are part of a cycle
+    /// or refer to something unknown.
+    fn define_named_types(&mut self, mut definitions: Vec<(TypeId, &TypeExpression)>) {
+        while !definitions.is_empty() {
+            let count = definitions.len();
+            let mut pending = Vec::new();
+            for (named, type_expression) in definitions {
+                match self.type_from_expression(type_expression) {
+                    Some(underlying) if !self.is_undefined_named(underlying) => {
+                        self.types.set_underlying(named, underlying);
+                    }
+                    _ => pending.push((named, type_expression)),
+                }
+            }
+            if pending.len() == count {
+                return;
+            }
+            definitions = pending;
+        }
+    }
+
+    /// Whether `type_id` is a named type whose definition hasn't been checked yet
+    fn is_undefined_named(&self, type_id: TypeId) -> bool {
+        matches!(self.types.get(type_id), Type::Named(_))
+            && self.types.underlying(type_id) == type_id
+    }
+
+    /// Add a method to the method set of its receiver's base type
+    fn declare_method(
+        &mut self,
+        declaration: &Declaration,
+        receiver: &Parameter,
+        signature: Signature,
+    ) {
+        let DeclarationKind::Function { name, .. } = &declaration.kind else {
+            return;
+        };
+        let (base, pointer_receiver) = match &receiver.type_expression.kind {
+            TypeExpressionKind::Pointer(base) => (base.as_ref(), true),
+            _ => (&receiver.type_expression, false),
+        };
+        // Methods can only be declared on the file's own types, and not on pointers or
+        // interfaces (https://go.dev/ref/spec#Method_declarations)
+        let named = match &base.kind {
+            TypeExpressionKind::Name(_) => self
+                .symbols
+                .use_at(base.position_start)
+                .and_then(|symbol| self.named_types.get(&symbol))
+                .copied(),
+            _ => None,
+        };
+        let Some(named) = named.filter(|named| {
+            !matches!(
+                self.types.get(self.types.underlying(*named)),
+                Type::Pointer(_) | Type::Interface(_)
+            )
+        }) else {
+            self.error(
+                SemanticErrorKind::InvalidReceiver(receiver.type_expression.to_string()),
+                receiver.type_expression.position_start,
+            );
+            return;
+        };
+        let method = DeclaredMethod {
+            method: Method {
+                name: name.clone(),
+                signature,
+            },
+            pointer_receiver,
+        };
+        if name != "_" && !self.types.add_method(named, method) {
+            self.error(
+                SemanticErrorKind::MethodRedeclared {
+                    type_name: self.types.type_string(named),
+                    method: name.clone(),
+                },
+                declaration.position_start,
+            );
+        }
+    }
+
+    fn check_declaration(&mut self, declaration: &Declaration) {
+        match &declaration.kind {
+            DeclarationKind::Function {
+                receiver,
+                parameters,
+                results,
+                body,
+                ..
+            } => {
+                let parameters: Vec<&Parameter> = receiver.iter().chain(parameters).collect();
+                self.check_function(&parameters, results, body, declaration.position_end);
+            }
+            DeclarationKind::Variable { specs, .. } => {
+                for spec in specs {
+                    self.check_variable_spec(spec);
+                }
+            }
+            DeclarationKind::Constant { specs, .. } => {
+                for constant in constant_specs(specs) {
+                    self.check_constant_spec(constant);
+                }
+            }
+            // Package level types were defined up front
+            DeclarationKind::Type { .. } => {}
+        }
+    }
+
+    /// Check a function's body, with its parameters and results declared, against its results.
+    /// `end` is the position of its closing brace.
+    fn check_function(
+        &mut self,
+        parameters: &[&Parameter],
+        results: &[Parameter],
+        body: &[Statement],
+        end: Position,
+    ) {
+        for parameter in parameters.iter().copied().chain(results) {
+            let Some(mut type_id) = self.type_from_expression(&parameter.type_expression) else {
+                continue;
+            };
+            if parameter.variadic {
+                type_id = self.types.slice(type_id);
+            }
+            for name in &parameter.names {
+                self.define(name, type_id);
+            }
+        }
+        self.results = self.parameter_types(results);
+        self.named_results = results.iter().any(|result| !result.names.is_empty());
+        self.check_statements(body);
+        if !results.is_empty() && !self.is_terminating_list(body) {
+            // Reported at the closing brace, like gc
+            let position = Position::new(end.line, end.column_start, end.column_start + 1);
+            self.error(SemanticErrorKind::MissingReturn, position);
+        }
+    }
+
+    /// The function type of a function literal, after checking its body, whose return
+    /// statements return from the literal rather than the function around it
+    fn check_function_literal(
+        &mut self,
+        literal: &Expression,
+        parameters: &[Parameter],
+        results: &[Parameter],
+        body: &[Statement],
+    ) -> TypeId {
+        let signature = self.signature(parameters, results);
+        let outer_results = std::mem::take(&mut self.results);
+        let outer_named_results = self.named_results;
+        let parameters: Vec<&Parameter> = parameters.iter().collect();
+        self.check_function(&parameters, results, body, literal.position_end);
+        self.results = outer_results;
+        self.named_results = outer_named_results;
+        self.types.function(signature)
+    }
+
+    /// Type the names of a `var` spec, from its type if it has one and otherwise from its
+    /// values
+    fn check_variable_spec(&mut self, spec: &VariableSpec) {
+        if !self
+            .checked_specs
+            .insert((spec.position_start, spec.position_end))
+        {
+            return;
+        }
+        let declared = spec
+            .type_expression
+            .as_ref()
+            .and_then(|type_expression| self.type_from_expression(type_expression));
+        let values = self.value_types(&spec.values, spec.names.len(), spec.position_start);
+        if let Some(declared) = declared {
+            for (index, value_type) in values.iter().enumerate() {
+                let Some(value_type) = *value_type else {
+                    continue;
+                };
+                let value = match spec.values.get(index) {
+                    Some(value) if spec.values.len() == values.len() => value,
+                    _ => &spec.values[0],
+                };
+                self.check_assignable(
+                    value,
+                    value_type,
+                    declared,
+                    "variable declaration",
+                    span(value),
+                );
+            }
+        }
+        for (index, name) in spec.names.iter().enumerate() {
+            let type_id = match declared {
+                Some(declared) => Some(declared),
+                None => values.get(index).copied().flatten().and_then(|value_type| {
+                    let value = spec
+                        .values
+                        .get(index)
+                        .filter(|_| spec.values.len() == spec.names.len());
+                    self.default_type(value_type, value, spec.position_start)
+                }),
+            };
+            if let Some(type_id) = type_id {
+                self.define(name, type_id);
+            }
+        }
+    }
+
+    /// Type the names of a `const` spec like those of a `var` spec, except that names without
+    /// a type keep their value's untyped type, and record their values
+    fn check_constant_spec(&mut self, constant: ConstantSpec) {
+        let spec = constant.spec;
+        if !self
+            .checked_specs
+            .insert((spec.position_start, spec.position_end))
+        {
+            return;
+        }
+        let iota = self.iota.replace(constant.iota);
+        let declared = constant
+            .type_expression
+            .and_then(|type_expression| self.type_from_expression(type_expression));
+        let values = self.value_types(constant.values, spec.names.len(), spec.position_start);
+        for (index, name) in spec.names.iter().enumerate() {
+            let (Some(value), Some(value_type)) = (
+                constant.values.get(index),
+                values.get(index).copied().flatten(),
+            ) else {
+                continue;
+            };
+            if let Some(declared) = declared {
+                self.check_assignable(
+                    value,
+                    value_type,
+                    declared,
+                    "constant declaration",
+                    span(value),
+                );
+            }
+            self.define(name, declared.unwrap_or(value_type));
+            let constant = self.constant_value(value).and_then(|constant| {
+                match declared.and_then(|declared| self.types.basic_kind(declared)) {
+                    Some(kind) if kind.is_numeric() => constant.convert(kind).ok(),
+                    _ => Some(constant),
+                }
+            });
+            if let (Some(symbol), Some(constant)) =
+                (self.symbols.def_at(name.position_start), constant)
+            {
+                self.constant_values.insert(symbol, constant);
+            }
+        }
+        self.iota = iota;
+    }
+
+    fn check_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Statement) {
+        match &statement.kind {
+            StatementKind::Expression(expression) => {
+                self.check_expression(expression);
+            }
+            StatementKind::Declaration(declaration) => match &declaration.kind {
+                DeclarationKind::Variable { specs, .. } => {
+                    for spec in specs {
+                        self.check_variable_spec(spec);
+                    }
+                }
+                DeclarationKind::Constant { specs, .. } => {
+                    for constant in constant_specs(specs) {
+                        self.check_constant_spec(constant);
+                    }
+                }
+                DeclarationKind::Type {
+                    name,
+                    type_expression,
+                } => {
+                    if let Some(named) = self.declare_named_type(name) {
+                        self.define_named_types(vec![(named, type_expression)]);
+                    }
+                }
+                DeclarationKind::Function { .. } => self.check_declaration(declaration),
+            },
+            StatementKind::Assignment {
+                left,
+                right,
+                define,
+            } => {
+                let values = self.value_types(right, left.len(), statement.position_start);
+                for (index, (name, value)) in left.iter().zip(&values).enumerate() {
+                    // Names `:=` declares get the default type of their value, the rest are
+                    // assigned to like with `=`
+                    let declares = *define && self.symbols.def_at(name.position_start).is_some();
+                    let value_expression = right.get(index).filter(|_| right.len() == left.len());
+                    if declares {
+                        if let Some(type_id) = value.and_then(|value| {
+                            self.default_type(value, value_expression, name.position_start)
+                        }) {
+                            self.define(name, type_id);
+                        }
+                        continue;
+                    }
+                    // A value assigned to `_` only needs a type of its own
+                    if matches!(name.kind, ExpressionKind::BlankIdentifier) {
+                        if let Some(value) = value {
+                            self.default_type(*value, value_expression, name.position_start);
+                        }
+                        continue;
+                    }
+                    if let (Some(target), Some(value_type)) = (self.check_expression(name), value) {
+                        let value = match right.get(index) {
+                            Some(value) if right.len() == left.len() => value,
+                            _ => &right[0],
+                        };
+                        self.check_assignable(
+                            value,
+                            *value_type,
+                            target,
+                            "assignment",
+                            span(value),
+                        );
+                    }
+                }
+            }
+            StatementKind::IncDec { target, .. } => {
+                self.check_expression(target);
+            }
+            StatementKind::OpAssign {
+                target,
+                operator,
+                value,
+            } => {
+                let target_type = self.check_expression(target);
+                let value_type = self.check_single_value(value);
+                // A shift's count needn't have the type of what's shifted
+                if let (Some(target_type), Some(value_type)) = (target_type, value_type)
+                    && !matches!(operator, Operator::LessLess | Operator::GreaterGreater)
+                {
+                    self.check_assignable(
+                        value,
+                        value_type,
+                        target_type,
+                        "assignment",
+                        span(value),
+                    );
+                }
+            }
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                if let Some(init) = init {
+                    self.check_statement(init);
+                }
+                if let Some(condition) = condition {
+                    self.check_expression(condition);
+                }
+                if let Some(post) = post {
+                    self.check_statement(post);
+                }
+                self.check_statements(body);
+            }
+            StatementKind::Switch { init, tag, cases } => {
+                if let Some(init) = init {
+                    self.check_statement(init);
+                }
+                if let Some(tag) = tag
+                    && let Some(tag_type) = self.check_single_value(tag)
+                {
+                    self.default_type(tag_type, Some(tag), tag.position_start);
+                }
+                for case in cases {
+                    for expression in case.expressions.iter().flatten() {
+                        self.check_expression(expression);
+                    }
+                    self.check_statements(&case.body);
+                }
+            }
+            StatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => {
+                let ranged = self.check_expression(expression);
+                let (key_type, value_type) = match ranged {
+                    Some(ranged) if self.is_channel(ranged) => {
+                        let element =
+                            self.channel_element(expression, ranged, ChannelOperation::Range);
+                        (element, None)
+                    }
+                    Some(ranged) => self.range_types(ranged),
+                    None => (None, None),
+                };
+                for (variable, type_id) in [(key, key_type), (value, value_type)] {
+                    let Some(variable) = variable else {
+                        continue;
+                    };
+                    match (type_id, define) {
+                        (Some(type_id), true) => self.define(variable, type_id),
+                        (_, false) if !matches!(variable.kind, ExpressionKind::BlankIdentifier) => {
+                            self.check_expression(variable);
+                        }
+                        _ => {}
+                    }
+                }
+                self.check_statements(body);
+            }
+            StatementKind::Block(body) => self.check_statements(body),
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => {
+                if let Some(init) = init {
+                    self.check_statement(init);
+                }
+                self.check_expression(condition);
+                self.check_statements(body);
+                if let Some(else_branch) = else_branch {
+                    self.check_statement(else_branch);
+                }
+            }
+            StatementKind::Return(values) => self.check_return(statement, values),
+            StatementKind::Send { channel, value } => self.check_send(channel, value),
+            StatementKind::Defer(call) | StatementKind::Go(call) => {
+                self.check_expression(call);
+            }
+            StatementKind::Select(cases) => {
+                for case in cases {
+                    if let Some(communication) = &case.communication {
+                        self.check_statement(communication);
+                    }
+                    self.check_statements(&case.body);
+                }
+            }
+            StatementKind::Break | StatementKind::Continue | StatementKind::Fallthrough => {}
+        }
+    }
+
+    /// Check `channel <- value` (https://go.dev/ref/spec#Send_statements)
+    fn check_send(&mut self, channel: &Expression, value: &Expression) {
+        let channel_type = self.check_single_value(channel);
+        let value_type = self.check_single_value(value);
+        let Some(element) = channel_type.and_then(|channel_type| {
+            self.channel_element(channel, channel_type, ChannelOperation::Send)
+        }) else {
+            return;
+        };
+        if let Some(value_type) = value_type {
+            self.check_assignable(value, value_type, element, "send", span(value));
+        }
+    }
+
+    fn is_channel(&self, type_id: TypeId) -> bool {
+        matches!(
+            self.types.get(self.types.underlying(type_id)),
+            Type::Channel { .. }
+        )
+    }
+
+    /// The element type of `channel`, or None after reporting it if `operation` isn't allowed
+    /// on it. Only the sending side of a channel may close it.
+    fn channel_element(
+        &mut self,
+        channel: &Expression,
+        channel_type: TypeId,
+        operation: ChannelOperation,
+    ) -> Option<TypeId> {
+        let Type::Channel { direction, element } =
+            self.types.get(self.types.underlying(channel_type))
+        else {
+            self.error(
+                SemanticErrorKind::NotAChannel {
+                    operation,
+                    value: channel.to_string(),
+                    value_type: self.types.type_string(channel_type),
+                },
+                span(channel),
+            );
+            return None;
+        };
+        let (direction, element) = (*direction, *element);
+        let forbidden = match operation {
+            ChannelOperation::Send | ChannelOperation::Close => ChannelDirection::Receive,
+            ChannelOperation::Receive | ChannelOperation::Range => ChannelDirection::Send,
+        };
+        if direction == forbidden {
+            self.error(
+                SemanticErrorKind::WrongChannelDirection {
+                    operation,
+                    channel: channel.to_string(),
+                    channel_type: self.types.type_string(channel_type),
+                },
+                span(channel),
+            );
+            return None;
+        }
+        Some(element)
+    }
+
+    /// Check a return statement's values against the function's results
+    /// (https://go.dev/ref/spec#Return_statements)
+    fn check_return(&mut self, statement: &Statement, values: &[Expression]) {
+        // A bare return returns the named results as they are
+        if values.is_empty() && self.named_results {
+            return;
+        }
+        let value_types = self.argument_types(values, false);
+        let results = self.results.clone();
+        if value_types.len() != results.len() {
+            let have = self.type_list_string(value_types.iter().map(|(type_id, _)| *type_id));
+            let want = self.type_list_string(results.iter().copied().map(Some));
+            let kind = if value_types.len() < results.len() {
+                SemanticErrorKind::NotEnoughReturnValues { have, want }
+            } else {
+                SemanticErrorKind::TooManyReturnValues { have, want }
+            };
+            // Extra values are reported at the first one, missing ones at the `return`
+            let position = match value_types.get(results.len()) {
+                Some((_, position)) => *position,
+                None => statement.position_start,
+            };
+            self.error(kind, position);
+            return;
+        }
+        for (index, ((value_type, position), result)) in
+            value_types.iter().zip(&results).enumerate()
+        {
+            let Some(value_type) = *value_type else {
+                continue;
+            };
+            // A call spread over several results is reported as a whole
+            let value = match values.get(index) {
+                Some(value) if values.len() == results.len() => value,
+                _ => &values[0],
+            };
+            self.check_assignable(value, value_type, *result, "return statement", *position);
+        }
+    }
+
+    /// Whether the statements end in a terminating statement, so control can't fall off their
+    /// end (https://go.dev/ref/spec#Terminating_statements)
+    fn is_terminating_list(&self, statements: &[Statement]) -> bool {
+        statements
+            .last()
+            .is_some_and(|statement| self.is_terminating(statement))
+    }
+
+    fn is_terminating(&self, statement: &Statement) -> bool {
+        match &statement.kind {
+            StatementKind::Return(_) => true,
+            StatementKind::Expression(expression) => self.is_panic_call(expression),
+            StatementKind::Block(body) => self.is_terminating_list(body),
+            StatementKind::If {
+                body, else_branch, ..
+            } => {
+                self.is_terminating_list(body)
+                    && else_branch
+                        .as_ref()
+                        .is_some_and(|else_branch| self.is_terminating(else_branch))
+            }
+            // An infinite loop only ends by breaking out of it
+            StatementKind::For {
+                condition: None,
+                body,
+                ..
+            } => !has_break(body),
+            // Likewise a select only ends through one of its clauses
+            StatementKind::Select(cases) => cases
+                .iter()
+                .all(|case| !has_break(&case.body) && self.is_terminating_list(&case.body)),
+            // And a switch with a default, where a case falling through ends as the next does
+            StatementKind::Switch { cases, .. } => {
+                cases.iter().any(|case| case.expressions.is_none())
+                    && cases.iter().all(|case| {
+                        !has_break(&case.body)
+                            && (self.is_terminating_list(&case.body)
+                                || case
+                                    .body
+                                    .last()
+                                    .is_some_and(|last| last.kind == StatementKind::Fallthrough))
+                    })
+            }
+            StatementKind::For { .. }
+            | StatementKind::Range { .. }
+            | StatementKind::Declaration(_)
+            | StatementKind::Assignment { .. }
+            | StatementKind::IncDec { .. }
+            | StatementKind::OpAssign { .. }
+            | StatementKind::Send { .. }
+            | StatementKind::Defer(_)
+            | StatementKind::Go(_)
+            | StatementKind::Break
+            | StatementKind::Continue
+            | StatementKind::Fallthrough => false,
+        }
+    }
+
+    /// Whether `expression` calls the predeclared `panic`, rather than something shadowing it
+    fn is_panic_call(&self, expression: &Expression) -> bool {
+        let ExpressionKind::FunctionCall { name, .. } = &expression.kind else {
+            return false;
+        };
+        self.builtin(name) == Some(Builtin::Panic)
+    }
+
+    /// The builtin `callee` refers to, unless something declared in the file shadows it
+    fn builtin(&self, callee: &Expression) -> Option<Builtin> {
+        let ExpressionKind::Identifier(name) = &callee.kind else {
+            return None;
+        };
+        let symbol = self
+            .symbols
+            .symbol(self.symbols.use_at(callee.position_start)?);
+        if symbol.kind != SymbolKind::Function || symbol.position.is_some() {
+            return None;
+        }
+        Builtin::from_name(name)
+    }
+
+    /// The types of the values assigned to `count` variables. A single call returning several
+    /// values is spread across them (https://go.dev/ref/spec#Assignment_statements).
+    fn value_types(
+        &mut self,
+        values: &[Expression],
+        count: usize,
+        position: Position,
+    ) -> Vec<Option<TypeId>> {
+        if values.is_empty() {
+            return Vec::new();
+        }
+        // v, ok := <-ch, where ok is whether the value was sent rather than the channel closed
+        if let [value] = values
+            && count == 2
+            && matches!(value.kind, ExpressionKind::Receive(_))
+        {
+            let element = self.check_expression(value);
+            return vec![element, Some(self.types.basic(BasicKind::UntypedBool))];
+        }
+        // v, ok := m[k], where ok is whether the key is present
+        if let [value] = values
+            && count == 2
+            && let ExpressionKind::Index { object, .. } = &value.kind
+        {
+            let element = self.check_expression(value);
+            let object_type = self.nodes.get(&(&**object).into()).copied();
+            if let Some(object_type) = object_type
+                && matches!(
+                    self.types.get(self.types.underlying(object_type)),
+                    Type::Map { .. }
+                )
+            {
+                return vec![element, Some(self.types.basic(BasicKind::UntypedBool))];
+            }
+            if element.is_some() {
+                self.error(
+                    SemanticErrorKind::AssignmentMismatch {
+                        variables: count,
+                        values: 1,
+                    },
+                    position,
+                );
+            }
+            return vec![element];
+        }
+        if let [value] = values
+            && count > 1
+        {
+            let type_id = self.check_expression(value);
+            let results = match type_id.map(|type_id| self.types.get(type_id)) {
+                Some(Type::Tuple(results)) => results.iter().copied().map(Some).collect(),
+                Some(_) => vec![type_id],
+                None => return Vec::new(),
+            };
+            if results.len() != count {
+                self.error(
+                    SemanticErrorKind::AssignmentMismatch {
+                        variables: count,
+                        values: results.len(),
+                    },
+                    position,
+                );
+            }
+            return results;
+        }
+        let types = values
+            .iter()
+            .map(|value| self.check_single_value(value))
+            .collect();
+        if values.len() != count {
+            self.error(
+                SemanticErrorKind::AssignmentMismatch {
+                    variables: count,
+                    values: values.len(),
+                },
+                position,
+            );
+        }
+        types
+    }
+
+    /// Check an expression used where exactly one value is expected
+    fn check_single_value(&mut self, expression: &Expression) -> Option<TypeId> {
+        let type_id = self.check_expression(expression)?;
+        match self.types.get(type_id) {
+            Type::Tuple(results) if results.is_empty() => {
+                self.error(
+                    SemanticErrorKind::NoValue(expression.to_string()),
+                    expression.position_start,
+                );
+                None
+            }
+            Type::Tuple(_) => {
+                self.error(
+                    SemanticErrorKind::MultipleValues(expression.to_string()),
+                    expression.position_start,
+                );
+                None
+            }
+            _ => Some(type_id),
+        }
+    }
+
+    /// The types of a range clause's key and value
+    /// (https://go.dev/ref/spec#For_range)
+    fn range_types(&self, ranged: TypeId) -> (Option<TypeId>, Option<TypeId>) {
+        let int = self.types.basic(BasicKind::Int);
+        match self.types.get(self.types.underlying(ranged)) {
+            Type::Basic(kind) if kind.is_string() => {
+                (Some(int), Some(self.types.basic(BasicKind::Int32)))
+            }
+            Type::Basic(BasicKind::UntypedInt) => (Some(int), None),
+            Type::Basic(kind) if kind.is_integer() => (Some(ranged), None),
+            Type::Slice(element) | Type::Array { element, .. } => (Some(int), Some(*element)),
+            Type::Pointer(pointer) => match self.types.get(self.types.underlying(*pointer)) {
+                Type::Array { element, .. } => (Some(int), Some(*element)),
+                _ => (None, None),
+            },
+            Type::Map { key, value } => (Some(*key), Some(*value)),
+            Type::Channel { element, .. } => (Some(*element), None),
+            _ => (None, None),
+        }
+    }
+
+    /// Infer the type of `expression` and its subexpressions, recording each one. Calls that
+    /// return several values, or none, have a tuple type.
+    fn check_expression(&mut self, expression: &Expression) -> Option<TypeId> {
+        let type_id = match &expression.kind {
+            ExpressionKind::Identifier(name) => {
+                if self.builtin(expression).is_some() {
+                    self.error(
+                        SemanticErrorKind::BuiltinNotCalled(name.clone()),
+                        span(expression),
+                    );
+                }
+                self.identifier_type(expression.position_start)
+            }
+            ExpressionKind::BlankIdentifier => None,
+            ExpressionKind::IntegerLiteral(_) => Some(self.types.basic(BasicKind::UntypedInt)),
+            ExpressionKind::FloatLiteral(_) => Some(self.types.basic(BasicKind::UntypedFloat)),
+            ExpressionKind::RuneLiteral(_) => Some(self.types.basic(BasicKind::UntypedRune)),
+            ExpressionKind::StringLiteral(_) => Some(self.types.basic(BasicKind::UntypedString)),
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left_type = self.check_single_value(left);
+                let right_type = self.check_single_value(right);
+                let (Some(left_type), Some(right_type)) = (left_type, right_type) else {
+                    return None;
+                };
+                let type_id = self.binary_type(*operator, left_type, right_type);
+                self.nodes.insert(expression.into(), type_id);
+                // Untyped constant operands are converted to the operation's type, except for
+                // a shift's count
+                if self.types.untyped_kind(type_id).is_none() {
+                    if self.types.untyped_kind(left_type).is_some() {
+                        self.check_representable(left, type_id, span(left));
+                    }
+                    if self.types.untyped_kind(right_type).is_some()
+                        && !matches!(operator, Operator::LessLess | Operator::GreaterGreater)
+                    {
+                        self.check_representable(right, type_id, span(right));
+                    }
+                    if let Some(constant) = self.operation_value(expression, left, *operator, right)
+                    {
+                        self.report_unrepresentable(constant, type_id, span(expression));
+                    }
+                }
+                Some(type_id)
+            }
+            ExpressionKind::FunctionCall {
+                name,
+                arguments,
+                spread,
+            } => self.check_call(expression, name, arguments, *spread),
+            ExpressionKind::FieldAccess { object, field, .. } => {
+                self.check_selector(expression, object, field)
+            }
+            ExpressionKind::Index { object, index } => self.check_index(object, index),
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                let bounds = [low, high, max].into_iter().flatten();
+                let bounds = bounds.map(|bound| &**bound).collect();
+                self.check_slice_expression(expression, object, bounds, max.is_some())
+            }
+            ExpressionKind::Parenthesized(inner) => self.check_expression(inner),
+            ExpressionKind::Receive(channel) => {
+                let channel_type = self.check_single_value(channel)?;
+                self.channel_element(channel, channel_type, ChannelOperation::Receive)
+            }
+            ExpressionKind::Conversion { target, expression } => {
+                let target = self.type_from_expression(target);
+                self.check_conversion(expression, target)
+            }
+            ExpressionKind::Unary { operator, operand } => self.check_unary(*operator, operand),
+            ExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            } => {
+                let literal_type = type_expression
+                    .as_ref()
+                    .and_then(|type_expression| self.type_from_expression(type_expression));
+                self.check_elements(literal_type, elements);
+                literal_type
+            }
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            } => Some(self.check_function_literal(expression, parameters, results, body)),
+            ExpressionKind::Type(type_expression) => {
+                self.type_from_expression(type_expression);
+                self.error(
+                    SemanticErrorKind::NotAnExpression(type_expression.to_string()),
+                    span(expression),
+                );
+                None
+            }
+        };
+        if let Some(type_id) = type_id {
+            self.nodes.insert(expression.into(), type_id);
+        }
+        type_id
+    }
+
+    /// https://go.dev/ref/spec#Address_operators, https://go.dev/ref/spec#Arithmetic_operators
+    fn check_unary(&mut self, operator: UnaryOperator, operand: &Expression) -> Option<TypeId> {
+        let operand_type = self.check_single_value(operand)?;
+        match operator {
+            UnaryOperator::Address => Some(self.types.pointer(operand_type)),
+            UnaryOperator::Dereference => match self.types.get(self.types.underlying(operand_type))
+            {
+                Type::Pointer(element) => Some(*element),
+                _ => None,
+            },
+            _ => Some(operand_type),
+        }
+    }
+
+    /// Check a composite literal's elements against its type, which is None when it isn't
+    /// known (https://go.dev/ref/spec#Composite_literals)
+    fn check_elements(&mut self, literal_type: Option<TypeId>, elements: &[KeyedElement]) {
+        let underlying = literal_type.map(|type_id| self.types.get(self.types.underlying(type_id)));
+        match underlying.cloned() {
+            Some(Type::Struct(fields)) => {
+                for (i, element) in elements.iter().enumerate() {
+                    let field = match &element.key {
+                        Some(key) => {
+                            let ExpressionKind::Identifier(name) = &key.kind else {
+                                continue;
+                            };
+                            let field = fields.iter().find(|field| field.name == *name);
+                            if field.is_none() {
+                                self.error(
+                                    SemanticErrorKind::UnknownField {
+                                        field: name.clone(),
+                                        type_name: self
+                                            .types
+                                            .type_string(literal_type.unwrap_or(self.any)),
+                                    },
+                                    span(key),
+                                );
+                            }
+                            field
+                        }
+                        None => fields.get(i),
+                    };
+                    let field_type = field.map(|field| field.type_id);
+                    self.check_element(&element.value, field_type, "struct literal");
+                }
+            }
+            Some(
+                Type::Slice(element_type)
+                | Type::Array {
+                    element: element_type,
+                    ..
+                },
+            ) => {
+                for element in elements {
+                    if let Some(index) = &element.key {
+                        self.check_single_value(index);
+                    }
+                    self.check_element(&element.value, Some(element_type), "slice literal");
+                }
+            }
+            Some(Type::Map { key, value }) => {
+                for element in elements {
+                    if let Some(element_key) = &element.key {
+                        self.check_element(element_key, Some(key), "map literal");
+                    }
+                    self.check_element(&element.value, Some(value), "map literal");
+                }
+            }
+            _ => {
+                for element in elements {
+                    self.check_element(&element.value, None, "composite literal");
+                }
+            }
+        }
+    }
+
+    /// Check an element of a composite literal, or a map key, that should be assignable to
+    /// `expected`. An element that's a literal itself may leave out its type, which for a
+    /// pointer type is the type pointed to: `{1, 2}` as a `*Point` is `&Point{1, 2}`.
+    fn check_element(&mut self, value: &Expression, expected: Option<TypeId>, context: &str) {
+        if let ExpressionKind::CompositeLiteral {
+            type_expression: None,
+            elements,
+        } = &value.kind
+        {
+            let literal_type =
+                expected.map(
+                    |expected| match self.types.get(self.types.underlying(expected)) {
+                        Type::Pointer(element) => *element,
+                        _ => expected,
+                    },
+                );
+            self.check_elements(literal_type, elements);
+            if let Some(expected) = expected {
+                self.nodes.insert(value.into(), expected);
+            }
+            return;
+        }
+        let Some(value_type) = self.check_single_value(value) else {
+            return;
+        };
+        if let Some(expected) = expected {
+            self.check_assignable(value, value_type, expected, context, span(value));
+        }
+    }
+
+    /// The type of the element or map value `object[index]` is
+    /// (https://go.dev/ref/spec#Index_expressions)
+    fn check_index(&mut self, object: &Expression, index: &Expression) -> Option<TypeId> {
+        let object_type = self.check_single_value(object);
+        let index_type = self.check_single_value(index);
+        let object_type = object_type?;
+        let (element, length) = match self.types.get(self.types.underlying(object_type)) {
+            // Indexing a string gives a byte
+            Type::Basic(kind) if kind.is_string() => (self.types.basic(BasicKind::Uint8), None),
+            Type::Slice(element) => (*element, None),
+            Type::Array { length, element } => (*element, Some(*length)),
+            Type::Pointer(pointer) => match self.types.get(self.types.underlying(*pointer)) {
+                Type::Array { length, element } => (*element, Some(*length)),
+                _ => return self.cannot_index(object, object_type),
+            },
+            Type::Map { key, value } => {
+                let (key, value) = (*key, *value);
+                if let Some(index_type) = index_type {
+                    self.check_assignable(index, index_type, key, "map index", span(index));
+                }
+                return Some(value);
+            }
+            _ => return self.cannot_index(object, object_type),
+        };
+        if let Some(index_type) = index_type {
+            self.check_integer_index(index, index_type, length);
+        }
+        Some(element)
+    }
+
+    fn cannot_index(&mut self, object: &Expression, object_type: TypeId) -> Option<TypeId> {
+        self.error(
+            SemanticErrorKind::CannotIndex {
+                value: object.to_string(),
+                value_type: self.types.type_string(object_type),
+            },
+            span(object),
+        );
+        None
+    }
+
+    /// The type of a slice expression with the given bounds: a string for a string, and
+    /// otherwise a slice (https://go.dev/ref/spec#Slice_expressions)
+    fn check_slice_expression(
+        &mut self,
+        expression: &Expression,
+        object: &Expression,
+        bounds: Vec<&Expression>,
+        three_index: bool,
+    ) -> Option<TypeId> {
+        let object_type = self.check_single_value(object);
+        let length = object_type.and_then(|object_type| self.array_length(object_type));
+        for bound in bounds {
+            if let Some(bound_type) = self.check_single_value(bound) {
+                // A bound may be the length itself, one past the last index
+                self.check_integer_index(bound, bound_type, length.map(|length| length + 1));
+            }
+        }
+        let object_type = object_type?;
+        let element = match self.types.get(self.types.underlying(object_type)) {
+            Type::Basic(kind) if kind.is_string() && three_index => {
+                self.error(SemanticErrorKind::ThreeIndexString, span(expression));
+                return None;
+            }
+            Type::Basic(BasicKind::UntypedString) => {
+                return Some(self.types.basic(BasicKind::String));
+            }
+            Type::Basic(kind) if kind.is_string() => return Some(object_type),
+            Type::Slice(_) => return Some(object_type),
+            Type::Array { element, .. } => *element,
+            Type::Pointer(pointer) => match self.types.get(self.types.underlying(*pointer)) {
+                Type::Array { element, .. } => *element,
+                _ => return self.cannot_slice(object, object_type),
+            },
+            _ => return self.cannot_slice(object, object_type),
+        };
+        Some(self.types.slice(element))
+    }
+
+    fn cannot_slice(&mut self, object: &Expression, object_type: TypeId) -> Option<TypeId> {
+        self.error(
+            SemanticErrorKind::CannotSlice {
+                value: object.to_string(),
+                value_type: self.types.type_string(object_type),
+            },
+            span(object),
+        );
+        None
+    }
+
+    /// The length of an array, or of the array a pointer points to
+    fn array_length(&self, type_id: TypeId) -> Option<u64> {
+        match self.types.get(self.types.underlying(type_id)) {
+            Type::Array { length, .. } => Some(*length),
+            Type::Pointer(pointer) => match self.types.get(self.types.underlying(*pointer)) {
+                Type::Array { length, .. } => Some(*length),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Report an index that isn't an integer, or a constant one of `limit` or more
+    fn check_integer_index(&mut self, index: &Expression, index_type: TypeId, limit: Option<u64>) {
+        match self.types.basic_kind(index_type) {
+            Some(kind) if kind.is_integer() => {}
+            // An untyped constant like 1.0 is an integer
+            Some(kind) if kind.is_untyped() && kind.is_numeric() => {
+                let int = self.types.basic(BasicKind::Int);
+                self.check_representable(index, int, span(index));
+            }
+            _ => {
+                self.error(
+                    SemanticErrorKind::NonIntegerIndex {
+                        index: index.to_string(),
+                        index_type: self.types.type_string(index_type),
+                    },
+                    span(index),
+                );
+                return;
+            }
+        }
+        if let (Some(Constant::Int(value)), Some(limit)) = (self.constant_value(index), limit)
+            && value >= i128::from(limit)
+        {
+            self.error(
+                SemanticErrorKind::IndexOutOfBounds {
+                    index: value.to_string(),
+                    length: limit,
+                },
+                span(index),
+            );
+        }
+    }
+
+    /// The type of the field or method `object.field` selects. A method value is a function
+    /// with the receiver bound. Package members aren't typed until packages are loaded.
+    fn check_selector(
+        &mut self,
+        selector: &Expression,
+        object: &Expression,
+        field: &str,
+    ) -> Option<TypeId> {
+        let object_type = self.check_single_value(object)?;
+        match self.types.lookup_field_or_method(object_type, field) {
+            Some(Selection::Field(type_id)) => Some(type_id),
+            Some(Selection::Method(method)) => Some(self.types.function(method.signature)),
+            None => {
+                let names = self.types.selectable_names(object_type);
+                let suggestion = closest_match(field, names.iter().map(|(name, _)| name.as_str()))
+                    .and_then(|closest| names.iter().find(|(name, _)| name == closest))
+                    .map(|(name, is_method)| match is_method {
+                        true => format!("method {}", name),
+                        false => format!("field {}", name),
+                    });
+                self.error(
+                    SemanticErrorKind::MissingFieldOrMethod {
+                        selector: selector.to_string(),
+                        type_name: self.types.type_string(object_type),
+                        name: field.to_string(),
+                        suggestion,
+                    },
+                    span(selector),
+                );
+                None
+            }
+        }
+    }
+
+    /// Check a call's arguments against the callee's signature, returning the call's result
+    /// (https://go.dev/ref/spec#Calls)
+    fn check_call(
+        &mut self,
+        call: &Expression,
+        callee: &Expression,
+        arguments: &[Expression],
+        spread: bool,
+    ) -> Option<TypeId> {
+        // `T(x)` with a type name `T` is a conversion
+        if let ExpressionKind::Identifier(name) = &callee.kind
+            && let Some(symbol) = self.symbols.use_at(callee.position_start)
+            && self.symbols.symbol(symbol).kind == SymbolKind::Type
+        {
+            let target = self.named_type(name, callee.position_start);
+            if let Some(target) = target {
+                self.nodes.insert(callee.into(), target);
+            }
+            return match arguments {
+                [argument] => self.check_conversion(argument, target),
+                _ => {
+                    for argument in arguments {
+                        self.check_single_value(argument);
+                    }
+                    target
+                }
+            };
+        }
+
+        if let Some(builtin) = self.builtin(callee) {
+            return self.check_builtin_call(call, builtin, arguments, spread);
+        }
+
+        let callee_type = self.check_expression(callee);
+        let argument_types = self.argument_types(arguments, spread);
+        let Type::Function(signature) = self.types.get(self.types.underlying(callee_type?)) else {
+            return None;
+        };
+        let signature = signature.clone();
+        self.check_arguments(call, callee, arguments, &argument_types, spread, &signature);
+        match signature.results.as_slice() {
+            [result] => Some(*result),
+            results => {
+                let results = results.to_vec();
+                Some(self.types.intern(Type::Tuple(results)))
+            }
+        }
+    }
+
+    /// Check a call of a builtin, returning its result. A builtin without a result has the
+    /// empty tuple type, like a function without one.
+    fn check_builtin_call(
+        &mut self,
+        call: &Expression,
+        builtin: Builtin,
+        arguments: &[Expression],
+        spread: bool,
+    ) -> Option<TypeId> {
+        let (fewest, most) = builtin.arity();
+        let want = if arguments.len() < fewest {
+            Some(fewest)
+        } else {
+            most.filter(|most| arguments.len() > *most)
+        };
+        if let Some(want) = want {
+            self.report_argument_count(call, arguments, want);
+            return None;
+        }
+        if spread && builtin != Builtin::Append {
+            self.error(
+                SemanticErrorKind::NonVariadicSpread(builtin.name().to_string()),
+                arguments.last().map_or(call.position_end, span),
+            );
+            return None;
+        }
+
+        // make and new take a type rather than a value
+        if let Builtin::Make | Builtin::New = builtin {
+            let target = self.type_operand(&arguments[0]);
+            for size in &arguments[1..] {
+                self.check_size(builtin, size);
+            }
+            let target = target?;
+            if builtin == Builtin::New {
+                return Some(self.types.pointer(target));
+            }
+            let sizes = match self.types.get(self.types.underlying(target)) {
+                // make([]T, length, capacity)
+                Type::Slice(_) => 2..=3,
+                // make(map[K]V, hint), make(chan T, buffer)
+                Type::Map { .. } | Type::Channel { .. } => 1..=2,
+                _ => {
+                    self.invalid_argument(builtin, &arguments[0], target);
+                    return None;
+                }
+            };
+            if !sizes.contains(&arguments.len()) {
+                let want = if arguments.len() < *sizes.start() {
+                    *sizes.start()
+                } else {
+                    *sizes.end()
+                };
+                self.report_argument_count(call, arguments, want);
+                return None;
+            }
+            return Some(target);
+        }
+
+        let types: Vec<Option<TypeId>> = arguments
+            .iter()
+            .map(|argument| self.check_single_value(argument))
+            .collect();
+        let int = self.types.basic(BasicKind::Int);
+        let no_value = self.types.intern(Type::Tuple(Vec::new()));
+        match builtin {
+            Builtin::Len | Builtin::Cap => {
+                let value = types[0]?;
+                let valid = match self.types.get(self.types.underlying(value)) {
+                    Type::Basic(kind) => builtin == Builtin::Len && kind.is_string(),
+                    Type::Array { .. } | Type::Slice(_) | Type::Channel { .. } => true,
+                    Type::Map { .. } => builtin == Builtin::Len,
+                    Type::Pointer(base) => matches!(
+                        self.types.get(self.types.underlying(*base)),
+                        Type::Array { .. }
+                    ),
+                    _ => false,
+                };
+                if !valid {
+                    self.invalid_argument(builtin, &arguments[0], value);
+                    return None;
+                }
+                Some(int)
+            }
+            Builtin::Append => {
+                let slice = types[0]?;
+                let Type::Slice(element) = self.types.get(self.types.underlying(slice)) else {
+                    self.invalid_argument(builtin, &arguments[0], slice);
+                    return None;
+                };
+                let element = *element;
+                let context = "argument to append";
+                if spread {
+                    if arguments.len() != 2 {
+                        self.report_argument_count(call, arguments, 2);
+                        return None;
+                    }
+                    // append(bytes, s...) appends the bytes of a string
+                    let appends_string = self.types.basic_kind(element) == Some(BasicKind::Uint8)
+                        && types[1]
+                            .and_then(|value| self.types.basic_kind(value))
+                            .is_some_and(|kind| kind.is_string());
+                    if let Some(value) = types[1]
+                        && !appends_string
+                    {
+                        self.check_assignable(
+                            &arguments[1],
+                            value,
+                            slice,
+                            context,
+                            span(&arguments[1]),
+                        );
+                    }
+                } else {
+                    for (argument, value) in arguments.iter().zip(&types).skip(1) {
+                        if let Some(value) = *value {
+                            self.check_assignable(
+                                argument,
+                                value,
+                                element,
+                                context,
+                                span(argument),
+                            );
+                        }
+                    }
+                }
+                Some(slice)
+            }
+            Builtin::Clear => {
+                let value = types[0]?;
+                if !matches!(
+                    self.types.get(self.types.underlying(value)),
+                    Type::Map { .. } | Type::Slice(_)
+                ) {
+                    self.invalid_argument(builtin, &arguments[0], value);
+                }
+                Some(no_value)
+            }
+            Builtin::Close => {
+                let channel = types[0]?;
+                self.channel_element(&arguments[0], channel, ChannelOperation::Close);
+                Some(no_value)
+            }
+            Builtin::Complex => {
+                let (real, imaginary) = (types[0]?, types[1]?);
+                if self.types.untyped_kind(real).is_none()
+                    && self.types.untyped_kind(imaginary).is_none()
+                    && real != imaginary
+                {
+                    self.invalid_argument(builtin, &arguments[1], imaginary);
+                    return None;
+                }
+                // An untyped part takes the other's type
+                let (part, argument) = match self.types.untyped_kind(real) {
+                    Some(_) => (imaginary, &arguments[1]),
+                    None => (real, &arguments[0]),
+                };
+                let kind = match self.types.basic_kind(part) {
+                    Some(BasicKind::Float32) => BasicKind::Complex64,
+                    Some(BasicKind::Float64) => BasicKind::Complex128,
+                    Some(kind) if kind.is_untyped() && kind.is_numeric() => BasicKind::Complex128,
+                    _ => {
+                        self.invalid_argument(builtin, argument, part);
+                        return None;
+                    }
+                };
+                Some(self.types.basic(kind))
+            }
+            Builtin::Real | Builtin::Imag => {
+                let value = types[0]?;
+                let kind = match self.types.basic_kind(value) {
+                    Some(BasicKind::Complex64) => BasicKind::Float32,
+                    Some(BasicKind::Complex128) => BasicKind::Float64,
+                    Some(kind) if kind.is_untyped() && kind.is_numeric() => BasicKind::UntypedFloat,
+                    _ => {
+                        self.invalid_argument(builtin, &arguments[0], value);
+                        return None;
+                    }
+                };
+                Some(self.types.basic(kind))
+            }
+            Builtin::Copy => {
+                let (destination, source) = (types[0]?, types[1]?);
+                let Type::Slice(element) = self.types.get(self.types.underlying(destination))
+                else {
+                    self.invalid_argument(builtin, &arguments[0], destination);
+                    return None;
+                };
+                let element = *element;
+                // copy(bytes, s) copies the bytes of a string
+                let valid = match self.types.get(self.types.underlying(source)) {
+                    Type::Slice(source_element) => {
+                        self.types.underlying(*source_element) == self.types.underlying(element)
+                    }
+                    Type::Basic(kind) => {
+                        kind.is_string() && self.types.basic_kind(element) == Some(BasicKind::Uint8)
+                    }
+                    _ => false,
+                };
+                if !valid {
+                    self.invalid_argument(builtin, &arguments[1], source);
+                    return None;
+                }
+                Some(int)
+            }
+            Builtin::Delete => {
+                let map = types[0]?;
+                let Type::Map { key, .. } = self.types.get(self.types.underlying(map)) else {
+                    self.invalid_argument(builtin, &arguments[0], map);
+                    return Some(no_value);
+                };
+                let key = *key;
+                if let Some(value) = types[1] {
+                    let context = "argument to delete";
+                    self.check_assignable(&arguments[1], value, key, context, span(&arguments[1]));
+                }
+                Some(no_value)
+            }
+            Builtin::Max | Builtin::Min => {
+                let mut result = None;
+                for (argument, value) in arguments.iter().zip(&types) {
+                    let value = (*value)?;
+                    let ordered = self.types.basic_kind(value).is_some_and(|kind| {
+                        kind.is_integer() || kind.is_float() || kind.is_string()
+                    });
+                    if !ordered {
+                        self.invalid_argument(builtin, argument, value);
+                        return None;
+                    }
+                    result = Some(match result {
+                        None => value,
+                        Some(result) => self.binary_type(Operator::Plus, result, value),
+                    });
+                }
+                // Typed arguments must all be the same type
+                let result = result?;
+                for (argument, value) in arguments.iter().zip(&types) {
+                    if let Some(value) = *value
+                        && !self.types.assignable(value, result)
+                    {
+                        self.invalid_argument(builtin, argument, value);
+                        return None;
+                    }
+                }
+                Some(result)
+            }
+            Builtin::Panic | Builtin::Print | Builtin::Println => Some(no_value),
+            Builtin::Recover => Some(self.any),
+            Builtin::Make | Builtin::New => unreachable!("handled above"),
+        }
+    }
+
+    fn report_argument_count(&mut self, call: &Expression, arguments: &[Expression], want: usize) {
+        let position = arguments.get(want).map_or(call.position_end, span);
+        self.error(
+            SemanticErrorKind::BuiltinArgumentCount {
+                call: call.to_string(),
+                have: arguments.len(),
+                want,
+            },
+            position,
+        );
+    }
+
+    fn invalid_argument(&mut self, builtin: Builtin, argument: &Expression, value: TypeId) {
+        self.error(
+            SemanticErrorKind::InvalidBuiltinArgument {
+                builtin: builtin.name().to_string(),
+                value: argument.to_string(),
+                value_type: self.types.type_string(value),
+            },
+            span(argument),
+        );
+    }
+
+    /// The type a `make` or `new` argument names, like `int` or `[]string`
+    fn type_operand(&mut self, operand: &Expression) -> Option<TypeId> {
+        match &operand.kind {
+            ExpressionKind::Type(type_expression) => self.type_from_expression(type_expression),
+            ExpressionKind::Identifier(name)
+                if self
+                    .symbols
+                    .use_at(operand.position_start)
+                    .is_some_and(|symbol| self.symbols.symbol(symbol).kind == SymbolKind::Type) =>
+            {
+                let type_id = self.named_type(name, operand.position_start)?;
+                self.nodes.insert(operand.into(), type_id);
+                Some(type_id)
+            }
+            ExpressionKind::Parenthesized(inner) => self.type_operand(inner),
+            // A type from another package can't be known yet
+            ExpressionKind::FieldAccess { .. } => None,
+            _ => {
+                self.check_expression(operand);
+                self.error(
+                    SemanticErrorKind::NotAType(operand.to_string()),
+                    span(operand),
+                );
+                None
+            }
+        }
+    }
+
+    /// A length, capacity or size hint passed to `make`, which must be an integer
+    fn check_size(&mut self, builtin: Builtin, size: &Expression) {
+        let Some(value) = self.check_single_value(size) else {
+            return;
+        };
+        let valid = match self.types.basic_kind(value) {
+            Some(kind) if kind.is_untyped() => kind.is_numeric(),
+            Some(kind) => kind.is_integer(),
+            None => false,
+        };
+        if !valid {
+            self.invalid_argument(builtin, size, value);
+        }
+    }
+
+    /// The type of each value passed to a call, and the span to report it at. `f(g())` passes
+    /// every result of `g`.
+    fn argument_types(
+        &mut self,
+        arguments: &[Expression],
+        spread: bool,
+    ) -> Vec<(Option<TypeId>, Position)> {
+        if let [argument] = arguments
+            && !spread
+        {
+            let span = span(argument);
+            let type_id = self.check_expression(argument);
+            return match type_id.map(|type_id| self.types.get(type_id)) {
+                Some(Type::Tuple(results)) if results.len() > 1 => {
+                    results.iter().map(|result| (Some(*result), span)).collect()
+                }
+                Some(Type::Tuple(_)) => {
+                    self.error(SemanticErrorKind::NoValue(argument.to_string()), span);
+                    vec![(None, span)]
+                }
+                _ => vec![(type_id, span)],
+            };
+        }
+        arguments
+            .iter()
+            .map(|argument| (self.check_single_value(argument), span(argument)))
+            .collect()
+    }
+
+    fn check_arguments(
+        &mut self,
+        call: &Expression,
+        callee: &Expression,
+        arguments: &[Expression],
+        argument_types: &[(Option<TypeId>, Position)],
+        spread: bool,
+        signature: &Signature,
+    ) {
+        let function = callee.to_string();
+        let parameters = &signature.parameters;
+        if spread && !signature.variadic {
+            let position = arguments.last().map_or(call.position_end, span);
+            self.error(SemanticErrorKind::NonVariadicSpread(function), position);
+            return;
+        }
+
+        // Without `...`, the arguments matching a variadic parameter are its elements
+        let fixed = if signature.variadic && !spread {
+            parameters.len() - 1
+        } else {
+            parameters.len()
+        };
+        let enough = argument_types.len() >= fixed;
+        let too_many = argument_types.len() > fixed && (spread || !signature.variadic);
+        if !enough || too_many {
+            let mut have =
+                self.type_list_string(argument_types.iter().map(|(type_id, _)| *type_id));
+            if spread {
+                have.insert_str(have.len() - 1, "...");
+            }
+            let want = self.types.signature_string(&Signature {
+                parameters: parameters.clone(),
+                results: Vec::new(),
+                variadic: signature.variadic,
+            });
+            let (kind, position) = if enough {
+                let (_, position) = argument_types[fixed];
+                let kind = SemanticErrorKind::TooManyArguments {
+                    function,
+                    have,
+                    want,
+                };
+                (kind, position)
+            } else {
+                let kind = SemanticErrorKind::NotEnoughArguments {
+                    function,
+                    have,
+                    want,
+                };
+                (kind, call.position_end)
+            };
+            self.error(kind, position);
+            return;
+        }
+
+        for (index, (argument_type, position)) in argument_types.iter().enumerate() {
+            let parameter = if index < fixed {
+                parameters[index]
+            } else {
+                match self.types.get(parameters[fixed]) {
+                    Type::Slice(element) => *element,
+                    _ => continue,
+                }
+            };
+            let Some(argument_type) = *argument_type else {
+                continue;
+            };
+            // A call spread over several parameters is reported as a whole
+            let argument = match arguments.get(index) {
+                Some(argument) if argument_types.len() == arguments.len() => argument,
+                _ => &arguments[0],
+            };
+            let context = format!("argument to {}", function);
+            self.check_assignable(argument, argument_type, parameter, &context, *position);
+        }
+    }
+
+    /// `(int, string)`, for listing what was passed against what was wanted
+    fn type_list_string(&self, types: impl Iterator<Item = Option<TypeId>>) -> String {
+        let types: Vec<String> = types
+            .map(|type_id| match type_id {
+                Some(type_id) => self.types.type_string(type_id),
+                None => "invalid type".to_string(),
+            })
+            .collect();
+        format!("({})", types.join(", "))
+    }
+
+    /// Report `value` if its type can't be assigned to `target`
+    /// (https://go.dev/ref/spec#Assignability)
+    fn check_assignable(
+        &mut self,
+        value: &Expression,
+        value_type: TypeId,
+        target: TypeId,
+        context: &str,
+        position: Position,
+    ) {
+        if self.types.assignable(value_type, target) {
+            self.check_representable(value, target, position);
+            return;
+        }
+        // Untyped constants implement an interface through their default type
+        let concrete = self.types.default_type(value_type).unwrap_or(value_type);
+        if let Some(missing) = self.types.missing_method(concrete, target) {
+            self.error(
+                SemanticErrorKind::DoesNotImplement {
+                    value: value.to_string(),
+                    value_type: self.types.type_string(value_type),
+                    target_type: self.types.type_string(target),
+                    context: context.to_string(),
+                    reason: format!(
+                        "{} does not implement {} ({})",
+                        self.types.type_string(concrete),
+                        self.types.type_string(target),
+                        missing
+                    ),
+                },
+                position,
+            );
+            return;
+        }
+        self.error(
+            SemanticErrorKind::CannotUse {
+                value: value.to_string(),
+                value_type: self.types.type_string(value_type),
+                target_type: self.types.type_string(target),
+                context: context.to_string(),
+            },
+            position,
+        );
+    }
+
+    /// Check the operand of a conversion to `target`, returning `target`
+    /// (https://go.dev/ref/spec#Conversions)
+    fn check_conversion(&mut self, value: &Expression, target: Option<TypeId>) -> Option<TypeId> {
+        let value_type = self.check_single_value(value);
+        if let (Some(value_type), Some(target)) = (value_type, target) {
+            if self.types.convertible(value_type, target) {
+                self.check_representable(value, target, span(value));
+            } else {
+                self.error(
+                    SemanticErrorKind::CannotConvert {
+                        value: value.to_string(),
+                        value_type: self.types.type_string(value_type),
+                        target_type: self.types.type_string(target),
+                    },
+                    span(value),
+                );
+            }
+        }
+        target
+    }
+
+    /// Report the constant `value` if `target` can't represent it, like 300 as a `byte`
+    /// (https://go.dev/ref/spec#Representability)
+    fn check_representable(&mut self, value: &Expression, target: TypeId, position: Position) {
+        if let Some(constant) = self.constant_value(value) {
+            self.report_unrepresentable(constant, target, position);
+        }
+    }
+
+    fn report_unrepresentable(&mut self, constant: Constant, target: TypeId, position: Position) {
+        let Some(kind) = self.types.basic_kind(target) else {
+            return;
+        };
+        let error = match constant.convert(kind) {
+            Ok(_) => return,
+            Err(Unrepresentable::Overflows) => SemanticErrorKind::ConstantOverflows {
+                constant: constant.to_string(),
+                target_type: self.types.type_string(target),
+            },
+            Err(Unrepresentable::Truncated) => {
+                SemanticErrorKind::ConstantTruncated(constant.to_string())
+            }
+        };
+        self.error(error, position);
+    }
+
+    /// The value of `expression` if it's a constant (https://go.dev/ref/spec#Constants). A typed
+    /// constant its type can't represent has no value, since it's been reported already.
+    fn constant_value(&self, expression: &Expression) -> Option<Constant> {
+        let value = match &expression.kind {
+            ExpressionKind::IntegerLiteral(literal) => Constant::from_integer_literal(literal)?,
+            ExpressionKind::FloatLiteral(literal) => Constant::from_float_literal(literal)?,
+            ExpressionKind::RuneLiteral(literal) => Constant::from_rune_literal(literal)?,
+            ExpressionKind::Identifier(name) => {
+                let symbol = self
+                    .symbols
+                    .symbol(self.symbols.use_at(expression.position_start)?);
+                match (symbol.kind, name.as_str()) {
+                    // A constant declared in the file
+                    (SymbolKind::Constant, _) if symbol.position.is_some() => *self
+                        .constant_values
+                        .get(&self.symbols.use_at(expression.position_start)?)?,
+                    (SymbolKind::Constant, "true") => Constant::Bool(true),
+                    (SymbolKind::Constant, "false") => Constant::Bool(false),
+                    (SymbolKind::Constant, "iota") => Constant::Int(self.iota?.into()),
+                    _ => return None,
+                }
+            }
+            ExpressionKind::Parenthesized(inner) => self.constant_value(inner)?,
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => self.operation_value(expression, left, *operator, right)?,
+            ExpressionKind::Conversion { expression, .. } => self.constant_value(expression)?,
+            ExpressionKind::Unary { operator, operand } => {
+                self.constant_value(operand)?.unary(*operator)?
+            }
+            ExpressionKind::FunctionCall {
+                name, arguments, ..
+            } => match arguments.as_slice() {
+                [argument]
+                    if self
+                        .symbols
+                        .use_at(name.position_start)
+                        .is_some_and(|symbol| {
+                            self.symbols.symbol(symbol).kind == SymbolKind::Type
+                        }) =>
+                {
+                    self.constant_value(argument)?
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        match self.typed_numeric_kind(expression) {
+            Some(kind) => value.convert(kind).ok(),
+            None => Some(value),
+        }
+    }
+
+    /// The value of a constant operation before it takes the operation's type. Untyped operands
+    /// take that type first, except for a shift's count.
+    fn operation_value(
+        &self,
+        operation: &Expression,
+        left: &Expression,
+        operator: Operator,
+        right: &Expression,
+    ) -> Option<Constant> {
+        let mut left = self.constant_value(left)?;
+        let mut right = self.constant_value(right)?;
+        if let Some(kind) = self.typed_numeric_kind(operation) {
+            left = left.convert(kind).ok()?;
+            if !matches!(operator, Operator::LessLess | Operator::GreaterGreater) {
+                right = right.convert(kind).ok()?;
+            }
+        }
+        left.binary(operator, right)
+    }
+
+    /// The kind of `expression`'s type, if it's a typed number
+    fn typed_numeric_kind(&self, expression: &Expression) -> Option<BasicKind> {
+        let kind = self
+            .types
+            .basic_kind(*self.nodes.get(&expression.into())?)?;
+        (kind.is_numeric() && !kind.is_untyped()).then_some(kind)
+    }
+
+    /// https://go.dev/ref/spec#Arithmetic_operators, https://go.dev/ref/spec#Comparison_operators
+    fn binary_type(&self, operator: Operator, left: TypeId, right: TypeId) -> TypeId {
+        match operator {
+            Operator::EqualEqual
+            | Operator::BangEqual
+            | Operator::Less
+            | Operator::LessEqual
+            | Operator::Greater
+            | Operator::GreaterEqual => self.types.basic(BasicKind::UntypedBool),
+            // The right operand of a shift only says how far to shift
+            Operator::LessLess | Operator::GreaterGreater => left,
+            _ => match (
+                self.types.untyped_kind(left),
+                self.types.untyped_kind(right),
+            ) {
+                // Untyped operands take the kind that appears later in int, rune, float
+                (Some(left_kind), Some(right_kind)) => {
+                    if untyped_rank(right_kind) > untyped_rank(left_kind) {
+                        right
+                    } else {
+                        left
+                    }
+                }
+                // An untyped operand is converted to the other operand's type
+                (Some(_), None) => right,
+                _ => left,
+            },
+        }
+    }
+
+    /// The type of the variable, function or constant the identifier at `position` refers to
+    fn identifier_type(&mut self, position: Position) -> Option<TypeId> {
+        let symbol = self.symbols.use_at(position)?;
+        let (name, kind) = {
+            let symbol = self.symbols.symbol(symbol);
+            (symbol.name.clone(), symbol.kind)
+        };
+        match kind {
+            SymbolKind::Variable | SymbolKind::Function => {
+                if !self.symbol_types.contains_key(&symbol)
+                    && let Some(spec) = self.package_variables.get(&symbol).copied()
+                {
+                    // A constant's value may refer to a variable, whose own can't use iota
+                    let iota = self.iota.take();
+                    self.check_variable_spec(spec);
+                    self.iota = iota;
+                }
+                self.symbol_types.get(&symbol).copied()
+            }
+            SymbolKind::Constant if self.symbols.symbol(symbol).position.is_some() => {
+                if !self.symbol_types.contains_key(&symbol)
+                    && let Some(constant) = self.package_constants.get(&symbol).copied()
+                {
+                    self.check_constant_spec(constant);
+                }
+                self.symbol_types.get(&symbol).copied()
+            }
+            SymbolKind::Constant => match name.as_str() {
+                "true" | "false" => Some(self.types.basic(BasicKind::UntypedBool)),
+                "iota" if self.iota.is_none() => {
+                    self.error(SemanticErrorKind::IotaOutsideConstant, position);
+                    None
+                }
+                "iota" => Some(self.types.basic(BasicKind::UntypedInt)),
+                _ => None,
+            },
+            SymbolKind::Nil => Some(self.types.basic(BasicKind::UntypedNil)),
+            SymbolKind::Type | SymbolKind::Package => None,
+        }
+    }
+
+    /// The type a written type refers to, or None if it can't be known without other packages
+    fn type_from_expression(&mut self, type_expression: &TypeExpression) -> Option<TypeId> {
+        let type_id = self.denoted_type(type_expression)?;
+        self.nodes.insert(type_expression.into(), type_id);
+        Some(type_id)
+    }
+
+    fn denoted_type(&mut self, type_expression: &TypeExpression) -> Option<TypeId> {
+        match &type_expression.kind {
+            TypeExpressionKind::Name(name) => self.named_type(name, type_expression.position_start),
+            TypeExpressionKind::Qualified { .. } => None,
+            TypeExpressionKind::Pointer(element) => {
+                let element = self.type_from_expression(element)?;
+                Some(self.types.pointer(element))
+            }
+            TypeExpressionKind::Slice(element) => {
+                let element = self.type_from_expression(element)?;
+                Some(self.types.slice(element))
+            }
+            TypeExpressionKind::Array { length, element } => {
+                let ExpressionKind::IntegerLiteral(literal) = &length.kind else {
+                    return None;
+                };
+                let Some(Constant::Int(length)) = Constant::from_integer_literal(literal) else {
+                    return None;
+                };
+                let length = u64::try_from(length).ok()?;
+                let element = self.type_from_expression(element)?;
+                Some(self.types.intern(Type::Array { length, element }))
+            }
+            TypeExpressionKind::Map { key, value } => {
+                let key = self.type_from_expression(key)?;
+                let value = self.type_from_expression(value)?;
+                Some(self.types.intern(Type::Map { key, value }))
+            }
+            TypeExpressionKind::Channel { direction, element } => {
+                let element = self.type_from_expression(element)?;
+                Some(self.types.intern(Type::Channel {
+                    direction: *direction,
+                    element,
+                }))
+            }
+            TypeExpressionKind::Struct(fields) => {
+                let mut struct_fields = Vec::new();
+                for field in fields {
+                    let type_id = self.type_from_expression(&field.type_expression)?;
+                    if field.names.is_empty() {
+                        struct_fields.push(Field {
+                            name: field.embedded_name()?.to_string(),
+                            type_id,
+                            embedded: true,
+                        });
+                    }
+                    for name in &field.names {
+                        struct_fields.push(Field {
+                            name: name.to_string(),
+                            type_id,
+                            embedded: false,
+                        });
+                    }
+                }
+                Some(self.types.intern(Type::Struct(struct_fields)))
+            }
+            TypeExpressionKind::Interface(methods) => {
+                let methods = methods
+                    .iter()
+                    .map(|method| Method {
+                        name: method.name.clone(),
+                        signature: self.signature(&method.parameters, &method.results),
+                    })
+                    .collect();
+                Some(self.types.interface(methods))
+            }
+            TypeExpressionKind::Function {
+                parameters,
+                results,
+            } => {
+                let signature = self.signature(parameters, results);
+                Some(self.types.function(signature))
+            }
+        }
+    }
+
+    fn signature(&mut self, parameters: &[Parameter], results: &[Parameter]) -> Signature {
+        Signature {
+            parameters: self.parameter_types(parameters),
+            results: self.parameter_types(results),
+            variadic: parameters.last().is_some_and(|last| last.variadic),
+        }
+    }
+
+    /// The type a type name at `position` refers to
+    fn named_type(&self, name: &str, position: Position) -> Option<TypeId> {
+        let symbol = self.symbols.use_at(position)?;
+        if let Some(named) = self.named_types.get(&symbol) {
+            return Some(*named);
+        }
+        if self.symbols.symbol(symbol).position.is_some() {
+            return None;
+        }
+        match name {
+            "any" => Some(self.any),
+            "error" => Some(self.error),
+            _ => self.types.predeclared(name),
+        }
+    }
+
+    fn parameter_types(&mut self, parameters: &[Parameter]) -> Vec<TypeId> {
+        let mut types = Vec::new();
+        for parameter in parameters {
+            // A type the checker doesn't know yet stands in as `any`, keeping the arity right
+            let mut type_id = self
+                .type_from_expression(&parameter.type_expression)
+                .unwrap_or(self.any);
+            if parameter.variadic {
+                type_id = self.types.slice(type_id);
+            }
+            // `a, b int` is two parameters, an unnamed parameter is one
+            for _ in 0..parameter.names.len().max(1) {
+                types.push(type_id);
+            }
+        }
+        types
+    }
+
+    /// The type a variable initialized with a value of type `value` gets, which is the value's
+    /// own type unless it's an untyped constant (https://go.dev/ref/spec#Constants), whose
+    /// `value` must then fit the default type, so `x := 1 << 70` is an error
+    fn default_type(
+        &mut self,
+        value_type: TypeId,
+        value: Option<&Expression>,
+        position: Position,
+    ) -> Option<TypeId> {
+        if self.types.untyped_kind(value_type).is_none() {
+            return Some(value_type);
+        }
+        let default = self.types.default_type(value_type);
+        match (default, value) {
+            (None, _) => self.error(SemanticErrorKind::UntypedNil, position),
+            (Some(default), Some(value)) => self.check_representable(value, default, span(value)),
+            (Some(_), None) => {}
+        }
+        default
+    }
+
+    /// Record the type of the variable declared by the identifier `name`
+    fn define(&mut self, name: &Expression, type_id: TypeId) {
+        if let Some(symbol) = self.symbols.def_at(name.position_start) {
+            self.symbol_types.insert(symbol, type_id);
+            self.nodes.insert(name.into(), type_id);
+        }
+    }
+
+    fn error(&mut self, kind: SemanticErrorKind, position: Position) {
+        self.errors.push(SemanticError::new(kind, position));
+    }
+}
+
+/// Whether a `break` in `statements` ends the loop they're the body of, rather than a loop
+/// nested inside it
+fn has_break(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match &statement.kind {
+        StatementKind::Break => true,
+        StatementKind::Block(body) => has_break(body),
+        StatementKind::If {
+            body, else_branch, ..
+        } => {
+            has_break(body)
+                || else_branch
+                    .as_ref()
+                    .is_some_and(|else_branch| has_break(std::slice::from_ref(else_branch)))
+        }
+        _ => false,
+    })
+}
+
+/// The expression's span on its first line, for reporting it as a whole
+fn span(expression: &Expression) -> Position {
+    let start = expression.position_start;
+    let end = expression.position_end;
+    if start.line == end.line {
+        Position::new(start.line, start.column_start, end.column_end)
+    } else {
+        start
+    }
+}
+
+/// Order of the numeric untyped kinds, where mixing two gives the later one
+fn untyped_rank(kind: BasicKind) -> u8 {
+    match kind {
+        BasicKind::UntypedRune => 1,
+        BasicKind::UntypedFloat => 2,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    fn check_source(source: &str) -> (TypeInfo, Vec<SemanticError>) {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        check(&file)
+    }
+
+    /// The type of the variable named by the first occurrence of `name` after `after` in
+    /// `source`
+    fn type_of_name(info: &TypeInfo, source: &str, after: &str, name: &str) -> Option<String> {
+        let offset = source.find(after).unwrap() + after.find(name).unwrap();
+        let line = source[..offset].matches('\n').count() + 1;
+        let line_start = source[..offset]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let column = offset - line_start;
+        let position = Position::new(line, column, column + name.len());
+        info.type_at(position)
+            .map(|type_id| info.types.type_string(type_id))
+    }
+
+    #[test]
+    fn check_short_variable_declarations_use_default_types() {
+        let source = "package main\n\nfunc main() {\n\tx := 10\n\ts := \"hi\"\n\tf := 1.5\n\tr := 'a'\n\tb := x == 10\n}\n";
+        let (info, errors) = check_source(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            type_of_name(&info, source, "\tx ", "x"),
+            Some("int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\ts ", "s"),
+            Some("string".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tf ", "f"),
+            Some("float64".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tr ", "r"),
+            Some("int32".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tb ", "b"),
+            Some("bool".to_string())
+        );
+    }
+
+    #[test]
+    fn check_untyped_constant_expressions() {
+        let source = "package main\n\nfunc main() {\n\ta := 1 + 2.5\n\tb := 'a' + 1\n\tc := a + 1\n\td := 1 << 2\n}\n";
+        let (info, errors) = check_source(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            type_of_name(&info, source, "\ta ", "a"),
+            Some("float64".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tb ", "b"),
+            Some("int32".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tc ", "c"),
+            Some("float64".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\td ", "d"),
+            Some("int".to_string())
+        );
+    }
+
+    #[test]
+    fn check_var_declarations() {
+        let source = "package main\n\nvar y = g()\n\nvar n, m = pair()\n\nfunc g() string {\n\treturn \"\"\n}\n\nfunc pair() (int, bool) {\n\treturn 0, false\n}\n\nfunc main() {\n\tvar z int64 = 1\n\tvar w = y\n}\n";
+        let (info, errors) = check_source(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(
+            type_of_name(&info, source, "var y", "y"),
+            Some("string".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "var n, m", "n"),
+            Some("int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "var n, m", "m"),
+            Some("bool".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "var z", "z"),
+            Some("int64".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "var w", "w"),
+            Some("string".to_string())
+        );
+    }
+
+    #[test]
+    fn check_parameters_and_range_variables() {
+        let source = "package main\n\nfunc sum(values ...int) {\n\tfor i, v := range values {\n\t\tprintln(i, v)\n\t}\n\tfor k := range \"go\" {\n\t}\n}\n";
+        let (info, errors) = check_source(source);
+        assert!(
+            errors
+                .iter()
+                .all(|error| matches!(error.kind, SemanticErrorKind::UndefinedName(_))),
+            "{:?}",
+            errors
+        );
+        assert_eq!(
+            type_of_name(&info, source, "sum(values", "values"),
+            Some("[]int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "for i", "i"),
+            Some("int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "i, v", "v"),
+            Some("int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "for k", "k"),
+            Some("int".to_string())
+        );
+    }
+
+    #[test]
+    fn check_reports_assignment_mismatches() {
+        let source = "package main\n\nfunc pair() (int, int) {\n\treturn 1, 2\n}\n\nfunc none() {\n}\n\nfunc main() {\n\ta, b := 1\n\tc, d, e := pair()\n\tf := none()\n\tg := nil\n}\n";
+        let (_, errors) = check_source(source);
+        let messages: Vec<String> = errors.iter().map(|error| error.kind.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "assignment mismatch: 2 variables but 1 value",
+                "assignment mismatch: 3 variables but 2 values",
+                "none() (no value) used as value",
+                "use of untyped nil in assignment",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_call_arguments() {
+        let source = "package main\n\nfunc f(a int, b string) {\n}\n\nfunc join(sep string, parts ...string) string {\n\treturn sep\n}\n\nfunc pair() (int, string) {\n\treturn 0, \"\"\n}\n\nfunc main() {\n\tf(1)\n\tf(1, \"a\", 2.5)\n\tf(\"a\", \"b\")\n\tf(pair())\n\tjoin(\",\")\n\tjoin(\",\", \"a\", \"b\")\n\tjoin(\",\", 1)\n\tvar parts []string\n\tjoin(\",\", parts...)\n\tjoin(\",\", \"a\", parts...)\n\tf(1, parts...)\n}\n";
+        let (_, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "not enough arguments in call to f\n\thave (untyped int)\n\twant (int, string)"
+                        .to_string(),
+                    Position::new(15, 4, 5)
+                ),
+                (
+                    "too many arguments in call to f\n\thave (untyped int, untyped string, untyped float)\n\twant (int, string)"
+                        .to_string(),
+                    Position::new(16, 11, 14)
+                ),
+                (
+                    "cannot use \"a\" (type untyped string) as type int in argument to f"
+                        .to_string(),
+                    Position::new(17, 3, 6)
+                ),
+                (
+                    "cannot use 1 (type untyped int) as type string in argument to join"
+                        .to_string(),
+                    Position::new(21, 11, 12)
+                ),
+                (
+                    "too many arguments in call to join\n\thave (untyped string, untyped string, []string...)\n\twant (string, ...string)"
+                        .to_string(),
+                    Position::new(24, 16, 21)
+                ),
+                (
+                    "cannot use ... in call to non-variadic f".to_string(),
+                    Position::new(25, 6, 11)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_return_statements() {
+        let source = "package main\n\nfunc pair() (int, string) {\n\treturn 1\n}\n\nfunc one() int {\n\treturn 1, \"a\"\n}\n\nfunc wrong() string {\n\treturn 1\n}\n\nfunc none() {\n\treturn 1\n}\n\nfunc named() (n int) {\n\treturn\n}\n\nfunc forwards() (int, string) {\n\treturn pair()\n}\n\nfunc bare() int {\n\treturn\n}\n";
+        let (_, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "not enough return values\n\thave (untyped int)\n\twant (int, string)"
+                        .to_string(),
+                    Position::new(4, 1, 7)
+                ),
+                (
+                    "too many return values\n\thave (untyped int, untyped string)\n\twant (int)"
+                        .to_string(),
+                    Position::new(8, 11, 14)
+                ),
+                (
+                    "cannot use 1 (type untyped int) as type string in return statement"
+                        .to_string(),
+                    Position::new(12, 8, 9)
+                ),
+                (
+                    "too many return values\n\thave (untyped int)\n\twant ()".to_string(),
+                    Position::new(16, 8, 9)
+                ),
+                (
+                    "not enough return values\n\thave ()\n\twant (int)".to_string(),
+                    Position::new(28, 1, 7)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_missing_returns() {
+        let source = "package main\n\nfunc f(x int) int {\n\tif x > 0 {\n\t\treturn 1\n\t}\n}\n\nfunc g(x int) int {\n\tif x > 0 {\n\t\treturn 1\n\t} else if x < 0 {\n\t\treturn 2\n\t} else {\n\t\tpanic(\"zero\")\n\t}\n}\n\nfunc h() int {\n\tfor {\n\t}\n}\n\nfunc i() int {\n\tfor {\n\t\tbreak\n\t}\n}\n\nfunc j() int {\n\tfor {\n\t\tfor {\n\t\t\tbreak\n\t\t}\n\t}\n}\n\nfunc k(xs []int) int {\n\tfor range xs {\n\t\treturn 1\n\t}\n}\n\nfunc l() int {\n\t{\n\t\treturn 1\n\t}\n}\n";
+        let (_, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ("missing return".to_string(), Position::new(7, 0, 1)),
+                ("missing return".to_string(), Position::new(28, 0, 1)),
+                ("missing return".to_string(), Position::new(42, 0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_assignability_and_conversions() {
+        let source = "package main\n\nvar s string = 1\n\nvar e error = nil\n\nfunc main() {\n\tvar n int64 = 2\n\tx := 1\n\tx = \"a\"\n\tn = x\n\tvar f float64\n\tf = 1\n\tvar i any = n\n\ti = f\n\tb := []byte(\"hi\")\n\ts = string(b)\n\tn = int64(x)\n\tn = int64(\"a\")\n\tx, f = 1, i\n}\n";
+        let (_, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "cannot use 1 (type untyped int) as type string in variable declaration"
+                        .to_string(),
+                    Position::new(3, 15, 16)
+                ),
+                (
+                    "cannot use \"a\" (type untyped string) as type int in assignment".to_string(),
+                    Position::new(10, 5, 8)
+                ),
+                (
+                    "cannot use x (type int) as type int64 in assignment".to_string(),
+                    Position::new(11, 5, 6)
+                ),
+                (
+                    "cannot convert \"a\" (type untyped string) to type int64".to_string(),
+                    Position::new(19, 11, 14)
+                ),
+                (
+                    "cannot use i (type interface{}) as type float64 in assignment".to_string(),
+                    Position::new(20, 11, 12)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_interface_satisfaction() {
+        let source = "package main\n\ntype Stringer interface {\n\tString() string\n}\n\ntype Celsius float64\n\nfunc (c Celsius) String() string {\n\treturn \"\"\n}\n\ntype Counter int\n\nfunc (c *Counter) String() string {\n\treturn \"\"\n}\n\nfunc (c *Counter) Count() int {\n\treturn 0\n}\n\nfunc (c Counter) Count() int {\n\treturn 0\n}\n\nfunc (s Stringer) Bad() {\n}\n\nfunc main() {\n\tvar s Stringer = Celsius(1)\n\tvar c Counter\n\ts = c\n\tvar p *Counter\n\ts = p\n\ts = 1\n\tvar t string = s.String()\n\tn := c.Count()\n\tn = t\n}\n";
+        let (_, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "method Counter.Count already declared".to_string(),
+                    Position::new(23, 0, 4)
+                ),
+                (
+                    "invalid receiver type Stringer".to_string(),
+                    Position::new(27, 8, 16)
+                ),
+                (
+                    "cannot use c (type Counter) as type Stringer in assignment:\n\tCounter does not implement Stringer (method String has pointer receiver)".to_string(),
+                    Position::new(33, 5, 6)
+                ),
+                (
+                    "cannot use 1 (type untyped int) as type Stringer in assignment:\n\tint does not implement Stringer (missing method String)".to_string(),
+                    Position::new(36, 5, 6)
+                ),
+                (
+                    "cannot use t (type string) as type int in assignment".to_string(),
+                    Position::new(39, 5, 6)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_builtin_calls() {
+        let source = "package main\n\nfunc main() {\n\txs := make([]int, 0, 10)\n\txs = append(xs, 1, 2)\n\tn := len(xs) + cap(xs)\n\tm := make(map[string]int)\n\tdelete(m, \"a\")\n\tp := new(int)\n\tc := make(chan int)\n\tclose(c)\n\tr := recover()\n\tprintln(n, p, r)\n\tlow := min(1, 2.5)\n\t_ = len(1)\n\t_ = make(int)\n\txs = append(xs, \"a\")\n\t_ = len(xs, xs)\n\tf := len\n}\n";
+        let (info, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "invalid argument: 1 (type untyped int) for built-in len".to_string(),
+                    Position::new(15, 9, 10)
+                ),
+                (
+                    "invalid argument: int (type int) for built-in make".to_string(),
+                    Position::new(16, 10, 13)
+                ),
+                (
+                    "cannot use \"a\" (type untyped string) as type int in argument to append"
+                        .to_string(),
+                    Position::new(17, 17, 20)
+                ),
+                (
+                    "too many arguments for len(xs, xs) (expected 1, found 2)".to_string(),
+                    Position::new(18, 13, 15)
+                ),
+                (
+                    "len (built-in function len) must be called".to_string(),
+                    Position::new(19, 6, 9)
+                ),
+            ]
+        );
+        for (name, want) in [
+            ("xs", "[]int"),
+            ("n", "int"),
+            ("m", "map[string]int"),
+            ("p", "*int"),
+            ("c", "chan int"),
+            ("r", "interface{}"),
+            ("low", "float64"),
+        ] {
+            assert_eq!(
+                type_of_name(&info, source, &format!("\t{} :=", name), name),
+                Some(want.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn check_struct_field_access() {
+        let source = "package main\n\ntype Point struct {\n\tX, Y int\n}\n\nfunc (p *Point) Scale(factor int) {\n}\n\ntype Named struct {\n\t*Point\n\tName string\n}\n\nfunc main() {\n\tvar n Named\n\tx := n.X\n\tname := n.Name\n\tscale := n.Scale\n\tvar p *Point\n\ty := p.Y\n\t_ = n.name\n\t_ = n.Nmae\n\t_ = p.Z\n}\n";
+        let (info, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "n.name undefined (type Named has no field or method name, but does have field Name)"
+                        .to_string(),
+                    Position::new(22, 5, 11)
+                ),
+                (
+                    "n.Nmae undefined (type Named has no field or method Nmae, but does have field Name)"
+                        .to_string(),
+                    Position::new(23, 5, 11)
+                ),
+                (
+                    "p.Z undefined (type *Point has no field or method Z)".to_string(),
+                    Position::new(24, 5, 8)
+                ),
+            ]
+        );
+        for (name, want) in [
+            ("x", "int"),
+            ("name", "string"),
+            ("scale", "func(int)"),
+            ("y", "int"),
+        ] {
+            assert_eq!(
+                type_of_name(&info, source, &format!("\t{} :=", name), name),
+                Some(want.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn check_composite_literals_and_unary_operators() {
+        let source = "package main\n\ntype Point struct {\n\tX, Y int\n}\n\nfunc main() {\n\tp := Point{X: 1, Y: 2}\n\tq := &Point{1, 2}\n\tps := []*Point{{1, 2}}\n\tm := map[string]Point{\"a\": {Y: 1}}\n\tx := -p.X\n\ty := *q\n\t_ = Point{Z: 1}\n\t_ = Point{X: \"a\"}\n\t_ = []int{1, \"b\"}\n\t_, _, _, _ = ps, m, x, y\n}\n";
+        let (info, errors) = check_source(source);
+        let messages: Vec<String> = errors.iter().map(|error| error.kind.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "unknown field Z in struct literal of type Point",
+                "cannot use \"a\" (type untyped string) as type int in struct literal",
+                "cannot use \"b\" (type untyped string) as type int in slice literal",
+            ]
+        );
+        for (name, want) in [
+            ("p", "Point"),
+            ("q", "*Point"),
+            ("ps", "[]*Point"),
+            ("m", "map[string]Point"),
+            ("x", "int"),
+            ("y", "Point"),
+        ] {
+            assert_eq!(
+                type_of_name(&info, source, &format!("\t{} :=", name), name),
+                Some(want.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn check_channel_directions() {
+        let source = "package main\n\nfunc main() {\n\tc := make(chan int, 1)\n\tvar send chan<- int = c\n\tvar receive <-chan int = c\n\tsend <- 1\n\tv := <-receive\n\tw, ok := <-c\n\tclose(send)\n\tfor x := range receive {\n\t\t_ = x\n\t}\n\treceive <- v\n\t_ = <-send\n\tclose(receive)\n\tfor range send {\n\t}\n\tc <- \"a\"\n\tv <- 1\n\t_, _ = w, ok\n}\n";
+        let (info, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "invalid operation: cannot send to receive-only channel receive (type <-chan int)"
+                        .to_string(),
+                    Position::new(14, 1, 8)
+                ),
+                (
+                    "invalid operation: cannot receive from send-only channel send (type chan<- int)"
+                        .to_string(),
+                    Position::new(15, 7, 11)
+                ),
+                (
+                    "invalid operation: cannot close receive-only channel receive (type <-chan int)"
+                        .to_string(),
+                    Position::new(16, 7, 14)
+                ),
+                (
+                    "invalid operation: cannot range over send-only channel send (type chan<- int)"
+                        .to_string(),
+                    Position::new(17, 11, 15)
+                ),
+                (
+                    "cannot use \"a\" (type untyped string) as type int in send".to_string(),
+                    Position::new(19, 6, 9)
+                ),
+                (
+                    "invalid operation: cannot send to non-channel v (type int)".to_string(),
+                    Position::new(20, 1, 2)
+                ),
+            ]
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tv :=", "v"),
+            Some("int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "w, ok", "ok"),
+            Some("bool".to_string())
+        );
+    }
+
+    #[test]
+    fn check_go_and_select_statements() {
+        let source = "package main\n\nfunc worker(c chan int) {\n}\n\nfunc first(a <-chan int, b chan string) int {\n\tselect {\n\tcase v := <-a:\n\t\treturn v\n\tcase s, ok := <-b:\n\t\t_, _ = s, ok\n\t\treturn 0\n\t}\n}\n\nfunc main() {\n\tc := make(chan int)\n\tgo worker(c)\n\tselect {\n\tcase c <- \"a\":\n\tdefault:\n\t}\n\t_ = first(c, nil)\n}\n";
+        let (info, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        // A select whose every clause returns is a terminating statement
+        assert_eq!(
+            messages,
+            vec![(
+                "cannot use \"a\" (type untyped string) as type int in send".to_string(),
+                Position::new(20, 11, 14)
+            )]
+        );
+        assert_eq!(
+            type_of_name(&info, source, "case v", "v"),
+            Some("int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "s, ok :=", "s"),
+            Some("string".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "s, ok", "ok"),
+            Some("bool".to_string())
+        );
+    }
+
+    #[test]
+    fn check_function_literals() {
+        let source = "package main\n\nfunc main() {\n\tn := 0\n\tnext := func() int {\n\t\tn++\n\t\treturn n\n\t}\n\tvar f func() int = next\n\tbad := func() string {\n\t\treturn n\n\t}\n\t_ = func() int {\n\t}\n\t_, _ = f, bad\n}\n";
+        let (info, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        // The literal's returns are checked against its own results, not main's
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "cannot use n (type int) as type string in return statement".to_string(),
+                    Position::new(11, 9, 10)
+                ),
+                ("missing return".to_string(), Position::new(14, 1, 2)),
+            ]
+        );
+        assert_eq!(
+            type_of_name(&info, source, "next :=", "next"),
+            Some("func() int".to_string())
+        );
+    }
+
+    #[test]
+    fn check_constant_overflow() {
+        let source = "package main\n\nfunc take(b byte) {\n}\n\nfunc main() {\n\tvar b byte = 300\n\tvar u uint8 = 255\n\tvar i int = 1.5\n\tvar f float32 = 1.5\n\tx := 1 << 70\n\ty := int8(100) * 2\n\tz := byte(300)\n\tvar g float32 = 400000000000000000000000000000000000000.0\n\ttake(256)\n\tvar k int = 'a' + 2.0\n\tw := int8(1) + 300\n\t_, _, _, _, _, _, _, _, _, _ = b, u, i, f, x, y, z, g, k, w\n}\n";
+        let (_, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "constant 300 overflows uint8".to_string(),
+                    Position::new(7, 14, 17)
+                ),
+                (
+                    "constant 1.5 truncated to integer".to_string(),
+                    Position::new(9, 13, 16)
+                ),
+                (
+                    "constant 1180591620717411303424 overflows int".to_string(),
+                    Position::new(11, 6, 13)
+                ),
+                (
+                    "constant 200 overflows int8".to_string(),
+                    Position::new(12, 6, 19)
+                ),
+                (
+                    "constant 300 overflows uint8".to_string(),
+                    Position::new(13, 11, 14)
+                ),
+                (
+                    "constant 4e+38 overflows float32".to_string(),
+                    Position::new(14, 17, 58)
+                ),
+                (
+                    "constant 256 overflows uint8".to_string(),
+                    Position::new(15, 6, 9)
+                ),
+                (
+                    "constant 300 overflows int8".to_string(),
+                    Position::new(17, 16, 19)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_constants() {
+        let source = "package main\n\nconst (\n\ta int8 = 100 * iota\n\tb\n\tc\n)\n\nconst big = 1 << 70\n\nfunc main() {\n\tconst shift = 64\n\tvar x int8 = big >> shift\n\tvar y int = big\n\tz := b\n\tw := iota\n\t_, _, _, _ = x, y, z, w\n}\n";
+        let (info, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        // c repeats `100 * iota` with an iota of 2
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "constant 200 overflows int8".to_string(),
+                    Position::new(4, 10, 20)
+                ),
+                (
+                    "constant 1180591620717411303424 overflows int".to_string(),
+                    Position::new(14, 13, 16)
+                ),
+                (
+                    "cannot use iota outside constant declaration".to_string(),
+                    Position::new(16, 6, 10)
+                ),
+            ]
+        );
+        assert_eq!(
+            type_of_name(&info, source, "z := ", "z"),
+            Some("int8".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "const big", "big"),
+            Some("untyped int".to_string())
+        );
+    }
+
+    #[test]
+    fn check_index_and_slice_expressions() {
+        let source = "package main\n\nfunc main() {\n\ts := make([]int, 1)\n\tvar a [4]int\n\tm := make(map[string]bool)\n\tword := \"go\"\n\tx := 1\n\te := s[0]\n\tb := word[1]\n\tv, ok := m[\"a\"]\n\tt := a[1:3]\n\tsub := word[:1]\n\t_ = x[0]\n\t_ = s[\"a\"]\n\t_ = a[5]\n\t_ = a[1:5]\n\t_ = m[1]\n\t_ = m[1:]\n\t_ = word[0:1:2]\n\t_, _, _, _, _, _ = e, b, v, ok, t, sub\n}\n";
+        let (info, errors) = check_source(source);
+        let messages: Vec<(String, Position)> = errors
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (
+                    "invalid operation: cannot index x (type int)".to_string(),
+                    Position::new(14, 5, 6)
+                ),
+                (
+                    "invalid argument: index \"a\" (type untyped string) must be integer"
+                        .to_string(),
+                    Position::new(15, 7, 10)
+                ),
+                (
+                    "invalid argument: index 5 out of bounds [0:4]".to_string(),
+                    Position::new(16, 7, 8)
+                ),
+                (
+                    "invalid argument: index 5 out of bounds [0:5]".to_string(),
+                    Position::new(17, 9, 10)
+                ),
+                (
+                    "cannot use 1 (type untyped int) as type string in map index".to_string(),
+                    Position::new(18, 7, 8)
+                ),
+                (
+                    "cannot slice m (type map[string]bool)".to_string(),
+                    Position::new(19, 5, 6)
+                ),
+                (
+                    "invalid operation: 3-index slice of string".to_string(),
+                    Position::new(20, 5, 16)
+                ),
+            ]
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\te :=", "e"),
+            Some("int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tb :=", "b"),
+            Some("uint8".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "v, ok", "ok"),
+            Some("bool".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tt :=", "t"),
+            Some("[]int".to_string())
+        );
+        assert_eq!(
+            type_of_name(&info, source, "\tsub :=", "sub"),
+            Some("string".to_string())
+        );
+    }
+}
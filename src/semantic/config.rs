@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use crate::primitives::{
+    diagnostic::{Diagnostic, Severity},
+    errors::semantic::{SemanticError, SemanticErrorKind, SemanticWarning, SemanticWarningKind},
+};
+
+/// A group of related semantic checks, turned off or given a severity together
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Check {
+    // undefined: x, x redeclared in this block, fmt.println
+    Names,
+    // a, b := f() with the wrong count, cannot use x (type int) as type string
+    Assignments,
+    // f(1) with the wrong arguments, len(1), f := len
+    Calls,
+    // int("a")
+    Conversions,
+    // return with the wrong values, or without one at the end of a function
+    Returns,
+    // invalid receivers and methods declared twice
+    Methods,
+    // p.nme
+    Selectors,
+    // x[0] on an int, s["a"] on a slice
+    Indexes,
+    // ch <- 1 on a receive-only or non-channel ch
+    Channels,
+    // type T struct { t T }, var a = b; var b = a
+    Cycles,
+    // var b byte = 300
+    Constants,
+    // err := g() hiding an outer err, which is legal Go and so off unless asked for
+    Shadowing,
+}
+
+impl Check {
+    pub const ALL: &[Check] = &[
+        Check::Names,
+        Check::Assignments,
+        Check::Calls,
+        Check::Conversions,
+        Check::Returns,
+        Check::Methods,
+        Check::Selectors,
+        Check::Indexes,
+        Check::Channels,
+        Check::Cycles,
+        Check::Constants,
+        Check::Shadowing,
+    ];
+
+    /// The check's name, like `names`, which is the code of the diagnostics it reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::Names => "names",
+            Check::Assignments => "assignments",
+            Check::Calls => "calls",
+            Check::Conversions => "conversions",
+            Check::Returns => "returns",
+            Check::Methods => "methods",
+            Check::Selectors => "selectors",
+            Check::Indexes => "indexes",
+            Check::Channels => "channels",
+            Check::Cycles => "cycles",
+            Check::Constants => "constants",
+            Check::Shadowing => "shadowing",
+        }
+    }
+
+    /// The check that finds errors of `kind`
+    pub fn of_error(kind: &SemanticErrorKind) -> Check {
+        match kind {
+            SemanticErrorKind::UndefinedName(_)
+            | SemanticErrorKind::Redeclared(_)
+            | SemanticErrorKind::NotAType(_)
+            | SemanticErrorKind::UnexportedName { .. } => Check::Names,
+            SemanticErrorKind::NoNewVariables
+            | SemanticErrorKind::AssignmentMismatch { .. }
+            | SemanticErrorKind::UntypedNil
+            | SemanticErrorKind::CannotUse { .. }
+            | SemanticErrorKind::DoesNotImplement { .. } => Check::Assignments,
+            SemanticErrorKind::NoValue(_)
+            | SemanticErrorKind::MultipleValues(_)
+            | SemanticErrorKind::NotEnoughArguments { .. }
+            | SemanticErrorKind::TooManyArguments { .. }
+            | SemanticErrorKind::NonVariadicSpread(_)
+            | SemanticErrorKind::BuiltinArgumentCount { .. }
+            | SemanticErrorKind::InvalidBuiltinArgument { .. }
+            | SemanticErrorKind::BuiltinNotCalled(_) => Check::Calls,
+            SemanticErrorKind::CannotConvert { .. } | SemanticErrorKind::NotAnExpression(_) => {
+                Check::Conversions
+            }
+            SemanticErrorKind::NotEnoughReturnValues { .. }
+            | SemanticErrorKind::TooManyReturnValues { .. }
+            | SemanticErrorKind::MissingReturn => Check::Returns,
+            SemanticErrorKind::InvalidReceiver(_) | SemanticErrorKind::MethodRedeclared { .. } => {
+                Check::Methods
+            }
+            SemanticErrorKind::MissingFieldOrMethod { .. }
+            | SemanticErrorKind::UnknownField { .. } => Check::Selectors,
+            SemanticErrorKind::CannotIndex { .. }
+            | SemanticErrorKind::CannotSlice { .. }
+            | SemanticErrorKind::ThreeIndexString
+            | SemanticErrorKind::NonIntegerIndex { .. }
+            | SemanticErrorKind::IndexOutOfBounds { .. } => Check::Indexes,
+            SemanticErrorKind::NotAChannel { .. }
+            | SemanticErrorKind::WrongChannelDirection { .. } => Check::Channels,
+            SemanticErrorKind::InvalidRecursiveType(_)
+            | SemanticErrorKind::InitializationCycle(_) => Check::Cycles,
+            SemanticErrorKind::ConstantOverflows { .. }
+            | SemanticErrorKind::ConstantTruncated(_)
+            | SemanticErrorKind::IotaOutsideConstant => Check::Constants,
+        }
+    }
+
+    /// The check that finds warnings of `kind`
+    pub fn of_warning(kind: &SemanticWarningKind) -> Check {
+        match kind {
+            SemanticWarningKind::Shadowed { .. } => Check::Shadowing,
+        }
+    }
+
+    /// How the check is reported when the config doesn't say: what the Go compiler rejects is
+    /// an error, and the analyses of legal code are off
+    pub fn default_level(self) -> Level {
+        match self {
+            Check::Shadowing => Level::Off,
+            _ => Level::Error,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Level {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// The severity findings are reported at, or None when they aren't reported
+    pub fn severity(self) -> Option<Severity> {
+        match self {
+            Level::Off => None,
+            Level::Warning => Some(Severity::Warning),
+            Level::Error => Some(Severity::Error),
+        }
+    }
+}
+
+/// Which semantic checks run and at what severity, for `check_with_config`. The default
+/// behaves like a compiler front-end; `lenient` suits an analyzer of code that's still being
+/// written, where an undefined name or a missing return is expected rather than fatal.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CheckerConfig {
+    /// Checks whose level isn't their default
+    levels: HashMap<Check, Level>,
+}
+
+impl CheckerConfig {
+    /// Every check the Go compiler makes is reported as a warning
+    pub fn lenient() -> CheckerConfig {
+        Check::ALL
+            .iter()
+            .filter(|check| check.default_level() == Level::Error)
+            .fold(CheckerConfig::default(), |config, check| {
+                config.with(*check, Level::Warning)
+            })
+    }
+
+    pub fn with(mut self, check: Check, level: Level) -> CheckerConfig {
+        self.levels.insert(check, level);
+        self
+    }
+
+    pub fn level(&self, check: Check) -> Level {
+        self.levels
+            .get(&check)
+            .copied()
+            .unwrap_or_else(|| check.default_level())
+    }
+
+    pub fn is_enabled(&self, check: Check) -> bool {
+        self.level(check) != Level::Off
+    }
+
+    /// The diagnostic for `error` at its check's severity, or None when the check is off
+    pub fn error_diagnostic(&self, error: &SemanticError) -> Option<Diagnostic> {
+        let check = Check::of_error(&error.kind);
+        let severity = self.level(check).severity()?;
+        Some(Diagnostic {
+            severity,
+            ..Diagnostic::from(error).with_code(check.name())
+        })
+    }
+
+    /// The diagnostic for `warning` at its check's severity, or None when the check is off
+    pub fn warning_diagnostic(&self, warning: &SemanticWarning) -> Option<Diagnostic> {
+        let check = Check::of_warning(&warning.kind);
+        let severity = self.level(check).severity()?;
+        Some(Diagnostic {
+            severity,
+            ..Diagnostic::from(warning).with_code(check.name())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::semantic::checker::check_with_config;
+
+    fn diagnostics(source: &str, config: &CheckerConfig) -> Vec<String> {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        let (_, diagnostics) = check_with_config(&file, config);
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn configure_checks() {
+        let source = "package main\n\ntype T struct {\n\tt T\n}\n\nfunc f() int {\n\tx := 1\n\tif x > 0 {\n\t\tx := g()\n\t\t_ = x\n\t}\n\t_ = x\n}\n";
+        assert_eq!(
+            diagnostics(source, &CheckerConfig::default()),
+            vec![
+                "10:7: error: undefined: g",
+                "14:0: error: missing return",
+                "3:5: error: invalid recursive type: T refers to itself",
+            ]
+        );
+        assert_eq!(
+            diagnostics(source, &CheckerConfig::lenient()),
+            vec![
+                "10:7: warning: undefined: g",
+                "14:0: warning: missing return",
+                "3:5: warning: invalid recursive type: T refers to itself",
+            ]
+        );
+        let config = CheckerConfig::default()
+            .with(Check::Names, Level::Off)
+            .with(Check::Cycles, Level::Off)
+            .with(Check::Shadowing, Level::Warning);
+        assert_eq!(
+            diagnostics(source, &config),
+            vec![
+                "14:0: error: missing return",
+                "10:2: warning: declaration of x shadows declaration at line 8\n8:1: note: shadowed x declared here",
+            ]
+        );
+    }
+}
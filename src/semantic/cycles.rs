@@ -0,0 +1,292 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{
+    ast::File,
+    declaration::{DeclarationKind, constant_specs},
+    type_expression::{TypeExpression, TypeExpressionKind},
+};
+use crate::primitives::{
+    errors::semantic::{SemanticError, SemanticErrorKind},
+    position::Position,
+};
+use crate::semantic::scope::{ScopeKind, SymbolId, SymbolKind, SymbolTable};
+
+/// Find package level types that contain themselves, like `type T struct { t T }`, and package
+/// level variables and constants whose initialization depends on their own value, like
+/// `var a = b; var b = a`.
+/// Each cycle is reported once, at the first of its members declared in the file.
+pub fn find_cycles(file: &File, symbols: &SymbolTable) -> Vec<SemanticError> {
+    let mut errors = Vec::new();
+
+    // Members of cycles already reported, so each cycle is only reported from its first member
+    let mut reported = HashSet::new();
+
+    let types = type_dependencies(file, symbols);
+    for (symbol, position) in declared_in_order(symbols, &types) {
+        if reported.contains(&symbol) {
+            continue;
+        }
+        if let Some(path) = find_cycle(&types, symbol) {
+            reported.extend(path.iter().copied());
+            errors.push(SemanticError::new(
+                SemanticErrorKind::InvalidRecursiveType(names(symbols, &path)),
+                position,
+            ));
+        }
+    }
+
+    // A cycle through functions alone is just recursion, so only cycles with a variable or a
+    // constant count
+    let initializers = initialization_dependencies(file, symbols);
+    for (symbol, position) in declared_in_order(symbols, &initializers) {
+        let kind = symbols.symbol(symbol).kind;
+        if !matches!(kind, SymbolKind::Variable | SymbolKind::Constant)
+            || reported.contains(&symbol)
+        {
+            continue;
+        }
+        if let Some(path) = find_cycle(&initializers, symbol) {
+            reported.extend(path.iter().copied());
+            errors.push(SemanticError::new(
+                SemanticErrorKind::InitializationCycle(names(symbols, &path)),
+                position,
+            ));
+        }
+    }
+    errors
+}
+
+type Dependencies = HashMap<SymbolId, Vec<SymbolId>>;
+
+/// The package level types each type declaration needs the size of. Pointers, slices, maps,
+/// channels and interfaces have a size of their own whatever they refer to, so they break
+/// cycles.
+fn type_dependencies(file: &File, symbols: &SymbolTable) -> Dependencies {
+    let mut dependencies = HashMap::new();
+    for declaration in &file.decls {
+        let DeclarationKind::Type {
+            name,
+            type_expression,
+        } = &declaration.kind
+        else {
+            continue;
+        };
+        let Some(symbol) = symbols.def_at(name.position_start) else {
+            continue;
+        };
+        let mut referenced = Vec::new();
+        contained_types(type_expression, symbols, &mut referenced);
+        dependencies.insert(symbol, referenced);
+    }
+    // Only types declared in the file can be part of a cycle
+    let declared: HashSet<SymbolId> = dependencies.keys().copied().collect();
+    for referenced in dependencies.values_mut() {
+        referenced.retain(|symbol| declared.contains(symbol));
+    }
+    dependencies
+}
+
+fn contained_types(
+    type_expression: &TypeExpression,
+    symbols: &SymbolTable,
+    referenced: &mut Vec<SymbolId>,
+) {
+    match &type_expression.kind {
+        TypeExpressionKind::Name(_) => {
+            if let Some(symbol) = symbols.use_at(type_expression.position_start) {
+                referenced.push(symbol);
+            }
+        }
+        TypeExpressionKind::Array { element, .. } => contained_types(element, symbols, referenced),
+        TypeExpressionKind::Struct(fields) => {
+            for field in fields {
+                contained_types(&field.type_expression, symbols, referenced);
+            }
+        }
+        TypeExpressionKind::Qualified { .. }
+        | TypeExpressionKind::Pointer(_)
+        | TypeExpressionKind::Slice(_)
+        | TypeExpressionKind::Map { .. }
+        | TypeExpressionKind::Channel { .. }
+        | TypeExpressionKind::Interface(_)
+        | TypeExpressionKind::Function { .. } => {}
+    }
+}
+
+/// The package level variables, constants and functions each package level variable's or
+/// constant's initializer and each function's body refer to
+/// (https://go.dev/ref/spec#Package_initialization)
+fn initialization_dependencies(file: &File, symbols: &SymbolTable) -> Dependencies {
+    let mut uses: Vec<(Position, SymbolId)> = symbols
+        .uses()
+        .filter(|(_, symbol)| {
+            let symbol = symbols.symbol(*symbol);
+            matches!(
+                symbol.kind,
+                SymbolKind::Variable | SymbolKind::Function | SymbolKind::Constant
+            ) && symbols.scope(symbol.scope).kind == ScopeKind::Package
+        })
+        .collect();
+    uses.sort_by_key(|(position, _)| key(*position));
+    let used_between = |start: Position, end: Position| -> Vec<SymbolId> {
+        uses.iter()
+            .filter(|(position, _)| key(start) <= key(*position) && key(*position) <= key(end))
+            .map(|(_, symbol)| *symbol)
+            .collect()
+    };
+
+    let mut dependencies = HashMap::new();
+    for declaration in &file.decls {
+        match &declaration.kind {
+            // Methods aren't declared in the package scope, so they're skipped here
+            DeclarationKind::Function { .. } => {
+                if let Some(symbol) = symbols.def_at(declaration.position_start) {
+                    dependencies.insert(
+                        symbol,
+                        used_between(declaration.position_start, declaration.position_end),
+                    );
+                }
+            }
+            DeclarationKind::Variable { specs, .. } => {
+                for spec in specs {
+                    let referenced = used_between(spec.position_start, spec.position_end);
+                    for name in &spec.names {
+                        if let Some(symbol) = symbols.def_at(name.position_start) {
+                            dependencies.insert(symbol, referenced.clone());
+                        }
+                    }
+                }
+            }
+            // A spec repeating the values before it depends on what they refer to
+            DeclarationKind::Constant { specs, .. } => {
+                for constant in constant_specs(specs) {
+                    let referenced = match (constant.values.first(), constant.values.last()) {
+                        (Some(first), Some(last)) => {
+                            used_between(first.position_start, last.position_end)
+                        }
+                        _ => Vec::new(),
+                    };
+                    for name in &constant.spec.names {
+                        if let Some(symbol) = symbols.def_at(name.position_start) {
+                            dependencies.insert(symbol, referenced.clone());
+                        }
+                    }
+                }
+            }
+            DeclarationKind::Type { .. } => {}
+        }
+    }
+    dependencies
+}
+
+/// A path of dependencies from `start` back to itself, beginning with `start`
+fn find_cycle(dependencies: &Dependencies, start: SymbolId) -> Option<Vec<SymbolId>> {
+    let mut path = vec![start];
+    let mut visited = HashSet::from([start]);
+    if walk(dependencies, start, &mut path, &mut visited) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+fn walk(
+    dependencies: &Dependencies,
+    start: SymbolId,
+    path: &mut Vec<SymbolId>,
+    visited: &mut HashSet<SymbolId>,
+) -> bool {
+    let current = *path.last().unwrap();
+    for next in dependencies.get(&current).into_iter().flatten() {
+        if *next == start {
+            return true;
+        }
+        if visited.insert(*next) {
+            path.push(*next);
+            if walk(dependencies, start, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+    }
+    false
+}
+
+/// The symbols with dependencies, in the order they're declared
+fn declared_in_order(
+    symbols: &SymbolTable,
+    dependencies: &Dependencies,
+) -> Vec<(SymbolId, Position)> {
+    let mut declared: Vec<(SymbolId, Position)> = dependencies
+        .keys()
+        .filter_map(|symbol| Some((*symbol, symbols.symbol(*symbol).position?)))
+        .collect();
+    declared.sort_by_key(|(_, position)| key(*position));
+    declared
+}
+
+fn names(symbols: &SymbolTable, path: &[SymbolId]) -> Vec<String> {
+    path.iter()
+        .map(|symbol| symbols.symbol(*symbol).name.clone())
+        .collect()
+}
+
+fn key(position: Position) -> (usize, usize) {
+    (position.line, position.column_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::semantic::resolver::resolve;
+
+    fn cycles(source: &str) -> Vec<(String, Position)> {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        let (symbols, errors) = resolve(&file);
+        assert!(errors.is_empty(), "{:?}", errors);
+        find_cycles(&file, &symbols)
+            .iter()
+            .map(|error| (error.kind.to_string(), error.position))
+            .collect()
+    }
+
+    #[test]
+    fn recursive_types() {
+        let source = "package main\n\ntype T struct {\n\tt T\n}\n\ntype A struct {\n\tb [2]B\n}\n\ntype B struct{ A }\n\ntype List struct {\n\tnext *List\n\titems []List\n}\n";
+        assert_eq!(
+            cycles(source),
+            vec![
+                (
+                    "invalid recursive type: T refers to itself".to_string(),
+                    Position::new(3, 5, 6)
+                ),
+                (
+                    "invalid recursive type A\n\tA refers to B\n\tB refers to A".to_string(),
+                    Position::new(7, 5, 6)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn initialization_cycles() {
+        let source = "package main\n\nvar a = b + 1\n\nvar b = f()\n\nfunc f() int {\n\treturn a\n}\n\nvar c = c\n\nfunc g() int {\n\treturn g()\n}\n\nvar d = g()\n";
+        assert_eq!(
+            cycles(source),
+            vec![
+                (
+                    "initialization cycle:\n\ta refers to b\n\tb refers to f\n\tf refers to a"
+                        .to_string(),
+                    Position::new(3, 4, 5)
+                ),
+                (
+                    "initialization cycle: c refers to itself".to_string(),
+                    Position::new(11, 4, 5)
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use crate::ast::ast::NodeId;
+use crate::primitives::position::Position;
+use crate::semantic::scope::{SymbolId, SymbolTable};
+use crate::types::types::{TypeId, TypeTable};
+
+/// What the checker learned about a file, for tools to query without checking it again
+/// (like go/types' `Info`). Types live beside the AST rather than in it, keyed by node and
+/// by symbol.
+#[derive(Debug, Clone)]
+pub struct TypeInfo {
+    pub symbols: SymbolTable,
+    pub types: TypeTable,
+    /// The type of every expression and type expression that has one
+    pub(crate) nodes: HashMap<NodeId, TypeId>,
+    /// The type of every variable and function
+    pub(crate) symbol_types: HashMap<SymbolId, TypeId>,
+}
+
+impl TypeInfo {
+    /// The type of an expression, or the type a type expression denotes. Untyped constants
+    /// keep their untyped type, e.g. `10` is an untyped int even when it initializes an
+    /// `int64`.
+    pub fn type_of(&self, node: impl Into<NodeId>) -> Option<TypeId> {
+        self.nodes.get(&node.into()).copied()
+    }
+
+    /// Every typed node, in no particular order
+    pub fn node_types(&self) -> impl Iterator<Item = (NodeId, TypeId)> + '_ {
+        self.nodes.iter().map(|(node, type_id)| (*node, *type_id))
+    }
+
+    /// The symbol each declaring identifier declares. A function is declared by its `func`
+    /// keyword, since its name isn't a node of its own.
+    pub fn defs(&self) -> impl Iterator<Item = (NodeId, SymbolId)> + '_ {
+        self.symbols
+            .defs()
+            .map(|(position, symbol)| (NodeId::identifier(position), symbol))
+    }
+
+    /// The symbol each identifier that isn't a declaration refers to
+    pub fn uses(&self) -> impl Iterator<Item = (NodeId, SymbolId)> + '_ {
+        self.symbols
+            .uses()
+            .map(|(position, symbol)| (NodeId::identifier(position), symbol))
+    }
+
+    /// The symbol an identifier declares or refers to
+    pub fn symbol_of(&self, identifier: impl Into<NodeId>) -> Option<SymbolId> {
+        let position = identifier.into().start;
+        self.symbols
+            .def_at(position)
+            .or_else(|| self.symbols.use_at(position))
+    }
+
+    /// The type of a variable or function
+    pub fn symbol_type(&self, symbol: SymbolId) -> Option<TypeId> {
+        self.symbol_types.get(&symbol).copied()
+    }
+
+    /// The type of the variable or function declared or used by the identifier at `position`
+    pub fn type_at(&self, position: Position) -> Option<TypeId> {
+        self.symbol_type(self.symbol_of(NodeId::identifier(position))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::declaration::DeclarationKind;
+    use crate::ast::statement::StatementKind;
+    use crate::parser::parser::Parser;
+    use crate::semantic::checker::check;
+
+    #[test]
+    fn query_types_defs_and_uses() {
+        let source = "package main\n\nfunc main() {\n\tx := 1.5\n\ty := x\n}\n";
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        let (info, errors) = check(&file);
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let DeclarationKind::Function { body, .. } = &file.decls[0].kind else {
+            unreachable!()
+        };
+        let StatementKind::Assignment { left, right, .. } = &body[1].kind else {
+            unreachable!()
+        };
+        let (y, x) = (&left[0], &right[0]);
+        let type_string = |type_id| info.types.type_string(type_id);
+        assert_eq!(
+            info.type_of(x).map(type_string),
+            Some("float64".to_string())
+        );
+        assert_eq!(
+            info.type_of(y).map(type_string),
+            Some("float64".to_string())
+        );
+
+        let x_symbol = info.symbol_of(x).unwrap();
+        assert_eq!(info.symbols.symbol(x_symbol).name, "x");
+        assert!(
+            info.uses()
+                .any(|(node, symbol)| node == x.into() && symbol == x_symbol)
+        );
+        assert!(info.defs().any(|(node, _)| node == y.into()));
+        assert!(!info.defs().any(|(node, _)| node == x.into()));
+    }
+}
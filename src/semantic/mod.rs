@@ -0,0 +1,9 @@
+pub mod builtins;
+pub mod checker;
+pub mod config;
+pub mod cycles;
+pub mod info;
+pub mod navigation;
+pub mod resolver;
+pub mod scope;
+pub mod shadow;
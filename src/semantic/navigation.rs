@@ -0,0 +1,604 @@
+//! Finding where a file's names are declared, and what they are, for editors to go to and
+//! show
+
+use std::collections::HashSet;
+
+use crate::ast::{
+    ast::{File, NodeId},
+    declaration::{Declaration, DeclarationKind},
+    expression::{Expression, ExpressionKind},
+    outline::base_type_name,
+    statement::{Statement, StatementKind},
+    type_expression::{TypeExpression, TypeExpressionKind},
+};
+use crate::primitives::position::Position;
+use crate::semantic::{
+    checker::check,
+    info::TypeInfo,
+    scope::{ScopeKind, SymbolId, SymbolKind, SymbolTable},
+};
+use crate::types::{
+    lookup::Selection as Selected,
+    types::{Type, TypeId},
+};
+use crate::vet::walk::{Visitor, walk_file};
+
+/// Where the name at `position` in `file` is declared, `position` being anywhere in it: a
+/// variable, constant, type, function or import as the resolver finds it, or a field or
+/// method as the type of the value it's selected from shows. A function or method is declared
+/// at its `func` keyword. None if it isn't declared in the file, like a predeclared name or
+/// another package's.
+pub fn definition_at(file: &File, position: Position) -> Option<Position> {
+    let (info, _) = check(file);
+    if let Some(symbol) = symbol_at(&info.symbols, position) {
+        return info.symbols.symbol(symbol).position;
+    }
+    let (selection, types) = selection_at(file, position);
+    let selection = selection?;
+    let type_name = named_type(&info, info.type_of(selection.value)?)?;
+    if !selection.field_only
+        && let Some(method) = method_declaration(file, &type_name, selection.name)
+    {
+        return Some(method.position_start);
+    }
+    field_declaration(&types, &type_name, selection.name, &mut HashSet::new())
+}
+
+/// What an editor shows for the name under its cursor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hover {
+    /// The name's identifier
+    pub position: Position,
+    /// What the name is, as Go declares it on a line with its type resolved, like
+    /// `var origin Point`, `func scale(int, int) int` or `field X int`
+    pub signature: String,
+    /// The documentation of the declaration it's declared by
+    pub doc: Option<String>,
+}
+
+/// What the name at `position` in `file` is, `position` being anywhere in it: the type of a
+/// variable or function as the checker finds it, a type's declaration, or the field or method
+/// selected from a value. None if there's no name there, or it can't be typed.
+pub fn hover_at(file: &File, position: Position) -> Option<Hover> {
+    let (info, _) = check(file);
+    let (selection, types) = selection_at(file, position);
+    if let Some((identifier, symbol_id)) = identifier_at(&info.symbols, position) {
+        let symbol = info.symbols.symbol(symbol_id);
+        let type_string = || {
+            let type_id = info
+                .symbol_type(symbol_id)
+                .or_else(|| info.type_of(NodeId::identifier(identifier)))?;
+            Some(info.types.type_string(type_id))
+        };
+        let signature = match symbol.kind {
+            SymbolKind::Variable => match type_string() {
+                Some(written) => format!("var {} {}", symbol.name, written),
+                None => format!("var {}", symbol.name),
+            },
+            SymbolKind::Function => {
+                match info.symbol_type(symbol_id).map(|id| info.types.get(id)) {
+                    Some(Type::Function(signature)) => format!(
+                        "func {}{}",
+                        symbol.name,
+                        info.types.signature_string(signature)
+                    ),
+                    _ => format!("func {}", symbol.name),
+                }
+            }
+            SymbolKind::Constant => match type_string() {
+                Some(written) => format!("const {} {}", symbol.name, written),
+                None => format!("const {}", symbol.name),
+            },
+            SymbolKind::Nil => symbol.name.clone(),
+            SymbolKind::Type => match symbol.position.and_then(|at| type_declaration(&types, at)) {
+                Some((name, type_expression)) => format!("type {} {}", name, type_expression),
+                None => format!("type {}", symbol.name),
+            },
+            SymbolKind::Package => match file
+                .imports
+                .iter()
+                .find(|import| symbol.position == Some(import.position_start))
+            {
+                Some(import) => format!("import {}", import),
+                None => format!("package {}", symbol.name),
+            },
+        };
+        // Only declarations at the top level are documented
+        let doc = match (symbol.position, info.symbols.scope(symbol.scope).kind) {
+            (Some(at), ScopeKind::Package) => file
+                .decls
+                .iter()
+                .find(|declaration| {
+                    key(declaration.position_start) <= key(at)
+                        && key(at) <= key(declaration.position_end)
+                })
+                .and_then(|declaration| declaration.doc.clone()),
+            _ => None,
+        };
+        return Some(Hover {
+            position: identifier,
+            signature,
+            doc,
+        });
+    }
+    let selection = selection?;
+    let value_type = info.type_of(selection.value)?;
+    let (signature, doc) = match info
+        .types
+        .lookup_field_or_method(value_type, selection.name)?
+    {
+        Selected::Field(type_id) => (
+            format!(
+                "field {} {}",
+                selection.name,
+                info.types.type_string(type_id)
+            ),
+            None,
+        ),
+        Selected::Method(_) if selection.field_only => return None,
+        Selected::Method(method) => {
+            let type_name = named_type(&info, value_type);
+            let declaration = type_name
+                .as_deref()
+                .and_then(|type_name| method_declaration(file, type_name, selection.name));
+            let receiver = match declaration.map(|declaration| &declaration.kind) {
+                Some(DeclarationKind::Function {
+                    receiver: Some(receiver),
+                    ..
+                }) => receiver.type_expression.to_string(),
+                _ => type_name.unwrap_or_else(|| info.types.type_string(value_type)),
+            };
+            let signature = format!(
+                "func ({}) {}{}",
+                receiver,
+                method.name,
+                info.types.signature_string(&method.signature)
+            );
+            (
+                signature,
+                declaration.and_then(|declaration| declaration.doc.clone()),
+            )
+        }
+    };
+    Some(Hover {
+        position: selection.position,
+        signature,
+        doc,
+    })
+}
+
+/// Every identifier that declares or refers to `symbol`: where it's declared, if it's
+/// declared in the source, and then its uses in the order they're in. These are the
+/// positions the resolver keys them by, so a function is declared at its `func` keyword and
+/// `shapes.New` uses `New` at `shapes`; `name_span` finds the name itself.
+pub fn references(symbols: &SymbolTable, symbol: SymbolId) -> Vec<Position> {
+    let mut uses: Vec<Position> = symbols.uses_of(symbol).collect();
+    uses.sort_by_key(|position| key(*position));
+    symbols
+        .symbol(symbol)
+        .position
+        .into_iter()
+        .chain(uses)
+        .collect()
+}
+
+/// Where `name` is on `line`, the source line of the reference `position`: the first time
+/// it's there as a whole word from `position` on, which is the identifier itself, the name
+/// after a function's `func` or the member named after a package's qualifier. A name the
+/// loader qualifies, like `shapes.New`, is looked for without its package. `position` if it
+/// isn't there.
+pub fn name_span(line: &str, position: Position, name: &str) -> Position {
+    let is_identifier = |c: char| c == '_' || c.is_alphanumeric();
+    let name = name.rsplit('.').next().unwrap_or(name);
+    let start = position.column_start.min(line.len());
+    let rest = line.get(start..).unwrap_or("");
+    let found = rest.match_indices(name).find(|(offset, _)| {
+        let at = start + offset;
+        !line[..at].ends_with(is_identifier) && !line[at + name.len()..].starts_with(is_identifier)
+    });
+    match found {
+        Some((offset, _)) => {
+            Position::new(position.line, start + offset, start + offset + name.len())
+        }
+        None => position,
+    }
+}
+
+/// The symbol whose name is at `position` on `line`, the source line it's on, its name being
+/// where `name_span` finds it. Unlike `symbol_at`, this finds a function from its name as
+/// well as its `func`, and a member of another package from its name after the qualifier.
+pub fn symbol_named_at(symbols: &SymbolTable, line: &str, position: Position) -> Option<SymbolId> {
+    let spans: Vec<(Position, SymbolId)> = symbols
+        .defs()
+        .chain(symbols.uses())
+        .filter(|(reference, _)| reference.line == position.line)
+        .map(|(reference, symbol)| {
+            let name = &symbols.symbol(symbol).name;
+            (name_span(line, reference, name), symbol)
+        })
+        .collect();
+    // Right after a name is only in it if it isn't at the start of another
+    let column = position.column_start;
+    spans
+        .iter()
+        .find(|(span, _)| span.column_start <= column && column < span.column_end)
+        .or_else(|| spans.iter().find(|(span, _)| span.column_end == column))
+        .map(|(_, symbol)| *symbol)
+}
+
+/// The symbol the identifier at `position`, anywhere in it, declares or refers to
+pub fn symbol_at(symbols: &SymbolTable, position: Position) -> Option<SymbolId> {
+    identifier_at(symbols, position).map(|(_, symbol)| symbol)
+}
+
+/// The identifier `position` is in, and the symbol it declares or refers to
+fn identifier_at(symbols: &SymbolTable, position: Position) -> Option<(Position, SymbolId)> {
+    symbols
+        .defs()
+        .chain(symbols.uses())
+        .find(|(identifier, _)| contains(*identifier, position))
+}
+
+/// The name selected from a value that `position` is in, if it's in one, and the types
+/// declared in the file
+fn selection_at(file: &File, position: Position) -> (Option<Selection<'_>>, Vec<&Declaration>) {
+    let mut finder = SelectionFinder {
+        position,
+        selection: None,
+        types: file.decls.iter().collect(),
+    };
+    walk_file(file, &mut finder);
+    (finder.selection, finder.types)
+}
+
+fn key(position: Position) -> (usize, usize) {
+    (position.line, position.column_start)
+}
+
+/// Whether `position` is in the identifier at `identifier`, or just after its end, where
+/// an editor's cursor is once it's typed
+fn contains(identifier: Position, position: Position) -> bool {
+    identifier.line == position.line
+        && identifier.column_start <= position.column_start
+        && position.column_start <= identifier.column_end
+}
+
+/// The name of the type whose fields and methods a value of type `id` has: the named type
+/// itself, or the one a pointer points to
+fn named_type(info: &TypeInfo, id: TypeId) -> Option<String> {
+    match info.types.get(id) {
+        Type::Pointer(element) => named_type(info, *element),
+        Type::Named(named) => Some(info.types.named(*named).name.clone()),
+        _ => None,
+    }
+}
+
+fn method_declaration<'a>(
+    file: &'a File,
+    type_name: &str,
+    method: &str,
+) -> Option<&'a Declaration> {
+    file.decls
+        .iter()
+        .find(|declaration| match &declaration.kind {
+            DeclarationKind::Function {
+                receiver: Some(receiver),
+                name,
+                ..
+            } => name == method && base_type_name(&receiver.type_expression) == type_name,
+            _ => false,
+        })
+}
+
+/// The name and type expression of the one of `types` whose name is at `position`
+fn type_declaration<'a>(
+    types: &[&'a Declaration],
+    position: Position,
+) -> Option<(&'a Expression, &'a TypeExpression)> {
+    types
+        .iter()
+        .find_map(|declaration| match &declaration.kind {
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } if name.position_start == position => Some((name, type_expression)),
+            _ => None,
+        })
+}
+
+/// Where the field `field` of the struct type `type_name`, one of `types`, is named, searching
+/// the types it embeds after its own fields
+fn field_declaration(
+    types: &[&Declaration],
+    type_name: &str,
+    field: &str,
+    searched: &mut HashSet<String>,
+) -> Option<Position> {
+    if !searched.insert(type_name.to_string()) {
+        return None;
+    }
+    let fields = types
+        .iter()
+        .find_map(|declaration| match &declaration.kind {
+            DeclarationKind::Type {
+                name,
+                type_expression,
+            } if is_identifier(name, type_name) => match &type_expression.kind {
+                TypeExpressionKind::Struct(fields) => Some(fields),
+                _ => None,
+            },
+            _ => None,
+        })?;
+    if let Some(name) = fields
+        .iter()
+        .flat_map(|declared| &declared.names)
+        .find(|name| is_identifier(name, field))
+    {
+        return Some(name.position_start);
+    }
+    fields
+        .iter()
+        .filter(|declared| declared.names.is_empty())
+        .find_map(|embedded| {
+            let embedded = base_type_name(&embedded.type_expression);
+            field_declaration(types, &embedded, field, searched)
+        })
+}
+
+fn is_identifier(expression: &Expression, name: &str) -> bool {
+    matches!(&expression.kind, ExpressionKind::Identifier(identifier) if identifier == name)
+}
+
+/// A name selected from a value, `value.name` or the key of a struct literal
+struct Selection<'a> {
+    value: &'a Expression,
+    name: &'a str,
+    /// Where the name is
+    position: Position,
+    /// Whether it can only be a field, as a struct literal's key is
+    field_only: bool,
+}
+
+/// Finds the selection whose name `position` is in, and the types declared in the file,
+/// in function bodies as well as at the top level
+struct SelectionFinder<'a> {
+    position: Position,
+    selection: Option<Selection<'a>>,
+    types: Vec<&'a Declaration>,
+}
+
+impl<'a> Visitor<'a> for SelectionFinder<'a> {
+    fn statement(&mut self, statement: &'a Statement) {
+        if let StatementKind::Declaration(declaration) = &statement.kind
+            && matches!(declaration.kind, DeclarationKind::Type { .. })
+        {
+            self.types.push(declaration);
+        }
+    }
+
+    fn expression(&mut self, expression: &'a Expression) {
+        match &expression.kind {
+            // The field's name ends the expression
+            ExpressionKind::FieldAccess { object, field, .. }
+                if contains(expression.position_end, self.position) =>
+            {
+                self.selection = Some(Selection {
+                    value: object,
+                    name: field,
+                    position: expression.position_end,
+                    field_only: false,
+                });
+            }
+            ExpressionKind::CompositeLiteral { elements, .. } => {
+                let key = elements
+                    .iter()
+                    .filter_map(|element| element.key.as_ref())
+                    .find(|key| contains(key.position_start, self.position));
+                if let Some(Expression {
+                    kind: ExpressionKind::Identifier(name),
+                    position_start,
+                    ..
+                }) = key
+                {
+                    self.selection = Some(Selection {
+                        value: expression,
+                        name,
+                        position: *position_start,
+                        field_only: true,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    const SOURCE: &str = "package main
+
+import \"fmt\"
+
+type Base struct {
+\tID int
+}
+
+type Point struct {
+\tBase
+\tX, Y int
+}
+
+func (p *Point) Move(by int) {
+\tp.X = p.X + by
+}
+
+var origin = Point{X: 0}
+
+func main() {
+\tp := &Point{Y: 1}
+\tp.Move(2)
+\tfmt.Println(p.X, p.ID, origin, len(\"\"))
+}
+";
+
+    /// Where the declaration of the first `name` in the first `context` in the source is, as
+    /// a line and column
+    fn definition(context: &str, name: &str) -> Option<(usize, usize)> {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        let offset = SOURCE.find(context).unwrap() + context.find(name).unwrap();
+        let line = SOURCE[..offset].matches('\n').count() + 1;
+        let column = offset
+            - SOURCE[..offset]
+                .rfind('\n')
+                .map_or(0, |newline| newline + 1);
+        // Anywhere in the name will do
+        let position = Position::new_single_position(line, column + name.len() / 2);
+        definition_at(&file, position).map(|position| (position.line, position.column_start))
+    }
+
+    #[test]
+    fn find_definitions() {
+        // Locals and parameters
+        assert_eq!(definition("p.Move", "p"), Some((21, 1)));
+        assert_eq!(definition("+ by", "by"), Some((14, 21)));
+        // Package level names and imports
+        assert_eq!(definition("origin, len", "origin"), Some((18, 4)));
+        assert_eq!(definition("&Point", "Point"), Some((9, 5)));
+        assert_eq!(definition("fmt.Println", "fmt"), Some((3, 7)));
+        // Fields and methods, through pointers and embedded structs
+        assert_eq!(definition("p.X + by", "X"), Some((11, 1)));
+        assert_eq!(definition("Point{X", "X"), Some((11, 1)));
+        assert_eq!(definition("{Y: 1}", "Y"), Some((11, 4)));
+        assert_eq!(definition("p.ID", "ID"), Some((6, 1)));
+        assert_eq!(definition("p.Move", "Move"), Some((14, 0)));
+        // Names declared elsewhere, or nowhere
+        assert_eq!(definition("fmt.Println", "Println"), None);
+        assert_eq!(definition("len(", "len"), None);
+    }
+
+    #[test]
+    fn hover_names() {
+        let source = "package main
+
+// Point is a place on a grid.
+type Point struct {
+\tX, Y int
+}
+
+// Scale multiplies p's coordinates.
+func (p *Point) Scale(factor int) Point {
+\treturn Point{X: p.X * factor}
+}
+
+// scale gives n times factor.
+func scale(n, factor int) int {
+\treturn n * factor
+}
+
+func main() {
+\tp := &Point{}
+\tvar ok = p.Scale(scale(1, 2)).Y > 0
+\t_ = ok && true
+}
+";
+        let file = Parser::new(source).parse().unwrap();
+        let hover = |context: &str, name: &str| {
+            let offset = source.find(context).unwrap() + context.find(name).unwrap();
+            let line = source[..offset].matches('\n').count() + 1;
+            let column = offset
+                - source[..offset]
+                    .rfind('\n')
+                    .map_or(0, |newline| newline + 1);
+            let hover = hover_at(&file, Position::new_single_position(line, column))?;
+            assert_eq!(
+                (hover.position.line, hover.position.column_start),
+                (line, column)
+            );
+            Some((hover.signature, hover.doc))
+        };
+        let signature = |signature: &str| Some((signature.to_string(), None));
+        let documented =
+            |signature: &str, doc: &str| Some((signature.to_string(), Some(doc.to_string())));
+
+        assert_eq!(hover("p :=", "p"), signature("var p *Point"));
+        assert_eq!(hover("var ok", "ok"), signature("var ok bool"));
+        assert_eq!(hover("p.X", "p"), signature("var p *Point"));
+        assert_eq!(hover("n * factor", "factor"), signature("var factor int"));
+        assert_eq!(
+            hover("scale(1", "scale"),
+            documented("func scale(int, int) int", "scale gives n times factor.")
+        );
+        assert_eq!(
+            hover("&Point", "Point"),
+            documented(
+                "type Point struct { X, Y int }",
+                "Point is a place on a grid."
+            )
+        );
+        assert_eq!(hover("true", "true"), signature("const true untyped bool"));
+        // Fields and methods, by the type of the value they're selected from
+        assert_eq!(hover("p.X", "X"), signature("field X int"));
+        assert_eq!(hover("{X:", "X"), signature("field X int"));
+        assert_eq!(hover(").Y", "Y"), signature("field Y int"));
+        assert_eq!(
+            hover("p.Scale", "Scale"),
+            documented(
+                "func (*Point) Scale(int) Point",
+                "Scale multiplies p's coordinates."
+            )
+        );
+        assert_eq!(hover("package", "package"), None);
+    }
+
+    #[test]
+    fn find_references() {
+        let file = Parser::new(SOURCE).parse().unwrap();
+        let (info, _) = check(&file);
+        let references_of = |line: usize, column: usize| {
+            let symbol = symbol_at(&info.symbols, Position::new_single_position(line, column));
+            references(&info.symbols, symbol.unwrap())
+                .into_iter()
+                .map(|position| {
+                    let text = SOURCE.lines().nth(position.line - 1).unwrap();
+                    let name = info.symbols.symbol(symbol.unwrap()).name.as_str();
+                    let span = name_span(text, position, name);
+                    (span.line, span.column_start, span.column_end)
+                })
+                .collect::<Vec<_>>()
+        };
+        // From the declaration or a use, the declaration comes first
+        assert_eq!(
+            references_of(21, 1),
+            [(21, 1, 2), (22, 1, 2), (23, 13, 14), (23, 18, 19)]
+        );
+        assert_eq!(
+            references_of(9, 5),
+            [(9, 5, 10), (14, 9, 14), (18, 13, 18), (21, 7, 12)]
+        );
+        assert_eq!(references_of(23, 1), [(3, 8, 11), (23, 1, 4)]);
+        assert_eq!(references_of(23, 32), [(23, 32, 35)]);
+        // A function's name, not its `func`
+        let main = symbol_named_at(&info.symbols, "func main() {", Position::new(20, 6, 6));
+        assert_eq!(info.symbols.symbol(main.unwrap()).name, "main");
+        let source = "package main\n\nfunc  run() {}\n";
+        assert_eq!(
+            name_span(
+                source.lines().nth(2).unwrap(),
+                Position::new(3, 0, 4),
+                "run"
+            ),
+            Position::new(3, 6, 9)
+        );
+        assert_eq!(
+            name_span(
+                "\tfmt.Println(fmt.Sprint())",
+                Position::new(1, 13, 16),
+                "Sprint"
+            ),
+            Position::new(1, 17, 23)
+        );
+    }
+}
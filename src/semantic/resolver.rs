@@ -0,0 +1,828 @@
+use crate::ast::{
+    ast::{File, ImportSpec},
+    declaration::{Declaration, DeclarationKind, VariableSpec},
+    expression::{Expression, ExpressionKind},
+    statement::{Statement, StatementKind},
+    type_expression::{TypeExpression, TypeExpressionKind},
+};
+use crate::primitives::{
+    errors::semantic::{SemanticError, SemanticErrorKind},
+    position::Position,
+};
+use crate::semantic::builtins::Builtin;
+use crate::semantic::scope::{ScopeId, ScopeKind, SymbolId, SymbolKind, SymbolTable, is_exported};
+
+/// https://go.dev/ref/spec#Predeclared_identifiers
+const PREDECLARED_TYPES: &[&str] = &[
+    "any",
+    "bool",
+    "byte",
+    "comparable",
+    "complex64",
+    "complex128",
+    "error",
+    "float32",
+    "float64",
+    "int",
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "rune",
+    "string",
+    "uint",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "uintptr",
+];
+const PREDECLARED_CONSTANTS: &[&str] = &["true", "false", "iota"];
+
+/// Build the scopes of `file` and resolve every identifier in it to its declaration. Names
+/// that can't be resolved are reported, and left out of the table's uses.
+pub fn resolve(file: &File) -> (SymbolTable, Vec<SemanticError>) {
+    let mut resolver = Resolver::new();
+    resolver.resolve_file(file);
+    (resolver.table, resolver.errors)
+}
+
+struct Resolver {
+    table: SymbolTable,
+    scope: ScopeId,
+    errors: Vec<SemanticError>,
+    /// A `.` import makes another package's names visible without qualification. Those names
+    /// are unknown until packages are loaded, so undefined names aren't reported in its file.
+    has_dot_import: bool,
+}
+
+impl Resolver {
+    fn new() -> Resolver {
+        let mut table = SymbolTable::new();
+        let universe = table.add_scope(ScopeKind::Universe, None, None);
+        for name in PREDECLARED_TYPES {
+            let _ = table.declare(universe, name, SymbolKind::Type, None);
+        }
+        for name in PREDECLARED_CONSTANTS {
+            let _ = table.declare(universe, name, SymbolKind::Constant, None);
+        }
+        for builtin in Builtin::ALL {
+            let _ = table.declare(universe, builtin.name(), SymbolKind::Function, None);
+        }
+        let _ = table.declare(universe, "nil", SymbolKind::Nil, None);
+        Resolver {
+            table,
+            scope: universe,
+            errors: Vec::new(),
+            has_dot_import: false,
+        }
+    }
+
+    fn resolve_file(&mut self, file: &File) {
+        // Package level names are visible throughout the package, regardless of the order
+        // they're declared in, so they're all declared before any body is resolved
+        let package = self.push_scope(ScopeKind::Package, None);
+        for declaration in &file.decls {
+            self.declare_top_level(declaration);
+        }
+
+        self.push_scope(ScopeKind::File, None);
+        for import in &file.imports {
+            self.declare_import(import, package);
+        }
+        for declaration in &file.decls {
+            self.resolve_declaration(declaration);
+        }
+    }
+
+    fn declare_top_level(&mut self, declaration: &Declaration) {
+        match &declaration.kind {
+            DeclarationKind::Function { receiver, name, .. } => {
+                // `init` functions can't be referred to, so any number of them may be declared.
+                // Methods are only reachable through their receiver's type.
+                if receiver.is_none() && name != "_" && name != "init" {
+                    self.declare(name, SymbolKind::Function, declaration.position_start);
+                }
+            }
+            DeclarationKind::Variable { specs, .. } => {
+                for spec in specs {
+                    self.declare_names(&spec.names, SymbolKind::Variable);
+                }
+            }
+            DeclarationKind::Constant { specs, .. } => {
+                for spec in specs {
+                    self.declare_names(&spec.names, SymbolKind::Constant);
+                }
+            }
+            DeclarationKind::Type { name, .. } => {
+                self.declare_names(std::slice::from_ref(name), SymbolKind::Type);
+            }
+        }
+    }
+
+    fn declare_import(&mut self, import: &ImportSpec, package: ScopeId) {
+        if let Some(name) = import.name.as_deref() {
+            match name {
+                "_" => return,
+                "." => {
+                    self.has_dot_import = true;
+                    return;
+                }
+                _ => {}
+            }
+        }
+        let name = import.local_name();
+        // A file's imports and the package's declarations share one namespace
+        if self.table.lookup_local(package, &name).is_some() {
+            self.error(SemanticErrorKind::Redeclared(name), import.position_start);
+            return;
+        }
+        self.declare(&name, SymbolKind::Package, import.position_start);
+    }
+
+    fn resolve_declaration(&mut self, declaration: &Declaration) {
+        match &declaration.kind {
+            DeclarationKind::Function {
+                receiver,
+                parameters,
+                results,
+                body,
+                ..
+            } => {
+                // The receiver, parameters and named results live in the same block as the
+                // function body's own declarations
+                self.push_scope(
+                    ScopeKind::Function,
+                    Some((declaration.position_start, declaration.position_end)),
+                );
+                for parameter in receiver.iter().chain(parameters).chain(results) {
+                    self.resolve_type(&parameter.type_expression);
+                    self.declare_names(&parameter.names, SymbolKind::Variable);
+                }
+                self.resolve_statements(body);
+                self.pop_scope();
+            }
+            // Package level names were declared up front
+            DeclarationKind::Variable { specs, .. } | DeclarationKind::Constant { specs, .. } => {
+                for spec in specs {
+                    self.resolve_variable_spec(spec);
+                }
+            }
+            DeclarationKind::Type {
+                type_expression, ..
+            } => self.resolve_type(type_expression),
+        }
+    }
+
+    /// Resolve a spec's type and values. Its names aren't in scope until the spec ends, so
+    /// `var x = x` refers to an outer `x`.
+    fn resolve_variable_spec(&mut self, spec: &VariableSpec) {
+        if let Some(type_expression) = &spec.type_expression {
+            self.resolve_type(type_expression);
+        }
+        for value in &spec.values {
+            self.resolve_expression(value);
+        }
+    }
+
+    fn resolve_statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+    }
+
+    /// Resolve the statements of a block, which starts and ends at `extent`
+    fn resolve_block(&mut self, statements: &[Statement], extent: (Position, Position)) {
+        self.push_scope(ScopeKind::Block, Some(extent));
+        self.resolve_statements(statements);
+        self.pop_scope();
+    }
+
+    fn resolve_statement(&mut self, statement: &Statement) {
+        let extent = (statement.position_start, statement.position_end);
+        match &statement.kind {
+            StatementKind::Expression(expression) => self.resolve_expression(expression),
+            StatementKind::Declaration(declaration) => match &declaration.kind {
+                DeclarationKind::Variable { specs, .. } => {
+                    for spec in specs {
+                        self.resolve_variable_spec(spec);
+                        self.declare_names(&spec.names, SymbolKind::Variable);
+                    }
+                }
+                DeclarationKind::Constant { specs, .. } => {
+                    for spec in specs {
+                        self.resolve_variable_spec(spec);
+                        self.declare_names(&spec.names, SymbolKind::Constant);
+                    }
+                }
+                // A type is in scope in its own definition, so `type List struct { next *List }`
+                // refers to itself
+                DeclarationKind::Type {
+                    name,
+                    type_expression,
+                } => {
+                    self.declare_names(std::slice::from_ref(name), SymbolKind::Type);
+                    self.resolve_type(type_expression);
+                }
+                DeclarationKind::Function { .. } => self.resolve_declaration(declaration),
+            },
+            StatementKind::Assignment {
+                left,
+                right,
+                define,
+            } => {
+                for expression in right {
+                    self.resolve_expression(expression);
+                }
+                if *define {
+                    self.resolve_short_variable_declaration(left, statement.position_start);
+                } else {
+                    for expression in left {
+                        self.resolve_expression(expression);
+                    }
+                }
+            }
+            StatementKind::IncDec { target, .. } => self.resolve_expression(target),
+            StatementKind::OpAssign { target, value, .. } => {
+                self.resolve_expression(value);
+                self.resolve_expression(target);
+            }
+            // Names declared by the init statement are in scope in the rest of the loop
+            StatementKind::For {
+                init,
+                condition,
+                post,
+                body,
+            } => {
+                self.push_scope(ScopeKind::Block, Some(extent));
+                if let Some(init) = init {
+                    self.resolve_statement(init);
+                }
+                if let Some(condition) = condition {
+                    self.resolve_expression(condition);
+                }
+                if let Some(post) = post {
+                    self.resolve_statement(post);
+                }
+                self.resolve_block(body, extent);
+                self.pop_scope();
+            }
+            StatementKind::Range {
+                key,
+                value,
+                define,
+                expression,
+                body,
+            } => {
+                // The iteration variables aren't in scope in the range expression itself
+                self.resolve_expression(expression);
+                self.push_scope(ScopeKind::Block, Some(extent));
+                for variable in [key, value].into_iter().flatten() {
+                    match (&variable.kind, define) {
+                        (ExpressionKind::Identifier(name), true) => {
+                            self.declare(name, SymbolKind::Variable, variable.position_start);
+                        }
+                        (ExpressionKind::BlankIdentifier, _) => {}
+                        _ => self.resolve_expression(variable),
+                    }
+                }
+                self.resolve_block(body, extent);
+                self.pop_scope();
+            }
+            StatementKind::Block(body) => self.resolve_block(body, extent),
+            StatementKind::If {
+                init,
+                condition,
+                body,
+                else_branch,
+            } => {
+                // Names declared by the init statement are in scope in every branch
+                self.push_scope(ScopeKind::Block, Some(extent));
+                if let Some(init) = init {
+                    self.resolve_statement(init);
+                }
+                self.resolve_expression(condition);
+                // The body's block is taken to end with the statement, but the else branch's,
+                // added after it, is the innermost of the two within the else branch
+                self.resolve_block(body, extent);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_statement(else_branch);
+                }
+                self.pop_scope();
+            }
+            StatementKind::Return(results) => {
+                for result in results {
+                    self.resolve_expression(result);
+                }
+            }
+            StatementKind::Send { channel, value } => {
+                self.resolve_expression(channel);
+                self.resolve_expression(value);
+            }
+            StatementKind::Defer(call) | StatementKind::Go(call) => self.resolve_expression(call),
+            // Each clause is a block of its own, with `v := <-ch` declared in it
+            StatementKind::Select(cases) => {
+                for (index, case) in cases.iter().enumerate() {
+                    let end = cases
+                        .get(index + 1)
+                        .map_or(statement.position_end, |next| next.position);
+                    self.push_scope(ScopeKind::Block, Some((case.position, end)));
+                    if let Some(communication) = &case.communication {
+                        self.resolve_statement(communication);
+                    }
+                    self.resolve_statements(&case.body);
+                    self.pop_scope();
+                }
+            }
+            // Like a select's, each clause is a block of its own inside the switch's, which
+            // holds what the init statement declares
+            StatementKind::Switch { init, tag, cases } => {
+                self.push_scope(ScopeKind::Block, Some(extent));
+                if let Some(init) = init {
+                    self.resolve_statement(init);
+                }
+                if let Some(tag) = tag {
+                    self.resolve_expression(tag);
+                }
+                for (index, case) in cases.iter().enumerate() {
+                    let end = cases
+                        .get(index + 1)
+                        .map_or(statement.position_end, |next| next.position);
+                    self.push_scope(ScopeKind::Block, Some((case.position, end)));
+                    for expression in case.expressions.iter().flatten() {
+                        self.resolve_expression(expression);
+                    }
+                    self.resolve_statements(&case.body);
+                    self.pop_scope();
+                }
+                self.pop_scope();
+            }
+            StatementKind::Break | StatementKind::Continue | StatementKind::Fallthrough => {}
+        }
+    }
+
+    /// `a, b := ...` declares the names not already declared in the current scope and assigns
+    /// to the rest, as long as at least one is new
+    /// (https://go.dev/ref/spec#Short_variable_declarations)
+    fn resolve_short_variable_declaration(&mut self, names: &[Expression], position: Position) {
+        let mut declared_any = false;
+        for name in names {
+            let ExpressionKind::Identifier(identifier) = &name.kind else {
+                continue;
+            };
+            match self.table.lookup_local(self.scope, identifier) {
+                Some(existing) => self.table.record_use(name.position_start, existing),
+                None => {
+                    self.declare(identifier, SymbolKind::Variable, name.position_start);
+                    declared_any = true;
+                }
+            }
+        }
+        if !declared_any {
+            self.error(SemanticErrorKind::NoNewVariables, position);
+        }
+    }
+
+    fn resolve_expression(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::Identifier(name) => {
+                self.resolve_name(name, expression.position_start);
+            }
+            ExpressionKind::Binary { left, right, .. } => {
+                self.resolve_expression(left);
+                self.resolve_expression(right);
+            }
+            ExpressionKind::FunctionCall {
+                name, arguments, ..
+            } => {
+                self.resolve_expression(name);
+                for argument in arguments {
+                    self.resolve_expression(argument);
+                }
+            }
+            // Which field or method is selected depends on the object's type, so only the
+            // object is resolved here, unless it's a package
+            ExpressionKind::FieldAccess { object, field, .. } => {
+                self.resolve_expression(object);
+                if let ExpressionKind::Identifier(package) = &object.kind {
+                    self.check_exported(
+                        package,
+                        object.position_start,
+                        field,
+                        expression.position_end,
+                    );
+                }
+            }
+            ExpressionKind::Index { object, index } => {
+                self.resolve_expression(object);
+                self.resolve_expression(index);
+            }
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                self.resolve_expression(object);
+                for bound in [low, high, max].into_iter().flatten() {
+                    self.resolve_expression(bound);
+                }
+            }
+            ExpressionKind::Parenthesized(inner) | ExpressionKind::Receive(inner) => {
+                self.resolve_expression(inner)
+            }
+            ExpressionKind::Conversion { target, expression } => {
+                self.resolve_type(target);
+                self.resolve_expression(expression);
+            }
+            ExpressionKind::Unary { operand, .. } => self.resolve_expression(operand),
+            ExpressionKind::CompositeLiteral {
+                type_expression,
+                elements,
+            } => {
+                if let Some(type_expression) = type_expression {
+                    self.resolve_type(type_expression);
+                }
+                // A bare name as a key is a field name, unless the literal is of a map type
+                // literal. A declared map type's keys aren't known to be names until types are.
+                let is_map = matches!(
+                    type_expression,
+                    Some(TypeExpression {
+                        kind: TypeExpressionKind::Map { .. },
+                        ..
+                    })
+                );
+                for element in elements {
+                    match &element.key {
+                        Some(key)
+                            if is_map || !matches!(key.kind, ExpressionKind::Identifier(_)) =>
+                        {
+                            self.resolve_expression(key)
+                        }
+                        _ => {}
+                    }
+                    self.resolve_expression(&element.value);
+                }
+            }
+            ExpressionKind::FunctionLiteral {
+                parameters,
+                results,
+                body,
+            } => {
+                self.push_scope(
+                    ScopeKind::Function,
+                    Some((expression.position_start, expression.position_end)),
+                );
+                for parameter in parameters.iter().chain(results) {
+                    self.resolve_type(&parameter.type_expression);
+                    self.declare_names(&parameter.names, SymbolKind::Variable);
+                }
+                self.resolve_statements(body);
+                self.pop_scope();
+            }
+            ExpressionKind::Type(type_expression) => self.resolve_type(type_expression),
+            ExpressionKind::BlankIdentifier
+            | ExpressionKind::IntegerLiteral(_)
+            | ExpressionKind::FloatLiteral(_)
+            | ExpressionKind::RuneLiteral(_)
+            | ExpressionKind::StringLiteral(_) => {}
+        }
+    }
+
+    fn resolve_type(&mut self, type_expression: &TypeExpression) {
+        match &type_expression.kind {
+            TypeExpressionKind::Name(name) => {
+                let position = type_expression.position_start;
+                if let Some(symbol) = self.resolve_name(name, position)
+                    && self.table.symbol(symbol).kind != SymbolKind::Type
+                {
+                    self.error(SemanticErrorKind::NotAType(name.clone()), position);
+                }
+            }
+            TypeExpressionKind::Qualified { package, name } => {
+                let position = type_expression.position_start;
+                self.resolve_name(package, position);
+                self.check_exported(package, position, name, type_expression.position_end);
+            }
+            TypeExpressionKind::Pointer(element)
+            | TypeExpressionKind::Slice(element)
+            | TypeExpressionKind::Channel { element, .. } => self.resolve_type(element),
+            TypeExpressionKind::Array { length, element } => {
+                self.resolve_expression(length);
+                self.resolve_type(element);
+            }
+            TypeExpressionKind::Map { key, value } => {
+                self.resolve_type(key);
+                self.resolve_type(value);
+            }
+            TypeExpressionKind::Struct(fields) => {
+                for field in fields {
+                    self.resolve_type(&field.type_expression);
+                }
+            }
+            // Parameter names in a method signature aren't declared anywhere
+            TypeExpressionKind::Interface(methods) => {
+                for method in methods {
+                    for parameter in method.parameters.iter().chain(&method.results) {
+                        self.resolve_type(&parameter.type_expression);
+                    }
+                }
+            }
+            // Nor are those of a function type
+            TypeExpressionKind::Function {
+                parameters,
+                results,
+            } => {
+                for parameter in parameters.iter().chain(results) {
+                    self.resolve_type(&parameter.type_expression);
+                }
+            }
+        }
+    }
+
+    /// Record which symbol the name at `position` refers to, reporting it if there's none
+    fn resolve_name(&mut self, name: &str, position: Position) -> Option<SymbolId> {
+        match self.table.lookup(self.scope, name) {
+            Some(symbol) => {
+                self.table.record_use(position, symbol);
+                Some(symbol)
+            }
+            None => {
+                if !self.has_dot_import {
+                    self.error(SemanticErrorKind::UndefinedName(name.to_string()), position);
+                }
+                None
+            }
+        }
+    }
+
+    /// Report `package.name` if `package` is an import and `name` isn't exported. The members of
+    /// other packages aren't known until they're loaded, so there's no declaration to point at
+    /// yet.
+    fn check_exported(
+        &mut self,
+        package: &str,
+        package_position: Position,
+        name: &str,
+        position: Position,
+    ) {
+        let is_package = self
+            .table
+            .use_at(package_position)
+            .is_some_and(|symbol| self.table.symbol(symbol).kind == SymbolKind::Package);
+        if is_package && !is_exported(name) {
+            self.error(
+                SemanticErrorKind::UnexportedName {
+                    package: package.to_string(),
+                    name: name.to_string(),
+                    declaration: None,
+                },
+                position,
+            );
+        }
+    }
+
+    /// Declare each identifier in `names`, skipping `_`
+    fn declare_names(&mut self, names: &[Expression], kind: SymbolKind) {
+        for name in names {
+            if let ExpressionKind::Identifier(identifier) = &name.kind {
+                self.declare(identifier, kind, name.position_start);
+            }
+        }
+    }
+
+    fn declare(&mut self, name: &str, kind: SymbolKind, position: Position) {
+        if self
+            .table
+            .declare(self.scope, name, kind, Some(position))
+            .is_err()
+        {
+            self.error(SemanticErrorKind::Redeclared(name.to_string()), position);
+        }
+    }
+
+    fn push_scope(&mut self, kind: ScopeKind, extent: Option<(Position, Position)>) -> ScopeId {
+        self.scope = self.table.add_scope(kind, Some(self.scope), extent);
+        self.scope
+    }
+
+    fn pop_scope(&mut self) {
+        self.scope = self
+            .table
+            .scope(self.scope)
+            .parent
+            .expect("the universe scope is never popped");
+    }
+
+    fn error(&mut self, kind: SemanticErrorKind, position: Position) {
+        self.errors.push(SemanticError::new(kind, position));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+
+    fn resolve_source(source: &str) -> (SymbolTable, Vec<SemanticError>) {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        resolve(&file)
+    }
+
+    fn error_messages(source: &str) -> Vec<String> {
+        let (_, errors) = resolve_source(source);
+        errors.iter().map(|error| error.kind.to_string()).collect()
+    }
+
+    /// The symbol used by the first occurrence of `name` after `after` in `source`
+    fn symbol_used<'a>(
+        table: &'a SymbolTable,
+        source: &str,
+        after: &str,
+        name: &str,
+    ) -> Option<&'a crate::semantic::scope::Symbol> {
+        let offset = source.find(after).unwrap() + after.find(name).unwrap();
+        let line = source[..offset].matches('\n').count() + 1;
+        let line_start = source[..offset]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let column = offset - line_start;
+        let position = Position::new(line, column, column + name.len());
+        table.use_at(position).map(|symbol| table.symbol(symbol))
+    }
+
+    #[test]
+    fn resolve_package_functions_in_any_order() {
+        let source = "package main\n\nfunc main() {\n\thelper()\n}\n\nfunc helper() {\n}\n";
+        let (table, errors) = resolve_source(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let symbol = symbol_used(&table, source, "helper()", "helper").unwrap();
+        assert_eq!(symbol.kind, SymbolKind::Function);
+        assert_eq!(symbol.position.unwrap().line, 7);
+    }
+
+    #[test]
+    fn resolve_imports() {
+        let source = "package main\n\nimport (\n\t\"fmt\"\n\tstr \"strings\"\n\t_ \"os\"\n)\n\nfunc main() {\n\tfmt.Println(str.ToUpper(os.Args))\n}\n";
+        let (table, errors) = resolve_source(source);
+        assert_eq!(
+            errors,
+            vec![SemanticError::new(
+                SemanticErrorKind::UndefinedName("os".to_string()),
+                Position::new(10, 25, 27),
+            )]
+        );
+        let symbol = symbol_used(&table, source, "fmt.Println", "fmt").unwrap();
+        assert_eq!(symbol.kind, SymbolKind::Package);
+        assert_eq!(symbol.position.unwrap().line, 4);
+        let symbol = symbol_used(&table, source, "str.ToUpper", "str").unwrap();
+        assert_eq!(symbol.position.unwrap().line, 5);
+    }
+
+    #[test]
+    fn resolve_undefined_names() {
+        assert_eq!(
+            error_messages("package main\n\nfunc main() {\n\tmissing(a + 1)\n}\n"),
+            vec!["undefined: missing", "undefined: a"]
+        );
+    }
+
+    #[test]
+    fn resolve_redeclarations() {
+        assert_eq!(
+            error_messages(
+                "package main\n\nimport \"fmt\"\n\nfunc fmt() {\n}\n\nfunc a() {\n}\n\nfunc a() {\n}\n\nfunc init() {\n}\n\nfunc init() {\n}\n"
+            ),
+            vec!["a redeclared in this block", "fmt redeclared in this block"]
+        );
+    }
+
+    #[test]
+    fn resolve_range_variables_in_loop_scope() {
+        let source = "package main\n\nfunc main() {\n\tfor i, v := range items(i) {\n\t\tuse(i, v)\n\t}\n\tuse(v)\n}\n\nfunc items() {\n}\n\nfunc use() {\n}\n";
+        let (table, errors) = resolve_source(source);
+        let messages: Vec<String> = errors.iter().map(|error| error.kind.to_string()).collect();
+        // Neither variable is visible in the range expression or after the loop
+        assert_eq!(messages, vec!["undefined: i", "undefined: v"]);
+        let symbol = symbol_used(&table, source, "use(i, v)", "v").unwrap();
+        assert_eq!(symbol.kind, SymbolKind::Variable);
+        assert_eq!(symbol.position.unwrap().line, 4);
+    }
+
+    #[test]
+    fn resolve_inner_declarations_shadow_outer() {
+        let source = "package main\n\nfunc v() {\n}\n\nfunc main() {\n\tfor v := range []int(nil) {\n\t\tv()\n\t}\n\tv()\n}\n";
+        let (table, errors) = resolve_source(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let inner = symbol_used(&table, source, "\t\tv()", "v").unwrap();
+        assert_eq!(inner.kind, SymbolKind::Variable);
+        let outer = symbol_used(&table, source, "}\n\tv()", "v").unwrap();
+        assert_eq!(outer.kind, SymbolKind::Function);
+    }
+
+    #[test]
+    fn visible_names_in_scopes() {
+        let source = "package main\n\nimport \"fmt\"\n\nvar total int\n\nfunc main() {\n\ta := 1\n\tif b := a; b > 0 {\n\t\tc := b\n\t\tfmt.Println(c)\n\t} else {\n\t\td := 2\n\t\tfmt.Println(d)\n\t}\n\te := a\n\tfmt.Println(e)\n}\n";
+        let (table, errors) = resolve_source(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        // The declared names visible where `after` is, leaving out the predeclared ones
+        let visible = |after: &str| -> Vec<String> {
+            let offset = source.find(after).unwrap();
+            let line = source[..offset].matches('\n').count() + 1;
+            let column = offset - source[..offset].rfind('\n').unwrap() - 1;
+            table
+                .visible_at(Position::new_single_position(line, column))
+                .into_iter()
+                .map(|symbol| table.symbol(symbol))
+                .filter(|symbol| symbol.position.is_some())
+                .map(|symbol| symbol.name.clone())
+                .collect()
+        };
+        assert_eq!(
+            visible("fmt.Println(c)"),
+            ["c", "b", "a", "fmt", "main", "total"]
+        );
+        assert_eq!(
+            visible("fmt.Println(d)"),
+            ["d", "b", "a", "fmt", "main", "total"]
+        );
+        assert_eq!(
+            visible("fmt.Println(e)"),
+            ["a", "e", "fmt", "main", "total"]
+        );
+        assert_eq!(visible("var total"), ["fmt", "main", "total"]);
+    }
+
+    #[test]
+    fn resolve_types_in_conversions() {
+        assert_eq!(
+            error_messages(
+                "package main\n\nfunc main() {\n\tf([]byte(\"a\"), map[string]main(nil), []T(nil))\n}\n\nfunc f() {\n}\n"
+            ),
+            vec!["main is not a type", "undefined: T"]
+        );
+    }
+
+    #[test]
+    fn resolve_ignores_names_after_dot_import() {
+        assert!(
+            error_messages("package main\n\nimport . \"fmt\"\n\nfunc main() {\n\tPrintln(1)\n}\n")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn resolve_short_variable_declarations() {
+        let source = "package main\n\nvar g = 1\n\nfunc main() {\n\ta := g\n\ta, b := 2, a\n\ta, b := 3, 4\n\t_ := 5\n}\n";
+        let (table, errors) = resolve_source(source);
+        let messages: Vec<String> = errors.iter().map(|error| error.kind.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "no new variables on left side of :=",
+                "no new variables on left side of :="
+            ]
+        );
+        // The second `:=` assigns to the existing `a`
+        let reused = symbol_used(&table, source, "a, b := 2", "a").unwrap();
+        assert_eq!(reused.position, Some(Position::new(6, 1, 2)));
+        let global = symbol_used(&table, source, "a := g", "g").unwrap();
+        assert_eq!(global.kind, SymbolKind::Variable);
+    }
+
+    #[test]
+    fn resolve_unexported_names() {
+        let source = "package main\n\nimport \"strings\"\n\nvar b strings.builder\n\nfunc main() {\n\tstrings.toUpper(\"a\")\n\tstrings.ToUpper(\"a\")\n}\n";
+        let (_, errors) = resolve_source(source);
+        let positions: Vec<Position> = errors.iter().map(|error| error.position).collect();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.kind.to_string())
+                .collect::<Vec<String>>(),
+            vec![
+                "name builder not exported by package strings",
+                "name toUpper not exported by package strings"
+            ]
+        );
+        assert_eq!(
+            positions,
+            vec![Position::new(5, 14, 21), Position::new(8, 9, 16)]
+        );
+        assert!(is_exported("Ünicode"));
+        assert!(!is_exported("_Hidden"));
+    }
+
+    #[test]
+    fn resolve_predeclared_identifiers() {
+        let source = "package main\n\nfunc f() error {\n\txs := make([]int, 0)\n\txs = append(xs, len(xs), cap(xs))\n\tm := make(map[string]bool)\n\tdelete(m, \"a\")\n\tcopy(xs, xs)\n\tp := new(int)\n\tprintln(p, true, false, iota)\n\tprint(recover())\n\tpanic(nil)\n}\n";
+        assert_eq!(error_messages(source), Vec::<String>::new());
+
+        // A declaration shadows the builtin
+        let (table, _) = resolve_source("package main\n\nfunc len() int {\n\treturn len()\n}\n");
+        let symbol = table.use_at(Position::new(4, 8, 11)).unwrap();
+        assert!(table.symbol(symbol).position.is_some());
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::primitives::position::Position;
+
+/// Index of a scope in a `SymbolTable`
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct ScopeId(usize);
+
+/// Index of a symbol in a `SymbolTable`
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct SymbolId(usize);
+
+/// The kinds of block that introduce a scope (https://go.dev/ref/spec#Blocks)
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ScopeKind {
+    // Predeclared identifiers like `int` and `true`
+    Universe,
+    // Top level declarations of every file in the package
+    Package,
+    // Imports, which are only visible in the file that declares them
+    File,
+    // Parameters, results and the declarations at the top of the function body
+    Function,
+    // A `{ ... }` block, or the implicit block of an `if`, `for` or `switch`
+    Block,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub parent: Option<ScopeId>,
+    /// Where the scope starts and ends in the source, or None for those that aren't in the
+    /// source, like the universe, or span the whole file, like the package
+    pub extent: Option<(Position, Position)>,
+    symbols: HashMap<String, SymbolId>,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SymbolKind {
+    Type,
+    Constant,
+    // nil
+    Nil,
+    Variable,
+    Function,
+    // The name an import is referred to by
+    Package,
+}
+
+/// Something a name can refer to
+#[derive(Debug, PartialEq, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Where the symbol is declared, or None for predeclared identifiers
+    pub position: Option<Position>,
+    pub scope: ScopeId,
+}
+
+impl Symbol {
+    pub fn is_exported(&self) -> bool {
+        is_exported(&self.name)
+    }
+}
+
+/// Whether `name` can be referred to from other packages, which is when it starts with an
+/// upper case letter (https://go.dev/ref/spec#Exported_identifiers)
+pub fn is_exported(name: &str) -> bool {
+    name.chars().next().is_some_and(char::is_uppercase)
+}
+
+/// Every scope and symbol in a file, and which symbol each identifier refers to
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SymbolTable {
+    scopes: Vec<Scope>,
+    symbols: Vec<Symbol>,
+    /// Declaring identifiers, keyed by the identifier's position
+    defs: HashMap<Position, SymbolId>,
+    /// Identifier uses, keyed by the identifier's position
+    uses: HashMap<Position, SymbolId>,
+}
+
+impl SymbolTable {
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    pub fn add_scope(
+        &mut self,
+        kind: ScopeKind,
+        parent: Option<ScopeId>,
+        extent: Option<(Position, Position)>,
+    ) -> ScopeId {
+        self.scopes.push(Scope {
+            kind,
+            parent,
+            extent,
+            symbols: HashMap::new(),
+        });
+        ScopeId(self.scopes.len() - 1)
+    }
+
+    /// Declare `name` in `scope`, or return the symbol already declared there under that name
+    pub fn declare(
+        &mut self,
+        scope: ScopeId,
+        name: &str,
+        kind: SymbolKind,
+        position: Option<Position>,
+    ) -> Result<SymbolId, SymbolId> {
+        if let Some(existing) = self.scopes[scope.0].symbols.get(name) {
+            return Err(*existing);
+        }
+        self.symbols.push(Symbol {
+            name: name.to_string(),
+            kind,
+            position,
+            scope,
+        });
+        let id = SymbolId(self.symbols.len() - 1);
+        self.scopes[scope.0].symbols.insert(name.to_string(), id);
+        if let Some(position) = position {
+            self.defs.insert(position, id);
+        }
+        Ok(id)
+    }
+
+    /// Find the symbol `name` refers to from inside `scope`, searching outwards
+    pub fn lookup(&self, scope: ScopeId, name: &str) -> Option<SymbolId> {
+        let mut current = Some(scope);
+        while let Some(id) = current {
+            let scope = &self.scopes[id.0];
+            if let Some(symbol) = scope.symbols.get(name) {
+                return Some(*symbol);
+            }
+            current = scope.parent;
+        }
+        None
+    }
+
+    /// Find `name` in `scope` itself, ignoring enclosing scopes
+    pub fn lookup_local(&self, scope: ScopeId, name: &str) -> Option<SymbolId> {
+        self.scopes[scope.0].symbols.get(name).copied()
+    }
+
+    pub fn record_use(&mut self, position: Position, symbol: SymbolId) {
+        self.uses.insert(position, symbol);
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id.0]
+    }
+
+    pub fn symbol(&self, id: SymbolId) -> &Symbol {
+        &self.symbols[id.0]
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = (SymbolId, &Symbol)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| (SymbolId(index), symbol))
+    }
+
+    /// The symbol declared by the identifier at `position`
+    pub fn def_at(&self, position: Position) -> Option<SymbolId> {
+        self.defs.get(&position).copied()
+    }
+
+    /// The symbol used by the identifier at `position`
+    pub fn use_at(&self, position: Position) -> Option<SymbolId> {
+        self.uses.get(&position).copied()
+    }
+
+    /// Every declaring identifier's position and the symbol it declares, in no particular order
+    pub fn defs(&self) -> impl Iterator<Item = (Position, SymbolId)> + '_ {
+        self.defs
+            .iter()
+            .map(|(position, symbol)| (*position, *symbol))
+    }
+
+    /// Every identifier use's position and the symbol it refers to, in no particular order
+    pub fn uses(&self) -> impl Iterator<Item = (Position, SymbolId)> + '_ {
+        self.uses
+            .iter()
+            .map(|(position, symbol)| (*position, *symbol))
+    }
+
+    /// Positions of every identifier referring to `symbol`, in no particular order
+    pub fn uses_of(&self, symbol: SymbolId) -> impl Iterator<Item = Position> + '_ {
+        self.uses
+            .iter()
+            .filter(move |(_, used)| **used == symbol)
+            .map(|(position, _)| *position)
+    }
+
+    /// The innermost scope `position` is in, or the file scope if it's in no function. Scopes
+    /// are added in the order the source is read, each after those around it, so that's the
+    /// last one whose extent holds the position.
+    pub fn scope_at(&self, position: Position) -> Option<ScopeId> {
+        let at = key(position);
+        self.scopes
+            .iter()
+            .rposition(|scope| match scope.extent {
+                Some((start, end)) => key(start) <= at && at <= (end.line, end.column_end),
+                None => scope.kind == ScopeKind::File,
+            })
+            .map(ScopeId)
+    }
+
+    /// Every symbol a name at `position` could refer to, those of the innermost scope first and
+    /// each in order of name, leaving out those a nearer one of the same name shadows. A
+    /// function's or block's own names are only in scope after they're declared, so those
+    /// declared after `position` are left out too.
+    pub fn visible_at(&self, position: Position) -> Vec<SymbolId> {
+        let mut names = HashSet::new();
+        let mut visible = Vec::new();
+        let mut current = self.scope_at(position);
+        while let Some(id) = current {
+            let scope = &self.scopes[id.0];
+            let local = matches!(scope.kind, ScopeKind::Function | ScopeKind::Block);
+            let mut symbols: Vec<SymbolId> = scope
+                .symbols
+                .values()
+                .copied()
+                .filter(|symbol| {
+                    !local
+                        || self
+                            .symbol(*symbol)
+                            .position
+                            .is_some_and(|declared| key(declared) < key(position))
+                })
+                .collect();
+            symbols.sort_by(|a, b| self.symbol(*a).name.cmp(&self.symbol(*b).name));
+            for symbol in symbols {
+                if names.insert(self.symbol(symbol).name.as_str()) {
+                    visible.push(symbol);
+                }
+            }
+            current = scope.parent;
+        }
+        visible
+    }
+}
+
+fn key(position: Position) -> (usize, usize) {
+    (position.line, position.column_start)
+}
@@ -0,0 +1,94 @@
+use crate::primitives::{
+    errors::semantic::{SemanticWarning, SemanticWarningKind},
+    position::Position,
+};
+use crate::semantic::scope::{ScopeKind, SymbolKind, SymbolTable};
+
+/// Find local variables that shadow a variable of an enclosing block, like an `err := g()`
+/// inside an `if` hiding the function's own `err`. As with `go vet`'s shadow analysis, a
+/// declaration is only reported when the shadowed variable is used after it, since that's
+/// where a value assigned to the inner variable was probably meant for the outer one.
+///
+/// This is opt-in: shadowing is legal Go, so nothing here is part of `check`, and
+/// `check_with_config` only runs it when `Check::Shadowing` is turned on.
+pub fn find_shadowing(symbols: &SymbolTable) -> Vec<SemanticWarning> {
+    let mut warnings = Vec::new();
+    for (_, symbol) in symbols.symbols() {
+        let (SymbolKind::Variable, Some(position)) = (symbol.kind, symbol.position) else {
+            continue;
+        };
+        let Some(parent) = symbols.scope(symbol.scope).parent else {
+            continue;
+        };
+        let Some(outer) = symbols.lookup(parent, &symbol.name) else {
+            continue;
+        };
+        let shadowed = symbols.symbol(outer);
+        let Some(outer_position) = shadowed.position else {
+            continue;
+        };
+        // Package level variables are shadowed on purpose often enough to not be worth
+        // reporting
+        if shadowed.kind != SymbolKind::Variable
+            || matches!(
+                symbols.scope(shadowed.scope).kind,
+                ScopeKind::Universe | ScopeKind::Package | ScopeKind::File
+            )
+        {
+            continue;
+        }
+        if symbols.uses_of(outer).any(|used| is_before(position, used)) {
+            warnings.push(SemanticWarning::new(
+                SemanticWarningKind::Shadowed {
+                    name: symbol.name.clone(),
+                    declaration: outer_position,
+                },
+                position,
+            ));
+        }
+    }
+    warnings.sort_by_key(|warning| (warning.position.line, warning.position.column_start));
+    warnings
+}
+
+fn is_before(a: Position, b: Position) -> bool {
+    (a.line, a.column_start) < (b.line, b.column_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::semantic::resolver::resolve;
+
+    fn shadowing(source: &str) -> Vec<(String, Position)> {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        let (symbols, _) = resolve(&file);
+        find_shadowing(&symbols)
+            .iter()
+            .map(|warning| (warning.kind.to_string(), warning.position))
+            .collect()
+    }
+
+    #[test]
+    fn shadowed_variable_used_afterwards() {
+        let source = "package main\n\nfunc f() (int, error) {\n\treturn 0, nil\n}\n\nfunc main() {\n\tn, err := f()\n\tif n > 0 {\n\t\tn, err := f()\n\t\t_ = n\n\t\t_ = err\n\t}\n\t_ = err\n}\n";
+        assert_eq!(
+            shadowing(source),
+            vec![(
+                "declaration of err shadows declaration at line 8".to_string(),
+                Position::new(10, 5, 8)
+            )]
+        );
+    }
+
+    #[test]
+    fn shadowing_not_reported() {
+        // The outer x isn't used after the inner one is declared, and package level
+        // variables aren't reported
+        let source = "package main\n\nvar total int\n\nfunc main() {\n\tx := 1\n\t_ = x\n\tfor {\n\t\tx := 2\n\t\ttotal := x\n\t\t_ = total\n\t}\n\t_ = total\n}\n";
+        assert_eq!(shadowing(source), Vec::new());
+    }
+}
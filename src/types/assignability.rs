@@ -0,0 +1,249 @@
+use crate::ast::type_expression::ChannelDirection;
+use crate::types::types::{BasicKind, Type, TypeId, TypeTable};
+
+impl TypeTable {
+    /// Whether a value of type `value` can be assigned to a variable of type `target`
+    /// (https://go.dev/ref/spec#Assignability). Untyped constants are judged by their kind
+    /// alone, whether their value fits the target is up to the checker.
+    pub fn assignable(&self, value: TypeId, target: TypeId) -> bool {
+        if value == target {
+            return true;
+        }
+        let value_underlying = self.underlying(value);
+        let target_underlying = self.underlying(target);
+
+        if let Some(kind) = self.untyped_kind(value) {
+            return match self.get(target_underlying) {
+                Type::Interface(_) if kind == BasicKind::UntypedNil => true,
+                // The constant is converted to its default type, which must implement the
+                // interface
+                Type::Interface(_) => self
+                    .default_type(value)
+                    .is_some_and(|default| self.implements(default, target)),
+                Type::Basic(target_kind) => untyped_fits(kind, *target_kind),
+                Type::Pointer(_)
+                | Type::Slice(_)
+                | Type::Map { .. }
+                | Type::Channel { .. }
+                | Type::Function(_) => kind == BasicKind::UntypedNil,
+                _ => false,
+            };
+        }
+
+        // A defined type is only assignable to itself, but a type literal like `[]int` is
+        // assignable to any type defined as that literal
+        let either_unnamed = !self.is_named(value) || !self.is_named(target);
+        if value_underlying == target_underlying && either_unnamed {
+            return true;
+        }
+        if let (
+            Type::Channel {
+                direction: ChannelDirection::Both,
+                element: value_element,
+            },
+            Type::Channel {
+                element: target_element,
+                ..
+            },
+        ) = (self.get(value_underlying), self.get(target_underlying))
+            && value_element == target_element
+            && either_unnamed
+        {
+            return true;
+        }
+        matches!(self.get(target_underlying), Type::Interface(_)) && self.implements(value, target)
+    }
+
+    /// Whether `T(x)` is allowed for an `x` of type `value` and a `T` of type `target`
+    /// (https://go.dev/ref/spec#Conversions)
+    pub fn convertible(&self, value: TypeId, target: TypeId) -> bool {
+        if self.assignable(value, target) {
+            return true;
+        }
+        let value_underlying = self.underlying(value);
+        let target_underlying = self.underlying(target);
+        if value_underlying == target_underlying {
+            return true;
+        }
+        match (self.get(value_underlying), self.get(target_underlying)) {
+            (Type::Pointer(value_base), Type::Pointer(target_base)) => {
+                self.underlying(*value_base) == self.underlying(*target_base)
+            }
+            (Type::Basic(value_kind), Type::Basic(target_kind)) => {
+                let value_kind = *value_kind;
+                let target_kind = *target_kind;
+                (is_real(value_kind) && is_real(target_kind))
+                    || (value_kind.is_complex() && target_kind.is_complex())
+                    // string(65) is "A"
+                    || (value_kind.is_integer() && target_kind.is_string())
+                    || (value_kind.is_string() && target_kind.is_string())
+            }
+            (Type::Basic(kind), Type::Slice(element)) => {
+                kind.is_string() && self.is_byte_or_rune(*element)
+            }
+            (Type::Slice(element), Type::Basic(kind)) => {
+                kind.is_string() && self.is_byte_or_rune(*element)
+            }
+            // A slice converts to an array, or a pointer to one, with the same element type
+            (Type::Slice(value_element), Type::Array { element, .. }) => value_element == element,
+            (Type::Slice(value_element), Type::Pointer(base)) => matches!(
+                self.get(self.underlying(*base)),
+                Type::Array { element, .. } if element == value_element
+            ),
+            _ => false,
+        }
+    }
+
+    /// The type an untyped constant of type `id` takes when nothing else decides it, or None
+    /// for `nil` and typed values (https://go.dev/ref/spec#Constants)
+    pub fn default_type(&self, id: TypeId) -> Option<TypeId> {
+        let kind = match self.untyped_kind(id)? {
+            BasicKind::UntypedBool => BasicKind::Bool,
+            BasicKind::UntypedInt => BasicKind::Int,
+            BasicKind::UntypedRune => BasicKind::Int32,
+            BasicKind::UntypedFloat => BasicKind::Float64,
+            BasicKind::UntypedString => BasicKind::String,
+            _ => return None,
+        };
+        Some(self.basic(kind))
+    }
+
+    pub fn untyped_kind(&self, id: TypeId) -> Option<BasicKind> {
+        match self.get(id) {
+            Type::Basic(kind) if kind.is_untyped() => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Predeclared types like `int` are named, type literals like `[]int` aren't
+    /// (https://go.dev/ref/spec#Types)
+    fn is_named(&self, id: TypeId) -> bool {
+        matches!(self.get(id), Type::Basic(_) | Type::Named(_))
+    }
+
+    fn is_byte_or_rune(&self, id: TypeId) -> bool {
+        matches!(
+            self.basic_kind(id),
+            Some(BasicKind::Uint8) | Some(BasicKind::Int32)
+        )
+    }
+}
+
+/// Whether an untyped constant of kind `kind` can become a `target`
+fn untyped_fits(kind: BasicKind, target: BasicKind) -> bool {
+    match kind {
+        BasicKind::UntypedBool => target.is_boolean(),
+        BasicKind::UntypedString => target.is_string(),
+        BasicKind::UntypedNil => false,
+        // Whether the value itself fits, e.g. 1.5 in an int, depends on the value
+        _ => target.is_numeric(),
+    }
+}
+
+/// Integer or floating point
+fn is_real(kind: BasicKind) -> bool {
+    kind.is_integer() || kind.is_float()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::types::{Method, Signature};
+
+    fn stringer(table: &mut TypeTable) -> TypeId {
+        let string = table.basic(BasicKind::String);
+        table.interface(vec![Method {
+            name: "String".to_string(),
+            signature: Signature {
+                parameters: Vec::new(),
+                results: vec![string],
+                variadic: false,
+            },
+        }])
+    }
+
+    #[test]
+    fn assignable_basic_and_untyped() {
+        let table = TypeTable::new();
+        let int = table.basic(BasicKind::Int);
+        let int64 = table.basic(BasicKind::Int64);
+        let string = table.basic(BasicKind::String);
+        assert!(table.assignable(int, int));
+        assert!(!table.assignable(int, int64));
+        assert!(table.assignable(table.basic(BasicKind::UntypedInt), int64));
+        assert!(table.assignable(table.basic(BasicKind::UntypedFloat), int));
+        assert!(!table.assignable(table.basic(BasicKind::UntypedString), int));
+        assert!(!table.assignable(table.basic(BasicKind::UntypedNil), string));
+    }
+
+    #[test]
+    fn assignable_named_and_unnamed() {
+        let mut table = TypeTable::new();
+        let int = table.basic(BasicKind::Int);
+        let ints = table.slice(int);
+        let named = table.new_named("Ints");
+        table.set_underlying(named, ints);
+        let other = table.new_named("Other");
+        table.set_underlying(other, ints);
+        assert!(table.assignable(ints, named));
+        assert!(table.assignable(named, ints));
+        assert!(!table.assignable(named, other));
+        assert!(table.assignable(table.basic(BasicKind::UntypedNil), named));
+        assert!(table.convertible(named, other));
+    }
+
+    #[test]
+    fn assignable_channels() {
+        let mut table = TypeTable::new();
+        let int = table.basic(BasicKind::Int);
+        let mut channel = |direction| {
+            table.intern(Type::Channel {
+                direction,
+                element: int,
+            })
+        };
+        let both = channel(ChannelDirection::Both);
+        let send = channel(ChannelDirection::Send);
+        let receive = channel(ChannelDirection::Receive);
+        assert!(table.assignable(both, send));
+        assert!(table.assignable(both, receive));
+        assert!(!table.assignable(send, both));
+        assert!(!table.assignable(receive, send));
+    }
+
+    #[test]
+    fn assignable_interfaces() {
+        let mut table = TypeTable::new();
+        let any = table.interface(Vec::new());
+        let stringer = stringer(&mut table);
+        let int = table.basic(BasicKind::Int);
+        assert!(table.assignable(int, any));
+        assert!(table.assignable(table.basic(BasicKind::UntypedInt), any));
+        assert!(!table.assignable(int, stringer));
+        assert!(table.assignable(stringer, any));
+        assert!(!table.assignable(any, stringer));
+    }
+
+    #[test]
+    fn convertible_types() {
+        let mut table = TypeTable::new();
+        let int = table.basic(BasicKind::Int);
+        let float = table.basic(BasicKind::Float64);
+        let string = table.basic(BasicKind::String);
+        let byte = table.basic(BasicKind::Uint8);
+        let bytes = table.slice(byte);
+        let ints = table.slice(int);
+        let array = table.intern(Type::Array {
+            length: 4,
+            element: byte,
+        });
+        assert!(table.convertible(int, float));
+        assert!(table.convertible(int, string));
+        assert!(table.convertible(string, bytes));
+        assert!(table.convertible(bytes, string));
+        assert!(table.convertible(bytes, array));
+        assert!(!table.convertible(string, ints));
+        assert!(!table.convertible(string, int));
+        assert!(!table.convertible(table.basic(BasicKind::Bool), int));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::ast::expression::UnaryOperator;
+use crate::lexer::{literal::unquote_rune, token_type::Operator};
+use crate::types::types::BasicKind;
+
+/// The value of a constant expression (https://go.dev/ref/spec#Constants). Go's integer
+/// constants are unbounded, but here they're exact up to 128 bits and anything larger isn't
+/// evaluated. Floats are approximated by an f64.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Constant {
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+}
+
+/// Why a constant can't be represented by a type
+/// (https://go.dev/ref/spec#Representability)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Unrepresentable {
+    // 300 as a byte
+    Overflows,
+    // 1.5 as an int
+    Truncated,
+}
+
+impl Constant {
+    pub fn from_integer_literal(literal: &str) -> Option<Constant> {
+        let digits = literal.replace('_', "").to_ascii_lowercase();
+        let (digits, radix) = if let Some(hex) = digits.strip_prefix("0x") {
+            (hex, 16)
+        } else if let Some(binary) = digits.strip_prefix("0b") {
+            (binary, 2)
+        } else if let Some(octal) = digits.strip_prefix("0o") {
+            (octal, 8)
+        } else if digits.len() > 1 && digits.starts_with('0') {
+            (&digits[1..], 8)
+        } else {
+            (digits.as_str(), 10)
+        };
+        i128::from_str_radix(digits, radix).ok().map(Constant::Int)
+    }
+
+    pub fn from_float_literal(literal: &str) -> Option<Constant> {
+        literal.replace('_', "").parse().ok().map(Constant::Float)
+    }
+
+    /// The code point of a rune literal like `'a'`, `'\n'` or `'é'`
+    pub fn from_rune_literal(literal: &str) -> Option<Constant> {
+        unquote_rune(literal).map(|code_point| Constant::Int(i128::from(code_point)))
+    }
+
+    /// `self operator other`, or None when the operation isn't defined on the operands, divides
+    /// by zero or overflows 128 bits. An integer operand with a float one is treated as a float.
+    pub fn binary(self, operator: Operator, other: Constant) -> Option<Constant> {
+        match (self, other) {
+            (Constant::Bool(left), Constant::Bool(right)) => Some(Constant::Bool(match operator {
+                Operator::AndAnd => left && right,
+                Operator::PipePipe => left || right,
+                Operator::EqualEqual => left == right,
+                Operator::BangEqual => left != right,
+                _ => return None,
+            })),
+            (Constant::Int(left), Constant::Int(right)) => {
+                if let Some(comparison) = compare(operator, left.cmp(&right)) {
+                    return Some(Constant::Bool(comparison));
+                }
+                let value = match operator {
+                    Operator::Plus => left.checked_add(right)?,
+                    Operator::Minus => left.checked_sub(right)?,
+                    Operator::Star => left.checked_mul(right)?,
+                    Operator::Slash => left.checked_div(right)?,
+                    Operator::Percent => left.checked_rem(right)?,
+                    Operator::Ampersand => left & right,
+                    Operator::Pipe => left | right,
+                    Operator::Caret => left ^ right,
+                    Operator::AmpersandCaret => left & !right,
+                    Operator::LessLess => {
+                        let shift = u32::try_from(right).ok().filter(|shift| *shift < 127)?;
+                        let value = left.checked_shl(shift)?;
+                        // Bits shifted out mean the result doesn't fit
+                        (value >> shift == left).then_some(value)?
+                    }
+                    Operator::GreaterGreater => left >> u32::try_from(right).ok()?.min(127),
+                    _ => return None,
+                };
+                Some(Constant::Int(value))
+            }
+            (Constant::Bool(_), _) | (_, Constant::Bool(_)) => None,
+            (left, right) => {
+                let (left, right) = (left.as_float(), right.as_float());
+                if let Some(comparison) = compare(operator, left.partial_cmp(&right)?) {
+                    return Some(Constant::Bool(comparison));
+                }
+                let value = match operator {
+                    Operator::Plus => left + right,
+                    Operator::Minus => left - right,
+                    Operator::Star => left * right,
+                    Operator::Slash if right != 0.0 => left / right,
+                    _ => return None,
+                };
+                Some(Constant::Float(value))
+            }
+        }
+    }
+
+    /// `operator self`, or None when the operation isn't defined on the operand or overflows
+    /// 128 bits. Taking an address isn't an operation on a constant.
+    pub fn unary(self, operator: UnaryOperator) -> Option<Constant> {
+        Some(match (operator, self) {
+            (UnaryOperator::Plus, Constant::Int(_) | Constant::Float(_)) => self,
+            (UnaryOperator::Minus, Constant::Int(value)) => Constant::Int(value.checked_neg()?),
+            (UnaryOperator::Minus, Constant::Float(value)) => Constant::Float(-value),
+            (UnaryOperator::Not, Constant::Bool(value)) => Constant::Bool(!value),
+            (UnaryOperator::Complement, Constant::Int(value)) => Constant::Int(!value),
+            _ => return None,
+        })
+    }
+
+    /// The value as a `kind`, or why `kind` can't represent it. Values of other kinds, like a
+    /// bool as an int, are left to the conversion rules to reject.
+    pub fn convert(self, kind: BasicKind) -> Result<Constant, Unrepresentable> {
+        if kind.is_integer() && !kind.is_untyped() {
+            let value = match self {
+                Constant::Int(value) => value,
+                Constant::Float(value) if value.fract() != 0.0 => {
+                    return Err(Unrepresentable::Truncated);
+                }
+                Constant::Float(value) if value.abs() < 1e38 => value as i128,
+                Constant::Float(_) => return Err(Unrepresentable::Overflows),
+                Constant::Bool(_) => return Ok(self),
+            };
+            let (min, max) = integer_range(kind);
+            if value < min || value > max {
+                return Err(Unrepresentable::Overflows);
+            }
+            return Ok(Constant::Int(value));
+        }
+        if kind.is_float() && !kind.is_untyped() {
+            let value = match self {
+                Constant::Bool(_) => return Ok(self),
+                other => other.as_float(),
+            };
+            let max = match kind {
+                BasicKind::Float32 => f32::MAX as f64,
+                _ => f64::MAX,
+            };
+            if !value.is_finite() || value.abs() > max {
+                return Err(Unrepresentable::Overflows);
+            }
+            let value = match kind {
+                BasicKind::Float32 => value as f32 as f64,
+                _ => value,
+            };
+            return Ok(Constant::Float(value));
+        }
+        Ok(self)
+    }
+
+    fn as_float(self) -> f64 {
+        match self {
+            Constant::Int(value) => value as f64,
+            Constant::Float(value) => value,
+            Constant::Bool(_) => f64::NAN,
+        }
+    }
+}
+
+impl std::fmt::Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constant::Bool(value) => write!(f, "{}", value),
+            Constant::Int(value) => write!(f, "{}", value),
+            // Go's style for large and tiny values, like 1e+100
+            Constant::Float(value) if *value != 0.0 && !(1e-6..1e21).contains(&value.abs()) => {
+                let formatted = format!("{:e}", value);
+                match formatted.split_once('e') {
+                    Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+                        write!(f, "{}e+{}", mantissa, exponent)
+                    }
+                    _ => write!(f, "{}", formatted),
+                }
+            }
+            Constant::Float(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// The result of a comparison operator, or None for other operators
+fn compare(operator: Operator, ordering: std::cmp::Ordering) -> Option<bool> {
+    use std::cmp::Ordering;
+    Some(match operator {
+        Operator::EqualEqual => ordering == Ordering::Equal,
+        Operator::BangEqual => ordering != Ordering::Equal,
+        Operator::Less => ordering == Ordering::Less,
+        Operator::LessEqual => ordering != Ordering::Greater,
+        Operator::Greater => ordering == Ordering::Greater,
+        Operator::GreaterEqual => ordering != Ordering::Less,
+        _ => return None,
+    })
+}
+
+/// The smallest and largest values of a sized integer kind. `int`, `uint` and `uintptr` are
+/// 64 bits, as on every platform Go programs here are run on.
+fn integer_range(kind: BasicKind) -> (i128, i128) {
+    let (bits, signed) = match kind {
+        BasicKind::Int8 => (8, true),
+        BasicKind::Int16 => (16, true),
+        BasicKind::Int32 => (32, true),
+        BasicKind::Int | BasicKind::Int64 => (64, true),
+        BasicKind::Uint8 => (8, false),
+        BasicKind::Uint16 => (16, false),
+        BasicKind::Uint32 => (32, false),
+        _ => (64, false),
+    };
+    if signed {
+        (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+    } else {
+        (0, (1 << bits) - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_literals_and_operators() {
+        let int = |value| Some(Constant::Int(value));
+        assert_eq!(Constant::from_integer_literal("0x_FF"), int(255));
+        assert_eq!(Constant::from_integer_literal("0755"), int(0o755));
+        assert_eq!(Constant::from_rune_literal("'a'"), int(97));
+        assert_eq!(Constant::from_rune_literal("'\\n'"), int(10));
+        assert_eq!(Constant::from_rune_literal("'\\u00e9'"), int(0xe9));
+        assert_eq!(Constant::from_rune_literal("'\\101'"), int(65));
+
+        let one = Constant::Int(1);
+        assert_eq!(
+            one.binary(Operator::LessLess, Constant::Int(70)),
+            int(1 << 70)
+        );
+        assert_eq!(one.binary(Operator::LessLess, Constant::Int(200)), None);
+        assert_eq!(
+            Constant::Int(7).binary(Operator::Slash, Constant::Int(2)),
+            int(3)
+        );
+        assert_eq!(one.binary(Operator::Slash, Constant::Int(0)), None);
+        assert_eq!(
+            Constant::Int(7).binary(Operator::Slash, Constant::Float(2.0)),
+            Some(Constant::Float(3.5))
+        );
+        assert_eq!(
+            one.binary(Operator::Less, Constant::Int(2)),
+            Some(Constant::Bool(true))
+        );
+        assert_eq!(Constant::Float(1e100).to_string(), "1e+100");
+        assert_eq!(Constant::Float(1.5).to_string(), "1.5");
+    }
+
+    #[test]
+    fn representability() {
+        assert_eq!(
+            Constant::Int(300).convert(BasicKind::Uint8),
+            Err(Unrepresentable::Overflows)
+        );
+        assert_eq!(
+            Constant::Int(-1).convert(BasicKind::Uint),
+            Err(Unrepresentable::Overflows)
+        );
+        assert_eq!(
+            Constant::Int(-128).convert(BasicKind::Int8),
+            Ok(Constant::Int(-128))
+        );
+        assert_eq!(
+            Constant::Float(1.5).convert(BasicKind::Int),
+            Err(Unrepresentable::Truncated)
+        );
+        assert_eq!(
+            Constant::Float(2.0).convert(BasicKind::Int),
+            Ok(Constant::Int(2))
+        );
+        assert_eq!(
+            Constant::Float(1e300).convert(BasicKind::Float32),
+            Err(Unrepresentable::Overflows)
+        );
+        assert_eq!(
+            Constant::Int(3).convert(BasicKind::Float64),
+            Ok(Constant::Float(3.0))
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use crate::types::types::{Field, Method, Type, TypeId, TypeTable};
+
+/// What a selector `x.f` denotes (https://go.dev/ref/spec#Selectors)
+#[derive(Debug, PartialEq, Clone)]
+pub enum Selection {
+    // The field's type
+    Field(TypeId),
+    Method(Method),
+}
+
+impl TypeTable {
+    /// The field or method `x.name` selects for an `x` of type `id`, including those promoted
+    /// from embedded fields. Embedded fields are searched breadth first, so the shallowest
+    /// match wins. Matches at the same depth are ambiguous in Go, but here the first one wins.
+    pub fn lookup_field_or_method(&self, id: TypeId, name: &str) -> Option<Selection> {
+        let mut found = None;
+        self.walk_embedded(id, |table, type_id, fields| {
+            if let Some(method) = table.lookup_method(type_id, name) {
+                found = Some(Selection::Method(method.clone()));
+                return true;
+            }
+            if let Some(field) = fields.iter().find(|field| field.name == name) {
+                found = Some(Selection::Field(field.type_id));
+                return true;
+            }
+            false
+        });
+        found
+    }
+
+    /// Every field and method name selectable on `id`, along with whether it's a method, for
+    /// suggesting one when a selector names neither
+    pub fn selectable_names(&self, id: TypeId) -> Vec<(String, bool)> {
+        let mut names = Vec::new();
+        self.walk_embedded(id, |table, type_id, fields| {
+            for field in fields {
+                names.push((field.name.clone(), false));
+            }
+            // Pointer receiver methods can be called on an addressable `T` too
+            match table.get(type_id) {
+                Type::Named(named) => names.extend(
+                    table
+                        .named(*named)
+                        .methods
+                        .iter()
+                        .map(|declared| (declared.method.name.clone(), true)),
+                ),
+                _ => names.extend(
+                    table
+                        .method_set(type_id)
+                        .into_iter()
+                        .map(|method| (method.name, true)),
+                ),
+            }
+            false
+        });
+        names
+    }
+
+    /// Visit `id` and then each type embedded in it, depth by depth, until `visit` returns true.
+    /// Pointers are visited as the type they point to, along with its fields if it's a struct.
+    fn walk_embedded(
+        &self,
+        id: TypeId,
+        mut visit: impl FnMut(&TypeTable, TypeId, &[Field]) -> bool,
+    ) {
+        let mut depth = vec![id];
+        // `type Node struct { *Node }` embeds itself
+        let mut seen = HashSet::new();
+        while !depth.is_empty() {
+            let mut next = Vec::new();
+            for type_id in depth {
+                let base = match self.get(type_id) {
+                    Type::Pointer(base) => *base,
+                    _ => type_id,
+                };
+                if !seen.insert(base) {
+                    continue;
+                }
+                let fields: &[Field] = match self.get(self.underlying(base)) {
+                    Type::Struct(fields) => fields,
+                    _ => &[],
+                };
+                if visit(self, base, fields) {
+                    return;
+                }
+                next.extend(
+                    fields
+                        .iter()
+                        .filter(|field| field.embedded)
+                        .map(|field| field.type_id),
+                );
+            }
+            depth = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::types::{BasicKind, DeclaredMethod, Signature};
+
+    #[test]
+    fn lookup_promoted_fields_and_methods() {
+        let mut table = TypeTable::new();
+        let int = table.basic(BasicKind::Int);
+        let string = table.basic(BasicKind::String);
+        let inner = table.new_named("Inner");
+        let inner_struct = table.intern(Type::Struct(vec![
+            Field {
+                name: "x".to_string(),
+                type_id: int,
+                embedded: false,
+            },
+            Field {
+                name: "name".to_string(),
+                type_id: int,
+                embedded: false,
+            },
+        ]));
+        table.set_underlying(inner, inner_struct);
+        let close = Method {
+            name: "Close".to_string(),
+            signature: Signature {
+                parameters: Vec::new(),
+                results: Vec::new(),
+                variadic: false,
+            },
+        };
+        table.add_method(
+            inner,
+            DeclaredMethod {
+                method: close.clone(),
+                pointer_receiver: true,
+            },
+        );
+        let inner_pointer = table.pointer(inner);
+        let outer = table.intern(Type::Struct(vec![
+            Field {
+                name: "Inner".to_string(),
+                type_id: inner_pointer,
+                embedded: true,
+            },
+            Field {
+                name: "name".to_string(),
+                type_id: string,
+                embedded: false,
+            },
+        ]));
+
+        assert_eq!(
+            table.lookup_field_or_method(outer, "x"),
+            Some(Selection::Field(int))
+        );
+        // The outer name hides the promoted one
+        assert_eq!(
+            table.lookup_field_or_method(outer, "name"),
+            Some(Selection::Field(string))
+        );
+        assert_eq!(
+            table.lookup_field_or_method(outer, "Close"),
+            Some(Selection::Method(close))
+        );
+        assert_eq!(table.lookup_field_or_method(outer, "y"), None);
+        assert_eq!(
+            table.selectable_names(outer),
+            vec![
+                ("Inner".to_string(), false),
+                ("name".to_string(), false),
+                ("x".to_string(), false),
+                ("name".to_string(), false),
+                ("Close".to_string(), true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use crate::types::types::{DeclaredMethod, Method, Type, TypeId, TypeTable};
+
+/// Why a type doesn't implement an interface, naming the first method at fault
+#[derive(Debug, PartialEq, Clone)]
+pub enum MissingMethod {
+    Missing(String),
+    // The method is declared on `*T`, but a `T` was given
+    PointerReceiver(String),
+    WrongType(String),
+}
+
+impl std::fmt::Display for MissingMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MissingMethod::Missing(name) => write!(f, "missing method {}", name),
+            MissingMethod::PointerReceiver(name) => {
+                write!(f, "method {} has pointer receiver", name)
+            }
+            MissingMethod::WrongType(name) => write!(f, "wrong type for method {}", name),
+        }
+    }
+}
+
+impl TypeTable {
+    /// The methods callable on a value of type `id`, sorted by name
+    /// (https://go.dev/ref/spec#Method_sets). A named type `T` has the methods declared with
+    /// a `T` receiver, and `*T` has those declared with either a `T` or a `*T` receiver.
+    pub fn method_set(&self, id: TypeId) -> Vec<Method> {
+        let mut methods: Vec<Method> = match self.get(self.underlying(id)) {
+            Type::Interface(methods) => return methods.clone(),
+            _ => match self.declared_methods(id) {
+                Some((declared, pointer)) => declared
+                    .iter()
+                    .filter(|declared| pointer || !declared.pointer_receiver)
+                    .map(|declared| declared.method.clone())
+                    .collect(),
+                None => Vec::new(),
+            },
+        };
+        methods.sort_by(|a, b| a.name.cmp(&b.name));
+        methods
+    }
+
+    /// The method `name` selected by `x.name` for an `x` of type `id`. Pointer receiver methods
+    /// are included for a `T`, since a variable of type `T` is addressable.
+    pub fn lookup_method(&self, id: TypeId, name: &str) -> Option<&Method> {
+        if let Type::Interface(methods) = self.get(self.underlying(id)) {
+            return methods.iter().find(|method| method.name == name);
+        }
+        let (declared, _) = self.declared_methods(id)?;
+        declared
+            .iter()
+            .map(|declared| &declared.method)
+            .find(|method| method.name == name)
+    }
+
+    /// Whether `value` has every method of the interface `interface`
+    /// (https://go.dev/ref/spec#Implementing_an_interface)
+    pub fn implements(&self, value: TypeId, interface: TypeId) -> bool {
+        matches!(self.get(self.underlying(interface)), Type::Interface(_))
+            && self.missing_method(value, interface).is_none()
+    }
+
+    /// The first method of `interface` that `value` lacks, or has with another signature
+    pub fn missing_method(&self, value: TypeId, interface: TypeId) -> Option<MissingMethod> {
+        let Type::Interface(wanted) = self.get(self.underlying(interface)) else {
+            return None;
+        };
+        let methods = self.method_set(value);
+        for method in wanted {
+            match methods.iter().find(|have| have.name == method.name) {
+                Some(have) if have.signature == method.signature => {}
+                Some(_) => return Some(MissingMethod::WrongType(method.name.clone())),
+                // Declared, but only on the pointer type
+                None if self.lookup_method(value, &method.name).is_some() => {
+                    return Some(MissingMethod::PointerReceiver(method.name.clone()));
+                }
+                None => return Some(MissingMethod::Missing(method.name.clone())),
+            }
+        }
+        None
+    }
+
+    /// The methods declared on `id` or, for a pointer, on the type it points to, along with
+    /// whether `id` is that pointer
+    fn declared_methods(&self, id: TypeId) -> Option<(&[DeclaredMethod], bool)> {
+        let (named, pointer) = match self.get(id) {
+            Type::Named(named) => (*named, false),
+            Type::Pointer(base) => match self.get(*base) {
+                Type::Named(named) => (*named, true),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some((&self.named(named).methods, pointer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::types::{BasicKind, Signature};
+
+    fn method(table: &TypeTable, name: &str, result: BasicKind) -> Method {
+        Method {
+            name: name.to_string(),
+            signature: Signature {
+                parameters: Vec::new(),
+                results: vec![table.basic(result)],
+                variadic: false,
+            },
+        }
+    }
+
+    #[test]
+    fn method_sets_of_values_and_pointers() {
+        let mut table = TypeTable::new();
+        let point = table.new_named("Point");
+        let int = table.basic(BasicKind::Int);
+        table.set_underlying(point, int);
+        let string = method(&table, "String", BasicKind::String);
+        let scale = method(&table, "Scale", BasicKind::Int);
+        assert!(table.add_method(
+            point,
+            DeclaredMethod {
+                method: string.clone(),
+                pointer_receiver: false,
+            },
+        ));
+        assert!(table.add_method(
+            point,
+            DeclaredMethod {
+                method: scale.clone(),
+                pointer_receiver: true,
+            },
+        ));
+        assert!(!table.add_method(
+            point,
+            DeclaredMethod {
+                method: scale.clone(),
+                pointer_receiver: false,
+            },
+        ));
+        let pointer = table.pointer(point);
+        assert_eq!(table.method_set(point), vec![string.clone()]);
+        assert_eq!(table.method_set(pointer), vec![scale.clone(), string]);
+        assert_eq!(table.lookup_method(point, "Scale"), Some(&scale));
+        assert_eq!(table.method_set(int), Vec::new());
+    }
+
+    #[test]
+    fn interface_satisfaction() {
+        let mut table = TypeTable::new();
+        let point = table.new_named("Point");
+        let int = table.basic(BasicKind::Int);
+        table.set_underlying(point, int);
+        table.add_method(
+            point,
+            DeclaredMethod {
+                method: method(&table, "String", BasicKind::String),
+                pointer_receiver: true,
+            },
+        );
+        let stringer = table.interface(vec![method(&table, "String", BasicKind::String)]);
+        let counter = table.interface(vec![method(&table, "String", BasicKind::Int)]);
+        let closer = table.interface(vec![method(&table, "Close", BasicKind::Bool)]);
+        let pointer = table.pointer(point);
+
+        assert!(table.implements(pointer, stringer));
+        assert_eq!(
+            table.missing_method(point, stringer),
+            Some(MissingMethod::PointerReceiver("String".to_string()))
+        );
+        assert_eq!(
+            table.missing_method(pointer, counter),
+            Some(MissingMethod::WrongType("String".to_string()))
+        );
+        assert_eq!(
+            table.missing_method(pointer, closer),
+            Some(MissingMethod::Missing("Close".to_string()))
+        );
+        assert!(table.implements(stringer, stringer));
+        assert!(!table.implements(int, stringer));
+    }
+}
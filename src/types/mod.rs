@@ -0,0 +1,6 @@
+pub mod assignability;
+pub mod constant;
+pub mod lookup;
+pub mod methods;
+#[allow(clippy::module_inception)]
+pub mod types;
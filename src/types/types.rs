@@ -0,0 +1,585 @@
+use std::collections::HashMap;
+
+use crate::ast::type_expression::ChannelDirection;
+
+/// A handle to an interned type. Types are interned so that two ids are equal exactly when
+/// their types are identical (https://go.dev/ref/spec#Type_identity).
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
+pub struct TypeId(usize);
+
+/// Handle to a named type's declaration, see `NamedType`
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct NamedId(usize);
+
+/// https://go.dev/ref/spec#Boolean_types, https://go.dev/ref/spec#Numeric_types and
+/// https://go.dev/ref/spec#String_types, plus the types of untyped constants
+/// (https://go.dev/ref/spec#Constants)
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum BasicKind {
+    Bool,
+    String,
+    Int,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Uint,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Uintptr,
+    Float32,
+    Float64,
+    Complex64,
+    Complex128,
+    UntypedBool,
+    UntypedInt,
+    UntypedRune,
+    UntypedFloat,
+    UntypedString,
+    UntypedNil,
+}
+
+impl BasicKind {
+    /// Every kind, in the order they're interned in a new `TypeTable`
+    pub const ALL: [BasicKind; 23] = [
+        BasicKind::Bool,
+        BasicKind::String,
+        BasicKind::Int,
+        BasicKind::Int8,
+        BasicKind::Int16,
+        BasicKind::Int32,
+        BasicKind::Int64,
+        BasicKind::Uint,
+        BasicKind::Uint8,
+        BasicKind::Uint16,
+        BasicKind::Uint32,
+        BasicKind::Uint64,
+        BasicKind::Uintptr,
+        BasicKind::Float32,
+        BasicKind::Float64,
+        BasicKind::Complex64,
+        BasicKind::Complex128,
+        BasicKind::UntypedBool,
+        BasicKind::UntypedInt,
+        BasicKind::UntypedRune,
+        BasicKind::UntypedFloat,
+        BasicKind::UntypedString,
+        BasicKind::UntypedNil,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BasicKind::Bool => "bool",
+            BasicKind::String => "string",
+            BasicKind::Int => "int",
+            BasicKind::Int8 => "int8",
+            BasicKind::Int16 => "int16",
+            BasicKind::Int32 => "int32",
+            BasicKind::Int64 => "int64",
+            BasicKind::Uint => "uint",
+            BasicKind::Uint8 => "uint8",
+            BasicKind::Uint16 => "uint16",
+            BasicKind::Uint32 => "uint32",
+            BasicKind::Uint64 => "uint64",
+            BasicKind::Uintptr => "uintptr",
+            BasicKind::Float32 => "float32",
+            BasicKind::Float64 => "float64",
+            BasicKind::Complex64 => "complex64",
+            BasicKind::Complex128 => "complex128",
+            BasicKind::UntypedBool => "untyped bool",
+            BasicKind::UntypedInt => "untyped int",
+            BasicKind::UntypedRune => "untyped rune",
+            BasicKind::UntypedFloat => "untyped float",
+            BasicKind::UntypedString => "untyped string",
+            BasicKind::UntypedNil => "untyped nil",
+        }
+    }
+
+    pub fn is_untyped(&self) -> bool {
+        matches!(
+            self,
+            BasicKind::UntypedBool
+                | BasicKind::UntypedInt
+                | BasicKind::UntypedRune
+                | BasicKind::UntypedFloat
+                | BasicKind::UntypedString
+                | BasicKind::UntypedNil
+        )
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            BasicKind::Int
+                | BasicKind::Int8
+                | BasicKind::Int16
+                | BasicKind::Int32
+                | BasicKind::Int64
+                | BasicKind::Uint
+                | BasicKind::Uint8
+                | BasicKind::Uint16
+                | BasicKind::Uint32
+                | BasicKind::Uint64
+                | BasicKind::Uintptr
+                | BasicKind::UntypedInt
+                | BasicKind::UntypedRune
+        )
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            BasicKind::Float32 | BasicKind::Float64 | BasicKind::UntypedFloat
+        )
+    }
+
+    pub fn is_complex(&self) -> bool {
+        matches!(self, BasicKind::Complex64 | BasicKind::Complex128)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float() || self.is_complex()
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, BasicKind::String | BasicKind::UntypedString)
+    }
+
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, BasicKind::Bool | BasicKind::UntypedBool)
+    }
+}
+
+/// Parameter and result types of a function (https://go.dev/ref/spec#Function_types)
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Signature {
+    pub parameters: Vec<TypeId>,
+    pub results: Vec<TypeId>,
+    /// The last parameter is `...T`, and its type is `[]T`
+    pub variadic: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Field {
+    pub name: String,
+    pub type_id: TypeId,
+    pub embedded: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Method {
+    pub name: String,
+    pub signature: Signature,
+}
+
+/// https://go.dev/ref/spec#Types
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum Type {
+    Basic(BasicKind),
+    // *T
+    Pointer(TypeId),
+    // []T
+    Slice(TypeId),
+    // [4]T
+    Array {
+        length: u64,
+        element: TypeId,
+    },
+    // map[K]V
+    Map {
+        key: TypeId,
+        value: TypeId,
+    },
+    // chan T, chan<- T, <-chan T
+    Channel {
+        direction: ChannelDirection,
+        element: TypeId,
+    },
+    // func(int) string
+    Function(Signature),
+    // struct { x int }
+    Struct(Vec<Field>),
+    // interface { M() }, with the methods sorted by name
+    Interface(Vec<Method>),
+    // type T ..., identical only to itself
+    Named(NamedId),
+    // The results of a call returning more than one value
+    Tuple(Vec<TypeId>),
+}
+
+/// A defined type (https://go.dev/ref/spec#Type_definitions). Its underlying type is filled in
+/// once it's known, which allows recursive types like `type List struct { next *List }`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NamedType {
+    pub name: String,
+    pub underlying: Option<TypeId>,
+    /// Methods declared with this type as their receiver, in declaration order
+    pub methods: Vec<DeclaredMethod>,
+}
+
+/// A method declared on a named type (https://go.dev/ref/spec#Method_declarations)
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeclaredMethod {
+    pub method: Method,
+    /// Declared on `*T` rather than `T`, so it's only in the method set of `*T`
+    pub pointer_receiver: bool,
+}
+
+/// Owns every type and hands out ids for them. Structurally equal types are interned to the
+/// same id, so comparing ids checks type identity.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypeTable {
+    types: Vec<Type>,
+    ids: HashMap<Type, TypeId>,
+    named: Vec<NamedType>,
+}
+
+impl Default for TypeTable {
+    fn default() -> TypeTable {
+        TypeTable::new()
+    }
+}
+
+impl TypeTable {
+    pub fn new() -> TypeTable {
+        let mut table = TypeTable {
+            types: Vec::new(),
+            ids: HashMap::new(),
+            named: Vec::new(),
+        };
+        for kind in BasicKind::ALL {
+            table.intern(Type::Basic(kind));
+        }
+        table
+    }
+
+    pub fn intern(&mut self, ty: Type) -> TypeId {
+        if let Some(id) = self.ids.get(&ty) {
+            return *id;
+        }
+        let id = TypeId(self.types.len());
+        self.types.push(ty.clone());
+        self.ids.insert(ty, id);
+        id
+    }
+
+    pub fn get(&self, id: TypeId) -> &Type {
+        &self.types[id.0]
+    }
+
+    pub fn basic(&self, kind: BasicKind) -> TypeId {
+        // Basic types are interned first, in the order of `BasicKind::ALL`
+        TypeId(
+            BasicKind::ALL
+                .iter()
+                .position(|basic| *basic == kind)
+                .expect("every basic kind is in BasicKind::ALL"),
+        )
+    }
+
+    /// The type a predeclared type name refers to, with `byte` and `rune` being aliases for
+    /// `uint8` and `int32`
+    pub fn predeclared(&self, name: &str) -> Option<TypeId> {
+        let kind = match name {
+            "byte" => BasicKind::Uint8,
+            "rune" => BasicKind::Int32,
+            _ => *BasicKind::ALL
+                .iter()
+                .find(|kind| !kind.is_untyped() && kind.name() == name)?,
+        };
+        Some(self.basic(kind))
+    }
+
+    pub fn pointer(&mut self, element: TypeId) -> TypeId {
+        self.intern(Type::Pointer(element))
+    }
+
+    pub fn slice(&mut self, element: TypeId) -> TypeId {
+        self.intern(Type::Slice(element))
+    }
+
+    pub fn function(&mut self, signature: Signature) -> TypeId {
+        self.intern(Type::Function(signature))
+    }
+
+    /// Method order doesn't affect identity, so methods are sorted before interning
+    pub fn interface(&mut self, mut methods: Vec<Method>) -> TypeId {
+        methods.sort_by(|a, b| a.name.cmp(&b.name));
+        self.intern(Type::Interface(methods))
+    }
+
+    /// Declare a new named type, distinct from every other, whose underlying type is set later
+    /// with `set_underlying`
+    pub fn new_named(&mut self, name: &str) -> TypeId {
+        self.named.push(NamedType {
+            name: name.to_string(),
+            underlying: None,
+            methods: Vec::new(),
+        });
+        self.intern(Type::Named(NamedId(self.named.len() - 1)))
+    }
+
+    pub fn set_underlying(&mut self, named: TypeId, underlying: TypeId) {
+        let underlying = self.underlying(underlying);
+        if let Type::Named(id) = self.get(named) {
+            let id = id.0;
+            self.named[id].underlying = Some(underlying);
+        }
+    }
+
+    /// Add a method to the named type `named`, returning false if it already has a method of
+    /// that name
+    pub fn add_method(&mut self, named: TypeId, method: DeclaredMethod) -> bool {
+        let Type::Named(id) = self.get(named) else {
+            panic!("methods are only declared on named types");
+        };
+        let id = id.0;
+        let methods = &mut self.named[id].methods;
+        if methods
+            .iter()
+            .any(|existing| existing.method.name == method.method.name)
+        {
+            return false;
+        }
+        methods.push(method);
+        true
+    }
+
+    pub fn named(&self, id: NamedId) -> &NamedType {
+        &self.named[id.0]
+    }
+
+    /// The type `id` is defined in terms of (https://go.dev/ref/spec#Underlying_types). A named
+    /// type whose underlying type isn't known yet is its own underlying type.
+    pub fn underlying(&self, id: TypeId) -> TypeId {
+        match self.get(id) {
+            Type::Named(named) => self.named(*named).underlying.unwrap_or(id),
+            _ => id,
+        }
+    }
+
+    /// The basic kind of `id`'s underlying type, if it's a basic type
+    pub fn basic_kind(&self, id: TypeId) -> Option<BasicKind> {
+        match self.get(self.underlying(id)) {
+            Type::Basic(kind) => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Go syntax for a type, e.g. `map[string][]int`
+    pub fn type_string(&self, id: TypeId) -> String {
+        match self.get(id) {
+            Type::Basic(kind) => kind.name().to_string(),
+            Type::Pointer(element) => format!("*{}", self.type_string(*element)),
+            Type::Slice(element) => format!("[]{}", self.type_string(*element)),
+            Type::Array { length, element } => {
+                format!("[{}]{}", length, self.type_string(*element))
+            }
+            Type::Map { key, value } => {
+                format!(
+                    "map[{}]{}",
+                    self.type_string(*key),
+                    self.type_string(*value)
+                )
+            }
+            Type::Channel { direction, element } => {
+                let element_string = self.type_string(*element);
+                match direction {
+                    // `chan <-chan T` would read as `chan<- (chan T)`
+                    ChannelDirection::Both
+                        if matches!(
+                            self.get(*element),
+                            Type::Channel {
+                                direction: ChannelDirection::Receive,
+                                ..
+                            }
+                        ) =>
+                    {
+                        format!("chan ({})", element_string)
+                    }
+                    ChannelDirection::Both => format!("chan {}", element_string),
+                    ChannelDirection::Send => format!("chan<- {}", element_string),
+                    ChannelDirection::Receive => format!("<-chan {}", element_string),
+                }
+            }
+            Type::Function(signature) => format!("func{}", self.signature_string(signature)),
+            Type::Struct(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|field| match field.embedded {
+                        true => self.type_string(field.type_id),
+                        false => format!("{} {}", field.name, self.type_string(field.type_id)),
+                    })
+                    .collect();
+                format!("struct{{{}}}", fields.join("; "))
+            }
+            Type::Interface(methods) => {
+                let methods: Vec<String> = methods
+                    .iter()
+                    .map(|method| {
+                        format!(
+                            "{}{}",
+                            method.name,
+                            self.signature_string(&method.signature)
+                        )
+                    })
+                    .collect();
+                format!("interface{{{}}}", methods.join("; "))
+            }
+            Type::Named(named) => self.named(*named).name.clone(),
+            Type::Tuple(elements) => format!("({})", self.type_list_string(elements)),
+        }
+    }
+
+    /// `(int, ...string) (bool, error)`, the part of a function type after `func`
+    pub fn signature_string(&self, signature: &Signature) -> String {
+        let mut parameters: Vec<String> = signature
+            .parameters
+            .iter()
+            .map(|parameter| self.type_string(*parameter))
+            .collect();
+        if signature.variadic
+            && let Some(last) = signature.parameters.last()
+            && let Type::Slice(element) = self.get(*last)
+        {
+            let variadic = format!("...{}", self.type_string(*element));
+            *parameters
+                .last_mut()
+                .expect("variadic signatures have parameters") = variadic;
+        }
+        let parameters = format!("({})", parameters.join(", "));
+        match signature.results.as_slice() {
+            [] => parameters,
+            [result] => format!("{} {}", parameters, self.type_string(*result)),
+            results => format!("{} ({})", parameters, self.type_list_string(results)),
+        }
+    }
+
+    fn type_list_string(&self, types: &[TypeId]) -> String {
+        types
+            .iter()
+            .map(|id| self.type_string(*id))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern_identical_types_once() {
+        let mut table = TypeTable::new();
+        let int = table.basic(BasicKind::Int);
+        let first = table.slice(int);
+        let second = table.slice(int);
+        assert_eq!(first, second);
+        let map = table.intern(Type::Map {
+            key: table.basic(BasicKind::String),
+            value: first,
+        });
+        let other = table.intern(Type::Map {
+            key: table.basic(BasicKind::String),
+            value: second,
+        });
+        assert_eq!(map, other);
+        assert_ne!(table.slice(map), first);
+    }
+
+    #[test]
+    fn predeclared_aliases() {
+        let table = TypeTable::new();
+        assert_eq!(
+            table.predeclared("byte"),
+            Some(table.basic(BasicKind::Uint8))
+        );
+        assert_eq!(
+            table.predeclared("rune"),
+            Some(table.basic(BasicKind::Int32))
+        );
+        assert_eq!(
+            table.predeclared("float64"),
+            Some(table.basic(BasicKind::Float64))
+        );
+        assert_eq!(table.predeclared("untyped int"), None);
+        assert_eq!(table.predeclared("main"), None);
+    }
+
+    #[test]
+    fn named_types_are_distinct() {
+        let mut table = TypeTable::new();
+        let int = table.basic(BasicKind::Int);
+        let celsius = table.new_named("Celsius");
+        let fahrenheit = table.new_named("Fahrenheit");
+        table.set_underlying(celsius, int);
+        table.set_underlying(fahrenheit, celsius);
+        assert_ne!(celsius, fahrenheit);
+        assert_eq!(table.underlying(fahrenheit), int);
+        assert_eq!(table.basic_kind(celsius), Some(BasicKind::Int));
+    }
+
+    #[test]
+    fn recursive_named_type() {
+        let mut table = TypeTable::new();
+        let list = table.new_named("List");
+        let next = table.pointer(list);
+        let fields = table.intern(Type::Struct(vec![Field {
+            name: "next".to_string(),
+            type_id: next,
+            embedded: false,
+        }]));
+        table.set_underlying(list, fields);
+        assert_eq!(
+            table.type_string(table.underlying(list)),
+            "struct{next *List}"
+        );
+    }
+
+    #[test]
+    fn type_strings() {
+        let mut table = TypeTable::new();
+        let int = table.basic(BasicKind::Int);
+        let string = table.basic(BasicKind::String);
+        let error = table.new_named("error");
+        let strings = table.slice(string);
+        let function = table.function(Signature {
+            parameters: vec![int, strings],
+            results: vec![int, error],
+            variadic: true,
+        });
+        assert_eq!(
+            table.type_string(function),
+            "func(int, ...string) (int, error)"
+        );
+        let receive = table.intern(Type::Channel {
+            direction: ChannelDirection::Receive,
+            element: int,
+        });
+        let channel = table.intern(Type::Channel {
+            direction: ChannelDirection::Both,
+            element: receive,
+        });
+        assert_eq!(table.type_string(channel), "chan (<-chan int)");
+        let array = table.intern(Type::Array {
+            length: 4,
+            element: int,
+        });
+        let stringer = table.interface(vec![Method {
+            name: "String".to_string(),
+            signature: Signature {
+                parameters: vec![],
+                results: vec![string],
+                variadic: false,
+            },
+        }]);
+        assert_eq!(table.type_string(array), "[4]int");
+        assert_eq!(table.type_string(stringer), "interface{String() string}");
+        assert_eq!(
+            table.type_string(table.basic(BasicKind::UntypedFloat)),
+            "untyped float"
+        );
+    }
+}
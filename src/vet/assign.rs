@@ -0,0 +1,104 @@
+use crate::ast::ast::File;
+use crate::ast::expression::{Expression, ExpressionKind};
+use crate::ast::statement::{Statement, StatementKind};
+use crate::primitives::diagnostic::Diagnostic;
+use crate::semantic::info::TypeInfo;
+use crate::vet::vet::Rule;
+use crate::vet::walk::{Visitor, walk_file};
+
+/// Assignments of a variable to itself, like `x = x` or `p.x = p.x`, which do nothing and
+/// were usually meant to assign something else. Only operands without side effects are
+/// compared, so `a[f()] = a[f()]` isn't reported.
+pub struct SelfAssignment;
+
+impl Rule for SelfAssignment {
+    fn name(&self) -> &'static str {
+        "assign"
+    }
+
+    fn summary(&self) -> &'static str {
+        "assignments of a variable to itself"
+    }
+
+    fn check(&self, file: &File, _info: &TypeInfo) -> Vec<Diagnostic> {
+        let mut finder = Finder(Vec::new());
+        walk_file(file, &mut finder);
+        finder.0
+    }
+}
+
+struct Finder(Vec<Diagnostic>);
+
+impl<'a> Visitor<'a> for Finder {
+    fn statement(&mut self, statement: &'a Statement) {
+        let StatementKind::Assignment {
+            left,
+            right,
+            define: false,
+        } = &statement.kind
+        else {
+            return;
+        };
+        if left.len() != right.len() {
+            return;
+        }
+        for (left, right) in left.iter().zip(right) {
+            let (target, value) = (left.to_string(), right.to_string());
+            if is_pure(left) && target == value {
+                self.0.push(Diagnostic::warning(
+                    format!("self-assignment of {} to {}", value, target),
+                    statement.position_start,
+                ));
+            }
+        }
+    }
+}
+
+/// Whether evaluating an expression twice is sure to give the same place, which it isn't
+/// when there's a call or a receive in it
+fn is_pure(expression: &Expression) -> bool {
+    match &expression.kind {
+        ExpressionKind::Identifier(_)
+        | ExpressionKind::IntegerLiteral(_)
+        | ExpressionKind::FloatLiteral(_)
+        | ExpressionKind::RuneLiteral(_)
+        | ExpressionKind::StringLiteral(_) => true,
+        ExpressionKind::FieldAccess { object, .. } | ExpressionKind::Parenthesized(object) => {
+            is_pure(object)
+        }
+        ExpressionKind::Index { object, index } => is_pure(object) && is_pure(index),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::semantic::checker::check;
+
+    fn self_assignments(source: &str) -> Vec<String> {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        let (info, errors) = check(&file);
+        assert!(errors.is_empty(), "{:?}", errors);
+        SelfAssignment
+            .check(&file, &info)
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn report_self_assignment() {
+        let source = "package main\n\ntype Point struct {\n\tx int\n}\n\nfunc f() int {\n\treturn 0\n}\n\nfunc main() {\n\tp := Point{}\n\ta := []int{1, 2}\n\ti := 0\n\tp.x = p.x\n\ta[i], i = a[i], 1\n\ta[f()] = a[f()]\n\ti = p.x\n\t_ = i\n}\n";
+        assert_eq!(
+            self_assignments(source),
+            vec![
+                "15:1: warning: self-assignment of p.x to p.x",
+                "16:1: warning: self-assignment of a[i] to a[i]",
+            ]
+        );
+    }
+}
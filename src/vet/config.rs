@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+use crate::vet::vet::Rule;
+
+/// Which of `Vet`'s rules run. The default runs each rule that's on by default.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VetConfig {
+    /// Rules turned on or off by name, whatever their default
+    rules: HashMap<String, bool>,
+}
+
+impl VetConfig {
+    /// Run the rule with the name, even if it's off by default
+    pub fn with_enabled(mut self, rule: &str) -> VetConfig {
+        self.rules.insert(rule.to_string(), true);
+        self
+    }
+
+    /// Don't run the rule with the name
+    pub fn with_disabled(mut self, rule: &str) -> VetConfig {
+        self.rules.insert(rule.to_string(), false);
+        self
+    }
+
+    pub fn is_enabled(&self, rule: &dyn Rule) -> bool {
+        self.rules
+            .get(rule.name())
+            .copied()
+            .unwrap_or_else(|| rule.enabled_by_default())
+    }
+}
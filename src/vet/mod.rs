@@ -0,0 +1,8 @@
+pub mod assign;
+pub mod config;
+pub mod printf;
+pub mod shadow;
+pub mod unreachable;
+#[allow(clippy::module_inception)]
+pub mod vet;
+pub mod walk;
@@ -0,0 +1,351 @@
+use crate::ast::ast::File;
+use crate::ast::expression::{Expression, ExpressionKind};
+use crate::lexer::literal::unquote_string;
+use crate::primitives::{diagnostic::Diagnostic, position::Position};
+use crate::semantic::{info::TypeInfo, scope::SymbolKind};
+use crate::types::types::{BasicKind, Type};
+use crate::vet::vet::Rule;
+use crate::vet::walk::{Visitor, walk_file};
+
+/// The functions that format their arguments as a format string says, each with its import
+/// path and which argument is the format
+const PRINTF_FUNCTIONS: &[(&str, &str, usize)] = &[
+    ("fmt", "Errorf", 0),
+    ("fmt", "Fprintf", 1),
+    ("fmt", "Printf", 0),
+    ("fmt", "Sprintf", 0),
+    ("log", "Fatalf", 0),
+    ("log", "Panicf", 0),
+    ("log", "Printf", 0),
+];
+
+/// Calls to `fmt.Printf` and the like whose format string doesn't match their arguments:
+/// too few or too many of them, a verb that isn't one, or an argument of a basic type the
+/// verb can't print, like a string for `%d`. Only formats written as string literals are
+/// checked, and arguments of other types aren't, since they may have a `String` method.
+pub struct Printf;
+
+impl Rule for Printf {
+    fn name(&self) -> &'static str {
+        "printf"
+    }
+
+    fn summary(&self) -> &'static str {
+        "Printf calls whose format doesn't match their arguments"
+    }
+
+    fn check(&self, file: &File, info: &TypeInfo) -> Vec<Diagnostic> {
+        let mut finder = Finder {
+            file,
+            info,
+            diagnostics: Vec::new(),
+        };
+        walk_file(file, &mut finder);
+        finder.diagnostics
+    }
+}
+
+struct Finder<'i> {
+    file: &'i File,
+    info: &'i TypeInfo,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Visitor<'a> for Finder<'_> {
+    fn expression(&mut self, expression: &'a Expression) {
+        let ExpressionKind::FunctionCall {
+            name,
+            arguments,
+            spread,
+        } = &expression.kind
+        else {
+            return;
+        };
+        let Some((function, format_index)) = self.printf_function(name) else {
+            return;
+        };
+        let Some(ExpressionKind::StringLiteral(literal)) =
+            arguments.get(format_index).map(|format| &format.kind)
+        else {
+            return;
+        };
+        let Some(format) = unquote_string(literal) else {
+            return;
+        };
+        let call = Call {
+            function,
+            arguments: &arguments[format_index + 1..],
+            // The arguments of a call passing a slice aren't known
+            spread: *spread,
+            position: expression.position_start,
+        };
+        self.check_format(&call, &format);
+    }
+}
+
+/// A call to a Printf-like function, for its diagnostics
+struct Call<'a> {
+    /// Its name as go vet reports it, like `fmt.Printf`
+    function: String,
+    /// The arguments after the format
+    arguments: &'a [Expression],
+    spread: bool,
+    position: Position,
+}
+
+impl Finder<'_> {
+    /// The name of the Printf-like function a call is to and which of its arguments is the
+    /// format, if it is to one
+    fn printf_function(&self, name: &Expression) -> Option<(String, usize)> {
+        let ExpressionKind::FieldAccess { object, field, .. } = &name.kind else {
+            return None;
+        };
+        let symbol = self
+            .info
+            .symbols
+            .symbol(self.info.symbol_of(object.as_ref())?);
+        if symbol.kind != SymbolKind::Package {
+            return None;
+        }
+        let import = self
+            .file
+            .imports
+            .iter()
+            .find(|import| Some(import.position_start) == symbol.position)?;
+        let path = import.unquoted_path();
+        PRINTF_FUNCTIONS
+            .iter()
+            .find(|(package, function, _)| *package == path && function == field)
+            .map(|(package, function, index)| (format!("{}.{}", package, function), *index))
+    }
+
+    fn check_format(&mut self, call: &Call, format: &str) {
+        let mut chars = format.chars().peekable();
+        // The next argument a directive reads
+        let mut argument = 0;
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+            let mut directive = String::from("%");
+            while let Some(&flag) = chars.peek().filter(|c| "+-# 0".contains(**c)) {
+                directive.push(flag);
+                chars.next();
+            }
+            // An explicit argument index like %[2]d reorders the arguments, which isn't
+            // followed here
+            if chars.peek() == Some(&'[') {
+                return;
+            }
+            // A width and a precision, either of which can be a * that reads an int argument
+            let mut stars = 0;
+            for part in 0..2 {
+                if part == 1 {
+                    if chars.peek() != Some(&'.') {
+                        break;
+                    }
+                    directive.push('.');
+                    chars.next();
+                }
+                if chars.peek() == Some(&'*') {
+                    directive.push('*');
+                    chars.next();
+                    stars += 1;
+                }
+                while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    directive.push(digit);
+                    chars.next();
+                }
+            }
+            let Some(verb) = chars.next() else {
+                self.report(
+                    call,
+                    format!(
+                        "{} format {} is missing verb at end of string",
+                        call.function, directive
+                    ),
+                );
+                return;
+            };
+            directive.push(verb);
+            if verb == '%' {
+                continue;
+            }
+            for _ in 0..stars {
+                if !self.read_argument(call, &directive, argument, Reads::Star) {
+                    return;
+                }
+                argument += 1;
+            }
+            if verb == 'w' && call.function != "fmt.Errorf" {
+                self.report(
+                    call,
+                    format!(
+                        "{} does not support error-wrapping directive %w",
+                        call.function
+                    ),
+                );
+                return;
+            }
+            let Some(accepts) = accepts(verb) else {
+                self.report(
+                    call,
+                    format!(
+                        "{} format {} has unknown verb {}",
+                        call.function, directive, verb
+                    ),
+                );
+                return;
+            };
+            if !self.read_argument(call, &directive, argument, Reads::Verb(accepts)) {
+                return;
+            }
+            argument += 1;
+        }
+        if !call.spread && argument < call.arguments.len() {
+            self.report(
+                call,
+                format!(
+                    "{} call needs {} but has {}",
+                    call.function,
+                    args(argument),
+                    args(call.arguments.len())
+                ),
+            );
+        }
+    }
+
+    /// Check the argument a directive reads, returning whether the rest of the format is
+    /// worth checking
+    fn read_argument(&mut self, call: &Call, directive: &str, index: usize, reads: Reads) -> bool {
+        let Some(argument) = call.arguments.get(index) else {
+            if !call.spread {
+                self.report(
+                    call,
+                    format!(
+                        "{} format {} reads arg #{}, but call has {}",
+                        call.function,
+                        directive,
+                        index + 1,
+                        args(call.arguments.len())
+                    ),
+                );
+            }
+            return false;
+        };
+        let kind =
+            self.info
+                .type_of(argument)
+                .and_then(|type_id| match self.info.types.get(type_id) {
+                    Type::Basic(kind) if *kind != BasicKind::UntypedNil => Some(*kind),
+                    _ => None,
+                });
+        let Some(kind) = kind else {
+            return true;
+        };
+        let message = match reads {
+            Reads::Star if !kind.is_integer() => format!(
+                "{} format {} uses non-int {} as argument of *",
+                call.function, directive, argument
+            ),
+            Reads::Verb(accepts) if !accepts(kind) => format!(
+                "{} format {} has arg {} of wrong type {}",
+                call.function,
+                directive,
+                argument,
+                kind.name()
+            ),
+            _ => return true,
+        };
+        self.report(call, message);
+        false
+    }
+
+    fn report(&mut self, call: &Call, message: String) {
+        self.diagnostics
+            .push(Diagnostic::warning(message, call.position));
+    }
+}
+
+/// What a directive reads an argument for
+enum Reads {
+    // A width or precision given as *, which is an int
+    Star,
+    // The verb, with the basic types it can print
+    Verb(fn(BasicKind) -> bool),
+}
+
+/// `1 arg` or `2 args`
+fn args(count: usize) -> String {
+    match count {
+        1 => "1 arg".to_string(),
+        count => format!("{} args", count),
+    }
+}
+
+/// What the basic types a verb can print are, or None if it isn't a verb
+/// (https://pkg.go.dev/fmt#hdr-Printing)
+fn accepts(verb: char) -> Option<fn(BasicKind) -> bool> {
+    let accepts: fn(BasicKind) -> bool = match verb {
+        'v' | 'T' => |_| true,
+        't' => |kind| kind.is_boolean(),
+        'd' | 'c' | 'o' | 'O' | 'U' => |kind| kind.is_integer(),
+        'b' => |kind| kind.is_numeric(),
+        'x' | 'X' => |kind| kind.is_numeric() || kind.is_string(),
+        'e' | 'E' | 'f' | 'F' | 'g' | 'G' => |kind| kind.is_float() || kind.is_complex(),
+        's' => |kind| kind.is_string(),
+        'q' => |kind| kind.is_string() || kind.is_integer(),
+        // Pointers and errors, which no basic type is
+        'p' | 'w' => |_| false,
+        _ => return None,
+    };
+    Some(accepts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::semantic::checker::check;
+
+    fn printf(body: &str) -> Vec<String> {
+        let source = format!(
+            "package main\n\nimport (\n\t\"fmt\"\n\tl \"log\"\n)\n\nfunc main() {{\n\tn, s, f := 1, \"a\", 1.5\n{}\t_, _, _ = n, s, f\n}}\n",
+            body
+        );
+        let mut parser = Parser::new(&source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        let (info, errors) = check(&file);
+        assert!(errors.is_empty(), "{:?}", errors);
+        Printf
+            .check(&file, &info)
+            .iter()
+            .map(|diagnostic| diagnostic.message.clone())
+            .collect()
+    }
+
+    #[test]
+    fn matching_formats() {
+        let body = "\tfmt.Printf(\"%d %s %.2f %v %% %5.1f %x %q\\n\", n, s, f, n, f, s, n)\n\tfmt.Printf(\"%*d\\n\", n, n)\n\t_ = fmt.Sprintf(\"%[2]d %[1]d\", n, n)\n\tl.Printf(\"%t\", n > 0)\n\t_ = fmt.Errorf(\"%s\", s)\n\tfmt.Println(\"%d\")\n";
+        assert_eq!(printf(body), Vec::<String>::new());
+    }
+
+    #[test]
+    fn report_mismatched_formats() {
+        let body = "\tfmt.Printf(\"%d %d\\n\", n)\n\tfmt.Printf(\"%d\\n\", n, s)\n\tfmt.Printf(\"%d\\n\", s)\n\t_ = fmt.Sprintf(\"%s\", f)\n\tl.Printf(\"%z\", n)\n\tfmt.Printf(\"%*d\", s, n)\n\tfmt.Printf(\"%w\", n)\n\tfmt.Printf(\"100%\")\n";
+        assert_eq!(
+            printf(body),
+            vec![
+                "fmt.Printf format %d reads arg #2, but call has 1 arg",
+                "fmt.Printf call needs 1 arg but has 2 args",
+                "fmt.Printf format %d has arg s of wrong type string",
+                "fmt.Sprintf format %s has arg f of wrong type float64",
+                "log.Printf format %z has unknown verb z",
+                "fmt.Printf format %*d uses non-int s as argument of *",
+                "fmt.Printf does not support error-wrapping directive %w",
+                "fmt.Printf format % is missing verb at end of string",
+            ]
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use crate::ast::ast::File;
+use crate::primitives::diagnostic::Diagnostic;
+use crate::semantic::{info::TypeInfo, shadow::find_shadowing};
+use crate::vet::vet::Rule;
+
+/// Variables declared over one of an enclosing block that's used after them, as the
+/// checker's `Check::Shadowing` finds them. Shadowing is legal Go and often meant, so the
+/// rule is off unless it's asked for.
+pub struct Shadow;
+
+impl Rule for Shadow {
+    fn name(&self) -> &'static str {
+        "shadow"
+    }
+
+    fn summary(&self) -> &'static str {
+        "variables hiding one of an enclosing block that's used after them, off by default"
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    fn check(&self, _file: &File, info: &TypeInfo) -> Vec<Diagnostic> {
+        find_shadowing(&info.symbols)
+            .iter()
+            .map(Diagnostic::from)
+            .collect()
+    }
+}
@@ -0,0 +1,239 @@
+use crate::ast::ast::File;
+use crate::ast::declaration::DeclarationKind;
+use crate::ast::expression::{Expression, ExpressionKind};
+use crate::ast::statement::{Statement, StatementKind};
+use crate::primitives::diagnostic::Diagnostic;
+use crate::semantic::{info::TypeInfo, scope::ScopeKind};
+use crate::vet::vet::Rule;
+use crate::vet::walk::{Visitor, blocks, walk_file};
+
+/// Statements that can't be run because the one before them never finishes: a `return`, a
+/// call to `panic`, a `break` or `continue`, a `for` without a condition or a `break` out of
+/// it, an `if` and `else` that both end so, or likewise a `select` or a `switch` with a
+/// `default` (https://go.dev/ref/spec#Terminating_statements).
+/// Only the first such statement of a block is reported.
+pub struct Unreachable;
+
+impl Rule for Unreachable {
+    fn name(&self) -> &'static str {
+        "unreachable"
+    }
+
+    fn summary(&self) -> &'static str {
+        "statements after a return, panic or endless loop"
+    }
+
+    fn check(&self, file: &File, info: &TypeInfo) -> Vec<Diagnostic> {
+        let mut finder = Finder {
+            info,
+            diagnostics: Vec::new(),
+        };
+        for declaration in &file.decls {
+            if let DeclarationKind::Function { body, .. } = &declaration.kind {
+                finder.block(body);
+            }
+        }
+        walk_file(file, &mut finder);
+        let mut diagnostics = finder.diagnostics;
+        diagnostics
+            .sort_by_key(|diagnostic| (diagnostic.position.line, diagnostic.position.column_start));
+        diagnostics
+    }
+}
+
+struct Finder<'i> {
+    info: &'i TypeInfo,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Visitor<'a> for Finder<'_> {
+    fn statement(&mut self, statement: &'a Statement) {
+        for block in blocks(statement) {
+            self.block(block);
+        }
+    }
+
+    fn expression(&mut self, expression: &'a Expression) {
+        if let ExpressionKind::FunctionLiteral { body, .. } = &expression.kind {
+            self.block(body);
+        }
+    }
+}
+
+impl Finder<'_> {
+    /// Report the first statement of a block after one that doesn't finish
+    fn block(&mut self, body: &[Statement]) {
+        let Some(index) = body.iter().position(|statement| self.ends(statement)) else {
+            return;
+        };
+        if let Some(unreachable) = body.get(index + 1) {
+            self.diagnostics.push(Diagnostic::warning(
+                "unreachable code".to_string(),
+                unreachable.position_start,
+            ));
+        }
+    }
+
+    /// Whether the statements after this one in its block can't be run
+    fn ends(&self, statement: &Statement) -> bool {
+        match &statement.kind {
+            StatementKind::Return(_) | StatementKind::Break | StatementKind::Continue => true,
+            StatementKind::Expression(expression) => match &expression.kind {
+                ExpressionKind::FunctionCall { name, .. } => self.is_panic(name),
+                _ => false,
+            },
+            StatementKind::Block(body) => body.last().is_some_and(|last| self.terminates(last)),
+            StatementKind::If {
+                body, else_branch, ..
+            } => {
+                body.last().is_some_and(|last| self.terminates(last))
+                    && else_branch
+                        .as_ref()
+                        .is_some_and(|else_branch| self.terminates(else_branch))
+            }
+            StatementKind::For {
+                condition: None,
+                body,
+                ..
+            } => !breaks(body),
+            StatementKind::Select(cases) => cases.iter().all(|case| {
+                !breaks(&case.body) && case.body.last().is_some_and(|last| self.terminates(last))
+            }),
+            // A case falling through ends as the next one does
+            StatementKind::Switch { cases, .. } => {
+                cases.iter().any(|case| case.expressions.is_none())
+                    && cases.iter().all(|case| {
+                        !breaks(&case.body)
+                            && case.body.last().is_some_and(|last| {
+                                last.kind == StatementKind::Fallthrough || self.terminates(last)
+                            })
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a statement ends its function, for the last statement of a block that's
+    /// part of one that does. A `break` or `continue` only ends the block it's in.
+    fn terminates(&self, statement: &Statement) -> bool {
+        match &statement.kind {
+            StatementKind::Break | StatementKind::Continue => false,
+            _ => self.ends(statement),
+        }
+    }
+
+    /// Whether a call is to the builtin `panic`, rather than a function declared over it
+    fn is_panic(&self, name: &Expression) -> bool {
+        let ExpressionKind::Identifier(identifier) = &name.kind else {
+            return false;
+        };
+        let symbols = &self.info.symbols;
+        identifier == "panic"
+            && self.info.symbol_of(name).is_some_and(|symbol| {
+                symbols.scope(symbols.symbol(symbol).scope).kind == ScopeKind::Universe
+            })
+    }
+}
+
+/// Whether a `break` in the body of a `for` or a `select` or `switch` case breaks out of it,
+/// rather than out of a loop, select or switch inside it
+fn breaks(body: &[Statement]) -> bool {
+    body.iter().any(|statement| match &statement.kind {
+        StatementKind::Break => true,
+        StatementKind::Block(_) | StatementKind::If { .. } => {
+            blocks(statement).into_iter().any(breaks)
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::semantic::checker::check;
+
+    fn unreachable(source: &str) -> Vec<usize> {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        let (info, errors) = check(&file);
+        assert!(errors.is_empty(), "{:?}", errors);
+        Unreachable
+            .check(&file, &info)
+            .iter()
+            .map(|diagnostic| diagnostic.position.line)
+            .collect()
+    }
+
+    #[test]
+    fn report_unreachable_code() {
+        let source = "package main
+
+func f(x int) int {
+	if x > 0 {
+		return 1
+	} else {
+		panic(\"negative\")
+	}
+	x = x + 1
+	return x
+}
+
+func g() {
+	for {
+		for {
+			break
+		}
+	}
+	g()
+}
+
+func h(ch chan int) {
+	for x := range ch {
+		if x > 0 {
+			continue
+			g()
+		}
+	}
+	{
+		return
+	}
+	h(ch)
+}
+
+func k() {
+	defer func() {
+		return
+		k()
+	}()
+}
+";
+        assert_eq!(unreachable(source), vec![9, 19, 26, 32, 38]);
+    }
+
+    #[test]
+    fn reachable_code() {
+        // A loop that can be broken out of finishes, and so does an if without an else or a
+        // call to a function declared as panic
+        let source = "package main
+
+func panic(message string) {}
+
+func f(x int) int {
+	for {
+		if x > 0 {
+			break
+		}
+		x = x + 1
+	}
+	if x > 1 {
+		return x
+	}
+	panic(\"not really\")
+	return 0
+}
+";
+        assert_eq!(unreachable(source), Vec::<usize>::new());
+    }
+}
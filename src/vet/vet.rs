@@ -0,0 +1,188 @@
+use crate::ast::ast::File;
+use crate::primitives::diagnostic::{Diagnostic, Severity};
+use crate::semantic::{checker::check_with_config, config::CheckerConfig, info::TypeInfo};
+use crate::vet::{
+    assign::SelfAssignment, config::VetConfig, printf::Printf, shadow::Shadow,
+    unreachable::Unreachable,
+};
+
+/// A check of code that compiles but is probably wrong, which `gor vet` makes of a file
+/// once it's type-checked. Rules are added to a `Vet` to run them, which may be shared by
+/// the threads packages are vetted on.
+pub trait Rule: Send + Sync {
+    /// The rule's name, like `printf`, which the config turns it on or off by and which is
+    /// the code of the diagnostics it reports
+    fn name(&self) -> &'static str;
+
+    /// What the rule reports, a line for `gor vet`'s usage
+    fn summary(&self) -> &'static str;
+
+    /// Whether the rule runs when the config doesn't say
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+
+    /// What the rule finds in a file, as warnings
+    fn check(&self, file: &File, info: &TypeInfo) -> Vec<Diagnostic>;
+}
+
+/// The rules `gor vet` runs, and which of them the config turns on
+pub struct Vet {
+    rules: Vec<Box<dyn Rule>>,
+    config: VetConfig,
+}
+
+impl Default for Vet {
+    fn default() -> Vet {
+        Vet::new(VetConfig::default())
+    }
+}
+
+impl Vet {
+    /// The rules gor comes with: unreachable code, Printf calls whose format doesn't match
+    /// their arguments, self-assignments, and shadowed variables, which are off by default
+    pub fn new(config: VetConfig) -> Vet {
+        Vet {
+            rules: Vec::new(),
+            config,
+        }
+        .with_rule(Unreachable)
+        .with_rule(Printf)
+        .with_rule(SelfAssignment)
+        .with_rule(Shadow)
+    }
+
+    /// Run another rule after those already added
+    pub fn with_rule(mut self, rule: impl Rule + 'static) -> Vet {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Every rule, whether it's on or off, in the order they run
+    pub fn rules(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules.iter().map(|rule| rule.as_ref())
+    }
+
+    pub fn rule(&self, name: &str) -> Option<&dyn Rule> {
+        self.rules().find(|rule| rule.name() == name)
+    }
+
+    /// Type-check a file, then run each rule that's on over it, returning what they find in
+    /// source order. As with `go vet`, a file with type errors isn't vetted, and its errors
+    /// are returned instead.
+    pub fn check(&self, file: &File) -> Vec<Diagnostic> {
+        let (info, mut diagnostics) = check_with_config(file, &CheckerConfig::default());
+        if !diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            diagnostics = self.check_typed(file, &info);
+        }
+        diagnostics
+            .sort_by_key(|diagnostic| (diagnostic.position.line, diagnostic.position.column_start));
+        diagnostics
+    }
+
+    /// Run each rule that's on over a file that's already been type-checked
+    pub fn check_typed(&self, file: &File, info: &TypeInfo) -> Vec<Diagnostic> {
+        self.rules()
+            .filter(|rule| self.config.is_enabled(*rule))
+            .flat_map(|rule| {
+                rule.check(file, info)
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.with_code(rule.name()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::expression::ExpressionKind;
+    use crate::parser::parser::Parser;
+    use crate::primitives::position::Position;
+    use crate::vet::walk::{Visitor, walk_file};
+
+    fn vet(source: &str, vet: &Vet) -> Vec<String> {
+        let mut parser = Parser::new(source);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        vet.check(&file)
+            .iter()
+            .map(|diagnostic| format!("{} {}", diagnostic.code.unwrap_or(""), diagnostic))
+            .collect()
+    }
+
+    /// Reports every call to a function named `todo`
+    struct Todo;
+
+    impl Rule for Todo {
+        fn name(&self) -> &'static str {
+            "todo"
+        }
+
+        fn summary(&self) -> &'static str {
+            "report calls to todo"
+        }
+
+        fn check(&self, file: &File, _info: &TypeInfo) -> Vec<Diagnostic> {
+            struct Calls(Vec<Position>);
+            impl<'a> Visitor<'a> for Calls {
+                fn expression(&mut self, expression: &'a crate::ast::expression::Expression) {
+                    if let ExpressionKind::FunctionCall { name, .. } = &expression.kind
+                        && name.to_string() == "todo"
+                    {
+                        self.0.push(expression.position_start);
+                    }
+                }
+            }
+            let mut calls = Calls(Vec::new());
+            walk_file(file, &mut calls);
+            calls
+                .0
+                .into_iter()
+                .map(|position| Diagnostic::warning("todo left in".to_string(), position))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn configure_rules() {
+        let source = "package main\n\nfunc todo() {}\n\nfunc main() {\n\tx := 1\n\tx = x\n\tif x > 0 {\n\t\tx := 2\n\t\t_ = x\n\t}\n\ttodo()\n\t_ = x\n\treturn\n\ttodo()\n}\n";
+        assert_eq!(
+            vet(source, &Vet::default()),
+            vec![
+                "assign 7:1: warning: self-assignment of x to x",
+                "unreachable 15:1: warning: unreachable code",
+            ]
+        );
+        let config = VetConfig::default()
+            .with_enabled("shadow")
+            .with_disabled("unreachable");
+        assert_eq!(
+            vet(source, &Vet::new(config).with_rule(Todo)),
+            vec![
+                "assign 7:1: warning: self-assignment of x to x",
+                "shadow 9:2: warning: declaration of x shadows declaration at line 6\n6:1: note: shadowed x declared here",
+                "todo 12:1: warning: todo left in",
+                "todo 15:1: warning: todo left in",
+            ]
+        );
+        let config = VetConfig::default().with_disabled("todo");
+        assert_eq!(vet(source, &Vet::new(config).with_rule(Todo)).len(), 2);
+        assert_eq!(
+            Vet::default().rules().map(Rule::name).collect::<Vec<_>>(),
+            vec!["unreachable", "printf", "assign", "shadow"]
+        );
+    }
+
+    #[test]
+    fn type_errors_stop_vetting() {
+        let source = "package main\n\nfunc main() {\n\tx := y\n\tx = x\n}\n";
+        assert_eq!(
+            vet(source, &Vet::default()),
+            vec!["names 4:6: error: undefined: y"]
+        );
+    }
+}
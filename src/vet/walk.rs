@@ -0,0 +1,214 @@
+//! Walking a file's statements and expressions, for rules that look at each one of a kind
+//! wherever it is
+
+use crate::ast::ast::File;
+use crate::ast::declaration::{Declaration, DeclarationKind};
+use crate::ast::expression::{Expression, ExpressionKind};
+use crate::ast::statement::{Statement, StatementKind};
+
+/// What a rule does with each node as it's walked. Outer nodes are visited before those
+/// inside them, and statements before the expressions in them.
+pub trait Visitor<'a> {
+    fn statement(&mut self, _statement: &'a Statement) {}
+
+    fn expression(&mut self, _expression: &'a Expression) {}
+}
+
+/// Visit every statement and expression of a file's declarations
+pub fn walk_file<'a>(file: &'a File, visitor: &mut impl Visitor<'a>) {
+    for declaration in &file.decls {
+        walk_declaration(declaration, visitor);
+    }
+}
+
+fn walk_declaration<'a>(declaration: &'a Declaration, visitor: &mut impl Visitor<'a>) {
+    match &declaration.kind {
+        DeclarationKind::Function { body, .. } => {
+            for statement in body {
+                walk_statement(statement, visitor);
+            }
+        }
+        DeclarationKind::Variable { specs, .. } | DeclarationKind::Constant { specs, .. } => {
+            for value in specs.iter().flat_map(|spec| &spec.values) {
+                walk_expression(value, visitor);
+            }
+        }
+        DeclarationKind::Type { .. } => {}
+    }
+}
+
+pub fn walk_statement<'a>(statement: &'a Statement, visitor: &mut impl Visitor<'a>) {
+    visitor.statement(statement);
+    match &statement.kind {
+        StatementKind::Expression(expression)
+        | StatementKind::Defer(expression)
+        | StatementKind::Go(expression) => walk_expression(expression, visitor),
+        StatementKind::Declaration(declaration) => walk_declaration(declaration, visitor),
+        StatementKind::Assignment { left, right, .. } => {
+            for expression in left.iter().chain(right) {
+                walk_expression(expression, visitor);
+            }
+        }
+        StatementKind::IncDec { target, .. } => walk_expression(target, visitor),
+        StatementKind::OpAssign { target, value, .. } => {
+            walk_expression(target, visitor);
+            walk_expression(value, visitor);
+        }
+        StatementKind::For {
+            init,
+            condition,
+            post,
+            ..
+        } => {
+            if let Some(init) = init {
+                walk_statement(init, visitor);
+            }
+            if let Some(condition) = condition {
+                walk_expression(condition, visitor);
+            }
+            if let Some(post) = post {
+                walk_statement(post, visitor);
+            }
+        }
+        StatementKind::Range {
+            key,
+            value,
+            expression,
+            ..
+        } => {
+            for variable in [key, value].into_iter().flatten() {
+                walk_expression(variable, visitor);
+            }
+            walk_expression(expression, visitor);
+        }
+        StatementKind::If {
+            init, condition, ..
+        } => {
+            if let Some(init) = init {
+                walk_statement(init, visitor);
+            }
+            walk_expression(condition, visitor);
+        }
+        StatementKind::Return(results) => {
+            for result in results {
+                walk_expression(result, visitor);
+            }
+        }
+        StatementKind::Send { channel, value } => {
+            walk_expression(channel, visitor);
+            walk_expression(value, visitor);
+        }
+        StatementKind::Select(cases) => {
+            for communication in cases.iter().filter_map(|case| case.communication.as_ref()) {
+                walk_statement(communication, visitor);
+            }
+        }
+        StatementKind::Switch { init, tag, cases } => {
+            if let Some(init) = init {
+                walk_statement(init, visitor);
+            }
+            if let Some(tag) = tag {
+                walk_expression(tag, visitor);
+            }
+            for expression in cases
+                .iter()
+                .flat_map(|case| case.expressions.iter().flatten())
+            {
+                walk_expression(expression, visitor);
+            }
+        }
+        StatementKind::Block(_)
+        | StatementKind::Break
+        | StatementKind::Continue
+        | StatementKind::Fallthrough => {}
+    }
+    for block in blocks(statement) {
+        for statement in block {
+            walk_statement(statement, visitor);
+        }
+    }
+}
+
+pub fn walk_expression<'a>(expression: &'a Expression, visitor: &mut impl Visitor<'a>) {
+    visitor.expression(expression);
+    match &expression.kind {
+        ExpressionKind::Binary { left, right, .. } => {
+            walk_expression(left, visitor);
+            walk_expression(right, visitor);
+        }
+        ExpressionKind::FunctionCall {
+            name, arguments, ..
+        } => {
+            walk_expression(name, visitor);
+            for argument in arguments {
+                walk_expression(argument, visitor);
+            }
+        }
+        ExpressionKind::Index { object, index } => {
+            walk_expression(object, visitor);
+            walk_expression(index, visitor);
+        }
+        ExpressionKind::Slice {
+            object,
+            low,
+            high,
+            max,
+        } => {
+            walk_expression(object, visitor);
+            for bound in [low, high, max].into_iter().flatten() {
+                walk_expression(bound, visitor);
+            }
+        }
+        ExpressionKind::FieldAccess { object: inner, .. }
+        | ExpressionKind::Parenthesized(inner)
+        | ExpressionKind::Conversion {
+            expression: inner, ..
+        }
+        | ExpressionKind::Receive(inner)
+        | ExpressionKind::Unary { operand: inner, .. } => walk_expression(inner, visitor),
+        ExpressionKind::CompositeLiteral { elements, .. } => {
+            for element in elements {
+                if let Some(key) = &element.key {
+                    walk_expression(key, visitor);
+                }
+                walk_expression(&element.value, visitor);
+            }
+        }
+        ExpressionKind::FunctionLiteral { body, .. } => {
+            for statement in body {
+                walk_statement(statement, visitor);
+            }
+        }
+        ExpressionKind::Identifier(_)
+        | ExpressionKind::BlankIdentifier
+        | ExpressionKind::IntegerLiteral(_)
+        | ExpressionKind::FloatLiteral(_)
+        | ExpressionKind::RuneLiteral(_)
+        | ExpressionKind::StringLiteral(_)
+        | ExpressionKind::Type(_) => {}
+    }
+}
+
+/// The blocks of statements directly inside a statement, like an `if`'s body and its
+/// `else`, or each case of a `select` or `switch`
+pub fn blocks(statement: &Statement) -> Vec<&[Statement]> {
+    match &statement.kind {
+        StatementKind::For { body, .. }
+        | StatementKind::Range { body, .. }
+        | StatementKind::Block(body) => vec![body],
+        StatementKind::If {
+            body, else_branch, ..
+        } => {
+            let mut blocks = vec![body.as_slice()];
+            if let Some(else_branch) = else_branch {
+                blocks.push(std::slice::from_ref(else_branch.as_ref()));
+            }
+            blocks
+        }
+        StatementKind::Select(cases) => cases.iter().map(|case| case.body.as_slice()).collect(),
+        StatementKind::Switch { cases, .. } => {
+            cases.iter().map(|case| case.body.as_slice()).collect()
+        }
+        _ => Vec::new(),
+    }
+}
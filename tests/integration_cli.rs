@@ -0,0 +1,113 @@
+mod tests {
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use std::process::{Command, Output, Stdio};
+
+    /// Run gor with `arguments`, giving it `input` on standard input
+    fn gor(arguments: &[&str], input: &str) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_gor"))
+            .args(arguments)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    #[test]
+    fn exits_0_when_clean() {
+        let output = gor(&["check", "tests/testfiles/simple.go"], "");
+        assert_eq!(output.status.code(), Some(0));
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn exits_1_for_diagnostics() {
+        let output = gor(
+            &["check", "-"],
+            "package main\n\nfunc main() {\n\tx :=\n}\n",
+        );
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.starts_with("<standard input>:5:0: error:"),
+            "{}",
+            stderr
+        );
+    }
+
+    #[test]
+    fn exits_2_for_usage_errors() {
+        for arguments in [&["frobnicate"][..], &["check", "--frobnicate"], &[]] {
+            let output = gor(arguments, "");
+            assert_eq!(output.status.code(), Some(2), "{:?}", arguments);
+        }
+
+        let output = gor(&["strip", "missing.go"], "");
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "gor strip: missing.go: no such file\nRun 'gor help strip' for usage.\n"
+        );
+    }
+
+    #[test]
+    fn selftest_fmt_fails_when_no_file_is_checked() {
+        let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("selftest_fmt_unchecked");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir(&directory).unwrap();
+
+        let arguments = ["internal", "selftest-fmt", directory.to_str().unwrap()];
+        let output = gor(&arguments, "");
+        assert_eq!(output.status.code(), Some(1));
+
+        // Every file skipped is no better than none at all
+        fs::write(directory.join("broken.go"), "package main\n\nfunc {\n").unwrap();
+        let output = gor(&arguments, "");
+        assert_eq!(output.status.code(), Some(1));
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, "0 passed, 0 failed, 1 skipped\n");
+
+        fs::write(
+            directory.join("main.go"),
+            "package main\n\nfunc main() {}\n",
+        )
+        .unwrap();
+        assert_eq!(gor(&arguments, "").status.code(), Some(0));
+    }
+
+    #[test]
+    fn exits_3_for_internal_errors() {
+        let output = gor(&["internal", "panic"], "");
+        assert_eq!(output.status.code(), Some(3));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.ends_with("gor: internal error\n"), "{}", stderr);
+    }
+
+    #[test]
+    fn stops_quietly_when_stdout_is_closed() {
+        // More output than a pipe holds, so gor is still writing when the reader has gone
+        let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("closed_stdout.go");
+        let literal = "x".repeat(1 << 20);
+        fs::write(&path, format!("package main\n\nvar s = \"{}\"\n", literal)).unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_gor"))
+            .args(["fmt", path.to_str().unwrap()])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert!(output.stderr.is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+mod tests {
+    #[cfg(test)]
+    use gor::format::{formatter::format_source, selftest::selftest_directory};
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_formatted_files_unchanged() {
+        for path in [
+            "tests/testfiles/simple.go",
+            "tests/testfiles/module/cmd/app/main.go",
+            "tests/testfiles/module/shapes/shapes.go",
+        ] {
+            let input = fs::read_to_string(path).unwrap();
+            assert_eq!(format_source(&input).unwrap(), input, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_format_unindented() {
+        let input = fs::read_to_string("tests/testfiles/simple.go").unwrap();
+        let unindented: String = input
+            .lines()
+            .map(|line| line.trim_start().to_string() + "\n")
+            .collect();
+        assert_eq!(format_source(&unindented).unwrap(), input);
+    }
+
+    #[test]
+    fn test_selftest_testfiles() {
+        let results = selftest_directory(Path::new("tests/testfiles"));
+        assert!(!results.is_empty());
+        for (path, result) in results {
+            assert!(
+                result.is_ok(),
+                "{}: {}",
+                path.display(),
+                result.unwrap_err()
+            );
+        }
+    }
+}
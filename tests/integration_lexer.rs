@@ -12,6 +12,10 @@ mod tests {
         let token = lexer.next_token();
         assert_eq!(token.kind, Some(TokenKind::Identifier));
         let token = lexer.next_token();
+        assert_eq!(token.kind, Some(TokenKind::Newline));
+        let token = lexer.next_token();
+        assert_eq!(token.kind, Some(TokenKind::Newline));
+        let token = lexer.next_token();
         assert_eq!(token.kind, Some(TokenKind::Keyword(Keyword::Import)));
     }
 }
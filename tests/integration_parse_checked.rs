@@ -0,0 +1,92 @@
+mod tests {
+    #[cfg(test)]
+    use gor::parse_checked;
+    use std::fs;
+
+    /// Tokens and fragments that are likely to trip up the lexer and parser when combined
+    const FRAGMENTS: &[&str] = &[
+        "package", "main", "import", "\"fmt\"", "func", "(", ")", "{", "}", "[", "]", ",", ";",
+        ".", "+", "-", "*", "/", "//", "/*", "*/", "\"", "'", "`", "\\", ":=", "=", "for", "range",
+        "map", "chan", "<-", "_", "x", "42", "1.5", "'a'", "\n", " ", "\t", "é", "€", "$", "@",
+        "#", "...",
+    ];
+
+    /// Deterministic xorshift so failures are reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    fn assert_no_panic(input: &str) {
+        let (_, diagnostics) = parse_checked(input);
+        assert!(
+            !diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.is_internal()),
+            "parser panicked on {:?}: {:?}",
+            input,
+            diagnostics
+        );
+    }
+
+    #[test]
+    fn test_parse_checked_valid_file() {
+        let input = fs::read_to_string("tests/testfiles/simple.go").unwrap();
+        let (file, diagnostics) = parse_checked(&input);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(file.unwrap().package_name, "main");
+    }
+
+    #[test]
+    fn test_parse_checked_reports_errors() {
+        let (file, diagnostics) = parse_checked("package main\n\nfunc main() {\n\tf(\n}\n");
+        assert!(file.is_some());
+        assert!(!diagnostics.is_empty());
+
+        let (file, diagnostics) = parse_checked("package main\n\"unterminated");
+        assert!(file.is_none());
+        assert!(!diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_parse_checked_deep_nesting() {
+        for (open, close) in [("(", ")"), ("[]", ""), ("for {", "}"), ("if x {", "}")] {
+            let input = format!(
+                "package main\nfunc main() {{\n{}x{}\n}}\n",
+                open.repeat(10_000),
+                close.repeat(10_000)
+            );
+            let (_, diagnostics) = parse_checked(&input);
+            assert!(
+                diagnostics
+                    .iter()
+                    .any(|diagnostic| diagnostic.message == "Nesting is too deep")
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_checked_random_input() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        for _ in 0..2000 {
+            let length = rng.next() % 40;
+            let mut input = String::new();
+            if rng.next().is_multiple_of(2) {
+                input.push_str("package main\n");
+            }
+            for _ in 0..length {
+                input.push_str(FRAGMENTS[rng.next() as usize % FRAGMENTS.len()]);
+                if rng.next().is_multiple_of(3) {
+                    input.push(' ');
+                }
+            }
+            assert_no_panic(&input);
+        }
+    }
+}
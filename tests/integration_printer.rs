@@ -0,0 +1,39 @@
+mod tests {
+    #[cfg(test)]
+    use gor::parser::parser::Parser;
+    use std::fs;
+
+    fn print(input: &str) -> String {
+        let mut parser = Parser::new(input);
+        let file = parser.parse().unwrap();
+        assert!(parser.errors.is_empty(), "{}", parser.dump_errors());
+        file.to_source()
+    }
+
+    #[test]
+    fn test_simple_round_trip() {
+        let input = fs::read_to_string("tests/testfiles/simple.go").unwrap();
+        let printed = print(&input);
+        assert_eq!(printed, input.trim_end().to_string() + "\n");
+        assert_eq!(print(&printed), printed);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_precedence() {
+        let input = "package main\n\nfunc main() {\n\tf((a + b) * c, a - (b - c), a + b * c)\n}\n";
+        let printed = print(input);
+        assert_eq!(printed, input);
+        assert_eq!(print(&printed), printed);
+    }
+
+    #[test]
+    fn test_round_trip_grouped_imports() {
+        let input = "package main\nimport (\n\"fmt\"\nstr \"strings\"\n)\nfunc main() {}";
+        let printed = print(input);
+        assert_eq!(
+            printed,
+            "package main\n\nimport (\n\t\"fmt\"\n\tstr \"strings\"\n)\n\nfunc main() {\n}\n"
+        );
+        assert_eq!(print(&printed), printed);
+    }
+}
@@ -0,0 +1,276 @@
+mod tests {
+    #[cfg(test)]
+    use gor::ast::{
+        expression::{Expression, ExpressionKind, SelectionKind},
+        type_expression::{ChannelDirection, TypeExpression, TypeExpressionKind},
+    };
+    use gor::lexer::token_type::{Operator, TokenKind};
+    use gor::parser::parser::Parser;
+    use gor::primitives::position::Position;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+
+    const OPERATORS: &[Operator] = &[
+        Operator::Plus,
+        Operator::Minus,
+        Operator::Star,
+        Operator::Slash,
+        Operator::Percent,
+        Operator::Ampersand,
+        Operator::Pipe,
+        Operator::Caret,
+        Operator::LessLess,
+        Operator::GreaterGreater,
+        Operator::AmpersandCaret,
+        Operator::AndAnd,
+        Operator::PipePipe,
+        Operator::EqualEqual,
+        Operator::BangEqual,
+        Operator::Less,
+        Operator::LessEqual,
+        Operator::Greater,
+        Operator::GreaterEqual,
+    ];
+
+    fn position() -> Position {
+        Position::new(1, 0, 0)
+    }
+
+    fn name() -> impl Strategy<Value = String> {
+        "[a-zA-Z][a-zA-Z0-9_]{0,6}".prop_filter("keywords aren't identifiers", |name| {
+            TokenKind::from_text(name) == Some(TokenKind::Identifier)
+        })
+    }
+
+    fn type_name() -> impl Strategy<Value = TypeExpression> {
+        prop_oneof![
+            name().prop_map(|name| TypeExpression::new_name(name, position())),
+            (name(), name()).prop_map(|(package, name)| TypeExpression::new(
+                TypeExpressionKind::Qualified { package, name },
+                position(),
+                position(),
+            )),
+        ]
+    }
+
+    fn type_expression(
+        expression: BoxedStrategy<Expression>,
+    ) -> impl Strategy<Value = TypeExpression> {
+        type_name().prop_recursive(4, 16, 2, move |element| {
+            let composite = |kind| TypeExpression::new(kind, position(), position());
+            prop_oneof![
+                element
+                    .clone()
+                    .prop_map(
+                        move |element| composite(TypeExpressionKind::Pointer(Box::new(element)))
+                    ),
+                element
+                    .clone()
+                    .prop_map(move |element| composite(TypeExpressionKind::Slice(Box::new(
+                        element
+                    )))),
+                (expression.clone(), element.clone()).prop_map(move |(length, element)| {
+                    composite(TypeExpressionKind::Array {
+                        length: Box::new(length),
+                        element: Box::new(element),
+                    })
+                }),
+                (element.clone(), element.clone()).prop_map(move |(key, value)| {
+                    composite(TypeExpressionKind::Map {
+                        key: Box::new(key),
+                        value: Box::new(value),
+                    })
+                }),
+                (
+                    select(vec![
+                        ChannelDirection::Both,
+                        ChannelDirection::Send,
+                        ChannelDirection::Receive
+                    ]),
+                    element
+                )
+                    .prop_map(move |(direction, element)| {
+                        composite(TypeExpressionKind::Channel {
+                            direction,
+                            element: Box::new(element),
+                        })
+                    }),
+            ]
+        })
+    }
+
+    /// Conversions are only parsed for type literals that start with `[`, `map` or `chan`
+    fn is_conversion_target(target: &TypeExpression) -> bool {
+        match &target.kind {
+            TypeExpressionKind::Slice(_)
+            | TypeExpressionKind::Array { .. }
+            | TypeExpressionKind::Map { .. } => true,
+            TypeExpressionKind::Channel { direction, .. } => {
+                *direction != ChannelDirection::Receive
+            }
+            _ => false,
+        }
+    }
+
+    fn operand() -> impl Strategy<Value = Expression> {
+        prop_oneof![
+            name().prop_map(|name| Expression::new_identifier(name, position())),
+            "[0-9]{1,8}".prop_map(|value| Expression::new_integer_literal(value, position())),
+            "[0-9]{1,4}\\.[0-9]{1,4}"
+                .prop_map(|value| Expression::new_float_literal(value, position())),
+            // Any printable character but the quote and backslash, or an escape sequence
+            r"'([^'\\\p{C}]|\\[abfnrtv\\']|\\x[0-9a-f]{2}|\\u[0-9a-f]{4})'"
+                .prop_map(|value| Expression::new_rune_literal(value, position())),
+            r#""([^"\\\p{C}]|\\[abfnrtv\\"]|\\x[0-9a-f]{2}|\\u[0-9a-f]{4}){0,8}""#
+                .prop_map(|value| Expression::new_string_literal(value, position())),
+        ]
+    }
+
+    fn expression() -> impl Strategy<Value = Expression> {
+        operand().prop_recursive(6, 64, 4, |inner| {
+            prop_oneof![
+                (inner.clone(), select(OPERATORS), inner.clone()).prop_map(
+                    |(left, operator, right)| {
+                        Expression::new_binary(left, operator, right, position(), position())
+                    }
+                ),
+                (
+                    inner.clone(),
+                    prop::collection::vec(inner.clone(), 0..4),
+                    any::<bool>()
+                )
+                    .prop_map(|(name, arguments, spread)| {
+                        // `...` needs an argument to follow
+                        let spread = spread && !arguments.is_empty();
+                        Expression::new_function_call(
+                            name,
+                            arguments,
+                            spread,
+                            position(),
+                            position(),
+                        )
+                    }),
+                (inner.clone(), name()).prop_map(|(object, field)| {
+                    Expression::new_field_access(object, field, position(), position())
+                }),
+                (inner.clone(), inner.clone()).prop_map(|(object, index)| {
+                    Expression::new_index(object, index, position(), position())
+                }),
+                (
+                    inner.clone(),
+                    prop::option::of(inner.clone()),
+                    prop::option::of(inner.clone()),
+                    prop::option::of(inner.clone())
+                )
+                    .prop_map(|(object, low, high, max)| {
+                        // The 3-index form needs its high bound
+                        let max = max.filter(|_| high.is_some());
+                        Expression::new_slice(object, low, high, max, position(), position())
+                    }),
+                inner
+                    .clone()
+                    .prop_map(|inner| Expression::new_parenthesized(inner, position(), position())),
+                (
+                    type_expression(inner.clone().boxed())
+                        .prop_filter("not a conversion target", is_conversion_target),
+                    inner
+                )
+                    .prop_map(|(target, expression)| {
+                        Expression::new_conversion(target, expression, position(), position())
+                    }),
+            ]
+        })
+    }
+
+    /// Forget positions, and the parentheses the printer adds, so trees compare by structure
+    fn normalize(expression: &Expression) -> Expression {
+        let kind = match &expression.kind {
+            ExpressionKind::Parenthesized(inner) => return normalize(inner),
+            ExpressionKind::Binary {
+                left,
+                operator,
+                right,
+            } => ExpressionKind::Binary {
+                left: Box::new(normalize(left)),
+                operator: *operator,
+                right: Box::new(normalize(right)),
+            },
+            ExpressionKind::FunctionCall {
+                name,
+                arguments,
+                spread,
+            } => ExpressionKind::FunctionCall {
+                name: Box::new(normalize(name)),
+                arguments: arguments.iter().map(normalize).collect(),
+                spread: *spread,
+            },
+            ExpressionKind::FieldAccess { object, field, .. } => ExpressionKind::FieldAccess {
+                object: Box::new(normalize(object)),
+                field: field.clone(),
+                selection: SelectionKind::Unresolved,
+            },
+            ExpressionKind::Index { object, index } => ExpressionKind::Index {
+                object: Box::new(normalize(object)),
+                index: Box::new(normalize(index)),
+            },
+            ExpressionKind::Slice {
+                object,
+                low,
+                high,
+                max,
+            } => {
+                let bound = |bound: &Option<Box<Expression>>| {
+                    bound.as_ref().map(|bound| Box::new(normalize(bound)))
+                };
+                ExpressionKind::Slice {
+                    object: Box::new(normalize(object)),
+                    low: bound(low),
+                    high: bound(high),
+                    max: bound(max),
+                }
+            }
+            ExpressionKind::Conversion { target, expression } => ExpressionKind::Conversion {
+                target: normalize_type(target),
+                expression: Box::new(normalize(expression)),
+            },
+            kind => kind.clone(),
+        };
+        Expression::new(kind, position(), position())
+    }
+
+    fn normalize_type(target: &TypeExpression) -> TypeExpression {
+        let normalize_element = |element: &TypeExpression| Box::new(normalize_type(element));
+        let kind = match &target.kind {
+            TypeExpressionKind::Pointer(element) => {
+                TypeExpressionKind::Pointer(normalize_element(element))
+            }
+            TypeExpressionKind::Slice(element) => {
+                TypeExpressionKind::Slice(normalize_element(element))
+            }
+            TypeExpressionKind::Array { length, element } => TypeExpressionKind::Array {
+                length: Box::new(normalize(length)),
+                element: normalize_element(element),
+            },
+            TypeExpressionKind::Map { key, value } => TypeExpressionKind::Map {
+                key: normalize_element(key),
+                value: normalize_element(value),
+            },
+            TypeExpressionKind::Channel { direction, element } => TypeExpressionKind::Channel {
+                direction: *direction,
+                element: normalize_element(element),
+            },
+            kind => kind.clone(),
+        };
+        TypeExpression::new(kind, position(), position())
+    }
+
+    proptest! {
+        #[test]
+        fn test_print_parse_round_trip(expression in expression()) {
+            let printed = expression.to_source();
+            let parsed = Parser::parse_expression_str(&printed)
+                .unwrap_or_else(|errors| panic!("{:?} failed to parse: {:?}", printed, errors));
+            prop_assert_eq!(normalize(&parsed), normalize(&expression), "{}", printed);
+        }
+    }
+}
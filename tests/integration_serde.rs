@@ -0,0 +1,19 @@
+#[cfg(feature = "serde")]
+mod tests {
+    use gor::ast::ast::File;
+    use gor::parser::parser::Parser;
+    use std::fs;
+
+    #[test]
+    fn test_simple_json_round_trip() {
+        let input = fs::read_to_string("tests/testfiles/simple.go").unwrap();
+        let mut parser = Parser::new(&input);
+        let file = parser.parse().unwrap();
+
+        let json = serde_json::to_string(&file).unwrap();
+        assert!(json.contains("\"package_name\":\"main\""));
+
+        let deserialized: File = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, file);
+    }
+}
@@ -0,0 +1,7 @@
+package main
+
+import "example.com/app/textutil"
+
+func greeting(name string) string {
+	return text.Title("hello, " + name)
+}
@@ -0,0 +1,18 @@
+package main
+
+import (
+	"fmt"
+
+	"example.com/app/shapes"
+	t "example.com/app/textutil"
+)
+
+func main() {
+	r := shapes.New(2, 3)
+	fmt.Println(r.Area(), shapes.Count, greeting("gor"))
+	var s shapes.Shape = &shapes.Square{Side: 2}
+	fmt.Println(s.Area(), t.Upper("done"))
+	fmt.Printf("%T %v\n", r, r)
+	shapes := []int{1, 2}
+	fmt.Println(len(shapes))
+}
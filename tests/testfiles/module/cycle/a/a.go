@@ -0,0 +1,5 @@
+package a
+
+import "example.com/app/cycle/b"
+
+var A = b.B
@@ -0,0 +1,5 @@
+package b
+
+import "example.com/app/cycle/a"
+
+var B = a.A
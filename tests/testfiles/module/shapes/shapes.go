@@ -0,0 +1,21 @@
+package shapes
+
+// Count is how many shapes New has made
+var Count = 0
+
+type Shape interface {
+	Area() int
+}
+
+type Rect struct {
+	W, H int
+}
+
+func New(w, h int) Rect {
+	Count = Count + 1
+	return Rect{W: w, H: h}
+}
+
+func (r Rect) Area() int {
+	return area(r.W, r.H)
+}
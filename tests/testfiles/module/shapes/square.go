@@ -0,0 +1,14 @@
+package shapes
+
+type Square struct {
+	Side int
+}
+
+func (s *Square) Area() int {
+	area := s.Side * s.Side
+	return area
+}
+
+func area(w, h int) int {
+	return w * h
+}
@@ -0,0 +1,11 @@
+package text_test
+
+import (
+	"fmt"
+
+	"example.com/app/textutil"
+)
+
+func ExampleTitle() {
+	fmt.Println(text.Title("go"))
+}
@@ -0,0 +1,6 @@
+//go:build ignore
+
+// Generates nothing, but a file like this is run with go run and never built with the package
+package main
+
+func main() {}
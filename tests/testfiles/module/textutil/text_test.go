@@ -0,0 +1,9 @@
+package text
+
+import "testing"
+
+func TestTitle(t *testing.T) {
+	if Title("go") != "Go" {
+		t.Fail()
+	}
+}